@@ -1,5 +1,28 @@
+use std::collections::HashMap;
 use std::env;
-use dotenvy::dotenv;
+use innosystem_common::config::load_env_file;
+use innosystem_common::database::{PoolConfig, TenancyConfig};
+use innosystem_common::queue::LeaderElectionConfig;
+use innosystem_common::repositories::RepositoryMetricsConfig;
+use innosystem_common::schema_drift::SchemaCheckMode;
+
+use crate::services::autoscaling::AutoscalingConfig;
+use crate::services::backpressure::BackpressureConfig;
+use crate::services::billing::{ReservationExpiryConfig, WalletApprovalConfig};
+use crate::services::billing_export::BillingExportConfig;
+use crate::services::cache::ResponseCacheConfig;
+use crate::services::capacity_planning::CapacityPlanningConfig;
+use crate::services::feature_flags::FeatureFlagConfig;
+use crate::services::job_events::JobEventConfig;
+use crate::services::partitions::PartitionConfig;
+use crate::services::provider_health::ProviderHealthConfig;
+use crate::services::queue_stats::QueueWaitConfig;
+use crate::services::request_signing::RequestSigningConfig;
+use crate::services::runner_health::RunnerHealthConfig;
+use crate::services::sandbox::SandboxConfig;
+use crate::services::security_events::SecurityEventConfig;
+use crate::services::spending_anomaly::SpendingAnomalyConfig;
+use crate::services::webhook::NotificationConfig;
 
 /// API configuration loaded from environment variables
 #[derive(Debug, Clone)]
@@ -9,21 +32,71 @@ pub struct AppConfig {
     pub environment: String,
     /// Application port
     pub port: Option<u16>,
+    /// Seconds in-flight requests may take to finish after SIGTERM or SIGINT before the server stops anyway
+    pub shutdown_drain_secs: u64,
     /// Database URL
     #[allow(dead_code)]
     pub database_url: Option<String>,
     /// Redis URL
     #[allow(dead_code)]
     pub redis_url: Option<String>,
+    /// Size of the database connection pool and how long requests wait for a connection (`DATABASE_POOL_*` variables)
+    pub database_pool: PoolConfig,
     /// Admin API key for authentication
     pub admin_api_key: String,
+    /// Keys of individually identified admins, mapped to the admin's ID (`ADMIN_API_KEYS` as `id=key` pairs)
+    pub admin_api_keys: HashMap<String, String>,
+    /// Manual wallet adjustments needing a second admin's approval (`WALLET_APPROVAL_*` variables)
+    pub wallet_approval: WalletApprovalConfig,
+    /// Autoscaling advice settings (`AUTOSCALING_*` variables)
+    pub autoscaling: AutoscalingConfig,
+    /// Runner health scoring and alerting settings (`RUNNER_HEALTH_*` variables)
+    pub runner_health: RunnerHealthConfig,
+    /// Expiry of reservations held by jobs that are never processed (`RESERVATION_*` variables)
+    pub reservation_expiry: ReservationExpiryConfig,
+    /// Caching of catalog responses in Redis (`RESPONSE_CACHE_*` variables)
+    pub response_cache: ResponseCacheConfig,
+    /// Queue wait time reporting and the Low priority starvation bound (`QUEUE_WAIT_*` variables)
+    pub queue_wait: QueueWaitConfig,
+    /// Queue depth limits and what happens to jobs submitted beyond them (`BACKPRESSURE_*` variables)
+    pub backpressure: BackpressureConfig,
+    /// Repository call metrics and the slow query log threshold (`REPOSITORY_*` variables)
+    pub repository_metrics: RepositoryMetricsConfig,
+    /// Optional schema-per-reseller storage of customer data (`TENANCY_*` variables)
+    pub tenancy: TenancyConfig,
+    /// Which replica runs each periodic background task (`CLUSTER_*` variables)
+    pub cluster: LeaderElectionConfig,
+    /// Caching of the runtime feature flags (`FEATURE_FLAG_*` variables)
+    pub feature_flags: FeatureFlagConfig,
+    /// Detection of spend spikes and failure rate jumps per customer (`SPENDING_ANOMALY_*` variables)
+    pub spending_anomaly: SpendingAnomalyConfig,
+    /// Monthly partitions of jobs and wallet transactions and their retention (`PARTITION_*` variables)
+    pub partitions: PartitionConfig,
+    /// Signed reseller requests and their replay window (`REQUEST_SIGNING_*` variables)
+    pub request_signing: RequestSigningConfig,
+    /// Health checks of the external providers job types depend on (`PROVIDER_HEALTH_*` variables)
+    pub provider_health: ProviderHealthConfig,
+    /// Warm-up of runner pools ahead of declared load windows (`CAPACITY_PLANNING_*` variables)
+    pub capacity_planning: CapacityPlanningConfig,
+    /// Lockouts and alerts on failed authentications (`SECURITY_*` variables)
+    pub security_events: SecurityEventConfig,
+    /// Export of finalized job charges to an external billing system (`BILLING_EXPORT_*` variables)
+    pub billing_export: BillingExportConfig,
+    /// Notification emails and digests (`NOTIFICATION_*` variables)
+    pub notifications: NotificationConfig,
+    /// Notification of jobs starting and finishing (`JOB_EVENTS_*` variables)
+    pub job_events: JobEventConfig,
+    /// Sandbox tenants of resellers and their lifetime (`SANDBOX_*` variables)
+    pub sandboxes: SandboxConfig,
+    /// What to do when the database schema drifted from the Diesel schema on startup (`SCHEMA_DRIFT_CHECK` variable)
+    pub schema_check: SchemaCheckMode,
 }
 
 impl AppConfig {
     /// Load configuration from environment variables
     pub fn load() -> anyhow::Result<Self> {
-        // Load .env file if present
-        let _ = dotenv();
+        // Load the settings file if present
+        load_env_file();
         
         // Read configuration from environment variables
         let environment = env::var("ENVIRONMENT")
@@ -34,6 +107,12 @@ impl AppConfig {
             .ok()
             .and_then(|p| p.parse::<u16>().ok());
             
+        // Long-lived requests, e.g. event streams, are cut off after the drain period
+        let shutdown_drain_secs = env::var("SHUTDOWN_DRAIN_SECS")
+            .ok()
+            .and_then(|secs| secs.parse::<u64>().ok())
+            .unwrap_or(30);
+        
         let database_url = env::var("DATABASE_URL").ok();
         let redis_url = env::var("REDIS_URL").ok();
         
@@ -47,12 +126,57 @@ impl AppConfig {
                 }
             });
         
+        // Named admin keys identify who took an action, e.g. who approved an adjustment
+        let admin_api_keys = env::var("ADMIN_API_KEYS")
+            .map(|value| {
+                value.split(',')
+                    .filter_map(|pair| pair.split_once('='))
+                    .map(|(id, key)| (key.trim().to_string(), id.trim().to_string()))
+                    .filter(|(key, id)| !key.is_empty() && !id.is_empty())
+                    .collect()
+            })
+            .unwrap_or_default();
+        
         Ok(Self {
             environment,
             port,
+            shutdown_drain_secs,
             database_url,
             redis_url,
+            database_pool: PoolConfig::from_env(),
             admin_api_key,
+            admin_api_keys,
+            wallet_approval: WalletApprovalConfig::from_env(),
+            autoscaling: AutoscalingConfig::from_env(),
+            runner_health: RunnerHealthConfig::from_env(),
+            reservation_expiry: ReservationExpiryConfig::from_env(),
+            response_cache: ResponseCacheConfig::from_env(),
+            queue_wait: QueueWaitConfig::from_env(),
+            backpressure: BackpressureConfig::from_env(),
+            repository_metrics: RepositoryMetricsConfig::from_env(),
+            tenancy: TenancyConfig::from_env(),
+            cluster: LeaderElectionConfig::from_env(),
+            feature_flags: FeatureFlagConfig::from_env(),
+            spending_anomaly: SpendingAnomalyConfig::from_env(),
+            partitions: PartitionConfig::from_env(),
+            request_signing: RequestSigningConfig::from_env(),
+            provider_health: ProviderHealthConfig::from_env(),
+            capacity_planning: CapacityPlanningConfig::from_env(),
+            security_events: SecurityEventConfig::from_env(),
+            billing_export: BillingExportConfig::from_env(),
+            notifications: NotificationConfig::from_env(),
+            job_events: JobEventConfig::from_env(),
+            sandboxes: SandboxConfig::from_env(),
+            schema_check: SchemaCheckMode::from_env(),
         })
     }
+    
+    /// ID of the admin an API key belongs to: the shared admin key is the admin `admin`,
+    /// keys listed in `ADMIN_API_KEYS` identify their own admin
+    pub fn admin_id(&self, api_key: &str) -> Option<String> {
+        if api_key == self.admin_api_key {
+            return Some("admin".to_string());
+        }
+        self.admin_api_keys.get(api_key).cloned()
+    }
 }
@@ -0,0 +1,24 @@
+use axum::{extract::State, http::header, response::IntoResponse, Json};
+use serde_json::Value;
+
+use crate::services::alerting;
+use crate::state::AppState;
+
+/// Get Prometheus alerting rules derived from the SLA, runner health and queue wait configuration
+/// Access: Admin
+pub async fn get_prometheus_rules(
+    State(state): State<AppState>,
+) -> impl IntoResponse {
+    (
+        [(header::CONTENT_TYPE, "application/yaml")],
+        alerting::render_prometheus_rules(&state.config.autoscaling, &state.config.runner_health, &state.config.queue_wait),
+    )
+}
+
+/// Get a Grafana dashboard plotting the alerted metrics against the configured thresholds
+/// Access: Admin
+pub async fn get_grafana_dashboard(
+    State(state): State<AppState>,
+) -> Json<Value> {
+    Json(alerting::render_grafana_dashboard(&state.config.autoscaling, &state.config.runner_health, &state.config.queue_wait))
+}
@@ -0,0 +1,37 @@
+use axum::{extract::State, http::{header, StatusCode}, response::IntoResponse, Json};
+use tracing::error;
+
+use crate::services::autoscaling::AutoscalingAdvice;
+use crate::services::AutoscalingService;
+use crate::state::AppState;
+
+/// Get the advised runner count, derived from queue depth, processing times and the SLA target
+/// Access: Admin
+pub async fn get_autoscaling_advice(
+    State(state): State<AppState>,
+) -> Result<Json<AutoscalingAdvice>, StatusCode> {
+    let advice = state.autoscaling_service.advise().await
+        .map_err(|e| {
+            error!("Failed to compute autoscaling advice: {}", e);
+            StatusCode::INTERNAL_SERVER_ERROR
+        })?;
+
+    Ok(Json(advice))
+}
+
+/// Get the autoscaling signals as Prometheus metrics, for HPA/Nomad metric adapters
+/// Access: Admin
+pub async fn get_autoscaling_metrics(
+    State(state): State<AppState>,
+) -> Result<impl IntoResponse, StatusCode> {
+    let advice = state.autoscaling_service.advise().await
+        .map_err(|e| {
+            error!("Failed to compute autoscaling advice: {}", e);
+            StatusCode::INTERNAL_SERVER_ERROR
+        })?;
+
+    Ok((
+        [(header::CONTENT_TYPE, "text/plain; version=0.0.4")],
+        AutoscalingService::render_metrics(&advice),
+    ))
+}
@@ -0,0 +1,172 @@
+use axum::{extract::{Path, Query, State, Extension}, http::StatusCode, Json};
+use chrono::{DateTime, Duration, Utc};
+use serde::{Deserialize, Serialize};
+use serde_json::{json, Value};
+use uuid::Uuid;
+use tracing::{error, info};
+
+use innosystem_common::database::with_reseller;
+use innosystem_common::models::audit::NewAuditEntry;
+use innosystem_common::models::billing_export::{BillingExport, BillingReconciliation};
+use crate::middleware::auth::AdminUser;
+use crate::state::AppState;
+
+/// Days reconciled unless a start is given
+const DEFAULT_RECONCILIATION_DAYS: i64 = 30;
+/// Unexported records listed unless a limit is given
+const DEFAULT_LIMIT: i64 = 100;
+/// Most unexported records listed
+const MAX_LIMIT: i64 = 1000;
+
+/// Query parameters of the billing export reconciliation
+#[derive(Debug, Deserialize)]
+pub struct ReconciliationQuery {
+    /// Start of the period jobs completed in (defaults to 30 days before its end)
+    pub from: Option<DateTime<Utc>>,
+    /// Exclusive end of the period (defaults to now)
+    pub to: Option<DateTime<Utc>>,
+    /// Most unexported records listed (defaults to 100, at most 1000)
+    pub limit: Option<i64>,
+}
+
+/// Response data for the billing export reconciliation
+#[derive(Debug, Serialize)]
+pub struct ReconciliationResponse {
+    pub from: DateTime<Utc>,
+    pub to: DateTime<Utc>,
+    #[serde(flatten)]
+    pub reconciliation: BillingReconciliation,
+}
+
+/// Response data for a billing export
+#[derive(Debug, Serialize)]
+pub struct BillingExportResponse {
+    pub id: Uuid,
+    pub job_id: Uuid,
+    pub customer_id: Uuid,
+    pub amount_cents: i32,
+    pub cost_breakdown: Value,
+    pub idempotency_key: String,
+    pub status: String,
+    pub attempts: i32,
+    pub status_code: Option<i32>,
+    pub last_error: Option<String>,
+    pub next_attempt_at: Option<DateTime<Utc>>,
+    pub completed_at: DateTime<Utc>,
+    pub exported_at: Option<DateTime<Utc>>,
+}
+
+impl From<BillingExport> for BillingExportResponse {
+    fn from(export: BillingExport) -> Self {
+        Self {
+            id: export.id,
+            job_id: export.job_id,
+            customer_id: export.customer_id,
+            amount_cents: export.amount_cents,
+            cost_breakdown: export.cost_breakdown,
+            idempotency_key: export.idempotency_key,
+            status: export.status,
+            attempts: export.attempts,
+            status_code: export.status_code,
+            last_error: export.last_error,
+            next_attempt_at: export.next_attempt_at,
+            completed_at: export.completed_at,
+            exported_at: export.exported_at,
+        }
+    }
+}
+
+/// Compare the charges of the jobs completed in a period to what was exported of them,
+/// listing the charges the billing system has not accepted yet, oldest first
+///
+/// Charges are reconciled in the shared schema and in every reseller schema.
+/// Access: Admin
+pub async fn get_reconciliation(
+    State(state): State<AppState>,
+    Extension(admin): Extension<AdminUser>,
+    Query(query): Query<ReconciliationQuery>,
+) -> Result<Json<ReconciliationResponse>, StatusCode> {
+    let to = query.to.unwrap_or_else(Utc::now);
+    let from = query.from.unwrap_or(to - Duration::days(DEFAULT_RECONCILIATION_DAYS));
+    if from >= to {
+        error!("Billing export reconciliation period must start before it ends");
+        return Err(StatusCode::BAD_REQUEST);
+    }
+    let limit = query.limit.unwrap_or(DEFAULT_LIMIT).clamp(1, MAX_LIMIT);
+    let internal_error = |e: anyhow::Error| {
+        error!("Failed to reconcile billing exports: {}", e);
+        StatusCode::INTERNAL_SERVER_ERROR
+    };
+
+    let resolver = state.schema_resolver.clone();
+    let scopes = tokio::task::spawn_blocking(move || resolver.scopes()).await
+        .map_err(|e| internal_error(e.into()))?
+        .map_err(|e| internal_error(e.into()))?;
+    let mut reconciliation = BillingReconciliation::default();
+    for scope in scopes {
+        let scoped = with_reseller(scope, state.billing_export_repo.reconcile(from, to, limit)).await
+            .map_err(internal_error)?;
+        reconciliation.merge(scoped, limit as usize);
+    }
+
+    info!(
+        "Admin {} reconciled billing exports: {} of {} finalized charges not exported",
+        admin.id, reconciliation.pending + reconciliation.failed + reconciliation.uncaptured, reconciliation.finalized_jobs
+    );
+
+    Ok(Json(ReconciliationResponse {
+        from,
+        to,
+        reconciliation,
+    }))
+}
+
+/// Attempt a billing export again right away, including one whose attempts are exhausted
+///
+/// The export keeps its idempotency key, so the billing system drops the record if an
+/// earlier attempt already reached it. Exported ones are returned as they are.
+/// Access: Admin
+pub async fn retry_export(
+    State(state): State<AppState>,
+    Extension(admin): Extension<AdminUser>,
+    Path(id): Path<Uuid>,
+) -> Result<Json<BillingExportResponse>, StatusCode> {
+    let resolver = state.schema_resolver.clone();
+    let scopes = tokio::task::spawn_blocking(move || resolver.scopes()).await
+        .map_err(|e| {
+            error!("Failed to resolve schemas: {}", e);
+            StatusCode::INTERNAL_SERVER_ERROR
+        })?
+        .map_err(|e| {
+            error!("Failed to resolve schemas: {}", e);
+            StatusCode::INTERNAL_SERVER_ERROR
+        })?;
+
+    for scope in scopes {
+        let Ok(export) = with_reseller(scope, state.billing_export_repo.find_by_id(id)).await else {
+            continue;
+        };
+        let previous_status = export.status.clone();
+        let export = with_reseller(scope, state.billing_export_service.retry(&export)).await
+            .map_err(|e| {
+                error!("Failed to retry billing export {}: {}", id, e);
+                StatusCode::SERVICE_UNAVAILABLE
+            })?;
+
+        let entry = NewAuditEntry::new(admin.id.clone(), "billing_export.retried", "billing_export", export.id, json!({
+            "job_id": export.job_id,
+            "previous_status": previous_status,
+            "status": export.status,
+            "attempts": export.attempts,
+        }));
+        if let Err(e) = state.audit_log_repo.record(entry).await {
+            error!("Failed to record the retry of billing export {} in the audit log: {}", export.id, e);
+        }
+
+        info!("Admin {} retried billing export {}: {}", admin.id, export.id, export.status);
+        return Ok(Json(export.into()));
+    }
+
+    error!("Billing export not found with ID: {}", id);
+    Err(StatusCode::NOT_FOUND)
+}
@@ -0,0 +1,252 @@
+use axum::{
+    extract::{Path, State, Extension},
+    http::{header, StatusCode},
+    response::IntoResponse,
+    Json,
+};
+use serde::{Deserialize, Serialize};
+use serde_json::json;
+use uuid::Uuid;
+use tracing::{error, info};
+use chrono::{DateTime, Utc};
+
+use innosystem_common::models::audit::NewAuditEntry;
+use innosystem_common::models::billing_period::{ledger_csv, ledger_sha256, month_bounds, BillingPeriod, Invoice, LedgerError};
+use innosystem_common::models::job_cost::CostBreakdown;
+use crate::middleware::auth::{AdminUser, CustomerUser};
+use crate::request::StrictJson;
+use crate::state::AppState;
+
+/// Request data for closing a billing month
+#[derive(Debug, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct CloseBillingPeriodRequest {
+    pub year: i32,
+    /// Month of the year, 1 to 12
+    pub month: u32,
+}
+
+/// Response data for a closed billing period
+#[derive(Debug, Serialize)]
+pub struct BillingPeriodResponse {
+    pub id: Uuid,
+    pub period_start: DateTime<Utc>,
+    /// Exclusive end of the period
+    pub period_end: DateTime<Utc>,
+    pub closed_by: String,
+    pub transaction_count: i32,
+    pub credits_cents: i64,
+    pub debits_cents: i64,
+    /// SHA-256 of the ledger export, taken when the period was closed
+    pub ledger_sha256: String,
+    pub closed_at: Option<DateTime<Utc>>,
+}
+
+impl From<BillingPeriod> for BillingPeriodResponse {
+    fn from(period: BillingPeriod) -> Self {
+        Self {
+            id: period.id,
+            period_start: period.period_start,
+            period_end: period.period_end,
+            closed_by: period.closed_by,
+            transaction_count: period.transaction_count,
+            credits_cents: period.credits_cents,
+            debits_cents: period.debits_cents,
+            ledger_sha256: period.ledger_sha256,
+            closed_at: period.closed_at,
+        }
+    }
+}
+
+/// Response data for a final invoice
+#[derive(Debug, Serialize)]
+pub struct InvoiceResponse {
+    pub id: Uuid,
+    pub billing_period_id: Uuid,
+    pub customer_id: Uuid,
+    pub wallet_id: Uuid,
+    /// Bounds of the invoice: the month in the customer's time zone, as UTC instants
+    pub period_start: DateTime<Utc>,
+    pub period_end: DateTime<Utc>,
+    pub timezone: String,
+    pub opening_balance_cents: i64,
+    pub credits_cents: i64,
+    pub debits_cents: i64,
+    pub closing_balance_cents: i64,
+    pub transaction_count: i32,
+    pub finalized_at: Option<DateTime<Utc>>,
+    /// What the jobs charged in the period cost, item by item
+    pub cost_breakdown: Option<CostBreakdown>,
+}
+
+impl From<Invoice> for InvoiceResponse {
+    fn from(invoice: Invoice) -> Self {
+        let cost_breakdown = invoice.cost_breakdown();
+        Self {
+            id: invoice.id,
+            billing_period_id: invoice.billing_period_id,
+            customer_id: invoice.customer_id,
+            wallet_id: invoice.wallet_id,
+            period_start: invoice.period_start,
+            period_end: invoice.period_end,
+            timezone: invoice.timezone,
+            opening_balance_cents: invoice.opening_balance_cents,
+            credits_cents: invoice.credits_cents,
+            debits_cents: invoice.debits_cents,
+            closing_balance_cents: invoice.closing_balance_cents,
+            transaction_count: invoice.transaction_count,
+            finalized_at: invoice.finalized_at,
+            cost_breakdown,
+        }
+    }
+}
+
+/// Response data for a closed billing period with its invoices
+#[derive(Debug, Serialize)]
+pub struct BillingPeriodDetailsResponse {
+    #[serde(flatten)]
+    pub period: BillingPeriodResponse,
+    pub invoices: Vec<InvoiceResponse>,
+}
+
+/// Map a failed billing period operation to a status code
+fn ledger_error_status(e: &anyhow::Error) -> StatusCode {
+    match e.downcast_ref::<LedgerError>() {
+        Some(LedgerError::InvalidMonth { .. } | LedgerError::PeriodNotOver(_)) => StatusCode::BAD_REQUEST,
+        Some(LedgerError::AlreadyClosed(_) | LedgerError::PeriodClosed(_)) => StatusCode::CONFLICT,
+        None if e.to_string().contains("not found") => StatusCode::NOT_FOUND,
+        None => StatusCode::INTERNAL_SERVER_ERROR,
+    }
+}
+
+/// Close a billing month: its wallet transactions become final, an invoice is finalized
+/// for every wallet and the checksum of the ledger export is recorded
+///
+/// Later corrections to a closed month are posted as new transactions, e.g. through a
+/// wallet adjustment.
+/// Access: Admin
+pub async fn close_billing_period(
+    State(state): State<AppState>,
+    Extension(admin): Extension<AdminUser>,
+    StrictJson(payload): StrictJson<CloseBillingPeriodRequest>,
+) -> Result<(StatusCode, Json<BillingPeriodResponse>), StatusCode> {
+    let (period_start, period_end) = month_bounds(payload.year, payload.month)
+        .map_err(|e| {
+            error!("Failed to close billing period: {}", e);
+            StatusCode::BAD_REQUEST
+        })?;
+
+    let period = state.billing_period_repo.close(period_start, period_end, admin.id.clone()).await
+        .map_err(|e| {
+            error!("Failed to close billing period {}-{:02}: {}", payload.year, payload.month, e);
+            ledger_error_status(&e)
+        })?;
+
+    let entry = NewAuditEntry::new(admin.id.clone(), "billing_period.closed", "billing_period", period.id, json!({
+        "period_start": period.period_start,
+        "period_end": period.period_end,
+        "transaction_count": period.transaction_count,
+        "ledger_sha256": period.ledger_sha256,
+    }));
+    if let Err(e) = state.audit_log_repo.record(entry).await {
+        error!("Failed to record the closing of billing period {} in the audit log: {}", period.id, e);
+    }
+
+    info!("Admin {} closed billing period {}-{:02} with {} transactions", admin.id, payload.year, payload.month, period.transaction_count);
+    Ok((StatusCode::CREATED, Json(period.into())))
+}
+
+/// List the closed billing periods, most recent first
+/// Access: Admin
+pub async fn list_billing_periods(
+    State(state): State<AppState>,
+    Extension(_admin): Extension<AdminUser>,
+) -> Result<Json<Vec<BillingPeriodResponse>>, StatusCode> {
+    let periods = state.billing_period_repo.list().await
+        .map_err(|e| {
+            error!("Failed to list billing periods: {}", e);
+            StatusCode::INTERNAL_SERVER_ERROR
+        })?;
+
+    Ok(Json(periods.into_iter().map(BillingPeriodResponse::from).collect()))
+}
+
+/// Get a closed billing period with its invoices
+/// Access: Admin
+pub async fn get_billing_period(
+    State(state): State<AppState>,
+    Extension(_admin): Extension<AdminUser>,
+    Path(id): Path<Uuid>,
+) -> Result<Json<BillingPeriodDetailsResponse>, StatusCode> {
+    let period = state.billing_period_repo.find_by_id(id).await
+        .map_err(|e| {
+            error!("Failed to fetch billing period {}: {}", id, e);
+            ledger_error_status(&e)
+        })?;
+
+    let invoices = state.billing_period_repo.find_invoices(id).await
+        .map_err(|e| {
+            error!("Failed to fetch the invoices of billing period {}: {}", id, e);
+            StatusCode::INTERNAL_SERVER_ERROR
+        })?;
+
+    Ok(Json(BillingPeriodDetailsResponse {
+        period: period.into(),
+        invoices: invoices.into_iter().map(InvoiceResponse::from).collect(),
+    }))
+}
+
+/// Export the ledger of a closed billing period as CSV
+///
+/// The export is checked against the checksum recorded at closing time, so a ledger
+/// changed since is never handed out as final.
+/// Access: Admin
+pub async fn export_ledger(
+    State(state): State<AppState>,
+    Extension(_admin): Extension<AdminUser>,
+    Path(id): Path<Uuid>,
+) -> Result<impl IntoResponse, StatusCode> {
+    let period = state.billing_period_repo.find_by_id(id).await
+        .map_err(|e| {
+            error!("Failed to fetch billing period {}: {}", id, e);
+            ledger_error_status(&e)
+        })?;
+
+    let transactions = state.billing_period_repo.ledger(id).await
+        .map_err(|e| {
+            error!("Failed to fetch the ledger of billing period {}: {}", id, e);
+            StatusCode::INTERNAL_SERVER_ERROR
+        })?;
+
+    let csv = ledger_csv(&transactions);
+    let checksum = ledger_sha256(&csv);
+    if checksum != period.ledger_sha256 {
+        error!("Ledger of billing period {} no longer matches its closing checksum", id);
+        return Err(StatusCode::INTERNAL_SERVER_ERROR);
+    }
+
+    let filename = format!("attachment; filename=\"ledger-{}.csv\"", period.period_start.format("%Y-%m"));
+    Ok((
+        [
+            (header::CONTENT_TYPE, "text/csv".to_string()),
+            (header::CONTENT_DISPOSITION, filename),
+            (header::HeaderName::from_static("x-ledger-sha256"), checksum),
+        ],
+        csv,
+    ))
+}
+
+/// List the authenticated customer's final invoices, most recent first
+/// Access: Customer
+pub async fn list_customer_invoices(
+    State(state): State<AppState>,
+    Extension(customer): Extension<CustomerUser>,
+) -> Result<Json<Vec<InvoiceResponse>>, StatusCode> {
+    let invoices = state.billing_period_repo.find_invoices_by_customer(customer.id).await
+        .map_err(|e| {
+            error!("Failed to fetch the invoices of customer {}: {}", customer.id, e);
+            StatusCode::INTERNAL_SERVER_ERROR
+        })?;
+
+    Ok(Json(invoices.into_iter().map(InvoiceResponse::from).collect()))
+}
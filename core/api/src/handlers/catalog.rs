@@ -0,0 +1,199 @@
+use axum::{extract::{Path, Query, State}, http::StatusCode, Json};
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use uuid::Uuid;
+use tracing::error;
+
+use crate::services::cache::job_type_stats_key;
+use crate::state::AppState;
+use innosystem_common::models::input_contract::InputContract;
+use innosystem_common::models::job_type::{CatalogVisibility, JobType};
+use innosystem_common::repositories::job::JobTypeExecutionStats;
+use innosystem_common::repositories::job_type::CatalogFilter;
+
+/// Visibility scopes listed to customers
+const CUSTOMER_SCOPE: [CatalogVisibility; 1] = [CatalogVisibility::Public];
+/// Visibility scopes listed to resellers
+const RESELLER_SCOPE: [CatalogVisibility; 2] = [CatalogVisibility::Public, CatalogVisibility::Reseller];
+/// Hours of finished jobs the execution statistics of a job type are based on
+const STATS_WINDOW_HOURS: i32 = 24;
+
+/// Query parameters for browsing the catalog
+#[derive(Debug, Deserialize)]
+pub struct CatalogQuery {
+    /// Only list job types of this category
+    pub category: Option<String>,
+    /// Text searched in name, description and category
+    pub q: Option<String>,
+}
+
+/// A job type as presented in the marketplace catalog
+#[derive(Debug, Serialize)]
+pub struct CatalogEntry {
+    pub id: Uuid,
+    pub name: String,
+    pub description: Option<String>,
+    pub category: Option<String>,
+    pub icon: Option<String>,
+    pub documentation_url: Option<String>,
+    pub sample_input: Option<Value>,
+    pub sample_output: Option<Value>,
+    pub standard_cost_cents: i32,
+    pub visibility: String,
+    /// Input fields the job type's processor expects, e.g. `webhook_url` for webhooks
+    pub input_contract: InputContract,
+}
+
+impl From<JobType> for CatalogEntry {
+    fn from(job_type: JobType) -> Self {
+        Self {
+            id: job_type.id,
+            name: job_type.name,
+            description: job_type.description,
+            category: job_type.category,
+            icon: job_type.icon,
+            documentation_url: job_type.documentation_url,
+            sample_input: job_type.sample_input,
+            sample_output: job_type.sample_output,
+            standard_cost_cents: job_type.standard_cost_cents,
+            visibility: job_type.visibility.as_str().to_string(),
+            input_contract: InputContract::of(&job_type.processor_type),
+        }
+    }
+}
+
+/// Number of listed job types in a category
+#[derive(Debug, Serialize)]
+pub struct CatalogCategory {
+    pub category: String,
+    pub job_types: i64,
+}
+
+/// How a job type performed recently, for integrators to size their timeouts and expectations
+#[derive(Debug, Serialize, Deserialize)]
+pub struct JobTypeStatsResponse {
+    pub job_type_id: Uuid,
+    /// Hours of finished jobs the statistics are based on
+    pub window_hours: i32,
+    pub succeeded: i64,
+    pub failed: i64,
+    /// Share of finished jobs that succeeded; None if no job finished in the window
+    pub success_rate: Option<f64>,
+    /// Median time in seconds from start to completion
+    pub p50_processing_seconds: Option<f64>,
+    /// 95th percentile time in seconds from start to completion
+    pub p95_processing_seconds: Option<f64>,
+}
+
+impl From<JobTypeExecutionStats> for JobTypeStatsResponse {
+    fn from(stats: JobTypeExecutionStats) -> Self {
+        Self {
+            job_type_id: stats.job_type_id,
+            window_hours: STATS_WINDOW_HOURS,
+            succeeded: stats.succeeded,
+            failed: stats.failed,
+            success_rate: stats.success_rate(),
+            p50_processing_seconds: stats.p50_processing_seconds,
+            p95_processing_seconds: stats.p95_processing_seconds,
+        }
+    }
+}
+
+async fn search(state: &AppState, scope: &[CatalogVisibility], query: CatalogQuery) -> Result<Json<Vec<CatalogEntry>>, StatusCode> {
+    let filter = CatalogFilter {
+        visibilities: scope.to_vec(),
+        category: query.category.filter(|category| !category.trim().is_empty()),
+        search: query.q.filter(|q| !q.trim().is_empty()),
+    };
+
+    let job_types = state.job_type_repo.search_catalog(filter).await
+        .map_err(|e| {
+            error!("Failed to search the job type catalog: {}", e);
+            StatusCode::INTERNAL_SERVER_ERROR
+        })?;
+
+    Ok(Json(job_types.into_iter().map(CatalogEntry::from).collect()))
+}
+
+async fn categories(state: &AppState, scope: &[CatalogVisibility]) -> Result<Json<Vec<CatalogCategory>>, StatusCode> {
+    let categories = state.job_type_repo.list_catalog_categories(scope.to_vec()).await
+        .map_err(|e| {
+            error!("Failed to list job type catalog categories: {}", e);
+            StatusCode::INTERNAL_SERVER_ERROR
+        })?;
+
+    Ok(Json(categories.into_iter()
+        .map(|(category, job_types)| CatalogCategory { category, job_types })
+        .collect()))
+}
+
+/// Browse the public job type catalog, optionally by category or search text
+/// Access: Customer
+pub async fn browse_catalog(
+    State(state): State<AppState>,
+    Query(query): Query<CatalogQuery>,
+) -> Result<Json<Vec<CatalogEntry>>, StatusCode> {
+    search(&state, &CUSTOMER_SCOPE, query).await
+}
+
+/// List the categories of the public job type catalog
+/// Access: Customer
+pub async fn list_catalog_categories(
+    State(state): State<AppState>,
+) -> Result<Json<Vec<CatalogCategory>>, StatusCode> {
+    categories(&state, &CUSTOMER_SCOPE).await
+}
+
+/// Browse the job type catalog including reseller-only listings
+/// Access: Reseller
+pub async fn browse_reseller_catalog(
+    State(state): State<AppState>,
+    Query(query): Query<CatalogQuery>,
+) -> Result<Json<Vec<CatalogEntry>>, StatusCode> {
+    search(&state, &RESELLER_SCOPE, query).await
+}
+
+/// List the categories of the job type catalog including reseller-only listings
+/// Access: Reseller
+pub async fn list_reseller_catalog_categories(
+    State(state): State<AppState>,
+) -> Result<Json<Vec<CatalogCategory>>, StatusCode> {
+    categories(&state, &RESELLER_SCOPE).await
+}
+
+/// Get the processing time percentiles and success rate of a public job type over the last 24 hours
+/// Access: Customer
+pub async fn get_job_type_stats(
+    State(state): State<AppState>,
+    Path(job_type_id): Path<Uuid>,
+) -> Result<Json<JobTypeStatsResponse>, StatusCode> {
+    let cache_key = job_type_stats_key(job_type_id);
+    if let Some(cached) = state.response_cache.get::<JobTypeStatsResponse>(&cache_key).await {
+        return Ok(Json(cached));
+    }
+
+    let job_type = state.job_type_repo.find_by_id(job_type_id).await
+        .map_err(|e| {
+            if e.to_string().contains("not found") {
+                StatusCode::NOT_FOUND
+            } else {
+                error!("Failed to fetch job type: {}", e);
+                StatusCode::INTERNAL_SERVER_ERROR
+            }
+        })?;
+    // Job types customers cannot browse do not exist for them
+    if !CUSTOMER_SCOPE.contains(&job_type.visibility) {
+        return Err(StatusCode::NOT_FOUND);
+    }
+
+    let stats = state.job_repo.get_job_type_execution_stats(job_type.id, STATS_WINDOW_HOURS * 60).await
+        .map_err(|e| {
+            error!("Failed to compute execution statistics of job type {}: {}", job_type.id, e);
+            StatusCode::INTERNAL_SERVER_ERROR
+        })?;
+
+    let response = JobTypeStatsResponse::from(stats);
+    state.response_cache.put(&cache_key, &response).await;
+
+    Ok(Json(response))
+}
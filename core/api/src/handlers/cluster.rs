@@ -0,0 +1,24 @@
+use axum::{extract::{State, Extension}, http::StatusCode, Json};
+use tracing::error;
+
+use innosystem_common::queue::ClusterStatus;
+use crate::middleware::auth::AdminUser;
+use crate::state::AppState;
+
+/// Get this replica's identity and the replica leading each periodic background task
+///
+/// A task is only listed once this replica has competed for it, which happens on the
+/// task's first tick after startup.
+/// Access: Admin
+pub async fn get_cluster_status(
+    State(state): State<AppState>,
+    Extension(_admin): Extension<AdminUser>,
+) -> Result<Json<ClusterStatus>, StatusCode> {
+    let status = state.leader_election.status().await
+        .map_err(|e| {
+            error!("Failed to read the leaders of the background tasks: {}", e);
+            StatusCode::INTERNAL_SERVER_ERROR
+        })?;
+
+    Ok(Json(status))
+}
@@ -0,0 +1,242 @@
+use axum::{extract::{Query, State, Extension}, http::StatusCode, Json};
+use serde::{Deserialize, Serialize};
+use serde_json::json;
+use uuid::Uuid;
+use tracing::{error, info};
+
+use innosystem_common::models::audit::NewAuditEntry;
+use innosystem_common::models::config_document::{
+    ChangeAction, ConfigDocument, DeployedRunner, JobTypeSpec, ResourceChange, ResourceKind,
+};
+use innosystem_common::models::feature_flag::{FeatureFlag, NewFeatureFlag};
+use innosystem_common::models::job_type::{JobType, NewJobType, ProcessorType};
+use innosystem_common::models::runner::{NewRunner, RunnerStatus};
+use crate::middleware::auth::AdminUser;
+use crate::state::AppState;
+
+/// Query parameters for applying a configuration document
+#[derive(Debug, Deserialize)]
+pub struct ApplyConfigQuery {
+    /// Only return the change plan, without changing anything
+    #[serde(default)]
+    pub dry_run: bool,
+    /// Disable, deactivate or delete the resources missing from the document's
+    /// sections (defaults to true)
+    #[serde(default = "prune_by_default")]
+    pub prune: bool,
+}
+
+fn prune_by_default() -> bool {
+    true
+}
+
+/// Response data for an applied (or planned) configuration document
+#[derive(Debug, Serialize)]
+pub struct ApplyConfigResponse {
+    pub dry_run: bool,
+    /// Changes needed to reach the document, applied unless `dry_run`; empty when the
+    /// platform already matches it
+    pub changes: Vec<ResourceChange>,
+}
+
+/// Load the job types, runners and feature flags a document is diffed against
+async fn current_state(state: &AppState) -> anyhow::Result<(Vec<JobType>, Vec<DeployedRunner>, Vec<FeatureFlag>)> {
+    let job_types = state.job_type_repo.list_all().await?;
+
+    let mut runners = Vec::new();
+    for runner in state.runner_repo.list_all().await? {
+        let job_type_ids = state.runner_repo.find_compatible_job_type_ids(runner.id).await?;
+        runners.push(DeployedRunner { runner, job_type_ids });
+    }
+
+    let feature_flags = state.feature_flag_repo.list().await?;
+    Ok((job_types, runners, feature_flags))
+}
+
+/// Insert row of a job type created from its spec
+fn new_job_type(name: &str, spec: &JobTypeSpec) -> NewJobType {
+    let processor_type = ProcessorType::from_str(&spec.processor_type).unwrap_or(ProcessorType::Async);
+    NewJobType {
+        id: Uuid::new_v4(),
+        name: name.to_string(),
+        description: spec.description.clone(),
+        processing_logic_id: spec.processing_logic_id.clone(),
+        processor_type: processor_type.as_str().to_string(),
+        standard_cost_cents: spec.standard_cost_cents,
+        enabled: spec.enabled,
+        category: spec.category.clone(),
+        icon: spec.icon.clone(),
+        documentation_url: spec.documentation_url.clone(),
+        sample_input: spec.sample_input.clone(),
+        sample_output: spec.sample_output.clone(),
+        visibility: spec.visibility.as_str().to_string(),
+    }
+}
+
+/// Make one planned change, returning the IDs of the records it changed
+async fn apply_change(
+    state: &AppState,
+    admin: &AdminUser,
+    document: &ConfigDocument,
+    change: &ResourceChange,
+) -> anyhow::Result<Vec<Uuid>> {
+    let name = change.name.as_str();
+    match change.kind {
+        ResourceKind::JobType => {
+            let spec = document.job_types.as_ref().and_then(|specs| specs.get(name));
+            let job_type = match (change.action, spec) {
+                (ChangeAction::Create, Some(spec)) => {
+                    let mut job_type = state.job_type_repo.create(new_job_type(name, spec)).await?;
+                    if !spec.redaction_rules.is_empty() || spec.unredacted_retention_hours.is_some() {
+                        spec.apply_to(&mut job_type);
+                        job_type = state.job_type_repo.update(job_type).await?;
+                    }
+                    job_type
+                }
+                (ChangeAction::Update, Some(spec)) => {
+                    let mut job_type = find_job_type(state, name).await?;
+                    spec.apply_to(&mut job_type);
+                    state.job_type_repo.update(job_type).await?
+                }
+                (ChangeAction::Disable, _) => {
+                    let mut job_type = find_job_type(state, name).await?;
+                    job_type.enabled = false;
+                    state.job_type_repo.update(job_type).await?
+                }
+                _ => anyhow::bail!("No settings for job type {}", name),
+            };
+            Ok(vec![job_type.id])
+        }
+        ResourceKind::RunnerPool => {
+            let spec = document.runner_pools.as_ref().and_then(|specs| specs.get(name));
+            let job_types = state.job_type_repo.list_all().await?;
+            let pool_job_types: Vec<&JobType> = spec.into_iter()
+                .flat_map(|spec| spec.job_types.iter())
+                .filter_map(|job_type| job_types.iter().find(|candidate| &candidate.name == job_type))
+                .collect();
+            let job_type_ids: Vec<Uuid> = pool_job_types.iter().map(|job_type| job_type.id).collect();
+            let members: Vec<Uuid> = state.runner_repo.list_all().await?
+                .into_iter()
+                .filter(|runner| runner.name == name)
+                .map(|runner| runner.id)
+                .collect();
+
+            match (change.action, spec) {
+                (ChangeAction::Create, Some(spec)) => {
+                    let status = if spec.active { RunnerStatus::Active } else { RunnerStatus::Inactive };
+                    let runner = state.runner_repo.register(NewRunner {
+                        id: Uuid::new_v4(),
+                        name: name.to_string(),
+                        description: None,
+                        status: status.as_str().to_string(),
+                        compatible_job_types: pool_job_types.iter().map(|job_type| job_type.name.clone()).collect(),
+                    }).await?;
+                    Ok(vec![runner.id])
+                }
+                (ChangeAction::Update, Some(spec)) => {
+                    for id in &members {
+                        state.runner_repo.update_capabilities(*id, job_type_ids.clone()).await?;
+                        state.runner_repo.set_status(*id, spec.active).await?;
+                    }
+                    Ok(members)
+                }
+                (ChangeAction::Disable, _) => {
+                    for id in &members {
+                        state.runner_repo.set_status(*id, false).await?;
+                    }
+                    Ok(members)
+                }
+                _ => anyhow::bail!("No settings for runner pool {}", name),
+            }
+        }
+        ResourceKind::FeatureFlag => {
+            let spec = document.feature_flags.as_ref().and_then(|specs| specs.get(name));
+            let flag = match (change.action, spec) {
+                (ChangeAction::Delete, _) => state.feature_flag_repo.delete(name.to_string()).await?,
+                (_, Some(spec)) => state.feature_flag_repo.upsert(NewFeatureFlag {
+                    id: Uuid::new_v4(),
+                    name: name.to_string(),
+                    enabled: spec.enabled,
+                    reseller_ids: spec.reseller_ids.iter().copied().collect(),
+                    description: spec.description.clone(),
+                    updated_by: admin.id.clone(),
+                }).await?,
+                _ => anyhow::bail!("No settings for feature flag {}", name),
+            };
+            state.feature_flags.invalidate();
+            Ok(vec![flag.id])
+        }
+    }
+}
+
+/// Find a job type by name
+async fn find_job_type(state: &AppState, name: &str) -> anyhow::Result<JobType> {
+    state.job_type_repo.list_all().await?
+        .into_iter()
+        .find(|job_type| job_type.name == name)
+        .ok_or_else(|| anyhow::anyhow!("Job type not found: {}", name))
+}
+
+/// Bring job types, runner pools and feature flags in line with a declarative document
+///
+/// The body is a YAML or JSON [`ConfigDocument`]. The response lists the changes the
+/// document makes; with `?dry_run=true` they are only planned. With `?prune=false`
+/// resources missing from the document are left alone, so a partial document only
+/// creates and updates. Changes are made in
+/// order and not in a transaction: if one fails the request fails with the earlier
+/// changes kept, and applying the document again completes it. Each change is
+/// recorded in the audit log.
+/// Access: Admin
+pub async fn apply_config(
+    State(state): State<AppState>,
+    Extension(admin): Extension<AdminUser>,
+    Query(query): Query<ApplyConfigQuery>,
+    body: String,
+) -> Result<Json<ApplyConfigResponse>, StatusCode> {
+    let document = ConfigDocument::parse(&body)
+        .map_err(|e| {
+            error!("Invalid configuration document: {}", e);
+            StatusCode::BAD_REQUEST
+        })?;
+
+    let (job_types, runners, feature_flags) = current_state(&state).await
+        .map_err(|e| {
+            error!("Failed to load the current configuration: {}", e);
+            StatusCode::INTERNAL_SERVER_ERROR
+        })?;
+    let changes = document.plan(&job_types, &runners, &feature_flags, query.prune)
+        .map_err(|e| {
+            error!("Configuration document cannot be applied: {}", e);
+            StatusCode::UNPROCESSABLE_ENTITY
+        })?;
+
+    if query.dry_run {
+        return Ok(Json(ApplyConfigResponse { dry_run: true, changes }));
+    }
+
+    for change in &changes {
+        let ids = apply_change(&state, &admin, &document, change).await
+            .map_err(|e| {
+                error!("Failed to {:?} {} {}: {}", change.action, change.kind.as_str(), change.name, e);
+                StatusCode::INTERNAL_SERVER_ERROR
+            })?;
+
+        let entity_type = match change.kind {
+            ResourceKind::RunnerPool => "runner",
+            kind => kind.as_str(),
+        };
+        for id in ids {
+            let entry = NewAuditEntry::new(admin.id.clone(), format!("{}.{}", entity_type, change.action.past_tense()), entity_type, id, json!({
+                "name": change.name,
+                "fields": change.fields,
+                "source": "config.apply",
+            }));
+            if let Err(e) = state.audit_log_repo.record(entry).await {
+                error!("Failed to record the change of {} {} in the audit log: {}", entity_type, change.name, e);
+            }
+        }
+    }
+
+    info!("Admin {} applied a configuration document with {} changes", admin.id, changes.len());
+    Ok(Json(ApplyConfigResponse { dry_run: false, changes }))
+}
@@ -0,0 +1,62 @@
+use axum::{extract::{State, Extension}, http::StatusCode, Json};
+use serde::Serialize;
+use tracing::info;
+
+use crate::middleware::auth::AdminUser;
+use crate::services::backpressure::SaturationPolicy;
+use crate::services::config_reload::{ConfigReload, ConfigReloadStatus};
+use crate::state::AppState;
+
+/// Tunable settings currently applied by this replica
+#[derive(Debug, Serialize)]
+pub struct TunableSettingsResponse {
+    pub backpressure_priority_limits: [u64; 4],
+    pub backpressure_job_type_limits: usize,
+    pub backpressure_policy: SaturationPolicy,
+    pub backpressure_retry_after_secs: u64,
+    pub feature_flag_cache_ttl_secs: u64,
+}
+
+/// Response data for the configuration reloads of this replica
+#[derive(Debug, Serialize)]
+pub struct ConfigReloadStatusResponse {
+    pub settings: TunableSettingsResponse,
+    #[serde(flatten)]
+    pub status: ConfigReloadStatus,
+}
+
+/// Get the tunable settings this replica applies and the outcome of its last reload
+/// Access: Admin
+pub async fn get_config_reload_status(
+    State(state): State<AppState>,
+    Extension(_admin): Extension<AdminUser>,
+) -> Json<ConfigReloadStatusResponse> {
+    let current = state.config_reload.current();
+    Json(ConfigReloadStatusResponse {
+        settings: TunableSettingsResponse {
+            backpressure_priority_limits: current.backpressure.priority_limits,
+            backpressure_job_type_limits: current.backpressure.job_type_limits.len(),
+            backpressure_policy: current.backpressure.policy,
+            backpressure_retry_after_secs: current.backpressure.retry_after_secs,
+            feature_flag_cache_ttl_secs: current.feature_flags.cache_ttl_secs,
+        },
+        status: state.config_reload.status(),
+    })
+}
+
+/// Read the settings file again and apply the tunable settings, as SIGHUP does
+///
+/// Only this replica is reloaded; other replicas are reloaded through their own
+/// endpoint or signal. A file with an invalid value is refused as a whole, with the
+/// reason reported by `GET /admin/config/reload`.
+/// Access: Admin
+pub async fn reload_config(
+    State(state): State<AppState>,
+    Extension(admin): Extension<AdminUser>,
+) -> Result<Json<ConfigReload>, StatusCode> {
+    let reload = state.config_reload.reload()
+        .map_err(|_| StatusCode::UNPROCESSABLE_ENTITY)?;
+
+    info!("Admin {} reloaded the configuration (changed: {:?})", admin.id, reload.changed);
+    Ok(Json(reload))
+}
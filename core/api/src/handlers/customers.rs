@@ -1,13 +1,23 @@
-use axum::{extract::{Path, State, Extension}, http::StatusCode, Json};
+use axum::{extract::{Path, Query, State, Extension}, http::StatusCode, Json};
 use serde::{Deserialize, Serialize};
 use uuid::Uuid;
-use tracing::error;
+use tracing::{error, warn};
+use chrono::{DateTime, Utc};
 
+use innosystem_common::database::with_reseller;
+use innosystem_common::models::job::PriorityLevel;
+use innosystem_common::models::pii::PiiPolicy;
+use innosystem_common::timezone::{self, Tz};
+use crate::handlers::dependencies::{DependencyError, ForceQuery};
+use crate::handlers::jobs::priority_name;
+use crate::middleware::auth::{verify_reseller_access, ResellerUser};
+use crate::request::{self, StrictJson};
 use crate::state::AppState;
 // Customer model is imported via NewCustomer
 
 /// Request data for creating a new customer
 #[derive(Debug, Deserialize)]
+#[serde(deny_unknown_fields)]
 pub struct CreateCustomerRequest {
     /// Customer name
     pub name: String,
@@ -32,50 +42,60 @@ pub struct CustomerResponse {
     pub api_key: Option<String>,
     /// Reseller ID (if the customer belongs to a reseller)
     pub reseller_id: Option<Uuid>,
+    /// Time zone the customer's days and months follow (IANA name); the reseller's when not set
+    pub timezone: Option<String>,
+    /// Priority of the customer's jobs submitted without one ("low", "medium", "high" or "critical")
+    pub default_priority: Option<String>,
+    /// What happens to jobs whose input contains personal data ("reject", "mask" or
+    /// "flag"); inputs are not scanned when not set
+    pub pii_policy: Option<String>,
+    /// Whether the customer may authenticate
+    pub active: bool,
     /// Wallet ID
     pub wallet_id: Option<Uuid>,
     /// Wallet balance in cents
     pub balance_cents: Option<i64>,
     /// Creation timestamp
-    pub created_at: Option<String>,
+    pub created_at: Option<DateTime<Utc>>,
     /// Last update timestamp
-    pub updated_at: Option<String>,
+    pub updated_at: Option<DateTime<Utc>>,
 }
 
 /// Create a new customer
 pub async fn create_customer(
     State(state): State<AppState>,
-    Extension(api_key): Extension<String>,
-    Json(payload): Json<CreateCustomerRequest>,
+    reseller: Option<Extension<ResellerUser>>,
+    StrictJson(payload): StrictJson<CreateCustomerRequest>,
 ) -> (StatusCode, Json<CustomerResponse>) {
-    // Determine the reseller_id based on API key
-    let reseller_id = match payload.reseller_id {
-        // If specified in payload, use that (for admin operations)
-        Some(id) => Some(id),
-        // Otherwise, lookup reseller by API key (for reseller operations)
-        None => match state.reseller_repo.find_by_api_key(&api_key).await {
-            Ok(reseller) => Some(reseller.id),
-            Err(e) => {
-                // If it's an admin key, then customer has no reseller
-                if &api_key == &state.config.admin_api_key {
-                    None
-                } else {
-                    error!("Failed to find reseller by API key: {}", e);
-                    return (StatusCode::INTERNAL_SERVER_ERROR, Json(CustomerResponse {
-                        id: Uuid::nil(),
-                        name: "".to_string(),
-                        email: "".to_string(),
-                        api_key: None,
-                        reseller_id: None,
-                        wallet_id: None,
-                        balance_cents: None,
-                        created_at: None,
-                        updated_at: None,
-                    }));
-                }
+    // Resellers create customers under themselves; admins may name the reseller
+    let reseller_id = match reseller {
+        Some(Extension(reseller)) => Some(reseller.id),
+        None => payload.reseller_id,
+    };
+    
+    // Refuse new customers under a deactivated reseller that blocks customer creation
+    if let Some(reseller_id) = reseller_id {
+        if let Ok(reseller) = state.reseller_repo.find_by_id(reseller_id).await {
+            if !reseller.allows_customer_creation() {
+                error!("Customer creation blocked for deactivated reseller {}", reseller_id);
+                return (StatusCode::FORBIDDEN, Json(CustomerResponse {
+                    id: Uuid::nil(),
+                    name: "".to_string(),
+                    email: "".to_string(),
+                    api_key: None,
+                    reseller_id: Some(reseller_id),
+                    timezone: None,
+                    default_priority: None,
+                    pii_policy: None,
+                    active: false,
+                    wallet_id: None,
+                    balance_cents: None,
+                    created_at: None,
+                    updated_at: None,
+                }));
             }
         }
-    };
+    }
     
     // Generate API key if needed
     let api_key = if reseller_id.is_some() {
@@ -92,6 +112,8 @@ pub async fn create_customer(
         email: payload.email.clone(),
         api_key,
         reseller_id,
+        parent_id: None,
+        budget_cents: None,
     };
     
     // Insert the customer into the database
@@ -105,6 +127,10 @@ pub async fn create_customer(
                 email: "".to_string(),
                 api_key: None,
                 reseller_id: None,
+                timezone: None,
+                default_priority: None,
+                pii_policy: None,
+                active: false,
                 wallet_id: None,
                 balance_cents: None,
                 created_at: None,
@@ -132,10 +158,14 @@ pub async fn create_customer(
                 email: customer.email,
                 api_key: customer.api_key.clone(),
                 reseller_id: customer.reseller_id,
+                timezone: customer.timezone.clone(),
+                default_priority: customer.default_priority.map(priority_name),
+                pii_policy: customer.pii_policy.clone(),
+                active: customer.active,
                 wallet_id: None,
                 balance_cents: None,
-                created_at: customer.created_at.map(|dt| dt.and_utc().to_rfc3339()),
-                updated_at: customer.updated_at.map(|dt| dt.and_utc().to_rfc3339()),
+                created_at: customer.created_at,
+                updated_at: customer.updated_at,
             }));
         }
     };
@@ -147,10 +177,14 @@ pub async fn create_customer(
         email: customer.email,
         api_key: customer.api_key.clone(),
         reseller_id: customer.reseller_id,
+        timezone: customer.timezone.clone(),
+        default_priority: customer.default_priority.map(priority_name),
+        pii_policy: customer.pii_policy.clone(),
+        active: customer.active,
         wallet_id: Some(wallet.id),
         balance_cents: Some(wallet.balance_cents as i64), // Convert i32 to i64
-        created_at: customer.created_at.map(|dt| dt.and_utc().to_rfc3339()),
-        updated_at: customer.updated_at.map(|dt| dt.and_utc().to_rfc3339()),
+        created_at: customer.created_at,
+        updated_at: customer.updated_at,
     };
     
     tracing::info!("Created new customer with ID: {}", customer.id);
@@ -161,6 +195,7 @@ pub async fn create_customer(
 pub async fn get_customer(
     State(state): State<AppState>,
     Path(customer_id_str): Path<String>,
+    reseller: Option<Extension<ResellerUser>>,
 ) -> Result<Json<CustomerResponse>, StatusCode> {
     // Try to parse the customer_id as a UUID
     let customer_id = match Uuid::parse_str(&customer_id_str) {
@@ -183,6 +218,9 @@ pub async fn get_customer(
             }
         })?;
     
+    // Resellers only see their own customers
+    verify_reseller_access(customer.reseller_id, &reseller)?;
+    
     // Fetch the customer's wallet
     let wallet = state.wallet_repo.find_by_customer_id(customer.id).await;
     
@@ -201,10 +239,14 @@ pub async fn get_customer(
         email: customer.email,
         api_key: customer.api_key.clone(),
         reseller_id: customer.reseller_id,
+        timezone: customer.timezone.clone(),
+        default_priority: customer.default_priority.map(priority_name),
+        pii_policy: customer.pii_policy.clone(),
+        active: customer.active,
         wallet_id,
         balance_cents: balance_cents.map(|b| b as i64), // Convert from i32 to i64
-        created_at: customer.created_at.map(|dt| dt.and_utc().to_rfc3339()),
-        updated_at: customer.updated_at.map(|dt| dt.and_utc().to_rfc3339()),
+        created_at: customer.created_at,
+        updated_at: customer.updated_at,
     };
     
     tracing::info!("Retrieved customer with ID: {}", customer.id);
@@ -214,27 +256,21 @@ pub async fn get_customer(
 /// Get all customers
 pub async fn get_all_customers(
     State(state): State<AppState>,
-    Extension(api_key): Extension<String>,
+    reseller: Option<Extension<ResellerUser>>,
 ) -> Result<Json<Vec<CustomerResponse>>, StatusCode> {
     // Determine if this is an admin or reseller request
-    let customers = if api_key == state.config.admin_api_key {
-        // Admin sees all customers
-        state.customer_repo.list_all().await
+    let customers = if let Some(Extension(reseller)) = reseller {
+        // Reseller sees only their customers
+        state.customer_repo.find_by_reseller_id(reseller.id).await
             .map_err(|e| {
-                error!("Failed to fetch customers: {}", e);
+                error!("Failed to fetch customers for reseller {}: {}", reseller.id, e);
                 StatusCode::INTERNAL_SERVER_ERROR
             })?
     } else {
-        // Reseller sees only their customers
-        let reseller = state.reseller_repo.find_by_api_key(&api_key).await
-            .map_err(|e| {
-                error!("Failed to find reseller by API key: {}", e);
-                StatusCode::INTERNAL_SERVER_ERROR
-            })?;
-        
-        state.customer_repo.find_by_reseller_id(reseller.id).await
+        // Admin sees all customers
+        state.customer_repo.list_all().await
             .map_err(|e| {
-                error!("Failed to fetch customers for reseller {}: {}", reseller.id, e);
+                error!("Failed to fetch customers: {}", e);
                 StatusCode::INTERNAL_SERVER_ERROR
             })?
     };
@@ -257,13 +293,326 @@ pub async fn get_all_customers(
             email: customer.email,
             api_key: customer.api_key.clone(),
             reseller_id: customer.reseller_id,
+            timezone: customer.timezone.clone(),
+            default_priority: customer.default_priority.map(priority_name),
+            pii_policy: customer.pii_policy.clone(),
+            active: customer.active,
             wallet_id,
             balance_cents: balance_cents.map(|b| b as i64), // Convert from i32 to i64
-            created_at: customer.created_at.map(|dt| dt.and_utc().to_rfc3339()),
-            updated_at: customer.updated_at.map(|dt| dt.and_utc().to_rfc3339()),
+            created_at: customer.created_at,
+            updated_at: customer.updated_at,
         });
     }
     
     tracing::info!("Retrieved all customers from database");
     Ok(Json(customer_responses))
 }
+
+/// Request data for setting a customer's time zone
+#[derive(Debug, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct UpdateTimezoneRequest {
+    /// IANA time zone name such as `Europe/Helsinki`; null or empty falls back to the reseller's
+    pub timezone: Option<String>,
+}
+
+/// Set or remove the time zone of a customer, which its submission windows, budget months
+/// and invoice periods follow; timestamps stay in UTC
+pub async fn update_customer_timezone(
+    State(state): State<AppState>,
+    Path(customer_id): Path<Uuid>,
+    reseller: Option<Extension<ResellerUser>>,
+    StrictJson(request): StrictJson<UpdateTimezoneRequest>,
+) -> Result<Json<CustomerResponse>, StatusCode> {
+    let timezone = request.timezone.filter(|name| !name.trim().is_empty());
+    if let Some(name) = &timezone {
+        if timezone::parse(name).is_none() {
+            error!("Invalid time zone {:?} for customer {}", name, customer_id);
+            return Err(StatusCode::BAD_REQUEST);
+        }
+    }
+
+    let customer = state.customer_repo.find_by_id(customer_id).await
+        .map_err(|e| {
+            error!("Failed to fetch customer {}: {}", customer_id, e);
+            StatusCode::NOT_FOUND
+        })?;
+    verify_reseller_access(customer.reseller_id, &reseller)?;
+
+    let customer = state.customer_repo.set_timezone(customer_id, timezone).await
+        .map_err(|e| {
+            error!("Failed to set time zone of customer {}: {}", customer_id, e);
+            StatusCode::INTERNAL_SERVER_ERROR
+        })?;
+
+    let (wallet_id, balance_cents) = match state.wallet_repo.find_by_customer_id(customer.id).await {
+        Ok(wallet) => (Some(wallet.id), Some(wallet.balance_cents as i64)),
+        Err(_) => (None, None),
+    };
+
+    tracing::info!("Set time zone of customer {} to {:?}", customer.id, customer.timezone);
+    Ok(Json(CustomerResponse {
+        id: customer.id,
+        name: customer.name,
+        email: customer.email,
+        api_key: customer.api_key.clone(),
+        reseller_id: customer.reseller_id,
+        timezone: customer.timezone.clone(),
+        default_priority: customer.default_priority.map(priority_name),
+        pii_policy: customer.pii_policy.clone(),
+        active: customer.active,
+        wallet_id,
+        balance_cents,
+        created_at: customer.created_at,
+        updated_at: customer.updated_at,
+    }))
+}
+
+/// Request data for setting the default priority of a customer's jobs
+#[derive(Debug, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct UpdateDefaultPriorityRequest {
+    /// Priority level, 0 (low) to 3 (critical) or its name; null falls back to the job type's
+    #[serde(deserialize_with = "request::optional_priority")]
+    pub default_priority: Option<PriorityLevel>,
+}
+
+/// Set or remove the default priority of a customer, given to its jobs submitted
+/// without one ahead of the job type's default, e.g. high for premium customers
+pub async fn update_customer_default_priority(
+    State(state): State<AppState>,
+    Path(customer_id): Path<Uuid>,
+    reseller: Option<Extension<ResellerUser>>,
+    StrictJson(request): StrictJson<UpdateDefaultPriorityRequest>,
+) -> Result<Json<CustomerResponse>, StatusCode> {
+    let customer = state.customer_repo.find_by_id(customer_id).await
+        .map_err(|e| {
+            error!("Failed to fetch customer {}: {}", customer_id, e);
+            StatusCode::NOT_FOUND
+        })?;
+    verify_reseller_access(customer.reseller_id, &reseller)?;
+
+    let default_priority = request.default_priority.map(|priority| priority.as_i32());
+    let customer = state.customer_repo.set_default_priority(customer_id, default_priority).await
+        .map_err(|e| {
+            error!("Failed to set default priority of customer {}: {}", customer_id, e);
+            StatusCode::INTERNAL_SERVER_ERROR
+        })?;
+
+    let (wallet_id, balance_cents) = match state.wallet_repo.find_by_customer_id(customer.id).await {
+        Ok(wallet) => (Some(wallet.id), Some(wallet.balance_cents as i64)),
+        Err(_) => (None, None),
+    };
+
+    tracing::info!("Set default priority of customer {} to {:?}", customer.id, customer.default_priority);
+    Ok(Json(CustomerResponse {
+        id: customer.id,
+        name: customer.name,
+        email: customer.email,
+        api_key: customer.api_key.clone(),
+        reseller_id: customer.reseller_id,
+        timezone: customer.timezone.clone(),
+        default_priority: customer.default_priority.map(priority_name),
+        pii_policy: customer.pii_policy.clone(),
+        active: customer.active,
+        wallet_id,
+        balance_cents,
+        created_at: customer.created_at,
+        updated_at: customer.updated_at,
+    }))
+}
+
+/// Request data for setting the policy for personal data in a customer's job inputs
+#[derive(Debug, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct UpdatePiiPolicyRequest {
+    /// `reject`, `mask` or `flag`; null stops scanning the customer's job inputs
+    pub pii_policy: Option<PiiPolicy>,
+}
+
+/// Set or remove the policy applied when a customer's job inputs contain email
+/// addresses or card numbers: reject the job, mask the data or flag the job in its log
+pub async fn update_customer_pii_policy(
+    State(state): State<AppState>,
+    Path(customer_id): Path<Uuid>,
+    reseller: Option<Extension<ResellerUser>>,
+    StrictJson(request): StrictJson<UpdatePiiPolicyRequest>,
+) -> Result<Json<CustomerResponse>, StatusCode> {
+    let customer = state.customer_repo.find_by_id(customer_id).await
+        .map_err(|e| {
+            error!("Failed to fetch customer {}: {}", customer_id, e);
+            StatusCode::NOT_FOUND
+        })?;
+    verify_reseller_access(customer.reseller_id, &reseller)?;
+
+    let customer = state.customer_repo.set_pii_policy(customer_id, request.pii_policy).await
+        .map_err(|e| {
+            error!("Failed to set PII policy of customer {}: {}", customer_id, e);
+            StatusCode::INTERNAL_SERVER_ERROR
+        })?;
+
+    let (wallet_id, balance_cents) = match state.wallet_repo.find_by_customer_id(customer.id).await {
+        Ok(wallet) => (Some(wallet.id), Some(wallet.balance_cents as i64)),
+        Err(_) => (None, None),
+    };
+
+    tracing::info!("Set PII policy of customer {} to {:?}", customer.id, customer.pii_policy);
+    Ok(Json(CustomerResponse {
+        id: customer.id,
+        name: customer.name,
+        email: customer.email,
+        api_key: customer.api_key.clone(),
+        reseller_id: customer.reseller_id,
+        timezone: customer.timezone.clone(),
+        default_priority: customer.default_priority.map(priority_name),
+        pii_policy: customer.pii_policy.clone(),
+        active: customer.active,
+        wallet_id,
+        balance_cents,
+        created_at: customer.created_at,
+        updated_at: customer.updated_at,
+    }))
+}
+
+/// Request data for activating or deactivating a customer
+#[derive(Debug, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct UpdateStatusRequest {
+    /// Whether the customer may authenticate
+    pub active: bool,
+}
+
+/// Activate or deactivate a customer; deactivated customers can no longer authenticate
+///
+/// A customer with queued jobs or reserved funds is not deactivated (409 listing them)
+/// unless `?force=true` is given; forced, its pending and scheduled jobs are cancelled
+/// and what was reserved for them is released. Running jobs finish and are billed as usual.
+pub async fn update_customer_status(
+    State(state): State<AppState>,
+    Path(customer_id): Path<Uuid>,
+    Query(query): Query<ForceQuery>,
+    reseller: Option<Extension<ResellerUser>>,
+    StrictJson(request): StrictJson<UpdateStatusRequest>,
+) -> Result<Json<CustomerResponse>, DependencyError> {
+    let customer = state.customer_repo.find_by_id(customer_id).await
+        .map_err(|e| {
+            error!("Failed to fetch customer {}: {}", customer_id, e);
+            StatusCode::NOT_FOUND
+        })?;
+    verify_reseller_access(customer.reseller_id, &reseller)?;
+
+    if customer.active && !request.active {
+        // The customer's jobs and reservations are in its reseller's schema if it has one
+        let blockers = with_reseller(customer.reseller_id, state.dependencies.customer_blockers(customer_id)).await
+            .map_err(|e| {
+                error!("Failed to check the dependents of customer {}: {}", customer_id, e);
+                StatusCode::INTERNAL_SERVER_ERROR
+            })?;
+        if !blockers.is_empty() {
+            if !query.force {
+                tracing::info!("Refused to deactivate customer {}: {} blockers", customer_id, blockers.len());
+                return Err(DependencyError::Blocked(blockers));
+            }
+            with_reseller(customer.reseller_id, state.dependencies.cancel_queued_jobs_of_customer(customer_id)).await
+                .map_err(|e| {
+                    error!("Failed to cancel the queued jobs of customer {}: {}", customer_id, e);
+                    StatusCode::INTERNAL_SERVER_ERROR
+                })?;
+        }
+    }
+
+    let customer = state.customer_repo.set_active(customer_id, request.active).await
+        .map_err(|e| {
+            error!("Failed to set status of customer {}: {}", customer_id, e);
+            StatusCode::INTERNAL_SERVER_ERROR
+        })?;
+
+    let (wallet_id, balance_cents) = match state.wallet_repo.find_by_customer_id(customer.id).await {
+        Ok(wallet) => (Some(wallet.id), Some(wallet.balance_cents as i64)),
+        Err(_) => (None, None),
+    };
+
+    tracing::info!("Customer {} is now {}", customer.id, if customer.active { "active" } else { "deactivated" });
+    Ok(Json(CustomerResponse {
+        id: customer.id,
+        name: customer.name,
+        email: customer.email,
+        api_key: customer.api_key.clone(),
+        reseller_id: customer.reseller_id,
+        timezone: customer.timezone.clone(),
+        default_priority: customer.default_priority.map(priority_name),
+        pii_policy: customer.pii_policy.clone(),
+        active: customer.active,
+        wallet_id,
+        balance_cents,
+        created_at: customer.created_at,
+        updated_at: customer.updated_at,
+    }))
+}
+
+/// Time zone a customer's days and months follow: its own, else its reseller's, else UTC
+///
+/// Lookup failures fall back to UTC rather than failing the caller.
+pub(crate) async fn customer_timezone(state: &AppState, customer_id: Uuid) -> Tz {
+    let customer = match state.customer_repo.find_by_id(customer_id).await {
+        Ok(customer) => customer,
+        Err(e) => {
+            warn!("Failed to fetch customer {} for its time zone, using UTC: {}", customer_id, e);
+            return Tz::UTC;
+        }
+    };
+    if let Some(tz) = customer.timezone.as_deref().and_then(timezone::parse) {
+        return tz;
+    }
+
+    let reseller_timezone = match customer.reseller_id {
+        Some(reseller_id) => match state.reseller_repo.find_by_id(reseller_id).await {
+            Ok(reseller) => reseller.timezone,
+            Err(e) => {
+                warn!("Failed to fetch reseller {} for its time zone, using UTC: {}", reseller_id, e);
+                None
+            }
+        },
+        None => None,
+    };
+    timezone::effective(None, reseller_timezone.as_deref())
+}
+
+/// Response data for a customer's sandbox key
+#[derive(Debug, Serialize)]
+pub struct TestApiKeyResponse {
+    /// Customer ID
+    pub customer_id: Uuid,
+    /// Sandbox API key; jobs submitted with it run in test mode and are billed against the test balance
+    pub test_api_key: String,
+}
+
+/// Generate (or rotate) the sandbox API key of a customer
+pub async fn generate_test_api_key(
+    State(state): State<AppState>,
+    Path(customer_id): Path<Uuid>,
+    reseller: Option<Extension<ResellerUser>>,
+) -> Result<Json<TestApiKeyResponse>, StatusCode> {
+    // Resellers only manage their own customers
+    if reseller.is_some() {
+        let customer = state.customer_repo.find_by_id(customer_id).await
+            .map_err(|e| {
+                tracing::error!("Failed to fetch customer {}: {}", customer_id, e);
+                StatusCode::NOT_FOUND
+            })?;
+        verify_reseller_access(customer.reseller_id, &reseller)?;
+    }
+    
+    let test_api_key = state.customer_repo.generate_test_api_key(customer_id).await
+        .map_err(|e| {
+            tracing::error!("Failed to generate test API key for customer {}: {}", customer_id, e);
+            if e.to_string().contains("not found") {
+                StatusCode::NOT_FOUND
+            } else {
+                StatusCode::INTERNAL_SERVER_ERROR
+            }
+        })?;
+    
+    tracing::info!("Generated test API key for customer {}", customer_id);
+    Ok(Json(TestApiKeyResponse { customer_id, test_api_key }))
+}
@@ -0,0 +1,58 @@
+//! Refusal of destructive operations on entities with dependents
+//!
+//! Deleting a project with jobs, disabling a job type with queued jobs or deactivating
+//! a customer with queued jobs or reserved funds is answered with 409 Conflict and the
+//! blockers, e.g.
+//!
+//! ```json
+//! {"error": "has_dependents", "blockers": [{"kind": "queued_jobs", "count": 3, "message": "3 jobs are queued; ..."}]}
+//! ```
+//!
+//! Repeating the request with `?force=true` performs it anyway, with the cascade each
+//! blocker's message describes.
+
+use axum::{
+    http::StatusCode,
+    response::{IntoResponse, Response},
+    Json,
+};
+use serde::Deserialize;
+
+use innosystem_common::api::ApiErrorBody;
+use innosystem_common::models::dependency::Blocker;
+
+/// Query of a destructive operation
+#[derive(Debug, Default, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct ForceQuery {
+    /// Perform the operation despite its blockers, cascading to the dependents
+    #[serde(default)]
+    pub force: bool,
+}
+
+/// Reason a destructive operation was not performed
+#[derive(Debug)]
+pub(crate) enum DependencyError {
+    /// The request failed with the given status
+    Status(StatusCode),
+    /// The entity has dependents and the operation was not forced
+    Blocked(Vec<Blocker>),
+}
+
+impl From<StatusCode> for DependencyError {
+    fn from(status: StatusCode) -> Self {
+        Self::Status(status)
+    }
+}
+
+impl IntoResponse for DependencyError {
+    fn into_response(self) -> Response {
+        match self {
+            Self::Status(status) => status.into_response(),
+            Self::Blocked(blockers) => (
+                StatusCode::CONFLICT,
+                Json(ApiErrorBody::new("has_dependents").with("blockers", blockers)),
+            ).into_response(),
+        }
+    }
+}
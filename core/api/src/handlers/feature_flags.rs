@@ -0,0 +1,167 @@
+use axum::{extract::{Path, State, Extension}, http::StatusCode, Json};
+use serde::{Deserialize, Serialize};
+use serde_json::json;
+use uuid::Uuid;
+use tracing::{error, info};
+use chrono::{DateTime, Utc};
+
+use innosystem_common::models::audit::NewAuditEntry;
+use innosystem_common::models::feature_flag::{FeatureFlag, FeatureFlagError, NewFeatureFlag};
+use crate::middleware::auth::{AdminUser, CustomerUser};
+use crate::request::StrictJson;
+use crate::state::AppState;
+
+/// Request data for creating or replacing a feature flag
+#[derive(Debug, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct UpdateFeatureFlagRequest {
+    /// On for everyone (defaults to false)
+    #[serde(default)]
+    pub enabled: bool,
+    /// On for the customers of these resellers only, e.g. for a canary
+    #[serde(default)]
+    pub reseller_ids: Vec<Uuid>,
+    pub description: Option<String>,
+}
+
+/// Response data for a feature flag
+#[derive(Debug, Serialize)]
+pub struct FeatureFlagResponse {
+    pub id: Uuid,
+    pub name: String,
+    pub enabled: bool,
+    pub reseller_ids: Vec<Uuid>,
+    pub description: Option<String>,
+    pub updated_by: String,
+    pub created_at: Option<DateTime<Utc>>,
+    pub updated_at: Option<DateTime<Utc>>,
+}
+
+impl From<FeatureFlag> for FeatureFlagResponse {
+    fn from(flag: FeatureFlag) -> Self {
+        Self {
+            id: flag.id,
+            name: flag.name,
+            enabled: flag.enabled,
+            reseller_ids: flag.reseller_ids,
+            description: flag.description,
+            updated_by: flag.updated_by,
+            created_at: flag.created_at,
+            updated_at: flag.updated_at,
+        }
+    }
+}
+
+/// Map a failed feature flag operation to a status code
+fn feature_flag_error_status(e: &anyhow::Error) -> StatusCode {
+    match e.downcast_ref::<FeatureFlagError>() {
+        Some(FeatureFlagError::InvalidName(_)) => StatusCode::BAD_REQUEST,
+        None if e.to_string().contains("not found") => StatusCode::NOT_FOUND,
+        None => StatusCode::INTERNAL_SERVER_ERROR,
+    }
+}
+
+/// List all feature flags
+/// Access: Admin
+pub async fn list_feature_flags(
+    State(state): State<AppState>,
+    Extension(_admin): Extension<AdminUser>,
+) -> Result<Json<Vec<FeatureFlagResponse>>, StatusCode> {
+    let flags = state.feature_flag_repo.list().await
+        .map_err(|e| {
+            error!("Failed to list feature flags: {}", e);
+            StatusCode::INTERNAL_SERVER_ERROR
+        })?;
+
+    Ok(Json(flags.into_iter().map(FeatureFlagResponse::from).collect()))
+}
+
+/// Get a feature flag by name
+/// Access: Admin
+pub async fn get_feature_flag(
+    State(state): State<AppState>,
+    Extension(_admin): Extension<AdminUser>,
+    Path(name): Path<String>,
+) -> Result<Json<FeatureFlagResponse>, StatusCode> {
+    let flag = state.feature_flag_repo.find_by_name(name.clone()).await
+        .map_err(|e| {
+            error!("Failed to fetch feature flag {}: {}", name, e);
+            feature_flag_error_status(&e)
+        })?;
+
+    Ok(Json(flag.into()))
+}
+
+/// Create a feature flag or replace its settings
+///
+/// Well-known flags are `read_only`, `job_submission_disabled`, `fixtures` and
+/// `processor_disabled.<processor_type>`; other flags are canary features evaluated by
+/// clients through `GET /features`. Changes apply on this replica at once and on the
+/// others once their flag cache expires.
+/// Access: Admin
+pub async fn put_feature_flag(
+    State(state): State<AppState>,
+    Extension(admin): Extension<AdminUser>,
+    Path(name): Path<String>,
+    StrictJson(payload): StrictJson<UpdateFeatureFlagRequest>,
+) -> Result<Json<FeatureFlagResponse>, StatusCode> {
+    let flag = state.feature_flag_repo.upsert(NewFeatureFlag {
+        id: Uuid::new_v4(),
+        name: name.clone(),
+        enabled: payload.enabled,
+        reseller_ids: payload.reseller_ids,
+        description: payload.description,
+        updated_by: admin.id.clone(),
+    }).await
+        .map_err(|e| {
+            error!("Failed to update feature flag {}: {}", name, e);
+            feature_flag_error_status(&e)
+        })?;
+    state.feature_flags.invalidate();
+
+    let entry = NewAuditEntry::new(admin.id.clone(), "feature_flag.updated", "feature_flag", flag.id, json!({
+        "name": flag.name,
+        "enabled": flag.enabled,
+        "reseller_ids": flag.reseller_ids,
+    }));
+    if let Err(e) = state.audit_log_repo.record(entry).await {
+        error!("Failed to record the update of feature flag {} in the audit log: {}", flag.name, e);
+    }
+
+    info!("Admin {} set feature flag {} (enabled: {}, resellers: {})", admin.id, flag.name, flag.enabled, flag.reseller_ids.len());
+    Ok(Json(flag.into()))
+}
+
+/// Delete a feature flag, turning it off for everyone
+/// Access: Admin
+pub async fn delete_feature_flag(
+    State(state): State<AppState>,
+    Extension(admin): Extension<AdminUser>,
+    Path(name): Path<String>,
+) -> Result<StatusCode, StatusCode> {
+    let flag = state.feature_flag_repo.delete(name.clone()).await
+        .map_err(|e| {
+            error!("Failed to delete feature flag {}: {}", name, e);
+            feature_flag_error_status(&e)
+        })?;
+    state.feature_flags.invalidate();
+
+    let entry = NewAuditEntry::new(admin.id.clone(), "feature_flag.deleted", "feature_flag", flag.id, json!({
+        "name": flag.name,
+    }));
+    if let Err(e) = state.audit_log_repo.record(entry).await {
+        error!("Failed to record the deletion of feature flag {} in the audit log: {}", flag.name, e);
+    }
+
+    info!("Admin {} deleted feature flag {}", admin.id, flag.name);
+    Ok(StatusCode::NO_CONTENT)
+}
+
+/// List the names of the feature flags that are on for the authenticated customer
+/// Access: Customer
+pub async fn list_customer_features(
+    State(state): State<AppState>,
+    Extension(customer): Extension<CustomerUser>,
+) -> Json<Vec<String>> {
+    Json(state.feature_flags.enabled_for(customer.reseller_id).await)
+}
@@ -0,0 +1,203 @@
+use axum::{extract::{Path, State, Extension}, http::StatusCode, Json};
+use serde::Serialize;
+use serde_json::json;
+use uuid::Uuid;
+use tracing::{error, info, warn};
+use chrono::{DateTime, Utc};
+
+use innosystem_common::models::audit::NewAuditEntry;
+use innosystem_common::models::feature_flag;
+use innosystem_common::models::fixture::{FixtureBundle, FixtureError, FixtureSpec, NewFixtureBundle};
+use crate::middleware::auth::AdminUser;
+use crate::request::StrictJson;
+use crate::services::cache::{job_type_key, ACTIVE_RESELLERS_KEY, JOB_TYPES_KEY, RESELLERS_KEY};
+use crate::state::AppState;
+
+/// Response data for a fixture bundle
+#[derive(Debug, Serialize)]
+pub struct FixtureBundleResponse {
+    pub id: Uuid,
+    pub name: String,
+    pub spec: serde_json::Value,
+    pub reseller_id: Uuid,
+    pub job_type_id: Uuid,
+    pub customer_ids: Vec<Uuid>,
+    pub runner_ids: Vec<Uuid>,
+    pub job_count: i32,
+    pub created_by: String,
+    pub created_at: Option<DateTime<Utc>>,
+}
+
+impl From<FixtureBundle> for FixtureBundleResponse {
+    fn from(bundle: FixtureBundle) -> Self {
+        Self {
+            id: bundle.id,
+            name: bundle.name,
+            spec: bundle.spec,
+            reseller_id: bundle.reseller_id,
+            job_type_id: bundle.job_type_id,
+            customer_ids: bundle.customer_ids,
+            runner_ids: bundle.runner_ids,
+            job_count: bundle.job_count,
+            created_by: bundle.created_by,
+            created_at: bundle.created_at,
+        }
+    }
+}
+
+/// A customer of a newly provisioned bundle, with the key tests authenticate with
+#[derive(Debug, Serialize)]
+pub struct FixtureCustomerResponse {
+    pub id: Uuid,
+    pub name: String,
+    pub api_key: Option<String>,
+}
+
+/// Response data for a newly provisioned bundle: the bundle with the API keys of its
+/// reseller and customers, which are only returned here
+#[derive(Debug, Serialize)]
+pub struct ProvisionedFixtureResponse {
+    #[serde(flatten)]
+    pub bundle: FixtureBundleResponse,
+    pub reseller_api_key: String,
+    pub customers: Vec<FixtureCustomerResponse>,
+}
+
+/// Refuse fixture requests unless the `fixtures` flag is on, so the endpoints do not
+/// exist outside of staging
+async fn require_fixtures_enabled(state: &AppState) -> Result<(), StatusCode> {
+    if state.feature_flags.is_enabled(feature_flag::FIXTURES, None).await {
+        Ok(())
+    } else {
+        warn!("Refusing fixture request, the {} feature flag is off", feature_flag::FIXTURES);
+        Err(StatusCode::NOT_FOUND)
+    }
+}
+
+/// Provision a bundle of test data described by a spec: a reseller, its customers with
+/// their wallets and jobs in the given statuses, and runners, all created at once
+///
+/// Jobs are recorded in their status without being queued, so no runner processes
+/// them. Only available while the `fixtures` feature flag is on.
+/// Access: Admin
+pub async fn create_fixture(
+    State(state): State<AppState>,
+    Extension(admin): Extension<AdminUser>,
+    StrictJson(spec): StrictJson<FixtureSpec>,
+) -> Result<(StatusCode, Json<ProvisionedFixtureResponse>), StatusCode> {
+    require_fixtures_enabled(&state).await?;
+    spec.validate()
+        .map_err(|e| {
+            error!("Refusing fixture bundle {}: {}", spec.name, e);
+            StatusCode::BAD_REQUEST
+        })?;
+
+    let bundle_id = Uuid::new_v4();
+    let records = spec.records(bundle_id);
+    let reseller_api_key = records.reseller.api_key.clone();
+    let customers: Vec<FixtureCustomerResponse> = records.customers.iter()
+        .map(|customer| FixtureCustomerResponse {
+            id: customer.id,
+            name: customer.name.clone(),
+            api_key: customer.api_key.clone(),
+        })
+        .collect();
+
+    let new_bundle = NewFixtureBundle::new(bundle_id, &spec, &records, admin.id.clone());
+    let bundle = state.fixture_repo.create(new_bundle, records).await
+        .map_err(|e| {
+            error!("Failed to provision fixture bundle {}: {}", spec.name, e);
+            StatusCode::INTERNAL_SERVER_ERROR
+        })?;
+    state.response_cache.invalidate(&[RESELLERS_KEY, ACTIVE_RESELLERS_KEY, JOB_TYPES_KEY]).await;
+
+    let entry = NewAuditEntry::new(admin.id.clone(), "fixture.created", "fixture", bundle.id, json!({
+        "name": bundle.name,
+        "customers": bundle.customer_ids.len(),
+        "runners": bundle.runner_ids.len(),
+        "jobs": bundle.job_count,
+    }));
+    if let Err(e) = state.audit_log_repo.record(entry).await {
+        error!("Failed to record the creation of fixture bundle {} in the audit log: {}", bundle.id, e);
+    }
+
+    info!("Admin {} provisioned fixture bundle {} ({})", admin.id, bundle.name, bundle.id);
+    Ok((StatusCode::CREATED, Json(ProvisionedFixtureResponse {
+        bundle: bundle.into(),
+        reseller_api_key,
+        customers,
+    })))
+}
+
+/// List the provisioned fixture bundles, newest first
+/// Access: Admin
+pub async fn list_fixtures(
+    State(state): State<AppState>,
+    Extension(_admin): Extension<AdminUser>,
+) -> Result<Json<Vec<FixtureBundleResponse>>, StatusCode> {
+    require_fixtures_enabled(&state).await?;
+    let bundles = state.fixture_repo.list().await
+        .map_err(|e| {
+            error!("Failed to list fixture bundles: {}", e);
+            StatusCode::INTERNAL_SERVER_ERROR
+        })?;
+
+    Ok(Json(bundles.into_iter().map(FixtureBundleResponse::from).collect()))
+}
+
+/// Get a fixture bundle by ID
+/// Access: Admin
+pub async fn get_fixture(
+    State(state): State<AppState>,
+    Extension(_admin): Extension<AdminUser>,
+    Path(id): Path<Uuid>,
+) -> Result<Json<FixtureBundleResponse>, StatusCode> {
+    require_fixtures_enabled(&state).await?;
+    let bundle = state.fixture_repo.find_by_id(id).await
+        .map_err(|e| {
+            error!("Failed to fetch fixture bundle {}: {}", id, e);
+            StatusCode::NOT_FOUND
+        })?;
+
+    Ok(Json(bundle.into()))
+}
+
+/// Tear a fixture bundle down: its reseller, customers, runners and job type are deleted
+/// with everything the customers did since, such as jobs and wallet transactions
+///
+/// Bundles whose customers were invoiced in a closed billing period cannot be torn down.
+/// Access: Admin
+pub async fn delete_fixture(
+    State(state): State<AppState>,
+    Extension(admin): Extension<AdminUser>,
+    Path(id): Path<Uuid>,
+) -> Result<StatusCode, StatusCode> {
+    require_fixtures_enabled(&state).await?;
+    let bundle = state.fixture_repo.find_by_id(id).await
+        .map_err(|e| {
+            error!("Failed to fetch fixture bundle {}: {}", id, e);
+            StatusCode::NOT_FOUND
+        })?;
+
+    state.fixture_repo.delete(id).await
+        .map_err(|e| {
+            error!("Failed to tear down fixture bundle {}: {}", id, e);
+            match e.downcast_ref::<FixtureError>() {
+                Some(FixtureError::Invoiced(_)) => StatusCode::CONFLICT,
+                _ if e.to_string().contains("not found") => StatusCode::NOT_FOUND,
+                _ => StatusCode::INTERNAL_SERVER_ERROR,
+            }
+        })?;
+    let job_type_key = job_type_key(bundle.job_type_id);
+    state.response_cache.invalidate(&[RESELLERS_KEY, ACTIVE_RESELLERS_KEY, JOB_TYPES_KEY, &job_type_key]).await;
+
+    let entry = NewAuditEntry::new(admin.id.clone(), "fixture.deleted", "fixture", bundle.id, json!({
+        "name": bundle.name,
+    }));
+    if let Err(e) = state.audit_log_repo.record(entry).await {
+        error!("Failed to record the teardown of fixture bundle {} in the audit log: {}", bundle.id, e);
+    }
+
+    info!("Admin {} tore down fixture bundle {} ({})", admin.id, bundle.name, bundle.id);
+    Ok(StatusCode::NO_CONTENT)
+}
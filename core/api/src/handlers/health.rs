@@ -1,22 +1,47 @@
 use axum::{
+    extract::State,
     http::StatusCode,
     Json,
 };
 use serde::Serialize;
+use tracing::warn;
+
+use crate::state::AppState;
 
 /// Response structure for health endpoint
 #[derive(Serialize)]
 pub struct HealthResponse {
     status: String,
+    /// Whether a job queue is at or above its depth limit, so submissions are turned away
+    queue_saturated: bool,
+    /// Whether the job queue's Redis answers; jobs submitted meanwhile are stored but
+    /// not queued until it does
+    queue_reachable: bool,
 }
 
 /// Health check endpoint handler
+///
+/// A saturated or unreachable queue reports the API as degraded but still answers 200,
+/// since the API itself is able to serve requests.
 #[allow(dead_code)]
-pub async fn health_check() -> (StatusCode, Json<HealthResponse>) {
+pub async fn health_check(
+    State(state): State<AppState>,
+) -> (StatusCode, Json<HealthResponse>) {
+    let queue_saturated = state.backpressure_service.status().await.saturated;
+    let queue_reachable = match state.job_queue.health_check().await {
+        Ok(()) => true,
+        Err(e) => {
+            warn!("Job queue failed its health check: {}", e);
+            false
+        }
+    };
+    let degraded = queue_saturated || !queue_reachable;
     (
         StatusCode::OK,
         Json(HealthResponse {
-            status: "OK".to_string(),
+            status: if degraded { "DEGRADED" } else { "OK" }.to_string(),
+            queue_saturated,
+            queue_reachable,
         }),
     )
 }
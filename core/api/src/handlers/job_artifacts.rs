@@ -0,0 +1,41 @@
+use axum::{extract::{Path, State, Extension}, http::{header, StatusCode}, response::IntoResponse};
+use uuid::Uuid;
+use tracing::error;
+
+use crate::handlers::jobs::find_job;
+use crate::middleware::auth::CustomerUser;
+use crate::state::AppState;
+
+/// Download a binary response body the runner kept apart from a job's output
+///
+/// The job's output refers to it under `artifact.id`.
+/// Access: Job's Customer
+pub async fn get_job_artifact(
+    State(state): State<AppState>,
+    Extension(customer): Extension<CustomerUser>,
+    Path((job_ref, artifact_id)): Path<(String, Uuid)>,
+) -> Result<impl IntoResponse, StatusCode> {
+    let job = find_job(&state, &job_ref).await?;
+    if job.customer_id != customer.id {
+        return Err(StatusCode::FORBIDDEN);
+    }
+
+    let artifact = state.job_artifact_repo.find(job.id, artifact_id).await
+        .map_err(|e| {
+            error!("Failed to fetch artifact {} of job {}: {}", artifact_id, job.id, e);
+            if e.to_string().contains("not found") {
+                StatusCode::NOT_FOUND
+            } else {
+                StatusCode::INTERNAL_SERVER_ERROR
+            }
+        })?;
+
+    let filename = format!("attachment; filename=\"{}\"", artifact.id);
+    Ok((
+        [
+            (header::CONTENT_TYPE, artifact.content_type),
+            (header::CONTENT_DISPOSITION, filename),
+        ],
+        artifact.content,
+    ))
+}
@@ -0,0 +1,82 @@
+use axum::{extract::{Path, State, Extension}, http::StatusCode, Json};
+use serde::Serialize;
+use uuid::Uuid;
+use tracing::error;
+use chrono::{DateTime, Utc};
+
+use crate::handlers::jobs::find_job;
+use crate::state::AppState;
+use crate::middleware::auth::CustomerUser;
+use innosystem_common::models::job_attempt::{CrashReport, JobAttempt};
+use innosystem_common::models::job_error::JobError;
+
+/// Response data for a single execution of a job
+#[derive(Debug, Serialize)]
+pub struct JobAttemptResponse {
+    pub attempt_number: i32,
+    pub runner_id: Option<Uuid>,
+    /// `running`, `succeeded`, `failed` or `abandoned`
+    pub status: String,
+    /// Structured error of a failed attempt
+    pub error: Option<JobError>,
+    pub cost_cents: i32,
+    pub started_at: DateTime<Utc>,
+    pub finished_at: Option<DateTime<Utc>>,
+    pub duration_ms: Option<i64>,
+    /// Crash of the runner while it ran the attempt, with its panic message if captured
+    pub crash: Option<CrashReport>,
+}
+
+impl From<JobAttempt> for JobAttemptResponse {
+    fn from(attempt: JobAttempt) -> Self {
+        Self {
+            error: attempt.job_error(),
+            duration_ms: attempt.duration_ms(),
+            crash: attempt.crash_report(),
+            attempt_number: attempt.attempt_number,
+            runner_id: attempt.runner_id,
+            status: attempt.status,
+            cost_cents: attempt.cost_cents,
+            started_at: attempt.started_at,
+            finished_at: attempt.finished_at,
+        }
+    }
+}
+
+/// Response data for the attempt history of a job
+#[derive(Debug, Serialize)]
+pub struct JobAttemptsResponse {
+    pub job_id: Uuid,
+    /// Cost of all attempts together
+    pub total_cost_cents: i64,
+    /// Attempts, oldest first
+    pub attempts: Vec<JobAttemptResponse>,
+}
+
+/// Get every attempt made at running a job, with its runner, duration, error and cost
+/// Access: Job's Customer
+pub async fn get_job_attempts(
+    State(state): State<AppState>,
+    Extension(customer): Extension<CustomerUser>,
+    Path(job_ref): Path<String>,
+) -> Result<Json<JobAttemptsResponse>, StatusCode> {
+    let job = find_job(&state, &job_ref).await?;
+    let job_id = job.id;
+
+    if job.customer_id != customer.id {
+        return Err(StatusCode::FORBIDDEN);
+    }
+
+    let attempts = state.job_attempt_repo.find_by_job_id(job_id)
+        .await
+        .map_err(|e| {
+            error!("Failed to fetch the attempts of job {}: {}", job_id, e);
+            StatusCode::INTERNAL_SERVER_ERROR
+        })?;
+
+    Ok(Json(JobAttemptsResponse {
+        job_id,
+        total_cost_cents: attempts.iter().map(|attempt| i64::from(attempt.cost_cents)).sum(),
+        attempts: attempts.into_iter().map(JobAttemptResponse::from).collect(),
+    }))
+}
@@ -0,0 +1,301 @@
+use axum::{
+    extract::{Path, State, Extension},
+    http::{header, StatusCode},
+    response::IntoResponse,
+    Json,
+};
+use serde::{Deserialize, Serialize};
+use uuid::Uuid;
+use tracing::{debug, error, info, warn};
+use chrono::{DateTime, Utc};
+
+use crate::handlers::jobs::submit_job;
+use crate::middleware::auth::CustomerUser;
+use crate::request::{self, StrictJson};
+use crate::services::backpressure::SaturationPolicy;
+use crate::state::AppState;
+use innosystem_common::models::job::{Job, PriorityLevel};
+use innosystem_common::models::job_import::{
+    import_report_csv, parse_rows, ColumnMapping, ImportFormat, ImportStatus, JobImport, NewJobImport,
+};
+use innosystem_common::models::submission::{NewSubmission, SubmissionSource};
+
+/// Rows of an import submitted per pass, so large files do not hold up other imports
+const ROWS_PER_PASS: usize = 100;
+
+/// Request data for importing job submissions from a file
+#[derive(Debug, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct CreateJobImportRequest {
+    /// Job type every row is submitted as
+    pub job_type_id: Uuid,
+    pub format: ImportFormat,
+    /// Column -> input_data field (dotted for nested fields); every column goes to the
+    /// field of the same name when omitted
+    #[serde(default)]
+    pub mapping: Option<ColumnMapping>,
+    /// Priority of the rows' jobs, 0 (low) to 3 (critical) or its name (optional, defaults to medium)
+    #[serde(default = "default_priority", deserialize_with = "request::priority")]
+    pub priority: PriorityLevel,
+    /// File content: CSV with a header row, or one JSON object per line
+    pub content: String,
+}
+
+/// Default priority function
+fn default_priority() -> PriorityLevel {
+    PriorityLevel::Medium
+}
+
+/// Response data for a job import
+#[derive(Debug, Serialize)]
+pub struct JobImportResponse {
+    pub id: Uuid,
+    pub job_type_id: Uuid,
+    pub format: String,
+    pub mapping: Option<serde_json::Value>,
+    pub priority: String,
+    pub test_mode: bool,
+    /// `pending`, `processing`, `completed` or `failed`
+    pub status: String,
+    pub total_rows: i32,
+    /// Rows a job was submitted for
+    pub submitted_rows: i32,
+    /// Rows rejected, with their reason in the report
+    pub failed_rows: i32,
+    pub created_at: Option<DateTime<Utc>>,
+    pub completed_at: Option<DateTime<Utc>>,
+}
+
+impl From<JobImport> for JobImportResponse {
+    fn from(import: JobImport) -> Self {
+        Self {
+            id: import.id,
+            job_type_id: import.job_type_id,
+            format: import.format,
+            mapping: import.mapping,
+            priority: PriorityLevel::from_i32(import.priority).as_str().to_string(),
+            test_mode: import.test_mode,
+            status: import.status,
+            total_rows: import.total_rows,
+            submitted_rows: import.submitted_rows,
+            failed_rows: import.failed_rows,
+            created_at: import.created_at,
+            completed_at: import.completed_at,
+        }
+    }
+}
+
+/// Load an import and verify it belongs to the authenticated customer
+async fn find_owned_import(state: &AppState, customer: &CustomerUser, id: Uuid) -> Result<JobImport, StatusCode> {
+    let import = state.job_import_repo.find_by_id(id).await
+        .map_err(|e| {
+            error!("Failed to find job import {}: {}", id, e);
+            StatusCode::NOT_FOUND
+        })?;
+
+    if import.customer_id != customer.id {
+        return Err(StatusCode::FORBIDDEN);
+    }
+
+    Ok(import)
+}
+
+/// Import job submissions from a CSV or NDJSON file: each row becomes a job of the
+/// given type, submitted in the background like a directly submitted job
+///
+/// The file is checked as a whole up front; rows that cannot be submitted are
+/// reported per row in the import's report. The jobs form a submission with the
+/// import's ID, tracked and cancelled through `/submissions/{id}`.
+/// Access: Customer
+pub async fn create_job_import(
+    State(state): State<AppState>,
+    Extension(customer): Extension<CustomerUser>,
+    StrictJson(request): StrictJson<CreateJobImportRequest>,
+) -> Result<(StatusCode, Json<JobImportResponse>), StatusCode> {
+    let rows = parse_rows(request.format, &request.content, request.mapping.as_ref())
+        .map_err(|e| {
+            error!("Refusing job import: {}", e);
+            StatusCode::BAD_REQUEST
+        })?;
+
+    let job_type = state.job_type_repo.find_by_id(request.job_type_id).await
+        .map_err(|e| {
+            error!("Failed to find job type {} of job import: {}", request.job_type_id, e);
+            StatusCode::BAD_REQUEST
+        })?;
+    if !job_type.enabled {
+        error!("Refusing job import for disabled job type {}", job_type.name);
+        return Err(StatusCode::BAD_REQUEST);
+    }
+
+    let id = Uuid::new_v4();
+    state.submission_repo.create(NewSubmission::new(id, customer.id, SubmissionSource::Import)).await
+        .map_err(|e| {
+            error!("Failed to create submission of job import: {}", e);
+            StatusCode::INTERNAL_SERVER_ERROR
+        })?;
+
+    let new_import = NewJobImport {
+        id,
+        customer_id: customer.id,
+        job_type_id: job_type.id,
+        format: request.format.as_str().to_string(),
+        mapping: request.mapping.map(|mapping| serde_json::json!(mapping)),
+        content: request.content,
+        priority: request.priority.as_i32(),
+        // Jobs submitted with a sandbox key run through the stub processor
+        test_mode: customer.test_mode,
+        total_rows: rows.len() as i32,
+    };
+
+    let import = state.job_import_repo.create(new_import).await
+        .map_err(|e| {
+            error!("Failed to create job import: {}", e);
+            StatusCode::INTERNAL_SERVER_ERROR
+        })?;
+
+    info!("Accepted job import {} of {} rows for customer {}", import.id, import.total_rows, customer.id);
+
+    Ok((StatusCode::ACCEPTED, Json(import.into())))
+}
+
+/// List the job imports of the authenticated customer, newest first
+/// Access: Customer
+pub async fn list_job_imports(
+    State(state): State<AppState>,
+    Extension(customer): Extension<CustomerUser>,
+) -> Result<Json<Vec<JobImportResponse>>, StatusCode> {
+    let imports = state.job_import_repo.find_by_customer_id(customer.id).await
+        .map_err(|e| {
+            error!("Failed to list job imports for customer {}: {}", customer.id, e);
+            StatusCode::INTERNAL_SERVER_ERROR
+        })?;
+
+    Ok(Json(imports.into_iter().map(JobImportResponse::from).collect()))
+}
+
+/// Get a job import with its progress
+/// Access: Import's Customer
+pub async fn get_job_import(
+    State(state): State<AppState>,
+    Extension(customer): Extension<CustomerUser>,
+    Path(id): Path<Uuid>,
+) -> Result<Json<JobImportResponse>, StatusCode> {
+    let import = find_owned_import(&state, &customer, id).await?;
+    Ok(Json(import.into()))
+}
+
+/// Download the per-row result report of a job import as CSV: the job submitted for
+/// each row processed so far with its current status, or why the row was rejected
+/// Access: Import's Customer
+pub async fn get_job_import_report(
+    State(state): State<AppState>,
+    Extension(customer): Extension<CustomerUser>,
+    Path(id): Path<Uuid>,
+) -> Result<impl IntoResponse, StatusCode> {
+    let import = find_owned_import(&state, &customer, id).await?;
+
+    let rows = state.job_import_repo.find_rows(import.id).await
+        .map_err(|e| {
+            error!("Failed to fetch the rows of job import {}: {}", id, e);
+            StatusCode::INTERNAL_SERVER_ERROR
+        })?;
+
+    let filename = format!("attachment; filename=\"job-import-{}.csv\"", import.id);
+    Ok((
+        [
+            (header::CONTENT_TYPE, "text/csv".to_string()),
+            (header::CONTENT_DISPOSITION, filename),
+        ],
+        import_report_csv(&rows),
+    ))
+}
+
+/// Submit the next rows of every unfinished import and close the imports whose last
+/// row was processed
+///
+/// Returns the number of imports that finished.
+pub(crate) async fn process_imports(state: &AppState) -> anyhow::Result<usize> {
+    let imports = state.job_import_repo.find_unfinished().await?;
+
+    let mut finished = 0;
+    for import in imports {
+        match process_import(state, &import).await {
+            Ok(Some(_)) => finished += 1,
+            Ok(None) => {}
+            Err(e) => error!("Failed to process job import {}: {}", import.id, e),
+        }
+    }
+
+    Ok(finished)
+}
+
+/// Submit up to a pass worth of rows of an import; returns its final status once
+/// every row was processed
+async fn process_import(state: &AppState, import: &JobImport) -> anyhow::Result<Option<ImportStatus>> {
+    let rows = import.format()
+        .ok_or_else(|| anyhow::anyhow!("unknown format {}", import.format))
+        .and_then(|format| Ok(parse_rows(format, &import.content, import.column_mapping().as_ref())?));
+    let rows = match rows {
+        Ok(rows) => rows,
+        Err(e) => {
+            warn!("Job import {} can no longer be parsed: {}", import.id, e);
+            state.job_import_repo.finish(import.id, ImportStatus::Failed).await?;
+            return Ok(Some(ImportStatus::Failed));
+        }
+    };
+
+    let first = import.next_row.max(1) as usize;
+    for (index, row) in rows.into_iter().enumerate().skip(first - 1).take(ROWS_PER_PASS) {
+        let row_number = index as i32 + 1;
+        let input_data = match row {
+            Ok(input_data) => input_data,
+            Err(reason) => {
+                if !state.job_import_repo.claim_row(import.id, row_number, None, Some(reason)).await? {
+                    return Ok(None);
+                }
+                continue;
+            }
+        };
+
+        let mut job = Job::new(
+            import.customer_id,
+            import.job_type_id,
+            input_data,
+            PriorityLevel::from_i32(import.priority),
+            1000, // $10.00 default estimated cost, as for directly submitted jobs
+        );
+        job.test_mode = import.test_mode;
+        job.submission_id = Some(import.id);
+
+        // Rows wait for a saturated queue to drain instead of failing
+        if state.backpressure_service.policy() == SaturationPolicy::Reject
+            && state.backpressure_service.is_saturated(&job.priority, job.job_type_id).await
+        {
+            debug!("Holding back row {} of job import {} while its queue is saturated", row_number, import.id);
+            return Ok(None);
+        }
+
+        // Another API instance may have taken the row in the meantime
+        let job_id = job.id;
+        if !state.job_import_repo.claim_row(import.id, row_number, Some(job_id), None).await? {
+            return Ok(None);
+        }
+        if let Err(e) = submit_job(state, job).await {
+            warn!("Failed to submit row {} of job import {}: {}", row_number, import.id, e);
+            state.job_import_repo.fail_row(import.id, row_number, e.to_string()).await?;
+        }
+    }
+
+    let import = state.job_import_repo.find_by_id(import.id).await?;
+    if import.next_row <= import.total_rows {
+        return Ok(None);
+    }
+    state.job_import_repo.finish(import.id, ImportStatus::Completed).await?;
+    info!(
+        "Job import {} completed: {} rows submitted, {} failed",
+        import.id, import.submitted_rows, import.failed_rows
+    );
+
+    Ok(Some(ImportStatus::Completed))
+}
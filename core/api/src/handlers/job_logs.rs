@@ -0,0 +1,96 @@
+use axum::{extract::{Path, Query, State, Extension}, http::StatusCode, Json};
+use serde::{Deserialize, Serialize};
+use uuid::Uuid;
+use tracing::error;
+use chrono::{DateTime, Utc};
+
+use crate::handlers::jobs::find_job;
+use crate::state::AppState;
+use crate::middleware::auth::CustomerUser;
+use innosystem_common::api::ApiListMeta;
+use innosystem_common::models::job_log::JobLog;
+
+/// Maximum number of log lines returned by a single request
+const MAX_PAGE_SIZE: i64 = 500;
+
+/// Query parameters for paginating job logs
+#[derive(Debug, Deserialize)]
+pub struct JobLogsQuery {
+    /// Number of lines to return (defaults to 100)
+    pub limit: Option<i64>,
+    /// Number of lines to skip (defaults to 0)
+    pub offset: Option<i64>,
+}
+
+/// Response data for a single log line
+#[derive(Debug, Serialize)]
+pub struct JobLogLineResponse {
+    pub line_number: i32,
+    /// Severity ("debug", "info", "warn" or "error")
+    pub level: String,
+    pub message: String,
+    /// Structured context attached to the line, if any
+    pub fields: Option<serde_json::Value>,
+    pub created_at: Option<DateTime<Utc>>,
+}
+
+/// Response data for a page of job logs
+#[derive(Debug, Serialize)]
+pub struct JobLogsResponse {
+    pub job_id: Uuid,
+    /// Paging of the lines; `total` counts all lines logged for the job
+    #[serde(flatten)]
+    pub meta: ApiListMeta,
+    pub lines: Vec<JobLogLineResponse>,
+}
+
+impl From<JobLog> for JobLogLineResponse {
+    fn from(line: JobLog) -> Self {
+        Self {
+            line_number: line.line_number,
+            level: line.level,
+            message: line.message,
+            fields: line.fields,
+            created_at: line.created_at,
+        }
+    }
+}
+
+/// Get the execution logs of a job
+/// Access: Job's Customer
+pub async fn get_job_logs(
+    State(state): State<AppState>,
+    Extension(customer): Extension<CustomerUser>,
+    Path(job_ref): Path<String>,
+    Query(query): Query<JobLogsQuery>,
+) -> Result<Json<JobLogsResponse>, StatusCode> {
+    let job = find_job(&state, &job_ref).await?;
+    let job_id = job.id;
+
+    if job.customer_id != customer.id {
+        return Err(StatusCode::FORBIDDEN);
+    }
+
+    let limit = query.limit.unwrap_or(100).clamp(1, MAX_PAGE_SIZE);
+    let offset = query.offset.unwrap_or(0).max(0);
+
+    let total = state.job_log_repo.count_by_job_id(job_id)
+        .await
+        .map_err(|e| {
+            error!("Failed to count logs for job {}: {}", job_id, e);
+            StatusCode::INTERNAL_SERVER_ERROR
+        })?;
+
+    let lines = state.job_log_repo.find_by_job_id(job_id, limit, offset)
+        .await
+        .map_err(|e| {
+            error!("Failed to fetch logs for job {}: {}", job_id, e);
+            StatusCode::INTERNAL_SERVER_ERROR
+        })?;
+
+    Ok(Json(JobLogsResponse {
+        job_id,
+        meta: ApiListMeta::offset(total, limit, offset),
+        lines: lines.into_iter().map(JobLogLineResponse::from).collect(),
+    }))
+}
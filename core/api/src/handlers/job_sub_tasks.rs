@@ -0,0 +1,116 @@
+use axum::{extract::{Path, State, Extension}, http::StatusCode, Json};
+use serde::Serialize;
+use uuid::Uuid;
+use tracing::{error, info};
+use chrono::{DateTime, Utc};
+
+use crate::handlers::jobs::find_job;
+use crate::state::AppState;
+use crate::middleware::auth::CustomerUser;
+use innosystem_common::models::job::{Job, JobStatus};
+
+/// Response data for a single sub-task of a batch job
+#[derive(Debug, Serialize)]
+pub struct SubTaskResponse {
+    pub id: Uuid,
+    pub public_id: String,
+    pub sub_task_index: Option<i32>,
+    pub status: String,
+    pub runner_id: Option<Uuid>,
+    pub cost_cents: i32,
+    pub completed_at: Option<DateTime<Utc>>,
+}
+
+impl From<Job> for SubTaskResponse {
+    fn from(job: Job) -> Self {
+        Self {
+            id: job.id,
+            public_id: job.public_id,
+            sub_task_index: job.sub_task_index,
+            status: job.status.as_str().to_string(),
+            runner_id: job.runner_id,
+            cost_cents: job.cost_cents,
+            completed_at: job.completed_at,
+        }
+    }
+}
+
+/// Response data for the sub-tasks of a batch job
+#[derive(Debug, Serialize)]
+pub struct JobSubTasksResponse {
+    pub job_id: Uuid,
+    /// Status of the batch job, derived from its sub-tasks once all of them finished
+    pub status: String,
+    /// Number of sub-tasks the job fanned out into; None until it fanned out
+    pub sub_task_count: Option<i32>,
+    pub succeeded: usize,
+    /// Sub-tasks that failed, were cancelled or expired
+    pub failed: usize,
+    /// Sub-tasks that have not finished
+    pub outstanding: usize,
+    /// Cost of the sub-tasks that finished running so far
+    pub cost_cents: i64,
+    /// Sub-tasks in order
+    pub sub_tasks: Vec<SubTaskResponse>,
+}
+
+/// Get the sub-tasks a batch job fanned out into, with their progress
+/// Access: Job's Customer
+pub async fn get_job_sub_tasks(
+    State(state): State<AppState>,
+    Extension(customer): Extension<CustomerUser>,
+    Path(job_ref): Path<String>,
+) -> Result<Json<JobSubTasksResponse>, StatusCode> {
+    let job = find_job(&state, &job_ref).await?;
+    let job_id = job.id;
+
+    if job.customer_id != customer.id {
+        return Err(StatusCode::FORBIDDEN);
+    }
+
+    let sub_tasks = state.job_repo.find_sub_tasks(job_id)
+        .await
+        .map_err(|e| {
+            error!("Failed to fetch the sub-tasks of job {}: {}", job_id, e);
+            StatusCode::INTERNAL_SERVER_ERROR
+        })?;
+
+    let outstanding = sub_tasks.iter().filter(|sub_task| !sub_task.status.is_terminal()).count();
+    let succeeded = sub_tasks.iter().filter(|sub_task| sub_task.status == JobStatus::Succeeded).count();
+    Ok(Json(JobSubTasksResponse {
+        job_id,
+        status: job.status.as_str().to_string(),
+        sub_task_count: job.sub_task_count,
+        succeeded,
+        failed: sub_tasks.len() - outstanding - succeeded,
+        outstanding,
+        cost_cents: sub_tasks.iter()
+            .filter(|sub_task| matches!(sub_task.status, JobStatus::Succeeded | JobStatus::Failed))
+            .map(|sub_task| i64::from(sub_task.cost_cents))
+            .sum(),
+        sub_tasks: sub_tasks.into_iter().map(SubTaskResponse::from).collect(),
+    }))
+}
+
+/// Finish the batch jobs whose sub-tasks all finished without their runner fanning
+/// them in, e.g. because the last sub-task was cancelled, expired or its completion was
+/// buffered by a runner that lost the database
+///
+/// Returns the number of batch jobs that finished.
+pub(crate) async fn fan_in_sub_tasks(state: &AppState) -> anyhow::Result<usize> {
+    let jobs = state.job_repo.find_fanned_out().await?;
+
+    let mut finished = 0;
+    for job in jobs {
+        match state.job_repo.fan_in(job.id).await {
+            Ok(Some(job)) => {
+                info!("Batch job {} {} with its sub-tasks", job.id, job.status.as_str());
+                finished += 1;
+            }
+            Ok(None) => {}
+            Err(e) => error!("Failed to fan in the sub-tasks of job {}: {}", job.id, e),
+        }
+    }
+
+    Ok(finished)
+}
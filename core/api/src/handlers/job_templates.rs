@@ -0,0 +1,230 @@
+use axum::{extract::{Path, State, Extension}, http::StatusCode, Json};
+use serde::{Deserialize, Serialize};
+use serde_json::{Map, Value};
+use uuid::Uuid;
+use tracing::{error, info};
+use chrono::{DateTime, Utc};
+
+use crate::handlers::jobs::{submit_job, JobResponse, SubmitError};
+use crate::middleware::auth::CustomerUser;
+use crate::request::{self, StrictJson};
+use crate::state::AppState;
+use innosystem_common::models::job::{Job, PriorityLevel};
+use innosystem_common::models::job_template::{JobTemplate, NewJobTemplate};
+
+/// Request data for creating a job template
+#[derive(Debug, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct CreateJobTemplateRequest {
+    /// Job type the template submits jobs for
+    pub job_type_id: Uuid,
+    /// Template name, unique per customer
+    pub name: String,
+    pub description: Option<String>,
+    /// input_data skeleton; strings may contain `{{variable}}` placeholders
+    pub input_template: Value,
+    /// Declared variables, e.g. `{"region": {"type": "string", "default": "eu"}}`
+    #[serde(default = "default_variables")]
+    pub variables: Value,
+}
+
+/// Default (empty) variable declarations
+fn default_variables() -> Value {
+    Value::Object(Map::new())
+}
+
+/// Request data for submitting a job from a template
+#[derive(Debug, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct SubmitFromTemplateRequest {
+    /// Values of the template's variables
+    #[serde(default)]
+    pub variables: Map<String, Value>,
+    /// Priority level, 0 (low) to 3 (critical) or its name (optional, defaults to medium)
+    #[serde(default = "default_priority", deserialize_with = "request::priority")]
+    pub priority: PriorityLevel,
+}
+
+/// Default priority function
+fn default_priority() -> PriorityLevel {
+    PriorityLevel::Medium
+}
+
+/// Response data for a job template
+#[derive(Debug, Serialize)]
+pub struct JobTemplateResponse {
+    pub id: Uuid,
+    pub customer_id: Uuid,
+    pub job_type_id: Uuid,
+    pub name: String,
+    pub description: Option<String>,
+    pub input_template: Value,
+    pub variables: Value,
+    pub created_at: Option<DateTime<Utc>>,
+    pub updated_at: Option<DateTime<Utc>>,
+}
+
+impl From<JobTemplate> for JobTemplateResponse {
+    fn from(template: JobTemplate) -> Self {
+        Self {
+            id: template.id,
+            customer_id: template.customer_id,
+            job_type_id: template.job_type_id,
+            name: template.name,
+            description: template.description,
+            input_template: template.input_template,
+            variables: template.variables,
+            created_at: template.created_at,
+            updated_at: template.updated_at,
+        }
+    }
+}
+
+/// Load a job template and verify it belongs to the authenticated customer
+async fn find_owned_template(state: &AppState, customer: &CustomerUser, id: Uuid) -> Result<JobTemplate, StatusCode> {
+    let template = state.job_template_repo.find_by_id(id).await
+        .map_err(|e| {
+            error!("Failed to find job template {}: {}", id, e);
+            StatusCode::NOT_FOUND
+        })?;
+
+    if template.customer_id != customer.id {
+        return Err(StatusCode::FORBIDDEN);
+    }
+
+    Ok(template)
+}
+
+/// Create a job template
+/// Access: Customer
+pub async fn create_job_template(
+    State(state): State<AppState>,
+    Extension(customer): Extension<CustomerUser>,
+    StrictJson(request): StrictJson<CreateJobTemplateRequest>,
+) -> Result<(StatusCode, Json<JobTemplateResponse>), StatusCode> {
+    if request.name.trim().is_empty() {
+        error!("Job template name must not be empty");
+        return Err(StatusCode::BAD_REQUEST);
+    }
+
+    JobTemplate::validate(&request.input_template, &request.variables)
+        .map_err(|e| {
+            error!("Invalid job template: {}", e);
+            StatusCode::BAD_REQUEST
+        })?;
+
+    state.job_type_repo.find_by_id(request.job_type_id).await
+        .map_err(|e| {
+            error!("Failed to find job type {}: {}", request.job_type_id, e);
+            StatusCode::BAD_REQUEST
+        })?;
+
+    let existing = state.job_template_repo.find_by_customer_id(customer.id).await
+        .map_err(|e| {
+            error!("Failed to list job templates for customer {}: {}", customer.id, e);
+            StatusCode::INTERNAL_SERVER_ERROR
+        })?;
+    if existing.iter().any(|template| template.name == request.name) {
+        error!("Job template {} already exists for customer {}", request.name, customer.id);
+        return Err(StatusCode::CONFLICT);
+    }
+
+    let new_template = NewJobTemplate {
+        id: Uuid::new_v4(),
+        customer_id: customer.id,
+        job_type_id: request.job_type_id,
+        name: request.name,
+        description: request.description,
+        input_template: request.input_template,
+        variables: request.variables,
+    };
+
+    let template = state.job_template_repo.create(new_template).await
+        .map_err(|e| {
+            error!("Failed to create job template: {}", e);
+            StatusCode::INTERNAL_SERVER_ERROR
+        })?;
+
+    info!("Created job template {} for customer {}", template.id, customer.id);
+
+    Ok((StatusCode::CREATED, Json(template.into())))
+}
+
+/// List the job templates of the authenticated customer
+/// Access: Customer
+pub async fn list_job_templates(
+    State(state): State<AppState>,
+    Extension(customer): Extension<CustomerUser>,
+) -> Result<Json<Vec<JobTemplateResponse>>, StatusCode> {
+    let templates = state.job_template_repo.find_by_customer_id(customer.id).await
+        .map_err(|e| {
+            error!("Failed to list job templates for customer {}: {}", customer.id, e);
+            StatusCode::INTERNAL_SERVER_ERROR
+        })?;
+
+    Ok(Json(templates.into_iter().map(JobTemplateResponse::from).collect()))
+}
+
+/// Get a job template by ID
+/// Access: Template's Customer
+pub async fn get_job_template(
+    State(state): State<AppState>,
+    Extension(customer): Extension<CustomerUser>,
+    Path(id): Path<Uuid>,
+) -> Result<Json<JobTemplateResponse>, StatusCode> {
+    let template = find_owned_template(&state, &customer, id).await?;
+    Ok(Json(template.into()))
+}
+
+/// Delete a job template
+/// Access: Template's Customer
+pub async fn delete_job_template(
+    State(state): State<AppState>,
+    Extension(customer): Extension<CustomerUser>,
+    Path(id): Path<Uuid>,
+) -> Result<StatusCode, StatusCode> {
+    find_owned_template(&state, &customer, id).await?;
+
+    state.job_template_repo.delete(id).await
+        .map_err(|e| {
+            error!("Failed to delete job template {}: {}", id, e);
+            StatusCode::INTERNAL_SERVER_ERROR
+        })?;
+
+    info!("Deleted job template: {}", id);
+
+    Ok(StatusCode::NO_CONTENT)
+}
+
+/// Submit a job from a template by supplying only its variable values
+/// Access: Template's Customer
+pub async fn submit_job_from_template(
+    State(state): State<AppState>,
+    Extension(customer): Extension<CustomerUser>,
+    Path(template_id): Path<Uuid>,
+    StrictJson(request): StrictJson<SubmitFromTemplateRequest>,
+) -> Result<(StatusCode, Json<JobResponse>), SubmitError> {
+    let template = find_owned_template(&state, &customer, template_id).await?;
+
+    let input_data = template.render(&request.variables)
+        .map_err(|e| {
+            error!("Invalid variables for job template {}: {}", template_id, e);
+            StatusCode::BAD_REQUEST
+        })?;
+
+    let mut job = Job::new(
+        customer.id,
+        template.job_type_id,
+        input_data,
+        request.priority,
+        1000, // $10.00 default estimated cost, as for directly submitted jobs
+    );
+
+    // Jobs submitted with a sandbox key run through the stub processor
+    job.test_mode = customer.test_mode;
+
+    let response = submit_job(&state, job).await?;
+    info!("Submitted job {} from template {}", response.id, template_id);
+
+    Ok((StatusCode::CREATED, Json(response)))
+}
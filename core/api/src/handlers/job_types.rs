@@ -1,18 +1,32 @@
-use axum::{extract::{Path, State}, http::StatusCode, Json};
+use axum::{extract::{Path, Query, State}, http::StatusCode, Json};
 use serde::{Deserialize, Serialize};
+use serde_json::Value;
 use uuid::Uuid;
+use chrono::{DateTime, Utc};
 
+use innosystem_common::models::job::PriorityLevel;
+use innosystem_common::models::job_type::{CatalogVisibility, JobType, ProcessorType};
+use innosystem_common::models::redaction::parse_rules;
+use innosystem_common::models::response_policy::ResponsePolicy;
+use innosystem_common::models::webhook_template::WebhookTemplate;
+
+use crate::handlers::dependencies::{DependencyError, ForceQuery};
+use crate::handlers::jobs::priority_name;
+use crate::request::{self, StrictJson};
+use crate::services::cache::{job_type_key, JOB_TYPES_KEY};
 use crate::state::AppState;
 
 /// Request data for creating a new job type
 #[derive(Debug, Deserialize)]
+#[serde(deny_unknown_fields)]
 pub struct CreateJobTypeRequest {
     /// Job type name
     pub name: String,
     /// Job type description
     pub description: String,
-    /// Processor type
-    pub processor_type: String,
+    /// Processor type: sync, async, external_api, batch or webhook
+    #[serde(deserialize_with = "request::processor_type")]
+    pub processor_type: ProcessorType,
     /// Custom processing logic ID (if applicable)
     pub processing_logic_id: Option<Uuid>,
     /// Standard cost in cents
@@ -20,6 +34,124 @@ pub struct CreateJobTypeRequest {
     /// Whether the job type is enabled
     #[serde(default = "default_enabled")]
     pub enabled: bool,
+    /// Catalog category, e.g. "documents"
+    pub category: Option<String>,
+    /// Icon name or image URL
+    pub icon: Option<String>,
+    /// Link to the job type's documentation (http or https)
+    pub documentation_url: Option<String>,
+    /// Example input_data
+    pub sample_input: Option<Value>,
+    /// Example output for `sample_input`
+    pub sample_output: Option<Value>,
+    /// public, reseller or internal (defaults to public)
+    pub visibility: Option<String>,
+}
+
+impl CreateJobTypeRequest {
+    /// Marketplace listing metadata of the new job type
+    fn listing(&self) -> ListingRequest {
+        ListingRequest {
+            category: self.category.clone(),
+            icon: self.icon.clone(),
+            documentation_url: self.documentation_url.clone(),
+            sample_input: self.sample_input.clone(),
+            sample_output: self.sample_output.clone(),
+            visibility: self.visibility.clone(),
+        }
+    }
+}
+
+/// Marketplace listing metadata of a job type
+#[derive(Debug, Default, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct ListingRequest {
+    /// Catalog category, e.g. "documents"
+    pub category: Option<String>,
+    /// Icon name or image URL
+    pub icon: Option<String>,
+    /// Link to the job type's documentation (http or https)
+    pub documentation_url: Option<String>,
+    /// Example input_data
+    pub sample_input: Option<Value>,
+    /// Example output for `sample_input`
+    pub sample_output: Option<Value>,
+    /// public, reseller or internal (defaults to public)
+    pub visibility: Option<String>,
+}
+
+impl ListingRequest {
+    /// Validate the metadata and apply it to a job type
+    fn apply(self, job_type: &mut JobType) -> Result<(), String> {
+        if let Some(visibility) = self.visibility {
+            job_type.visibility = CatalogVisibility::parse(&visibility)
+                .ok_or_else(|| format!("Invalid visibility: {} (valid: public, reseller, internal)", visibility))?;
+        }
+        if let Some(url) = &self.documentation_url {
+            if !(url.starts_with("https://") || url.starts_with("http://")) {
+                return Err(format!("Invalid documentation URL: {}", url));
+            }
+        }
+        job_type.category = self.category
+            .map(|category| category.trim().to_lowercase())
+            .filter(|category| !category.is_empty());
+        job_type.icon = self.icon;
+        job_type.documentation_url = self.documentation_url;
+        job_type.sample_input = self.sample_input;
+        job_type.sample_output = self.sample_output;
+        Ok(())
+    }
+}
+
+/// Output redaction settings of a job type
+#[derive(Debug, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct RedactionRequest {
+    /// JSONPath patterns of the output values to redact, e.g. "$.token" or "$..password"
+    #[serde(default)]
+    pub rules: Vec<String>,
+    /// Hours the original output is kept for administrators (omit to keep none)
+    pub unredacted_retention_hours: Option<i32>,
+}
+
+/// External provider a job type depends on
+#[derive(Debug, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct ProviderLinkRequest {
+    /// Provider whose health holds back the job type's jobs (omit to depend on none)
+    pub provider_id: Option<Uuid>,
+}
+
+/// Priority level of the jobs of a job type submitted without one
+#[derive(Debug, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct DefaultPriorityRequest {
+    /// Priority level, 0 (low) to 3 (critical) or its name (null to fall back to medium)
+    #[serde(deserialize_with = "request::optional_priority")]
+    pub default_priority: Option<PriorityLevel>,
+}
+
+/// Request the webhook jobs of a job type send
+#[derive(Debug, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct WebhookTemplateRequest {
+    /// Method, headers and body template (null to post the jobs' input_data)
+    pub webhook_template: Option<WebhookTemplate>,
+}
+
+/// How the runner keeps the webhook responses of a job type
+#[derive(Debug, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct ResponsePolicyRequest {
+    /// Size limits, JSON parsing and handling of binary bodies (null for the defaults)
+    pub response_policy: Option<ResponsePolicy>,
+}
+
+/// Whether a job type accepts new jobs
+#[derive(Debug, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct EnabledRequest {
+    pub enabled: bool,
 }
 
 /// Default enabled status
@@ -28,7 +160,7 @@ fn default_enabled() -> bool {
 }
 
 /// Response data for job type operations
-#[derive(Debug, Serialize)]
+#[derive(Debug, Serialize, Deserialize)]
 pub struct JobTypeResponse {
     /// Job type ID
     pub id: Uuid,
@@ -44,37 +176,107 @@ pub struct JobTypeResponse {
     pub standard_cost_cents: i32,
     /// Whether the job type is enabled
     pub enabled: bool,
+    /// Marketplace category
+    pub category: Option<String>,
+    /// Icon name or image URL
+    pub icon: Option<String>,
+    /// Link to the job type's documentation
+    pub documentation_url: Option<String>,
+    /// Example input_data
+    pub sample_input: Option<Value>,
+    /// Example output for `sample_input`
+    pub sample_output: Option<Value>,
+    /// Catalog visibility scope
+    pub visibility: String,
+    /// JSONPath patterns of the output values that are redacted
+    pub redaction_rules: Vec<String>,
+    /// Hours the original output of redacted jobs is kept
+    pub unredacted_retention_hours: Option<i32>,
+    /// External provider the job type depends on
+    pub provider_id: Option<Uuid>,
+    /// Priority of jobs submitted without one, unless their customer has its own
+    pub default_priority: Option<String>,
+    /// Method, headers and body template of webhook jobs
+    pub webhook_template: Option<Value>,
+    /// How webhook responses are kept in the output of jobs
+    pub response_policy: Option<Value>,
     /// Creation timestamp
-    pub created_at: Option<String>,
+    pub created_at: Option<DateTime<Utc>>,
     /// Last update timestamp
-    pub updated_at: Option<String>,
+    pub updated_at: Option<DateTime<Utc>>,
+}
+
+impl JobTypeResponse {
+    /// Placeholder body of error responses
+    fn empty() -> Self {
+        Self {
+            id: Uuid::nil(),
+            name: "".to_string(),
+            description: "".to_string(),
+            processor_type: "".to_string(),
+            processing_logic_id: None,
+            standard_cost_cents: 0,
+            enabled: false,
+            category: None,
+            icon: None,
+            documentation_url: None,
+            sample_input: None,
+            sample_output: None,
+            visibility: "".to_string(),
+            redaction_rules: Vec::new(),
+            unredacted_retention_hours: None,
+            provider_id: None,
+            default_priority: None,
+            webhook_template: None,
+            response_policy: None,
+            created_at: None,
+            updated_at: None,
+        }
+    }
+}
+
+impl From<JobType> for JobTypeResponse {
+    fn from(job_type: JobType) -> Self {
+        Self {
+            id: job_type.id,
+            name: job_type.name,
+            description: job_type.description.unwrap_or_default(),
+            processor_type: job_type.processor_type.as_str().to_string(),
+            processing_logic_id: Uuid::parse_str(&job_type.processing_logic_id).ok(),
+            standard_cost_cents: job_type.standard_cost_cents,
+            enabled: job_type.enabled,
+            category: job_type.category,
+            icon: job_type.icon,
+            documentation_url: job_type.documentation_url,
+            sample_input: job_type.sample_input,
+            sample_output: job_type.sample_output,
+            visibility: job_type.visibility.as_str().to_string(),
+            redaction_rules: job_type.redaction_rules,
+            unredacted_retention_hours: job_type.unredacted_retention_hours,
+            provider_id: job_type.provider_id,
+            default_priority: job_type.default_priority.map(priority_name),
+            webhook_template: job_type.webhook_template,
+            response_policy: job_type.response_policy,
+            created_at: job_type.created_at,
+            updated_at: job_type.updated_at,
+        }
+    }
 }
 
 /// Create a new job type
 pub async fn create_job_type(
     State(state): State<AppState>,
-    Json(payload): Json<CreateJobTypeRequest>,
+    StrictJson(payload): StrictJson<CreateJobTypeRequest>,
 ) -> (StatusCode, Json<JobTypeResponse>) {
-    tracing::info!("Received job type creation request: name={}, processor_type={}", payload.name, payload.processor_type);
-    // Parse processor type from string
-    let processor_type = match innosystem_common::models::job_type::ProcessorType::from_str(&payload.processor_type) {
-        Some(pt) => pt,
-        None => {
-            tracing::error!("Invalid processor type: {}", payload.processor_type);
-            tracing::error!("Valid processor types are: sync, async, external_api, batch, webhook");
-            return (StatusCode::BAD_REQUEST, Json(JobTypeResponse {
-                id: Uuid::nil(),
-                name: "".to_string(),
-                description: "".to_string(),
-                processor_type: "".to_string(),
-                processing_logic_id: None,
-                standard_cost_cents: 0,
-                enabled: false,
-                created_at: None,
-                updated_at: None,
-            }));
-        }
-    };
+    tracing::info!("Received job type creation request: name={}, processor_type={}", payload.name, payload.processor_type.as_str());
+    let processor_type = payload.processor_type.clone();
+    
+    // Validate the listing metadata on a draft of the job type
+    let mut listing = JobType::new(payload.name.clone(), String::new(), processor_type.clone(), payload.standard_cost_cents);
+    if let Err(reason) = payload.listing().apply(&mut listing) {
+        tracing::error!("{}", reason);
+        return (StatusCode::BAD_REQUEST, Json(JobTypeResponse::empty()));
+    }
     
     // Create the job type model for database insertion
     let new_job_type = innosystem_common::models::job_type::NewJobType {
@@ -87,6 +289,12 @@ pub async fn create_job_type(
             .unwrap_or_else(|| Uuid::new_v4().to_string()),
         standard_cost_cents: payload.standard_cost_cents,
         enabled: payload.enabled,
+        category: listing.category,
+        icon: listing.icon,
+        documentation_url: listing.documentation_url,
+        sample_input: listing.sample_input,
+        sample_output: listing.sample_output,
+        visibility: listing.visibility.as_str().to_string(),
     };
     
     tracing::debug!("Creating job type with processor_type: {}", processor_type.as_str());
@@ -98,34 +306,17 @@ pub async fn create_job_type(
         Err(e) => {
             tracing::error!("Failed to create job type: {}", e);
             tracing::error!("Error details: {:?}", e);
-            return (StatusCode::INTERNAL_SERVER_ERROR, Json(JobTypeResponse {
-                id: Uuid::nil(),
-                name: "".to_string(),
-                description: "".to_string(),
-                processor_type: "".to_string(),
-                processing_logic_id: None,
-                standard_cost_cents: 0,
-                enabled: false,
-                created_at: None,
-                updated_at: None,
-            }));
+            return (StatusCode::INTERNAL_SERVER_ERROR, Json(JobTypeResponse::empty()));
         }
     };
     
-    // Create the response
-    let response = JobTypeResponse {
-        id: job_type.id,
-        name: job_type.name,
-        description: job_type.description.unwrap_or_default(),
-        processor_type: job_type.processor_type.as_str().to_string(),
-        processing_logic_id: Uuid::parse_str(&job_type.processing_logic_id).ok(),
-        standard_cost_cents: job_type.standard_cost_cents,
-        enabled: job_type.enabled,
-        created_at: job_type.created_at.map(|dt| dt.and_utc().to_rfc3339()),
-        updated_at: job_type.updated_at.map(|dt| dt.and_utc().to_rfc3339()),
-    };
+    let job_type_id = job_type.id;
+    let response = JobTypeResponse::from(job_type);
     
-    tracing::info!("Created new job type with ID: {}", job_type.id);
+    // The catalog changed, stop serving the cached list
+    state.response_cache.invalidate(&[JOB_TYPES_KEY]).await;
+    
+    tracing::info!("Created new job type with ID: {}", job_type_id);
     (StatusCode::CREATED, Json(response))
 }
 
@@ -143,6 +334,11 @@ pub async fn get_job_type(
         }
     };
     
+    let cache_key = job_type_key(job_type_id);
+    if let Some(cached) = state.response_cache.get::<JobTypeResponse>(&cache_key).await {
+        return Ok(Json(cached));
+    }
+    
     // Fetch the job type from the repository
     let job_type = state.job_type_repo.find_by_id(job_type_id).await
         .map_err(|e| {
@@ -155,20 +351,12 @@ pub async fn get_job_type(
             }
         })?;
     
-    // Create the response
-    let response = JobTypeResponse {
-        id: job_type.id,
-        name: job_type.name,
-        description: job_type.description.unwrap_or_default(),
-        processor_type: job_type.processor_type.as_str().to_string(),
-        processing_logic_id: Uuid::parse_str(&job_type.processing_logic_id).ok(),
-        standard_cost_cents: job_type.standard_cost_cents,
-        enabled: job_type.enabled,
-        created_at: job_type.created_at.map(|dt| dt.and_utc().to_rfc3339()),
-        updated_at: job_type.updated_at.map(|dt| dt.and_utc().to_rfc3339()),
-    };
+    let job_type_id = job_type.id;
+    let response = JobTypeResponse::from(job_type);
+    
+    state.response_cache.put(&cache_key, &response).await;
     
-    tracing::info!("Retrieved job type with ID: {}", job_type.id);
+    tracing::info!("Retrieved job type with ID: {}", job_type_id);
     Ok(Json(response))
 }
 
@@ -177,6 +365,10 @@ pub async fn get_job_type(
 pub async fn get_all_job_types(
     State(state): State<AppState>,
 ) -> Result<Json<Vec<JobTypeResponse>>, StatusCode> {
+    if let Some(cached) = state.response_cache.get::<Vec<JobTypeResponse>>(JOB_TYPES_KEY).await {
+        return Ok(Json(cached));
+    }
+    
     // Fetch all job types from the repository
     let job_types = state.job_type_repo.list_all().await
         .map_err(|e| {
@@ -185,20 +377,286 @@ pub async fn get_all_job_types(
         })?;
     
     // Convert to response format
-    let job_type_responses = job_types.into_iter().map(|jt| {
-        JobTypeResponse {
-            id: jt.id,
-            name: jt.name,
-            description: jt.description.unwrap_or_default(),
-            processor_type: jt.processor_type.as_str().to_string(),
-            processing_logic_id: Uuid::parse_str(&jt.processing_logic_id).ok(),
-            standard_cost_cents: jt.standard_cost_cents,
-            enabled: jt.enabled,
-            created_at: jt.created_at.map(|dt| dt.and_utc().to_rfc3339()),
-            updated_at: jt.updated_at.map(|dt| dt.and_utc().to_rfc3339()),
-        }
-    }).collect();
+    let job_type_responses: Vec<JobTypeResponse> = job_types.into_iter().map(JobTypeResponse::from).collect();
+    
+    state.response_cache.put(JOB_TYPES_KEY, &job_type_responses).await;
     
     tracing::info!("Retrieved all job types from database");
     Ok(Json(job_type_responses))
 }
+
+/// Update the marketplace listing metadata of a job type
+/// Access: Admin
+pub async fn update_job_type_listing(
+    State(state): State<AppState>,
+    Path(job_type_id): Path<Uuid>,
+    StrictJson(payload): StrictJson<ListingRequest>,
+) -> Result<Json<JobTypeResponse>, StatusCode> {
+    let mut job_type = state.job_type_repo.find_by_id(job_type_id).await
+        .map_err(|e| {
+            tracing::error!("Failed to fetch job type {}: {}", job_type_id, e);
+            StatusCode::NOT_FOUND
+        })?;
+    
+    payload.apply(&mut job_type)
+        .map_err(|reason| {
+            tracing::error!("{}", reason);
+            StatusCode::BAD_REQUEST
+        })?;
+    
+    let job_type = state.job_type_repo.update(job_type).await
+        .map_err(|e| {
+            tracing::error!("Failed to update listing of job type {}: {}", job_type_id, e);
+            StatusCode::INTERNAL_SERVER_ERROR
+        })?;
+    
+    state.response_cache.invalidate(&[JOB_TYPES_KEY, &job_type_key(job_type_id)]).await;
+    
+    tracing::info!("Updated marketplace listing of job type {}", job_type_id);
+    Ok(Json(job_type.into()))
+}
+
+/// Replace the output redaction rules of a job type
+///
+/// Rules apply to jobs completed from now on; outputs stored earlier are unchanged.
+/// Access: Admin
+pub async fn update_job_type_redaction(
+    State(state): State<AppState>,
+    Path(job_type_id): Path<Uuid>,
+    StrictJson(payload): StrictJson<RedactionRequest>,
+) -> Result<Json<JobTypeResponse>, StatusCode> {
+    let mut job_type = state.job_type_repo.find_by_id(job_type_id).await
+        .map_err(|e| {
+            tracing::error!("Failed to fetch job type {}: {}", job_type_id, e);
+            StatusCode::NOT_FOUND
+        })?;
+    
+    parse_rules(&payload.rules)
+        .map_err(|e| {
+            tracing::error!("{}", e);
+            StatusCode::BAD_REQUEST
+        })?;
+    if payload.unredacted_retention_hours.is_some_and(|hours| hours <= 0) {
+        tracing::error!("Invalid unredacted output retention: {:?} hours", payload.unredacted_retention_hours);
+        return Err(StatusCode::BAD_REQUEST);
+    }
+    job_type.redaction_rules = payload.rules;
+    job_type.unredacted_retention_hours = payload.unredacted_retention_hours;
+    
+    let job_type = state.job_type_repo.update(job_type).await
+        .map_err(|e| {
+            tracing::error!("Failed to update redaction rules of job type {}: {}", job_type_id, e);
+            StatusCode::INTERNAL_SERVER_ERROR
+        })?;
+    
+    state.response_cache.invalidate(&[JOB_TYPES_KEY, &job_type_key(job_type_id)]).await;
+    
+    tracing::info!("Set {} redaction rules on job type {}", job_type.redaction_rules.len(), job_type_id);
+    Ok(Json(job_type.into()))
+}
+
+/// Make a job type depend on an external provider, or on none
+///
+/// Jobs of the type submitted while the provider is down are scheduled for after its
+/// retry delay instead of being queued.
+/// Access: Admin
+pub async fn update_job_type_provider(
+    State(state): State<AppState>,
+    Path(job_type_id): Path<Uuid>,
+    StrictJson(payload): StrictJson<ProviderLinkRequest>,
+) -> Result<Json<JobTypeResponse>, StatusCode> {
+    let mut job_type = state.job_type_repo.find_by_id(job_type_id).await
+        .map_err(|e| {
+            tracing::error!("Failed to fetch job type {}: {}", job_type_id, e);
+            StatusCode::NOT_FOUND
+        })?;
+    
+    if let Some(provider_id) = payload.provider_id {
+        state.provider_repo.find_by_id(provider_id).await
+            .map_err(|e| {
+                tracing::error!("Failed to find provider {}: {}", provider_id, e);
+                StatusCode::BAD_REQUEST
+            })?;
+    }
+    job_type.provider_id = payload.provider_id;
+    
+    let job_type = state.job_type_repo.update(job_type).await
+        .map_err(|e| {
+            tracing::error!("Failed to update provider of job type {}: {}", job_type_id, e);
+            StatusCode::INTERNAL_SERVER_ERROR
+        })?;
+    
+    state.response_cache.invalidate(&[JOB_TYPES_KEY, &job_type_key(job_type_id)]).await;
+    
+    tracing::info!("Job type {} now depends on provider {:?}", job_type_id, job_type.provider_id);
+    Ok(Json(job_type.into()))
+}
+
+/// Set or clear the default priority of a job type
+///
+/// Jobs of the type submitted without a priority get it, unless their customer has a
+/// default priority of its own.
+/// Access: Admin
+pub async fn update_job_type_default_priority(
+    State(state): State<AppState>,
+    Path(job_type_id): Path<Uuid>,
+    StrictJson(payload): StrictJson<DefaultPriorityRequest>,
+) -> Result<Json<JobTypeResponse>, StatusCode> {
+    let mut job_type = state.job_type_repo.find_by_id(job_type_id).await
+        .map_err(|e| {
+            tracing::error!("Failed to fetch job type {}: {}", job_type_id, e);
+            StatusCode::NOT_FOUND
+        })?;
+    job_type.default_priority = payload.default_priority.map(|priority| priority.as_i32());
+    
+    let job_type = state.job_type_repo.update(job_type).await
+        .map_err(|e| {
+            tracing::error!("Failed to update default priority of job type {}: {}", job_type_id, e);
+            StatusCode::INTERNAL_SERVER_ERROR
+        })?;
+    
+    state.response_cache.invalidate(&[JOB_TYPES_KEY, &job_type_key(job_type_id)]).await;
+    
+    tracing::info!("Set default priority of job type {} to {:?}", job_type_id, job_type.default_priority);
+    Ok(Json(job_type.into()))
+}
+
+/// Set or clear the request the webhook jobs of a job type send
+///
+/// The template sets the HTTP method, headers and JSON body, whose strings may reference
+/// the job's input with `{{ input.<path> }}`, its ID with `{{ job.id }}` and the time
+/// of the request with `{{ datetime }}`. Without one, jobs post their input_data.
+/// Access: Admin
+pub async fn update_job_type_webhook_template(
+    State(state): State<AppState>,
+    Path(job_type_id): Path<Uuid>,
+    StrictJson(payload): StrictJson<WebhookTemplateRequest>,
+) -> Result<Json<JobTypeResponse>, StatusCode> {
+    let mut job_type = state.job_type_repo.find_by_id(job_type_id).await
+        .map_err(|e| {
+            tracing::error!("Failed to fetch job type {}: {}", job_type_id, e);
+            StatusCode::NOT_FOUND
+        })?;
+    
+    if let Some(template) = &payload.webhook_template {
+        if !matches!(job_type.processor_type, ProcessorType::Webhook) {
+            tracing::error!("Job type {} is not a webhook job type", job_type_id);
+            return Err(StatusCode::BAD_REQUEST);
+        }
+        template.validate().map_err(|e| {
+            tracing::error!("Invalid webhook template for job type {}: {}", job_type_id, e);
+            StatusCode::BAD_REQUEST
+        })?;
+    }
+    job_type.webhook_template = payload.webhook_template
+        .map(serde_json::to_value)
+        .transpose()
+        .map_err(|e| {
+            tracing::error!("Failed to serialize webhook template of job type {}: {}", job_type_id, e);
+            StatusCode::INTERNAL_SERVER_ERROR
+        })?;
+    
+    let job_type = state.job_type_repo.update(job_type).await
+        .map_err(|e| {
+            tracing::error!("Failed to update webhook template of job type {}: {}", job_type_id, e);
+            StatusCode::INTERNAL_SERVER_ERROR
+        })?;
+    
+    state.response_cache.invalidate(&[JOB_TYPES_KEY, &job_type_key(job_type_id)]).await;
+    
+    tracing::info!("Set webhook template of job type {}", job_type_id);
+    Ok(Json(job_type.into()))
+}
+
+/// Set or clear how the runner keeps the webhook responses of a job type
+///
+/// Text and JSON responses are kept up to `max_captured_bytes`, JSON ones parsed into
+/// structured output when `parse_json` is set; binary ones are stored as artifacts of
+/// the job, up to `max_artifact_bytes`, or discarded.
+/// Access: Admin
+pub async fn update_job_type_response_policy(
+    State(state): State<AppState>,
+    Path(job_type_id): Path<Uuid>,
+    StrictJson(payload): StrictJson<ResponsePolicyRequest>,
+) -> Result<Json<JobTypeResponse>, StatusCode> {
+    let mut job_type = state.job_type_repo.find_by_id(job_type_id).await
+        .map_err(|e| {
+            tracing::error!("Failed to fetch job type {}: {}", job_type_id, e);
+            StatusCode::NOT_FOUND
+        })?;
+    
+    if let Some(policy) = &payload.response_policy {
+        policy.validate().map_err(|e| {
+            tracing::error!("Invalid response policy for job type {}: {}", job_type_id, e);
+            StatusCode::BAD_REQUEST
+        })?;
+    }
+    job_type.response_policy = payload.response_policy
+        .map(serde_json::to_value)
+        .transpose()
+        .map_err(|e| {
+            tracing::error!("Failed to serialize response policy of job type {}: {}", job_type_id, e);
+            StatusCode::INTERNAL_SERVER_ERROR
+        })?;
+    
+    let job_type = state.job_type_repo.update(job_type).await
+        .map_err(|e| {
+            tracing::error!("Failed to update response policy of job type {}: {}", job_type_id, e);
+            StatusCode::INTERNAL_SERVER_ERROR
+        })?;
+    
+    state.response_cache.invalidate(&[JOB_TYPES_KEY, &job_type_key(job_type_id)]).await;
+    
+    tracing::info!("Set response policy of job type {} to {:?}", job_type_id, job_type.response_policy);
+    Ok(Json(job_type.into()))
+}
+
+/// Enable or disable a job type
+///
+/// A job type with queued jobs is not disabled (409 listing them) unless `?force=true`
+/// is given; forced, its pending and scheduled jobs are cancelled in the schemas of all
+/// resellers and what was reserved for them is released. Running jobs finish as usual.
+/// Access: Admin
+pub async fn update_job_type_enabled(
+    State(state): State<AppState>,
+    Path(job_type_id): Path<Uuid>,
+    Query(query): Query<ForceQuery>,
+    StrictJson(payload): StrictJson<EnabledRequest>,
+) -> Result<Json<JobTypeResponse>, DependencyError> {
+    let mut job_type = state.job_type_repo.find_by_id(job_type_id).await
+        .map_err(|e| {
+            tracing::error!("Failed to fetch job type {}: {}", job_type_id, e);
+            StatusCode::NOT_FOUND
+        })?;
+    
+    if job_type.enabled && !payload.enabled {
+        let blockers = state.dependencies.job_type_blockers(job_type_id).await
+            .map_err(|e| {
+                tracing::error!("Failed to check the dependents of job type {}: {}", job_type_id, e);
+                StatusCode::INTERNAL_SERVER_ERROR
+            })?;
+        if !blockers.is_empty() {
+            if !query.force {
+                tracing::info!("Refused to disable job type {}: {} blockers", job_type_id, blockers.len());
+                return Err(DependencyError::Blocked(blockers));
+            }
+            state.dependencies.cancel_queued_jobs_of_job_type(job_type_id).await
+                .map_err(|e| {
+                    tracing::error!("Failed to cancel the queued jobs of job type {}: {}", job_type_id, e);
+                    StatusCode::INTERNAL_SERVER_ERROR
+                })?;
+        }
+    }
+    job_type.enabled = payload.enabled;
+    
+    let job_type = state.job_type_repo.update(job_type).await
+        .map_err(|e| {
+            tracing::error!("Failed to update job type {}: {}", job_type_id, e);
+            StatusCode::INTERNAL_SERVER_ERROR
+        })?;
+    
+    state.response_cache.invalidate(&[JOB_TYPES_KEY, &job_type_key(job_type_id)]).await;
+    
+    tracing::info!("Job type {} is now {}", job_type_id, if job_type.enabled { "enabled" } else { "disabled" });
+    Ok(Json(job_type.into()))
+}
@@ -0,0 +1,73 @@
+use axum::{extract::{Query, State, Extension}, http::StatusCode, Json};
+use chrono::{DateTime, Duration, Utc};
+use serde::{Deserialize, Serialize};
+use uuid::Uuid;
+use tracing::{error, info};
+
+use innosystem_common::database::with_reseller;
+use innosystem_common::models::job_usage::{merge_summaries, UsageSummary};
+use crate::middleware::auth::AdminUser;
+use crate::state::AppState;
+
+/// Days reported unless a start is given
+const DEFAULT_REPORT_DAYS: i64 = 30;
+
+/// Query parameters of the resource usage report
+#[derive(Debug, Deserialize)]
+pub struct UsageReportQuery {
+    /// Start of the period the usage was recorded in (defaults to 30 days before its end)
+    pub from: Option<DateTime<Utc>>,
+    /// Exclusive end of the period (defaults to now)
+    pub to: Option<DateTime<Utc>>,
+    /// Only report the jobs of this customer
+    pub customer_id: Option<Uuid>,
+}
+
+/// Response data for the resource usage report
+#[derive(Debug, Serialize)]
+pub struct UsageReportResponse {
+    pub from: DateTime<Utc>,
+    pub to: DateTime<Utc>,
+    pub summaries: Vec<UsageSummary>,
+}
+
+/// Report the resources runners used for jobs in a period, per customer and job type
+///
+/// Usage is reported from the shared schema and from every reseller schema.
+/// Access: Admin
+pub async fn get_usage_report(
+    State(state): State<AppState>,
+    Extension(admin): Extension<AdminUser>,
+    Query(query): Query<UsageReportQuery>,
+) -> Result<Json<UsageReportResponse>, StatusCode> {
+    let to = query.to.unwrap_or_else(Utc::now);
+    let from = query.from.unwrap_or(to - Duration::days(DEFAULT_REPORT_DAYS));
+    if from >= to {
+        error!("Usage report period must start before it ends");
+        return Err(StatusCode::BAD_REQUEST);
+    }
+    let internal_error = |e: anyhow::Error| {
+        error!("Failed to report job resource usage: {}", e);
+        StatusCode::INTERNAL_SERVER_ERROR
+    };
+
+    let resolver = state.schema_resolver.clone();
+    let scopes = tokio::task::spawn_blocking(move || resolver.scopes()).await
+        .map_err(|e| internal_error(e.into()))?
+        .map_err(|e| internal_error(e.into()))?;
+    let mut summaries = Vec::new();
+    for scope in scopes {
+        let scoped = with_reseller(scope, state.job_usage_repo.summarize(from, to, query.customer_id)).await
+            .map_err(internal_error)?;
+        summaries.extend(scoped);
+    }
+    let summaries = merge_summaries(summaries);
+
+    info!("Admin {} reported the resource usage of {} customer and job type pairs", admin.id, summaries.len());
+
+    Ok(Json(UsageReportResponse {
+        from,
+        to,
+        summaries,
+    }))
+}
@@ -1,29 +1,88 @@
-use axum::{extract::{Path, State}, http::StatusCode, Json};
+use axum::{extract::{Path, Query, State, Extension}, http::{header, HeaderMap, HeaderValue, StatusCode}, response::{IntoResponse, Response}, Json};
 use serde::{Deserialize, Serialize};
+use serde_json::json;
+use chrono::{DateTime, Utc};
 use uuid::Uuid;
 use tracing::{info, error, warn};
 
-use innosystem_common::models::job::{NewJob, PriorityLevel, JobStatus};
+use innosystem_common::Error;
+use innosystem_common::api::{ApiErrorBody, ApiListMeta, Paginated};
+use innosystem_common::models::audit::NewAuditEntry;
+use innosystem_common::models::dry_run;
+use innosystem_common::models::feature_flag;
+use innosystem_common::models::job::{Job, NewJob, PriorityLevel, PrioritySource, JobStatus, PUBLIC_ID_PREFIX};
+use innosystem_common::models::job_cost::CostBreakdown;
+use innosystem_common::models::job_error::JobError;
+use innosystem_common::models::job_type::ProcessorType;
+use innosystem_common::models::job_log::LogLevel;
+use innosystem_common::models::job_usage::{NewJobUsage, ResourceUsage};
+use innosystem_common::models::pii::{self, PiiFinding, PiiPolicy};
+use innosystem_common::models::wallet::validate_customer_reference;
+use innosystem_common::models::redaction::redact_output;
+use innosystem_common::repositories::job::{JobCursor, JobFilter, JobSortOrder, Pagination};
 
+use crate::middleware::auth::{AdminUser, CustomerUser};
+use crate::request::{self, StrictJson};
+use crate::services::backpressure::Admission;
+use crate::services::billing::JobCancellation;
 use crate::state::AppState;
 
+/// Maximum number of jobs returned by a single page
+const MAX_PAGE_SIZE: u32 = 500;
+
+/// Response header carrying the cursor of the next page
+const NEXT_CURSOR_HEADER: &str = "x-next-cursor";
+
+/// Query parameters for listing jobs
+///
+/// Jobs are paginated by offset: `page` (1-based) of `per_page` jobs in `sort` order, with
+/// the total count of matching jobs in the envelope. With `limit` and/or `cursor` they are
+/// paginated by keyset instead, newest first, and the next page's cursor is returned in
+/// the envelope and in `X-Next-Cursor`. The filters apply to both.
+#[derive(Debug, Deserialize)]
+pub struct ListJobsQuery {
+    /// Opaque cursor from a previous page's `X-Next-Cursor` header
+    pub cursor: Option<String>,
+    /// Number of jobs per keyset page (defaults to 50)
+    pub limit: Option<u32>,
+    /// Only the job with this public ID
+    pub public_id: Option<String>,
+    /// Only jobs in this status, e.g. `running`
+    pub status: Option<String>,
+    /// Only jobs of this customer; must be the authenticated customer
+    pub customer_id: Option<Uuid>,
+    /// Only jobs of this job type
+    pub job_type_id: Option<Uuid>,
+    /// Page number, starting at 1
+    pub page: Option<u32>,
+    /// Number of jobs per page (defaults to 50)
+    pub per_page: Option<u32>,
+    /// `created_desc` (default), `created_asc`, `priority_desc` or `priority_asc`
+    pub sort: Option<String>,
+}
+
 /// Request data for creating a new job
 #[derive(Debug, Deserialize)]
+#[serde(deny_unknown_fields)]
 pub struct CreateJobRequest {
     /// Customer ID
     pub customer_id: Uuid,
     /// Job type ID
     pub job_type_id: Uuid,
-    /// Priority level (optional, defaults to 1)
-    #[serde(default = "default_priority")]
-    pub priority: i32,
+    /// Priority level, 0 (low) to 3 (critical) or its name (optional, defaults to the
+    /// customer's default, then the job type's, then medium)
+    #[serde(default, deserialize_with = "request::optional_priority")]
+    pub priority: Option<PriorityLevel>,
     /// Input data for the job
     pub input_data: serde_json::Value,
-}
-
-/// Default priority function
-fn default_priority() -> i32 {
-    1
+    /// Deadline for starting the job (optional); a job still waiting then expires
+    /// with its reservation released instead of running late
+    pub expires_at: Option<DateTime<Utc>>,
+    /// Customer's own reference, e.g. a purchase order number, copied onto the job's
+    /// wallet transactions (optional)
+    pub customer_reference: Option<String>,
+    /// Customer's own metadata as a JSON object, copied along with the reference (optional)
+    pub customer_metadata: Option<serde_json::Value>,
 }
 
 /// Response data for job operations
@@ -31,34 +90,118 @@ fn default_priority() -> i32 {
 pub struct JobResponse {
     /// Job ID
     pub id: Uuid,
+    /// Short public ID, accepted wherever the job ID is
+    pub public_id: String,
     /// Customer ID
     pub customer_id: Uuid,
     /// Job type ID
     pub job_type_id: Uuid,
     /// Current status
     pub status: String,
-    /// Priority level
-    pub priority: i32,
+    /// Priority ("low", "medium", "high" or "critical")
+    pub priority: String,
     /// Input data
     pub input_data: serde_json::Value,
     /// Output data (if completed)
     pub output_data: Option<serde_json::Value>,
-    /// Error message (if failed)
-    pub error: Option<String>,
+    /// Structured error (if failed)
+    pub error: Option<JobError>,
     /// Estimated cost in cents
     pub estimated_cost_cents: i32,
     /// Actual cost in cents (if completed)
     pub cost_cents: Option<i32>,
     /// Creation timestamp
-    pub created_at: Option<String>,
-    /// Start timestamp
-    pub started_at: Option<String>,
+    pub created_at: Option<DateTime<Utc>>,
+    /// When the job entered the queue: at submission, when released from the schedule or
+    /// when handed back by an unresponsive runner
+    pub queued_at: Option<DateTime<Utc>>,
+    /// When a runner started processing the job
+    pub started_at: Option<DateTime<Utc>>,
     /// Completion timestamp
-    pub completed_at: Option<String>,
+    pub completed_at: Option<DateTime<Utc>>,
+    /// Whether the job was submitted with a test key
+    pub test_mode: bool,
+    /// When the job enters the queue, if it was submitted outside its submission window
+    pub scheduled_for: Option<DateTime<Utc>>,
+    /// When a runner took the job off the queue
+    pub claimed_at: Option<DateTime<Utc>>,
+    /// Time the job waited in the queue until claimed, in milliseconds
+    pub queue_wait_ms: Option<i64>,
+    /// Time from the start of the job until it finished, in milliseconds
+    pub processing_ms: Option<i64>,
+    /// Until when the claiming runner holds the job in its prefetch buffer, if it was prefetched
+    pub lease_expires_at: Option<DateTime<Utc>>,
+    /// Deadline for starting the job, after which it expires instead of running
+    pub expires_at: Option<DateTime<Utc>>,
+    /// When cancelling the job was requested while it was running
+    pub cancel_requested_at: Option<DateTime<Utc>>,
+    /// Customer's own reference given at submission
+    pub customer_reference: Option<String>,
+    /// Customer's own metadata given with the reference
+    pub customer_metadata: Option<serde_json::Value>,
+    /// Batch job this job is a sub-task of
+    pub parent_id: Option<Uuid>,
+    /// Position of the sub-task within its batch job, from 0
+    pub sub_task_index: Option<i32>,
+    /// Number of sub-tasks a batch job fanned out into
+    pub sub_task_count: Option<i32>,
+    /// Batch or file import the job was submitted with, see `GET /submissions/{id}`
+    pub submission_id: Option<Uuid>,
+    /// Failed or expired job an admin requeued this one from
+    pub requeued_from: Option<Uuid>,
+    /// What the actual cost is made of, once the job was charged
+    pub cost_breakdown: Option<CostBreakdown>,
+    /// Resources the job used on its runner, once the runner reported them
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub usage: Option<ResourceUsage>,
+    /// Where the priority came from when the job was submitted without one: `request`,
+    /// `customer`, `job_type` or `default`, consulted in that order; only returned on creation
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub priority_source: Option<PrioritySource>,
+}
+
+/// Job type of a dry run is switched off
+const REJECTION_JOB_TYPE_DISABLED: &str = "job_type_disabled";
+/// Submission or the job type's processor is switched off by a feature flag
+const REJECTION_FEATURE_DISABLED: &str = "feature_disabled";
+/// The sub-account's monthly budget cannot cover the job
+const REJECTION_BUDGET_EXCEEDED: &str = "budget_exceeded";
+/// The paying wallet is overdrawn under the `suspend` policy
+const REJECTION_WALLET_OVERDRAWN: &str = "wallet_overdrawn";
+/// The paying wallet cannot cover the funds reserved for the job
+const REJECTION_INSUFFICIENT_FUNDS: &str = "insufficient_funds";
+/// The input contains personal data and the customer's PII policy rejects it
+const REJECTION_PII_DETECTED: &str = "pii_detected";
+
+/// Response data for a dry run
+#[derive(Debug, Serialize)]
+pub struct DryRunResponse {
+    /// Job type ID
+    pub job_type_id: Uuid,
+    /// Processor that was simulated
+    pub processor_type: String,
+    /// Whether the job would run in test mode, billing the sandbox balance
+    pub test_mode: bool,
+    /// Whether the job would be accepted now and complete
+    pub accepted: bool,
+    /// Why the job would be refused if submitted now: `job_type_disabled`,
+    /// `feature_disabled`, `budget_exceeded`, `wallet_overdrawn` or `insufficient_funds`
+    pub rejections: Vec<String>,
+    /// Output the job would produce, with webhooks left unsent
+    pub output: Option<serde_json::Value>,
+    /// Error the job would fail with
+    pub error: Option<JobError>,
+    /// Funds reserved while the job runs, in cents
+    pub estimated_cost_cents: i32,
+    /// What the job would be charged, in cents; failed jobs are not charged
+    pub cost_cents: i32,
+    /// What the charge would be made of, before promotional credit
+    pub cost_breakdown: Option<CostBreakdown>,
 }
 
 /// Request to calculate job cost
 #[derive(Debug, Deserialize)]
+#[serde(deny_unknown_fields)]
 pub struct CalculateJobCostRequest {
     /// Job ID to calculate cost for
     pub job_id: Uuid,
@@ -73,10 +216,13 @@ pub struct JobCostResponse {
     pub estimated_cost_cents: i32,
     /// Calculated actual cost in cents
     pub calculated_cost_cents: i32,
+    /// What the calculated cost is made of
+    pub cost_breakdown: CostBreakdown,
 }
 
 /// Request to complete a job
 #[derive(Debug, Deserialize)]
+#[serde(deny_unknown_fields)]
 pub struct CompleteJobRequest {
     /// Job ID to mark as completed
     pub job_id: Uuid,
@@ -84,75 +230,516 @@ pub struct CompleteJobRequest {
     pub success: bool,
     /// Output data from the job
     pub output_data: Option<serde_json::Value>,
-    /// Error message if job failed
-    pub error: Option<String>,
+    /// Structured error if job failed
+    pub error: Option<JobError>,
+    /// Resources the runner measured while running the job (optional)
+    #[serde(default)]
+    pub usage: Option<ResourceUsage>,
+}
+
+/// Reason a job submission was not accepted
+#[derive(Debug)]
+pub(crate) enum SubmitError {
+    /// The request failed with the given status
+    Status(StatusCode),
+    /// The job's queue is at its depth limit; the client should retry later
+    QueueSaturated { retry_after_secs: u64 },
+    /// Submissions are switched off by the named feature flag
+    Disabled { flag: String },
+    /// The paying wallet is overdrawn under the `suspend` policy until it is topped up
+    Overdrawn { balance_cents: i32 },
+    /// The input contains personal data and the customer's PII policy rejects it
+    PiiDetected { findings: Vec<PiiFinding> },
+}
+
+impl From<StatusCode> for SubmitError {
+    fn from(status: StatusCode) -> Self {
+        Self::Status(status)
+    }
+}
+
+impl std::fmt::Display for SubmitError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::Status(status) => write!(f, "{}", status),
+            Self::QueueSaturated { retry_after_secs } => write!(f, "queue saturated, retry after {} seconds", retry_after_secs),
+            Self::Disabled { flag } => write!(f, "disabled by feature flag {}", flag),
+            Self::Overdrawn { balance_cents } => write!(f, "wallet overdrawn at {} cents", balance_cents),
+            Self::PiiDetected { findings } => write!(f, "{} personal data findings in input", findings.len()),
+        }
+    }
+}
+
+impl IntoResponse for SubmitError {
+    fn into_response(self) -> Response {
+        match self {
+            Self::Status(status) => status.into_response(),
+            Self::QueueSaturated { retry_after_secs } => (
+                StatusCode::TOO_MANY_REQUESTS,
+                [(header::RETRY_AFTER, retry_after_secs.to_string())],
+                Json(ApiErrorBody::new("queue_saturated").with("retry_after_secs", retry_after_secs)),
+            ).into_response(),
+            Self::Disabled { flag } => (
+                StatusCode::SERVICE_UNAVAILABLE,
+                Json(ApiErrorBody::new("feature_disabled").with("flag", flag)),
+            ).into_response(),
+            Self::Overdrawn { balance_cents } => (
+                StatusCode::PAYMENT_REQUIRED,
+                Json(ApiErrorBody::new("wallet_overdrawn").with("balance_cents", balance_cents)),
+            ).into_response(),
+            Self::PiiDetected { findings } => (
+                StatusCode::UNPROCESSABLE_ENTITY,
+                Json(ApiErrorBody::new("pii_detected").with("findings", findings)),
+            ).into_response(),
+        }
+    }
 }
 
 /// Create a new job
+///
+/// A job submitted without a priority gets its customer's default priority, else its job
+/// type's, else medium; `priority_source` in the response tells which one applied.
+///
+/// Returns 429 Too Many Requests with a Retry-After header when the job's queue is
+/// saturated and the backpressure policy rejects submissions, 503 Service Unavailable
+/// while job submission or the job type's processor is switched off by a feature flag,
+/// 402 Payment Required while the paying wallet is overdrawn under the `suspend`
+/// overdraft policy, and 422 Unprocessable Entity listing where personal data was found
+/// when the input contains some and the customer's PII policy is `reject`.
 #[allow(dead_code)]
 pub async fn create_job(
     State(state): State<AppState>,
-    Json(payload): Json<CreateJobRequest>,
-) -> Result<(StatusCode, Json<JobResponse>), StatusCode> {
-    // Convert the priority from i32 to PriorityLevel
-    let priority = PriorityLevel::from_i32(payload.priority);
+    customer: Option<Extension<CustomerUser>>,
+    StrictJson(payload): StrictJson<CreateJobRequest>,
+) -> Result<(StatusCode, Json<JobResponse>), SubmitError> {
+    let (job, priority_source) = job_from_request(&state, payload, customer).await?;
+    let mut response = submit_job(&state, job).await?;
+    response.priority_source = Some(priority_source);
+    Ok((StatusCode::CREATED, Json(response)))
+}
+
+/// Check a job request and build the job it submits, along with where its priority came from
+pub(crate) async fn job_from_request(
+    state: &AppState,
+    payload: CreateJobRequest,
+    customer: Option<Extension<CustomerUser>>,
+) -> Result<(Job, PrioritySource), StatusCode> {
+    // A deadline already passed would expire the job before it could run
+    if payload.expires_at.is_some_and(|expires_at| expires_at <= Utc::now()) {
+        error!("Refusing job with a deadline in the past: {:?}", payload.expires_at);
+        return Err(StatusCode::BAD_REQUEST);
+    }
+    if let Err(reason) = validate_customer_reference(payload.customer_reference.as_deref(), payload.customer_metadata.as_ref()) {
+        error!("{}", reason);
+        return Err(StatusCode::BAD_REQUEST);
+    }
+    let (priority, priority_source) = resolve_priority(state, payload.priority, payload.customer_id, payload.job_type_id).await?;
     
     // First create a full Job with all application-level fields
-    let job = innosystem_common::models::job::Job::new(
+    let mut job = Job::new(
         payload.customer_id,
         payload.job_type_id,
-        payload.input_data.clone(),
+        payload.input_data,
         priority,
         1000, // $10.00 default estimated cost for now
     );
     
+    // Jobs submitted with a sandbox key run through the stub processor
+    job.test_mode = customer.is_some_and(|Extension(customer)| customer.test_mode);
+    job.expires_at = payload.expires_at;
+    job.customer_reference = payload.customer_reference;
+    job.customer_metadata = payload.customer_metadata;
+    
+    Ok((job, priority_source))
+}
+
+/// Resolve the priority of a submitted job, looking up the defaults of its customer and
+/// job type only when the request gave none (see [`PriorityLevel::resolve`])
+async fn resolve_priority(
+    state: &AppState,
+    requested: Option<PriorityLevel>,
+    customer_id: Uuid,
+    job_type_id: Uuid,
+) -> Result<(PriorityLevel, PrioritySource), StatusCode> {
+    if requested.is_some() {
+        return Ok(PriorityLevel::resolve(requested, None, None));
+    }
+    
+    let customer = state.customer_repo.find_by_id(customer_id).await
+        .map_err(|e| {
+            error!("Failed to find customer {} of job: {}", customer_id, e);
+            StatusCode::BAD_REQUEST
+        })?;
+    let job_type = state.job_type_repo.find_by_id(job_type_id).await
+        .map_err(|e| {
+            error!("Failed to find job type {} of job: {}", job_type_id, e);
+            StatusCode::BAD_REQUEST
+        })?;
+    Ok(PriorityLevel::resolve(None, customer.default_priority, job_type.default_priority))
+}
+
+/// Dry-run a job: check it like a submission and pass its input through a simulated
+/// processor, returning the output it would produce and what it would cost
+///
+/// Nothing is stored, queued or charged, and no webhooks or external APIs are called.
+/// Requests a submission would refuse outright fail the same way (400); checks that
+/// depend on the moment, such as feature flags, budgets and the wallet's balance, are
+/// reported in `rejections` instead. The live processor of the job type is simulated
+/// for test keys as well, so payloads can be checked before going live.
+/// Access: Customer
+pub async fn dry_run_job(
+    State(state): State<AppState>,
+    customer: Option<Extension<CustomerUser>>,
+    StrictJson(payload): StrictJson<CreateJobRequest>,
+) -> Result<Json<DryRunResponse>, StatusCode> {
+    let (job, _) = job_from_request(&state, payload, customer).await?;
+    let job_type = state.job_type_repo.find_by_id(job.job_type_id).await
+        .map_err(|e| {
+            error!("Failed to find job type {} of dry run: {}", job.job_type_id, e);
+            StatusCode::BAD_REQUEST
+        })?;
+    
+    let mut rejections = Vec::new();
+    if !job_type.enabled {
+        rejections.push(REJECTION_JOB_TYPE_DISABLED.to_string());
+    }
+    if let Err(SubmitError::Disabled { .. }) = check_feature_flags(&state, &job).await {
+        rejections.push(REJECTION_FEATURE_DISABLED.to_string());
+    }
+    if let Err(SubmitError::PiiDetected { .. }) = check_pii(&state, &mut job.clone()).await {
+        rejections.push(REJECTION_PII_DETECTED.to_string());
+    }
+    if !job.test_mode {
+        if crate::handlers::sub_accounts::check_budget(&state, job.customer_id, job.estimated_cost_cents).await.is_err() {
+            rejections.push(REJECTION_BUDGET_EXCEEDED.to_string());
+        }
+        if let Err(SubmitError::Overdrawn { .. }) = check_overdraft(&state, &job).await {
+            rejections.push(REJECTION_WALLET_OVERDRAWN.to_string());
+        }
+    }
+    
+    let markup_rate = match state.customer_repo.find_by_id(job.customer_id).await {
+        Ok(customer) => match customer.reseller_id {
+            Some(reseller_id) => state.reseller_repo.find_by_id(reseller_id).await
+                .map(|reseller| reseller.markup_rate)
+                .map_err(|e| {
+                    error!("Failed to find reseller {} of dry run: {}", reseller_id, e);
+                    StatusCode::INTERNAL_SERVER_ERROR
+                })?,
+            None => 0,
+        },
+        Err(e) => {
+            error!("Failed to find customer {} of dry run: {}", job.customer_id, e);
+            return Err(StatusCode::BAD_REQUEST);
+        }
+    };
+    
+    // Batch jobs are priced by their sub-tasks, each billed like a job of its own
+    let now = Utc::now();
+    let simulation = match job_type.processor_type {
+        ProcessorType::Batch => {
+            let job_types = state.job_type_repo.list_all().await
+                .map_err(|e| {
+                    error!("Failed to list the job types of dry run: {}", e);
+                    StatusCode::INTERNAL_SERVER_ERROR
+                })?;
+            dry_run::batch_tasks(&job.input_data)
+                .and_then(|tasks| Ok((tasks, dry_run::sub_task_type(&job_type, &job_types)?)))
+                .and_then(|(tasks, sub_task_type)| {
+                    let outputs = tasks.iter()
+                        .map(|input| dry_run::simulate_output(sub_task_type, input, Uuid::new_v4(), now))
+                        .collect::<Result<Vec<_>, _>>()?;
+                    let cost = (0..tasks.len())
+                        .map(|_| CostBreakdown::completed(sub_task_type.standard_cost_cents, &job.priority, markup_rate))
+                        .sum::<CostBreakdown>();
+                    Ok((json!({ "sub_tasks": outputs }), cost))
+                })
+        }
+        _ => dry_run::simulate_output(&job_type, &job.input_data, job.id, now)
+            .map(|output| (output, CostBreakdown::completed(job.estimated_cost_cents, &job.priority, markup_rate))),
+    };
+    let (output, error, cost_breakdown) = match simulation {
+        Ok((output, cost)) => (Some(output), None, Some(cost)),
+        Err(job_error) => (None, Some(job_error), None),
+    };
+    
+    // The funds reserved up front must be available, from the test balance for test keys
+    let required_cents = match (&cost_breakdown, job.test_mode) {
+        (Some(cost), true) => cost.total(),
+        _ => job.estimated_cost_cents,
+    };
+    match state.billing_service.find_billing_wallet(job.customer_id).await {
+        Ok(wallet) if job.test_mode && wallet.test_balance_cents < required_cents => {
+            rejections.push(REJECTION_INSUFFICIENT_FUNDS.to_string());
+        }
+        Ok(wallet) if !job.test_mode && !wallet.can_reserve(required_cents) => {
+            rejections.push(REJECTION_INSUFFICIENT_FUNDS.to_string());
+        }
+        Ok(_) => {}
+        Err(e) => {
+            warn!("Failed to find the wallet paying for the dry run of customer {}: {}", job.customer_id, e);
+            rejections.push(REJECTION_INSUFFICIENT_FUNDS.to_string());
+        }
+    }
+    
+    info!(
+        "Dry run of job type {} for customer {}: {} rejections, {}",
+        job_type.name,
+        job.customer_id,
+        rejections.len(),
+        error.as_ref().map_or("succeeded".to_string(), |e| format!("failed with {}", e.code)),
+    );
+    Ok(Json(DryRunResponse {
+        job_type_id: job_type.id,
+        processor_type: job_type.processor_type.as_str().to_string(),
+        test_mode: job.test_mode,
+        accepted: rejections.is_empty() && error.is_none(),
+        rejections,
+        output,
+        error,
+        estimated_cost_cents: job.estimated_cost_cents,
+        cost_cents: cost_breakdown.map_or(0, |cost| cost.total()),
+        cost_breakdown,
+    }))
+}
+
+/// Persist a new job, push it to the queue and build its response
+pub(crate) async fn submit_job(
+    state: &AppState,
+    mut job: innosystem_common::models::job::Job,
+) -> Result<JobResponse, SubmitError> {
+    // Kill switches flipped at runtime, e.g. during an incident
+    check_feature_flags(state, &job).await?;
+    
+    // Personal data in the input is rejected, masked or flagged as the customer chose
+    let pii = check_pii(state, &mut job).await?;
+    
+    // Sub-accounts may not exceed their monthly budget, and overdrawn wallets may be
+    // suspended until topped up; test mode jobs cost nothing
+    if !job.test_mode {
+        crate::handlers::sub_accounts::check_budget(state, job.customer_id, job.estimated_cost_cents).await?;
+        check_overdraft(state, &job).await?;
+    }
+    
+    // Jobs submitted outside their submission window wait for its next opening
+    let mut release_time = crate::handlers::submission_windows::release_time(state, &job).await?;
+    
+    // Jobs depending on an external provider that is down wait for it to recover
+    // instead of failing against it
+    if release_time.is_none() {
+        release_time = state.provider_health.hold_until(job.job_type_id).await;
+    }
+    
+    // Jobs entering the queue now must not push it beyond its depth limits
+    if release_time.is_none() {
+        match state.backpressure_service.admit(&job.priority, job.job_type_id).await {
+            Admission::Accept => {}
+            Admission::Reject { retry_after_secs } => return Err(SubmitError::QueueSaturated { retry_after_secs }),
+            Admission::Defer { until } => release_time = Some(until),
+        }
+    }
+    
     // Convert to NewJob for repository storage
-    let new_job = NewJob::from(job.clone());
+    let new_job = NewJob::from(job);
     
     // Save the job to the repository
-    let created_job = state.job_repo.create(new_job)
+    let mut created_job = state.job_repo.create(new_job)
         .await
         .map_err(|e| {
             tracing::error!("Failed to create job: {}", e);
             StatusCode::INTERNAL_SERVER_ERROR
         })?;
-    
-    // Push the job to the queue for processing
-    // Clone priority to avoid ownership issues
-    let job_priority = created_job.priority.clone();
-    match state.job_queue.push_job(created_job.id, job_priority).await {
-        Ok(_) => tracing::info!("Job {} added to queue for processing", created_job.id),
-        Err(e) => {
-            tracing::error!("Failed to queue job {}: {}", created_job.id, e);
-            // We don't fail the request here - the job is still created, just not queued
-            // The runner will periodically scan for unqueued jobs
+    if let Some((policy, findings)) = pii {
+        let message = format!("Personal data found in the input ({} findings)", findings.len());
+        let fields = json!({
+            "event": "pii_detected",
+            "policy": policy.as_str(),
+            "findings": findings,
+        });
+        if let Err(e) = state.job_log_repo.append_event(created_job.id, LogLevel::Warn, message, Some(fields)).await {
+            warn!("Failed to record the personal data found in job {}: {}", created_job.id, e);
         }
     }
     
-    // Convert the timestamps to RFC3339 strings if they exist
-    let created_at = created_job.created_at.map(|dt| dt.and_utc().to_rfc3339());
-    let updated_at = created_job.updated_at.map(|dt| dt.and_utc().to_rfc3339()); // Changed to updated_at
-    let completed_at = created_job.completed_at.map(|dt| dt.and_utc().to_rfc3339());
+    if let Some(release_time) = release_time {
+        // Hand the job to the scheduled queue; runners pick it up once it is due
+        // (held back by a submission window or a provider that is down, or deferred by a saturated queue)
+        created_job = state.job_repo.schedule(created_job.id, release_time)
+            .await
+            .map_err(|e| {
+                tracing::error!("Failed to schedule job {}: {}", created_job.id, e);
+                StatusCode::INTERNAL_SERVER_ERROR
+            })?;
+        match state.job_queue.schedule_job(created_job.id, release_time).await {
+            Ok(_) => tracing::info!("Job {} scheduled for {}", created_job.id, release_time),
+            Err(e) => tracing::error!("Failed to schedule job {}: {}", created_job.id, e),
+        }
+    } else {
+        // Push the job to the queue for processing
+        // Clone priority to avoid ownership issues
+        let job_priority = created_job.priority.clone();
+        match state.job_queue.push_job(created_job.id, job_priority).await {
+            Ok(_) => tracing::info!("Job {} added to queue for processing", created_job.id),
+            Err(e) => {
+                tracing::error!("Failed to queue job {}: {}", created_job.id, e);
+                // We don't fail the request here - the job is still created, just not queued
+                // The runner will periodically scan for unqueued jobs
+            }
+        }
+    }
     
+    let (queue_wait_ms, processing_ms) = (created_job.queue_wait_ms(), created_job.processing_ms());
+
     // Create the response
     let response = JobResponse {
         id: created_job.id,
+        public_id: created_job.public_id.clone(),
         customer_id: created_job.customer_id,
         job_type_id: created_job.job_type_id,
         status: created_job.status.as_str().to_string(),
-        priority: created_job.priority.as_i32(), // This should work now
+        priority: created_job.priority.as_str().to_string(),
         input_data: created_job.input_data,
         output_data: created_job.output_data,
         error: created_job.error,
         estimated_cost_cents: created_job.estimated_cost_cents,
         cost_cents: Some(created_job.cost_cents), // Now cost_cents is i32, not Option<i32>
-        created_at,
-        started_at: updated_at, // Use updated_at instead of started_at
-        completed_at,
+        created_at: created_job.created_at,
+        queued_at: created_job.queued_at,
+        started_at: created_job.started_at,
+        completed_at: created_job.completed_at,
+        test_mode: created_job.test_mode,
+        scheduled_for: created_job.scheduled_for,
+        claimed_at: created_job.claimed_at,
+        queue_wait_ms,
+        processing_ms,
+        lease_expires_at: created_job.lease_expires_at,
+        expires_at: created_job.expires_at,
+        cancel_requested_at: created_job.cancel_requested_at,
+        customer_reference: created_job.customer_reference.clone(),
+        customer_metadata: created_job.customer_metadata.clone(),
+        parent_id: created_job.parent_id,
+        sub_task_index: created_job.sub_task_index,
+        sub_task_count: created_job.sub_task_count,
+        submission_id: created_job.submission_id,
+        requeued_from: created_job.requeued_from,
+        cost_breakdown: created_job.cost_breakdown,
+        usage: None,
+        priority_source: None,
     };
     
     tracing::info!("Created new job with ID: {}", created_job.id);
-    Ok((StatusCode::CREATED, Json(response)))
+    Ok(response)
+}
+
+/// Refuse a job while its submission is switched off, for everyone or for the
+/// customer's reseller
+async fn check_feature_flags(
+    state: &AppState,
+    job: &innosystem_common::models::job::Job,
+) -> Result<(), SubmitError> {
+    let reseller_id = match state.customer_repo.find_by_id(job.customer_id).await {
+        Ok(customer) => customer.reseller_id,
+        Err(_) => None,
+    };
+    
+    if state.feature_flags.is_enabled(feature_flag::JOB_SUBMISSION_DISABLED, reseller_id).await {
+        warn!("Rejected job for customer {}: job submission is disabled", job.customer_id);
+        return Err(SubmitError::Disabled { flag: feature_flag::JOB_SUBMISSION_DISABLED.to_string() });
+    }
+    
+    if let Ok(job_type) = state.job_type_repo.find_by_id(job.job_type_id).await {
+        let flag = feature_flag::processor_disabled(job_type.processor_type.as_str());
+        if state.feature_flags.is_enabled(&flag, reseller_id).await {
+            warn!("Rejected job for customer {}: {} processors are disabled", job.customer_id, job_type.processor_type.as_str());
+            return Err(SubmitError::Disabled { flag });
+        }
+    }
+    
+    Ok(())
+}
+
+/// Scan a job's input for personal data under its customer's PII policy
+///
+/// Rejects the job, or masks the data in its input, as the policy says; returns what was
+/// found under the `mask` and `flag` policies so it can be recorded in the job's log.
+/// Inputs of customers without a policy are not scanned.
+async fn check_pii(
+    state: &AppState,
+    job: &mut innosystem_common::models::job::Job,
+) -> Result<Option<(PiiPolicy, Vec<PiiFinding>)>, SubmitError> {
+    let customer = state.customer_repo.find_by_id(job.customer_id).await
+        .map_err(|e| {
+            error!("Failed to find customer {} of job: {}", job.customer_id, e);
+            StatusCode::BAD_REQUEST
+        })?;
+    let Some(policy) = customer.pii_policy() else {
+        return Ok(None);
+    };
+    
+    let findings = match policy {
+        PiiPolicy::Mask => pii::mask(&mut job.input_data),
+        PiiPolicy::Reject | PiiPolicy::Flag => pii::scan(&job.input_data),
+    };
+    if findings.is_empty() {
+        return Ok(None);
+    }
+    if policy == PiiPolicy::Reject {
+        warn!("Rejected job for customer {}: {} personal data findings in input", job.customer_id, findings.len());
+        return Err(SubmitError::PiiDetected { findings });
+    }
+    Ok(Some((policy, findings)))
+}
+
+/// Refuse a job while the wallet paying for it is overdrawn under the `suspend` policy
+async fn check_overdraft(
+    state: &AppState,
+    job: &innosystem_common::models::job::Job,
+) -> Result<(), SubmitError> {
+    let wallet = match state.billing_service.find_billing_wallet(job.customer_id).await {
+        Ok(wallet) => wallet,
+        // Jobs without a wallet fail when their funds are reserved instead
+        Err(e) => {
+            warn!("Failed to find the wallet paying for jobs of customer {}: {}", job.customer_id, e);
+            return Ok(());
+        }
+    };
+    
+    if wallet.submissions_suspended() {
+        warn!(
+            "Rejected job for customer {}: wallet {} is overdrawn at {} cents",
+            job.customer_id, wallet.id, wallet.balance_cents
+        );
+        return Err(SubmitError::Overdrawn { balance_cents: wallet.balance_cents });
+    }
+    
+    Ok(())
+}
+
+/// Name of a stored priority level, as responses give priorities
+pub(crate) fn priority_name(level: i32) -> String {
+    PriorityLevel::from_i32(level).as_str().to_string()
+}
+
+/// Find a job by its UUID or its public ID, as given in a path
+pub(crate) async fn find_job(state: &AppState, reference: &str) -> Result<Job, StatusCode> {
+    let job = match Uuid::parse_str(reference) {
+        Ok(id) => state.job_repo.find_by_id(id).await,
+        Err(_) if reference.starts_with(PUBLIC_ID_PREFIX) => state.job_repo.find_by_public_id(reference).await,
+        Err(_) => {
+            tracing::error!("Invalid job ID format: {}", reference);
+            return Err(StatusCode::BAD_REQUEST);
+        }
+    };
+    
+    job.map_err(|e| {
+        tracing::error!("Failed to fetch job {}: {}", reference, e);
+        // If job not found, return 404
+        if e.to_string().contains("not found") {
+            StatusCode::NOT_FOUND
+        } else {
+            StatusCode::INTERNAL_SERVER_ERROR
+        }
+    })
 }
 
 /// Get a job by ID
@@ -161,104 +748,186 @@ pub async fn get_job(
     State(state): State<AppState>,
     Path(job_id_str): Path<String>,
 ) -> Result<Json<JobResponse>, StatusCode> {
-    // Try to parse the job_id as a UUID
-    let job_id = match Uuid::parse_str(&job_id_str) {
-        Ok(id) => id,
-        Err(_) => {
-            tracing::error!("Invalid job ID format: {}", job_id_str);
-            return Err(StatusCode::BAD_REQUEST);
+    let job = find_job(&state, &job_id_str).await?;
+    let job_id = job.id;
+    let usage = match state.job_usage_repo.find_by_job(job_id).await {
+        Ok(usage) => usage.map(|usage| usage.usage()),
+        Err(e) => {
+            warn!("Failed to load the resource usage of job {}: {}", job_id, e);
+            None
         }
     };
     
-    // Fetch the job from the repository
-    let job = state.job_repo.find_by_id(job_id)
-        .await
-        .map_err(|e| {
-            tracing::error!("Failed to fetch job: {}", e);
-            // If job not found, return 404
-            if e.to_string().contains("not found") {
-                StatusCode::NOT_FOUND
-            } else {
-                StatusCode::INTERNAL_SERVER_ERROR
-            }
-        })?;
-    
-    // Convert the timestamps to RFC3339 strings if they exist
-    let created_at = job.created_at.map(|dt| dt.and_utc().to_rfc3339());
-    let updated_at = job.updated_at.map(|dt| dt.and_utc().to_rfc3339()); // Changed to updated_at
-    let completed_at = job.completed_at.map(|dt| dt.and_utc().to_rfc3339());
-    
+    let (queue_wait_ms, processing_ms) = (job.queue_wait_ms(), job.processing_ms());
+
     // Create the response
     let response = JobResponse {
         id: job.id,
+        public_id: job.public_id.clone(),
         customer_id: job.customer_id,
         job_type_id: job.job_type_id,
         status: job.status.as_str().to_string(),
-        priority: job.priority.as_i32(),
+        priority: job.priority.as_str().to_string(),
         input_data: job.input_data,
         output_data: job.output_data,
         error: job.error,
         estimated_cost_cents: job.estimated_cost_cents,
         cost_cents: Some(job.cost_cents), // Now cost_cents is i32, not Option<i32>
-        created_at,
-        started_at: updated_at, // Use updated_at instead of started_at
-        completed_at,
+        created_at: job.created_at,
+        queued_at: job.queued_at,
+        started_at: job.started_at,
+        completed_at: job.completed_at,
+        test_mode: job.test_mode,
+        scheduled_for: job.scheduled_for,
+        claimed_at: job.claimed_at,
+        queue_wait_ms,
+        processing_ms,
+        lease_expires_at: job.lease_expires_at,
+        expires_at: job.expires_at,
+        cancel_requested_at: job.cancel_requested_at,
+        customer_reference: job.customer_reference.clone(),
+        customer_metadata: job.customer_metadata.clone(),
+        parent_id: job.parent_id,
+        sub_task_index: job.sub_task_index,
+        sub_task_count: job.sub_task_count,
+        submission_id: job.submission_id,
+        requeued_from: job.requeued_from,
+        cost_breakdown: job.cost_breakdown,
+        usage,
+        priority_source: None,
     };
     
     tracing::info!("Retrieved job with ID: {}", job_id);
     Ok(Json(response))
 }
 
-/// Get all jobs
+/// List the jobs of the authenticated customer, filtered and paginated
+/// Access: Customer
 #[allow(dead_code)]
 pub async fn get_all_jobs(
     State(state): State<AppState>,
-) -> Result<Json<Vec<JobResponse>>, StatusCode> {
-    // Create default filter and pagination
-    let filter = innosystem_common::repositories::job::JobFilter::default();
-    let sort = Some(innosystem_common::repositories::job::JobSortOrder::CreatedDesc);
-    let pagination = None; // Get all jobs without pagination
-    
-    // Fetch all jobs from the repository using query_jobs
-    let (jobs, _total_count) = state.job_repo.query_jobs(filter, sort, pagination).await
-        .map_err(|e| {
-            tracing::error!("Failed to fetch jobs: {}", e);
-            StatusCode::INTERNAL_SERVER_ERROR
-        })?;
+    Extension(customer): Extension<CustomerUser>,
+    Query(query): Query<ListJobsQuery>,
+) -> Result<(HeaderMap, Json<Paginated<JobResponse>>), StatusCode> {
+    if query.customer_id.is_some_and(|customer_id| customer_id != customer.id) {
+        return Err(StatusCode::FORBIDDEN);
+    }
+    let status = match query.status.as_deref() {
+        Some(status) => Some(JobStatus::from_str(status).ok_or_else(|| {
+            error!("Invalid job status filter: {}", status);
+            StatusCode::BAD_REQUEST
+        })?),
+        None => None,
+    };
+    let filter = JobFilter {
+        public_id: query.public_id.clone(),
+        status,
+        customer_id: Some(customer.id),
+        job_type_id: query.job_type_id,
+        ..Default::default()
+    };
+    let mut headers = HeaderMap::new();
     
-    // Convert the jobs to the response format
-    let job_responses = jobs.into_iter().map(|job| {
-        // Convert the timestamps to RFC3339 strings if they exist
-        let created_at = job.created_at.map(|dt| dt.and_utc().to_rfc3339());
-        let updated_at = job.updated_at.map(|dt| dt.and_utc().to_rfc3339());
-        let completed_at = job.completed_at.map(|dt| dt.and_utc().to_rfc3339());
+    let (jobs, meta) = if query.cursor.is_some() || query.limit.is_some() {
+        // Keyset pagination: stable pages even while new jobs arrive
+        if query.page.is_some() || query.per_page.is_some() || query.sort.is_some() {
+            error!("Job list requested with both keyset and offset pagination");
+            return Err(StatusCode::BAD_REQUEST);
+        }
+        let cursor = match query.cursor.as_deref() {
+            Some(token) => Some(JobCursor::decode(token).ok_or_else(|| {
+                error!("Invalid job cursor: {}", token);
+                StatusCode::BAD_REQUEST
+            })?),
+            None => None,
+        };
+        let limit = query.limit.unwrap_or(50).clamp(1, MAX_PAGE_SIZE);
         
+        let (jobs, next_cursor) = state.job_repo.query_jobs_after(filter, cursor, limit).await
+            .map_err(|e| {
+                tracing::error!("Failed to fetch jobs: {}", e);
+                StatusCode::INTERNAL_SERVER_ERROR
+            })?;
+        
+        let next_cursor = next_cursor.map(|cursor| cursor.encode());
+        if let Some(next_cursor) = next_cursor.as_deref() {
+            // The token is hex encoded, so it is always a valid header value
+            if let Ok(value) = HeaderValue::from_str(next_cursor) {
+                headers.insert(NEXT_CURSOR_HEADER, value);
+            }
+        }
+        (jobs, ApiListMeta::keyset(limit.into(), next_cursor))
+    } else {
+        let sort = match query.sort.as_deref() {
+            Some(sort) => JobSortOrder::parse(sort).ok_or_else(|| {
+                error!("Invalid job sort order: {}", sort);
+                StatusCode::BAD_REQUEST
+            })?,
+            None => JobSortOrder::CreatedDesc,
+        };
+        let pagination = Pagination {
+            page: query.page.unwrap_or(1).max(1) - 1,
+            per_page: query.per_page.unwrap_or(50).clamp(1, MAX_PAGE_SIZE),
+        };
+        let (limit, offset) = (i64::from(pagination.per_page), i64::from(pagination.page) * i64::from(pagination.per_page));
+        
+        let (jobs, total) = state.job_repo.query_jobs(filter, Some(sort), Some(pagination)).await
+            .map_err(|e| {
+                tracing::error!("Failed to fetch jobs: {}", e);
+                StatusCode::INTERNAL_SERVER_ERROR
+            })?;
+        (jobs, ApiListMeta::offset(total as i64, limit, offset))
+    };
+    
+    // Convert the jobs to the response format
+    let job_responses: Vec<JobResponse> = jobs.into_iter().map(|job| {
+        let (queue_wait_ms, processing_ms) = (job.queue_wait_ms(), job.processing_ms());
         JobResponse {
             id: job.id,
+            public_id: job.public_id.clone(),
             customer_id: job.customer_id,
             job_type_id: job.job_type_id,
             status: job.status.as_str().to_string(),
-            priority: job.priority.as_i32(),
+            priority: job.priority.as_str().to_string(),
             input_data: job.input_data,
             output_data: job.output_data,
             error: job.error,
             estimated_cost_cents: job.estimated_cost_cents,
             cost_cents: Some(job.cost_cents),
-            created_at,
-            started_at: updated_at,
-            completed_at,
+            created_at: job.created_at,
+            queued_at: job.queued_at,
+            started_at: job.started_at,
+            completed_at: job.completed_at,
+            test_mode: job.test_mode,
+            scheduled_for: job.scheduled_for,
+            claimed_at: job.claimed_at,
+            queue_wait_ms,
+            processing_ms,
+            lease_expires_at: job.lease_expires_at,
+            expires_at: job.expires_at,
+            cancel_requested_at: job.cancel_requested_at,
+            customer_reference: job.customer_reference.clone(),
+            customer_metadata: job.customer_metadata.clone(),
+            parent_id: job.parent_id,
+            sub_task_index: job.sub_task_index,
+            sub_task_count: job.sub_task_count,
+            submission_id: job.submission_id,
+            requeued_from: job.requeued_from,
+            cost_breakdown: job.cost_breakdown,
+            usage: None,
+            priority_source: None,
         }
     }).collect();
     
-    tracing::info!("Retrieved all jobs from database");
-    Ok(Json(job_responses))
+    tracing::info!("Retrieved {} jobs from database", job_responses.len());
+    Ok((headers, Json(Paginated::new(job_responses, meta))))
 }
 
 /// Calculate the cost of a job
 #[allow(dead_code)]
 pub async fn calculate_job_cost(
     State(state): State<AppState>,
-    Json(payload): Json<CalculateJobCostRequest>,
+    StrictJson(payload): StrictJson<CalculateJobCostRequest>,
 ) -> Result<Json<JobCostResponse>, StatusCode> {
     // Fetch the job to ensure it exists
     let job = state.job_repo.find_by_id(payload.job_id)
@@ -273,7 +942,7 @@ pub async fn calculate_job_cost(
         })?;
     
     // Calculate the cost using the billing service
-    let calculated_cost = state.billing_service.calculate_job_cost(payload.job_id)
+    let calculated_cost = state.billing_service.calculate_job_cost(payload.job_id, true)
         .await
         .map_err(|e| {
             error!("Failed to calculate job cost: {}", e);
@@ -284,18 +953,46 @@ pub async fn calculate_job_cost(
     let response = JobCostResponse {
         job_id: job.id,
         estimated_cost_cents: job.estimated_cost_cents,
-        calculated_cost_cents: calculated_cost,
+        calculated_cost_cents: calculated_cost.total(),
+        cost_breakdown: calculated_cost,
     };
     
-    info!("Calculated cost for job {}: {} cents", job.id, calculated_cost);
+    info!("Calculated cost for job {}: {} cents", job.id, calculated_cost.total());
     Ok(Json(response))
 }
 
+/// Apply the redaction rules of a job's type to its output
+///
+/// Fails rather than risk storing a secret if the rules cannot be read.
+async fn redact_job_output(
+    state: &AppState,
+    job: &innosystem_common::models::job::Job,
+    output: serde_json::Value,
+) -> Result<serde_json::Value, StatusCode> {
+    let job_type = state.job_type_repo.find_by_id(job.job_type_id).await
+        .map_err(|e| {
+            error!("Failed to fetch the redaction rules of job {}: {}", job.id, e);
+            StatusCode::INTERNAL_SERVER_ERROR
+        })?;
+    let redacted = redact_output(&job_type, job.id, output, chrono::Utc::now())
+        .map_err(|e| {
+            error!("Failed to redact the output of job {}: {}", job.id, e);
+            StatusCode::INTERNAL_SERVER_ERROR
+        })?;
+    
+    if let Some(unredacted) = redacted.unredacted {
+        if let Err(e) = state.unredacted_output_repo.store(unredacted).await {
+            warn!("Failed to keep the unredacted output of job {}: {}", job.id, e);
+        }
+    }
+    Ok(redacted.output)
+}
+
 /// Complete a job and process billing
 #[allow(dead_code)]
 pub async fn complete_job(
     State(state): State<AppState>,
-    Json(payload): Json<CompleteJobRequest>,
+    StrictJson(payload): StrictJson<CompleteJobRequest>,
 ) -> Result<Json<JobResponse>, StatusCode> {
     // Fetch the job to ensure it exists and check its current status
     let job = state.job_repo.find_by_id(payload.job_id)
@@ -315,19 +1012,28 @@ pub async fn complete_job(
         return Err(StatusCode::BAD_REQUEST);
     }
     
+    // Redact the output before it is stored, keeping the original if the job type retains it
+    let output_data = match payload.output_data.clone() {
+        Some(output) => Some(redact_job_output(&state, &job, output).await?),
+        None => None,
+    };
+    
     // Process billing for the job
+    let mut job_error = payload.error.clone();
     if let Err(e) = state.billing_service.process_job_billing(payload.job_id, payload.success).await {
         error!("Failed to process billing for job {}: {}", payload.job_id, e);
         // Continue with job completion even if billing fails, but log the error
         warn!("Job {} will be marked as completed but billing failed", payload.job_id);
+        // Surface the billing failure unless the caller already reported an error
+        job_error = job_error.or_else(|| Some(JobError::from_anyhow(&e)));
     }
     
     // Update the job status and other fields
     let updated_job = state.job_repo.set_completed(
         payload.job_id,
         payload.success,
-        payload.output_data.clone(),
-        payload.error.clone(),
+        output_data,
+        job_error,
         job.cost_cents, // Pass current cost_cents as this was updated by the billing service
     )
     .await
@@ -336,28 +1042,180 @@ pub async fn complete_job(
         StatusCode::INTERNAL_SERVER_ERROR
     })?;
     
-    // Convert the timestamps to RFC3339 strings if they exist
-    let created_at = updated_job.created_at.map(|dt| dt.and_utc().to_rfc3339());
-    let updated_at = updated_job.updated_at.map(|dt| dt.and_utc().to_rfc3339());
-    let completed_at = updated_job.completed_at.map(|dt| dt.and_utc().to_rfc3339());
+    // Store the resources the runner reported; losing them must not fail the completion
+    let usage = match payload.usage {
+        Some(usage) => match state.job_usage_repo.record(NewJobUsage::new(&job, None, job.runner_id, usage)).await {
+            Ok(recorded) => Some(recorded.usage()),
+            Err(e) => {
+                warn!("Failed to record the resource usage of job {}: {}", job.id, e);
+                None
+            }
+        },
+        None => None,
+    };
     
+    // Finish the batch job this was the last outstanding sub-task of
+    if let Some(parent_id) = updated_job.parent_id {
+        match state.job_repo.fan_in(parent_id).await {
+            Ok(Some(parent)) => info!("Batch job {} {} with its sub-tasks", parent_id, parent.status.as_str()),
+            Ok(None) => {}
+            Err(e) => warn!("Failed to fan in the sub-tasks of job {}: {}", parent_id, e),
+        }
+    }
+    
+    let (queue_wait_ms, processing_ms) = (updated_job.queue_wait_ms(), updated_job.processing_ms());
+
     // Create the response
     let response = JobResponse {
         id: updated_job.id,
+        public_id: updated_job.public_id.clone(),
         customer_id: updated_job.customer_id,
         job_type_id: updated_job.job_type_id,
         status: updated_job.status.as_str().to_string(),
-        priority: updated_job.priority.as_i32(),
+        priority: updated_job.priority.as_str().to_string(),
         input_data: updated_job.input_data,
         output_data: updated_job.output_data,
         error: updated_job.error,
         estimated_cost_cents: updated_job.estimated_cost_cents,
         cost_cents: Some(updated_job.cost_cents),
-        created_at,
-        started_at: updated_at,
-        completed_at,
+        created_at: updated_job.created_at,
+        queued_at: updated_job.queued_at,
+        started_at: updated_job.started_at,
+        completed_at: updated_job.completed_at,
+        test_mode: updated_job.test_mode,
+        scheduled_for: updated_job.scheduled_for,
+        claimed_at: updated_job.claimed_at,
+        queue_wait_ms,
+        processing_ms,
+        lease_expires_at: updated_job.lease_expires_at,
+        expires_at: updated_job.expires_at,
+        cancel_requested_at: updated_job.cancel_requested_at,
+        customer_reference: updated_job.customer_reference.clone(),
+        customer_metadata: updated_job.customer_metadata.clone(),
+        parent_id: updated_job.parent_id,
+        sub_task_index: updated_job.sub_task_index,
+        sub_task_count: updated_job.sub_task_count,
+        submission_id: updated_job.submission_id,
+        requeued_from: updated_job.requeued_from,
+        cost_breakdown: updated_job.cost_breakdown,
+        usage,
+        priority_source: None,
     };
     
     info!("Job {} completed with status: {}", payload.job_id, if payload.success { "SUCCESS" } else { "FAILURE" });
     Ok(Json(response))
 }
+
+/// Response data for a request to cancel a job
+#[derive(Debug, Serialize)]
+pub struct CancelJobResponse {
+    pub job_id: Uuid,
+    /// `cancelled` with the amount released, or `requested` while the runner stops the job
+    #[serde(flatten)]
+    pub cancellation: JobCancellation,
+}
+
+/// Cancel a job, or have the runner processing it stop it
+///
+/// Jobs that have not started are cancelled right away and what was reserved for them is
+/// released (200). A running job is stopped by its runner, which cuts calls to external
+/// services short, charges the failure fee of failed jobs and reports the job as
+/// cancelled (202). Finished jobs, and batch jobs waiting for their sub-tasks, cannot be
+/// cancelled (409).
+/// Access: Job's Customer
+pub async fn cancel_job(
+    State(state): State<AppState>,
+    Extension(customer): Extension<CustomerUser>,
+    Path(job_ref): Path<String>,
+) -> Result<(StatusCode, Json<CancelJobResponse>), StatusCode> {
+    let job = find_job(&state, &job_ref).await?;
+    if job.customer_id != customer.id {
+        return Err(StatusCode::FORBIDDEN);
+    }
+
+    let response = request_cancellation(&state, job.id).await?;
+    info!("Customer {} cancelled job {}: {:?}", customer.id, job.id, response.1.cancellation);
+    Ok(response)
+}
+
+/// Cancel any customer's job, or have the runner processing it stop it, like the
+/// customer's own `POST /jobs/{id}/cancel`
+/// Access: Admin
+pub async fn admin_cancel_job(
+    State(state): State<AppState>,
+    Extension(admin): Extension<AdminUser>,
+    Path(job_ref): Path<String>,
+) -> Result<(StatusCode, Json<CancelJobResponse>), StatusCode> {
+    let job = find_job(&state, &job_ref).await?;
+
+    let response = request_cancellation(&state, job.id).await?;
+    let entry = NewAuditEntry::new(admin.id.clone(), "job.cancelled", "job", job.id, json!(response.1.cancellation));
+    if let Err(e) = state.audit_log_repo.record(entry).await {
+        error!("Failed to record the cancellation of job {} in the audit log: {}", job.id, e);
+    }
+
+    info!("Admin {} cancelled job {}: {:?}", admin.id, job.id, response.1.cancellation);
+    Ok(response)
+}
+
+/// Request data for requeueing a job
+#[derive(Debug, Default, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct RequeueJobRequest {
+    /// Input fields replacing those of the original job, e.g. a corrected `webhook_url`;
+    /// `null` removes a field
+    #[serde(default)]
+    pub input_overrides: serde_json::Map<String, serde_json::Value>,
+}
+
+/// Requeue a failed or expired job as a new job referring back to it
+///
+/// The new job runs with the original's input, patched by `input_overrides`, and is
+/// reserved for and charged on its own; what the original attempt was charged stays as
+/// it is. Jobs in any other state, and sub-tasks of batch jobs, cannot be requeued (409).
+/// Access: Admin
+pub async fn admin_requeue_job(
+    State(state): State<AppState>,
+    Extension(admin): Extension<AdminUser>,
+    Path(job_ref): Path<String>,
+    payload: Option<StrictJson<RequeueJobRequest>>,
+) -> Result<(StatusCode, Json<JobResponse>), SubmitError> {
+    let payload = payload.map(|StrictJson(payload)| payload).unwrap_or_default();
+    let job = find_job(&state, &job_ref).await?;
+    if !job.can_requeue() {
+        error!("Refusing to requeue job {} in status {}", job.id, job.status.as_str());
+        return Err(StatusCode::CONFLICT.into());
+    }
+
+    let requeued = job.requeue(&payload.input_overrides, 1000); // $10.00 default estimated cost, as for submitted jobs
+    let response = submit_job(&state, requeued).await?;
+
+    let entry = NewAuditEntry::new(admin.id.clone(), "job.requeued", "job", job.id, json!({
+        "requeued_as": response.id,
+        "input_overrides": payload.input_overrides.keys().collect::<Vec<_>>(),
+    }));
+    if let Err(e) = state.audit_log_repo.record(entry).await {
+        error!("Failed to record the requeue of job {} in the audit log: {}", job.id, e);
+    }
+
+    info!("Admin {} requeued job {} as {}", admin.id, job.id, response.id);
+    Ok((StatusCode::CREATED, Json(response)))
+}
+
+async fn request_cancellation(state: &AppState, job_id: Uuid) -> Result<(StatusCode, Json<CancelJobResponse>), StatusCode> {
+    let cancellation = state.billing_service.request_cancellation(job_id).await
+        .map_err(|e| {
+            error!("Failed to cancel job {}: {}", job_id, e);
+            match e.downcast_ref::<Error>() {
+                Some(Error::InvalidInput(_)) => StatusCode::CONFLICT,
+                Some(Error::NotFound(_)) => StatusCode::NOT_FOUND,
+                _ => StatusCode::INTERNAL_SERVER_ERROR,
+            }
+        })?;
+
+    let status = match cancellation {
+        JobCancellation::Cancelled { .. } => StatusCode::OK,
+        JobCancellation::Requested { .. } => StatusCode::ACCEPTED,
+    };
+    Ok((status, Json(CancelJobResponse { job_id, cancellation })))
+}
@@ -0,0 +1,68 @@
+use axum::{extract::{Extension, State}, http::StatusCode, Json};
+use serde::Serialize;
+use uuid::Uuid;
+use tracing::{error, info, warn};
+
+use innosystem_common::database::with_reseller;
+use innosystem_common::models::ledger::AccountBalance;
+use crate::middleware::auth::AdminUser;
+use crate::state::AppState;
+
+/// Balance of a ledger account, with the isolated reseller whose schema keeps it
+#[derive(Debug, Serialize)]
+pub struct AccountBalanceResponse {
+    /// Isolated reseller whose schema keeps the account; none for the shared schema
+    pub schema_reseller_id: Option<Uuid>,
+    #[serde(flatten)]
+    pub balance: AccountBalance,
+}
+
+/// Response data for the trial balance of the ledger
+#[derive(Debug, Serialize)]
+pub struct TrialBalanceResponse {
+    pub accounts: Vec<AccountBalanceResponse>,
+    /// Sum of all balances; zero unless an entry was posted without its counterpart
+    pub total_cents: i64,
+    pub balanced: bool,
+}
+
+/// Trial balance of the ledger: the balance of every account in the shared schema and
+/// in every reseller schema
+///
+/// Every wallet transaction posts entries adding up to zero, so the balances do too.
+/// Access: Admin
+pub async fn get_trial_balance(
+    State(state): State<AppState>,
+    Extension(admin): Extension<AdminUser>,
+) -> Result<Json<TrialBalanceResponse>, StatusCode> {
+    let internal_error = |e: anyhow::Error| {
+        error!("Failed to compute the trial balance of the ledger: {}", e);
+        StatusCode::INTERNAL_SERVER_ERROR
+    };
+
+    let resolver = state.schema_resolver.clone();
+    let scopes = tokio::task::spawn_blocking(move || resolver.scopes()).await
+        .map_err(|e| internal_error(e.into()))?
+        .map_err(|e| internal_error(e.into()))?;
+    let mut accounts = Vec::new();
+    for scope in scopes {
+        let balances = with_reseller(scope, state.ledger_repo.balances()).await
+            .map_err(internal_error)?;
+        accounts.extend(balances.into_iter().map(|balance| AccountBalanceResponse {
+            schema_reseller_id: scope,
+            balance,
+        }));
+    }
+
+    let total_cents = accounts.iter().map(|account| account.balance.balance_cents).sum::<i64>();
+    if total_cents != 0 {
+        warn!("Ledger accounts do not balance: they add up to {} cents", total_cents);
+    }
+    info!("Admin {} computed the trial balance of {} ledger accounts", admin.id, accounts.len());
+
+    Ok(Json(TrialBalanceResponse {
+        accounts,
+        total_cents,
+        balanced: total_cents == 0,
+    }))
+}
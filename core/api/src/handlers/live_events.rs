@@ -0,0 +1,77 @@
+use axum::{
+    extract::{ws::{Message, WebSocket, WebSocketUpgrade}, Query, State},
+    http::StatusCode,
+    response::Response,
+};
+use serde::Deserialize;
+use serde_json::json;
+use tokio::sync::broadcast::error::RecvError;
+use tracing::{debug, error, info, warn};
+
+use crate::services::live_events::LiveEventFilter;
+use crate::state::AppState;
+
+/// Query parameters for following the live tail
+#[derive(Debug, Deserialize)]
+pub struct LiveTailQuery {
+    /// Comma-separated event types: `job_lifecycle`, `runner_health`, `queue_depth`
+    /// and/or `auth_failure` (optional, defaults to all)
+    pub types: Option<String>,
+    /// Only events concerning this entity: a job ID or public ID, customer, job type or
+    /// runner ID, source IP or API key prefix (optional)
+    pub entity: Option<String>,
+}
+
+/// Follow system events as they happen over a WebSocket, for an ops console during
+/// incidents
+///
+/// Each event is sent as a JSON text message with its `type`, `event` (e.g.
+/// `job.failed` or `runner.critical`), the `entities` it concerns, its `data` and
+/// `occurred_at`. Clients too slow to keep up are sent an `events_dropped` message with
+/// the number of events they missed. Unknown event types are refused (400).
+/// Access: Admin
+pub async fn live_tail(
+    State(state): State<AppState>,
+    Query(query): Query<LiveTailQuery>,
+    ws: WebSocketUpgrade,
+) -> Result<Response, StatusCode> {
+    let filter = LiveEventFilter::parse(query.types.as_deref(), query.entity)
+        .map_err(|reason| {
+            error!("Refusing live tail: {}", reason);
+            StatusCode::BAD_REQUEST
+        })?;
+
+    Ok(ws.on_upgrade(move |socket| stream_events(state, socket, filter)))
+}
+
+/// Send the events matching the filter until the client goes away
+async fn stream_events(state: AppState, mut socket: WebSocket, filter: LiveEventFilter) {
+    let mut events = state.live_events.subscribe();
+    info!("Admin started following the live tail ({:?})", filter);
+
+    loop {
+        let message = tokio::select! {
+            event = events.recv() => match event {
+                Ok(event) if filter.matches(&event) => json!(event),
+                Ok(_) => continue,
+                Err(RecvError::Lagged(missed)) => {
+                    warn!("Live tail client fell behind by {} events", missed);
+                    json!({ "type": "events_dropped", "missed": missed })
+                }
+                Err(RecvError::Closed) => break,
+            },
+            incoming = socket.recv() => match incoming {
+                // Pings are answered by axum; anything else from the client is ignored
+                Some(Ok(Message::Close(_))) | Some(Err(_)) | None => break,
+                Some(Ok(_)) => continue,
+            },
+        };
+
+        if let Err(e) = socket.send(Message::Text(message.to_string().into())).await {
+            debug!("Live tail client went away: {}", e);
+            break;
+        }
+    }
+
+    info!("Admin stopped following the live tail");
+}
@@ -0,0 +1,228 @@
+use axum::{extract::{Path, State, Extension}, http::StatusCode, Json};
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use uuid::Uuid;
+use tracing::{error, info};
+
+use innosystem_common::models::load_window::{LoadWindow, NewLoadWindow};
+use crate::middleware::auth::AdminUser;
+use crate::request::StrictJson;
+use crate::state::AppState;
+
+/// Request data for declaring a load window
+#[derive(Debug, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct LoadWindowRequest {
+    pub name: String,
+    /// Name the runners of the pool register under; omit for the whole fleet
+    pub pool: Option<String>,
+    /// Job types whose scheduled jobs wait for the pool to be ready; omit to only warm it up
+    #[serde(default)]
+    pub job_type_ids: Vec<Uuid>,
+    /// Active runners the pool needs before the window's jobs run
+    pub target_runners: i32,
+    pub starts_at: DateTime<Utc>,
+    pub ends_at: DateTime<Utc>,
+    /// Minutes before the start autoscalers are told to scale up (defaults to 15)
+    #[serde(default = "default_warm_up_minutes")]
+    pub warm_up_minutes: i32,
+    /// Minutes after the start jobs wait for the pool at most (defaults to 30)
+    #[serde(default = "default_max_hold_minutes")]
+    pub max_hold_minutes: i32,
+}
+
+fn default_warm_up_minutes() -> i32 {
+    15
+}
+
+fn default_max_hold_minutes() -> i32 {
+    30
+}
+
+impl LoadWindowRequest {
+    /// Check the settings are usable
+    fn validate(&self) -> Result<(), String> {
+        if self.name.trim().is_empty() {
+            return Err("Load window name must not be empty".to_string());
+        }
+        if self.pool.as_deref().is_some_and(|pool| pool.trim().is_empty()) {
+            return Err("Pool name must not be empty".to_string());
+        }
+        if self.target_runners < 1 {
+            return Err(format!("Target runners must be positive, got {}", self.target_runners));
+        }
+        if self.ends_at <= self.starts_at {
+            return Err("Load window must start before it ends".to_string());
+        }
+        if self.ends_at <= Utc::now() {
+            return Err("Load window must end in the future".to_string());
+        }
+        if self.warm_up_minutes < 0 || self.max_hold_minutes < 0 {
+            return Err("Warm-up and maximum hold must not be negative".to_string());
+        }
+        Ok(())
+    }
+}
+
+/// Response data for a load window
+#[derive(Debug, Serialize)]
+pub struct LoadWindowResponse {
+    pub id: Uuid,
+    pub name: String,
+    pub pool: Option<String>,
+    pub job_type_ids: Vec<Uuid>,
+    pub target_runners: i32,
+    pub starts_at: DateTime<Utc>,
+    pub ends_at: DateTime<Utc>,
+    pub warm_up_minutes: i32,
+    pub max_hold_minutes: i32,
+    /// planned, warming, ready, released or cancelled
+    pub status: String,
+    /// Active runners of the pool at the last check
+    pub active_runners: Option<i32>,
+    pub notified_at: Option<DateTime<Utc>>,
+    pub ready_at: Option<DateTime<Utc>>,
+    pub released_at: Option<DateTime<Utc>>,
+    pub created_by: String,
+}
+
+impl From<LoadWindow> for LoadWindowResponse {
+    fn from(window: LoadWindow) -> Self {
+        Self {
+            id: window.id,
+            name: window.name,
+            pool: window.pool,
+            job_type_ids: window.job_type_ids,
+            target_runners: window.target_runners,
+            starts_at: window.starts_at,
+            ends_at: window.ends_at,
+            warm_up_minutes: window.warm_up_minutes,
+            max_hold_minutes: window.max_hold_minutes,
+            status: window.status,
+            active_runners: window.active_runners,
+            notified_at: window.notified_at,
+            ready_at: window.ready_at,
+            released_at: window.released_at,
+            created_by: window.created_by,
+        }
+    }
+}
+
+/// List all load windows, latest start first
+/// Access: Admin
+pub async fn list_load_windows(
+    State(state): State<AppState>,
+    Extension(_admin): Extension<AdminUser>,
+) -> Result<Json<Vec<LoadWindowResponse>>, StatusCode> {
+    let windows = state.load_window_repo.list_all().await
+        .map_err(|e| {
+            error!("Failed to list load windows: {}", e);
+            StatusCode::INTERNAL_SERVER_ERROR
+        })?;
+
+    Ok(Json(windows.into_iter().map(LoadWindowResponse::from).collect()))
+}
+
+/// Declare an upcoming load window, e.g. a nightly batch, to scale its runner pool up
+/// ahead of time
+/// Access: Admin
+pub async fn create_load_window(
+    State(state): State<AppState>,
+    Extension(admin): Extension<AdminUser>,
+    StrictJson(payload): StrictJson<LoadWindowRequest>,
+) -> Result<(StatusCode, Json<LoadWindowResponse>), StatusCode> {
+    payload.validate()
+        .map_err(|reason| {
+            error!("{}", reason);
+            StatusCode::BAD_REQUEST
+        })?;
+    for &job_type_id in &payload.job_type_ids {
+        if state.job_type_repo.find_by_id(job_type_id).await.is_err() {
+            error!("Job type not found with ID: {}", job_type_id);
+            return Err(StatusCode::BAD_REQUEST);
+        }
+    }
+
+    let mut job_type_ids = payload.job_type_ids;
+    job_type_ids.sort();
+    job_type_ids.dedup();
+    let window = state.load_window_repo.create(NewLoadWindow {
+        id: Uuid::new_v4(),
+        name: payload.name,
+        pool: payload.pool,
+        job_type_ids,
+        target_runners: payload.target_runners,
+        starts_at: payload.starts_at,
+        ends_at: payload.ends_at,
+        warm_up_minutes: payload.warm_up_minutes,
+        max_hold_minutes: payload.max_hold_minutes,
+        created_by: admin.id.clone(),
+    }).await
+        .map_err(|e| {
+            error!("Failed to create load window: {}", e);
+            StatusCode::INTERNAL_SERVER_ERROR
+        })?;
+
+    info!("Admin {} declared load window {} starting at {}", admin.id, window.name, window.starts_at);
+    Ok((StatusCode::CREATED, Json(window.into())))
+}
+
+/// Get a load window by ID
+/// Access: Admin
+pub async fn get_load_window(
+    State(state): State<AppState>,
+    Extension(_admin): Extension<AdminUser>,
+    Path(id): Path<Uuid>,
+) -> Result<Json<LoadWindowResponse>, StatusCode> {
+    let window = state.load_window_repo.find_by_id(id).await
+        .map_err(|e| {
+            error!("Failed to fetch load window {}: {}", id, e);
+            StatusCode::NOT_FOUND
+        })?;
+
+    Ok(Json(window.into()))
+}
+
+/// Cancel a load window that is not released yet; jobs it held run at their next due time
+/// Access: Admin
+pub async fn cancel_load_window(
+    State(state): State<AppState>,
+    Extension(admin): Extension<AdminUser>,
+    Path(id): Path<Uuid>,
+) -> Result<Json<LoadWindowResponse>, StatusCode> {
+    let window = state.load_window_repo.find_by_id(id).await
+        .map_err(|e| {
+            error!("Failed to fetch load window {}: {}", id, e);
+            StatusCode::NOT_FOUND
+        })?;
+    if !window.window_status().is_open() {
+        error!("Load window {} is already {}", id, window.status);
+        return Err(StatusCode::CONFLICT);
+    }
+
+    let window = state.load_window_repo.cancel(id).await
+        .map_err(|e| {
+            error!("Failed to cancel load window {}: {}", id, e);
+            StatusCode::CONFLICT
+        })?;
+
+    info!("Admin {} cancelled load window {}", admin.id, window.name);
+    Ok(Json(window.into()))
+}
+
+/// Check every open load window now, instead of waiting for the next periodic check,
+/// and return the windows as checked
+/// Access: Admin
+pub async fn check_load_windows(
+    State(state): State<AppState>,
+    Extension(admin): Extension<AdminUser>,
+) -> Result<Json<Vec<LoadWindowResponse>>, StatusCode> {
+    let windows = state.capacity_planning.check_all().await
+        .map_err(|e| {
+            error!("Failed to check the load windows: {}", e);
+            StatusCode::INTERNAL_SERVER_ERROR
+        })?;
+
+    info!("Admin {} checked {} load windows", admin.id, windows.len());
+    Ok(Json(windows.into_iter().map(LoadWindowResponse::from).collect()))
+}
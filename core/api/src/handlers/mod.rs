@@ -6,4 +6,48 @@ pub mod resellers;
 pub mod projects;
 pub mod runners;
 pub mod wallet;
-pub mod runner_health;
\ No newline at end of file
+pub mod runner_health;
+pub mod runner_environments;
+pub mod webhooks;
+pub mod notifications;
+pub mod job_logs;
+pub mod autoscaling;
+pub mod alerting;
+pub mod job_templates;
+pub mod queue_stats;
+pub mod catalog;
+pub mod sub_accounts;
+
+pub mod submission_windows;
+pub mod repository_metrics;
+pub mod pool_metrics;
+pub mod tenancy;
+pub mod pipelines;
+pub mod wallet_adjustments;
+pub mod billing_periods;
+pub mod cluster;
+pub mod feature_flags;
+pub mod job_attempts;
+pub mod job_sub_tasks;
+pub mod unredacted_outputs;
+pub mod spending_alerts;
+pub mod config_apply;
+pub mod config_reload;
+pub mod partitions;
+pub mod providers;
+pub mod search;
+pub mod job_imports;
+pub mod security_events;
+pub mod fixtures;
+pub mod billing_exports;
+pub mod job_usage;
+pub mod load_windows;
+pub mod submissions;
+pub mod live_events;
+pub mod sandboxes;
+pub mod dependencies;
+pub mod replay_corpus;
+pub mod reseller_invoices;
+pub mod purge;
+pub mod job_artifacts;
+pub mod ledger;
@@ -0,0 +1,253 @@
+use axum::{extract::{Path, State, Extension}, http::StatusCode, Json};
+use serde::{Deserialize, Serialize};
+use uuid::Uuid;
+use tracing::{error, info};
+use chrono::{DateTime, Utc};
+
+use crate::request::StrictJson;
+use crate::state::AppState;
+use crate::middleware::auth::CustomerUser;
+use innosystem_common::models::notification::{self, DeliveryChannel, DeliveryStatus, NewNotificationPreference, NotificationDelivery, NotificationMode};
+use innosystem_common::models::webhook::WebhookEventType;
+
+/// Maximum number of deliveries returned by a single listing
+const MAX_PAGE_SIZE: i64 = 100;
+
+/// Response data for a notification delivery
+#[derive(Debug, Serialize)]
+pub struct NotificationDeliveryResponse {
+    pub id: Uuid,
+    pub customer_id: Uuid,
+    /// Target webhook endpoint, if the delivery went to a webhook
+    pub webhook_id: Option<Uuid>,
+    /// Delivery channel ("webhook" or "email")
+    pub channel: String,
+    pub event_type: String,
+    /// Delivery status ("pending", "succeeded" or "failed")
+    pub status: String,
+    /// HTTP status code of the last attempt, if a response was received
+    pub status_code: Option<i32>,
+    /// Latency of the last attempt in milliseconds
+    pub latency_ms: Option<i32>,
+    pub last_error: Option<String>,
+    pub retry_count: i32,
+    /// When the next automatic retry is scheduled, if any
+    pub next_retry_at: Option<DateTime<Utc>>,
+    pub created_at: Option<DateTime<Utc>>,
+    pub updated_at: Option<DateTime<Utc>>,
+}
+
+impl From<NotificationDelivery> for NotificationDeliveryResponse {
+    fn from(delivery: NotificationDelivery) -> Self {
+        Self {
+            id: delivery.id,
+            customer_id: delivery.customer_id,
+            webhook_id: delivery.webhook_id,
+            channel: delivery.channel,
+            event_type: delivery.event_type,
+            status: delivery.status,
+            status_code: delivery.status_code,
+            latency_ms: delivery.latency_ms,
+            last_error: delivery.last_error,
+            retry_count: delivery.retry_count,
+            next_retry_at: delivery.next_retry_at,
+            created_at: delivery.created_at,
+            updated_at: delivery.updated_at,
+        }
+    }
+}
+
+/// Load the failed deliveries of a customer as a response page
+async fn list_failures(state: &AppState, customer_id: Uuid, limit_str: &str, offset_str: &str) -> Result<Vec<NotificationDeliveryResponse>, StatusCode> {
+    let limit = limit_str.parse::<i64>().unwrap_or(10).clamp(1, MAX_PAGE_SIZE);
+    let offset = offset_str.parse::<i64>().unwrap_or(0).max(0);
+
+    let deliveries = state.notification_delivery_repo.find_failed_by_customer(customer_id, limit, offset)
+        .await
+        .map_err(|e| {
+            error!("Failed to list failed deliveries for customer {}: {}", customer_id, e);
+            StatusCode::INTERNAL_SERVER_ERROR
+        })?;
+
+    Ok(deliveries.into_iter().map(NotificationDeliveryResponse::from).collect())
+}
+
+/// List the failed notification deliveries of the authenticated customer
+/// Access: Customer
+pub async fn list_failed_deliveries(
+    State(state): State<AppState>,
+    Extension(customer): Extension<CustomerUser>,
+    Path((limit_str, offset_str)): Path<(String, String)>,
+) -> Result<Json<Vec<NotificationDeliveryResponse>>, StatusCode> {
+    Ok(Json(list_failures(&state, customer.id, &limit_str, &offset_str).await?))
+}
+
+/// List the failed notification deliveries of any customer
+/// Access: Admin
+pub async fn list_customer_failed_deliveries(
+    State(state): State<AppState>,
+    Path((customer_id, limit_str, offset_str)): Path<(Uuid, String, String)>,
+) -> Result<Json<Vec<NotificationDeliveryResponse>>, StatusCode> {
+    Ok(Json(list_failures(&state, customer_id, &limit_str, &offset_str).await?))
+}
+
+/// Manually retry a failed notification delivery with its original payload
+/// Access: Delivery's Customer
+pub async fn retry_delivery(
+    State(state): State<AppState>,
+    Extension(customer): Extension<CustomerUser>,
+    Path(id): Path<Uuid>,
+) -> Result<Json<NotificationDeliveryResponse>, StatusCode> {
+    let delivery = state.notification_delivery_repo.find_by_id(id)
+        .await
+        .map_err(|e| {
+            error!("Failed to find notification delivery {}: {}", id, e);
+            StatusCode::NOT_FOUND
+        })?;
+
+    if delivery.customer_id != customer.id {
+        return Err(StatusCode::FORBIDDEN);
+    }
+
+    if delivery.status() != Some(DeliveryStatus::Failed) {
+        error!("Notification delivery {} is not in a failed state", id);
+        return Err(StatusCode::CONFLICT);
+    }
+
+    let (delivery, _) = state.webhook_service.retry_delivery(id)
+        .await
+        .map_err(|e| {
+            error!("Failed to retry notification delivery {}: {}", id, e);
+            if e.to_string().contains("not found") {
+                StatusCode::NOT_FOUND
+            } else {
+                StatusCode::INTERNAL_SERVER_ERROR
+            }
+        })?;
+
+    info!("Manually retried notification delivery {} (status: {})", id, delivery.status);
+
+    Ok(Json(NotificationDeliveryResponse::from(delivery)))
+}
+
+/// A customer's notification preference for one event type on one channel
+#[derive(Debug, Serialize)]
+pub struct NotificationPreferenceResponse {
+    pub event_type: String,
+    /// Delivery channel ("webhook" or "email")
+    pub channel: String,
+    /// How the event is sent ("immediate", "digest" or "off")
+    pub mode: NotificationMode,
+    /// Whether the customer chose the mode, rather than getting the default
+    pub customized: bool,
+}
+
+/// Request data for setting a notification preference
+#[derive(Debug, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct SetNotificationPreferenceRequest {
+    pub mode: NotificationMode,
+}
+
+/// Validate the event type and channel of a preference
+fn parse_preference_key(event_type: &str, channel: &str) -> Result<(WebhookEventType, DeliveryChannel), StatusCode> {
+    let event_type = WebhookEventType::parse(event_type)
+        .filter(WebhookEventType::is_subscribable)
+        .ok_or_else(|| {
+            error!("Invalid notification event type: {}", event_type);
+            StatusCode::BAD_REQUEST
+        })?;
+    let channel = DeliveryChannel::parse(channel)
+        .ok_or_else(|| {
+            error!("Invalid notification channel: {}", channel);
+            StatusCode::BAD_REQUEST
+        })?;
+    Ok((event_type, channel))
+}
+
+/// List how the authenticated customer hears about every event type on every channel,
+/// whether they chose it or not
+///
+/// Emails are only sent when the platform has an email relay configured.
+/// Access: Customer
+pub async fn list_preferences(
+    State(state): State<AppState>,
+    Extension(customer): Extension<CustomerUser>,
+) -> Result<Json<Vec<NotificationPreferenceResponse>>, StatusCode> {
+    let preferences = state.notification_preference_repo.find_by_customer(customer.id)
+        .await
+        .map_err(|e| {
+            error!("Failed to list notification preferences for customer {}: {}", customer.id, e);
+            StatusCode::INTERNAL_SERVER_ERROR
+        })?;
+
+    let mut response = Vec::new();
+    for event_type in WebhookEventType::SUBSCRIBABLE {
+        for channel in [DeliveryChannel::Webhook, DeliveryChannel::Email] {
+            response.push(NotificationPreferenceResponse {
+                event_type: event_type.as_str().to_string(),
+                channel: channel.as_str().to_string(),
+                mode: notification::mode_for(&preferences, event_type, channel),
+                customized: preferences.iter()
+                    .any(|preference| preference.event_type == event_type.as_str() && preference.channel == channel.as_str()),
+            });
+        }
+    }
+
+    Ok(Json(response))
+}
+
+/// Choose how the authenticated customer hears about an event type on a channel
+/// Access: Customer
+pub async fn set_preference(
+    State(state): State<AppState>,
+    Extension(customer): Extension<CustomerUser>,
+    Path((event_type, channel)): Path<(String, String)>,
+    StrictJson(payload): StrictJson<SetNotificationPreferenceRequest>,
+) -> Result<Json<NotificationPreferenceResponse>, StatusCode> {
+    let (event_type, channel) = parse_preference_key(&event_type, &channel)?;
+
+    let preference = state.notification_preference_repo
+        .upsert(NewNotificationPreference::new(customer.id, event_type, channel, payload.mode))
+        .await
+        .map_err(|e| {
+            error!("Failed to set notification preference for customer {}: {}", customer.id, e);
+            StatusCode::INTERNAL_SERVER_ERROR
+        })?;
+
+    info!(
+        "Customer {} now receives {} by {}: {}",
+        customer.id, preference.event_type, preference.channel, preference.mode
+    );
+
+    Ok(Json(NotificationPreferenceResponse {
+        event_type: preference.event_type,
+        channel: preference.channel,
+        mode: payload.mode,
+        customized: true,
+    }))
+}
+
+/// Go back to the default for an event type on a channel: sent right away
+/// Access: Customer
+pub async fn delete_preference(
+    State(state): State<AppState>,
+    Extension(customer): Extension<CustomerUser>,
+    Path((event_type, channel)): Path<(String, String)>,
+) -> Result<StatusCode, StatusCode> {
+    let (event_type, channel) = parse_preference_key(&event_type, &channel)?;
+
+    let deleted = state.notification_preference_repo.delete(customer.id, event_type, channel)
+        .await
+        .map_err(|e| {
+            error!("Failed to delete notification preference for customer {}: {}", customer.id, e);
+            StatusCode::INTERNAL_SERVER_ERROR
+        })?;
+
+    if !deleted {
+        return Err(StatusCode::NOT_FOUND);
+    }
+
+    info!("Customer {} reset their preference for {} by {}", customer.id, event_type.as_str(), channel.as_str());
+    Ok(StatusCode::NO_CONTENT)
+}
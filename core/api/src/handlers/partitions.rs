@@ -0,0 +1,63 @@
+use axum::{extract::{State, Extension}, http::StatusCode, Json};
+use chrono::NaiveDate;
+use serde::Serialize;
+use tracing::{error, info};
+
+use innosystem_common::models::partition::PartitionedTable;
+use crate::middleware::auth::AdminUser;
+use crate::services::partitions::PartitionMaintenance;
+use crate::state::AppState;
+
+/// Response data for the monthly partitions of a table
+#[derive(Debug, Serialize)]
+pub struct PartitionedTableResponse {
+    pub table: PartitionedTable,
+    /// Full months rows are kept after their month ends; None keeps them forever
+    pub retention_months: Option<u32>,
+    /// First days of the months that have a partition, oldest first
+    pub months: Vec<NaiveDate>,
+}
+
+/// List the monthly partitions of jobs and wallet transactions
+/// Access: Admin
+pub async fn list_partitions(
+    State(state): State<AppState>,
+    Extension(_admin): Extension<AdminUser>,
+) -> Result<Json<Vec<PartitionedTableResponse>>, StatusCode> {
+    let mut tables = Vec::new();
+    for table in PartitionedTable::ALL {
+        let months = state.partition_repo.list_months(table).await
+            .map_err(|e| {
+                error!("Failed to list the partitions of {}: {}", table.as_str(), e);
+                StatusCode::INTERNAL_SERVER_ERROR
+            })?;
+        tables.push(PartitionedTableResponse {
+            table,
+            retention_months: state.config.partitions.retention_months(table),
+            months,
+        });
+    }
+
+    Ok(Json(tables))
+}
+
+/// Create the partitions of the coming months and drop those past retention now,
+/// instead of waiting for the next maintenance run
+/// Access: Admin
+pub async fn maintain_partitions(
+    State(state): State<AppState>,
+    Extension(admin): Extension<AdminUser>,
+) -> Result<Json<PartitionMaintenance>, StatusCode> {
+    let maintenance = state.partition_service.maintain().await
+        .map_err(|e| {
+            error!("Failed to maintain the monthly partitions: {}", e);
+            StatusCode::INTERNAL_SERVER_ERROR
+        })?;
+
+    info!(
+        "Admin {} ran partition maintenance: {} partitions created, {} dropped",
+        admin.id, maintenance.created.len(), maintenance.dropped.len()
+    );
+
+    Ok(Json(maintenance))
+}
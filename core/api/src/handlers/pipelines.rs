@@ -0,0 +1,537 @@
+use std::collections::HashMap;
+use chrono::{DateTime, Utc};
+
+use axum::{extract::{Path, State, Extension}, http::StatusCode, Json};
+use serde::{Deserialize, Serialize};
+use serde_json::{Map, Value};
+use uuid::Uuid;
+use tracing::{debug, error, info, warn};
+
+use crate::handlers::jobs::submit_job;
+use crate::middleware::auth::CustomerUser;
+use crate::request::{self, StrictJson};
+use crate::services::backpressure::SaturationPolicy;
+use crate::state::AppState;
+use innosystem_common::models::job::{Job, JobStatus, PriorityLevel};
+use innosystem_common::models::job_type::JobType;
+use innosystem_common::models::pipeline::{
+    FailurePolicy, NewPipeline, NewPipelineRun, Pipeline, PipelineDefinition, PipelineRun, PipelineRunStatus,
+    PipelineRunStep, StepStatus,
+};
+
+/// Request data for starting a pipeline run
+#[derive(Debug, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct RunPipelineRequest {
+    /// Run input, referenced by steps as `{{ input.<path> }}`
+    #[serde(default = "default_input")]
+    pub input: Value,
+    /// Priority of the run, 0 (low) to 3 (critical) or its name (optional, defaults to
+    /// medium); its steps' jobs get theirs from it according to the pipeline's priority policy
+    #[serde(default = "default_priority", deserialize_with = "request::priority")]
+    pub priority: PriorityLevel,
+}
+
+/// Default (empty) run input
+fn default_input() -> Value {
+    Value::Object(Map::new())
+}
+
+/// Default priority function
+fn default_priority() -> PriorityLevel {
+    PriorityLevel::Medium
+}
+
+/// Response data for a pipeline
+#[derive(Debug, Serialize)]
+pub struct PipelineResponse {
+    pub id: Uuid,
+    pub customer_id: Uuid,
+    pub name: String,
+    pub description: Option<String>,
+    /// YAML definition as uploaded
+    pub definition: String,
+    pub created_at: Option<DateTime<Utc>>,
+    pub updated_at: Option<DateTime<Utc>>,
+}
+
+impl From<Pipeline> for PipelineResponse {
+    fn from(pipeline: Pipeline) -> Self {
+        Self {
+            id: pipeline.id,
+            customer_id: pipeline.customer_id,
+            name: pipeline.name,
+            description: pipeline.description,
+            definition: pipeline.definition,
+            created_at: pipeline.created_at,
+            updated_at: pipeline.updated_at,
+        }
+    }
+}
+
+/// Response data for a step of a pipeline run
+#[derive(Debug, Serialize)]
+pub struct PipelineRunStepResponse {
+    pub name: String,
+    pub job_type: String,
+    pub depends_on: Vec<String>,
+    pub on_failure: FailurePolicy,
+    /// Priority of the step's job under the pipeline's priority policy
+    pub priority: String,
+    /// `pending`, `running`, `succeeded`, `failed` or `skipped`
+    pub status: String,
+    /// Job submitted for the step, once it started
+    pub job_id: Option<Uuid>,
+    pub updated_at: Option<DateTime<Utc>>,
+}
+
+/// Response data for a pipeline run
+#[derive(Debug, Serialize)]
+pub struct PipelineRunResponse {
+    pub id: Uuid,
+    pub pipeline_id: Uuid,
+    /// `running`, `succeeded` or `failed`
+    pub status: String,
+    pub input: Value,
+    pub priority: String,
+    pub test_mode: bool,
+    /// Steps in the order of the definition
+    pub steps: Vec<PipelineRunStepResponse>,
+    pub created_at: Option<DateTime<Utc>>,
+    pub completed_at: Option<DateTime<Utc>>,
+}
+
+impl PipelineRunResponse {
+    fn new(run: PipelineRun, steps: Vec<PipelineRunStep>) -> Self {
+        let mut steps: HashMap<String, PipelineRunStep> = steps.into_iter()
+            .map(|step| (step.step_name.clone(), step))
+            .collect();
+        let definition = run.definition().ok();
+        let run_priority = PriorityLevel::from_i32(run.priority);
+        let priorities: HashMap<String, PriorityLevel> = definition.iter()
+            .flat_map(|definition| definition.steps.iter()
+                .map(|step| (step.name.clone(), definition.step_priority(&step.name, run_priority.clone()))))
+            .collect();
+
+        Self {
+            id: run.id,
+            pipeline_id: run.pipeline_id,
+            status: run.status,
+            input: run.input,
+            priority: run_priority.as_str().to_string(),
+            test_mode: run.test_mode,
+            steps: definition.map(|definition| definition.steps).unwrap_or_default().into_iter()
+                .map(|step| {
+                    let state = steps.remove(&step.name);
+                    PipelineRunStepResponse {
+                        status: state.as_ref().map(|s| s.status.clone()).unwrap_or_else(|| StepStatus::Pending.as_str().to_string()),
+                        job_id: state.as_ref().and_then(|s| s.job_id),
+                        updated_at: state.and_then(|s| s.updated_at),
+                        priority: priorities.get(&step.name).unwrap_or(&run_priority).as_str().to_string(),
+                        name: step.name,
+                        job_type: step.job_type,
+                        depends_on: step.depends_on,
+                        on_failure: step.on_failure,
+                    }
+                })
+                .collect(),
+            created_at: run.created_at,
+            completed_at: run.completed_at,
+        }
+    }
+}
+
+/// Load a pipeline and verify it belongs to the authenticated customer
+async fn find_owned_pipeline(state: &AppState, customer: &CustomerUser, id: Uuid) -> Result<Pipeline, StatusCode> {
+    let pipeline = state.pipeline_repo.find_by_id(id).await
+        .map_err(|e| {
+            error!("Failed to find pipeline {}: {}", id, e);
+            StatusCode::NOT_FOUND
+        })?;
+
+    if pipeline.customer_id != customer.id {
+        return Err(StatusCode::FORBIDDEN);
+    }
+
+    Ok(pipeline)
+}
+
+/// Find the enabled job type a step refers to by ID or by name
+async fn resolve_job_type(state: &AppState, reference: &str) -> anyhow::Result<JobType> {
+    let job_type = match Uuid::parse_str(reference) {
+        Ok(id) => state.job_type_repo.find_by_id(id).await?,
+        Err(_) => state.job_type_repo.list_all().await?
+            .into_iter()
+            .find(|job_type| job_type.name == reference)
+            .ok_or_else(|| anyhow::anyhow!("Job type not found: {}", reference))?,
+    };
+
+    if !job_type.enabled {
+        anyhow::bail!("Job type {} is disabled", job_type.name);
+    }
+    Ok(job_type)
+}
+
+/// Parse an uploaded YAML definition and check the job types of its steps
+async fn parse_definition(state: &AppState, yaml: &str) -> Result<PipelineDefinition, StatusCode> {
+    let definition = PipelineDefinition::parse(yaml)
+        .map_err(|e| {
+            error!("Invalid pipeline definition: {}", e);
+            StatusCode::BAD_REQUEST
+        })?;
+
+    for step in &definition.steps {
+        resolve_job_type(state, &step.job_type).await
+            .map_err(|e| {
+                error!("Invalid job type of pipeline step {}: {}", step.name, e);
+                StatusCode::BAD_REQUEST
+            })?;
+    }
+
+    Ok(definition)
+}
+
+/// Fail with a conflict if the customer already has another pipeline with the name
+async fn check_name_available(state: &AppState, customer: &CustomerUser, name: &str, id: Option<Uuid>) -> Result<(), StatusCode> {
+    let existing = state.pipeline_repo.find_by_customer_id(customer.id).await
+        .map_err(|e| {
+            error!("Failed to list pipelines for customer {}: {}", customer.id, e);
+            StatusCode::INTERNAL_SERVER_ERROR
+        })?;
+    if existing.iter().any(|pipeline| pipeline.name == name && Some(pipeline.id) != id) {
+        error!("Pipeline {} already exists for customer {}", name, customer.id);
+        return Err(StatusCode::CONFLICT);
+    }
+
+    Ok(())
+}
+
+/// Load a run with its steps for a response
+async fn run_response(state: &AppState, run: PipelineRun) -> Result<PipelineRunResponse, StatusCode> {
+    let steps = state.pipeline_repo.find_run_steps(run.id).await
+        .map_err(|e| {
+            error!("Failed to load the steps of pipeline run {}: {}", run.id, e);
+            StatusCode::INTERNAL_SERVER_ERROR
+        })?;
+
+    Ok(PipelineRunResponse::new(run, steps))
+}
+
+/// Upload a pipeline definition; the request body is the YAML document
+/// Access: Customer
+pub async fn create_pipeline(
+    State(state): State<AppState>,
+    Extension(customer): Extension<CustomerUser>,
+    yaml: String,
+) -> Result<(StatusCode, Json<PipelineResponse>), StatusCode> {
+    let definition = parse_definition(&state, &yaml).await?;
+    check_name_available(&state, &customer, &definition.name, None).await?;
+
+    let new_pipeline = NewPipeline {
+        id: Uuid::new_v4(),
+        customer_id: customer.id,
+        name: definition.name,
+        description: definition.description,
+        definition: yaml,
+    };
+
+    let pipeline = state.pipeline_repo.create(new_pipeline).await
+        .map_err(|e| {
+            error!("Failed to create pipeline: {}", e);
+            StatusCode::INTERNAL_SERVER_ERROR
+        })?;
+
+    info!("Created pipeline {} for customer {}", pipeline.id, customer.id);
+
+    Ok((StatusCode::CREATED, Json(pipeline.into())))
+}
+
+/// List the pipelines of the authenticated customer
+/// Access: Customer
+pub async fn list_pipelines(
+    State(state): State<AppState>,
+    Extension(customer): Extension<CustomerUser>,
+) -> Result<Json<Vec<PipelineResponse>>, StatusCode> {
+    let pipelines = state.pipeline_repo.find_by_customer_id(customer.id).await
+        .map_err(|e| {
+            error!("Failed to list pipelines for customer {}: {}", customer.id, e);
+            StatusCode::INTERNAL_SERVER_ERROR
+        })?;
+
+    Ok(Json(pipelines.into_iter().map(PipelineResponse::from).collect()))
+}
+
+/// Get a pipeline by ID
+/// Access: Pipeline's Customer
+pub async fn get_pipeline(
+    State(state): State<AppState>,
+    Extension(customer): Extension<CustomerUser>,
+    Path(id): Path<Uuid>,
+) -> Result<Json<PipelineResponse>, StatusCode> {
+    let pipeline = find_owned_pipeline(&state, &customer, id).await?;
+    Ok(Json(pipeline.into()))
+}
+
+/// Replace the definition of a pipeline with the YAML document in the request body;
+/// runs already started keep following the definition they started with
+/// Access: Pipeline's Customer
+pub async fn update_pipeline(
+    State(state): State<AppState>,
+    Extension(customer): Extension<CustomerUser>,
+    Path(id): Path<Uuid>,
+    yaml: String,
+) -> Result<Json<PipelineResponse>, StatusCode> {
+    find_owned_pipeline(&state, &customer, id).await?;
+    let definition = parse_definition(&state, &yaml).await?;
+    check_name_available(&state, &customer, &definition.name, Some(id)).await?;
+
+    let pipeline = state.pipeline_repo.update_definition(id, definition.name, definition.description, yaml).await
+        .map_err(|e| {
+            error!("Failed to update pipeline {}: {}", id, e);
+            StatusCode::INTERNAL_SERVER_ERROR
+        })?;
+
+    info!("Updated pipeline: {}", id);
+
+    Ok(Json(pipeline.into()))
+}
+
+/// Delete a pipeline and its run history; jobs already submitted keep running
+/// Access: Pipeline's Customer
+pub async fn delete_pipeline(
+    State(state): State<AppState>,
+    Extension(customer): Extension<CustomerUser>,
+    Path(id): Path<Uuid>,
+) -> Result<StatusCode, StatusCode> {
+    find_owned_pipeline(&state, &customer, id).await?;
+
+    state.pipeline_repo.delete(id).await
+        .map_err(|e| {
+            error!("Failed to delete pipeline {}: {}", id, e);
+            StatusCode::INTERNAL_SERVER_ERROR
+        })?;
+
+    info!("Deleted pipeline: {}", id);
+
+    Ok(StatusCode::NO_CONTENT)
+}
+
+/// Start a run of a pipeline: the steps without dependencies are submitted right
+/// away, the others as their dependencies finish
+/// Access: Pipeline's Customer
+pub async fn run_pipeline(
+    State(state): State<AppState>,
+    Extension(customer): Extension<CustomerUser>,
+    Path(id): Path<Uuid>,
+    StrictJson(request): StrictJson<RunPipelineRequest>,
+) -> Result<(StatusCode, Json<PipelineRunResponse>), StatusCode> {
+    let pipeline = find_owned_pipeline(&state, &customer, id).await?;
+    let definition = pipeline.definition()
+        .map_err(|e| {
+            error!("Stored definition of pipeline {} is invalid: {}", id, e);
+            StatusCode::INTERNAL_SERVER_ERROR
+        })?;
+
+    let new_run = NewPipelineRun {
+        id: Uuid::new_v4(),
+        pipeline_id: pipeline.id,
+        customer_id: customer.id,
+        definition: pipeline.definition,
+        input: request.input,
+        priority: request.priority.as_i32(),
+        // Jobs submitted with a sandbox key run through the stub processor
+        test_mode: customer.test_mode,
+    };
+    let step_names = definition.steps.iter().map(|step| step.name.clone()).collect();
+
+    let run = state.pipeline_repo.create_run(new_run, step_names).await
+        .map_err(|e| {
+            error!("Failed to create a run of pipeline {}: {}", id, e);
+            StatusCode::INTERNAL_SERVER_ERROR
+        })?;
+    info!("Started run {} of pipeline {}", run.id, id);
+
+    let run_id = run.id;
+    if let Err(e) = advance_run(&state, &run).await {
+        error!("Failed to start pipeline run {}: {}", run_id, e);
+    }
+
+    let run = state.pipeline_repo.find_run_by_id(run_id).await
+        .map_err(|e| {
+            error!("Failed to find pipeline run {}: {}", run_id, e);
+            StatusCode::INTERNAL_SERVER_ERROR
+        })?;
+    let response = run_response(&state, run).await?;
+
+    Ok((StatusCode::CREATED, Json(response)))
+}
+
+/// List the runs of a pipeline, newest first
+/// Access: Pipeline's Customer
+pub async fn list_pipeline_runs(
+    State(state): State<AppState>,
+    Extension(customer): Extension<CustomerUser>,
+    Path(id): Path<Uuid>,
+) -> Result<Json<Vec<PipelineRunResponse>>, StatusCode> {
+    find_owned_pipeline(&state, &customer, id).await?;
+
+    let runs = state.pipeline_repo.find_runs_by_pipeline_id(id).await
+        .map_err(|e| {
+            error!("Failed to list the runs of pipeline {}: {}", id, e);
+            StatusCode::INTERNAL_SERVER_ERROR
+        })?;
+
+    let mut responses = Vec::with_capacity(runs.len());
+    for run in runs {
+        responses.push(run_response(&state, run).await?);
+    }
+
+    Ok(Json(responses))
+}
+
+/// Get a pipeline run with the status and job of each step
+/// Access: Pipeline's Customer
+pub async fn get_pipeline_run(
+    State(state): State<AppState>,
+    Extension(customer): Extension<CustomerUser>,
+    Path((id, run_id)): Path<(Uuid, Uuid)>,
+) -> Result<Json<PipelineRunResponse>, StatusCode> {
+    find_owned_pipeline(&state, &customer, id).await?;
+
+    let run = state.pipeline_repo.find_run_by_id(run_id).await
+        .map_err(|e| {
+            error!("Failed to find pipeline run {}: {}", run_id, e);
+            StatusCode::NOT_FOUND
+        })?;
+    if run.pipeline_id != id {
+        return Err(StatusCode::NOT_FOUND);
+    }
+
+    Ok(Json(run_response(&state, run).await?))
+}
+
+/// Move all running pipeline runs forward: record the outcome of finished step jobs,
+/// submit the steps whose dependencies are done and finish completed runs
+///
+/// Returns the number of runs that finished.
+pub(crate) async fn advance_runs(state: &AppState) -> anyhow::Result<usize> {
+    let runs = state.pipeline_repo.find_running().await?;
+
+    let mut finished = 0;
+    for run in runs {
+        match advance_run(state, &run).await {
+            Ok(Some(_)) => finished += 1,
+            Ok(None) => {}
+            Err(e) => error!("Failed to advance pipeline run {}: {}", run.id, e),
+        }
+    }
+
+    Ok(finished)
+}
+
+/// Move a single run forward; returns its final status once it finished
+async fn advance_run(state: &AppState, run: &PipelineRun) -> anyhow::Result<Option<PipelineRunStatus>> {
+    let definition = match run.definition() {
+        Ok(definition) => definition,
+        Err(e) => {
+            warn!("Pipeline run {} has an invalid definition: {}", run.id, e);
+            state.pipeline_repo.finish_run(run.id, PipelineRunStatus::Failed).await?;
+            return Ok(Some(PipelineRunStatus::Failed));
+        }
+    };
+
+    // Record the outcome of step jobs that finished since the last pass
+    let mut statuses = HashMap::new();
+    let mut outputs = HashMap::new();
+    for step in state.pipeline_repo.find_run_steps(run.id).await? {
+        let mut status = step.status().unwrap_or(StepStatus::Pending);
+        if let (StepStatus::Running | StepStatus::Succeeded, Some(job_id)) = (status, step.job_id) {
+            let job = state.job_repo.find_by_id(job_id).await?;
+            let finished = match job.status {
+                JobStatus::Succeeded => Some(StepStatus::Succeeded),
+                JobStatus::Failed | JobStatus::Cancelled | JobStatus::Expired => Some(StepStatus::Failed),
+                _ => None,
+            };
+            if let Some(finished) = finished.filter(|finished| *finished != status) {
+                state.pipeline_repo.update_step(run.id, step.step_name.clone(), finished, Some(job_id)).await?;
+                info!("Step {} of pipeline run {} {}", step.step_name, run.id, finished.as_str());
+                status = finished;
+            }
+            if status == StepStatus::Succeeded {
+                outputs.insert(step.step_name.clone(), job.output_data.unwrap_or(Value::Null));
+            }
+        }
+        statuses.insert(step.step_name, status);
+    }
+
+    let progress = definition.progress(&statuses);
+    for name in progress.skip {
+        state.pipeline_repo.claim_step(run.id, name, StepStatus::Skipped, None).await?;
+    }
+    for name in progress.start {
+        let Some(step) = definition.step(&name) else { continue };
+        let input_data = step.render_input(&run.input, &outputs);
+        let priority = definition.step_priority(&name, PriorityLevel::from_i32(run.priority));
+
+        // Failure policies apply on the next pass
+        let job = match step_job(state, run, &step.job_type, input_data, priority).await {
+            Ok(job) => job,
+            Err(e) => {
+                warn!("Cannot start step {} of pipeline run {}: {}", name, run.id, e);
+                state.pipeline_repo.claim_step(run.id, name, StepStatus::Failed, None).await?;
+                continue;
+            }
+        };
+
+        // Steps wait for a saturated queue to drain instead of failing
+        if state.backpressure_service.policy() == SaturationPolicy::Reject
+            && state.backpressure_service.is_saturated(&job.priority, job.job_type_id).await
+        {
+            debug!("Holding back step {} of pipeline run {} while its queue is saturated", name, run.id);
+            continue;
+        }
+
+        // Another API instance may have started the step in the meantime
+        let job_id = job.id;
+        if !state.pipeline_repo.claim_step(run.id, name.clone(), StepStatus::Running, Some(job_id)).await? {
+            continue;
+        }
+        match submit_job(state, job).await {
+            Ok(_) => info!("Submitted job {} for step {} of pipeline run {}", job_id, name, run.id),
+            Err(e) => {
+                warn!("Failed to submit step {} of pipeline run {}: {}", name, run.id, e);
+                state.pipeline_repo.update_step(run.id, name, StepStatus::Failed, None).await?;
+            }
+        }
+    }
+
+    if let Some(outcome) = progress.outcome {
+        state.pipeline_repo.finish_run(run.id, outcome).await?;
+        info!("Pipeline run {} {}", run.id, outcome.as_str());
+    }
+
+    Ok(progress.outcome)
+}
+
+/// Build the job of a step on behalf of the run's customer, so it is billed like the
+/// run: to the same wallet, against the same sub-account budget and in the same mode
+async fn step_job(
+    state: &AppState,
+    run: &PipelineRun,
+    job_type: &str,
+    input_data: Value,
+    priority: PriorityLevel,
+) -> anyhow::Result<Job> {
+    let job_type = resolve_job_type(state, job_type).await?;
+
+    let mut job = Job::new(
+        run.customer_id,
+        job_type.id,
+        input_data,
+        priority,
+        1000, // $10.00 default estimated cost, as for directly submitted jobs
+    );
+    job.test_mode = run.test_mode;
+
+    Ok(job)
+}
@@ -0,0 +1,24 @@
+use axum::{extract::State, http::header, response::IntoResponse, Json};
+
+use crate::services::pool_metrics::PoolMetricsReport;
+use crate::state::AppState;
+
+/// Get the utilization of the database and Redis connection pools and the time
+/// requests waited for a connection
+/// Access: Admin
+pub async fn get_pool_stats(
+    State(state): State<AppState>,
+) -> Json<PoolMetricsReport> {
+    Json(PoolMetricsReport::collect(&state))
+}
+
+/// Get connection pool utilization and wait times as Prometheus metrics
+/// Access: Admin
+pub async fn get_pool_metrics(
+    State(state): State<AppState>,
+) -> impl IntoResponse {
+    (
+        [(header::CONTENT_TYPE, "text/plain; version=0.0.4")],
+        PoolMetricsReport::collect(&state).render_metrics(),
+    )
+}
@@ -1,14 +1,18 @@
-use axum::{extract::{Path, State, Extension}, http::StatusCode, Json};
+use axum::{extract::{Path, Query, State, Extension}, http::StatusCode, Json};
 use serde::{Deserialize, Serialize};
 use uuid::Uuid;
 use tracing::{error, info};
+use chrono::{DateTime, Utc};
 
+use crate::handlers::dependencies::{DependencyError, ForceQuery};
+use crate::request::StrictJson;
 use crate::state::AppState;
 use innosystem_common::models::project::NewProject;
 use crate::middleware::auth::{AdminUser, CustomerUser};
 
 /// Request data for creating a new project
 #[derive(Debug, Deserialize)]
+#[serde(deny_unknown_fields)]
 pub struct CreateProjectRequest {
     pub name: String,
     pub description: Option<String>,
@@ -21,8 +25,8 @@ pub struct ProjectResponse {
     pub customer_id: Uuid,
     pub name: String,
     pub description: Option<String>,
-    pub created_at: Option<String>,
-    pub updated_at: Option<String>,
+    pub created_at: Option<DateTime<Utc>>,
+    pub updated_at: Option<DateTime<Utc>>,
 }
 
 /// Create a new project for a customer
@@ -30,7 +34,7 @@ pub struct ProjectResponse {
 pub async fn create_project(
     State(state): State<AppState>,
     Extension(customer): Extension<CustomerUser>,
-    Json(request): Json<CreateProjectRequest>,
+    StrictJson(request): StrictJson<CreateProjectRequest>,
 ) -> Result<(StatusCode, Json<ProjectResponse>), StatusCode> {
     // Create a new project for the customer
     let new_project = NewProject {
@@ -54,8 +58,8 @@ pub async fn create_project(
         customer_id: project.customer_id,
         name: project.name.clone(),
         description: project.description.clone(),
-        created_at: project.created_at.map(|dt| dt.and_utc().to_rfc3339()),
-        updated_at: project.updated_at.map(|dt| dt.and_utc().to_rfc3339()),
+        created_at: project.created_at,
+        updated_at: project.updated_at,
     })))
 }
 
@@ -87,8 +91,8 @@ pub async fn get_project(
         customer_id: project.customer_id,
         name: project.name.clone(),
         description: project.description.clone(),
-        created_at: project.created_at.map(|dt| dt.and_utc().to_rfc3339()),
-        updated_at: project.updated_at.map(|dt| dt.and_utc().to_rfc3339()),
+        created_at: project.created_at,
+        updated_at: project.updated_at,
     }))
 }
 
@@ -98,7 +102,7 @@ pub async fn update_project(
     State(state): State<AppState>,
     Extension(customer): Extension<CustomerUser>,
     Path(id): Path<Uuid>,
-    Json(request): Json<CreateProjectRequest>,
+    StrictJson(request): StrictJson<CreateProjectRequest>,
 ) -> Result<Json<ProjectResponse>, StatusCode> {
     // First retrieve the project
     let mut project = state.project_repo.find_by_id(id).await
@@ -134,18 +138,22 @@ pub async fn update_project(
         customer_id: updated_project.customer_id,
         name: updated_project.name.clone(),
         description: updated_project.description.clone(),
-        created_at: updated_project.created_at.map(|dt| dt.and_utc().to_rfc3339()),
-        updated_at: updated_project.updated_at.map(|dt| dt.and_utc().to_rfc3339()),
+        created_at: updated_project.created_at,
+        updated_at: updated_project.updated_at,
     }))
 }
 
 /// Delete a project
+///
+/// A project with jobs is not deleted (409 listing them) unless `?force=true` is given;
+/// forced, its jobs are kept with their results and charges, no longer in a project.
 /// Access: Project's Customer or Admin
 pub async fn delete_project(
     State(state): State<AppState>,
     Extension(customer): Extension<CustomerUser>,
     Path(id): Path<Uuid>,
-) -> Result<StatusCode, StatusCode> {
+    Query(query): Query<ForceQuery>,
+) -> Result<StatusCode, DependencyError> {
     // First retrieve the project to check ownership
     let project = state.project_repo.find_by_id(id).await
         .map_err(|e| {
@@ -157,18 +165,29 @@ pub async fn delete_project(
     if project.customer_id != customer.id {
         // Check if the customer is associated with a reseller
         if customer.reseller_id.is_none() {
-            return Err(StatusCode::FORBIDDEN);
+            return Err(StatusCode::FORBIDDEN.into());
         }
     }
     
-    // Delete the project
+    // Refuse to delete a project with jobs unless forced
+    let blockers = state.dependencies.project_blockers(id).await
+        .map_err(|e| {
+            error!("Failed to check the dependents of project {}: {}", id, e);
+            StatusCode::INTERNAL_SERVER_ERROR
+        })?;
+    if !blockers.is_empty() && !query.force {
+        info!("Refused to delete project {}: {} blockers", id, blockers.len());
+        return Err(DependencyError::Blocked(blockers));
+    }
+    
+    // Delete the project; its jobs are detached by the database
     state.project_repo.delete(id).await
         .map_err(|e| {
             error!("Failed to delete project {}: {}", id, e);
             StatusCode::INTERNAL_SERVER_ERROR
         })?;
     
-    info!("Deleted project: {}", id);
+    info!("Deleted project: {} (forced: {})", id, query.force && !blockers.is_empty());
     
     // Return success status
     Ok(StatusCode::NO_CONTENT)
@@ -194,8 +213,8 @@ pub async fn list_customer_projects(
             customer_id: project.customer_id,
             name: project.name.clone(),
             description: project.description.clone(),
-            created_at: project.created_at.map(|dt| dt.and_utc().to_rfc3339()),
-            updated_at: project.updated_at.map(|dt| dt.and_utc().to_rfc3339()),
+            created_at: project.created_at,
+            updated_at: project.updated_at,
         })
         .collect();
     
@@ -223,8 +242,8 @@ pub async fn list_all_projects(
             customer_id: project.customer_id,
             name: project.name.clone(),
             description: project.description.clone(),
-            created_at: project.created_at.map(|dt| dt.and_utc().to_rfc3339()),
-            updated_at: project.updated_at.map(|dt| dt.and_utc().to_rfc3339()),
+            created_at: project.created_at,
+            updated_at: project.updated_at,
         })
         .collect();
     
@@ -0,0 +1,240 @@
+use axum::{extract::{Path, State, Extension}, http::StatusCode, Json};
+use serde::{Deserialize, Serialize};
+use uuid::Uuid;
+use tracing::{error, info};
+use chrono::{DateTime, Utc};
+
+use innosystem_common::models::provider::{NewProvider, Provider};
+use crate::middleware::auth::AdminUser;
+use crate::request::StrictJson;
+use crate::state::AppState;
+
+/// Request data for registering a provider or replacing its settings
+#[derive(Debug, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct ProviderRequest {
+    pub name: String,
+    /// URL probed with GET on every check (http or https); omit to rely on the failure rate alone
+    pub ping_url: Option<String>,
+    /// Share of failed jobs, from 0 to 1, from which the provider is down (defaults to 0.5)
+    #[serde(default = "default_failure_rate_threshold")]
+    pub failure_rate_threshold: f64,
+    /// Finished jobs needed before the failure rate counts (defaults to 10)
+    #[serde(default = "default_min_jobs")]
+    pub min_jobs: i32,
+    /// Seconds new jobs are held back while the provider is down (defaults to 300)
+    #[serde(default = "default_retry_delay_secs")]
+    pub retry_delay_secs: i32,
+}
+
+fn default_failure_rate_threshold() -> f64 {
+    0.5
+}
+
+fn default_min_jobs() -> i32 {
+    10
+}
+
+fn default_retry_delay_secs() -> i32 {
+    300
+}
+
+impl ProviderRequest {
+    /// Check the settings are usable
+    fn validate(&self) -> Result<(), String> {
+        if self.name.trim().is_empty() {
+            return Err("Provider name must not be empty".to_string());
+        }
+        if let Some(url) = &self.ping_url {
+            if !(url.starts_with("https://") || url.starts_with("http://")) {
+                return Err(format!("Invalid ping URL: {}", url));
+            }
+        }
+        if !(self.failure_rate_threshold > 0.0 && self.failure_rate_threshold <= 1.0) {
+            return Err(format!("Failure rate threshold must be above 0 and at most 1, got {}", self.failure_rate_threshold));
+        }
+        if self.min_jobs < 1 || self.retry_delay_secs < 1 {
+            return Err("Minimum jobs and retry delay must be positive".to_string());
+        }
+        Ok(())
+    }
+}
+
+/// Response data for a provider
+#[derive(Debug, Serialize)]
+pub struct ProviderResponse {
+    pub id: Uuid,
+    pub name: String,
+    pub ping_url: Option<String>,
+    pub failure_rate_threshold: f64,
+    pub min_jobs: i32,
+    pub retry_delay_secs: i32,
+    /// up or down
+    pub status: String,
+    /// Why the provider is down
+    pub status_reason: Option<String>,
+    pub last_checked_at: Option<DateTime<Utc>>,
+    pub status_changed_at: Option<DateTime<Utc>>,
+}
+
+impl From<Provider> for ProviderResponse {
+    fn from(provider: Provider) -> Self {
+        Self {
+            id: provider.id,
+            name: provider.name,
+            ping_url: provider.ping_url,
+            failure_rate_threshold: provider.failure_rate_threshold,
+            min_jobs: provider.min_jobs,
+            retry_delay_secs: provider.retry_delay_secs,
+            status: provider.status,
+            status_reason: provider.status_reason,
+            last_checked_at: provider.last_checked_at,
+            status_changed_at: provider.status_changed_at,
+        }
+    }
+}
+
+/// Fail with 409 Conflict if another provider already has the name
+async fn check_name_available(state: &AppState, name: &str, id: Option<Uuid>) -> Result<(), StatusCode> {
+    let providers = state.provider_repo.list_all().await
+        .map_err(|e| {
+            error!("Failed to list providers: {}", e);
+            StatusCode::INTERNAL_SERVER_ERROR
+        })?;
+    if providers.iter().any(|provider| provider.name == name && Some(provider.id) != id) {
+        error!("Provider {} already exists", name);
+        return Err(StatusCode::CONFLICT);
+    }
+    Ok(())
+}
+
+/// List all providers with their last assessed health
+/// Access: Admin
+pub async fn list_providers(
+    State(state): State<AppState>,
+    Extension(_admin): Extension<AdminUser>,
+) -> Result<Json<Vec<ProviderResponse>>, StatusCode> {
+    let providers = state.provider_repo.list_all().await
+        .map_err(|e| {
+            error!("Failed to list providers: {}", e);
+            StatusCode::INTERNAL_SERVER_ERROR
+        })?;
+
+    Ok(Json(providers.into_iter().map(ProviderResponse::from).collect()))
+}
+
+/// Register an external provider job types can depend on
+/// Access: Admin
+pub async fn create_provider(
+    State(state): State<AppState>,
+    Extension(admin): Extension<AdminUser>,
+    StrictJson(payload): StrictJson<ProviderRequest>,
+) -> Result<(StatusCode, Json<ProviderResponse>), StatusCode> {
+    payload.validate()
+        .map_err(|reason| {
+            error!("{}", reason);
+            StatusCode::BAD_REQUEST
+        })?;
+    check_name_available(&state, &payload.name, None).await?;
+
+    let provider = state.provider_repo.create(NewProvider {
+        id: Uuid::new_v4(),
+        name: payload.name,
+        ping_url: payload.ping_url,
+        failure_rate_threshold: payload.failure_rate_threshold,
+        min_jobs: payload.min_jobs,
+        retry_delay_secs: payload.retry_delay_secs,
+    }).await
+        .map_err(|e| {
+            error!("Failed to create provider: {}", e);
+            StatusCode::INTERNAL_SERVER_ERROR
+        })?;
+
+    info!("Admin {} registered provider {}", admin.id, provider.name);
+    Ok((StatusCode::CREATED, Json(provider.into())))
+}
+
+/// Get a provider by ID
+/// Access: Admin
+pub async fn get_provider(
+    State(state): State<AppState>,
+    Extension(_admin): Extension<AdminUser>,
+    Path(id): Path<Uuid>,
+) -> Result<Json<ProviderResponse>, StatusCode> {
+    let provider = state.provider_repo.find_by_id(id).await
+        .map_err(|e| {
+            error!("Failed to fetch provider {}: {}", id, e);
+            StatusCode::NOT_FOUND
+        })?;
+
+    Ok(Json(provider.into()))
+}
+
+/// Replace the settings of a provider; its status changes on the next check
+/// Access: Admin
+pub async fn update_provider(
+    State(state): State<AppState>,
+    Extension(admin): Extension<AdminUser>,
+    Path(id): Path<Uuid>,
+    StrictJson(payload): StrictJson<ProviderRequest>,
+) -> Result<Json<ProviderResponse>, StatusCode> {
+    payload.validate()
+        .map_err(|reason| {
+            error!("{}", reason);
+            StatusCode::BAD_REQUEST
+        })?;
+    let mut provider = state.provider_repo.find_by_id(id).await
+        .map_err(|e| {
+            error!("Failed to fetch provider {}: {}", id, e);
+            StatusCode::NOT_FOUND
+        })?;
+    check_name_available(&state, &payload.name, Some(id)).await?;
+
+    provider.name = payload.name;
+    provider.ping_url = payload.ping_url;
+    provider.failure_rate_threshold = payload.failure_rate_threshold;
+    provider.min_jobs = payload.min_jobs;
+    provider.retry_delay_secs = payload.retry_delay_secs;
+    let provider = state.provider_repo.update(provider).await
+        .map_err(|e| {
+            error!("Failed to update provider {}: {}", id, e);
+            StatusCode::INTERNAL_SERVER_ERROR
+        })?;
+
+    info!("Admin {} updated provider {}", admin.id, provider.name);
+    Ok(Json(provider.into()))
+}
+
+/// Delete a provider; its job types no longer depend on a provider
+/// Access: Admin
+pub async fn delete_provider(
+    State(state): State<AppState>,
+    Extension(admin): Extension<AdminUser>,
+    Path(id): Path<Uuid>,
+) -> Result<StatusCode, StatusCode> {
+    state.provider_repo.delete(id).await
+        .map_err(|e| {
+            error!("Failed to delete provider {}: {}", id, e);
+            StatusCode::NOT_FOUND
+        })?;
+
+    info!("Admin {} deleted provider {}", admin.id, id);
+    Ok(StatusCode::NO_CONTENT)
+}
+
+/// Check the health of every provider now, instead of waiting for the next periodic
+/// check, and return the providers as checked
+/// Access: Admin
+pub async fn check_providers(
+    State(state): State<AppState>,
+    Extension(admin): Extension<AdminUser>,
+) -> Result<Json<Vec<ProviderResponse>>, StatusCode> {
+    let providers = state.provider_health.check_all().await
+        .map_err(|e| {
+            error!("Failed to check the health of the providers: {}", e);
+            StatusCode::INTERNAL_SERVER_ERROR
+        })?;
+
+    info!("Admin {} checked the health of {} providers", admin.id, providers.len());
+    Ok(Json(providers.into_iter().map(ProviderResponse::from).collect()))
+}
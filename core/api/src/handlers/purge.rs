@@ -0,0 +1,85 @@
+use axum::{extract::{State, Extension}, http::StatusCode, Json};
+use chrono::Utc;
+use serde::Deserialize;
+use serde_json::json;
+use tracing::{error, info};
+
+use innosystem_common::models::audit::NewAuditEntry;
+use innosystem_common::models::purge::{job_retention_cutoff, PurgeReport};
+use innosystem_common::purge::Purger;
+use crate::middleware::auth::AdminUser;
+use crate::request::StrictJson;
+use crate::state::AppState;
+
+/// Rows deleted per transaction unless the request says otherwise
+const DEFAULT_BATCH_SIZE: i64 = 1000;
+
+/// Request data for purging the data past its retention
+#[derive(Debug, Default, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct PurgeRequest {
+    /// Rows deleted per transaction (optional, defaults to 1000)
+    pub batch_size: Option<i64>,
+    /// Only count what would be deleted
+    #[serde(default)]
+    pub dry_run: bool,
+}
+
+/// Permanently delete the data past its retention from every schema
+///
+/// Finished jobs are purged once past the job retention of the monthly partitions
+/// (`PARTITION_JOB_RETENTION_MONTHS`, kept forever when unset) with their logs,
+/// attempts, unredacted outputs, usage and artifacts; unredacted outputs once their
+/// own retention ended. Rows are deleted in batches, one transaction each, and counted
+/// again afterwards: the report lists the rows removed and any left per table.
+/// Access: Admin
+pub async fn purge_expired_data(
+    State(state): State<AppState>,
+    Extension(admin): Extension<AdminUser>,
+    payload: Option<StrictJson<PurgeRequest>>,
+) -> Result<Json<PurgeReport>, StatusCode> {
+    let payload = payload.map(|StrictJson(payload)| payload).unwrap_or_default();
+    let batch_size = payload.batch_size.unwrap_or(DEFAULT_BATCH_SIZE);
+    if batch_size <= 0 {
+        error!("Invalid purge batch size: {}", batch_size);
+        return Err(StatusCode::BAD_REQUEST);
+    }
+
+    let resolver = state.schema_resolver.clone();
+    let scopes = tokio::task::spawn_blocking(move || resolver.scopes()).await
+        .map_err(anyhow::Error::from)
+        .and_then(|scopes| scopes.map_err(anyhow::Error::from))
+        .map_err(|e| {
+            error!("Failed to list reseller schemas: {}", e);
+            StatusCode::INTERNAL_SERVER_ERROR
+        })?;
+    let jobs_created_before = state.config.partitions.job_retention_months
+        .map(|months| job_retention_cutoff(Utc::now(), months));
+
+    let purger = Purger::new(state.purge_repo.clone(), batch_size);
+    let report = purger.run(&scopes, jobs_created_before, payload.dry_run, |progress| {
+        info!("Purge batch {} deleted {:?} past retention from {:?}", progress.batch, progress.deleted, progress.reseller_id);
+    }).await
+        .map_err(|e| {
+            error!("Failed to purge the data past retention: {}", e);
+            StatusCode::INTERNAL_SERVER_ERROR
+        })?;
+
+    if !report.dry_run {
+        let entry = NewAuditEntry::new(admin.id.clone(), "data.purged", "purge", report.id, json!({
+            "jobs_created_before": report.jobs_created_before,
+            "deleted": report.deleted,
+            "remaining": report.remaining,
+            "verified": report.verified,
+        }));
+        if let Err(e) = state.audit_log_repo.record(entry).await {
+            error!("Failed to record purge {} in the audit log: {}", report.id, e);
+        }
+    }
+
+    info!(
+        "Admin {} purged the data past retention: {:?} deleted in {} batches, verified: {}",
+        admin.id, report.deleted, report.batches, report.verified
+    );
+    Ok(Json(report))
+}
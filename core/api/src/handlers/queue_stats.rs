@@ -0,0 +1,149 @@
+use axum::{extract::{Extension, Query, State}, http::{header, StatusCode}, response::IntoResponse, Json};
+use serde::{Deserialize, Serialize};
+use serde_json::json;
+use tracing::{error, info};
+use uuid::Uuid;
+
+use innosystem_common::models::audit::NewAuditEntry;
+use innosystem_common::queue::QueueError;
+use crate::middleware::auth::AdminUser;
+use crate::services::backpressure::BackpressureStatus;
+use crate::services::queue_stats::{QuarantineReport, QueueWaitReport};
+use crate::services::{BackpressureService, QueueStatsService};
+use crate::state::AppState;
+
+/// Number of quarantined entries returned when no limit is given
+const DEFAULT_QUARANTINE_LIMIT: usize = 100;
+
+/// Query parameters for listing quarantined queue entries
+#[derive(Debug, Deserialize)]
+pub struct QuarantineQuery {
+    /// Most entries to return, newest first (defaults to 100)
+    pub limit: Option<usize>,
+}
+
+/// Response data for clearing the quarantine
+#[derive(Debug, Serialize)]
+pub struct ClearQuarantineResponse {
+    pub dropped: usize,
+}
+
+/// Get queue wait time percentiles per priority level and whether Low priority jobs are starving
+/// Access: Admin
+pub async fn get_queue_wait_times(
+    State(state): State<AppState>,
+) -> Result<Json<QueueWaitReport>, StatusCode> {
+    let report = state.queue_stats_service.wait_times().await
+        .map_err(|e| {
+            error!("Failed to compute queue wait times: {}", e);
+            StatusCode::INTERNAL_SERVER_ERROR
+        })?;
+
+    Ok(Json(report))
+}
+
+/// Get queue wait times per priority level as Prometheus metrics
+/// Access: Admin
+pub async fn get_queue_wait_metrics(
+    State(state): State<AppState>,
+) -> Result<impl IntoResponse, StatusCode> {
+    let report = state.queue_stats_service.wait_times().await
+        .map_err(|e| {
+            error!("Failed to compute queue wait times: {}", e);
+            StatusCode::INTERNAL_SERVER_ERROR
+        })?;
+
+    Ok((
+        [(header::CONTENT_TYPE, "text/plain; version=0.0.4")],
+        QueueStatsService::render_metrics(&report),
+    ))
+}
+
+/// Get the depth of every limited queue against its limit and the jobs turned away so far
+/// Access: Admin
+pub async fn get_backpressure(
+    State(state): State<AppState>,
+) -> Json<BackpressureStatus> {
+    Json(state.backpressure_service.status().await)
+}
+
+/// Get queue saturation and turned away jobs as Prometheus metrics
+/// Access: Admin
+pub async fn get_backpressure_metrics(
+    State(state): State<AppState>,
+) -> impl IntoResponse {
+    let status = state.backpressure_service.status().await;
+    (
+        [(header::CONTENT_TYPE, "text/plain; version=0.0.4")],
+        BackpressureService::render_metrics(&status),
+    )
+}
+
+/// Read the quarantine counts and up to `limit` of its newest entries
+async fn quarantine_report(state: &AppState, limit: usize) -> Result<QuarantineReport, QueueError> {
+    Ok(QuarantineReport {
+        held: state.job_queue.quarantine_length().await?,
+        total: state.job_queue.quarantined_total().await?,
+        entries: state.job_queue.quarantined_entries(limit).await?,
+    })
+}
+
+/// Get the queue entries that runners set aside because they are not job IDs, newest first
+///
+/// Such entries would otherwise make every runner fail on them; they are moved here with
+/// the queue they came from, and the queue keeps moving.
+/// Access: Admin
+pub async fn get_quarantine(
+    State(state): State<AppState>,
+    Query(query): Query<QuarantineQuery>,
+) -> Result<Json<QuarantineReport>, StatusCode> {
+    let limit = query.limit.unwrap_or(DEFAULT_QUARANTINE_LIMIT);
+    let report = quarantine_report(&state, limit).await
+        .map_err(|e| {
+            error!("Failed to read the queue quarantine: {}", e);
+            StatusCode::INTERNAL_SERVER_ERROR
+        })?;
+
+    Ok(Json(report))
+}
+
+/// Get the number of quarantined queue entries as Prometheus metrics
+/// Access: Admin
+pub async fn get_quarantine_metrics(
+    State(state): State<AppState>,
+) -> Result<impl IntoResponse, StatusCode> {
+    let report = quarantine_report(&state, 0).await
+        .map_err(|e| {
+            error!("Failed to read the queue quarantine: {}", e);
+            StatusCode::INTERNAL_SERVER_ERROR
+        })?;
+
+    Ok((
+        [(header::CONTENT_TYPE, "text/plain; version=0.0.4")],
+        QueueStatsService::render_quarantine_metrics(&report),
+    ))
+}
+
+/// Drop every quarantined queue entry once they have been looked into; the count of
+/// quarantined entries keeps growing
+/// Access: Admin
+pub async fn clear_quarantine(
+    State(state): State<AppState>,
+    Extension(admin): Extension<AdminUser>,
+) -> Result<Json<ClearQuarantineResponse>, StatusCode> {
+    let dropped = state.job_queue.clear_quarantine().await
+        .map_err(|e| {
+            error!("Failed to clear the queue quarantine: {}", e);
+            StatusCode::INTERNAL_SERVER_ERROR
+        })?;
+
+    let entry = NewAuditEntry::new(admin.id.clone(), "queue.quarantine_cleared", "queue", Uuid::nil(), json!({
+        "dropped": dropped,
+    }));
+    if let Err(e) = state.audit_log_repo.record(entry).await {
+        error!("Failed to record the clearing of the queue quarantine in the audit log: {}", e);
+    }
+
+    info!("Admin {} dropped {} quarantined queue entries", admin.id, dropped);
+    Ok(Json(ClearQuarantineResponse { dropped }))
+}
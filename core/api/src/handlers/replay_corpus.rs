@@ -0,0 +1,139 @@
+use axum::{
+    extract::{Extension, Path, State},
+    http::StatusCode,
+    Json,
+};
+use serde::{Deserialize, Serialize};
+use serde_json::json;
+use uuid::Uuid;
+use tracing::{error, info};
+use chrono::{DateTime, Utc};
+
+use innosystem_common::models::audit::NewAuditEntry;
+use innosystem_common::models::job::PriorityLevel;
+use innosystem_common::models::replay::ReplayCase;
+use crate::middleware::auth::AdminUser;
+use crate::request::StrictJson;
+use crate::services::replay_corpus::ReplayCorpusError;
+use crate::state::AppState;
+
+/// Request data for sampling jobs into the replay corpus of a job type
+#[derive(Debug, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct SampleReplayCorpusRequest {
+    /// Number of jobs to sample
+    pub count: usize,
+    /// JSONPath patterns of input values to anonymize besides those the job type
+    /// redacts from outputs, e.g. "$.customer.email" or "$..name"
+    #[serde(default)]
+    pub anonymize: Vec<String>,
+}
+
+/// A job kept in the replay corpus of its job type
+#[derive(Debug, Serialize)]
+pub struct ReplayCaseResponse {
+    pub id: Uuid,
+    pub job_type_id: Uuid,
+    pub source_job_id: Uuid,
+    pub priority: String,
+    pub input_data: serde_json::Value,
+    pub sampled_at: DateTime<Utc>,
+    /// Output and cost replays are compared against, once a runner recorded them
+    pub baseline_output: Option<serde_json::Value>,
+    pub baseline_cost_cents: Option<i32>,
+    /// Processor version that recorded the baseline
+    pub baseline_version: Option<String>,
+    pub baseline_recorded_at: Option<DateTime<Utc>>,
+}
+
+impl From<ReplayCase> for ReplayCaseResponse {
+    fn from(case: ReplayCase) -> Self {
+        Self {
+            id: case.id,
+            job_type_id: case.job_type_id,
+            source_job_id: case.source_job_id,
+            priority: PriorityLevel::from_i32(case.priority).as_str().to_string(),
+            input_data: case.input_data,
+            sampled_at: case.sampled_at,
+            baseline_output: case.baseline_output,
+            baseline_cost_cents: case.baseline_cost_cents,
+            baseline_version: case.baseline_version,
+            baseline_recorded_at: case.baseline_recorded_at,
+        }
+    }
+}
+
+/// Sample historical jobs of a job type into its replay corpus
+///
+/// Picks succeeded live jobs at random that are not in the corpus yet, from the
+/// schemas of all resellers. Their inputs are anonymized: the values the job type
+/// redacts from outputs and those at the given paths are replaced with pseudonyms
+/// that keep equal values equal. Runners in replay mode run the corpus against their
+/// processor version to catch regressions before upgrades reach production.
+/// Access: Admin
+pub async fn sample_replay_corpus(
+    State(state): State<AppState>,
+    Extension(admin): Extension<AdminUser>,
+    Path(job_type_id): Path<Uuid>,
+    StrictJson(payload): StrictJson<SampleReplayCorpusRequest>,
+) -> Result<(StatusCode, Json<Vec<ReplayCaseResponse>>), StatusCode> {
+    let job_type = state.job_type_repo.find_by_id(job_type_id).await
+        .map_err(|e| {
+            error!("Failed to fetch job type {}: {}", job_type_id, e);
+            StatusCode::NOT_FOUND
+        })?;
+
+    let cases = state.replay_corpus.sample(&job_type, payload.count, &payload.anonymize).await
+        .map_err(|e| {
+            error!("Failed to sample jobs of job type {} into its replay corpus: {}", job_type_id, e);
+            match e {
+                ReplayCorpusError::InvalidCount { .. } | ReplayCorpusError::InvalidRule(_) => StatusCode::BAD_REQUEST,
+                ReplayCorpusError::Repository(_) => StatusCode::INTERNAL_SERVER_ERROR,
+            }
+        })?;
+
+    let entry = NewAuditEntry::new(admin.id.clone(), "job_type.replay_corpus_sampled", "job_type", job_type_id, json!({
+        "sampled": cases.len(),
+        "anonymize": payload.anonymize,
+    }));
+    if let Err(e) = state.audit_log_repo.record(entry).await {
+        error!("Failed to record the sampling of the replay corpus of job type {} in the audit log: {}", job_type_id, e);
+    }
+
+    info!("Admin {} sampled {} jobs into the replay corpus of job type {}", admin.id, cases.len(), job_type_id);
+    Ok((StatusCode::CREATED, Json(cases.into_iter().map(Into::into).collect())))
+}
+
+/// List the replay corpus of a job type, oldest cases first
+/// Access: Admin
+pub async fn list_replay_corpus(
+    State(state): State<AppState>,
+    Path(job_type_id): Path<Uuid>,
+) -> Result<Json<Vec<ReplayCaseResponse>>, StatusCode> {
+    let cases = state.replay_corpus.list(job_type_id).await
+        .map_err(|e| {
+            error!("Failed to list the replay corpus of job type {}: {}", job_type_id, e);
+            StatusCode::INTERNAL_SERVER_ERROR
+        })?;
+
+    Ok(Json(cases.into_iter().map(Into::into).collect()))
+}
+
+/// Remove a case from the replay corpus of a job type
+/// Access: Admin
+pub async fn delete_replay_case(
+    State(state): State<AppState>,
+    Path((job_type_id, case_id)): Path<(Uuid, Uuid)>,
+) -> Result<StatusCode, StatusCode> {
+    let deleted = state.replay_corpus.remove(job_type_id, case_id).await
+        .map_err(|e| {
+            error!("Failed to remove case {} from the replay corpus of job type {}: {}", case_id, job_type_id, e);
+            StatusCode::INTERNAL_SERVER_ERROR
+        })?;
+    if !deleted {
+        return Err(StatusCode::NOT_FOUND);
+    }
+
+    info!("Removed case {} from the replay corpus of job type {}", case_id, job_type_id);
+    Ok(StatusCode::NO_CONTENT)
+}
@@ -0,0 +1,23 @@
+use axum::{extract::State, http::header, response::IntoResponse, Json};
+
+use crate::services::repository_metrics::RepositoryMetricsReport;
+use crate::state::AppState;
+
+/// Get call counts, errors, slow calls and timings per repository method
+/// Access: Admin
+pub async fn get_repository_stats(
+    State(state): State<AppState>,
+) -> Json<RepositoryMetricsReport> {
+    Json(RepositoryMetricsReport::collect(&state.repository_metrics))
+}
+
+/// Get repository call metrics per entity and method as Prometheus metrics
+/// Access: Admin
+pub async fn get_repository_metrics(
+    State(state): State<AppState>,
+) -> impl IntoResponse {
+    (
+        [(header::CONTENT_TYPE, "text/plain; version=0.0.4")],
+        RepositoryMetricsReport::collect(&state.repository_metrics).render_metrics(),
+    )
+}
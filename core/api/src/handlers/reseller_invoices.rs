@@ -0,0 +1,123 @@
+use std::collections::HashMap;
+
+use axum::{
+    extract::{Extension, Path, Query, State},
+    http::{header, StatusCode},
+    response::IntoResponse,
+    Json,
+};
+use serde::Deserialize;
+use tracing::error;
+
+use innosystem_common::models::reseller_invoice::{period_bounds, ResellerInvoice};
+use crate::middleware::auth::ResellerUser;
+use crate::state::AppState;
+
+/// Format of an exported reseller invoice
+#[derive(Debug, Default, Clone, Copy, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum InvoiceFormat {
+    #[default]
+    Csv,
+    Pdf,
+}
+
+/// Query parameters for exporting a reseller invoice
+#[derive(Debug, Deserialize)]
+pub struct ExportInvoiceQuery {
+    /// `csv` (default) or `pdf`
+    #[serde(default)]
+    pub format: InvoiceFormat,
+}
+
+/// Consolidate the charges of the authenticated reseller's customers over a month
+///
+/// Admins have no invoice of their own (404); an invalid period is rejected (400).
+async fn build_invoice(
+    state: &AppState,
+    reseller: Option<Extension<ResellerUser>>,
+    period: &str,
+) -> Result<ResellerInvoice, StatusCode> {
+    let Some(Extension(reseller)) = reseller else {
+        return Err(StatusCode::NOT_FOUND);
+    };
+    let (period_start, period_end) = period_bounds(period).map_err(|_| StatusCode::BAD_REQUEST)?;
+
+    let reseller = state.reseller_repo.find_by_id(reseller.id).await
+        .map_err(|e| {
+            error!("Failed to fetch reseller {}: {}", reseller.id, e);
+            StatusCode::INTERNAL_SERVER_ERROR
+        })?;
+
+    let customers = state.customer_repo.find_by_reseller_id(reseller.id).await
+        .map_err(|e| {
+            error!("Failed to fetch the customers of reseller {}: {}", reseller.id, e);
+            StatusCode::INTERNAL_SERVER_ERROR
+        })?;
+    let customer_ids = customers.iter().map(|customer| customer.id).collect();
+    let customer_names: HashMap<_, _> = customers.into_iter().map(|customer| (customer.id, customer.name)).collect();
+
+    let charges = state.job_repo.get_charges_by_job_type(customer_ids, period_start, period_end).await
+        .map_err(|e| {
+            error!("Failed to fetch the charges of reseller {} for {}: {}", reseller.id, period, e);
+            StatusCode::INTERNAL_SERVER_ERROR
+        })?;
+
+    let job_type_names: HashMap<_, _> = state.job_type_repo.list_all().await
+        .map_err(|e| {
+            error!("Failed to fetch job types: {}", e);
+            StatusCode::INTERNAL_SERVER_ERROR
+        })?
+        .into_iter()
+        .map(|job_type| (job_type.id, job_type.name))
+        .collect();
+
+    let closed = state.billing_period_repo.list().await
+        .map_err(|e| {
+            error!("Failed to fetch billing periods: {}", e);
+            StatusCode::INTERNAL_SERVER_ERROR
+        })?
+        .iter()
+        .any(|closed| closed.period_start <= period_start && closed.period_end >= period_end);
+
+    ResellerInvoice::build(&reseller, period, closed, charges, &customer_names, &job_type_names)
+        .map_err(|_| StatusCode::BAD_REQUEST)
+}
+
+/// Get the authenticated reseller's consolidated invoice for a month, e.g. `2025-04`
+///
+/// Lines cover what each customer was charged per job type for jobs completed in the
+/// month, with the reseller's markup and commission, subtotaled per customer and per
+/// job type. Until the month's billing period is closed the amounts may still change.
+/// Access: Reseller
+pub async fn get_reseller_invoice(
+    State(state): State<AppState>,
+    reseller: Option<Extension<ResellerUser>>,
+    Path(period): Path<String>,
+) -> Result<Json<ResellerInvoice>, StatusCode> {
+    build_invoice(&state, reseller, &period).await.map(Json)
+}
+
+/// Download the authenticated reseller's consolidated invoice for a month as CSV or PDF
+/// Access: Reseller
+pub async fn export_reseller_invoice(
+    State(state): State<AppState>,
+    reseller: Option<Extension<ResellerUser>>,
+    Path(period): Path<String>,
+    Query(query): Query<ExportInvoiceQuery>,
+) -> Result<impl IntoResponse, StatusCode> {
+    let invoice = build_invoice(&state, reseller, &period).await?;
+
+    let (content_type, extension, body) = match query.format {
+        InvoiceFormat::Csv => ("text/csv", "csv", invoice.to_csv().into_bytes()),
+        InvoiceFormat::Pdf => ("application/pdf", "pdf", invoice.to_pdf()),
+    };
+    let filename = format!("attachment; filename=\"reseller-invoice-{}.{}\"", invoice.period, extension);
+    Ok((
+        [
+            (header::CONTENT_TYPE, content_type.to_string()),
+            (header::CONTENT_DISPOSITION, filename),
+        ],
+        body,
+    ))
+}
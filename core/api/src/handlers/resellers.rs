@@ -6,12 +6,18 @@ use axum::{
 use serde::{Deserialize, Serialize};
 use uuid::Uuid;
 use tracing::{info, error};
+use chrono::{DateTime, Utc};
 
+use crate::middleware::auth::ResellerUser;
+use crate::request::StrictJson;
+use crate::services::cache::{ACTIVE_RESELLERS_KEY, RESELLERS_KEY};
 use crate::state::AppState;
+use innosystem_common::i18n::Locale;
 use innosystem_common::models::reseller::{Reseller, NewReseller};
 
 /// Request data for creating a new reseller
 #[derive(Debug, Deserialize)]
+#[serde(deny_unknown_fields)]
 pub struct CreateResellerRequest {
     /// Reseller name
     pub name: String,
@@ -19,10 +25,14 @@ pub struct CreateResellerRequest {
     pub email: String,
     /// Commission rate as a percentage (e.g., 10.5 for 10.5%)
     pub commission_rate_percentage: f64,
+    /// Markup added to the price of the reseller's customers' jobs, as a percentage (optional, defaults to 0)
+    #[serde(default)]
+    pub markup_rate_percentage: f64,
 }
 
 /// Request data for updating a reseller
 #[derive(Debug, Deserialize)]
+#[serde(deny_unknown_fields)]
 pub struct UpdateResellerRequest {
     /// Reseller name
     pub name: Option<String>,
@@ -30,12 +40,47 @@ pub struct UpdateResellerRequest {
     pub email: Option<String>,
     /// Commission rate as a percentage
     pub commission_rate_percentage: Option<f64>,
+    /// Markup added to the price of the reseller's customers' jobs, as a percentage
+    pub markup_rate_percentage: Option<f64>,
     /// Whether the reseller is active
     pub active: Option<bool>,
+    /// While inactive, suspend job submission for the reseller's customers
+    pub suspend_customers: Option<bool>,
+    /// While inactive, block creation of new customers under the reseller
+    pub block_customer_creation: Option<bool>,
+    /// Reason for the deactivation, shown to suspended customers
+    pub deactivation_reason: Option<String>,
+    /// Locale of messages to the reseller's customers (`en`, `de` or `fr`); empty to unset
+    pub default_locale: Option<String>,
+    /// Time zone of the reseller's customers that have none of their own (IANA name such
+    /// as `Europe/Helsinki`); empty to unset
+    pub timezone: Option<String>,
+    /// Refuse the reseller's API key so only signed requests are accepted; needs a signing secret
+    pub require_signed_requests: Option<bool>,
 }
 
-/// Response data for reseller operations
+/// Request data for creating or rotating a reseller's signing secret
+#[derive(Debug, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct SigningSecretRequest {
+    /// Refuse the reseller's API key from now on, so only signed requests are accepted
+    #[serde(default)]
+    pub require_signed_requests: bool,
+}
+
+/// Response data for a reseller's signing secret, only returned when it is created
 #[derive(Debug, Serialize)]
+pub struct SigningSecretResponse {
+    /// Reseller ID, sent with signed requests
+    pub reseller_id: Uuid,
+    /// Key of the HMAC signatures of the reseller's requests
+    pub signing_secret: String,
+    /// Whether the reseller's API key is refused
+    pub require_signed_requests: bool,
+}
+
+/// Response data for reseller operations
+#[derive(Debug, Serialize, Deserialize)]
 pub struct ResellerResponse {
     /// Reseller ID
     pub id: Uuid,
@@ -49,16 +94,32 @@ pub struct ResellerResponse {
     pub active: bool,
     /// Commission rate as a percentage (e.g., 10.5 for 10.5%)
     pub commission_rate_percentage: f64,
+    /// Markup added to the price of the reseller's customers' jobs, as a percentage
+    pub markup_rate_percentage: f64,
+    /// Whether the reseller's customers are suspended while the reseller is inactive
+    pub suspend_customers: bool,
+    /// Whether customer creation is blocked while the reseller is inactive
+    pub block_customer_creation: bool,
+    /// Reason for the deactivation
+    pub deactivation_reason: Option<String>,
+    /// Locale of messages to the reseller's customers, if set
+    pub default_locale: Option<String>,
+    /// Time zone of the reseller's customers that have none of their own, if set
+    pub timezone: Option<String>,
+    /// Whether the reseller has a signing secret for signed requests
+    pub request_signing: bool,
+    /// Whether the reseller's API key is refused, so only signed requests are accepted
+    pub require_signed_requests: bool,
     /// Creation timestamp
-    pub created_at: Option<String>,
+    pub created_at: Option<DateTime<Utc>>,
     /// Last update timestamp
-    pub updated_at: Option<String>,
+    pub updated_at: Option<DateTime<Utc>>,
 }
 
 /// Create a new reseller
 pub async fn create_reseller(
     State(state): State<AppState>,
-    Json(payload): Json<CreateResellerRequest>,
+    StrictJson(payload): StrictJson<CreateResellerRequest>,
 ) -> Result<(StatusCode, Json<ResellerResponse>), StatusCode> {
     // Generate a new API key for the reseller
     let api_key = Reseller::generate_api_key();
@@ -74,6 +135,12 @@ pub async fn create_reseller(
     // Set commission rate from percentage
     new_reseller.set_commission_rate_from_percentage(payload.commission_rate_percentage);
     
+    if payload.markup_rate_percentage < 0.0 {
+        error!("Invalid markup rate: {}", payload.markup_rate_percentage);
+        return Err(StatusCode::BAD_REQUEST);
+    }
+    new_reseller.set_markup_rate_from_percentage(payload.markup_rate_percentage);
+    
     // Convert to NewReseller for database insertion
     let new_reseller_db = NewReseller::from(new_reseller.clone());
     
@@ -94,10 +161,21 @@ pub async fn create_reseller(
         api_key: reseller.api_key.clone(),
         active: reseller.active,
         commission_rate_percentage: reseller.commission_rate_percentage(),
-        created_at: reseller.created_at.map(|dt| dt.and_utc().to_rfc3339()),
-        updated_at: reseller.updated_at.map(|dt| dt.and_utc().to_rfc3339()),
+        markup_rate_percentage: reseller.markup_rate_percentage(),
+        suspend_customers: reseller.suspend_customers,
+        block_customer_creation: reseller.block_customer_creation,
+        deactivation_reason: reseller.deactivation_reason.clone(),
+        default_locale: reseller.default_locale.clone(),
+        timezone: reseller.timezone.clone(),
+        request_signing: reseller.signing_secret.is_some(),
+        require_signed_requests: reseller.require_signed_requests,
+        created_at: reseller.created_at,
+        updated_at: reseller.updated_at,
     };
     
+    // Reseller lists changed, stop serving the cached ones
+    state.response_cache.invalidate(&[RESELLERS_KEY, ACTIVE_RESELLERS_KEY]).await;
+    
     info!("Created new reseller with ID: {}", reseller.id);
     Ok((StatusCode::CREATED, Json(response)))
 }
@@ -136,8 +214,16 @@ pub async fn get_reseller(
         api_key: reseller.api_key.clone(),
         active: reseller.active,
         commission_rate_percentage: reseller.commission_rate_percentage(),
-        created_at: reseller.created_at.map(|dt| dt.and_utc().to_rfc3339()),
-        updated_at: reseller.updated_at.map(|dt| dt.and_utc().to_rfc3339()),
+        markup_rate_percentage: reseller.markup_rate_percentage(),
+        suspend_customers: reseller.suspend_customers,
+        block_customer_creation: reseller.block_customer_creation,
+        deactivation_reason: reseller.deactivation_reason.clone(),
+        default_locale: reseller.default_locale.clone(),
+        timezone: reseller.timezone.clone(),
+        request_signing: reseller.signing_secret.is_some(),
+        require_signed_requests: reseller.require_signed_requests,
+        created_at: reseller.created_at,
+        updated_at: reseller.updated_at,
     };
     
     info!("Retrieved reseller with ID: {}", reseller.id);
@@ -148,7 +234,7 @@ pub async fn get_reseller(
 pub async fn update_reseller(
     State(state): State<AppState>,
     Path(reseller_id_str): Path<String>,
-    Json(payload): Json<UpdateResellerRequest>,
+    StrictJson(payload): StrictJson<UpdateResellerRequest>,
 ) -> Result<Json<ResellerResponse>, StatusCode> {
     // Try to parse the reseller_id as a UUID
     let reseller_id = match Uuid::parse_str(&reseller_id_str) {
@@ -184,10 +270,69 @@ pub async fn update_reseller(
         reseller.set_commission_rate_from_percentage(commission_rate);
     }
     
+    if let Some(markup_rate) = payload.markup_rate_percentage {
+        if markup_rate < 0.0 {
+            error!("Invalid markup rate: {}", markup_rate);
+            return Err(StatusCode::BAD_REQUEST);
+        }
+        reseller.set_markup_rate_from_percentage(markup_rate);
+    }
+    
     if let Some(active) = payload.active {
         reseller.active = active;
     }
     
+    if let Some(suspend_customers) = payload.suspend_customers {
+        reseller.suspend_customers = suspend_customers;
+    }
+    
+    if let Some(block_customer_creation) = payload.block_customer_creation {
+        reseller.block_customer_creation = block_customer_creation;
+    }
+    
+    if payload.deactivation_reason.is_some() {
+        reseller.deactivation_reason = payload.deactivation_reason;
+    }
+    
+    if let Some(default_locale) = payload.default_locale {
+        reseller.default_locale = if default_locale.is_empty() {
+            None
+        } else {
+            let locale = Locale::parse(&default_locale).ok_or_else(|| {
+                error!("Unsupported locale: {}", default_locale);
+                StatusCode::BAD_REQUEST
+            })?;
+            Some(locale.as_str().to_string())
+        };
+    }
+    
+    if let Some(timezone) = payload.timezone {
+        reseller.timezone = if timezone.trim().is_empty() {
+            None
+        } else {
+            let tz = innosystem_common::timezone::parse(&timezone).ok_or_else(|| {
+                error!("Unknown time zone: {}", timezone);
+                StatusCode::BAD_REQUEST
+            })?;
+            Some(tz.name().to_string())
+        };
+    }
+    
+    if let Some(require_signed_requests) = payload.require_signed_requests {
+        if require_signed_requests && reseller.signing_secret.is_none() {
+            error!("Reseller {} cannot require signed requests without a signing secret", reseller.id);
+            return Err(StatusCode::BAD_REQUEST);
+        }
+        reseller.require_signed_requests = require_signed_requests;
+    }
+    
+    // Reactivating a reseller resets the cascade so a later deactivation starts clean
+    if payload.active == Some(true) {
+        reseller.suspend_customers = false;
+        reseller.block_customer_creation = false;
+        reseller.deactivation_reason = None;
+    }
+    
     // Update the reseller in the database
     let updated_reseller = state.reseller_repo.update(&reseller).await
         .map_err(|e| {
@@ -203,23 +348,39 @@ pub async fn update_reseller(
         api_key: updated_reseller.api_key.clone(),
         active: updated_reseller.active,
         commission_rate_percentage: updated_reseller.commission_rate_percentage(),
-        created_at: updated_reseller.created_at.map(|dt| dt.and_utc().to_rfc3339()),
-        updated_at: updated_reseller.updated_at.map(|dt| dt.and_utc().to_rfc3339()),
+        markup_rate_percentage: updated_reseller.markup_rate_percentage(),
+        suspend_customers: updated_reseller.suspend_customers,
+        block_customer_creation: updated_reseller.block_customer_creation,
+        deactivation_reason: updated_reseller.deactivation_reason.clone(),
+        default_locale: updated_reseller.default_locale.clone(),
+        timezone: updated_reseller.timezone.clone(),
+        request_signing: updated_reseller.signing_secret.is_some(),
+        require_signed_requests: updated_reseller.require_signed_requests,
+        created_at: updated_reseller.created_at,
+        updated_at: updated_reseller.updated_at,
     };
     
+    // Reseller lists changed, stop serving the cached ones
+    state.response_cache.invalidate(&[RESELLERS_KEY, ACTIVE_RESELLERS_KEY]).await;
+    
     info!("Updated reseller with ID: {}", updated_reseller.id);
     Ok(Json(response))
 }
 
-/// Get current reseller profile based on API key
+/// Get the profile of the authenticated reseller
 pub async fn get_current_reseller_profile(
     State(state): State<AppState>,
-    Extension(api_key): Extension<String>,
+    reseller: Option<Extension<ResellerUser>>,
 ) -> Result<Json<ResellerResponse>, StatusCode> {
-    // Fetch the reseller from the repository using the API key from the request extension
-    let reseller = state.reseller_repo.find_by_api_key(&api_key).await
+    // Admins pass reseller authentication too, but have no reseller profile
+    let Some(Extension(reseller)) = reseller else {
+        return Err(StatusCode::NOT_FOUND);
+    };
+    
+    // Fetch the reseller from the repository using the ID it authenticated as
+    let reseller = state.reseller_repo.find_by_id(reseller.id).await
         .map_err(|e| {
-            error!("Failed to fetch reseller by API key: {}", e);
+            error!("Failed to fetch reseller: {}", e);
             // If reseller not found, return 404
             if e.to_string().contains("not found") {
                 StatusCode::NOT_FOUND
@@ -236,8 +397,16 @@ pub async fn get_current_reseller_profile(
         api_key: reseller.api_key.clone(),
         active: reseller.active,
         commission_rate_percentage: reseller.commission_rate_percentage(),
-        created_at: reseller.created_at.map(|dt| dt.and_utc().to_rfc3339()),
-        updated_at: reseller.updated_at.map(|dt| dt.and_utc().to_rfc3339()),
+        markup_rate_percentage: reseller.markup_rate_percentage(),
+        suspend_customers: reseller.suspend_customers,
+        block_customer_creation: reseller.block_customer_creation,
+        deactivation_reason: reseller.deactivation_reason.clone(),
+        default_locale: reseller.default_locale.clone(),
+        timezone: reseller.timezone.clone(),
+        request_signing: reseller.signing_secret.is_some(),
+        require_signed_requests: reseller.require_signed_requests,
+        created_at: reseller.created_at,
+        updated_at: reseller.updated_at,
     };
     
     info!("Retrieved current reseller profile with ID: {}", reseller.id);
@@ -248,6 +417,10 @@ pub async fn get_current_reseller_profile(
 pub async fn get_all_resellers(
     State(state): State<AppState>,
 ) -> Result<Json<Vec<ResellerResponse>>, StatusCode> {
+    if let Some(cached) = state.response_cache.get::<Vec<ResellerResponse>>(RESELLERS_KEY).await {
+        return Ok(Json(cached));
+    }
+    
     // Fetch all resellers from the repository
     let resellers = state.reseller_repo.list_all().await
         .map_err(|e| {
@@ -265,11 +438,21 @@ pub async fn get_all_resellers(
             api_key: reseller.api_key.clone(),
             active: reseller.active,
             commission_rate_percentage: reseller.commission_rate_percentage(),
-            created_at: reseller.created_at.map(|dt| dt.and_utc().to_rfc3339()),
-            updated_at: reseller.updated_at.map(|dt| dt.and_utc().to_rfc3339()),
+            markup_rate_percentage: reseller.markup_rate_percentage(),
+            suspend_customers: reseller.suspend_customers,
+            block_customer_creation: reseller.block_customer_creation,
+            deactivation_reason: reseller.deactivation_reason.clone(),
+            default_locale: reseller.default_locale.clone(),
+            timezone: reseller.timezone.clone(),
+            request_signing: reseller.signing_secret.is_some(),
+            require_signed_requests: reseller.require_signed_requests,
+            created_at: reseller.created_at,
+            updated_at: reseller.updated_at,
         })
         .collect();
     
+    state.response_cache.put(RESELLERS_KEY, &reseller_responses).await;
+    
     info!("Retrieved all resellers from database");
     Ok(Json(reseller_responses))
 }
@@ -278,6 +461,10 @@ pub async fn get_all_resellers(
 pub async fn get_active_resellers(
     State(state): State<AppState>,
 ) -> Result<Json<Vec<ResellerResponse>>, StatusCode> {
+    if let Some(cached) = state.response_cache.get::<Vec<ResellerResponse>>(ACTIVE_RESELLERS_KEY).await {
+        return Ok(Json(cached));
+    }
+    
     // Fetch active resellers from the repository
     let resellers = state.reseller_repo.list_active().await
         .map_err(|e| {
@@ -295,11 +482,21 @@ pub async fn get_active_resellers(
             api_key: reseller.api_key.clone(),
             active: reseller.active,
             commission_rate_percentage: reseller.commission_rate_percentage(),
-            created_at: reseller.created_at.map(|dt| dt.and_utc().to_rfc3339()),
-            updated_at: reseller.updated_at.map(|dt| dt.and_utc().to_rfc3339()),
+            markup_rate_percentage: reseller.markup_rate_percentage(),
+            suspend_customers: reseller.suspend_customers,
+            block_customer_creation: reseller.block_customer_creation,
+            deactivation_reason: reseller.deactivation_reason.clone(),
+            default_locale: reseller.default_locale.clone(),
+            timezone: reseller.timezone.clone(),
+            request_signing: reseller.signing_secret.is_some(),
+            require_signed_requests: reseller.require_signed_requests,
+            created_at: reseller.created_at,
+            updated_at: reseller.updated_at,
         })
         .collect();
     
+    state.response_cache.put(ACTIVE_RESELLERS_KEY, &reseller_responses).await;
+    
     info!("Retrieved active resellers from database");
     Ok(Json(reseller_responses))
 }
@@ -348,10 +545,89 @@ pub async fn regenerate_api_key(
         api_key: updated_reseller.api_key.clone(),
         active: updated_reseller.active,
         commission_rate_percentage: updated_reseller.commission_rate_percentage(),
-        created_at: updated_reseller.created_at.map(|dt| dt.and_utc().to_rfc3339()),
-        updated_at: updated_reseller.updated_at.map(|dt| dt.and_utc().to_rfc3339()),
+        markup_rate_percentage: updated_reseller.markup_rate_percentage(),
+        suspend_customers: updated_reseller.suspend_customers,
+        block_customer_creation: updated_reseller.block_customer_creation,
+        deactivation_reason: updated_reseller.deactivation_reason.clone(),
+        default_locale: updated_reseller.default_locale.clone(),
+        timezone: updated_reseller.timezone.clone(),
+        request_signing: updated_reseller.signing_secret.is_some(),
+        require_signed_requests: updated_reseller.require_signed_requests,
+        created_at: updated_reseller.created_at,
+        updated_at: updated_reseller.updated_at,
     };
     
+    // Reseller lists changed, stop serving the cached ones
+    state.response_cache.invalidate(&[RESELLERS_KEY, ACTIVE_RESELLERS_KEY]).await;
+    
     info!("Regenerated API key for reseller with ID: {}", updated_reseller.id);
     Ok(Json(response))
 }
+
+/// Create or rotate the secret a reseller signs its requests with; a previous secret
+/// stops working immediately
+pub async fn rotate_signing_secret(
+    State(state): State<AppState>,
+    Path(reseller_id): Path<Uuid>,
+    StrictJson(payload): StrictJson<SigningSecretRequest>,
+) -> Result<Json<SigningSecretResponse>, StatusCode> {
+    let mut reseller = find_reseller(&state, reseller_id).await?;
+    
+    reseller.signing_secret = Some(Reseller::generate_signing_secret());
+    reseller.require_signed_requests = payload.require_signed_requests;
+    
+    let updated_reseller = state.reseller_repo.update(&reseller).await
+        .map_err(|e| {
+            error!("Failed to update reseller signing secret: {}", e);
+            StatusCode::INTERNAL_SERVER_ERROR
+        })?;
+    
+    // Reseller lists changed, stop serving the cached ones
+    state.response_cache.invalidate(&[RESELLERS_KEY, ACTIVE_RESELLERS_KEY]).await;
+    
+    info!(
+        "Rotated signing secret for reseller with ID: {} (signed requests required: {})",
+        updated_reseller.id, updated_reseller.require_signed_requests
+    );
+    Ok(Json(SigningSecretResponse {
+        reseller_id: updated_reseller.id,
+        signing_secret: updated_reseller.signing_secret.unwrap_or_default(),
+        require_signed_requests: updated_reseller.require_signed_requests,
+    }))
+}
+
+/// Turn off signed requests for a reseller, so it authenticates with its API key again
+pub async fn disable_request_signing(
+    State(state): State<AppState>,
+    Path(reseller_id): Path<Uuid>,
+) -> Result<StatusCode, StatusCode> {
+    let mut reseller = find_reseller(&state, reseller_id).await?;
+    
+    reseller.signing_secret = None;
+    reseller.require_signed_requests = false;
+    
+    state.reseller_repo.update(&reseller).await
+        .map_err(|e| {
+            error!("Failed to disable request signing for reseller: {}", e);
+            StatusCode::INTERNAL_SERVER_ERROR
+        })?;
+    
+    // Reseller lists changed, stop serving the cached ones
+    state.response_cache.invalidate(&[RESELLERS_KEY, ACTIVE_RESELLERS_KEY]).await;
+    
+    info!("Disabled request signing for reseller with ID: {}", reseller_id);
+    Ok(StatusCode::NO_CONTENT)
+}
+
+// Fetch a reseller, mapping a missing one to 404
+async fn find_reseller(state: &AppState, reseller_id: Uuid) -> Result<Reseller, StatusCode> {
+    state.reseller_repo.find_by_id(reseller_id).await
+        .map_err(|e| {
+            error!("Failed to fetch reseller: {}", e);
+            if e.to_string().contains("not found") {
+                StatusCode::NOT_FOUND
+            } else {
+                StatusCode::INTERNAL_SERVER_ERROR
+            }
+        })
+}
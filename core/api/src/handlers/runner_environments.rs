@@ -0,0 +1,158 @@
+use std::collections::{BTreeMap, HashMap};
+use chrono::{DateTime, Utc};
+
+use axum::{extract::{Path, Query, State, Extension}, http::StatusCode, Json};
+use serde::{Deserialize, Serialize};
+use uuid::Uuid;
+use tracing::{error, warn};
+
+use innosystem_common::models::runner::{environment_drift, EnvironmentDrift, NewRunnerEnvironmentReport, Runner, RunnerEnvironment, RunnerEnvironmentReport};
+use crate::middleware::auth::AdminUser;
+use crate::state::AppState;
+
+/// Number of reported environments returned when no limit is given
+const DEFAULT_HISTORY_LIMIT: i64 = 20;
+
+/// Query parameters for a runner's environment history
+#[derive(Debug, Deserialize)]
+pub struct EnvironmentHistoryQuery {
+    /// Maximum number of reports to return (defaults to 20)
+    pub limit: Option<i64>,
+}
+
+/// An environment reported by a runner
+#[derive(Debug, Serialize)]
+pub struct RunnerEnvironmentResponse {
+    pub runner_id: Uuid,
+    pub fingerprint: String,
+    pub environment: serde_json::Value,
+    /// When the runner first reported this environment
+    pub reported_at: DateTime<Utc>,
+}
+
+impl From<RunnerEnvironmentReport> for RunnerEnvironmentResponse {
+    fn from(report: RunnerEnvironmentReport) -> Self {
+        Self {
+            runner_id: report.runner_id,
+            fingerprint: report.fingerprint,
+            environment: report.environment,
+            reported_at: report.reported_at,
+        }
+    }
+}
+
+/// Environments of the runners of one pool and the settings they disagree on
+#[derive(Debug, Serialize)]
+pub struct PoolDriftResponse {
+    /// Name the pool's runners are registered under
+    pub pool: String,
+    /// Latest environment of every runner of the pool that reported one
+    pub runners: Vec<RunnerEnvironmentResponse>,
+    /// Settings with more than one value in the pool; empty when the runners agree
+    pub drift: Vec<EnvironmentDrift>,
+}
+
+/// Store an environment reported by a runner and warn when it sets the runner apart from
+/// the rest of its pool
+///
+/// Fails with 400 Bad Request when the environment is too large to store.
+pub(crate) async fn record_environment(state: &AppState, runner: &Runner, environment: RunnerEnvironment) -> Result<(), StatusCode> {
+    environment.validate()
+        .map_err(|reason| {
+            error!("Refusing environment of runner {}: {}", runner.id, reason);
+            StatusCode::BAD_REQUEST
+        })?;
+
+    let stored = state.runner_repo.record_environment(NewRunnerEnvironmentReport::new(runner.id, &environment)).await
+        .map_err(|e| {
+            error!("Failed to record the environment of runner {}: {}", runner.id, e);
+            StatusCode::INTERNAL_SERVER_ERROR
+        })?;
+
+    // Only a changed environment can introduce drift
+    if stored.is_some() {
+        match pool_drift(state).await {
+            Ok(pools) => {
+                for pool in pools.into_iter().filter(|pool| pool.pool == runner.name && !pool.drift.is_empty()) {
+                    let settings: Vec<&str> = pool.drift.iter().map(|drift| drift.setting.as_str()).collect();
+                    warn!("Runners of pool {} differ in {} after runner {} reported its environment", pool.pool, settings.join(", "), runner.id);
+                }
+            }
+            Err(status) => warn!("Failed to check the environment drift of pool {}: {}", runner.name, status),
+        }
+    }
+
+    Ok(())
+}
+
+/// Latest environments of the runners grouped by pool, with the drift of each pool
+async fn pool_drift(state: &AppState) -> Result<Vec<PoolDriftResponse>, StatusCode> {
+    let runners = state.runner_repo.list_all().await
+        .map_err(|e| {
+            error!("Failed to list runners: {}", e);
+            StatusCode::INTERNAL_SERVER_ERROR
+        })?;
+    let reports = state.runner_repo.latest_environments().await
+        .map_err(|e| {
+            error!("Failed to load the latest runner environments: {}", e);
+            StatusCode::INTERNAL_SERVER_ERROR
+        })?;
+
+    let pools_by_runner: HashMap<Uuid, String> = runners.into_iter()
+        .map(|runner| (runner.id, runner.name))
+        .collect();
+    let mut pools: BTreeMap<String, Vec<RunnerEnvironmentReport>> = BTreeMap::new();
+    for report in reports {
+        if let Some(pool) = pools_by_runner.get(&report.runner_id) {
+            pools.entry(pool.clone()).or_default().push(report);
+        }
+    }
+
+    Ok(pools.into_iter()
+        .map(|(pool, reports)| {
+            let environments: Vec<(Uuid, RunnerEnvironment)> = reports.iter()
+                .filter_map(|report| report.runner_environment().map(|environment| (report.runner_id, environment)))
+                .collect();
+            PoolDriftResponse {
+                pool,
+                drift: environment_drift(&environments),
+                runners: reports.into_iter().map(RunnerEnvironmentResponse::from).collect(),
+            }
+        })
+        .collect())
+}
+
+/// Get the environments a runner reported, newest first; a new entry is kept each time
+/// the environment changes
+/// Access: Admin
+pub async fn get_environment_history(
+    State(state): State<AppState>,
+    Extension(_admin): Extension<AdminUser>,
+    Path(runner_id): Path<Uuid>,
+    Query(query): Query<EnvironmentHistoryQuery>,
+) -> Result<Json<Vec<RunnerEnvironmentResponse>>, StatusCode> {
+    state.runner_repo.find_by_id(runner_id).await
+        .map_err(|e| {
+            error!("Failed to find runner {}: {}", runner_id, e);
+            StatusCode::NOT_FOUND
+        })?;
+
+    let limit = query.limit.unwrap_or(DEFAULT_HISTORY_LIMIT).clamp(1, 1000);
+    let reports = state.runner_repo.get_environment_history(runner_id, limit).await
+        .map_err(|e| {
+            error!("Failed to load the environment history of runner {}: {}", runner_id, e);
+            StatusCode::INTERNAL_SERVER_ERROR
+        })?;
+
+    Ok(Json(reports.into_iter().map(RunnerEnvironmentResponse::from).collect()))
+}
+
+/// Compare the latest environments of the runners of each pool and list the settings
+/// (OS, architecture, processors, library versions) they disagree on
+/// Access: Admin
+pub async fn get_environment_drift(
+    State(state): State<AppState>,
+    Extension(_admin): Extension<AdminUser>,
+) -> Result<Json<Vec<PoolDriftResponse>>, StatusCode> {
+    Ok(Json(pool_drift(&state).await?))
+}
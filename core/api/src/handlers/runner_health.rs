@@ -1,19 +1,51 @@
-use axum::{extract::{Path, State, Extension}, http::StatusCode, Json};
-use serde::Serialize;
+use axum::{extract::{Path, Query, State, Extension}, http::{header, StatusCode}, response::IntoResponse, Json};
+use serde::{Deserialize, Serialize};
 use uuid::Uuid;
 use tracing::{error, info};
+use chrono::{DateTime, Utc};
 
 use crate::state::AppState;
 use crate::middleware::auth::AdminUser;
+use crate::services::runner_health::RunnerLifecycleSummary;
+use crate::services::RunnerHealthService;
 // RunnerHealthStatus is used internally in the service
 
+/// Default number of health checks returned by the history endpoint
+const DEFAULT_HISTORY_LIMIT: i64 = 100;
+
 /// Response for runner health status
 #[derive(Debug, Serialize)]
 pub struct RunnerHealthResponse {
     pub runner_id: Uuid,
     pub status: String,
+    /// Composite score from 0 (down) to 100 (fully healthy)
+    pub score: i32,
+    pub heartbeat_age_secs: Option<i64>,
+    pub failure_rate: Option<f64>,
+    pub avg_processing_secs: Option<f64>,
+    pub jobs_sampled: i64,
     pub compatible_job_types: Vec<String>,
-    pub last_heartbeat: Option<String>,
+    pub last_heartbeat: Option<DateTime<Utc>>,
+}
+
+/// Query parameters for the health history
+#[derive(Debug, Deserialize)]
+pub struct HealthHistoryQuery {
+    /// Number of checks to return, newest first (defaults to 100)
+    pub limit: Option<i64>,
+}
+
+/// A recorded runner health check
+#[derive(Debug, Serialize)]
+pub struct RunnerHealthCheckResponse {
+    pub id: Uuid,
+    pub status: String,
+    pub score: i32,
+    pub heartbeat_age_secs: Option<i64>,
+    pub failure_rate: Option<f64>,
+    pub avg_processing_secs: Option<f64>,
+    pub jobs_sampled: i32,
+    pub checked_at: Option<DateTime<Utc>>,
 }
 
 /// Response for runner compatibility check
@@ -52,8 +84,8 @@ pub async fn check_runner_health(
             StatusCode::NOT_FOUND
         })?;
     
-    // Check health status
-    let health_status = state.runner_health_service.check_runner_health(runner_id).await
+    // Compute the composite health
+    let report = state.runner_health_service.evaluate_runner(runner_id).await
         .map_err(|e| {
             error!("Failed to check health for runner {}: {}", runner_id, e);
             StatusCode::INTERNAL_SERVER_ERROR
@@ -62,12 +94,52 @@ pub async fn check_runner_health(
     // Return runner health info
     Ok(Json(RunnerHealthResponse {
         runner_id: runner.id,
-        status: health_status.as_str().to_string(),
+        status: report.status,
+        score: report.score,
+        heartbeat_age_secs: report.heartbeat_age_secs,
+        failure_rate: report.failure_rate,
+        avg_processing_secs: report.avg_processing_secs,
+        jobs_sampled: report.jobs_sampled,
         compatible_job_types: runner.compatible_job_types.clone(),
-        last_heartbeat: runner.last_heartbeat.map(|dt| dt.and_utc().to_rfc3339()),
+        last_heartbeat: runner.last_heartbeat,
     }))
 }
 
+/// Get the recorded health checks of a runner, newest first
+/// Access: Admin
+pub async fn get_health_history(
+    State(state): State<AppState>,
+    Extension(_admin): Extension<AdminUser>,
+    Path(runner_id): Path<Uuid>,
+    Query(query): Query<HealthHistoryQuery>,
+) -> Result<Json<Vec<RunnerHealthCheckResponse>>, StatusCode> {
+    state.runner_repo.find_by_id(runner_id).await
+        .map_err(|e| {
+            error!("Failed to find runner {}: {}", runner_id, e);
+            StatusCode::NOT_FOUND
+        })?;
+    
+    let limit = query.limit.unwrap_or(DEFAULT_HISTORY_LIMIT).clamp(1, 1000);
+    let checks = state.runner_repo.get_health_history(runner_id, limit).await
+        .map_err(|e| {
+            error!("Failed to load health history for runner {}: {}", runner_id, e);
+            StatusCode::INTERNAL_SERVER_ERROR
+        })?;
+    
+    Ok(Json(checks.into_iter()
+        .map(|check| RunnerHealthCheckResponse {
+            id: check.id,
+            status: check.status,
+            score: check.score,
+            heartbeat_age_secs: check.heartbeat_age_seconds,
+            failure_rate: check.failure_rate,
+            avg_processing_secs: check.avg_processing_seconds,
+            jobs_sampled: check.jobs_sampled,
+            checked_at: check.created_at,
+        })
+        .collect()))
+}
+
 /// Check if a runner is compatible with a job type
 /// Access: Admin
 pub async fn check_compatibility(
@@ -138,3 +210,37 @@ pub async fn check_and_reassign_jobs(
     // Return number of reassigned jobs
     Ok(Json(reassigned_count))
 }
+
+/// Deactivate runners that stayed critical and delete long-inactive runners
+/// Access: Admin
+pub async fn apply_lifecycle_policy(
+    State(state): State<AppState>,
+    Extension(_admin): Extension<AdminUser>,
+) -> Result<Json<RunnerLifecycleSummary>, StatusCode> {
+    let summary = state.runner_health_service.apply_lifecycle_policy().await
+        .map_err(|e| {
+            error!("Failed to apply the runner lifecycle policy: {}", e);
+            StatusCode::INTERNAL_SERVER_ERROR
+        })?;
+    
+    info!("Runner lifecycle policy deactivated {} and deleted {} runners", summary.deactivated.len(), summary.deleted.len());
+    
+    Ok(Json(summary))
+}
+
+/// Get the health of active runners as Prometheus metrics, for the exported alerting rules
+/// Access: Admin
+pub async fn get_runner_metrics(
+    State(state): State<AppState>,
+) -> Result<impl IntoResponse, StatusCode> {
+    let reports = state.runner_health_service.evaluate_active_runners().await
+        .map_err(|e| {
+            error!("Failed to evaluate runner health: {}", e);
+            StatusCode::INTERNAL_SERVER_ERROR
+        })?;
+    
+    Ok((
+        [(header::CONTENT_TYPE, "text/plain; version=0.0.4")],
+        RunnerHealthService::render_metrics(&reports),
+    ))
+}
@@ -2,22 +2,36 @@ use axum::{extract::{Path, State, Extension}, http::StatusCode, Json};
 use serde::{Deserialize, Serialize};
 use uuid::Uuid;
 use tracing::{error, info};
-use chrono::{Utc, Duration};
+use chrono::{DateTime, Duration, Utc};
 
+use crate::request::StrictJson;
 use crate::state::AppState;
-use innosystem_common::models::runner::{NewRunner, RunnerStatus};
+use innosystem_common::models::runner::{NewRunner, RunnerEnvironment, RunnerStatus};
+use crate::handlers::runner_environments::record_environment;
 use crate::middleware::auth::AdminUser;
 
 /// Request data for registering a new runner
 #[derive(Debug, Deserialize)]
+#[serde(deny_unknown_fields)]
 pub struct RegisterRunnerRequest {
     pub name: String,
     pub description: Option<String>,
     pub compatible_job_types: Vec<String>,
+    /// Environment the runner executes jobs in (optional)
+    pub environment: Option<RunnerEnvironment>,
+}
+
+/// Request body of a runner heartbeat, which may be sent without one
+#[derive(Debug, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct HeartbeatRequest {
+    /// Environment the runner executes jobs in; stored when it changed
+    pub environment: Option<RunnerEnvironment>,
 }
 
 /// Request for updating runner capabilities
 #[derive(Debug, Deserialize)]
+#[serde(deny_unknown_fields)]
 pub struct UpdateRunnerCapabilitiesRequest {
     pub job_type_ids: Vec<Uuid>,
 }
@@ -30,9 +44,9 @@ pub struct RunnerResponse {
     pub description: Option<String>,
     pub status: String,
     pub compatible_job_types: Vec<String>,
-    pub last_heartbeat: Option<String>,
-    pub created_at: Option<String>,
-    pub updated_at: Option<String>,
+    pub last_heartbeat: Option<DateTime<Utc>>,
+    pub created_at: Option<DateTime<Utc>>,
+    pub updated_at: Option<DateTime<Utc>>,
 }
 
 /// Register a new runner
@@ -40,8 +54,27 @@ pub struct RunnerResponse {
 pub async fn register_runner(
     State(state): State<AppState>,
     Extension(_admin): Extension<AdminUser>,
-    Json(request): Json<RegisterRunnerRequest>,
+    StrictJson(request): StrictJson<RegisterRunnerRequest>,
 ) -> Result<(StatusCode, Json<RunnerResponse>), StatusCode> {
+    if let Some(environment) = &request.environment {
+        environment.validate()
+            .map_err(|reason| {
+                error!("Refusing runner environment: {}", reason);
+                StatusCode::BAD_REQUEST
+            })?;
+    }
+    
+    // Compatible job types are given by name and must exist
+    let job_types = state.job_type_repo.list_all().await
+        .map_err(|e| {
+            error!("Failed to load job types: {}", e);
+            StatusCode::INTERNAL_SERVER_ERROR
+        })?;
+    if let Some(unknown) = request.compatible_job_types.iter().find(|name| !job_types.iter().any(|job_type| &job_type.name == *name)) {
+        error!("Refusing runner compatible with unknown job type: {}", unknown);
+        return Err(StatusCode::BAD_REQUEST);
+    }
+    
     // Create a new runner
     let new_runner = NewRunner {
         id: Uuid::new_v4(),
@@ -59,6 +92,10 @@ pub async fn register_runner(
     
     info!("Registered new runner: {}", runner.id);
     
+    if let Some(environment) = request.environment {
+        record_environment(&state, &runner, environment).await?;
+    }
+    
     // Return the created runner
     Ok((StatusCode::CREATED, Json(RunnerResponse {
         id: runner.id,
@@ -66,26 +103,31 @@ pub async fn register_runner(
         description: runner.description.clone(),
         status: runner.status.as_str().to_string(),
         compatible_job_types: runner.compatible_job_types.clone(),
-        last_heartbeat: runner.last_heartbeat.map(|dt| dt.and_utc().to_rfc3339()),
-        created_at: runner.created_at.map(|dt| dt.and_utc().to_rfc3339()),
-        updated_at: runner.updated_at.map(|dt| dt.and_utc().to_rfc3339()),
+        last_heartbeat: runner.last_heartbeat,
+        created_at: runner.created_at,
+        updated_at: runner.updated_at,
     })))
 }
 
-/// Update runner heartbeat
+/// Update runner heartbeat, recording the runner's environment if it sends one
 /// Access: Public (runner itself)
 pub async fn update_heartbeat(
     State(state): State<AppState>,
     Path(id): Path<Uuid>,
+    request: Option<StrictJson<HeartbeatRequest>>,
 ) -> Result<StatusCode, StatusCode> {
     // Update the runner's heartbeat with the current timestamp
-    let now = Utc::now().naive_utc();
-    state.runner_repo.update_heartbeat(id, now).await
+    let now = Utc::now();
+    let runner = state.runner_repo.update_heartbeat(id, now).await
         .map_err(|e| {
             error!("Failed to update runner heartbeat for {}: {}", id, e);
             StatusCode::NOT_FOUND
         })?;
     
+    if let Some(StrictJson(HeartbeatRequest { environment: Some(environment) })) = request {
+        record_environment(&state, &runner, environment).await?;
+    }
+    
     // Return success status
     Ok(StatusCode::OK)
 }
@@ -111,9 +153,9 @@ pub async fn get_runner(
         description: runner.description.clone(),
         status: runner.status.as_str().to_string(),
         compatible_job_types: runner.compatible_job_types.clone(),
-        last_heartbeat: runner.last_heartbeat.map(|dt| dt.and_utc().to_rfc3339()),
-        created_at: runner.created_at.map(|dt| dt.and_utc().to_rfc3339()),
-        updated_at: runner.updated_at.map(|dt| dt.and_utc().to_rfc3339()),
+        last_heartbeat: runner.last_heartbeat,
+        created_at: runner.created_at,
+        updated_at: runner.updated_at,
     }))
 }
 
@@ -123,7 +165,7 @@ pub async fn update_capabilities(
     State(state): State<AppState>,
     Extension(_admin): Extension<AdminUser>,
     Path(id): Path<Uuid>,
-    Json(request): Json<UpdateRunnerCapabilitiesRequest>,
+    StrictJson(request): StrictJson<UpdateRunnerCapabilitiesRequest>,
 ) -> Result<Json<RunnerResponse>, StatusCode> {
     // Update the runner's capabilities
     let runner = state.runner_repo.update_capabilities(id, request.job_type_ids).await
@@ -141,9 +183,9 @@ pub async fn update_capabilities(
         description: runner.description.clone(),
         status: runner.status.as_str().to_string(),
         compatible_job_types: runner.compatible_job_types.clone(),
-        last_heartbeat: runner.last_heartbeat.map(|dt| dt.and_utc().to_rfc3339()),
-        created_at: runner.created_at.map(|dt| dt.and_utc().to_rfc3339()),
-        updated_at: runner.updated_at.map(|dt| dt.and_utc().to_rfc3339()),
+        last_heartbeat: runner.last_heartbeat,
+        created_at: runner.created_at,
+        updated_at: runner.updated_at,
     }))
 }
 
@@ -168,9 +210,9 @@ pub async fn list_all_runners(
             description: runner.description.clone(),
             status: runner.status.as_str().to_string(),
             compatible_job_types: runner.compatible_job_types.clone(),
-            last_heartbeat: runner.last_heartbeat.map(|dt| dt.and_utc().to_rfc3339()),
-            created_at: runner.created_at.map(|dt| dt.and_utc().to_rfc3339()),
-            updated_at: runner.updated_at.map(|dt| dt.and_utc().to_rfc3339()),
+            last_heartbeat: runner.last_heartbeat,
+            created_at: runner.created_at,
+            updated_at: runner.updated_at,
         })
         .collect();
     
@@ -185,7 +227,7 @@ pub async fn list_active_runners(
     Extension(_admin): Extension<AdminUser>,
 ) -> Result<Json<Vec<RunnerResponse>>, StatusCode> {
     // Define what "active" means (heartbeat within last 5 minutes)
-    let since = (Utc::now() - Duration::minutes(5)).naive_utc();
+    let since = Utc::now() - Duration::minutes(5);
     
     // Retrieve active runners
     let runners = state.runner_repo.list_active(since).await
@@ -202,9 +244,9 @@ pub async fn list_active_runners(
             description: runner.description.clone(),
             status: runner.status.as_str().to_string(),
             compatible_job_types: runner.compatible_job_types.clone(),
-            last_heartbeat: runner.last_heartbeat.map(|dt| dt.and_utc().to_rfc3339()),
-            created_at: runner.created_at.map(|dt| dt.and_utc().to_rfc3339()),
-            updated_at: runner.updated_at.map(|dt| dt.and_utc().to_rfc3339()),
+            last_heartbeat: runner.last_heartbeat,
+            created_at: runner.created_at,
+            updated_at: runner.updated_at,
         })
         .collect();
     
@@ -218,7 +260,7 @@ pub async fn set_runner_status(
     State(state): State<AppState>,
     Extension(_admin): Extension<AdminUser>,
     Path(id): Path<Uuid>,
-    Json(active): Json<bool>,
+    StrictJson(active): StrictJson<bool>,
 ) -> Result<Json<RunnerResponse>, StatusCode> {
     // Update the runner's status
     let runner = state.runner_repo.set_status(id, active).await
@@ -236,8 +278,8 @@ pub async fn set_runner_status(
         description: runner.description.clone(),
         status: runner.status.as_str().to_string(),
         compatible_job_types: runner.compatible_job_types.clone(),
-        last_heartbeat: runner.last_heartbeat.map(|dt| dt.and_utc().to_rfc3339()),
-        created_at: runner.created_at.map(|dt| dt.and_utc().to_rfc3339()),
-        updated_at: runner.updated_at.map(|dt| dt.and_utc().to_rfc3339()),
+        last_heartbeat: runner.last_heartbeat,
+        created_at: runner.created_at,
+        updated_at: runner.updated_at,
     }))
 }
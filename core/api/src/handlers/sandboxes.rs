@@ -0,0 +1,115 @@
+use axum::{
+    extract::{Extension, State},
+    http::StatusCode,
+    Json,
+};
+use serde::{Deserialize, Serialize};
+use uuid::Uuid;
+use tracing::error;
+use chrono::{DateTime, Utc};
+
+use crate::middleware::auth::ResellerUser;
+use crate::request::StrictJson;
+use crate::services::sandbox::SandboxError;
+use crate::state::AppState;
+use innosystem_common::database::TenancyMode;
+use innosystem_common::models::reseller::Reseller;
+
+/// Request data for spinning up a sandbox
+#[derive(Debug, Default, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct CreateSandboxRequest {
+    /// Name of the sandbox (optional, defaults to the reseller's name followed by "sandbox")
+    pub name: Option<String>,
+    /// Days the sandbox is kept before it is deleted with its data (optional, defaults
+    /// to the configured lifetime)
+    pub ttl_days: Option<u32>,
+}
+
+/// A sandbox tenant of a reseller
+#[derive(Debug, Serialize)]
+pub struct SandboxResponse {
+    pub id: Uuid,
+    pub name: String,
+    /// Key the sandbox authenticates with as a reseller, e.g. to create its customers
+    pub api_key: String,
+    /// Reseller the sandbox belongs to
+    pub sandbox_of: Option<Uuid>,
+    /// Schema holding the sandbox's data, when resellers are isolated
+    pub schema: Option<String>,
+    pub expires_at: Option<DateTime<Utc>>,
+    pub created_at: Option<DateTime<Utc>>,
+}
+
+impl SandboxResponse {
+    fn new(state: &AppState, sandbox: Reseller) -> Self {
+        let schema = (state.schema_resolver.mode() == TenancyMode::SchemaPerReseller)
+            .then(|| state.schema_resolver.schema_name(sandbox.id));
+        Self {
+            id: sandbox.id,
+            name: sandbox.name,
+            api_key: sandbox.api_key,
+            sandbox_of: sandbox.sandbox_of,
+            schema,
+            expires_at: sandbox.sandbox_expires_at,
+            created_at: sandbox.created_at,
+        }
+    }
+}
+
+/// The reseller the request authenticated as; admins have no sandboxes of their own
+async fn authenticated_reseller(state: &AppState, reseller: Option<Extension<ResellerUser>>) -> Result<Reseller, StatusCode> {
+    let Some(Extension(reseller)) = reseller else {
+        return Err(StatusCode::NOT_FOUND);
+    };
+    state.reseller_repo.find_by_id(reseller.id).await
+        .map_err(|e| {
+            error!("Failed to fetch reseller {}: {}", reseller.id, e);
+            StatusCode::INTERNAL_SERVER_ERROR
+        })
+}
+
+/// Spin up an isolated sandbox tenant to demo and develop against
+///
+/// The sandbox is a reseller of its own with a separate customer namespace (and its
+/// own schema when resellers are isolated). Its customers only run test jobs, with
+/// stub processors charging a play-money balance, and it is deleted with all of its
+/// data once it expires. Resellers may only have a few sandboxes at a time (409).
+/// Access: Reseller
+pub async fn create_sandbox(
+    State(state): State<AppState>,
+    reseller: Option<Extension<ResellerUser>>,
+    payload: Option<StrictJson<CreateSandboxRequest>>,
+) -> Result<(StatusCode, Json<SandboxResponse>), StatusCode> {
+    let parent = authenticated_reseller(&state, reseller).await?;
+    let payload = payload.map(|StrictJson(payload)| payload).unwrap_or_default();
+
+    let sandbox = state.sandboxes.create(&parent, payload.name, payload.ttl_days).await
+        .map_err(|e| {
+            error!("Failed to create a sandbox for reseller {}: {}", parent.id, e);
+            match e {
+                SandboxError::NestedSandbox | SandboxError::LimitReached(..) => StatusCode::CONFLICT,
+                SandboxError::InvalidTtl { .. } => StatusCode::BAD_REQUEST,
+                SandboxError::Provisioning(_) | SandboxError::Repository(_) => StatusCode::INTERNAL_SERVER_ERROR,
+            }
+        })?;
+
+    Ok((StatusCode::CREATED, Json(SandboxResponse::new(&state, sandbox))))
+}
+
+/// List the sandboxes of the authenticated reseller, newest first
+/// Access: Reseller
+pub async fn list_sandboxes(
+    State(state): State<AppState>,
+    reseller: Option<Extension<ResellerUser>>,
+) -> Result<Json<Vec<SandboxResponse>>, StatusCode> {
+    let parent = authenticated_reseller(&state, reseller).await?;
+
+    let sandboxes = state.sandboxes.list(parent.id).await
+        .map_err(|e| {
+            error!("Failed to list the sandboxes of reseller {}: {}", parent.id, e);
+            StatusCode::INTERNAL_SERVER_ERROR
+        })?;
+
+    Ok(Json(sandboxes.into_iter().map(|sandbox| SandboxResponse::new(&state, sandbox)).collect()))
+}
@@ -0,0 +1,169 @@
+use axum::{extract::{Query, State, Extension}, http::StatusCode, Json};
+use serde::{Deserialize, Serialize};
+use uuid::Uuid;
+use tracing::{error, info};
+use chrono::{DateTime, Utc};
+
+use innosystem_common::database::with_reseller;
+use innosystem_common::models::{Customer, Job, Project, Reseller};
+use crate::middleware::auth::AdminUser;
+use crate::state::AppState;
+
+/// Shortest query searched, as shorter ones match nearly everything
+const MIN_QUERY_LEN: usize = 2;
+/// Results per group unless a limit is given
+const DEFAULT_LIMIT: i64 = 10;
+/// Most results per group
+const MAX_LIMIT: i64 = 50;
+
+/// Query parameters of the admin search
+#[derive(Debug, Deserialize)]
+pub struct SearchQuery {
+    /// Text matched against names, emails and job public IDs, or the UUID of an entity
+    pub q: String,
+    /// Most results per group (defaults to 10, at most 50)
+    pub limit: Option<i64>,
+}
+
+/// A customer found by the search
+#[derive(Debug, Serialize)]
+pub struct CustomerHit {
+    pub id: Uuid,
+    pub name: String,
+    pub email: String,
+    pub reseller_id: Option<Uuid>,
+}
+
+impl From<Customer> for CustomerHit {
+    fn from(customer: Customer) -> Self {
+        Self {
+            id: customer.id,
+            name: customer.name,
+            email: customer.email,
+            reseller_id: customer.reseller_id,
+        }
+    }
+}
+
+/// A reseller found by the search
+#[derive(Debug, Serialize)]
+pub struct ResellerHit {
+    pub id: Uuid,
+    pub name: String,
+    pub email: String,
+    pub active: bool,
+}
+
+impl From<Reseller> for ResellerHit {
+    fn from(reseller: Reseller) -> Self {
+        Self {
+            id: reseller.id,
+            name: reseller.name,
+            email: reseller.email,
+            active: reseller.active,
+        }
+    }
+}
+
+/// A job found by the search
+#[derive(Debug, Serialize)]
+pub struct JobHit {
+    pub id: Uuid,
+    pub public_id: String,
+    pub customer_id: Uuid,
+    pub job_type_id: Uuid,
+    pub status: String,
+    pub created_at: Option<DateTime<Utc>>,
+}
+
+impl From<Job> for JobHit {
+    fn from(job: Job) -> Self {
+        Self {
+            id: job.id,
+            public_id: job.public_id,
+            customer_id: job.customer_id,
+            job_type_id: job.job_type_id,
+            status: job.status.as_str().to_string(),
+            created_at: job.created_at,
+        }
+    }
+}
+
+/// A project found by the search
+#[derive(Debug, Serialize)]
+pub struct ProjectHit {
+    pub id: Uuid,
+    pub name: String,
+    pub customer_id: Uuid,
+}
+
+impl From<Project> for ProjectHit {
+    fn from(project: Project) -> Self {
+        Self {
+            id: project.id,
+            name: project.name,
+            customer_id: project.customer_id,
+        }
+    }
+}
+
+/// Search results grouped by entity, best matches first within each group
+#[derive(Debug, Serialize)]
+pub struct SearchResponse {
+    pub query: String,
+    pub customers: Vec<CustomerHit>,
+    pub resellers: Vec<ResellerHit>,
+    pub jobs: Vec<JobHit>,
+    pub projects: Vec<ProjectHit>,
+}
+
+/// Search customers (name, email), resellers (name, email), jobs (public ID) and
+/// projects (name) in one call; a UUID finds the entity with that ID
+///
+/// Jobs and projects are searched in the shared schema and in every reseller schema.
+/// Access: Admin
+pub async fn search(
+    State(state): State<AppState>,
+    Extension(admin): Extension<AdminUser>,
+    Query(query): Query<SearchQuery>,
+) -> Result<Json<SearchResponse>, StatusCode> {
+    let q = query.q.trim().to_string();
+    if q.chars().count() < MIN_QUERY_LEN {
+        error!("Search query must have at least {} characters", MIN_QUERY_LEN);
+        return Err(StatusCode::BAD_REQUEST);
+    }
+    let limit = query.limit.unwrap_or(DEFAULT_LIMIT).clamp(1, MAX_LIMIT);
+    let internal_error = |e: anyhow::Error| {
+        error!("Failed to search for {:?}: {}", q, e);
+        StatusCode::INTERNAL_SERVER_ERROR
+    };
+
+    // Customers and resellers are kept in the shared schema only
+    let customers = state.search_repo.search_customers(&q, limit).await.map_err(internal_error)?;
+    let resellers = state.search_repo.search_resellers(&q, limit).await.map_err(internal_error)?;
+
+    let resolver = state.schema_resolver.clone();
+    let scopes = tokio::task::spawn_blocking(move || resolver.scopes()).await
+        .map_err(|e| internal_error(e.into()))?
+        .map_err(|e| internal_error(e.into()))?;
+    let (mut jobs, mut projects) = (Vec::new(), Vec::new());
+    for scope in scopes {
+        jobs.extend(with_reseller(scope, state.search_repo.search_jobs(&q, limit)).await.map_err(internal_error)?);
+        projects.extend(with_reseller(scope, state.search_repo.search_projects(&q, limit)).await.map_err(internal_error)?);
+    }
+    jobs.truncate(limit as usize);
+    projects.truncate(limit as usize);
+
+    info!(
+        "Admin {} searched for {:?}: {} customers, {} resellers, {} jobs, {} projects",
+        admin.id, q, customers.len(), resellers.len(), jobs.len(), projects.len()
+    );
+
+    Ok(Json(SearchResponse {
+        customers: customers.into_iter().map(CustomerHit::from).collect(),
+        resellers: resellers.into_iter().map(ResellerHit::from).collect(),
+        jobs: jobs.into_iter().map(JobHit::from).collect(),
+        projects: projects.into_iter().map(ProjectHit::from).collect(),
+        query: q,
+    }))
+}
@@ -0,0 +1,50 @@
+use axum::{extract::{Query, State, Extension}, http::StatusCode, Json};
+use serde::Deserialize;
+use tracing::error;
+
+use innosystem_common::models::security_event::{SecurityEvent, SecurityEventKind};
+use crate::middleware::auth::AdminUser;
+use crate::state::AppState;
+
+/// Number of events returned when no limit is given
+const DEFAULT_EVENT_LIMIT: usize = 100;
+
+/// Query parameters for the security event listing
+#[derive(Debug, Deserialize)]
+pub struct SecurityEventQuery {
+    /// Number of events to return, newest first (defaults to 100)
+    pub limit: Option<usize>,
+    /// Only events of this kind: `lockout`, `key_enumeration` or `distributed_attack`
+    pub kind: Option<String>,
+}
+
+/// List the most recent lockouts and brute-force patterns noticed in failed
+/// authentications
+/// Access: Admin
+pub async fn list_security_events(
+    State(state): State<AppState>,
+    Extension(_admin): Extension<AdminUser>,
+    Query(query): Query<SecurityEventQuery>,
+) -> Result<Json<Vec<SecurityEvent>>, StatusCode> {
+    let kind = match query.kind.as_deref() {
+        Some(kind) => Some(SecurityEventKind::parse(kind).ok_or_else(|| {
+            error!("Unknown security event kind: {}", kind);
+            StatusCode::BAD_REQUEST
+        })?),
+        None => None,
+    };
+    let limit = query.limit.unwrap_or(DEFAULT_EVENT_LIMIT).clamp(1, state.security_events.config().max_events);
+
+    // Filtered listings look through every kept event
+    let fetched = if kind.is_some() { state.security_events.config().max_events } else { limit };
+    let events = state.security_events.recent_events(fetched).await
+        .map_err(|e| {
+            error!("Failed to list security events: {}", e);
+            StatusCode::SERVICE_UNAVAILABLE
+        })?;
+
+    Ok(Json(events.into_iter()
+        .filter(|event| kind.is_none_or(|kind| event.kind == kind))
+        .take(limit)
+        .collect()))
+}
@@ -0,0 +1,127 @@
+use axum::{extract::{Path, Query, State, Extension}, http::StatusCode, Json};
+use serde::{Deserialize, Serialize};
+use uuid::Uuid;
+use tracing::{error, info};
+use chrono::{DateTime, Utc};
+
+use innosystem_common::models::spending_alert::SpendingAlert;
+use crate::middleware::auth::{verify_reseller_access, AdminUser, CustomerUser, ResellerUser};
+use crate::state::AppState;
+
+/// Number of alerts returned when no limit is given
+const DEFAULT_ALERT_LIMIT: i64 = 100;
+
+/// Query parameters for alert listings
+#[derive(Debug, Deserialize)]
+pub struct SpendingAlertQuery {
+    /// Number of alerts to return, newest first (defaults to 100)
+    pub limit: Option<i64>,
+}
+
+impl SpendingAlertQuery {
+    fn limit(&self) -> i64 {
+        self.limit.unwrap_or(DEFAULT_ALERT_LIMIT).clamp(1, 1000)
+    }
+}
+
+/// Response data for an anomaly detected in a customer's spending pattern
+#[derive(Debug, Serialize)]
+pub struct SpendingAlertResponse {
+    pub id: Uuid,
+    pub customer_id: Uuid,
+    pub reseller_id: Option<Uuid>,
+    /// `spend_spike` or `failure_rate_jump`
+    pub kind: String,
+    /// Spend in cents or failure rate observed within the window
+    pub observed: f64,
+    /// The same measure expected from the customer's history
+    pub baseline: f64,
+    pub window_start: DateTime<Utc>,
+    pub window_end: DateTime<Utc>,
+    pub created_at: DateTime<Utc>,
+}
+
+impl From<SpendingAlert> for SpendingAlertResponse {
+    fn from(alert: SpendingAlert) -> Self {
+        Self {
+            id: alert.id,
+            customer_id: alert.customer_id,
+            reseller_id: alert.reseller_id,
+            kind: alert.kind,
+            observed: alert.observed,
+            baseline: alert.baseline,
+            window_start: alert.window_start,
+            window_end: alert.window_end,
+            created_at: alert.created_at,
+        }
+    }
+}
+
+/// List the spending alerts raised for the authenticated customer
+/// Access: Customer
+pub async fn list_customer_alerts(
+    State(state): State<AppState>,
+    Extension(customer): Extension<CustomerUser>,
+    Query(query): Query<SpendingAlertQuery>,
+) -> Result<Json<Vec<SpendingAlertResponse>>, StatusCode> {
+    let alerts = state.spending_alert_repo.list_by_customer(customer.id, query.limit()).await
+        .map_err(|e| {
+            error!("Failed to list spending alerts of customer {}: {}", customer.id, e);
+            StatusCode::INTERNAL_SERVER_ERROR
+        })?;
+
+    Ok(Json(alerts.into_iter().map(SpendingAlertResponse::from).collect()))
+}
+
+/// List the spending alerts raised for the customers of a reseller
+/// Access: Reseller
+pub async fn list_reseller_alerts(
+    State(state): State<AppState>,
+    Path(reseller_id): Path<Uuid>,
+    reseller: Option<Extension<ResellerUser>>,
+    Query(query): Query<SpendingAlertQuery>,
+) -> Result<Json<Vec<SpendingAlertResponse>>, StatusCode> {
+    verify_reseller_access(Some(reseller_id), &reseller)?;
+
+    let alerts = state.spending_alert_repo.list_by_reseller(reseller_id, query.limit()).await
+        .map_err(|e| {
+            error!("Failed to list spending alerts of reseller {}: {}", reseller_id, e);
+            StatusCode::INTERNAL_SERVER_ERROR
+        })?;
+
+    Ok(Json(alerts.into_iter().map(SpendingAlertResponse::from).collect()))
+}
+
+/// List the most recent spending alerts of all customers
+/// Access: Admin
+pub async fn list_alerts(
+    State(state): State<AppState>,
+    Extension(_admin): Extension<AdminUser>,
+    Query(query): Query<SpendingAlertQuery>,
+) -> Result<Json<Vec<SpendingAlertResponse>>, StatusCode> {
+    let alerts = state.spending_alert_repo.list_recent(query.limit()).await
+        .map_err(|e| {
+            error!("Failed to list spending alerts: {}", e);
+            StatusCode::INTERNAL_SERVER_ERROR
+        })?;
+
+    Ok(Json(alerts.into_iter().map(SpendingAlertResponse::from).collect()))
+}
+
+/// Compare every customer's recent activity to their baseline now, instead of
+/// waiting for the periodic detection, and return the alerts raised
+/// Access: Admin
+pub async fn detect_anomalies(
+    State(state): State<AppState>,
+    Extension(_admin): Extension<AdminUser>,
+) -> Result<Json<Vec<SpendingAlertResponse>>, StatusCode> {
+    let alerts = state.spending_anomaly_service.detect().await
+        .map_err(|e| {
+            error!("Failed to detect spending anomalies: {}", e);
+            StatusCode::INTERNAL_SERVER_ERROR
+        })?;
+
+    info!("Raised {} spending alerts", alerts.len());
+
+    Ok(Json(alerts.into_iter().map(SpendingAlertResponse::from).collect()))
+}
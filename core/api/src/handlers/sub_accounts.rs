@@ -0,0 +1,300 @@
+use axum::{extract::{Path, Query, State, Extension}, http::StatusCode, Json};
+use chrono::{DateTime, Datelike, NaiveDate, NaiveTime, Utc};
+use serde::{Deserialize, Serialize};
+use uuid::Uuid;
+use tracing::{error, info, warn};
+
+use crate::handlers::customers::customer_timezone;
+use crate::middleware::auth::CustomerUser;
+use crate::request::StrictJson;
+use crate::state::AppState;
+use innosystem_common::models::customer::{Customer, NewCustomer};
+use innosystem_common::repositories::job::CustomerSpend;
+use innosystem_common::timezone::{self, Tz};
+
+/// Request data for creating a sub-account
+#[derive(Debug, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct CreateSubAccountRequest {
+    pub name: String,
+    pub email: String,
+    /// Monthly spending limit in cents (optional, unlimited if not set)
+    pub budget_cents: Option<i32>,
+}
+
+/// Request data for changing the budget of a sub-account
+#[derive(Debug, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct UpdateBudgetRequest {
+    /// Monthly spending limit in cents, or null to remove the limit
+    pub budget_cents: Option<i32>,
+}
+
+/// Query parameters for the hierarchy report
+#[derive(Debug, Deserialize)]
+pub struct HierarchyReportQuery {
+    /// First day of the period (YYYY-MM-DD in the customer's time zone, defaults to the first
+    /// day of the current month)
+    pub since: Option<NaiveDate>,
+}
+
+/// Response data for a sub-account
+#[derive(Debug, Serialize)]
+pub struct SubAccountResponse {
+    pub id: Uuid,
+    pub parent_id: Uuid,
+    pub name: String,
+    pub email: String,
+    /// API key the sub-account authenticates with
+    pub api_key: Option<String>,
+    pub budget_cents: Option<i32>,
+    /// Spend of the current month in cents
+    pub spent_this_month_cents: i64,
+    pub created_at: Option<DateTime<Utc>>,
+}
+
+impl SubAccountResponse {
+    fn new(customer: Customer, spent_this_month_cents: i64) -> Self {
+        Self {
+            id: customer.id,
+            parent_id: customer.parent_id.unwrap_or_default(),
+            name: customer.name,
+            email: customer.email,
+            api_key: customer.api_key,
+            budget_cents: customer.budget_cents,
+            spent_this_month_cents,
+            created_at: customer.created_at,
+        }
+    }
+}
+
+/// Jobs and spend of one account in the hierarchy report
+#[derive(Debug, Serialize)]
+pub struct AccountSpendResponse {
+    pub customer_id: Uuid,
+    pub name: String,
+    /// Whether this is the parent account itself
+    pub is_parent: bool,
+    pub jobs: i64,
+    pub cost_cents: i64,
+    pub budget_cents: Option<i32>,
+}
+
+/// Jobs and spend of a customer and its sub-accounts, all paid from the parent's wallet
+#[derive(Debug, Serialize)]
+pub struct HierarchyReportResponse {
+    pub parent_id: Uuid,
+    pub since: DateTime<Utc>,
+    pub accounts: Vec<AccountSpendResponse>,
+    pub total_jobs: i64,
+    pub total_cost_cents: i64,
+    /// Balance of the shared wallet, if it exists
+    pub wallet_balance_cents: Option<i32>,
+}
+
+/// Start of the current calendar month in the time zone, the period budgets apply to
+///
+/// Budgets follow the months of the parent customer's time zone.
+fn current_month_start(tz: Tz) -> DateTime<Utc> {
+    let today = timezone::to_local(Utc::now(), tz).date();
+    timezone::to_utc(today.with_day(1).unwrap_or(today).and_time(NaiveTime::MIN), tz)
+}
+
+/// Spend per customer since `since`, with zero spend for customers without jobs
+async fn load_spend(state: &AppState, customer_ids: Vec<Uuid>, since: DateTime<Utc>) -> Result<Vec<CustomerSpend>, StatusCode> {
+    let spend = state.job_repo.get_spend_by_customer(customer_ids.clone(), since).await
+        .map_err(|e| {
+            error!("Failed to load spend of customers {:?}: {}", customer_ids, e);
+            StatusCode::INTERNAL_SERVER_ERROR
+        })?;
+
+    Ok(customer_ids.into_iter()
+        .map(|id| spend.iter().find(|s| s.customer_id == id).cloned().unwrap_or_else(|| CustomerSpend::new(id)))
+        .collect())
+}
+
+/// Load a sub-account and verify it belongs to the authenticated customer
+async fn find_owned_sub_account(state: &AppState, customer: &CustomerUser, id: Uuid) -> Result<Customer, StatusCode> {
+    let sub_account = state.customer_repo.find_by_id(id).await
+        .map_err(|e| {
+            error!("Failed to find sub-account {}: {}", id, e);
+            StatusCode::NOT_FOUND
+        })?;
+
+    if sub_account.parent_id != Some(customer.id) {
+        return Err(StatusCode::FORBIDDEN);
+    }
+
+    Ok(sub_account)
+}
+
+/// Reject a job that would take a sub-account over its monthly budget
+pub(crate) async fn check_budget(state: &AppState, customer_id: Uuid, estimated_cost_cents: i32) -> Result<(), StatusCode> {
+    let customer = state.customer_repo.find_by_id(customer_id).await
+        .map_err(|e| {
+            error!("Failed to find customer {}: {}", customer_id, e);
+            StatusCode::INTERNAL_SERVER_ERROR
+        })?;
+
+    let Some(budget_cents) = customer.budget_cents else {
+        return Ok(());
+    };
+
+    let tz = customer_timezone(state, customer.billing_customer_id()).await;
+    let spent = load_spend(state, vec![customer_id], current_month_start(tz)).await?
+        .first()
+        .map_or(0, |spend| spend.cost_cents);
+
+    if spent + estimated_cost_cents as i64 > budget_cents as i64 {
+        warn!(
+            "Rejected job of sub-account {}: {} cents spent this month, {} cents estimated, budget {} cents",
+            customer_id, spent, estimated_cost_cents, budget_cents
+        );
+        return Err(StatusCode::PAYMENT_REQUIRED);
+    }
+
+    Ok(())
+}
+
+/// Create a sub-account with its own API key, billed to the authenticated customer
+/// Access: Customer (not a sub-account)
+pub async fn create_sub_account(
+    State(state): State<AppState>,
+    Extension(customer): Extension<CustomerUser>,
+    StrictJson(request): StrictJson<CreateSubAccountRequest>,
+) -> Result<(StatusCode, Json<SubAccountResponse>), StatusCode> {
+    // Only one level: sub-accounts cannot have sub-accounts of their own
+    if customer.parent_id.is_some() {
+        error!("Sub-account {} cannot create sub-accounts", customer.id);
+        return Err(StatusCode::FORBIDDEN);
+    }
+
+    if request.name.trim().is_empty() || request.budget_cents.is_some_and(|budget| budget < 0) {
+        error!("Invalid sub-account request from customer {}", customer.id);
+        return Err(StatusCode::BAD_REQUEST);
+    }
+
+    let new_customer = NewCustomer {
+        id: Uuid::new_v4(),
+        name: request.name,
+        email: request.email,
+        reseller_id: customer.reseller_id,
+        api_key: Some(Customer::generate_api_key()),
+        parent_id: Some(customer.id),
+        budget_cents: request.budget_cents,
+    };
+
+    let sub_account = state.customer_repo.create(new_customer).await
+        .map_err(|e| {
+            error!("Failed to create sub-account for customer {}: {}", customer.id, e);
+            StatusCode::INTERNAL_SERVER_ERROR
+        })?;
+
+    info!("Created sub-account {} for customer {}", sub_account.id, customer.id);
+
+    Ok((StatusCode::CREATED, Json(SubAccountResponse::new(sub_account, 0))))
+}
+
+/// List the sub-accounts of the authenticated customer with their spend this month
+/// Access: Customer
+pub async fn list_sub_accounts(
+    State(state): State<AppState>,
+    Extension(customer): Extension<CustomerUser>,
+) -> Result<Json<Vec<SubAccountResponse>>, StatusCode> {
+    let sub_accounts = state.customer_repo.find_by_parent_id(customer.id).await
+        .map_err(|e| {
+            error!("Failed to list sub-accounts of customer {}: {}", customer.id, e);
+            StatusCode::INTERNAL_SERVER_ERROR
+        })?;
+
+    let ids = sub_accounts.iter().map(|sub_account| sub_account.id).collect();
+    let tz = customer_timezone(&state, customer.id).await;
+    let spend = load_spend(&state, ids, current_month_start(tz)).await?;
+
+    Ok(Json(sub_accounts.into_iter()
+        .zip(spend)
+        .map(|(sub_account, spend)| SubAccountResponse::new(sub_account, spend.cost_cents))
+        .collect()))
+}
+
+/// Set or remove the monthly budget of a sub-account
+/// Access: Sub-account's parent Customer
+pub async fn update_sub_account_budget(
+    State(state): State<AppState>,
+    Extension(customer): Extension<CustomerUser>,
+    Path(id): Path<Uuid>,
+    StrictJson(request): StrictJson<UpdateBudgetRequest>,
+) -> Result<Json<SubAccountResponse>, StatusCode> {
+    if request.budget_cents.is_some_and(|budget| budget < 0) {
+        return Err(StatusCode::BAD_REQUEST);
+    }
+
+    find_owned_sub_account(&state, &customer, id).await?;
+
+    let sub_account = state.customer_repo.set_budget(id, request.budget_cents).await
+        .map_err(|e| {
+            error!("Failed to set budget of sub-account {}: {}", id, e);
+            StatusCode::INTERNAL_SERVER_ERROR
+        })?;
+
+    let tz = customer_timezone(&state, customer.id).await;
+    let spent = load_spend(&state, vec![id], current_month_start(tz)).await?
+        .first()
+        .map_or(0, |spend| spend.cost_cents);
+
+    info!("Set budget of sub-account {} to {:?} cents", id, request.budget_cents);
+    Ok(Json(SubAccountResponse::new(sub_account, spent)))
+}
+
+/// Report jobs and spend of the authenticated customer and its sub-accounts
+/// Access: Customer
+pub async fn get_hierarchy_report(
+    State(state): State<AppState>,
+    Extension(customer): Extension<CustomerUser>,
+    Query(query): Query<HierarchyReportQuery>,
+) -> Result<Json<HierarchyReportResponse>, StatusCode> {
+    let tz = customer_timezone(&state, customer.id).await;
+    let since = query.since
+        .map(|date| timezone::to_utc(date.and_time(NaiveTime::MIN), tz))
+        .unwrap_or_else(|| current_month_start(tz));
+
+    let parent = state.customer_repo.find_by_id(customer.id).await
+        .map_err(|e| {
+            error!("Failed to find customer {}: {}", customer.id, e);
+            StatusCode::INTERNAL_SERVER_ERROR
+        })?;
+    let sub_accounts = state.customer_repo.find_by_parent_id(customer.id).await
+        .map_err(|e| {
+            error!("Failed to list sub-accounts of customer {}: {}", customer.id, e);
+            StatusCode::INTERNAL_SERVER_ERROR
+        })?;
+
+    let accounts: Vec<Customer> = std::iter::once(parent).chain(sub_accounts).collect();
+    let ids = accounts.iter().map(|account| account.id).collect();
+    let spend = load_spend(&state, ids, since).await?;
+
+    let accounts: Vec<AccountSpendResponse> = accounts.into_iter()
+        .zip(spend)
+        .map(|(account, spend)| AccountSpendResponse {
+            customer_id: account.id,
+            is_parent: account.id == customer.id,
+            name: account.name,
+            jobs: spend.jobs,
+            cost_cents: spend.cost_cents,
+            budget_cents: account.budget_cents,
+        })
+        .collect();
+
+    let wallet_balance_cents = state.wallet_repo.find_by_customer_id(customer.id).await
+        .ok()
+        .map(|wallet| wallet.balance_cents);
+
+    Ok(Json(HierarchyReportResponse {
+        parent_id: customer.id,
+        since,
+        total_jobs: accounts.iter().map(|account| account.jobs).sum(),
+        total_cost_cents: accounts.iter().map(|account| account.cost_cents).sum(),
+        accounts,
+        wallet_balance_cents,
+    }))
+}
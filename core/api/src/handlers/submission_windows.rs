@@ -0,0 +1,244 @@
+use axum::{extract::{Path, State, Extension}, http::StatusCode, Json};
+use chrono::{DateTime, NaiveTime, Utc};
+use serde::{Deserialize, Serialize};
+use uuid::Uuid;
+use tracing::{error, info};
+
+use crate::handlers::customers::customer_timezone;
+use crate::middleware::auth::{verify_reseller_access, CustomerUser, ResellerUser};
+use crate::request::StrictJson;
+use crate::state::AppState;
+use innosystem_common::models::job::Job;
+use innosystem_common::models::submission_window::{NewSubmissionWindow, SubmissionWindow};
+
+/// Request data for creating a submission window
+#[derive(Debug, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct CreateSubmissionWindowRequest {
+    /// Job type the window restricts
+    pub job_type_id: Uuid,
+    /// Opening time in the customer's time zone (`HH:MM` or `HH:MM:SS`)
+    pub start_time: String,
+    /// Closing time in the customer's time zone; before `start_time` the window wraps past midnight
+    pub end_time: String,
+}
+
+/// Response data for a submission window
+#[derive(Debug, Serialize)]
+pub struct SubmissionWindowResponse {
+    pub id: Uuid,
+    pub job_type_id: Uuid,
+    pub customer_id: Option<Uuid>,
+    pub reseller_id: Option<Uuid>,
+    pub start_time: String,
+    pub end_time: String,
+    pub created_at: Option<DateTime<Utc>>,
+}
+
+impl From<SubmissionWindow> for SubmissionWindowResponse {
+    fn from(window: SubmissionWindow) -> Self {
+        Self {
+            id: window.id,
+            job_type_id: window.job_type_id,
+            customer_id: window.customer_id,
+            reseller_id: window.reseller_id,
+            start_time: window.start_time.format("%H:%M:%S").to_string(),
+            end_time: window.end_time.format("%H:%M:%S").to_string(),
+            created_at: window.created_at,
+        }
+    }
+}
+
+/// Parse a time of day given as `HH:MM` or `HH:MM:SS`
+fn parse_time(value: &str) -> Option<NaiveTime> {
+    NaiveTime::parse_from_str(value, "%H:%M:%S").ok()
+        .or_else(|| NaiveTime::parse_from_str(value, "%H:%M").ok())
+}
+
+/// Validate a window request and build the window for its owner
+async fn new_window(
+    state: &AppState,
+    request: CreateSubmissionWindowRequest,
+    customer_id: Option<Uuid>,
+    reseller_id: Option<Uuid>,
+) -> Result<NewSubmissionWindow, StatusCode> {
+    let (Some(start_time), Some(end_time)) = (parse_time(&request.start_time), parse_time(&request.end_time)) else {
+        error!("Invalid submission window times: {} - {}", request.start_time, request.end_time);
+        return Err(StatusCode::BAD_REQUEST);
+    };
+    if start_time == end_time {
+        error!("Submission window must not start and end at the same time");
+        return Err(StatusCode::BAD_REQUEST);
+    }
+
+    state.job_type_repo.find_by_id(request.job_type_id).await
+        .map_err(|e| {
+            error!("Failed to find job type {}: {}", request.job_type_id, e);
+            StatusCode::BAD_REQUEST
+        })?;
+
+    Ok(NewSubmissionWindow {
+        id: Uuid::new_v4(),
+        job_type_id: request.job_type_id,
+        customer_id,
+        reseller_id,
+        start_time,
+        end_time,
+    })
+}
+
+async fn create_window(state: &AppState, window: NewSubmissionWindow) -> Result<SubmissionWindow, StatusCode> {
+    let window = state.submission_window_repo.create(window).await
+        .map_err(|e| {
+            error!("Failed to create submission window: {}", e);
+            StatusCode::INTERNAL_SERVER_ERROR
+        })?;
+
+    info!(
+        "Created submission window {} for job type {} ({} - {} local time)",
+        window.id, window.job_type_id, window.start_time, window.end_time
+    );
+    Ok(window)
+}
+
+async fn delete_window(state: &AppState, id: Uuid) -> Result<StatusCode, StatusCode> {
+    state.submission_window_repo.delete(id).await
+        .map_err(|e| {
+            error!("Failed to delete submission window {}: {}", id, e);
+            StatusCode::INTERNAL_SERVER_ERROR
+        })?;
+
+    info!("Deleted submission window: {}", id);
+    Ok(StatusCode::NO_CONTENT)
+}
+
+async fn find_window(state: &AppState, id: Uuid) -> Result<SubmissionWindow, StatusCode> {
+    state.submission_window_repo.find_by_id(id).await
+        .map_err(|e| {
+            error!("Failed to find submission window {}: {}", id, e);
+            StatusCode::NOT_FOUND
+        })
+}
+
+/// Decide when a new job may enter the queue: None to queue it now, otherwise the
+/// next opening of the windows that apply to its customer and job type
+pub(crate) async fn release_time(state: &AppState, job: &Job) -> Result<Option<DateTime<Utc>>, StatusCode> {
+    let customer = state.customer_repo.find_by_id(job.customer_id).await
+        .map_err(|e| {
+            error!("Failed to find customer {}: {}", job.customer_id, e);
+            StatusCode::INTERNAL_SERVER_ERROR
+        })?;
+
+    let windows = state.submission_window_repo.find_applicable(job.job_type_id, customer.id, customer.reseller_id).await
+        .map_err(|e| {
+            error!("Failed to load submission windows of customer {}: {}", customer.id, e);
+            StatusCode::INTERNAL_SERVER_ERROR
+        })?;
+
+    if windows.is_empty() {
+        return Ok(None);
+    }
+
+    // Windows are wall-clock hours of the customer, so they move with its time zone
+    let tz = customer_timezone(state, customer.id).await;
+    Ok(SubmissionWindow::release_time(&windows, Utc::now(), tz))
+}
+
+/// Restrict when the authenticated customer's jobs of a type are queued
+/// Access: Customer
+pub async fn create_customer_window(
+    State(state): State<AppState>,
+    Extension(customer): Extension<CustomerUser>,
+    StrictJson(request): StrictJson<CreateSubmissionWindowRequest>,
+) -> Result<(StatusCode, Json<SubmissionWindowResponse>), StatusCode> {
+    let window = new_window(&state, request, Some(customer.id), None).await?;
+    let window = create_window(&state, window).await?;
+    Ok((StatusCode::CREATED, Json(window.into())))
+}
+
+/// List the submission windows of the authenticated customer
+/// Access: Customer
+pub async fn list_customer_windows(
+    State(state): State<AppState>,
+    Extension(customer): Extension<CustomerUser>,
+) -> Result<Json<Vec<SubmissionWindowResponse>>, StatusCode> {
+    let windows = state.submission_window_repo.find_by_customer_id(customer.id).await
+        .map_err(|e| {
+            error!("Failed to list submission windows of customer {}: {}", customer.id, e);
+            StatusCode::INTERNAL_SERVER_ERROR
+        })?;
+
+    Ok(Json(windows.into_iter().map(SubmissionWindowResponse::from).collect()))
+}
+
+/// Delete a submission window of the authenticated customer
+/// Access: Window's Customer
+pub async fn delete_customer_window(
+    State(state): State<AppState>,
+    Extension(customer): Extension<CustomerUser>,
+    Path(id): Path<Uuid>,
+) -> Result<StatusCode, StatusCode> {
+    let window = find_window(&state, id).await?;
+    if window.customer_id != Some(customer.id) {
+        return Err(StatusCode::FORBIDDEN);
+    }
+
+    delete_window(&state, id).await
+}
+
+/// Restrict when jobs of a type are queued for all customers of a reseller
+/// without windows of their own
+/// Access: Reseller
+pub async fn create_reseller_window(
+    State(state): State<AppState>,
+    Path(reseller_id): Path<Uuid>,
+    reseller: Option<Extension<ResellerUser>>,
+    StrictJson(request): StrictJson<CreateSubmissionWindowRequest>,
+) -> Result<(StatusCode, Json<SubmissionWindowResponse>), StatusCode> {
+    verify_reseller_access(Some(reseller_id), &reseller)?;
+
+    state.reseller_repo.find_by_id(reseller_id).await
+        .map_err(|e| {
+            error!("Failed to find reseller {}: {}", reseller_id, e);
+            StatusCode::NOT_FOUND
+        })?;
+
+    let window = new_window(&state, request, None, Some(reseller_id)).await?;
+    let window = create_window(&state, window).await?;
+    Ok((StatusCode::CREATED, Json(window.into())))
+}
+
+/// List the submission windows of a reseller
+/// Access: Reseller
+pub async fn list_reseller_windows(
+    State(state): State<AppState>,
+    Path(reseller_id): Path<Uuid>,
+    reseller: Option<Extension<ResellerUser>>,
+) -> Result<Json<Vec<SubmissionWindowResponse>>, StatusCode> {
+    verify_reseller_access(Some(reseller_id), &reseller)?;
+
+    let windows = state.submission_window_repo.find_by_reseller_id(reseller_id).await
+        .map_err(|e| {
+            error!("Failed to list submission windows of reseller {}: {}", reseller_id, e);
+            StatusCode::INTERNAL_SERVER_ERROR
+        })?;
+
+    Ok(Json(windows.into_iter().map(SubmissionWindowResponse::from).collect()))
+}
+
+/// Delete a submission window of a reseller
+/// Access: Reseller
+pub async fn delete_reseller_window(
+    State(state): State<AppState>,
+    Path((reseller_id, id)): Path<(Uuid, Uuid)>,
+    reseller: Option<Extension<ResellerUser>>,
+) -> Result<StatusCode, StatusCode> {
+    verify_reseller_access(Some(reseller_id), &reseller)?;
+
+    let window = find_window(&state, id).await?;
+    if window.reseller_id != Some(reseller_id) {
+        return Err(StatusCode::NOT_FOUND);
+    }
+
+    delete_window(&state, id).await
+}
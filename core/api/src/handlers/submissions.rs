@@ -0,0 +1,282 @@
+use axum::{
+    extract::{Path, State, Extension},
+    http::StatusCode,
+    Json,
+};
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use uuid::Uuid;
+use tracing::{error, info, warn};
+
+use crate::handlers::jobs::{job_from_request, submit_job, CreateJobRequest, JobResponse};
+use crate::middleware::auth::CustomerUser;
+use crate::request::{self, StrictJson};
+use crate::state::AppState;
+use innosystem_common::models::job::{JobStatus, PriorityLevel};
+use innosystem_common::models::job_import::ImportStatus;
+use innosystem_common::models::submission::{NewSubmission, Submission, SubmissionSource, SubmissionSummary};
+
+/// Maximum number of jobs submitted in one batch; larger sets go through a file import
+const MAX_BATCH_JOBS: usize = 100;
+
+/// Request data for submitting several jobs at once
+#[derive(Debug, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct SubmitBatchRequest {
+    /// Jobs of the batch, 1 to 100
+    pub jobs: Vec<BatchJobRequest>,
+}
+
+/// One job of a batch, submitted for the authenticated customer
+#[derive(Debug, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct BatchJobRequest {
+    pub job_type_id: Uuid,
+    /// Priority level, 0 (low) to 3 (critical) or its name (optional, defaults like a
+    /// single job's)
+    #[serde(default, deserialize_with = "request::optional_priority")]
+    pub priority: Option<PriorityLevel>,
+    pub input_data: serde_json::Value,
+    /// Deadline for starting the job (optional)
+    pub expires_at: Option<DateTime<Utc>>,
+    /// Customer's own reference (optional)
+    pub customer_reference: Option<String>,
+    /// Customer's own metadata as a JSON object (optional)
+    pub customer_metadata: Option<serde_json::Value>,
+}
+
+impl SubmitBatchRequest {
+    pub fn validate(&self) -> Result<(), String> {
+        if self.jobs.is_empty() {
+            return Err("A batch needs at least one job".to_string());
+        }
+        if self.jobs.len() > MAX_BATCH_JOBS {
+            return Err(format!("A batch holds at most {} jobs, got {}", MAX_BATCH_JOBS, self.jobs.len()));
+        }
+        Ok(())
+    }
+}
+
+/// Job of a batch that was not accepted, and why
+#[derive(Debug, Serialize)]
+pub struct RejectedBatchJob {
+    /// Position of the job in the request, from 0
+    pub index: usize,
+    pub reason: String,
+}
+
+/// Response data for a submitted batch
+#[derive(Debug, Serialize)]
+pub struct SubmitBatchResponse {
+    /// Submission the accepted jobs belong to
+    pub submission_id: Uuid,
+    pub jobs: Vec<JobResponse>,
+    pub rejected: Vec<RejectedBatchJob>,
+}
+
+/// Response data for a submission with where its jobs stand
+#[derive(Debug, Serialize)]
+pub struct SubmissionResponse {
+    pub id: Uuid,
+    pub customer_id: Uuid,
+    /// `batch` or `import`
+    pub source: String,
+    pub created_at: DateTime<Utc>,
+    pub cancelled_at: Option<DateTime<Utc>>,
+    /// Whether every job of the submission finished
+    pub finished: bool,
+    #[serde(flatten)]
+    pub summary: SubmissionSummary,
+}
+
+impl SubmissionResponse {
+    fn new(submission: Submission, summary: SubmissionSummary) -> Self {
+        Self {
+            id: submission.id,
+            customer_id: submission.customer_id,
+            source: submission.source,
+            created_at: submission.created_at,
+            cancelled_at: submission.cancelled_at,
+            finished: summary.is_finished(),
+            summary,
+        }
+    }
+}
+
+/// Response data for a cancelled submission
+#[derive(Debug, Serialize)]
+pub struct CancelSubmissionResponse {
+    #[serde(flatten)]
+    pub submission: SubmissionResponse,
+    /// Jobs this request cancelled
+    pub cancelled_jobs: usize,
+    /// Reserved funds released for them
+    pub released_cents: i64,
+}
+
+/// Load a submission and verify it belongs to the authenticated customer
+async fn find_owned_submission(state: &AppState, customer: &CustomerUser, id: Uuid) -> Result<Submission, StatusCode> {
+    let submission = state.submission_repo.find_by_id(id).await
+        .map_err(|e| {
+            error!("Failed to find submission {}: {}", id, e);
+            StatusCode::NOT_FOUND
+        })?;
+
+    if submission.customer_id != customer.id {
+        return Err(StatusCode::FORBIDDEN);
+    }
+
+    Ok(submission)
+}
+
+/// Submit several jobs at once as one submission, tracked and cancelled together
+/// through `/submissions/{id}`
+///
+/// Every job is checked up front and the whole batch refused (400) if one is invalid.
+/// Jobs refused at submission, e.g. by a saturated queue or a disabled feature, are
+/// listed under `rejected` with their reason while the others are accepted.
+/// Access: Customer
+pub async fn submit_batch(
+    State(state): State<AppState>,
+    Extension(customer): Extension<CustomerUser>,
+    StrictJson(request): StrictJson<SubmitBatchRequest>,
+) -> Result<(StatusCode, Json<SubmitBatchResponse>), StatusCode> {
+    if let Err(reason) = request.validate() {
+        error!("Refusing batch: {}", reason);
+        return Err(StatusCode::BAD_REQUEST);
+    }
+
+    let mut jobs = Vec::with_capacity(request.jobs.len());
+    for item in request.jobs {
+        jobs.push(job_from_request(
+            &state,
+            CreateJobRequest {
+                customer_id: customer.id,
+                job_type_id: item.job_type_id,
+                priority: item.priority,
+                input_data: item.input_data,
+                expires_at: item.expires_at,
+                customer_reference: item.customer_reference,
+                customer_metadata: item.customer_metadata,
+            },
+            Some(Extension(customer.clone())),
+        ).await?);
+    }
+
+    let submission = state.submission_repo.create(NewSubmission::new(Uuid::new_v4(), customer.id, SubmissionSource::Batch))
+        .await
+        .map_err(|e| {
+            error!("Failed to create submission for customer {}: {}", customer.id, e);
+            StatusCode::INTERNAL_SERVER_ERROR
+        })?;
+
+    let mut accepted = Vec::with_capacity(jobs.len());
+    let mut rejected = Vec::new();
+    for (index, (mut job, priority_source)) in jobs.into_iter().enumerate() {
+        job.submission_id = Some(submission.id);
+        match submit_job(&state, job).await {
+            Ok(response) => accepted.push(JobResponse { priority_source: Some(priority_source), ..response }),
+            Err(e) => {
+                warn!("Failed to submit job {} of submission {}: {}", index, submission.id, e);
+                rejected.push(RejectedBatchJob { index, reason: e.to_string() });
+            }
+        }
+    }
+
+    info!(
+        "Accepted submission {} for customer {}: {} jobs submitted, {} rejected",
+        submission.id, customer.id, accepted.len(), rejected.len()
+    );
+
+    Ok((StatusCode::CREATED, Json(SubmitBatchResponse {
+        submission_id: submission.id,
+        jobs: accepted,
+        rejected,
+    })))
+}
+
+/// Get a submission with its jobs counted by status and what they cost so far
+///
+/// The ID of a file import is the ID of the submission of its jobs.
+/// Access: Submission's Customer
+pub async fn get_submission(
+    State(state): State<AppState>,
+    Extension(customer): Extension<CustomerUser>,
+    Path(id): Path<Uuid>,
+) -> Result<Json<SubmissionResponse>, StatusCode> {
+    let submission = find_owned_submission(&state, &customer, id).await?;
+    let jobs = state.job_repo.find_by_submission(submission.id).await
+        .map_err(|e| {
+            error!("Failed to fetch the jobs of submission {}: {}", id, e);
+            StatusCode::INTERNAL_SERVER_ERROR
+        })?;
+
+    Ok(Json(SubmissionResponse::new(submission, SubmissionSummary::of(&jobs))))
+}
+
+/// Cancel the jobs of a submission that have not started and release what was reserved
+/// for them; jobs already running finish as usual
+///
+/// A file import still submitting rows stops. Cancelling again cancels jobs submitted
+/// in the meantime.
+/// Access: Submission's Customer
+pub async fn cancel_submission(
+    State(state): State<AppState>,
+    Extension(customer): Extension<CustomerUser>,
+    Path(id): Path<Uuid>,
+) -> Result<Json<CancelSubmissionResponse>, StatusCode> {
+    find_owned_submission(&state, &customer, id).await?;
+    let submission = state.submission_repo.cancel(id).await
+        .map_err(|e| {
+            error!("Failed to cancel submission {}: {}", id, e);
+            StatusCode::INTERNAL_SERVER_ERROR
+        })?;
+
+    // An import stops submitting rows once finished
+    if let Ok(import) = state.job_import_repo.find_by_id(id).await {
+        if matches!(import.status(), Some(ImportStatus::Pending | ImportStatus::Processing)) {
+            state.job_import_repo.finish(id, ImportStatus::Failed).await
+                .map_err(|e| {
+                    error!("Failed to stop job import {} of cancelled submission: {}", id, e);
+                    StatusCode::INTERNAL_SERVER_ERROR
+                })?;
+        }
+    }
+
+    let jobs = state.job_repo.find_by_submission(id).await
+        .map_err(|e| {
+            error!("Failed to fetch the jobs of submission {}: {}", id, e);
+            StatusCode::INTERNAL_SERVER_ERROR
+        })?;
+
+    let mut cancelled_jobs = 0;
+    let mut released_cents = 0;
+    for job in jobs.iter().filter(|job| matches!(job.status, JobStatus::Pending | JobStatus::Scheduled)) {
+        match state.billing_service.cancel_job(job.id).await {
+            Ok(Some(released)) => {
+                cancelled_jobs += 1;
+                released_cents += i64::from(released);
+            }
+            // Started in the meantime
+            Ok(None) => {}
+            Err(e) => {
+                error!("Failed to cancel job {} of submission {}: {}", job.id, id, e);
+                return Err(StatusCode::INTERNAL_SERVER_ERROR);
+            }
+        }
+    }
+
+    let jobs = state.job_repo.find_by_submission(id).await
+        .map_err(|e| {
+            error!("Failed to fetch the jobs of submission {}: {}", id, e);
+            StatusCode::INTERNAL_SERVER_ERROR
+        })?;
+
+    info!("Cancelled submission {}: {} jobs cancelled, {} cents released", id, cancelled_jobs, released_cents);
+
+    Ok(Json(CancelSubmissionResponse {
+        submission: SubmissionResponse::new(submission, SubmissionSummary::of(&jobs)),
+        cancelled_jobs,
+        released_cents,
+    }))
+}
@@ -0,0 +1,89 @@
+use axum::{extract::{Path, State}, http::StatusCode, Json};
+use serde::Serialize;
+use uuid::Uuid;
+use tracing::{error, info};
+
+use crate::state::AppState;
+use innosystem_common::database::TenancyMode;
+use innosystem_common::Error;
+
+/// A reseller whose customer data lives in its own schema
+#[derive(Debug, Serialize)]
+pub struct ResellerSchemaResponse {
+    pub reseller_id: Uuid,
+    pub schema: String,
+}
+
+/// Configured tenancy mode and the resellers with their own schema
+#[derive(Debug, Serialize)]
+pub struct TenancyResponse {
+    /// `shared` or `schema_per_reseller`
+    pub mode: String,
+    pub resellers: Vec<ResellerSchemaResponse>,
+}
+
+/// Get the tenancy mode and the provisioned reseller schemas
+/// Access: Admin
+pub async fn get_tenancy(
+    State(state): State<AppState>,
+) -> Result<Json<TenancyResponse>, StatusCode> {
+    let resolver = state.schema_resolver.clone();
+    let resellers = tokio::task::spawn_blocking(move || resolver.isolated_resellers()).await
+        .map_err(|e| {
+            error!("Failed to list reseller schemas: {}", e);
+            StatusCode::INTERNAL_SERVER_ERROR
+        })?
+        .map_err(|e| {
+            error!("Failed to list reseller schemas: {}", e);
+            StatusCode::INTERNAL_SERVER_ERROR
+        })?;
+
+    Ok(Json(TenancyResponse {
+        mode: state.schema_resolver.mode().as_str().to_string(),
+        resellers: resellers.into_iter()
+            .map(|reseller_id| ResellerSchemaResponse {
+                reseller_id,
+                schema: state.schema_resolver.schema_name(reseller_id),
+            })
+            .collect(),
+    }))
+}
+
+/// Create a reseller's own schema so its customers' data is isolated from other
+/// resellers, or bring an existing schema up to date
+///
+/// Only possible in `schema_per_reseller` mode and before the reseller's customers
+/// have data in the shared schema.
+/// Access: Admin
+pub async fn provision_reseller_schema(
+    State(state): State<AppState>,
+    Path(reseller_id): Path<Uuid>,
+) -> Result<Json<ResellerSchemaResponse>, StatusCode> {
+    if state.schema_resolver.mode() != TenancyMode::SchemaPerReseller {
+        error!("Cannot provision a schema for reseller {}: tenancy mode is shared", reseller_id);
+        return Err(StatusCode::CONFLICT);
+    }
+
+    state.reseller_repo.find_by_id(reseller_id).await
+        .map_err(|e| {
+            error!("Failed to find reseller {}: {}", reseller_id, e);
+            StatusCode::NOT_FOUND
+        })?;
+
+    let resolver = state.schema_resolver.clone();
+    let schema = tokio::task::spawn_blocking(move || resolver.provision(reseller_id)).await
+        .map_err(|e| {
+            error!("Failed to provision the schema of reseller {}: {}", reseller_id, e);
+            StatusCode::INTERNAL_SERVER_ERROR
+        })?
+        .map_err(|e| {
+            error!("Failed to provision the schema of reseller {}: {}", reseller_id, e);
+            match e {
+                Error::InvalidInput(_) => StatusCode::CONFLICT,
+                _ => StatusCode::INTERNAL_SERVER_ERROR,
+            }
+        })?;
+
+    info!("Provisioned schema {} for reseller {}", schema, reseller_id);
+    Ok(Json(ResellerSchemaResponse { reseller_id, schema }))
+}
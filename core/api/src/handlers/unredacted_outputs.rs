@@ -0,0 +1,65 @@
+use axum::{extract::{Path, State, Extension}, http::StatusCode, Json};
+use serde::Serialize;
+use serde_json::json;
+use uuid::Uuid;
+use tracing::{error, info};
+use chrono::{DateTime, Utc};
+
+use innosystem_common::models::audit::NewAuditEntry;
+use innosystem_common::models::redaction::UnredactedOutput;
+use crate::middleware::auth::AdminUser;
+use crate::handlers::jobs::find_job;
+use crate::state::AppState;
+
+/// Response data for the original output of a job that had values redacted
+#[derive(Debug, Serialize)]
+pub struct UnredactedOutputResponse {
+    pub job_id: Uuid,
+    pub output_data: serde_json::Value,
+    pub created_at: DateTime<Utc>,
+    /// After this time the output is purged
+    pub expires_at: DateTime<Utc>,
+}
+
+impl From<UnredactedOutput> for UnredactedOutputResponse {
+    fn from(output: UnredactedOutput) -> Self {
+        Self {
+            job_id: output.job_id,
+            output_data: output.output_data,
+            created_at: output.created_at,
+            expires_at: output.expires_at,
+        }
+    }
+}
+
+/// Get the original output of a job before its values were redacted
+///
+/// Only kept when the job's type retains unredacted output, and only until the
+/// retention ends. Every read is recorded in the audit log.
+/// Access: Admin
+pub async fn get_unredacted_output(
+    State(state): State<AppState>,
+    Extension(admin): Extension<AdminUser>,
+    Path(job_ref): Path<String>,
+) -> Result<Json<UnredactedOutputResponse>, StatusCode> {
+    let job_id = find_job(&state, &job_ref).await?.id;
+    let output = state.unredacted_output_repo.find_by_job_id(job_id).await
+        .map_err(|e| {
+            error!("Failed to fetch the unredacted output of job {}: {}", job_id, e);
+            if e.to_string().contains("not found") {
+                StatusCode::NOT_FOUND
+            } else {
+                StatusCode::INTERNAL_SERVER_ERROR
+            }
+        })?;
+
+    let entry = NewAuditEntry::new(admin.id.clone(), "job.unredacted_output_viewed", "job", job_id, json!({
+        "expires_at": output.expires_at,
+    }));
+    if let Err(e) = state.audit_log_repo.record(entry).await {
+        error!("Failed to record the read of the unredacted output of job {} in the audit log: {}", job_id, e);
+    }
+
+    info!("Admin {} read the unredacted output of job {}", admin.id, job_id);
+    Ok(Json(output.into()))
+}
@@ -1,22 +1,58 @@
-use axum::{extract::{Path, State}, http::StatusCode, Json};
+use axum::{extract::{Path, Query, State, Extension}, http::{HeaderMap, StatusCode}, Json};
 use serde::{Deserialize, Serialize};
+use serde_json::json;
 use uuid::Uuid;
 use tracing::{info, error};
+use chrono::{DateTime, Utc};
 
-use innosystem_common::models::wallet::WalletTransaction;
+use innosystem_common::models::audit::NewAuditEntry;
+use innosystem_common::models::wallet::{reference_id, validate_customer_reference, NewWalletTransaction, OverdraftPolicy, TransactionType, WalletTransaction};
+use crate::middleware::auth::{AdminUser, CustomerUser};
+use crate::request::StrictJson;
+use crate::services::billing::{ExpiredJob, ExpiredReservation};
+use crate::services::billing_export::IDEMPOTENCY_KEY_HEADER;
 use crate::state::AppState;
 
 /// Request for depositing funds to a wallet
 #[derive(Debug, Deserialize)]
+#[serde(deny_unknown_fields)]
 pub struct DepositRequest {
     /// Amount to deposit in cents
     pub amount: i32,
     /// Optional description
     pub description: Option<String>,
+    /// Customer's own reference, e.g. a purchase order number (optional)
+    pub customer_reference: Option<String>,
+    /// Customer's own metadata as a JSON object (optional)
+    pub customer_metadata: Option<serde_json::Value>,
+}
+
+/// Request for granting promotional credit to a wallet
+#[derive(Debug, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct GrantCreditRequest {
+    /// Credit to add in cents
+    pub amount_cents: i32,
+    /// Why the credit was granted, kept in the audit log
+    pub reason: Option<String>,
+}
+
+/// Request for setting a wallet's overdraft policy
+#[derive(Debug, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct OverdraftPolicyRequest {
+    /// `deny`, `grace` or `suspend`
+    pub policy: OverdraftPolicy,
+    /// How far below zero reservations may take the balance under `grace` and `suspend`
+    #[serde(default)]
+    pub limit_cents: i32,
+    /// Why the policy was changed, kept in the audit log
+    pub reason: Option<String>,
 }
 
 /// Request for withdrawing funds from a wallet
 #[derive(Debug, Deserialize)]
+#[serde(deny_unknown_fields)]
 pub struct WithdrawRequest {
     /// Amount to withdraw in cents
     pub amount: i32,
@@ -33,10 +69,23 @@ pub struct WalletResponse {
     pub customer_id: Uuid,
     /// Current balance in cents
     pub balance_cents: i32,
+    /// Sandbox balance charged by test-mode jobs, in cents
+    pub test_balance_cents: i32,
+    /// Promotional credit spent on job charges before the balance, in cents
+    pub credit_cents: i32,
+    /// What happens when a reservation would take the balance below zero: `deny`,
+    /// `grace` or `suspend`
+    pub overdraft_policy: String,
+    /// How far below zero reservations may take the balance under `grace` and `suspend`
+    pub overdraft_limit_cents: i32,
+    /// How far the balance is below zero, in cents
+    pub overdraft_cents: i32,
+    /// Whether new job submissions are refused until the wallet is topped up
+    pub submissions_suspended: bool,
     /// Creation timestamp
-    pub created_at: Option<String>,
+    pub created_at: Option<DateTime<Utc>>,
     /// Last update timestamp
-    pub updated_at: Option<String>,
+    pub updated_at: Option<DateTime<Utc>>,
 }
 
 /// Response data for wallet transaction operations
@@ -58,8 +107,37 @@ pub struct WalletTransactionResponse {
     pub description: Option<String>,
     /// Related job ID if applicable
     pub job_id: Option<Uuid>,
+    /// Customer's own reference given with the deposit or the job
+    pub customer_reference: Option<String>,
+    /// Customer's own metadata given with the reference
+    pub customer_metadata: Option<serde_json::Value>,
     /// Creation timestamp
-    pub created_at: Option<String>,
+    pub created_at: Option<DateTime<Utc>>,
+}
+
+impl From<WalletTransaction> for WalletTransactionResponse {
+    fn from(tx: WalletTransaction) -> Self {
+        Self {
+            id: tx.id,
+            wallet_id: tx.wallet_id,
+            transaction_type: tx.transaction_type,
+            amount_cents: tx.amount_cents,
+            previous_balance_cents: 0, // Not stored in WalletTransaction
+            new_balance_cents: 0,      // Not stored in WalletTransaction
+            description: tx.description,
+            job_id: tx.job_id,
+            customer_reference: tx.customer_reference,
+            customer_metadata: tx.customer_metadata,
+            created_at: tx.created_at,
+        }
+    }
+}
+
+/// Query parameters for finding transactions by the customer's reference
+#[derive(Debug, Deserialize)]
+pub struct TransactionReferenceQuery {
+    /// Reference given with deposits or job submissions
+    pub reference: String,
 }
 
 /// Get a wallet by customer ID
@@ -90,17 +168,19 @@ pub async fn get_wallet(
             }
         })?;
     
-    // Convert the timestamps to RFC3339 strings if they exist
-    let created_at = wallet.created_at.map(|dt| dt.and_utc().to_rfc3339());
-    let updated_at = wallet.updated_at.map(|dt| dt.and_utc().to_rfc3339());
-    
     // Create the response
     let response = WalletResponse {
         id: wallet.id,
         customer_id: wallet.customer_id,
         balance_cents: wallet.balance_cents,
-        created_at,
-        updated_at,
+        test_balance_cents: wallet.test_balance_cents,
+        credit_cents: wallet.credit_cents,
+        overdraft_policy: wallet.overdraft_policy.clone(),
+        overdraft_limit_cents: wallet.overdraft_limit_cents,
+        overdraft_cents: wallet.overdraft_cents(),
+        submissions_suspended: wallet.submissions_suspended(),
+        created_at: wallet.created_at,
+        updated_at: wallet.updated_at,
     };
     
     info!("Retrieved wallet for customer ID: {}", customer_id);
@@ -108,17 +188,26 @@ pub async fn get_wallet(
 }
 
 /// Deposit funds to a wallet
+///
+/// A deposit sent with an `Idempotency-Key` header is posted once per key, so a deposit
+/// retried after its response was lost does not credit the wallet twice.
 #[allow(dead_code)]
 pub async fn deposit_funds(
     State(state): State<AppState>,
     Path(customer_id_str): Path<String>,
-    Json(payload): Json<DepositRequest>,
+    headers: HeaderMap,
+    StrictJson(payload): StrictJson<DepositRequest>,
 ) -> Result<Json<WalletResponse>, StatusCode> {
     // Validate the amount
     if payload.amount <= 0 {
         error!("Invalid deposit amount: {}", payload.amount);
         return Err(StatusCode::BAD_REQUEST);
     }
+    validate_customer_reference(payload.customer_reference.as_deref(), payload.customer_metadata.as_ref())
+        .map_err(|reason| {
+            error!("{}", reason);
+            StatusCode::BAD_REQUEST
+        })?;
     
     // Try to parse the customer_id as a UUID
     let customer_id = match Uuid::parse_str(&customer_id_str) {
@@ -128,6 +217,16 @@ pub async fn deposit_funds(
             return Err(StatusCode::BAD_REQUEST);
         }
     };
+    let idempotency_key = match headers.get(IDEMPOTENCY_KEY_HEADER) {
+        Some(value) => match value.to_str() {
+            Ok(key) if !key.trim().is_empty() => Some(key.trim().to_string()),
+            _ => {
+                error!("Invalid {} header on deposit", IDEMPOTENCY_KEY_HEADER);
+                return Err(StatusCode::BAD_REQUEST);
+            }
+        },
+        None => None,
+    };
     
     // Fetch the wallet from the repository
     let wallet = state.wallet_repo.find_by_customer_id(customer_id)
@@ -141,30 +240,45 @@ pub async fn deposit_funds(
             }
         })?;
     
-    // Deposit funds to the wallet
-    let updated_wallet = state.wallet_repo.deposit(
-        wallet.id,
-        payload.amount,
-        payload.description,
-        None, // No job ID for manual deposits
-    )
+    // Deposit funds to the wallet, recording the customer's reference on the transaction
+    state.wallet_repo.add_transaction(NewWalletTransaction {
+        id: Uuid::new_v4(),
+        wallet_id: wallet.id,
+        amount_cents: payload.amount,
+        transaction_type: TransactionType::Deposit.to_string(),
+        customer_id: wallet.customer_id,
+        reference_id: idempotency_key.map(|key| reference_id(&format!("deposit:{}", key))),
+        description: payload.description.or_else(|| Some(format!("Deposit of {} cents", payload.amount))),
+        job_id: None, // No job ID for manual deposits
+        created_at: None,
+        customer_reference: payload.customer_reference,
+        customer_metadata: payload.customer_metadata,
+    })
     .await
     .map_err(|e| {
         error!("Failed to deposit funds: {}", e);
         StatusCode::INTERNAL_SERVER_ERROR
     })?;
-    
-    // Convert the timestamps to RFC3339 strings if they exist
-    let created_at = updated_wallet.created_at.map(|dt| dt.and_utc().to_rfc3339());
-    let updated_at = updated_wallet.updated_at.map(|dt| dt.and_utc().to_rfc3339());
+    let updated_wallet = state.wallet_repo.find_by_id(wallet.id)
+        .await
+        .map_err(|e| {
+            error!("Failed to fetch wallet: {}", e);
+            StatusCode::INTERNAL_SERVER_ERROR
+        })?;
     
     // Create the response
     let response = WalletResponse {
         id: updated_wallet.id,
         customer_id: updated_wallet.customer_id,
         balance_cents: updated_wallet.balance_cents,
-        created_at,
-        updated_at,
+        test_balance_cents: updated_wallet.test_balance_cents,
+        credit_cents: updated_wallet.credit_cents,
+        overdraft_policy: updated_wallet.overdraft_policy.clone(),
+        overdraft_limit_cents: updated_wallet.overdraft_limit_cents,
+        overdraft_cents: updated_wallet.overdraft_cents(),
+        submissions_suspended: updated_wallet.submissions_suspended(),
+        created_at: updated_wallet.created_at,
+        updated_at: updated_wallet.updated_at,
     };
     
     info!("Deposited {} cents to wallet for customer ID: {}", payload.amount, customer_id);
@@ -211,30 +325,39 @@ pub async fn get_transactions(
         })?;
     
     // Convert the transactions to the response format
-    let transaction_responses: Vec<WalletTransactionResponse> = transactions.into_iter().map(|tx| {
-        // Convert the timestamp to RFC3339 string if it exists
-        let created_at = tx.created_at.map(|dt| dt.and_utc().to_rfc3339());
-        
-        // Transaction type is already a string in the model
-        let transaction_type = tx.transaction_type;
-        
-        WalletTransactionResponse {
-            id: tx.id,
-            wallet_id: tx.wallet_id,
-            transaction_type,
-            amount_cents: tx.amount_cents,
-            previous_balance_cents: 0, // Not stored in WalletTransaction
-            new_balance_cents: 0,      // Not stored in WalletTransaction
-            description: tx.description,
-            job_id: tx.job_id,
-            created_at,
-        }
-    }).collect();
+    let transaction_responses: Vec<WalletTransactionResponse> = transactions.into_iter()
+        .map(WalletTransactionResponse::from)
+        .collect();
     
     info!("Retrieved {} transactions for customer ID: {}", transaction_responses.len(), customer_id);
     Ok(Json(transaction_responses))
 }
 
+/// Find the caller's transactions carrying one of their references, newest first, to
+/// reconcile them against their own records such as purchase orders
+pub async fn find_transactions_by_reference(
+    State(state): State<AppState>,
+    Extension(customer): Extension<CustomerUser>,
+    Path(customer_id): Path<Uuid>,
+    Query(query): Query<TransactionReferenceQuery>,
+) -> Result<Json<Vec<WalletTransactionResponse>>, StatusCode> {
+    // Customers only see their own transactions
+    if customer.id != customer_id {
+        error!("Customer {} asked for the transactions of customer {}", customer.id, customer_id);
+        return Err(StatusCode::NOT_FOUND);
+    }
+    
+    let transactions = state.wallet_transaction_repo.find_by_customer_reference(customer_id, &query.reference)
+        .await
+        .map_err(|e| {
+            error!("Failed to fetch transactions: {}", e);
+            StatusCode::INTERNAL_SERVER_ERROR
+        })?;
+    
+    info!("Found {} transactions with reference {:?} for customer ID: {}", transactions.len(), query.reference, customer_id);
+    Ok(Json(transactions.into_iter().map(WalletTransactionResponse::from).collect()))
+}
+
 /// Get job-related transactions
 #[allow(dead_code)]
 pub async fn get_job_transactions(
@@ -289,26 +412,160 @@ pub async fn get_job_transactions(
         .collect();
     
     // Convert the transactions to the response format
-    let transaction_responses: Vec<WalletTransactionResponse> = job_transactions.into_iter().map(|tx| {
-        // Convert the timestamp to RFC3339 string if it exists
-        let created_at = tx.created_at.map(|dt| dt.and_utc().to_rfc3339());
-        
-        // Transaction type is already a string in the model
-        let transaction_type = tx.transaction_type;
-        
-        WalletTransactionResponse {
-            id: tx.id,
-            wallet_id: tx.wallet_id,
-            transaction_type,
-            amount_cents: tx.amount_cents,
-            previous_balance_cents: 0, // Not stored in WalletTransaction
-            new_balance_cents: 0,      // Not stored in WalletTransaction
-            description: tx.description,
-            job_id: tx.job_id,
-            created_at,
-        }
-    }).collect();
+    let transaction_responses: Vec<WalletTransactionResponse> = job_transactions.into_iter()
+        .map(WalletTransactionResponse::from)
+        .collect();
     
     info!("Retrieved {} job-related transactions for job ID: {}", transaction_responses.len(), job_id);
     Ok(Json(transaction_responses))
 }
+
+/// Cancel jobs whose reservation outlived the hold TTL and release their funds
+/// Access: Admin
+pub async fn expire_reservations(
+    State(state): State<AppState>,
+    Extension(_admin): Extension<AdminUser>,
+) -> Result<Json<Vec<ExpiredReservation>>, StatusCode> {
+    let expired = state.billing_service.expire_abandoned_reservations().await
+        .map_err(|e| {
+            error!("Failed to expire abandoned reservations: {}", e);
+            StatusCode::INTERNAL_SERVER_ERROR
+        })?;
+    
+    info!("Expired the reservations of {} jobs", expired.len());
+    
+    Ok(Json(expired))
+}
+
+/// Expire jobs not started before their deadline and release their funds
+/// Access: Admin
+pub async fn expire_overdue_jobs(
+    State(state): State<AppState>,
+    Extension(_admin): Extension<AdminUser>,
+) -> Result<Json<Vec<ExpiredJob>>, StatusCode> {
+    let expired = state.billing_service.expire_overdue_jobs().await
+        .map_err(|e| {
+            error!("Failed to expire jobs past their deadline: {}", e);
+            StatusCode::INTERNAL_SERVER_ERROR
+        })?;
+    
+    info!("Expired {} jobs past their deadline", expired.len());
+    
+    Ok(Json(expired))
+}
+
+/// Grant promotional credit to a customer's wallet; job charges spend it before the
+/// balance and list it as credits applied in their cost breakdown
+/// Access: Admin
+pub async fn grant_credit(
+    State(state): State<AppState>,
+    Extension(admin): Extension<AdminUser>,
+    Path(customer_id): Path<Uuid>,
+    StrictJson(payload): StrictJson<GrantCreditRequest>,
+) -> Result<Json<WalletResponse>, StatusCode> {
+    if payload.amount_cents <= 0 {
+        error!("Invalid credit amount: {}", payload.amount_cents);
+        return Err(StatusCode::BAD_REQUEST);
+    }
+    
+    let wallet = state.wallet_repo.find_by_customer_id(customer_id)
+        .await
+        .map_err(|e| {
+            error!("Failed to fetch wallet of customer {}: {}", customer_id, e);
+            StatusCode::NOT_FOUND
+        })?;
+    
+    let wallet = state.wallet_repo.grant_credit(wallet.id, payload.amount_cents)
+        .await
+        .map_err(|e| {
+            error!("Failed to grant credit to wallet {}: {}", wallet.id, e);
+            StatusCode::INTERNAL_SERVER_ERROR
+        })?;
+    
+    let entry = NewAuditEntry::new(admin.id.clone(), "wallet.credit_granted", "wallet", wallet.id, json!({
+        "customer_id": customer_id,
+        "amount_cents": payload.amount_cents,
+        "reason": payload.reason,
+        "credit_cents": wallet.credit_cents,
+    }));
+    if let Err(e) = state.audit_log_repo.record(entry).await {
+        error!("Failed to record the credit granted to wallet {} in the audit log: {}", wallet.id, e);
+    }
+    
+    info!("Admin {} granted {} cents of credit to customer {}", admin.id, payload.amount_cents, customer_id);
+    
+    Ok(Json(WalletResponse {
+        id: wallet.id,
+        customer_id: wallet.customer_id,
+        balance_cents: wallet.balance_cents,
+        test_balance_cents: wallet.test_balance_cents,
+        credit_cents: wallet.credit_cents,
+        overdraft_policy: wallet.overdraft_policy.clone(),
+        overdraft_limit_cents: wallet.overdraft_limit_cents,
+        overdraft_cents: wallet.overdraft_cents(),
+        submissions_suspended: wallet.submissions_suspended(),
+        created_at: wallet.created_at,
+        updated_at: wallet.updated_at,
+    }))
+}
+
+/// Set what happens when a job's reservation would take a customer's wallet below zero:
+/// refuse it (`deny`), allow it down to minus the limit (`grace`), or allow it as far and
+/// refuse new submissions while the balance is below zero (`suspend`)
+/// Access: Admin
+pub async fn set_overdraft_policy(
+    State(state): State<AppState>,
+    Extension(admin): Extension<AdminUser>,
+    Path(customer_id): Path<Uuid>,
+    StrictJson(payload): StrictJson<OverdraftPolicyRequest>,
+) -> Result<Json<WalletResponse>, StatusCode> {
+    if payload.limit_cents < 0 {
+        error!("Invalid overdraft limit: {}", payload.limit_cents);
+        return Err(StatusCode::BAD_REQUEST);
+    }
+    
+    let wallet = state.wallet_repo.find_by_customer_id(customer_id)
+        .await
+        .map_err(|e| {
+            error!("Failed to fetch wallet of customer {}: {}", customer_id, e);
+            StatusCode::NOT_FOUND
+        })?;
+    let previous_policy = wallet.overdraft_policy.clone();
+    
+    let wallet = state.wallet_repo.set_overdraft_policy(wallet.id, payload.policy, payload.limit_cents)
+        .await
+        .map_err(|e| {
+            error!("Failed to set the overdraft policy of wallet {}: {}", wallet.id, e);
+            StatusCode::INTERNAL_SERVER_ERROR
+        })?;
+    
+    let entry = NewAuditEntry::new(admin.id.clone(), "wallet.overdraft_policy_set", "wallet", wallet.id, json!({
+        "customer_id": customer_id,
+        "previous_policy": previous_policy,
+        "policy": wallet.overdraft_policy,
+        "limit_cents": wallet.overdraft_limit_cents,
+        "reason": payload.reason,
+    }));
+    if let Err(e) = state.audit_log_repo.record(entry).await {
+        error!("Failed to record the overdraft policy of wallet {} in the audit log: {}", wallet.id, e);
+    }
+    
+    info!(
+        "Admin {} set the overdraft policy of customer {} to {} with a limit of {} cents",
+        admin.id, customer_id, wallet.overdraft_policy, wallet.overdraft_limit_cents
+    );
+    
+    Ok(Json(WalletResponse {
+        id: wallet.id,
+        customer_id: wallet.customer_id,
+        balance_cents: wallet.balance_cents,
+        test_balance_cents: wallet.test_balance_cents,
+        credit_cents: wallet.credit_cents,
+        overdraft_policy: wallet.overdraft_policy.clone(),
+        overdraft_limit_cents: wallet.overdraft_limit_cents,
+        overdraft_cents: wallet.overdraft_cents(),
+        submissions_suspended: wallet.submissions_suspended(),
+        created_at: wallet.created_at,
+        updated_at: wallet.updated_at,
+    }))
+}
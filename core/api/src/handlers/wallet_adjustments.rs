@@ -0,0 +1,277 @@
+use axum::{extract::{Path, Query, State, Extension}, http::StatusCode, Json};
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use uuid::Uuid;
+use tracing::{error, info, warn};
+use chrono::{DateTime, Utc};
+
+use innosystem_common::models::audit::AuditEntry;
+use innosystem_common::models::wallet_adjustment::{AdjustmentError, AdjustmentKind, AdjustmentStatus, NewWalletAdjustment, WalletAdjustment};
+use crate::middleware::auth::AdminUser;
+use crate::request::StrictJson;
+use crate::state::AppState;
+
+/// Request data for a manual wallet adjustment or refund
+#[derive(Debug, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct CreateAdjustmentRequest {
+    /// `adjustment` (default) or `refund`
+    #[serde(default = "default_kind")]
+    pub kind: AdjustmentKind,
+    /// Amount in cents; positive credits the wallet, negative debits it
+    pub amount_cents: i32,
+    pub description: Option<String>,
+    /// Job the adjustment or refund relates to (optional)
+    pub job_id: Option<Uuid>,
+}
+
+/// Default adjustment kind
+fn default_kind() -> AdjustmentKind {
+    AdjustmentKind::Adjustment
+}
+
+/// Request data for approving or rejecting an adjustment
+#[derive(Debug, Default, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct ReviewAdjustmentRequest {
+    /// Reviewer's reason, kept with the adjustment and in the audit log
+    pub note: Option<String>,
+}
+
+/// Query parameters for listing adjustments
+#[derive(Debug, Deserialize)]
+pub struct ListAdjustmentsQuery {
+    /// `pending` (default), `approved` or `rejected`
+    pub status: Option<String>,
+}
+
+/// Response data for a wallet adjustment
+#[derive(Debug, Serialize)]
+pub struct WalletAdjustmentResponse {
+    pub id: Uuid,
+    pub wallet_id: Uuid,
+    pub customer_id: Uuid,
+    pub kind: String,
+    pub amount_cents: i32,
+    pub description: Option<String>,
+    pub job_id: Option<Uuid>,
+    /// `pending`, `approved` or `rejected`
+    pub status: String,
+    /// Whether a second admin has to approve the adjustment before it posts
+    pub requires_approval: bool,
+    pub requested_by: String,
+    pub reviewed_by: Option<String>,
+    pub review_note: Option<String>,
+    /// Wallet transaction posted on approval
+    pub transaction_id: Option<Uuid>,
+    pub created_at: Option<DateTime<Utc>>,
+    pub reviewed_at: Option<DateTime<Utc>>,
+}
+
+impl From<WalletAdjustment> for WalletAdjustmentResponse {
+    fn from(adjustment: WalletAdjustment) -> Self {
+        Self {
+            id: adjustment.id,
+            wallet_id: adjustment.wallet_id,
+            customer_id: adjustment.customer_id,
+            kind: adjustment.kind,
+            amount_cents: adjustment.amount_cents,
+            description: adjustment.description,
+            job_id: adjustment.job_id,
+            status: adjustment.status,
+            requires_approval: adjustment.requires_approval,
+            requested_by: adjustment.requested_by,
+            reviewed_by: adjustment.reviewed_by,
+            review_note: adjustment.review_note,
+            transaction_id: adjustment.transaction_id,
+            created_at: adjustment.created_at,
+            reviewed_at: adjustment.reviewed_at,
+        }
+    }
+}
+
+/// Response data for an entry of the audit log
+#[derive(Debug, Serialize)]
+pub struct AuditEntryResponse {
+    pub actor: String,
+    pub action: String,
+    pub details: Value,
+    pub created_at: Option<DateTime<Utc>>,
+}
+
+impl From<AuditEntry> for AuditEntryResponse {
+    fn from(entry: AuditEntry) -> Self {
+        Self {
+            actor: entry.actor,
+            action: entry.action,
+            details: entry.details,
+            created_at: entry.created_at,
+        }
+    }
+}
+
+/// Response data for an adjustment with its review chain
+#[derive(Debug, Serialize)]
+pub struct WalletAdjustmentDetailsResponse {
+    #[serde(flatten)]
+    pub adjustment: WalletAdjustmentResponse,
+    /// Request and review of the adjustment, oldest first
+    pub audit_trail: Vec<AuditEntryResponse>,
+}
+
+/// Map a failed adjustment operation to a status code
+fn adjustment_error_status(e: &anyhow::Error) -> StatusCode {
+    match e.downcast_ref::<AdjustmentError>() {
+        Some(AdjustmentError::ZeroAmount | AdjustmentError::NegativeRefund) => StatusCode::BAD_REQUEST,
+        Some(AdjustmentError::SameReviewer) => StatusCode::FORBIDDEN,
+        Some(AdjustmentError::NotPending(_) | AdjustmentError::InsufficientFunds { .. }) => StatusCode::CONFLICT,
+        None if e.to_string().contains("not found") => StatusCode::NOT_FOUND,
+        None => StatusCode::INTERNAL_SERVER_ERROR,
+    }
+}
+
+/// Request a manual adjustment or refund of a customer's wallet
+///
+/// Amounts up to the approval threshold post immediately; larger ones stay pending
+/// until a different admin approves them.
+/// Access: Admin
+pub async fn create_adjustment(
+    State(state): State<AppState>,
+    Extension(admin): Extension<AdminUser>,
+    Path(customer_id): Path<Uuid>,
+    StrictJson(payload): StrictJson<CreateAdjustmentRequest>,
+) -> Result<(StatusCode, Json<WalletAdjustmentResponse>), StatusCode> {
+    let wallet = state.wallet_repo.find_by_customer_id(customer_id).await
+        .map_err(|e| {
+            error!("Failed to fetch wallet of customer {}: {}", customer_id, e);
+            if e.to_string().contains("not found") {
+                StatusCode::NOT_FOUND
+            } else {
+                StatusCode::INTERNAL_SERVER_ERROR
+            }
+        })?;
+
+    let requires_approval = state.config.wallet_approval.requires_approval(payload.amount_cents);
+    let adjustment = state.wallet_adjustment_repo.create(NewWalletAdjustment {
+        id: Uuid::new_v4(),
+        wallet_id: wallet.id,
+        customer_id,
+        kind: payload.kind.as_str().to_string(),
+        amount_cents: payload.amount_cents,
+        description: payload.description,
+        job_id: payload.job_id,
+        requires_approval,
+        requested_by: admin.id.clone(),
+    }).await
+        .map_err(|e| {
+            error!("Failed to create wallet adjustment for customer {}: {}", customer_id, e);
+            adjustment_error_status(&e)
+        })?;
+
+    if requires_approval {
+        info!("Admin {} requested a {} of {} cents for customer {}, pending approval", admin.id, adjustment.kind, adjustment.amount_cents, customer_id);
+        return Ok((StatusCode::CREATED, Json(adjustment.into())));
+    }
+
+    // Small enough for the requesting admin to post alone
+    let adjustment = state.wallet_adjustment_repo.approve(adjustment.id, admin.id.clone(), None).await
+        .map_err(|e| {
+            error!("Failed to post wallet adjustment {}: {}", adjustment.id, e);
+            adjustment_error_status(&e)
+        })?;
+
+    info!("Admin {} posted a {} of {} cents for customer {}", admin.id, adjustment.kind, adjustment.amount_cents, customer_id);
+    Ok((StatusCode::CREATED, Json(adjustment.into())))
+}
+
+/// List wallet adjustments in a status, oldest first
+/// Access: Admin
+pub async fn list_adjustments(
+    State(state): State<AppState>,
+    Extension(_admin): Extension<AdminUser>,
+    Query(query): Query<ListAdjustmentsQuery>,
+) -> Result<Json<Vec<WalletAdjustmentResponse>>, StatusCode> {
+    let status = match query.status.as_deref() {
+        None => AdjustmentStatus::Pending,
+        Some(value) => AdjustmentStatus::parse(value).ok_or_else(|| {
+            error!("Invalid wallet adjustment status: {}", value);
+            StatusCode::BAD_REQUEST
+        })?,
+    };
+
+    let adjustments = state.wallet_adjustment_repo.find_by_status(status).await
+        .map_err(|e| {
+            error!("Failed to list {} wallet adjustments: {}", status.as_str(), e);
+            StatusCode::INTERNAL_SERVER_ERROR
+        })?;
+
+    Ok(Json(adjustments.into_iter().map(WalletAdjustmentResponse::from).collect()))
+}
+
+/// Get a wallet adjustment with its audit trail
+/// Access: Admin
+pub async fn get_adjustment(
+    State(state): State<AppState>,
+    Extension(_admin): Extension<AdminUser>,
+    Path(id): Path<Uuid>,
+) -> Result<Json<WalletAdjustmentDetailsResponse>, StatusCode> {
+    let adjustment = state.wallet_adjustment_repo.find_by_id(id).await
+        .map_err(|e| {
+            error!("Failed to fetch wallet adjustment {}: {}", id, e);
+            adjustment_error_status(&e)
+        })?;
+
+    let audit_trail = state.audit_log_repo.find_by_entity("wallet_adjustment".to_string(), id).await
+        .map_err(|e| {
+            error!("Failed to fetch the audit trail of wallet adjustment {}: {}", id, e);
+            StatusCode::INTERNAL_SERVER_ERROR
+        })?;
+
+    Ok(Json(WalletAdjustmentDetailsResponse {
+        adjustment: adjustment.into(),
+        audit_trail: audit_trail.into_iter().map(AuditEntryResponse::from).collect(),
+    }))
+}
+
+/// Approve a pending wallet adjustment and post it to the wallet
+///
+/// The admin who requested the adjustment cannot approve it.
+/// Access: Admin
+pub async fn approve_adjustment(
+    State(state): State<AppState>,
+    Extension(admin): Extension<AdminUser>,
+    Path(id): Path<Uuid>,
+    payload: Option<StrictJson<ReviewAdjustmentRequest>>,
+) -> Result<Json<WalletAdjustmentResponse>, StatusCode> {
+    let note = payload.map(|StrictJson(review)| review).unwrap_or_default().note;
+    let adjustment = state.wallet_adjustment_repo.approve(id, admin.id.clone(), note).await
+        .map_err(|e| {
+            warn!("Admin {} could not approve wallet adjustment {}: {}", admin.id, id, e);
+            adjustment_error_status(&e)
+        })?;
+
+    info!("Admin {} approved wallet adjustment {} requested by {}", admin.id, id, adjustment.requested_by);
+    Ok(Json(adjustment.into()))
+}
+
+/// Reject a pending wallet adjustment, leaving the wallet unchanged
+///
+/// The admin who requested the adjustment cannot reject it; it stays pending for
+/// another admin to review.
+/// Access: Admin
+pub async fn reject_adjustment(
+    State(state): State<AppState>,
+    Extension(admin): Extension<AdminUser>,
+    Path(id): Path<Uuid>,
+    payload: Option<StrictJson<ReviewAdjustmentRequest>>,
+) -> Result<Json<WalletAdjustmentResponse>, StatusCode> {
+    let note = payload.map(|StrictJson(review)| review).unwrap_or_default().note;
+    let adjustment = state.wallet_adjustment_repo.reject(id, admin.id.clone(), note).await
+        .map_err(|e| {
+            warn!("Admin {} could not reject wallet adjustment {}: {}", admin.id, id, e);
+            adjustment_error_status(&e)
+        })?;
+
+    info!("Admin {} rejected wallet adjustment {} requested by {}", admin.id, id, adjustment.requested_by);
+    Ok(Json(adjustment.into()))
+}
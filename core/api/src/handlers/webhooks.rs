@@ -0,0 +1,267 @@
+use axum::{extract::{Path, State, Extension}, http::StatusCode, Json};
+use serde::{Deserialize, Serialize};
+use uuid::Uuid;
+use tracing::{error, info};
+use chrono::{DateTime, Utc};
+
+use crate::request::StrictJson;
+use crate::state::AppState;
+use crate::middleware::auth::CustomerUser;
+use innosystem_common::models::webhook::{CustomerWebhook, NewCustomerWebhook, WebhookEventType};
+
+/// Request data for registering a webhook endpoint
+#[derive(Debug, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct CreateWebhookRequest {
+    /// Endpoint URL that receives the events
+    pub url: String,
+    /// Event types to subscribe to (e.g. "job.succeeded")
+    pub event_types: Vec<String>,
+    /// Whether the endpoint is active (defaults to true)
+    #[serde(default = "default_active")]
+    pub active: bool,
+}
+
+/// Default active status
+fn default_active() -> bool {
+    true
+}
+
+/// Request data for updating a webhook endpoint
+#[derive(Debug, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct UpdateWebhookRequest {
+    pub url: Option<String>,
+    pub event_types: Option<Vec<String>>,
+    pub active: Option<bool>,
+}
+
+/// Response data for a webhook endpoint
+#[derive(Debug, Serialize)]
+pub struct WebhookResponse {
+    pub id: Uuid,
+    pub customer_id: Uuid,
+    pub url: String,
+    /// Signing secret, only returned when the endpoint is created
+    pub secret: Option<String>,
+    pub event_types: Vec<String>,
+    pub active: bool,
+    pub last_tested_at: Option<DateTime<Utc>>,
+    pub last_test_status_code: Option<i32>,
+    pub created_at: Option<DateTime<Utc>>,
+    pub updated_at: Option<DateTime<Utc>>,
+}
+
+/// Response data for a webhook test-fire
+#[derive(Debug, Serialize)]
+pub struct WebhookTestResponse {
+    pub webhook_id: Uuid,
+    pub success: bool,
+    pub status_code: Option<i32>,
+    pub response: Option<String>,
+    pub tested_at: Option<DateTime<Utc>>,
+}
+
+impl WebhookResponse {
+    fn from_webhook(webhook: CustomerWebhook, include_secret: bool) -> Self {
+        Self {
+            id: webhook.id,
+            customer_id: webhook.customer_id,
+            url: webhook.url,
+            secret: if include_secret { Some(webhook.secret) } else { None },
+            event_types: webhook.event_types,
+            active: webhook.active,
+            last_tested_at: webhook.last_tested_at,
+            last_test_status_code: webhook.last_test_status_code,
+            created_at: webhook.created_at,
+            updated_at: webhook.updated_at,
+        }
+    }
+}
+
+/// Validate and normalize the requested event type names
+fn parse_event_types(event_types: &[String]) -> Result<Vec<String>, StatusCode> {
+    let mut parsed = Vec::with_capacity(event_types.len());
+    for name in event_types {
+        match WebhookEventType::parse(name) {
+            Some(event_type) if event_type.is_subscribable() => {
+                let name = event_type.as_str().to_string();
+                if !parsed.contains(&name) {
+                    parsed.push(name);
+                }
+            }
+            _ => {
+                error!("Invalid webhook event type: {}", name);
+                return Err(StatusCode::BAD_REQUEST);
+            }
+        }
+    }
+    Ok(parsed)
+}
+
+/// Validate that the endpoint URL is an absolute http(s) URL pointing at a public address
+async fn validate_url(state: &AppState, url: &str) -> Result<(), StatusCode> {
+    if !url.starts_with("https://") && !url.starts_with("http://") {
+        error!("Invalid webhook URL: {}", url);
+        return Err(StatusCode::BAD_REQUEST);
+    }
+    state.webhook_service.check_target(url).await.map_err(|reason| {
+        error!("Refused webhook URL {}: {}", url, reason);
+        StatusCode::BAD_REQUEST
+    })
+}
+
+/// Load a webhook and verify it belongs to the authenticated customer
+async fn find_owned_webhook(state: &AppState, customer: &CustomerUser, id: Uuid) -> Result<CustomerWebhook, StatusCode> {
+    let webhook = state.webhook_repo.find_by_id(id).await
+        .map_err(|e| {
+            error!("Failed to find webhook {}: {}", id, e);
+            StatusCode::NOT_FOUND
+        })?;
+
+    if webhook.customer_id != customer.id {
+        return Err(StatusCode::FORBIDDEN);
+    }
+
+    Ok(webhook)
+}
+
+/// Register a new webhook endpoint
+/// Access: Customer
+pub async fn create_webhook(
+    State(state): State<AppState>,
+    Extension(customer): Extension<CustomerUser>,
+    StrictJson(request): StrictJson<CreateWebhookRequest>,
+) -> Result<(StatusCode, Json<WebhookResponse>), StatusCode> {
+    validate_url(&state, &request.url).await?;
+    let event_types = parse_event_types(&request.event_types)?;
+
+    let new_webhook = NewCustomerWebhook {
+        id: Uuid::new_v4(),
+        customer_id: customer.id,
+        url: request.url,
+        secret: CustomerWebhook::generate_secret(),
+        event_types,
+        active: request.active,
+    };
+
+    let webhook = state.webhook_repo.create(new_webhook).await
+        .map_err(|e| {
+            error!("Failed to create webhook: {}", e);
+            StatusCode::INTERNAL_SERVER_ERROR
+        })?;
+
+    info!("Registered webhook {} for customer {}", webhook.id, customer.id);
+
+    Ok((StatusCode::CREATED, Json(WebhookResponse::from_webhook(webhook, true))))
+}
+
+/// List the webhook endpoints of the authenticated customer
+/// Access: Customer
+pub async fn list_webhooks(
+    State(state): State<AppState>,
+    Extension(customer): Extension<CustomerUser>,
+) -> Result<Json<Vec<WebhookResponse>>, StatusCode> {
+    let webhooks = state.webhook_repo.find_by_customer_id(customer.id).await
+        .map_err(|e| {
+            error!("Failed to list webhooks for customer {}: {}", customer.id, e);
+            StatusCode::INTERNAL_SERVER_ERROR
+        })?;
+
+    Ok(Json(webhooks.into_iter()
+        .map(|webhook| WebhookResponse::from_webhook(webhook, false))
+        .collect()))
+}
+
+/// Get a webhook endpoint by ID
+/// Access: Webhook's Customer
+pub async fn get_webhook(
+    State(state): State<AppState>,
+    Extension(customer): Extension<CustomerUser>,
+    Path(id): Path<Uuid>,
+) -> Result<Json<WebhookResponse>, StatusCode> {
+    let webhook = find_owned_webhook(&state, &customer, id).await?;
+    Ok(Json(WebhookResponse::from_webhook(webhook, false)))
+}
+
+/// Update a webhook endpoint
+/// Access: Webhook's Customer
+pub async fn update_webhook(
+    State(state): State<AppState>,
+    Extension(customer): Extension<CustomerUser>,
+    Path(id): Path<Uuid>,
+    StrictJson(request): StrictJson<UpdateWebhookRequest>,
+) -> Result<Json<WebhookResponse>, StatusCode> {
+    let mut webhook = find_owned_webhook(&state, &customer, id).await?;
+
+    if let Some(url) = request.url {
+        validate_url(&state, &url).await?;
+        webhook.url = url;
+    }
+
+    if let Some(event_types) = request.event_types {
+        webhook.event_types = parse_event_types(&event_types)?;
+    }
+
+    if let Some(active) = request.active {
+        // Re-enabling an endpoint starts a fresh failure window
+        if active && !webhook.active {
+            webhook.failing_since = None;
+        }
+        webhook.active = active;
+    }
+
+    let updated_webhook = state.webhook_repo.update(&webhook).await
+        .map_err(|e| {
+            error!("Failed to update webhook {}: {}", id, e);
+            StatusCode::INTERNAL_SERVER_ERROR
+        })?;
+
+    info!("Updated webhook: {}", id);
+
+    Ok(Json(WebhookResponse::from_webhook(updated_webhook, false)))
+}
+
+/// Delete a webhook endpoint
+/// Access: Webhook's Customer
+pub async fn delete_webhook(
+    State(state): State<AppState>,
+    Extension(customer): Extension<CustomerUser>,
+    Path(id): Path<Uuid>,
+) -> Result<StatusCode, StatusCode> {
+    find_owned_webhook(&state, &customer, id).await?;
+
+    state.webhook_repo.delete(id).await
+        .map_err(|e| {
+            error!("Failed to delete webhook {}: {}", id, e);
+            StatusCode::INTERNAL_SERVER_ERROR
+        })?;
+
+    info!("Deleted webhook: {}", id);
+
+    Ok(StatusCode::NO_CONTENT)
+}
+
+/// Fire a signed sample event at a webhook endpoint and record the response
+/// Access: Webhook's Customer
+pub async fn test_webhook(
+    State(state): State<AppState>,
+    Extension(customer): Extension<CustomerUser>,
+    Path(id): Path<Uuid>,
+) -> Result<Json<WebhookTestResponse>, StatusCode> {
+    find_owned_webhook(&state, &customer, id).await?;
+
+    let (webhook, result) = state.webhook_service.test_fire(id).await
+        .map_err(|e| {
+            error!("Failed to test-fire webhook {}: {}", id, e);
+            StatusCode::INTERNAL_SERVER_ERROR
+        })?;
+
+    Ok(Json(WebhookTestResponse {
+        webhook_id: webhook.id,
+        success: result.success,
+        status_code: result.status_code,
+        response: result.response,
+        tested_at: webhook.last_tested_at,
+    }))
+}
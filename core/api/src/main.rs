@@ -1,11 +1,16 @@
 use std::net::SocketAddr;
 use tracing_subscriber::{layer::SubscriberExt, util::SubscriberInitExt};
-use axum::{Router, routing::{get, post, put}};
-use axum::middleware::from_fn_with_state;
+use axum::{Router, routing::{delete, get, post, put}};
+use axum::middleware::{from_fn, from_fn_with_state};
+use innosystem_common::database::{with_reseller, SchemaResolver, TenancyMode};
+use innosystem_common::schema_drift;
+use std::sync::Arc;
+use uuid::Uuid;
 
 mod config;
 mod handlers;
 mod middleware;
+mod request;
 mod services;
 mod state;
 
@@ -39,46 +44,565 @@ async fn main() -> anyhow::Result<()> {
         }
     };
     
-    // Create the router with routes
-    let app = Router::new()
-        // Health check endpoint (no auth required)
-        .route("/health", get(handlers::health::health_check))
-        
-        // Public routes (no authentication needed)
-        .nest("/public", Router::new()
-            // Test endpoints for debugging (no auth required)
-        )
-        
-        // Admin routes (admin authentication required)
-        .nest("/admin", Router::new()
-            // Reseller management endpoints (admin only)
-            .route("/resellers", get(handlers::resellers::get_all_resellers)
-                                .post(handlers::resellers::create_reseller))
-            .route("/resellers/active", get(handlers::resellers::get_active_resellers))
-            .route("/resellers/{id}", get(handlers::resellers::get_reseller)
-                                    .put(handlers::resellers::update_reseller))
-            .route("/resellers/{id}/regenerate-key", post(handlers::resellers::regenerate_api_key))
-            .layer(from_fn_with_state(app_state.clone(), crate::middleware::auth::admin_auth))
-        )
-        
-        // Reseller routes (reseller authentication required)
-        .nest("/reseller", Router::new()
-            // Endpoints accessible to resellers
-            .route("/profile", get(handlers::resellers::get_current_reseller_profile))
-            .route("/active-resellers", get(handlers::resellers::get_active_resellers))
-            .layer(from_fn_with_state(app_state.clone(), crate::middleware::auth::reseller_auth))
-        )
-        
-        // Runner heartbeat endpoint (public - no auth required)
-        .route("/runners/{id}/heartbeat", post(handlers::runners::update_heartbeat))
-        
-        // Regular API routes with appropriate authentication
+    // Refuse to serve requests against a database the Diesel schema does not describe
+    let shared_pool = app_state.schema_resolver.shared_pool().clone();
+    let schema_check = config.schema_check;
+    let drift = tokio::task::spawn_blocking(move || schema_drift::verify(&shared_pool, schema_check)).await??;
+    if !drift.is_empty() {
+        tracing::warn!("The database schema drifted from diesel_schema:\n{}", schema_drift::format_drift(&drift));
+    }
+    
+    // Bring reseller schemas up to date with migrations applied since they were provisioned
+    if config.tenancy.mode == TenancyMode::SchemaPerReseller {
+        let schema_resolver = app_state.schema_resolver.clone();
+        let synced = tokio::task::spawn_blocking(move || schema_resolver.sync_all()).await??;
+        tracing::info!("Synced {} reseller schemas", synced);
+    }
+    
+    // Each periodic task below runs on a single replica at a time, the one holding the
+    // task's lease; the others skip their ticks until the leader stops renewing it
+
+    // Reload the tunable settings on SIGHUP, as `POST /admin/config/reload` does
+    #[cfg(unix)]
+    {
+        let config_reload = app_state.config_reload.clone();
+        tokio::spawn(async move {
+            use tokio::signal::unix::{signal, SignalKind};
+            let mut hangups = match signal(SignalKind::hangup()) {
+                Ok(hangups) => hangups,
+                Err(e) => {
+                    tracing::error!("Failed to listen for SIGHUP, the configuration can only be reloaded through the API: {}", e);
+                    return;
+                }
+            };
+            while hangups.recv().await.is_some() {
+                // Failures are logged and recorded by the service
+                let _ = config_reload.reload();
+            }
+        });
+    }
+    
+    // Periodically retry failed webhook and email deliveries in the background, in the
+    // shared schema and in every reseller schema
+    let webhook_service = app_state.webhook_service.clone();
+    let schema_resolver = app_state.schema_resolver.clone();
+    let leader_election = app_state.leader_election.clone();
+    tokio::spawn(async move {
+        let period = std::time::Duration::from_secs(60);
+        let mut interval = tokio::time::interval(period);
+        loop {
+            interval.tick().await;
+            if !leader_election.acquire("webhook_retries", period).await {
+                continue;
+            }
+            for reseller_id in background_scopes(&schema_resolver).await {
+                match with_reseller(reseller_id, webhook_service.process_due_retries()).await {
+                    Ok(0) => {}
+                    Ok(count) => tracing::info!("Retried {} failed webhook deliveries", count),
+                    Err(e) => tracing::error!("Failed to process webhook delivery retries: {}", e),
+                }
+            }
+        }
+    });
+    
+    // Periodically score the health of active runners, alert on degradation, hand the
+    // jobs of runners lost for too long back to the queue, reconcile those of runners
+    // that recovered and retire runners that stay critical or inactive for too long
+    let runner_health_service = app_state.runner_health_service.clone();
+    let schema_resolver = app_state.schema_resolver.clone();
+    let leader_election = app_state.leader_election.clone();
+    let health_interval_secs = config.runner_health.check_interval_secs.max(1);
+    tokio::spawn(async move {
+        let period = std::time::Duration::from_secs(health_interval_secs);
+        let mut interval = tokio::time::interval(period);
+        loop {
+            interval.tick().await;
+            if !leader_election.acquire("runner_health", period).await {
+                continue;
+            }
+            match runner_health_service.monitor_runners().await {
+                Ok(count) => tracing::debug!("Checked the health of {} runners", count),
+                Err(e) => tracing::error!("Failed to check runner health: {}", e),
+            }
+            let recovered = runner_health_service.take_recovered_runners();
+            for reseller_id in background_scopes(&schema_resolver).await {
+                match with_reseller(reseller_id, runner_health_service.reconcile_runner_jobs(&recovered)).await {
+                    Ok(reconciliation) if reconciliation.is_empty() => {}
+                    Ok(reconciliation) => tracing::info!(
+                        "Handed back {} jobs of lost runners; recovered runners kept {} jobs and lost {}",
+                        reconciliation.handed_back.len(), reconciliation.kept.len(), reconciliation.reassigned.len()
+                    ),
+                    Err(e) => tracing::error!("Failed to reconcile the jobs of critical and recovered runners: {}", e),
+                }
+            }
+            if let Err(e) = runner_health_service.apply_lifecycle_policy().await {
+                tracing::error!("Failed to apply the runner lifecycle policy: {}", e);
+            }
+        }
+    });
+    
+    // Periodically cancel jobs that never got processed and release their reservations
+    let billing_service = app_state.billing_service.clone();
+    let schema_resolver = app_state.schema_resolver.clone();
+    let leader_election = app_state.leader_election.clone();
+    let sweep_interval_secs = config.reservation_expiry.sweep_interval_secs.max(1);
+    tokio::spawn(async move {
+        let period = std::time::Duration::from_secs(sweep_interval_secs);
+        let mut interval = tokio::time::interval(period);
+        loop {
+            interval.tick().await;
+            if !leader_election.acquire("reservation_expiry", period).await {
+                continue;
+            }
+            for reseller_id in background_scopes(&schema_resolver).await {
+                match with_reseller(reseller_id, billing_service.expire_abandoned_reservations()).await {
+                    Ok(expired) if expired.is_empty() => {}
+                    Ok(expired) => tracing::info!("Expired the reservations of {} abandoned jobs", expired.len()),
+                    Err(e) => tracing::error!("Failed to expire abandoned reservations: {}", e),
+                }
+            }
+        }
+    });
+    
+    // Periodically expire jobs that were not started before their deadline, so
+    // time-sensitive work is never run late and its funds are released
+    let billing_service = app_state.billing_service.clone();
+    let schema_resolver = app_state.schema_resolver.clone();
+    let leader_election = app_state.leader_election.clone();
+    tokio::spawn(async move {
+        let period = std::time::Duration::from_secs(30);
+        let mut interval = tokio::time::interval(period);
+        loop {
+            interval.tick().await;
+            if !leader_election.acquire("job_deadlines", period).await {
+                continue;
+            }
+            for reseller_id in background_scopes(&schema_resolver).await {
+                match with_reseller(reseller_id, billing_service.expire_overdue_jobs()).await {
+                    Ok(expired) if expired.is_empty() => {}
+                    Ok(expired) => tracing::info!("Expired {} jobs past their deadline", expired.len()),
+                    Err(e) => tracing::error!("Failed to expire jobs past their deadline: {}", e),
+                }
+            }
+        }
+    });
+    
+    // Periodically submit the pipeline steps whose dependencies finished and close
+    // completed pipeline runs
+    let pipeline_state = app_state.clone();
+    let schema_resolver = app_state.schema_resolver.clone();
+    tokio::spawn(async move {
+        let period = std::time::Duration::from_secs(5);
+        let mut interval = tokio::time::interval(period);
+        loop {
+            interval.tick().await;
+            if !pipeline_state.leader_election.acquire("pipeline_runs", period).await {
+                continue;
+            }
+            for reseller_id in background_scopes(&schema_resolver).await {
+                match with_reseller(reseller_id, handlers::pipelines::advance_runs(&pipeline_state)).await {
+                    Ok(0) => {}
+                    Ok(count) => tracing::info!("Finished {} pipeline runs", count),
+                    Err(e) => tracing::error!("Failed to advance pipeline runs: {}", e),
+                }
+            }
+        }
+    });
+    
+    // Periodically finish the batch jobs whose sub-tasks all finished without a runner
+    // fanning them in
+    let fan_in_state = app_state.clone();
+    let schema_resolver = app_state.schema_resolver.clone();
+    tokio::spawn(async move {
+        let period = std::time::Duration::from_secs(5);
+        let mut interval = tokio::time::interval(period);
+        loop {
+            interval.tick().await;
+            if !fan_in_state.leader_election.acquire("sub_task_fan_in", period).await {
+                continue;
+            }
+            for reseller_id in background_scopes(&schema_resolver).await {
+                match with_reseller(reseller_id, handlers::job_sub_tasks::fan_in_sub_tasks(&fan_in_state)).await {
+                    Ok(0) => {}
+                    Ok(count) => tracing::info!("Finished {} batch jobs with their sub-tasks", count),
+                    Err(e) => tracing::error!("Failed to fan in sub-tasks: {}", e),
+                }
+            }
+        }
+    });
+    
+    // Periodically submit the next rows of job imports and close the imports whose
+    // rows were all processed
+    let import_state = app_state.clone();
+    let schema_resolver = app_state.schema_resolver.clone();
+    tokio::spawn(async move {
+        let period = std::time::Duration::from_secs(5);
+        let mut interval = tokio::time::interval(period);
+        loop {
+            interval.tick().await;
+            if !import_state.leader_election.acquire("job_imports", period).await {
+                continue;
+            }
+            for reseller_id in background_scopes(&schema_resolver).await {
+                match with_reseller(reseller_id, handlers::job_imports::process_imports(&import_state)).await {
+                    Ok(0) => {}
+                    Ok(count) => tracing::info!("Completed {} job imports", count),
+                    Err(e) => tracing::error!("Failed to process job imports: {}", e),
+                }
+            }
+        }
+    });
+    
+    // Periodically purge the original output of redacted jobs once its retention ends
+    let unredacted_output_repo = app_state.unredacted_output_repo.clone();
+    let schema_resolver = app_state.schema_resolver.clone();
+    let leader_election = app_state.leader_election.clone();
+    tokio::spawn(async move {
+        let period = std::time::Duration::from_secs(3600);
+        let mut interval = tokio::time::interval(period);
+        loop {
+            interval.tick().await;
+            if !leader_election.acquire("unredacted_output_purge", period).await {
+                continue;
+            }
+            for reseller_id in background_scopes(&schema_resolver).await {
+                match with_reseller(reseller_id, unredacted_output_repo.purge_expired()).await {
+                    Ok(0) => {}
+                    Ok(count) => tracing::info!("Purged {} expired unredacted job outputs", count),
+                    Err(e) => tracing::error!("Failed to purge expired unredacted job outputs: {}", e),
+                }
+            }
+        }
+    });
+    
+    // Periodically forget the nonces of signed requests whose timestamp is no longer
+    // accepted; they are kept with the resellers in the shared schema
+    let request_nonce_repo = app_state.request_nonce_repo.clone();
+    let leader_election = app_state.leader_election.clone();
+    tokio::spawn(async move {
+        let period = std::time::Duration::from_secs(600);
+        let mut interval = tokio::time::interval(period);
+        loop {
+            interval.tick().await;
+            if !leader_election.acquire("request_nonce_purge", period).await {
+                continue;
+            }
+            match request_nonce_repo.purge_expired().await {
+                Ok(0) => {}
+                Ok(count) => tracing::info!("Purged {} expired request nonces", count),
+                Err(e) => tracing::error!("Failed to purge expired request nonces: {}", e),
+            }
+        }
+    });
+    
+    // Periodically delete the sandbox tenants of resellers that expired, with their
+    // customers' data and schema
+    let sandboxes = app_state.sandboxes.clone();
+    let leader_election = app_state.leader_election.clone();
+    tokio::spawn(async move {
+        let period = std::time::Duration::from_secs(sandboxes.sweep_interval_secs().max(1));
+        let mut interval = tokio::time::interval(period);
+        loop {
+            interval.tick().await;
+            if !leader_election.acquire("sandbox_expiry", period).await {
+                continue;
+            }
+            match sandboxes.sweep(chrono::Utc::now()).await {
+                Ok(deleted) if deleted.is_empty() => {}
+                Ok(deleted) => tracing::info!("Deleted {} expired sandboxes", deleted.len()),
+                Err(e) => tracing::error!("Failed to delete expired sandboxes: {}", e),
+            }
+        }
+    });
+    
+    // Periodically compare each customer's recent spend and failure rate to their
+    // history and alert on anomalies, catching runaway scripts before a wallet is drained
+    let spending_anomaly_service = app_state.spending_anomaly_service.clone();
+    let schema_resolver = app_state.schema_resolver.clone();
+    let leader_election = app_state.leader_election.clone();
+    let anomaly_interval_secs = config.spending_anomaly.check_interval_secs.max(1);
+    tokio::spawn(async move {
+        let period = std::time::Duration::from_secs(anomaly_interval_secs);
+        let mut interval = tokio::time::interval(period);
+        loop {
+            interval.tick().await;
+            if !leader_election.acquire("spending_anomalies", period).await {
+                continue;
+            }
+            for reseller_id in background_scopes(&schema_resolver).await {
+                match with_reseller(reseller_id, spending_anomaly_service.detect()).await {
+                    Ok(alerts) if alerts.is_empty() => {}
+                    Ok(alerts) => tracing::warn!("Raised {} spending alerts", alerts.len()),
+                    Err(e) => tracing::error!("Failed to detect spending anomalies: {}", e),
+                }
+            }
+        }
+    });
+    
+    // Periodically put jobs back on their queue whose prefetch lease expired before the
+    // runner holding them started them, e.g. because it stopped
+    let lease_state = app_state.clone();
+    let schema_resolver = app_state.schema_resolver.clone();
+    tokio::spawn(async move {
+        let period = std::time::Duration::from_secs(30);
+        let mut interval = tokio::time::interval(period);
+        loop {
+            interval.tick().await;
+            if !lease_state.leader_election.acquire("job_lease_expiry", period).await {
+                continue;
+            }
+            for reseller_id in background_scopes(&schema_resolver).await {
+                match with_reseller(reseller_id, requeue_expired_leases(&lease_state)).await {
+                    Ok(0) => {}
+                    Ok(count) => tracing::warn!("Requeued {} jobs whose prefetch lease expired", count),
+                    Err(e) => tracing::error!("Failed to requeue jobs with expired leases: {}", e),
+                }
+            }
+        }
+    });
+    
+    // Periodically check the health of the external providers job types depend on,
+    // holding back the jobs of those that are down
+    let provider_health = app_state.provider_health.clone();
+    let leader_election = app_state.leader_election.clone();
+    let provider_interval_secs = config.provider_health.check_interval_secs.max(1);
+    tokio::spawn(async move {
+        let period = std::time::Duration::from_secs(provider_interval_secs);
+        let mut interval = tokio::time::interval(period);
+        loop {
+            interval.tick().await;
+            if !leader_election.acquire("provider_health", period).await {
+                continue;
+            }
+            if let Err(e) = provider_health.check_all().await {
+                tracing::error!("Failed to check the health of the providers: {}", e);
+            }
+        }
+    });
+    
+    // Periodically warm runner pools up ahead of the declared load windows, holding
+    // back their scheduled jobs until the pools are ready
+    let capacity_planning = app_state.capacity_planning.clone();
+    let leader_election = app_state.leader_election.clone();
+    let capacity_interval_secs = config.capacity_planning.check_interval_secs.max(1);
+    tokio::spawn(async move {
+        let period = std::time::Duration::from_secs(capacity_interval_secs);
+        let mut interval = tokio::time::interval(period);
+        loop {
+            interval.tick().await;
+            if !leader_election.acquire("capacity_planning", period).await {
+                continue;
+            }
+            if let Err(e) = capacity_planning.check_all().await {
+                tracing::error!("Failed to check the load windows: {}", e);
+            }
+        }
+    });
+    
+    // Periodically create the monthly partitions of the coming months and drop those
+    // past retention; only the shared schema is partitioned
+    let partition_service = app_state.partition_service.clone();
+    let leader_election = app_state.leader_election.clone();
+    let partition_interval_secs = config.partitions.check_interval_secs.max(1);
+    tokio::spawn(async move {
+        let period = std::time::Duration::from_secs(partition_interval_secs);
+        let mut interval = tokio::time::interval(period);
+        loop {
+            interval.tick().await;
+            if !leader_election.acquire("partition_maintenance", period).await {
+                continue;
+            }
+            match partition_service.maintain().await {
+                Ok(maintenance) if maintenance.created.is_empty() && maintenance.dropped.is_empty() => {}
+                Ok(maintenance) => tracing::info!(
+                    "Created {} and dropped {} monthly partitions",
+                    maintenance.created.len(), maintenance.dropped.len()
+                ),
+                Err(e) => tracing::error!("Failed to maintain the monthly partitions: {}", e),
+            }
+        }
+    });
+    
+    // Periodically send the events customers chose to receive as a digest
+    let digest_service = app_state.webhook_service.clone();
+    let schema_resolver = app_state.schema_resolver.clone();
+    let leader_election = app_state.leader_election.clone();
+    let digest_interval_secs = config.notifications.digest_interval_secs.max(1);
+    tokio::spawn(async move {
+        let period = std::time::Duration::from_secs(digest_interval_secs);
+        let mut interval = tokio::time::interval(period);
+        loop {
+            interval.tick().await;
+            if !leader_election.acquire("notification_digests", period).await {
+                continue;
+            }
+            for reseller_id in background_scopes(&schema_resolver).await {
+                match with_reseller(reseller_id, digest_service.send_digests()).await {
+                    Ok(0) => {}
+                    Ok(count) => tracing::info!("Sent {} notification digests", count),
+                    Err(e) => tracing::error!("Failed to send notification digests: {}", e),
+                }
+            }
+        }
+    });
+    
+    // Periodically tell customers which of their jobs started, succeeded or failed
+    let job_events = app_state.job_events.clone();
+    let schema_resolver = app_state.schema_resolver.clone();
+    let leader_election = app_state.leader_election.clone();
+    let job_events_interval_secs = config.job_events.interval_secs.max(1);
+    tokio::spawn(async move {
+        let period = std::time::Duration::from_secs(job_events_interval_secs);
+        let mut interval = tokio::time::interval(period);
+        loop {
+            interval.tick().await;
+            if !leader_election.acquire("job_lifecycle_events", period).await {
+                continue;
+            }
+            for reseller_id in background_scopes(&schema_resolver).await {
+                match with_reseller(reseller_id, job_events.run()).await {
+                    Ok(run) if run.captured == 0 && run.notified == 0 && run.failed == 0 => {}
+                    Ok(run) => tracing::info!(
+                        "Captured {} job lifecycle events, notified {}, {} failed",
+                        run.captured, run.notified, run.failed
+                    ),
+                    Err(e) => tracing::error!("Failed to notify job lifecycle events: {}", e),
+                }
+            }
+        }
+    });
+    
+    // Periodically capture the charges of completed jobs and export them to the
+    // external billing system, if one is configured
+    if config.billing_export.endpoint_url.is_some() {
+        let billing_export_service = app_state.billing_export_service.clone();
+        let schema_resolver = app_state.schema_resolver.clone();
+        let leader_election = app_state.leader_election.clone();
+        let interval_secs = config.billing_export.interval_secs.max(1);
+        tokio::spawn(async move {
+            let period = std::time::Duration::from_secs(interval_secs);
+            let mut interval = tokio::time::interval(period);
+            loop {
+                interval.tick().await;
+                if !leader_election.acquire("billing_export", period).await {
+                    continue;
+                }
+                for reseller_id in background_scopes(&schema_resolver).await {
+                    match with_reseller(reseller_id, billing_export_service.run()).await {
+                        Ok(run) if run.captured == 0 && run.exported == 0 && run.failed == 0 => {}
+                        Ok(run) => tracing::info!(
+                            "Captured {} job charges, exported {}, {} attempts failed",
+                            run.captured, run.exported, run.failed
+                        ),
+                        Err(e) => tracing::error!("Failed to export job charges: {}", e),
+                    }
+                }
+            }
+        });
+    }
+    
+        // Publish autoscaling advice to the configured webhook, if any
+    if config.autoscaling.webhook_url.is_some() {
+        let autoscaling_service = app_state.autoscaling_service.clone();
+        let leader_election = app_state.leader_election.clone();
+        let interval_secs = config.autoscaling.publish_interval_secs.max(1);
+        tokio::spawn(async move {
+            let period = std::time::Duration::from_secs(interval_secs);
+            let mut interval = tokio::time::interval(period);
+            loop {
+                interval.tick().await;
+                if !leader_election.acquire("autoscaling_publish", period).await {
+                    continue;
+                }
+                match autoscaling_service.publish().await {
+                    Ok(Some(advice)) => tracing::debug!("Published autoscaling advice: {} runners desired", advice.desired_runners),
+                    Ok(None) => {}
+                    Err(e) => tracing::error!("Failed to publish autoscaling advice: {}", e),
+                }
+            }
+        });
+    }
+
+    // Stream job, runner and queue changes to the admins following the live tail; every
+    // replica polls for its own followers, and only while it has any
+    let live_events = app_state.live_events.clone();
+    let schema_resolver = app_state.schema_resolver.clone();
+    tokio::spawn(async move {
+        let mut interval = tokio::time::interval(std::time::Duration::from_secs(2));
+        loop {
+            interval.tick().await;
+            if !live_events.has_subscribers() {
+                continue;
+            }
+            for reseller_id in background_scopes(&schema_resolver).await {
+                if let Err(e) = with_reseller(reseller_id, live_events.poll_job_changes()).await {
+                    tracing::error!("Failed to poll job changes for the live tail: {}", e);
+                }
+            }
+            if let Err(e) = live_events.poll_runner_changes().await {
+                tracing::error!("Failed to poll runner changes for the live tail: {}", e);
+            }
+            if let Err(e) = live_events.publish_queue_depth().await {
+                tracing::error!("Failed to publish the queue depth to the live tail: {}", e);
+            }
+        }
+    });
+
+    // Customer routes (customer authentication required); each group is its own
+    // router so its auth layer only wraps the group's own routes
+    let customer_routes = Router::new()
         // Jobs endpoints - require customer auth
         .route("/jobs", get(handlers::jobs::get_all_jobs)
                         .post(handlers::jobs::create_job))
+        .route("/jobs/dry-run", post(handlers::jobs::dry_run_job))
         .route("/jobs/{id}", get(handlers::jobs::get_job))
+        .route("/jobs/{id}/logs", get(handlers::job_logs::get_job_logs))
+        .route("/jobs/{id}/attempts", get(handlers::job_attempts::get_job_attempts))
+        .route("/jobs/{id}/sub-tasks", get(handlers::job_sub_tasks::get_job_sub_tasks))
+        .route("/jobs/{id}/artifacts/{artifact_id}", get(handlers::job_artifacts::get_job_artifact))
+        .route("/jobs/{id}/cancel", post(handlers::jobs::cancel_job))
         .route("/jobs/cost/calculate", post(handlers::jobs::calculate_job_cost))
         .route("/jobs/complete", post(handlers::jobs::complete_job))
+        .route("/jobs/from-template/{template_id}", post(handlers::job_templates::submit_job_from_template))
+        .route("/jobs/import", post(handlers::job_imports::create_job_import))
+        .route("/jobs/imports", get(handlers::job_imports::list_job_imports))
+        .route("/jobs/imports/{id}", get(handlers::job_imports::get_job_import))
+        .route("/jobs/imports/{id}/report", get(handlers::job_imports::get_job_import_report))
+        .route("/jobs/batch", post(handlers::submissions::submit_batch))
+        .route("/submissions/{id}", get(handlers::submissions::get_submission))
+        .route("/submissions/{id}/cancel", post(handlers::submissions::cancel_submission))
+        
+        // Pipeline endpoints - require customer auth
+        .route("/pipelines", get(handlers::pipelines::list_pipelines)
+                            .post(handlers::pipelines::create_pipeline))
+        .route("/pipelines/{id}", get(handlers::pipelines::get_pipeline)
+                                .put(handlers::pipelines::update_pipeline)
+                                .delete(handlers::pipelines::delete_pipeline))
+        .route("/pipelines/{id}/run", post(handlers::pipelines::run_pipeline))
+        .route("/pipelines/{id}/runs", get(handlers::pipelines::list_pipeline_runs))
+        .route("/pipelines/{id}/runs/{run_id}", get(handlers::pipelines::get_pipeline_run))
+        
+        // Sub-account endpoints - require customer auth
+        .route("/sub-accounts", get(handlers::sub_accounts::list_sub_accounts)
+                               .post(handlers::sub_accounts::create_sub_account))
+        .route("/sub-accounts/report", get(handlers::sub_accounts::get_hierarchy_report))
+        .route("/sub-accounts/{id}/budget", put(handlers::sub_accounts::update_sub_account_budget))
+        
+        // Submission window (quiet hours) endpoints - require customer auth
+        .route("/submission-windows", get(handlers::submission_windows::list_customer_windows)
+                                     .post(handlers::submission_windows::create_customer_window))
+        .route("/submission-windows/{id}", delete(handlers::submission_windows::delete_customer_window))
+        
+        // Job type catalog endpoints - require customer auth
+        .route("/catalog", get(handlers::catalog::browse_catalog))
+        .route("/catalog/categories", get(handlers::catalog::list_catalog_categories))
+        .route("/job-types/{id}/stats", get(handlers::catalog::get_job_type_stats))
+        
+        // Job template endpoints - require customer auth
+        .route("/job-templates", get(handlers::job_templates::list_job_templates)
+                                .post(handlers::job_templates::create_job_template))
+        .route("/job-templates/{id}", get(handlers::job_templates::get_job_template)
+                                    .delete(handlers::job_templates::delete_job_template))
         
         // Project endpoints - require customer auth
         .route("/projects", get(handlers::projects::list_customer_projects)
@@ -90,14 +614,47 @@ async fn main() -> anyhow::Result<()> {
         // Wallet endpoints - require customer auth
         .route("/wallets/{customer_id}", get(handlers::wallet::get_wallet))
         .route("/wallets/{customer_id}/deposit", post(handlers::wallet::deposit_funds))
+        .route("/wallets/{customer_id}/transactions", get(handlers::wallet::find_transactions_by_reference))
         .route("/wallets/{customer_id}/transactions/{limit}/{offset}", get(handlers::wallet::get_transactions))
         .route("/wallets/job/{job_id}/transactions", get(handlers::wallet::get_job_transactions))
-        .layer(from_fn_with_state(app_state.clone(), crate::middleware::auth::customer_auth))
+        .route("/invoices", get(handlers::billing_periods::list_customer_invoices))
+        .route("/spending-alerts", get(handlers::spending_alerts::list_customer_alerts))
         
+        // Feature flags that are on for the customer - require customer auth
+        .route("/features", get(handlers::feature_flags::list_customer_features))
+        
+        // Webhook endpoints - require customer auth
+        .route("/webhooks", get(handlers::webhooks::list_webhooks)
+                           .post(handlers::webhooks::create_webhook))
+        .route("/webhooks/{id}", get(handlers::webhooks::get_webhook)
+                               .put(handlers::webhooks::update_webhook)
+                               .delete(handlers::webhooks::delete_webhook))
+        .route("/webhooks/{id}/test", post(handlers::webhooks::test_webhook))
+        
+        // Notification delivery endpoints - require customer auth
+        .route("/notifications/failures/{limit}/{offset}", get(handlers::notifications::list_failed_deliveries))
+        .route("/notifications/deliveries/{id}/retry", post(handlers::notifications::retry_delivery))
+        .route("/notifications/preferences", get(handlers::notifications::list_preferences))
+        .route("/notifications/preferences/{event_type}/{channel}", put(handlers::notifications::set_preference)
+                                                                  .delete(handlers::notifications::delete_preference))
+        .layer(from_fn_with_state(app_state.clone(), crate::middleware::auth::customer_auth));
+    
+    // Admin routes outside of /admin (admin authentication required)
+    let admin_routes = Router::new()
         // Job types endpoints - require admin auth
         .route("/job-types", get(handlers::job_types::get_all_job_types)
                              .post(handlers::job_types::create_job_type))
         .route("/job-types/{id}", get(handlers::job_types::get_job_type))
+        .route("/job-types/{id}/listing", put(handlers::job_types::update_job_type_listing))
+        .route("/job-types/{id}/redaction", put(handlers::job_types::update_job_type_redaction))
+        .route("/job-types/{id}/provider", put(handlers::job_types::update_job_type_provider))
+        .route("/job-types/{id}/default-priority", put(handlers::job_types::update_job_type_default_priority))
+        .route("/job-types/{id}/webhook-template", put(handlers::job_types::update_job_type_webhook_template))
+        .route("/job-types/{id}/response-policy", put(handlers::job_types::update_job_type_response_policy))
+        .route("/job-types/{id}/enabled", put(handlers::job_types::update_job_type_enabled))
+        .route("/job-types/{id}/replay-corpus", get(handlers::replay_corpus::list_replay_corpus)
+                                                .post(handlers::replay_corpus::sample_replay_corpus))
+        .route("/job-types/{id}/replay-corpus/{case_id}", delete(handlers::replay_corpus::delete_replay_case))
         
         // Admin project endpoints - require admin auth
         .route("/all-projects", get(handlers::projects::list_all_projects))
@@ -112,16 +669,198 @@ async fn main() -> anyhow::Result<()> {
         
         // Runner health and compatibility endpoints - require admin auth
         .route("/runners/{id}/health", get(handlers::runner_health::check_runner_health))
+        .route("/runners/{id}/health/history", get(handlers::runner_health::get_health_history))
         .route("/runners/{runner_id}/compatible/{job_type_id}", get(handlers::runner_health::check_compatibility))
         .route("/job-types/{job_type_id}/compatible-runners", get(handlers::runner_health::find_compatible_runners))
         .route("/runners/maintenance/reassign-jobs", post(handlers::runner_health::check_and_reassign_jobs))
-        .layer(from_fn_with_state(app_state.clone(), crate::middleware::auth::admin_auth))
+        .route("/runners/maintenance/lifecycle", post(handlers::runner_health::apply_lifecycle_policy))
         
+        // Runner execution environments and their drift within pools - require admin auth
+        .route("/runners/environment-drift", get(handlers::runner_environments::get_environment_drift))
+        .route("/runners/{id}/environment", get(handlers::runner_environments::get_environment_history))
+        .layer(from_fn_with_state(app_state.clone(), crate::middleware::auth::admin_auth));
+    
+    // Customer management routes (reseller authentication required)
+    let reseller_routes = Router::new()
         // Customers endpoints - require reseller auth
         .route("/customers", get(handlers::customers::get_all_customers)
                              .post(handlers::customers::create_customer))
         .route("/customers/{id}", get(handlers::customers::get_customer))
-        .layer(from_fn_with_state(app_state.clone(), crate::middleware::auth::reseller_auth))
+        .route("/customers/{id}/test-key", post(handlers::customers::generate_test_api_key))
+        .route("/customers/{id}/timezone", put(handlers::customers::update_customer_timezone))
+        .route("/customers/{id}/default-priority", put(handlers::customers::update_customer_default_priority))
+        .route("/customers/{id}/pii-policy", put(handlers::customers::update_customer_pii_policy))
+        .route("/customers/{id}/status", put(handlers::customers::update_customer_status))
+        
+        // Submission windows applying to all customers of a reseller - require reseller auth
+        .route("/resellers/{reseller_id}/submission-windows", get(handlers::submission_windows::list_reseller_windows)
+                                                              .post(handlers::submission_windows::create_reseller_window))
+        .route("/resellers/{reseller_id}/submission-windows/{id}", delete(handlers::submission_windows::delete_reseller_window))
+        
+        // Spending anomalies of a reseller's customers - require reseller auth
+        .route("/resellers/{reseller_id}/spending-alerts", get(handlers::spending_alerts::list_reseller_alerts))
+        .layer(from_fn_with_state(app_state.clone(), crate::middleware::auth::reseller_auth));
+    
+    // Create the router with routes
+    let app = Router::new()
+        // Health check endpoint (no auth required)
+        .route("/health", get(handlers::health::health_check))
+        
+        // Public routes (no authentication needed)
+        .nest("/public", Router::new()
+            // Test endpoints for debugging (no auth required)
+        )
+        
+        // Admin routes (admin authentication required)
+        .nest("/admin", Router::new()
+            // Reseller management endpoints (admin only)
+            .route("/resellers", get(handlers::resellers::get_all_resellers)
+                                .post(handlers::resellers::create_reseller))
+            .route("/resellers/active", get(handlers::resellers::get_active_resellers))
+            .route("/resellers/{id}", get(handlers::resellers::get_reseller)
+                                    .put(handlers::resellers::update_reseller))
+            .route("/resellers/{id}/regenerate-key", post(handlers::resellers::regenerate_api_key))
+            .route("/resellers/{id}/signing-secret", post(handlers::resellers::rotate_signing_secret)
+                                                   .delete(handlers::resellers::disable_request_signing))
+            // Leaders of the periodic background tasks across API replicas (admin only)
+            .route("/cluster", get(handlers::cluster::get_cluster_status))
+            // Schema-per-reseller isolation of customer data (admin only)
+            .route("/tenancy", get(handlers::tenancy::get_tenancy))
+            .route("/resellers/{id}/schema", post(handlers::tenancy::provision_reseller_schema))
+            // Notification delivery log (admin only)
+            .route("/customers/{customer_id}/notifications/failures/{limit}/{offset}", get(handlers::notifications::list_customer_failed_deliveries))
+            // Autoscaling signals for the runner fleet (admin only)
+            .route("/autoscaling", get(handlers::autoscaling::get_autoscaling_advice))
+            .route("/autoscaling/metrics", get(handlers::autoscaling::get_autoscaling_metrics))
+            // Queue wait times per priority level (admin only)
+            .route("/queue/wait-times", get(handlers::queue_stats::get_queue_wait_times))
+            .route("/queue/metrics", get(handlers::queue_stats::get_queue_wait_metrics))
+            // Queue depth limits and the jobs turned away by them (admin only)
+            .route("/queue/backpressure", get(handlers::queue_stats::get_backpressure))
+            .route("/queue/backpressure/metrics", get(handlers::queue_stats::get_backpressure_metrics))
+            // Queue entries set aside because they are not job IDs (admin only)
+            .route("/queue/quarantine", get(handlers::queue_stats::get_quarantine)
+                                        .delete(handlers::queue_stats::clear_quarantine))
+            .route("/queue/quarantine/metrics", get(handlers::queue_stats::get_quarantine_metrics))
+            // Call counts and timings per repository method (admin only)
+            .route("/repositories/stats", get(handlers::repository_metrics::get_repository_stats))
+            .route("/repositories/metrics", get(handlers::repository_metrics::get_repository_metrics))
+            // Utilization of the database and Redis connection pools (admin only)
+            .route("/pools", get(handlers::pool_metrics::get_pool_stats))
+            .route("/pools/metrics", get(handlers::pool_metrics::get_pool_metrics))
+            // Runner health metrics and the alerting rules derived from the configuration (admin only)
+            .route("/runners/metrics", get(handlers::runner_health::get_runner_metrics))
+            .route("/alerting/prometheus-rules", get(handlers::alerting::get_prometheus_rules))
+            .route("/alerting/grafana-dashboard", get(handlers::alerting::get_grafana_dashboard))
+            // Expiry of reservations held by abandoned jobs (admin only)
+            .route("/reservations/expire", post(handlers::wallet::expire_reservations))
+            // Expiry of jobs not started before their deadline (admin only)
+            .route("/jobs/expire", post(handlers::wallet::expire_overdue_jobs))
+            // Manual wallet adjustments and their second-admin approval (admin only)
+            .route("/wallets/{customer_id}/adjustments", post(handlers::wallet_adjustments::create_adjustment))
+            // Promotional credit spent on job charges before the balance (admin only)
+            .route("/wallets/{customer_id}/credit", post(handlers::wallet::grant_credit))
+            .route("/wallets/{customer_id}/overdraft", put(handlers::wallet::set_overdraft_policy))
+            .route("/wallet-adjustments", get(handlers::wallet_adjustments::list_adjustments))
+            .route("/wallet-adjustments/{id}", get(handlers::wallet_adjustments::get_adjustment))
+            .route("/wallet-adjustments/{id}/approve", post(handlers::wallet_adjustments::approve_adjustment))
+            .route("/wallet-adjustments/{id}/reject", post(handlers::wallet_adjustments::reject_adjustment))
+            // Monthly close of the ledger and the final invoices (admin only)
+            .route("/billing-periods", get(handlers::billing_periods::list_billing_periods)
+                                      .post(handlers::billing_periods::close_billing_period))
+            .route("/billing-periods/{id}", get(handlers::billing_periods::get_billing_period))
+            .route("/billing-periods/{id}/ledger", get(handlers::billing_periods::export_ledger))
+            // Double-entry accounts behind the wallets, which must add up to zero (admin only)
+            .route("/ledger/trial-balance", get(handlers::ledger::get_trial_balance))
+            // Export of job charges to the external billing system (admin only)
+            .route("/billing-exports/reconciliation", get(handlers::billing_exports::get_reconciliation))
+            .route("/billing-exports/{id}/retry", post(handlers::billing_exports::retry_export))
+            // Resources runners used for jobs, per customer and job type (admin only)
+            .route("/usage", get(handlers::job_usage::get_usage_report))
+            // Spend spikes and failure rate jumps of customers (admin only)
+            .route("/spending-alerts", get(handlers::spending_alerts::list_alerts))
+            .route("/spending-alerts/detect", post(handlers::spending_alerts::detect_anomalies))
+            // Original output of jobs with redacted values, while it is retained (admin only)
+            .route("/jobs/{id}/unredacted-output", get(handlers::unredacted_outputs::get_unredacted_output))
+            // Cancellation of any customer's job, stopping it on its runner if it runs (admin only)
+            .route("/jobs/{id}/cancel", post(handlers::jobs::admin_cancel_job))
+            // Another run of a failed or expired job, with its input optionally corrected (admin only)
+            .route("/jobs/{id}/requeue", post(handlers::jobs::admin_requeue_job))
+            // Runtime kill switches, read-only mode and canary features (admin only)
+            .route("/feature-flags", get(handlers::feature_flags::list_feature_flags))
+            .route("/feature-flags/{name}", get(handlers::feature_flags::get_feature_flag)
+                                           .put(handlers::feature_flags::put_feature_flag)
+                                           .delete(handlers::feature_flags::delete_feature_flag))
+            // Declarative job types, runner pools and feature flags kept in version control (admin only)
+            .route("/config/apply", put(handlers::config_apply::apply_config))
+            // Reload of the settings tunable without a restart, on this replica (admin only)
+            .route("/config/reload", get(handlers::config_reload::get_config_reload_status)
+                                    .post(handlers::config_reload::reload_config))
+            // Monthly partitions of jobs and wallet transactions (admin only)
+            .route("/partitions", get(handlers::partitions::list_partitions))
+            .route("/partitions/maintain", post(handlers::partitions::maintain_partitions))
+            // Permanent deletion of the data past its retention (admin only)
+            .route("/purge", post(handlers::purge::purge_expired_data))
+            // External providers job types depend on and their health (admin only)
+            .route("/providers", get(handlers::providers::list_providers)
+                                .post(handlers::providers::create_provider))
+            .route("/providers/check", post(handlers::providers::check_providers))
+            .route("/providers/{id}", get(handlers::providers::get_provider)
+                                     .put(handlers::providers::update_provider)
+                                     .delete(handlers::providers::delete_provider))
+            // Load windows runner pools are scaled up ahead of (admin only)
+            .route("/load-windows", get(handlers::load_windows::list_load_windows)
+                                   .post(handlers::load_windows::create_load_window))
+            .route("/load-windows/check", post(handlers::load_windows::check_load_windows))
+            .route("/load-windows/{id}", get(handlers::load_windows::get_load_window)
+                                        .delete(handlers::load_windows::cancel_load_window))
+            // Customers, resellers, jobs and projects by name, email or ID (admin only)
+            .route("/search", get(handlers::search::search))
+            // Lockouts and brute-force patterns in failed authentications (admin only)
+            .route("/security/events", get(handlers::security_events::list_security_events))
+            // Live tail of job, runner, queue and auth events over a WebSocket (admin only)
+            .route("/live-events", get(handlers::live_events::live_tail))
+            // Bundles of test data for staging, while the fixtures flag is on (admin only)
+            .route("/fixtures", get(handlers::fixtures::list_fixtures)
+                               .post(handlers::fixtures::create_fixture))
+            .route("/fixtures/{id}", get(handlers::fixtures::get_fixture)
+                                    .delete(handlers::fixtures::delete_fixture))
+            .layer(from_fn_with_state(app_state.clone(), crate::middleware::auth::admin_auth))
+        )
+        
+        // Reseller routes (reseller authentication required)
+        .nest("/reseller", Router::new()
+            // Endpoints accessible to resellers
+            .route("/profile", get(handlers::resellers::get_current_reseller_profile))
+            .route("/active-resellers", get(handlers::resellers::get_active_resellers))
+            // Job type catalog including reseller-only listings
+            .route("/catalog", get(handlers::catalog::browse_reseller_catalog))
+            .route("/catalog/categories", get(handlers::catalog::list_reseller_catalog_categories))
+            // Isolated sandbox tenants to demo and develop against
+            .route("/sandbox", get(handlers::sandboxes::list_sandboxes)
+                              .post(handlers::sandboxes::create_sandbox))
+            // Monthly invoice consolidating the reseller's customers' charges
+            .route("/invoices/{period}", get(handlers::reseller_invoices::get_reseller_invoice))
+            .route("/invoices/{period}/export", get(handlers::reseller_invoices::export_reseller_invoice))
+            .layer(from_fn_with_state(app_state.clone(), crate::middleware::auth::reseller_auth))
+        )
+        
+        // Runner heartbeat endpoint (public - no auth required)
+        .route("/runners/{id}/heartbeat", post(handlers::runners::update_heartbeat))
+        
+        // Authenticated route groups
+        .merge(customer_routes)
+        .merge(admin_routes)
+        .merge(reseller_routes)
+        
+        // Lock out sources of repeated authentication failures
+        .layer(from_fn_with_state(app_state.clone(), crate::middleware::security::detect_auth_failures))
+        
+        // Answer 503 when the connection pools are exhausted rather than a misleading error
+        .layer(from_fn(crate::middleware::pool_exhaustion::reject_when_pool_exhausted))
+        
+        // Explain error codes in the client's language
+        .layer(from_fn(crate::middleware::i18n::localize_errors))
         
         // Add application state
         .with_state(app_state);
@@ -131,9 +870,77 @@ async fn main() -> anyhow::Result<()> {
     let addr = SocketAddr::from(([0, 0, 0, 0], port));
     tracing::info!("Starting server on {}", addr);
     
-    // Start the server
+    // Start the server; on SIGTERM or SIGINT it stops accepting connections and waits
+    // for in-flight requests up to the drain period
     let listener = tokio::net::TcpListener::bind(addr).await.unwrap();
-    axum::serve(listener, app).await.unwrap();
+    let signalled = Arc::new(tokio::sync::Notify::new());
+    let notify_signalled = signalled.clone();
+    let server = axum::serve(listener, app.into_make_service_with_connect_info::<SocketAddr>())
+        .with_graceful_shutdown(async move {
+            let signal = shutdown_signal().await;
+            tracing::info!("Received {}, draining in-flight requests", signal);
+            notify_signalled.notify_one();
+        });
+    let drain = std::time::Duration::from_secs(config.shutdown_drain_secs);
+    tokio::select! {
+        result = server.into_future() => result?,
+        _ = async { signalled.notified().await; tokio::time::sleep(drain).await } => {
+            tracing::warn!("Stopping with requests still in flight after draining for {} s", drain.as_secs());
+        }
+    }
+    tracing::info!("API stopped");
     
     Ok(())
 }
+
+/// Wait for SIGTERM or SIGINT, returning the name of the signal received
+async fn shutdown_signal() -> &'static str {
+    let interrupt = async {
+        if let Err(e) = tokio::signal::ctrl_c().await {
+            tracing::error!("Failed to listen for SIGINT: {}", e);
+            std::future::pending::<()>().await;
+        }
+        "SIGINT"
+    };
+    #[cfg(unix)]
+    {
+        use tokio::signal::unix::{signal, SignalKind};
+        match signal(SignalKind::terminate()) {
+            Ok(mut terminations) => tokio::select! {
+                _ = terminations.recv() => "SIGTERM",
+                signal = interrupt => signal,
+            },
+            Err(e) => {
+                tracing::error!("Failed to listen for SIGTERM, only SIGINT stops the API gracefully: {}", e);
+                interrupt.await
+            }
+        }
+    }
+    #[cfg(not(unix))]
+    interrupt.await
+}
+
+/// Put the pending jobs whose prefetch lease expired back on the queue they were taken from
+async fn requeue_expired_leases(state: &AppState) -> anyhow::Result<usize> {
+    let released = state.job_repo.release_expired_leases(chrono::Utc::now()).await?;
+    let mut requeued = 0;
+    for (job_id, priority) in released {
+        match state.job_queue.push_job(job_id, priority).await {
+            Ok(()) => requeued += 1,
+            Err(e) => tracing::error!("Failed to requeue job {} after its lease expired, it must be requeued manually: {}", job_id, e),
+        }
+    }
+    Ok(requeued)
+}
+
+/// Schemas background work runs in: the shared schema and every reseller schema
+async fn background_scopes(schema_resolver: &Arc<SchemaResolver>) -> Vec<Option<Uuid>> {
+    let resolver = schema_resolver.clone();
+    tokio::task::spawn_blocking(move || resolver.scopes()).await
+        .map_err(anyhow::Error::from)
+        .and_then(|scopes| scopes.map_err(anyhow::Error::from))
+        .unwrap_or_else(|e| {
+            tracing::error!("Failed to list reseller schemas: {}", e);
+            vec![None]
+        })
+}
@@ -1,14 +1,20 @@
 use axum::{
-    extract::State,
-    http::{Request, StatusCode},
+    extract::{OriginalUri, State},
+    http::{Method, Request, StatusCode},
     middleware::Next,
-    response::Response,
-    Extension,
+    response::{IntoResponse, Response},
+    Extension, Json,
 };
 use axum::body::Body;
 use uuid::Uuid;
 use tracing::{debug, error, info};
 
+use innosystem_common::api::ApiErrorBody;
+use innosystem_common::database::with_reseller;
+use innosystem_common::models::feature_flag;
+use innosystem_common::models::request_signature::{SignedRequest, NONCE_HEADER, RESELLER_HEADER, SIGNATURE_HEADER, TIMESTAMP_HEADER};
+use innosystem_common::models::reseller::Reseller;
+use crate::services::request_signing::SignedRequestError;
 use crate::state::AppState;
 
 // Define the authorization roles
@@ -38,17 +44,18 @@ pub struct CustomerUser {
     pub id: Uuid,
     pub name: String,
     pub reseller_id: Option<Uuid>,
+    /// Parent customer if this is a sub-account
+    pub parent_id: Option<Uuid>,
+    /// Authenticated with the sandbox key: jobs run in test mode
+    pub test_mode: bool,
 }
 
 // API authentication middleware for admin access
-pub async fn admin_auth<B>(
+pub async fn admin_auth(
     State(app_state): State<AppState>,
-    mut req: Request<B>,
+    mut req: Request<Body>,
     next: Next,
-) -> Result<Response, StatusCode> 
-where
-    B: Send + 'static,
-{
+) -> Result<Response, StatusCode> {
     debug!("Processing admin authentication");
     
     // Get the API key from the header
@@ -60,21 +67,17 @@ where
     
     // For now, the admin API key is hardcoded or retrieved from configuration
     // In a real-world scenario, this would be securely stored and compared
-    if api_key == app_state.config.admin_api_key {
+    if let Some(id) = app_state.config.admin_id(&api_key) {
         // Admin is authenticated
-        info!("Admin authentication successful");
-        let admin = AdminUser {
-            id: "admin".to_string(),
-        };
+        info!("Admin authentication successful for {}", id);
+        let admin = AdminUser { id };
         
         // Add the admin user to the request extensions
         req.extensions_mut().insert(admin);
         
-        // Continue to the handler
-        // Convert request body type to Body for compatibility with next.run()
-        let (parts, _) = req.into_parts();
-        let req = Request::from_parts(parts, Body::empty());
-        Ok(next.run(req).await)
+        // Continue to the handler, keeping the request body intact
+        let reseller_id = get_admin_reseller_scope(&req)?;
+        Ok(with_reseller(reseller_id, next.run(req)).await)
     } else {
         error!("Invalid admin API key");
         Err(StatusCode::UNAUTHORIZED)
@@ -82,16 +85,21 @@ where
 }
 
 // API authentication middleware for reseller access
-pub async fn reseller_auth<B>(
+pub async fn reseller_auth(
     State(app_state): State<AppState>,
-    mut req: Request<B>,
+    mut req: Request<Body>,
     next: Next,
-) -> Result<Response, StatusCode> 
-where
-    B: Send + 'static,
-{
+) -> Result<Response, StatusCode> {
     debug!("Processing reseller authentication");
     
+    // Signed requests carry no API key: the reseller is identified by its signature
+    if req.headers().contains_key(SIGNATURE_HEADER) {
+        let (reseller, mut req) = verify_signed_request(&app_state, req).await?;
+        info!("Signed request authentication successful for reseller {}", reseller.id);
+        req.extensions_mut().insert(ResellerUser { id: reseller.id, name: reseller.name });
+        return Ok(with_reseller(Some(reseller.id), next.run(req)).await);
+    }
+    
     // Get the API key from the header
     let api_key = get_api_key_from_header(&req)
         .ok_or_else(|| {
@@ -100,36 +108,80 @@ where
         })?;
     
     // Check if this is an admin key first (admins can access reseller endpoints)
-    if api_key == app_state.config.admin_api_key {
-        let admin = AdminUser {
-            id: "admin".to_string(),
-        };
+    if let Some(id) = app_state.config.admin_id(&api_key) {
+        let admin = AdminUser { id };
         req.extensions_mut().insert(admin);
-        
-        // Convert request body type to Body for compatibility with next.run()
-        let (parts, _) = req.into_parts();
-        let req = Request::from_parts(parts, Body::empty());
-        return Ok(next.run(req).await);
+        let reseller_id = get_admin_reseller_scope(&req)?;
+        return Ok(with_reseller(reseller_id, next.run(req)).await);
     }
     
-    // TODO: Update once ResellerRepository is implemented in Phase 3.3.2
-    // For now, we'll use a stub implementation which just returns unauthorized
-    error!("Reseller repository not yet implemented");
-    return Err(StatusCode::UNAUTHORIZED);
+    let reseller = app_state.reseller_repo.find_by_api_key(&api_key).await
+        .map_err(|e| {
+            error!("Failed to find reseller with API key: {}", e);
+            StatusCode::UNAUTHORIZED
+        })?;
+    
+    // Resellers may give up their API key for signed requests
+    if !reseller.accepts_api_key() {
+        error!("Rejected API key of reseller {}: only signed requests are accepted", reseller.id);
+        return Err(StatusCode::UNAUTHORIZED);
+    }
+    
+    // Reseller is authenticated; its customers' data is read from its schema if it has one
+    info!("Reseller authentication successful: {}", reseller.id);
+    req.extensions_mut().insert(ResellerUser { id: reseller.id, name: reseller.name });
+    Ok(with_reseller(Some(reseller.id), next.run(req)).await)
+}
+
+// Verify the signature of a reseller's request, returning the reseller and the
+// request with its body restored for the handler
+async fn verify_signed_request(app_state: &AppState, req: Request<Body>) -> Result<(Reseller, Request<Body>), StatusCode> {
+    let headers = {
+        let header = |name: &str| req.headers().get(name).and_then(|value| value.to_str().ok()).map(str::to_string);
+        (header(RESELLER_HEADER), header(TIMESTAMP_HEADER), header(NONCE_HEADER), header(SIGNATURE_HEADER))
+    };
+    let (Some(reseller_id), Some(timestamp), Some(nonce), Some(signature)) = headers else {
+        error!("Signed request is missing one of the {}, {} and {} headers", RESELLER_HEADER, TIMESTAMP_HEADER, NONCE_HEADER);
+        return Err(StatusCode::UNAUTHORIZED);
+    };
+    let (Ok(reseller_id), Ok(timestamp)) = (Uuid::parse_str(&reseller_id), timestamp.parse::<i64>()) else {
+        error!("Invalid {} or {} header", RESELLER_HEADER, TIMESTAMP_HEADER);
+        return Err(StatusCode::UNAUTHORIZED);
+    };
+    
+    // The path is signed as the client sent it, before nested routers strip their prefix
+    let method = req.method().to_string();
+    let path = req.extensions().get::<OriginalUri>().map(|uri| &uri.0).unwrap_or(req.uri())
+        .path_and_query()
+        .map(|path| path.as_str().to_string())
+        .unwrap_or_default();
+    
+    let (parts, body) = req.into_parts();
+    let body = axum::body::to_bytes(body, app_state.request_signing.max_body_bytes()).await
+        .map_err(|e| {
+            error!("Failed to read the body of a signed request: {}", e);
+            StatusCode::PAYLOAD_TOO_LARGE
+        })?;
+    
+    let request = SignedRequest { method: &method, path: &path, timestamp, nonce: &nonce, body: &body };
+    let reseller = app_state.request_signing.authenticate(reseller_id, request, &signature).await
+        .map_err(|e| {
+            error!("Rejected signed request of reseller {}: {}", reseller_id, e);
+            match e {
+                SignedRequestError::Repository(_) => StatusCode::INTERNAL_SERVER_ERROR,
+                _ => StatusCode::UNAUTHORIZED,
+            }
+        })?;
     
-    // Note: The code below is unreachable until ResellerRepository is implemented
-    // It's kept here as a template for the future implementation
+    Ok((reseller, Request::from_parts(parts, Body::from(body))))
 }
 
 // API authentication middleware for customer access
-pub async fn customer_auth<B>(
+pub async fn customer_auth(
     State(app_state): State<AppState>,
-    mut req: Request<B>,
+    mut req: Request<Body>,
     next: Next,
-) -> Result<Response, StatusCode> 
-where
-    B: Send + 'static,
-{
+) -> Result<Response, StatusCode> {
     debug!("Processing customer authentication");
     
     // Get the API key from the header
@@ -140,16 +192,11 @@ where
         })?;
     
     // Check if this is an admin key first (admins can access customer endpoints)
-    if api_key == app_state.config.admin_api_key {
-        let admin = AdminUser {
-            id: "admin".to_string(),
-        };
+    if let Some(id) = app_state.config.admin_id(&api_key) {
+        let admin = AdminUser { id };
         req.extensions_mut().insert(admin);
-        
-        // Convert request body type to Body for compatibility with next.run()
-        let (parts, _) = req.into_parts();
-        let req = Request::from_parts(parts, Body::empty());
-        return Ok(next.run(req).await);
+        let reseller_id = get_admin_reseller_scope(&req)?;
+        return Ok(with_reseller(reseller_id, next.run(req)).await);
     }
     
     // Look up the reseller by API key to check if this is a reseller
@@ -157,39 +204,118 @@ where
     // For now, we'll bypass this check and assume it's not a reseller
     // Just continue with customer authentication
     
-    // Look up the customer by API key
-    let customer = match app_state.customer_repo.find_by_api_key(&api_key).await {
-        Ok(customer) => customer,
-        Err(e) => {
-            error!("Failed to find customer with API key: {}", e);
-            return Err(StatusCode::UNAUTHORIZED);
+    // Look up the customer by API key, falling back to the sandbox key
+    let (customer, test_mode) = match app_state.customer_repo.find_by_api_key(&api_key).await {
+        Ok(customer) => (customer, false),
+        Err(e) => match app_state.customer_repo.find_by_test_api_key(&api_key).await {
+            Ok(customer) => (customer, true),
+            Err(_) => {
+                error!("Failed to find customer with API key: {}", e);
+                return Err(StatusCode::UNAUTHORIZED);
+            }
+        },
+    };
+    
+    // Without its reseller the customer cannot be checked for suspension, so the request
+    // is refused rather than let through
+    let reseller = match customer.reseller_id {
+        Some(reseller_id) => Some(app_state.reseller_repo.find_by_id(reseller_id).await.map_err(|e| {
+            error!("Failed to load reseller {} of customer {}: {}", reseller_id, customer.id, e);
+            StatusCode::SERVICE_UNAVAILABLE
+        })?),
+        None => None,
+    };
+    
+    // Customers of a sandbox tenant only ever run test jobs, whichever key they use
+    let test_mode = test_mode || reseller.as_ref().is_some_and(Reseller::is_sandbox);
+    
+    // Messages are in the reseller's locale unless the request names a supported language
+    let locale = reseller.as_ref().and_then(Reseller::default_locale);
+    let localized = |mut response: Response| {
+        if let Some(locale) = locale {
+            response.extensions_mut().insert(locale);
         }
+        response
     };
     
-    // Note: Customer struct doesn't have an 'active' field in the current implementation
-    // For now, we'll assume all customers are active
-    // TODO: Add active field to Customer model in Phase 3.3.2
+    // Deactivated customers are refused outright
+    if !customer.active {
+        error!("Rejected {} from customer {}: the customer is deactivated", req.method(), customer.id);
+        return Ok(localized(suspended_response("The account has been deactivated")));
+    }
+    
+    // Customers of a deactivated reseller may be suspended: reads are still allowed
+    if let Some(reseller) = reseller.filter(|reseller| reseller.customers_suspended()) {
+        if !is_read_only(req.method()) {
+            error!("Rejected {} from customer {}: reseller {} is deactivated", req.method(), customer.id, reseller.id);
+            let reason = reseller.deactivation_reason
+                .unwrap_or_else(|| "The account's reseller has been deactivated".to_string());
+            return Ok(localized(suspended_response(&reason)));
+        }
+    }
+    
+    // In read-only mode, set for everyone or for the customer's reseller, only reads are served
+    if !is_read_only(req.method()) && app_state.feature_flags.is_enabled(feature_flag::READ_ONLY, customer.reseller_id).await {
+        error!("Rejected {} from customer {}: the API is in read-only mode", req.method(), customer.id);
+        return Ok(localized(read_only_response()));
+    }
     
     // Customer is authenticated
-    info!("Customer authentication successful: {}", customer.id);
+    info!("Customer authentication successful: {} (test mode: {})", customer.id, test_mode);
     let customer_user = CustomerUser {
         id: customer.id,
         name: customer.name,
         reseller_id: customer.reseller_id,
+        parent_id: customer.parent_id,
+        test_mode,
     };
     
     // Add the customer user to the request extensions
     req.extensions_mut().insert(customer_user);
     
-    // Continue to the handler
-    // Convert request body type to Body for compatibility with next.run()
-    let (parts, _) = req.into_parts();
-    let req = Request::from_parts(parts, Body::empty());
-    Ok(next.run(req).await)
+    // Continue to the handler, keeping the request body intact; its data is read
+    // from the reseller's schema if the reseller has one
+    Ok(localized(with_reseller(customer.reseller_id, next.run(req)).await))
+}
+
+// Whether the request method only reads data
+fn is_read_only(method: &Method) -> bool {
+    matches!(*method, Method::GET | Method::HEAD | Method::OPTIONS)
+}
+
+// Build the error response returned to suspended customers
+fn suspended_response(reason: &str) -> Response {
+    (
+        StatusCode::FORBIDDEN,
+        Json(ApiErrorBody::new("account_suspended").with("reason", reason)),
+    ).into_response()
+}
+
+// Build the error response returned to writes while the API is in read-only mode
+fn read_only_response() -> Response {
+    (
+        StatusCode::SERVICE_UNAVAILABLE,
+        Json(ApiErrorBody::new("read_only").with("reason", "The API is temporarily in read-only mode")),
+    ).into_response()
+}
+
+// Reseller an admin request acts for, from the optional X-Reseller-Id header, so
+// admins can reach data stored in a reseller's own schema
+fn get_admin_reseller_scope<B>(req: &Request<B>) -> Result<Option<Uuid>, StatusCode> {
+    let Some(header) = req.headers().get("X-Reseller-Id") else {
+        return Ok(None);
+    };
+    header.to_str().ok()
+        .and_then(|value| Uuid::parse_str(value).ok())
+        .map(Some)
+        .ok_or_else(|| {
+            error!("Invalid X-Reseller-Id header");
+            StatusCode::BAD_REQUEST
+        })
 }
 
 // Helper function to get the API key from the request header
-fn get_api_key_from_header<B>(req: &Request<B>) -> Option<String> {
+pub(crate) fn get_api_key_from_header<B>(req: &Request<B>) -> Option<String> {
     // First try the Authorization header with Bearer scheme
     if let Some(auth_header) = req.headers().get("Authorization") {
         if let Ok(auth_value) = auth_header.to_str() {
@@ -239,3 +365,19 @@ pub async fn verify_customer_access(
     // Access denied
     Err(StatusCode::FORBIDDEN)
 }
+
+// Utility function to verify access to the resources of a reseller: resellers only
+// reach their own, admins (who carry no reseller) reach those of every reseller
+pub fn verify_reseller_access(
+    reseller_id: Option<Uuid>,
+    extension_reseller: &Option<Extension<ResellerUser>>,
+) -> Result<(), StatusCode> {
+    match extension_reseller {
+        None => Ok(()),
+        Some(Extension(reseller)) if reseller_id == Some(reseller.id) => Ok(()),
+        Some(Extension(reseller)) => {
+            error!("Reseller {} ({}) denied access to resources of reseller {:?}", reseller.name, reseller.id, reseller_id);
+            Err(StatusCode::FORBIDDEN)
+        }
+    }
+}
@@ -0,0 +1,60 @@
+use axum::{
+    body::{self, Body},
+    http::{header, HeaderValue, Request},
+    middleware::Next,
+    response::Response,
+};
+use serde_json::Value;
+use tracing::error;
+
+use innosystem_common::i18n::Locale;
+
+/// Largest error body that is localized
+const MAX_ERROR_BODY_BYTES: usize = 64 * 1024;
+
+/// Add a message in the client's language to JSON error responses
+///
+/// Error bodies carry a stable machine-readable `error` code; this layer adds a
+/// `message` explaining it in the language preferred by the `Accept-Language` header
+/// or, failing that, the default locale of the customer's reseller, which customer
+/// authentication leaves in the response extensions. Bodies that already have a
+/// message or whose code has none are passed through unchanged.
+pub async fn localize_errors(req: Request<Body>, next: Next) -> Response {
+    let requested = req.headers().get(header::ACCEPT_LANGUAGE)
+        .and_then(|value| value.to_str().ok())
+        .and_then(Locale::negotiate);
+
+    let response = next.run(req).await;
+    let is_error = response.status().is_client_error() || response.status().is_server_error();
+    let is_json = response.headers().get(header::CONTENT_TYPE)
+        .and_then(|value| value.to_str().ok())
+        .is_some_and(|content_type| content_type.starts_with("application/json"));
+    if !is_error || !is_json {
+        return response;
+    }
+    let locale = requested
+        .or_else(|| response.extensions().get::<Locale>().copied())
+        .unwrap_or_default();
+
+    let (mut parts, body) = response.into_parts();
+    let bytes = match body::to_bytes(body, MAX_ERROR_BODY_BYTES).await {
+        Ok(bytes) => bytes,
+        Err(e) => {
+            error!("Failed to read error response body: {}", e);
+            return Response::from_parts(parts, Body::empty());
+        }
+    };
+
+    let Ok(Value::Object(mut fields)) = serde_json::from_slice::<Value>(&bytes) else {
+        return Response::from_parts(parts, Body::from(bytes));
+    };
+    let message = fields.get("error").and_then(Value::as_str).and_then(|code| locale.message(code));
+    let Some(message) = message.filter(|_| !fields.contains_key("message")) else {
+        return Response::from_parts(parts, Body::from(bytes));
+    };
+
+    fields.insert("message".to_string(), Value::from(message));
+    parts.headers.remove(header::CONTENT_LENGTH);
+    parts.headers.insert(header::CONTENT_LANGUAGE, HeaderValue::from_static(locale.as_str()));
+    Response::from_parts(parts, Body::from(Value::Object(fields).to_string()))
+}
@@ -1,2 +1,11 @@
 // Export the authentication middleware
 pub mod auth;
+
+// Localization of error messages
+pub mod i18n;
+
+// Detection of brute-force patterns in failed authentications
+pub mod security;
+
+// 503 responses for requests failed by an exhausted connection pool
+pub mod pool_exhaustion;
@@ -0,0 +1,39 @@
+use axum::{
+    body::Body,
+    http::{header, Request, StatusCode},
+    middleware::Next,
+    response::{IntoResponse, Response},
+    Json,
+};
+use tracing::warn;
+
+use innosystem_common::api::ApiErrorBody;
+use innosystem_common::database::detect_pool_exhaustion;
+
+/// Delay clients are asked to wait before retrying a request turned away by an exhausted pool
+const RETRY_AFTER_SECS: u64 = 1;
+
+/// Turn errors caused by an exhausted connection pool into 503 responses
+///
+/// Checkouts give up after the pool's timeout instead of waiting indefinitely, and
+/// handlers report the failure as whatever error they map storage failures to. When a
+/// request fails after one of its checkouts found every connection of the database or
+/// Redis pool in use, the client gets `503 Service Unavailable` with a `Retry-After`
+/// header and the `database_busy` code instead.
+pub async fn reject_when_pool_exhausted(req: Request<Body>, next: Next) -> Response {
+    let method = req.method().clone();
+    let path = req.uri().path().to_string();
+    let (response, exhausted) = detect_pool_exhaustion(next.run(req)).await;
+
+    let failed = response.status().is_client_error() || response.status().is_server_error();
+    if !exhausted || !failed {
+        return response;
+    }
+
+    warn!("{} {} failed on an exhausted connection pool, answering 503 instead of {}", method, path, response.status());
+    (
+        StatusCode::SERVICE_UNAVAILABLE,
+        [(header::RETRY_AFTER, RETRY_AFTER_SECS.to_string())],
+        Json(ApiErrorBody::new("database_busy").with("retry_after_secs", RETRY_AFTER_SECS)),
+    ).into_response()
+}
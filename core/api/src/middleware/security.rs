@@ -0,0 +1,78 @@
+use std::net::SocketAddr;
+
+use axum::{
+    body::Body,
+    extract::{ConnectInfo, State},
+    http::{header, Request, StatusCode},
+    middleware::Next,
+    response::{IntoResponse, Response},
+    Json,
+};
+use tracing::warn;
+
+use innosystem_common::api::ApiErrorBody;
+use innosystem_common::models::request_signature::SIGNATURE_HEADER;
+use innosystem_common::models::security_event::key_prefix;
+use crate::middleware::auth::get_api_key_from_header;
+use crate::state::AppState;
+
+/// Count failed authentications per source IP and API key prefix, and turn away
+/// requests from locked out subjects before they reach authentication
+///
+/// Only requests presenting credentials are checked, so public endpoints stay
+/// reachable from a locked out IP.
+pub async fn detect_auth_failures(
+    State(app_state): State<AppState>,
+    req: Request<Body>,
+    next: Next,
+) -> Response {
+    let api_key = get_api_key_from_header(&req);
+    if api_key.is_none() && !req.headers().contains_key(SIGNATURE_HEADER) {
+        return next.run(req).await;
+    }
+
+    let security = &app_state.security_events;
+    let source_ip = source_ip(&req, security.config().trust_forwarded_for);
+    let key_prefix = api_key.map(|api_key| key_prefix(&api_key, security.config().key_prefix_length))
+        .filter(|prefix| !prefix.is_empty());
+
+    if let Some(retry_after_secs) = security.locked_out(source_ip.as_deref(), key_prefix.as_deref()).await {
+        warn!("Rejected request from {:?} with key prefix {:?}: locked out after failed authentications", source_ip, key_prefix);
+        return locked_out_response(retry_after_secs);
+    }
+
+    let method = req.method().to_string();
+    let path = req.uri().path().to_string();
+    let response = next.run(req).await;
+    if response.status() == StatusCode::UNAUTHORIZED {
+        security.record_failure(source_ip.as_deref(), key_prefix.as_deref()).await;
+        app_state.live_events.auth_failure(source_ip.as_deref(), key_prefix.as_deref(), &method, &path);
+    }
+    response
+}
+
+// IP the request came from: the first `X-Forwarded-For` entry behind a trusted proxy,
+// the peer address otherwise
+fn source_ip(req: &Request<Body>, trust_forwarded_for: bool) -> Option<String> {
+    let forwarded = trust_forwarded_for
+        .then(|| req.headers().get("X-Forwarded-For"))
+        .flatten()
+        .and_then(|value| value.to_str().ok())
+        .and_then(|value| value.split(',').next())
+        .map(|ip| ip.trim().to_string())
+        .filter(|ip| !ip.is_empty());
+
+    forwarded.or_else(|| {
+        req.extensions().get::<ConnectInfo<SocketAddr>>()
+            .map(|ConnectInfo(addr)| addr.ip().to_string())
+    })
+}
+
+// Build the error response returned to locked out subjects
+fn locked_out_response(retry_after_secs: u64) -> Response {
+    (
+        StatusCode::TOO_MANY_REQUESTS,
+        [(header::RETRY_AFTER, retry_after_secs.to_string())],
+        Json(ApiErrorBody::new("auth_locked_out").with("retry_after_secs", retry_after_secs)),
+    ).into_response()
+}
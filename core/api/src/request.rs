@@ -0,0 +1,191 @@
+//! Strict parsing of JSON request bodies
+//!
+//! Request DTOs deny unknown fields, so a typo such as `priorty` is refused instead of
+//! silently falling back to the default, and enumerated fields are parsed into their
+//! typed enums up front. Bodies that do not fit their DTO are answered with 422
+//! Unprocessable Entity and the offending fields, e.g.
+//!
+//! ```json
+//! {"error": "invalid_request_body", "fields": [{"field": "priorty", "message": "unknown field `priorty`, expected one of ..."}]}
+//! ```
+
+use axum::{
+    body::Bytes,
+    extract::{FromRequest, OptionalFromRequest, Request},
+    http::{header, HeaderMap, StatusCode},
+    response::{IntoResponse, Response},
+    Json,
+};
+use serde::de::{self, DeserializeOwned, Deserializer, Unexpected};
+use serde::{Deserialize, Serialize};
+use tracing::{error, warn};
+
+use innosystem_common::api::ApiErrorBody;
+use innosystem_common::models::job::PriorityLevel;
+use innosystem_common::models::job_type::ProcessorType;
+
+/// JSON request body deserialized strictly, rejected with the offending fields
+///
+/// Used in place of axum's `Json` extractor for request bodies; responses are still
+/// written with `Json`.
+#[derive(Debug)]
+pub struct StrictJson<T>(pub T);
+
+/// Field of a request body that could not be used, and why
+#[derive(Debug, Clone, Serialize, PartialEq, Eq)]
+pub struct FieldError {
+    /// Path of the field, dotted for nested fields and with indexes for array items
+    pub field: String,
+    pub message: String,
+}
+
+/// Why a request body was refused
+#[derive(Debug)]
+pub enum BodyRejection {
+    /// The request has no JSON content type (415)
+    UnsupportedMediaType,
+    /// The body is not JSON at all (400)
+    Malformed(String),
+    /// The body is JSON, but some fields are unknown, missing or of the wrong type (422)
+    Invalid(Vec<FieldError>),
+}
+
+impl IntoResponse for BodyRejection {
+    fn into_response(self) -> Response {
+        match self {
+            Self::UnsupportedMediaType => (
+                StatusCode::UNSUPPORTED_MEDIA_TYPE,
+                Json(ApiErrorBody::new("unsupported_media_type")),
+            ).into_response(),
+            Self::Malformed(reason) => (
+                StatusCode::BAD_REQUEST,
+                Json(ApiErrorBody::new("malformed_request_body").with("reason", reason)),
+            ).into_response(),
+            Self::Invalid(fields) => (
+                StatusCode::UNPROCESSABLE_ENTITY,
+                Json(ApiErrorBody::new("invalid_request_body").with("fields", fields)),
+            ).into_response(),
+        }
+    }
+}
+
+impl<T, S> FromRequest<S> for StrictJson<T>
+where
+    T: DeserializeOwned,
+    S: Send + Sync,
+{
+    type Rejection = BodyRejection;
+
+    async fn from_request(req: Request, state: &S) -> Result<Self, Self::Rejection> {
+        if !is_json(req.headers()) {
+            return Err(BodyRejection::UnsupportedMediaType);
+        }
+        let bytes = Bytes::from_request(req, state).await
+            .map_err(|e| BodyRejection::Malformed(e.body_text()))?;
+
+        parse(&bytes).map(StrictJson).inspect_err(|rejection| {
+            if let BodyRejection::Invalid(fields) = rejection {
+                error!("Refusing request body with invalid fields: {:?}", fields);
+            }
+        })
+    }
+}
+
+/// Optional bodies are absent when the request has no content type at all
+impl<T, S> OptionalFromRequest<S> for StrictJson<T>
+where
+    T: DeserializeOwned,
+    S: Send + Sync,
+{
+    type Rejection = BodyRejection;
+
+    async fn from_request(req: Request, state: &S) -> Result<Option<Self>, Self::Rejection> {
+        if req.headers().get(header::CONTENT_TYPE).is_none() {
+            return Ok(None);
+        }
+        <Self as FromRequest<S>>::from_request(req, state).await.map(Some)
+    }
+}
+
+/// Whether the request declares a JSON body, e.g. `application/json` or `application/merge-patch+json`
+fn is_json(headers: &HeaderMap) -> bool {
+    let Some(content_type) = headers.get(header::CONTENT_TYPE).and_then(|value| value.to_str().ok()) else {
+        return false;
+    };
+    let essence = content_type.split(';').next().unwrap_or_default().trim().to_lowercase();
+    essence == "application/json" || (essence.starts_with("application/") && essence.ends_with("+json"))
+}
+
+/// Deserialize a JSON body, keeping the path of the field it failed at
+fn parse<T: DeserializeOwned>(bytes: &[u8]) -> Result<T, BodyRejection> {
+    let mut deserializer = serde_json::Deserializer::from_slice(bytes);
+    let value = serde_path_to_error::deserialize(&mut deserializer).map_err(|e| {
+        let path = e.path().to_string();
+        let inner = e.into_inner();
+        match inner.classify() {
+            serde_json::error::Category::Data => BodyRejection::Invalid(vec![field_error(&path, &inner)]),
+            _ => BodyRejection::Malformed(inner.to_string()),
+        }
+    })?;
+    deserializer.end().map_err(|e| BodyRejection::Malformed(e.to_string()))?;
+    Ok(value)
+}
+
+/// Describe a data error at `path`; missing fields are reported at their own path
+/// rather than at the object lacking them
+fn field_error(path: &str, error: &serde_json::Error) -> FieldError {
+    // The message without serde_json's "at line 1 column 10" suffix
+    let message = error.to_string();
+    let message = message.rsplit_once(" at line ").map_or(message.as_str(), |(message, _)| message).to_string();
+
+    let missing = message.strip_prefix("missing field `").and_then(|rest| rest.strip_suffix('`'));
+    let field = match (path, missing) {
+        (".", Some(name)) => name.to_string(),
+        (path, Some(name)) => format!("{}.{}", path, name),
+        (path, None) => path.to_string(),
+    };
+    FieldError { field, message }
+}
+
+/// Deserialize a priority given as its name, or as its level, 0 (low) to 3 (critical)
+///
+/// Levels are deprecated: they are still accepted, with a warning logged, until clients
+/// have moved to the names responses give priorities as.
+pub fn priority<'de, D: Deserializer<'de>>(deserializer: D) -> Result<PriorityLevel, D::Error> {
+    const EXPECTED: &str = "low, medium, high or critical, or a level from 0 (low) to 3 (critical)";
+
+    #[derive(Deserialize)]
+    #[serde(untagged)]
+    enum Priority {
+        Level(i64),
+        Name(String),
+    }
+
+    match Priority::deserialize(deserializer).map_err(|_| de::Error::custom(format!("invalid priority, expected {}", EXPECTED)))? {
+        Priority::Level(level @ 0..=3) => {
+            let priority = PriorityLevel::from_i32(level as i32);
+            warn!("Priority given as deprecated level {}; send \"{}\" instead", level, priority.as_str());
+            Ok(priority)
+        }
+        Priority::Level(level) => Err(de::Error::invalid_value(Unexpected::Signed(level), &EXPECTED)),
+        Priority::Name(name) => PriorityLevel::from_name(&name)
+            .ok_or_else(|| de::Error::invalid_value(Unexpected::Str(&name), &EXPECTED)),
+    }
+}
+
+/// Deserialize a priority that may be left out or null, see [`priority`]
+pub fn optional_priority<'de, D: Deserializer<'de>>(deserializer: D) -> Result<Option<PriorityLevel>, D::Error> {
+    #[derive(Deserialize)]
+    struct Given(#[serde(deserialize_with = "priority")] PriorityLevel);
+
+    Ok(Option::<Given>::deserialize(deserializer)?.map(|Given(priority)| priority))
+}
+
+/// Deserialize a processor type by its name, e.g. `webhook`
+pub fn processor_type<'de, D: Deserializer<'de>>(deserializer: D) -> Result<ProcessorType, D::Error> {
+    const EXPECTED: &str = "one of sync, async, external_api, batch or webhook";
+
+    let name = String::deserialize(deserializer)?;
+    ProcessorType::from_str(&name)
+        .ok_or_else(|| de::Error::invalid_value(Unexpected::Str(&name), &EXPECTED))
+}
@@ -0,0 +1,282 @@
+use serde_json::{json, Value};
+
+use crate::services::autoscaling::AutoscalingConfig;
+use crate::services::queue_stats::QueueWaitConfig;
+use crate::services::runner_health::RunnerHealthConfig;
+
+/// Name of the Prometheus rule group and prefix of the Grafana dashboard title
+const RULE_GROUP: &str = "innosystem";
+
+/// A single Prometheus alerting rule
+#[derive(Debug, Clone)]
+pub struct AlertRule {
+    pub name: &'static str,
+    /// PromQL expression over the metrics exported by the API
+    pub expr: String,
+    /// Seconds the expression must hold before the alert fires
+    pub for_secs: u64,
+    /// `warning` or `critical`
+    pub severity: &'static str,
+    pub summary: String,
+}
+
+/// Derive the alerting rules from the thresholds the application enforces
+///
+/// Queue alerts follow the autoscaling SLA target and runner bounds; runner alerts
+/// follow the heartbeat, failure rate and processing time thresholds of the health
+/// scoring, so alerts fire exactly when the API reports the matching status. The
+/// starvation alert follows the Low priority wait bound of the queue wait report, and
+/// the poison entry alert fires as soon as runners quarantine a queue entry.
+pub fn alert_rules(autoscaling: &AutoscalingConfig, runner_health: &RunnerHealthConfig, queue_wait: &QueueWaitConfig) -> Vec<AlertRule> {
+    let max_slots = autoscaling.max_runners.max(autoscaling.min_runners).max(1) as u64 * autoscaling.jobs_per_runner.max(1) as u64;
+    let sla_target_secs = autoscaling.sla_target_secs.max(1.0);
+    let check_secs = runner_health.check_interval_secs.max(1);
+
+    vec![
+        AlertRule {
+            name: "InnosystemQueueBacklogExceedsSla",
+            expr: format!(
+                "sum(innosystem_queue_depth * on(job_type_id) innosystem_job_processing_seconds_avg) / {} > {}",
+                max_slots, sla_target_secs
+            ),
+            for_secs: 300,
+            severity: "critical",
+            summary: format!(
+                "The pending backlog cannot be processed within the {}s SLA target even at the maximum of {} runners",
+                sla_target_secs, autoscaling.max_runners
+            ),
+        },
+        AlertRule {
+            name: "InnosystemRunnerCapacityExhausted",
+            expr: format!("innosystem_autoscaling_desired_runners >= {}", autoscaling.max_runners),
+            for_secs: 600,
+            severity: "warning",
+            summary: format!("The advised runner count reached the configured maximum of {}", autoscaling.max_runners),
+        },
+        AlertRule {
+            name: "InnosystemRunnersBelowDesired",
+            expr: "innosystem_autoscaling_current_runners < innosystem_autoscaling_desired_runners".to_string(),
+            for_secs: 900,
+            severity: "warning",
+            summary: "Fewer runners are active than needed to meet the SLA target".to_string(),
+        },
+        AlertRule {
+            name: "InnosystemLowPriorityStarving",
+            expr: format!(
+                "innosystem_queue_wait_seconds{{priority=\"low\",quantile=\"0.9\"}} > {}",
+                queue_wait.low_priority_max_wait_secs
+            ),
+            for_secs: 900,
+            severity: "warning",
+            summary: format!(
+                "Low priority jobs wait longer than {}s for a runner; higher priority work is starving them",
+                queue_wait.low_priority_max_wait_secs
+            ),
+        },
+        AlertRule {
+            name: "InnosystemQueuePoisonEntries",
+            expr: "increase(innosystem_queue_quarantined_total[15m]) > 0".to_string(),
+            for_secs: 0,
+            severity: "warning",
+            summary: "Queue entries that are not job IDs were quarantined; inspect them under /admin/queue/quarantine".to_string(),
+        },
+        AlertRule {
+            name: "InnosystemRunnerHeartbeatLate",
+            expr: format!("innosystem_runner_heartbeat_age_seconds > {}", runner_health.healthy_heartbeat_interval_secs),
+            for_secs: check_secs,
+            severity: "warning",
+            summary: format!(
+                "Runner {{{{ $labels.runner_id }}}} has not sent a heartbeat for more than {}s",
+                runner_health.healthy_heartbeat_interval_secs
+            ),
+        },
+        AlertRule {
+            name: "InnosystemRunnerHeartbeatMissing",
+            expr: format!("innosystem_runner_heartbeat_age_seconds > {}", runner_health.warning_heartbeat_interval_secs),
+            for_secs: check_secs,
+            severity: "critical",
+            summary: format!(
+                "Runner {{{{ $labels.runner_id }}}} has not sent a heartbeat for more than {}s",
+                runner_health.warning_heartbeat_interval_secs
+            ),
+        },
+        AlertRule {
+            name: "InnosystemRunnerFailureRateHigh",
+            expr: format!("innosystem_runner_failure_rate >= {}", runner_health.warning_failure_rate),
+            for_secs: check_secs,
+            severity: "warning",
+            summary: format!(
+                "Runner {{{{ $labels.runner_id }}}} fails at least {:.0}% of its jobs",
+                runner_health.warning_failure_rate * 100.0
+            ),
+        },
+        AlertRule {
+            name: "InnosystemRunnerFailureRateCritical",
+            expr: format!("innosystem_runner_failure_rate >= {}", runner_health.critical_failure_rate),
+            for_secs: check_secs,
+            severity: "critical",
+            summary: format!(
+                "Runner {{{{ $labels.runner_id }}}} fails at least {:.0}% of its jobs",
+                runner_health.critical_failure_rate * 100.0
+            ),
+        },
+        AlertRule {
+            name: "InnosystemRunnerSlow",
+            expr: format!("innosystem_runner_processing_seconds_avg >= {}", runner_health.warning_processing_secs),
+            for_secs: check_secs,
+            severity: "warning",
+            summary: format!(
+                "Jobs on runner {{{{ $labels.runner_id }}}} take {}s or longer on average",
+                runner_health.warning_processing_secs
+            ),
+        },
+        AlertRule {
+            name: "InnosystemRunnerSlowCritical",
+            expr: format!("innosystem_runner_processing_seconds_avg >= {}", runner_health.critical_processing_secs),
+            for_secs: check_secs,
+            severity: "critical",
+            summary: format!(
+                "Jobs on runner {{{{ $labels.runner_id }}}} take {}s or longer on average",
+                runner_health.critical_processing_secs
+            ),
+        },
+    ]
+}
+
+/// Quote a string as a YAML double-quoted scalar
+fn yaml_string(value: &str) -> String {
+    // JSON strings are valid YAML double-quoted scalars
+    Value::String(value.to_string()).to_string()
+}
+
+/// Render the alerting rules as a Prometheus rule file
+pub fn render_prometheus_rules(autoscaling: &AutoscalingConfig, runner_health: &RunnerHealthConfig, queue_wait: &QueueWaitConfig) -> String {
+    let mut out = String::new();
+    out.push_str("# Generated by the InnoSystem API from its SLA, runner health and queue wait configuration\n");
+    out.push_str("groups:\n");
+    out.push_str(&format!("  - name: {}\n", RULE_GROUP));
+    out.push_str("    rules:\n");
+    for rule in alert_rules(autoscaling, runner_health, queue_wait) {
+        out.push_str(&format!("      - alert: {}\n", rule.name));
+        out.push_str(&format!("        expr: {}\n", yaml_string(&rule.expr)));
+        out.push_str(&format!("        for: {}s\n", rule.for_secs));
+        out.push_str("        labels:\n");
+        out.push_str(&format!("          severity: {}\n", rule.severity));
+        out.push_str("        annotations:\n");
+        out.push_str(&format!("          summary: {}\n", yaml_string(&rule.summary)));
+    }
+    out
+}
+
+/// Time series panel with threshold lines at the warning and critical levels
+fn threshold_panel(id: u32, title: &str, expr: &str, unit: &str, warning: f64, critical: f64, (x, y): (u32, u32)) -> Value {
+    json!({
+        "id": id,
+        "type": "timeseries",
+        "title": title,
+        "gridPos": { "h": 8, "w": 12, "x": x, "y": y },
+        "targets": [{ "expr": expr, "legendFormat": "{{runner_id}}", "refId": "A" }],
+        "fieldConfig": {
+            "defaults": {
+                "unit": unit,
+                "thresholds": {
+                    "mode": "absolute",
+                    "steps": [
+                        { "color": "green", "value": null },
+                        { "color": "orange", "value": warning },
+                        { "color": "red", "value": critical },
+                    ],
+                },
+                "custom": { "thresholdsStyle": { "mode": "line" } },
+            },
+        },
+    })
+}
+
+/// Render a Grafana dashboard showing the alerted metrics against the configured thresholds
+pub fn render_grafana_dashboard(autoscaling: &AutoscalingConfig, runner_health: &RunnerHealthConfig, queue_wait: &QueueWaitConfig) -> Value {
+    json!({
+        "title": format!("{} operations", RULE_GROUP),
+        "uid": format!("{}-operations", RULE_GROUP),
+        "schemaVersion": 39,
+        "refresh": "1m",
+        "time": { "from": "now-6h", "to": "now" },
+        "panels": [
+            {
+                "id": 1,
+                "type": "timeseries",
+                "title": "Queue depth per job type",
+                "gridPos": { "h": 8, "w": 12, "x": 0, "y": 0 },
+                "targets": [{ "expr": "innosystem_queue_depth", "legendFormat": "{{job_type_id}}", "refId": "A" }],
+            },
+            {
+                "id": 2,
+                "type": "timeseries",
+                "title": "Runners: current vs. desired",
+                "gridPos": { "h": 8, "w": 12, "x": 12, "y": 0 },
+                "targets": [
+                    { "expr": "innosystem_autoscaling_current_runners", "legendFormat": "current", "refId": "A" },
+                    { "expr": "innosystem_autoscaling_desired_runners", "legendFormat": "desired", "refId": "B" },
+                ],
+                "fieldConfig": {
+                    "defaults": {
+                        "thresholds": {
+                            "mode": "absolute",
+                            "steps": [
+                                { "color": "green", "value": null },
+                                { "color": "red", "value": autoscaling.max_runners },
+                            ],
+                        },
+                        "custom": { "thresholdsStyle": { "mode": "line" } },
+                    },
+                },
+            },
+            threshold_panel(
+                3, "Runner heartbeat age", "innosystem_runner_heartbeat_age_seconds", "s",
+                runner_health.healthy_heartbeat_interval_secs as f64,
+                runner_health.warning_heartbeat_interval_secs as f64,
+                (0, 8),
+            ),
+            threshold_panel(
+                4, "Runner failure rate", "innosystem_runner_failure_rate", "percentunit",
+                runner_health.warning_failure_rate,
+                runner_health.critical_failure_rate,
+                (12, 8),
+            ),
+            threshold_panel(
+                5, "Runner average processing time", "innosystem_runner_processing_seconds_avg", "s",
+                runner_health.warning_processing_secs,
+                runner_health.critical_processing_secs,
+                (0, 16),
+            ),
+            {
+                "id": 6,
+                "type": "timeseries",
+                "title": "Runner health score",
+                "gridPos": { "h": 8, "w": 12, "x": 12, "y": 16 },
+                "targets": [{ "expr": "innosystem_runner_health_score", "legendFormat": "{{runner_id}}", "refId": "A" }],
+                "fieldConfig": { "defaults": { "min": 0, "max": 100 } },
+            },
+            {
+                "id": 7,
+                "type": "timeseries",
+                "title": "Queue wait (p90) per priority",
+                "gridPos": { "h": 8, "w": 12, "x": 0, "y": 24 },
+                "targets": [{ "expr": "innosystem_queue_wait_seconds{quantile=\"0.9\"}", "legendFormat": "{{priority}}", "refId": "A" }],
+                "fieldConfig": {
+                    "defaults": {
+                        "unit": "s",
+                        "thresholds": {
+                            "mode": "absolute",
+                            "steps": [
+                                { "color": "green", "value": null },
+                                { "color": "red", "value": queue_wait.low_priority_max_wait_secs },
+                            ],
+                        },
+                        "custom": { "thresholdsStyle": { "mode": "line" } },
+                    },
+                },
+            },
+        ],
+    })
+}
@@ -0,0 +1,270 @@
+use std::env;
+use std::sync::Arc;
+use std::time::Duration;
+use anyhow::{Result, anyhow};
+use chrono::{DateTime, Duration as ChronoDuration, Utc};
+use serde::Serialize;
+use uuid::Uuid;
+
+use innosystem_common::config::parse_env;
+use innosystem_common::models::load_window::LoadWindowStatus;
+use innosystem_common::repositories::{JobRepository, JobTypeRepository, LoadWindowRepository, RunnerRepository};
+
+/// Runners that sent a heartbeat within this many seconds count as current capacity
+pub(crate) const ACTIVE_RUNNER_WINDOW_SECS: i64 = 180;
+
+/// Configuration for autoscaling advice
+#[derive(Debug, Clone)]
+pub struct AutoscalingConfig {
+    /// Target time (in seconds) within which the pending backlog should be processed
+    pub sla_target_secs: f64,
+    /// Number of jobs a single runner processes concurrently
+    pub jobs_per_runner: u32,
+    /// Lower bound for the advised runner count
+    pub min_runners: u32,
+    /// Upper bound for the advised runner count
+    pub max_runners: u32,
+    /// Processing time assumed for job types without recent completions
+    pub default_processing_secs: f64,
+    /// Window (in minutes) over which processing times are averaged
+    pub sample_window_minutes: i32,
+    /// Endpoint receiving the advice periodically, e.g. an autoscaler's webhook
+    pub webhook_url: Option<String>,
+    /// Interval between two webhook publications
+    pub publish_interval_secs: u64,
+}
+
+impl Default for AutoscalingConfig {
+    fn default() -> Self {
+        Self {
+            sla_target_secs: 300.0,        // 5 minutes
+            jobs_per_runner: 4,
+            min_runners: 1,
+            max_runners: 50,
+            default_processing_secs: 30.0,
+            sample_window_minutes: 60,
+            webhook_url: None,
+            publish_interval_secs: 60,
+        }
+    }
+}
+
+impl AutoscalingConfig {
+    /// Load the configuration from `AUTOSCALING_*` environment variables, using defaults for unset values
+    pub fn from_env() -> Self {
+        let defaults = Self::default();
+        Self {
+            sla_target_secs: parse_env("AUTOSCALING_SLA_TARGET_SECS").unwrap_or(defaults.sla_target_secs),
+            jobs_per_runner: parse_env("AUTOSCALING_JOBS_PER_RUNNER").unwrap_or(defaults.jobs_per_runner).max(1),
+            min_runners: parse_env("AUTOSCALING_MIN_RUNNERS").unwrap_or(defaults.min_runners),
+            max_runners: parse_env("AUTOSCALING_MAX_RUNNERS").unwrap_or(defaults.max_runners),
+            default_processing_secs: parse_env("AUTOSCALING_DEFAULT_PROCESSING_SECS").unwrap_or(defaults.default_processing_secs),
+            sample_window_minutes: parse_env("AUTOSCALING_SAMPLE_WINDOW_MINUTES").unwrap_or(defaults.sample_window_minutes),
+            webhook_url: env::var("AUTOSCALING_WEBHOOK_URL").ok().filter(|url| !url.is_empty()),
+            publish_interval_secs: parse_env("AUTOSCALING_PUBLISH_INTERVAL_SECS").unwrap_or(defaults.publish_interval_secs),
+        }
+    }
+}
+
+/// Capacity demand of a single job type
+#[derive(Debug, Clone, Serialize)]
+pub struct JobTypeDemand {
+    pub job_type_id: Uuid,
+    pub job_type_name: Option<String>,
+    /// Jobs waiting to be picked up
+    pub queue_depth: i64,
+    /// Jobs currently being processed
+    pub running: i64,
+    /// Average processing time used for the estimate
+    pub avg_processing_secs: f64,
+    /// Whether the processing time was measured (false: the configured default was used)
+    pub measured: bool,
+    /// Concurrent job slots needed to clear the backlog within the SLA target
+    pub required_slots: u64,
+}
+
+/// Runner count a load window asks of its pool while it warms up
+#[derive(Debug, Clone, Serialize)]
+pub struct PoolTarget {
+    pub load_window_id: Uuid,
+    pub load_window_name: String,
+    /// Name the runners of the pool register under; None for the whole fleet
+    pub pool: Option<String>,
+    pub target_runners: u32,
+    /// Runners of the pool with a recent heartbeat
+    pub current_runners: u32,
+    pub starts_at: DateTime<Utc>,
+}
+
+/// Advised runner fleet size, derived from queue depth, processing times and the load
+/// windows warming up
+#[derive(Debug, Clone, Serialize)]
+pub struct AutoscalingAdvice {
+    /// Runners with a recent heartbeat
+    pub current_runners: u32,
+    /// Runner count needed to meet the SLA target, within the configured bounds, and
+    /// at least the target of every load window warming up
+    pub desired_runners: u32,
+    /// Runners to add (positive) or remove (negative)
+    pub scale_delta: i64,
+    /// Total pending jobs across all job types
+    pub queue_depth: i64,
+    pub sla_target_secs: f64,
+    pub jobs_per_runner: u32,
+    pub job_types: Vec<JobTypeDemand>,
+    /// Pools to scale up ahead of the load windows warming up, earliest start first
+    pub pool_targets: Vec<PoolTarget>,
+    pub generated_at: DateTime<Utc>,
+}
+
+/// Service computing autoscaling signals for the runner fleet
+pub struct AutoscalingService {
+    job_repo: Arc<dyn JobRepository>,
+    job_type_repo: Arc<dyn JobTypeRepository>,
+    runner_repo: Arc<dyn RunnerRepository>,
+    load_window_repo: Arc<dyn LoadWindowRepository>,
+    config: AutoscalingConfig,
+    client: reqwest::Client,
+}
+
+impl AutoscalingService {
+    /// Create a new AutoscalingService
+    pub fn new(
+        job_repo: Arc<dyn JobRepository>,
+        job_type_repo: Arc<dyn JobTypeRepository>,
+        runner_repo: Arc<dyn RunnerRepository>,
+        load_window_repo: Arc<dyn LoadWindowRepository>,
+        config: Option<AutoscalingConfig>,
+    ) -> Self {
+        Self {
+            job_repo,
+            job_type_repo,
+            runner_repo,
+            load_window_repo,
+            config: config.unwrap_or_default(),
+            client: reqwest::Client::new(),
+        }
+    }
+
+    /// Compute the advised runner count from the current queue
+    ///
+    /// Each job type needs one slot per running job plus enough slots to work off its
+    /// backlog within the SLA target at its average processing time. Load windows warming
+    /// up ask for their target runner count ahead of the load, beyond the configured
+    /// maximum if need be.
+    pub async fn advise(&self) -> Result<AutoscalingAdvice> {
+        let stats = self.job_repo.get_queue_stats_by_job_type(self.config.sample_window_minutes).await
+            .map_err(|e| anyhow!("Failed to load queue statistics: {}", e))?;
+        let job_type_names = self.job_type_repo.list_all().await?
+            .into_iter()
+            .map(|job_type| (job_type.id, job_type.name))
+            .collect::<std::collections::HashMap<_, _>>();
+
+        let sla_target_secs = self.config.sla_target_secs.max(1.0);
+        let mut job_types: Vec<JobTypeDemand> = stats.into_iter()
+            .filter(|s| s.pending > 0 || s.running > 0)
+            .map(|s| {
+                let avg_processing_secs = s.avg_processing_seconds.unwrap_or(self.config.default_processing_secs);
+                let backlog_slots = (s.pending as f64 * avg_processing_secs / sla_target_secs).ceil() as u64;
+                JobTypeDemand {
+                    job_type_id: s.job_type_id,
+                    job_type_name: job_type_names.get(&s.job_type_id).cloned(),
+                    queue_depth: s.pending,
+                    running: s.running,
+                    avg_processing_secs,
+                    measured: s.avg_processing_seconds.is_some(),
+                    required_slots: s.running as u64 + backlog_slots,
+                }
+            })
+            .collect();
+        job_types.sort_by_key(|demand| std::cmp::Reverse(demand.required_slots));
+
+        let required_slots: u64 = job_types.iter().map(|demand| demand.required_slots).sum();
+        let jobs_per_runner = self.config.jobs_per_runner.max(1);
+        let needed = required_slots.div_ceil(jobs_per_runner as u64);
+        let desired_runners = needed
+            .clamp(self.config.min_runners as u64, self.config.max_runners.max(self.config.min_runners) as u64) as u32;
+
+        let since = Utc::now() - ChronoDuration::seconds(ACTIVE_RUNNER_WINDOW_SECS);
+        let active_runners = self.runner_repo.list_active(since).await?;
+        let current_runners = active_runners.len() as u32;
+
+        let pool_targets: Vec<PoolTarget> = self.load_window_repo.list_open().await?
+            .into_iter()
+            .filter(|window| matches!(window.window_status(), LoadWindowStatus::Warming | LoadWindowStatus::Ready))
+            .map(|window| PoolTarget {
+                load_window_id: window.id,
+                current_runners: active_runners.iter().filter(|runner| window.includes_runner(&runner.name)).count() as u32,
+                target_runners: window.target_runners.max(0) as u32,
+                starts_at: window.starts_at,
+                load_window_name: window.name,
+                pool: window.pool,
+            })
+            .collect();
+        let desired_runners = pool_targets.iter().map(|target| target.target_runners).fold(desired_runners, u32::max);
+
+        Ok(AutoscalingAdvice {
+            current_runners,
+            desired_runners,
+            scale_delta: desired_runners as i64 - current_runners as i64,
+            queue_depth: job_types.iter().map(|demand| demand.queue_depth).sum(),
+            sla_target_secs,
+            jobs_per_runner,
+            job_types,
+            pool_targets,
+            generated_at: Utc::now(),
+        })
+    }
+
+    /// Render the advice in the Prometheus text exposition format
+    pub fn render_metrics(advice: &AutoscalingAdvice) -> String {
+        let mut out = String::new();
+        out.push_str("# HELP innosystem_autoscaling_desired_runners Runner count needed to meet the SLA target\n");
+        out.push_str("# TYPE innosystem_autoscaling_desired_runners gauge\n");
+        out.push_str(&format!("innosystem_autoscaling_desired_runners {}\n", advice.desired_runners));
+        out.push_str("# HELP innosystem_autoscaling_current_runners Runners with a recent heartbeat\n");
+        out.push_str("# TYPE innosystem_autoscaling_current_runners gauge\n");
+        out.push_str(&format!("innosystem_autoscaling_current_runners {}\n", advice.current_runners));
+        out.push_str("# HELP innosystem_queue_depth Pending jobs per job type\n");
+        out.push_str("# TYPE innosystem_queue_depth gauge\n");
+        for demand in &advice.job_types {
+            out.push_str(&format!("innosystem_queue_depth{{job_type_id=\"{}\"}} {}\n", demand.job_type_id, demand.queue_depth));
+        }
+        out.push_str("# HELP innosystem_job_processing_seconds_avg Average job processing time per job type\n");
+        out.push_str("# TYPE innosystem_job_processing_seconds_avg gauge\n");
+        for demand in &advice.job_types {
+            out.push_str(&format!("innosystem_job_processing_seconds_avg{{job_type_id=\"{}\"}} {}\n", demand.job_type_id, demand.avg_processing_secs));
+        }
+        out.push_str("# HELP innosystem_autoscaling_pool_target_runners Runner count a load window warming up asks of its pool\n");
+        out.push_str("# TYPE innosystem_autoscaling_pool_target_runners gauge\n");
+        for target in &advice.pool_targets {
+            // Pool names are chosen by admins, so they are escaped as label values
+            let pool = target.pool.as_deref().unwrap_or("").replace('\\', "\\\\").replace('"', "\\\"").replace('\n', "\\n");
+            out.push_str(&format!(
+                "innosystem_autoscaling_pool_target_runners{{load_window_id=\"{}\",pool=\"{}\"}} {}\n",
+                target.load_window_id, pool, target.target_runners
+            ));
+        }
+        out
+    }
+
+    /// Compute the advice and POST it to the configured webhook, if any
+    pub async fn publish(&self) -> Result<Option<AutoscalingAdvice>> {
+        let Some(url) = self.config.webhook_url.as_deref() else {
+            return Ok(None);
+        };
+
+        let advice = self.advise().await?;
+        let response = self.client.post(url)
+            .timeout(Duration::from_secs(10))
+            .json(&advice)
+            .send()
+            .await
+            .map_err(|e| anyhow!("Failed to publish autoscaling advice: {}", e))?;
+
+        if !response.status().is_success() {
+            return Err(anyhow!("Autoscaling webhook responded with status {}", response.status()));
+        }
+        Ok(Some(advice))
+    }
+}
@@ -0,0 +1,338 @@
+use std::collections::HashMap;
+use std::env;
+use std::sync::{Arc, RwLock};
+use std::sync::atomic::{AtomicU64, Ordering};
+use chrono::{DateTime, Duration, Utc};
+use serde::Serialize;
+use tracing::warn;
+use uuid::Uuid;
+
+use innosystem_common::models::job::PriorityLevel;
+use innosystem_common::queue::JobQueue;
+use innosystem_common::repositories::JobRepository;
+
+
+/// Priority levels in the order their limits are configured
+const PRIORITIES: [PriorityLevel; 4] = [
+    PriorityLevel::Critical,
+    PriorityLevel::High,
+    PriorityLevel::Medium,
+    PriorityLevel::Low,
+];
+
+/// What happens to jobs submitted while their queue is saturated
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum SaturationPolicy {
+    /// Refuse the job with 429 Too Many Requests and a Retry-After
+    Reject,
+    /// Accept the job and schedule it for after the retry delay
+    Defer,
+}
+
+impl SaturationPolicy {
+    /// Parse a policy from its configuration value
+    pub fn parse(value: &str) -> Option<Self> {
+        match value {
+            "reject" => Some(Self::Reject),
+            "defer" => Some(Self::Defer),
+            _ => None,
+        }
+    }
+}
+
+/// Configuration for limiting the depth of the job queues
+#[derive(Debug, Clone, PartialEq)]
+pub struct BackpressureConfig {
+    /// Most jobs waiting per priority level (critical, high, medium, low); 0 means unlimited
+    pub priority_limits: [u64; 4],
+    /// Most pending jobs per job type; job types not listed are unlimited
+    pub job_type_limits: HashMap<Uuid, u64>,
+    /// What happens to jobs submitted while a limit is exceeded
+    pub policy: SaturationPolicy,
+    /// Seconds clients are asked to wait before retrying, and by which deferred jobs are delayed
+    pub retry_after_secs: u64,
+}
+
+impl Default for BackpressureConfig {
+    fn default() -> Self {
+        Self {
+            priority_limits: [0; 4],
+            job_type_limits: HashMap::new(),
+            policy: SaturationPolicy::Reject,
+            retry_after_secs: 30,
+        }
+    }
+}
+
+impl BackpressureConfig {
+    /// Load the configuration from `BACKPRESSURE_*` environment variables, using defaults for unset values
+    ///
+    /// Priority limits are four comma-separated values, highest priority first; job type
+    /// limits are comma-separated `job_type_id=limit` pairs.
+    pub fn from_env() -> Self {
+        let defaults = Self::default();
+        Self {
+            priority_limits: env::var("BACKPRESSURE_PRIORITY_LIMITS").ok()
+                .and_then(|value| parse_priority_limits(&value))
+                .unwrap_or(defaults.priority_limits),
+            job_type_limits: env::var("BACKPRESSURE_JOB_TYPE_LIMITS").ok()
+                .map(|value| parse_job_type_limits(&value))
+                .unwrap_or(defaults.job_type_limits),
+            policy: env::var("BACKPRESSURE_POLICY").ok()
+                .and_then(|value| SaturationPolicy::parse(&value))
+                .unwrap_or(defaults.policy),
+            retry_after_secs: env::var("BACKPRESSURE_RETRY_AFTER_SECS").ok()
+                .and_then(|value| value.parse().ok())
+                .filter(|secs| *secs > 0)
+                .unwrap_or(defaults.retry_after_secs),
+        }
+    }
+
+    /// Check that the `BACKPRESSURE_*` variables that are set hold valid values, which
+    /// `from_env` would otherwise replace by defaults or skip
+    pub fn validate_env() -> Result<(), String> {
+        if let Ok(value) = env::var("BACKPRESSURE_PRIORITY_LIMITS") {
+            parse_priority_limits(&value)
+                .ok_or_else(|| format!("BACKPRESSURE_PRIORITY_LIMITS must be four comma-separated limits, got {}", value))?;
+        }
+        if let Ok(value) = env::var("BACKPRESSURE_JOB_TYPE_LIMITS") {
+            let pairs = value.split(',').filter(|pair| !pair.trim().is_empty()).count();
+            if parse_job_type_limits(&value).len() != pairs {
+                return Err(format!("BACKPRESSURE_JOB_TYPE_LIMITS must be job_type_id=limit pairs, got {}", value));
+            }
+        }
+        if let Ok(value) = env::var("BACKPRESSURE_POLICY") {
+            SaturationPolicy::parse(&value)
+                .ok_or_else(|| format!("BACKPRESSURE_POLICY must be reject or defer, got {}", value))?;
+        }
+        if let Ok(value) = env::var("BACKPRESSURE_RETRY_AFTER_SECS") {
+            value.parse::<u64>().ok()
+                .filter(|secs| *secs > 0)
+                .ok_or_else(|| format!("BACKPRESSURE_RETRY_AFTER_SECS must be a positive number of seconds, got {}", value))?;
+        }
+        Ok(())
+    }
+
+    /// Whether any limit is configured
+    pub fn is_enabled(&self) -> bool {
+        self.priority_limits.iter().any(|limit| *limit > 0) || !self.job_type_limits.is_empty()
+    }
+
+    /// Configured depth limit of a priority level
+    fn priority_limit(&self, priority: &PriorityLevel) -> Option<u64> {
+        let index = PRIORITIES.iter().position(|p| p == priority)?;
+        Some(self.priority_limits[index]).filter(|limit| *limit > 0)
+    }
+}
+
+/// Parse four comma-separated limits, one per priority level
+fn parse_priority_limits(value: &str) -> Option<[u64; 4]> {
+    let limits: Vec<u64> = value.split(',').map(|part| part.trim().parse().ok()).collect::<Option<_>>()?;
+    limits.try_into().ok()
+}
+
+/// Parse `job_type_id=limit` pairs, skipping malformed ones
+fn parse_job_type_limits(value: &str) -> HashMap<Uuid, u64> {
+    value.split(',')
+        .filter(|pair| !pair.trim().is_empty())
+        .filter_map(|pair| {
+            let parsed = pair.split_once('=')
+                .and_then(|(id, limit)| Some((Uuid::parse_str(id.trim()).ok()?, limit.trim().parse().ok()?)));
+            if parsed.is_none() {
+                warn!("Ignoring malformed job type queue limit: {}", pair);
+            }
+            parsed
+        })
+        .collect()
+}
+
+/// Whether a submitted job may enter the queue
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Admission {
+    Accept,
+    /// Refuse the job; the client should retry after the given number of seconds
+    Reject { retry_after_secs: u64 },
+    /// Accept the job but hold it back until the given time
+    Defer { until: DateTime<Utc> },
+}
+
+/// Depth of a queue against its limit
+#[derive(Debug, Clone, Serialize)]
+pub struct QueueDepth {
+    /// Priority label or job type ID
+    pub queue: String,
+    pub depth: u64,
+    pub limit: u64,
+    pub saturated: bool,
+}
+
+/// Depth of every limited queue and the jobs turned away so far
+#[derive(Debug, Clone, Serialize)]
+pub struct BackpressureStatus {
+    pub enabled: bool,
+    pub policy: SaturationPolicy,
+    /// Whether any limited queue is at or above its limit
+    pub saturated: bool,
+    pub priorities: Vec<QueueDepth>,
+    pub job_types: Vec<QueueDepth>,
+    /// Jobs refused since the API started
+    pub rejected_jobs: u64,
+    /// Jobs deferred since the API started
+    pub deferred_jobs: u64,
+    pub generated_at: DateTime<Utc>,
+}
+
+/// Service keeping job submission from growing the queues without bound
+///
+/// Priority levels are limited by the length of their Redis list and job types by
+/// their pending jobs in the database. When the depth cannot be read the job is
+/// accepted, so an unreachable queue never blocks submissions on its own. The limits
+/// can be replaced while the API runs; a check in progress keeps the ones it started with.
+pub struct BackpressureService {
+    job_queue: Arc<dyn JobQueue>,
+    job_repo: Arc<dyn JobRepository>,
+    config: RwLock<Arc<BackpressureConfig>>,
+    rejected: AtomicU64,
+    deferred: AtomicU64,
+}
+
+impl BackpressureService {
+    /// Create a new BackpressureService
+    pub fn new(job_queue: Arc<dyn JobQueue>, job_repo: Arc<dyn JobRepository>, config: Option<BackpressureConfig>) -> Self {
+        Self {
+            job_queue,
+            job_repo,
+            config: RwLock::new(Arc::new(config.unwrap_or_default())),
+            rejected: AtomicU64::new(0),
+            deferred: AtomicU64::new(0),
+        }
+    }
+
+    /// Limits currently applied
+    pub fn config(&self) -> Arc<BackpressureConfig> {
+        self.config.read().unwrap_or_else(|e| e.into_inner()).clone()
+    }
+
+    /// Apply reloaded limits to the submissions checked from now on
+    pub fn reconfigure(&self, config: BackpressureConfig) {
+        *self.config.write().unwrap_or_else(|e| e.into_inner()) = Arc::new(config);
+    }
+
+    /// Policy applied to jobs submitted while their queue is saturated
+    pub fn policy(&self) -> SaturationPolicy {
+        self.config().policy
+    }
+
+    /// Whether the queue of the given priority or job type is at or above its limit
+    pub async fn is_saturated(&self, priority: &PriorityLevel, job_type_id: Uuid) -> bool {
+        let config = self.config();
+        if let Some(limit) = config.priority_limit(priority) {
+            if self.priority_depth(priority).await.is_some_and(|depth| depth >= limit) {
+                return true;
+            }
+        }
+        match config.job_type_limits.get(&job_type_id) {
+            Some(limit) => self.job_type_depth(job_type_id).await.is_some_and(|depth| depth >= *limit),
+            None => false,
+        }
+    }
+
+    /// Decide whether a job of the given priority and type may enter the queue now
+    pub async fn admit(&self, priority: &PriorityLevel, job_type_id: Uuid) -> Admission {
+        if !self.is_saturated(priority, job_type_id).await {
+            return Admission::Accept;
+        }
+
+        let config = self.config();
+        warn!(
+            "Queue saturated for {} priority jobs of type {}, {} the submission",
+            priority.as_str(),
+            job_type_id,
+            match config.policy {
+                SaturationPolicy::Reject => "rejecting",
+                SaturationPolicy::Defer => "deferring",
+            }
+        );
+        match config.policy {
+            SaturationPolicy::Reject => {
+                self.rejected.fetch_add(1, Ordering::Relaxed);
+                Admission::Reject { retry_after_secs: config.retry_after_secs }
+            }
+            SaturationPolicy::Defer => {
+                self.deferred.fetch_add(1, Ordering::Relaxed);
+                let until = Utc::now() + Duration::seconds(config.retry_after_secs as i64);
+                Admission::Defer { until }
+            }
+        }
+    }
+
+    /// Report the depth of every limited queue
+    pub async fn status(&self) -> BackpressureStatus {
+        let config = self.config();
+        let mut priorities = Vec::new();
+        for priority in &PRIORITIES {
+            let Some(limit) = config.priority_limit(priority) else { continue };
+            let depth = self.priority_depth(priority).await.unwrap_or(0);
+            priorities.push(QueueDepth { queue: priority.as_str().to_string(), depth, limit, saturated: depth >= limit });
+        }
+
+        let mut job_types = Vec::new();
+        for (job_type_id, limit) in &config.job_type_limits {
+            let depth = self.job_type_depth(*job_type_id).await.unwrap_or(0);
+            job_types.push(QueueDepth { queue: job_type_id.to_string(), depth, limit: *limit, saturated: depth >= *limit });
+        }
+        job_types.sort_by(|a, b| a.queue.cmp(&b.queue));
+
+        BackpressureStatus {
+            enabled: config.is_enabled(),
+            policy: config.policy,
+            saturated: priorities.iter().chain(&job_types).any(|queue| queue.saturated),
+            priorities,
+            job_types,
+            rejected_jobs: self.rejected.load(Ordering::Relaxed),
+            deferred_jobs: self.deferred.load(Ordering::Relaxed),
+            generated_at: Utc::now(),
+        }
+    }
+
+    /// Render the queue depths and turned away jobs in the Prometheus text exposition format
+    pub fn render_metrics(status: &BackpressureStatus) -> String {
+        let mut out = String::new();
+        out.push_str("# HELP innosystem_queue_saturated Whether a queue is at or above its depth limit\n");
+        out.push_str("# TYPE innosystem_queue_saturated gauge\n");
+        for (label, queues) in [("priority", &status.priorities), ("job_type_id", &status.job_types)] {
+            for queue in queues {
+                out.push_str(&format!("innosystem_queue_saturated{{{}=\"{}\"}} {}\n", label, queue.queue, u8::from(queue.saturated)));
+            }
+        }
+        out.push_str("# HELP innosystem_queue_depth_limit Depth limit of a queue\n");
+        out.push_str("# TYPE innosystem_queue_depth_limit gauge\n");
+        for (label, queues) in [("priority", &status.priorities), ("job_type_id", &status.job_types)] {
+            for queue in queues {
+                out.push_str(&format!("innosystem_queue_depth_limit{{{}=\"{}\"}} {}\n", label, queue.queue, queue.limit));
+            }
+        }
+        out.push_str("# HELP innosystem_backpressure_jobs_total Jobs turned away from a saturated queue\n");
+        out.push_str("# TYPE innosystem_backpressure_jobs_total counter\n");
+        out.push_str(&format!("innosystem_backpressure_jobs_total{{action=\"rejected\"}} {}\n", status.rejected_jobs));
+        out.push_str(&format!("innosystem_backpressure_jobs_total{{action=\"deferred\"}} {}\n", status.deferred_jobs));
+        out
+    }
+
+    /// Number of jobs waiting in the queue of a priority level
+    async fn priority_depth(&self, priority: &PriorityLevel) -> Option<u64> {
+        self.job_queue.queue_length_by_priority(priority.clone()).await
+            .map(|depth| depth as u64)
+            .map_err(|e| warn!("Failed to read the {} priority queue depth: {}", priority.as_str(), e))
+            .ok()
+    }
+
+    /// Number of pending jobs of a job type
+    async fn job_type_depth(&self, job_type_id: Uuid) -> Option<u64> {
+        self.job_repo.count_pending_by_job_type(job_type_id).await
+            .map(|depth| depth as u64)
+            .map_err(|e| warn!("Failed to count the pending jobs of job type {}: {}", job_type_id, e))
+            .ok()
+    }
+}
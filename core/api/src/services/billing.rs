@@ -1,10 +1,118 @@
 use std::sync::Arc;
 use uuid::Uuid;
-use anyhow::{Result, Context, anyhow};
+use anyhow::{Result, Context};
+use chrono::{DateTime, Duration, Utc};
+use serde::Serialize;
+use serde_json::json;
 use tracing::{info, error, warn};
 
 // Import wallet models when needed
-use innosystem_common::repositories::{JobRepository, JobTypeRepository, WalletRepository, CustomerRepository};
+use innosystem_common::config::parse_env;
+use innosystem_common::Error;
+use innosystem_common::models::job::JobStatus;
+use innosystem_common::models::job_cost::{CostBreakdown, FAILURE_FEE_RATE};
+use innosystem_common::models::job_error::{codes, JobError};
+use innosystem_common::models::ledger::JobDebit;
+use innosystem_common::models::reseller::Reseller;
+use innosystem_common::models::wallet::Wallet;
+use innosystem_common::models::webhook::WebhookEventType;
+use innosystem_common::repositories::{JobRepository, JobTypeRepository, WalletRepository, CustomerRepository, ResellerRepository};
+
+use crate::services::WebhookService;
+
+/// Configuration for expiring reservations of jobs that are never processed
+#[derive(Debug, Clone)]
+pub struct ReservationExpiryConfig {
+    /// Minutes a pending or scheduled job may hold reserved funds before it is cancelled (0 disables expiry)
+    pub hold_ttl_minutes: i64,
+    /// Interval between two sweeps for expired reservations
+    pub sweep_interval_secs: u64,
+}
+
+impl Default for ReservationExpiryConfig {
+    fn default() -> Self {
+        Self {
+            hold_ttl_minutes: 24 * 60,     // 1 day
+            sweep_interval_secs: 300,
+        }
+    }
+}
+
+impl ReservationExpiryConfig {
+    /// Load the configuration from `RESERVATION_*` environment variables, using defaults for unset values
+    pub fn from_env() -> Self {
+        let defaults = Self::default();
+        Self {
+            hold_ttl_minutes: parse_env("RESERVATION_HOLD_TTL_MINUTES").unwrap_or(defaults.hold_ttl_minutes),
+            sweep_interval_secs: parse_env("RESERVATION_SWEEP_INTERVAL_SECS").unwrap_or(defaults.sweep_interval_secs),
+        }
+    }
+}
+
+/// Configuration for manual wallet adjustments needing a second admin's approval
+#[derive(Debug, Clone)]
+pub struct WalletApprovalConfig {
+    /// Largest adjustment or refund in cents, in either direction, an admin may post alone
+    pub threshold_cents: i32,
+}
+
+impl Default for WalletApprovalConfig {
+    fn default() -> Self {
+        Self {
+            threshold_cents: 10_000,       // 100.00
+        }
+    }
+}
+
+impl WalletApprovalConfig {
+    /// Load the configuration from `WALLET_APPROVAL_*` environment variables, using defaults for unset values
+    pub fn from_env() -> Self {
+        let defaults = Self::default();
+        Self {
+            threshold_cents: parse_env::<i32>("WALLET_APPROVAL_THRESHOLD_CENTS")
+                .filter(|cents| *cents >= 0)
+                .unwrap_or(defaults.threshold_cents),
+        }
+    }
+
+    /// Whether an adjustment of the given amount must be approved by a second admin
+    pub fn requires_approval(&self, amount_cents: i32) -> bool {
+        amount_cents.unsigned_abs() > self.threshold_cents as u32
+    }
+}
+
+/// A job cancelled because its reservation expired
+#[derive(Debug, Clone, Serialize)]
+pub struct ExpiredReservation {
+    pub job_id: Uuid,
+    pub customer_id: Uuid,
+    /// Amount returned to the customer's wallet
+    pub released_cents: i32,
+    /// Number of customer webhook endpoints that accepted the cancellation event
+    pub notified_endpoints: usize,
+}
+
+/// A job that expired because it was not started before its deadline
+#[derive(Debug, Clone, Serialize)]
+pub struct ExpiredJob {
+    pub job_id: Uuid,
+    pub customer_id: Uuid,
+    pub expires_at: Option<DateTime<Utc>>,
+    /// Amount returned to the customer's wallet
+    pub released_cents: i32,
+    /// Number of customer webhook endpoints that accepted the expiry event
+    pub notified_endpoints: usize,
+}
+
+/// What became of a request to cancel a job
+#[derive(Debug, Clone, Serialize)]
+#[serde(tag = "outcome", rename_all = "snake_case")]
+pub enum JobCancellation {
+    /// The job had not started and was cancelled, releasing what was reserved for it
+    Cancelled { released_cents: i32 },
+    /// The job is running; its runner stops it and charges the failure fee
+    Requested { requested_at: DateTime<Utc> },
+}
 
 /// Service for handling billing and cost calculation operations
 pub struct BillingService {
@@ -12,6 +120,9 @@ pub struct BillingService {
     job_type_repo: Arc<dyn JobTypeRepository>,
     wallet_repo: Arc<dyn WalletRepository>,
     customer_repo: Arc<dyn CustomerRepository>,
+    reseller_repo: Arc<dyn ResellerRepository>,
+    webhook_service: Arc<WebhookService>,
+    reservation_expiry: ReservationExpiryConfig,
 }
 
 impl BillingService {
@@ -21,53 +132,83 @@ impl BillingService {
         job_type_repo: Arc<dyn JobTypeRepository>,
         wallet_repo: Arc<dyn WalletRepository>,
         customer_repo: Arc<dyn CustomerRepository>,
+        reseller_repo: Arc<dyn ResellerRepository>,
+        webhook_service: Arc<WebhookService>,
+        reservation_expiry: Option<ReservationExpiryConfig>,
     ) -> Self {
         Self {
             job_repo,
             job_type_repo,
             wallet_repo,
             customer_repo,
+            reseller_repo,
+            webhook_service,
+            reservation_expiry: reservation_expiry.unwrap_or_default(),
         }
     }
     
-    /// Calculate the actual cost of a completed job
-    pub async fn calculate_job_cost(&self, job_id: Uuid) -> Result<i32> {
+    /// Find the wallet paying for a customer's jobs: the parent's wallet for sub-accounts
+    pub async fn find_billing_wallet(&self, customer_id: Uuid) -> Result<Wallet> {
+        let customer = self.customer_repo.find_by_id(customer_id)
+            .await
+            .context("Failed to find customer")?;
+        
+        self.wallet_repo.find_by_customer_id(customer.billing_customer_id())
+            .await
+            .context("Failed to find customer wallet")
+    }
+    
+    /// Find the reseller of a customer, if it has one
+    async fn find_reseller(&self, customer_id: Uuid) -> Result<Option<Reseller>> {
+        let customer = self.customer_repo.find_by_id(customer_id)
+            .await
+            .context("Failed to fetch customer")?;
+        match customer.reseller_id {
+            Some(reseller_id) => self.reseller_repo.find_by_id(reseller_id)
+                .await
+                .map(Some)
+                .context("Failed to fetch reseller"),
+            None => Ok(None),
+        }
+    }
+    
+    /// Itemize the actual cost of a finished job
+    ///
+    /// A job that succeeded costs its job type's standard cost with the surcharge of its
+    /// priority, marked up by the customer's reseller; a job that failed costs a share
+    /// of its estimated cost as failure fee.
+    pub async fn calculate_job_cost(&self, job_id: Uuid, success: bool) -> Result<CostBreakdown> {
         // Fetch the job
         let job = self.job_repo.find_by_id(job_id)
             .await
             .context("Failed to fetch job for cost calculation")?;
         
+        if !success {
+            return Ok(CostBreakdown::failed(job.estimated_cost_cents, FAILURE_FEE_RATE));
+        }
+        
         // Fetch the job type to get the standard cost
         let job_type = self.job_type_repo.find_by_id(job.job_type_id)
             .await
             .context("Failed to fetch job type for cost calculation")?;
         
-        // Start with the base cost from the job type
-        let mut final_cost = job_type.standard_cost_cents;
-        
-        // Apply dynamic cost factors based on job details
-        // For now, we'll use a simple multiplier based on priority
-        let priority_multiplier = match job.priority.as_i32() {
-            0 => 1.0,   // Low priority - standard cost
-            1 => 1.0,   // Medium priority - standard cost
-            2 => 1.5,   // High priority - 50% premium
-            3 => 2.0,   // Critical priority - 100% premium
-            _ => 1.0,   // Default
-        };
-        
-        // Apply priority multiplier
-        final_cost = (final_cost as f64 * priority_multiplier).round() as i32;
+        // Resellers may mark up the price of their customers' jobs
+        let markup_rate = self.find_reseller(job.customer_id)
+            .await?
+            .map_or(0, |reseller| reseller.markup_rate);
         
-        // Apply any other business rules for cost adjustment
-        // (In the future, this could include duration-based costs, resource usage, etc.)
+        let cost = CostBreakdown::completed(job_type.standard_cost_cents, &job.priority, markup_rate);
         
-        info!("Calculated final cost for job {}: {} cents", job_id, final_cost);
+        info!("Calculated final cost for job {}: {} cents", job_id, cost.total());
         
-        Ok(final_cost)
+        Ok(cost)
     }
     
     /// Process billing for a completed job
     /// This method handles the wallet transaction and updates the job record
+    ///
+    /// Promotional credit is spent before the balance; the itemized cost is recorded on
+    /// the job.
     pub async fn process_job_billing(&self, job_id: Uuid, success: bool) -> Result<()> {
         // Fetch the job
         let job = self.job_repo.find_by_id(job_id)
@@ -75,26 +216,27 @@ impl BillingService {
             .context("Failed to fetch job for billing")?;
         
         // Calculate the actual cost of the job
-        let actual_cost = if success {
-            self.calculate_job_cost(job_id).await?
-        } else {
-            // For failed jobs, we might charge a reduced fee or nothing
-            // For now, let's charge 25% of the estimated cost for failed jobs
-            (job.estimated_cost_cents as f64 * 0.25).round() as i32
-        };
+        let mut cost = self.calculate_job_cost(job_id, success).await?;
         
         // Try to find the customer's wallet
-        let wallet = match self.wallet_repo.find_by_customer_id(job.customer_id).await {
+        let wallet = match self.find_billing_wallet(job.customer_id).await {
             Ok(wallet) => wallet,
             Err(e) => {
                 error!("Failed to find wallet for customer {}: {}", job.customer_id, e);
-                return Err(anyhow!("Customer wallet not found"));
+                return Err(JobError::input(codes::BILLING_FAILED, "Customer wallet not found").into());
             }
         };
         
-        // Perform the wallet transaction
-        // Use the correct transaction type from the model
-        // JobDebit for all jobs (successful and failed) with different descriptions
+        let credits = self.wallet_repo.spend_credit(wallet.id, cost.total())
+            .await
+            .map_err(|e| JobError::system(codes::BILLING_FAILED, format!("Failed to apply promotional credit: {}", e)))?;
+        cost.apply_credits(credits as i64);
+        
+        // Charge a job debit for all jobs (successful and failed) with different descriptions,
+        // split in the ledger between the platform and the customer's reseller
+        let reseller = self.find_reseller(job.customer_id)
+            .await
+            .map_err(|e| JobError::system(codes::BILLING_FAILED, format!("Failed to find the customer's reseller: {}", e)))?;
         
         let description = format!(
             "{} job {} - {}",
@@ -109,15 +251,18 @@ impl BillingService {
         
         // Check if there's a reservation to release or create a new charge
         // In a real system, you'd have a record of the reservation
-        // Here we'll just create a new withdrawal
-        match self.wallet_repo.withdraw(
-            wallet.id,
-            actual_cost,
-            Some(description),
-            Some(job_id)
-        ).await {
+        // Here we'll just create a new charge the balance must cover, unless credit covered the whole cost
+        let charged = if cost.total() > 0 {
+            let debit = JobDebit::new(wallet.id, job_id, &cost, reseller.as_ref())
+                .with_description(description)
+                .requiring_funds();
+            self.wallet_repo.charge_job(debit).await.map(|_| ())
+        } else {
+            Ok(())
+        };
+        match charged {
             Ok(_) => {
-                info!("Successfully charged {} cents for job {}", actual_cost, job_id);
+                info!("Successfully charged {} cents for job {}", cost.total(), job_id);
                 
                 // Update the job with the final cost
                 if let Err(e) = self.job_repo.set_completed(
@@ -125,19 +270,32 @@ impl BillingService {
                     success,
                     job.output_data.clone(),
                     job.error.clone(),
-                    actual_cost
+                    cost.total()
                 ).await {
                     error!("Failed to update job with final cost: {}", e);
                     // We don't want to fail the whole operation if just the cost update fails
                     // The customer has been charged, but the job record might not reflect the final cost
                     warn!("Job {} completed and customer charged, but job record not updated with final cost", job_id);
                 }
+                if let Err(e) = self.job_repo.set_cost_breakdown(job_id, cost).await {
+                    warn!("Job {} charged, but its cost breakdown was not recorded: {}", job_id, e);
+                }
                 
                 Ok(())
             },
             Err(e) => {
                 error!("Failed to process payment for job {}: {}", job_id, e);
-                Err(anyhow!("Payment processing failed: {}", e))
+                // Give back the credit spent on the charge that failed
+                if credits > 0 {
+                    if let Err(e) = self.wallet_repo.grant_credit(wallet.id, credits).await {
+                        error!("Failed to return {} cents of promotional credit to wallet {}: {}", credits, wallet.id, e);
+                    }
+                }
+                if e.to_string().contains("Insufficient funds") {
+                    Err(JobError::input(codes::INSUFFICIENT_FUNDS, format!("Payment processing failed: {}", e)).into())
+                } else {
+                    Err(JobError::system(codes::BILLING_FAILED, format!("Payment processing failed: {}", e)).into())
+                }
             }
         }
     }
@@ -151,9 +309,7 @@ impl BillingService {
             .context("Failed to fetch job for fund reservation")?;
         
         // Find the customer's wallet
-        let wallet = self.wallet_repo.find_by_customer_id(job.customer_id)
-            .await
-            .context("Failed to find customer wallet")?;
+        let wallet = self.find_billing_wallet(job.customer_id).await?;
         
         // Reserve the estimated cost
         let description = format!("Reservation for job {}", job_id);
@@ -179,9 +335,7 @@ impl BillingService {
             .context("Failed to fetch job for releasing funds")?;
         
         // Find the customer's wallet
-        let wallet = self.wallet_repo.find_by_customer_id(job.customer_id)
-            .await
-            .context("Failed to find customer wallet")?;
+        let wallet = self.find_billing_wallet(job.customer_id).await?;
         
         // Release the reserved funds
         let description = format!("Release reservation for job {}", job_id);
@@ -198,4 +352,195 @@ impl BillingService {
         
         Ok(())
     }
+    
+    /// Cancel a job that has not started yet and release what is still reserved for it
+    ///
+    /// Returns the amount released, or None if the job started or finished in the meantime.
+    pub async fn cancel_job(&self, job_id: Uuid) -> Result<Option<i32>> {
+        match self.job_repo.update_status(job_id, JobStatus::Cancelled).await {
+            Ok(_) => {}
+            Err(Error::InvalidInput(_)) => return Ok(None),
+            Err(e) => return Err(e).context("Failed to cancel job"),
+        }
+        
+        let job = self.job_repo.find_by_id(job_id)
+            .await
+            .context("Failed to fetch cancelled job")?;
+        let held = self.wallet_repo.get_reserved_for_job(job_id)
+            .await
+            .context("Failed to load reserved funds for job")?;
+        if held > 0 {
+            let wallet = self.find_billing_wallet(job.customer_id).await?;
+            self.wallet_repo.release_reservation(
+                wallet.id,
+                held,
+                Some(format!("Release reservation for cancelled job {}", job_id)),
+                Some(job_id)
+            ).await
+            .context("Failed to release reservation of cancelled job")?;
+        }
+        
+        info!("Cancelled job {}, releasing {} cents", job_id, held.max(0));
+        Ok(Some(held.max(0)))
+    }
+    
+    /// Cancel a job at the request of its customer or an admin
+    ///
+    /// A job that has not started is cancelled right away. For a running job the runner
+    /// processing it is asked to stop: it cuts the job short, charges the failure fee
+    /// failed jobs pay and reports the job as cancelled. Fails with `Error::InvalidInput`
+    /// if the job already finished or is waiting for its sub-tasks.
+    pub async fn request_cancellation(&self, job_id: Uuid) -> Result<JobCancellation> {
+        let job = self.job_repo.find_by_id(job_id)
+            .await
+            .context("Failed to fetch job to cancel")?;
+        if matches!(job.status, JobStatus::Pending | JobStatus::Scheduled) {
+            if let Some(released_cents) = self.cancel_job(job_id).await? {
+                return Ok(JobCancellation::Cancelled { released_cents });
+            }
+        }
+
+        let job = self.job_repo.request_cancellation(job_id).await?;
+        let requested_at = job.cancel_requested_at.unwrap_or_else(Utc::now);
+        info!("Requested the cancellation of running job {} from runner {:?}", job_id, job.runner_id);
+        Ok(JobCancellation::Requested { requested_at })
+    }
+    
+    /// Cancel jobs that have waited in pending or scheduled state longer than the hold TTL
+    /// while funds were reserved for them, release the reservation and notify the customer
+    pub async fn expire_abandoned_reservations(&self) -> Result<Vec<ExpiredReservation>> {
+        if self.reservation_expiry.hold_ttl_minutes <= 0 {
+            return Ok(Vec::new());
+        }
+        
+        let cutoff = Utc::now() - Duration::minutes(self.reservation_expiry.hold_ttl_minutes);
+        let waiting = self.job_repo.find_waiting_since(cutoff)
+            .await
+            .context("Failed to find waiting jobs")?;
+        
+        let mut expired = Vec::new();
+        for job in waiting {
+            let held = self.wallet_repo.get_reserved_for_job(job.id)
+                .await
+                .context("Failed to load reserved funds for job")?;
+            if held <= 0 {
+                continue;
+            }
+            
+            // Cancel first so a runner can no longer pick the job up
+            match self.job_repo.update_status(job.id, JobStatus::Cancelled).await {
+                Ok(_) => {}
+                Err(Error::InvalidInput(_)) => {
+                    // The job was started or finished since it was loaded
+                    continue;
+                }
+                Err(e) => {
+                    error!("Failed to cancel job {} with an expired reservation: {}", job.id, e);
+                    continue;
+                }
+            }
+            
+            let wallet = self.find_billing_wallet(job.customer_id).await?;
+            self.wallet_repo.release_reservation(
+                wallet.id,
+                held,
+                Some(format!("Release expired reservation for job {}", job.id)),
+                Some(job.id)
+            ).await
+            .context("Failed to release expired reservation")?;
+            
+            warn!("Cancelled job {} after its reservation of {} cents expired", job.id, held);
+            
+            let data = json!({
+                "job_id": job.id,
+                "status": JobStatus::Cancelled.as_str(),
+                "reason": "reservation_expired",
+                "released_cents": held,
+            });
+            let notified_endpoints = match self.webhook_service.notify_customer(job.customer_id, WebhookEventType::JobCancelled, data).await {
+                Ok(count) => count,
+                Err(e) => {
+                    error!("Failed to notify customer {} of cancelled job {}: {}", job.customer_id, job.id, e);
+                    0
+                }
+            };
+            
+            expired.push(ExpiredReservation {
+                job_id: job.id,
+                customer_id: job.customer_id,
+                released_cents: held,
+                notified_endpoints,
+            });
+        }
+        
+        Ok(expired)
+    }
+    
+    /// Expire pending and scheduled jobs whose deadline passed, release what is still
+    /// reserved for them and notify the customer
+    ///
+    /// Runners refuse to start such jobs already; this takes them off the books.
+    pub async fn expire_overdue_jobs(&self) -> Result<Vec<ExpiredJob>> {
+        let overdue = self.job_repo.find_expired(Utc::now())
+            .await
+            .context("Failed to find jobs past their deadline")?;
+        
+        let mut expired = Vec::new();
+        for job in overdue {
+            // Expire first so a runner can no longer pick the job up
+            match self.job_repo.update_status(job.id, JobStatus::Expired).await {
+                Ok(_) => {}
+                Err(Error::InvalidInput(_)) => {
+                    // The job was started or finished since it was loaded
+                    continue;
+                }
+                Err(e) => {
+                    error!("Failed to expire job {} past its deadline: {}", job.id, e);
+                    continue;
+                }
+            }
+            
+            let held = self.wallet_repo.get_reserved_for_job(job.id)
+                .await
+                .context("Failed to load reserved funds for job")?;
+            if held > 0 {
+                let wallet = self.find_billing_wallet(job.customer_id).await?;
+                self.wallet_repo.release_reservation(
+                    wallet.id,
+                    held,
+                    Some(format!("Release reservation for job {} past its deadline", job.id)),
+                    Some(job.id)
+                ).await
+                .context("Failed to release the reservation of an expired job")?;
+            }
+            
+            let expires_at = job.expires_at;
+            warn!("Expired job {} that did not start before its deadline {:?}", job.id, expires_at);
+            
+            let data = json!({
+                "job_id": job.id,
+                "status": JobStatus::Expired.as_str(),
+                "reason": "deadline_passed",
+                "expires_at": expires_at,
+                "released_cents": held,
+            });
+            let notified_endpoints = match self.webhook_service.notify_customer(job.customer_id, WebhookEventType::JobExpired, data).await {
+                Ok(count) => count,
+                Err(e) => {
+                    error!("Failed to notify customer {} of expired job {}: {}", job.customer_id, job.id, e);
+                    0
+                }
+            };
+            
+            expired.push(ExpiredJob {
+                job_id: job.id,
+                customer_id: job.customer_id,
+                expires_at,
+                released_cents: held,
+                notified_endpoints,
+            });
+        }
+        
+        Ok(expired)
+    }
 }
@@ -0,0 +1,196 @@
+use std::env;
+use std::sync::Arc;
+use std::time::Duration;
+use anyhow::{Context, Result};
+use chrono::{Duration as ChronoDuration, Utc};
+use serde::Serialize;
+use tracing::{error, info, warn};
+
+use innosystem_common::config::parse_env;
+use innosystem_common::models::billing_export::{self, BillingExport, ExportAttempt, ExportStatus};
+use innosystem_common::repositories::BillingExportRepository;
+
+/// Header carrying the idempotency key of a billing record
+pub const IDEMPOTENCY_KEY_HEADER: &str = "Idempotency-Key";
+
+/// Maximum number of response body characters kept as the error of a failed attempt
+const MAX_RECORDED_RESPONSE_CHARS: usize = 1024;
+
+/// Configuration for the export of finalized job charges to an external billing system
+#[derive(Debug, Clone)]
+pub struct BillingExportConfig {
+    /// Endpoint each billing record is POSTed to; nothing is exported while unset
+    pub endpoint_url: Option<String>,
+    /// Bearer token sent to the endpoint
+    pub auth_token: Option<String>,
+    /// Interval between two export runs
+    pub interval_secs: u64,
+    /// Hours back charges are captured from, bounding the scan of completed jobs
+    pub lookback_hours: i64,
+    /// Maximum number of charges captured and of exports attempted per run
+    pub batch_size: i64,
+    /// Attempts before an export is left for an admin to retry
+    pub max_attempts: i32,
+    /// Time allowed for the endpoint to answer
+    pub timeout_secs: u64,
+}
+
+impl Default for BillingExportConfig {
+    fn default() -> Self {
+        Self {
+            endpoint_url: None,
+            auth_token: None,
+            interval_secs: 60,
+            lookback_hours: 7 * 24,
+            batch_size: 100,
+            max_attempts: 10,
+            timeout_secs: 10,
+        }
+    }
+}
+
+impl BillingExportConfig {
+    /// Load the configuration from `BILLING_EXPORT_*` environment variables, using defaults for unset values
+    pub fn from_env() -> Self {
+        let defaults = Self::default();
+        Self {
+            endpoint_url: env::var("BILLING_EXPORT_URL").ok().filter(|url| !url.is_empty()),
+            auth_token: env::var("BILLING_EXPORT_TOKEN").ok().filter(|token| !token.is_empty()),
+            interval_secs: parse_env("BILLING_EXPORT_INTERVAL_SECS").filter(|secs| *secs > 0).unwrap_or(defaults.interval_secs),
+            lookback_hours: parse_env("BILLING_EXPORT_LOOKBACK_HOURS").filter(|hours| *hours > 0).unwrap_or(defaults.lookback_hours),
+            batch_size: parse_env("BILLING_EXPORT_BATCH_SIZE").filter(|size| *size > 0).unwrap_or(defaults.batch_size),
+            max_attempts: parse_env("BILLING_EXPORT_MAX_ATTEMPTS").filter(|attempts| *attempts > 0).unwrap_or(defaults.max_attempts),
+            timeout_secs: parse_env("BILLING_EXPORT_TIMEOUT_SECS").filter(|secs| *secs > 0).unwrap_or(defaults.timeout_secs),
+        }
+    }
+}
+
+/// Charges captured and exports attempted by a run
+#[derive(Debug, Clone, Copy, Default, Serialize)]
+pub struct ExportRun {
+    pub captured: usize,
+    pub exported: usize,
+    pub failed: usize,
+}
+
+/// Service exporting the charge of every completed job to an external billing system
+/// exactly once
+///
+/// Finalized charges are captured into an outbox, one export per job, and POSTed from
+/// there until the endpoint accepts them. Every attempt carries the export's
+/// idempotency key, so an attempt whose response was lost and is repeated is dropped
+/// by the endpoint rather than billed twice.
+pub struct BillingExportService {
+    repo: Arc<dyn BillingExportRepository>,
+    config: BillingExportConfig,
+    client: reqwest::Client,
+}
+
+impl BillingExportService {
+    /// Create a new BillingExportService
+    pub fn new(repo: Arc<dyn BillingExportRepository>, config: Option<BillingExportConfig>) -> Self {
+        Self {
+            repo,
+            config: config.unwrap_or_default(),
+            client: reqwest::Client::new(),
+        }
+    }
+
+    /// Whether charges are exported at all
+    pub fn enabled(&self) -> bool {
+        self.config.endpoint_url.is_some()
+    }
+
+    /// Capture the charges finalized since the lookback and attempt every due export
+    pub async fn run(&self) -> Result<ExportRun> {
+        let mut run = ExportRun::default();
+        if !self.enabled() {
+            return Ok(run);
+        }
+
+        let since = Utc::now() - ChronoDuration::hours(self.config.lookback_hours);
+        run.captured = self.repo.capture(since, self.config.batch_size).await
+            .context("Failed to capture finalized charges")?;
+
+        let due = self.repo.find_due(Utc::now(), self.config.batch_size).await
+            .context("Failed to load due billing exports")?;
+        for export in due {
+            match self.attempt(&export).await {
+                Ok(export) if export.export_status() == Some(ExportStatus::Exported) => run.exported += 1,
+                Ok(_) => run.failed += 1,
+                Err(e) => {
+                    error!("Failed to export the charge of job {}: {}", export.job_id, e);
+                    run.failed += 1;
+                }
+            }
+        }
+
+        Ok(run)
+    }
+
+    /// Attempt an export again right away, whatever its status; exported ones are left as they are
+    pub async fn retry(&self, export: &BillingExport) -> Result<BillingExport> {
+        if export.export_status() == Some(ExportStatus::Exported) {
+            return Ok(export.clone());
+        }
+        anyhow::ensure!(self.enabled(), "Billing export is not configured, set BILLING_EXPORT_URL");
+        self.attempt(export).await
+    }
+
+    /// POST the billing record of an export and store the outcome
+    async fn attempt(&self, export: &BillingExport) -> Result<BillingExport> {
+        let url = self.config.endpoint_url.as_deref()
+            .context("Billing export is not configured")?;
+        let mut request = self.client.post(url)
+            .header(IDEMPOTENCY_KEY_HEADER, &export.idempotency_key)
+            .timeout(Duration::from_secs(self.config.timeout_secs))
+            .json(&export.record());
+        if let Some(token) = &self.config.auth_token {
+            request = request.bearer_auth(token);
+        }
+
+        let (status_code, error) = match request.send().await {
+            Ok(response) if response.status().is_success() => (Some(response.status().as_u16() as i32), None),
+            Ok(response) => {
+                let status = response.status();
+                let body = response.text().await.unwrap_or_default();
+                (Some(status.as_u16() as i32), Some(format!("{}: {}", status, body.chars().take(MAX_RECORDED_RESPONSE_CHARS).collect::<String>())))
+            }
+            Err(e) => (None, Some(e.to_string())),
+        };
+
+        let now = Utc::now();
+        let attempts = export.attempts + 1;
+        let attempt = match error {
+            None => ExportAttempt {
+                status: ExportStatus::Exported,
+                attempts,
+                status_code,
+                last_error: None,
+                next_attempt_at: None,
+                exported_at: Some(now),
+            },
+            Some(error) => {
+                let next_attempt_at = billing_export::retry_delay(attempts, self.config.max_attempts).map(|delay| now + delay);
+                if next_attempt_at.is_none() {
+                    warn!("Giving up on exporting the charge of job {} after {} attempts: {}", export.job_id, attempts, error);
+                }
+                ExportAttempt {
+                    status: if next_attempt_at.is_some() { ExportStatus::Pending } else { ExportStatus::Failed },
+                    attempts,
+                    status_code,
+                    last_error: Some(error),
+                    next_attempt_at,
+                    exported_at: None,
+                }
+            }
+        };
+
+        let export = self.repo.record_attempt(export.id, attempt).await
+            .context("Failed to record billing export attempt")?;
+        if export.export_status() == Some(ExportStatus::Exported) {
+            info!("Exported the charge of job {} ({} cents)", export.job_id, export.amount_cents);
+        }
+        Ok(export)
+    }
+}
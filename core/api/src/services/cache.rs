@@ -0,0 +1,152 @@
+use std::env;
+use std::time::Duration;
+use anyhow::{Result, anyhow};
+use bb8_redis::{
+    bb8::Pool,
+    redis::AsyncCommands,
+    RedisConnectionManager,
+};
+use serde::{de::DeserializeOwned, Serialize};
+use tracing::warn;
+
+/// Time to wait for a Redis connection before treating the lookup as a miss
+const CONNECTION_TIMEOUT_MS: u64 = 250;
+
+/// Cache key of the job type catalog
+pub const JOB_TYPES_KEY: &str = "job_types:all";
+/// Cache key of the list of all resellers
+pub const RESELLERS_KEY: &str = "resellers:all";
+/// Cache key of the list of active resellers
+pub const ACTIVE_RESELLERS_KEY: &str = "resellers:active";
+
+/// Cache key of a single job type
+pub fn job_type_key(id: uuid::Uuid) -> String {
+    format!("job_types:{}", id)
+}
+
+/// Cache key of the execution statistics of a job type
+pub fn job_type_stats_key(id: uuid::Uuid) -> String {
+    format!("job_types:{}:stats", id)
+}
+
+/// Configuration for caching responses of rarely changing reads
+#[derive(Debug, Clone)]
+pub struct ResponseCacheConfig {
+    /// Seconds a cached response is served before it is read from the database again (0 disables caching)
+    pub ttl_secs: u64,
+    /// Prefix of all cache keys in Redis
+    pub key_prefix: String,
+}
+
+impl Default for ResponseCacheConfig {
+    fn default() -> Self {
+        Self {
+            ttl_secs: 30,
+            key_prefix: "innosystem:cache".to_string(),
+        }
+    }
+}
+
+impl ResponseCacheConfig {
+    /// Load the configuration from `RESPONSE_CACHE_*` environment variables, using defaults for unset values
+    pub fn from_env() -> Self {
+        let defaults = Self::default();
+        Self {
+            ttl_secs: env::var("RESPONSE_CACHE_TTL_SECS").ok()
+                .and_then(|value| value.parse().ok())
+                .unwrap_or(defaults.ttl_secs),
+            key_prefix: env::var("RESPONSE_CACHE_KEY_PREFIX").ok()
+                .filter(|prefix| !prefix.is_empty())
+                .unwrap_or(defaults.key_prefix),
+        }
+    }
+}
+
+/// Short-lived Redis cache for responses of catalog endpoints polled by dashboards
+///
+/// The cache is best effort: Redis errors are logged and treated as a miss, so an
+/// unavailable Redis only means reads go to the database.
+pub struct ResponseCache {
+    pool: Pool<RedisConnectionManager>,
+    config: ResponseCacheConfig,
+}
+
+impl ResponseCache {
+    /// Create a new ResponseCache; connections are established on first use
+    pub fn new(redis_url: &str, config: ResponseCacheConfig) -> Result<Self> {
+        let manager = RedisConnectionManager::new(redis_url)
+            .map_err(|e| anyhow!("Failed to create Redis manager: {}", e))?;
+
+        let pool = Pool::builder()
+            .connection_timeout(Duration::from_millis(CONNECTION_TIMEOUT_MS))
+            .build_unchecked(manager);
+
+        Ok(Self { pool, config })
+    }
+
+    fn key(&self, key: &str) -> String {
+        format!("{}:{}", self.config.key_prefix, key)
+    }
+
+    /// Look up a cached response
+    pub async fn get<T: DeserializeOwned>(&self, key: &str) -> Option<T> {
+        if self.config.ttl_secs == 0 {
+            return None;
+        }
+
+        let mut conn = match self.pool.get().await {
+            Ok(conn) => conn,
+            Err(e) => {
+                warn!("Response cache unavailable: {}", e);
+                return None;
+            }
+        };
+
+        let cached: Option<String> = match conn.get(self.key(key)).await {
+            Ok(cached) => cached,
+            Err(e) => {
+                warn!("Failed to read {} from the response cache: {}", key, e);
+                return None;
+            }
+        };
+
+        cached.and_then(|json| serde_json::from_str(&json).ok())
+    }
+
+    /// Store a response for the configured TTL
+    pub async fn put<T: Serialize>(&self, key: &str, value: &T) {
+        if self.config.ttl_secs == 0 {
+            return;
+        }
+
+        let Ok(json) = serde_json::to_string(value) else {
+            return;
+        };
+
+        match self.pool.get().await {
+            Ok(mut conn) => {
+                if let Err(e) = conn.set_ex::<_, _, ()>(self.key(key), json, self.config.ttl_secs).await {
+                    warn!("Failed to write {} to the response cache: {}", key, e);
+                }
+            }
+            Err(e) => warn!("Response cache unavailable: {}", e),
+        }
+    }
+
+    /// Drop cached responses after the data behind them changed
+    pub async fn invalidate(&self, keys: &[&str]) {
+        if self.config.ttl_secs == 0 || keys.is_empty() {
+            return;
+        }
+
+        let keys: Vec<String> = keys.iter().map(|key| self.key(key)).collect();
+        match self.pool.get().await {
+            Ok(mut conn) => {
+                if let Err(e) = conn.del::<_, ()>(&keys).await {
+                    warn!("Failed to invalidate {:?} in the response cache: {}", keys, e);
+                }
+            }
+            Err(e) => warn!("Response cache unavailable: {}", e),
+        }
+    }
+}
@@ -0,0 +1,163 @@
+use std::sync::Arc;
+use anyhow::{Context, Result};
+use chrono::{DateTime, Duration, Utc};
+use tracing::{info, warn};
+use uuid::Uuid;
+
+use innosystem_common::config::parse_env;
+use innosystem_common::database::{with_reseller, SchemaResolver};
+use innosystem_common::models::load_window::{LoadWindow, LoadWindowStatus};
+use innosystem_common::models::Runner;
+use innosystem_common::queue::JobQueue;
+use innosystem_common::repositories::{JobRepository, LoadWindowRepository, RunnerRepository};
+
+use crate::services::autoscaling::ACTIVE_RUNNER_WINDOW_SECS;
+use crate::services::AutoscalingService;
+
+/// Configuration of the warm-up of the runner fleet ahead of load windows
+#[derive(Debug, Clone)]
+pub struct CapacityPlanningConfig {
+    /// Interval between two checks of the open load windows
+    pub check_interval_secs: u64,
+}
+
+impl Default for CapacityPlanningConfig {
+    fn default() -> Self {
+        Self {
+            check_interval_secs: 30,
+        }
+    }
+}
+
+impl CapacityPlanningConfig {
+    /// Load the configuration from `CAPACITY_PLANNING_*` environment variables, using defaults for unset values
+    pub fn from_env() -> Self {
+        let defaults = Self::default();
+        Self {
+            check_interval_secs: parse_env("CAPACITY_PLANNING_CHECK_INTERVAL_SECS").filter(|secs| *secs > 0).unwrap_or(defaults.check_interval_secs),
+        }
+    }
+}
+
+/// Service scaling the runner fleet up ahead of the load windows admins declared
+///
+/// When the warm-up of a window begins, the autoscaling advice is published right away
+/// so autoscalers need not wait for their next poll. Until the pool of the window has
+/// its target count of active runners, scheduled jobs of the window's job types coming
+/// due are pushed back to the next check, in the shared schema and every reseller
+/// schema alike.
+pub struct CapacityPlanningService {
+    load_window_repo: Arc<dyn LoadWindowRepository>,
+    runner_repo: Arc<dyn RunnerRepository>,
+    job_repo: Arc<dyn JobRepository>,
+    job_queue: Arc<dyn JobQueue>,
+    autoscaling: Arc<AutoscalingService>,
+    schema_resolver: Arc<SchemaResolver>,
+    config: CapacityPlanningConfig,
+}
+
+impl CapacityPlanningService {
+    /// Create a new CapacityPlanningService
+    pub fn new(
+        load_window_repo: Arc<dyn LoadWindowRepository>,
+        runner_repo: Arc<dyn RunnerRepository>,
+        job_repo: Arc<dyn JobRepository>,
+        job_queue: Arc<dyn JobQueue>,
+        autoscaling: Arc<AutoscalingService>,
+        schema_resolver: Arc<SchemaResolver>,
+        config: Option<CapacityPlanningConfig>,
+    ) -> Self {
+        Self {
+            load_window_repo,
+            runner_repo,
+            job_repo,
+            job_queue,
+            autoscaling,
+            schema_resolver,
+            config: config.unwrap_or_default(),
+        }
+    }
+
+    /// Check every open load window against the runners of its pool, returning the
+    /// windows as checked
+    pub async fn check_all(&self) -> Result<Vec<LoadWindow>> {
+        let windows = self.load_window_repo.list_open().await?;
+        if windows.is_empty() {
+            return Ok(windows);
+        }
+
+        let now = Utc::now();
+        let since = now - Duration::seconds(ACTIVE_RUNNER_WINDOW_SECS);
+        let runners = self.runner_repo.list_active(since).await?;
+        let resolver = self.schema_resolver.clone();
+        let scopes = tokio::task::spawn_blocking(move || resolver.scopes()).await??;
+
+        let mut checked = Vec::with_capacity(windows.len());
+        let mut notify = false;
+        for window in windows {
+            let (window, warming_up) = self.check(window, &runners, now, &scopes).await?;
+            notify |= warming_up;
+            checked.push(window);
+        }
+
+        // Tell autoscalers once about every window whose warm-up began
+        if notify {
+            match self.autoscaling.publish().await {
+                Ok(Some(advice)) => info!("Published autoscaling advice ahead of a load window: {} runners desired", advice.desired_runners),
+                Ok(None) => {}
+                Err(e) => warn!("Failed to publish autoscaling advice ahead of a load window: {}", e),
+            }
+        }
+
+        Ok(checked)
+    }
+
+    /// Check a single window, returning it as recorded and whether its warm-up began
+    async fn check(&self, window: LoadWindow, runners: &[Runner], now: DateTime<Utc>, scopes: &[Option<Uuid>]) -> Result<(LoadWindow, bool)> {
+        let active = runners.iter().filter(|runner| window.includes_runner(&runner.name)).count() as u32;
+        let next_check = now + Duration::seconds(self.config.check_interval_secs as i64);
+        let step = window.step(now, active, next_check);
+
+        let was = window.window_status();
+        let window = if step.status != was || window.active_runners != Some(active as i32) {
+            self.load_window_repo.record_check(window.id, step.status, active as i32, step.notify).await?
+        } else {
+            window
+        };
+        match step.status {
+            status if status == was => {}
+            LoadWindowStatus::Warming => info!("Warming up pool for load window {}: {} of {} runners", window.name, active, window.target_runners),
+            LoadWindowStatus::Ready => info!("Pool for load window {} is ready with {} runners", window.name, active),
+            LoadWindowStatus::Released if active < window.target_runners as u32 => {
+                warn!("Released load window {} with {} of {} runners", window.name, active, window.target_runners)
+            }
+            status => info!("Load window {} is {}", window.name, status.as_str()),
+        }
+
+        if let Some(until) = step.hold_until {
+            let mut held = 0;
+            for &scope in scopes {
+                held += with_reseller(scope, self.hold(window.job_type_ids.clone(), until)).await?;
+            }
+            if held > 0 {
+                info!("Held back {} scheduled jobs until the pool for load window {} is ready", held, window.name);
+            }
+        }
+
+        Ok((window, step.notify))
+    }
+
+    /// Push back the scheduled jobs of the job types due before `until` to it, in the
+    /// current scope, returning how many were held
+    async fn hold(&self, job_type_ids: Vec<Uuid>, until: DateTime<Utc>) -> Result<usize> {
+        let held = self.job_repo.postpone_scheduled(job_type_ids, until, until).await
+            .context("Failed to hold back scheduled jobs")?;
+        for &job_id in &held {
+            if let Err(e) = self.job_queue.schedule_job(job_id, until).await {
+                warn!("Failed to reschedule job {} on the queue: {}", job_id, e);
+            }
+        }
+
+        Ok(held.len())
+    }
+}
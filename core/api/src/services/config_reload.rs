@@ -0,0 +1,131 @@
+use std::sync::{Arc, Mutex};
+use anyhow::{Result, anyhow};
+use chrono::{DateTime, Utc};
+use serde::Serialize;
+use tracing::{error, info};
+
+use innosystem_common::config::reload_env_file;
+
+use crate::services::backpressure::BackpressureConfig;
+use crate::services::feature_flags::FeatureFlagConfig;
+use crate::services::{BackpressureService, FeatureFlagService};
+
+/// Settings the API applies without a restart, on SIGHUP or `POST /admin/config/reload`
+#[derive(Debug, Clone, PartialEq)]
+pub struct TunableConfig {
+    /// Queue depth limits applied to job submissions (`BACKPRESSURE_*` variables)
+    pub backpressure: BackpressureConfig,
+    /// Caching of the runtime feature flags (`FEATURE_FLAG_*` variables)
+    pub feature_flags: FeatureFlagConfig,
+}
+
+impl TunableConfig {
+    /// Load the tunable settings from environment variables, refusing malformed values
+    /// rather than falling back to defaults as on startup
+    pub fn from_env() -> Result<Self> {
+        BackpressureConfig::validate_env().map_err(|e| anyhow!(e))?;
+        FeatureFlagConfig::validate_env().map_err(|e| anyhow!(e))?;
+        Ok(Self {
+            backpressure: BackpressureConfig::from_env(),
+            feature_flags: FeatureFlagConfig::from_env(),
+        })
+    }
+}
+
+/// A configuration reload that was applied
+#[derive(Debug, Clone, Serialize)]
+pub struct ConfigReload {
+    /// Groups of tunable settings whose values changed (`backpressure`, `feature_flags`)
+    pub changed: Vec<&'static str>,
+    pub reloaded_at: DateTime<Utc>,
+}
+
+/// Reloads of this replica since it started
+#[derive(Debug, Clone, Default, Serialize)]
+pub struct ConfigReloadStatus {
+    pub reloads: u64,
+    /// Reloads refused because the settings file or a value was invalid
+    pub failures: u64,
+    pub last_reload: Option<ConfigReload>,
+    /// Why the last refused reload was refused
+    pub last_error: Option<String>,
+}
+
+/// Service reloading the tunable settings while the API runs
+///
+/// The settings file is read again with its values replacing the ones read on startup.
+/// All tunable settings are validated before any is applied, so a bad value leaves the
+/// running configuration untouched; each service then swaps in its new settings as a
+/// whole. Other settings only take effect after a restart.
+pub struct ConfigReloadService {
+    backpressure: Arc<BackpressureService>,
+    feature_flags: Arc<FeatureFlagService>,
+    /// Held for the whole reload, so concurrent reloads apply one after the other
+    status: Mutex<ConfigReloadStatus>,
+}
+
+impl ConfigReloadService {
+    /// Create a new ConfigReloadService
+    pub fn new(backpressure: Arc<BackpressureService>, feature_flags: Arc<FeatureFlagService>) -> Self {
+        Self {
+            backpressure,
+            feature_flags,
+            status: Mutex::new(ConfigReloadStatus::default()),
+        }
+    }
+
+    /// Tunable settings currently applied
+    pub fn current(&self) -> TunableConfig {
+        TunableConfig {
+            backpressure: self.backpressure.config().as_ref().clone(),
+            feature_flags: self.feature_flags.config().as_ref().clone(),
+        }
+    }
+
+    /// Reloads so far and the outcome of the last one
+    pub fn status(&self) -> ConfigReloadStatus {
+        self.status.lock().unwrap_or_else(|e| e.into_inner()).clone()
+    }
+
+    /// Read the settings file again and apply the tunable settings if all of them are valid
+    pub fn reload(&self) -> Result<ConfigReload> {
+        let mut status = self.status.lock().unwrap_or_else(|e| e.into_inner());
+
+        let loaded = reload_env_file()
+            .map_err(|e| anyhow!("Failed to read the settings file: {}", e))
+            .and_then(|_| TunableConfig::from_env());
+        let config = match loaded {
+            Ok(config) => config,
+            Err(e) => {
+                error!("Keeping the current configuration, the reloaded one is invalid: {}", e);
+                status.failures += 1;
+                status.last_error = Some(e.to_string());
+                return Err(e);
+            }
+        };
+
+        let current = self.current();
+        let mut changed = Vec::new();
+        if config.backpressure != current.backpressure {
+            self.backpressure.reconfigure(config.backpressure);
+            changed.push("backpressure");
+        }
+        if config.feature_flags != current.feature_flags {
+            self.feature_flags.reconfigure(config.feature_flags);
+            changed.push("feature_flags");
+        }
+
+        if changed.is_empty() {
+            info!("Reloaded configuration, no tunable setting changed");
+        } else {
+            info!("Reloaded configuration, applied new {} settings", changed.join(" and "));
+        }
+        let reload = ConfigReload {
+            changed,
+            reloaded_at: Utc::now(),
+        };
+        status.reloads += 1;
+        status.last_reload = Some(reload.clone());
+        Ok(reload)
+    }
+}
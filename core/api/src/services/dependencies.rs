@@ -0,0 +1,110 @@
+use std::sync::Arc;
+use anyhow::{Context, Result};
+use tracing::info;
+use uuid::Uuid;
+
+use innosystem_common::database::{with_reseller, SchemaResolver};
+use innosystem_common::models::dependency::Blocker;
+use innosystem_common::repositories::DependencyRepository;
+
+use crate::services::BillingService;
+
+/// Jobs cancelled when a destructive operation is forced past its queued jobs
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct ForcedCancellation {
+    pub cancelled_jobs: usize,
+    pub released_cents: i64,
+}
+
+/// Service checking the dependents of projects, job types and customers before they
+/// are deleted, disabled or deactivated, and cascading to them when forced
+///
+/// Projects and customers belong to one reseller, so their dependents are in the
+/// schema of the request's reseller scope. Job types are shared by all resellers, so
+/// their queued jobs are looked up in every scope.
+pub struct DependencyService {
+    dependency_repo: Arc<dyn DependencyRepository>,
+    billing_service: Arc<BillingService>,
+    schema_resolver: Arc<SchemaResolver>,
+}
+
+impl DependencyService {
+    /// Create a new DependencyService
+    pub fn new(
+        dependency_repo: Arc<dyn DependencyRepository>,
+        billing_service: Arc<BillingService>,
+        schema_resolver: Arc<SchemaResolver>,
+    ) -> Self {
+        Self {
+            dependency_repo,
+            billing_service,
+            schema_resolver,
+        }
+    }
+
+    /// Jobs keeping a project from being deleted
+    pub async fn project_blockers(&self, project_id: Uuid) -> Result<Vec<Blocker>> {
+        self.dependency_repo.project_blockers(project_id).await
+    }
+
+    /// Queued jobs keeping a job type from being disabled, in the schemas of all resellers
+    pub async fn job_type_blockers(&self, job_type_id: Uuid) -> Result<Vec<Blocker>> {
+        let mut blockers = Vec::new();
+        for scope in self.scopes().await? {
+            blockers.extend(with_reseller(scope, self.dependency_repo.job_type_blockers(job_type_id)).await?);
+        }
+        Ok(Blocker::combine(blockers))
+    }
+
+    /// Queued jobs and reserved funds keeping a customer from being deactivated
+    pub async fn customer_blockers(&self, customer_id: Uuid) -> Result<Vec<Blocker>> {
+        self.dependency_repo.customer_blockers(customer_id).await
+    }
+
+    /// Cancel the queued jobs of a job type in the schemas of all resellers, releasing
+    /// what was reserved for them
+    pub async fn cancel_queued_jobs_of_job_type(&self, job_type_id: Uuid) -> Result<ForcedCancellation> {
+        let mut cancellation = ForcedCancellation::default();
+        for scope in self.scopes().await? {
+            let job_ids = with_reseller(scope, self.dependency_repo.find_queued_jobs_by_job_type(job_type_id)).await?;
+            let cancelled = with_reseller(scope, self.cancel_jobs(job_ids)).await?;
+            cancellation.cancelled_jobs += cancelled.cancelled_jobs;
+            cancellation.released_cents += cancelled.released_cents;
+        }
+        info!(
+            "Cancelled {} queued jobs of job type {}, releasing {} cents",
+            cancellation.cancelled_jobs, job_type_id, cancellation.released_cents
+        );
+        Ok(cancellation)
+    }
+
+    /// Cancel the queued jobs of a customer, releasing what was reserved for them
+    pub async fn cancel_queued_jobs_of_customer(&self, customer_id: Uuid) -> Result<ForcedCancellation> {
+        let job_ids = self.dependency_repo.find_queued_jobs_by_customer(customer_id).await?;
+        let cancellation = self.cancel_jobs(job_ids).await?;
+        info!(
+            "Cancelled {} queued jobs of customer {}, releasing {} cents",
+            cancellation.cancelled_jobs, customer_id, cancellation.released_cents
+        );
+        Ok(cancellation)
+    }
+
+    async fn cancel_jobs(&self, job_ids: Vec<Uuid>) -> Result<ForcedCancellation> {
+        let mut cancellation = ForcedCancellation::default();
+        for job_id in job_ids {
+            // None when a runner picked the job up in the meantime
+            if let Some(released) = self.billing_service.cancel_job(job_id).await
+                .with_context(|| format!("Failed to cancel job {}", job_id))?
+            {
+                cancellation.cancelled_jobs += 1;
+                cancellation.released_cents += i64::from(released);
+            }
+        }
+        Ok(cancellation)
+    }
+
+    async fn scopes(&self) -> Result<Vec<Option<Uuid>>> {
+        let resolver = self.schema_resolver.clone();
+        Ok(tokio::task::spawn_blocking(move || resolver.scopes()).await??)
+    }
+}
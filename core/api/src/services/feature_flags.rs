@@ -0,0 +1,122 @@
+use std::env;
+use std::sync::{Arc, Mutex, RwLock};
+use std::time::{Duration, Instant};
+use tracing::warn;
+use uuid::Uuid;
+
+use innosystem_common::models::feature_flag::FeatureFlag;
+use innosystem_common::repositories::FeatureFlagRepository;
+
+/// Configuration for evaluating feature flags
+#[derive(Debug, Clone, PartialEq)]
+pub struct FeatureFlagConfig {
+    /// Seconds the flags are served from memory before they are read again, so a change
+    /// made on another replica takes effect within this delay (0 reads them on every check)
+    pub cache_ttl_secs: u64,
+}
+
+impl Default for FeatureFlagConfig {
+    fn default() -> Self {
+        Self {
+            cache_ttl_secs: 5,
+        }
+    }
+}
+
+impl FeatureFlagConfig {
+    /// Load the configuration from `FEATURE_FLAG_*` environment variables, using defaults for unset values
+    pub fn from_env() -> Self {
+        let defaults = Self::default();
+        Self {
+            cache_ttl_secs: env::var("FEATURE_FLAG_CACHE_TTL_SECS").ok()
+                .and_then(|value| value.parse().ok())
+                .unwrap_or(defaults.cache_ttl_secs),
+        }
+    }
+
+    /// Check that the `FEATURE_FLAG_*` variables that are set hold valid values, which
+    /// `from_env` would otherwise replace by defaults
+    pub fn validate_env() -> Result<(), String> {
+        if let Ok(value) = env::var("FEATURE_FLAG_CACHE_TTL_SECS") {
+            value.parse::<u64>()
+                .map_err(|_| format!("FEATURE_FLAG_CACHE_TTL_SECS must be a number of seconds, got {}", value))?;
+        }
+        Ok(())
+    }
+}
+
+/// Evaluates the runtime feature flags stored in the database
+///
+/// All flags are held in memory and reloaded once the cache expires. When the database
+/// cannot be read the last loaded flags are kept, so an outage does not silently lift
+/// a kill switch.
+pub struct FeatureFlagService {
+    repo: Arc<dyn FeatureFlagRepository>,
+    config: RwLock<Arc<FeatureFlagConfig>>,
+    /// Flags as last loaded, with the time they were loaded
+    cache: Mutex<Option<(Instant, Arc<Vec<FeatureFlag>>)>>,
+}
+
+impl FeatureFlagService {
+    /// Create a new FeatureFlagService
+    pub fn new(repo: Arc<dyn FeatureFlagRepository>, config: Option<FeatureFlagConfig>) -> Self {
+        Self {
+            repo,
+            config: RwLock::new(Arc::new(config.unwrap_or_default())),
+            cache: Mutex::new(None),
+        }
+    }
+
+    /// Configuration currently applied
+    pub fn config(&self) -> Arc<FeatureFlagConfig> {
+        self.config.read().unwrap_or_else(|e| e.into_inner()).clone()
+    }
+
+    /// Apply a reloaded configuration; the flags cached so far expire under the new TTL
+    pub fn reconfigure(&self, config: FeatureFlagConfig) {
+        *self.config.write().unwrap_or_else(|e| e.into_inner()) = Arc::new(config);
+    }
+
+    /// Whether a flag is on for requests made on behalf of a reseller's customers, or
+    /// of direct customers when `reseller_id` is None
+    pub async fn is_enabled(&self, name: &str, reseller_id: Option<Uuid>) -> bool {
+        self.flags().await.iter()
+            .any(|flag| flag.name == name && flag.is_enabled_for(reseller_id))
+    }
+
+    /// Names of the flags that are on for a reseller's customers
+    pub async fn enabled_for(&self, reseller_id: Option<Uuid>) -> Vec<String> {
+        self.flags().await.iter()
+            .filter(|flag| flag.is_enabled_for(reseller_id))
+            .map(|flag| flag.name.clone())
+            .collect()
+    }
+
+    /// Drop the cached flags so a change made on this replica applies immediately
+    pub fn invalidate(&self) {
+        *self.cache.lock().unwrap_or_else(|e| e.into_inner()) = None;
+    }
+
+    /// Get all flags, from memory while the cache is fresh
+    async fn flags(&self) -> Arc<Vec<FeatureFlag>> {
+        let ttl = Duration::from_secs(self.config().cache_ttl_secs);
+        let cached = self.cache.lock().unwrap_or_else(|e| e.into_inner()).clone();
+        if let Some((loaded_at, flags)) = &cached {
+            if loaded_at.elapsed() < ttl {
+                return flags.clone();
+            }
+        }
+
+        match self.repo.list().await {
+            Ok(flags) => {
+                let flags = Arc::new(flags);
+                *self.cache.lock().unwrap_or_else(|e| e.into_inner()) = Some((Instant::now(), flags.clone()));
+                flags
+            }
+            Err(e) => {
+                warn!("Failed to load feature flags, keeping the last known ones: {}", e);
+                cached.map(|(_, flags)| flags).unwrap_or_default()
+            }
+        }
+    }
+}
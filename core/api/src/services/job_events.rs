@@ -0,0 +1,111 @@
+use std::sync::Arc;
+use anyhow::{Context, Result};
+use chrono::{Duration as ChronoDuration, Utc};
+use serde::Serialize;
+use tracing::{error, warn};
+
+use innosystem_common::config::parse_env;
+use innosystem_common::repositories::JobEventRepository;
+
+use crate::services::WebhookService;
+
+/// Configuration for the notification of jobs starting and finishing
+#[derive(Debug, Clone)]
+pub struct JobEventConfig {
+    /// Interval between two runs capturing and notifying job lifecycle events
+    pub interval_secs: u64,
+    /// Hours back job transitions are captured from, bounding the scan of jobs
+    pub lookback_hours: i64,
+    /// Maximum number of events captured per event type and notified per run
+    pub batch_size: i64,
+}
+
+impl Default for JobEventConfig {
+    fn default() -> Self {
+        Self {
+            interval_secs: 10,
+            lookback_hours: 24,
+            batch_size: 500,
+        }
+    }
+}
+
+impl JobEventConfig {
+    /// Load the configuration from `JOB_EVENTS_*` environment variables, using defaults for unset values
+    pub fn from_env() -> Self {
+        let defaults = Self::default();
+        Self {
+            interval_secs: parse_env("JOB_EVENTS_INTERVAL_SECS").filter(|secs| *secs > 0).unwrap_or(defaults.interval_secs),
+            lookback_hours: parse_env("JOB_EVENTS_LOOKBACK_HOURS").filter(|hours| *hours > 0).unwrap_or(defaults.lookback_hours),
+            batch_size: parse_env("JOB_EVENTS_BATCH_SIZE").filter(|size| *size > 0).unwrap_or(defaults.batch_size),
+        }
+    }
+}
+
+/// Events captured and notified by a run
+#[derive(Debug, Clone, Copy, Default, Serialize)]
+pub struct JobEventRun {
+    pub captured: usize,
+    pub notified: usize,
+    pub failed: usize,
+}
+
+/// Service telling customers when their jobs start, succeed or fail, so they need not poll
+///
+/// Runners move jobs along in the database, so their transitions are captured from the
+/// jobs into an outbox, one event per job and transition, and handed from there to the
+/// webhook service. It signs each event, logs its deliveries and retries the failed ones,
+/// as the customer's notification preferences say.
+///
+/// A run covers the schema of the reseller scope it runs in: each reseller schema keeps
+/// the outbox of its own jobs, next to the webhooks of its customers.
+pub struct JobEventService {
+    repo: Arc<dyn JobEventRepository>,
+    webhook_service: Arc<WebhookService>,
+    config: JobEventConfig,
+}
+
+impl JobEventService {
+    /// Create a new JobEventService
+    pub fn new(repo: Arc<dyn JobEventRepository>, webhook_service: Arc<WebhookService>, config: Option<JobEventConfig>) -> Self {
+        Self {
+            repo,
+            webhook_service,
+            config: config.unwrap_or_default(),
+        }
+    }
+
+    /// Capture the transitions since the lookback and notify every pending event
+    ///
+    /// Events the webhook service could not take, e.g. because the database was
+    /// unavailable, stay pending for the next run.
+    pub async fn run(&self) -> Result<JobEventRun> {
+        let mut run = JobEventRun::default();
+
+        let since = Utc::now() - ChronoDuration::hours(self.config.lookback_hours);
+        run.captured = self.repo.capture(since, self.config.batch_size).await
+            .context("Failed to capture job lifecycle events")?;
+
+        let pending = self.repo.find_pending(self.config.batch_size).await
+            .context("Failed to load pending job lifecycle events")?;
+        for event in pending {
+            let Some(event_type) = event.webhook_event_type() else {
+                warn!("Skipping job lifecycle event {} of unknown type {}", event.id, event.event_type);
+                continue;
+            };
+            match self.webhook_service.notify_customer(event.customer_id, event_type, event.data.clone()).await {
+                Ok(_) => {
+                    self.repo.mark_notified(event.id, Utc::now()).await
+                        .context("Failed to mark job lifecycle event as notified")?;
+                    run.notified += 1;
+                }
+                Err(e) => {
+                    error!("Failed to notify {} of job {}: {}", event.event_type, event.job_id, e);
+                    run.failed += 1;
+                }
+            }
+        }
+
+        Ok(run)
+    }
+}
@@ -0,0 +1,315 @@
+use std::collections::HashMap;
+use std::sync::{Arc, Mutex};
+
+use anyhow::{Result, anyhow};
+use chrono::{DateTime, Duration, Utc};
+use serde::Serialize;
+use serde_json::{json, Value};
+use tokio::sync::broadcast;
+use uuid::Uuid;
+
+use innosystem_common::database::current_reseller;
+use innosystem_common::models::job::{Job, JobStatus, PriorityLevel};
+use innosystem_common::queue::JobQueue;
+use innosystem_common::repositories::{JobRepository, RunnerRepository};
+
+
+/// Events buffered per subscriber before the slowest ones start missing events
+const CHANNEL_CAPACITY: usize = 1024;
+
+/// Job changes read per scope and poll
+const JOB_CHANGES_PER_POLL: i64 = 500;
+
+/// How far back each poll looks again, for changes committed after the previous poll
+/// but stamped before it
+const POLL_OVERLAP_SECS: i64 = 5;
+
+/// Kinds of system events streamed to the live tail
+#[derive(Debug, Clone, Copy, Serialize, PartialEq, Eq, Hash)]
+#[serde(rename_all = "snake_case")]
+pub enum LiveEventType {
+    /// A job was created or changed status
+    JobLifecycle,
+    /// A runner's health status or activity changed
+    RunnerHealth,
+    /// Snapshot of the queue's depth by priority
+    QueueDepth,
+    /// A request was refused for missing or invalid credentials
+    AuthFailure,
+}
+
+impl LiveEventType {
+    pub const ALL: [LiveEventType; 4] = [
+        LiveEventType::JobLifecycle,
+        LiveEventType::RunnerHealth,
+        LiveEventType::QueueDepth,
+        LiveEventType::AuthFailure,
+    ];
+
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            LiveEventType::JobLifecycle => "job_lifecycle",
+            LiveEventType::RunnerHealth => "runner_health",
+            LiveEventType::QueueDepth => "queue_depth",
+            LiveEventType::AuthFailure => "auth_failure",
+        }
+    }
+}
+
+/// System event as streamed to the live tail
+#[derive(Debug, Clone, Serialize)]
+pub struct LiveEvent {
+    #[serde(rename = "type")]
+    pub event_type: LiveEventType,
+    /// e.g. `job.running`, `runner.critical`, `queue.depth` or `auth.failed`
+    pub event: String,
+    /// Entities the event concerns, e.g. the job, its customer and job type; the live
+    /// tail can be filtered by any of them
+    pub entities: Vec<String>,
+    pub data: Value,
+    pub occurred_at: DateTime<Utc>,
+}
+
+impl LiveEvent {
+    fn new(event_type: LiveEventType, event: impl Into<String>, entities: Vec<String>, data: Value) -> Self {
+        Self {
+            event_type,
+            event: event.into(),
+            entities,
+            data,
+            occurred_at: Utc::now(),
+        }
+    }
+}
+
+/// Server-side filter of a live tail subscription
+#[derive(Debug, Clone, Default)]
+pub struct LiveEventFilter {
+    /// Event types streamed; all of them when empty
+    pub types: Vec<LiveEventType>,
+    /// Only events concerning this entity, e.g. a job, customer, runner or source IP
+    pub entity: Option<String>,
+}
+
+impl LiveEventFilter {
+    /// Build a filter from a comma-separated list of event types and an entity
+    pub fn parse(types: Option<&str>, entity: Option<String>) -> Result<Self, String> {
+        let types = types.unwrap_or_default()
+            .split(',')
+            .map(str::trim)
+            .filter(|name| !name.is_empty())
+            .map(|name| {
+                LiveEventType::ALL.into_iter()
+                    .find(|event_type| event_type.as_str() == name)
+                    .ok_or_else(|| format!("Unknown event type: {}", name))
+            })
+            .collect::<Result<Vec<_>, _>>()?;
+        let entity = entity.map(|entity| entity.trim().to_string()).filter(|entity| !entity.is_empty());
+        Ok(Self { types, entity })
+    }
+
+    pub fn matches(&self, event: &LiveEvent) -> bool {
+        (self.types.is_empty() || self.types.contains(&event.event_type))
+            && self.entity.as_ref().is_none_or(|entity| event.entities.iter().any(|candidate| candidate == entity))
+    }
+}
+
+/// What the last poll saw of the jobs and runners, so only changes are streamed
+#[derive(Debug, Default)]
+struct Watermarks {
+    /// Last change time read per reseller scope
+    job_cursors: HashMap<Option<Uuid>, DateTime<Utc>>,
+    /// Last status streamed per job, and when the job changed to it
+    job_statuses: HashMap<Uuid, (JobStatus, DateTime<Utc>)>,
+    /// Last health status and activity streamed per runner
+    runner_statuses: HashMap<Uuid, (Option<String>, String)>,
+}
+
+/// Fans system events out to the admins following the live tail
+///
+/// Job and runner changes are read from the database while anyone is subscribed, so
+/// every API instance streams all of them whichever process made the change; auth
+/// failures are those answered by the instance serving the stream.
+pub struct LiveEventService {
+    sender: broadcast::Sender<LiveEvent>,
+    job_repo: Arc<dyn JobRepository>,
+    runner_repo: Arc<dyn RunnerRepository>,
+    job_queue: Arc<dyn JobQueue>,
+    watermarks: Mutex<Watermarks>,
+}
+
+impl LiveEventService {
+    /// Create a new LiveEventService
+    pub fn new(job_repo: Arc<dyn JobRepository>, runner_repo: Arc<dyn RunnerRepository>, job_queue: Arc<dyn JobQueue>) -> Self {
+        let (sender, _) = broadcast::channel(CHANNEL_CAPACITY);
+        Self {
+            sender,
+            job_repo,
+            runner_repo,
+            job_queue,
+            watermarks: Mutex::new(Watermarks::default()),
+        }
+    }
+
+    /// Follow the events published from now on
+    pub fn subscribe(&self) -> broadcast::Receiver<LiveEvent> {
+        // Nothing was polled while nobody followed, so start over from now
+        if !self.has_subscribers() {
+            if let Ok(mut watermarks) = self.watermarks.lock() {
+                *watermarks = Watermarks::default();
+            }
+        }
+        self.sender.subscribe()
+    }
+
+    /// Whether anyone follows the live tail; nothing is polled otherwise
+    pub fn has_subscribers(&self) -> bool {
+        self.sender.receiver_count() > 0
+    }
+
+    fn publish(&self, event: LiveEvent) {
+        // Fails only when nobody is subscribed
+        let _ = self.sender.send(event);
+    }
+
+    /// Stream a request refused for missing or invalid credentials
+    pub fn auth_failure(&self, source_ip: Option<&str>, key_prefix: Option<&str>, method: &str, path: &str) {
+        if !self.has_subscribers() {
+            return;
+        }
+        let entities = source_ip.into_iter().chain(key_prefix).map(str::to_string).collect();
+        self.publish(LiveEvent::new(LiveEventType::AuthFailure, "auth.failed", entities, json!({
+            "source_ip": source_ip,
+            "key_prefix": key_prefix,
+            "method": method,
+            "path": path,
+        })));
+    }
+
+    /// Stream the jobs of the current reseller scope created or changed since the
+    /// previous poll; returns the number of events streamed
+    ///
+    /// The first poll of a scope only sets its starting point.
+    pub async fn poll_job_changes(&self) -> Result<usize> {
+        let scope = current_reseller();
+        let overlap = Duration::seconds(POLL_OVERLAP_SECS);
+        let now = Utc::now();
+        let Some(from) = self.lock()?.job_cursors.get(&scope).copied() else {
+            self.lock()?.job_cursors.insert(scope, now - overlap);
+            return Ok(0);
+        };
+
+        let jobs = self.job_repo.find_changed_since(from, JOB_CHANGES_PER_POLL).await?;
+        let next_from = match jobs.last().and_then(|job| job.updated_at.or(job.created_at)) {
+            // Pick up the rest of a full page next time
+            Some(changed_at) if jobs.len() as i64 >= JOB_CHANGES_PER_POLL => changed_at,
+            _ => now - overlap,
+        };
+
+        let mut events = Vec::new();
+        {
+            let mut watermarks = self.lock()?;
+            watermarks.job_cursors.insert(scope, next_from);
+            for job in jobs {
+                let changed_at = job.updated_at.or(job.created_at).unwrap_or(now);
+                let previous = watermarks.job_statuses.insert(job.id, (job.status.clone(), changed_at));
+                let previous = previous.map(|(status, _)| status);
+                if previous.as_ref() != Some(&job.status) {
+                    events.push(job_event(&job, previous));
+                }
+            }
+            // Finished jobs no longer change once they are out of the overlap; unfinished
+            // ones are kept so that updates without a status change are not streamed again
+            watermarks.job_statuses.retain(|_, (status, changed_at)| !status.is_terminal() || *changed_at >= next_from);
+        }
+
+        let count = events.len();
+        for event in events {
+            self.publish(event);
+        }
+        Ok(count)
+    }
+
+    /// Stream the runners whose health status or activity changed since the previous
+    /// poll; returns the number of events streamed
+    pub async fn poll_runner_changes(&self) -> Result<usize> {
+        let runners = self.runner_repo.list_all().await?;
+        let mut events = Vec::new();
+        for runner in runners {
+            let health_check = self.runner_repo.latest_health_check(runner.id).await?;
+            let health = health_check.as_ref().map(|check| check.status.clone());
+            let activity = runner.status.as_str().to_string();
+
+            let previous = self.lock()?.runner_statuses.insert(runner.id, (health.clone(), activity.clone()));
+            let Some((previous_health, previous_activity)) = previous else {
+                // First seen: nothing changed yet
+                continue;
+            };
+            if previous_health == health && previous_activity == activity {
+                continue;
+            }
+
+            let event = if previous_activity != activity {
+                format!("runner.{}", activity)
+            } else {
+                format!("runner.{}", health.as_deref().unwrap_or("unknown"))
+            };
+            events.push(LiveEvent::new(LiveEventType::RunnerHealth, event, vec![runner.id.to_string()], json!({
+                "runner_id": runner.id,
+                "runner_name": runner.name,
+                "status": activity,
+                "previous_status": previous_activity,
+                "health": health,
+                "previous_health": previous_health,
+                "score": health_check.map(|check| check.score),
+            })));
+        }
+
+        let count = events.len();
+        for event in events {
+            self.publish(event);
+        }
+        Ok(count)
+    }
+
+    /// Stream a snapshot of the queue's depth by priority
+    pub async fn publish_queue_depth(&self) -> Result<()> {
+        let mut by_priority = serde_json::Map::new();
+        for priority in [PriorityLevel::Critical, PriorityLevel::High, PriorityLevel::Medium, PriorityLevel::Low] {
+            let depth = self.job_queue.queue_length_by_priority(priority.clone()).await
+                .map_err(|e| anyhow!("Failed to read the queue depth: {}", e))?;
+            by_priority.insert(priority.as_str().to_string(), json!(depth));
+        }
+        let total = self.job_queue.queue_length().await
+            .map_err(|e| anyhow!("Failed to read the queue depth: {}", e))?;
+
+        self.publish(LiveEvent::new(LiveEventType::QueueDepth, "queue.depth", Vec::new(), json!({
+            "total": total,
+            "by_priority": by_priority,
+        })));
+        Ok(())
+    }
+
+    fn lock(&self) -> Result<std::sync::MutexGuard<'_, Watermarks>> {
+        self.watermarks.lock().map_err(|_| anyhow!("Live event watermarks are poisoned"))
+    }
+}
+
+/// Event of a job created or moved to its current status
+fn job_event(job: &Job, previous: Option<JobStatus>) -> LiveEvent {
+    LiveEvent::new(
+        LiveEventType::JobLifecycle,
+        format!("job.{}", job.status.as_str()),
+        vec![job.id.to_string(), job.public_id.clone(), job.customer_id.to_string(), job.job_type_id.to_string()],
+        json!({
+            "job_id": job.id,
+            "public_id": job.public_id,
+            "customer_id": job.customer_id,
+            "job_type_id": job.job_type_id,
+            "status": job.status.as_str(),
+            "previous_status": previous.map(|status| status.as_str()),
+            "priority": job.priority.as_str(),
+            "test_mode": job.test_mode,
+        }),
+    )
+}
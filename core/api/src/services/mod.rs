@@ -1,6 +1,47 @@
+pub mod alerting;
+pub mod autoscaling;
+pub mod backpressure;
 pub mod billing;
+pub mod billing_export;
+pub mod cache;
+pub mod capacity_planning;
+pub mod config_reload;
+pub mod dependencies;
+pub mod feature_flags;
+pub mod job_events;
+pub mod live_events;
+pub mod partitions;
+pub mod pool_metrics;
+pub mod provider_health;
+pub mod queue_stats;
+pub mod replay_corpus;
+pub mod repository_metrics;
+pub mod request_signing;
 pub mod runner_health;
+pub mod sandbox;
+pub mod security_events;
+pub mod spending_anomaly;
+pub mod webhook;
 
 // Export the service structs for easier imports
+pub use autoscaling::AutoscalingService;
+pub use backpressure::BackpressureService;
 pub use billing::BillingService;
+pub use billing_export::BillingExportService;
+pub use cache::ResponseCache;
+pub use capacity_planning::CapacityPlanningService;
+pub use config_reload::ConfigReloadService;
+pub use dependencies::DependencyService;
+pub use feature_flags::FeatureFlagService;
+pub use job_events::JobEventService;
+pub use live_events::LiveEventService;
+pub use partitions::PartitionService;
+pub use provider_health::ProviderHealthService;
+pub use queue_stats::QueueStatsService;
+pub use replay_corpus::ReplayCorpusService;
+pub use request_signing::RequestSigningService;
 pub use runner_health::RunnerHealthService;
+pub use sandbox::SandboxService;
+pub use security_events::SecurityEventService;
+pub use spending_anomaly::SpendingAnomalyService;
+pub use webhook::WebhookService;
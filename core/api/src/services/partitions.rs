@@ -0,0 +1,143 @@
+use std::sync::Arc;
+use anyhow::Result;
+use chrono::{NaiveDate, Utc};
+use serde::Serialize;
+use tracing::info;
+
+use innosystem_common::config::parse_env;
+use innosystem_common::models::billing_period::BillingPeriod;
+use innosystem_common::models::partition::{months_past_retention, months_to_create, next_month, PartitionedTable};
+use innosystem_common::repositories::{BillingPeriodRepository, PartitionRepository};
+
+/// Configuration of the monthly partitions of jobs and wallet transactions
+#[derive(Debug, Clone)]
+pub struct PartitionConfig {
+    /// Interval between two maintenance runs
+    pub check_interval_secs: u64,
+    /// Months after the current one whose partitions are created ahead of time
+    pub premake_months: u32,
+    /// Full months jobs are kept after the month they were created in (None keeps them forever)
+    pub job_retention_months: Option<u32>,
+    /// Full months wallet transactions are kept after the month they were posted in
+    /// (None keeps them forever); months not covered by a closed billing period are kept
+    pub wallet_transaction_retention_months: Option<u32>,
+}
+
+impl Default for PartitionConfig {
+    fn default() -> Self {
+        Self {
+            check_interval_secs: 3600,  // 1 hour
+            premake_months: 3,
+            job_retention_months: None,
+            wallet_transaction_retention_months: None,
+        }
+    }
+}
+
+impl PartitionConfig {
+    /// Load the configuration from `PARTITION_*` environment variables, using defaults for unset values
+    pub fn from_env() -> Self {
+        let defaults = Self::default();
+        Self {
+            check_interval_secs: parse_env("PARTITION_CHECK_INTERVAL_SECS").unwrap_or(defaults.check_interval_secs),
+            premake_months: parse_env("PARTITION_PREMAKE_MONTHS").unwrap_or(defaults.premake_months),
+            job_retention_months: parse_env("PARTITION_JOB_RETENTION_MONTHS").or(defaults.job_retention_months),
+            wallet_transaction_retention_months: parse_env("PARTITION_WALLET_TRANSACTION_RETENTION_MONTHS")
+                .or(defaults.wallet_transaction_retention_months),
+        }
+    }
+
+    /// Retention of a partitioned table, if its rows are ever dropped
+    pub fn retention_months(&self, table: PartitionedTable) -> Option<u32> {
+        match table {
+            PartitionedTable::Jobs => self.job_retention_months,
+            PartitionedTable::WalletTransactions => self.wallet_transaction_retention_months,
+        }
+    }
+}
+
+/// Partition created or dropped by a maintenance run
+#[derive(Debug, Clone, Serialize)]
+pub struct PartitionChange {
+    pub table: PartitionedTable,
+    pub partition: String,
+    /// First day of the partition's month
+    pub month: NaiveDate,
+    /// Rows moved into the created partition, or dropped with the dropped one
+    pub rows: usize,
+}
+
+/// Outcome of a partition maintenance run
+#[derive(Debug, Clone, Default, Serialize)]
+pub struct PartitionMaintenance {
+    pub created: Vec<PartitionChange>,
+    pub dropped: Vec<PartitionChange>,
+}
+
+/// Whether closed billing periods cover a whole month, so its ledger is final
+fn month_closed(month: NaiveDate, periods: &[BillingPeriod]) -> bool {
+    let start = month.and_hms_opt(0, 0, 0).unwrap_or_default().and_utc();
+    let end = next_month(month).and_hms_opt(0, 0, 0).unwrap_or_default().and_utc();
+    periods.iter().any(|period| period.period_start <= start && end <= period.period_end)
+}
+
+/// Service keeping the monthly partitions of jobs and wallet transactions in line
+/// with the retention policy
+///
+/// Partitions of the current month and the coming ones are created ahead of time, so
+/// new rows never land in the default partition; months past their table's retention
+/// are dropped as a whole. Only the `public` schema is partitioned.
+pub struct PartitionService {
+    partition_repo: Arc<dyn PartitionRepository>,
+    billing_period_repo: Arc<dyn BillingPeriodRepository>,
+    config: PartitionConfig,
+}
+
+impl PartitionService {
+    /// Create a new PartitionService
+    pub fn new(
+        partition_repo: Arc<dyn PartitionRepository>,
+        billing_period_repo: Arc<dyn BillingPeriodRepository>,
+        config: Option<PartitionConfig>,
+    ) -> Self {
+        Self {
+            partition_repo,
+            billing_period_repo,
+            config: config.unwrap_or_default(),
+        }
+    }
+
+    /// Create the partitions of the coming months and drop those past retention
+    pub async fn maintain(&self) -> Result<PartitionMaintenance> {
+        let now = Utc::now();
+        let mut maintenance = PartitionMaintenance::default();
+
+        for table in PartitionedTable::ALL {
+            let months = self.partition_repo.list_months(table).await?;
+
+            for month in months_to_create(now, self.config.premake_months) {
+                if months.contains(&month) {
+                    continue;
+                }
+                let rows = self.partition_repo.create_month(table, month).await?;
+                info!("Created partition {} ({} rows moved from the default partition)", table.partition_name(month), rows);
+                maintenance.created.push(PartitionChange { table, partition: table.partition_name(month), month, rows });
+            }
+
+            let Some(retention_months) = self.config.retention_months(table) else { continue };
+            let mut expired = months_past_retention(&months, now, retention_months);
+            if table == PartitionedTable::WalletTransactions && !expired.is_empty() {
+                // Transactions are only dropped once their ledger was closed and checksummed
+                let periods = self.billing_period_repo.list().await?;
+                expired.retain(|month| month_closed(*month, &periods));
+            }
+            for month in expired {
+                let rows = self.partition_repo.drop_month(table, month).await?;
+                info!("Dropped partition {} with {} rows past retention", table.partition_name(month), rows);
+                maintenance.dropped.push(PartitionChange { table, partition: table.partition_name(month), month, rows });
+            }
+        }
+
+        Ok(maintenance)
+    }
+}
@@ -0,0 +1,52 @@
+use chrono::{DateTime, Utc};
+use serde::Serialize;
+
+use innosystem_common::database::PoolStatus;
+
+use crate::state::AppState;
+
+/// Utilization of the database and Redis connection pools
+#[derive(Debug, Clone, Serialize)]
+pub struct PoolMetricsReport {
+    /// Pool of the shared schema; pools of per-reseller schemas are not included
+    pub database: PoolStatus,
+    /// Pool of the job queue
+    pub redis: PoolStatus,
+    pub generated_at: DateTime<Utc>,
+}
+
+impl PoolMetricsReport {
+    /// Snapshot the current state of the pools
+    pub fn collect(state: &AppState) -> Self {
+        Self {
+            database: state.pool_metrics.status(state.schema_resolver.shared_pool()),
+            redis: state.job_queue.pool_status(),
+            generated_at: Utc::now(),
+        }
+    }
+
+    /// Render the report in the Prometheus text exposition format
+    pub fn render_metrics(&self) -> String {
+        let mut out = String::new();
+        self.push_series(&mut out, "innosystem_pool_max_size", "gauge", "Maximum connections of the pool", |p| Some(p.max_size.to_string()));
+        self.push_series(&mut out, "innosystem_pool_connections", "gauge", "Connections open, idle or in use", |p| Some(p.connections.to_string()));
+        self.push_series(&mut out, "innosystem_pool_connections_in_use", "gauge", "Connections checked out", |p| Some(p.in_use().to_string()));
+        self.push_series(&mut out, "innosystem_pool_utilization_ratio", "gauge", "Share of the pool's connections checked out", |p| Some(p.utilization().to_string()));
+        self.push_series(&mut out, "innosystem_pool_checkouts_total", "counter", "Connections handed out by the pool", |p| Some(p.checkouts.to_string()));
+        self.push_series(&mut out, "innosystem_pool_timeouts_total", "counter", "Checkouts that gave up waiting for a connection", |p| Some(p.timeouts.to_string()));
+        self.push_series(&mut out, "innosystem_pool_wait_seconds_sum", "counter", "Total time checkouts waited for a connection", |p| Some(p.wait_seconds_total.to_string()));
+        self.push_series(&mut out, "innosystem_pool_wait_seconds_max", "gauge", "Longest wait of a single checkout", |p| p.wait_seconds_max.map(|max| max.to_string()));
+        out
+    }
+
+    /// Append one metric with a sample per pool that reports it
+    fn push_series(&self, out: &mut String, name: &str, kind: &str, help: &str, value: impl Fn(&PoolStatus) -> Option<String>) {
+        out.push_str(&format!("# HELP {} {}\n", name, help));
+        out.push_str(&format!("# TYPE {} {}\n", name, kind));
+        for (pool, status) in [("database", &self.database), ("redis", &self.redis)] {
+            if let Some(value) = value(status) {
+                out.push_str(&format!("{}{{pool=\"{}\"}} {}\n", name, pool, value));
+            }
+        }
+    }
+}
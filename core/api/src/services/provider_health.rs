@@ -0,0 +1,217 @@
+use std::sync::Arc;
+use std::time::Duration as StdDuration;
+use anyhow::{Context, Result};
+use chrono::{DateTime, Duration, Utc};
+use tracing::{info, warn};
+use uuid::Uuid;
+
+use innosystem_common::config::parse_env;
+use innosystem_common::database::{with_reseller, SchemaResolver};
+use innosystem_common::models::provider::{Provider, ProviderStatus};
+use innosystem_common::queue::JobQueue;
+use innosystem_common::repositories::{JobRepository, JobTypeRepository, ProviderRepository};
+use innosystem_common::repositories::job::{JobFilter, Pagination};
+
+/// Configuration of the health checks of external providers
+#[derive(Debug, Clone)]
+pub struct ProviderHealthConfig {
+    /// Interval between two health checks of every provider
+    pub check_interval_secs: u64,
+    /// Minutes of finished jobs the failure rate of a provider is computed over
+    pub window_minutes: i64,
+    /// Seconds a ping URL has to answer within
+    pub ping_timeout_secs: u64,
+}
+
+impl Default for ProviderHealthConfig {
+    fn default() -> Self {
+        Self {
+            check_interval_secs: 60,
+            window_minutes: 15,
+            ping_timeout_secs: 5,
+        }
+    }
+}
+
+impl ProviderHealthConfig {
+    /// Load the configuration from `PROVIDER_HEALTH_*` environment variables, using defaults for unset values
+    pub fn from_env() -> Self {
+        let defaults = Self::default();
+        Self {
+            check_interval_secs: parse_env("PROVIDER_HEALTH_CHECK_INTERVAL_SECS").unwrap_or(defaults.check_interval_secs),
+            window_minutes: parse_env("PROVIDER_HEALTH_WINDOW_MINUTES").filter(|minutes| *minutes > 0).unwrap_or(defaults.window_minutes),
+            ping_timeout_secs: parse_env("PROVIDER_HEALTH_PING_TIMEOUT_SECS").unwrap_or(defaults.ping_timeout_secs),
+        }
+    }
+}
+
+/// Service monitoring the external providers job types depend on
+///
+/// Jobs submitted while their job type's provider is down are scheduled for after its
+/// retry delay instead of being queued, so they do not fail (and incur failure fees)
+/// against a provider that cannot serve them. Scheduled jobs coming due while it stays
+/// down are pushed back again on every check.
+pub struct ProviderHealthService {
+    provider_repo: Arc<dyn ProviderRepository>,
+    job_type_repo: Arc<dyn JobTypeRepository>,
+    job_repo: Arc<dyn JobRepository>,
+    job_queue: Arc<dyn JobQueue>,
+    schema_resolver: Arc<SchemaResolver>,
+    config: ProviderHealthConfig,
+    client: reqwest::Client,
+}
+
+impl ProviderHealthService {
+    /// Create a new ProviderHealthService
+    pub fn new(
+        provider_repo: Arc<dyn ProviderRepository>,
+        job_type_repo: Arc<dyn JobTypeRepository>,
+        job_repo: Arc<dyn JobRepository>,
+        job_queue: Arc<dyn JobQueue>,
+        schema_resolver: Arc<SchemaResolver>,
+        config: Option<ProviderHealthConfig>,
+    ) -> Self {
+        Self {
+            provider_repo,
+            job_type_repo,
+            job_repo,
+            job_queue,
+            schema_resolver,
+            config: config.unwrap_or_default(),
+            client: reqwest::Client::new(),
+        }
+    }
+
+    /// When a job of the given type submitted now may be queued: None unless the job
+    /// type depends on a provider that is down
+    ///
+    /// Jobs are queued as usual when the provider cannot be read.
+    pub async fn hold_until(&self, job_type_id: Uuid) -> Option<DateTime<Utc>> {
+        let provider_id = self.job_type_repo.find_by_id(job_type_id).await.ok()?.provider_id?;
+        match self.provider_repo.find_by_id(provider_id).await {
+            Ok(provider) => provider.hold_until(Utc::now()),
+            Err(e) => {
+                warn!("Failed to read provider {} of job type {}: {}", provider_id, job_type_id, e);
+                None
+            }
+        }
+    }
+
+    /// Check the health of every provider, record its status and push back the
+    /// scheduled jobs of those that are down, returning the providers as checked
+    pub async fn check_all(&self) -> Result<Vec<Provider>> {
+        let providers = self.provider_repo.list_all().await?;
+        if providers.is_empty() {
+            return Ok(providers);
+        }
+
+        let job_types = self.job_type_repo.list_all().await?;
+        let resolver = self.schema_resolver.clone();
+        let scopes = tokio::task::spawn_blocking(move || resolver.scopes()).await??;
+
+        let mut checked = Vec::with_capacity(providers.len());
+        for provider in providers {
+            let job_type_ids: Vec<Uuid> = job_types.iter()
+                .filter(|job_type| job_type.provider_id == Some(provider.id))
+                .map(|job_type| job_type.id)
+                .collect();
+            checked.push(self.check(provider, job_type_ids, &scopes).await?);
+        }
+
+        Ok(checked)
+    }
+
+    /// Check a single provider across the shared schema and every reseller schema
+    async fn check(&self, provider: Provider, job_type_ids: Vec<Uuid>, scopes: &[Option<Uuid>]) -> Result<Provider> {
+        let ping = match &provider.ping_url {
+            Some(url) => Some(self.ping(url).await),
+            None => None,
+        };
+
+        let since = Utc::now() - Duration::minutes(self.config.window_minutes);
+        let (mut finished, mut failed) = (0, 0);
+        for &scope in scopes {
+            let (scope_finished, scope_failed) = with_reseller(scope, self.failure_counts(&job_type_ids, since)).await?;
+            finished += scope_finished;
+            failed += scope_failed;
+        }
+
+        let (status, reason) = provider.assess(ping.as_ref(), finished, failed);
+        let was = provider.provider_status();
+        let provider = self.provider_repo.record_check(provider.id, status, reason).await?;
+        if status != was {
+            match &provider.status_reason {
+                Some(reason) => warn!("Provider {} is {}: {}", provider.name, status.as_str(), reason),
+                None => info!("Provider {} is {}", provider.name, status.as_str()),
+            }
+        }
+
+        if status == ProviderStatus::Down {
+            let mut postponed = 0;
+            for &scope in scopes {
+                postponed += with_reseller(scope, self.postpone(&provider, job_type_ids.clone())).await?;
+            }
+            if postponed > 0 {
+                info!("Postponed {} scheduled jobs while provider {} is down", postponed, provider.name);
+            }
+        }
+
+        Ok(provider)
+    }
+
+    /// Probe a ping URL, which has to answer with a success status in time
+    async fn ping(&self, url: &str) -> Result<(), String> {
+        let response = self.client.get(url)
+            .timeout(StdDuration::from_secs(self.config.ping_timeout_secs.max(1)))
+            .send()
+            .await
+            .map_err(|e| e.to_string())?;
+
+        if !response.status().is_success() {
+            return Err(format!("HTTP {}", response.status()));
+        }
+
+        Ok(())
+    }
+
+    /// Finished and failed jobs of the job types since the given time, in the current scope
+    async fn failure_counts(&self, job_type_ids: &[Uuid], since: DateTime<Utc>) -> Result<(u64, u64)> {
+        let (mut finished, mut failed) = (0, 0);
+        for &job_type_id in job_type_ids {
+            // Only the totals are needed, not the jobs themselves
+            let (_, total) = self.job_repo.query_jobs(
+                JobFilter { job_type_id: Some(job_type_id), created_after: Some(since), completed_only: true, ..Default::default() },
+                None,
+                Some(Pagination { page: 0, per_page: 1 }),
+            ).await.context("Failed to count finished jobs")?;
+            finished += total;
+
+            let (_, total) = self.job_repo.query_jobs(
+                JobFilter { job_type_id: Some(job_type_id), created_after: Some(since), failed_only: true, ..Default::default() },
+                None,
+                Some(Pagination { page: 0, per_page: 1 }),
+            ).await.context("Failed to count failed jobs")?;
+            failed += total;
+        }
+
+        Ok((finished, failed))
+    }
+
+    /// Push back the scheduled jobs of a provider that is down until after the next
+    /// check, in the current scope, returning how many were postponed
+    async fn postpone(&self, provider: &Provider, job_type_ids: Vec<Uuid>) -> Result<usize> {
+        let now = Utc::now();
+        let next_check = now + Duration::seconds(self.config.check_interval_secs as i64);
+        let until = provider.hold_until(now).map_or(next_check, |until| until.max(next_check));
+
+        let postponed = self.job_repo.postpone_scheduled(job_type_ids, until, until).await
+            .context("Failed to postpone scheduled jobs")?;
+        for &job_id in &postponed {
+            if let Err(e) = self.job_queue.schedule_job(job_id, until).await {
+                warn!("Failed to reschedule job {} on the queue: {}", job_id, e);
+            }
+        }
+
+        Ok(postponed.len())
+    }
+}
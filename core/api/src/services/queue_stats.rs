@@ -0,0 +1,150 @@
+use std::sync::Arc;
+use anyhow::{Result, anyhow};
+use chrono::{DateTime, Utc};
+use serde::Serialize;
+
+use innosystem_common::config::parse_env;
+use innosystem_common::models::job::PriorityLevel;
+use innosystem_common::queue::QuarantinedEntry;
+use innosystem_common::repositories::JobRepository;
+use innosystem_common::repositories::job::PriorityWaitStats;
+
+/// Configuration for queue wait time reporting
+#[derive(Debug, Clone)]
+pub struct QueueWaitConfig {
+    /// Window (in minutes) of claimed jobs the wait time percentiles are computed over
+    pub sample_window_minutes: i32,
+    /// Longest acceptable 90th percentile wait (in seconds) of Low priority jobs before they count as starving
+    pub low_priority_max_wait_secs: f64,
+}
+
+impl Default for QueueWaitConfig {
+    fn default() -> Self {
+        Self {
+            sample_window_minutes: 60,
+            low_priority_max_wait_secs: 3600.0, // 1 hour
+        }
+    }
+}
+
+impl QueueWaitConfig {
+    /// Load the configuration from `QUEUE_WAIT_*` environment variables, using defaults for unset values
+    pub fn from_env() -> Self {
+        let defaults = Self::default();
+        Self {
+            sample_window_minutes: parse_env("QUEUE_WAIT_SAMPLE_WINDOW_MINUTES").unwrap_or(defaults.sample_window_minutes),
+            low_priority_max_wait_secs: parse_env("QUEUE_WAIT_LOW_PRIORITY_MAX_SECS").unwrap_or(defaults.low_priority_max_wait_secs),
+        }
+    }
+}
+
+/// Queue wait times of a single priority level
+#[derive(Debug, Clone, Serialize)]
+pub struct PriorityWaitTimes {
+    pub priority: &'static str,
+    /// Jobs claimed by runners within the sampling window
+    pub samples: i64,
+    pub p50_secs: f64,
+    pub p90_secs: f64,
+    pub p99_secs: f64,
+    pub max_secs: f64,
+}
+
+impl From<PriorityWaitStats> for PriorityWaitTimes {
+    fn from(stats: PriorityWaitStats) -> Self {
+        Self {
+            priority: stats.priority.as_str(),
+            samples: stats.samples,
+            p50_secs: stats.p50_seconds,
+            p90_secs: stats.p90_seconds,
+            p99_secs: stats.p99_seconds,
+            max_secs: stats.max_seconds,
+        }
+    }
+}
+
+/// Per-priority queue wait times, checked against the Low priority bound
+#[derive(Debug, Clone, Serialize)]
+pub struct QueueWaitReport {
+    pub sample_window_minutes: i32,
+    pub low_priority_max_wait_secs: f64,
+    /// Whether the 90th percentile wait of Low priority jobs exceeds the bound
+    pub low_priority_starving: bool,
+    /// Priority levels with claimed jobs in the window, lowest first
+    pub priorities: Vec<PriorityWaitTimes>,
+    pub generated_at: DateTime<Utc>,
+}
+
+/// Queue entries set aside because they are not job IDs
+#[derive(Debug, Clone, Serialize)]
+pub struct QuarantineReport {
+    /// Entries held in quarantine
+    pub held: usize,
+    /// Entries quarantined so far, including those dropped since
+    pub total: u64,
+    /// Most recently quarantined entries, newest first
+    pub entries: Vec<QuarantinedEntry>,
+}
+
+/// Service reporting how long jobs wait in the queue per priority level
+pub struct QueueStatsService {
+    job_repo: Arc<dyn JobRepository>,
+    config: QueueWaitConfig,
+}
+
+impl QueueStatsService {
+    /// Create a new QueueStatsService
+    pub fn new(job_repo: Arc<dyn JobRepository>, config: Option<QueueWaitConfig>) -> Self {
+        Self {
+            job_repo,
+            config: config.unwrap_or_default(),
+        }
+    }
+
+    /// Compute the wait time percentiles of recently claimed jobs
+    pub async fn wait_times(&self) -> Result<QueueWaitReport> {
+        let stats = self.job_repo.get_queue_wait_stats(self.config.sample_window_minutes).await
+            .map_err(|e| anyhow!("Failed to load queue wait statistics: {}", e))?;
+
+        let priorities: Vec<PriorityWaitTimes> = stats.into_iter().map(PriorityWaitTimes::from).collect();
+        let low_priority_starving = priorities.iter()
+            .any(|wait| wait.priority == PriorityLevel::Low.as_str() && wait.p90_secs > self.config.low_priority_max_wait_secs);
+
+        Ok(QueueWaitReport {
+            sample_window_minutes: self.config.sample_window_minutes,
+            low_priority_max_wait_secs: self.config.low_priority_max_wait_secs,
+            low_priority_starving,
+            priorities,
+            generated_at: Utc::now(),
+        })
+    }
+
+    /// Render the wait times in the Prometheus text exposition format
+    pub fn render_metrics(report: &QueueWaitReport) -> String {
+        let mut out = String::new();
+        out.push_str("# HELP innosystem_queue_wait_seconds Time jobs waited in the queue before a runner claimed them\n");
+        out.push_str("# TYPE innosystem_queue_wait_seconds summary\n");
+        for wait in &report.priorities {
+            for (quantile, value) in [("0.5", wait.p50_secs), ("0.9", wait.p90_secs), ("0.99", wait.p99_secs), ("1", wait.max_secs)] {
+                out.push_str(&format!(
+                    "innosystem_queue_wait_seconds{{priority=\"{}\",quantile=\"{}\"}} {}\n",
+                    wait.priority, quantile, value
+                ));
+            }
+            out.push_str(&format!("innosystem_queue_wait_seconds_count{{priority=\"{}\"}} {}\n", wait.priority, wait.samples));
+        }
+        out
+    }
+
+    /// Render the quarantined queue entries in the Prometheus text exposition format
+    pub fn render_quarantine_metrics(report: &QuarantineReport) -> String {
+        let mut out = String::new();
+        out.push_str("# HELP innosystem_queue_quarantined_entries Queue entries held in quarantine\n");
+        out.push_str("# TYPE innosystem_queue_quarantined_entries gauge\n");
+        out.push_str(&format!("innosystem_queue_quarantined_entries {}\n", report.held));
+        out.push_str("# HELP innosystem_queue_quarantined_total Queue entries quarantined because they are not job IDs\n");
+        out.push_str("# TYPE innosystem_queue_quarantined_total counter\n");
+        out.push_str(&format!("innosystem_queue_quarantined_total {}\n", report.total));
+        out
+    }
+}
@@ -0,0 +1,119 @@
+use std::collections::HashMap;
+use std::sync::Arc;
+use thiserror::Error;
+use tracing::info;
+use uuid::Uuid;
+
+use innosystem_common::database::{with_reseller, SchemaResolver};
+use innosystem_common::models::job_type::JobType;
+use innosystem_common::models::redaction::{parse_rules, RedactionError};
+use innosystem_common::models::replay::{Anonymizer, NewReplayCase, ReplayCase};
+use innosystem_common::repositories::{CustomerRepository, ReplayCaseRepository, ResellerRepository};
+
+/// Most jobs sampled into a corpus at once
+pub const MAX_SAMPLE_SIZE: usize = 500;
+
+/// Reasons jobs are not sampled into a replay corpus
+#[derive(Debug, Error)]
+pub enum ReplayCorpusError {
+    #[error("Between 1 and {max} jobs are sampled at once, got {count}")]
+    InvalidCount { count: usize, max: usize },
+    #[error(transparent)]
+    InvalidRule(#[from] RedactionError),
+    #[error(transparent)]
+    Repository(#[from] anyhow::Error),
+}
+
+/// Service sampling historical jobs into the replay corpus of their job type
+///
+/// Runners in replay mode run a job type's corpus against their processor version and
+/// compare the outputs and costs with the baseline recorded by an earlier version.
+/// Inputs are anonymized when sampled: the values the job type redacts from outputs
+/// and those at the paths given with the sample are replaced with pseudonyms. Jobs are
+/// sampled from the schemas of all resellers; test-mode jobs are never sampled.
+pub struct ReplayCorpusService {
+    replay_case_repo: Arc<dyn ReplayCaseRepository>,
+    customer_repo: Arc<dyn CustomerRepository>,
+    reseller_repo: Arc<dyn ResellerRepository>,
+    schema_resolver: Arc<SchemaResolver>,
+}
+
+impl ReplayCorpusService {
+    /// Create a new ReplayCorpusService
+    pub fn new(
+        replay_case_repo: Arc<dyn ReplayCaseRepository>,
+        customer_repo: Arc<dyn CustomerRepository>,
+        reseller_repo: Arc<dyn ResellerRepository>,
+        schema_resolver: Arc<SchemaResolver>,
+    ) -> Self {
+        Self {
+            replay_case_repo,
+            customer_repo,
+            reseller_repo,
+            schema_resolver,
+        }
+    }
+
+    /// Sample up to `count` succeeded jobs of a job type into its corpus, anonymizing
+    /// the values the job type's redaction rules and the `anonymize` paths select
+    ///
+    /// Returns the cases added, fewer than asked for if the job type ran fewer jobs
+    /// that are not in its corpus yet.
+    pub async fn sample(&self, job_type: &JobType, count: usize, anonymize: &[String]) -> Result<Vec<ReplayCase>, ReplayCorpusError> {
+        if count == 0 || count > MAX_SAMPLE_SIZE {
+            return Err(ReplayCorpusError::InvalidCount { count, max: MAX_SAMPLE_SIZE });
+        }
+        let mut rules = parse_rules(&job_type.redaction_rules)?;
+        rules.extend(parse_rules(anonymize)?);
+        let anonymizer = Anonymizer::new(rules);
+
+        let mut markup_rates = HashMap::new();
+        let mut sampled = Vec::new();
+        for scope in self.scopes().await? {
+            let remaining = count - sampled.len();
+            if remaining == 0 {
+                break;
+            }
+            let candidates = with_reseller(scope, self.replay_case_repo.find_candidates(job_type.id, remaining as i64)).await?;
+            let mut cases = Vec::with_capacity(candidates.len());
+            for job in &candidates {
+                let markup_rate = match markup_rates.get(&job.customer_id) {
+                    Some(rate) => *rate,
+                    None => {
+                        let rate = self.markup_rate(job.customer_id).await?;
+                        markup_rates.insert(job.customer_id, rate);
+                        rate
+                    }
+                };
+                cases.push(NewReplayCase::sample(job, markup_rate, &anonymizer));
+            }
+            sampled.extend(self.replay_case_repo.add(cases).await?);
+        }
+
+        info!("Sampled {} jobs into the replay corpus of job type {}", sampled.len(), job_type.id);
+        Ok(sampled)
+    }
+
+    /// The corpus of a job type, oldest cases first
+    pub async fn list(&self, job_type_id: Uuid) -> anyhow::Result<Vec<ReplayCase>> {
+        self.replay_case_repo.list_by_job_type(job_type_id).await
+    }
+
+    /// Remove a case from the corpus of a job type; returns whether it was there
+    pub async fn remove(&self, job_type_id: Uuid, case_id: Uuid) -> anyhow::Result<bool> {
+        self.replay_case_repo.delete(job_type_id, case_id).await
+    }
+
+    /// Markup the reseller of a customer adds to its jobs, in basis points
+    async fn markup_rate(&self, customer_id: Uuid) -> anyhow::Result<i32> {
+        match self.customer_repo.find_by_id(customer_id).await?.reseller_id {
+            Some(reseller_id) => Ok(self.reseller_repo.find_by_id(reseller_id).await?.markup_rate),
+            None => Ok(0),
+        }
+    }
+
+    async fn scopes(&self) -> anyhow::Result<Vec<Option<Uuid>>> {
+        let resolver = self.schema_resolver.clone();
+        Ok(tokio::task::spawn_blocking(move || resolver.scopes()).await??)
+    }
+}
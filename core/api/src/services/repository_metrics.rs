@@ -0,0 +1,48 @@
+use chrono::{DateTime, Utc};
+use serde::Serialize;
+
+use innosystem_common::repositories::RepositoryMetrics;
+use innosystem_common::repositories::instrumented::MethodStats;
+
+/// Call counts and timings of every repository method called since startup
+#[derive(Debug, Clone, Serialize)]
+pub struct RepositoryMetricsReport {
+    pub slow_query_threshold_ms: u64,
+    /// Methods ordered by entity and name
+    pub methods: Vec<MethodStats>,
+    pub generated_at: DateTime<Utc>,
+}
+
+impl RepositoryMetricsReport {
+    /// Snapshot the current repository metrics
+    pub fn collect(metrics: &RepositoryMetrics) -> Self {
+        Self {
+            slow_query_threshold_ms: metrics.slow_query_threshold_ms(),
+            methods: metrics.snapshot(),
+            generated_at: Utc::now(),
+        }
+    }
+
+    /// Render the report in the Prometheus text exposition format
+    pub fn render_metrics(&self) -> String {
+        let mut out = String::new();
+        self.push_series(&mut out, "innosystem_repository_calls_total", "counter", "Repository method calls", |m| m.calls.to_string());
+        self.push_series(&mut out, "innosystem_repository_errors_total", "counter", "Repository method calls that returned an error", |m| m.errors.to_string());
+        self.push_series(&mut out, "innosystem_repository_slow_calls_total", "counter", "Repository method calls exceeding the slow query threshold", |m| m.slow_calls.to_string());
+        self.push_series(&mut out, "innosystem_repository_duration_seconds_sum", "counter", "Total time spent in repository methods", |m| m.total_seconds.to_string());
+        self.push_series(&mut out, "innosystem_repository_duration_seconds_max", "gauge", "Longest repository method call", |m| m.max_seconds.to_string());
+        out
+    }
+
+    /// Append one metric with a sample per repository method
+    fn push_series(&self, out: &mut String, name: &str, kind: &str, help: &str, value: impl Fn(&MethodStats) -> String) {
+        out.push_str(&format!("# HELP {} {}\n", name, help));
+        out.push_str(&format!("# TYPE {} {}\n", name, kind));
+        for method in &self.methods {
+            out.push_str(&format!(
+                "{}{{entity=\"{}\",method=\"{}\"}} {}\n",
+                name, method.entity, method.method, value(method)
+            ));
+        }
+    }
+}
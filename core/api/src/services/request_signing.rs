@@ -0,0 +1,110 @@
+use std::sync::Arc;
+use chrono::DateTime;
+use thiserror::Error;
+use uuid::Uuid;
+
+use innosystem_common::config::parse_env;
+use innosystem_common::models::request_signature::{SignatureError, SignedRequest};
+use innosystem_common::models::reseller::Reseller;
+use innosystem_common::repositories::{RequestNonceRepository, ResellerRepository};
+
+/// Configuration of signed reseller requests
+#[derive(Debug, Clone)]
+pub struct RequestSigningConfig {
+    /// Seconds a request's timestamp may be away from the server time; its nonce is
+    /// kept for as long, so a captured request cannot be replayed
+    pub tolerance_secs: i64,
+    /// Largest body of a signed request, read in full to verify its signature
+    pub max_body_bytes: usize,
+}
+
+impl Default for RequestSigningConfig {
+    fn default() -> Self {
+        Self {
+            tolerance_secs: 300,  // 5 minutes
+            max_body_bytes: 10 * 1024 * 1024,
+        }
+    }
+}
+
+impl RequestSigningConfig {
+    /// Load the configuration from `REQUEST_SIGNING_*` environment variables, using defaults for unset values
+    pub fn from_env() -> Self {
+        let defaults = Self::default();
+        Self {
+            tolerance_secs: parse_env("REQUEST_SIGNING_TOLERANCE_SECS").unwrap_or(defaults.tolerance_secs),
+            max_body_bytes: parse_env("REQUEST_SIGNING_MAX_BODY_BYTES").unwrap_or(defaults.max_body_bytes),
+        }
+    }
+}
+
+/// Reasons a signed request is not authenticated
+#[derive(Debug, Error)]
+pub enum SignedRequestError {
+    #[error("Unknown reseller {0}")]
+    UnknownReseller(Uuid),
+    #[error("Reseller {0} has no signing secret")]
+    SigningDisabled(Uuid),
+    #[error(transparent)]
+    Signature(#[from] SignatureError),
+    #[error("Nonce was already used")]
+    Replayed,
+    #[error("Failed to record the nonce: {0}")]
+    Repository(#[from] anyhow::Error),
+}
+
+/// Service authenticating resellers by the HMAC signature of their requests
+///
+/// An alternative to bearer API keys for resellers that may not send long-lived
+/// secrets: the signing secret never leaves the reseller, and each signature is
+/// only accepted once and within a few minutes of being made.
+pub struct RequestSigningService {
+    reseller_repo: Arc<dyn ResellerRepository>,
+    nonce_repo: Arc<dyn RequestNonceRepository>,
+    config: RequestSigningConfig,
+}
+
+impl RequestSigningService {
+    /// Create a new RequestSigningService
+    pub fn new(
+        reseller_repo: Arc<dyn ResellerRepository>,
+        nonce_repo: Arc<dyn RequestNonceRepository>,
+        config: Option<RequestSigningConfig>,
+    ) -> Self {
+        Self {
+            reseller_repo,
+            nonce_repo,
+            config: config.unwrap_or_default(),
+        }
+    }
+
+    /// Largest body of a signed request
+    pub fn max_body_bytes(&self) -> usize {
+        self.config.max_body_bytes
+    }
+
+    /// Verify a request signed by a reseller and use up its nonce, returning the reseller
+    pub async fn authenticate(
+        &self,
+        reseller_id: Uuid,
+        request: SignedRequest<'_>,
+        signature: &str,
+    ) -> Result<Reseller, SignedRequestError> {
+        let reseller = self.reseller_repo.find_by_id(reseller_id).await
+            .map_err(|_| SignedRequestError::UnknownReseller(reseller_id))?;
+        let secret = reseller.signing_secret.as_deref()
+            .ok_or(SignedRequestError::SigningDisabled(reseller_id))?;
+
+        let now = chrono::Utc::now().timestamp();
+        request.verify(secret, signature, now, self.config.tolerance_secs)?;
+
+        // Past this time the timestamp is refused anyway, so the nonce can be forgotten
+        let expires_at = DateTime::from_timestamp(request.timestamp + self.config.tolerance_secs, 0)
+            .unwrap_or_default();
+        if !self.nonce_repo.record(reseller_id, request.nonce, expires_at).await? {
+            return Err(SignedRequestError::Replayed);
+        }
+
+        Ok(reseller)
+    }
+}
@@ -1,12 +1,18 @@
-use std::sync::Arc;
+use std::env;
+use std::sync::{Arc, Mutex};
 use uuid::Uuid;
-use anyhow::{Result, Context};
-use chrono::{Utc, Duration};
-use tracing::{info, error};
+use anyhow::{Result, Context, anyhow};
+use chrono::{DateTime, Duration, Utc};
+use serde::Serialize;
+use serde_json::json;
+use tracing::{info, error, warn};
 
-use innosystem_common::models::runner::RunnerStatus;
+use innosystem_common::config::parse_env;
+use innosystem_common::models::runner::{NewRunnerHealthCheck, Runner, RunnerHealthCheck, RunnerStatus};
 use innosystem_common::models::job::JobStatus;
-use innosystem_common::repositories::{JobRepository, JobTypeRepository, RunnerRepository};
+use innosystem_common::models::job_log::LogLevel;
+use innosystem_common::queue::JobQueue;
+use innosystem_common::repositories::{JobAttemptRepository, JobLogRepository, JobRepository, JobTypeRepository, RunnerRepository};
 
 /// Defines the health status of a runner
 #[derive(Debug, Clone, PartialEq, Eq)]
@@ -30,6 +36,26 @@ impl RunnerHealthStatus {
             RunnerHealthStatus::Unknown => "unknown",
         }
     }
+
+    pub fn parse(s: &str) -> Option<Self> {
+        match s.to_lowercase().as_str() {
+            "healthy" => Some(RunnerHealthStatus::Healthy),
+            "warning" => Some(RunnerHealthStatus::Warning),
+            "critical" => Some(RunnerHealthStatus::Critical),
+            "unknown" => Some(RunnerHealthStatus::Unknown),
+            _ => None,
+        }
+    }
+
+    /// Severity of the status for comparisons; `None` when the health cannot be determined
+    fn severity(&self) -> Option<u8> {
+        match self {
+            RunnerHealthStatus::Healthy => Some(0),
+            RunnerHealthStatus::Warning => Some(1),
+            RunnerHealthStatus::Critical => Some(2),
+            RunnerHealthStatus::Unknown => None,
+        }
+    }
 }
 
 /// Configuration for the runner health service
@@ -39,6 +65,30 @@ pub struct RunnerHealthConfig {
     pub healthy_heartbeat_interval_secs: i64,
     /// Maximum duration between heartbeats (in seconds) for a runner to be considered in warning state
     pub warning_heartbeat_interval_secs: i64,
+    /// Share of failed jobs from which a runner is in warning state
+    pub warning_failure_rate: f64,
+    /// Share of failed jobs from which a runner is critical
+    pub critical_failure_rate: f64,
+    /// Minimum number of completed jobs before the failure rate is taken into account
+    pub min_jobs_for_failure_rate: i64,
+    /// Average processing time (in seconds) from which a runner is in warning state
+    pub warning_processing_secs: f64,
+    /// Average processing time (in seconds) from which a runner is critical
+    pub critical_processing_secs: f64,
+    /// Window (in minutes) of completed jobs the failure rate and processing time are sampled from
+    pub sample_window_minutes: i32,
+    /// Interval between two health evaluations of all active runners
+    pub check_interval_secs: u64,
+    /// Endpoint receiving `runner.degraded` and `runner.recovered` events, e.g. an alert manager
+    pub alert_webhook_url: Option<String>,
+    /// Seconds a runner that stopped heartbeating may stay critical before its running jobs
+    /// are handed back to the queue for other runners (0 disables); a runner recovering
+    /// sooner keeps them
+    pub reassign_after_critical_secs: i64,
+    /// Hours a runner may stay critical before it is marked inactive (0 disables)
+    pub deactivate_after_critical_hours: i64,
+    /// Days an inactive runner is kept before it is deleted (0 disables)
+    pub delete_inactive_after_days: i64,
 }
 
 impl Default for RunnerHealthConfig {
@@ -46,16 +96,140 @@ impl Default for RunnerHealthConfig {
         Self {
             healthy_heartbeat_interval_secs: 60,  // 1 minute
             warning_heartbeat_interval_secs: 180, // 3 minutes
+            warning_failure_rate: 0.25,
+            critical_failure_rate: 0.5,
+            min_jobs_for_failure_rate: 5,
+            warning_processing_secs: 300.0,       // 5 minutes
+            critical_processing_secs: 900.0,      // 15 minutes
+            sample_window_minutes: 60,
+            check_interval_secs: 60,
+            alert_webhook_url: None,
+            reassign_after_critical_secs: 300,    // 5 minutes
+            deactivate_after_critical_hours: 24,
+            delete_inactive_after_days: 30,
+        }
+    }
+}
+
+impl RunnerHealthConfig {
+    /// Load the configuration from `RUNNER_HEALTH_*` environment variables, using defaults for unset values
+    pub fn from_env() -> Self {
+        let defaults = Self::default();
+        Self {
+            healthy_heartbeat_interval_secs: parse_env("RUNNER_HEALTH_HEALTHY_HEARTBEAT_SECS").unwrap_or(defaults.healthy_heartbeat_interval_secs),
+            warning_heartbeat_interval_secs: parse_env("RUNNER_HEALTH_WARNING_HEARTBEAT_SECS").unwrap_or(defaults.warning_heartbeat_interval_secs),
+            warning_failure_rate: parse_env("RUNNER_HEALTH_WARNING_FAILURE_RATE").unwrap_or(defaults.warning_failure_rate),
+            critical_failure_rate: parse_env("RUNNER_HEALTH_CRITICAL_FAILURE_RATE").unwrap_or(defaults.critical_failure_rate),
+            min_jobs_for_failure_rate: parse_env("RUNNER_HEALTH_MIN_JOBS").unwrap_or(defaults.min_jobs_for_failure_rate),
+            warning_processing_secs: parse_env("RUNNER_HEALTH_WARNING_PROCESSING_SECS").unwrap_or(defaults.warning_processing_secs),
+            critical_processing_secs: parse_env("RUNNER_HEALTH_CRITICAL_PROCESSING_SECS").unwrap_or(defaults.critical_processing_secs),
+            sample_window_minutes: parse_env("RUNNER_HEALTH_SAMPLE_WINDOW_MINUTES").unwrap_or(defaults.sample_window_minutes),
+            check_interval_secs: parse_env("RUNNER_HEALTH_CHECK_INTERVAL_SECS").unwrap_or(defaults.check_interval_secs),
+            alert_webhook_url: env::var("RUNNER_HEALTH_ALERT_WEBHOOK_URL").ok().filter(|url| !url.is_empty()),
+            reassign_after_critical_secs: parse_env("RUNNER_REASSIGN_AFTER_CRITICAL_SECS").unwrap_or(defaults.reassign_after_critical_secs),
+            deactivate_after_critical_hours: parse_env("RUNNER_DEACTIVATE_AFTER_CRITICAL_HOURS").unwrap_or(defaults.deactivate_after_critical_hours),
+            delete_inactive_after_days: parse_env("RUNNER_DELETE_INACTIVE_AFTER_DAYS").unwrap_or(defaults.delete_inactive_after_days),
         }
     }
 }
 
+/// Score (0-100) of a measurement that is fine up to `warning` and fully degraded at `critical`
+fn component_score(value: f64, warning: f64, critical: f64) -> f64 {
+    if value <= warning {
+        100.0
+    } else if value >= critical || critical <= warning {
+        0.0
+    } else {
+        100.0 * (critical - value) / (critical - warning)
+    }
+}
+
+/// Status of a single measurement against its thresholds
+fn component_status(value: f64, warning: f64, critical: f64) -> RunnerHealthStatus {
+    if value >= critical {
+        RunnerHealthStatus::Critical
+    } else if value >= warning {
+        RunnerHealthStatus::Warning
+    } else {
+        RunnerHealthStatus::Healthy
+    }
+}
+
+/// Composite health of a runner, derived from heartbeat recency, failure rate and processing time
+#[derive(Debug, Clone, Serialize)]
+pub struct RunnerHealthReport {
+    pub runner_id: Uuid,
+    pub status: String,
+    /// Weighted score from 0 (down) to 100 (fully healthy)
+    pub score: i32,
+    pub heartbeat_age_secs: Option<i64>,
+    /// Share of failed jobs, if enough jobs completed in the sample window
+    pub failure_rate: Option<f64>,
+    pub avg_processing_secs: Option<f64>,
+    /// Completed jobs in the sample window
+    pub jobs_sampled: i64,
+    #[serde(skip)]
+    health_status: RunnerHealthStatus,
+}
+
+/// Runners affected by one application of the lifecycle policy
+#[derive(Debug, Clone, Default, Serialize)]
+pub struct RunnerLifecycleSummary {
+    /// Runners marked inactive after being critical for too long
+    pub deactivated: Vec<Uuid>,
+    /// Inactive runners deleted with their compatibility rows
+    pub deleted: Vec<Uuid>,
+}
+
+/// Event sent to the alert webhook when a runner's health changes
+#[derive(Debug, Clone, Serialize)]
+pub struct RunnerHealthEvent {
+    /// `runner.degraded` or `runner.recovered`
+    pub event: &'static str,
+    pub runner_id: Uuid,
+    pub runner_name: String,
+    pub previous_status: String,
+    pub report: RunnerHealthReport,
+    pub occurred_at: DateTime<Utc>,
+}
+
+/// Runner that stopped being critical, with when it had become critical
+#[derive(Debug, Clone, Copy)]
+pub struct RecoveredRunner {
+    pub runner_id: Uuid,
+    pub critical_since: DateTime<Utc>,
+}
+
+/// Jobs affected by one reconciliation of the jobs of critical and recovered runners
+#[derive(Debug, Clone, Default, Serialize)]
+pub struct RunnerJobReconciliation {
+    /// Jobs of runners critical for too long, handed back to the queue
+    pub handed_back: Vec<Uuid>,
+    /// Jobs recovered runners still hold, as they recovered before their jobs were handed back
+    pub kept: Vec<Uuid>,
+    /// Jobs recovered runners lost while they were critical; their late results are
+    /// only stored if nobody started the job again
+    pub reassigned: Vec<Uuid>,
+}
+
+impl RunnerJobReconciliation {
+    pub fn is_empty(&self) -> bool {
+        self.handed_back.is_empty() && self.kept.is_empty() && self.reassigned.is_empty()
+    }
+}
+
 /// Service for monitoring runner health and compatibility
 pub struct RunnerHealthService {
     job_repo: Arc<dyn JobRepository>,
     job_type_repo: Arc<dyn JobTypeRepository>,
     runner_repo: Arc<dyn RunnerRepository>,
+    job_attempt_repo: Arc<dyn JobAttemptRepository>,
+    job_log_repo: Arc<dyn JobLogRepository>,
+    job_queue: Arc<dyn JobQueue>,
     config: RunnerHealthConfig,
+    client: reqwest::Client,
+    /// Runners that recovered from critical since their jobs were last reconciled
+    recovered: Mutex<Vec<RecoveredRunner>>,
 }
 
 impl RunnerHealthService {
@@ -64,46 +238,264 @@ impl RunnerHealthService {
         job_repo: Arc<dyn JobRepository>,
         job_type_repo: Arc<dyn JobTypeRepository>,
         runner_repo: Arc<dyn RunnerRepository>,
+        job_attempt_repo: Arc<dyn JobAttemptRepository>,
+        job_log_repo: Arc<dyn JobLogRepository>,
+        job_queue: Arc<dyn JobQueue>,
         config: Option<RunnerHealthConfig>,
     ) -> Self {
         Self {
             job_repo,
             job_type_repo,
             runner_repo,
+            job_attempt_repo,
+            job_log_repo,
+            job_queue,
             config: config.unwrap_or_default(),
+            client: reqwest::Client::new(),
+            recovered: Mutex::new(Vec::new()),
         }
     }
     
     /// Check if a runner is healthy
     pub async fn check_runner_health(&self, runner_id: Uuid) -> Result<RunnerHealthStatus> {
+        Ok(self.evaluate_runner(runner_id).await?.health_status)
+    }
+    
+    /// Compute the composite health of a runner
+    ///
+    /// The status is the worst of the heartbeat, failure rate and processing time
+    /// statuses, so a runner that heartbeats but fails every job is not healthy.
+    /// The score weighs heartbeat recency and failure rate at 40% and processing time at 20%.
+    pub async fn evaluate_runner(&self, runner_id: Uuid) -> Result<RunnerHealthReport> {
         // Get the runner
         let runner = self.runner_repo.find_by_id(runner_id)
             .await
             .context("Failed to find runner for health check")?;
         
-        // If the runner is marked as inactive or maintenance, return unknown
-        if runner.status != RunnerStatus::Active {
-            return Ok(RunnerHealthStatus::Unknown);
+        self.evaluate(&runner).await
+    }
+    
+    async fn evaluate(&self, runner: &Runner) -> Result<RunnerHealthReport> {
+        // Every attempt counts, so a job retried elsewhere still counts against the
+        // runner it failed on; runners without attempt history are scored by their jobs
+        let mut stats = self.job_attempt_repo.get_runner_stats(runner.id, self.config.sample_window_minutes)
+            .await
+            .map_err(|e| anyhow!("Failed to load attempt statistics for runner {}: {}", runner.id, e))?;
+        if stats.completed() == 0 {
+            stats = self.job_repo.get_runner_job_stats(runner.id, self.config.sample_window_minutes)
+                .await
+                .map_err(|e| anyhow!("Failed to load job statistics for runner {}: {}", runner.id, e))?;
         }
         
-        // If no heartbeat, return critical
-        let last_heartbeat = match runner.last_heartbeat {
-            Some(heartbeat) => heartbeat,
-            None => return Ok(RunnerHealthStatus::Critical),
+        let heartbeat_age_secs = runner.last_heartbeat
+            .map(|heartbeat| Utc::now().signed_duration_since(heartbeat).num_seconds().max(0));
+        let failure_rate = stats.failure_rate().filter(|_| stats.completed() >= self.config.min_jobs_for_failure_rate);
+        
+        // Heartbeat recency; a runner that never sent a heartbeat is critical
+        let (heartbeat_score, heartbeat_status) = match heartbeat_age_secs {
+            Some(age) => (
+                component_score(age as f64, self.config.healthy_heartbeat_interval_secs as f64, self.config.warning_heartbeat_interval_secs as f64),
+                if age <= self.config.healthy_heartbeat_interval_secs {
+                    RunnerHealthStatus::Healthy
+                } else if age <= self.config.warning_heartbeat_interval_secs {
+                    RunnerHealthStatus::Warning
+                } else {
+                    RunnerHealthStatus::Critical
+                },
+            ),
+            None => (0.0, RunnerHealthStatus::Critical),
+        };
+        
+        // Failure rate and processing time count as healthy until there is data
+        let (failure_score, failure_status) = match failure_rate {
+            Some(rate) => (
+                component_score(rate, 0.0, self.config.critical_failure_rate),
+                component_status(rate, self.config.warning_failure_rate, self.config.critical_failure_rate),
+            ),
+            None => (100.0, RunnerHealthStatus::Healthy),
+        };
+        let (latency_score, latency_status) = match stats.avg_processing_seconds {
+            Some(secs) => (
+                component_score(secs, self.config.warning_processing_secs, self.config.critical_processing_secs),
+                component_status(secs, self.config.warning_processing_secs, self.config.critical_processing_secs),
+            ),
+            None => (100.0, RunnerHealthStatus::Healthy),
+        };
+        
+        let score = (0.4 * heartbeat_score + 0.4 * failure_score + 0.2 * latency_score).round() as i32;
+        
+        // Inactive runners and runners in maintenance are not expected to be healthy
+        let health_status = if runner.status != RunnerStatus::Active {
+            RunnerHealthStatus::Unknown
+        } else {
+            [heartbeat_status, failure_status, latency_status]
+                .into_iter()
+                .max_by_key(|status| status.severity())
+                .unwrap_or(RunnerHealthStatus::Unknown)
         };
         
-        // Calculate the duration since the last heartbeat
-        let now = Utc::now().naive_utc();
-        let duration = now.signed_duration_since(last_heartbeat);
+        Ok(RunnerHealthReport {
+            runner_id: runner.id,
+            status: health_status.as_str().to_string(),
+            score,
+            heartbeat_age_secs,
+            failure_rate,
+            avg_processing_secs: stats.avg_processing_seconds,
+            jobs_sampled: stats.completed(),
+            health_status,
+        })
+    }
+    
+    /// Evaluate a runner, store the result in its health history and alert if its health changed
+    pub async fn record_runner_health(&self, runner_id: Uuid) -> Result<RunnerHealthReport> {
+        let runner = self.runner_repo.find_by_id(runner_id)
+            .await
+            .context("Failed to find runner for health check")?;
+        
+        let report = self.evaluate(&runner).await?;
+        let previous = self.runner_repo.latest_health_check(runner_id).await?;
+        
+        // A runner coming back from critical has its in-flight jobs reconciled
+        let was_critical = previous.as_ref().is_some_and(|check| check.status == RunnerHealthStatus::Critical.as_str());
+        if was_critical && matches!(report.health_status, RunnerHealthStatus::Healthy | RunnerHealthStatus::Warning) {
+            if let Some(critical_since) = self.runner_repo.health_status_since(runner_id, RunnerHealthStatus::Critical.as_str()).await? {
+                info!("Runner {} recovered after being critical since {}", runner_id, critical_since);
+                if let Ok(mut recovered) = self.recovered.lock() {
+                    recovered.push(RecoveredRunner { runner_id, critical_since });
+                }
+            }
+        }
+        
+        self.runner_repo.record_health_check(NewRunnerHealthCheck {
+            id: Uuid::new_v4(),
+            runner_id,
+            status: report.status.clone(),
+            score: report.score,
+            heartbeat_age_seconds: report.heartbeat_age_secs,
+            failure_rate: report.failure_rate,
+            avg_processing_seconds: report.avg_processing_secs,
+            jobs_sampled: report.jobs_sampled as i32,
+        }).await?;
+        
+        if let Some(event) = Self::health_event(&runner, previous.as_ref(), &report) {
+            warn!("Runner {} health changed from {} to {} (score {})", runner_id, event.previous_status, report.status, report.score);
+            if let Err(e) = self.send_alert(&event).await {
+                error!("Failed to send health alert for runner {}: {}", runner_id, e);
+            }
+        }
+        
+        Ok(report)
+    }
+    
+    /// Health event for a change from the previous check, if the runner degraded or recovered
+    fn health_event(runner: &Runner, previous: Option<&RunnerHealthCheck>, report: &RunnerHealthReport) -> Option<RunnerHealthEvent> {
+        let previous_status = previous
+            .and_then(|check| RunnerHealthStatus::parse(&check.status))
+            .unwrap_or(RunnerHealthStatus::Healthy);
+        let (Some(before), Some(now)) = (previous_status.severity(), report.health_status.severity()) else {
+            return None;
+        };
         
-        // Check against thresholds
-        if duration.num_seconds() <= self.config.healthy_heartbeat_interval_secs {
-            Ok(RunnerHealthStatus::Healthy)
-        } else if duration.num_seconds() <= self.config.warning_heartbeat_interval_secs {
-            Ok(RunnerHealthStatus::Warning)
+        let event = if now > before {
+            "runner.degraded"
+        } else if now < before && report.health_status == RunnerHealthStatus::Healthy {
+            "runner.recovered"
         } else {
-            Ok(RunnerHealthStatus::Critical)
+            return None;
+        };
+        
+        Some(RunnerHealthEvent {
+            event,
+            runner_id: runner.id,
+            runner_name: runner.name.clone(),
+            previous_status: previous_status.as_str().to_string(),
+            report: report.clone(),
+            occurred_at: Utc::now(),
+        })
+    }
+    
+    /// POST a health event to the configured alert webhook, if any
+    async fn send_alert(&self, event: &RunnerHealthEvent) -> Result<()> {
+        let Some(url) = self.config.alert_webhook_url.as_deref() else {
+            return Ok(());
+        };
+        
+        let response = self.client.post(url)
+            .timeout(std::time::Duration::from_secs(10))
+            .json(event)
+            .send()
+            .await
+            .map_err(|e| anyhow!("Failed to send runner health alert: {}", e))?;
+        
+        if !response.status().is_success() {
+            return Err(anyhow!("Runner health alert webhook responded with status {}", response.status()));
         }
+        Ok(())
+    }
+    
+    /// Evaluate and record the health of all active runners
+    pub async fn monitor_runners(&self) -> Result<usize> {
+        let runners = self.runner_repo.list_all()
+            .await
+            .context("Failed to list runners")?;
+        
+        let mut checked = 0;
+        for runner in runners.into_iter().filter(|runner| runner.status == RunnerStatus::Active) {
+            match self.record_runner_health(runner.id).await {
+                Ok(_) => checked += 1,
+                Err(e) => error!("Failed to check health of runner {}: {}", runner.id, e),
+            }
+        }
+        
+        Ok(checked)
+    }
+    
+    /// Evaluate the health of all active runners without recording it
+    pub async fn evaluate_active_runners(&self) -> Result<Vec<RunnerHealthReport>> {
+        let runners = self.runner_repo.list_all()
+            .await
+            .context("Failed to list runners")?;
+        
+        let mut reports = Vec::new();
+        for runner in runners.into_iter().filter(|runner| runner.status == RunnerStatus::Active) {
+            reports.push(self.evaluate(&runner).await?);
+        }
+        
+        Ok(reports)
+    }
+    
+    /// Render runner health reports in the Prometheus text exposition format
+    ///
+    /// Failure rates are only exported once a runner completed enough jobs for them to count.
+    pub fn render_metrics(reports: &[RunnerHealthReport]) -> String {
+        let mut out = String::new();
+        out.push_str("# HELP innosystem_runner_health_score Weighted runner health score from 0 to 100\n");
+        out.push_str("# TYPE innosystem_runner_health_score gauge\n");
+        for report in reports {
+            out.push_str(&format!("innosystem_runner_health_score{{runner_id=\"{}\"}} {}\n", report.runner_id, report.score));
+        }
+        out.push_str("# HELP innosystem_runner_heartbeat_age_seconds Seconds since the runner's last heartbeat\n");
+        out.push_str("# TYPE innosystem_runner_heartbeat_age_seconds gauge\n");
+        for report in reports {
+            if let Some(age) = report.heartbeat_age_secs {
+                out.push_str(&format!("innosystem_runner_heartbeat_age_seconds{{runner_id=\"{}\"}} {}\n", report.runner_id, age));
+            }
+        }
+        out.push_str("# HELP innosystem_runner_failure_rate Share of failed jobs in the sample window\n");
+        out.push_str("# TYPE innosystem_runner_failure_rate gauge\n");
+        for report in reports {
+            if let Some(rate) = report.failure_rate {
+                out.push_str(&format!("innosystem_runner_failure_rate{{runner_id=\"{}\"}} {}\n", report.runner_id, rate));
+            }
+        }
+        out.push_str("# HELP innosystem_runner_processing_seconds_avg Average processing time of the runner's jobs\n");
+        out.push_str("# TYPE innosystem_runner_processing_seconds_avg gauge\n");
+        for report in reports {
+            if let Some(secs) = report.avg_processing_secs {
+                out.push_str(&format!("innosystem_runner_processing_seconds_avg{{runner_id=\"{}\"}} {}\n", report.runner_id, secs));
+            }
+        }
+        out
     }
     
     /// Check runner compatibility with a job type
@@ -113,15 +505,12 @@ impl RunnerHealthService {
             .await
             .context("Failed to find runner for compatibility check")?;
         
-        // Get the job type
-        let job_type = self.job_type_repo.find_by_id(job_type_id)
+        // Check if the job type is among the runner's compatible job types
+        let job_type_ids = self.runner_repo.find_compatible_job_type_ids(runner.id)
             .await
-            .context("Failed to find job type for compatibility check")?;
+            .context("Failed to load the runner's compatible job types")?;
         
-        // Check if the job type name is in the runner's compatible job types
-        let is_compatible = runner.compatible_job_types.contains(&job_type.name);
-        
-        Ok(is_compatible)
+        Ok(job_type_ids.contains(&job_type_id))
     }
     
     /// Find compatible runners for a job type, sorted by health status
@@ -131,20 +520,16 @@ impl RunnerHealthService {
             .await
             .context("Failed to find job type")?;
         
-        // Get all active runners
-        let since = (Utc::now() - Duration::minutes(5)).naive_utc();
-        let runners = self.runner_repo.list_active(since)
+        // Get the active runners that are compatible with the job type
+        let runners = self.runner_repo.find_compatible_with_job_type(&job_type)
             .await
-            .context("Failed to list active runners")?;
+            .context("Failed to list compatible runners")?;
         
-        // Filter runners that are compatible with the job type
         let mut compatible_runners = Vec::new();
         for runner in runners {
-            if runner.compatible_job_types.contains(&job_type.name) {
-                // Check the health status
-                let health_status = self.check_runner_health(runner.id).await?;
-                compatible_runners.push((runner.id, health_status));
-            }
+            // Check the health status
+            let health_status = self.check_runner_health(runner.id).await?;
+            compatible_runners.push((runner.id, health_status));
         }
         
         // Sort by health status (Healthy > Warning > Critical > Unknown)
@@ -169,6 +554,185 @@ impl RunnerHealthService {
         Ok(compatible_runners)
     }
     
+    /// Apply the runner lifecycle policy
+    ///
+    /// Active runners whose health checks have been critical for longer than
+    /// `deactivate_after_critical_hours` are marked inactive; inactive runners without a
+    /// status change or heartbeat for `delete_inactive_after_days` are deleted, so
+    /// instances removed by an autoscaler do not pile up.
+    pub async fn apply_lifecycle_policy(&self) -> Result<RunnerLifecycleSummary> {
+        let mut summary = RunnerLifecycleSummary::default();
+        let now = Utc::now();
+        
+        if self.config.deactivate_after_critical_hours > 0 {
+            let cutoff = now - Duration::hours(self.config.deactivate_after_critical_hours);
+            let runners = self.runner_repo.list_all()
+                .await
+                .context("Failed to list runners")?;
+            
+            for runner in runners.into_iter().filter(|runner| runner.status == RunnerStatus::Active) {
+                let critical_since = self.runner_repo.health_status_since(runner.id, RunnerHealthStatus::Critical.as_str()).await?;
+                if critical_since.is_some_and(|since| since <= cutoff) {
+                    info!("Setting runner {} to inactive after being critical for over {} hours", runner.id, self.config.deactivate_after_critical_hours);
+                    self.runner_repo.set_status(runner.id, false).await?;
+                    summary.deactivated.push(runner.id);
+                }
+            }
+        }
+        
+        if self.config.delete_inactive_after_days > 0 {
+            let cutoff = now - Duration::days(self.config.delete_inactive_after_days);
+            summary.deleted = self.runner_repo.delete_inactive_before(cutoff)
+                .await
+                .context("Failed to delete inactive runners")?;
+            for runner_id in &summary.deleted {
+                info!("Deleted runner {} after being inactive for over {} days", runner_id, self.config.delete_inactive_after_days);
+            }
+        }
+        
+        Ok(summary)
+    }
+    
+    /// Take the runners that recovered from critical since the last call, for their jobs
+    /// to be reconciled in every schema
+    pub fn take_recovered_runners(&self) -> Vec<RecoveredRunner> {
+        self.recovered.lock().map(|mut recovered| std::mem::take(&mut *recovered)).unwrap_or_default()
+    }
+    
+    /// Reconcile the running jobs of critical and recovered runners in the current schema
+    ///
+    /// Active runners that stopped heartbeating and stayed critical for longer than
+    /// `reassign_after_critical_secs` have their running jobs handed back to the queue, so
+    /// other runners can run them. For each
+    /// recovered runner, the jobs it still holds are kept and those handed back while it
+    /// was critical are noted as reassigned; its late results for them are resolved when
+    /// it reports them. Every decision is recorded in the job's log.
+    pub async fn reconcile_runner_jobs(&self, recovered: &[RecoveredRunner]) -> Result<RunnerJobReconciliation> {
+        let mut reconciliation = RunnerJobReconciliation::default();
+        
+        if self.config.reassign_after_critical_secs > 0 {
+            let now = Utc::now();
+            let cutoff = now - Duration::seconds(self.config.reassign_after_critical_secs);
+            let silent_since = now - Duration::seconds(self.config.warning_heartbeat_interval_secs);
+            let runners = self.runner_repo.list_all()
+                .await
+                .context("Failed to list runners")?;
+            
+            // Runners critical for failing or slow jobs still run theirs
+            let silent = runners.into_iter()
+                .filter(|runner| runner.status == RunnerStatus::Active)
+                .filter(|runner| runner.last_heartbeat.is_none_or(|heartbeat| heartbeat <= silent_since));
+            for runner in silent {
+                let critical_since = self.runner_repo.health_status_since(runner.id, RunnerHealthStatus::Critical.as_str()).await?;
+                let Some(critical_since) = critical_since.filter(|since| *since <= cutoff) else {
+                    continue;
+                };
+                reconciliation.handed_back.extend(self.hand_back_jobs(runner.id, critical_since).await?);
+            }
+        }
+        
+        for runner in recovered {
+            let held = self.job_repo.find_running_by_runner(runner.runner_id)
+                .await
+                .map_err(|e| anyhow!("Failed to find the jobs of runner {}: {}", runner.runner_id, e))?;
+            for job in held {
+                self.record_job_event(job.id, LogLevel::Info, format!(
+                    "Runner {} recovered before the job was handed back and keeps running it",
+                    runner.runner_id
+                ), json!({
+                    "event": "runner_recovered",
+                    "runner_id": runner.runner_id,
+                    "critical_since": runner.critical_since,
+                    "kept": true,
+                })).await;
+                reconciliation.kept.push(job.id);
+            }
+            
+            let abandoned = self.job_attempt_repo.find_abandoned_by_runner(runner.runner_id, runner.critical_since)
+                .await
+                .context("Failed to find the abandoned attempts of the recovered runner")?;
+            for attempt in abandoned {
+                let job = self.job_repo.find_by_id(attempt.job_id)
+                    .await
+                    .map_err(|e| anyhow!("Failed to find job {}: {}", attempt.job_id, e))?;
+                let outcome = if job.status == JobStatus::Pending {
+                    "its late result will be kept as nobody started the job again yet"
+                } else {
+                    "its late result will be discarded"
+                };
+                self.record_job_event(job.id, LogLevel::Warn, format!(
+                    "Runner {} recovered after the job was handed back and is {}; {}",
+                    runner.runner_id, job.status.as_str(), outcome
+                ), json!({
+                    "event": "runner_recovered",
+                    "runner_id": runner.runner_id,
+                    "critical_since": runner.critical_since,
+                    "kept": false,
+                    "attempt_id": attempt.id,
+                    "job_status": job.status.as_str(),
+                })).await;
+                reconciliation.reassigned.push(job.id);
+            }
+        }
+        
+        Ok(reconciliation)
+    }
+    
+    /// Hand the running jobs of a critical runner back to the queue, returning their IDs;
+    /// jobs whose cancellation was requested are cancelled instead
+    async fn hand_back_jobs(&self, runner_id: Uuid, critical_since: DateTime<Utc>) -> Result<Vec<Uuid>> {
+        let jobs = self.job_repo.find_running_by_runner(runner_id)
+            .await
+            .map_err(|e| anyhow!("Failed to find the jobs of runner {}: {}", runner_id, e))?;
+        
+        let mut handed_back = Vec::new();
+        for job in jobs {
+            // The runner may have finished the job in the meantime
+            let Some(job) = self.job_repo.hand_back(job.id, runner_id)
+                .await
+                .map_err(|e| anyhow!("Failed to hand back job {}: {}", job.id, e))? else {
+                continue;
+            };
+            
+            // Its cancellation was requested, so the job was cancelled rather than handed back
+            if job.status == JobStatus::Cancelled {
+                info!("Cancelled job {} of runner {}, critical since {}, as requested", job.id, runner_id, critical_since);
+                self.record_job_event(job.id, LogLevel::Warn, format!(
+                    "Cancelled as requested after runner {} was critical for over {} s",
+                    runner_id, self.config.reassign_after_critical_secs
+                ), json!({
+                    "event": "cancelled",
+                    "runner_id": runner_id,
+                    "critical_since": critical_since,
+                })).await;
+                continue;
+            }
+            
+            info!("Handed job {} back to the queue after runner {} was critical since {}", job.id, runner_id, critical_since);
+            if let Err(e) = self.job_queue.push_job(job.id, job.priority.clone()).await {
+                error!("Failed to requeue job {} handed back from runner {}, it must be requeued manually: {}", job.id, runner_id, e);
+            }
+            self.record_job_event(job.id, LogLevel::Warn, format!(
+                "Handed back to the queue after runner {} was critical for over {} s",
+                runner_id, self.config.reassign_after_critical_secs
+            ), json!({
+                "event": "handed_back",
+                "runner_id": runner_id,
+                "critical_since": critical_since,
+            })).await;
+            handed_back.push(job.id);
+        }
+        
+        Ok(handed_back)
+    }
+    
+    /// Note a reconciliation decision in the job's log; losing it must not stop the reconciliation
+    async fn record_job_event(&self, job_id: Uuid, level: LogLevel, message: String, fields: serde_json::Value) {
+        if let Err(e) = self.job_log_repo.append_event(job_id, level, message, Some(fields)).await {
+            warn!("Failed to record a reconciliation event of job {}: {}", job_id, e);
+        }
+    }
+    
     /// Update runner status based on health status
     pub async fn update_status_based_on_health(&self, runner_id: Uuid) -> Result<()> {
         // Check the health status
@@ -211,6 +775,10 @@ impl RunnerHealthService {
             match self.job_repo.update_status(job.id, JobStatus::Pending).await {
                 Ok(_) => {
                     info!("Reset stalled job {} to pending status for reassignment", job.id);
+                    // The interrupted run stays in the job's history as abandoned
+                    if let Err(e) = self.job_attempt_repo.abandon_running(job.id).await {
+                        warn!("Failed to mark the running attempt of job {} as abandoned: {}", job.id, e);
+                    }
                     reassigned_count += 1;
                 },
                 Err(e) => {
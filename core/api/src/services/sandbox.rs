@@ -0,0 +1,160 @@
+use std::sync::Arc;
+use chrono::{DateTime, Duration, Utc};
+use thiserror::Error;
+use tracing::{error, info};
+use uuid::Uuid;
+
+use innosystem_common::config::parse_env;
+use innosystem_common::database::{with_reseller, SchemaResolver, TenancyMode};
+use innosystem_common::models::reseller::{NewReseller, Reseller};
+use innosystem_common::repositories::ResellerRepository;
+
+/// Configuration of the sandbox tenants resellers spin up
+#[derive(Debug, Clone)]
+pub struct SandboxConfig {
+    /// Days a sandbox is kept when the reseller asks for no other lifetime
+    pub default_ttl_days: u32,
+    /// Most days a reseller may ask to keep a sandbox
+    pub max_ttl_days: u32,
+    /// Sandboxes a reseller may have at the same time
+    pub max_per_reseller: usize,
+    /// Interval between two sweeps for expired sandboxes
+    pub sweep_interval_secs: u64,
+}
+
+impl Default for SandboxConfig {
+    fn default() -> Self {
+        Self {
+            default_ttl_days: 14,
+            max_ttl_days: 90,
+            max_per_reseller: 3,
+            sweep_interval_secs: 3600,  // 1 hour
+        }
+    }
+}
+
+impl SandboxConfig {
+    /// Load the configuration from `SANDBOX_*` environment variables, using defaults for unset values
+    pub fn from_env() -> Self {
+        let defaults = Self::default();
+        Self {
+            default_ttl_days: parse_env("SANDBOX_DEFAULT_TTL_DAYS").unwrap_or(defaults.default_ttl_days),
+            max_ttl_days: parse_env("SANDBOX_MAX_TTL_DAYS").unwrap_or(defaults.max_ttl_days),
+            max_per_reseller: parse_env("SANDBOX_MAX_PER_RESELLER").unwrap_or(defaults.max_per_reseller),
+            sweep_interval_secs: parse_env("SANDBOX_SWEEP_INTERVAL_SECS").unwrap_or(defaults.sweep_interval_secs),
+        }
+    }
+}
+
+/// Reasons a sandbox is not created
+#[derive(Debug, Error)]
+pub enum SandboxError {
+    #[error("Sandboxes cannot spin up sandboxes of their own")]
+    NestedSandbox,
+    #[error("A sandbox is kept for 1 to {max} days, got {days}")]
+    InvalidTtl { days: u32, max: u32 },
+    #[error("Reseller {0} already has {1} sandboxes")]
+    LimitReached(Uuid, usize),
+    #[error("Failed to provision the sandbox's schema: {0}")]
+    Provisioning(#[from] innosystem_common::Error),
+    #[error(transparent)]
+    Repository(#[from] anyhow::Error),
+}
+
+/// Service managing the sandbox tenants of resellers
+///
+/// A sandbox is a reseller of its own, so its customers are a namespace apart from
+/// the reseller's real ones, and it gets its own schema when resellers are isolated.
+/// Its customers only run test jobs: the stub processors charge their play-money
+/// balance and nothing is exported to billing. Sandboxes are deleted with all of
+/// their data once they expire.
+pub struct SandboxService {
+    reseller_repo: Arc<dyn ResellerRepository>,
+    schema_resolver: Arc<SchemaResolver>,
+    config: SandboxConfig,
+}
+
+impl SandboxService {
+    /// Create a new SandboxService
+    pub fn new(
+        reseller_repo: Arc<dyn ResellerRepository>,
+        schema_resolver: Arc<SchemaResolver>,
+        config: Option<SandboxConfig>,
+    ) -> Self {
+        Self {
+            reseller_repo,
+            schema_resolver,
+            config: config.unwrap_or_default(),
+        }
+    }
+
+    /// Interval between two sweeps for expired sandboxes
+    pub fn sweep_interval_secs(&self) -> u64 {
+        self.config.sweep_interval_secs
+    }
+
+    /// Spin up a sandbox for a reseller, kept for the given number of days
+    pub async fn create(&self, parent: &Reseller, name: Option<String>, ttl_days: Option<u32>) -> Result<Reseller, SandboxError> {
+        if parent.is_sandbox() {
+            return Err(SandboxError::NestedSandbox);
+        }
+        let days = ttl_days.unwrap_or(self.config.default_ttl_days);
+        if days == 0 || days > self.config.max_ttl_days {
+            return Err(SandboxError::InvalidTtl { days, max: self.config.max_ttl_days });
+        }
+        let sandboxes = self.reseller_repo.list_sandboxes(parent.id).await?;
+        if sandboxes.len() >= self.config.max_per_reseller {
+            return Err(SandboxError::LimitReached(parent.id, sandboxes.len()));
+        }
+
+        let expires_at = Utc::now() + Duration::days(days as i64);
+        let name = name.unwrap_or_else(|| format!("{} sandbox", parent.name));
+        let sandbox = self.reseller_repo.create(NewReseller::sandbox(parent, name, expires_at)).await?;
+
+        if self.schema_resolver.mode() == TenancyMode::SchemaPerReseller {
+            let resolver = self.schema_resolver.clone();
+            let sandbox_id = sandbox.id;
+            let provisioned = tokio::task::spawn_blocking(move || resolver.provision(sandbox_id)).await
+                .map_err(|e| innosystem_common::Error::Other(e.into()))
+                .and_then(|result| result);
+            if let Err(e) = provisioned {
+                // Without its schema the sandbox's data would land in the shared one
+                if let Err(cleanup) = self.reseller_repo.delete_sandbox(sandbox.id).await {
+                    error!("Failed to remove sandbox {} after its schema could not be provisioned: {}", sandbox.id, cleanup);
+                }
+                return Err(e.into());
+            }
+        }
+
+        info!("Reseller {} spun up sandbox {} until {}", parent.id, sandbox.id, expires_at);
+        Ok(sandbox)
+    }
+
+    /// The sandboxes of a reseller, newest first
+    pub async fn list(&self, parent_id: Uuid) -> anyhow::Result<Vec<Reseller>> {
+        self.reseller_repo.list_sandboxes(parent_id).await
+    }
+
+    /// Delete the sandboxes that expired by the given time with all of their data,
+    /// returning the IDs of those deleted
+    pub async fn sweep(&self, now: DateTime<Utc>) -> anyhow::Result<Vec<Uuid>> {
+        let mut deleted = Vec::new();
+        for sandbox in self.reseller_repo.find_expired_sandboxes(now).await? {
+            // Its customers' data is in its own schema if it has one
+            if let Err(e) = with_reseller(Some(sandbox.id), self.reseller_repo.delete_sandbox(sandbox.id)).await {
+                error!("Failed to delete expired sandbox {}: {}", sandbox.id, e);
+                continue;
+            }
+            let resolver = self.schema_resolver.clone();
+            let sandbox_id = sandbox.id;
+            match tokio::task::spawn_blocking(move || resolver.drop_schema(sandbox_id)).await {
+                Ok(Ok(())) => {}
+                Ok(Err(e)) => error!("Failed to drop the schema of expired sandbox {}: {}", sandbox.id, e),
+                Err(e) => error!("Failed to drop the schema of expired sandbox {}: {}", sandbox.id, e),
+            }
+            info!("Deleted sandbox {} of reseller {:?}, expired at {:?}", sandbox.id, sandbox.sandbox_of, sandbox.sandbox_expires_at);
+            deleted.push(sandbox.id);
+        }
+        Ok(deleted)
+    }
+}
@@ -0,0 +1,327 @@
+use std::env;
+use std::time::Duration;
+use anyhow::{Result, anyhow};
+use bb8_redis::{
+    bb8::Pool,
+    redis::{self, AsyncCommands},
+    RedisConnectionManager,
+};
+use tracing::{error, warn};
+
+use innosystem_common::config::parse_env;
+use innosystem_common::models::security_event::{lockout_secs, AuthSubject, SecurityEvent, SecurityEventKind};
+use innosystem_common::models::webhook::WebhookEventType;
+
+use crate::services::WebhookService;
+
+/// Time to wait for a Redis connection before letting the request through unchecked
+const CONNECTION_TIMEOUT_MS: u64 = 250;
+
+/// Configuration for the detection of brute-force patterns in failed authentications
+#[derive(Debug, Clone)]
+pub struct SecurityEventConfig {
+    /// Failed authentications of a source IP or key prefix within the window that lock it out
+    pub max_failures: u64,
+    /// Seconds over which failed authentications are counted
+    pub window_secs: u64,
+    /// Duration of a first lockout; each further lockout doubles it
+    pub lockout_base_secs: u64,
+    /// Longest lockout
+    pub lockout_max_secs: u64,
+    /// Seconds after a lockout during which the next one of the same subject is longer
+    pub lockout_memory_secs: u64,
+    /// Characters of a presented API key failures are counted against
+    pub key_prefix_length: usize,
+    /// Distinct key prefixes one source IP fails with within the window that raise a
+    /// key enumeration alert
+    pub key_enumeration_threshold: u64,
+    /// Distinct source IPs failing with one key prefix within the window that raise a
+    /// distributed attack alert
+    pub distributed_attack_threshold: u64,
+    /// Security events kept for `GET /admin/security/events`
+    pub max_events: usize,
+    /// Take the source IP from the first `X-Forwarded-For` entry, behind a trusted proxy
+    pub trust_forwarded_for: bool,
+    /// Prefix of all security keys in Redis
+    pub key_prefix: String,
+    /// Endpoint receiving every `security.alert` event for the operators
+    pub alert_webhook_url: Option<String>,
+}
+
+impl Default for SecurityEventConfig {
+    fn default() -> Self {
+        Self {
+            max_failures: 10,
+            window_secs: 300,  // 5 minutes
+            lockout_base_secs: 60,
+            lockout_max_secs: 86400,
+            lockout_memory_secs: 86400,
+            key_prefix_length: 12,
+            key_enumeration_threshold: 20,
+            distributed_attack_threshold: 10,
+            max_events: 1000,
+            trust_forwarded_for: false,
+            key_prefix: "innosystem:security".to_string(),
+            alert_webhook_url: None,
+        }
+    }
+}
+
+impl SecurityEventConfig {
+    /// Load the configuration from `SECURITY_*` environment variables, using defaults for unset values
+    pub fn from_env() -> Self {
+        let defaults = Self::default();
+        Self {
+            max_failures: parse_env("SECURITY_MAX_AUTH_FAILURES").filter(|max| *max > 0).unwrap_or(defaults.max_failures),
+            window_secs: parse_env("SECURITY_FAILURE_WINDOW_SECS").filter(|secs| *secs > 0).unwrap_or(defaults.window_secs),
+            lockout_base_secs: parse_env("SECURITY_LOCKOUT_BASE_SECS").filter(|secs| *secs > 0).unwrap_or(defaults.lockout_base_secs),
+            lockout_max_secs: parse_env("SECURITY_LOCKOUT_MAX_SECS").filter(|secs| *secs > 0).unwrap_or(defaults.lockout_max_secs),
+            lockout_memory_secs: parse_env("SECURITY_LOCKOUT_MEMORY_SECS").filter(|secs| *secs > 0).unwrap_or(defaults.lockout_memory_secs),
+            key_prefix_length: parse_env("SECURITY_KEY_PREFIX_LENGTH").filter(|length| *length > 0).unwrap_or(defaults.key_prefix_length),
+            key_enumeration_threshold: parse_env("SECURITY_KEY_ENUMERATION_THRESHOLD").unwrap_or(defaults.key_enumeration_threshold),
+            distributed_attack_threshold: parse_env("SECURITY_DISTRIBUTED_ATTACK_THRESHOLD").unwrap_or(defaults.distributed_attack_threshold),
+            max_events: parse_env("SECURITY_MAX_EVENTS").filter(|max| *max > 0).unwrap_or(defaults.max_events),
+            trust_forwarded_for: parse_env("SECURITY_TRUST_FORWARDED_FOR").unwrap_or(defaults.trust_forwarded_for),
+            key_prefix: env::var("SECURITY_KEY_PREFIX").ok()
+                .filter(|prefix| !prefix.is_empty())
+                .unwrap_or(defaults.key_prefix),
+            alert_webhook_url: env::var("SECURITY_ALERT_WEBHOOK_URL").ok().filter(|url| !url.is_empty()),
+        }
+    }
+}
+
+/// Subjects a request's failed authentication is counted against
+fn subjects(source_ip: Option<&str>, key_prefix: Option<&str>) -> Vec<AuthSubject> {
+    source_ip.map(|ip| AuthSubject::SourceIp(ip.to_string())).into_iter()
+        .chain(key_prefix.map(|prefix| AuthSubject::KeyPrefix(prefix.to_string())))
+        .collect()
+}
+
+/// Service counting failed authentications per source IP and API key prefix in Redis
+///
+/// A subject failing too often within the window is locked out, for twice as long
+/// with each lockout in a row, so a flood of invalid keys is turned away before
+/// reaching the database. Lockouts and brute-force patterns spanning several keys
+/// or IPs are kept as security events for the admins and sent to the operators'
+/// alert endpoint. Counting is best effort: while Redis is unavailable requests are
+/// let through and failures go uncounted.
+pub struct SecurityEventService {
+    pool: Pool<RedisConnectionManager>,
+    config: SecurityEventConfig,
+    client: reqwest::Client,
+}
+
+impl SecurityEventService {
+    /// Create a new SecurityEventService; connections are established on first use
+    pub fn new(redis_url: &str, config: SecurityEventConfig) -> Result<Self> {
+        let manager = RedisConnectionManager::new(redis_url)
+            .map_err(|e| anyhow!("Failed to create Redis manager: {}", e))?;
+
+        let pool = Pool::builder()
+            .connection_timeout(Duration::from_millis(CONNECTION_TIMEOUT_MS))
+            .build_unchecked(manager);
+
+        Ok(Self { pool, config, client: reqwest::Client::new() })
+    }
+
+    pub fn config(&self) -> &SecurityEventConfig {
+        &self.config
+    }
+
+    fn key(&self, kind: &str, subject: &AuthSubject) -> String {
+        format!("{}:{}:{}", self.config.key_prefix, kind, subject.key())
+    }
+
+    fn events_key(&self) -> String {
+        format!("{}:events", self.config.key_prefix)
+    }
+
+    /// Seconds until the longest running lockout of the request's source IP and key
+    /// prefix ends, if either is locked out
+    pub async fn locked_out(&self, source_ip: Option<&str>, key_prefix: Option<&str>) -> Option<u64> {
+        let subjects = subjects(source_ip, key_prefix);
+        if subjects.is_empty() {
+            return None;
+        }
+
+        let mut conn = match self.pool.get().await {
+            Ok(conn) => conn,
+            Err(e) => {
+                warn!("Cannot check authentication lockouts, Redis is unavailable: {}", e);
+                return None;
+            }
+        };
+
+        let mut pipe = redis::pipe();
+        for subject in &subjects {
+            pipe.ttl(self.key("lockout", subject));
+        }
+        let ttls: Vec<i64> = match pipe.query_async(&mut *conn).await {
+            Ok(ttls) => ttls,
+            Err(e) => {
+                warn!("Failed to check authentication lockouts: {}", e);
+                return None;
+            }
+        };
+
+        ttls.into_iter().filter(|ttl| *ttl > 0).max().map(|ttl| ttl as u64)
+    }
+
+    /// Count a failed authentication against its subjects, locking out those that
+    /// reached the limit and recording the brute-force patterns it completes
+    pub async fn record_failure(&self, source_ip: Option<&str>, key_prefix: Option<&str>) -> Vec<SecurityEvent> {
+        let mut events = Vec::new();
+        for subject in &subjects(source_ip, key_prefix) {
+            match self.count_failure(subject).await {
+                Ok(Some(event)) => events.push(event),
+                Ok(None) => {}
+                Err(e) => warn!("Failed to count a failed authentication of {}: {}", subject.key(), e),
+            }
+        }
+
+        // Patterns only show across both subjects of a failure
+        if let (Some(ip), Some(prefix)) = (source_ip, key_prefix) {
+            match self.detect_patterns(ip, prefix).await {
+                Ok(patterns) => events.extend(patterns),
+                Err(e) => warn!("Failed to check failed authentications for brute-force patterns: {}", e),
+            }
+        }
+
+        for event in &events {
+            self.raise(event).await;
+        }
+        events
+    }
+
+    /// Count a failure of one subject; returns the lockout it triggered, if any
+    async fn count_failure(&self, subject: &AuthSubject) -> Result<Option<SecurityEvent>> {
+        let mut conn = self.pool.get().await?;
+        let failures_key = self.key("failures", subject);
+
+        // The window starts with the first failure
+        let (failures,): (u64,) = redis::pipe()
+            .atomic()
+            .cmd("SET").arg(&failures_key).arg(0).arg("EX").arg(self.config.window_secs).arg("NX").ignore()
+            .incr(&failures_key, 1)
+            .query_async(&mut *conn)
+            .await?;
+
+        // Only the failure reaching the limit locks the subject out
+        if failures != self.config.max_failures {
+            return Ok(None);
+        }
+
+        let lockouts_key = self.key("lockouts", subject);
+        let (level,): (u32,) = redis::pipe()
+            .atomic()
+            .incr(&lockouts_key, 1)
+            .expire(&lockouts_key, self.config.lockout_memory_secs as i64).ignore()
+            .query_async(&mut *conn)
+            .await?;
+        let duration = lockout_secs(self.config.lockout_base_secs, self.config.lockout_max_secs, level);
+        let _: () = redis::pipe()
+            .atomic()
+            .set_ex(self.key("lockout", subject), level, duration).ignore()
+            .del(&failures_key).ignore()
+            .query_async(&mut *conn)
+            .await?;
+
+        let mut event = SecurityEvent::new(SecurityEventKind::Lockout, subject.clone(), failures);
+        event.lockout_secs = Some(duration);
+        Ok(Some(event))
+    }
+
+    /// Record the key prefix a source IP failed with and the source IP a key prefix
+    /// failed from; returns the patterns whose threshold this failure reached
+    async fn detect_patterns(&self, source_ip: &str, key_prefix: &str) -> Result<Vec<SecurityEvent>> {
+        let mut conn = self.pool.get().await?;
+        let ip = AuthSubject::SourceIp(source_ip.to_string());
+        let prefix = AuthSubject::KeyPrefix(key_prefix.to_string());
+        let prefixes_key = self.key("prefixes", &ip);
+        let ips_key = self.key("ips", &prefix);
+        let window = self.config.window_secs as i64;
+
+        let (new_prefix, prefixes, new_ip, ips): (u64, u64, u64, u64) = redis::pipe()
+            .atomic()
+            .sadd(&prefixes_key, key_prefix)
+            .expire(&prefixes_key, window).ignore()
+            .scard(&prefixes_key)
+            .sadd(&ips_key, source_ip)
+            .expire(&ips_key, window).ignore()
+            .scard(&ips_key)
+            .query_async(&mut *conn)
+            .await?;
+
+        // Each pattern is raised once, by the failure reaching its threshold
+        let mut events = Vec::new();
+        let threshold = self.config.key_enumeration_threshold;
+        if threshold > 0 && new_prefix == 1 && prefixes == threshold {
+            let mut event = SecurityEvent::new(SecurityEventKind::KeyEnumeration, ip, prefixes);
+            event.distinct = Some(prefixes);
+            events.push(event);
+        }
+        let threshold = self.config.distributed_attack_threshold;
+        if threshold > 0 && new_ip == 1 && ips == threshold {
+            let mut event = SecurityEvent::new(SecurityEventKind::DistributedAttack, prefix, ips);
+            event.distinct = Some(ips);
+            events.push(event);
+        }
+        Ok(events)
+    }
+
+    /// Keep an event for the admins and send it to the alert endpoint
+    async fn raise(&self, event: &SecurityEvent) {
+        warn!(
+            "Security event {}: {} against {} after {} failed authentications",
+            event.id, event.kind.as_str(), event.subject.key(), event.failures
+        );
+
+        let Ok(json) = serde_json::to_string(event) else {
+            return;
+        };
+        match self.pool.get().await {
+            Ok(mut conn) => {
+                let stored: redis::RedisResult<()> = redis::pipe()
+                    .atomic()
+                    .lpush(self.events_key(), json).ignore()
+                    .ltrim(self.events_key(), 0, self.config.max_events as isize - 1).ignore()
+                    .query_async(&mut *conn)
+                    .await;
+                if let Err(e) = stored {
+                    error!("Failed to store security event {}: {}", event.id, e);
+                }
+            }
+            Err(e) => error!("Failed to store security event {}, Redis is unavailable: {}", event.id, e),
+        }
+
+        // Alerts are sent in the background so the rejected request is not held up
+        if let Some(url) = self.config.alert_webhook_url.clone() {
+            let client = self.client.clone();
+            let body = WebhookService::build_event(WebhookEventType::SecurityAlert, serde_json::json!(event));
+            let event_id = event.id;
+            tokio::spawn(async move {
+                let sent = client.post(&url)
+                    .timeout(Duration::from_secs(10))
+                    .json(&body)
+                    .send()
+                    .await;
+                match sent {
+                    Ok(response) if response.status().is_success() => {}
+                    Ok(response) => error!("Security alert webhook responded with status {} to event {}", response.status(), event_id),
+                    Err(e) => error!("Failed to send security alert {}: {}", event_id, e),
+                }
+            });
+        }
+    }
+
+    /// Most recent security events, newest first
+    pub async fn recent_events(&self, limit: usize) -> Result<Vec<SecurityEvent>> {
+        let mut conn = self.pool.get().await
+            .map_err(|e| anyhow!("Redis is unavailable: {}", e))?;
+        let events: Vec<String> = conn.lrange(self.events_key(), 0, limit.max(1) as isize - 1).await?;
+
+        Ok(events.iter()
+            .filter_map(|json| serde_json::from_str(json).ok())
+            .collect())
+    }
+}
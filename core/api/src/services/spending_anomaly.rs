@@ -0,0 +1,246 @@
+use std::collections::HashMap;
+use std::env;
+use std::sync::Arc;
+use anyhow::{Result, Context, anyhow};
+use chrono::{DateTime, Duration, Utc};
+use serde_json::{json, Value};
+use tracing::{error, warn};
+use uuid::Uuid;
+
+use innosystem_common::config::parse_env;
+use innosystem_common::models::spending_alert::{AnomalyKind, NewSpendingAlert, SpendingAlert};
+use innosystem_common::models::webhook::WebhookEventType;
+use innosystem_common::repositories::{CustomerRepository, JobRepository, SpendingAlertRepository};
+use innosystem_common::repositories::job::CustomerActivity;
+
+use crate::services::WebhookService;
+
+/// Configuration for the detection of anomalies in customers' spending patterns
+#[derive(Debug, Clone)]
+pub struct SpendingAnomalyConfig {
+    /// Interval between two detection runs
+    pub check_interval_secs: u64,
+    /// Hours of recent activity compared to the baseline
+    pub window_hours: i64,
+    /// Days of activity before the window the baseline is taken from
+    pub baseline_days: i64,
+    /// Multiple of the baseline spend per window from which spend is a spike
+    pub spend_spike_factor: f64,
+    /// Minimum spend (in cents) within the window before it can be a spike
+    pub min_spend_cents: i64,
+    /// Increase of the failure rate over the baseline, e.g. 0.5 for 10% to 60%, that is a jump
+    pub failure_rate_jump: f64,
+    /// Minimum number of finished jobs within the window before the failure rate counts
+    pub min_jobs: i64,
+    /// Hours after an alert before the same anomaly of the same customer is alerted again
+    pub cooldown_hours: i64,
+    /// Endpoint receiving every `spending.anomaly` event for the operators, e.g. an alert manager
+    pub alert_webhook_url: Option<String>,
+}
+
+impl Default for SpendingAnomalyConfig {
+    fn default() -> Self {
+        Self {
+            check_interval_secs: 300,  // 5 minutes
+            window_hours: 1,
+            baseline_days: 7,
+            spend_spike_factor: 10.0,
+            min_spend_cents: 1000,
+            failure_rate_jump: 0.5,
+            min_jobs: 10,
+            cooldown_hours: 24,
+            alert_webhook_url: None,
+        }
+    }
+}
+
+impl SpendingAnomalyConfig {
+    /// Load the configuration from `SPENDING_ANOMALY_*` environment variables, using defaults for unset values
+    pub fn from_env() -> Self {
+        let defaults = Self::default();
+        Self {
+            check_interval_secs: parse_env("SPENDING_ANOMALY_CHECK_INTERVAL_SECS").unwrap_or(defaults.check_interval_secs),
+            window_hours: parse_env("SPENDING_ANOMALY_WINDOW_HOURS").filter(|hours| *hours > 0).unwrap_or(defaults.window_hours),
+            baseline_days: parse_env("SPENDING_ANOMALY_BASELINE_DAYS").filter(|days| *days > 0).unwrap_or(defaults.baseline_days),
+            spend_spike_factor: parse_env("SPENDING_ANOMALY_SPEND_SPIKE_FACTOR").unwrap_or(defaults.spend_spike_factor),
+            min_spend_cents: parse_env("SPENDING_ANOMALY_MIN_SPEND_CENTS").unwrap_or(defaults.min_spend_cents),
+            failure_rate_jump: parse_env("SPENDING_ANOMALY_FAILURE_RATE_JUMP").unwrap_or(defaults.failure_rate_jump),
+            min_jobs: parse_env("SPENDING_ANOMALY_MIN_JOBS").unwrap_or(defaults.min_jobs),
+            cooldown_hours: parse_env("SPENDING_ANOMALY_COOLDOWN_HOURS").unwrap_or(defaults.cooldown_hours),
+            alert_webhook_url: env::var("SPENDING_ANOMALY_ALERT_WEBHOOK_URL").ok().filter(|url| !url.is_empty()),
+        }
+    }
+}
+
+/// Service comparing each customer's recent spend and failure rate to their history
+///
+/// Catches runaway scripts before they drain a wallet: every anomaly is recorded as
+/// an alert the customer, their reseller and the admins can list, and is sent to the
+/// customer's webhooks and the operators' alert endpoint.
+pub struct SpendingAnomalyService {
+    job_repo: Arc<dyn JobRepository>,
+    customer_repo: Arc<dyn CustomerRepository>,
+    spending_alert_repo: Arc<dyn SpendingAlertRepository>,
+    webhook_service: Arc<WebhookService>,
+    config: SpendingAnomalyConfig,
+    client: reqwest::Client,
+}
+
+impl SpendingAnomalyService {
+    /// Create a new SpendingAnomalyService
+    pub fn new(
+        job_repo: Arc<dyn JobRepository>,
+        customer_repo: Arc<dyn CustomerRepository>,
+        spending_alert_repo: Arc<dyn SpendingAlertRepository>,
+        webhook_service: Arc<WebhookService>,
+        config: Option<SpendingAnomalyConfig>,
+    ) -> Self {
+        Self {
+            job_repo,
+            customer_repo,
+            spending_alert_repo,
+            webhook_service,
+            config: config.unwrap_or_default(),
+            client: reqwest::Client::new(),
+        }
+    }
+
+    /// Anomalies in a customer's activity within the window compared to their baseline
+    ///
+    /// Returns the kind, the observed value and the baseline value of each anomaly.
+    /// A customer without history has a baseline of no spend and no failures.
+    fn anomalies(&self, current: &CustomerActivity, baseline: Option<&CustomerActivity>) -> Vec<(AnomalyKind, f64, f64)> {
+        let mut anomalies = Vec::new();
+
+        // Spend the customer usually has within a window of the same length
+        let windows_in_baseline = (self.config.baseline_days * 24) as f64 / self.config.window_hours as f64;
+        let expected_spend = baseline.map_or(0.0, |baseline| baseline.cost_cents as f64 / windows_in_baseline);
+        let spend = current.cost_cents as f64;
+        if current.cost_cents >= self.config.min_spend_cents && spend >= self.config.spend_spike_factor * expected_spend {
+            anomalies.push((AnomalyKind::SpendSpike, spend, expected_spend));
+        }
+
+        if current.finished() >= self.config.min_jobs {
+            let failure_rate = current.failure_rate().unwrap_or(0.0);
+            let expected_rate = baseline.and_then(CustomerActivity::failure_rate).unwrap_or(0.0);
+            if failure_rate - expected_rate >= self.config.failure_rate_jump {
+                anomalies.push((AnomalyKind::FailureRateJump, failure_rate, expected_rate));
+            }
+        }
+
+        anomalies
+    }
+
+    /// Compare every active customer's recent activity to their baseline and raise
+    /// an alert for each new anomaly
+    ///
+    /// An anomaly already alerted within the cooldown is not alerted again; notification
+    /// failures are logged and do not undo the alert.
+    pub async fn detect(&self) -> Result<Vec<SpendingAlert>> {
+        let window_end = Utc::now();
+        let window_start = window_end - Duration::hours(self.config.window_hours);
+        let baseline_start = window_start - Duration::days(self.config.baseline_days);
+
+        let current = self.job_repo.get_customer_activity(window_start, window_end)
+            .await
+            .context("Failed to load recent customer activity")?;
+        let baseline: HashMap<Uuid, CustomerActivity> = self.job_repo.get_customer_activity(baseline_start, window_start)
+            .await
+            .context("Failed to load baseline customer activity")?
+            .into_iter()
+            .map(|activity| (activity.customer_id, activity))
+            .collect();
+
+        let mut alerts = Vec::new();
+        for activity in &current {
+            for (kind, observed, expected) in self.anomalies(activity, baseline.get(&activity.customer_id)) {
+                match self.raise(activity.customer_id, kind, observed, expected, window_start, window_end).await {
+                    Ok(Some(alert)) => alerts.push(alert),
+                    Ok(None) => {}
+                    Err(e) => error!("Failed to raise {} alert for customer {}: {}", kind.as_str(), activity.customer_id, e),
+                }
+            }
+        }
+
+        Ok(alerts)
+    }
+
+    /// Record and send an alert, unless one was raised within the cooldown
+    async fn raise(
+        &self,
+        customer_id: Uuid,
+        kind: AnomalyKind,
+        observed: f64,
+        baseline: f64,
+        window_start: DateTime<Utc>,
+        window_end: DateTime<Utc>,
+    ) -> Result<Option<SpendingAlert>> {
+        let cooldown_start = window_end - Duration::hours(self.config.cooldown_hours);
+        if self.spending_alert_repo.exists_since(customer_id, kind, cooldown_start).await? {
+            return Ok(None);
+        }
+
+        let customer = self.customer_repo.find_by_id(customer_id)
+            .await
+            .context("Failed to load customer")?;
+        let alert = self.spending_alert_repo.create(NewSpendingAlert {
+            id: Uuid::new_v4(),
+            customer_id,
+            reseller_id: customer.reseller_id,
+            kind: kind.as_str().to_string(),
+            observed,
+            baseline,
+            window_start,
+            window_end,
+        }).await?;
+
+        warn!(
+            "Customer {} shows a {} anomaly: {} observed against a baseline of {}",
+            customer_id, kind.as_str(), observed, baseline
+        );
+
+        let data = Self::event_data(&alert);
+        if let Err(e) = self.webhook_service.notify_customer(customer_id, WebhookEventType::SpendingAnomaly, data.clone()).await {
+            error!("Failed to notify customer {} of spending alert {}: {}", customer_id, alert.id, e);
+        }
+        if let Err(e) = self.send_alert(data).await {
+            error!("Failed to send spending alert {}: {}", alert.id, e);
+        }
+
+        Ok(Some(alert))
+    }
+
+    /// Payload of the `spending.anomaly` event of an alert
+    fn event_data(alert: &SpendingAlert) -> Value {
+        json!({
+            "alert_id": alert.id,
+            "customer_id": alert.customer_id,
+            "reseller_id": alert.reseller_id,
+            "kind": alert.kind,
+            "observed": alert.observed,
+            "baseline": alert.baseline,
+            "window_start": alert.window_start,
+            "window_end": alert.window_end,
+        })
+    }
+
+    /// Post a `spending.anomaly` event to the configured alert endpoint, if any
+    async fn send_alert(&self, data: Value) -> Result<()> {
+        let Some(url) = self.config.alert_webhook_url.as_deref() else {
+            return Ok(());
+        };
+
+        let event = WebhookService::build_event(WebhookEventType::SpendingAnomaly, data);
+        let response = self.client.post(url)
+            .timeout(std::time::Duration::from_secs(10))
+            .json(&event)
+            .send()
+            .await
+            .map_err(|e| anyhow!("Failed to send spending alert: {}", e))?;
+
+        if !response.status().is_success() {
+            return Err(anyhow!("Spending alert webhook responded with status {}", response.status()));
+        }
+        Ok(())
+    }
+}
@@ -0,0 +1,782 @@
+use std::collections::BTreeMap;
+use std::env;
+use std::net::{IpAddr, SocketAddr};
+use std::sync::Arc;
+use std::time::{Duration, Instant};
+use uuid::Uuid;
+use anyhow::{Result, Context, anyhow};
+use chrono::{Duration as ChronoDuration, Utc};
+use hmac::{Hmac, Mac};
+use serde_json::json;
+use sha2::Sha256;
+use tracing::{error, info, warn};
+
+use innosystem_common::i18n::Locale;
+use innosystem_common::models::notification::{self, DeliveryAttempt, DeliveryChannel, DeliveryStatus, NewNotificationDelivery, NotificationDelivery, NotificationMode};
+use innosystem_common::models::webhook::{is_public_address, CustomerWebhook, WebhookEventType};
+use innosystem_common::repositories::{
+    CustomerRepository, CustomerWebhookRepository, NotificationDeliveryRepository, NotificationPreferenceRepository, ResellerRepository,
+};
+
+/// Header carrying the HMAC-SHA256 signature of the payload
+pub const SIGNATURE_HEADER: &str = "X-Innosystem-Signature";
+/// Header carrying the unix timestamp that was signed together with the payload
+pub const TIMESTAMP_HEADER: &str = "X-Innosystem-Timestamp";
+/// Header carrying the event type name
+pub const EVENT_HEADER: &str = "X-Innosystem-Event";
+
+/// Maximum number of response body characters kept when recording a delivery
+const MAX_RECORDED_RESPONSE_CHARS: usize = 1024;
+
+/// Base delay before the first automatic retry, doubled for every further retry
+const RETRY_BASE_DELAY_SECS: i64 = 30;
+/// Upper bound for the delay between two automatic retries
+const MAX_RETRY_DELAY_SECS: i64 = 6 * 60 * 60;
+/// Number of automatic retries before a delivery is left for manual retry
+const MAX_AUTOMATIC_RETRIES: i32 = 8;
+/// Endpoints failing continuously for this long are disabled
+const AUTO_DISABLE_AFTER_HOURS: i64 = 24;
+/// Maximum number of due retries processed per sweep
+const RETRY_BATCH_SIZE: i64 = 100;
+/// Maximum number of queued deliveries bundled into digests per run
+const DIGEST_BATCH_SIZE: i64 = 1000;
+
+/// Configuration for notifications beyond webhooks: email and digests
+#[derive(Debug, Clone)]
+pub struct NotificationConfig {
+    /// HTTP endpoint of the email relay notifications are POSTed to; no email is sent while unset
+    pub email_relay_url: Option<String>,
+    /// Bearer token sent to the email relay
+    pub email_relay_token: Option<String>,
+    /// Sender address of notification emails
+    pub email_from: String,
+    /// Interval between two digests of the events customers chose to receive bundled
+    pub digest_interval_secs: u64,
+    /// Allow webhook endpoints on loopback, private and other internal addresses, for local development
+    pub allow_private_webhook_targets: bool,
+}
+
+impl Default for NotificationConfig {
+    fn default() -> Self {
+        Self {
+            email_relay_url: None,
+            email_relay_token: None,
+            email_from: "notifications@innosystem.local".to_string(),
+            digest_interval_secs: 60 * 60,
+            allow_private_webhook_targets: false,
+        }
+    }
+}
+
+impl NotificationConfig {
+    /// Load the configuration from `NOTIFICATION_*` environment variables, using defaults for unset values
+    pub fn from_env() -> Self {
+        let defaults = Self::default();
+        Self {
+            email_relay_url: env::var("NOTIFICATION_EMAIL_RELAY_URL").ok().filter(|url| !url.is_empty()),
+            email_relay_token: env::var("NOTIFICATION_EMAIL_RELAY_TOKEN").ok().filter(|token| !token.is_empty()),
+            email_from: env::var("NOTIFICATION_EMAIL_FROM").ok().filter(|from| !from.is_empty()).unwrap_or(defaults.email_from),
+            digest_interval_secs: env::var("NOTIFICATION_DIGEST_INTERVAL_SECS").ok()
+                .and_then(|value| value.parse().ok())
+                .filter(|secs| *secs > 0)
+                .unwrap_or(defaults.digest_interval_secs),
+            allow_private_webhook_targets: env::var("NOTIFICATION_ALLOW_PRIVATE_WEBHOOK_TARGETS").ok()
+                .and_then(|value| value.parse().ok())
+                .unwrap_or(defaults.allow_private_webhook_targets),
+        }
+    }
+}
+
+/// Outcome of sending a single event to a webhook endpoint
+#[derive(Debug, Clone)]
+pub struct WebhookDispatchResult {
+    /// HTTP status code returned by the endpoint, if a response was received
+    pub status_code: Option<i32>,
+    /// Truncated response body, or the transport error if no response was received
+    pub response: Option<String>,
+    /// Whether the endpoint answered with a 2xx status
+    pub success: bool,
+    /// Time from sending the request until the response body was read
+    pub latency_ms: i32,
+}
+
+/// Service for signing, dispatching and retrying customer notifications
+///
+/// Events go to the customer's webhook endpoints and, with an email relay configured,
+/// to their email address, each right away, in a digest or not at all as the
+/// customer's notification preferences say.
+pub struct WebhookService {
+    webhook_repo: Arc<dyn CustomerWebhookRepository>,
+    delivery_repo: Arc<dyn NotificationDeliveryRepository>,
+    preference_repo: Arc<dyn NotificationPreferenceRepository>,
+    customer_repo: Arc<dyn CustomerRepository>,
+    reseller_repo: Arc<dyn ResellerRepository>,
+    config: NotificationConfig,
+    client: reqwest::Client,
+    /// Client for customers' endpoints, which only connects to public addresses
+    webhook_client: reqwest::Client,
+    timeout: Duration,
+}
+
+impl WebhookService {
+    /// Create a new WebhookService
+    pub fn new(
+        webhook_repo: Arc<dyn CustomerWebhookRepository>,
+        delivery_repo: Arc<dyn NotificationDeliveryRepository>,
+        preference_repo: Arc<dyn NotificationPreferenceRepository>,
+        customer_repo: Arc<dyn CustomerRepository>,
+        reseller_repo: Arc<dyn ResellerRepository>,
+        config: Option<NotificationConfig>,
+    ) -> Self {
+        let config = config.unwrap_or_default();
+        let webhook_client = if config.allow_private_webhook_targets {
+            reqwest::Client::new()
+        } else {
+            reqwest::Client::builder()
+                .dns_resolver(Arc::new(PublicResolver))
+                .redirect(reqwest::redirect::Policy::custom(|attempt| {
+                    // Hosts given by name are resolved by PublicResolver; addresses are checked here
+                    let host = attempt.url().host_str().map(|host| host.trim_start_matches('[').trim_end_matches(']'));
+                    match host.and_then(|host| host.parse::<IpAddr>().ok()) {
+                        Some(ip) if !is_public_address(ip) => attempt.error(format!("redirected to internal address {}", ip)),
+                        _ if attempt.previous().len() >= 10 => attempt.error("too many redirects"),
+                        _ => attempt.follow(),
+                    }
+                }))
+                .build()
+                .expect("Failed to build the webhook HTTP client")
+        };
+        Self {
+            webhook_repo,
+            delivery_repo,
+            preference_repo,
+            customer_repo,
+            reseller_repo,
+            config,
+            client: reqwest::Client::new(),
+            webhook_client,
+            timeout: Duration::from_secs(10),
+        }
+    }
+
+    /// Whether notifications are emailed at all
+    pub fn email_enabled(&self) -> bool {
+        self.config.email_relay_url.is_some()
+    }
+
+    /// Subject and text of the email for an event body
+    pub fn email_message(body: &str) -> (String, String) {
+        let event: serde_json::Value = serde_json::from_str(body).unwrap_or_default();
+        let event_type = event["type"].as_str().unwrap_or("notification");
+        let data = &event["data"];
+
+        if event_type == WebhookEventType::Digest.as_str() {
+            let events = data["events"].as_array().map(Vec::as_slice).unwrap_or_default();
+            let lines: Vec<String> = events.iter()
+                .map(|event| match event["data"]["message"].as_str() {
+                    Some(message) => format!("- {} ({}): {}", event["type"].as_str().unwrap_or_default(), event["created_at"].as_str().unwrap_or_default(), message),
+                    None => format!("- {} ({})", event["type"].as_str().unwrap_or_default(), event["created_at"].as_str().unwrap_or_default()),
+                })
+                .collect();
+            return (format!("[InnoSystem] {} notifications", events.len()), lines.join("\n"));
+        }
+
+        let details = serde_json::to_string_pretty(data).unwrap_or_default();
+        let text = match data["message"].as_str() {
+            Some(message) => format!("{}\n\n{}", message, details),
+            None => details,
+        };
+        (format!("[InnoSystem] {}", event_type), text)
+    }
+
+    /// Compute the hex-encoded HMAC-SHA256 signature of `{timestamp}.{body}`
+    pub fn sign_payload(secret: &str, timestamp: i64, body: &str) -> String {
+        let mut mac = Hmac::<Sha256>::new_from_slice(secret.as_bytes())
+            .expect("HMAC accepts keys of any length");
+        mac.update(timestamp.to_string().as_bytes());
+        mac.update(b".");
+        mac.update(body.as_bytes());
+        format!("sha256={}", hex::encode(mac.finalize().into_bytes()))
+    }
+
+    /// Build the event envelope sent to customer endpoints
+    pub fn build_event(event_type: WebhookEventType, data: serde_json::Value) -> serde_json::Value {
+        json!({
+            "id": Uuid::new_v4(),
+            "type": event_type.as_str(),
+            "created_at": Utc::now(),
+            "data": data,
+        })
+    }
+
+    /// Delay before the next automatic retry, or None once retries are exhausted
+    fn next_retry_delay(retry_count: i32) -> Option<ChronoDuration> {
+        if retry_count >= MAX_AUTOMATIC_RETRIES {
+            return None;
+        }
+        let delay = RETRY_BASE_DELAY_SECS.saturating_mul(1i64 << retry_count.clamp(0, 30));
+        Some(ChronoDuration::seconds(delay.min(MAX_RETRY_DELAY_SECS)))
+    }
+
+    /// Check that a webhook URL points at a public address
+    ///
+    /// Hosts given by name must only resolve to public addresses; hosts that do not
+    /// resolve at all pass, as deliveries to them fail anyway. Returns why the URL is
+    /// refused otherwise.
+    pub async fn check_target(&self, url: &str) -> std::result::Result<(), String> {
+        if self.config.allow_private_webhook_targets {
+            return Ok(());
+        }
+        let url = reqwest::Url::parse(url).map_err(|e| format!("invalid URL: {}", e))?;
+        let host = url.host_str().ok_or("the URL has no host")?;
+        let addresses: Vec<IpAddr> = match host.trim_start_matches('[').trim_end_matches(']').parse::<IpAddr>() {
+            Ok(ip) => vec![ip],
+            Err(_) => match tokio::net::lookup_host((host, url.port_or_known_default().unwrap_or(443))).await {
+                Ok(addresses) => addresses.map(|address| address.ip()).collect(),
+                Err(_) => Vec::new(),
+            },
+        };
+        match addresses.into_iter().find(|ip| !is_public_address(*ip)) {
+            Some(ip) => Err(format!("{} points at internal address {}", host, ip)),
+            None => Ok(()),
+        }
+    }
+
+    /// Sign and POST an already serialized event body to a webhook endpoint
+    async fn send(&self, webhook: &CustomerWebhook, event_name: &str, body: String) -> WebhookDispatchResult {
+        if let Err(reason) = self.check_target(&webhook.url).await {
+            warn!("Refused to deliver {} to webhook {}: {}", event_name, webhook.id, reason);
+            return WebhookDispatchResult {
+                status_code: None,
+                response: Some(reason),
+                success: false,
+                latency_ms: 0,
+            };
+        }
+
+        let timestamp = Utc::now().timestamp();
+        let signature = Self::sign_payload(&webhook.secret, timestamp, &body);
+
+        let request = self.webhook_client.post(&webhook.url)
+            .header("Content-Type", "application/json")
+            .header(EVENT_HEADER, event_name)
+            .header(TIMESTAMP_HEADER, timestamp.to_string())
+            .header(SIGNATURE_HEADER, signature)
+            .timeout(self.timeout)
+            .body(body);
+
+        let started = Instant::now();
+        match request.send().await {
+            Ok(response) => {
+                let status = response.status();
+                WebhookDispatchResult {
+                    status_code: Some(status.as_u16() as i32),
+                    response: Some(read_recorded_response(response).await),
+                    success: status.is_success(),
+                    latency_ms: started.elapsed().as_millis().min(i32::MAX as u128) as i32,
+                }
+            }
+            Err(e) => {
+                warn!("Failed to deliver {} to webhook {}: {}", event_name, webhook.id, e);
+                WebhookDispatchResult {
+                    status_code: None,
+                    response: Some(e.to_string()),
+                    success: false,
+                    latency_ms: started.elapsed().as_millis().min(i32::MAX as u128) as i32,
+                }
+            }
+        }
+    }
+
+    /// POST an email to the configured relay
+    async fn send_email(&self, to: &str, body: &str) -> WebhookDispatchResult {
+        let started = Instant::now();
+        let Some(relay_url) = self.config.email_relay_url.as_deref() else {
+            return WebhookDispatchResult {
+                status_code: None,
+                response: Some("Email relay is not configured".to_string()),
+                success: false,
+                latency_ms: 0,
+            };
+        };
+
+        let (subject, text) = Self::email_message(body);
+        let mut request = self.client.post(relay_url)
+            .timeout(self.timeout)
+            .json(&json!({
+                "from": self.config.email_from,
+                "to": to,
+                "subject": subject,
+                "text": text,
+            }));
+        if let Some(token) = &self.config.email_relay_token {
+            request = request.bearer_auth(token);
+        }
+
+        match request.send().await {
+            Ok(response) => {
+                let status = response.status();
+                WebhookDispatchResult {
+                    status_code: Some(status.as_u16() as i32),
+                    response: Some(read_recorded_response(response).await),
+                    success: status.is_success(),
+                    latency_ms: started.elapsed().as_millis().min(i32::MAX as u128) as i32,
+                }
+            }
+            Err(e) => {
+                warn!("Failed to send notification email: {}", e);
+                WebhookDispatchResult {
+                    status_code: None,
+                    response: Some(e.to_string()),
+                    success: false,
+                    latency_ms: started.elapsed().as_millis().min(i32::MAX as u128) as i32,
+                }
+            }
+        }
+    }
+
+    /// Email an already serialized event body to a customer
+    async fn send_to_customer(&self, customer_id: Uuid, body: &str) -> WebhookDispatchResult {
+        match self.customer_repo.find_by_id(customer_id).await {
+            Ok(customer) => self.send_email(&customer.email, body).await,
+            Err(e) => WebhookDispatchResult {
+                status_code: None,
+                response: Some(format!("Failed to load customer {}: {}", customer_id, e)),
+                success: false,
+                latency_ms: 0,
+            },
+        }
+    }
+
+    /// Hold an event back for the customer's next digest
+    async fn queue(&self, customer_id: Uuid, webhook_id: Option<Uuid>, channel: DeliveryChannel, event_type: WebhookEventType, data: serde_json::Value) -> Result<NotificationDelivery> {
+        self.delivery_repo.create(NewNotificationDelivery {
+            id: Uuid::new_v4(),
+            customer_id,
+            webhook_id,
+            channel: channel.as_str().to_string(),
+            event_type: event_type.as_str().to_string(),
+            payload: Self::build_event(event_type, data).to_string(),
+            status: DeliveryStatus::Queued.as_str().to_string(),
+        })
+        .await
+        .context("Failed to queue notification for the digest")
+    }
+
+    /// Email an event to a customer and record the attempt in the delivery log
+    pub async fn deliver_email(
+        &self,
+        customer_id: Uuid,
+        event_type: WebhookEventType,
+        data: serde_json::Value,
+    ) -> Result<(NotificationDelivery, WebhookDispatchResult)> {
+        let body = Self::build_event(event_type, data).to_string();
+
+        let delivery = self.delivery_repo.create(NewNotificationDelivery {
+            id: Uuid::new_v4(),
+            customer_id,
+            webhook_id: None,
+            channel: DeliveryChannel::Email.as_str().to_string(),
+            event_type: event_type.as_str().to_string(),
+            payload: body.clone(),
+            status: DeliveryStatus::Pending.as_str().to_string(),
+        })
+        .await
+        .context("Failed to create notification delivery")?;
+
+        let result = self.send_to_customer(customer_id, &body).await;
+        let delivery = self.record_outcome(None, &delivery, &result, delivery.retry_count, true).await?;
+
+        Ok((delivery, result))
+    }
+
+    /// Send an event to a webhook endpoint and record the attempt in the delivery log
+    pub async fn deliver(
+        &self,
+        webhook: &CustomerWebhook,
+        event_type: WebhookEventType,
+        data: serde_json::Value,
+    ) -> Result<(NotificationDelivery, WebhookDispatchResult)> {
+        let body = Self::build_event(event_type, data).to_string();
+
+        let delivery = self.delivery_repo.create(NewNotificationDelivery {
+            id: Uuid::new_v4(),
+            customer_id: webhook.customer_id,
+            webhook_id: Some(webhook.id),
+            channel: DeliveryChannel::Webhook.as_str().to_string(),
+            event_type: event_type.as_str().to_string(),
+            payload: body.clone(),
+            status: DeliveryStatus::Pending.as_str().to_string(),
+        })
+        .await
+        .context("Failed to create notification delivery")?;
+
+        let result = self.send(webhook, event_type.as_str(), body).await;
+        // Test events are an interactive check and are never retried automatically
+        let retryable = event_type != WebhookEventType::Test;
+        let delivery = self.record_outcome(Some(webhook), &delivery, &result, delivery.retry_count, retryable).await?;
+
+        Ok((delivery, result))
+    }
+
+    /// Locale of messages to a customer: their reseller's default, or English
+    async fn customer_locale(&self, customer_id: Uuid) -> Locale {
+        let Ok(customer) = self.customer_repo.find_by_id(customer_id).await else {
+            return Locale::default();
+        };
+        let Some(reseller_id) = customer.reseller_id else {
+            return Locale::default();
+        };
+        match self.reseller_repo.find_by_id(reseller_id).await {
+            Ok(reseller) => reseller.default_locale().unwrap_or_default(),
+            Err(e) => {
+                warn!("Failed to load reseller {} of customer {}: {}", reseller_id, customer_id, e);
+                Locale::default()
+            }
+        }
+    }
+
+    /// Deliver an event to every active endpoint of the customer subscribed to it, and
+    /// email it to the customer, as their notification preferences say
+    ///
+    /// Events with a `reason` or `kind` get a `message` explaining it in the customer's
+    /// locale. Events the customer wants in a digest are queued for it, and failed
+    /// deliveries are logged and left to the automatic retries; returns the number of
+    /// endpoints and mailboxes that accepted the event right away.
+    pub async fn notify_customer(
+        &self,
+        customer_id: Uuid,
+        event_type: WebhookEventType,
+        mut data: serde_json::Value,
+    ) -> Result<usize> {
+        let preferences = match self.preference_repo.find_by_customer(customer_id).await {
+            Ok(preferences) => preferences,
+            Err(e) => {
+                warn!("Failed to load the notification preferences of customer {}, sending right away: {}", customer_id, e);
+                Vec::new()
+            }
+        };
+        let webhook_mode = notification::mode_for(&preferences, event_type, DeliveryChannel::Webhook);
+        let email_mode = match self.email_enabled() {
+            true => notification::mode_for(&preferences, event_type, DeliveryChannel::Email),
+            false => NotificationMode::Off,
+        };
+
+        let webhooks = match webhook_mode {
+            NotificationMode::Off => Vec::new(),
+            _ => self.webhook_repo.find_active_for_event(customer_id, event_type)
+                .await
+                .context("Failed to load customer webhooks")?,
+        };
+        if webhooks.is_empty() && email_mode == NotificationMode::Off {
+            return Ok(0);
+        }
+
+        let reason = data.get("reason").or_else(|| data.get("kind")).and_then(|value| value.as_str());
+        if let Some(code) = reason.map(|reason| format!("{}.{}", event_type.as_str(), reason)) {
+            if let Some(message) = self.customer_locale(customer_id).await.message(&code) {
+                data["message"] = json!(message);
+            }
+        }
+
+        let mut delivered = 0;
+        for webhook in webhooks {
+            if webhook_mode == NotificationMode::Digest {
+                if let Err(e) = self.queue(customer_id, Some(webhook.id), DeliveryChannel::Webhook, event_type, data.clone()).await {
+                    error!("Failed to queue {} for webhook {}: {}", event_type.as_str(), webhook.id, e);
+                }
+                continue;
+            }
+            match self.deliver(&webhook, event_type, data.clone()).await {
+                Ok((_, result)) if result.success => delivered += 1,
+                Ok(_) => {}
+                Err(e) => error!("Failed to deliver {} to webhook {}: {}", event_type.as_str(), webhook.id, e),
+            }
+        }
+
+        match email_mode {
+            NotificationMode::Off => {}
+            NotificationMode::Digest => {
+                if let Err(e) = self.queue(customer_id, None, DeliveryChannel::Email, event_type, data).await {
+                    error!("Failed to queue {} for the email of customer {}: {}", event_type.as_str(), customer_id, e);
+                }
+            }
+            NotificationMode::Immediate => match self.deliver_email(customer_id, event_type, data).await {
+                Ok((_, result)) if result.success => delivered += 1,
+                Ok(_) => {}
+                Err(e) => error!("Failed to email {} to customer {}: {}", event_type.as_str(), customer_id, e),
+            },
+        }
+
+        Ok(delivered)
+    }
+
+    /// Re-send a previously recorded delivery with its original payload
+    pub async fn retry_delivery(&self, delivery_id: Uuid) -> Result<(NotificationDelivery, WebhookDispatchResult)> {
+        // Lookup errors are propagated as-is so callers can tell "not found" apart
+        let delivery = self.delivery_repo.find_by_id(delivery_id).await?;
+
+        let webhook = match delivery.channel() {
+            Some(DeliveryChannel::Email) => None,
+            _ => {
+                let webhook_id = delivery.webhook_id
+                    .ok_or_else(|| anyhow!("Webhook not found for delivery: {}", delivery_id))?;
+                Some(self.webhook_repo.find_by_id(webhook_id).await?)
+            }
+        };
+
+        let result = match &webhook {
+            Some(webhook) => self.send(webhook, &delivery.event_type, delivery.payload.clone()).await,
+            None => self.send_to_customer(delivery.customer_id, &delivery.payload).await,
+        };
+        let retryable = delivery.event_type != WebhookEventType::Test.as_str();
+        let delivery = self.record_outcome(webhook.as_ref(), &delivery, &result, delivery.retry_count + 1, retryable).await?;
+
+        info!("Retried notification delivery {} (status: {:?})", delivery_id, result.status_code);
+
+        Ok((delivery, result))
+    }
+
+    /// Retry every failed webhook and email delivery whose next retry is due
+    pub async fn process_due_retries(&self) -> Result<usize> {
+        let now = Utc::now();
+
+        let mut retried = 0;
+        for channel in [DeliveryChannel::Webhook, DeliveryChannel::Email] {
+            let due = self.delivery_repo.find_due_retries(channel, now, RETRY_BATCH_SIZE)
+                .await
+                .context("Failed to load due notification retries")?;
+
+            for delivery in due {
+                // Stop retrying deliveries whose endpoint was deleted or disabled, or
+                // emails once the relay is no longer configured
+                if let Some(reason) = self.undeliverable(&delivery).await {
+                    self.give_up(&delivery, reason).await?;
+                    continue;
+                }
+
+                match self.retry_delivery(delivery.id).await {
+                    Ok(_) => retried += 1,
+                    Err(e) => error!("Failed to retry notification delivery {}: {}", delivery.id, e),
+                }
+            }
+        }
+
+        Ok(retried)
+    }
+
+    /// Why a delivery can no longer be attempted, if it cannot
+    async fn undeliverable(&self, delivery: &NotificationDelivery) -> Option<&'static str> {
+        if delivery.channel() == Some(DeliveryChannel::Email) {
+            return (!self.email_enabled()).then_some("Email relay is not configured");
+        }
+        let webhook = match delivery.webhook_id {
+            Some(webhook_id) => self.webhook_repo.find_by_id(webhook_id).await.ok(),
+            None => None,
+        };
+        (!webhook.is_some_and(|w| w.active)).then_some("Webhook endpoint is disabled or was deleted")
+    }
+
+    /// Mark a delivery as failed for good
+    async fn give_up(&self, delivery: &NotificationDelivery, reason: &str) -> Result<()> {
+        self.delivery_repo.record_attempt(delivery.id, DeliveryAttempt {
+            status: DeliveryStatus::Failed,
+            status_code: delivery.status_code,
+            latency_ms: delivery.latency_ms,
+            last_error: Some(reason.to_string()),
+            retry_count: delivery.retry_count,
+            next_retry_at: None,
+        })
+        .await?;
+        Ok(())
+    }
+
+    /// Send the events held back for digests, one digest per webhook endpoint and one
+    /// email per customer
+    ///
+    /// Bundled deliveries take the outcome of their digest; failed ones are retried
+    /// on their own. Returns the number of digests that were accepted.
+    pub async fn send_digests(&self) -> Result<usize> {
+        let mut sent = 0;
+        for channel in [DeliveryChannel::Webhook, DeliveryChannel::Email] {
+            let queued = self.delivery_repo.find_queued(channel, DIGEST_BATCH_SIZE)
+                .await
+                .context("Failed to load queued notifications")?;
+
+            let mut digests: BTreeMap<(Uuid, Option<Uuid>), Vec<NotificationDelivery>> = BTreeMap::new();
+            for delivery in queued {
+                digests.entry((delivery.customer_id, delivery.webhook_id)).or_default().push(delivery);
+            }
+
+            for ((customer_id, webhook_id), deliveries) in digests {
+                if let Some(reason) = self.undeliverable(&deliveries[0]).await {
+                    for delivery in &deliveries {
+                        self.give_up(delivery, reason).await?;
+                    }
+                    continue;
+                }
+
+                let events: Vec<serde_json::Value> = deliveries.iter()
+                    .filter_map(|delivery| serde_json::from_str(&delivery.payload).ok())
+                    .collect();
+                let body = Self::build_event(WebhookEventType::Digest, json!({ "events": events })).to_string();
+
+                let webhook = match webhook_id {
+                    Some(webhook_id) => Some(self.webhook_repo.find_by_id(webhook_id).await?),
+                    None => None,
+                };
+                let result = match &webhook {
+                    Some(webhook) => self.send(webhook, WebhookEventType::Digest.as_str(), body).await,
+                    None => self.send_to_customer(customer_id, &body).await,
+                };
+
+                for delivery in &deliveries {
+                    self.delivery_repo.record_attempt(delivery.id, Self::attempt(&result, delivery.retry_count, true))
+                        .await
+                        .context("Failed to record notification delivery attempt")?;
+                }
+                if let Some(webhook) = &webhook {
+                    self.track_endpoint_health(webhook, result.success).await?;
+                }
+                if result.success {
+                    sent += 1;
+                }
+            }
+        }
+
+        Ok(sent)
+    }
+
+    /// Outcome of an attempt to store in the delivery log
+    fn attempt(result: &WebhookDispatchResult, retry_count: i32, retryable: bool) -> DeliveryAttempt {
+        if result.success {
+            DeliveryAttempt {
+                status: DeliveryStatus::Succeeded,
+                status_code: result.status_code,
+                latency_ms: Some(result.latency_ms),
+                last_error: None,
+                retry_count,
+                next_retry_at: None,
+            }
+        } else {
+            DeliveryAttempt {
+                status: DeliveryStatus::Failed,
+                status_code: result.status_code,
+                latency_ms: Some(result.latency_ms),
+                last_error: result.response.clone(),
+                retry_count,
+                next_retry_at: if retryable {
+                    Self::next_retry_delay(retry_count).map(|delay| Utc::now() + delay)
+                } else {
+                    None
+                },
+            }
+        }
+    }
+
+    /// Store the result of an attempt and update the endpoint's failure tracking
+    async fn record_outcome(
+        &self,
+        webhook: Option<&CustomerWebhook>,
+        delivery: &NotificationDelivery,
+        result: &WebhookDispatchResult,
+        retry_count: i32,
+        retryable: bool,
+    ) -> Result<NotificationDelivery> {
+        let delivery = self.delivery_repo.record_attempt(delivery.id, Self::attempt(result, retry_count, retryable))
+            .await
+            .context("Failed to record notification delivery attempt")?;
+
+        if let Some(webhook) = webhook {
+            self.track_endpoint_health(webhook, result.success).await?;
+        }
+
+        Ok(delivery)
+    }
+
+    /// Reset the failure run on success, and disable endpoints that keep failing
+    ///
+    /// The run is tracked in the database rather than from `webhook`, which was loaded
+    /// before the delivery and may be outdated by concurrent ones.
+    async fn track_endpoint_health(&self, webhook: &CustomerWebhook, success: bool) -> Result<()> {
+        if success {
+            return self.webhook_repo.record_delivery_success(webhook.id).await;
+        }
+
+        let now = Utc::now();
+        let disable_failing_since = now - ChronoDuration::hours(AUTO_DISABLE_AFTER_HOURS);
+        if let Some(disabled) = self.webhook_repo.record_delivery_failure(webhook.id, now, disable_failing_since).await? {
+            warn!(
+                "Disabled webhook {} after failing continuously since {}",
+                disabled.id,
+                disabled.failing_since.unwrap_or(now),
+            );
+        }
+
+        Ok(())
+    }
+
+    /// Fire a signed sample event at a webhook endpoint and record the response
+    pub async fn test_fire(&self, webhook_id: Uuid) -> Result<(CustomerWebhook, WebhookDispatchResult)> {
+        let webhook = self.webhook_repo.find_by_id(webhook_id)
+            .await
+            .context("Failed to find webhook for test-fire")?;
+
+        let sample = json!({
+            "message": "This is a test event from InnoSystem",
+            "webhook_id": webhook.id,
+            "customer_id": webhook.customer_id,
+        });
+
+        let (_, result) = self.deliver(&webhook, WebhookEventType::Test, sample).await?;
+
+        let webhook = self.webhook_repo.record_test_result(
+            webhook_id,
+            result.status_code,
+            result.response.clone(),
+        )
+        .await
+        .context("Failed to record webhook test result")?;
+
+        info!("Test-fired webhook {} (status: {:?})", webhook_id, result.status_code);
+
+        Ok((webhook, result))
+    }
+}
+
+/// Read the start of a response body to record, up to `MAX_RECORDED_RESPONSE_CHARS`
+/// characters; the rest is never read, however large the body is
+async fn read_recorded_response(mut response: reqwest::Response) -> String {
+    // A character takes at most 4 bytes in UTF-8
+    let limit = MAX_RECORDED_RESPONSE_CHARS * 4;
+    let mut body = Vec::new();
+    while let Ok(Some(chunk)) = response.chunk().await {
+        let room = limit - body.len();
+        if chunk.len() >= room {
+            body.extend_from_slice(&chunk[..room]);
+            break;
+        }
+        body.extend_from_slice(&chunk);
+    }
+    String::from_utf8_lossy(&body).chars().take(MAX_RECORDED_RESPONSE_CHARS).collect()
+}
+
+/// Resolver of webhook hosts that only hands out public addresses
+///
+/// Checking the URL before sending is not enough on its own: the host's DNS records may
+/// change between the check and the connection.
+struct PublicResolver;
+
+impl reqwest::dns::Resolve for PublicResolver {
+    fn resolve(&self, name: reqwest::dns::Name) -> reqwest::dns::Resolving {
+        Box::pin(async move {
+            let addresses: Vec<SocketAddr> = tokio::net::lookup_host((name.as_str(), 0)).await?
+                .filter(|address| is_public_address(address.ip()))
+                .collect();
+            if addresses.is_empty() {
+                return Err(format!("{} resolves to no public address", name.as_str()).into());
+            }
+            Ok(Box::new(addresses.into_iter()) as reqwest::dns::Addrs)
+        })
+    }
+}
@@ -0,0 +1,3495 @@
+//! End-to-end tests that run the API binary against a test environment
+//!
+//! These need a database and, unless they queue jobs in memory, Redis, so they are
+//! ignored by default. Run them with `cargo test -p innosystem-api --test api -- --ignored`;
+//! containers are started unless `TEST_DATABASE_URL` and `TEST_REDIS_URL` point at
+//! existing instances.
+
+use std::net::TcpListener;
+use std::process::{Child, Command, Stdio};
+use std::sync::Arc;
+use std::time::Duration;
+
+use innosystem_common::database::{with_reseller, SchemaResolver, TenancyConfig, TenancyMode, TenantPool};
+use innosystem_common::models::customer::Customer;
+use innosystem_common::models::job_artifact::NewJobArtifact;
+use innosystem_common::models::job_attempt::AttemptOutcome;
+use innosystem_common::models::job_error::{codes, JobError};
+use innosystem_common::models::job_type::ProcessorType;
+use innosystem_common::models::request_signature::{self, SignedRequest};
+use innosystem_common::models::runner::{NewRunner, RunnerStatus};
+use innosystem_common::models::wallet::{NewWalletTransaction, TransactionType};
+use innosystem_common::repositories::{
+    CustomerRepository, DieselCustomerRepository, DieselJobArtifactRepository, DieselJobAttemptRepository,
+    DieselJobRepository, DieselJobTypeRepository, DieselResellerRepository, DieselRunnerRepository,
+    DieselWalletRepository, JobArtifactRepository, JobAttemptRepository, JobRepository, RunnerRepository, WalletRepository,
+};
+use innosystem_common::testing::TestEnvironment;
+use innosystem_common::testing::factories::{CustomerFactory, JobFactory, JobTypeFactory, ResellerFactory, WalletFactory};
+use reqwest::StatusCode;
+use serde_json::{json, Value};
+
+const ADMIN_API_KEY: &str = "test-admin-api-key";
+
+/// API process bound to a free local port, killed when dropped
+struct ApiServer {
+    process: Child,
+    base_url: String,
+    client: reqwest::Client,
+}
+
+impl ApiServer {
+    /// Start the API against the given environment and wait until it accepts connections
+    async fn start(env: &TestEnvironment) -> Self {
+        Self::start_with(env, &[]).await
+    }
+
+    /// Start the API with additional environment variables
+    ///
+    /// An environment started without Redis gets the in-memory job queue.
+    async fn start_with(env: &TestEnvironment, vars: &[(&str, &str)]) -> Self {
+        let port = TcpListener::bind("127.0.0.1:0").unwrap().local_addr().unwrap().port();
+        let mut command = Command::new(env!("CARGO_BIN_EXE_innosystem-api"));
+        command
+            .env("ENVIRONMENT", "development")
+            .env("PORT", port.to_string())
+            .env("DATABASE_URL", &env.database_url)
+            .env("ADMIN_API_KEY", ADMIN_API_KEY)
+            .env("RUST_LOG", "warn");
+        match env.redis_url.as_deref() {
+            Some(redis_url) => command.env("REDIS_URL", redis_url),
+            None => command.env("QUEUE_BACKEND", "memory"),
+        };
+        let process = command
+            .envs(vars.iter().copied())
+            .stdout(Stdio::null())
+            .spawn()
+            .expect("failed to start the API binary");
+
+        let server = Self {
+            process,
+            base_url: format!("http://127.0.0.1:{}", port),
+            client: reqwest::Client::new(),
+        };
+
+        for _ in 0..100 {
+            if server.client.get(server.url("/health")).send().await.is_ok() {
+                return server;
+            }
+            tokio::time::sleep(Duration::from_millis(100)).await;
+        }
+        panic!("API did not start listening within 10 seconds");
+    }
+
+    fn url(&self, path: &str) -> String {
+        format!("{}{}", self.base_url, path)
+    }
+
+    /// GET a path with the given API key, returning the status and JSON body
+    async fn get(&self, path: &str, api_key: Option<&str>) -> (StatusCode, Value) {
+        self.send(self.client.get(self.url(path)), api_key).await
+    }
+
+    /// POST a JSON body to a path with the given API key, returning the status and JSON body
+    async fn post(&self, path: &str, api_key: Option<&str>, body: Value) -> (StatusCode, Value) {
+        self.send(self.client.post(self.url(path)).json(&body), api_key).await
+    }
+
+    async fn send(&self, mut request: reqwest::RequestBuilder, api_key: Option<&str>) -> (StatusCode, Value) {
+        if let Some(api_key) = api_key {
+            request = request.header("X-API-Key", api_key);
+        }
+        let response = request.send().await.unwrap();
+        let status = response.status();
+        let body = response.json::<Value>().await.unwrap_or(Value::Null);
+        (status, body)
+    }
+}
+
+impl Drop for ApiServer {
+    fn drop(&mut self) {
+        let _ = self.process.kill();
+        let _ = self.process.wait();
+    }
+}
+
+/// Start a fresh environment with Redis and the API running against it
+async fn start() -> (TestEnvironment, ApiServer) {
+    let env = TestEnvironment::start_with_redis().await.expect("failed to start test environment");
+    let server = ApiServer::start(&env).await;
+    (env, server)
+}
+
+/// Start a fresh environment without Redis and the API running against it, queueing
+/// jobs in memory
+async fn start_without_redis() -> (TestEnvironment, ApiServer) {
+    let env = TestEnvironment::start().await.expect("failed to start test environment");
+    let server = ApiServer::start(&env).await;
+    (env, server)
+}
+
+/// Insert a customer with a wallet
+async fn customer_with_wallet(env: &TestEnvironment, balance_cents: i32) -> Customer {
+    let customer = CustomerFactory::new()
+        .create(&DieselCustomerRepository::new(env.pool.clone()))
+        .await
+        .unwrap();
+    WalletFactory::new(customer.id)
+        .balance_cents(balance_cents)
+        .create(&DieselWalletRepository::new(env.pool.clone()))
+        .await
+        .unwrap();
+    customer
+}
+
+#[tokio::test]
+#[ignore = "requires Docker or TEST_DATABASE_URL and TEST_REDIS_URL"]
+async fn health_check_is_public() {
+    let (_env, server) = start().await;
+
+    let (status, body) = server.get("/health", None).await;
+    assert_eq!(status, StatusCode::OK);
+    assert_eq!(body["status"], "OK");
+    assert_eq!(body["queue_reachable"], true);
+}
+
+#[tokio::test]
+#[ignore = "requires Docker or TEST_DATABASE_URL and TEST_REDIS_URL"]
+async fn customer_routes_require_a_valid_api_key() {
+    let (env, server) = start().await;
+    let customer = customer_with_wallet(&env, 1000).await;
+    let path = format!("/wallets/{}", customer.id);
+
+    assert_eq!(server.get(&path, None).await.0, StatusCode::UNAUTHORIZED);
+    assert_eq!(server.get(&path, Some("cus_unknown")).await.0, StatusCode::UNAUTHORIZED);
+    assert_eq!(server.get(&path, customer.api_key.as_deref()).await.0, StatusCode::OK);
+}
+
+#[tokio::test]
+#[ignore = "requires Docker or TEST_DATABASE_URL and TEST_REDIS_URL"]
+async fn customers_read_their_wallet_and_jobs() {
+    let (env, server) = start().await;
+    let customer = customer_with_wallet(&env, 2500).await;
+    let api_key = customer.api_key.as_deref();
+
+    let (status, wallet) = server.get(&format!("/wallets/{}", customer.id), api_key).await;
+    assert_eq!(status, StatusCode::OK);
+    assert_eq!(wallet["balance_cents"], 2500);
+
+    let job_type = JobTypeFactory::new()
+        .create(&DieselJobTypeRepository::new(env.pool.clone()))
+        .await
+        .unwrap();
+    let job = JobFactory::new(customer.id, job_type.id)
+        .create(&DieselJobRepository::new(env.pool.clone()))
+        .await
+        .unwrap();
+
+    let (status, body) = server.get(&format!("/jobs/{}", job.id), api_key).await;
+    assert_eq!(status, StatusCode::OK);
+    assert_eq!(body["id"], job.id.to_string());
+    assert_eq!(body["status"], "pending");
+    assert_eq!(body["test_mode"], false);
+    assert_eq!(body["public_id"], job.public_id);
+
+    // The short public ID works in paths and searches as well as the UUID
+    let (status, body) = server.get(&format!("/jobs/{}", job.public_id), api_key).await;
+    assert_eq!(status, StatusCode::OK);
+    assert_eq!(body["id"], job.id.to_string());
+    assert_eq!(server.get(&format!("/jobs/{}/attempts", job.public_id), api_key).await.0, StatusCode::OK);
+    let (status, jobs) = server.get(&format!("/jobs?public_id={}", job.public_id), api_key).await;
+    assert_eq!(status, StatusCode::OK);
+    assert_eq!(jobs["data"].as_array().unwrap().len(), 1);
+    assert_eq!(jobs["meta"]["total"], 1);
+
+    // Filters and paging map onto the job query, reporting the total across pages
+    let (status, jobs) = server.get(&format!("/jobs?customer_id={}&status=pending&per_page=1&page=1&sort=created_asc", job.customer_id), api_key).await;
+    assert_eq!(status, StatusCode::OK);
+    assert_eq!(jobs["data"][0]["id"], job.id.to_string());
+    assert_eq!(jobs["meta"]["limit"], 1);
+    assert_eq!(jobs["meta"]["offset"], 0);
+    let (status, jobs) = server.get(&format!("/jobs?customer_id={}&status=running", job.customer_id), api_key).await;
+    assert_eq!(status, StatusCode::OK);
+    assert_eq!(jobs["meta"]["total"], 0);
+    assert_eq!(server.get("/jobs?status=unknown", api_key).await.0, StatusCode::BAD_REQUEST);
+    assert_eq!(server.get("/jobs?sort=cost", api_key).await.0, StatusCode::BAD_REQUEST);
+    assert_eq!(server.get("/jobs/job_missing", api_key).await.0, StatusCode::NOT_FOUND);
+    assert_eq!(server.get("/jobs/not-a-job", api_key).await.0, StatusCode::BAD_REQUEST);
+}
+
+#[tokio::test]
+#[ignore = "requires Docker or TEST_DATABASE_URL and TEST_REDIS_URL"]
+async fn authenticated_requests_keep_their_body() {
+    let (env, server) = start().await;
+    let customer = customer_with_wallet(&env, 1000).await;
+    let api_key = customer.api_key.as_deref();
+
+    let (status, webhook) = server.post("/webhooks", api_key, json!({
+        "url": "https://example.test/hooks",
+        "event_types": ["job.succeeded"],
+    })).await;
+    assert_eq!(status, StatusCode::CREATED);
+    assert_eq!(webhook["url"], "https://example.test/hooks");
+
+    let (status, webhooks) = server.get("/webhooks", api_key).await;
+    assert_eq!(status, StatusCode::OK);
+    assert_eq!(webhooks.as_array().map(Vec::len), Some(1));
+}
+
+#[tokio::test]
+#[ignore = "requires Docker or TEST_DATABASE_URL"]
+async fn webhooks_cannot_target_internal_addresses() {
+    let (env, server) = start_without_redis().await;
+    let customer = customer_with_wallet(&env, 1000).await;
+    let api_key = customer.api_key.as_deref();
+
+    for url in ["http://127.0.0.1:8080/hooks", "http://localhost/hooks", "http://169.254.169.254/latest", "http://[::1]/hooks", "http://10.0.0.5/hooks"] {
+        let (status, _) = server.post("/webhooks", api_key, json!({ "url": url, "event_types": ["job.succeeded"] })).await;
+        assert_eq!(status, StatusCode::BAD_REQUEST, "{} was accepted", url);
+    }
+
+    let (status, webhook) = server.post("/webhooks", api_key, json!({
+        "url": "https://example.test/hooks",
+        "event_types": ["job.succeeded"],
+    })).await;
+    assert_eq!(status, StatusCode::CREATED);
+    let path = format!("/webhooks/{}", webhook["id"].as_str().unwrap());
+    let (status, _) = server.send(server.client.put(server.url(&path)).json(&json!({ "url": "http://192.168.1.1/hooks" })), api_key).await;
+    assert_eq!(status, StatusCode::BAD_REQUEST);
+}
+
+#[tokio::test]
+#[ignore = "requires Docker or TEST_DATABASE_URL and TEST_REDIS_URL"]
+async fn test_keys_authenticate_customers() {
+    let (env, server) = start().await;
+    let customer = customer_with_wallet(&env, 1000).await;
+    let test_key = DieselCustomerRepository::new(env.pool.clone())
+        .generate_test_api_key(customer.id)
+        .await
+        .unwrap();
+
+    let (status, wallet) = server.get(&format!("/wallets/{}", customer.id), Some(&test_key)).await;
+    assert_eq!(status, StatusCode::OK);
+    assert_eq!(wallet["customer_id"], customer.id.to_string());
+}
+
+#[tokio::test]
+#[ignore = "requires Docker or TEST_DATABASE_URL and TEST_REDIS_URL"]
+async fn admin_routes_require_the_admin_key() {
+    let (env, server) = start().await;
+    let customer = customer_with_wallet(&env, 1000).await;
+
+    assert_eq!(server.get("/admin/resellers", customer.api_key.as_deref()).await.0, StatusCode::UNAUTHORIZED);
+
+    let (status, body) = server.get("/admin/resellers", Some(ADMIN_API_KEY)).await;
+    assert_eq!(status, StatusCode::OK);
+    assert!(body.is_array());
+}
+
+#[tokio::test]
+#[ignore = "requires Docker or TEST_DATABASE_URL and TEST_REDIS_URL"]
+async fn autoscaling_advice_reports_queue_depth() {
+    let (env, server) = start().await;
+    let customer = customer_with_wallet(&env, 1000).await;
+    let job_type = JobTypeFactory::new()
+        .create(&DieselJobTypeRepository::new(env.pool.clone()))
+        .await
+        .unwrap();
+    let job_repo = DieselJobRepository::new(env.pool.clone());
+    for _ in 0..3 {
+        JobFactory::new(customer.id, job_type.id).create(&job_repo).await.unwrap();
+    }
+
+    let (status, advice) = server.get("/admin/autoscaling", Some(ADMIN_API_KEY)).await;
+    assert_eq!(status, StatusCode::OK);
+    let demand = advice["job_types"].as_array().unwrap()
+        .iter()
+        .find(|demand| demand["job_type_id"] == job_type.id.to_string())
+        .expect("job type missing from advice");
+    assert_eq!(demand["queue_depth"], 3);
+    assert_eq!(demand["measured"], false);
+    assert!(advice["desired_runners"].as_u64().unwrap() >= 1);
+}
+
+#[tokio::test]
+#[ignore = "requires Docker or TEST_DATABASE_URL and TEST_REDIS_URL"]
+async fn jobs_are_submitted_from_templates() {
+    let (env, server) = start().await;
+    let customer = customer_with_wallet(&env, 1000).await;
+    let api_key = customer.api_key.as_deref();
+    let job_type = JobTypeFactory::new()
+        .create(&DieselJobTypeRepository::new(env.pool.clone()))
+        .await
+        .unwrap();
+
+    let (status, _) = server.post("/job-templates", api_key, json!({
+        "job_type_id": job_type.id,
+        "name": "broken",
+        "input_template": { "to": "{{recipient}}" },
+    })).await;
+    assert_eq!(status, StatusCode::BAD_REQUEST);
+
+    let (status, template) = server.post("/job-templates", api_key, json!({
+        "job_type_id": job_type.id,
+        "name": "greeting",
+        "input_template": { "text": "Hello {{name}}", "repeat": "{{repeat}}" },
+        "variables": { "name": { "type": "string" }, "repeat": { "type": "integer", "default": 1 } },
+    })).await;
+    assert_eq!(status, StatusCode::CREATED);
+    let path = format!("/jobs/from-template/{}", template["id"].as_str().unwrap());
+
+    let (status, _) = server.post(&path, api_key, json!({ "variables": { "name": 42 } })).await;
+    assert_eq!(status, StatusCode::BAD_REQUEST);
+    let (status, _) = server.post(&path, api_key, json!({ "variables": {} })).await;
+    assert_eq!(status, StatusCode::BAD_REQUEST);
+
+    let (status, job) = server.post(&path, api_key, json!({ "variables": { "name": "world" } })).await;
+    assert_eq!(status, StatusCode::CREATED);
+    assert_eq!(job["customer_id"], customer.id.to_string());
+    assert_eq!(job["job_type_id"], job_type.id.to_string());
+    assert_eq!(job["status"], "pending");
+
+    let other = customer_with_wallet(&env, 1000).await;
+    let (status, _) = server.post(&path, other.api_key.as_deref(), json!({ "variables": { "name": "world" } })).await;
+    assert_eq!(status, StatusCode::FORBIDDEN);
+}
+
+#[tokio::test]
+#[ignore = "requires Docker or TEST_DATABASE_URL and TEST_REDIS_URL"]
+async fn runners_failing_their_jobs_are_not_healthy() {
+    let (env, server) = start().await;
+    let customer = customer_with_wallet(&env, 1000).await;
+    let job_type = JobTypeFactory::new()
+        .create(&DieselJobTypeRepository::new(env.pool.clone()))
+        .await
+        .unwrap();
+    let runner_repo = DieselRunnerRepository::new(env.pool.clone());
+    let runner = runner_repo.register(NewRunner {
+        id: uuid::Uuid::new_v4(),
+        name: "flaky-runner".to_string(),
+        description: None,
+        status: RunnerStatus::Active.as_str().to_string(),
+        compatible_job_types: Vec::new(),
+    }).await.unwrap();
+    runner_repo.update_heartbeat(runner.id, chrono::Utc::now()).await.unwrap();
+
+    let path = format!("/runners/{}/health", runner.id);
+    let (status, health) = server.get(&path, Some(ADMIN_API_KEY)).await;
+    assert_eq!(status, StatusCode::OK);
+    assert_eq!(health["status"], "healthy");
+    assert_eq!(health["score"], 100);
+
+    let job_repo = DieselJobRepository::new(env.pool.clone());
+    for _ in 0..6 {
+        let job = JobFactory::new(customer.id, job_type.id).create(&job_repo).await.unwrap();
+        job_repo.set_started(job.id).await.unwrap();
+        job_repo.assign_runner(job.id, runner.id).await.unwrap();
+        job_repo.set_completed(job.id, false, None, None, 0).await.unwrap();
+    }
+
+    let (status, health) = server.get(&path, Some(ADMIN_API_KEY)).await;
+    assert_eq!(status, StatusCode::OK);
+    assert_eq!(health["status"], "critical");
+    assert_eq!(health["failure_rate"], 1.0);
+    assert_eq!(health["jobs_sampled"], 6);
+    assert!(health["score"].as_i64().unwrap() < 100);
+
+    let (status, history) = server.get(&format!("{}/history", path), Some(ADMIN_API_KEY)).await;
+    assert_eq!(status, StatusCode::OK);
+    assert!(history.is_array());
+}
+
+#[tokio::test]
+#[ignore = "requires Docker or TEST_DATABASE_URL and TEST_REDIS_URL"]
+async fn abandoned_jobs_release_their_reservations() {
+    use diesel::RunQueryDsl;
+
+    let (env, server) = start().await;
+    let customer = customer_with_wallet(&env, 5000).await;
+    let job_type = JobTypeFactory::new()
+        .create(&DieselJobTypeRepository::new(env.pool.clone()))
+        .await
+        .unwrap();
+    let job_repo = DieselJobRepository::new(env.pool.clone());
+    let wallet_repo = DieselWalletRepository::new(env.pool.clone());
+    let wallet = wallet_repo.find_by_customer_id(customer.id).await.unwrap();
+
+    let abandoned = JobFactory::new(customer.id, job_type.id).create(&job_repo).await.unwrap();
+    let recent = JobFactory::new(customer.id, job_type.id).create(&job_repo).await.unwrap();
+    wallet_repo.reserve_funds(wallet.id, 1200, None, Some(abandoned.id)).await.unwrap();
+    wallet_repo.reserve_funds(wallet.id, 800, None, Some(recent.id)).await.unwrap();
+
+    let mut conn = env.pool.get().unwrap();
+    diesel::sql_query("UPDATE jobs SET created_at = NOW() - INTERVAL '2 days', updated_at = NOW() - INTERVAL '2 days' WHERE id = $1")
+        .bind::<diesel::sql_types::Uuid, _>(abandoned.id)
+        .execute(&mut conn)
+        .unwrap();
+
+    let (status, expired) = server.post("/admin/reservations/expire", Some(ADMIN_API_KEY), json!({})).await;
+    assert_eq!(status, StatusCode::OK);
+    let expired = expired.as_array().unwrap();
+    let entry = expired.iter().find(|e| e["job_id"] == abandoned.id.to_string()).unwrap();
+    assert_eq!(entry["released_cents"], 1200);
+    assert!(!expired.iter().any(|e| e["job_id"] == recent.id.to_string()));
+
+    assert_eq!(job_repo.find_by_id(abandoned.id).await.unwrap().status.as_str(), "cancelled");
+    assert_eq!(job_repo.find_by_id(recent.id).await.unwrap().status.as_str(), "pending");
+    assert_eq!(wallet_repo.get_reserved_for_job(abandoned.id).await.unwrap(), 0);
+    assert_eq!(wallet_repo.get_balance(wallet.id).await.unwrap(), 4200);
+
+    // A second sweep finds nothing left to release
+    let (_, expired) = server.post("/admin/reservations/expire", Some(ADMIN_API_KEY), json!({})).await;
+    assert!(!expired.as_array().unwrap().iter().any(|e| e["job_id"] == abandoned.id.to_string()));
+}
+
+#[tokio::test]
+#[ignore = "requires Docker or TEST_DATABASE_URL and TEST_REDIS_URL"]
+async fn cached_catalogs_are_invalidated_on_changes() {
+    let (_env, server) = start().await;
+
+    let (status, before) = server.get("/job-types", Some(ADMIN_API_KEY)).await;
+    assert_eq!(status, StatusCode::OK);
+    let count = before.as_array().unwrap().len();
+
+    let (status, created) = server.post("/job-types", Some(ADMIN_API_KEY), json!({
+        "name": format!("cached-{}", uuid::Uuid::new_v4()),
+        "description": "Cached catalog entry",
+        "processor_type": "webhook",
+        "standard_cost_cents": 250,
+    })).await;
+    assert_eq!(status, StatusCode::CREATED);
+
+    let (_, after) = server.get("/job-types", Some(ADMIN_API_KEY)).await;
+    let after = after.as_array().unwrap();
+    assert!(after.len() > count);
+    assert!(after.iter().any(|job_type| job_type["id"] == created["id"]));
+
+    let path = format!("/job-types/{}", created["id"].as_str().unwrap());
+    let (_, first) = server.get(&path, Some(ADMIN_API_KEY)).await;
+    let (_, second) = server.get(&path, Some(ADMIN_API_KEY)).await;
+    assert_eq!(first, second);
+    assert_eq!(first["standard_cost_cents"], 250);
+
+    let (status, _) = server.get("/admin/resellers", Some(ADMIN_API_KEY)).await;
+    assert_eq!(status, StatusCode::OK);
+    let (status, reseller) = server.post("/admin/resellers", Some(ADMIN_API_KEY), json!({
+        "name": "Cached Reseller",
+        "email": format!("{}@example.com", uuid::Uuid::new_v4()),
+        "commission_rate_percentage": 10.0,
+    })).await;
+    assert_eq!(status, StatusCode::CREATED);
+    let (_, resellers) = server.get("/admin/resellers", Some(ADMIN_API_KEY)).await;
+    assert!(resellers.as_array().unwrap().iter().any(|r| r["id"] == reseller["id"]));
+}
+
+#[tokio::test]
+#[ignore = "requires Docker or TEST_DATABASE_URL and TEST_REDIS_URL"]
+async fn alerting_rules_follow_the_configured_thresholds() {
+    let (_env, server) = start().await;
+
+    let response = server.client.get(server.url("/admin/alerting/prometheus-rules"))
+        .header("X-API-Key", ADMIN_API_KEY)
+        .send()
+        .await
+        .unwrap();
+    assert_eq!(response.status(), StatusCode::OK);
+    let rules = response.text().await.unwrap();
+    assert!(rules.starts_with("# Generated"));
+    assert!(rules.contains("- alert: InnosystemRunnerHeartbeatLate"));
+    assert!(rules.contains("innosystem_runner_heartbeat_age_seconds > 60"));
+    assert!(rules.contains("innosystem_runner_failure_rate >= 0.5"));
+    assert!(rules.contains("innosystem_autoscaling_desired_runners >= 50"));
+    assert!(rules.contains("innosystem_queue_wait_seconds{priority=\\\"low\\\",quantile=\\\"0.9\\\"} > 3600"));
+    assert!(rules.contains("- alert: InnosystemQueuePoisonEntries"));
+
+    let (status, dashboard) = server.get("/admin/alerting/grafana-dashboard", Some(ADMIN_API_KEY)).await;
+    assert_eq!(status, StatusCode::OK);
+    assert_eq!(dashboard["panels"].as_array().unwrap().len(), 7);
+
+    let response = server.client.get(server.url("/admin/runners/metrics"))
+        .header("X-API-Key", ADMIN_API_KEY)
+        .send()
+        .await
+        .unwrap();
+    assert_eq!(response.status(), StatusCode::OK);
+    assert!(response.text().await.unwrap().contains("# TYPE innosystem_runner_heartbeat_age_seconds gauge"));
+
+    let (status, _) = server.get("/admin/alerting/prometheus-rules", None).await;
+    assert_eq!(status, StatusCode::UNAUTHORIZED);
+}
+
+#[tokio::test]
+#[ignore = "requires Docker or TEST_DATABASE_URL"]
+async fn jobs_are_queued_in_memory_without_redis() {
+    let (env, server) = start_without_redis().await;
+    let customer = customer_with_wallet(&env, 5000).await;
+    let job_type = JobTypeFactory::new()
+        .create(&DieselJobTypeRepository::new(env.pool.clone()))
+        .await
+        .unwrap();
+
+    let (status, health) = server.get("/health", None).await;
+    assert_eq!(status, StatusCode::OK);
+    assert_eq!(health["queue_reachable"], true);
+
+    let job = json!({ "customer_id": customer.id, "job_type_id": job_type.id, "input_data": {} });
+    let (status, submitted) = server.post("/jobs", customer.api_key.as_deref(), job).await;
+    assert_eq!(status, StatusCode::CREATED);
+    assert_eq!(submitted["status"], "pending");
+
+    let (status, quarantine) = server.get("/admin/queue/quarantine", Some(ADMIN_API_KEY)).await;
+    assert_eq!(status, StatusCode::OK);
+    assert_eq!(quarantine["held"], 0);
+}
+
+#[tokio::test]
+#[ignore = "requires Docker or TEST_DATABASE_URL"]
+async fn customers_list_only_their_own_jobs() {
+    let (env, server) = start_without_redis().await;
+    let customer = customer_with_wallet(&env, 1000).await;
+    let other = customer_with_wallet(&env, 1000).await;
+    let api_key = customer.api_key.as_deref();
+    let job_type = JobTypeFactory::new()
+        .create(&DieselJobTypeRepository::new(env.pool.clone()))
+        .await
+        .unwrap();
+    let jobs = DieselJobRepository::new(env.pool.clone());
+    let job = JobFactory::new(customer.id, job_type.id).create(&jobs).await.unwrap();
+    JobFactory::new(other.id, job_type.id).create(&jobs).await.unwrap();
+
+    // Without a customer filter the list still holds only the caller's jobs
+    let (status, body) = server.get("/jobs", api_key).await;
+    assert_eq!(status, StatusCode::OK);
+    assert_eq!(body["meta"]["total"], 1);
+    assert_eq!(body["data"][0]["id"], job.id.to_string());
+    assert_eq!(server.get(&format!("/jobs?customer_id={}", customer.id), api_key).await.0, StatusCode::OK);
+    assert_eq!(server.get(&format!("/jobs?customer_id={}", other.id), api_key).await.0, StatusCode::FORBIDDEN);
+
+    // Pages far past the end are empty instead of overflowing the offset
+    let (status, body) = server.get(&format!("/jobs?page={}&per_page=500", u32::MAX), api_key).await;
+    assert_eq!(status, StatusCode::OK);
+    assert_eq!(body["data"].as_array().unwrap().len(), 0);
+    assert_eq!(body["meta"]["total"], 1);
+    assert_eq!(body["meta"]["offset"], i64::from(u32::MAX - 1) * 500);
+}
+
+#[tokio::test]
+#[ignore = "requires Docker or TEST_DATABASE_URL and TEST_REDIS_URL"]
+async fn poison_queue_entries_are_quarantined_and_the_queue_keeps_moving() {
+    use innosystem_common::models::job::PriorityLevel;
+    use innosystem_common::queue::{JobQueue, JobQueueConfig, RedisJobQueue};
+    use redis::AsyncCommands;
+
+    let (env, server) = start().await;
+    let queue = RedisJobQueue::new(JobQueueConfig::new(env.redis_url().to_string())).await.unwrap();
+    let mut conn = redis::Client::open(env.redis_url()).unwrap()
+        .get_multiplexed_async_connection()
+        .await
+        .unwrap();
+
+    // Jobs are popped from the tail, so the poison entry comes before the job
+    let poison = format!("poison-{}", uuid::Uuid::new_v4());
+    let scheduled_poison = format!("scheduled-poison-{}", uuid::Uuid::new_v4());
+    let job_id = uuid::Uuid::new_v4();
+    let _: () = conn.lpush("innosystem:jobs:p0:pending", &poison).await.unwrap();
+    queue.push_job(job_id, PriorityLevel::Low).await.unwrap();
+    let _: () = conn.zadd("innosystem:jobs:scheduled", &scheduled_poison, 0).await.unwrap();
+
+    let jobs = queue.try_pop_jobs_from(&[PriorityLevel::Low], 100).await.unwrap();
+    assert!(jobs.contains(&(job_id, PriorityLevel::Low)));
+    queue.claim_due_scheduled_jobs(100).await.unwrap();
+
+    let (status, quarantine) = server.get("/admin/queue/quarantine?limit=1000", Some(ADMIN_API_KEY)).await;
+    assert_eq!(status, StatusCode::OK);
+    assert!(quarantine["held"].as_u64().unwrap() >= 2);
+    assert!(quarantine["total"].as_u64().unwrap() >= 2);
+    let entries = quarantine["entries"].as_array().unwrap();
+    let entry = entries.iter().find(|entry| entry["value"] == poison.as_str()).expect("poison entry not quarantined");
+    assert_eq!(entry["source"], "innosystem:jobs:p0:pending");
+    let entry = entries.iter().find(|entry| entry["value"] == scheduled_poison.as_str()).expect("scheduled poison entry not quarantined");
+    assert_eq!(entry["source"], "innosystem:jobs:scheduled");
+
+    let response = server.client.get(server.url("/admin/queue/quarantine/metrics"))
+        .header("X-API-Key", ADMIN_API_KEY)
+        .send()
+        .await
+        .unwrap();
+    assert_eq!(response.status(), StatusCode::OK);
+    assert!(response.text().await.unwrap().contains("# TYPE innosystem_queue_quarantined_total counter"));
+
+    let (status, cleared) = server.send(server.client.delete(server.url("/admin/queue/quarantine")), Some(ADMIN_API_KEY)).await;
+    assert_eq!(status, StatusCode::OK);
+    assert!(cleared["dropped"].as_u64().unwrap() >= 2);
+
+    let (status, _) = server.get("/admin/queue/quarantine", None).await;
+    assert_eq!(status, StatusCode::UNAUTHORIZED);
+}
+
+#[tokio::test]
+#[ignore = "requires Docker or TEST_DATABASE_URL and TEST_REDIS_URL"]
+async fn starving_low_priority_jobs_are_reported() {
+    use diesel::RunQueryDsl;
+    use innosystem_common::models::job::PriorityLevel;
+
+    let (env, server) = start().await;
+    let customer = customer_with_wallet(&env, 5000).await;
+    let job_type = JobTypeFactory::new()
+        .create(&DieselJobTypeRepository::new(env.pool.clone()))
+        .await
+        .unwrap();
+    let job_repo = DieselJobRepository::new(env.pool.clone());
+
+    // A Low priority job that waited two days before a runner claimed it
+    let job = JobFactory::new(customer.id, job_type.id).create(&job_repo).await.unwrap();
+    let mut conn = env.pool.get().unwrap();
+    diesel::sql_query("UPDATE jobs SET created_at = NOW() - INTERVAL '2 days' WHERE id = $1")
+        .bind::<diesel::sql_types::Uuid, _>(job.id)
+        .execute(&mut conn)
+        .unwrap();
+    job_repo.record_claim(job.id, PriorityLevel::Low).await.unwrap();
+
+    let (status, report) = server.get("/admin/queue/wait-times", Some(ADMIN_API_KEY)).await;
+    assert_eq!(status, StatusCode::OK);
+    assert_eq!(report["low_priority_starving"], true);
+    let low = report["priorities"].as_array().unwrap().iter().find(|p| p["priority"] == "low").unwrap();
+    assert!(low["max_secs"].as_f64().unwrap() >= 2.0 * 86400.0);
+
+    let response = server.client.get(server.url("/admin/queue/metrics"))
+        .header("X-API-Key", ADMIN_API_KEY)
+        .send()
+        .await
+        .unwrap();
+    assert_eq!(response.status(), StatusCode::OK);
+    assert!(response.text().await.unwrap().contains("innosystem_queue_wait_seconds{priority=\"low\",quantile=\"0.9\"}"));
+}
+
+#[tokio::test]
+#[ignore = "requires Docker or TEST_DATABASE_URL and TEST_REDIS_URL"]
+async fn the_catalog_lists_job_types_by_visibility() {
+    let (env, server) = start().await;
+    let customer = customer_with_wallet(&env, 1000).await;
+    let category = format!("catalog-{}", uuid::Uuid::new_v4());
+
+    let (status, listed) = server.post("/job-types", Some(ADMIN_API_KEY), json!({
+        "name": format!("Listed {}", category),
+        "description": "Listed in the marketplace",
+        "processor_type": "sync",
+        "standard_cost_cents": 150,
+        "category": category,
+        "documentation_url": "https://docs.example.com/listed",
+        "sample_input": { "text": "hello" },
+    })).await;
+    assert_eq!(status, StatusCode::CREATED);
+    assert_eq!(listed["visibility"], "public");
+
+    let (status, _) = server.post("/job-types", Some(ADMIN_API_KEY), json!({
+        "name": "Bad listing",
+        "description": "Invalid visibility",
+        "processor_type": "sync",
+        "standard_cost_cents": 150,
+        "visibility": "everyone",
+    })).await;
+    assert_eq!(status, StatusCode::BAD_REQUEST);
+
+    let (status, hidden) = server.post("/job-types", Some(ADMIN_API_KEY), json!({
+        "name": format!("Reseller only {}", category),
+        "description": "Offered through resellers",
+        "processor_type": "sync",
+        "standard_cost_cents": 150,
+    })).await;
+    assert_eq!(status, StatusCode::CREATED);
+    let path = format!("/job-types/{}/listing", hidden["id"].as_str().unwrap());
+    let response = server.client.put(server.url(&path))
+        .header("X-API-Key", ADMIN_API_KEY)
+        .json(&json!({ "category": category, "visibility": "reseller" }))
+        .send()
+        .await
+        .unwrap();
+    assert_eq!(response.status(), StatusCode::OK);
+
+    let (status, catalog) = server.get(&format!("/catalog?category={}", category), customer.api_key.as_deref()).await;
+    assert_eq!(status, StatusCode::OK);
+    let catalog = catalog.as_array().unwrap();
+    assert_eq!(catalog.len(), 1);
+    assert_eq!(catalog[0]["id"], listed["id"]);
+    assert_eq!(catalog[0]["sample_input"]["text"], "hello");
+
+    let (_, categories) = server.get("/catalog/categories", customer.api_key.as_deref()).await;
+    assert!(categories.as_array().unwrap().iter().any(|c| c["category"] == category.as_str() && c["job_types"] == 1));
+
+    // Execution statistics are shown for listed job types only
+    let (status, stats) = server.get(&format!("/job-types/{}/stats", listed["id"].as_str().unwrap()), customer.api_key.as_deref()).await;
+    assert_eq!(status, StatusCode::OK);
+    assert_eq!(stats["window_hours"], 24);
+    assert_eq!(stats["succeeded"], 0);
+    assert!(stats["success_rate"].is_null());
+    let (status, _) = server.get(&format!("/job-types/{}/stats", hidden["id"].as_str().unwrap()), customer.api_key.as_deref()).await;
+    assert_eq!(status, StatusCode::NOT_FOUND);
+
+    // Reseller routes also accept the admin key
+    let (status, catalog) = server.get(&format!("/reseller/catalog?category={}", category), Some(ADMIN_API_KEY)).await;
+    assert_eq!(status, StatusCode::OK);
+    assert_eq!(catalog.as_array().unwrap().len(), 2);
+
+    let (status, _) = server.get("/catalog", None).await;
+    assert_eq!(status, StatusCode::UNAUTHORIZED);
+}
+
+#[tokio::test]
+#[ignore = "requires Docker or TEST_DATABASE_URL and TEST_REDIS_URL"]
+async fn sub_accounts_spend_from_the_parent_wallet_within_their_budget() {
+    let (env, server) = start().await;
+    let parent = customer_with_wallet(&env, 5000).await;
+    let parent_key = parent.api_key.as_deref();
+    let job_type = JobTypeFactory::new()
+        .create(&DieselJobTypeRepository::new(env.pool.clone()))
+        .await
+        .unwrap();
+
+    let (status, sub_account) = server.post("/sub-accounts", parent_key, json!({
+        "name": "Marketing",
+        "email": format!("{}@example.com", uuid::Uuid::new_v4()),
+        "budget_cents": 1500,
+    })).await;
+    assert_eq!(status, StatusCode::CREATED);
+    assert_eq!(sub_account["parent_id"], parent.id.to_string());
+    let sub_key = sub_account["api_key"].as_str().unwrap().to_string();
+
+    // Sub-accounts cannot nest
+    let (status, _) = server.post("/sub-accounts", Some(&sub_key), json!({ "name": "Nested", "email": "nested@example.com" })).await;
+    assert_eq!(status, StatusCode::FORBIDDEN);
+
+    // Jobs are estimated at 1000 cents: the second one would exceed the budget
+    let job = json!({ "customer_id": sub_account["id"], "job_type_id": job_type.id, "input_data": {} });
+    let (status, submitted) = server.post("/jobs", Some(&sub_key), job.clone()).await;
+    assert_eq!(status, StatusCode::CREATED);
+    let (status, _) = server.post("/jobs", Some(&sub_key), job.clone()).await;
+    assert_eq!(status, StatusCode::PAYMENT_REQUIRED);
+
+    // Completing the job charges the parent's wallet
+    let (status, _) = server.post("/jobs/complete", Some(&sub_key), json!({
+        "job_id": submitted["id"],
+        "success": true,
+    })).await;
+    assert!(status.is_success());
+    let wallet = DieselWalletRepository::new(env.pool.clone()).find_by_customer_id(parent.id).await.unwrap();
+    assert!(wallet.balance_cents < 5000);
+
+    let path = format!("/sub-accounts/{}/budget", sub_account["id"].as_str().unwrap());
+    let response = server.client.put(server.url(&path))
+        .header("X-API-Key", parent.api_key.as_deref().unwrap())
+        .json(&json!({ "budget_cents": null }))
+        .send()
+        .await
+        .unwrap();
+    assert_eq!(response.status(), StatusCode::OK);
+    let (status, _) = server.post("/jobs", Some(&sub_key), job).await;
+    assert_eq!(status, StatusCode::CREATED);
+
+    let (status, report) = server.get("/sub-accounts/report", parent_key).await;
+    assert_eq!(status, StatusCode::OK);
+    let accounts = report["accounts"].as_array().unwrap();
+    assert_eq!(accounts.len(), 2);
+    let marketing = accounts.iter().find(|a| a["customer_id"] == sub_account["id"]).unwrap();
+    assert_eq!(marketing["jobs"], 2);
+    assert_eq!(report["total_jobs"], 2);
+    assert_eq!(report["wallet_balance_cents"], wallet.balance_cents);
+}
+
+#[tokio::test]
+#[ignore = "requires Docker or TEST_DATABASE_URL and TEST_REDIS_URL"]
+async fn jobs_outside_their_submission_window_are_scheduled() {
+    let (env, server) = start().await;
+    let reseller = ResellerFactory::new()
+        .create(&DieselResellerRepository::new(env.pool.clone()))
+        .await
+        .unwrap();
+    let customer = CustomerFactory::new()
+        .reseller(reseller.id)
+        .create(&DieselCustomerRepository::new(env.pool.clone()))
+        .await
+        .unwrap();
+    let key = customer.api_key.as_deref();
+    let job_type = JobTypeFactory::new()
+        .create(&DieselJobTypeRepository::new(env.pool.clone()))
+        .await
+        .unwrap();
+    let hours_from_now = |hours: i64| (chrono::Utc::now() + chrono::Duration::hours(hours)).format("%H:%M").to_string();
+
+    // The reseller only allows the job type in a window opening in two hours
+    let path = format!("/resellers/{}/submission-windows", reseller.id);
+    let (status, window) = server.post(&path, Some(ADMIN_API_KEY), json!({
+        "job_type_id": job_type.id,
+        "start_time": hours_from_now(2),
+        "end_time": hours_from_now(3),
+    })).await;
+    assert_eq!(status, StatusCode::CREATED);
+    assert_eq!(window["reseller_id"], reseller.id.to_string());
+
+    let job = json!({ "customer_id": customer.id, "job_type_id": job_type.id, "input_data": {} });
+    let (status, deferred) = server.post("/jobs", key, job.clone()).await;
+    assert_eq!(status, StatusCode::CREATED);
+    assert_eq!(deferred["status"], "scheduled");
+    let scheduled_for = chrono::DateTime::parse_from_rfc3339(deferred["scheduled_for"].as_str().unwrap()).unwrap();
+    assert!(scheduled_for > chrono::Utc::now() + chrono::Duration::minutes(60));
+
+    // The customer's own window, open now, takes precedence
+    let (status, own) = server.post("/submission-windows", key, json!({
+        "job_type_id": job_type.id,
+        "start_time": hours_from_now(-1),
+        "end_time": hours_from_now(1),
+    })).await;
+    assert_eq!(status, StatusCode::CREATED);
+    let (status, queued) = server.post("/jobs", key, job).await;
+    assert_eq!(status, StatusCode::CREATED);
+    assert_eq!(queued["status"], "pending");
+    assert!(queued["scheduled_for"].is_null());
+
+    let (status, _) = server.post("/submission-windows", key, json!({
+        "job_type_id": job_type.id,
+        "start_time": "25:00",
+        "end_time": "06:00",
+    })).await;
+    assert_eq!(status, StatusCode::BAD_REQUEST);
+
+    let (_, windows) = server.get("/submission-windows", key).await;
+    assert_eq!(windows.as_array().unwrap().len(), 1);
+    let path = format!("/submission-windows/{}", own["id"].as_str().unwrap());
+    let response = server.client.delete(server.url(&path))
+        .header("X-API-Key", customer.api_key.as_deref().unwrap())
+        .send()
+        .await
+        .unwrap();
+    assert_eq!(response.status(), StatusCode::NO_CONTENT);
+}
+
+#[tokio::test]
+#[ignore = "requires Docker or TEST_DATABASE_URL and TEST_REDIS_URL"]
+async fn submission_windows_follow_the_customers_time_zone() {
+    let (env, server) = start().await;
+    let reseller = ResellerFactory::new()
+        .create(&DieselResellerRepository::new(env.pool.clone()))
+        .await
+        .unwrap();
+    let customer = CustomerFactory::new()
+        .reseller(reseller.id)
+        .create(&DieselCustomerRepository::new(env.pool.clone()))
+        .await
+        .unwrap();
+    let key = customer.api_key.as_deref();
+    let job_type = JobTypeFactory::new()
+        .create(&DieselJobTypeRepository::new(env.pool.clone()))
+        .await
+        .unwrap();
+    let set_timezone = |timezone: &str| {
+        let path = format!("/customers/{}/timezone", customer.id);
+        server.send(server.client.put(server.url(&path)).json(&json!({ "timezone": timezone })), Some(&reseller.api_key))
+    };
+
+    let (status, _) = set_timezone("Mars/Olympus_Mons").await;
+    assert_eq!(status, StatusCode::BAD_REQUEST);
+    // Five hours ahead of UTC all year round
+    let (status, updated) = set_timezone("Etc/GMT-5").await;
+    assert_eq!(status, StatusCode::OK);
+    assert_eq!(updated["timezone"], "Etc/GMT-5");
+
+    // A window open now on the customer's clock, but not in UTC
+    let local_hours_from_now = |hours: i64| (chrono::Utc::now() + chrono::Duration::hours(5 + hours)).format("%H:%M").to_string();
+    let (status, _) = server.post("/submission-windows", key, json!({
+        "job_type_id": job_type.id,
+        "start_time": local_hours_from_now(-1),
+        "end_time": local_hours_from_now(1),
+    })).await;
+    assert_eq!(status, StatusCode::CREATED);
+
+    let job = json!({ "customer_id": customer.id, "job_type_id": job_type.id, "input_data": {} });
+    let (status, queued) = server.post("/jobs", key, job.clone()).await;
+    assert_eq!(status, StatusCode::CREATED);
+    assert_eq!(queued["status"], "pending");
+
+    // Back on UTC the window opens again in about three hours
+    let (status, cleared) = set_timezone("").await;
+    assert_eq!(status, StatusCode::OK);
+    assert!(cleared["timezone"].is_null());
+    let (status, deferred) = server.post("/jobs", key, job).await;
+    assert_eq!(status, StatusCode::CREATED);
+    assert_eq!(deferred["status"], "scheduled");
+}
+
+#[tokio::test]
+#[ignore = "requires Docker or TEST_DATABASE_URL and TEST_REDIS_URL"]
+async fn jobs_without_a_priority_get_the_customers_then_the_job_types_default() {
+    let (env, server) = start().await;
+    let customer = customer_with_wallet(&env, 100_000).await;
+    let key = customer.api_key.as_deref();
+    let job_type = JobTypeFactory::new()
+        .create(&DieselJobTypeRepository::new(env.pool.clone()))
+        .await
+        .unwrap();
+    let put = |path: String, body: Value| server.send(server.client.put(server.url(&path)).json(&body), Some(ADMIN_API_KEY));
+    let job = json!({ "customer_id": customer.id, "job_type_id": job_type.id, "input_data": {} });
+
+    let (status, created) = server.post("/jobs", key, job.clone()).await;
+    assert_eq!(status, StatusCode::CREATED);
+    assert_eq!(created["priority"], "medium");
+    assert_eq!(created["priority_source"], "default");
+
+    let (status, _) = put(format!("/job-types/{}/default-priority", job_type.id), json!({ "default_priority": 7 })).await;
+    assert_eq!(status, StatusCode::UNPROCESSABLE_ENTITY);
+    let (status, updated) = put(format!("/job-types/{}/default-priority", job_type.id), json!({ "default_priority": "low" })).await;
+    assert_eq!(status, StatusCode::OK);
+    assert_eq!(updated["default_priority"], "low");
+    let (_, created) = server.post("/jobs", key, job.clone()).await;
+    assert_eq!(created["priority"], "low");
+    assert_eq!(created["priority_source"], "job_type");
+
+    // A premium customer's default takes precedence over the job type's
+    let (status, updated) = put(format!("/customers/{}/default-priority", customer.id), json!({ "default_priority": "high" })).await;
+    assert_eq!(status, StatusCode::OK);
+    assert_eq!(updated["default_priority"], "high");
+    let (_, created) = server.post("/jobs", key, job.clone()).await;
+    assert_eq!(created["priority"], "high");
+    assert_eq!(created["priority_source"], "customer");
+    let (status, batch) = server.post("/jobs/batch", key, json!({ "jobs": [
+        { "job_type_id": job_type.id, "input_data": {} },
+        { "job_type_id": job_type.id, "input_data": {}, "priority": "critical" },
+    ] })).await;
+    assert_eq!(status, StatusCode::CREATED);
+    assert_eq!(batch["jobs"][0]["priority_source"], "customer");
+    assert_eq!(batch["jobs"][1]["priority"], "critical");
+    assert_eq!(batch["jobs"][1]["priority_source"], "request");
+
+    // Only creation says where the priority came from
+    let (_, fetched) = server.get(&format!("/jobs/{}", created["id"].as_str().unwrap()), key).await;
+    assert!(fetched.get("priority_source").is_none());
+
+    let (status, cleared) = put(format!("/customers/{}/default-priority", customer.id), json!({ "default_priority": null })).await;
+    assert_eq!(status, StatusCode::OK);
+    assert!(cleared["default_priority"].is_null());
+    let (_, created) = server.post("/jobs", key, job).await;
+    assert_eq!(created["priority_source"], "job_type");
+}
+
+#[tokio::test]
+#[ignore = "requires Docker or TEST_DATABASE_URL and TEST_REDIS_URL"]
+async fn resellers_are_invoiced_monthly_for_their_customers_jobs() {
+    let (env, server) = start().await;
+    let reseller = ResellerFactory::new()
+        .commission_rate(1000)
+        .create(&DieselResellerRepository::new(env.pool.clone()))
+        .await
+        .unwrap();
+    let customer_repo = DieselCustomerRepository::new(env.pool.clone());
+    let customers = [
+        CustomerFactory::new().name("Beta").reseller(reseller.id).create(&customer_repo).await.unwrap(),
+        CustomerFactory::new().name("Alpha").reseller(reseller.id).create(&customer_repo).await.unwrap(),
+    ];
+    let job_type = JobTypeFactory::new()
+        .create(&DieselJobTypeRepository::new(env.pool.clone()))
+        .await
+        .unwrap();
+    let job_repo = DieselJobRepository::new(env.pool.clone());
+    let mut completed = None;
+    for (customer, cost_cents) in [(&customers[0], 1000), (&customers[0], 500), (&customers[1], 2000), (&customers[1], 0)] {
+        let job = JobFactory::new(customer.id, job_type.id).create(&job_repo).await.unwrap();
+        completed = job_repo.set_completed(job.id, true, None, None, cost_cents).await.unwrap().completed_at;
+    }
+    let test_job = JobFactory::new(customers[0].id, job_type.id).test_mode().create(&job_repo).await.unwrap();
+    job_repo.set_completed(test_job.id, true, None, None, 700).await.unwrap();
+    let period = completed.unwrap().format("%Y-%m").to_string();
+    let key = Some(reseller.api_key.as_str());
+
+    let (status, invoice) = server.get(&format!("/reseller/invoices/{}", period), key).await;
+    assert_eq!(status, StatusCode::OK);
+    assert_eq!(invoice["reseller_id"], reseller.id.to_string());
+    assert_eq!(invoice["closed"], false);
+    let lines = invoice["lines"].as_array().unwrap();
+    assert_eq!(lines.len(), 2);
+    assert_eq!(lines[0]["customer_name"], "Alpha");
+    assert_eq!(lines[0]["jobs"], 1);
+    assert_eq!(lines[1]["customer_name"], "Beta");
+    assert_eq!(lines[1]["job_type_name"], job_type.name);
+    assert_eq!(lines[1]["jobs"], 2);
+    assert_eq!(lines[1]["charged_cents"], 1500);
+    assert_eq!(invoice["totals"]["charged_cents"], 3500);
+    assert_eq!(invoice["totals"]["commission_cents"], 350);
+    assert_eq!(invoice["totals"]["due_cents"], 3150);
+
+    let (status, _) = server.get("/reseller/invoices/2025-13", key).await;
+    assert_eq!(status, StatusCode::BAD_REQUEST);
+    let (status, _) = server.get(&format!("/reseller/invoices/{}", period), Some(ADMIN_API_KEY)).await;
+    assert_eq!(status, StatusCode::NOT_FOUND);
+
+    let export = |format: &str| server.client
+        .get(server.url(&format!("/reseller/invoices/{}/export?format={}", period, format)))
+        .header("X-API-Key", reseller.api_key.as_str())
+        .send();
+    let csv = export("csv").await.unwrap();
+    assert_eq!(csv.status(), StatusCode::OK);
+    assert_eq!(csv.headers()["content-type"], "text/csv");
+    assert_eq!(csv.text().await.unwrap().lines().count(), 4);
+    let pdf = export("pdf").await.unwrap();
+    assert_eq!(pdf.headers()["content-type"], "application/pdf");
+    assert!(pdf.bytes().await.unwrap().starts_with(b"%PDF-"));
+}
+
+#[tokio::test]
+#[ignore = "requires Docker or TEST_DATABASE_URL and TEST_REDIS_URL"]
+async fn repository_calls_are_exported_as_metrics() {
+    let (env, server) = start().await;
+    let customer = customer_with_wallet(&env, 1000).await;
+
+    let (status, _) = server.get("/jobs", customer.api_key.as_deref()).await;
+    assert_eq!(status, StatusCode::OK);
+
+    let (status, stats) = server.get("/admin/repositories/stats", Some(ADMIN_API_KEY)).await;
+    assert_eq!(status, StatusCode::OK);
+    let methods = stats["methods"].as_array().unwrap();
+    let lookup = methods.iter()
+        .find(|m| m["entity"] == "customer" && m["method"] == "find_by_api_key")
+        .expect("the API key lookup of the request is recorded");
+    assert!(lookup["calls"].as_u64().unwrap() >= 1);
+    assert!(methods.iter().any(|m| m["entity"] == "job" && m["method"] == "query_jobs"));
+
+    let response = server.client.get(server.url("/admin/repositories/metrics"))
+        .header("X-API-Key", ADMIN_API_KEY)
+        .send()
+        .await
+        .unwrap();
+    assert_eq!(response.status(), StatusCode::OK);
+    let metrics = response.text().await.unwrap();
+    assert!(metrics.contains("innosystem_repository_calls_total{entity=\"job\",method=\"query_jobs\"}"));
+    assert!(metrics.contains("# TYPE innosystem_repository_duration_seconds_max gauge"));
+
+    let (status, _) = server.get("/admin/repositories/stats", customer.api_key.as_deref()).await;
+    assert_eq!(status, StatusCode::UNAUTHORIZED);
+}
+
+#[tokio::test]
+#[ignore = "requires Docker or TEST_DATABASE_URL and TEST_REDIS_URL"]
+async fn connection_pools_report_their_utilization() {
+    let (env, server) = start().await;
+    let customer = customer_with_wallet(&env, 1000).await;
+    let (status, _) = server.get("/jobs", customer.api_key.as_deref()).await;
+    assert_eq!(status, StatusCode::OK);
+
+    let (status, pools) = server.get("/admin/pools", Some(ADMIN_API_KEY)).await;
+    assert_eq!(status, StatusCode::OK);
+    assert_eq!(pools["database"]["max_size"], 10);
+    assert!(pools["database"]["checkouts"].as_u64().unwrap() >= 1);
+    assert_eq!(pools["database"]["timeouts"], 0);
+    assert!(pools["redis"]["connections"].as_u64().unwrap() >= 1);
+
+    let response = server.client.get(server.url("/admin/pools/metrics"))
+        .header("X-API-Key", ADMIN_API_KEY)
+        .send()
+        .await
+        .unwrap();
+    assert_eq!(response.status(), StatusCode::OK);
+    let metrics = response.text().await.unwrap();
+    assert!(metrics.contains("innosystem_pool_max_size{pool=\"database\"} 10"));
+    assert!(metrics.contains("# TYPE innosystem_pool_wait_seconds_sum counter"));
+    assert!(!metrics.contains("innosystem_pool_wait_seconds_max{pool=\"redis\"}"));
+
+    let (status, _) = server.get("/admin/pools", customer.api_key.as_deref()).await;
+    assert_eq!(status, StatusCode::UNAUTHORIZED);
+}
+
+#[tokio::test]
+#[ignore = "requires Docker or TEST_DATABASE_URL and TEST_REDIS_URL"]
+async fn customers_of_isolated_resellers_use_their_reseller_schema() {
+    let env = TestEnvironment::start_with_redis().await.expect("failed to start test environment");
+    let server = ApiServer::start_with(&env, &[("TENANCY_MODE", "schema_per_reseller")]).await;
+    let reseller = ResellerFactory::new().create(&DieselResellerRepository::new(env.pool.clone())).await.unwrap();
+    let customer = CustomerFactory::new()
+        .reseller(reseller.id)
+        .create(&DieselCustomerRepository::new(env.pool.clone()))
+        .await
+        .unwrap();
+
+    let (status, body) = server.post(&format!("/admin/resellers/{}/schema", reseller.id), Some(ADMIN_API_KEY), json!({})).await;
+    assert_eq!(status, StatusCode::OK);
+    assert_eq!(body["schema"], format!("reseller_{}", reseller.id.simple()));
+
+    let (status, body) = server.get("/admin/tenancy", Some(ADMIN_API_KEY)).await;
+    assert_eq!(status, StatusCode::OK);
+    assert_eq!(body["mode"], "schema_per_reseller");
+    assert!(body["resellers"].as_array().unwrap().iter().any(|r| r["reseller_id"] == reseller.id.to_string()));
+
+    // The customer's wallet lives in the reseller's schema
+    let config = TenancyConfig { mode: TenancyMode::SchemaPerReseller, ..TenancyConfig::default() };
+    let resolver = Arc::new(SchemaResolver::new(env.pool.clone(), env.database_url.clone(), config));
+    let wallet_repo = DieselWalletRepository::new(TenantPool::new(resolver));
+    with_reseller(Some(reseller.id), WalletFactory::new(customer.id).balance_cents(700).create(&wallet_repo)).await.unwrap();
+
+    let path = format!("/wallets/{}", customer.id);
+    let (status, wallet) = server.get(&path, customer.api_key.as_deref()).await;
+    assert_eq!(status, StatusCode::OK);
+    assert_eq!(wallet["balance_cents"], 700);
+
+    // Admins reach it by naming the reseller they act for
+    assert_eq!(server.get(&path, Some(ADMIN_API_KEY)).await.0, StatusCode::NOT_FOUND);
+    let response = server.client.get(server.url(&path))
+        .header("X-API-Key", ADMIN_API_KEY)
+        .header("X-Reseller-Id", reseller.id.to_string())
+        .send()
+        .await
+        .unwrap();
+    assert_eq!(response.status(), StatusCode::OK);
+}
+
+#[tokio::test]
+#[ignore = "requires Docker or TEST_DATABASE_URL and TEST_REDIS_URL"]
+async fn pipelines_submit_steps_as_their_dependencies_finish() {
+    let (env, server) = start().await;
+    let customer = customer_with_wallet(&env, 100_000).await;
+    let api_key = customer.api_key.as_deref();
+    let job_type_repo = DieselJobTypeRepository::new(env.pool.clone());
+    let ocr = JobTypeFactory::new().create(&job_type_repo).await.unwrap();
+    let summarize = JobTypeFactory::new().create(&job_type_repo).await.unwrap();
+    let job_repo = DieselJobRepository::new(env.pool.clone());
+
+    let upload = |yaml: String| server.send(server.client.post(server.url("/pipelines")).body(yaml), api_key);
+    let cyclic = format!(
+        "name: loop\nsteps:\n  - name: a\n    job_type: {0}\n    depends_on: [b]\n  - name: b\n    job_type: {0}\n    depends_on: [a]\n",
+        ocr.id
+    );
+    assert_eq!(upload(cyclic).await.0, StatusCode::BAD_REQUEST);
+    let unknown_type = "name: unknown\nsteps:\n  - name: a\n    job_type: no-such-type\n".to_string();
+    assert_eq!(upload(unknown_type).await.0, StatusCode::BAD_REQUEST);
+
+    // Steps refer to job types by ID or by name
+    let yaml = format!(r#"
+name: documents
+steps:
+  - name: ocr
+    job_type: {}
+    input:
+      document: "{{{{ input.document }}}}"
+  - name: summarize
+    job_type: "{}"
+    depends_on: [ocr]
+    input:
+      text: "{{{{ steps.ocr.output.text }}}}"
+priority_policy: escalate
+"#, ocr.id, summarize.name);
+    let (status, pipeline) = upload(yaml.clone()).await;
+    assert_eq!(status, StatusCode::CREATED);
+    assert_eq!(pipeline["name"], "documents");
+    assert_eq!(upload(yaml).await.0, StatusCode::CONFLICT);
+
+    let pipeline_path = format!("/pipelines/{}", pipeline["id"].as_str().unwrap());
+    let (status, run) = server.post(&format!("{}/run", pipeline_path), api_key, json!({ "input": { "document": "a.pdf" } })).await;
+    assert_eq!(status, StatusCode::CREATED);
+    assert_eq!(run["status"], "running");
+    assert_eq!(run["steps"][0]["status"], "running");
+    assert_eq!(run["steps"][1]["status"], "pending");
+    // The dependent step runs one level above the run's Medium priority
+    assert_eq!(run["steps"][0]["priority"], "medium");
+    assert_eq!(run["steps"][1]["priority"], "high");
+    let ocr_job: uuid::Uuid = run["steps"][0]["job_id"].as_str().unwrap().parse().unwrap();
+    assert_eq!(job_repo.find_by_id(ocr_job).await.unwrap().job_type_id, ocr.id);
+
+    let (status, _) = server.post("/jobs/complete", api_key, json!({
+        "job_id": ocr_job,
+        "success": true,
+        "output_data": { "text": "Hello" },
+    })).await;
+    assert!(status.is_success());
+
+    // The background loop submits the dependent step once the first succeeded
+    let run_path = format!("{}/runs/{}", pipeline_path, run["id"].as_str().unwrap());
+    let mut run = Value::Null;
+    for _ in 0..120 {
+        run = server.get(&run_path, api_key).await.1;
+        if run["steps"][1]["status"] == "running" {
+            break;
+        }
+        tokio::time::sleep(Duration::from_millis(500)).await;
+    }
+    assert_eq!(run["steps"][0]["status"], "succeeded");
+    assert_eq!(run["steps"][1]["status"], "running");
+    let summarize_job: uuid::Uuid = run["steps"][1]["job_id"].as_str().unwrap().parse().unwrap();
+    assert_eq!(job_repo.find_by_id(summarize_job).await.unwrap().job_type_id, summarize.id);
+
+    let (status, _) = server.post("/jobs/complete", api_key, json!({ "job_id": summarize_job, "success": false })).await;
+    assert!(status.is_success());
+    for _ in 0..120 {
+        run = server.get(&run_path, api_key).await.1;
+        if run["status"] != "running" {
+            break;
+        }
+        tokio::time::sleep(Duration::from_millis(500)).await;
+    }
+    assert_eq!(run["status"], "failed");
+    assert_eq!(run["steps"][1]["status"], "failed");
+    assert!(run["completed_at"].is_string());
+
+    let (status, runs) = server.get(&format!("{}/runs", pipeline_path), api_key).await;
+    assert_eq!(status, StatusCode::OK);
+    assert_eq!(runs.as_array().unwrap().len(), 1);
+
+    let other = customer_with_wallet(&env, 1000).await;
+    assert_eq!(server.get(&pipeline_path, other.api_key.as_deref()).await.0, StatusCode::FORBIDDEN);
+}
+
+#[tokio::test]
+#[ignore = "requires Docker or TEST_DATABASE_URL and TEST_REDIS_URL"]
+async fn csv_imports_submit_a_job_per_row_and_report_each_row() {
+    let (env, server) = start().await;
+    let customer = customer_with_wallet(&env, 100_000).await;
+    let api_key = customer.api_key.as_deref();
+    let job_type = JobTypeFactory::new()
+        .create(&DieselJobTypeRepository::new(env.pool.clone()))
+        .await
+        .unwrap();
+    let job_repo = DieselJobRepository::new(env.pool.clone());
+
+    // A mapped column missing from the file refuses the whole import
+    let (status, _) = server.post("/jobs/import", api_key, json!({
+        "job_type_id": job_type.id,
+        "format": "csv",
+        "mapping": { "email": "recipient" },
+        "content": "name\nAda\n",
+    })).await;
+    assert_eq!(status, StatusCode::BAD_REQUEST);
+
+    let (status, import) = server.post("/jobs/import", api_key, json!({
+        "job_type_id": job_type.id,
+        "format": "csv",
+        "mapping": { "name": "user.name", "city": "user.city" },
+        "content": "name,city,notes\nAda,London,x\n\"Hopper, Grace\",\"New York\",y\nTuring\n",
+    })).await;
+    assert_eq!(status, StatusCode::ACCEPTED);
+    assert_eq!(import["status"], "pending");
+    assert_eq!(import["total_rows"], 3);
+
+    // The background loop submits the rows
+    let import_path = format!("/jobs/imports/{}", import["id"].as_str().unwrap());
+    let mut import = Value::Null;
+    for _ in 0..120 {
+        import = server.get(&import_path, api_key).await.1;
+        if import["status"] == "completed" {
+            break;
+        }
+        tokio::time::sleep(Duration::from_millis(500)).await;
+    }
+    assert_eq!(import["status"], "completed");
+    assert_eq!(import["submitted_rows"], 2);
+    assert_eq!(import["failed_rows"], 1);
+
+    let response = server.client.get(server.url(&format!("{}/report", import_path)))
+        .header("X-API-Key", api_key.unwrap())
+        .send()
+        .await
+        .unwrap();
+    assert_eq!(response.status(), StatusCode::OK);
+    assert_eq!(response.headers()["content-type"], "text/csv");
+    let report = response.text().await.unwrap();
+    let lines: Vec<&str> = report.lines().collect();
+    assert_eq!(lines[0], "row,job_id,job_status,error");
+    assert_eq!(lines[3], "3,,,\"expected 3 columns, found 1\"");
+    let second_job: uuid::Uuid = lines[2].split(',').nth(1).unwrap().parse().unwrap();
+    let job = job_repo.find_by_id(second_job).await.unwrap();
+    assert_eq!(job.job_type_id, job_type.id);
+    assert_eq!(job.input_data, json!({ "user": { "name": "Hopper, Grace", "city": "New York" } }));
+
+    let (status, imports) = server.get("/jobs/imports", api_key).await;
+    assert_eq!(status, StatusCode::OK);
+    assert_eq!(imports.as_array().unwrap().len(), 1);
+
+    let other = customer_with_wallet(&env, 1000).await;
+    assert_eq!(server.get(&import_path, other.api_key.as_deref()).await.0, StatusCode::FORBIDDEN);
+}
+
+#[tokio::test]
+#[ignore = "requires Docker or TEST_DATABASE_URL and TEST_REDIS_URL"]
+async fn jobs_for_saturated_queues_are_rejected_with_a_retry_after() {
+    let env = TestEnvironment::start_with_redis().await.expect("failed to start test environment");
+    let customer = customer_with_wallet(&env, 1000).await;
+    let job_type = JobTypeFactory::new()
+        .create(&DieselJobTypeRepository::new(env.pool.clone()))
+        .await
+        .unwrap();
+    let job_repo = DieselJobRepository::new(env.pool.clone());
+    for _ in 0..2 {
+        JobFactory::new(customer.id, job_type.id).create(&job_repo).await.unwrap();
+    }
+    let limits = format!("{}=2", job_type.id);
+    let server = ApiServer::start_with(&env, &[
+        ("BACKPRESSURE_JOB_TYPE_LIMITS", limits.as_str()),
+        ("BACKPRESSURE_RETRY_AFTER_SECS", "45"),
+    ]).await;
+
+    let response = server.client.post(server.url("/jobs"))
+        .header("X-API-Key", customer.api_key.as_deref().unwrap())
+        .json(&json!({ "customer_id": customer.id, "job_type_id": job_type.id, "input_data": {} }))
+        .send()
+        .await
+        .unwrap();
+    assert_eq!(response.status(), StatusCode::TOO_MANY_REQUESTS);
+    assert_eq!(response.headers()["retry-after"], "45");
+
+    let (status, health) = server.get("/health", None).await;
+    assert_eq!(status, StatusCode::OK);
+    assert_eq!(health["status"], "DEGRADED");
+    assert_eq!(health["queue_saturated"], true);
+
+    let (status, backpressure) = server.get("/admin/queue/backpressure", Some(ADMIN_API_KEY)).await;
+    assert_eq!(status, StatusCode::OK);
+    assert_eq!(backpressure["policy"], "reject");
+    assert_eq!(backpressure["rejected_jobs"], 1);
+    assert_eq!(backpressure["job_types"][0]["depth"], 2);
+    assert_eq!(backpressure["job_types"][0]["saturated"], true);
+    assert_eq!(job_repo.find_by_customer_id(customer.id).await.unwrap().len(), 2);
+}
+
+#[tokio::test]
+#[ignore = "requires Docker or TEST_DATABASE_URL and TEST_REDIS_URL"]
+async fn overdrawn_wallets_under_the_suspend_policy_take_no_new_jobs() {
+    let (env, server) = start().await;
+    let customer = customer_with_wallet(&env, 100).await;
+    let key = customer.api_key.as_deref();
+    let job_type = JobTypeFactory::new()
+        .create(&DieselJobTypeRepository::new(env.pool.clone()))
+        .await
+        .unwrap();
+    let set_policy = |body: Value| {
+        let path = format!("/admin/wallets/{}/overdraft", customer.id);
+        server.send(server.client.put(server.url(&path)).json(&body), Some(ADMIN_API_KEY))
+    };
+
+    let (status, _) = set_policy(json!({ "policy": "suspend", "limit_cents": -1 })).await;
+    assert_eq!(status, StatusCode::BAD_REQUEST);
+    let (status, wallet) = set_policy(json!({ "policy": "suspend", "limit_cents": 2000, "reason": "Invoiced monthly" })).await;
+    assert_eq!(status, StatusCode::OK);
+    assert_eq!(wallet["overdraft_policy"], "suspend");
+    assert_eq!(wallet["submissions_suspended"], false);
+
+    // A charge beyond the balance overdraws the wallet
+    let wallets = DieselWalletRepository::new(env.pool.clone());
+    let wallet_id = wallet["id"].as_str().unwrap().parse().unwrap();
+    wallets.update_balance(wallet_id, -500, TransactionType::JobDebit, None, None).await.unwrap();
+    let (_, wallet) = server.get(&format!("/wallets/{}", customer.id), key).await;
+    assert_eq!((wallet["balance_cents"].as_i64(), wallet["overdraft_cents"].as_i64()), (Some(-400), Some(400)));
+    assert_eq!(wallet["submissions_suspended"], true);
+
+    let job = json!({ "customer_id": customer.id, "job_type_id": job_type.id, "input_data": {} });
+    let (status, body) = server.post("/jobs", key, job.clone()).await;
+    assert_eq!(status, StatusCode::PAYMENT_REQUIRED);
+    assert_eq!(body["error"], "wallet_overdrawn");
+
+    // Topping the wallet up lifts the suspension
+    wallets.deposit(wallet_id, 400, None, None).await.unwrap();
+    let (status, _) = server.post("/jobs", key, job).await;
+    assert_eq!(status, StatusCode::CREATED);
+}
+
+#[tokio::test]
+#[ignore = "requires Docker or TEST_DATABASE_URL and TEST_REDIS_URL"]
+async fn large_wallet_adjustments_post_after_a_second_admin_approves() {
+    let env = TestEnvironment::start_with_redis().await.expect("failed to start test environment");
+    let server = ApiServer::start_with(&env, &[
+        ("ADMIN_API_KEYS", "alice=alice-key,bob=bob-key"),
+        ("WALLET_APPROVAL_THRESHOLD_CENTS", "1000"),
+    ]).await;
+    let customer = customer_with_wallet(&env, 500).await;
+    let wallet_repo = DieselWalletRepository::new(env.pool.clone());
+    let adjustments_path = format!("/admin/wallets/{}/adjustments", customer.id);
+
+    // Up to the threshold an admin posts alone
+    let (status, small) = server.post(&adjustments_path, Some("alice-key"), json!({ "amount_cents": 1000 })).await;
+    assert_eq!(status, StatusCode::CREATED);
+    assert_eq!(small["status"], "approved");
+    assert_eq!(wallet_repo.find_by_customer_id(customer.id).await.unwrap().balance_cents, 1500);
+
+    let (status, refund) = server.post(&adjustments_path, Some("alice-key"), json!({
+        "kind": "refund",
+        "amount_cents": 2500,
+        "description": "Failed render",
+    })).await;
+    assert_eq!(status, StatusCode::CREATED);
+    assert_eq!(refund["status"], "pending");
+    assert_eq!(refund["requested_by"], "alice");
+    assert_eq!(wallet_repo.find_by_customer_id(customer.id).await.unwrap().balance_cents, 1500);
+
+    let (_, pending) = server.get("/admin/wallet-adjustments?status=pending", Some(ADMIN_API_KEY)).await;
+    assert!(pending.as_array().unwrap().iter().any(|adjustment| adjustment["id"] == refund["id"]));
+
+    let approve_path = format!("/admin/wallet-adjustments/{}/approve", refund["id"].as_str().unwrap());
+    let (status, _) = server.post(&approve_path, Some("alice-key"), json!({})).await;
+    assert_eq!(status, StatusCode::FORBIDDEN);
+    let (status, approved) = server.post(&approve_path, Some("bob-key"), json!({ "note": "Confirmed with support" })).await;
+    assert_eq!(status, StatusCode::OK);
+    assert_eq!(approved["status"], "approved");
+    assert_eq!(approved["reviewed_by"], "bob");
+    assert_eq!(wallet_repo.find_by_customer_id(customer.id).await.unwrap().balance_cents, 4000);
+    assert_eq!(server.post(&approve_path, Some("bob-key"), json!({})).await.0, StatusCode::CONFLICT);
+
+    let (status, details) = server.get(&format!("/admin/wallet-adjustments/{}", refund["id"].as_str().unwrap()), Some(ADMIN_API_KEY)).await;
+    assert_eq!(status, StatusCode::OK);
+    let trail: Vec<(&str, &str)> = details["audit_trail"].as_array().unwrap().iter()
+        .map(|entry| (entry["actor"].as_str().unwrap(), entry["action"].as_str().unwrap()))
+        .collect();
+    assert_eq!(trail, vec![("alice", "wallet_adjustment.requested"), ("bob", "wallet_adjustment.approved")]);
+
+    let (_, debit) = server.post(&adjustments_path, Some("bob-key"), json!({ "amount_cents": -3000 })).await;
+    let reject_path = format!("/admin/wallet-adjustments/{}/reject", debit["id"].as_str().unwrap());
+    let (status, rejected) = server.post(&reject_path, Some("alice-key"), json!({ "note": "Not agreed" })).await;
+    assert_eq!(status, StatusCode::OK);
+    assert_eq!(rejected["status"], "rejected");
+    assert_eq!(wallet_repo.find_by_customer_id(customer.id).await.unwrap().balance_cents, 4000);
+
+    assert_eq!(server.post(&adjustments_path, Some("not-an-admin"), json!({ "amount_cents": 10 })).await.0, StatusCode::UNAUTHORIZED);
+}
+
+#[tokio::test]
+#[ignore = "requires Docker or TEST_DATABASE_URL and TEST_REDIS_URL"]
+async fn closed_billing_months_have_final_invoices_and_a_checksummed_ledger() {
+    let (env, server) = start().await;
+    let customer = customer_with_wallet(&env, 1000).await;
+    let wallet_repo = DieselWalletRepository::new(env.pool.clone());
+    let wallet = wallet_repo.find_by_customer_id(customer.id).await.unwrap();
+
+    // A month in the distant past no other test run has closed
+    let year = 1000 + (uuid::Uuid::new_v4().as_u128() % 800) as i32;
+    let in_month = chrono::NaiveDate::from_ymd_opt(year, 3, 15).unwrap().and_hms_opt(12, 0, 0).unwrap().and_utc();
+    wallet_repo.add_transaction(NewWalletTransaction {
+        id: uuid::Uuid::new_v4(),
+        wallet_id: wallet.id,
+        amount_cents: -400,
+        transaction_type: TransactionType::JobDebit.to_string(),
+        customer_id: customer.id,
+        reference_id: None,
+        description: Some("Render".to_string()),
+        job_id: None,
+        created_at: Some(in_month),
+        customer_reference: None,
+        customer_metadata: None,
+    }).await.unwrap();
+
+    assert_eq!(server.post("/admin/billing-periods", Some(ADMIN_API_KEY), json!({ "year": year, "month": 13 })).await.0, StatusCode::BAD_REQUEST);
+    let (status, period) = server.post("/admin/billing-periods", Some(ADMIN_API_KEY), json!({ "year": year, "month": 3 })).await;
+    assert_eq!(status, StatusCode::CREATED);
+    assert_eq!(period["transaction_count"], 1);
+    assert_eq!(period["debits_cents"], 400);
+    assert_eq!(period["closed_by"], "admin");
+    assert_eq!(server.post("/admin/billing-periods", Some(ADMIN_API_KEY), json!({ "year": year, "month": 3 })).await.0, StatusCode::CONFLICT);
+
+    let period_id = period["id"].as_str().unwrap();
+    let (status, details) = server.get(&format!("/admin/billing-periods/{}", period_id), Some(ADMIN_API_KEY)).await;
+    assert_eq!(status, StatusCode::OK);
+    let invoice = details["invoices"].as_array().unwrap().iter().find(|invoice| invoice["customer_id"] == json!(customer.id)).unwrap();
+    assert_eq!(invoice["opening_balance_cents"], 1000);
+    assert_eq!(invoice["closing_balance_cents"], 600);
+
+    let response = server.client.get(server.url(&format!("/admin/billing-periods/{}/ledger", period_id)))
+        .header("X-API-Key", ADMIN_API_KEY)
+        .send()
+        .await
+        .unwrap();
+    assert_eq!(response.status(), StatusCode::OK);
+    assert_eq!(response.headers()["x-ledger-sha256"], period["ledger_sha256"].as_str().unwrap());
+    let csv = response.text().await.unwrap();
+    assert_eq!(csv.lines().count(), 2);
+    assert!(csv.contains(&wallet.id.to_string()));
+
+    let (status, invoices) = server.get("/invoices", customer.api_key.as_deref()).await;
+    assert_eq!(status, StatusCode::OK);
+    assert_eq!(invoices.as_array().unwrap().len(), 1);
+    assert_eq!(invoices[0]["debits_cents"], 400);
+}
+
+#[tokio::test]
+#[ignore = "requires Docker or TEST_DATABASE_URL and TEST_REDIS_URL"]
+async fn the_cluster_overview_lists_the_leader_of_each_background_task() {
+    let env = TestEnvironment::start_with_redis().await.expect("failed to start test environment");
+    let server = ApiServer::start_with(&env, &[
+        ("CLUSTER_INSTANCE_ID", "api-replica-1"),
+        ("CLUSTER_LEADER_ELECTION", "false"),
+    ]).await;
+
+    let (status, _) = server.get("/admin/cluster", None).await;
+    assert_eq!(status, StatusCode::UNAUTHORIZED);
+
+    // Every periodic task competes for leadership on its first tick after startup
+    let mut cluster = Value::Null;
+    for _ in 0..50 {
+        let (status, body) = server.get("/admin/cluster", Some(ADMIN_API_KEY)).await;
+        assert_eq!(status, StatusCode::OK);
+        cluster = body;
+        if cluster["tasks"].as_array().unwrap().len() >= 4 {
+            break;
+        }
+        tokio::time::sleep(Duration::from_millis(100)).await;
+    }
+
+    assert_eq!(cluster["instance_id"], "api-replica-1");
+    assert_eq!(cluster["leader_election"], false);
+    let tasks = cluster["tasks"].as_array().unwrap();
+    for name in ["webhook_retries", "runner_health", "reservation_expiry", "pipeline_runs"] {
+        let task = tasks.iter().find(|t| t["task"] == name).unwrap_or_else(|| panic!("task {} is not listed", name));
+        assert_eq!(task["leader"], "api-replica-1");
+        assert_eq!(task["is_self"], true);
+    }
+}
+
+#[tokio::test]
+#[ignore = "requires Docker or TEST_DATABASE_URL and TEST_REDIS_URL"]
+async fn feature_flags_switch_capabilities_off_for_a_reseller_at_runtime() {
+    let (env, server) = start().await;
+    let reseller = ResellerFactory::new().create(&DieselResellerRepository::new(env.pool.clone())).await.unwrap();
+    let customer = CustomerFactory::new()
+        .reseller(reseller.id)
+        .create(&DieselCustomerRepository::new(env.pool.clone()))
+        .await
+        .unwrap();
+    WalletFactory::new(customer.id)
+        .balance_cents(5000)
+        .create(&DieselWalletRepository::new(env.pool.clone()))
+        .await
+        .unwrap();
+    let direct_customer = customer_with_wallet(&env, 5000).await;
+    let job_type = JobTypeFactory::new()
+        .create(&DieselJobTypeRepository::new(env.pool.clone()))
+        .await
+        .unwrap();
+    let server = &server;
+    let submit = |customer: &Customer| {
+        let body = json!({ "customer_id": customer.id, "job_type_id": job_type.id, "input_data": {} });
+        let api_key = customer.api_key.clone();
+        async move { server.post("/jobs", api_key.as_deref(), body).await }
+    };
+    let set_flag = |name: &str, body: Value| {
+        server.send(server.client.put(server.url(&format!("/admin/feature-flags/{}", name))).json(&body), Some(ADMIN_API_KEY))
+    };
+    let delete_flag = |name: &str| {
+        server.send(server.client.delete(server.url(&format!("/admin/feature-flags/{}", name))), Some(ADMIN_API_KEY))
+    };
+    // Flags are scoped to this test's reseller, so they never reach other tests
+    let scope = json!({ "enabled": false, "reseller_ids": [reseller.id] });
+
+    let (status, flag) = set_flag("job_submission_disabled", scope.clone()).await;
+    assert_eq!(status, StatusCode::OK);
+    assert_eq!(flag["updated_by"], "admin");
+    let (status, body) = submit(&customer).await;
+    assert_eq!(status, StatusCode::SERVICE_UNAVAILABLE);
+    assert_eq!(body["flag"], "job_submission_disabled");
+    assert_eq!(submit(&direct_customer).await.0, StatusCode::CREATED);
+    assert_eq!(delete_flag("job_submission_disabled").await.0, StatusCode::NO_CONTENT);
+    assert_eq!(submit(&customer).await.0, StatusCode::CREATED);
+
+    let processor_flag = format!("processor_disabled.{}", ProcessorType::Sync.as_str());
+    set_flag(&processor_flag, scope.clone()).await;
+    let (status, body) = submit(&customer).await;
+    assert_eq!(status, StatusCode::SERVICE_UNAVAILABLE);
+    assert_eq!(body["flag"], processor_flag);
+    assert_eq!(delete_flag(&processor_flag).await.0, StatusCode::NO_CONTENT);
+
+    // Read-only mode serves reads but refuses writes
+    set_flag("read_only", scope.clone()).await;
+    let wallet_path = format!("/wallets/{}", customer.id);
+    assert_eq!(server.get(&wallet_path, customer.api_key.as_deref()).await.0, StatusCode::OK);
+    let (status, body) = server.post(&format!("{}/deposit", wallet_path), customer.api_key.as_deref(), json!({ "amount": 100 })).await;
+    assert_eq!(status, StatusCode::SERVICE_UNAVAILABLE);
+    assert_eq!(body["error"], "read_only");
+    assert_eq!(delete_flag("read_only").await.0, StatusCode::NO_CONTENT);
+
+    // Canary features are listed for the customers of the resellers they are on for
+    let canary = format!("canary.{}", reseller.id.simple());
+    set_flag(&canary, scope.clone()).await;
+    let (_, features) = server.get("/features", customer.api_key.as_deref()).await;
+    assert!(features.as_array().unwrap().iter().any(|f| *f == canary));
+    let (_, features) = server.get("/features", direct_customer.api_key.as_deref()).await;
+    assert!(!features.as_array().unwrap().iter().any(|f| *f == canary));
+    let (_, flags) = server.get("/admin/feature-flags", Some(ADMIN_API_KEY)).await;
+    assert!(flags.as_array().unwrap().iter().any(|f| f["name"] == canary));
+    assert_eq!(delete_flag(&canary).await.0, StatusCode::NO_CONTENT);
+
+    assert_eq!(set_flag("Canary", scope).await.0, StatusCode::BAD_REQUEST);
+    assert_eq!(delete_flag(&canary).await.0, StatusCode::NOT_FOUND);
+    assert_eq!(server.get("/admin/feature-flags/read_only", None).await.0, StatusCode::UNAUTHORIZED);
+}
+
+#[tokio::test]
+#[ignore = "requires Docker or TEST_DATABASE_URL and TEST_REDIS_URL"]
+async fn error_messages_follow_accept_language_and_the_resellers_locale() {
+    let (env, server) = start().await;
+    let reseller = ResellerFactory::new().create(&DieselResellerRepository::new(env.pool.clone())).await.unwrap();
+    let customer = CustomerFactory::new()
+        .reseller(reseller.id)
+        .create(&DieselCustomerRepository::new(env.pool.clone()))
+        .await
+        .unwrap();
+    let flag_path = server.url("/admin/feature-flags/read_only");
+    let scope = json!({ "enabled": false, "reseller_ids": [reseller.id] });
+    assert_eq!(server.send(server.client.put(&flag_path).json(&scope), Some(ADMIN_API_KEY)).await.0, StatusCode::OK);
+
+    let deposit_path = server.url(&format!("/wallets/{}/deposit", customer.id));
+    let deposit = |accept_language: Option<&str>| {
+        let mut request = server.client.post(&deposit_path)
+            .header("X-API-Key", customer.api_key.as_deref().unwrap())
+            .json(&json!({ "amount": 100 }));
+        if let Some(accept_language) = accept_language {
+            request = request.header("Accept-Language", accept_language);
+        }
+        async move {
+            let response = request.send().await.unwrap();
+            assert_eq!(response.status(), StatusCode::SERVICE_UNAVAILABLE);
+            let language = response.headers()["content-language"].to_str().unwrap().to_string();
+            (language, response.json::<Value>().await.unwrap())
+        }
+    };
+
+    // The code stays the same whatever the language of the message
+    let (language, body) = deposit(None).await;
+    assert_eq!(language, "en");
+    assert_eq!(body["error"], "read_only");
+    assert!(body["message"].as_str().unwrap().contains("read-only mode"));
+
+    let reseller_path = format!("/admin/resellers/{}", reseller.id);
+    let update = |default_locale: &str| {
+        server.send(server.client.put(server.url(&reseller_path)).json(&json!({ "default_locale": default_locale })), Some(ADMIN_API_KEY))
+    };
+    assert_eq!(update("xx").await.0, StatusCode::BAD_REQUEST);
+    let (status, updated) = update("de-DE").await;
+    assert_eq!(status, StatusCode::OK);
+    assert_eq!(updated["default_locale"], "de");
+
+    let (language, body) = deposit(None).await;
+    assert_eq!(language, "de");
+    assert_eq!(body["error"], "read_only");
+    assert!(body["message"].as_str().unwrap().contains("schreibgeschützt"));
+
+    // A supported language named by the request wins over the reseller's default
+    let (language, body) = deposit(Some("it, fr-CH;q=0.9, de;q=0.5")).await;
+    assert_eq!(language, "fr");
+    assert!(body["message"].as_str().unwrap().contains("lecture seule"));
+    let (language, _) = deposit(Some("it")).await;
+    assert_eq!(language, "de");
+
+    assert_eq!(server.send(server.client.delete(&flag_path), Some(ADMIN_API_KEY)).await.0, StatusCode::NO_CONTENT);
+}
+
+#[tokio::test]
+#[ignore = "requires Docker or TEST_DATABASE_URL and TEST_REDIS_URL"]
+async fn job_attempts_are_listed_to_their_owner_and_scored_per_runner() {
+    let (env, server) = start().await;
+    let customer = customer_with_wallet(&env, 1000).await;
+    let other = customer_with_wallet(&env, 1000).await;
+    let job_type = JobTypeFactory::new()
+        .create(&DieselJobTypeRepository::new(env.pool.clone()))
+        .await
+        .unwrap();
+    let runner_repo = DieselRunnerRepository::new(env.pool.clone());
+    let runner = runner_repo.register(NewRunner {
+        id: uuid::Uuid::new_v4(),
+        name: "retrying-runner".to_string(),
+        description: None,
+        status: RunnerStatus::Active.as_str().to_string(),
+        compatible_job_types: Vec::new(),
+    }).await.unwrap();
+    runner_repo.update_heartbeat(runner.id, chrono::Utc::now()).await.unwrap();
+
+    // The first run times out and the second one succeeds
+    let job = JobFactory::new(customer.id, job_type.id)
+        .create(&DieselJobRepository::new(env.pool.clone()))
+        .await
+        .unwrap();
+    let attempt_repo = DieselJobAttemptRepository::new(env.pool.clone());
+    for (success, cost_cents) in [(false, 0), (true, 300)] {
+        let attempt = attempt_repo.start(job.id, Some(runner.id)).await.unwrap();
+        attempt_repo.finish(attempt.id, AttemptOutcome {
+            success,
+            error: (!success).then(|| JobError::provider(codes::PROVIDER_TIMEOUT, "Provider timed out")),
+            cost_cents,
+            finished_at: chrono::Utc::now(),
+        }).await.unwrap();
+    }
+
+    let path = format!("/jobs/{}/attempts", job.id);
+    let (status, body) = server.get(&path, customer.api_key.as_deref()).await;
+    assert_eq!(status, StatusCode::OK);
+    assert_eq!(body["total_cost_cents"], 300);
+    let attempts = body["attempts"].as_array().unwrap();
+    assert_eq!(attempts.len(), 2);
+    assert_eq!(attempts[0]["attempt_number"], 1);
+    assert_eq!(attempts[0]["status"], "failed");
+    assert_eq!(attempts[0]["error"]["code"], codes::PROVIDER_TIMEOUT);
+    assert_eq!(attempts[0]["runner_id"], runner.id.to_string());
+    assert_eq!(attempts[1]["status"], "succeeded");
+    assert!(attempts[1]["duration_ms"].is_number());
+
+    assert_eq!(server.get(&path, other.api_key.as_deref()).await.0, StatusCode::FORBIDDEN);
+
+    // Health is sampled from the runner's attempts, too few yet for a failure rate
+    let (status, health) = server.get(&format!("/runners/{}/health", runner.id), Some(ADMIN_API_KEY)).await;
+    assert_eq!(status, StatusCode::OK);
+    assert_eq!(health["jobs_sampled"], 2);
+    assert!(health["failure_rate"].is_null());
+}
+
+#[tokio::test]
+#[ignore = "requires Docker or TEST_DATABASE_URL and TEST_REDIS_URL"]
+async fn job_outputs_are_redacted_with_the_original_kept_for_admins() {
+    let (env, server) = start().await;
+    let customer = customer_with_wallet(&env, 1000).await;
+    let api_key = customer.api_key.as_deref();
+    let job_type = JobTypeFactory::new()
+        .create(&DieselJobTypeRepository::new(env.pool.clone()))
+        .await
+        .unwrap();
+    let path = format!("/job-types/{}/redaction", job_type.id);
+    let set_rules = |body: Value| server.send(server.client.put(server.url(&path)).json(&body), Some(ADMIN_API_KEY));
+
+    let (status, _) = set_rules(json!({ "rules": ["token"] })).await;
+    assert_eq!(status, StatusCode::BAD_REQUEST);
+    let (status, body) = set_rules(json!({ "rules": ["$.token", "$..password"], "unredacted_retention_hours": 24 })).await;
+    assert_eq!(status, StatusCode::OK);
+    assert_eq!(body["redaction_rules"], json!(["$.token", "$..password"]));
+
+    let job = JobFactory::new(customer.id, job_type.id)
+        .create(&DieselJobRepository::new(env.pool.clone()))
+        .await
+        .unwrap();
+    let original = json!({ "token": "tok_123", "echo": { "password": "hunter2", "user": "ada" } });
+    let (status, completed) = server.post("/jobs/complete", api_key, json!({
+        "job_id": job.id,
+        "success": true,
+        "output_data": original,
+    })).await;
+    assert_eq!(status, StatusCode::OK);
+    let redacted = json!({ "token": "[REDACTED]", "echo": { "password": "[REDACTED]", "user": "ada" } });
+    assert_eq!(completed["output_data"], redacted);
+
+    let unredacted_path = format!("/admin/jobs/{}/unredacted-output", job.id);
+    assert_eq!(server.get(&unredacted_path, api_key).await.0, StatusCode::UNAUTHORIZED);
+    let (status, body) = server.get(&unredacted_path, Some(ADMIN_API_KEY)).await;
+    assert_eq!(status, StatusCode::OK);
+    assert_eq!(body["output_data"], original);
+}
+
+#[tokio::test]
+#[ignore = "requires Docker or TEST_DATABASE_URL and TEST_REDIS_URL"]
+async fn spending_spikes_and_failure_rate_jumps_raise_one_alert_each() {
+    use diesel::RunQueryDsl;
+
+    let env = TestEnvironment::start_with_redis().await.expect("failed to start test environment");
+    let server = ApiServer::start_with(&env, &[
+        ("SPENDING_ANOMALY_CHECK_INTERVAL_SECS", "3600"),
+        ("SPENDING_ANOMALY_MIN_SPEND_CENTS", "1000"),
+        ("SPENDING_ANOMALY_MIN_JOBS", "3"),
+    ]).await;
+    let reseller = ResellerFactory::new()
+        .create(&DieselResellerRepository::new(env.pool.clone()))
+        .await
+        .unwrap();
+    let customer = CustomerFactory::new()
+        .reseller(reseller.id)
+        .create(&DieselCustomerRepository::new(env.pool.clone()))
+        .await
+        .unwrap();
+    let other = customer_with_wallet(&env, 1000).await;
+    let job_type = JobTypeFactory::new()
+        .create(&DieselJobTypeRepository::new(env.pool.clone()))
+        .await
+        .unwrap();
+    let job_repo = DieselJobRepository::new(env.pool.clone());
+
+    // Two days ago the customer spent little and nothing failed
+    let usual = JobFactory::new(customer.id, job_type.id).create(&job_repo).await.unwrap();
+    job_repo.set_completed(usual.id, true, None, None, 100).await.unwrap();
+    let mut conn = env.pool.get().unwrap();
+    diesel::sql_query("UPDATE jobs SET created_at = NOW() - INTERVAL '2 days' WHERE id = $1")
+        .bind::<diesel::sql_types::Uuid, _>(usual.id)
+        .execute(&mut conn)
+        .unwrap();
+
+    // Within the last hour it spent 15 times that and most jobs failed
+    for (success, cost_cents) in [(true, 1500), (false, 0), (false, 0)] {
+        let job = JobFactory::new(customer.id, job_type.id).create(&job_repo).await.unwrap();
+        job_repo.set_completed(job.id, success, None, None, cost_cents).await.unwrap();
+    }
+
+    let (status, _) = server.post("/admin/spending-alerts/detect", Some(ADMIN_API_KEY), json!({})).await;
+    assert_eq!(status, StatusCode::OK);
+    // Detecting again within the cooldown raises nothing new
+    let (status, _) = server.post("/admin/spending-alerts/detect", Some(ADMIN_API_KEY), json!({})).await;
+    assert_eq!(status, StatusCode::OK);
+
+    let (status, alerts) = server.get("/spending-alerts", customer.api_key.as_deref()).await;
+    assert_eq!(status, StatusCode::OK);
+    let alerts = alerts.as_array().unwrap();
+    assert_eq!(alerts.len(), 2);
+    let spike = alerts.iter().find(|alert| alert["kind"] == "spend_spike").unwrap();
+    assert_eq!(spike["observed"], 1500.0);
+    assert!(spike["baseline"].as_f64().unwrap() < 1.0);
+    assert_eq!(spike["reseller_id"], reseller.id.to_string());
+    let jump = alerts.iter().find(|alert| alert["kind"] == "failure_rate_jump").unwrap();
+    assert_eq!(jump["baseline"], 0.0);
+
+    let (status, alerts) = server.get("/spending-alerts", other.api_key.as_deref()).await;
+    assert_eq!(status, StatusCode::OK);
+    assert_eq!(alerts, json!([]));
+
+    let (status, alerts) = server.get(&format!("/resellers/{}/spending-alerts", reseller.id), Some(ADMIN_API_KEY)).await;
+    assert_eq!(status, StatusCode::OK);
+    assert_eq!(alerts.as_array().unwrap().len(), 2);
+    assert_eq!(server.get("/admin/spending-alerts", customer.api_key.as_deref()).await.0, StatusCode::UNAUTHORIZED);
+}
+
+#[tokio::test]
+#[ignore = "requires Docker or TEST_DATABASE_URL and TEST_REDIS_URL"]
+async fn declarative_config_is_planned_applied_and_converges() {
+    let (env, server) = start().await;
+    let reseller = ResellerFactory::new().create(&DieselResellerRepository::new(env.pool.clone())).await.unwrap();
+    let unmanaged = JobTypeFactory::new().create(&DieselJobTypeRepository::new(env.pool.clone())).await.unwrap();
+    let suffix = &uuid::Uuid::new_v4().simple().to_string()[..8];
+    let (job_type, pool, flag) = (format!("cfg-{}", suffix), format!("cfg-pool-{}", suffix), format!("cfg.{}", suffix));
+
+    let document = |cost_cents: i32| format!(r#"
+job_types:
+  {job_type}:
+    processing_logic_id: ocr-v2
+    processor_type: async
+    standard_cost_cents: {cost_cents}
+    category: documents
+runner_pools:
+  {pool}:
+    job_types: [{job_type}]
+feature_flags:
+  {flag}:
+    reseller_ids: [{reseller}]
+"#, reseller = reseller.id);
+    // Other tests share the platform, so only this test's resources are pruned for real
+    let apply = |yaml: String, query: &str| {
+        server.send(server.client.put(server.url(&format!("/admin/config/apply?{}", query))).body(yaml), Some(ADMIN_API_KEY))
+    };
+    let actions = |plan: &Value| -> Vec<(String, String, String)> {
+        plan["changes"].as_array().unwrap().iter()
+            .map(|change| (
+                change["kind"].as_str().unwrap().to_string(),
+                change["name"].as_str().unwrap().to_string(),
+                change["action"].as_str().unwrap().to_string(),
+            ))
+            .collect()
+    };
+    let created = vec![
+        ("job_type".to_string(), job_type.clone(), "create".to_string()),
+        ("runner_pool".to_string(), pool.clone(), "create".to_string()),
+        ("feature_flag".to_string(), flag.clone(), "create".to_string()),
+    ];
+
+    let (status, plan) = apply(document(25), "dry_run=true&prune=false").await;
+    assert_eq!(status, StatusCode::OK);
+    assert_eq!(plan["dry_run"], true);
+    assert_eq!(actions(&plan), created);
+    assert_eq!(server.get(&format!("/admin/feature-flags/{}", flag), Some(ADMIN_API_KEY)).await.0, StatusCode::NOT_FOUND);
+
+    let (status, applied) = apply(document(25), "prune=false").await;
+    assert_eq!(status, StatusCode::OK);
+    assert_eq!(actions(&applied), created);
+    let (_, stored_flag) = server.get(&format!("/admin/feature-flags/{}", flag), Some(ADMIN_API_KEY)).await;
+    assert_eq!(stored_flag["reseller_ids"], json!([reseller.id]));
+    let runners = DieselRunnerRepository::new(env.pool.clone()).list_all().await.unwrap();
+    let runner = runners.iter().find(|runner| runner.name == pool).expect("pool runner registered");
+    assert_eq!(runner.status, RunnerStatus::Active);
+
+    let (_, reapplied) = apply(document(25), "prune=false").await;
+    assert_eq!(reapplied["changes"], json!([]));
+
+    let (_, repriced) = apply(document(30), "dry_run=true&prune=false").await;
+    assert_eq!(repriced["changes"], json!([{ "kind": "job_type", "name": job_type, "action": "update", "fields": ["standard_cost_cents"] }]));
+
+    // Pruning disables what the document leaves out
+    let (_, pruned) = apply(document(25), "dry_run=true").await;
+    assert!(actions(&pruned).contains(&("job_type".to_string(), unmanaged.name.clone(), "disable".to_string())));
+
+    let unknown_job_type = format!("runner_pools:\n  {}:\n    job_types: [no-such-type-{}]\n", pool, suffix);
+    assert_eq!(apply(unknown_job_type, "dry_run=true").await.0, StatusCode::UNPROCESSABLE_ENTITY);
+    assert_eq!(apply("job_types: [".to_string(), "dry_run=true").await.0, StatusCode::BAD_REQUEST);
+    assert_eq!(apply("widgets: {}".to_string(), "dry_run=true").await.0, StatusCode::BAD_REQUEST);
+    assert_eq!(server.send(server.client.put(server.url("/admin/config/apply")).body(""), None).await.0, StatusCode::UNAUTHORIZED);
+}
+
+#[tokio::test]
+#[ignore = "requires Docker or TEST_DATABASE_URL and TEST_REDIS_URL"]
+async fn jobs_not_started_before_their_deadline_expire_and_release_their_funds() {
+    let (env, server) = start().await;
+    let customer = customer_with_wallet(&env, 5000).await;
+    let api_key = customer.api_key.as_deref();
+    let job_type = JobTypeFactory::new()
+        .create(&DieselJobTypeRepository::new(env.pool.clone()))
+        .await
+        .unwrap();
+    let job_repo = DieselJobRepository::new(env.pool.clone());
+    let wallet_repo = DieselWalletRepository::new(env.pool.clone());
+    let wallet = wallet_repo.find_by_customer_id(customer.id).await.unwrap();
+
+    let job = |expires_at: chrono::DateTime<chrono::Utc>| json!({
+        "customer_id": customer.id,
+        "job_type_id": job_type.id,
+        "input_data": { "text": "send reminder" },
+        "expires_at": expires_at.to_rfc3339(),
+    });
+    let (status, _) = server.post("/jobs", api_key, job(chrono::Utc::now() - chrono::Duration::minutes(1))).await;
+    assert_eq!(status, StatusCode::BAD_REQUEST);
+    let deadline = chrono::Utc::now() + chrono::Duration::hours(1);
+    let (status, submitted) = server.post("/jobs", api_key, job(deadline)).await;
+    assert_eq!(status, StatusCode::CREATED);
+    let submitted_deadline = chrono::DateTime::parse_from_rfc3339(submitted["expires_at"].as_str().unwrap()).unwrap();
+    assert_eq!(submitted_deadline.timestamp(), deadline.timestamp());
+
+    let overdue = JobFactory::new(customer.id, job_type.id)
+        .expires_at(chrono::Utc::now() - chrono::Duration::seconds(5))
+        .create(&job_repo)
+        .await
+        .unwrap();
+    let balance = wallet_repo.get_balance(wallet.id).await.unwrap();
+    wallet_repo.reserve_funds(wallet.id, 700, None, Some(overdue.id)).await.unwrap();
+    assert!(job_repo.set_started(overdue.id).await.is_err());
+
+    let (status, expired) = server.post("/admin/jobs/expire", Some(ADMIN_API_KEY), json!({})).await;
+    assert_eq!(status, StatusCode::OK);
+    let expired = expired.as_array().unwrap();
+    // The background sweep may have expired the job first
+    if let Some(entry) = expired.iter().find(|e| e["job_id"] == overdue.id.to_string()) {
+        assert_eq!(entry["released_cents"], 700);
+    }
+    assert!(!expired.iter().any(|e| e["job_id"] == submitted["id"]));
+
+    let (_, stored) = server.get(&format!("/jobs/{}", overdue.public_id), api_key).await;
+    assert_eq!(stored["status"], "expired");
+    assert_eq!(wallet_repo.get_reserved_for_job(overdue.id).await.unwrap(), 0);
+    assert_eq!(wallet_repo.get_balance(wallet.id).await.unwrap(), balance);
+
+    // A second sweep finds nothing left to expire
+    let (_, expired) = server.post("/admin/jobs/expire", Some(ADMIN_API_KEY), json!({})).await;
+    assert!(!expired.as_array().unwrap().iter().any(|e| e["job_id"] == overdue.id.to_string()));
+}
+
+#[tokio::test]
+#[ignore = "requires Docker or TEST_DATABASE_URL and TEST_REDIS_URL"]
+async fn maintenance_premakes_monthly_partitions_of_jobs_and_wallet_transactions() {
+    let (_env, server) = start().await;
+
+    let (status, _) = server.get("/admin/partitions", None).await;
+    assert_eq!(status, StatusCode::UNAUTHORIZED);
+    let (status, maintenance) = server.post("/admin/partitions/maintain", Some(ADMIN_API_KEY), json!({})).await;
+    assert_eq!(status, StatusCode::OK);
+    assert!(maintenance["dropped"].as_array().unwrap().is_empty());
+
+    let current_month = chrono::Utc::now().format("%Y-%m-01").to_string();
+    let (status, tables) = server.get("/admin/partitions", Some(ADMIN_API_KEY)).await;
+    assert_eq!(status, StatusCode::OK);
+    for name in ["jobs", "wallet_transactions"] {
+        let table = tables.as_array().unwrap().iter().find(|t| t["table"] == name).unwrap();
+        assert_eq!(table["retention_months"], Value::Null);
+        assert!(table["months"].as_array().unwrap().iter().any(|m| m == current_month.as_str()));
+    }
+
+    // Partitions already made are left alone
+    let (_, maintenance) = server.post("/admin/partitions/maintain", Some(ADMIN_API_KEY), json!({})).await;
+    assert!(!maintenance["created"].as_array().unwrap().iter().any(|c| c["month"] == current_month.as_str()));
+}
+
+#[tokio::test]
+#[ignore = "requires Docker or TEST_DATABASE_URL and TEST_REDIS_URL"]
+async fn purges_report_and_verify_the_rows_removed_past_retention() {
+    let (_env, server) = start().await;
+
+    let (status, _) = server.post("/admin/purge", None, json!({})).await;
+    assert_eq!(status, StatusCode::UNAUTHORIZED);
+    let (status, _) = server.post("/admin/purge", Some(ADMIN_API_KEY), json!({ "batch_size": 0 })).await;
+    assert_eq!(status, StatusCode::BAD_REQUEST);
+
+    // Without a job retention only expired unredacted outputs are purged
+    let (status, report) = server.post("/admin/purge", Some(ADMIN_API_KEY), json!({ "batch_size": 10 })).await;
+    assert_eq!(status, StatusCode::OK);
+    assert_eq!(report["dry_run"], false);
+    assert!(report["jobs_created_before"].is_null());
+    assert_eq!(report["remaining"]["job_unredacted_outputs"], 0);
+    assert!(report["remaining"].get("jobs").is_none());
+    assert_eq!(report["verified"], true);
+
+    let (status, report) = server.post("/admin/purge", Some(ADMIN_API_KEY), json!({ "dry_run": true })).await;
+    assert_eq!(status, StatusCode::OK);
+    assert_eq!(report["batches"], 0);
+    assert_eq!(report["batch_size"], 1000);
+}
+
+/// Send a request signed with a reseller's signing secret
+async fn send_signed(
+    server: &ApiServer,
+    method: reqwest::Method,
+    path: &str,
+    body: &str,
+    (reseller_id, secret): (uuid::Uuid, &str),
+    timestamp: i64,
+    nonce: &str,
+) -> (StatusCode, Value) {
+    let signature = SignedRequest { method: method.as_str(), path, timestamp, nonce, body: body.as_bytes() }.sign(secret);
+    let request = server.client.request(method, server.url(path))
+        .header("Content-Type", "application/json")
+        .header(request_signature::RESELLER_HEADER, reseller_id.to_string())
+        .header(request_signature::TIMESTAMP_HEADER, timestamp.to_string())
+        .header(request_signature::NONCE_HEADER, nonce)
+        .header(request_signature::SIGNATURE_HEADER, signature)
+        .body(body.to_string());
+    server.send(request, None).await
+}
+
+#[tokio::test]
+#[ignore = "requires Docker or TEST_DATABASE_URL and TEST_REDIS_URL"]
+async fn resellers_can_sign_requests_instead_of_sending_their_api_key() {
+    let (env, server) = start().await;
+    let reseller_repo = DieselResellerRepository::new(env.pool.clone());
+    let reseller = ResellerFactory::new().create(&reseller_repo).await.unwrap();
+    let other = ResellerFactory::new().create(&reseller_repo).await.unwrap();
+    let nonce = || uuid::Uuid::new_v4().simple().to_string();
+    let now = || chrono::Utc::now().timestamp();
+
+    let (status, profile) = server.get("/reseller/profile", Some(&reseller.api_key)).await;
+    assert_eq!(status, StatusCode::OK);
+    assert_eq!(profile["id"], reseller.id.to_string());
+    assert_eq!(profile["request_signing"], false);
+
+    let signing_path = format!("/admin/resellers/{}/signing-secret", reseller.id);
+    let (status, _) = server.post(&signing_path, Some(&reseller.api_key), json!({})).await;
+    assert_eq!(status, StatusCode::UNAUTHORIZED);
+    let (status, signing) = server.post(&signing_path, Some(ADMIN_API_KEY), json!({ "require_signed_requests": true })).await;
+    assert_eq!(status, StatusCode::OK);
+    assert_eq!(signing["require_signed_requests"], true);
+    let secret = signing["signing_secret"].as_str().unwrap().to_string();
+    let key = (reseller.id, secret.as_str());
+
+    // The API key is refused once only signed requests are accepted
+    let (status, _) = server.get("/reseller/profile", Some(&reseller.api_key)).await;
+    assert_eq!(status, StatusCode::UNAUTHORIZED);
+
+    let replayed_nonce = nonce();
+    let timestamp = now();
+    let (status, profile) = send_signed(&server, reqwest::Method::GET, "/reseller/profile", "", key, timestamp, &replayed_nonce).await;
+    assert_eq!(status, StatusCode::OK);
+    assert_eq!(profile["id"], reseller.id.to_string());
+    assert_eq!(profile["request_signing"], true);
+    assert_eq!(profile["require_signed_requests"], true);
+    let (status, _) = send_signed(&server, reqwest::Method::GET, "/reseller/profile", "", key, timestamp, &replayed_nonce).await;
+    assert_eq!(status, StatusCode::UNAUTHORIZED);
+
+    // Customers are created under the signing reseller, whatever the body names
+    let email = format!("signed-{}@example.test", nonce());
+    let body = json!({ "name": "Signed Customer", "email": email, "reseller_id": other.id }).to_string();
+    let (status, customer) = send_signed(&server, reqwest::Method::POST, "/customers", &body, key, now(), &nonce()).await;
+    assert_eq!(status, StatusCode::CREATED);
+    assert_eq!(customer["reseller_id"], reseller.id.to_string());
+    let (status, customers) = send_signed(&server, reqwest::Method::GET, "/customers", "", key, now(), &nonce()).await;
+    assert_eq!(status, StatusCode::OK);
+    assert!(customers.as_array().unwrap().iter().all(|c| c["reseller_id"] == reseller.id.to_string()));
+    assert!(customers.as_array().unwrap().iter().any(|c| c["id"] == customer["id"]));
+
+    // Signatures cover the body, the query string and the timestamp
+    let signature = SignedRequest { method: "POST", path: "/customers", timestamp: now(), nonce: &nonce(), body: b"{}" }.sign(&secret);
+    let forged = server.client.post(server.url("/customers"))
+        .header("Content-Type", "application/json")
+        .header(request_signature::RESELLER_HEADER, reseller.id.to_string())
+        .header(request_signature::TIMESTAMP_HEADER, now().to_string())
+        .header(request_signature::NONCE_HEADER, nonce())
+        .header(request_signature::SIGNATURE_HEADER, signature)
+        .body(body.clone());
+    assert_eq!(server.send(forged, None).await.0, StatusCode::UNAUTHORIZED);
+    let (status, _) = send_signed(&server, reqwest::Method::GET, "/reseller/profile", "", key, now() - 3600, &nonce()).await;
+    assert_eq!(status, StatusCode::UNAUTHORIZED);
+    let (status, _) = send_signed(&server, reqwest::Method::GET, "/reseller/profile", "", (other.id, &secret), now(), &nonce()).await;
+    assert_eq!(status, StatusCode::UNAUTHORIZED);
+
+    // Resellers only reach their own resources
+    let own_alerts = format!("/resellers/{}/spending-alerts", reseller.id);
+    assert_eq!(send_signed(&server, reqwest::Method::GET, &own_alerts, "", key, now(), &nonce()).await.0, StatusCode::OK);
+    let other_alerts = format!("/resellers/{}/spending-alerts", other.id);
+    assert_eq!(send_signed(&server, reqwest::Method::GET, &other_alerts, "", key, now(), &nonce()).await.0, StatusCode::FORBIDDEN);
+    let (status, _) = server.get(&format!("/customers/{}", customer["id"].as_str().unwrap()), Some(&other.api_key)).await;
+    assert_eq!(status, StatusCode::FORBIDDEN);
+
+    // Turning signing off brings the API key back
+    let (status, _) = server.send(server.client.delete(server.url(&signing_path)), Some(ADMIN_API_KEY)).await;
+    assert_eq!(status, StatusCode::NO_CONTENT);
+    let (status, _) = server.get("/reseller/profile", Some(&reseller.api_key)).await;
+    assert_eq!(status, StatusCode::OK);
+    let (status, _) = send_signed(&server, reqwest::Method::GET, "/reseller/profile", "", key, now(), &nonce()).await;
+    assert_eq!(status, StatusCode::UNAUTHORIZED);
+}
+
+#[tokio::test]
+#[ignore = "requires Docker or TEST_DATABASE_URL and TEST_REDIS_URL"]
+async fn jobs_of_a_provider_that_is_down_are_scheduled_until_it_recovers() {
+    let (env, server) = start().await;
+    let customer = customer_with_wallet(&env, 10_000).await;
+    let key = customer.api_key.as_deref();
+    let job_type = JobTypeFactory::new()
+        .create(&DieselJobTypeRepository::new(env.pool.clone()))
+        .await
+        .unwrap();
+    let job_repo = DieselJobRepository::new(env.pool.clone());
+    let put = |path: String, body: Value| {
+        server.send(server.client.put(server.url(&path)).json(&body), Some(ADMIN_API_KEY))
+    };
+
+    // Nothing listens on port 1, so the ping fails
+    let name = format!("geocoder-{}", uuid::Uuid::new_v4().simple());
+    let settings = json!({ "name": name, "ping_url": "http://127.0.0.1:1/ping", "retry_delay_secs": 600 });
+    let (status, provider) = server.post("/admin/providers", Some(ADMIN_API_KEY), settings.clone()).await;
+    assert_eq!(status, StatusCode::CREATED);
+    assert_eq!(provider["status"], "up");
+    let provider_path = format!("/admin/providers/{}", provider["id"].as_str().unwrap());
+    let (status, _) = server.post("/admin/providers", Some(ADMIN_API_KEY), settings).await;
+    assert_eq!(status, StatusCode::CONFLICT);
+    let (status, _) = server.post("/admin/providers", Some(ADMIN_API_KEY), json!({ "name": "x", "failure_rate_threshold": 1.5 })).await;
+    assert_eq!(status, StatusCode::BAD_REQUEST);
+
+    let (status, linked) = put(format!("/job-types/{}/provider", job_type.id), json!({ "provider_id": provider["id"] })).await;
+    assert_eq!(status, StatusCode::OK);
+    assert_eq!(linked["provider_id"], provider["id"]);
+
+    // Until it is checked the provider counts as up
+    let job = json!({ "customer_id": customer.id, "job_type_id": job_type.id, "input_data": {} });
+    let (status, queued) = server.post("/jobs", key, job.clone()).await;
+    assert_eq!(status, StatusCode::CREATED);
+    assert_eq!(queued["status"], "pending");
+
+    let checked = |providers: Value| providers.as_array().unwrap().iter()
+        .find(|checked| checked["id"] == provider["id"])
+        .cloned()
+        .unwrap();
+    let (status, providers) = server.post("/admin/providers/check", Some(ADMIN_API_KEY), json!({})).await;
+    assert_eq!(status, StatusCode::OK);
+    let down = checked(providers);
+    assert_eq!(down["status"], "down");
+    assert!(down["status_reason"].as_str().unwrap().starts_with("Ping failed"));
+
+    let (status, held) = server.post("/jobs", key, job.clone()).await;
+    assert_eq!(status, StatusCode::CREATED);
+    assert_eq!(held["status"], "scheduled");
+    let held_id = uuid::Uuid::parse_str(held["id"].as_str().unwrap()).unwrap();
+    let scheduled_for = job_repo.find_by_id(held_id).await.unwrap().scheduled_for.unwrap();
+    assert!(scheduled_for > chrono::Utc::now() + chrono::Duration::minutes(5));
+
+    // Without a ping URL the failure rate of its recent jobs decides
+    let queued_id = uuid::Uuid::parse_str(queued["id"].as_str().unwrap()).unwrap();
+    job_repo.set_completed(queued_id, false, None, None, 0).await.unwrap();
+    let failed = JobFactory::new(customer.id, job_type.id).create(&job_repo).await.unwrap();
+    job_repo.set_completed(failed.id, false, None, None, 0).await.unwrap();
+    let (status, _) = put(provider_path.clone(), json!({ "name": name, "min_jobs": 2, "retry_delay_secs": 600 })).await;
+    assert_eq!(status, StatusCode::OK);
+    let (_, providers) = server.post("/admin/providers/check", Some(ADMIN_API_KEY), json!({})).await;
+    let still_down = checked(providers);
+    assert_eq!(still_down["status_reason"], "2 of the last 2 jobs failed");
+    assert_eq!(still_down["status_changed_at"], down["status_changed_at"]);
+    // The held job, coming due while the provider stays down, was pushed back
+    assert!(job_repo.find_by_id(held_id).await.unwrap().scheduled_for.unwrap() > scheduled_for);
+
+    let (status, _) = put(provider_path.clone(), json!({ "name": name, "min_jobs": 5 })).await;
+    assert_eq!(status, StatusCode::OK);
+    let (_, providers) = server.post("/admin/providers/check", Some(ADMIN_API_KEY), json!({})).await;
+    let up = checked(providers);
+    assert_eq!(up["status"], "up");
+    assert!(up["status_reason"].is_null());
+    let (status, queued) = server.post("/jobs", key, job).await;
+    assert_eq!(status, StatusCode::CREATED);
+    assert_eq!(queued["status"], "pending");
+
+    let response = server.client.delete(server.url(&provider_path))
+        .header("X-API-Key", ADMIN_API_KEY)
+        .send()
+        .await
+        .unwrap();
+    assert_eq!(response.status(), StatusCode::NO_CONTENT);
+    let (status, _) = server.get(&provider_path, Some(ADMIN_API_KEY)).await;
+    assert_eq!(status, StatusCode::NOT_FOUND);
+}
+
+#[tokio::test]
+#[ignore = "requires Docker or TEST_DATABASE_URL and TEST_REDIS_URL"]
+async fn admins_search_customers_resellers_jobs_and_projects_at_once() {
+    let (env, server) = start().await;
+    let token = uuid::Uuid::new_v4().simple().to_string()[..12].to_string();
+    let reseller = ResellerFactory::new()
+        .name(format!("Reseller {}", token))
+        .create(&DieselResellerRepository::new(env.pool.clone()))
+        .await
+        .unwrap();
+    let customer = CustomerFactory::new()
+        .name(format!("Customer {}", token))
+        .reseller(reseller.id)
+        .create(&DieselCustomerRepository::new(env.pool.clone()))
+        .await
+        .unwrap();
+    let job_type = JobTypeFactory::new()
+        .create(&DieselJobTypeRepository::new(env.pool.clone()))
+        .await
+        .unwrap();
+    let job = JobFactory::new(customer.id, job_type.id)
+        .create(&DieselJobRepository::new(env.pool.clone()))
+        .await
+        .unwrap();
+
+    let (status, results) = server.get(&format!("/admin/search?q={}", token), Some(ADMIN_API_KEY)).await;
+    assert_eq!(status, StatusCode::OK);
+    assert_eq!(results["query"], token);
+    assert_eq!(results["customers"], json!([{
+        "id": customer.id,
+        "name": customer.name,
+        "email": customer.email,
+        "reseller_id": reseller.id,
+    }]));
+    assert_eq!(results["resellers"][0]["id"], reseller.id.to_string());
+    assert_eq!(results["resellers"].as_array().unwrap().len(), 1);
+    assert_eq!(results["jobs"], json!([]));
+    assert_eq!(results["projects"], json!([]));
+    // API keys are not part of the results
+    assert!(results["customers"][0].get("api_key").is_none());
+
+    let (status, results) = server.get(&format!("/admin/search?q={}", job.public_id), Some(ADMIN_API_KEY)).await;
+    assert_eq!(status, StatusCode::OK);
+    assert_eq!(results["jobs"][0]["id"], job.id.to_string());
+    assert_eq!(results["jobs"][0]["status"], "pending");
+
+    let (status, results) = server.get(&format!("/admin/search?q={}&limit=1", customer.id), Some(ADMIN_API_KEY)).await;
+    assert_eq!(status, StatusCode::OK);
+    assert_eq!(results["customers"][0]["id"], customer.id.to_string());
+
+    let (status, _) = server.get("/admin/search?q=%20a%20", Some(ADMIN_API_KEY)).await;
+    assert_eq!(status, StatusCode::BAD_REQUEST);
+    let (status, _) = server.get(&format!("/admin/search?q={}", token), customer.api_key.as_deref()).await;
+    assert_eq!(status, StatusCode::UNAUTHORIZED);
+}
+
+#[tokio::test]
+#[ignore = "requires Docker or TEST_DATABASE_URL and TEST_REDIS_URL"]
+async fn customer_references_follow_deposits_and_jobs_into_the_ledger() {
+    let (env, server) = start().await;
+    let customer = customer_with_wallet(&env, 5000).await;
+    let api_key = customer.api_key.as_deref();
+    let job_type = JobTypeFactory::new()
+        .create(&DieselJobTypeRepository::new(env.pool.clone()))
+        .await
+        .unwrap();
+    let reference = format!("PO-{}", uuid::Uuid::new_v4().simple());
+
+    let (status, wallet) = server.post(&format!("/wallets/{}/deposit", customer.id), api_key, json!({
+        "amount": 2000,
+        "customer_reference": reference,
+        "customer_metadata": { "department": "research" },
+    })).await;
+    assert_eq!(status, StatusCode::OK);
+    assert_eq!(wallet["balance_cents"], 7000);
+
+    let (status, submitted) = server.post("/jobs", api_key, json!({
+        "customer_id": customer.id,
+        "job_type_id": job_type.id,
+        "input_data": {},
+        "customer_reference": reference,
+    })).await;
+    assert_eq!(status, StatusCode::CREATED);
+    assert_eq!(submitted["customer_reference"], reference);
+    let (status, _) = server.post("/jobs/complete", api_key, json!({
+        "job_id": submitted["id"],
+        "success": true,
+    })).await;
+    assert!(status.is_success());
+
+    // The deposit and every transaction of the job carry the reference
+    let (status, transactions) = server.get(&format!("/wallets/{}/transactions?reference={}", customer.id, reference), api_key).await;
+    assert_eq!(status, StatusCode::OK);
+    let transactions = transactions.as_array().unwrap();
+    assert!(transactions.iter().all(|t| t["customer_reference"] == reference));
+    let deposit = transactions.iter().find(|t| t["transaction_type"] == "DEPOSIT").unwrap();
+    assert_eq!(deposit["customer_metadata"], json!({ "department": "research" }));
+    assert!(transactions.iter().any(|t| t["job_id"] == submitted["id"] && t["amount_cents"].as_i64().unwrap() < 0));
+
+    let (status, none) = server.get(&format!("/wallets/{}/transactions?reference=PO-unknown", customer.id), api_key).await;
+    assert_eq!(status, StatusCode::OK);
+    assert_eq!(none, json!([]));
+
+    // References are validated and only their owner finds them
+    let (status, _) = server.post(&format!("/wallets/{}/deposit", customer.id), api_key, json!({
+        "amount": 100,
+        "customer_reference": "  ",
+    })).await;
+    assert_eq!(status, StatusCode::BAD_REQUEST);
+    let (status, _) = server.post("/jobs", api_key, json!({
+        "customer_id": customer.id,
+        "job_type_id": job_type.id,
+        "input_data": {},
+        "customer_metadata": ["not", "an", "object"],
+    })).await;
+    assert_eq!(status, StatusCode::BAD_REQUEST);
+    let other = customer_with_wallet(&env, 100).await;
+    let (status, _) = server.get(&format!("/wallets/{}/transactions?reference={}", customer.id, reference), other.api_key.as_deref()).await;
+    assert_eq!(status, StatusCode::NOT_FOUND);
+}
+
+#[tokio::test]
+#[ignore = "requires Docker or TEST_DATABASE_URL and TEST_REDIS_URL"]
+async fn deposits_retried_with_their_idempotency_key_are_credited_once() {
+    let (env, server) = start().await;
+    let customer = customer_with_wallet(&env, 1000).await;
+    let path = server.url(&format!("/wallets/{}/deposit", customer.id));
+    let deposit = |key: &'static str| {
+        server.send(server.client.post(&path).header("Idempotency-Key", key).json(&json!({ "amount": 500 })), customer.api_key.as_deref())
+    };
+
+    let (status, wallet) = deposit("topup-1").await;
+    assert_eq!(status, StatusCode::OK);
+    assert_eq!(wallet["balance_cents"], 1500);
+    let (status, wallet) = deposit("topup-1").await;
+    assert_eq!(status, StatusCode::OK);
+    assert_eq!(wallet["balance_cents"], 1500);
+
+    let (_, wallet) = deposit("topup-2").await;
+    assert_eq!(wallet["balance_cents"], 2000);
+}
+
+#[tokio::test]
+#[ignore = "requires Docker or TEST_DATABASE_URL and TEST_REDIS_URL"]
+async fn runners_report_their_environment_and_drift_within_a_pool_is_flagged() {
+    let (_env, server) = start().await;
+    let pool = format!("encoders-{}", uuid::Uuid::new_v4());
+    let environment = json!({
+        "os": "linux",
+        "arch": "x86_64",
+        "processors": 8,
+        "libraries": { "ffmpeg": "6.1", "innosystem-runner": "0.1.0" },
+    });
+
+    let mut runner_ids = Vec::new();
+    for _ in 0..2 {
+        let body = json!({ "name": pool, "compatible_job_types": [], "environment": environment });
+        let (status, runner) = server.post("/runners", Some(ADMIN_API_KEY), body).await;
+        assert_eq!(status, StatusCode::CREATED);
+        runner_ids.push(runner["id"].as_str().unwrap().to_string());
+    }
+    let pool_drift = |pools: &Value| pools.as_array().unwrap().iter().find(|p| p["pool"] == pool.as_str()).cloned().unwrap();
+
+    let (status, pools) = server.get("/runners/environment-drift", Some(ADMIN_API_KEY)).await;
+    assert_eq!(status, StatusCode::OK);
+    let drift = pool_drift(&pools);
+    assert_eq!(drift["runners"].as_array().unwrap().len(), 2);
+    assert_eq!(drift["drift"], json!([]));
+
+    // The second runner comes back from its heartbeat with a newer ffmpeg
+    let mut upgraded = environment.clone();
+    upgraded["libraries"]["ffmpeg"] = json!("7.0");
+    let heartbeat = format!("/runners/{}/heartbeat", runner_ids[1]);
+    let (status, _) = server.post(&heartbeat, None, json!({ "environment": upgraded })).await;
+    assert_eq!(status, StatusCode::OK);
+    // Heartbeats without a body are still accepted
+    let response = server.client.post(server.url(&heartbeat)).send().await.unwrap();
+    assert_eq!(response.status(), StatusCode::OK);
+
+    let (_, pools) = server.get("/runners/environment-drift", Some(ADMIN_API_KEY)).await;
+    let drift = pool_drift(&pools);
+    assert_eq!(drift["drift"].as_array().unwrap().len(), 1);
+    assert_eq!(drift["drift"][0]["setting"], "library:ffmpeg");
+    let values = drift["drift"][0]["values"].as_array().unwrap();
+    assert!(values.iter().any(|v| v["value"] == "6.1" && v["runner_ids"] == json!([runner_ids[0]])));
+    assert!(values.iter().any(|v| v["value"] == "7.0" && v["runner_ids"] == json!([runner_ids[1]])));
+
+    let (status, history) = server.get(&format!("/runners/{}/environment", runner_ids[1]), Some(ADMIN_API_KEY)).await;
+    assert_eq!(status, StatusCode::OK);
+    let history = history.as_array().unwrap();
+    assert_eq!(history.len(), 2);
+    assert_eq!(history[0]["environment"]["libraries"]["ffmpeg"], "7.0");
+    assert_eq!(history[1]["environment"]["libraries"]["ffmpeg"], "6.1");
+
+    let (status, _) = server.get(&format!("/runners/{}/environment", uuid::Uuid::new_v4()), Some(ADMIN_API_KEY)).await;
+    assert_eq!(status, StatusCode::NOT_FOUND);
+    let (status, _) = server.get("/runners/environment-drift", None).await;
+    assert_eq!(status, StatusCode::UNAUTHORIZED);
+}
+
+#[tokio::test]
+#[ignore = "requires Docker or TEST_DATABASE_URL and TEST_REDIS_URL"]
+async fn batch_jobs_finish_once_all_their_sub_tasks_did() {
+    let (env, server) = start().await;
+    let customer = customer_with_wallet(&env, 10_000).await;
+    let api_key = customer.api_key.as_deref();
+    let job_type = JobTypeFactory::new()
+        .create(&DieselJobTypeRepository::new(env.pool.clone()))
+        .await
+        .unwrap();
+    let job_repo = DieselJobRepository::new(env.pool.clone());
+
+    // A runner fanned the batch job out into three sub-tasks
+    let batch = JobFactory::new(customer.id, job_type.id).create(&job_repo).await.unwrap();
+    let batch = job_repo.set_started(batch.id).await.unwrap();
+    let sub_tasks = job_repo.fan_out(batch.id, (0..3)
+        .map(|index| batch.sub_task(index, job_type.id, json!({ "page": index }), 250).into())
+        .collect()).await.unwrap();
+
+    let sub_tasks_path = format!("/jobs/{}/sub-tasks", batch.public_id);
+    let (status, progress) = server.get(&sub_tasks_path, api_key).await;
+    assert_eq!(status, StatusCode::OK);
+    assert_eq!(progress["sub_task_count"], 3);
+    assert_eq!(progress["outstanding"], 3);
+    assert_eq!(progress["sub_tasks"][1]["sub_task_index"], 1);
+    let (_, sub_task) = server.get(&format!("/jobs/{}", sub_tasks[0].id), api_key).await;
+    assert_eq!(sub_task["parent_id"], batch.id.to_string());
+
+    for sub_task in &sub_tasks {
+        let (status, _) = server.post("/jobs/complete", api_key, json!({ "job_id": sub_task.id, "success": true })).await;
+        assert!(status.is_success());
+    }
+
+    // The batch job finishes with its last sub-task
+    let mut job = Value::Null;
+    for _ in 0..60 {
+        job = server.get(&format!("/jobs/{}", batch.id), api_key).await.1;
+        if job["status"] != "running" {
+            break;
+        }
+        tokio::time::sleep(Duration::from_millis(500)).await;
+    }
+    assert_eq!(job["status"], "succeeded");
+    assert_eq!(job["cost_cents"], 750);
+    assert_eq!(job["sub_task_count"], 3);
+
+    let (_, progress) = server.get(&sub_tasks_path, api_key).await;
+    assert_eq!(progress["succeeded"], 3);
+    assert_eq!(progress["outstanding"], 0);
+    assert_eq!(progress["cost_cents"], 750);
+
+    let other = customer_with_wallet(&env, 1000).await;
+    assert_eq!(server.get(&sub_tasks_path, other.api_key.as_deref()).await.0, StatusCode::FORBIDDEN);
+}
+
+#[tokio::test]
+#[ignore = "requires Docker or TEST_DATABASE_URL and TEST_REDIS_URL"]
+async fn tunable_settings_are_reloaded_without_a_restart() {
+    let env = TestEnvironment::start_with_redis().await.expect("failed to start test environment");
+    let settings_file = std::env::temp_dir().join(format!("innosystem-{}.env", uuid::Uuid::new_v4()));
+    std::fs::write(&settings_file, "BACKPRESSURE_PRIORITY_LIMITS=0,0,0,0\nFEATURE_FLAG_CACHE_TTL_SECS=5\n").unwrap();
+    let server = ApiServer::start_with(&env, &[("CONFIG_FILE", settings_file.to_str().unwrap())]).await;
+
+    let (status, reloads) = server.get("/admin/config/reload", Some(ADMIN_API_KEY)).await;
+    assert_eq!(status, StatusCode::OK);
+    assert_eq!(reloads["reloads"], 0);
+    assert_eq!(server.get("/admin/queue/backpressure", Some(ADMIN_API_KEY)).await.1["enabled"], false);
+
+    // Edited limits apply on request, without touching the other settings
+    std::fs::write(&settings_file, "BACKPRESSURE_PRIORITY_LIMITS=5,0,0,0\nBACKPRESSURE_POLICY=defer\nFEATURE_FLAG_CACHE_TTL_SECS=5\n").unwrap();
+    let (status, reload) = server.post("/admin/config/reload", Some(ADMIN_API_KEY), json!({})).await;
+    assert_eq!(status, StatusCode::OK);
+    assert_eq!(reload["changed"], json!(["backpressure"]));
+    let (_, backpressure) = server.get("/admin/queue/backpressure", Some(ADMIN_API_KEY)).await;
+    assert_eq!(backpressure["enabled"], true);
+    assert_eq!(backpressure["policy"], "defer");
+
+    // A file with an invalid value is refused as a whole
+    std::fs::write(&settings_file, "BACKPRESSURE_PRIORITY_LIMITS=9,9,9,9\nBACKPRESSURE_POLICY=sometimes\n").unwrap();
+    let (status, _) = server.post("/admin/config/reload", Some(ADMIN_API_KEY), json!({})).await;
+    assert_eq!(status, StatusCode::UNPROCESSABLE_ENTITY);
+    let (_, reloads) = server.get("/admin/config/reload", Some(ADMIN_API_KEY)).await;
+    assert_eq!(reloads["reloads"], 1);
+    assert_eq!(reloads["failures"], 1);
+    assert!(reloads["last_error"].as_str().unwrap().contains("BACKPRESSURE_POLICY"));
+    assert_eq!(reloads["settings"]["backpressure_priority_limits"], json!([5, 0, 0, 0]));
+
+    // SIGHUP reloads the same way
+    std::fs::write(&settings_file, "BACKPRESSURE_PRIORITY_LIMITS=5,0,0,0\nBACKPRESSURE_POLICY=defer\nFEATURE_FLAG_CACHE_TTL_SECS=30\n").unwrap();
+    let killed = Command::new("kill").args(["-HUP", &server.process.id().to_string()]).status().unwrap();
+    assert!(killed.success());
+    let mut reloads = Value::Null;
+    for _ in 0..50 {
+        reloads = server.get("/admin/config/reload", Some(ADMIN_API_KEY)).await.1;
+        if reloads["reloads"] == 2 {
+            break;
+        }
+        tokio::time::sleep(Duration::from_millis(100)).await;
+    }
+    assert_eq!(reloads["last_reload"]["changed"], json!(["feature_flags"]));
+    assert_eq!(reloads["settings"]["feature_flag_cache_ttl_secs"], 30);
+
+    let (status, _) = server.post("/admin/config/reload", None, json!({})).await;
+    assert_eq!(status, StatusCode::UNAUTHORIZED);
+    std::fs::remove_file(&settings_file).unwrap();
+}
+
+#[tokio::test]
+#[ignore = "requires Docker or TEST_DATABASE_URL and TEST_REDIS_URL"]
+async fn repeated_auth_failures_lock_out_their_source_and_are_reported() {
+    let env = TestEnvironment::start_with_redis().await.expect("failed to start test environment");
+    let server = ApiServer::start_with(&env, &[
+        ("SECURITY_MAX_AUTH_FAILURES", "3"),
+        ("SECURITY_TRUST_FORWARDED_FOR", "true"),
+    ]).await;
+    let customer = customer_with_wallet(&env, 1000).await;
+    let from = |ip: &str, api_key: &str| {
+        server.client.get(server.url("/jobs")).header("X-Forwarded-For", ip).header("X-API-Key", api_key)
+    };
+
+    let guessed = "cust_00000000000000000000000000000000";
+    for _ in 0..3 {
+        assert_eq!(server.send(from("203.0.113.7", guessed), None).await.0, StatusCode::UNAUTHORIZED);
+    }
+
+    // Further requests from the address are turned away, even with a valid key
+    let response = from("203.0.113.7", customer.api_key.as_deref().unwrap()).send().await.unwrap();
+    assert_eq!(response.status(), StatusCode::TOO_MANY_REQUESTS);
+    let retry_after: u64 = response.headers()["retry-after"].to_str().unwrap().parse().unwrap();
+    assert!(retry_after > 0 && retry_after <= 60);
+    let body = response.json::<Value>().await.unwrap();
+    assert_eq!(body["error"], "auth_locked_out");
+    assert!(body["message"].is_string());
+
+    // So are other addresses trying the locked out key prefix, while other keys still work from there
+    assert_eq!(server.send(from("198.51.100.1", guessed), None).await.0, StatusCode::TOO_MANY_REQUESTS);
+    assert_eq!(server.send(from("198.51.100.1", customer.api_key.as_deref().unwrap()), None).await.0, StatusCode::OK);
+
+    let (status, events) = server.get("/admin/security/events?kind=lockout", Some(ADMIN_API_KEY)).await;
+    assert_eq!(status, StatusCode::OK);
+    let subjects: Vec<&Value> = events.as_array().unwrap().iter().map(|event| &event["subject"]).collect();
+    assert!(subjects.contains(&&json!({ "type": "source_ip", "value": "203.0.113.7" })));
+    assert!(subjects.contains(&&json!({ "type": "key_prefix", "value": "cust_0000000" })));
+    assert!(events.as_array().unwrap().iter().all(|event| event["lockout_secs"] == 60));
+
+    assert_eq!(server.get("/admin/security/events?kind=sometimes", Some(ADMIN_API_KEY)).await.0, StatusCode::BAD_REQUEST);
+    assert_eq!(server.get("/admin/security/events", customer.api_key.as_deref()).await.0, StatusCode::UNAUTHORIZED);
+}
+
+#[tokio::test]
+#[ignore = "requires Docker or TEST_DATABASE_URL and TEST_REDIS_URL"]
+async fn job_costs_are_itemized_with_credits_markup_and_failure_fees() {
+    let (env, server) = start().await;
+    let job_type = JobTypeFactory::new()
+        .standard_cost_cents(200)
+        .create(&DieselJobTypeRepository::new(env.pool.clone()))
+        .await
+        .unwrap();
+
+    let (status, reseller) = server.post("/admin/resellers", Some(ADMIN_API_KEY), json!({
+        "name": "Marking Up Reseller",
+        "email": format!("{}@example.com", uuid::Uuid::new_v4()),
+        "commission_rate_percentage": 10.0,
+        "markup_rate_percentage": 10.0,
+    })).await;
+    assert_eq!(status, StatusCode::CREATED);
+    assert_eq!(reseller["markup_rate_percentage"], 10.0);
+    let customer = CustomerFactory::new()
+        .reseller(reseller["id"].as_str().unwrap().parse().unwrap())
+        .create(&DieselCustomerRepository::new(env.pool.clone()))
+        .await
+        .unwrap();
+    WalletFactory::new(customer.id)
+        .balance_cents(5000)
+        .create(&DieselWalletRepository::new(env.pool.clone()))
+        .await
+        .unwrap();
+    let api_key = customer.api_key.as_deref();
+
+    let path = format!("/admin/wallets/{}/credit", customer.id);
+    let (status, _) = server.post(&path, Some(ADMIN_API_KEY), json!({ "amount_cents": 0 })).await;
+    assert_eq!(status, StatusCode::BAD_REQUEST);
+    let (status, _) = server.post(&path, api_key, json!({ "amount_cents": 50 })).await;
+    assert_eq!(status, StatusCode::UNAUTHORIZED);
+    let (status, wallet) = server.post(&path, Some(ADMIN_API_KEY), json!({
+        "amount_cents": 50,
+        "reason": "Welcome offer",
+    })).await;
+    assert_eq!(status, StatusCode::OK);
+    assert_eq!(wallet["credit_cents"], 50);
+
+    // A high-priority job: 50% surcharge, then 10% markup, less the credit
+    let (status, submitted) = server.post("/jobs", api_key, json!({
+        "customer_id": customer.id,
+        "job_type_id": job_type.id,
+        "input_data": {},
+        "priority": 2,
+    })).await;
+    assert_eq!(status, StatusCode::CREATED);
+    assert_eq!(submitted["cost_breakdown"], Value::Null);
+    let (status, _) = server.post("/jobs/complete", api_key, json!({ "job_id": submitted["id"], "success": true })).await;
+    assert!(status.is_success());
+
+    let (status, job) = server.get(&format!("/jobs/{}", submitted["id"].as_str().unwrap()), api_key).await;
+    assert_eq!(status, StatusCode::OK);
+    assert_eq!(job["cost_breakdown"], json!({
+        "base_cents": 200,
+        "priority_surcharge_cents": 100,
+        "reseller_markup_cents": 30,
+        "credits_applied_cents": 50,
+        "failure_fee_cents": 0,
+        "total_cents": 280,
+    }));
+    assert_eq!(job["cost_cents"], 280);
+    let (_, wallet) = server.get(&format!("/wallets/{}", customer.id), api_key).await;
+    assert_eq!(wallet["credit_cents"], 0);
+
+    // A failed job is charged a quarter of its estimate and nothing else
+    let (_, submitted) = server.post("/jobs", api_key, json!({
+        "customer_id": customer.id,
+        "job_type_id": job_type.id,
+        "input_data": {},
+    })).await;
+    let (status, _) = server.post("/jobs/complete", api_key, json!({ "job_id": submitted["id"], "success": false })).await;
+    assert!(status.is_success());
+    let (_, job) = server.get(&format!("/jobs/{}", submitted["id"].as_str().unwrap()), api_key).await;
+    assert_eq!(job["cost_breakdown"]["failure_fee_cents"], job["cost_breakdown"]["total_cents"]);
+    assert_eq!(job["cost_breakdown"]["base_cents"], 0);
+    assert!(job["cost_breakdown"]["failure_fee_cents"].as_i64().unwrap() > 0);
+}
+
+#[tokio::test]
+#[ignore = "requires Docker or TEST_DATABASE_URL and TEST_REDIS_URL"]
+async fn fixture_bundles_are_provisioned_and_torn_down_while_their_flag_is_on() {
+    let (_env, server) = start().await;
+    let spec = json!({
+        "name": "checkout-smoke",
+        "reseller": { "markup_rate_percentage": 5.0 },
+        "customers": [
+            { "name": "Ada", "balance_cents": 2500, "jobs": { "pending": 2, "succeeded": 1, "failed": 1 } },
+            { "name": "Grace" },
+        ],
+        "runners": [{ "name": "fixture-runner" }],
+    });
+
+    // The endpoints do not exist until the flag is turned on
+    let (status, _) = server.post("/admin/fixtures", Some(ADMIN_API_KEY), spec.clone()).await;
+    assert_eq!(status, StatusCode::NOT_FOUND);
+    let flag = server.url("/admin/feature-flags/fixtures");
+    let (status, _) = server.send(server.client.put(&flag).json(&json!({ "enabled": true })), Some(ADMIN_API_KEY)).await;
+    assert_eq!(status, StatusCode::OK);
+
+    let (status, _) = server.post("/admin/fixtures", Some(ADMIN_API_KEY), json!({ "name": "empty", "customers": [] })).await;
+    assert_eq!(status, StatusCode::BAD_REQUEST);
+    let (status, bundle) = server.post("/admin/fixtures", Some(ADMIN_API_KEY), spec).await;
+    assert_eq!(status, StatusCode::CREATED);
+    assert_eq!(bundle["job_count"], 4);
+    assert_eq!(bundle["runner_ids"].as_array().unwrap().len(), 1);
+    let customers = bundle["customers"].as_array().unwrap();
+    assert_eq!(customers.len(), 2);
+
+    // Tests authenticate as the bundle's customers and see its data
+    let ada_key = customers[0]["api_key"].as_str().unwrap().to_string();
+    let (status, jobs) = server.get("/jobs", Some(&ada_key)).await;
+    assert_eq!(status, StatusCode::OK);
+    let statuses: Vec<&str> = jobs.as_array().unwrap().iter().map(|job| job["status"].as_str().unwrap()).collect();
+    assert_eq!(statuses.len(), 4);
+    assert!(statuses.contains(&"failed") && statuses.contains(&"succeeded"));
+    let (_, wallet) = server.get(&format!("/wallets/{}", customers[0]["id"].as_str().unwrap()), Some(&ada_key)).await;
+    assert_eq!(wallet["balance_cents"], 2500);
+    let (status, _) = server.get("/reseller/profile", bundle["reseller_api_key"].as_str()).await;
+    assert_eq!(status, StatusCode::OK);
+
+    let path = format!("/admin/fixtures/{}", bundle["id"].as_str().unwrap());
+    let (status, listed) = server.get("/admin/fixtures", Some(ADMIN_API_KEY)).await;
+    assert_eq!(status, StatusCode::OK);
+    assert!(listed.as_array().unwrap().iter().any(|listed| listed["id"] == bundle["id"]));
+    let (status, found) = server.get(&path, Some(ADMIN_API_KEY)).await;
+    assert_eq!(status, StatusCode::OK);
+    assert_eq!(found["name"], "checkout-smoke");
+    assert!(found.get("reseller_api_key").is_none());
+
+    let (status, _) = server.send(server.client.delete(server.url(&path)), Some(ADMIN_API_KEY)).await;
+    assert_eq!(status, StatusCode::NO_CONTENT);
+    let (status, _) = server.get("/jobs", Some(&ada_key)).await;
+    assert_eq!(status, StatusCode::UNAUTHORIZED);
+    let (status, _) = server.get(&path, Some(ADMIN_API_KEY)).await;
+    assert_eq!(status, StatusCode::NOT_FOUND);
+
+    let (status, _) = server.send(server.client.delete(&flag), Some(ADMIN_API_KEY)).await;
+    assert_eq!(status, StatusCode::NO_CONTENT);
+}
+
+#[tokio::test]
+#[ignore = "requires Docker or TEST_DATABASE_URL and TEST_REDIS_URL"]
+async fn dry_runs_simulate_jobs_without_side_effects() {
+    let (env, server) = start().await;
+    let customer = customer_with_wallet(&env, 500).await;
+    let key = customer.api_key.as_deref();
+    let job_type = JobTypeFactory::new()
+        .processor_type(ProcessorType::Webhook)
+        .create(&DieselJobTypeRepository::new(env.pool.clone()))
+        .await
+        .unwrap();
+    let dry_run = |input_data: Value| {
+        server.post("/jobs/dry-run", key, json!({ "customer_id": customer.id, "job_type_id": job_type.id, "input_data": input_data }))
+    };
+
+    // The webhook is not sent, and the balance cannot cover the reservation
+    let (status, simulated) = dry_run(json!({ "webhook_url": "http://127.0.0.1:9/unreachable" })).await;
+    assert_eq!(status, StatusCode::OK);
+    assert_eq!(simulated["output"]["status"], "simulated");
+    assert_eq!(simulated["cost_cents"], 1000);
+    assert_eq!(simulated["rejections"], json!(["insufficient_funds"]));
+    assert_eq!(simulated["accepted"], false);
+
+    let (status, invalid) = dry_run(json!({})).await;
+    assert_eq!(status, StatusCode::OK);
+    assert_eq!(invalid["error"]["code"], "missing_field");
+    assert_eq!(invalid["cost_cents"], 0);
+
+    // Nothing was reserved or charged
+    let (_, wallet) = server.get(&format!("/wallets/{}", customer.id), key).await;
+    assert_eq!(wallet["balance_cents"], 500);
+}
+
+#[tokio::test]
+#[ignore = "requires Docker or TEST_DATABASE_URL and TEST_REDIS_URL"]
+async fn webhook_jobs_send_the_request_of_their_job_types_template() {
+    let (env, server) = start().await;
+    let customer = customer_with_wallet(&env, 100_000).await;
+    let key = customer.api_key.as_deref();
+    let job_types = DieselJobTypeRepository::new(env.pool.clone());
+    let job_type = JobTypeFactory::new().processor_type(ProcessorType::Webhook).create(&job_types).await.unwrap();
+    let sync = JobTypeFactory::new().create(&job_types).await.unwrap();
+    let put = |id: uuid::Uuid, body: Value| {
+        server.send(server.client.put(server.url(&format!("/job-types/{}/webhook-template", id))).json(&body), Some(ADMIN_API_KEY))
+    };
+    let dry_run = || server.post("/jobs/dry-run", key, json!({
+        "customer_id": customer.id,
+        "job_type_id": job_type.id,
+        "input_data": { "webhook_url": "http://127.0.0.1:9/hook", "order": { "id": 42 } },
+    }));
+
+    // Without a template the input is posted as is
+    let (_, simulated) = dry_run().await;
+    assert_eq!(simulated["output"]["method"], "POST");
+    assert_eq!(simulated["output"]["payload"], json!({ "order": { "id": 42 } }));
+
+    let template = json!({
+        "method": "PUT",
+        "headers": { "Authorization": "Bearer secret" },
+        "body": { "order_id": "{{ input.order.id }}", "reference": "order-{{ input.order.id }}" },
+    });
+    assert_eq!(put(sync.id, json!({ "webhook_template": template })).await.0, StatusCode::BAD_REQUEST);
+    let (status, _) = put(job_type.id, json!({ "webhook_template": { "body": { "x": "{{ customer.id }}" } } })).await;
+    assert_eq!(status, StatusCode::BAD_REQUEST);
+    let (status, _) = put(job_type.id, json!({ "webhook_template": { "method": "TRACE" } })).await;
+    assert_eq!(status, StatusCode::UNPROCESSABLE_ENTITY);
+    let (status, updated) = put(job_type.id, json!({ "webhook_template": template })).await;
+    assert_eq!(status, StatusCode::OK);
+    assert_eq!(updated["webhook_template"]["method"], "PUT");
+
+    // The simulation names the headers without their values
+    let (_, simulated) = dry_run().await;
+    assert_eq!(simulated["output"]["method"], "PUT");
+    assert_eq!(simulated["output"]["headers"], json!(["Authorization"]));
+    assert_eq!(simulated["output"]["payload"], json!({ "order_id": 42, "reference": "order-42" }));
+
+    let (status, cleared) = put(job_type.id, json!({ "webhook_template": null })).await;
+    assert_eq!(status, StatusCode::OK);
+    assert!(cleared["webhook_template"].is_null());
+}
+
+#[tokio::test]
+#[ignore = "requires Docker or TEST_DATABASE_URL and TEST_REDIS_URL"]
+async fn binary_webhook_responses_are_downloaded_as_artifacts_of_the_job() {
+    let (env, server) = start().await;
+    let customer = customer_with_wallet(&env, 100_000).await;
+    let other = customer_with_wallet(&env, 100_000).await;
+    let job_type = JobTypeFactory::new()
+        .processor_type(ProcessorType::Webhook)
+        .create(&DieselJobTypeRepository::new(env.pool.clone()))
+        .await
+        .unwrap();
+    let put = |body: Value| {
+        let path = format!("/job-types/{}/response-policy", job_type.id);
+        server.send(server.client.put(server.url(&path)).json(&body), Some(ADMIN_API_KEY))
+    };
+
+    let (status, _) = put(json!({ "response_policy": { "max_captured_bytes": 0 } })).await;
+    assert_eq!(status, StatusCode::BAD_REQUEST);
+    let (status, _) = put(json!({ "response_policy": { "binary": "inline" } })).await;
+    assert_eq!(status, StatusCode::UNPROCESSABLE_ENTITY);
+    let (status, updated) = put(json!({ "response_policy": { "max_captured_bytes": 1024, "binary": "discard" } })).await;
+    assert_eq!(status, StatusCode::OK);
+    assert_eq!(updated["response_policy"]["max_captured_bytes"], 1024);
+    assert_eq!(updated["response_policy"]["parse_json"], true);
+
+    // The artifact a runner stored is only served to the job's customer
+    let job = JobFactory::new(customer.id, job_type.id)
+        .create(&DieselJobRepository::new(env.pool.clone()))
+        .await
+        .unwrap();
+    let artifact = NewJobArtifact::new(job.id, "image/png".to_string(), vec![0x89, b'P', b'N', b'G']);
+    let artifact_id = artifact.id;
+    DieselJobArtifactRepository::new(env.pool.clone()).create(artifact).await.unwrap();
+    let path = format!("/jobs/{}/artifacts/{}", job.id, artifact_id);
+
+    let response = server.client.get(server.url(&path))
+        .header("X-API-Key", customer.api_key.as_deref().unwrap())
+        .send()
+        .await
+        .unwrap();
+    assert_eq!(response.status(), StatusCode::OK);
+    assert_eq!(response.headers()["content-type"], "image/png");
+    assert_eq!(response.bytes().await.unwrap().as_ref(), &[0x89, b'P', b'N', b'G']);
+
+    assert_eq!(server.get(&path, other.api_key.as_deref()).await.0, StatusCode::FORBIDDEN);
+    let unknown = format!("/jobs/{}/artifacts/{}", job.id, uuid::Uuid::new_v4());
+    assert_eq!(server.get(&unknown, customer.api_key.as_deref()).await.0, StatusCode::NOT_FOUND);
+}
+
+#[tokio::test]
+#[ignore = "requires Docker or TEST_DATABASE_URL and TEST_REDIS_URL"]
+async fn billing_exports_reconcile_finalized_charges() {
+    let (_env, server) = start().await;
+
+    let (status, reconciliation) = server.get("/admin/billing-exports/reconciliation?limit=10", Some(ADMIN_API_KEY)).await;
+    assert_eq!(status, StatusCode::OK);
+    assert_eq!(reconciliation["finalized_jobs"], 0);
+    assert_eq!(reconciliation["uncaptured"], 0);
+    assert_eq!(reconciliation["unexported"], json!([]));
+
+    let (status, _) = server.get(
+        "/admin/billing-exports/reconciliation?from=2025-02-01T00:00:00Z&to=2025-01-01T00:00:00Z",
+        Some(ADMIN_API_KEY),
+    ).await;
+    assert_eq!(status, StatusCode::BAD_REQUEST);
+
+    let (status, _) = server.post(&format!("/admin/billing-exports/{}/retry", uuid::Uuid::new_v4()), Some(ADMIN_API_KEY), json!({})).await;
+    assert_eq!(status, StatusCode::NOT_FOUND);
+}
+
+#[tokio::test]
+#[ignore = "requires Docker or TEST_DATABASE_URL and TEST_REDIS_URL"]
+async fn customers_choose_how_they_are_notified() {
+    let (env, server) = start().await;
+    let customer = customer_with_wallet(&env, 1000).await;
+    let api_key = customer.api_key.as_deref();
+
+    let (status, preferences) = server.get("/notifications/preferences", api_key).await;
+    assert_eq!(status, StatusCode::OK);
+    let preferences = preferences.as_array().unwrap();
+    assert!(preferences.iter().all(|preference| preference["mode"] == "immediate" && preference["customized"] == false));
+
+    // Stop per-job emails while keeping failure alerts
+    let (status, preference) = server.send(
+        server.client.put(server.url("/notifications/preferences/job.succeeded/email")).json(&json!({ "mode": "off" })),
+        api_key,
+    ).await;
+    assert_eq!(status, StatusCode::OK);
+    assert_eq!(preference["mode"], "off");
+
+    let (_, preferences) = server.get("/notifications/preferences", api_key).await;
+    let mode = |event_type: &str, channel: &str| preferences.as_array().unwrap().iter()
+        .find(|preference| preference["event_type"] == event_type && preference["channel"] == channel)
+        .map(|preference| preference["mode"].clone())
+        .unwrap();
+    assert_eq!(mode("job.succeeded", "email"), "off");
+    assert_eq!(mode("job.failed", "email"), "immediate");
+    assert_eq!(mode("job.succeeded", "webhook"), "immediate");
+
+    let (status, _) = server.send(
+        server.client.put(server.url("/notifications/preferences/webhook.test/email")).json(&json!({ "mode": "off" })),
+        api_key,
+    ).await;
+    assert_eq!(status, StatusCode::BAD_REQUEST);
+
+    let (status, _) = server.send(server.client.delete(server.url("/notifications/preferences/job.succeeded/email")), api_key).await;
+    assert_eq!(status, StatusCode::NO_CONTENT);
+    let (status, _) = server.send(server.client.delete(server.url("/notifications/preferences/job.succeeded/email")), api_key).await;
+    assert_eq!(status, StatusCode::NOT_FOUND);
+}
+
+#[tokio::test]
+#[ignore = "requires Docker or TEST_DATABASE_URL and TEST_REDIS_URL"]
+async fn resource_usage_reported_by_runners_is_shown_per_job_and_summarized() {
+    let (env, server) = start().await;
+    let customer = customer_with_wallet(&env, 1000).await;
+    let job_type = JobTypeFactory::new()
+        .create(&DieselJobTypeRepository::new(env.pool.clone()))
+        .await
+        .unwrap();
+    let api_key = customer.api_key.as_deref();
+
+    let (status, submitted) = server.post("/jobs", api_key, json!({
+        "customer_id": customer.id,
+        "job_type_id": job_type.id,
+        "input_data": {},
+    })).await;
+    assert_eq!(status, StatusCode::CREATED);
+    assert!(submitted.get("usage").is_none());
+
+    let usage = json!({
+        "wall_time_ms": 1500,
+        "cpu_time_ms": 900,
+        "peak_memory_bytes": 52_428_800,
+        "bytes_sent": 120,
+        "bytes_received": 4096,
+    });
+    let (status, completed) = server.post("/jobs/complete", api_key, json!({
+        "job_id": submitted["id"],
+        "success": true,
+        "usage": usage,
+    })).await;
+    assert!(status.is_success());
+    assert_eq!(completed["usage"], usage);
+
+    let (status, job) = server.get(&format!("/jobs/{}", submitted["id"].as_str().unwrap()), api_key).await;
+    assert_eq!(status, StatusCode::OK);
+    assert_eq!(job["usage"], usage);
+
+    let (status, _) = server.get("/admin/usage", api_key).await;
+    assert_eq!(status, StatusCode::UNAUTHORIZED);
+    let (status, report) = server.get(&format!("/admin/usage?customer_id={}", customer.id), Some(ADMIN_API_KEY)).await;
+    assert_eq!(status, StatusCode::OK);
+    assert_eq!(report["summaries"], json!([{
+        "customer_id": customer.id,
+        "job_type_id": job_type.id,
+        "jobs": 1,
+        "wall_time_ms": 1500,
+        "cpu_time_ms": 900,
+        "peak_memory_bytes": 52_428_800,
+        "bytes_sent": 120,
+        "bytes_received": 4096,
+    }]));
+}
+
+#[tokio::test]
+#[ignore = "requires Docker or TEST_DATABASE_URL and TEST_REDIS_URL"]
+async fn load_windows_warm_their_pool_up_and_hold_jobs_until_it_is_ready() {
+    let (env, server) = start().await;
+    let customer = customer_with_wallet(&env, 1000).await;
+    let job_type = JobTypeFactory::new()
+        .create(&DieselJobTypeRepository::new(env.pool.clone()))
+        .await
+        .unwrap();
+    let job_repo = DieselJobRepository::new(env.pool.clone());
+    let pool = format!("batch-{}", uuid::Uuid::new_v4().simple());
+
+    // Starting right away, so the next check is after the start
+    let starts_at = chrono::Utc::now() + chrono::Duration::seconds(10);
+    let window = json!({
+        "name": "Nightly batch",
+        "pool": pool,
+        "job_type_ids": [job_type.id],
+        "target_runners": 500,
+        "starts_at": starts_at.to_rfc3339(),
+        "ends_at": (starts_at + chrono::Duration::hours(1)).to_rfc3339(),
+    });
+    let (status, created) = server.post("/admin/load-windows", Some(ADMIN_API_KEY), window.clone()).await;
+    assert_eq!(status, StatusCode::CREATED);
+    assert_eq!(created["status"], "planned");
+    assert_eq!(created["warm_up_minutes"], 15);
+    let path = format!("/admin/load-windows/{}", created["id"].as_str().unwrap());
+
+    let mut invalid = window.clone();
+    invalid["ends_at"] = json!((starts_at - chrono::Duration::hours(1)).to_rfc3339());
+    let (status, _) = server.post("/admin/load-windows", Some(ADMIN_API_KEY), invalid).await;
+    assert_eq!(status, StatusCode::BAD_REQUEST);
+    let mut invalid = window.clone();
+    invalid["job_type_ids"] = json!([uuid::Uuid::new_v4()]);
+    let (status, _) = server.post("/admin/load-windows", Some(ADMIN_API_KEY), invalid).await;
+    assert_eq!(status, StatusCode::BAD_REQUEST);
+
+    // A job of the window coming due before the pool is ready is held back
+    let job = JobFactory::new(customer.id, job_type.id).create(&job_repo).await.unwrap();
+    job_repo.schedule(job.id, starts_at + chrono::Duration::seconds(5)).await.unwrap();
+
+    let (status, windows) = server.post("/admin/load-windows/check", Some(ADMIN_API_KEY), json!({})).await;
+    assert_eq!(status, StatusCode::OK);
+    let checked = windows.as_array().unwrap().iter().find(|checked| checked["id"] == created["id"]).unwrap();
+    assert_eq!(checked["status"], "warming");
+    assert_eq!(checked["active_runners"], 0);
+    assert!(checked["notified_at"].is_string());
+    let scheduled_for = job_repo.find_by_id(job.id).await.unwrap().scheduled_for.unwrap();
+    assert!(scheduled_for > (starts_at + chrono::Duration::seconds(5)));
+
+    let (status, advice) = server.get("/admin/autoscaling", Some(ADMIN_API_KEY)).await;
+    assert_eq!(status, StatusCode::OK);
+    let target = advice["pool_targets"].as_array().unwrap().iter()
+        .find(|target| target["load_window_id"] == created["id"])
+        .expect("load window missing from advice");
+    assert_eq!(target["pool"], pool.as_str());
+    assert_eq!(target["target_runners"], 500);
+    assert!(advice["desired_runners"].as_u64().unwrap() >= 500);
+
+    // Cancelling stops holding its jobs back, and only works once
+    let (status, cancelled) = server.send(server.client.delete(server.url(&path)), Some(ADMIN_API_KEY)).await;
+    assert_eq!(status, StatusCode::OK);
+    assert_eq!(cancelled["status"], "cancelled");
+    let (status, _) = server.send(server.client.delete(server.url(&path)), Some(ADMIN_API_KEY)).await;
+    assert_eq!(status, StatusCode::CONFLICT);
+    let (_, advice) = server.get("/admin/autoscaling", Some(ADMIN_API_KEY)).await;
+    assert!(!advice["pool_targets"].as_array().unwrap().iter().any(|target| target["load_window_id"] == created["id"]));
+}
+
+#[tokio::test]
+#[ignore = "requires Docker or TEST_DATABASE_URL and TEST_REDIS_URL"]
+async fn request_bodies_with_unknown_or_invalid_fields_are_rejected_with_the_fields() {
+    let (env, server) = start().await;
+    let customer = customer_with_wallet(&env, 1000).await;
+    let key = customer.api_key.as_deref();
+    let job_type = JobTypeFactory::new()
+        .create(&DieselJobTypeRepository::new(env.pool.clone()))
+        .await
+        .unwrap();
+
+    // A misspelled field is refused instead of falling back to the default priority
+    let (status, body) = server.post("/jobs", key, json!({
+        "customer_id": customer.id,
+        "job_type_id": job_type.id,
+        "input_data": {},
+        "priorty": 3,
+    })).await;
+    assert_eq!(status, StatusCode::UNPROCESSABLE_ENTITY);
+    assert_eq!(body["error"], "invalid_request_body");
+    assert_eq!(body["fields"][0]["field"], "priorty");
+    assert!(body["message"].is_string());
+
+    let job = |priority: Value| json!({ "customer_id": customer.id, "job_type_id": job_type.id, "input_data": {}, "priority": priority });
+    let (status, body) = server.post("/jobs", key, job(json!(7))).await;
+    assert_eq!(status, StatusCode::UNPROCESSABLE_ENTITY);
+    assert_eq!(body["fields"][0]["field"], "priority");
+    let (status, body) = server.post("/jobs", key, job(json!("urgent"))).await;
+    assert_eq!(status, StatusCode::UNPROCESSABLE_ENTITY);
+    assert_eq!(body["fields"][0]["field"], "priority");
+    let (status, submitted) = server.post("/jobs", key, job(json!("high"))).await;
+    assert_eq!(status, StatusCode::CREATED);
+    assert_eq!(submitted["priority"], "high");
+    // Levels are deprecated but still accepted
+    let (status, submitted) = server.post("/jobs", key, job(json!(3))).await;
+    assert_eq!(status, StatusCode::CREATED);
+    assert_eq!(submitted["priority"], "critical");
+
+    let (status, body) = server.post("/jobs", key, json!({ "customer_id": customer.id, "input_data": {} })).await;
+    assert_eq!(status, StatusCode::UNPROCESSABLE_ENTITY);
+    assert_eq!(body["fields"][0]["field"], "job_type_id");
+
+    // Unknown processor types never reach the handler
+    let (status, body) = server.post("/job-types", Some(ADMIN_API_KEY), json!({
+        "name": format!("strict-{}", uuid::Uuid::new_v4()),
+        "description": "Unknown processor",
+        "processor_type": "grpc",
+        "standard_cost_cents": 100,
+    })).await;
+    assert_eq!(status, StatusCode::UNPROCESSABLE_ENTITY);
+    assert_eq!(body["fields"][0]["field"], "processor_type");
+    assert!(body["fields"][0]["message"].as_str().unwrap().contains("webhook"));
+
+    // Bodies that are not JSON at all are malformed rather than invalid
+    let request = server.client.post(server.url("/jobs")).header("Content-Type", "application/json").body("{\"customer_id\":");
+    let (status, body) = server.send(request, key).await;
+    assert_eq!(status, StatusCode::BAD_REQUEST);
+    assert_eq!(body["error"], "malformed_request_body");
+}
+
+#[tokio::test]
+#[ignore = "requires Docker or TEST_DATABASE_URL and TEST_REDIS_URL"]
+async fn batches_are_tracked_and_cancelled_as_one_submission() {
+    let (env, server) = start().await;
+    let customer = customer_with_wallet(&env, 100_000).await;
+    let key = customer.api_key.as_deref();
+    let job_type = JobTypeFactory::new()
+        .create(&DieselJobTypeRepository::new(env.pool.clone()))
+        .await
+        .unwrap();
+
+    // One invalid job refuses the whole batch
+    let (status, _) = server.post("/jobs/batch", key, json!({ "jobs": [] })).await;
+    assert_eq!(status, StatusCode::BAD_REQUEST);
+    let (status, _) = server.post("/jobs/batch", key, json!({ "jobs": [
+        { "job_type_id": job_type.id, "input_data": {} },
+        { "job_type_id": job_type.id, "input_data": {}, "expires_at": "2000-01-01T00:00:00Z" },
+    ] })).await;
+    assert_eq!(status, StatusCode::BAD_REQUEST);
+
+    let (status, batch) = server.post("/jobs/batch", key, json!({ "jobs": [
+        { "job_type_id": job_type.id, "input_data": { "n": 1 } },
+        { "job_type_id": job_type.id, "input_data": { "n": 2 }, "priority": "high" },
+        { "job_type_id": job_type.id, "input_data": { "n": 3 }, "customer_reference": "PO-1" },
+    ] })).await;
+    assert_eq!(status, StatusCode::CREATED);
+    assert_eq!(batch["jobs"].as_array().unwrap().len(), 3);
+    assert!(batch["rejected"].as_array().unwrap().is_empty());
+    let submission_id = batch["submission_id"].as_str().unwrap().to_string();
+    assert_eq!(batch["jobs"][1]["submission_id"], submission_id);
+
+    let path = format!("/submissions/{}", submission_id);
+    let (status, submission) = server.get(&path, key).await;
+    assert_eq!(status, StatusCode::OK);
+    assert_eq!(submission["source"], "batch");
+    assert_eq!(submission["total"], 3);
+    assert_eq!(submission["pending"], 3);
+    assert_eq!(submission["finished"], false);
+    assert_eq!(submission["cost_cents"], 0);
+
+    let other = customer_with_wallet(&env, 1000).await;
+    assert_eq!(server.get(&path, other.api_key.as_deref()).await.0, StatusCode::FORBIDDEN);
+    assert_eq!(server.post(&format!("{}/cancel", path), other.api_key.as_deref(), json!({})).await.0, StatusCode::FORBIDDEN);
+    assert_eq!(server.get(&format!("/submissions/{}", uuid::Uuid::new_v4()), key).await.0, StatusCode::NOT_FOUND);
+
+    let (status, cancelled) = server.post(&format!("{}/cancel", path), key, json!({})).await;
+    assert_eq!(status, StatusCode::OK);
+    assert_eq!(cancelled["cancelled_jobs"], 3);
+    assert_eq!(cancelled["cancelled"], 3);
+    assert_eq!(cancelled["finished"], true);
+    assert!(cancelled["cancelled_at"].is_string());
+
+    let job_path = format!("/jobs/{}", batch["jobs"][0]["id"].as_str().unwrap());
+    assert_eq!(server.get(&job_path, key).await.1["status"], "cancelled");
+
+    // Cancelling again finds nothing left to cancel
+    let (status, again) = server.post(&format!("{}/cancel", path), key, json!({})).await;
+    assert_eq!(status, StatusCode::OK);
+    assert_eq!(again["cancelled_jobs"], 0);
+    assert_eq!(again["cancelled_at"], cancelled["cancelled_at"]);
+}
+
+#[tokio::test]
+#[ignore = "requires Docker or TEST_DATABASE_URL and TEST_REDIS_URL"]
+async fn running_jobs_are_cancelled_through_their_runner() {
+    let (env, server) = start().await;
+    let customer = customer_with_wallet(&env, 10_000).await;
+    let key = customer.api_key.as_deref();
+    let other = customer_with_wallet(&env, 1000).await;
+    let job_type = JobTypeFactory::new()
+        .create(&DieselJobTypeRepository::new(env.pool.clone()))
+        .await
+        .unwrap();
+    let job_repo = DieselJobRepository::new(env.pool.clone());
+
+    // Queued jobs are cancelled right away
+    let pending = JobFactory::new(customer.id, job_type.id).create(&job_repo).await.unwrap();
+    let path = format!("/jobs/{}/cancel", pending.id);
+    assert_eq!(server.post(&path, other.api_key.as_deref(), json!({})).await.0, StatusCode::FORBIDDEN);
+    let (status, body) = server.post(&path, key, json!({})).await;
+    assert_eq!(status, StatusCode::OK);
+    assert_eq!(body["outcome"], "cancelled");
+    assert_eq!(server.get(&format!("/jobs/{}", pending.id), key).await.1["status"], "cancelled");
+
+    // Running jobs are left to their runner to stop
+    let running = JobFactory::new(customer.id, job_type.id).create(&job_repo).await.unwrap();
+    job_repo.set_started(running.id).await.unwrap();
+    let path = format!("/jobs/{}/cancel", running.id);
+    let (status, body) = server.post(&path, key, json!({})).await;
+    assert_eq!(status, StatusCode::ACCEPTED);
+    assert_eq!(body["outcome"], "requested");
+    let (_, job) = server.get(&format!("/jobs/{}", running.id), key).await;
+    assert_eq!(job["status"], "running");
+    assert_eq!(job["cancel_requested_at"], body["requested_at"]);
+    assert!(job_repo.cancellation_requested(running.id).await.unwrap());
+
+    // Finished jobs cannot be cancelled any more
+    job_repo.set_completed(running.id, true, None, None, 100).await.unwrap();
+    assert_eq!(server.post(&path, key, json!({})).await.0, StatusCode::CONFLICT);
+    assert_eq!(server.post(&format!("/admin/jobs/{}/cancel", uuid::Uuid::new_v4()), Some(ADMIN_API_KEY), json!({})).await.0, StatusCode::NOT_FOUND);
+}
+
+#[tokio::test]
+#[ignore = "requires Docker or TEST_DATABASE_URL and TEST_REDIS_URL"]
+async fn failed_jobs_are_requeued_with_corrected_input() {
+    let (env, server) = start().await;
+    let customer = customer_with_wallet(&env, 10_000).await;
+    let key = customer.api_key.as_deref();
+    let job_type = JobTypeFactory::new()
+        .create(&DieselJobTypeRepository::new(env.pool.clone()))
+        .await
+        .unwrap();
+    let job_repo = DieselJobRepository::new(env.pool.clone());
+
+    // Jobs that have not failed are not run again
+    let pending = JobFactory::new(customer.id, job_type.id).create(&job_repo).await.unwrap();
+    let (status, _) = server.post(&format!("/admin/jobs/{}/requeue", pending.id), Some(ADMIN_API_KEY), json!({})).await;
+    assert_eq!(status, StatusCode::CONFLICT);
+
+    let failed = JobFactory::new(customer.id, job_type.id).create(&job_repo).await.unwrap();
+    job_repo.set_started(failed.id).await.unwrap();
+    job_repo.set_completed(failed.id, false, None, None, 25).await.unwrap();
+    let path = format!("/admin/jobs/{}/requeue", failed.id);
+    assert_eq!(server.post(&path, key, json!({})).await.0, StatusCode::UNAUTHORIZED);
+    let (status, _) = server.post(&path, Some(ADMIN_API_KEY), json!({ "input_overrides": "https://example.com" })).await;
+    assert_eq!(status, StatusCode::UNPROCESSABLE_ENTITY);
+
+    let overrides = json!({ "webhook_url": "https://example.com/fixed" });
+    let (status, requeued) = server.post(&path, Some(ADMIN_API_KEY), json!({ "input_overrides": overrides })).await;
+    assert_eq!(status, StatusCode::CREATED);
+    assert_eq!(requeued["requeued_from"], failed.id.to_string());
+    assert_eq!(requeued["status"], "pending");
+    assert_eq!(requeued["customer_id"], customer.id.to_string());
+    assert_ne!(requeued["id"], failed.id.to_string());
+
+    // The original attempt keeps its outcome and its charge
+    let (_, original) = server.get(&format!("/jobs/{}", failed.id), key).await;
+    assert_eq!(original["status"], "failed");
+    assert_eq!(original["cost_cents"], 25);
+    assert!(original["requeued_from"].is_null());
+    let (_, job) = server.get(&format!("/jobs/{}", requeued["id"].as_str().unwrap()), key).await;
+    assert_eq!(job["requeued_from"], failed.id.to_string());
+}
+
+#[tokio::test]
+#[ignore = "requires Docker or TEST_DATABASE_URL and TEST_REDIS_URL"]
+async fn the_catalog_lists_the_input_fields_each_processor_expects() {
+    let (env, server) = start().await;
+    let customer = customer_with_wallet(&env, 1000).await;
+    let category = format!("contracts-{}", uuid::Uuid::new_v4());
+
+    let (status, webhook) = server.post("/job-types", Some(ADMIN_API_KEY), json!({
+        "name": format!("Webhook {}", category),
+        "description": "Posts to the customer's endpoint",
+        "processor_type": "webhook",
+        "standard_cost_cents": 150,
+        "category": category,
+    })).await;
+    assert_eq!(status, StatusCode::CREATED);
+
+    let (status, catalog) = server.get(&format!("/catalog?category={}", category), customer.api_key.as_deref()).await;
+    assert_eq!(status, StatusCode::OK);
+    let entry = &catalog[0];
+    assert_eq!(entry["id"], webhook["id"]);
+    assert_eq!(entry["input_contract"]["processor_type"], "webhook");
+    assert_eq!(entry["input_contract"]["fields"], json!([{
+        "name": "webhook_url",
+        "type": "url",
+        "required": true,
+        "description": "URL the payload is POSTed to",
+    }]));
+
+    // The dry run refuses input without the field the same way a run would
+    let (status, dry_run) = server.post("/jobs/dry-run", customer.api_key.as_deref(), json!({
+        "customer_id": customer.id,
+        "job_type_id": webhook["id"],
+        "input_data": {},
+    })).await;
+    assert_eq!(status, StatusCode::OK);
+    assert_eq!(dry_run["error"]["code"], "missing_field");
+}
+
+/// Open the admin live tail with the given query string
+async fn follow_live_events(
+    server: &ApiServer,
+    query: &str,
+    api_key: Option<&str>,
+) -> Result<tokio_tungstenite::WebSocketStream<tokio_tungstenite::MaybeTlsStream<tokio::net::TcpStream>>, StatusCode> {
+    use tokio_tungstenite::tungstenite::{client::IntoClientRequest, Error};
+
+    let url = server.url(&format!("/admin/live-events{}", query)).replacen("http", "ws", 1);
+    let mut request = url.into_client_request().unwrap();
+    if let Some(api_key) = api_key {
+        request.headers_mut().insert("X-API-Key", api_key.parse().unwrap());
+    }
+    match tokio_tungstenite::connect_async(request).await {
+        Ok((socket, _)) => Ok(socket),
+        Err(Error::Http(response)) => Err(StatusCode::from_u16(response.status().as_u16()).unwrap()),
+        Err(e) => panic!("failed to connect to the live tail: {}", e),
+    }
+}
+
+/// Wait for the next event streamed to the live tail
+async fn next_live_event(
+    socket: &mut tokio_tungstenite::WebSocketStream<tokio_tungstenite::MaybeTlsStream<tokio::net::TcpStream>>,
+) -> Value {
+    use futures_util::StreamExt;
+    use tokio_tungstenite::tungstenite::Message;
+
+    loop {
+        let message = tokio::time::timeout(Duration::from_secs(15), socket.next())
+            .await
+            .expect("no live event within 15 seconds")
+            .expect("the live tail closed")
+            .unwrap();
+        if let Message::Text(text) = message {
+            return serde_json::from_str(&text).unwrap();
+        }
+    }
+}
+
+#[tokio::test]
+#[ignore = "requires Docker or TEST_DATABASE_URL and TEST_REDIS_URL"]
+async fn admins_follow_filtered_system_events_live() {
+    let (env, server) = start().await;
+    let customer = customer_with_wallet(&env, 5000).await;
+    let job_type = JobTypeFactory::new()
+        .create(&DieselJobTypeRepository::new(env.pool.clone()))
+        .await
+        .unwrap();
+
+    assert_eq!(follow_live_events(&server, "", None).await.err(), Some(StatusCode::UNAUTHORIZED));
+    assert_eq!(
+        follow_live_events(&server, "", customer.api_key.as_deref()).await.err(),
+        Some(StatusCode::UNAUTHORIZED)
+    );
+    assert_eq!(
+        follow_live_events(&server, "?types=gossip", Some(ADMIN_API_KEY)).await.err(),
+        Some(StatusCode::BAD_REQUEST)
+    );
+
+    let mut auth_failures = follow_live_events(&server, "?types=auth_failure", Some(ADMIN_API_KEY)).await.unwrap();
+    let customer_id = customer.id.to_string();
+    let mut customer_jobs = follow_live_events(
+        &server,
+        &format!("?types=job_lifecycle&entity={}", customer_id),
+        Some(ADMIN_API_KEY),
+    ).await.unwrap();
+
+    assert_eq!(server.get("/admin/resellers", Some("not-an-admin")).await.0, StatusCode::UNAUTHORIZED);
+    let event = next_live_event(&mut auth_failures).await;
+    assert_eq!(event["type"], "auth_failure");
+    assert_eq!(event["event"], "auth.failed");
+    assert_eq!(event["data"]["method"], "GET");
+    assert_eq!(event["data"]["path"], "/admin/resellers");
+
+    // Let the first poll set its starting point, then submit a job of another customer
+    // and one of the followed customer
+    tokio::time::sleep(Duration::from_secs(3)).await;
+    let other = customer_with_wallet(&env, 5000).await;
+    let job = json!({ "customer_id": other.id, "job_type_id": job_type.id, "input_data": {} });
+    assert_eq!(server.post("/jobs", other.api_key.as_deref(), job).await.0, StatusCode::CREATED);
+    let job = json!({ "customer_id": customer.id, "job_type_id": job_type.id, "input_data": {} });
+    let (status, submitted) = server.post("/jobs", customer.api_key.as_deref(), job).await;
+    assert_eq!(status, StatusCode::CREATED);
+
+    let event = next_live_event(&mut customer_jobs).await;
+    assert_eq!(event["type"], "job_lifecycle");
+    assert_eq!(event["event"], "job.pending");
+    assert_eq!(event["data"]["job_id"], submitted["id"]);
+    assert_eq!(event["data"]["customer_id"], customer_id);
+}
+
+#[tokio::test]
+#[ignore = "requires Docker or TEST_DATABASE_URL and TEST_REDIS_URL"]
+async fn resellers_spin_up_sandboxes_whose_customers_only_run_test_jobs() {
+    let (env, server) = start().await;
+    let reseller = ResellerFactory::new()
+        .create(&DieselResellerRepository::new(env.pool.clone()))
+        .await
+        .unwrap();
+    let job_type = JobTypeFactory::new()
+        .create(&DieselJobTypeRepository::new(env.pool.clone()))
+        .await
+        .unwrap();
+
+    let (status, _) = server.post("/reseller/sandbox", Some(&reseller.api_key), json!({ "ttl_days": 0 })).await;
+    assert_eq!(status, StatusCode::BAD_REQUEST);
+    let (status, sandbox) = server.post("/reseller/sandbox", Some(&reseller.api_key), json!({ "name": "Demo", "ttl_days": 7 })).await;
+    assert_eq!(status, StatusCode::CREATED);
+    assert_eq!(sandbox["name"], "Demo");
+    assert_eq!(sandbox["sandbox_of"], reseller.id.to_string());
+    let expires_at = chrono::DateTime::parse_from_rfc3339(sandbox["expires_at"].as_str().unwrap()).unwrap();
+    assert!(expires_at > chrono::Utc::now() + chrono::Duration::days(6));
+    let sandbox_key = sandbox["api_key"].as_str().unwrap().to_string();
+
+    let (status, listed) = server.get("/reseller/sandbox", Some(&reseller.api_key)).await;
+    assert_eq!(status, StatusCode::OK);
+    assert_eq!(listed.as_array().unwrap().iter().map(|s| s["id"].clone()).collect::<Vec<_>>(), vec![sandbox["id"].clone()]);
+
+    // Sandboxes cannot spin up sandboxes of their own
+    let (status, _) = server.post("/reseller/sandbox", Some(&sandbox_key), json!({})).await;
+    assert_eq!(status, StatusCode::CONFLICT);
+
+    // The sandbox's customers are its own, and their jobs run in test mode even with their live key
+    let (status, customer) = server.post("/customers", Some(&sandbox_key), json!({
+        "name": "Demo customer",
+        "email": format!("demo-{}@example.test", uuid::Uuid::new_v4().simple()),
+    })).await;
+    assert_eq!(status, StatusCode::CREATED);
+    assert_eq!(customer["reseller_id"], sandbox["id"]);
+    let job = json!({ "customer_id": customer["id"], "job_type_id": job_type.id, "input_data": {} });
+    let (status, submitted) = server.post("/jobs", customer["api_key"].as_str(), job).await;
+    assert_eq!(status, StatusCode::CREATED);
+    assert_eq!(submitted["test_mode"], true);
+}
+
+#[tokio::test]
+#[ignore = "requires Docker or TEST_DATABASE_URL and TEST_REDIS_URL"]
+async fn destructive_operations_on_entities_with_dependents_need_to_be_forced() {
+    let (env, server) = start().await;
+    let customer = customer_with_wallet(&env, 10_000).await;
+    let job_type = JobTypeFactory::new()
+        .create(&DieselJobTypeRepository::new(env.pool.clone()))
+        .await
+        .unwrap();
+    let job = json!({ "customer_id": customer.id, "job_type_id": job_type.id, "input_data": {} });
+    let (status, submitted) = server.post("/jobs", customer.api_key.as_deref(), job).await;
+    assert_eq!(status, StatusCode::CREATED);
+    let job_id: uuid::Uuid = submitted["id"].as_str().unwrap().parse().unwrap();
+    let wallet_repo = DieselWalletRepository::new(env.pool.clone());
+    let wallet = wallet_repo.find_by_customer_id(customer.id).await.unwrap();
+    wallet_repo.reserve_funds(wallet.id, 300, None, Some(job_id)).await.unwrap();
+
+    // A job type with queued jobs stays enabled unless forced
+    let path = format!("/job-types/{}/enabled", job_type.id);
+    let (status, refused) = server.send(server.client.put(server.url(&path)).json(&json!({ "enabled": false })), Some(ADMIN_API_KEY)).await;
+    assert_eq!(status, StatusCode::CONFLICT);
+    assert_eq!(refused["error"], "has_dependents");
+    assert_eq!(refused["blockers"][0]["kind"], "queued_jobs");
+    assert_eq!(refused["blockers"][0]["count"], 1);
+
+    // A customer with queued jobs and reserved funds stays active unless forced
+    let path = format!("/customers/{}/status", customer.id);
+    let (status, refused) = server.send(server.client.put(server.url(&path)).json(&json!({ "active": false })), Some(ADMIN_API_KEY)).await;
+    assert_eq!(status, StatusCode::CONFLICT);
+    let kinds: Vec<_> = refused["blockers"].as_array().unwrap().iter().map(|b| b["kind"].clone()).collect();
+    assert_eq!(kinds, vec![json!("queued_jobs"), json!("reserved_funds")]);
+    assert_eq!(refused["blockers"][1]["count"], 300);
+
+    // Forced, the queued job is cancelled and the customer can no longer authenticate
+    let path = format!("/customers/{}/status?force=true", customer.id);
+    let (status, deactivated) = server.send(server.client.put(server.url(&path)).json(&json!({ "active": false })), Some(ADMIN_API_KEY)).await;
+    assert_eq!(status, StatusCode::OK);
+    assert_eq!(deactivated["active"], false);
+    let job = DieselJobRepository::new(env.pool.clone()).find_by_id(job_id).await.unwrap();
+    assert_eq!(job.status.as_str(), "cancelled");
+    assert_eq!(wallet_repo.get_reserved_for_job(job_id).await.unwrap(), 0);
+    let (status, _) = server.get(&format!("/wallets/{}", customer.id), customer.api_key.as_deref()).await;
+    assert_eq!(status, StatusCode::FORBIDDEN);
+
+    // With nothing queued any more, the job type is disabled without forcing
+    let path = format!("/job-types/{}/enabled", job_type.id);
+    let (status, disabled) = server.send(server.client.put(server.url(&path)).json(&json!({ "enabled": false })), Some(ADMIN_API_KEY)).await;
+    assert_eq!(status, StatusCode::OK);
+    assert_eq!(disabled["enabled"], false);
+}
+
+#[tokio::test]
+#[ignore = "requires Docker or TEST_DATABASE_URL"]
+async fn succeeded_jobs_are_sampled_into_an_anonymized_replay_corpus() {
+    let (env, server) = start_without_redis().await;
+    let customer = customer_with_wallet(&env, 1000).await;
+    let api_key = customer.api_key.as_deref();
+    let job_type = JobTypeFactory::new()
+        .create(&DieselJobTypeRepository::new(env.pool.clone()))
+        .await
+        .unwrap();
+    let job_repo = DieselJobRepository::new(env.pool.clone());
+    for _ in 0..2 {
+        let job = JobFactory::new(customer.id, job_type.id)
+            .create(&job_repo)
+            .await
+            .unwrap();
+        let (status, _) = server.post("/jobs/complete", api_key, json!({ "job_id": job.id, "success": true, "output_data": {} })).await;
+        assert_eq!(status, StatusCode::OK);
+    }
+
+    let path = format!("/job-types/{}/replay-corpus", job_type.id);
+    assert_eq!(server.post(&path, api_key, json!({ "count": 5 })).await.0, StatusCode::UNAUTHORIZED);
+    let (status, _) = server.post(&path, Some(ADMIN_API_KEY), json!({ "count": 0 })).await;
+    assert_eq!(status, StatusCode::BAD_REQUEST);
+    let (status, _) = server.post(&path, Some(ADMIN_API_KEY), json!({ "count": 5, "anonymize": ["email"] })).await;
+    assert_eq!(status, StatusCode::BAD_REQUEST);
+
+    let (status, sampled) = server.post(&path, Some(ADMIN_API_KEY), json!({ "count": 5, "anonymize": ["$.email"] })).await;
+    assert_eq!(status, StatusCode::CREATED);
+    let sampled = sampled.as_array().unwrap();
+    assert_eq!(sampled.len(), 2);
+    for case in sampled {
+        assert_eq!(case["job_type_id"], json!(job_type.id));
+        assert_eq!(case["baseline_output"], Value::Null);
+    }
+    // Jobs already in the corpus are not sampled again
+    let (status, resampled) = server.post(&path, Some(ADMIN_API_KEY), json!({ "count": 5 })).await;
+    assert_eq!(status, StatusCode::CREATED);
+    assert_eq!(resampled, json!([]));
+
+    let case_path = format!("{}/{}", path, sampled[0]["id"].as_str().unwrap());
+    let (status, _) = server.send(server.client.delete(server.url(&case_path)), Some(ADMIN_API_KEY)).await;
+    assert_eq!(status, StatusCode::NO_CONTENT);
+    let (status, _) = server.send(server.client.delete(server.url(&case_path)), Some(ADMIN_API_KEY)).await;
+    assert_eq!(status, StatusCode::NOT_FOUND);
+    let (status, corpus) = server.get(&path, Some(ADMIN_API_KEY)).await;
+    assert_eq!(status, StatusCode::OK);
+    assert_eq!(corpus.as_array().unwrap().len(), 1);
+}
@@ -0,0 +1,2 @@
+DROP INDEX IF EXISTS idx_customer_webhooks_customer_id;
+DROP TABLE IF EXISTS customer_webhooks;
@@ -0,0 +1,16 @@
+-- Customer webhook endpoints that receive job lifecycle notifications
+CREATE TABLE IF NOT EXISTS customer_webhooks (
+    id UUID PRIMARY KEY,
+    customer_id UUID NOT NULL REFERENCES customers(id) ON DELETE CASCADE,
+    url TEXT NOT NULL,
+    secret TEXT NOT NULL,
+    event_types TEXT[] NOT NULL DEFAULT '{}',
+    active BOOLEAN NOT NULL DEFAULT TRUE,
+    last_tested_at TIMESTAMP,
+    last_test_status_code INTEGER,
+    last_test_response TEXT,
+    created_at TIMESTAMP DEFAULT CURRENT_TIMESTAMP,
+    updated_at TIMESTAMP DEFAULT CURRENT_TIMESTAMP
+);
+
+CREATE INDEX IF NOT EXISTS idx_customer_webhooks_customer_id ON customer_webhooks(customer_id);
@@ -0,0 +1,6 @@
+ALTER TABLE customer_webhooks DROP COLUMN IF EXISTS failing_since;
+
+DROP INDEX IF EXISTS idx_notification_deliveries_next_retry_at;
+DROP INDEX IF EXISTS idx_notification_deliveries_webhook_id;
+DROP INDEX IF EXISTS idx_notification_deliveries_customer_id;
+DROP TABLE IF EXISTS notification_deliveries;
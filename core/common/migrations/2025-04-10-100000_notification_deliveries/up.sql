@@ -0,0 +1,24 @@
+-- Log of every outgoing notification attempt (webhooks, email)
+CREATE TABLE IF NOT EXISTS notification_deliveries (
+    id UUID PRIMARY KEY,
+    customer_id UUID NOT NULL REFERENCES customers(id) ON DELETE CASCADE,
+    webhook_id UUID REFERENCES customer_webhooks(id) ON DELETE SET NULL,
+    channel TEXT NOT NULL,              -- Must be one of: "webhook", "email"
+    event_type TEXT NOT NULL,
+    payload TEXT NOT NULL,              -- Exact body sent, re-used verbatim on retries
+    status TEXT NOT NULL DEFAULT 'pending',
+    status_code INTEGER,
+    latency_ms INTEGER,
+    last_error TEXT,
+    retry_count INTEGER NOT NULL DEFAULT 0,
+    next_retry_at TIMESTAMP,
+    created_at TIMESTAMP DEFAULT CURRENT_TIMESTAMP,
+    updated_at TIMESTAMP DEFAULT CURRENT_TIMESTAMP
+);
+
+CREATE INDEX IF NOT EXISTS idx_notification_deliveries_customer_id ON notification_deliveries(customer_id);
+CREATE INDEX IF NOT EXISTS idx_notification_deliveries_webhook_id ON notification_deliveries(webhook_id);
+CREATE INDEX IF NOT EXISTS idx_notification_deliveries_next_retry_at ON notification_deliveries(next_retry_at);
+
+-- Track how long an endpoint has been failing continuously so it can be disabled
+ALTER TABLE customer_webhooks ADD COLUMN IF NOT EXISTS failing_since TIMESTAMP;
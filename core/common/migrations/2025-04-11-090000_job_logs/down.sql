@@ -0,0 +1,2 @@
+DROP INDEX IF EXISTS idx_job_logs_job_id_line_number;
+DROP TABLE IF EXISTS job_logs;
@@ -0,0 +1,12 @@
+-- Structured log lines emitted by processors while a job executes
+CREATE TABLE IF NOT EXISTS job_logs (
+    id UUID PRIMARY KEY,
+    job_id UUID NOT NULL REFERENCES jobs(id) ON DELETE CASCADE,
+    line_number INTEGER NOT NULL,       -- Order of the line within the job's log, starting at 1
+    level TEXT NOT NULL,                -- Must be one of: "debug", "info", "warn", "error"
+    message TEXT NOT NULL,
+    fields JSONB,                       -- Optional structured context attached to the line
+    created_at TIMESTAMP DEFAULT CURRENT_TIMESTAMP
+);
+
+CREATE UNIQUE INDEX IF NOT EXISTS idx_job_logs_job_id_line_number ON job_logs(job_id, line_number);
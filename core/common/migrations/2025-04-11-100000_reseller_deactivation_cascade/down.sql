@@ -0,0 +1,3 @@
+ALTER TABLE resellers DROP COLUMN IF EXISTS deactivation_reason;
+ALTER TABLE resellers DROP COLUMN IF EXISTS block_customer_creation;
+ALTER TABLE resellers DROP COLUMN IF EXISTS suspend_customers;
@@ -0,0 +1,4 @@
+-- Cascade behavior applied to a reseller's customers while the reseller is inactive
+ALTER TABLE resellers ADD COLUMN IF NOT EXISTS suspend_customers BOOLEAN NOT NULL DEFAULT FALSE;      -- Customers keep read access but cannot submit jobs
+ALTER TABLE resellers ADD COLUMN IF NOT EXISTS block_customer_creation BOOLEAN NOT NULL DEFAULT FALSE; -- No new customers can be created under the reseller
+ALTER TABLE resellers ADD COLUMN IF NOT EXISTS deactivation_reason TEXT;                              -- Shown to suspended customers in auth errors
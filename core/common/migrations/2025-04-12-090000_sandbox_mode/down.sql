@@ -0,0 +1,3 @@
+ALTER TABLE jobs DROP COLUMN IF EXISTS test_mode;
+ALTER TABLE wallets DROP COLUMN IF EXISTS test_balance_cents;
+ALTER TABLE customers DROP COLUMN IF EXISTS test_api_key;
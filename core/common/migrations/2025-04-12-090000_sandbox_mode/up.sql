@@ -0,0 +1,4 @@
+-- Sandbox mode: jobs submitted with a customer's test key never run real processors
+ALTER TABLE customers ADD COLUMN IF NOT EXISTS test_api_key TEXT UNIQUE;
+ALTER TABLE wallets ADD COLUMN IF NOT EXISTS test_balance_cents INTEGER NOT NULL DEFAULT 100000; -- Separate play-money balance charged by test jobs
+ALTER TABLE jobs ADD COLUMN IF NOT EXISTS test_mode BOOLEAN NOT NULL DEFAULT FALSE;
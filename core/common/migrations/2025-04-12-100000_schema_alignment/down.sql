@@ -0,0 +1,5 @@
+DROP INDEX IF EXISTS idx_runner_job_type_compatibility_job_type_id;
+DROP TABLE IF EXISTS runner_job_type_compatibility;
+
+DROP INDEX IF EXISTS idx_wallet_transactions_customer_id;
+ALTER TABLE wallet_transactions DROP COLUMN IF EXISTS customer_id;
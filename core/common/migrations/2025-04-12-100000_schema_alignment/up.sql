@@ -0,0 +1,23 @@
+-- Align the database with columns and tables the Diesel schema already uses
+
+-- Owning customer of each wallet transaction, backfilled from the wallet
+ALTER TABLE wallet_transactions ADD COLUMN IF NOT EXISTS customer_id UUID REFERENCES customers(id) ON DELETE CASCADE;
+
+UPDATE wallet_transactions t
+SET customer_id = w.customer_id
+FROM wallets w
+WHERE t.wallet_id = w.id AND t.customer_id IS NULL;
+
+ALTER TABLE wallet_transactions ALTER COLUMN customer_id SET NOT NULL;
+
+CREATE INDEX IF NOT EXISTS idx_wallet_transactions_customer_id ON wallet_transactions(customer_id);
+
+-- Job types each runner is able to process
+CREATE TABLE IF NOT EXISTS runner_job_type_compatibility (
+    runner_id UUID NOT NULL REFERENCES runners(id) ON DELETE CASCADE,
+    job_type_id UUID NOT NULL REFERENCES job_types(id) ON DELETE CASCADE,
+    created_at TIMESTAMP DEFAULT CURRENT_TIMESTAMP,
+    PRIMARY KEY (runner_id, job_type_id)
+);
+
+CREATE INDEX IF NOT EXISTS idx_runner_job_type_compatibility_job_type_id ON runner_job_type_compatibility(job_type_id);
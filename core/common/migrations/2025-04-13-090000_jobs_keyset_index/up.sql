@@ -0,0 +1,2 @@
+-- Index backing keyset pagination of jobs, newest first by (created_at, id)
+CREATE INDEX IF NOT EXISTS idx_jobs_created_at_id ON jobs (created_at DESC, id DESC);
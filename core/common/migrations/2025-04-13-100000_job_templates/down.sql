@@ -0,0 +1,2 @@
+DROP INDEX IF EXISTS idx_job_templates_job_type_id;
+DROP TABLE IF EXISTS job_templates;
@@ -0,0 +1,15 @@
+-- Stored input_data skeletons with {{variables}} that customers fill in when submitting jobs
+CREATE TABLE IF NOT EXISTS job_templates (
+    id UUID PRIMARY KEY,
+    customer_id UUID NOT NULL REFERENCES customers(id) ON DELETE CASCADE,
+    job_type_id UUID NOT NULL REFERENCES job_types(id) ON DELETE CASCADE,
+    name TEXT NOT NULL,
+    description TEXT,
+    input_template JSONB NOT NULL,      -- input_data skeleton; strings may contain {{variable}} placeholders
+    variables JSONB NOT NULL DEFAULT '{}', -- Declared variables: name -> {"type", "required", "default", "description"}
+    created_at TIMESTAMP DEFAULT CURRENT_TIMESTAMP,
+    updated_at TIMESTAMP DEFAULT CURRENT_TIMESTAMP,
+    UNIQUE (customer_id, name)
+);
+
+CREATE INDEX IF NOT EXISTS idx_job_templates_job_type_id ON job_templates(job_type_id);
@@ -0,0 +1,4 @@
+DROP INDEX IF EXISTS idx_runner_health_checks_runner_id_created_at;
+DROP TABLE IF EXISTS runner_health_checks;
+DROP INDEX IF EXISTS idx_jobs_runner_id_completed_at;
+ALTER TABLE jobs DROP COLUMN IF EXISTS runner_id;
@@ -0,0 +1,17 @@
+-- Attribute jobs to the runner that processed them and keep a history of runner health checks
+ALTER TABLE jobs ADD COLUMN IF NOT EXISTS runner_id UUID REFERENCES runners(id) ON DELETE SET NULL;
+CREATE INDEX IF NOT EXISTS idx_jobs_runner_id_completed_at ON jobs (runner_id, completed_at);
+
+CREATE TABLE IF NOT EXISTS runner_health_checks (
+    id UUID PRIMARY KEY,
+    runner_id UUID NOT NULL REFERENCES runners(id) ON DELETE CASCADE,
+    status TEXT NOT NULL,                       -- healthy, warning, critical or unknown
+    score INTEGER NOT NULL,                     -- Composite score from 0 (down) to 100 (fully healthy)
+    heartbeat_age_seconds BIGINT,
+    failure_rate DOUBLE PRECISION,              -- Share of failed jobs in the sample window, if enough jobs ran
+    avg_processing_seconds DOUBLE PRECISION,
+    jobs_sampled INTEGER NOT NULL DEFAULT 0,
+    created_at TIMESTAMP DEFAULT CURRENT_TIMESTAMP
+);
+
+CREATE INDEX IF NOT EXISTS idx_runner_health_checks_runner_id_created_at ON runner_health_checks (runner_id, created_at DESC);
@@ -0,0 +1,3 @@
+DROP INDEX IF EXISTS idx_jobs_claimed_at;
+ALTER TABLE jobs DROP COLUMN IF EXISTS queue_priority;
+ALTER TABLE jobs DROP COLUMN IF EXISTS claimed_at;
@@ -0,0 +1,4 @@
+-- Record when a runner claimed a job from the queue and from which priority queue
+ALTER TABLE jobs ADD COLUMN IF NOT EXISTS claimed_at TIMESTAMP;
+ALTER TABLE jobs ADD COLUMN IF NOT EXISTS queue_priority INTEGER;   -- 0 (low) to 3 (critical)
+CREATE INDEX IF NOT EXISTS idx_jobs_claimed_at ON jobs (claimed_at) WHERE claimed_at IS NOT NULL;
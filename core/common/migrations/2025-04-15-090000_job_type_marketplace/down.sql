@@ -0,0 +1,7 @@
+DROP INDEX IF EXISTS idx_job_types_category;
+ALTER TABLE job_types DROP COLUMN IF EXISTS visibility;
+ALTER TABLE job_types DROP COLUMN IF EXISTS sample_output;
+ALTER TABLE job_types DROP COLUMN IF EXISTS sample_input;
+ALTER TABLE job_types DROP COLUMN IF EXISTS documentation_url;
+ALTER TABLE job_types DROP COLUMN IF EXISTS icon;
+ALTER TABLE job_types DROP COLUMN IF EXISTS category;
@@ -0,0 +1,9 @@
+-- Marketplace listing metadata resellers use to present the job type catalog
+ALTER TABLE job_types ADD COLUMN IF NOT EXISTS category TEXT;
+ALTER TABLE job_types ADD COLUMN IF NOT EXISTS icon TEXT;                  -- Icon name or image URL
+ALTER TABLE job_types ADD COLUMN IF NOT EXISTS documentation_url TEXT;
+ALTER TABLE job_types ADD COLUMN IF NOT EXISTS sample_input JSONB;
+ALTER TABLE job_types ADD COLUMN IF NOT EXISTS sample_output JSONB;
+ALTER TABLE job_types ADD COLUMN IF NOT EXISTS visibility TEXT NOT NULL DEFAULT 'public'; -- public, reseller or internal
+
+CREATE INDEX IF NOT EXISTS idx_job_types_category ON job_types(category);
@@ -0,0 +1,3 @@
+DROP INDEX IF EXISTS idx_customers_parent_id;
+ALTER TABLE customers DROP COLUMN IF EXISTS budget_cents;
+ALTER TABLE customers DROP COLUMN IF EXISTS parent_id;
@@ -0,0 +1,5 @@
+-- Sub-accounts: customers with their own API keys whose jobs are billed to a parent customer's wallet
+ALTER TABLE customers ADD COLUMN IF NOT EXISTS parent_id UUID REFERENCES customers(id) ON DELETE CASCADE;
+ALTER TABLE customers ADD COLUMN IF NOT EXISTS budget_cents INTEGER; -- Monthly spending limit of a sub-account, NULL for none
+
+CREATE INDEX IF NOT EXISTS idx_customers_parent_id ON customers(parent_id) WHERE parent_id IS NOT NULL;
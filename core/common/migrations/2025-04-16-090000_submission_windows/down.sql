@@ -0,0 +1,2 @@
+ALTER TABLE jobs DROP COLUMN IF EXISTS scheduled_for;
+DROP TABLE IF EXISTS submission_windows;
@@ -0,0 +1,17 @@
+-- Quiet hours: UTC time windows in which jobs of a type may be queued, set per customer or per reseller
+CREATE TABLE IF NOT EXISTS submission_windows (
+    id UUID PRIMARY KEY,
+    job_type_id UUID NOT NULL REFERENCES job_types(id) ON DELETE CASCADE,
+    customer_id UUID REFERENCES customers(id) ON DELETE CASCADE,
+    reseller_id UUID REFERENCES resellers(id) ON DELETE CASCADE,
+    start_time TIME NOT NULL, -- UTC; a window ending before it starts wraps past midnight
+    end_time TIME NOT NULL,
+    created_at TIMESTAMP DEFAULT CURRENT_TIMESTAMP,
+    CHECK ((customer_id IS NULL) <> (reseller_id IS NULL)),
+    CHECK (start_time <> end_time)
+);
+
+CREATE INDEX IF NOT EXISTS idx_submission_windows_job_type_id ON submission_windows(job_type_id);
+
+-- When a job submitted outside its window is released to the queue
+ALTER TABLE jobs ADD COLUMN IF NOT EXISTS scheduled_for TIMESTAMP;
@@ -0,0 +1,3 @@
+DROP TABLE IF EXISTS pipeline_run_steps;
+DROP TABLE IF EXISTS pipeline_runs;
+DROP TABLE IF EXISTS pipelines;
@@ -0,0 +1,36 @@
+-- Manual wallet adjustments and refunds, posted once approved by a second admin above a threshold
+CREATE TABLE IF NOT EXISTS wallet_adjustments (
+    id UUID PRIMARY KEY,
+    wallet_id UUID NOT NULL REFERENCES wallets(id) ON DELETE CASCADE,
+    customer_id UUID NOT NULL REFERENCES customers(id) ON DELETE CASCADE,
+    kind TEXT NOT NULL CHECK (kind IN ('adjustment', 'refund')),
+    amount_cents INTEGER NOT NULL CHECK (amount_cents <> 0),  -- Negative adjustments debit the wallet
+    description TEXT,
+    job_id UUID,                        -- Job a refund is for, if any
+    status TEXT NOT NULL DEFAULT 'pending' CHECK (status IN ('pending', 'approved', 'rejected')),
+    requires_approval BOOLEAN NOT NULL,
+    requested_by TEXT NOT NULL,
+    reviewed_by TEXT,
+    review_note TEXT,
+    transaction_id UUID,                -- Wallet transaction posted on approval
+    created_at TIMESTAMP DEFAULT CURRENT_TIMESTAMP,
+    reviewed_at TIMESTAMP,
+    -- Adjustments above the threshold are reviewed by someone other than the requester
+    CHECK (NOT requires_approval OR status = 'pending' OR (reviewed_by IS NOT NULL AND reviewed_by <> requested_by))
+);
+
+CREATE INDEX IF NOT EXISTS idx_wallet_adjustments_wallet_id ON wallet_adjustments(wallet_id);
+CREATE INDEX IF NOT EXISTS idx_wallet_adjustments_pending ON wallet_adjustments(status) WHERE status = 'pending';
+
+-- Who did what to which entity, shared across resellers
+CREATE TABLE IF NOT EXISTS audit_log (
+    id UUID PRIMARY KEY,
+    actor TEXT NOT NULL,
+    action TEXT NOT NULL,
+    entity_type TEXT NOT NULL,
+    entity_id UUID NOT NULL,
+    details JSONB NOT NULL DEFAULT '{}',
+    created_at TIMESTAMP DEFAULT CURRENT_TIMESTAMP
+);
+
+CREATE INDEX IF NOT EXISTS idx_audit_log_entity ON audit_log(entity_type, entity_id);
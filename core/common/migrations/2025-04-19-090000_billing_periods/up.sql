@@ -0,0 +1,31 @@
+-- Closed billing periods: their wallet transactions are final and summarized per customer in invoices
+CREATE TABLE IF NOT EXISTS billing_periods (
+    id UUID PRIMARY KEY,
+    period_start TIMESTAMP NOT NULL,
+    period_end TIMESTAMP NOT NULL,      -- Exclusive
+    closed_by TEXT NOT NULL,
+    transaction_count INTEGER NOT NULL,
+    credits_cents BIGINT NOT NULL,
+    debits_cents BIGINT NOT NULL,
+    ledger_sha256 TEXT NOT NULL,        -- Checksum of the ledger export taken when the period was closed
+    closed_at TIMESTAMP DEFAULT CURRENT_TIMESTAMP,
+    CHECK (period_end > period_start),
+    UNIQUE (period_start)
+);
+
+-- Final statement of a customer's wallet over a closed billing period
+CREATE TABLE IF NOT EXISTS invoices (
+    id UUID PRIMARY KEY,
+    billing_period_id UUID NOT NULL REFERENCES billing_periods(id) ON DELETE RESTRICT,
+    customer_id UUID NOT NULL REFERENCES customers(id) ON DELETE RESTRICT,
+    wallet_id UUID NOT NULL,
+    opening_balance_cents BIGINT NOT NULL,
+    credits_cents BIGINT NOT NULL,
+    debits_cents BIGINT NOT NULL,
+    closing_balance_cents BIGINT NOT NULL,
+    transaction_count INTEGER NOT NULL,
+    finalized_at TIMESTAMP DEFAULT CURRENT_TIMESTAMP,
+    UNIQUE (billing_period_id, customer_id)
+);
+
+CREATE INDEX IF NOT EXISTS idx_invoices_customer_id ON invoices(customer_id);
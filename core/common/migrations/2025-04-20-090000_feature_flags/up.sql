@@ -0,0 +1,11 @@
+-- Runtime feature flags and kill switches, shared across resellers
+CREATE TABLE IF NOT EXISTS feature_flags (
+    id UUID PRIMARY KEY,
+    name TEXT NOT NULL UNIQUE,
+    enabled BOOLEAN NOT NULL DEFAULT FALSE,     -- On for everyone
+    reseller_ids UUID[] NOT NULL DEFAULT '{}',  -- On for these resellers' customers only (canary)
+    description TEXT,
+    updated_by TEXT NOT NULL,
+    created_at TIMESTAMP DEFAULT CURRENT_TIMESTAMP,
+    updated_at TIMESTAMP DEFAULT CURRENT_TIMESTAMP
+);
@@ -0,0 +1,15 @@
+-- Every execution of a job by a runner, so retried jobs keep the runner, duration, error and cost of each attempt
+CREATE TABLE IF NOT EXISTS job_attempts (
+    id UUID PRIMARY KEY,
+    job_id UUID NOT NULL REFERENCES jobs(id) ON DELETE CASCADE,
+    attempt_number INTEGER NOT NULL CHECK (attempt_number > 0),
+    runner_id UUID,                     -- Runner that made the attempt, if it is registered
+    status TEXT NOT NULL DEFAULT 'running' CHECK (status IN ('running', 'succeeded', 'failed', 'abandoned')),
+    error JSONB,                        -- Structured error of a failed attempt
+    cost_cents INTEGER NOT NULL DEFAULT 0,
+    started_at TIMESTAMP NOT NULL DEFAULT CURRENT_TIMESTAMP,
+    finished_at TIMESTAMP,
+    UNIQUE (job_id, attempt_number)
+);
+
+CREATE INDEX IF NOT EXISTS idx_job_attempts_runner_finished ON job_attempts(runner_id, finished_at);
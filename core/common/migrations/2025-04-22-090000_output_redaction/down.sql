@@ -0,0 +1,3 @@
+DROP TABLE IF EXISTS job_unredacted_outputs;
+ALTER TABLE job_types DROP COLUMN IF EXISTS unredacted_retention_hours;
+ALTER TABLE job_types DROP COLUMN IF EXISTS redaction_rules;
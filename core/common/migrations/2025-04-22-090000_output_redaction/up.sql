@@ -0,0 +1,13 @@
+-- Redaction of job output, e.g. tokens echoed back by webhook receivers, before it is stored
+ALTER TABLE job_types ADD COLUMN IF NOT EXISTS redaction_rules TEXT[] NOT NULL DEFAULT '{}'; -- JSONPath patterns
+ALTER TABLE job_types ADD COLUMN IF NOT EXISTS unredacted_retention_hours INTEGER CHECK (unredacted_retention_hours > 0); -- NULL keeps no unredacted output
+
+-- Original output of jobs that had values redacted, kept for administrators until it expires
+CREATE TABLE IF NOT EXISTS job_unredacted_outputs (
+    job_id UUID PRIMARY KEY REFERENCES jobs(id) ON DELETE CASCADE,
+    output_data JSONB NOT NULL,
+    created_at TIMESTAMP NOT NULL DEFAULT CURRENT_TIMESTAMP,
+    expires_at TIMESTAMP NOT NULL
+);
+
+CREATE INDEX IF NOT EXISTS idx_job_unredacted_outputs_expires_at ON job_unredacted_outputs(expires_at);
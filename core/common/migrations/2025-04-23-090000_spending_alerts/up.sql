@@ -0,0 +1,15 @@
+-- Anomalies in a customer's spend or failure rate compared to their own history, e.g. a runaway script
+CREATE TABLE IF NOT EXISTS spending_alerts (
+    id UUID PRIMARY KEY,
+    customer_id UUID NOT NULL REFERENCES customers(id) ON DELETE CASCADE,
+    reseller_id UUID,                       -- Reseller of the customer when the alert was raised
+    kind TEXT NOT NULL CHECK (kind IN ('spend_spike', 'failure_rate_jump')),
+    observed DOUBLE PRECISION NOT NULL,     -- Spend in cents or failure rate within the detection window
+    baseline DOUBLE PRECISION NOT NULL,     -- The same measure expected from the customer's history
+    window_start TIMESTAMP NOT NULL,
+    window_end TIMESTAMP NOT NULL,
+    created_at TIMESTAMP NOT NULL DEFAULT CURRENT_TIMESTAMP
+);
+
+CREATE INDEX IF NOT EXISTS idx_spending_alerts_customer_created ON spending_alerts(customer_id, created_at DESC);
+CREATE INDEX IF NOT EXISTS idx_spending_alerts_reseller_created ON spending_alerts(reseller_id, created_at DESC);
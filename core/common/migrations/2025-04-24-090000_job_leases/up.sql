@@ -0,0 +1,3 @@
+-- Runners prefetching jobs hold them under a lease; a job not started before its lease expires goes back to its queue
+ALTER TABLE jobs ADD COLUMN IF NOT EXISTS lease_expires_at TIMESTAMP;
+CREATE INDEX IF NOT EXISTS idx_jobs_lease_expires_at ON jobs (lease_expires_at) WHERE lease_expires_at IS NOT NULL;
@@ -0,0 +1,2 @@
+DROP INDEX IF EXISTS idx_jobs_public_id;
+ALTER TABLE jobs DROP COLUMN IF EXISTS public_id;
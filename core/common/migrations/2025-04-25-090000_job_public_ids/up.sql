@@ -0,0 +1,3 @@
+-- Short public identifiers of jobs, e.g. job_8f3kq2wx1m, for support conversations and invoices; existing jobs get one from the default
+ALTER TABLE jobs ADD COLUMN IF NOT EXISTS public_id TEXT NOT NULL DEFAULT 'job_' || substr(md5(random()::text || clock_timestamp()::text), 1, 10);
+CREATE UNIQUE INDEX IF NOT EXISTS idx_jobs_public_id ON jobs (public_id);
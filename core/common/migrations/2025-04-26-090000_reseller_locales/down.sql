@@ -0,0 +1 @@
+ALTER TABLE resellers DROP COLUMN IF EXISTS default_locale;
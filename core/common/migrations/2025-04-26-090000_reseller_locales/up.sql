@@ -0,0 +1,2 @@
+-- Locale of customer-facing messages for the reseller's customers whose requests name no supported language
+ALTER TABLE resellers ADD COLUMN IF NOT EXISTS default_locale TEXT;
@@ -0,0 +1,2 @@
+DROP INDEX IF EXISTS idx_jobs_waiting_expires_at;
+ALTER TABLE jobs DROP COLUMN IF EXISTS expires_at;
@@ -0,0 +1,3 @@
+-- Deadline for starting a job: jobs still waiting when it passes expire instead of running late
+ALTER TABLE jobs ADD COLUMN IF NOT EXISTS expires_at TIMESTAMP;
+CREATE INDEX IF NOT EXISTS idx_jobs_waiting_expires_at ON jobs (expires_at) WHERE status IN ('pending', 'scheduled') AND expires_at IS NOT NULL;
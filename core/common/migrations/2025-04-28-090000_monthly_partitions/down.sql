@@ -0,0 +1,36 @@
+-- Fold the monthly partitions back into the former tables
+ALTER TABLE wallet_transactions DETACH PARTITION wallet_transactions_default;
+INSERT INTO wallet_transactions_default SELECT * FROM wallet_transactions;
+DROP TABLE wallet_transactions;
+ALTER TABLE wallet_transactions_default RENAME TO wallet_transactions;
+ALTER TABLE wallet_transactions DROP CONSTRAINT wallet_transactions_default_pkey;
+ALTER TABLE wallet_transactions ADD CONSTRAINT wallet_transactions_pkey PRIMARY KEY (id);
+ALTER INDEX wallet_transactions_default_customer_id_idx RENAME TO idx_wallet_transactions_customer_id;
+ALTER INDEX wallet_transactions_default_job_id_idx RENAME TO idx_wallet_transactions_job_id;
+ALTER INDEX wallet_transactions_default_reference_id_idx RENAME TO idx_wallet_transactions_reference_id;
+ALTER INDEX wallet_transactions_default_wallet_id_idx RENAME TO idx_wallet_transactions_wallet_id;
+
+ALTER TABLE jobs DETACH PARTITION jobs_default;
+INSERT INTO jobs_default SELECT * FROM jobs;
+DROP TABLE jobs;
+ALTER TABLE jobs_default RENAME TO jobs;
+ALTER TABLE jobs DROP CONSTRAINT jobs_default_pkey;
+ALTER TABLE jobs ADD CONSTRAINT jobs_pkey PRIMARY KEY (id);
+DROP INDEX IF EXISTS jobs_default_public_id_created_at_idx;
+CREATE UNIQUE INDEX idx_jobs_public_id ON jobs (public_id);
+ALTER INDEX jobs_default_claimed_at_idx RENAME TO idx_jobs_claimed_at;
+ALTER INDEX jobs_default_created_at_id_idx RENAME TO idx_jobs_created_at_id;
+ALTER INDEX jobs_default_customer_id_idx RENAME TO idx_jobs_customer_id;
+ALTER INDEX jobs_default_job_type_id_idx RENAME TO idx_jobs_job_type_id;
+ALTER INDEX jobs_default_lease_expires_at_idx RENAME TO idx_jobs_lease_expires_at;
+ALTER INDEX jobs_default_project_id_idx RENAME TO idx_jobs_project_id;
+ALTER INDEX jobs_default_runner_id_completed_at_idx RENAME TO idx_jobs_runner_id_completed_at;
+ALTER INDEX jobs_default_status_idx RENAME TO idx_jobs_status;
+ALTER INDEX jobs_default_waiting_expires_at_idx RENAME TO idx_jobs_waiting_expires_at;
+
+ALTER TABLE jobs ALTER COLUMN created_at DROP NOT NULL;
+ALTER TABLE wallet_transactions ALTER COLUMN created_at DROP NOT NULL;
+ALTER TABLE wallet_transactions ADD CONSTRAINT wallet_transactions_job_id_fkey FOREIGN KEY (job_id) REFERENCES jobs(id) ON DELETE SET NULL;
+ALTER TABLE job_unredacted_outputs ADD CONSTRAINT job_unredacted_outputs_job_id_fkey FOREIGN KEY (job_id) REFERENCES jobs(id) ON DELETE CASCADE;
+ALTER TABLE job_attempts ADD CONSTRAINT job_attempts_job_id_fkey FOREIGN KEY (job_id) REFERENCES jobs(id) ON DELETE CASCADE;
+ALTER TABLE job_logs ADD CONSTRAINT job_logs_job_id_fkey FOREIGN KEY (job_id) REFERENCES jobs(id) ON DELETE CASCADE;
@@ -0,0 +1,70 @@
+-- Partition jobs and wallet transactions by month of creation, so old months can be dropped and queries bounded by created_at only scan the months they cover
+--
+-- The existing tables become the default partitions of the new partitioned tables, so
+-- no rows are copied here. Monthly partitions are created by partition maintenance
+-- (the API, or `innosystem-migrations partitions`), which moves the rows of each month
+-- out of the default partition.
+
+-- Unique keys of a partitioned table include the partition key, which cannot be null
+UPDATE jobs SET created_at = COALESCE(updated_at, CURRENT_TIMESTAMP) WHERE created_at IS NULL;
+ALTER TABLE jobs ALTER COLUMN created_at SET NOT NULL;
+UPDATE wallet_transactions SET created_at = CURRENT_TIMESTAMP WHERE created_at IS NULL;
+ALTER TABLE wallet_transactions ALTER COLUMN created_at SET NOT NULL;
+
+-- Foreign keys need a unique key on the job ID alone, which the partitioned jobs table
+-- cannot have; rows referring to a job are removed with its partition instead
+ALTER TABLE job_logs DROP CONSTRAINT IF EXISTS job_logs_job_id_fkey;
+ALTER TABLE job_attempts DROP CONSTRAINT IF EXISTS job_attempts_job_id_fkey;
+ALTER TABLE job_unredacted_outputs DROP CONSTRAINT IF EXISTS job_unredacted_outputs_job_id_fkey;
+ALTER TABLE wallet_transactions DROP CONSTRAINT IF EXISTS wallet_transactions_job_id_fkey;
+
+-- Jobs
+ALTER TABLE jobs RENAME TO jobs_default;
+ALTER TABLE jobs_default DROP CONSTRAINT jobs_pkey;
+DROP INDEX IF EXISTS idx_jobs_public_id;
+ALTER INDEX idx_jobs_claimed_at RENAME TO jobs_default_claimed_at_idx;
+ALTER INDEX idx_jobs_created_at_id RENAME TO jobs_default_created_at_id_idx;
+ALTER INDEX idx_jobs_customer_id RENAME TO jobs_default_customer_id_idx;
+ALTER INDEX idx_jobs_job_type_id RENAME TO jobs_default_job_type_id_idx;
+ALTER INDEX idx_jobs_lease_expires_at RENAME TO jobs_default_lease_expires_at_idx;
+ALTER INDEX idx_jobs_project_id RENAME TO jobs_default_project_id_idx;
+ALTER INDEX idx_jobs_runner_id_completed_at RENAME TO jobs_default_runner_id_completed_at_idx;
+ALTER INDEX idx_jobs_status RENAME TO jobs_default_status_idx;
+ALTER INDEX idx_jobs_waiting_expires_at RENAME TO jobs_default_waiting_expires_at_idx;
+
+CREATE TABLE jobs (LIKE jobs_default INCLUDING DEFAULTS INCLUDING CONSTRAINTS) PARTITION BY RANGE (created_at);
+ALTER TABLE jobs ADD CONSTRAINT jobs_pkey PRIMARY KEY (id, created_at);
+ALTER TABLE jobs ADD CONSTRAINT jobs_job_type_id_fkey FOREIGN KEY (job_type_id) REFERENCES job_types(id) ON DELETE RESTRICT;
+ALTER TABLE jobs ADD CONSTRAINT jobs_customer_id_fkey FOREIGN KEY (customer_id) REFERENCES customers(id) ON DELETE CASCADE;
+ALTER TABLE jobs ADD CONSTRAINT jobs_project_id_fkey FOREIGN KEY (project_id) REFERENCES projects(id) ON DELETE SET NULL;
+ALTER TABLE jobs ADD CONSTRAINT jobs_runner_id_fkey FOREIGN KEY (runner_id) REFERENCES runners(id) ON DELETE SET NULL;
+CREATE INDEX idx_jobs_claimed_at ON jobs (claimed_at) WHERE claimed_at IS NOT NULL;
+CREATE INDEX idx_jobs_created_at_id ON jobs (created_at DESC, id DESC);
+CREATE INDEX idx_jobs_customer_id ON jobs (customer_id);
+CREATE INDEX idx_jobs_job_type_id ON jobs (job_type_id);
+CREATE INDEX idx_jobs_lease_expires_at ON jobs (lease_expires_at) WHERE lease_expires_at IS NOT NULL;
+CREATE INDEX idx_jobs_project_id ON jobs (project_id);
+CREATE INDEX idx_jobs_runner_id_completed_at ON jobs (runner_id, completed_at);
+CREATE INDEX idx_jobs_status ON jobs (status);
+CREATE INDEX idx_jobs_waiting_expires_at ON jobs (expires_at) WHERE status IN ('pending', 'scheduled') AND expires_at IS NOT NULL;
+-- Enforced within a month only; public IDs are random, so clashes across months are not expected
+CREATE UNIQUE INDEX idx_jobs_public_id ON jobs (public_id, created_at);
+ALTER TABLE jobs ATTACH PARTITION jobs_default DEFAULT;
+
+-- Wallet transactions
+ALTER TABLE wallet_transactions RENAME TO wallet_transactions_default;
+ALTER TABLE wallet_transactions_default DROP CONSTRAINT wallet_transactions_pkey;
+ALTER INDEX idx_wallet_transactions_customer_id RENAME TO wallet_transactions_default_customer_id_idx;
+ALTER INDEX idx_wallet_transactions_job_id RENAME TO wallet_transactions_default_job_id_idx;
+ALTER INDEX idx_wallet_transactions_reference_id RENAME TO wallet_transactions_default_reference_id_idx;
+ALTER INDEX idx_wallet_transactions_wallet_id RENAME TO wallet_transactions_default_wallet_id_idx;
+
+CREATE TABLE wallet_transactions (LIKE wallet_transactions_default INCLUDING DEFAULTS INCLUDING CONSTRAINTS) PARTITION BY RANGE (created_at);
+ALTER TABLE wallet_transactions ADD CONSTRAINT wallet_transactions_pkey PRIMARY KEY (id, created_at);
+ALTER TABLE wallet_transactions ADD CONSTRAINT wallet_transactions_wallet_id_fkey FOREIGN KEY (wallet_id) REFERENCES wallets(id) ON DELETE CASCADE;
+ALTER TABLE wallet_transactions ADD CONSTRAINT wallet_transactions_customer_id_fkey FOREIGN KEY (customer_id) REFERENCES customers(id) ON DELETE CASCADE;
+CREATE INDEX idx_wallet_transactions_customer_id ON wallet_transactions (customer_id);
+CREATE INDEX idx_wallet_transactions_job_id ON wallet_transactions (job_id);
+CREATE INDEX idx_wallet_transactions_reference_id ON wallet_transactions (reference_id);
+CREATE INDEX idx_wallet_transactions_wallet_id ON wallet_transactions (wallet_id);
+ALTER TABLE wallet_transactions ATTACH PARTITION wallet_transactions_default DEFAULT;
@@ -0,0 +1,3 @@
+DROP TABLE IF EXISTS request_nonces;
+ALTER TABLE resellers DROP COLUMN IF EXISTS require_signed_requests;
+ALTER TABLE resellers DROP COLUMN IF EXISTS signing_secret;
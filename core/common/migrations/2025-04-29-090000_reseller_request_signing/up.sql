@@ -0,0 +1,13 @@
+-- Signed server-to-server requests of resellers, as an alternative to bearer API keys
+ALTER TABLE resellers ADD COLUMN IF NOT EXISTS signing_secret TEXT;              -- HMAC key of signed requests; NULL when signing is off
+ALTER TABLE resellers ADD COLUMN IF NOT EXISTS require_signed_requests BOOLEAN NOT NULL DEFAULT FALSE;
+
+-- Nonces of accepted signed requests, kept while their timestamp is still accepted so a captured request cannot be replayed
+CREATE TABLE IF NOT EXISTS request_nonces (
+    reseller_id UUID NOT NULL REFERENCES resellers(id) ON DELETE CASCADE,
+    nonce TEXT NOT NULL,
+    expires_at TIMESTAMP NOT NULL,
+    PRIMARY KEY (reseller_id, nonce)
+);
+
+CREATE INDEX IF NOT EXISTS idx_request_nonces_expires_at ON request_nonces(expires_at);
@@ -0,0 +1,3 @@
+DROP INDEX IF EXISTS idx_job_types_provider_id;
+ALTER TABLE job_types DROP COLUMN IF EXISTS provider_id;
+DROP TABLE IF EXISTS providers;
@@ -0,0 +1,19 @@
+-- External providers job types depend on, e.g. a third-party API, and their monitored health
+CREATE TABLE IF NOT EXISTS providers (
+    id UUID PRIMARY KEY,
+    name TEXT NOT NULL UNIQUE,
+    ping_url TEXT,                                       -- Probed with GET; NULL relies on the failure rate alone
+    failure_rate_threshold DOUBLE PRECISION NOT NULL DEFAULT 0.5,
+    min_jobs INTEGER NOT NULL DEFAULT 10,                -- Finished jobs needed before the failure rate counts
+    retry_delay_secs INTEGER NOT NULL DEFAULT 300,       -- How long new jobs are held back while the provider is down
+    status TEXT NOT NULL DEFAULT 'up',                   -- up, down
+    status_reason TEXT,
+    last_checked_at TIMESTAMP,
+    status_changed_at TIMESTAMP,
+    created_at TIMESTAMP DEFAULT CURRENT_TIMESTAMP,
+    updated_at TIMESTAMP DEFAULT CURRENT_TIMESTAMP
+);
+
+ALTER TABLE job_types ADD COLUMN IF NOT EXISTS provider_id UUID REFERENCES providers(id) ON DELETE SET NULL;
+
+CREATE INDEX IF NOT EXISTS idx_job_types_provider_id ON job_types(provider_id);
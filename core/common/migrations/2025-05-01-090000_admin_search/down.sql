@@ -0,0 +1,6 @@
+DROP INDEX IF EXISTS idx_jobs_public_id_trgm;
+DROP INDEX IF EXISTS idx_projects_name_trgm;
+DROP INDEX IF EXISTS idx_resellers_email_trgm;
+DROP INDEX IF EXISTS idx_resellers_name_trgm;
+DROP INDEX IF EXISTS idx_customers_email_trgm;
+DROP INDEX IF EXISTS idx_customers_name_trgm;
@@ -0,0 +1,9 @@
+-- Trigram indexes behind the admin search, matching any part of names, emails and job public IDs
+CREATE EXTENSION IF NOT EXISTS pg_trgm;
+
+CREATE INDEX IF NOT EXISTS idx_customers_name_trgm ON customers USING gin (name gin_trgm_ops);
+CREATE INDEX IF NOT EXISTS idx_customers_email_trgm ON customers USING gin (email gin_trgm_ops);
+CREATE INDEX IF NOT EXISTS idx_resellers_name_trgm ON resellers USING gin (name gin_trgm_ops);
+CREATE INDEX IF NOT EXISTS idx_resellers_email_trgm ON resellers USING gin (email gin_trgm_ops);
+CREATE INDEX IF NOT EXISTS idx_projects_name_trgm ON projects USING gin (name gin_trgm_ops);
+CREATE INDEX IF NOT EXISTS idx_jobs_public_id_trgm ON jobs USING gin (public_id gin_trgm_ops);
@@ -0,0 +1,5 @@
+DROP INDEX IF EXISTS idx_wallet_transactions_customer_reference;
+ALTER TABLE wallet_transactions DROP COLUMN IF EXISTS customer_metadata;
+ALTER TABLE wallet_transactions DROP COLUMN IF EXISTS customer_reference;
+ALTER TABLE jobs DROP COLUMN IF EXISTS customer_metadata;
+ALTER TABLE jobs DROP COLUMN IF EXISTS customer_reference;
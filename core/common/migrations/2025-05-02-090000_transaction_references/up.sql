@@ -0,0 +1,7 @@
+-- Customer-defined reference and metadata, e.g. a purchase order number, given on deposits
+-- and job submissions and copied onto the job's wallet transactions for reconciliation
+ALTER TABLE jobs ADD COLUMN IF NOT EXISTS customer_reference TEXT;
+ALTER TABLE jobs ADD COLUMN IF NOT EXISTS customer_metadata JSONB;
+ALTER TABLE wallet_transactions ADD COLUMN IF NOT EXISTS customer_reference TEXT;
+ALTER TABLE wallet_transactions ADD COLUMN IF NOT EXISTS customer_metadata JSONB;
+CREATE INDEX IF NOT EXISTS idx_wallet_transactions_customer_reference ON wallet_transactions (customer_id, customer_reference) WHERE customer_reference IS NOT NULL;
@@ -0,0 +1,10 @@
+-- Execution environments reported by runners at registration and on heartbeats, one row per change
+CREATE TABLE IF NOT EXISTS runner_environments (
+    id UUID PRIMARY KEY,
+    runner_id UUID NOT NULL REFERENCES runners(id) ON DELETE CASCADE,
+    fingerprint TEXT NOT NULL,                  -- SHA-256 of the environment, equal for identical environments
+    environment JSONB NOT NULL,                 -- OS, architecture, processors and library versions
+    reported_at TIMESTAMP NOT NULL DEFAULT CURRENT_TIMESTAMP
+);
+
+CREATE INDEX IF NOT EXISTS idx_runner_environments_runner_id_reported_at ON runner_environments (runner_id, reported_at DESC);
@@ -0,0 +1,4 @@
+DROP INDEX IF EXISTS idx_jobs_parent_id;
+ALTER TABLE jobs DROP COLUMN IF EXISTS sub_task_count;
+ALTER TABLE jobs DROP COLUMN IF EXISTS sub_task_index;
+ALTER TABLE jobs DROP COLUMN IF EXISTS parent_id;
@@ -0,0 +1,6 @@
+-- Jobs of batch job types fan out into sub-tasks run as child jobs on any runner; the
+-- parent job finishes, with its status and cost derived from them, once all children did
+ALTER TABLE jobs ADD COLUMN IF NOT EXISTS parent_id UUID;
+ALTER TABLE jobs ADD COLUMN IF NOT EXISTS sub_task_index INTEGER;
+ALTER TABLE jobs ADD COLUMN IF NOT EXISTS sub_task_count INTEGER;
+CREATE INDEX IF NOT EXISTS idx_jobs_parent_id ON jobs (parent_id, sub_task_index) WHERE parent_id IS NOT NULL;
@@ -0,0 +1,2 @@
+DROP TABLE IF EXISTS job_import_rows;
+DROP TABLE IF EXISTS job_imports;
@@ -0,0 +1,32 @@
+-- Spreadsheet-shaped batch submissions: each row of an uploaded CSV or NDJSON file
+-- becomes a job, submitted in the background with a result recorded per row
+CREATE TABLE IF NOT EXISTS job_imports (
+    id UUID PRIMARY KEY,
+    customer_id UUID NOT NULL REFERENCES customers(id) ON DELETE CASCADE,
+    job_type_id UUID NOT NULL REFERENCES job_types(id),
+    format TEXT NOT NULL CHECK (format IN ('csv', 'ndjson')),
+    mapping JSONB,                      -- Column -> input_data field; every column under its own name when NULL
+    content TEXT NOT NULL,              -- File as uploaded
+    priority INTEGER NOT NULL DEFAULT 1,
+    test_mode BOOLEAN NOT NULL DEFAULT FALSE,
+    status TEXT NOT NULL DEFAULT 'pending' CHECK (status IN ('pending', 'processing', 'completed', 'failed')),
+    total_rows INTEGER NOT NULL,
+    next_row INTEGER NOT NULL DEFAULT 1,    -- First row not submitted yet
+    submitted_rows INTEGER NOT NULL DEFAULT 0,
+    failed_rows INTEGER NOT NULL DEFAULT 0,
+    created_at TIMESTAMP DEFAULT CURRENT_TIMESTAMP,
+    updated_at TIMESTAMP DEFAULT CURRENT_TIMESTAMP,
+    completed_at TIMESTAMP
+);
+
+CREATE TABLE IF NOT EXISTS job_import_rows (
+    import_id UUID NOT NULL REFERENCES job_imports(id) ON DELETE CASCADE,
+    row_number INTEGER NOT NULL,
+    job_id UUID,                        -- Job submitted for the row; recorded when the row is claimed, before the job is stored
+    error TEXT,                         -- Why no job was submitted for the row
+    created_at TIMESTAMP DEFAULT CURRENT_TIMESTAMP,
+    PRIMARY KEY (import_id, row_number)
+);
+
+CREATE INDEX IF NOT EXISTS idx_job_imports_customer_id ON job_imports(customer_id, created_at DESC);
+CREATE INDEX IF NOT EXISTS idx_job_imports_unfinished ON job_imports(created_at) WHERE status IN ('pending', 'processing');
@@ -0,0 +1,4 @@
+ALTER TABLE wallets DROP COLUMN IF EXISTS credit_cents;
+ALTER TABLE resellers DROP COLUMN IF EXISTS markup_rate;
+ALTER TABLE invoices DROP COLUMN IF EXISTS cost_breakdown;
+ALTER TABLE jobs DROP COLUMN IF EXISTS cost_breakdown;
@@ -0,0 +1,10 @@
+-- Job charges are itemized: each charged job keeps the breakdown of its cost and each
+-- invoice the total of its jobs' breakdowns
+ALTER TABLE jobs ADD COLUMN IF NOT EXISTS cost_breakdown JSONB;
+ALTER TABLE invoices ADD COLUMN IF NOT EXISTS cost_breakdown JSONB;
+
+-- Markup resellers add to the price of their customers' jobs, in basis points
+ALTER TABLE resellers ADD COLUMN IF NOT EXISTS markup_rate INTEGER NOT NULL DEFAULT 0 CHECK (markup_rate >= 0);
+
+-- Promotional credit granted by admins, spent on job charges before the balance
+ALTER TABLE wallets ADD COLUMN IF NOT EXISTS credit_cents INTEGER NOT NULL DEFAULT 0 CHECK (credit_cents >= 0);
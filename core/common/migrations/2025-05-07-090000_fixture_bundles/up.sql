@@ -0,0 +1,16 @@
+-- Bundles of test data provisioned through /admin/fixtures on staging environments,
+-- with the records each created so it can be torn down as a whole
+CREATE TABLE IF NOT EXISTS fixture_bundles (
+    id UUID PRIMARY KEY,
+    name TEXT NOT NULL,
+    spec JSONB NOT NULL,                -- Spec the bundle was provisioned from
+    reseller_id UUID NOT NULL,
+    job_type_id UUID NOT NULL,
+    customer_ids UUID[] NOT NULL DEFAULT '{}',
+    runner_ids UUID[] NOT NULL DEFAULT '{}',
+    job_count INTEGER NOT NULL DEFAULT 0,
+    created_by TEXT NOT NULL,
+    created_at TIMESTAMP DEFAULT CURRENT_TIMESTAMP
+);
+
+CREATE INDEX IF NOT EXISTS idx_fixture_bundles_name ON fixture_bundles(name, created_at DESC);
@@ -0,0 +1,5 @@
+ALTER TABLE invoices DROP COLUMN IF EXISTS timezone;
+ALTER TABLE invoices DROP COLUMN IF EXISTS period_end;
+ALTER TABLE invoices DROP COLUMN IF EXISTS period_start;
+ALTER TABLE resellers DROP COLUMN IF EXISTS timezone;
+ALTER TABLE customers DROP COLUMN IF EXISTS timezone;
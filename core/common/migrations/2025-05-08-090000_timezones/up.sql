@@ -0,0 +1,17 @@
+-- IANA time zone of customers and resellers; customers without one follow their
+-- reseller's, and UTC applies when neither is set
+ALTER TABLE customers ADD COLUMN IF NOT EXISTS timezone TEXT;
+ALTER TABLE resellers ADD COLUMN IF NOT EXISTS timezone TEXT;
+
+-- Invoices cover the period in their customer's time zone, so each keeps its own bounds
+ALTER TABLE invoices ADD COLUMN IF NOT EXISTS period_start TIMESTAMP;
+ALTER TABLE invoices ADD COLUMN IF NOT EXISTS period_end TIMESTAMP;
+ALTER TABLE invoices ADD COLUMN IF NOT EXISTS timezone TEXT NOT NULL DEFAULT 'UTC';
+
+UPDATE invoices
+SET period_start = billing_periods.period_start, period_end = billing_periods.period_end
+FROM billing_periods
+WHERE billing_periods.id = invoices.billing_period_id AND invoices.period_start IS NULL;
+
+ALTER TABLE invoices ALTER COLUMN period_start SET NOT NULL;
+ALTER TABLE invoices ALTER COLUMN period_end SET NOT NULL;
@@ -0,0 +1,2 @@
+ALTER TABLE wallets DROP COLUMN IF EXISTS overdraft_limit_cents;
+ALTER TABLE wallets DROP COLUMN IF EXISTS overdraft_policy;
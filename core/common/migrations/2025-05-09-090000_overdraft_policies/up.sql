@@ -0,0 +1,7 @@
+-- What happens when a job's reservation would take a wallet below zero: 'deny' it,
+-- allow it down to minus the overdraft limit ('grace'), or allow it as far and refuse
+-- new submissions until the wallet is topped up ('suspend')
+ALTER TABLE wallets ADD COLUMN IF NOT EXISTS overdraft_policy TEXT NOT NULL DEFAULT 'deny'
+    CHECK (overdraft_policy IN ('deny', 'grace', 'suspend'));
+ALTER TABLE wallets ADD COLUMN IF NOT EXISTS overdraft_limit_cents INTEGER NOT NULL DEFAULT 0
+    CHECK (overdraft_limit_cents >= 0);
@@ -0,0 +1,22 @@
+-- Outbox of finalized job charges exported to an external billing system: one row per
+-- charged job, posted with its idempotency key until the endpoint accepts it
+CREATE TABLE IF NOT EXISTS billing_exports (
+    id UUID PRIMARY KEY,
+    job_id UUID NOT NULL UNIQUE,
+    customer_id UUID NOT NULL,
+    amount_cents INTEGER NOT NULL,
+    cost_breakdown JSONB NOT NULL,
+    idempotency_key TEXT NOT NULL UNIQUE,     -- Sent with every attempt, so the endpoint can drop duplicates
+    status TEXT NOT NULL DEFAULT 'pending' CHECK (status IN ('pending', 'exported', 'failed')),
+    attempts INTEGER NOT NULL DEFAULT 0,
+    status_code INTEGER,
+    last_error TEXT,
+    next_attempt_at TIMESTAMP,
+    completed_at TIMESTAMP NOT NULL,          -- When the job completed
+    exported_at TIMESTAMP,
+    created_at TIMESTAMP NOT NULL DEFAULT CURRENT_TIMESTAMP,
+    updated_at TIMESTAMP NOT NULL DEFAULT CURRENT_TIMESTAMP
+);
+
+CREATE INDEX IF NOT EXISTS idx_billing_exports_due ON billing_exports(next_attempt_at) WHERE status = 'pending';
+CREATE INDEX IF NOT EXISTS idx_billing_exports_completed_at ON billing_exports(completed_at);
@@ -0,0 +1,2 @@
+DROP INDEX IF EXISTS idx_notification_deliveries_queued;
+DROP TABLE IF EXISTS notification_preferences;
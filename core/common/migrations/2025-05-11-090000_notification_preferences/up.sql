@@ -0,0 +1,17 @@
+-- How a customer wants to hear about each event on each channel: right away
+-- ('immediate'), bundled into a periodic digest ('digest') or not at all ('off');
+-- events without a preference are sent right away
+CREATE TABLE IF NOT EXISTS notification_preferences (
+    id UUID PRIMARY KEY,
+    customer_id UUID NOT NULL REFERENCES customers(id) ON DELETE CASCADE,
+    event_type TEXT NOT NULL,
+    channel TEXT NOT NULL,              -- Must be one of: "webhook", "email"
+    mode TEXT NOT NULL CHECK (mode IN ('immediate', 'digest', 'off')),
+    created_at TIMESTAMP DEFAULT CURRENT_TIMESTAMP,
+    updated_at TIMESTAMP DEFAULT CURRENT_TIMESTAMP,
+    UNIQUE (customer_id, event_type, channel)
+);
+
+-- Deliveries held back for the next digest are 'queued' until it is sent
+CREATE INDEX IF NOT EXISTS idx_notification_deliveries_queued
+    ON notification_deliveries(channel, created_at) WHERE status = 'queued';
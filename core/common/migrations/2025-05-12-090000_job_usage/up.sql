@@ -0,0 +1,17 @@
+-- Resources a job used on its runner, reported with its completion: one row per job,
+-- replaced when a retried job completes again
+CREATE TABLE IF NOT EXISTS job_usage (
+    job_id UUID PRIMARY KEY,
+    attempt_id UUID,
+    runner_id UUID,
+    customer_id UUID NOT NULL,
+    job_type_id UUID NOT NULL,
+    wall_time_ms BIGINT NOT NULL,
+    cpu_time_ms BIGINT,                       -- Unknown where the runner cannot measure it
+    peak_memory_bytes BIGINT,                 -- Peak resident memory of the runner while the job ran
+    bytes_sent BIGINT NOT NULL DEFAULT 0,     -- Request bodies of webhook and external API calls
+    bytes_received BIGINT NOT NULL DEFAULT 0, -- Response bodies of those calls
+    recorded_at TIMESTAMP NOT NULL DEFAULT CURRENT_TIMESTAMP
+);
+
+CREATE INDEX IF NOT EXISTS idx_job_usage_recorded_at ON job_usage(recorded_at);
@@ -0,0 +1,23 @@
+-- Upcoming load spikes declared by admins, e.g. a nightly batch, for which the runner
+-- fleet is scaled up ahead of time; fleet-wide, so they only live in the shared schema
+CREATE TABLE IF NOT EXISTS load_windows (
+    id UUID PRIMARY KEY,
+    name TEXT NOT NULL,
+    pool TEXT,                                         -- Name the pool's runners register under; NULL for the whole fleet
+    job_type_ids UUID[] NOT NULL DEFAULT '{}',         -- Scheduled jobs held until the pool is ready
+    target_runners INTEGER NOT NULL,
+    starts_at TIMESTAMP NOT NULL,
+    ends_at TIMESTAMP NOT NULL,
+    warm_up_minutes INTEGER NOT NULL DEFAULT 15,       -- How long before the start autoscalers are told to scale up
+    max_hold_minutes INTEGER NOT NULL DEFAULT 30,      -- How long after the start jobs wait for the pool at most
+    status TEXT NOT NULL DEFAULT 'planned',            -- planned, warming, ready, released, cancelled
+    active_runners INTEGER,                            -- Runners of the pool at the last check
+    notified_at TIMESTAMP,
+    ready_at TIMESTAMP,
+    released_at TIMESTAMP,
+    created_by TEXT NOT NULL,
+    created_at TIMESTAMP NOT NULL DEFAULT CURRENT_TIMESTAMP,
+    updated_at TIMESTAMP NOT NULL DEFAULT CURRENT_TIMESTAMP
+);
+
+CREATE INDEX IF NOT EXISTS idx_load_windows_status_starts_at ON load_windows(status, starts_at);
@@ -0,0 +1,3 @@
+DROP INDEX IF EXISTS idx_jobs_submission_id;
+ALTER TABLE jobs DROP COLUMN IF EXISTS submission_id;
+DROP TABLE IF EXISTS submissions;
@@ -0,0 +1,14 @@
+-- Jobs a customer submitted together, through the batch endpoint or a file import,
+-- tracked and cancelled as a group
+CREATE TABLE IF NOT EXISTS submissions (
+    id UUID PRIMARY KEY,                -- The import's ID for the jobs of a file import
+    customer_id UUID NOT NULL REFERENCES customers(id) ON DELETE CASCADE,
+    source TEXT NOT NULL CHECK (source IN ('batch', 'import')),
+    created_at TIMESTAMP NOT NULL DEFAULT CURRENT_TIMESTAMP,
+    cancelled_at TIMESTAMP
+);
+
+CREATE INDEX IF NOT EXISTS idx_submissions_customer_id ON submissions(customer_id, created_at DESC);
+
+ALTER TABLE jobs ADD COLUMN IF NOT EXISTS submission_id UUID;
+CREATE INDEX IF NOT EXISTS idx_jobs_submission_id ON jobs (submission_id) WHERE submission_id IS NOT NULL;
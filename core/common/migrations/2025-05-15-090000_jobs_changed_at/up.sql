@@ -0,0 +1,2 @@
+-- Jobs by the time their status last changed, for following job changes in order
+CREATE INDEX IF NOT EXISTS idx_jobs_changed_at ON jobs (COALESCE(updated_at, created_at), id);
@@ -0,0 +1,4 @@
+DROP INDEX IF EXISTS idx_resellers_sandbox_expires_at;
+DROP INDEX IF EXISTS idx_resellers_sandbox_of;
+ALTER TABLE resellers DROP COLUMN IF EXISTS sandbox_expires_at;
+ALTER TABLE resellers DROP COLUMN IF EXISTS sandbox_of;
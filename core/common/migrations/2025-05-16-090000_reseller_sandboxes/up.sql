@@ -0,0 +1,7 @@
+-- Sandbox tenants resellers spin up to demo and develop against: a reseller of its own
+-- whose customers only run test jobs, deleted with its data once it expires
+ALTER TABLE resellers ADD COLUMN IF NOT EXISTS sandbox_of UUID REFERENCES resellers(id) ON DELETE CASCADE;
+ALTER TABLE resellers ADD COLUMN IF NOT EXISTS sandbox_expires_at TIMESTAMP;
+
+CREATE INDEX IF NOT EXISTS idx_resellers_sandbox_of ON resellers (sandbox_of) WHERE sandbox_of IS NOT NULL;
+CREATE INDEX IF NOT EXISTS idx_resellers_sandbox_expires_at ON resellers (sandbox_expires_at) WHERE sandbox_expires_at IS NOT NULL;
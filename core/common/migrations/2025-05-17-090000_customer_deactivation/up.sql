@@ -0,0 +1,3 @@
+-- Deactivated customers can no longer authenticate; deactivating one with queued jobs
+-- or reserved funds is refused unless forced
+ALTER TABLE customers ADD COLUMN IF NOT EXISTS active BOOLEAN NOT NULL DEFAULT TRUE;
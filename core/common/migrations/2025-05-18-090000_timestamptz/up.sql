@@ -0,0 +1,133 @@
+-- Store every point in time as timestamptz, so values are unambiguous instants rather
+-- than wall-clock times the application has to know are in UTC
+--
+-- Existing values were written in UTC. Tables in the schemas of isolated resellers are
+-- converted with those in `public`, as schema syncs only add missing columns.
+-- The partition key of a partitioned table cannot change type, so the partitioned
+-- tables are recreated around their partitions, keeping their keys and indexes.
+
+SET LOCAL TimeZone = 'UTC';
+
+DO $$
+DECLARE
+    col RECORD;
+    parent RECORD;
+    children TEXT[];
+    bounds TEXT[];
+    con_names TEXT[];
+    con_defs TEXT[];
+    idx_defs TEXT[];
+    expr_names TEXT[];
+    expr_defs TEXT[];
+    i INT;
+BEGIN
+    -- Expression indexes cannot mix converted and unconverted columns, so they are
+    -- recreated once all columns are converted
+    SELECT array_agg(format('%I.%I', n.nspname, ic.relname)), array_agg(replace(pg_get_indexdef(ix.indexrelid), ' ON ONLY ', ' ON '))
+    INTO expr_names, expr_defs
+    FROM pg_index ix
+    JOIN pg_class ic ON ic.oid = ix.indexrelid
+    JOIN pg_class c ON c.oid = ix.indrelid
+    JOIN pg_namespace n ON n.oid = c.relnamespace
+    WHERE ix.indexprs IS NOT NULL AND NOT c.relispartition
+      AND n.nspname NOT IN ('pg_catalog', 'information_schema') AND n.nspname NOT LIKE 'pg\_%'
+      AND EXISTS (SELECT 1 FROM pg_attribute a WHERE a.attrelid = c.oid AND a.atttypid = 'timestamp'::regtype AND NOT a.attisdropped);
+    FOR i IN 1 .. coalesce(array_length(expr_names, 1), 0) LOOP
+        EXECUTE 'DROP INDEX ' || expr_names[i];
+    END LOOP;
+
+    -- Columns of plain tables, and those of partitioned tables other than their
+    -- partition key, which are converted on all partitions at once
+    FOR col IN
+        SELECT n.nspname, c.relname, a.attname
+        FROM pg_attribute a
+        JOIN pg_class c ON c.oid = a.attrelid
+        JOIN pg_namespace n ON n.oid = c.relnamespace
+        LEFT JOIN pg_partitioned_table pt ON pt.partrelid = c.oid
+        WHERE a.atttypid = 'timestamp'::regtype AND a.attnum > 0 AND NOT a.attisdropped
+          AND c.relkind IN ('r', 'p') AND NOT c.relispartition
+          AND n.nspname NOT IN ('pg_catalog', 'information_schema') AND n.nspname NOT LIKE 'pg\_%'
+          AND c.relname <> '__diesel_schema_migrations'
+          AND (pt.partrelid IS NULL OR NOT a.attnum = ANY(pt.partattrs::int2[]))
+    LOOP
+        EXECUTE format(
+            'ALTER TABLE %I.%I ALTER COLUMN %I TYPE timestamptz USING %I AT TIME ZONE ''UTC''',
+            col.nspname, col.relname, col.attname, col.attname
+        );
+    END LOOP;
+
+    -- Partitioned tables keyed by a timestamp: detach the partitions, convert them and
+    -- attach them to a partitioned table recreated with the converted key
+    FOR parent IN
+        SELECT n.nspname, c.relname, c.oid, a.attname AS key
+        FROM pg_partitioned_table pt
+        JOIN pg_class c ON c.oid = pt.partrelid
+        JOIN pg_namespace n ON n.oid = c.relnamespace
+        JOIN pg_attribute a ON a.attrelid = c.oid AND a.attnum = ANY(pt.partattrs::int2[])
+        WHERE a.atttypid = 'timestamp'::regtype AND NOT c.relispartition
+    LOOP
+        SELECT array_agg(k.relname ORDER BY pg_get_expr(k.relpartbound, k.oid) = 'DEFAULT', k.relname),
+               array_agg(pg_get_expr(k.relpartbound, k.oid) ORDER BY pg_get_expr(k.relpartbound, k.oid) = 'DEFAULT', k.relname)
+        INTO children, bounds
+        FROM pg_inherits inh
+        JOIN pg_class k ON k.oid = inh.inhrelid
+        WHERE inh.inhparent = parent.oid;
+
+        -- Check constraints are copied with the columns below
+        SELECT array_agg(conname ORDER BY position(contype::text IN 'puf'), conname),
+               array_agg(pg_get_constraintdef(oid) ORDER BY position(contype::text IN 'puf'), conname)
+        INTO con_names, con_defs
+        FROM pg_constraint
+        WHERE conrelid = parent.oid AND contype IN ('p', 'u', 'f');
+
+        SELECT array_agg(replace(pg_get_indexdef(ix.indexrelid), ' ON ONLY ', ' ON '))
+        INTO idx_defs
+        FROM pg_index ix
+        WHERE ix.indrelid = parent.oid
+          AND NOT EXISTS (SELECT 1 FROM pg_constraint WHERE conindid = ix.indexrelid AND conrelid = parent.oid);
+
+        FOR i IN 1 .. coalesce(array_length(children, 1), 0) LOOP
+            EXECUTE format('ALTER TABLE %I.%I DETACH PARTITION %I.%I', parent.nspname, parent.relname, parent.nspname, children[i]);
+            FOR col IN
+                SELECT a.attname
+                FROM pg_attribute a
+                JOIN pg_class c ON c.oid = a.attrelid
+                JOIN pg_namespace n ON n.oid = c.relnamespace
+                WHERE n.nspname = parent.nspname AND c.relname = children[i]
+                  AND a.atttypid = 'timestamp'::regtype AND a.attnum > 0 AND NOT a.attisdropped
+            LOOP
+                EXECUTE format(
+                    'ALTER TABLE %I.%I ALTER COLUMN %I TYPE timestamptz USING %I AT TIME ZONE ''UTC''',
+                    parent.nspname, children[i], col.attname, col.attname
+                );
+            END LOOP;
+        END LOOP;
+
+        -- The converted columns of the old table, with its defaults and check constraints
+        EXECUTE format('ALTER TABLE %I.%I RENAME TO %I', parent.nspname, parent.relname, parent.relname || '_timestamp');
+        EXECUTE format(
+            'CREATE TABLE %I.%I (LIKE %I.%I INCLUDING DEFAULTS INCLUDING CONSTRAINTS) PARTITION BY RANGE (%I)',
+            parent.nspname, parent.relname, parent.nspname, children[array_length(children, 1)], parent.key
+        );
+        EXECUTE format('DROP TABLE %I.%I', parent.nspname, parent.relname || '_timestamp');
+
+        FOR i IN 1 .. coalesce(array_length(con_names, 1), 0) LOOP
+            EXECUTE format('ALTER TABLE %I.%I ADD CONSTRAINT %I %s', parent.nspname, parent.relname, con_names[i], con_defs[i]);
+        END LOOP;
+        FOR i IN 1 .. coalesce(array_length(idx_defs, 1), 0) LOOP
+            EXECUTE idx_defs[i];
+        END LOOP;
+
+        -- Monthly partitions first, so the default partition is not scanned for their rows
+        FOR i IN 1 .. coalesce(array_length(children, 1), 0) LOOP
+            EXECUTE format(
+                'ALTER TABLE %I.%I ATTACH PARTITION %I.%I %s',
+                parent.nspname, parent.relname, parent.nspname, children[i], bounds[i]
+            );
+        END LOOP;
+    END LOOP;
+
+    FOR i IN 1 .. coalesce(array_length(expr_defs, 1), 0) LOOP
+        EXECUTE expr_defs[i];
+    END LOOP;
+END $$;
@@ -0,0 +1,16 @@
+-- Anonymized inputs of historical jobs, replayed by runners in replay mode against new
+-- processor versions and compared with the output and cost recorded as baseline
+CREATE TABLE IF NOT EXISTS replay_cases (
+    id UUID PRIMARY KEY,
+    job_type_id UUID NOT NULL REFERENCES job_types(id) ON DELETE CASCADE,
+    source_job_id UUID NOT NULL,
+    priority INTEGER NOT NULL,
+    input_data JSONB NOT NULL,
+    markup_rate INTEGER NOT NULL DEFAULT 0,
+    sampled_at TIMESTAMPTZ NOT NULL DEFAULT NOW(),
+    baseline_output JSONB,
+    baseline_cost_cents INTEGER,
+    baseline_version TEXT,
+    baseline_recorded_at TIMESTAMPTZ,
+    UNIQUE (job_type_id, source_job_id)
+);
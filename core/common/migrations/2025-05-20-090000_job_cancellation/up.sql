@@ -0,0 +1,3 @@
+-- Cancellation of a running job, requested by its customer or an admin and picked up
+-- by the runner processing it
+ALTER TABLE jobs ADD COLUMN IF NOT EXISTS cancel_requested_at TIMESTAMPTZ;
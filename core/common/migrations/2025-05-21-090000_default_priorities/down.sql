@@ -0,0 +1,2 @@
+ALTER TABLE customers DROP COLUMN IF EXISTS default_priority;
+ALTER TABLE job_types DROP COLUMN IF EXISTS default_priority;
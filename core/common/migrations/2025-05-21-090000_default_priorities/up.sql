@@ -0,0 +1,4 @@
+-- Priority given to jobs submitted without one: the customer's if set, otherwise the
+-- job type's, otherwise medium
+ALTER TABLE job_types ADD COLUMN IF NOT EXISTS default_priority INTEGER CHECK (default_priority BETWEEN 0 AND 3);
+ALTER TABLE customers ADD COLUMN IF NOT EXISTS default_priority INTEGER CHECK (default_priority BETWEEN 0 AND 3);
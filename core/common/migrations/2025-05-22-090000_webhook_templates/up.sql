@@ -0,0 +1,3 @@
+-- How the webhook jobs of a job type call their URL: method, headers and a body
+-- template; jobs without one post their input_data
+ALTER TABLE job_types ADD COLUMN IF NOT EXISTS webhook_template JSONB;
@@ -0,0 +1,2 @@
+DROP TABLE IF EXISTS job_artifacts;
+ALTER TABLE job_types DROP COLUMN IF EXISTS response_policy;
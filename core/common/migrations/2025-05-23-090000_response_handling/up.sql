@@ -0,0 +1,15 @@
+-- How the runner captures the responses of webhook calls: size limit, JSON parsing and
+-- where binary bodies go; NULL applies the defaults
+ALTER TABLE job_types ADD COLUMN IF NOT EXISTS response_policy JSONB;
+
+-- Binary response bodies kept apart from the job's output, which refers to them by ID
+CREATE TABLE IF NOT EXISTS job_artifacts (
+    id UUID PRIMARY KEY,
+    job_id UUID NOT NULL,
+    content_type TEXT NOT NULL,
+    size_bytes BIGINT NOT NULL,
+    content BYTEA NOT NULL,
+    created_at TIMESTAMPTZ NOT NULL DEFAULT CURRENT_TIMESTAMP
+);
+
+CREATE INDEX IF NOT EXISTS idx_job_artifacts_job_id ON job_artifacts(job_id);
@@ -0,0 +1,3 @@
+-- Failed or expired job an admin requeued this one from, keeping the lineage of the
+-- original, which is neither changed nor billed again
+ALTER TABLE jobs ADD COLUMN IF NOT EXISTS requeued_from UUID;
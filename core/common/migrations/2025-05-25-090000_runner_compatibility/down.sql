@@ -0,0 +1,11 @@
+ALTER TABLE runners ADD COLUMN IF NOT EXISTS compatible_job_types TEXT[] NOT NULL DEFAULT '{}';
+
+UPDATE runners
+SET compatible_job_types = compatible.names
+FROM (
+    SELECT runner_job_type_compatibility.runner_id, array_agg(job_types.name ORDER BY job_types.name) AS names
+    FROM runner_job_type_compatibility
+    JOIN job_types ON job_types.id = runner_job_type_compatibility.job_type_id
+    GROUP BY runner_job_type_compatibility.runner_id
+) AS compatible
+WHERE runners.id = compatible.runner_id;
@@ -0,0 +1,10 @@
+-- Runner compatibility is kept only as job type IDs in runner_job_type_compatibility;
+-- names listed on runners are carried over where a job type has the name
+INSERT INTO runner_job_type_compatibility (runner_id, job_type_id)
+SELECT runners.id, job_types.id
+FROM runners
+CROSS JOIN LATERAL unnest(runners.compatible_job_types) AS listed(name)
+JOIN job_types ON job_types.name = listed.name
+ON CONFLICT (runner_id, job_type_id) DO NOTHING;
+
+ALTER TABLE runners DROP COLUMN IF EXISTS compatible_job_types;
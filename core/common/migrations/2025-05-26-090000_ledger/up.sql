@@ -0,0 +1,43 @@
+-- Double-entry ledger of the money flow: every job debit moves the charge out of the
+-- customer's wallet account into the platform's revenue and the reseller's commission
+CREATE TABLE IF NOT EXISTS ledger_accounts (
+    id UUID PRIMARY KEY,
+    kind TEXT NOT NULL CHECK (kind IN ('customer_wallet', 'platform_revenue', 'reseller_commission')),
+    owner_id UUID,                      -- Wallet or reseller the account belongs to; none for the platform
+    created_at TIMESTAMPTZ NOT NULL DEFAULT NOW(),
+    CHECK ((kind = 'platform_revenue') = (owner_id IS NULL))
+);
+
+CREATE UNIQUE INDEX IF NOT EXISTS idx_ledger_accounts_kind_owner ON ledger_accounts (kind, owner_id) WHERE owner_id IS NOT NULL;
+CREATE UNIQUE INDEX IF NOT EXISTS idx_ledger_accounts_platform ON ledger_accounts (kind) WHERE owner_id IS NULL;
+
+-- Entries are grouped by the wallet transaction they post; what an account receives is
+-- positive, what it gives negative
+CREATE TABLE IF NOT EXISTS ledger_entries (
+    id UUID PRIMARY KEY,
+    transaction_id UUID NOT NULL,
+    account_id UUID NOT NULL REFERENCES ledger_accounts(id) ON DELETE RESTRICT,
+    job_id UUID,
+    amount_cents BIGINT NOT NULL CHECK (amount_cents <> 0),
+    created_at TIMESTAMPTZ NOT NULL DEFAULT NOW()
+);
+
+CREATE INDEX IF NOT EXISTS idx_ledger_entries_transaction_id ON ledger_entries (transaction_id);
+CREATE INDEX IF NOT EXISTS idx_ledger_entries_account_id ON ledger_entries (account_id);
+CREATE INDEX IF NOT EXISTS idx_ledger_entries_job_id ON ledger_entries (job_id) WHERE job_id IS NOT NULL;
+
+-- The entries of a transaction must add up to zero once it commits
+CREATE OR REPLACE FUNCTION ledger_transaction_balanced() RETURNS TRIGGER AS $$
+BEGIN
+    IF (SELECT SUM(amount_cents) FROM ledger_entries WHERE transaction_id = NEW.transaction_id) <> 0 THEN
+        RAISE EXCEPTION 'Ledger entries of transaction % do not balance', NEW.transaction_id;
+    END IF;
+    RETURN NULL;
+END;
+$$ LANGUAGE plpgsql;
+
+DROP TRIGGER IF EXISTS ledger_entries_balanced ON ledger_entries;
+CREATE CONSTRAINT TRIGGER ledger_entries_balanced
+    AFTER INSERT OR UPDATE ON ledger_entries
+    DEFERRABLE INITIALLY DEFERRED
+    FOR EACH ROW EXECUTE FUNCTION ledger_transaction_balanced();
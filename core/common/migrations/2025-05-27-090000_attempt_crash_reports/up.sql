@@ -0,0 +1,2 @@
+-- Crash a runner reported for the attempt it was running when it went down
+ALTER TABLE job_attempts ADD COLUMN IF NOT EXISTS crash JSONB;
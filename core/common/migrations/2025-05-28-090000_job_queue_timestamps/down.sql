@@ -0,0 +1,2 @@
+ALTER TABLE jobs DROP COLUMN IF EXISTS started_at;
+ALTER TABLE jobs DROP COLUMN IF EXISTS queued_at;
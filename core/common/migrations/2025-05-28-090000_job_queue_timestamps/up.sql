@@ -0,0 +1,10 @@
+-- When a job entered the queue and when a runner started it, so that queue wait and
+-- processing time can be told apart; updated_at changes with every status update
+ALTER TABLE jobs ADD COLUMN IF NOT EXISTS queued_at TIMESTAMPTZ;
+ALTER TABLE jobs ADD COLUMN IF NOT EXISTS started_at TIMESTAMPTZ;
+
+-- Jobs are queued when submitted, or when released if they were scheduled; when
+-- existing jobs started is not known
+UPDATE jobs SET queued_at = GREATEST(created_at, scheduled_for) WHERE queued_at IS NULL;
+
+ALTER TABLE jobs ALTER COLUMN queued_at SET DEFAULT NOW();
@@ -0,0 +1,3 @@
+ALTER TABLE jobs DROP COLUMN IF EXISTS error;
+ALTER TABLE jobs DROP COLUMN IF EXISTS output_data;
+ALTER TABLE jobs DROP COLUMN IF EXISTS input_data;
@@ -0,0 +1,7 @@
+-- Payload a job was submitted with and the result it produced, so that runners process
+-- the input the API accepted; jobs created before are left with a null input
+ALTER TABLE jobs ADD COLUMN IF NOT EXISTS input_data JSONB NOT NULL DEFAULT 'null'::jsonb;
+ALTER TABLE jobs ALTER COLUMN input_data DROP DEFAULT;
+ALTER TABLE jobs ADD COLUMN IF NOT EXISTS output_data JSONB;
+-- Structured error of a failed job, as a serialized JobError
+ALTER TABLE jobs ADD COLUMN IF NOT EXISTS error JSONB;
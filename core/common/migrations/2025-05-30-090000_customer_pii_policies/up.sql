@@ -0,0 +1,2 @@
+-- What to do with jobs of a customer whose input contains personal data; not scanned when unset
+ALTER TABLE customers ADD COLUMN IF NOT EXISTS pii_policy TEXT CHECK (pii_policy IN ('reject', 'mask', 'flag'));
@@ -0,0 +1,7 @@
+-- Priority a job was submitted with, 0 (low) to 3 (critical), so that jobs can be sorted
+-- by it; existing jobs get the level they were claimed at, or medium if not claimed yet
+ALTER TABLE jobs ADD COLUMN IF NOT EXISTS priority INTEGER NOT NULL DEFAULT 1;
+UPDATE jobs SET priority = queue_priority WHERE queue_priority IS NOT NULL;
+ALTER TABLE jobs ALTER COLUMN priority DROP DEFAULT;
+
+CREATE INDEX IF NOT EXISTS idx_jobs_priority ON jobs(priority, created_at);
@@ -0,0 +1,20 @@
+-- Reference IDs of wallet postings, unique per wallet, so that an operation retried after
+-- it went through, e.g. the charge of a job, is not posted twice
+--
+-- Unique keys of the partitioned wallet_transactions table must include created_at, which
+-- differs between retries, so the references are kept here. They stay when old months
+-- of transactions are dropped, so a reference is never posted again.
+CREATE TABLE IF NOT EXISTS wallet_transaction_references (
+    wallet_id UUID NOT NULL REFERENCES wallets(id) ON DELETE CASCADE,
+    reference_id UUID NOT NULL,
+    transaction_id UUID NOT NULL,
+    created_at TIMESTAMPTZ NOT NULL DEFAULT NOW(),
+    PRIMARY KEY (wallet_id, reference_id)
+);
+
+-- Wallet adjustments are the only postings that carried a reference so far
+INSERT INTO wallet_transaction_references (wallet_id, reference_id, transaction_id, created_at)
+SELECT wallet_id, reference_id, id, created_at
+FROM wallet_transactions
+WHERE reference_id IS NOT NULL
+ON CONFLICT DO NOTHING;
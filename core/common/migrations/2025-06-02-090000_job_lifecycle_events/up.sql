@@ -0,0 +1,15 @@
+-- Outbox of job lifecycle events sent to customers: one row per job and event, captured
+-- from the jobs as they start and finish and notified to the customer's webhooks once
+CREATE TABLE IF NOT EXISTS job_lifecycle_events (
+    id UUID PRIMARY KEY,
+    job_id UUID NOT NULL,
+    customer_id UUID NOT NULL,
+    event_type TEXT NOT NULL CHECK (event_type IN ('job.started', 'job.succeeded', 'job.failed')),
+    data JSONB NOT NULL,                      -- Event data as sent to the customer
+    occurred_at TIMESTAMPTZ NOT NULL,         -- When the job started or finished
+    notified_at TIMESTAMPTZ,                  -- When the event was handed to the customer's endpoints
+    created_at TIMESTAMPTZ NOT NULL DEFAULT NOW(),
+    UNIQUE (job_id, event_type)
+);
+
+CREATE INDEX IF NOT EXISTS idx_job_lifecycle_events_pending ON job_lifecycle_events(occurred_at) WHERE notified_at IS NULL;
@@ -0,0 +1,114 @@
+//! Response envelopes shared by the API and its clients
+//!
+//! Handlers build their list pages and error bodies from these types, and clients
+//! deserialize them from the same definitions, so the two sides cannot drift apart.
+//! Errors are a machine-readable code plus details specific to the code, e.g.
+//!
+//! ```json
+//! {"error": "queue_saturated", "retry_after_secs": 30}
+//! ```
+
+use serde::{Deserialize, Serialize};
+use serde_json::{Map, Value};
+
+/// Paging of a list response
+///
+/// Offset-paged lists report `total`, `limit` and `offset`; keyset-paged lists report
+/// `limit` and, while more items follow, the `next_cursor` to pass for the next page.
+/// Fields that do not apply are left out.
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct ApiListMeta {
+    /// Number of items across all pages
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub total: Option<i64>,
+    /// Maximum number of items in a page
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub limit: Option<i64>,
+    /// Number of items skipped before this page
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub offset: Option<i64>,
+    /// Opaque cursor of the page after this one
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub next_cursor: Option<String>,
+}
+
+impl ApiListMeta {
+    /// Meta of an offset-paged list
+    pub fn offset(total: i64, limit: i64, offset: i64) -> Self {
+        Self {
+            total: Some(total),
+            limit: Some(limit),
+            offset: Some(offset),
+            next_cursor: None,
+        }
+    }
+
+    /// Meta of a keyset-paged list, with no cursor on the last page
+    pub fn keyset(limit: i64, next_cursor: Option<String>) -> Self {
+        Self {
+            limit: Some(limit),
+            next_cursor,
+            ..Default::default()
+        }
+    }
+
+    /// Whether another page follows this one
+    pub fn has_more(&self) -> bool {
+        match (self.next_cursor.as_ref(), self.total, self.limit, self.offset) {
+            (Some(_), ..) => true,
+            (None, Some(total), Some(limit), Some(offset)) => offset + limit < total,
+            _ => false,
+        }
+    }
+}
+
+/// A page of a list response
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct Paginated<T> {
+    pub data: Vec<T>,
+    pub meta: ApiListMeta,
+}
+
+impl<T> Paginated<T> {
+    pub fn new(data: Vec<T>, meta: ApiListMeta) -> Self {
+        Self { data, meta }
+    }
+
+    /// Convert the items of the page, keeping its meta
+    pub fn map<U>(self, f: impl FnMut(T) -> U) -> Paginated<U> {
+        Paginated {
+            data: self.data.into_iter().map(f).collect(),
+            meta: self.meta,
+        }
+    }
+}
+
+/// Body of an error response
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct ApiErrorBody {
+    /// Machine-readable error code, e.g. `wallet_overdrawn`
+    pub error: String,
+    /// Details specific to the code, at the top level of the body
+    #[serde(flatten)]
+    pub details: Map<String, Value>,
+}
+
+impl ApiErrorBody {
+    pub fn new(error: impl Into<String>) -> Self {
+        Self {
+            error: error.into(),
+            details: Map::new(),
+        }
+    }
+
+    /// Add a detail to the body; a value that does not serialize is stored as null
+    pub fn with(mut self, key: impl Into<String>, value: impl Serialize) -> Self {
+        self.details.insert(key.into(), serde_json::to_value(value).unwrap_or(Value::Null));
+        self
+    }
+
+    /// A detail of the body, if present
+    pub fn detail(&self, key: &str) -> Option<&Value> {
+        self.details.get(key)
+    }
+}
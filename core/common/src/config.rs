@@ -47,3 +47,37 @@ impl Config {
         self.environment == "development"
     }
 }
+
+/// Variable naming the file of `KEY=value` settings read on startup and on reload;
+/// `.env` in the working directory or one of its parents when unset
+pub const CONFIG_FILE_VAR: &str = "CONFIG_FILE";
+
+/// Load the settings file into the environment, keeping variables that are already set
+///
+/// A missing file is not an error: every setting has a default or comes from the environment.
+pub fn load_env_file() {
+    let _ = match env::var(CONFIG_FILE_VAR) {
+        Ok(path) => dotenvy::from_path(path),
+        Err(_) => dotenvy::dotenv().map(|_| ()),
+    };
+}
+
+/// Read the settings file into the environment again, its values replacing the current
+/// ones, so a configuration reload sees the edits made since startup
+///
+/// Fails if the file named by `CONFIG_FILE` cannot be read or parsed; without it, a
+/// missing `.env` leaves the environment as it is.
+pub fn reload_env_file() -> std::result::Result<(), dotenvy::Error> {
+    match env::var(CONFIG_FILE_VAR) {
+        Ok(path) => dotenvy::from_path_override(path),
+        Err(_) => match dotenvy::dotenv_override() {
+            Err(err) if err.not_found() => Ok(()),
+            result => result.map(|_| ()),
+        },
+    }
+}
+
+/// Parse an environment variable, ignoring unset or malformed values
+pub fn parse_env<T: std::str::FromStr>(name: &str) -> Option<T> {
+    env::var(name).ok().and_then(|value| value.parse().ok())
+}
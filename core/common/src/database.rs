@@ -1,7 +1,17 @@
 use diesel::prelude::*;
 use diesel::pg::PgConnection;
-use diesel::r2d2::{ConnectionManager, Pool, PooledConnection};
+use diesel::r2d2::event::{CheckoutEvent, TimeoutEvent};
+use diesel::r2d2::{ConnectionManager, CustomizeConnection, HandleEvent, Pool, PooledConnection};
+use diesel::sql_types::{Array, Bool, Nullable, Text};
+use serde::Serialize;
+use std::cell::Cell;
+use std::collections::{HashMap, HashSet};
 use std::env;
+use std::future::Future;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant};
+use uuid::Uuid;
 use crate::errors::Error;
 
 pub type PgPool = Pool<ConnectionManager<PgConnection>>;
@@ -25,10 +35,33 @@ pub fn init_pool() -> Result<PgPool, Error> {
         .map_err(|e| Error::Configuration(format!("Failed to create database pool: {}", e)))
 }
 
+/// Build the shared connection pool, recording its checkouts in `metrics`
+pub fn build_pool(database_url: &str, config: &PoolConfig, metrics: Arc<PoolMetrics>) -> Result<PgPool, Error> {
+    let manager = ConnectionManager::<PgConnection>::new(database_url);
+    Pool::builder()
+        .max_size(config.max_size)
+        .min_idle(config.min_idle)
+        .connection_timeout(config.connection_timeout)
+        .event_handler(Box::new(PoolEvents(metrics)))
+        .build(manager)
+        .map_err(|e| Error::Configuration(format!("Failed to create database pool: {}", e)))
+}
+
 /// Get a connection from the pool
+///
+/// Fails with `PoolExhausted` when every connection stayed in use for the whole
+/// checkout timeout, and flags the current task (see `detect_pool_exhaustion`).
 pub fn get_connection(pool: &PgPool) -> Result<PgPooledConnection, Error> {
     pool.get()
-        .map_err(|e| Error::Configuration(format!("Failed to get DB connection from pool: {}", e)))
+        .map_err(|e| {
+            let state = pool.state();
+            if state.idle_connections == 0 && state.connections >= pool.max_size() {
+                mark_pool_exhausted();
+                Error::PoolExhausted(format!("all {} database connections are in use: {}", pool.max_size(), e))
+            } else {
+                Error::Configuration(format!("Failed to get DB connection from pool: {}", e))
+            }
+        })
 }
 
 /// Execute a function within a transaction
@@ -57,6 +90,757 @@ impl Transaction for PgPool {
     }
 }
 
+/// Tables holding a reseller's customer data, moved to the reseller's own schema in
+/// `SchemaPerReseller` mode. Everything else (the customer directory used to resolve
+/// API keys, resellers, job types, runners, submission windows and the audit log)
+/// stays in `public`.
+pub const TENANT_TABLES: [&str; 28] = [
+    "projects",
+    "jobs",
+    "job_logs",
+    "job_attempts",
+    "job_unredacted_outputs",
+    "job_lifecycle_events",
+    "job_artifacts",
+    "job_templates",
+    "wallets",
+    "wallet_transactions",
+    "wallet_transaction_references",
+    "ledger_accounts",
+    "ledger_entries",
+    "customer_webhooks",
+    "notification_deliveries",
+    "notification_preferences",
+    "pipelines",
+    "pipeline_runs",
+    "pipeline_run_steps",
+    "job_imports",
+    "job_import_rows",
+    "wallet_adjustments",
+    "billing_periods",
+    "invoices",
+    "spending_alerts",
+    "billing_exports",
+    "job_usage",
+    "submissions",
+];
+
+/// How often the list of provisioned reseller schemas is reloaded from Postgres
+const TENANT_REFRESH_INTERVAL: Duration = Duration::from_secs(30);
+
+/// Where reseller data is stored
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum TenancyMode {
+    /// All resellers share the `public` schema
+    Shared,
+    /// Resellers with a provisioned schema get their customer data stored in it;
+    /// fleet-wide reports (runner health, autoscaling, queue wait) only cover `public`
+    SchemaPerReseller,
+}
+
+impl TenancyMode {
+    /// Parse a mode from its configuration value
+    pub fn parse(value: &str) -> Option<Self> {
+        match value {
+            "shared" => Some(Self::Shared),
+            "schema_per_reseller" => Some(Self::SchemaPerReseller),
+            _ => None,
+        }
+    }
+
+    /// Configuration value of the mode
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Self::Shared => "shared",
+            Self::SchemaPerReseller => "schema_per_reseller",
+        }
+    }
+}
+
+/// Configuration of the optional schema-per-reseller deployment mode
+#[derive(Debug, Clone)]
+pub struct TenancyConfig {
+    pub mode: TenancyMode,
+    /// Prefix of reseller schema names, followed by the reseller ID without dashes
+    pub schema_prefix: String,
+    /// Maximum connections of each reseller schema's pool
+    pub pool_size: u32,
+}
+
+impl Default for TenancyConfig {
+    fn default() -> Self {
+        Self {
+            mode: TenancyMode::Shared,
+            schema_prefix: "reseller_".to_string(),
+            pool_size: 5,
+        }
+    }
+}
+
+impl TenancyConfig {
+    /// Load the configuration from `TENANCY_*` environment variables, using defaults for unset values
+    pub fn from_env() -> Self {
+        let defaults = Self::default();
+        Self {
+            mode: env::var("TENANCY_MODE").ok()
+                .and_then(|value| TenancyMode::parse(&value))
+                .unwrap_or(defaults.mode),
+            // Schema names are interpolated into SQL, so only plain identifiers are accepted
+            schema_prefix: env::var("TENANCY_SCHEMA_PREFIX").ok()
+                .filter(|prefix| !prefix.is_empty() && prefix.chars().all(|c| c.is_ascii_lowercase() || c.is_ascii_digit() || c == '_'))
+                .unwrap_or(defaults.schema_prefix),
+            pool_size: env::var("TENANCY_POOL_SIZE").ok()
+                .and_then(|value| value.parse().ok())
+                .filter(|size| *size > 0)
+                .unwrap_or(defaults.pool_size),
+        }
+    }
+}
+
+/// Sizing of the shared Postgres connection pool
+#[derive(Debug, Clone, PartialEq)]
+pub struct PoolConfig {
+    /// Maximum connections of the pool
+    pub max_size: u32,
+    /// Idle connections kept open, all of them when unset
+    pub min_idle: Option<u32>,
+    /// Time a checkout waits for a connection before giving up
+    pub connection_timeout: Duration,
+}
+
+impl Default for PoolConfig {
+    fn default() -> Self {
+        Self {
+            max_size: 10,
+            min_idle: None,
+            connection_timeout: Duration::from_secs(5),
+        }
+    }
+}
+
+impl PoolConfig {
+    /// Load the configuration from `DATABASE_POOL_*` environment variables, using defaults for unset values
+    pub fn from_env() -> Self {
+        let defaults = Self::default();
+        Self {
+            max_size: env::var("DATABASE_POOL_SIZE").ok()
+                .and_then(|value| value.parse().ok())
+                .filter(|size| *size > 0)
+                .unwrap_or(defaults.max_size),
+            min_idle: env::var("DATABASE_POOL_MIN_IDLE").ok()
+                .and_then(|value| value.parse().ok())
+                .or(defaults.min_idle),
+            connection_timeout: env::var("DATABASE_POOL_TIMEOUT_MS").ok()
+                .and_then(|value| value.parse().ok())
+                .filter(|ms| *ms > 0)
+                .map(Duration::from_millis)
+                .unwrap_or(defaults.connection_timeout),
+        }
+    }
+}
+
+/// Utilization of a connection pool and the checkouts that waited on it
+#[derive(Debug, Clone, Default, PartialEq, Serialize)]
+pub struct PoolStatus {
+    pub max_size: u32,
+    /// Connections open, idle or in use
+    pub connections: u32,
+    pub idle_connections: u32,
+    /// Checkouts served since startup
+    pub checkouts: u64,
+    /// Checkouts that gave up waiting for a connection
+    pub timeouts: u64,
+    /// Total time checkouts spent waiting for a connection
+    pub wait_seconds_total: f64,
+    /// Longest wait of a single checkout, where the pool tracks it
+    pub wait_seconds_max: Option<f64>,
+}
+
+impl PoolStatus {
+    /// Connections currently checked out
+    pub fn in_use(&self) -> u32 {
+        self.connections.saturating_sub(self.idle_connections)
+    }
+
+    /// Share of the pool's connections checked out, from 0 to 1
+    pub fn utilization(&self) -> f64 {
+        if self.max_size == 0 {
+            return 0.0;
+        }
+        f64::from(self.in_use()) / f64::from(self.max_size)
+    }
+}
+
+/// Checkouts of a Postgres pool and the time they waited, fed by the pool's events
+#[derive(Debug, Default)]
+pub struct PoolMetrics {
+    checkouts: AtomicU64,
+    timeouts: AtomicU64,
+    wait_micros_total: AtomicU64,
+    wait_micros_max: AtomicU64,
+}
+
+impl PoolMetrics {
+    /// Record a checkout that waited `wait` for its connection
+    pub fn record_checkout(&self, wait: Duration) {
+        let micros = wait.as_micros().min(u128::from(u64::MAX)) as u64;
+        self.checkouts.fetch_add(1, Ordering::Relaxed);
+        self.wait_micros_total.fetch_add(micros, Ordering::Relaxed);
+        self.wait_micros_max.fetch_max(micros, Ordering::Relaxed);
+    }
+
+    /// Record a checkout that gave up after waiting `wait`
+    pub fn record_timeout(&self, wait: Duration) {
+        let micros = wait.as_micros().min(u128::from(u64::MAX)) as u64;
+        self.timeouts.fetch_add(1, Ordering::Relaxed);
+        self.wait_micros_total.fetch_add(micros, Ordering::Relaxed);
+        self.wait_micros_max.fetch_max(micros, Ordering::Relaxed);
+    }
+
+    /// Current status of `pool` with the checkouts recorded so far
+    pub fn status(&self, pool: &PgPool) -> PoolStatus {
+        let state = pool.state();
+        PoolStatus {
+            max_size: pool.max_size(),
+            connections: state.connections,
+            idle_connections: state.idle_connections,
+            checkouts: self.checkouts.load(Ordering::Relaxed),
+            timeouts: self.timeouts.load(Ordering::Relaxed),
+            wait_seconds_total: self.wait_micros_total.load(Ordering::Relaxed) as f64 / 1_000_000.0,
+            wait_seconds_max: Some(self.wait_micros_max.load(Ordering::Relaxed) as f64 / 1_000_000.0),
+        }
+    }
+}
+
+/// Forwards the events of a pool to its metrics
+#[derive(Debug)]
+struct PoolEvents(Arc<PoolMetrics>);
+
+impl HandleEvent for PoolEvents {
+    fn handle_checkout(&self, event: CheckoutEvent) {
+        self.0.record_checkout(event.duration());
+    }
+
+    fn handle_timeout(&self, event: TimeoutEvent) {
+        tracing::warn!("Gave up waiting {:?} for a database connection, all connections are in use", event.timeout());
+        self.0.record_timeout(event.timeout());
+    }
+}
+
+tokio::task_local! {
+    /// Reseller the current request or task acts for
+    static CURRENT_RESELLER: Option<Uuid>;
+
+    /// Whether a connection checkout of the current request failed on an exhausted pool
+    static POOL_EXHAUSTED: Cell<bool>;
+}
+
+/// Flag the current task as having failed a checkout on an exhausted pool
+pub(crate) fn mark_pool_exhausted() {
+    let _ = POOL_EXHAUSTED.try_with(|exhausted| exhausted.set(true));
+}
+
+/// Run a future, reporting whether any of its connection checkouts failed because
+/// the pool was exhausted
+pub async fn detect_pool_exhaustion<F: Future>(f: F) -> (F::Output, bool) {
+    POOL_EXHAUSTED.scope(Cell::new(false), async move {
+        let output = f.await;
+        (output, POOL_EXHAUSTED.with(Cell::get))
+    }).await
+}
+
+/// Run a future on behalf of a reseller: repository calls inside it use the
+/// reseller's schema if it has one
+pub async fn with_reseller<F: Future>(reseller_id: Option<Uuid>, f: F) -> F::Output {
+    CURRENT_RESELLER.scope(reseller_id, f).await
+}
+
+/// Reseller the current task acts for, if any
+pub fn current_reseller() -> Option<Uuid> {
+    CURRENT_RESELLER.try_with(|reseller_id| *reseller_id).ok().flatten()
+}
+
+/// Points the connections of a reseller's pool at the reseller's schema
+#[derive(Debug)]
+struct SearchPath(String);
+
+impl CustomizeConnection<PgConnection, diesel::r2d2::Error> for SearchPath {
+    fn on_acquire(&self, conn: &mut PgConnection) -> Result<(), diesel::r2d2::Error> {
+        // Shared tables are not created in reseller schemas and resolve to `public`
+        diesel::sql_query(format!("SET search_path TO \"{}\", public", self.0))
+            .execute(conn)
+            .map(|_| ())
+            .map_err(diesel::r2d2::Error::QueryError)
+    }
+}
+
+#[derive(QueryableByName)]
+struct SchemaName {
+    #[diesel(sql_type = Text)]
+    nspname: String,
+}
+
+#[derive(QueryableByName)]
+struct ColumnDefinition {
+    #[diesel(sql_type = Text)]
+    table_name: String,
+    #[diesel(sql_type = Text)]
+    column_name: String,
+    #[diesel(sql_type = Text)]
+    column_type: String,
+    #[diesel(sql_type = Nullable<Text>)]
+    column_default: Option<String>,
+    #[diesel(sql_type = Bool)]
+    not_null: bool,
+}
+
+#[derive(QueryableByName)]
+struct ConstraintDefinition {
+    #[diesel(sql_type = Text)]
+    table_name: String,
+    #[diesel(sql_type = Text)]
+    name: String,
+    #[diesel(sql_type = Text)]
+    definition: String,
+}
+
+#[derive(QueryableByName)]
+struct IndexDefinition {
+    #[diesel(sql_type = Text)]
+    indexname: String,
+    #[diesel(sql_type = Text)]
+    indexdef: String,
+}
+
+#[derive(QueryableByName)]
+struct TriggerDefinition {
+    #[diesel(sql_type = Text)]
+    table_name: String,
+    #[diesel(sql_type = Text)]
+    name: String,
+    #[diesel(sql_type = Text)]
+    definition: String,
+}
+
+#[derive(QueryableByName)]
+struct Exists {
+    #[diesel(sql_type = Bool)]
+    exists: bool,
+}
+
+fn load_columns(conn: &mut PgConnection, schema: &str) -> diesel::QueryResult<Vec<ColumnDefinition>> {
+    diesel::sql_query(
+        "SELECT c.relname::text AS table_name, a.attname::text AS column_name, \
+                format_type(a.atttypid, a.atttypmod) AS column_type, \
+                pg_get_expr(d.adbin, d.adrelid) AS column_default, a.attnotnull AS not_null \
+         FROM pg_attribute a \
+         JOIN pg_class c ON c.oid = a.attrelid \
+         JOIN pg_namespace n ON n.oid = c.relnamespace \
+         LEFT JOIN pg_attrdef d ON d.adrelid = a.attrelid AND d.adnum = a.attnum \
+         WHERE n.nspname = $1 AND c.relkind IN ('r', 'p') AND c.relname = ANY($2) \
+           AND a.attnum > 0 AND NOT a.attisdropped \
+         ORDER BY c.relname, a.attnum",
+    )
+    .bind::<Text, _>(schema)
+    .bind::<Array<Text>, _>(TENANT_TABLES.to_vec())
+    .load(conn)
+}
+
+fn load_constraints(conn: &mut PgConnection, schema: &str) -> diesel::QueryResult<Vec<ConstraintDefinition>> {
+    // Keys first so foreign keys can reference them
+    diesel::sql_query(
+        "SELECT c.relname::text AS table_name, con.conname::text AS name, pg_get_constraintdef(con.oid) AS definition \
+         FROM pg_constraint con \
+         JOIN pg_class c ON c.oid = con.conrelid \
+         JOIN pg_namespace n ON n.oid = c.relnamespace \
+         WHERE n.nspname = $1 AND c.relname = ANY($2) AND con.contype IN ('p', 'u', 'c', 'f') \
+         ORDER BY position(con.contype::text IN 'pucf'), c.relname, con.conname",
+    )
+    .bind::<Text, _>(schema)
+    .bind::<Array<Text>, _>(TENANT_TABLES.to_vec())
+    .load(conn)
+}
+
+fn load_indexes(conn: &mut PgConnection, schema: &str) -> diesel::QueryResult<Vec<IndexDefinition>> {
+    diesel::sql_query(
+        "SELECT indexname::text AS indexname, indexdef FROM pg_indexes \
+         WHERE schemaname = $1 AND tablename = ANY($2) ORDER BY indexname",
+    )
+    .bind::<Text, _>(schema)
+    .bind::<Array<Text>, _>(TENANT_TABLES.to_vec())
+    .load(conn)
+}
+
+fn load_triggers(conn: &mut PgConnection, schema: &str) -> diesel::QueryResult<Vec<TriggerDefinition>> {
+    diesel::sql_query(
+        "SELECT c.relname::text AS table_name, t.tgname::text AS name, pg_get_triggerdef(t.oid) AS definition \
+         FROM pg_trigger t \
+         JOIN pg_class c ON c.oid = t.tgrelid \
+         JOIN pg_namespace n ON n.oid = c.relnamespace \
+         WHERE n.nspname = $1 AND c.relname = ANY($2) AND NOT t.tgisinternal \
+         ORDER BY c.relname, t.tgname",
+    )
+    .bind::<Text, _>(schema)
+    .bind::<Array<Text>, _>(TENANT_TABLES.to_vec())
+    .load(conn)
+}
+
+/// Create or update the tenant tables of a reseller schema to match `public`
+///
+/// Tables are created empty; columns, constraints and indexes added to `public` by
+/// later migrations are added to the reseller schema when it is synced again.
+/// Foreign keys to shared tables reference the `public` tables. Tables partitioned in
+/// `public` are created as plain tables. Triggers call the same functions as in
+/// `public`, which find the reseller's tables through the search path.
+fn sync_schema(conn: &mut PgConnection, schema: &str) -> diesel::QueryResult<()> {
+    conn.transaction(|conn| {
+        // Read the definitions before changing the search path, so references to
+        // tenant tables are not qualified with `public`
+        let public_columns = load_columns(conn, "public")?;
+        let public_constraints = load_constraints(conn, "public")?;
+        let public_indexes = load_indexes(conn, "public")?;
+        let public_triggers = load_triggers(conn, "public")?;
+
+        diesel::sql_query(format!("CREATE SCHEMA IF NOT EXISTS \"{}\"", schema)).execute(conn)?;
+        diesel::sql_query(format!("SET LOCAL search_path TO \"{}\", public", schema)).execute(conn)?;
+
+        for table in TENANT_TABLES {
+            diesel::sql_query(format!(
+                "CREATE TABLE IF NOT EXISTS \"{0}\".{1} (LIKE public.{1} INCLUDING DEFAULTS INCLUDING IDENTITY INCLUDING GENERATED)",
+                schema, table
+            )).execute(conn)?;
+        }
+
+        let columns: HashSet<(String, String)> = load_columns(conn, schema)?
+            .into_iter()
+            .map(|column| (column.table_name, column.column_name))
+            .collect();
+        for column in public_columns {
+            if columns.contains(&(column.table_name.clone(), column.column_name.clone())) {
+                continue;
+            }
+            let mut sql = format!(
+                "ALTER TABLE \"{}\".{} ADD COLUMN \"{}\" {}",
+                schema, column.table_name, column.column_name, column.column_type
+            );
+            if let Some(default) = &column.column_default {
+                sql.push_str(&format!(" DEFAULT {}", default));
+            }
+            if column.not_null {
+                sql.push_str(" NOT NULL");
+            }
+            diesel::sql_query(sql).execute(conn)?;
+        }
+
+        let constraints: HashSet<(String, String)> = load_constraints(conn, schema)?
+            .into_iter()
+            .map(|constraint| (constraint.table_name, constraint.name))
+            .collect();
+        for constraint in public_constraints {
+            if constraints.contains(&(constraint.table_name.clone(), constraint.name.clone())) {
+                continue;
+            }
+            diesel::sql_query(format!(
+                "ALTER TABLE \"{}\".{} ADD CONSTRAINT \"{}\" {}",
+                schema, constraint.table_name, constraint.name, constraint.definition
+            )).execute(conn)?;
+        }
+
+        // Key constraints created their indexes above under the same names
+        let indexes: HashSet<String> = load_indexes(conn, schema)?
+            .into_iter()
+            .map(|index| index.indexname)
+            .collect();
+        for index in public_indexes {
+            if indexes.contains(&index.indexname) {
+                continue;
+            }
+            // Indexes of partitioned tables are defined `ON ONLY` the parent table
+            let definition = index.indexdef
+                .replacen(" ON ONLY public.", " ON public.", 1)
+                .replacen(" ON public.", &format!(" ON \"{}\".", schema), 1);
+            diesel::sql_query(definition).execute(conn)?;
+        }
+
+        let triggers: HashSet<(String, String)> = load_triggers(conn, schema)?
+            .into_iter()
+            .map(|trigger| (trigger.table_name, trigger.name))
+            .collect();
+        for trigger in public_triggers {
+            if triggers.contains(&(trigger.table_name.clone(), trigger.name.clone())) {
+                continue;
+            }
+            let definition = trigger.definition.replacen(" ON public.", &format!(" ON \"{}\".", schema), 1);
+            diesel::sql_query(definition).execute(conn)?;
+        }
+
+        Ok(())
+    })
+}
+
+/// Provisioned reseller schemas and the pools connected to them
+#[derive(Default)]
+struct TenantCache {
+    refreshed_at: Option<Instant>,
+    resellers: HashSet<Uuid>,
+    pools: HashMap<Uuid, PgPool>,
+}
+
+/// Resolves the connection pool of the reseller a request acts for
+///
+/// In `Shared` mode, and for resellers without a provisioned schema, this is
+/// always the shared pool. Schemas are provisioned explicitly (see `provision`);
+/// the list of provisioned schemas is reloaded periodically so all API and runner
+/// instances pick up new ones.
+pub struct SchemaResolver {
+    config: TenancyConfig,
+    database_url: String,
+    shared: PgPool,
+    cache: Mutex<TenantCache>,
+}
+
+impl SchemaResolver {
+    /// Create a resolver falling back to the shared pool
+    pub fn new(shared: PgPool, database_url: impl Into<String>, config: TenancyConfig) -> Self {
+        Self {
+            config,
+            database_url: database_url.into(),
+            shared,
+            cache: Mutex::new(TenantCache::default()),
+        }
+    }
+
+    /// Pool of the shared schema
+    pub fn shared_pool(&self) -> &PgPool {
+        &self.shared
+    }
+
+    /// Configured tenancy mode
+    pub fn mode(&self) -> TenancyMode {
+        self.config.mode
+    }
+
+    /// Name of the schema holding a reseller's data in `SchemaPerReseller` mode
+    pub fn schema_name(&self, reseller_id: Uuid) -> String {
+        format!("{}{}", self.config.schema_prefix, reseller_id.simple())
+    }
+
+    fn lock_cache(&self) -> std::sync::MutexGuard<'_, TenantCache> {
+        // A poisoned lock only means another caller panicked while refreshing; keep resolving
+        self.cache.lock().unwrap_or_else(|poisoned| poisoned.into_inner())
+    }
+
+    /// Reload the provisioned reseller schemas if the cached list is stale
+    fn refresh(&self, cache: &mut TenantCache, force: bool) -> Result<(), Error> {
+        if !force && cache.refreshed_at.is_some_and(|at| at.elapsed() < TENANT_REFRESH_INTERVAL) {
+            return Ok(());
+        }
+
+        let mut conn = get_connection(&self.shared)?;
+        let schemas: Vec<SchemaName> = diesel::sql_query(
+            "SELECT nspname::text AS nspname FROM pg_namespace WHERE left(nspname, length($1)) = $1",
+        )
+        .bind::<Text, _>(&self.config.schema_prefix)
+        .load(&mut conn)?;
+
+        cache.resellers = schemas.iter()
+            .filter_map(|schema| schema.nspname.strip_prefix(&self.config.schema_prefix))
+            .filter_map(|id| Uuid::try_parse(id).ok())
+            .collect();
+        cache.refreshed_at = Some(Instant::now());
+        Ok(())
+    }
+
+    /// Resellers whose data lives in their own schema
+    pub fn isolated_resellers(&self) -> Result<Vec<Uuid>, Error> {
+        if self.config.mode == TenancyMode::Shared {
+            return Ok(Vec::new());
+        }
+        let mut cache = self.lock_cache();
+        self.refresh(&mut cache, false)?;
+        let mut resellers: Vec<Uuid> = cache.resellers.iter().copied().collect();
+        resellers.sort();
+        Ok(resellers)
+    }
+
+    /// Every scope background work has to run in: the shared schema followed by
+    /// each isolated reseller
+    pub fn scopes(&self) -> Result<Vec<Option<Uuid>>, Error> {
+        Ok(std::iter::once(None)
+            .chain(self.isolated_resellers()?.into_iter().map(Some))
+            .collect())
+    }
+
+    /// Pool holding the data of a reseller, or the shared pool
+    pub fn pool_for(&self, reseller_id: Option<Uuid>) -> Result<PgPool, Error> {
+        let Some(reseller_id) = reseller_id.filter(|_| self.config.mode == TenancyMode::SchemaPerReseller) else {
+            return Ok(self.shared.clone());
+        };
+
+        let mut cache = self.lock_cache();
+        if let Some(pool) = cache.pools.get(&reseller_id) {
+            return Ok(pool.clone());
+        }
+        self.refresh(&mut cache, false)?;
+        if !cache.resellers.contains(&reseller_id) {
+            return Ok(self.shared.clone());
+        }
+
+        // Connections are opened on first use rather than while holding the lock
+        let manager = ConnectionManager::<PgConnection>::new(self.database_url.clone());
+        let pool = Pool::builder()
+            .max_size(self.config.pool_size)
+            .min_idle(Some(0))
+            .connection_customizer(Box::new(SearchPath(self.schema_name(reseller_id))))
+            .build_unchecked(manager);
+        cache.pools.insert(reseller_id, pool.clone());
+        Ok(pool)
+    }
+
+    /// Create the schema of a reseller, or bring an existing one up to date
+    ///
+    /// Refused while the reseller's customers still have data in the shared schema,
+    /// since it would no longer be visible to them once their schema exists.
+    pub fn provision(&self, reseller_id: Uuid) -> Result<String, Error> {
+        if self.config.mode != TenancyMode::SchemaPerReseller {
+            return Err(Error::Configuration("Reseller schemas require TENANCY_MODE=schema_per_reseller".to_string()));
+        }
+
+        let schema = self.schema_name(reseller_id);
+        let mut conn = get_connection(&self.shared)?;
+        let provisioned = {
+            let mut cache = self.lock_cache();
+            self.refresh(&mut cache, true)?;
+            cache.resellers.contains(&reseller_id)
+        };
+        if !provisioned {
+            let shared_data: Exists = diesel::sql_query(
+                "SELECT EXISTS (SELECT 1 FROM public.customers c WHERE c.reseller_id = $1 AND ( \
+                     EXISTS (SELECT 1 FROM public.jobs t WHERE t.customer_id = c.id) \
+                     OR EXISTS (SELECT 1 FROM public.wallets t WHERE t.customer_id = c.id) \
+                     OR EXISTS (SELECT 1 FROM public.projects t WHERE t.customer_id = c.id) \
+                     OR EXISTS (SELECT 1 FROM public.job_templates t WHERE t.customer_id = c.id) \
+                     OR EXISTS (SELECT 1 FROM public.customer_webhooks t WHERE t.customer_id = c.id) \
+                     OR EXISTS (SELECT 1 FROM public.notification_deliveries t WHERE t.customer_id = c.id) \
+                     OR EXISTS (SELECT 1 FROM public.pipelines t WHERE t.customer_id = c.id) \
+                     OR EXISTS (SELECT 1 FROM public.job_imports t WHERE t.customer_id = c.id) \
+                 )) AS exists",
+            )
+            .bind::<diesel::sql_types::Uuid, _>(reseller_id)
+            .get_result(&mut conn)?;
+            if shared_data.exists {
+                return Err(Error::InvalidInput(format!(
+                    "Customers of reseller {} already have data in the shared schema", reseller_id
+                )));
+            }
+        }
+
+        sync_schema(&mut conn, &schema)?;
+
+        let mut cache = self.lock_cache();
+        self.refresh(&mut cache, true)?;
+        Ok(schema)
+    }
+
+    /// Drop the schema of a reseller with all the data left in it, e.g. once its
+    /// sandbox tenant expired; nothing to do if it has none
+    pub fn drop_schema(&self, reseller_id: Uuid) -> Result<(), Error> {
+        if self.config.mode != TenancyMode::SchemaPerReseller {
+            return Ok(());
+        }
+
+        let mut conn = get_connection(&self.shared)?;
+        diesel::sql_query(format!("DROP SCHEMA IF EXISTS \"{}\" CASCADE", self.schema_name(reseller_id)))
+            .execute(&mut conn)?;
+
+        let mut cache = self.lock_cache();
+        cache.pools.remove(&reseller_id);
+        self.refresh(&mut cache, true)
+    }
+
+    /// Bring all reseller schemas up to date with `public`; run after migrations
+    pub fn sync_all(&self) -> Result<usize, Error> {
+        let resellers = {
+            let mut cache = self.lock_cache();
+            self.refresh(&mut cache, true)?;
+            cache.resellers.clone()
+        };
+
+        let mut conn = get_connection(&self.shared)?;
+        for reseller_id in &resellers {
+            sync_schema(&mut conn, &self.schema_name(*reseller_id))?;
+        }
+        Ok(resellers.len())
+    }
+
+    /// Reseller whose schema holds a job, for workers that only know the job ID;
+    /// None if it is in the shared schema (or does not exist)
+    pub fn locate_job(&self, job_id: Uuid) -> Result<Option<Uuid>, Error> {
+        let resellers = self.isolated_resellers()?;
+        if resellers.is_empty() {
+            return Ok(None);
+        }
+
+        let mut conn = get_connection(&self.shared)?;
+        for reseller_id in resellers {
+            let found: Exists = diesel::sql_query(format!(
+                "SELECT EXISTS (SELECT 1 FROM \"{}\".jobs WHERE id = $1) AS exists",
+                self.schema_name(reseller_id)
+            ))
+            .bind::<diesel::sql_types::Uuid, _>(job_id)
+            .get_result(&mut conn)?;
+            if found.exists {
+                return Ok(Some(reseller_id));
+            }
+        }
+        Ok(None)
+    }
+}
+
+/// Connection source of the Diesel repositories
+///
+/// Hands out connections of the shared pool, or, with a `SchemaResolver`, of the
+/// pool of the reseller the current task acts for (see `with_reseller`).
+#[derive(Clone)]
+pub struct TenantPool {
+    shared: PgPool,
+    resolver: Option<Arc<SchemaResolver>>,
+}
+
+impl TenantPool {
+    /// Resolve connections through `resolver`
+    pub fn new(resolver: Arc<SchemaResolver>) -> Self {
+        Self {
+            shared: resolver.shared.clone(),
+            resolver: Some(resolver),
+        }
+    }
+
+    /// Get a connection for the current reseller
+    pub fn get(&self) -> Result<PgPooledConnection, Error> {
+        match &self.resolver {
+            Some(resolver) => get_connection(&resolver.pool_for(current_reseller())?),
+            None => get_connection(&self.shared),
+        }
+    }
+}
+
+impl From<PgPool> for TenantPool {
+    fn from(shared: PgPool) -> Self {
+        Self { shared, resolver: None }
+    }
+}
+
+impl Transaction for TenantPool {
+    fn run_in_transaction<F, T>(&self, f: F) -> Result<T, Error>
+    where
+        F: FnOnce(&mut PgConnection) -> Result<T, diesel::result::Error>,
+    {
+        let mut conn = self.get()?;
+        transaction(&mut conn, f)
+    }
+}
+
 /// Validate that all models match the database schema
 pub fn validate_schema(conn: &mut PgConnection) -> Result<(), Error> {
     // This function will be expanded in future phases to perform actual validation
@@ -10,8 +10,20 @@ table! {
         processor_type -> Text,
         standard_cost_cents -> Integer,
         enabled -> Bool,
-        created_at -> Nullable<Timestamp>,
-        updated_at -> Nullable<Timestamp>,
+        created_at -> Nullable<Timestamptz>,
+        updated_at -> Nullable<Timestamptz>,
+        category -> Nullable<Text>,
+        icon -> Nullable<Text>,
+        documentation_url -> Nullable<Text>,
+        sample_input -> Nullable<Jsonb>,
+        sample_output -> Nullable<Jsonb>,
+        visibility -> Text,
+        redaction_rules -> Array<Text>,
+        unredacted_retention_hours -> Nullable<Integer>,
+        provider_id -> Nullable<Uuid>,
+        default_priority -> Nullable<Integer>,
+        webhook_template -> Nullable<Jsonb>,
+        response_policy -> Nullable<Jsonb>,
     }
 }
 
@@ -23,9 +35,32 @@ table! {
         status -> Text,
         cost_cents -> Integer,
         project_id -> Nullable<Uuid>,
-        created_at -> Nullable<Timestamp>,
-        updated_at -> Nullable<Timestamp>,
-        completed_at -> Nullable<Timestamp>,
+        created_at -> Nullable<Timestamptz>,
+        updated_at -> Nullable<Timestamptz>,
+        completed_at -> Nullable<Timestamptz>,
+        test_mode -> Bool,
+        runner_id -> Nullable<Uuid>,
+        claimed_at -> Nullable<Timestamptz>,
+        queue_priority -> Nullable<Integer>,
+        scheduled_for -> Nullable<Timestamptz>,
+        lease_expires_at -> Nullable<Timestamptz>,
+        public_id -> Text,
+        expires_at -> Nullable<Timestamptz>,
+        customer_reference -> Nullable<Text>,
+        customer_metadata -> Nullable<Jsonb>,
+        parent_id -> Nullable<Uuid>,
+        sub_task_index -> Nullable<Integer>,
+        sub_task_count -> Nullable<Integer>,
+        cost_breakdown -> Nullable<Jsonb>,
+        submission_id -> Nullable<Uuid>,
+        cancel_requested_at -> Nullable<Timestamptz>,
+        requeued_from -> Nullable<Uuid>,
+        queued_at -> Nullable<Timestamptz>,
+        started_at -> Nullable<Timestamptz>,
+        input_data -> Jsonb,
+        output_data -> Nullable<Jsonb>,
+        error -> Nullable<Jsonb>,
+        priority -> Integer,
     }
 }
 
@@ -37,8 +72,18 @@ table! {
         api_key -> Text,
         active -> Bool,
         commission_rate -> Integer,
-        created_at -> Nullable<Timestamp>,
-        updated_at -> Nullable<Timestamp>,
+        created_at -> Nullable<Timestamptz>,
+        updated_at -> Nullable<Timestamptz>,
+        suspend_customers -> Bool,
+        block_customer_creation -> Bool,
+        deactivation_reason -> Nullable<Text>,
+        default_locale -> Nullable<Text>,
+        signing_secret -> Nullable<Text>,
+        require_signed_requests -> Bool,
+        markup_rate -> Integer,
+        timezone -> Nullable<Text>,
+        sandbox_of -> Nullable<Uuid>,
+        sandbox_expires_at -> Nullable<Timestamptz>,
     }
 }
 
@@ -49,8 +94,15 @@ table! {
         email -> Text,
         reseller_id -> Nullable<Uuid>,
         api_key -> Nullable<Text>,
-        created_at -> Nullable<Timestamp>,
-        updated_at -> Nullable<Timestamp>,
+        created_at -> Nullable<Timestamptz>,
+        updated_at -> Nullable<Timestamptz>,
+        test_api_key -> Nullable<Text>,
+        parent_id -> Nullable<Uuid>,
+        budget_cents -> Nullable<Integer>,
+        timezone -> Nullable<Text>,
+        active -> Bool,
+        default_priority -> Nullable<Integer>,
+        pii_policy -> Nullable<Text>,
     }
 }
 
@@ -60,8 +112,8 @@ table! {
         customer_id -> Uuid,
         name -> Text,
         description -> Nullable<Text>,
-        created_at -> Nullable<Timestamp>,
-        updated_at -> Nullable<Timestamp>,
+        created_at -> Nullable<Timestamptz>,
+        updated_at -> Nullable<Timestamptz>,
     }
 }
 
@@ -71,10 +123,9 @@ table! {
         name -> Text,
         description -> Nullable<Text>,
         status -> Text,
-        compatible_job_types -> Array<Text>,
-        last_heartbeat -> Nullable<Timestamp>,
-        created_at -> Nullable<Timestamp>,
-        updated_at -> Nullable<Timestamp>,
+        last_heartbeat -> Nullable<Timestamptz>,
+        created_at -> Nullable<Timestamptz>,
+        updated_at -> Nullable<Timestamptz>,
     }
 }
 
@@ -83,8 +134,12 @@ table! {
         id -> Uuid,
         customer_id -> Uuid,
         balance_cents -> Integer,
-        created_at -> Nullable<Timestamp>,
-        updated_at -> Nullable<Timestamp>,
+        created_at -> Nullable<Timestamptz>,
+        updated_at -> Nullable<Timestamptz>,
+        test_balance_cents -> Integer,
+        credit_cents -> Integer,
+        overdraft_policy -> Text,
+        overdraft_limit_cents -> Integer,
     }
 }
 
@@ -98,7 +153,18 @@ table! {
         reference_id -> Nullable<Uuid>,
         description -> Nullable<Text>,
         job_id -> Nullable<Uuid>,
-        created_at -> Nullable<Timestamp>,
+        created_at -> Nullable<Timestamptz>,
+        customer_reference -> Nullable<Text>,
+        customer_metadata -> Nullable<Jsonb>,
+    }
+}
+
+table! {
+    wallet_transaction_references (wallet_id, reference_id) {
+        wallet_id -> Uuid,
+        reference_id -> Uuid,
+        transaction_id -> Uuid,
+        created_at -> Timestamptz,
     }
 }
 
@@ -106,10 +172,476 @@ table! {
     runner_job_type_compatibility (runner_id, job_type_id) {
         runner_id -> Uuid,
         job_type_id -> Uuid,
-        created_at -> Nullable<Timestamp>,
+        created_at -> Nullable<Timestamptz>,
+    }
+}
+
+table! {
+    customer_webhooks (id) {
+        id -> Uuid,
+        customer_id -> Uuid,
+        url -> Text,
+        secret -> Text,
+        event_types -> Array<Text>,
+        active -> Bool,
+        last_tested_at -> Nullable<Timestamptz>,
+        last_test_status_code -> Nullable<Integer>,
+        last_test_response -> Nullable<Text>,
+        failing_since -> Nullable<Timestamptz>,
+        created_at -> Nullable<Timestamptz>,
+        updated_at -> Nullable<Timestamptz>,
+    }
+}
+
+table! {
+    notification_preferences (id) {
+        id -> Uuid,
+        customer_id -> Uuid,
+        event_type -> Text,
+        channel -> Text,
+        mode -> Text,
+        created_at -> Nullable<Timestamptz>,
+        updated_at -> Nullable<Timestamptz>,
+    }
+}
+
+table! {
+    notification_deliveries (id) {
+        id -> Uuid,
+        customer_id -> Uuid,
+        webhook_id -> Nullable<Uuid>,
+        channel -> Text,
+        event_type -> Text,
+        payload -> Text,
+        status -> Text,
+        status_code -> Nullable<Integer>,
+        latency_ms -> Nullable<Integer>,
+        last_error -> Nullable<Text>,
+        retry_count -> Integer,
+        next_retry_at -> Nullable<Timestamptz>,
+        created_at -> Nullable<Timestamptz>,
+        updated_at -> Nullable<Timestamptz>,
+    }
+}
+
+table! {
+    job_logs (id) {
+        id -> Uuid,
+        job_id -> Uuid,
+        line_number -> Integer,
+        level -> Text,
+        message -> Text,
+        fields -> Nullable<Jsonb>,
+        created_at -> Nullable<Timestamptz>,
+    }
+}
+
+table! {
+    job_templates (id) {
+        id -> Uuid,
+        customer_id -> Uuid,
+        job_type_id -> Uuid,
+        name -> Text,
+        description -> Nullable<Text>,
+        input_template -> Jsonb,
+        variables -> Jsonb,
+        created_at -> Nullable<Timestamptz>,
+        updated_at -> Nullable<Timestamptz>,
+    }
+}
+
+table! {
+    runner_health_checks (id) {
+        id -> Uuid,
+        runner_id -> Uuid,
+        status -> Text,
+        score -> Integer,
+        heartbeat_age_seconds -> Nullable<BigInt>,
+        failure_rate -> Nullable<Double>,
+        avg_processing_seconds -> Nullable<Double>,
+        jobs_sampled -> Integer,
+        created_at -> Nullable<Timestamptz>,
+    }
+}
+
+table! {
+    runner_environments (id) {
+        id -> Uuid,
+        runner_id -> Uuid,
+        fingerprint -> Text,
+        environment -> Jsonb,
+        reported_at -> Timestamptz,
+    }
+}
+
+table! {
+    submission_windows (id) {
+        id -> Uuid,
+        job_type_id -> Uuid,
+        customer_id -> Nullable<Uuid>,
+        reseller_id -> Nullable<Uuid>,
+        start_time -> Time,
+        end_time -> Time,
+        created_at -> Nullable<Timestamptz>,
+    }
+}
+
+table! {
+    pipelines (id) {
+        id -> Uuid,
+        customer_id -> Uuid,
+        name -> Text,
+        description -> Nullable<Text>,
+        definition -> Text,
+        created_at -> Nullable<Timestamptz>,
+        updated_at -> Nullable<Timestamptz>,
+    }
+}
+
+table! {
+    pipeline_runs (id) {
+        id -> Uuid,
+        pipeline_id -> Uuid,
+        customer_id -> Uuid,
+        status -> Text,
+        definition -> Text,
+        input -> Jsonb,
+        priority -> Integer,
+        test_mode -> Bool,
+        created_at -> Nullable<Timestamptz>,
+        completed_at -> Nullable<Timestamptz>,
+    }
+}
+
+table! {
+    pipeline_run_steps (id) {
+        id -> Uuid,
+        run_id -> Uuid,
+        step_name -> Text,
+        job_id -> Nullable<Uuid>,
+        status -> Text,
+        created_at -> Nullable<Timestamptz>,
+        updated_at -> Nullable<Timestamptz>,
+    }
+}
+
+table! {
+    job_imports (id) {
+        id -> Uuid,
+        customer_id -> Uuid,
+        job_type_id -> Uuid,
+        format -> Text,
+        mapping -> Nullable<Jsonb>,
+        content -> Text,
+        priority -> Int4,
+        test_mode -> Bool,
+        status -> Text,
+        total_rows -> Int4,
+        next_row -> Int4,
+        submitted_rows -> Int4,
+        failed_rows -> Int4,
+        created_at -> Nullable<Timestamptz>,
+        updated_at -> Nullable<Timestamptz>,
+        completed_at -> Nullable<Timestamptz>,
+    }
+}
+
+table! {
+    job_import_rows (import_id, row_number) {
+        import_id -> Uuid,
+        row_number -> Int4,
+        job_id -> Nullable<Uuid>,
+        error -> Nullable<Text>,
+        created_at -> Nullable<Timestamptz>,
+    }
+}
+
+table! {
+    wallet_adjustments (id) {
+        id -> Uuid,
+        wallet_id -> Uuid,
+        customer_id -> Uuid,
+        kind -> Text,
+        amount_cents -> Integer,
+        description -> Nullable<Text>,
+        job_id -> Nullable<Uuid>,
+        status -> Text,
+        requires_approval -> Bool,
+        requested_by -> Text,
+        reviewed_by -> Nullable<Text>,
+        review_note -> Nullable<Text>,
+        transaction_id -> Nullable<Uuid>,
+        created_at -> Nullable<Timestamptz>,
+        reviewed_at -> Nullable<Timestamptz>,
     }
 }
 
+table! {
+    audit_log (id) {
+        id -> Uuid,
+        actor -> Text,
+        action -> Text,
+        entity_type -> Text,
+        entity_id -> Uuid,
+        details -> Jsonb,
+        created_at -> Nullable<Timestamptz>,
+    }
+}
+
+table! {
+    billing_periods (id) {
+        id -> Uuid,
+        period_start -> Timestamptz,
+        period_end -> Timestamptz,
+        closed_by -> Text,
+        transaction_count -> Integer,
+        credits_cents -> BigInt,
+        debits_cents -> BigInt,
+        ledger_sha256 -> Text,
+        closed_at -> Nullable<Timestamptz>,
+    }
+}
+
+table! {
+    invoices (id) {
+        id -> Uuid,
+        billing_period_id -> Uuid,
+        customer_id -> Uuid,
+        wallet_id -> Uuid,
+        opening_balance_cents -> BigInt,
+        credits_cents -> BigInt,
+        debits_cents -> BigInt,
+        closing_balance_cents -> BigInt,
+        transaction_count -> Integer,
+        finalized_at -> Nullable<Timestamptz>,
+        cost_breakdown -> Nullable<Jsonb>,
+        period_start -> Timestamptz,
+        period_end -> Timestamptz,
+        timezone -> Text,
+    }
+}
+
+table! {
+    feature_flags (id) {
+        id -> Uuid,
+        name -> Text,
+        enabled -> Bool,
+        reseller_ids -> Array<Uuid>,
+        description -> Nullable<Text>,
+        updated_by -> Text,
+        created_at -> Nullable<Timestamptz>,
+        updated_at -> Nullable<Timestamptz>,
+    }
+}
+
+table! {
+    job_attempts (id) {
+        id -> Uuid,
+        job_id -> Uuid,
+        attempt_number -> Integer,
+        runner_id -> Nullable<Uuid>,
+        status -> Text,
+        error -> Nullable<Jsonb>,
+        cost_cents -> Integer,
+        started_at -> Timestamptz,
+        finished_at -> Nullable<Timestamptz>,
+        crash -> Nullable<Jsonb>,
+    }
+}
+
+table! {
+    job_unredacted_outputs (job_id) {
+        job_id -> Uuid,
+        output_data -> Jsonb,
+        created_at -> Timestamptz,
+        expires_at -> Timestamptz,
+    }
+}
+
+table! {
+    spending_alerts (id) {
+        id -> Uuid,
+        customer_id -> Uuid,
+        reseller_id -> Nullable<Uuid>,
+        kind -> Text,
+        observed -> Double,
+        baseline -> Double,
+        window_start -> Timestamptz,
+        window_end -> Timestamptz,
+        created_at -> Timestamptz,
+    }
+}
+
+table! {
+    request_nonces (reseller_id, nonce) {
+        reseller_id -> Uuid,
+        nonce -> Text,
+        expires_at -> Timestamptz,
+    }
+}
+
+table! {
+    providers (id) {
+        id -> Uuid,
+        name -> Text,
+        ping_url -> Nullable<Text>,
+        failure_rate_threshold -> Double,
+        min_jobs -> Integer,
+        retry_delay_secs -> Integer,
+        status -> Text,
+        status_reason -> Nullable<Text>,
+        last_checked_at -> Nullable<Timestamptz>,
+        status_changed_at -> Nullable<Timestamptz>,
+        created_at -> Nullable<Timestamptz>,
+        updated_at -> Nullable<Timestamptz>,
+    }
+}
+
+table! {
+    fixture_bundles (id) {
+        id -> Uuid,
+        name -> Text,
+        spec -> Jsonb,
+        reseller_id -> Uuid,
+        job_type_id -> Uuid,
+        customer_ids -> Array<Uuid>,
+        runner_ids -> Array<Uuid>,
+        job_count -> Integer,
+        created_by -> Text,
+        created_at -> Nullable<Timestamptz>,
+    }
+}
+
+table! {
+    billing_exports (id) {
+        id -> Uuid,
+        job_id -> Uuid,
+        customer_id -> Uuid,
+        amount_cents -> Integer,
+        cost_breakdown -> Jsonb,
+        idempotency_key -> Text,
+        status -> Text,
+        attempts -> Integer,
+        status_code -> Nullable<Integer>,
+        last_error -> Nullable<Text>,
+        next_attempt_at -> Nullable<Timestamptz>,
+        completed_at -> Timestamptz,
+        exported_at -> Nullable<Timestamptz>,
+        created_at -> Timestamptz,
+        updated_at -> Timestamptz,
+    }
+}
+
+table! {
+    job_lifecycle_events (id) {
+        id -> Uuid,
+        job_id -> Uuid,
+        customer_id -> Uuid,
+        event_type -> Text,
+        data -> Jsonb,
+        occurred_at -> Timestamptz,
+        notified_at -> Nullable<Timestamptz>,
+        created_at -> Timestamptz,
+    }
+}
+
+table! {
+    job_usage (job_id) {
+        job_id -> Uuid,
+        attempt_id -> Nullable<Uuid>,
+        runner_id -> Nullable<Uuid>,
+        customer_id -> Uuid,
+        job_type_id -> Uuid,
+        wall_time_ms -> BigInt,
+        cpu_time_ms -> Nullable<BigInt>,
+        peak_memory_bytes -> Nullable<BigInt>,
+        bytes_sent -> BigInt,
+        bytes_received -> BigInt,
+        recorded_at -> Timestamptz,
+    }
+}
+
+table! {
+    load_windows (id) {
+        id -> Uuid,
+        name -> Text,
+        pool -> Nullable<Text>,
+        job_type_ids -> Array<Uuid>,
+        target_runners -> Integer,
+        starts_at -> Timestamptz,
+        ends_at -> Timestamptz,
+        warm_up_minutes -> Integer,
+        max_hold_minutes -> Integer,
+        status -> Text,
+        active_runners -> Nullable<Integer>,
+        notified_at -> Nullable<Timestamptz>,
+        ready_at -> Nullable<Timestamptz>,
+        released_at -> Nullable<Timestamptz>,
+        created_by -> Text,
+        created_at -> Timestamptz,
+        updated_at -> Timestamptz,
+    }
+}
+
+table! {
+    submissions (id) {
+        id -> Uuid,
+        customer_id -> Uuid,
+        source -> Text,
+        created_at -> Timestamptz,
+        cancelled_at -> Nullable<Timestamptz>,
+    }
+}
+
+table! {
+    replay_cases (id) {
+        id -> Uuid,
+        job_type_id -> Uuid,
+        source_job_id -> Uuid,
+        priority -> Integer,
+        input_data -> Jsonb,
+        markup_rate -> Integer,
+        sampled_at -> Timestamptz,
+        baseline_output -> Nullable<Jsonb>,
+        baseline_cost_cents -> Nullable<Integer>,
+        baseline_version -> Nullable<Text>,
+        baseline_recorded_at -> Nullable<Timestamptz>,
+    }
+}
+
+table! {
+    job_artifacts (id) {
+        id -> Uuid,
+        job_id -> Uuid,
+        content_type -> Text,
+        size_bytes -> BigInt,
+        content -> Bytea,
+        created_at -> Timestamptz,
+    }
+}
+
+table! {
+    ledger_accounts (id) {
+        id -> Uuid,
+        kind -> Text,
+        owner_id -> Nullable<Uuid>,
+        created_at -> Timestamptz,
+    }
+}
+
+table! {
+    ledger_entries (id) {
+        id -> Uuid,
+        transaction_id -> Uuid,
+        account_id -> Uuid,
+        job_id -> Nullable<Uuid>,
+        amount_cents -> BigInt,
+        created_at -> Timestamptz,
+    }
+}
+
+joinable!(ledger_entries -> ledger_accounts (account_id));
+
 allow_tables_to_appear_in_same_query!(
     job_types,
     jobs,
@@ -120,4 +652,38 @@ allow_tables_to_appear_in_same_query!(
     projects,
     runners,
     runner_job_type_compatibility,
+    customer_webhooks,
+    notification_deliveries,
+    notification_preferences,
+    job_logs,
+    job_templates,
+    runner_health_checks,
+    runner_environments,
+    submission_windows,
+    pipelines,
+    pipeline_runs,
+    pipeline_run_steps,
+    job_imports,
+    job_import_rows,
+    wallet_adjustments,
+    audit_log,
+    billing_periods,
+    invoices,
+    feature_flags,
+    job_attempts,
+    job_unredacted_outputs,
+    spending_alerts,
+    request_nonces,
+    providers,
+    fixture_bundles,
+    billing_exports,
+    job_usage,
+    load_windows,
+    submissions,
+    replay_cases,
+    job_artifacts,
+    ledger_accounts,
+    ledger_entries,
+    wallet_transaction_references,
+    job_lifecycle_events,
 );
@@ -0,0 +1,120 @@
+//! Localization of customer-facing messages
+//!
+//! Machine-readable codes (error codes, event reasons) never change with the locale;
+//! only the human-readable message accompanying them is translated. Messages are
+//! looked up by code in a built-in catalog, falling back to English.
+
+use serde::{Deserialize, Serialize};
+
+/// Language customer-facing messages are written in
+#[derive(Debug, Clone, Copy, Default, Serialize, Deserialize, PartialEq, Eq, Hash)]
+#[serde(rename_all = "lowercase")]
+pub enum Locale {
+    #[default]
+    En,
+    De,
+    Fr,
+}
+
+impl Locale {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Locale::En => "en",
+            Locale::De => "de",
+            Locale::Fr => "fr",
+        }
+    }
+
+    /// Parse a language tag such as `de`, `de-CH` or `FR_fr`; only the primary
+    /// language subtag is considered
+    pub fn parse(s: &str) -> Option<Self> {
+        let language = s.trim().split(['-', '_']).next()?.to_lowercase();
+        match language.as_str() {
+            "en" => Some(Locale::En),
+            "de" => Some(Locale::De),
+            "fr" => Some(Locale::Fr),
+            _ => None,
+        }
+    }
+
+    /// Pick the supported locale the client prefers from an `Accept-Language` header
+    ///
+    /// Languages are ranked by their `q` weight, earlier entries winning ties; a
+    /// weight of 0 excludes the language. Returns None when no supported language is
+    /// acceptable, including for `*`.
+    pub fn negotiate(accept_language: &str) -> Option<Self> {
+        let mut best: Option<(Locale, f32)> = None;
+        for entry in accept_language.split(',') {
+            let mut parts = entry.split(';');
+            let Some(locale) = parts.next().and_then(Locale::parse) else { continue };
+            let weight = parts
+                .find_map(|param| param.trim().strip_prefix("q="))
+                .map_or(Some(1.0), |q| q.trim().parse::<f32>().ok())
+                .unwrap_or(0.0);
+            if weight > 0.0 && best.is_none_or(|(_, best_weight)| weight > best_weight) {
+                best = Some((locale, weight));
+            }
+        }
+        best.map(|(locale, _)| locale)
+    }
+
+    /// Message for a code in this locale, in English if it is not translated, or
+    /// None for codes without a message
+    pub fn message(&self, code: &str) -> Option<&'static str> {
+        translate(*self, code).or_else(|| translate(Locale::En, code))
+    }
+}
+
+/// Catalog of customer-facing messages by locale and code
+fn translate(locale: Locale, code: &str) -> Option<&'static str> {
+    let message = match (code, locale) {
+        // API errors
+        ("account_suspended", Locale::En) => "Your account is suspended: you can read your data but not make changes.",
+        ("account_suspended", Locale::De) => "Ihr Konto ist gesperrt: Sie können Ihre Daten einsehen, aber keine Änderungen vornehmen.",
+        ("account_suspended", Locale::Fr) => "Votre compte est suspendu : vous pouvez consulter vos données mais pas les modifier.",
+        ("read_only", Locale::En) => "The API is temporarily in read-only mode. Please try again later.",
+        ("read_only", Locale::De) => "Die API ist vorübergehend schreibgeschützt. Bitte versuchen Sie es später erneut.",
+        ("read_only", Locale::Fr) => "L'API est temporairement en lecture seule. Veuillez réessayer plus tard.",
+        ("feature_disabled", Locale::En) => "This feature is temporarily disabled. Please try again later.",
+        ("feature_disabled", Locale::De) => "Diese Funktion ist vorübergehend deaktiviert. Bitte versuchen Sie es später erneut.",
+        ("feature_disabled", Locale::Fr) => "Cette fonctionnalité est temporairement désactivée. Veuillez réessayer plus tard.",
+        ("queue_saturated", Locale::En) => "Too many jobs are waiting to be processed. Please retry after the indicated delay.",
+        ("queue_saturated", Locale::De) => "Zu viele Jobs warten auf ihre Verarbeitung. Bitte versuchen Sie es nach der angegebenen Wartezeit erneut.",
+        ("queue_saturated", Locale::Fr) => "Trop de tâches sont en attente de traitement. Veuillez réessayer après le délai indiqué.",
+        ("auth_locked_out", Locale::En) => "Too many failed authentication attempts. Please retry after the indicated delay.",
+        ("auth_locked_out", Locale::De) => "Zu viele fehlgeschlagene Anmeldeversuche. Bitte versuchen Sie es nach der angegebenen Wartezeit erneut.",
+        ("auth_locked_out", Locale::Fr) => "Trop de tentatives d'authentification échouées. Veuillez réessayer après le délai indiqué.",
+        ("wallet_overdrawn", Locale::En) => "Your wallet is overdrawn. Please top it up to submit new jobs.",
+        ("wallet_overdrawn", Locale::De) => "Ihr Guthaben ist überzogen. Bitte laden Sie es auf, um neue Jobs einzureichen.",
+        ("wallet_overdrawn", Locale::Fr) => "Votre portefeuille est à découvert. Veuillez le réapprovisionner pour soumettre de nouvelles tâches.",
+        ("database_busy", Locale::En) => "The service is handling too many requests right now. Please retry after the indicated delay.",
+        ("database_busy", Locale::De) => "Der Dienst bearbeitet gerade zu viele Anfragen. Bitte versuchen Sie es nach der angegebenen Wartezeit erneut.",
+        ("database_busy", Locale::Fr) => "Le service traite actuellement trop de requêtes. Veuillez réessayer après le délai indiqué.",
+        ("invalid_request_body", Locale::En) => "Some fields of the request are unknown or invalid. See the listed fields.",
+        ("invalid_request_body", Locale::De) => "Einige Felder der Anfrage sind unbekannt oder ungültig. Siehe die aufgeführten Felder.",
+        ("invalid_request_body", Locale::Fr) => "Certains champs de la requête sont inconnus ou invalides. Voir les champs indiqués.",
+        ("malformed_request_body", Locale::En) => "The request body is not valid JSON.",
+        ("malformed_request_body", Locale::De) => "Der Inhalt der Anfrage ist kein gültiges JSON.",
+        ("malformed_request_body", Locale::Fr) => "Le corps de la requête n'est pas un JSON valide.",
+        ("unsupported_media_type", Locale::En) => "The request body must be sent as application/json.",
+        ("unsupported_media_type", Locale::De) => "Der Inhalt der Anfrage muss als application/json gesendet werden.",
+        ("unsupported_media_type", Locale::Fr) => "Le corps de la requête doit être envoyé en application/json.",
+
+        // Notification events, by event type and reason
+        ("job.cancelled.reservation_expired", Locale::En) => "Your job was cancelled because it did not start before the funds reserved for it expired.",
+        ("job.cancelled.reservation_expired", Locale::De) => "Ihr Job wurde abgebrochen, da er nicht vor Ablauf der dafür reservierten Mittel gestartet wurde.",
+        ("job.cancelled.reservation_expired", Locale::Fr) => "Votre tâche a été annulée car elle n'a pas démarré avant l'expiration des fonds réservés.",
+        ("job.expired.deadline_passed", Locale::En) => "Your job expired because it could not start before its deadline. Reserved funds were released.",
+        ("job.expired.deadline_passed", Locale::De) => "Ihr Job ist abgelaufen, da er nicht vor seiner Frist starten konnte. Reservierte Mittel wurden freigegeben.",
+        ("job.expired.deadline_passed", Locale::Fr) => "Votre tâche a expiré car elle n'a pas pu démarrer avant son échéance. Les fonds réservés ont été libérés.",
+        ("spending.anomaly.spend_spike", Locale::En) => "Your spending is much higher than usual.",
+        ("spending.anomaly.spend_spike", Locale::De) => "Ihre Ausgaben sind deutlich höher als üblich.",
+        ("spending.anomaly.spend_spike", Locale::Fr) => "Vos dépenses sont bien plus élevées que d'habitude.",
+        ("spending.anomaly.failure_rate_jump", Locale::En) => "Many more of your jobs are failing than usual.",
+        ("spending.anomaly.failure_rate_jump", Locale::De) => "Deutlich mehr Ihrer Jobs schlagen fehl als üblich.",
+        ("spending.anomaly.failure_rate_jump", Locale::Fr) => "Beaucoup plus de vos tâches échouent que d'habitude.",
+
+        _ => return None,
+    };
+    Some(message)
+}
@@ -1,3 +1,4 @@
+pub mod api;
 pub mod models;
 pub mod repositories;
 pub mod errors;
@@ -6,7 +7,14 @@ pub mod config;
 pub mod diesel_schema;
 pub mod database;
 pub mod migrations;
+pub mod schema_drift;
 pub mod seed;
+pub mod i18n;
+pub mod timezone;
+pub mod pdf;
+pub mod purge;
+#[cfg(feature = "testing")]
+pub mod testing;
 
 /// Re-export commonly used types
 pub use errors::Error;
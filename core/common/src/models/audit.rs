@@ -0,0 +1,49 @@
+use chrono::{DateTime, Utc};
+use diesel::prelude::*;
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use uuid::Uuid;
+
+use crate::diesel_schema::audit_log;
+
+/// Record of an action taken on an entity, kept for accountability
+#[derive(Debug, Clone, Serialize, Deserialize, Queryable, Identifiable, Selectable)]
+#[diesel(table_name = audit_log)]
+#[diesel(check_for_backend(diesel::pg::Pg))]
+pub struct AuditEntry {
+    pub id: Uuid,
+    /// Admin or other principal who took the action
+    pub actor: String,
+    /// What was done, e.g. `wallet_adjustment.approved`
+    pub action: String,
+    pub entity_type: String,
+    pub entity_id: Uuid,
+    pub details: Value,
+    pub created_at: Option<DateTime<Utc>>,
+}
+
+// For DB insertion with Diesel
+#[derive(Debug, Clone, Serialize, Deserialize, Insertable)]
+#[diesel(table_name = audit_log)]
+pub struct NewAuditEntry {
+    pub id: Uuid,
+    pub actor: String,
+    pub action: String,
+    pub entity_type: String,
+    pub entity_id: Uuid,
+    pub details: Value,
+}
+
+impl NewAuditEntry {
+    /// Create an entry for an action on an entity
+    pub fn new(actor: impl Into<String>, action: impl Into<String>, entity_type: impl Into<String>, entity_id: Uuid, details: Value) -> Self {
+        Self {
+            id: Uuid::new_v4(),
+            actor: actor.into(),
+            action: action.into(),
+            entity_type: entity_type.into(),
+            entity_id,
+            details,
+        }
+    }
+}
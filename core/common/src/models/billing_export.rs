@@ -0,0 +1,196 @@
+use chrono::{DateTime, Duration, Utc};
+use diesel::prelude::*;
+use serde::{Deserialize, Serialize};
+use serde_json::{json, Value};
+use uuid::Uuid;
+
+use crate::diesel_schema::billing_exports;
+
+/// Base delay before the second attempt of an export, doubled for every further attempt
+const RETRY_BASE_DELAY_SECS: i64 = 60;
+/// Upper bound for the delay between two attempts
+const MAX_RETRY_DELAY_SECS: i64 = 6 * 60 * 60;
+
+/// State of a job's billing export
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+pub enum ExportStatus {
+    /// Not accepted by the billing endpoint yet; attempted again at `next_attempt_at`
+    Pending,
+    /// Accepted by the billing endpoint
+    Exported,
+    /// Attempts exhausted, left for an admin to retry
+    Failed,
+}
+
+impl ExportStatus {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            ExportStatus::Pending => "pending",
+            ExportStatus::Exported => "exported",
+            ExportStatus::Failed => "failed",
+        }
+    }
+
+    pub fn parse(s: &str) -> Option<Self> {
+        match s.to_lowercase().as_str() {
+            "pending" => Some(ExportStatus::Pending),
+            "exported" => Some(ExportStatus::Exported),
+            "failed" => Some(ExportStatus::Failed),
+            _ => None,
+        }
+    }
+}
+
+/// Idempotency key of the billing record of a job
+///
+/// Derived from the job alone, so every attempt to export a job's charge carries the
+/// same key, however often the export is retried or recreated.
+pub fn idempotency_key(job_id: Uuid) -> String {
+    format!("job-charge:{}", job_id)
+}
+
+/// Delay before the next attempt of an export that failed `attempts` times, or None
+/// once `max_attempts` are used up
+pub fn retry_delay(attempts: i32, max_attempts: i32) -> Option<Duration> {
+    if attempts >= max_attempts {
+        return None;
+    }
+    let exponent = (attempts - 1).clamp(0, 30);
+    let delay = RETRY_BASE_DELAY_SECS.saturating_mul(1i64 << exponent);
+    Some(Duration::seconds(delay.min(MAX_RETRY_DELAY_SECS)))
+}
+
+/// Finalized charge of a job in the outbox of the external billing system
+#[derive(Debug, Clone, Serialize, Deserialize, Queryable, Identifiable, Selectable)]
+#[diesel(table_name = billing_exports)]
+#[diesel(check_for_backend(diesel::pg::Pg))]
+pub struct BillingExport {
+    pub id: Uuid,
+    pub job_id: Uuid,
+    pub customer_id: Uuid,
+    /// Amount charged to the customer's wallet
+    pub amount_cents: i32,
+    pub cost_breakdown: Value,
+    pub idempotency_key: String,
+    pub status: String,
+    pub attempts: i32,
+    /// HTTP status of the last attempt, if the endpoint answered
+    pub status_code: Option<i32>,
+    pub last_error: Option<String>,
+    pub next_attempt_at: Option<DateTime<Utc>>,
+    /// When the job completed
+    pub completed_at: DateTime<Utc>,
+    pub exported_at: Option<DateTime<Utc>>,
+    pub created_at: DateTime<Utc>,
+    pub updated_at: DateTime<Utc>,
+}
+
+impl BillingExport {
+    /// Typed status of the export
+    pub fn export_status(&self) -> Option<ExportStatus> {
+        ExportStatus::parse(&self.status)
+    }
+
+    /// Billing record posted to the external billing system
+    pub fn record(&self) -> Value {
+        json!({
+            "idempotency_key": self.idempotency_key,
+            "job_id": self.job_id,
+            "customer_id": self.customer_id,
+            "amount_cents": self.amount_cents,
+            "cost_breakdown": self.cost_breakdown,
+            "completed_at": self.completed_at,
+        })
+    }
+}
+
+// For DB insertion with Diesel
+#[derive(Debug, Clone, Serialize, Deserialize, Insertable)]
+#[diesel(table_name = billing_exports)]
+pub struct NewBillingExport {
+    pub id: Uuid,
+    pub job_id: Uuid,
+    pub customer_id: Uuid,
+    pub amount_cents: i32,
+    pub cost_breakdown: Value,
+    pub idempotency_key: String,
+    pub completed_at: DateTime<Utc>,
+}
+
+impl NewBillingExport {
+    /// Outbox entry for the charge of a job
+    pub fn new(job_id: Uuid, customer_id: Uuid, amount_cents: i32, cost_breakdown: Value, completed_at: DateTime<Utc>) -> Self {
+        Self {
+            id: Uuid::new_v4(),
+            job_id,
+            customer_id,
+            amount_cents,
+            cost_breakdown,
+            idempotency_key: idempotency_key(job_id),
+            completed_at,
+        }
+    }
+}
+
+/// Outcome of a single export attempt, applied to an existing export
+#[derive(Debug, Clone)]
+pub struct ExportAttempt {
+    pub status: ExportStatus,
+    pub attempts: i32,
+    pub status_code: Option<i32>,
+    pub last_error: Option<String>,
+    pub next_attempt_at: Option<DateTime<Utc>>,
+    pub exported_at: Option<DateTime<Utc>>,
+}
+
+/// A finalized charge the billing system has not accepted yet
+#[derive(Debug, Clone, Serialize, PartialEq)]
+pub struct UnexportedRecord {
+    pub job_id: Uuid,
+    pub customer_id: Uuid,
+    pub amount_cents: i32,
+    pub completed_at: DateTime<Utc>,
+    /// Status of its export, or `uncaptured` while it has none
+    pub status: String,
+    pub attempts: i32,
+    pub last_error: Option<String>,
+}
+
+/// Finalized job charges of a period compared to what was exported of them
+///
+/// A charge is finalized once its job succeeded and the breakdown of its cost was
+/// recorded; sub-tasks are billed through their parent and test mode jobs not at all.
+#[derive(Debug, Clone, Default, Serialize, PartialEq)]
+pub struct BillingReconciliation {
+    pub finalized_jobs: i64,
+    pub finalized_cents: i64,
+    pub exported: i64,
+    pub exported_cents: i64,
+    /// Exports waiting for their next attempt
+    pub pending: i64,
+    /// Exports whose attempts are exhausted
+    pub failed: i64,
+    /// Finalized charges without an export
+    pub uncaptured: i64,
+    pub unexported_cents: i64,
+    /// Charges not exported yet, oldest first
+    pub unexported: Vec<UnexportedRecord>,
+}
+
+impl BillingReconciliation {
+    /// Add the reconciliation of another schema, keeping the `limit` oldest unexported records
+    pub fn merge(&mut self, other: BillingReconciliation, limit: usize) {
+        self.finalized_jobs += other.finalized_jobs;
+        self.finalized_cents += other.finalized_cents;
+        self.exported += other.exported;
+        self.exported_cents += other.exported_cents;
+        self.pending += other.pending;
+        self.failed += other.failed;
+        self.uncaptured += other.uncaptured;
+        self.unexported_cents += other.unexported_cents;
+        self.unexported.extend(other.unexported);
+        self.unexported.sort_by_key(|record| record.completed_at);
+        self.unexported.truncate(limit);
+    }
+}
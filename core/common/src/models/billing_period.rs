@@ -0,0 +1,173 @@
+use chrono::{DateTime, Datelike, NaiveDate, Utc};
+use diesel::prelude::*;
+use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
+use thiserror::Error;
+use uuid::Uuid;
+
+use crate::diesel_schema::{billing_periods, invoices};
+use crate::models::job_cost::CostBreakdown;
+use crate::models::wallet::WalletTransaction;
+
+/// Reasons a billing period cannot be closed or its ledger changed
+#[derive(Debug, Error, PartialEq, Eq)]
+pub enum LedgerError {
+    #[error("Invalid billing month {year}-{month}")]
+    InvalidMonth { year: i32, month: u32 },
+
+    #[error("Billing period starting {0} has not ended yet")]
+    PeriodNotOver(DateTime<Utc>),
+
+    #[error("Billing period starting {0} overlaps a closed period")]
+    AlreadyClosed(DateTime<Utc>),
+
+    #[error("Transactions dated {0} fall in a closed billing period; post a correcting entry instead")]
+    PeriodClosed(DateTime<Utc>),
+}
+
+/// Start and (exclusive) end of a calendar month
+pub fn month_bounds(year: i32, month: u32) -> Result<(DateTime<Utc>, DateTime<Utc>), LedgerError> {
+    let start = NaiveDate::from_ymd_opt(year, month, 1)
+        .ok_or(LedgerError::InvalidMonth { year, month })?;
+    let end = match start.month() {
+        12 => NaiveDate::from_ymd_opt(year + 1, 1, 1),
+        month => NaiveDate::from_ymd_opt(year, month + 1, 1),
+    }.ok_or(LedgerError::InvalidMonth { year, month })?;
+    Ok((start.and_hms_opt(0, 0, 0).unwrap_or_default().and_utc(), end.and_hms_opt(0, 0, 0).unwrap_or_default().and_utc()))
+}
+
+/// Header of the ledger export
+const LEDGER_HEADER: &str = "transaction_id,created_at,customer_id,wallet_id,transaction_type,amount_cents,job_id,reference_id,description";
+
+/// Render transactions as the CSV ledger export, one line per transaction in the given order
+pub fn ledger_csv(transactions: &[WalletTransaction]) -> String {
+    let mut out = String::from(LEDGER_HEADER);
+    out.push('\n');
+    for tx in transactions {
+        let optional = |id: Option<Uuid>| id.map(|id| id.to_string()).unwrap_or_default();
+        out.push_str(&format!(
+            "{},{},{},{},{},{},{},{},{}\n",
+            tx.id,
+            tx.created_at.map(|at| at.format("%Y-%m-%dT%H:%M:%S%.6f").to_string()).unwrap_or_default(),
+            tx.customer_id,
+            tx.wallet_id,
+            csv_field(&tx.transaction_type),
+            tx.amount_cents,
+            optional(tx.job_id),
+            optional(tx.reference_id),
+            csv_field(tx.description.as_deref().unwrap_or_default()),
+        ));
+    }
+    out
+}
+
+/// Quote a CSV field if it contains a separator, quote or line break
+pub(crate) fn csv_field(value: &str) -> String {
+    if value.contains([',', '"', '\n', '\r']) {
+        format!("\"{}\"", value.replace('"', "\"\""))
+    } else {
+        value.to_string()
+    }
+}
+
+/// Hex SHA-256 of a ledger export, recorded when its period is closed
+pub fn ledger_sha256(csv: &str) -> String {
+    hex::encode(Sha256::digest(csv.as_bytes()))
+}
+
+/// Month whose wallet transactions are final
+#[derive(Debug, Clone, Serialize, Deserialize, Queryable, Identifiable, Selectable)]
+#[diesel(table_name = billing_periods)]
+#[diesel(check_for_backend(diesel::pg::Pg))]
+pub struct BillingPeriod {
+    pub id: Uuid,
+    pub period_start: DateTime<Utc>,
+    /// Exclusive end of the period
+    pub period_end: DateTime<Utc>,
+    /// Admin who closed the period
+    pub closed_by: String,
+    pub transaction_count: i32,
+    /// Total of the period's credits, in cents
+    pub credits_cents: i64,
+    /// Total of the period's debits as a positive amount, in cents
+    pub debits_cents: i64,
+    /// Checksum of the ledger export at closing time
+    pub ledger_sha256: String,
+    pub closed_at: Option<DateTime<Utc>>,
+}
+
+impl BillingPeriod {
+    /// Whether a transaction dated `at` belongs to the period
+    pub fn contains(&self, at: DateTime<Utc>) -> bool {
+        self.period_start <= at && at < self.period_end
+    }
+}
+
+// For DB insertion with Diesel
+#[derive(Debug, Clone, Serialize, Deserialize, Insertable)]
+#[diesel(table_name = billing_periods)]
+pub struct NewBillingPeriod {
+    pub id: Uuid,
+    pub period_start: DateTime<Utc>,
+    pub period_end: DateTime<Utc>,
+    pub closed_by: String,
+    pub transaction_count: i32,
+    pub credits_cents: i64,
+    pub debits_cents: i64,
+    pub ledger_sha256: String,
+}
+
+/// Final statement of a customer's wallet over a closed billing period
+///
+/// The period is cut at the same wall-clock times as the billing period, read in the
+/// customer's time zone, so a customer's invoices follow its own calendar months.
+#[derive(Debug, Clone, Serialize, Deserialize, Queryable, Identifiable, Selectable)]
+#[diesel(table_name = invoices)]
+#[diesel(check_for_backend(diesel::pg::Pg))]
+pub struct Invoice {
+    pub id: Uuid,
+    pub billing_period_id: Uuid,
+    pub customer_id: Uuid,
+    pub wallet_id: Uuid,
+    pub opening_balance_cents: i64,
+    pub credits_cents: i64,
+    /// Debits as a positive amount
+    pub debits_cents: i64,
+    pub closing_balance_cents: i64,
+    pub transaction_count: i32,
+    pub finalized_at: Option<DateTime<Utc>>,
+    /// Total of the itemized costs of the jobs charged in the period
+    pub cost_breakdown: Option<serde_json::Value>,
+    /// Start of the period in the customer's time zone, as a UTC instant
+    pub period_start: DateTime<Utc>,
+    /// Exclusive end of the period in the customer's time zone, as a UTC instant
+    pub period_end: DateTime<Utc>,
+    /// Time zone the invoice's period was cut in
+    pub timezone: String,
+}
+
+impl Invoice {
+    /// What the jobs charged in the period cost, item by item
+    pub fn cost_breakdown(&self) -> Option<CostBreakdown> {
+        self.cost_breakdown.clone().and_then(|breakdown| serde_json::from_value(breakdown).ok())
+    }
+}
+
+// For DB insertion with Diesel
+#[derive(Debug, Clone, Serialize, Deserialize, Insertable)]
+#[diesel(table_name = invoices)]
+pub struct NewInvoice {
+    pub id: Uuid,
+    pub billing_period_id: Uuid,
+    pub customer_id: Uuid,
+    pub wallet_id: Uuid,
+    pub opening_balance_cents: i64,
+    pub credits_cents: i64,
+    pub debits_cents: i64,
+    pub closing_balance_cents: i64,
+    pub transaction_count: i32,
+    pub cost_breakdown: Option<serde_json::Value>,
+    pub period_start: DateTime<Utc>,
+    pub period_end: DateTime<Utc>,
+    pub timezone: String,
+}
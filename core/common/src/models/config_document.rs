@@ -0,0 +1,395 @@
+use std::collections::{BTreeMap, BTreeSet, HashMap};
+
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use thiserror::Error;
+use uuid::Uuid;
+
+use crate::models::feature_flag::{self, FeatureFlag};
+use crate::models::job_type::{CatalogVisibility, JobType, ProcessorType};
+use crate::models::runner::{Runner, RunnerStatus};
+use crate::models::response_policy::ResponsePolicy;
+use crate::models::webhook_template::WebhookTemplate;
+
+/// Reasons a declarative configuration document cannot be applied
+#[derive(Debug, Error, PartialEq)]
+pub enum ConfigDocumentError {
+    #[error("Invalid configuration document: {0}")]
+    InvalidDocument(String),
+
+    #[error("Job type {job_type} has unknown processor type {processor_type}")]
+    UnknownProcessorType { job_type: String, processor_type: String },
+
+    #[error("Runner pool {pool} uses unknown job type {job_type}")]
+    UnknownJobType { pool: String, job_type: String },
+}
+
+fn enabled_by_default() -> bool {
+    true
+}
+
+/// Desired settings of a job type, identified by its name
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+#[serde(deny_unknown_fields)]
+pub struct JobTypeSpec {
+    #[serde(default)]
+    pub description: Option<String>,
+    pub processing_logic_id: String,
+    /// sync, async, external_api, batch or webhook
+    pub processor_type: String,
+    pub standard_cost_cents: i32,
+    #[serde(default = "enabled_by_default")]
+    pub enabled: bool,
+    #[serde(default)]
+    pub category: Option<String>,
+    #[serde(default)]
+    pub icon: Option<String>,
+    #[serde(default)]
+    pub documentation_url: Option<String>,
+    #[serde(default)]
+    pub sample_input: Option<Value>,
+    #[serde(default)]
+    pub sample_output: Option<Value>,
+    #[serde(default)]
+    pub visibility: CatalogVisibility,
+    #[serde(default)]
+    pub redaction_rules: Vec<String>,
+    #[serde(default)]
+    pub unredacted_retention_hours: Option<i32>,
+    /// Priority level, 0 (low) to 3 (critical), of jobs submitted without one
+    #[serde(default)]
+    pub default_priority: Option<i32>,
+    /// Method, headers and body template of the job type's webhook jobs
+    #[serde(default)]
+    pub webhook_template: Option<WebhookTemplate>,
+    /// How the runner keeps the responses of the job type's webhook calls
+    #[serde(default)]
+    pub response_policy: Option<ResponsePolicy>,
+}
+
+/// Desired settings of the runners registered under a name
+///
+/// A pool without runners is created by registering one runner with the name.
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+#[serde(deny_unknown_fields)]
+pub struct RunnerPoolSpec {
+    /// Names of the job types the pool's runners process
+    #[serde(default)]
+    pub job_types: BTreeSet<String>,
+    #[serde(default = "enabled_by_default")]
+    pub active: bool,
+}
+
+/// Desired settings of a feature flag, the entitlement of resellers to a feature
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+#[serde(deny_unknown_fields)]
+pub struct FeatureFlagSpec {
+    #[serde(default)]
+    pub enabled: bool,
+    #[serde(default)]
+    pub reseller_ids: BTreeSet<Uuid>,
+    #[serde(default)]
+    pub description: Option<String>,
+}
+
+/// Platform configuration kept in version control and applied with `PUT /admin/config/apply`
+///
+/// ```yaml
+/// job_types:
+///   ocr:
+///     processing_logic_id: ocr-v2
+///     processor_type: async
+///     standard_cost_cents: 25
+/// runner_pools:
+///   gpu:
+///     job_types: [ocr]
+/// feature_flags:
+///   batch_exports:
+///     reseller_ids: [7f9c1a5e-2b1d-4c55-9a61-0c8e3f1d2a44]
+/// ```
+///
+/// Each section present is authoritative for its kind of resource: when pruning, job
+/// types missing from it are disabled, runner pools deactivated and feature flags
+/// deleted. Sections left out are not changed.
+#[derive(Debug, Clone, Default, Serialize, Deserialize, PartialEq)]
+#[serde(deny_unknown_fields)]
+pub struct ConfigDocument {
+    #[serde(default)]
+    pub job_types: Option<BTreeMap<String, JobTypeSpec>>,
+    #[serde(default)]
+    pub runner_pools: Option<BTreeMap<String, RunnerPoolSpec>>,
+    #[serde(default)]
+    pub feature_flags: Option<BTreeMap<String, FeatureFlagSpec>>,
+}
+
+/// Kind of resource a configuration document manages
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq, Eq, PartialOrd, Ord)]
+#[serde(rename_all = "snake_case")]
+pub enum ResourceKind {
+    JobType,
+    RunnerPool,
+    FeatureFlag,
+}
+
+impl ResourceKind {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            ResourceKind::JobType => "job_type",
+            ResourceKind::RunnerPool => "runner_pool",
+            ResourceKind::FeatureFlag => "feature_flag",
+        }
+    }
+}
+
+/// What applying a document does to a resource
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+pub enum ChangeAction {
+    Create,
+    Update,
+    /// Job types are disabled and runner pools deactivated rather than deleted, as
+    /// jobs and history refer to them
+    Disable,
+    Delete,
+}
+
+impl ChangeAction {
+    /// Past tense, as used in audit log actions
+    pub fn past_tense(&self) -> &'static str {
+        match self {
+            ChangeAction::Create => "created",
+            ChangeAction::Update => "updated",
+            ChangeAction::Disable => "disabled",
+            ChangeAction::Delete => "deleted",
+        }
+    }
+}
+
+/// One entry of the change plan of a document
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq, Eq)]
+pub struct ResourceChange {
+    pub kind: ResourceKind,
+    pub name: String,
+    pub action: ChangeAction,
+    /// Settings that differ, for updates
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub fields: Vec<String>,
+}
+
+/// A runner with the IDs of the job types it is registered as compatible with
+#[derive(Debug, Clone)]
+pub struct DeployedRunner {
+    pub runner: Runner,
+    pub job_type_ids: Vec<Uuid>,
+}
+
+impl ConfigDocument {
+    /// Parse a YAML (or JSON) document and check the settings that need no current state
+    pub fn parse(yaml: &str) -> Result<Self, ConfigDocumentError> {
+        let document: Self = serde_yaml::from_str(yaml)
+            .map_err(|e| ConfigDocumentError::InvalidDocument(e.to_string()))?;
+
+        for (name, spec) in document.job_types.iter().flatten() {
+            if name.trim().is_empty() {
+                return Err(ConfigDocumentError::InvalidDocument("job type names must not be empty".to_string()));
+            }
+            if ProcessorType::from_str(&spec.processor_type).is_none() {
+                return Err(ConfigDocumentError::UnknownProcessorType {
+                    job_type: name.clone(),
+                    processor_type: spec.processor_type.clone(),
+                });
+            }
+            if spec.default_priority.is_some_and(|level| !(0..=3).contains(&level)) {
+                return Err(ConfigDocumentError::InvalidDocument(format!("job type {} has a default priority outside 0 to 3", name)));
+            }
+            if let Some(template) = &spec.webhook_template {
+                template.validate()
+                    .map_err(|e| ConfigDocumentError::InvalidDocument(format!("job type {}: {}", name, e)))?;
+            }
+            if let Some(policy) = &spec.response_policy {
+                policy.validate()
+                    .map_err(|e| ConfigDocumentError::InvalidDocument(format!("job type {}: {}", name, e)))?;
+            }
+        }
+        if document.runner_pools.iter().flatten().any(|(name, _)| name.trim().is_empty()) {
+            return Err(ConfigDocumentError::InvalidDocument("runner pool names must not be empty".to_string()));
+        }
+        for name in document.feature_flags.iter().flatten().map(|(name, _)| name) {
+            feature_flag::validate_name(name)
+                .map_err(|e| ConfigDocumentError::InvalidDocument(e.to_string()))?;
+        }
+
+        Ok(document)
+    }
+
+    /// Diff the document against the current resources
+    ///
+    /// Changes are listed job types first, then runner pools, then feature flags, in
+    /// the order they must be applied; resources already as described are left out,
+    /// so the plan of an applied document is empty. Without `prune`, resources missing
+    /// from the document are kept as they are.
+    pub fn plan(
+        &self,
+        job_types: &[JobType],
+        runners: &[DeployedRunner],
+        feature_flags: &[FeatureFlag],
+        prune: bool,
+    ) -> Result<Vec<ResourceChange>, ConfigDocumentError> {
+        let mut changes = Vec::new();
+
+        if let Some(specs) = &self.job_types {
+            for (name, spec) in specs {
+                match job_types.iter().find(|job_type| &job_type.name == name) {
+                    None => changes.push(change(ResourceKind::JobType, name, ChangeAction::Create, Vec::new())),
+                    Some(current) => {
+                        let fields = job_type_differences(current, spec);
+                        if !fields.is_empty() {
+                            changes.push(change(ResourceKind::JobType, name, ChangeAction::Update, fields));
+                        }
+                    }
+                }
+            }
+            for job_type in job_types.iter().filter(|job_type| prune && job_type.enabled && !specs.contains_key(&job_type.name)) {
+                changes.push(change(ResourceKind::JobType, &job_type.name, ChangeAction::Disable, Vec::new()));
+            }
+        }
+
+        if let Some(specs) = &self.runner_pools {
+            let job_type_names: HashMap<Uuid, &str> = job_types.iter()
+                .map(|job_type| (job_type.id, job_type.name.as_str()))
+                .collect();
+            let mut pools: BTreeMap<&str, Vec<&DeployedRunner>> = BTreeMap::new();
+            for deployed in runners {
+                pools.entry(deployed.runner.name.as_str()).or_default().push(deployed);
+            }
+
+            for (name, spec) in specs {
+                if let Some(unknown) = spec.job_types.iter().find(|job_type| !self.provides_job_type(job_type, job_types, prune)) {
+                    return Err(ConfigDocumentError::UnknownJobType { pool: name.clone(), job_type: unknown.clone() });
+                }
+                let Some(members) = pools.get(name.as_str()) else {
+                    changes.push(change(ResourceKind::RunnerPool, name, ChangeAction::Create, Vec::new()));
+                    continue;
+                };
+
+                let mut fields = Vec::new();
+                let compatible_with = |deployed: &DeployedRunner| -> BTreeSet<String> {
+                    deployed.job_type_ids.iter()
+                        .filter_map(|id| job_type_names.get(id).map(|name| name.to_string()))
+                        .collect()
+                };
+                if members.iter().any(|deployed| compatible_with(deployed) != spec.job_types) {
+                    fields.push("job_types".to_string());
+                }
+                if members.iter().any(|deployed| is_active(&deployed.runner) != spec.active) {
+                    fields.push("active".to_string());
+                }
+                if !fields.is_empty() {
+                    changes.push(change(ResourceKind::RunnerPool, name, ChangeAction::Update, fields));
+                }
+            }
+            for (name, members) in &pools {
+                if prune && !specs.contains_key(*name) && members.iter().any(|deployed| is_active(&deployed.runner)) {
+                    changes.push(change(ResourceKind::RunnerPool, name, ChangeAction::Disable, Vec::new()));
+                }
+            }
+        }
+
+        if let Some(specs) = &self.feature_flags {
+            for (name, spec) in specs {
+                match feature_flags.iter().find(|flag| &flag.name == name) {
+                    None => changes.push(change(ResourceKind::FeatureFlag, name, ChangeAction::Create, Vec::new())),
+                    Some(current) => {
+                        let mut fields = Vec::new();
+                        if current.enabled != spec.enabled {
+                            fields.push("enabled".to_string());
+                        }
+                        if current.reseller_ids.iter().copied().collect::<BTreeSet<_>>() != spec.reseller_ids {
+                            fields.push("reseller_ids".to_string());
+                        }
+                        if current.description != spec.description {
+                            fields.push("description".to_string());
+                        }
+                        if !fields.is_empty() {
+                            changes.push(change(ResourceKind::FeatureFlag, name, ChangeAction::Update, fields));
+                        }
+                    }
+                }
+            }
+            for flag in feature_flags.iter().filter(|flag| prune && !specs.contains_key(&flag.name)) {
+                changes.push(change(ResourceKind::FeatureFlag, &flag.name, ChangeAction::Delete, Vec::new()));
+            }
+        }
+
+        Ok(changes)
+    }
+
+    /// Whether a job type is enabled once the document is applied
+    fn provides_job_type(&self, name: &str, job_types: &[JobType], prune: bool) -> bool {
+        match self.job_types.as_ref().and_then(|specs| specs.get(name)) {
+            Some(spec) => spec.enabled,
+            None => (self.job_types.is_none() || !prune) && job_types.iter().any(|job_type| job_type.name == name && job_type.enabled),
+        }
+    }
+}
+
+impl JobTypeSpec {
+    /// Apply the settings to a job type, keeping its ID and name
+    pub fn apply_to(&self, job_type: &mut JobType) {
+        job_type.description = self.description.clone();
+        job_type.processing_logic_id = self.processing_logic_id.clone();
+        if let Some(processor_type) = ProcessorType::from_str(&self.processor_type) {
+            job_type.processor_type = processor_type;
+        }
+        job_type.standard_cost_cents = self.standard_cost_cents;
+        job_type.enabled = self.enabled;
+        job_type.category = self.category.clone();
+        job_type.icon = self.icon.clone();
+        job_type.documentation_url = self.documentation_url.clone();
+        job_type.sample_input = self.sample_input.clone();
+        job_type.sample_output = self.sample_output.clone();
+        job_type.visibility = self.visibility;
+        job_type.redaction_rules = self.redaction_rules.clone();
+        job_type.unredacted_retention_hours = self.unredacted_retention_hours;
+        job_type.default_priority = self.default_priority;
+        job_type.webhook_template = self.webhook_template.as_ref().and_then(|template| serde_json::to_value(template).ok());
+        job_type.response_policy = self.response_policy.as_ref().and_then(|policy| serde_json::to_value(policy).ok());
+    }
+}
+
+fn change(kind: ResourceKind, name: &str, action: ChangeAction, fields: Vec<String>) -> ResourceChange {
+    ResourceChange { kind, name: name.to_string(), action, fields }
+}
+
+fn is_active(runner: &Runner) -> bool {
+    runner.status == RunnerStatus::Active
+}
+
+/// Names of the settings of a job type that differ from its spec
+fn job_type_differences(current: &JobType, spec: &JobTypeSpec) -> Vec<String> {
+    let mut desired = current.clone();
+    spec.apply_to(&mut desired);
+
+    let differences = [
+        ("description", current.description != desired.description),
+        ("processing_logic_id", current.processing_logic_id != desired.processing_logic_id),
+        ("processor_type", current.processor_type.as_str() != desired.processor_type.as_str()),
+        ("standard_cost_cents", current.standard_cost_cents != desired.standard_cost_cents),
+        ("enabled", current.enabled != desired.enabled),
+        ("category", current.category != desired.category),
+        ("icon", current.icon != desired.icon),
+        ("documentation_url", current.documentation_url != desired.documentation_url),
+        ("sample_input", current.sample_input != desired.sample_input),
+        ("sample_output", current.sample_output != desired.sample_output),
+        ("visibility", current.visibility != desired.visibility),
+        ("redaction_rules", current.redaction_rules != desired.redaction_rules),
+        ("unredacted_retention_hours", current.unredacted_retention_hours != desired.unredacted_retention_hours),
+        ("default_priority", current.default_priority != desired.default_priority),
+        ("webhook_template", current.webhook_template != desired.webhook_template),
+        ("response_policy", current.response_policy != desired.response_policy),
+    ];
+    differences.into_iter()
+        .filter(|(_, differs)| *differs)
+        .map(|(field, _)| field.to_string())
+        .collect()
+}
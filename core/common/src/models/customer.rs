@@ -1,9 +1,10 @@
 use diesel::prelude::*;
 use serde::{Deserialize, Serialize};
 use uuid::Uuid;
-use chrono::NaiveDateTime;
+use chrono::{DateTime, Utc};
 
 use crate::diesel_schema::customers;
+use crate::models::pii::PiiPolicy;
 
 #[derive(Debug, Clone, Serialize, Deserialize, Queryable, Selectable, Identifiable)]
 #[diesel(table_name = customers)]
@@ -14,8 +15,25 @@ pub struct Customer {
     pub email: String,
     pub reseller_id: Option<Uuid>,
     pub api_key: Option<String>,
-    pub created_at: Option<NaiveDateTime>,
-    pub updated_at: Option<NaiveDateTime>,
+    pub created_at: Option<DateTime<Utc>>,
+    pub updated_at: Option<DateTime<Utc>>,
+    /// Sandbox key; jobs submitted with it run in test mode
+    pub test_api_key: Option<String>,
+    /// Customer whose wallet pays for this sub-account's jobs
+    pub parent_id: Option<Uuid>,
+    /// Monthly spending limit of a sub-account
+    pub budget_cents: Option<i32>,
+    /// IANA time zone the customer's days and months follow, e.g. `America/New_York`;
+    /// its reseller's when unset
+    pub timezone: Option<String>,
+    /// Deactivated customers can no longer authenticate
+    pub active: bool,
+    /// Priority level of the customer's jobs submitted without one, e.g. high for premium
+    /// customers; takes precedence over the job type's default
+    pub default_priority: Option<i32>,
+    /// What to do with the customer's jobs whose input contains personal data, see
+    /// [`PiiPolicy`]; inputs are not scanned when unset
+    pub pii_policy: Option<String>,
 }
 
 impl Customer {
@@ -28,6 +46,13 @@ impl Customer {
             api_key: None,
             created_at: None,
             updated_at: None,
+            test_api_key: None,
+            parent_id: None,
+            budget_cents: None,
+            timezone: None,
+            active: true,
+            default_priority: None,
+            pii_policy: None,
         }
     }
     
@@ -40,12 +65,38 @@ impl Customer {
             api_key: None,
             created_at: None,
             updated_at: None,
+            test_api_key: None,
+            parent_id: None,
+            budget_cents: None,
+            timezone: None,
+            active: true,
+            default_priority: None,
+            pii_policy: None,
         }
     }
     
+    /// Typed policy for personal data in the customer's job inputs, if one is set
+    pub fn pii_policy(&self) -> Option<PiiPolicy> {
+        self.pii_policy.as_deref().and_then(PiiPolicy::parse)
+    }
+    
+    /// Whether this customer is a sub-account of another customer
+    pub fn is_sub_account(&self) -> bool {
+        self.parent_id.is_some()
+    }
+    
+    /// The customer whose wallet pays for this customer's jobs
+    pub fn billing_customer_id(&self) -> Uuid {
+        self.parent_id.unwrap_or(self.id)
+    }
+    
     pub fn generate_api_key() -> String {
         format!("cus_{}", Uuid::new_v4().to_string().replace("-", ""))
     }
+    
+    pub fn generate_test_api_key() -> String {
+        format!("cus_test_{}", Uuid::new_v4().to_string().replace("-", ""))
+    }
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize, Insertable, AsChangeset)]
@@ -57,4 +108,6 @@ pub struct NewCustomer {
     pub email: String,
     pub reseller_id: Option<Uuid>,
     pub api_key: Option<String>,
+    pub parent_id: Option<Uuid>,
+    pub budget_cents: Option<i32>,
 }
@@ -0,0 +1,74 @@
+use serde::{Deserialize, Serialize};
+
+/// Kind of dependent that keeps an entity from being deleted, disabled or deactivated
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq, Eq, Hash)]
+#[serde(rename_all = "snake_case")]
+pub enum BlockerKind {
+    /// Jobs of a project, whatever their status
+    Jobs,
+    /// Jobs pending or scheduled that no runner picked up yet
+    QueuedJobs,
+    /// Funds held in reservation for jobs that have not finished
+    ReservedFunds,
+}
+
+impl BlockerKind {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            BlockerKind::Jobs => "jobs",
+            BlockerKind::QueuedJobs => "queued_jobs",
+            BlockerKind::ReservedFunds => "reserved_funds",
+        }
+    }
+
+    /// What the dependents are and what forcing the operation does to them
+    fn describe(&self, count: i64) -> String {
+        match self {
+            BlockerKind::Jobs => format!(
+                "{} jobs belong to the project; forcing keeps them without a project", count
+            ),
+            BlockerKind::QueuedJobs => format!(
+                "{} jobs are queued; forcing cancels them and releases what is reserved for them", count
+            ),
+            BlockerKind::ReservedFunds => format!(
+                "{} cents are reserved for unfinished jobs; forcing releases the reservations of queued jobs, running jobs are billed as usual", count
+            ),
+        }
+    }
+}
+
+/// Dependents blocking a destructive operation unless it is forced
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq, Eq)]
+pub struct Blocker {
+    pub kind: BlockerKind,
+    /// Number of dependents; cents for reserved funds
+    pub count: i64,
+    pub message: String,
+}
+
+impl Blocker {
+    pub fn new(kind: BlockerKind, count: i64) -> Self {
+        Self { kind, count, message: kind.describe(count) }
+    }
+
+    /// Blockers of the given kinds and counts, leaving out those with nothing to block
+    pub fn from_counts(counts: impl IntoIterator<Item = (BlockerKind, i64)>) -> Vec<Self> {
+        counts.into_iter()
+            .filter(|(_, count)| *count > 0)
+            .map(|(kind, count)| Self::new(kind, count))
+            .collect()
+    }
+
+    /// Blockers found in several places, e.g. the schemas of isolated resellers, added
+    /// up by kind in the order each kind is first found
+    pub fn combine(blockers: impl IntoIterator<Item = Blocker>) -> Vec<Self> {
+        let mut totals: Vec<(BlockerKind, i64)> = Vec::new();
+        for blocker in blockers {
+            match totals.iter_mut().find(|(kind, _)| *kind == blocker.kind) {
+                Some((_, count)) => *count += blocker.count,
+                None => totals.push((blocker.kind, blocker.count)),
+            }
+        }
+        Self::from_counts(totals)
+    }
+}
@@ -0,0 +1,106 @@
+use chrono::{DateTime, Utc};
+use serde_json::{json, Value};
+use uuid::Uuid;
+
+use crate::models::job::MAX_SUB_TASKS;
+use crate::models::job_error::{codes, JobError};
+use crate::models::job_type::{JobType, ProcessorType};
+use crate::models::webhook_template::WebhookRequest;
+
+/// Output of the async processor: the `text` field of the input transformed, or a
+/// description of what is wrong with the input
+pub fn async_output(input: &Value) -> Value {
+    match input.get("text") {
+        Some(text) => match text.as_str() {
+            Some(text_str) => json!({
+                "original_text": text_str,
+                "transformed_text": text_str.to_uppercase(),
+                "character_count": text_str.len(),
+                "word_count": text_str.split_whitespace().count()
+            }),
+            None => json!({ "error": "Invalid text format, expected string" }),
+        },
+        None => json!({ "error": "Missing text field in input" }),
+    }
+}
+
+/// URL a webhook job posts to, from its `webhook_url` input field
+pub fn webhook_url(input: &Value) -> Result<&str, JobError> {
+    match input.get("webhook_url") {
+        Some(url_value) => url_value.as_str()
+            .ok_or_else(|| JobError::input(codes::INVALID_INPUT, "webhook_url must be a string")),
+        None => Err(JobError::input(codes::MISSING_FIELD, "webhook_url is required for webhook jobs")),
+    }
+}
+
+/// Request a webhook job of `job_type` with the given input sends at `now`, from the
+/// job type's template or, without one, posting the input
+pub fn webhook_request(job_type: &JobType, input: &Value, job_id: Uuid, now: DateTime<Utc>) -> Result<WebhookRequest, JobError> {
+    let template = job_type.webhook_template()
+        .map_err(|e| JobError::system(
+            codes::INVALID_WEBHOOK_TEMPLATE,
+            format!("Job type {} has an invalid webhook template: {}", job_type.name, e),
+        ).with_retryable(false))?
+        .unwrap_or_default();
+    template.render(input, job_id, now)
+}
+
+/// Inputs of the sub-tasks a batch job fans out into, from its `tasks` input field
+pub fn batch_tasks(input: &Value) -> Result<&[Value], JobError> {
+    let tasks = match input.get("tasks") {
+        Some(Value::Array(tasks)) => tasks,
+        Some(_) => return Err(JobError::input(codes::INVALID_INPUT, "tasks must be an array")),
+        None => return Err(JobError::input(codes::MISSING_FIELD, "tasks is required for batch jobs")),
+    };
+    if tasks.is_empty() || tasks.len() > MAX_SUB_TASKS {
+        return Err(JobError::input(
+            codes::INVALID_INPUT,
+            format!("A batch job takes between 1 and {} tasks, got {}", MAX_SUB_TASKS, tasks.len()),
+        ));
+    }
+    Ok(tasks)
+}
+
+/// Enabled, non-batch job type among `job_types` that the sub-tasks of a batch job type
+/// run, named by its `processing_logic_id` (the ID or name of the job type)
+pub fn sub_task_type<'a>(batch: &JobType, job_types: &'a [JobType]) -> Result<&'a JobType, JobError> {
+    let reference = batch.processing_logic_id.as_str();
+    job_types.iter()
+        .find(|candidate| candidate.id.to_string() == reference || candidate.name == reference)
+        .filter(|candidate| candidate.enabled && !matches!(candidate.processor_type, ProcessorType::Batch))
+        .ok_or_else(|| JobError::system(
+            codes::INVALID_SUB_TASK_TYPE,
+            format!("Job type {} does not name an enabled, non-batch job type for its sub-tasks: {}", batch.name, reference),
+        ).with_retryable(false))
+}
+
+/// Output the processor of `job_type` would produce for job `job_id` with the given
+/// input at `now`, without side effects
+///
+/// Webhook jobs get the request they would send with `"status": "simulated"` in place
+/// of the provider's response, and batch jobs the number of sub-tasks they would fan
+/// out into. Input a processor would refuse fails with the same error as a live run.
+pub fn simulate_output(job_type: &JobType, input: &Value, job_id: Uuid, now: DateTime<Utc>) -> Result<Value, JobError> {
+    match job_type.processor_type {
+        ProcessorType::Sync => Ok(input.clone()),
+        ProcessorType::Async => Ok(async_output(input)),
+        ProcessorType::Webhook => {
+            let webhook_url = webhook_url(input)?;
+            let request = webhook_request(job_type, input, job_id, now)?;
+            Ok(json!({
+                "webhook_url": webhook_url,
+                "method": request.method,
+                // Header values may hold the job type's credentials
+                "headers": request.headers.keys().collect::<Vec<_>>(),
+                "payload": request.body,
+                "status": "simulated"
+            }))
+        }
+        ProcessorType::ExternalApi => Err(JobError::system(codes::PROCESSOR_NOT_IMPLEMENTED, "External API processor not implemented in Phase 1")
+            .with_retryable(false)),
+        ProcessorType::Batch => {
+            let tasks = batch_tasks(input)?;
+            Ok(json!({ "sub_task_count": tasks.len() }))
+        }
+    }
+}
@@ -0,0 +1,81 @@
+use chrono::{DateTime, Utc};
+use diesel::prelude::*;
+use serde::{Deserialize, Serialize};
+use thiserror::Error;
+use uuid::Uuid;
+
+use crate::diesel_schema::feature_flags;
+
+/// Puts the customer and reseller API in read-only mode: only reads are served
+pub const READ_ONLY: &str = "read_only";
+
+/// Stops the submission of new jobs, whether directly, from templates or by pipelines
+pub const JOB_SUBMISSION_DISABLED: &str = "job_submission_disabled";
+
+/// Opens `/admin/fixtures` to provision and tear down bundles of test data; only meant
+/// for staging environments
+pub const FIXTURES: &str = "fixtures";
+
+/// Name of the flag that stops the submission of jobs of a processor type
+pub fn processor_disabled(processor_type: &str) -> String {
+    format!("processor_disabled.{}", processor_type)
+}
+
+/// Errors of feature flag management
+#[derive(Debug, Error)]
+pub enum FeatureFlagError {
+    #[error("Invalid feature flag name {0:?}: use 1 to 100 lowercase letters, digits, '_', '.' or '-'")]
+    InvalidName(String),
+}
+
+/// Check that a flag name is usable in URLs and configuration
+pub fn validate_name(name: &str) -> Result<(), FeatureFlagError> {
+    let valid = !name.is_empty()
+        && name.len() <= 100
+        && name.chars().all(|c| c.is_ascii_lowercase() || c.is_ascii_digit() || matches!(c, '_' | '.' | '-'));
+    if valid {
+        Ok(())
+    } else {
+        Err(FeatureFlagError::InvalidName(name.to_string()))
+    }
+}
+
+/// A capability switched at runtime, for everyone or for the customers of some resellers
+///
+/// Flags that do not exist are off.
+#[derive(Debug, Clone, Serialize, Deserialize, Queryable, Identifiable, Selectable)]
+#[diesel(table_name = feature_flags)]
+#[diesel(check_for_backend(diesel::pg::Pg))]
+pub struct FeatureFlag {
+    pub id: Uuid,
+    pub name: String,
+    /// On for everyone
+    pub enabled: bool,
+    /// On for the customers of these resellers, e.g. to try a feature on a canary
+    pub reseller_ids: Vec<Uuid>,
+    pub description: Option<String>,
+    /// Admin who last changed the flag
+    pub updated_by: String,
+    pub created_at: Option<DateTime<Utc>>,
+    pub updated_at: Option<DateTime<Utc>>,
+}
+
+impl FeatureFlag {
+    /// Whether the flag is on for a request made on behalf of a reseller's customer,
+    /// or of a direct customer when `reseller_id` is None
+    pub fn is_enabled_for(&self, reseller_id: Option<Uuid>) -> bool {
+        self.enabled || reseller_id.is_some_and(|id| self.reseller_ids.contains(&id))
+    }
+}
+
+// For DB insertion with Diesel
+#[derive(Debug, Clone, Serialize, Deserialize, Insertable)]
+#[diesel(table_name = feature_flags)]
+pub struct NewFeatureFlag {
+    pub id: Uuid,
+    pub name: String,
+    pub enabled: bool,
+    pub reseller_ids: Vec<Uuid>,
+    pub description: Option<String>,
+    pub updated_by: String,
+}
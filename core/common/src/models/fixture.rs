@@ -0,0 +1,316 @@
+use std::collections::BTreeMap;
+
+use chrono::{DateTime, Utc};
+use diesel::prelude::*;
+use serde::{Deserialize, Serialize};
+use thiserror::Error;
+use uuid::Uuid;
+
+use crate::diesel_schema::fixture_bundles;
+use crate::models::customer::{Customer, NewCustomer};
+use crate::models::job::{generate_public_id, JobStatus, NewJob, PriorityLevel};
+use crate::models::job_type::{CatalogVisibility, NewJobType, ProcessorType};
+use crate::models::reseller::{NewReseller, Reseller};
+use crate::models::runner::{NewRunner, RunnerStatus};
+use crate::models::wallet::NewWallet;
+
+/// Most customers a bundle may contain
+pub const MAX_FIXTURE_CUSTOMERS: usize = 50;
+/// Most runners a bundle may contain
+pub const MAX_FIXTURE_RUNNERS: usize = 20;
+/// Most jobs a bundle may contain, over all of its customers
+pub const MAX_FIXTURE_JOBS: u32 = 1_000;
+
+/// Reasons a fixture bundle is refused
+#[derive(Debug, Error, PartialEq)]
+pub enum FixtureError {
+    #[error("Invalid bundle name {0:?}: use 1 to 100 lowercase letters, digits, '_', '.' or '-'")]
+    InvalidName(String),
+
+    #[error("A bundle needs at least one customer")]
+    NoCustomers,
+
+    #[error("A bundle may contain at most {MAX_FIXTURE_CUSTOMERS} customers, got {0}")]
+    TooManyCustomers(usize),
+
+    #[error("A bundle may contain at most {MAX_FIXTURE_RUNNERS} runners, got {0}")]
+    TooManyRunners(usize),
+
+    #[error("A bundle may contain at most {MAX_FIXTURE_JOBS} jobs, got {0}")]
+    TooManyJobs(u32),
+
+    #[error("Unknown job status {0:?}")]
+    UnknownJobStatus(String),
+
+    #[error("Unknown runner status {0:?}")]
+    UnknownRunnerStatus(String),
+
+    #[error("Invalid amount for {0}: amounts cannot be negative")]
+    NegativeAmount(String),
+
+    #[error("Fixture bundle {0} cannot be torn down, its customers were invoiced in a closed billing period")]
+    Invoiced(String),
+}
+
+/// Declarative description of a bundle of test data: a reseller, its customers with
+/// their wallets and jobs, and runners
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+pub struct FixtureSpec {
+    /// Name of the bundle, also used to name its records
+    pub name: String,
+    #[serde(default)]
+    pub reseller: ResellerFixture,
+    pub customers: Vec<CustomerFixture>,
+    #[serde(default)]
+    pub runners: Vec<RunnerFixture>,
+    /// Standard cost of the job type created for the bundle's jobs
+    #[serde(default = "default_job_cost_cents")]
+    pub job_cost_cents: i32,
+}
+
+fn default_job_cost_cents() -> i32 {
+    100
+}
+
+/// The reseller of a bundle's customers
+#[derive(Debug, Clone, Default, Serialize, Deserialize, PartialEq)]
+pub struct ResellerFixture {
+    /// Name of the reseller, the bundle's name when omitted
+    #[serde(default)]
+    pub name: Option<String>,
+    #[serde(default)]
+    pub commission_rate_percentage: f64,
+    #[serde(default)]
+    pub markup_rate_percentage: f64,
+}
+
+/// A customer of a bundle, with its wallet and jobs
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+pub struct CustomerFixture {
+    pub name: String,
+    #[serde(default = "default_balance_cents")]
+    pub balance_cents: i32,
+    /// Number of jobs to create in each status, e.g. `{"pending": 3, "failed": 1}`
+    #[serde(default)]
+    pub jobs: BTreeMap<String, u32>,
+}
+
+fn default_balance_cents() -> i32 {
+    10_000
+}
+
+/// A runner of a bundle, able to run the bundle's job type
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+pub struct RunnerFixture {
+    pub name: String,
+    /// `active`, `inactive` or `maintenance` (defaults to `active`)
+    #[serde(default = "default_runner_status")]
+    pub status: String,
+}
+
+fn default_runner_status() -> String {
+    RunnerStatus::Active.as_str().to_string()
+}
+
+impl FixtureSpec {
+    /// Check the spec before anything is created
+    pub fn validate(&self) -> Result<(), FixtureError> {
+        let valid_name = !self.name.is_empty()
+            && self.name.len() <= 100
+            && self.name.chars().all(|c| c.is_ascii_lowercase() || c.is_ascii_digit() || matches!(c, '_' | '.' | '-'));
+        if !valid_name {
+            return Err(FixtureError::InvalidName(self.name.clone()));
+        }
+        if self.customers.is_empty() {
+            return Err(FixtureError::NoCustomers);
+        }
+        if self.customers.len() > MAX_FIXTURE_CUSTOMERS {
+            return Err(FixtureError::TooManyCustomers(self.customers.len()));
+        }
+        if self.runners.len() > MAX_FIXTURE_RUNNERS {
+            return Err(FixtureError::TooManyRunners(self.runners.len()));
+        }
+        if self.job_cost_cents < 0 {
+            return Err(FixtureError::NegativeAmount("job_cost_cents".to_string()));
+        }
+        if self.reseller.commission_rate_percentage < 0.0 || self.reseller.markup_rate_percentage < 0.0 {
+            return Err(FixtureError::NegativeAmount("reseller rates".to_string()));
+        }
+
+        let mut total_jobs: u32 = 0;
+        for customer in &self.customers {
+            if customer.balance_cents < 0 {
+                return Err(FixtureError::NegativeAmount(format!("balance of {}", customer.name)));
+            }
+            for (status, count) in &customer.jobs {
+                if JobStatus::from_str(status).is_none() {
+                    return Err(FixtureError::UnknownJobStatus(status.clone()));
+                }
+                total_jobs = total_jobs.saturating_add(*count);
+            }
+        }
+        if total_jobs > MAX_FIXTURE_JOBS {
+            return Err(FixtureError::TooManyJobs(total_jobs));
+        }
+
+        if let Some(runner) = self.runners.iter().find(|runner| RunnerStatus::from_str(&runner.status).is_none()) {
+            return Err(FixtureError::UnknownRunnerStatus(runner.status.clone()));
+        }
+        Ok(())
+    }
+
+    /// Build the records of the bundle, with fresh IDs and API keys
+    ///
+    /// Emails and names carry the bundle ID, so the same spec can be provisioned any
+    /// number of times side by side.
+    pub fn records(&self, bundle_id: Uuid) -> FixtureRecords {
+        let tag = bundle_id.simple().to_string()[..8].to_string();
+        let email = |local: &str| format!("{}@{}.fixtures.invalid", local, tag);
+
+        let reseller = NewReseller {
+            id: Uuid::new_v4(),
+            name: self.reseller.name.clone().unwrap_or_else(|| self.name.clone()),
+            email: email("reseller"),
+            api_key: Reseller::generate_api_key(),
+            active: true,
+            commission_rate: (self.reseller.commission_rate_percentage * 100.0).round() as i32,
+            markup_rate: (self.reseller.markup_rate_percentage * 100.0).round() as i32,
+            sandbox_of: None,
+            sandbox_expires_at: None,
+        };
+
+        let job_type = NewJobType {
+            id: Uuid::new_v4(),
+            name: format!("{}-{}", self.name, tag),
+            description: Some(format!("Job type of fixture bundle {}", self.name)),
+            processing_logic_id: "fixture".to_string(),
+            processor_type: ProcessorType::Sync.as_str().to_string(),
+            standard_cost_cents: self.job_cost_cents,
+            enabled: true,
+            category: None,
+            icon: None,
+            documentation_url: None,
+            sample_input: None,
+            sample_output: None,
+            visibility: CatalogVisibility::Internal.as_str().to_string(),
+        };
+
+        let mut customers = Vec::new();
+        let mut wallets = Vec::new();
+        let mut jobs = Vec::new();
+        for (index, spec) in self.customers.iter().enumerate() {
+            let customer = NewCustomer {
+                id: Uuid::new_v4(),
+                name: spec.name.clone(),
+                email: email(&format!("customer-{}", index + 1)),
+                reseller_id: Some(reseller.id),
+                api_key: Some(Customer::generate_api_key()),
+                parent_id: None,
+                budget_cents: None,
+            };
+            wallets.push(NewWallet {
+                id: Uuid::new_v4(),
+                customer_id: customer.id,
+                balance_cents: spec.balance_cents,
+            });
+            for (status, count) in &spec.jobs {
+                let status = JobStatus::from_str(status).unwrap_or(JobStatus::Pending);
+                for _ in 0..*count {
+                    jobs.push(NewJob {
+                        id: Uuid::new_v4(),
+                        job_type_id: job_type.id,
+                        customer_id: customer.id,
+                        status: status.as_str().to_string(),
+                        cost_cents: self.job_cost_cents,
+                        test_mode: false,
+                        public_id: generate_public_id(),
+                        expires_at: None,
+                        customer_reference: Some(format!("fixture:{}", self.name)),
+                        customer_metadata: None,
+                        parent_id: None,
+                        sub_task_index: None,
+                        submission_id: None,
+                        requeued_from: None,
+                        input_data: serde_json::json!({}),
+                        priority: PriorityLevel::Medium.as_i32(),
+                    });
+                }
+            }
+            customers.push(customer);
+        }
+
+        let runners = self.runners.iter()
+            .map(|spec| NewRunner {
+                id: Uuid::new_v4(),
+                name: spec.name.clone(),
+                description: Some(format!("Runner of fixture bundle {}", self.name)),
+                status: RunnerStatus::from_str(&spec.status).unwrap_or(RunnerStatus::Active).as_str().to_string(),
+                compatible_job_types: vec![job_type.name.clone()],
+            })
+            .collect();
+
+        FixtureRecords { reseller, job_type, customers, wallets, runners, jobs }
+    }
+}
+
+/// Records provisioned for a bundle
+#[derive(Debug, Clone)]
+pub struct FixtureRecords {
+    pub reseller: NewReseller,
+    pub job_type: NewJobType,
+    pub customers: Vec<NewCustomer>,
+    pub wallets: Vec<NewWallet>,
+    pub runners: Vec<NewRunner>,
+    /// Jobs recorded in their status without being queued, so no runner picks them up
+    pub jobs: Vec<NewJob>,
+}
+
+/// A provisioned bundle of test data, torn down as a whole
+#[derive(Debug, Clone, Serialize, Deserialize, Queryable, Identifiable, Selectable)]
+#[diesel(table_name = fixture_bundles)]
+#[diesel(check_for_backend(diesel::pg::Pg))]
+pub struct FixtureBundle {
+    pub id: Uuid,
+    pub name: String,
+    /// Spec the bundle was provisioned from
+    pub spec: serde_json::Value,
+    pub reseller_id: Uuid,
+    pub job_type_id: Uuid,
+    pub customer_ids: Vec<Uuid>,
+    pub runner_ids: Vec<Uuid>,
+    pub job_count: i32,
+    /// Admin who provisioned the bundle
+    pub created_by: String,
+    pub created_at: Option<DateTime<Utc>>,
+}
+
+// For DB insertion with Diesel
+#[derive(Debug, Clone, Serialize, Deserialize, Insertable)]
+#[diesel(table_name = fixture_bundles)]
+pub struct NewFixtureBundle {
+    pub id: Uuid,
+    pub name: String,
+    pub spec: serde_json::Value,
+    pub reseller_id: Uuid,
+    pub job_type_id: Uuid,
+    pub customer_ids: Vec<Uuid>,
+    pub runner_ids: Vec<Uuid>,
+    pub job_count: i32,
+    pub created_by: String,
+}
+
+impl NewFixtureBundle {
+    pub fn new(id: Uuid, spec: &FixtureSpec, records: &FixtureRecords, created_by: String) -> Self {
+        Self {
+            id,
+            name: spec.name.clone(),
+            spec: serde_json::to_value(spec).unwrap_or_default(),
+            reseller_id: records.reseller.id,
+            job_type_id: records.job_type.id,
+            customer_ids: records.customers.iter().map(|customer| customer.id).collect(),
+            runner_ids: records.runners.iter().map(|runner| runner.id).collect(),
+            job_count: records.jobs.len() as i32,
+            created_by,
+        }
+    }
+}
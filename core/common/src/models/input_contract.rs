@@ -0,0 +1,139 @@
+use serde::Serialize;
+use serde_json::Value;
+
+use crate::models::job::MAX_SUB_TASKS;
+use crate::models::job_error::{codes, JobError};
+use crate::models::job_type::ProcessorType;
+
+/// JSON type an input field must have
+#[derive(Debug, Clone, Copy, Serialize, PartialEq, Eq)]
+#[serde(rename_all = "lowercase")]
+pub enum InputFieldType {
+    String,
+    /// String holding an absolute http(s) URL
+    Url,
+    Array,
+    Object,
+}
+
+impl InputFieldType {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            InputFieldType::String => "string",
+            InputFieldType::Url => "url",
+            InputFieldType::Array => "array",
+            InputFieldType::Object => "object",
+        }
+    }
+
+    /// Whether `value` has this type; URLs are only checked for being strings, as the
+    /// processors do
+    pub fn matches(&self, value: &Value) -> bool {
+        match self {
+            InputFieldType::String | InputFieldType::Url => value.is_string(),
+            InputFieldType::Array => value.is_array(),
+            InputFieldType::Object => value.is_object(),
+        }
+    }
+}
+
+/// Top-level field of a job's `input_data` a processor reads
+#[derive(Debug, Clone, Serialize, PartialEq, Eq)]
+pub struct InputField {
+    pub name: &'static str,
+    #[serde(rename = "type")]
+    pub field_type: InputFieldType,
+    /// Jobs without the field fail instead of running
+    pub required: bool,
+    pub description: String,
+}
+
+/// Input fields a processor expects, so submitters can find out what a job type needs
+/// before a job fails on it
+#[derive(Debug, Clone, Serialize, PartialEq, Eq)]
+pub struct InputContract {
+    /// `sync`, `async`, `external_api`, `batch` or `webhook`
+    pub processor_type: &'static str,
+    /// What the processor does with its input as a whole
+    pub description: &'static str,
+    pub fields: Vec<InputField>,
+}
+
+impl InputContract {
+    /// Contract of the built-in processor of the given type
+    pub fn of(processor_type: &ProcessorType) -> Self {
+        let processor_type_name = processor_type.as_str();
+        match processor_type {
+            ProcessorType::Sync => Self {
+                processor_type: processor_type_name,
+                description: "Any JSON object, returned unchanged as the output",
+                fields: Vec::new(),
+            },
+            ProcessorType::Async => Self {
+                processor_type: processor_type_name,
+                description: "Text transformed to upper case, with its character and word counts",
+                fields: vec![InputField {
+                    name: "text",
+                    field_type: InputFieldType::String,
+                    required: false,
+                    description: "Text to transform; without it the output describes what is missing".to_string(),
+                }],
+            },
+            ProcessorType::Webhook => Self {
+                processor_type: processor_type_name,
+                description: "A JSON payload POSTed to the given URL, with the response as the output",
+                fields: vec![InputField {
+                    name: "webhook_url",
+                    field_type: InputFieldType::Url,
+                    required: true,
+                    description: "URL the payload is POSTed to".to_string(),
+                }],
+            },
+            ProcessorType::Batch => Self {
+                processor_type: processor_type_name,
+                description: "Inputs fanned out into one sub-task each, billed like jobs of their own",
+                fields: vec![InputField {
+                    name: "tasks",
+                    field_type: InputFieldType::Array,
+                    required: true,
+                    description: format!("Input of each sub-task, 1 to {} of them", MAX_SUB_TASKS),
+                }],
+            },
+            ProcessorType::ExternalApi => Self {
+                processor_type: processor_type_name,
+                description: "Not available yet; jobs of this type fail",
+                fields: Vec::new(),
+            },
+        }
+    }
+
+    /// Check that `input` has every required field with its type, failing with the
+    /// error the processor would fail the job with
+    ///
+    /// Optional fields are left to the processor.
+    pub fn check(&self, input: &Value) -> Result<(), JobError> {
+        for field in self.fields.iter().filter(|field| field.required) {
+            match input.get(field.name) {
+                Some(value) if field.field_type.matches(value) => {}
+                Some(_) => {
+                    let expected = match field.field_type {
+                        InputFieldType::Array | InputFieldType::Object => "an",
+                        InputFieldType::String | InputFieldType::Url => "a",
+                    };
+                    let type_name = match field.field_type {
+                        InputFieldType::Url => InputFieldType::String.as_str(),
+                        field_type => field_type.as_str(),
+                    };
+                    return Err(JobError::input(codes::INVALID_INPUT, format!("{} must be {} {}", field.name, expected, type_name)));
+                }
+                None => {
+                    return Err(JobError::input(
+                        codes::MISSING_FIELD,
+                        format!("{} is required for {} jobs", field.name, self.processor_type),
+                    ));
+                }
+            }
+        }
+        Ok(())
+    }
+}
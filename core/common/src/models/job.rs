@@ -1,10 +1,14 @@
 use serde::{Deserialize, Serialize};
 use uuid::Uuid;
-use chrono::NaiveDateTime;
+use chrono::{DateTime, Utc};
 use diesel::prelude::*;
 use diesel::pg::Pg;
 use diesel::sql_types::Text;
 use diesel::serialize::{self, Output, ToSql};
+use rand::Rng;
+
+use super::job_cost::CostBreakdown;
+use super::job_error::JobError;
 
 #[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
 pub enum JobStatus {
@@ -14,6 +18,8 @@ pub enum JobStatus {
     Failed,
     Cancelled,
     Scheduled,
+    /// Not started before its `expires_at` deadline, so never run
+    Expired,
 }
 
 // Implement Queryable for JobStatus
@@ -42,6 +48,7 @@ impl ToSql<Text, Pg> for JobStatus {
             JobStatus::Failed => ToSql::<Text, Pg>::to_sql("failed", out),
             JobStatus::Cancelled => ToSql::<Text, Pg>::to_sql("cancelled", out),
             JobStatus::Scheduled => ToSql::<Text, Pg>::to_sql("scheduled", out),
+            JobStatus::Expired => ToSql::<Text, Pg>::to_sql("expired", out),
         }
     }
 }
@@ -55,6 +62,7 @@ impl JobStatus {
             JobStatus::Failed => "failed",
             JobStatus::Cancelled => "cancelled",
             JobStatus::Scheduled => "scheduled",
+            JobStatus::Expired => "expired",
         }
     }
     
@@ -66,9 +74,29 @@ impl JobStatus {
             "failed" => Some(JobStatus::Failed),
             "cancelled" => Some(JobStatus::Cancelled),
             "scheduled" => Some(JobStatus::Scheduled),
+            "expired" => Some(JobStatus::Expired),
             _ => None,
         }
     }
+
+    /// Whether the job has finished and its status can no longer change
+    pub fn is_terminal(&self) -> bool {
+        matches!(self, JobStatus::Succeeded | JobStatus::Failed | JobStatus::Cancelled | JobStatus::Expired)
+    }
+
+    /// Whether a job in this status may move to `next`
+    ///
+    /// Running jobs may go back to pending when their runner is lost, and pending jobs
+    /// may be completed directly by a runner reporting a result. Only jobs that have not
+    /// started can expire.
+    pub fn can_transition_to(&self, next: &JobStatus) -> bool {
+        match self {
+            JobStatus::Pending => matches!(next, JobStatus::Running | JobStatus::Scheduled | JobStatus::Succeeded | JobStatus::Failed | JobStatus::Cancelled | JobStatus::Expired),
+            JobStatus::Scheduled => matches!(next, JobStatus::Pending | JobStatus::Running | JobStatus::Cancelled | JobStatus::Expired),
+            JobStatus::Running => matches!(next, JobStatus::Pending | JobStatus::Succeeded | JobStatus::Failed | JobStatus::Cancelled),
+            JobStatus::Succeeded | JobStatus::Failed | JobStatus::Cancelled | JobStatus::Expired => false,
+        }
+    }
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize, PartialEq, Eq, PartialOrd, Ord)]
@@ -100,6 +128,56 @@ impl PriorityLevel {
             _ => PriorityLevel::Low, // Default to Low for unknown values
         }
     }
+    
+    /// Name of the priority as the API gives it, e.g. `high`
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            PriorityLevel::Low => "low",
+            PriorityLevel::Medium => "medium",
+            PriorityLevel::High => "high",
+            PriorityLevel::Critical => "critical",
+        }
+    }
+    
+    /// Priority of the given name, in any case; None for unknown names
+    pub fn from_name(name: &str) -> Option<Self> {
+        match name.to_lowercase().as_str() {
+            "low" => Some(PriorityLevel::Low),
+            "medium" => Some(PriorityLevel::Medium),
+            "high" => Some(PriorityLevel::High),
+            "critical" => Some(PriorityLevel::Critical),
+            _ => None,
+        }
+    }
+    
+    /// Resolve the priority of a submitted job from the one requested and the default
+    /// levels of its customer and job type, in that order, falling back to medium
+    pub fn resolve(
+        requested: Option<PriorityLevel>,
+        customer_default: Option<i32>,
+        job_type_default: Option<i32>,
+    ) -> (PriorityLevel, PrioritySource) {
+        match (requested, customer_default, job_type_default) {
+            (Some(priority), _, _) => (priority, PrioritySource::Request),
+            (None, Some(level), _) => (PriorityLevel::from_i32(level), PrioritySource::Customer),
+            (None, None, Some(level)) => (PriorityLevel::from_i32(level), PrioritySource::JobType),
+            (None, None, None) => (PriorityLevel::Medium, PrioritySource::Default),
+        }
+    }
+}
+
+/// Where the priority of a submitted job came from, see [`PriorityLevel::resolve`]
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+pub enum PrioritySource {
+    /// Given with the request
+    Request,
+    /// The customer's default priority
+    Customer,
+    /// The job type's default priority
+    JobType,
+    /// Neither the request nor a default set one, so medium
+    Default,
 }
 
 // Database representation of a Job
@@ -112,9 +190,34 @@ pub struct JobDb {
     pub customer_id: Uuid,
     pub status: String,  // Store as String in DB representation
     pub cost_cents: i32,
-    pub created_at: Option<NaiveDateTime>,
-    pub updated_at: Option<NaiveDateTime>,
-    pub completed_at: Option<NaiveDateTime>,
+    pub created_at: Option<DateTime<Utc>>,
+    pub updated_at: Option<DateTime<Utc>>,
+    pub completed_at: Option<DateTime<Utc>>,
+    pub test_mode: bool,
+    pub runner_id: Option<Uuid>,
+    pub claimed_at: Option<DateTime<Utc>>,
+    pub queue_priority: Option<i32>,
+    pub scheduled_for: Option<DateTime<Utc>>,
+    pub lease_expires_at: Option<DateTime<Utc>>,
+    pub public_id: String,
+    pub expires_at: Option<DateTime<Utc>>,
+    pub customer_reference: Option<String>,
+    pub customer_metadata: Option<serde_json::Value>,
+    pub parent_id: Option<Uuid>,
+    pub sub_task_index: Option<i32>,
+    pub sub_task_count: Option<i32>,
+    pub cost_breakdown: Option<serde_json::Value>,
+    pub submission_id: Option<Uuid>,
+    pub cancel_requested_at: Option<DateTime<Utc>>,
+    pub requeued_from: Option<Uuid>,
+    pub queued_at: Option<DateTime<Utc>>,
+    pub started_at: Option<DateTime<Utc>>,
+    pub input_data: serde_json::Value,
+    pub output_data: Option<serde_json::Value>,
+    /// Structured error of a failed job, as a serialized `JobError`
+    pub error: Option<serde_json::Value>,
+    /// Priority level the job was submitted with
+    pub priority: i32,
 }
 
 // Full Job model with all fields used in application logic
@@ -127,12 +230,50 @@ pub struct Job {
     pub priority: PriorityLevel,
     pub input_data: serde_json::Value,
     pub output_data: Option<serde_json::Value>,
-    pub error: Option<String>,
+    pub error: Option<JobError>,
     pub estimated_cost_cents: i32,
     pub cost_cents: i32,
-    pub created_at: Option<NaiveDateTime>,
-    pub updated_at: Option<NaiveDateTime>,
-    pub completed_at: Option<NaiveDateTime>,
+    pub created_at: Option<DateTime<Utc>>,
+    pub updated_at: Option<DateTime<Utc>>,
+    pub completed_at: Option<DateTime<Utc>>,
+    /// Submitted with a test key: runs through the stub processor and is billed against the test balance
+    pub test_mode: bool,
+    /// Runner that picked up the job, if it identified itself
+    pub runner_id: Option<Uuid>,
+    /// When a runner took the job off the queue
+    pub claimed_at: Option<DateTime<Utc>>,
+    /// When the job is released to the queue, if it was submitted outside its submission window
+    pub scheduled_for: Option<DateTime<Utc>>,
+    /// Until when the claiming runner holds the job in its prefetch buffer; cleared once started
+    pub lease_expires_at: Option<DateTime<Utc>>,
+    /// Short identifier shown to customers, e.g. `job_8f3kq2wx1m`; accepted wherever the UUID is
+    pub public_id: String,
+    /// Deadline for starting the job: if it has not started by then it expires instead of running
+    pub expires_at: Option<DateTime<Utc>>,
+    /// Customer's own reference, e.g. a purchase order number, copied onto the job's wallet transactions
+    pub customer_reference: Option<String>,
+    /// Customer's own metadata given with the reference, copied along with it
+    pub customer_metadata: Option<serde_json::Value>,
+    /// Job this one is a sub-task of, if it was fanned out from a batch job
+    pub parent_id: Option<Uuid>,
+    /// Position of the sub-task among its parent's sub-tasks, from 0
+    pub sub_task_index: Option<i32>,
+    /// Number of sub-tasks the job fanned out into; it finishes once all of them did
+    pub sub_task_count: Option<i32>,
+    /// Itemized cost, once the job was charged
+    pub cost_breakdown: Option<CostBreakdown>,
+    /// Batch or file import the job was submitted with, if any
+    pub submission_id: Option<Uuid>,
+    /// When cancelling the job was requested while it was running; the runner processing
+    /// it stops and the job ends up cancelled
+    pub cancel_requested_at: Option<DateTime<Utc>>,
+    /// Failed or expired job this one was requeued from by an admin
+    pub requeued_from: Option<Uuid>,
+    /// When the job entered the queue: when it was submitted, released from the schedule
+    /// or handed back by an unresponsive runner
+    pub queued_at: Option<DateTime<Utc>>,
+    /// When a runner started processing the job
+    pub started_at: Option<DateTime<Utc>>,
 }
 
 // Conversion from database model to application model
@@ -143,15 +284,34 @@ impl From<JobDb> for Job {
             customer_id: db_job.customer_id,
             job_type_id: db_job.job_type_id,
             status: JobStatus::from_str(&db_job.status).unwrap_or(JobStatus::Pending),
-            priority: PriorityLevel::Medium, // Default value since not stored in DB
-            input_data: serde_json::Value::Null, // Default value since not stored in DB
-            output_data: None,
-            error: None,
+            // Jobs are priced at the level they were claimed at, once claimed
+            priority: PriorityLevel::from_i32(db_job.queue_priority.unwrap_or(db_job.priority)),
+            input_data: db_job.input_data,
+            output_data: db_job.output_data,
+            error: db_job.error.and_then(|error| serde_json::from_value(error).ok()),
             estimated_cost_cents: db_job.cost_cents, // Use cost_cents as estimate
             cost_cents: db_job.cost_cents,
             created_at: db_job.created_at,
             updated_at: db_job.updated_at,
             completed_at: db_job.completed_at,
+            test_mode: db_job.test_mode,
+            runner_id: db_job.runner_id,
+            claimed_at: db_job.claimed_at,
+            scheduled_for: db_job.scheduled_for,
+            lease_expires_at: db_job.lease_expires_at,
+            public_id: db_job.public_id,
+            expires_at: db_job.expires_at,
+            customer_reference: db_job.customer_reference,
+            customer_metadata: db_job.customer_metadata,
+            parent_id: db_job.parent_id,
+            sub_task_index: db_job.sub_task_index,
+            sub_task_count: db_job.sub_task_count,
+            cost_breakdown: db_job.cost_breakdown.and_then(|breakdown| serde_json::from_value(breakdown).ok()),
+            submission_id: db_job.submission_id,
+            cancel_requested_at: db_job.cancel_requested_at,
+            requeued_from: db_job.requeued_from,
+            queued_at: db_job.queued_at,
+            started_at: db_job.started_at,
         }
     }
 }
@@ -175,11 +335,227 @@ impl Job {
             error: None,
             estimated_cost_cents,
             cost_cents: estimated_cost_cents,  // Initialize with estimated cost
-            created_at: Some(chrono::Utc::now().naive_utc()),
+            created_at: Some(chrono::Utc::now()),
             updated_at: None,
             completed_at: None,
+            test_mode: false,
+            runner_id: None,
+            claimed_at: None,
+            scheduled_for: None,
+            lease_expires_at: None,
+            public_id: generate_public_id(),
+            expires_at: None,
+            customer_reference: None,
+            customer_metadata: None,
+            parent_id: None,
+            sub_task_index: None,
+            sub_task_count: None,
+            cost_breakdown: None,
+            submission_id: None,
+            cancel_requested_at: None,
+            requeued_from: None,
+            queued_at: None,
+            started_at: None,
         }
     }
+
+    /// Time the job waited in the queue until a runner claimed it, in milliseconds
+    ///
+    /// Scheduled jobs are not claimed from a priority queue, so their wait ends when
+    /// they are started.
+    pub fn queue_wait_ms(&self) -> Option<i64> {
+        let queued_at = self.queued_at?;
+        let claimed_at = self.claimed_at.filter(|claimed_at| *claimed_at >= queued_at).or(self.started_at)?;
+        Some((claimed_at - queued_at).num_milliseconds().max(0))
+    }
+
+    /// Time from the start of the job until it finished, in milliseconds
+    pub fn processing_ms(&self) -> Option<i64> {
+        let (started_at, completed_at) = (self.started_at?, self.completed_at?);
+        Some((completed_at - started_at).num_milliseconds().max(0))
+    }
+
+    /// Whether the runner processing the job can be asked to cancel it: it is running and
+    /// not waiting for its sub-tasks
+    pub fn can_request_cancellation(&self) -> bool {
+        self.status == JobStatus::Running && self.sub_task_count.is_none()
+    }
+
+    /// Sub-task `index` of this job, running `input_data` through the given job type
+    ///
+    /// The sub-task is billed like its parent: to the same customer, in the same mode and
+    /// with the same reference, and it must start by the parent's deadline.
+    pub fn sub_task(&self, index: i32, job_type_id: Uuid, input_data: serde_json::Value, estimated_cost_cents: i32) -> Self {
+        let mut sub_task = Job::new(self.customer_id, job_type_id, input_data, self.priority.clone(), estimated_cost_cents);
+        sub_task.test_mode = self.test_mode;
+        sub_task.expires_at = self.expires_at;
+        sub_task.customer_reference = self.customer_reference.clone();
+        sub_task.customer_metadata = self.customer_metadata.clone();
+        sub_task.parent_id = Some(self.id);
+        sub_task.sub_task_index = Some(index);
+        sub_task
+    }
+
+    /// Whether an admin may requeue the job: it failed or expired and is not a sub-task,
+    /// which its batch job accounts for
+    pub fn can_requeue(&self) -> bool {
+        matches!(self.status, JobStatus::Failed | JobStatus::Expired) && self.parent_id.is_none()
+    }
+
+    /// New job running this one again, with `input_overrides` merged into its input
+    ///
+    /// Overrides replace the input fields they name, `null` removing a field, as a JSON
+    /// merge patch (RFC 7396) would. The new job keeps the customer, type, priority, mode
+    /// and reference of this one but no deadline, and refers back to it.
+    pub fn requeue(&self, input_overrides: &serde_json::Map<String, serde_json::Value>, estimated_cost_cents: i32) -> Self {
+        let mut input_data = self.input_data.clone();
+        merge_patch(&mut input_data, &serde_json::Value::Object(input_overrides.clone()));
+
+        let mut requeued = Job::new(self.customer_id, self.job_type_id, input_data, self.priority.clone(), estimated_cost_cents);
+        requeued.test_mode = self.test_mode;
+        requeued.customer_reference = self.customer_reference.clone();
+        requeued.customer_metadata = self.customer_metadata.clone();
+        requeued.requeued_from = Some(self.id);
+        requeued
+    }
+}
+
+/// Apply a JSON merge patch (RFC 7396) to a value
+pub fn merge_patch(target: &mut serde_json::Value, patch: &serde_json::Value) {
+    let serde_json::Value::Object(fields) = patch else {
+        *target = patch.clone();
+        return;
+    };
+    if !target.is_object() {
+        *target = serde_json::Value::Object(serde_json::Map::new());
+    }
+    if let serde_json::Value::Object(target_fields) = target {
+        for (key, value) in fields {
+            if value.is_null() {
+                target_fields.remove(key);
+            } else {
+                merge_patch(target_fields.entry(key.clone()).or_insert(serde_json::Value::Null), value);
+            }
+        }
+    }
+}
+
+/// Most sub-tasks a single job may fan out into
+pub const MAX_SUB_TASKS: usize = 1000;
+
+/// Outcome of a job that fanned out, derived from its sub-tasks once all of them finished
+#[derive(Debug, Clone, PartialEq)]
+pub struct FanIn {
+    /// Succeeded if every sub-task succeeded, failed otherwise
+    pub status: JobStatus,
+    /// What the sub-tasks that ran cost together; cancelled and expired ones never ran
+    pub cost_cents: i32,
+    /// Itemized cost of the sub-tasks that were charged, if any was
+    pub cost_breakdown: Option<CostBreakdown>,
+    pub succeeded: usize,
+    /// Sub-tasks that failed, were cancelled or expired
+    pub failed: usize,
+}
+
+/// Outcome of a job from its sub-tasks; None while any of them has not finished
+pub fn fan_in(sub_tasks: &[Job]) -> Option<FanIn> {
+    if sub_tasks.iter().any(|sub_task| !sub_task.status.is_terminal()) {
+        return None;
+    }
+
+    let succeeded = sub_tasks.iter().filter(|sub_task| sub_task.status == JobStatus::Succeeded).count();
+    let failed = sub_tasks.len() - succeeded;
+    let charged: Vec<CostBreakdown> = sub_tasks.iter().filter_map(|sub_task| sub_task.cost_breakdown).collect();
+    Some(FanIn {
+        status: if failed == 0 { JobStatus::Succeeded } else { JobStatus::Failed },
+        cost_cents: sub_tasks.iter()
+            .filter(|sub_task| matches!(sub_task.status, JobStatus::Succeeded | JobStatus::Failed))
+            .map(|sub_task| sub_task.cost_cents)
+            .sum(),
+        cost_breakdown: (!charged.is_empty()).then(|| charged.into_iter().sum()),
+        succeeded,
+        failed,
+    })
+}
+
+/// Runner reporting the result of a job, with the attempt it opened when it started it
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct JobHolder {
+    pub runner_id: Option<Uuid>,
+    /// Attempt the runner opened, if it could be recorded
+    pub attempt_id: Option<Uuid>,
+}
+
+/// Result a runner reports for a job it started, as stored by `complete_held_many`
+#[derive(Debug, Clone, PartialEq)]
+pub struct HeldCompletion {
+    pub job_id: Uuid,
+    pub holder: JobHolder,
+    pub success: bool,
+    pub output: Option<serde_json::Value>,
+    pub error: Option<JobError>,
+    pub cost_cents: i32,
+}
+
+/// What becomes of a runner's result for a job, given where the job stands now
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum CompletionResolution {
+    /// The runner still holds the job; the result is stored
+    Held,
+    /// The job was handed back while the runner was unresponsive but nobody started it
+    /// again, so the late result is stored instead of running the job twice
+    Reclaimed,
+    /// Another runner started the job again, or it finished otherwise; the late result
+    /// is discarded so the job is not completed twice
+    Discarded,
+}
+
+impl CompletionResolution {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            CompletionResolution::Held => "held",
+            CompletionResolution::Reclaimed => "reclaimed",
+            CompletionResolution::Discarded => "discarded",
+        }
+    }
+}
+
+/// Resolve a runner's result against the job's status, whether the holder's attempt is
+/// still running (None without an attempt) and the runner the job is attributed to
+///
+/// The attempt decides whether the runner still holds a running job, as handing a job
+/// back abandons it; without one the job's runner has to match.
+pub fn resolve_completion(
+    status: &JobStatus,
+    attempt_running: Option<bool>,
+    job_runner_id: Option<Uuid>,
+    holder: &JobHolder,
+) -> CompletionResolution {
+    match status {
+        JobStatus::Running => {
+            let held = attempt_running.unwrap_or_else(|| {
+                holder.runner_id.is_none() || job_runner_id.is_none() || job_runner_id == holder.runner_id
+            });
+            if held { CompletionResolution::Held } else { CompletionResolution::Discarded }
+        }
+        JobStatus::Pending => CompletionResolution::Reclaimed,
+        JobStatus::Scheduled | JobStatus::Succeeded | JobStatus::Failed | JobStatus::Cancelled | JobStatus::Expired => {
+            CompletionResolution::Discarded
+        }
+    }
+}
+
+/// Prefix of job public IDs
+pub const PUBLIC_ID_PREFIX: &str = "job_";
+
+/// Generate a random public ID: the prefix followed by 10 lowercase letters and digits
+pub fn generate_public_id() -> String {
+    const CHARSET: &[u8] = b"abcdefghijklmnopqrstuvwxyz0123456789";
+    let mut rng = rand::rng();
+    let suffix: String = (0..10)
+        .map(|_| CHARSET[rng.random_range(0..CHARSET.len())] as char)
+        .collect();
+    format!("{}{}", PUBLIC_ID_PREFIX, suffix)
 }
 
 // For DB insertion with Diesel
@@ -191,6 +567,17 @@ pub struct NewJob {
     pub customer_id: Uuid,
     pub status: String,
     pub cost_cents: i32,
+    pub test_mode: bool,
+    pub public_id: String,
+    pub expires_at: Option<DateTime<Utc>>,
+    pub customer_reference: Option<String>,
+    pub customer_metadata: Option<serde_json::Value>,
+    pub parent_id: Option<Uuid>,
+    pub sub_task_index: Option<i32>,
+    pub submission_id: Option<Uuid>,
+    pub requeued_from: Option<Uuid>,
+    pub input_data: serde_json::Value,
+    pub priority: i32,
 }
 
 // Conversion from application model to database insert model
@@ -202,6 +589,17 @@ impl From<Job> for NewJob {
             customer_id: job.customer_id,
             status: job.status.as_str().to_string(),
             cost_cents: job.cost_cents,
+            test_mode: job.test_mode,
+            public_id: job.public_id,
+            expires_at: job.expires_at,
+            customer_reference: job.customer_reference,
+            customer_metadata: job.customer_metadata,
+            parent_id: job.parent_id,
+            sub_task_index: job.sub_task_index,
+            submission_id: job.submission_id,
+            requeued_from: job.requeued_from,
+            input_data: job.input_data,
+            priority: job.priority.as_i32(),
         }
     }
 }
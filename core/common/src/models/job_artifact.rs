@@ -0,0 +1,43 @@
+use chrono::{DateTime, Utc};
+use diesel::prelude::*;
+use serde::{Deserialize, Serialize};
+use uuid::Uuid;
+
+use crate::diesel_schema::job_artifacts;
+
+/// Binary response body of a job, kept apart from its output
+#[derive(Debug, Clone, Serialize, Deserialize, Queryable, Identifiable, Selectable)]
+#[diesel(table_name = job_artifacts)]
+#[diesel(check_for_backend(diesel::pg::Pg))]
+pub struct JobArtifact {
+    pub id: Uuid,
+    pub job_id: Uuid,
+    pub content_type: String,
+    pub size_bytes: i64,
+    #[serde(skip)]
+    pub content: Vec<u8>,
+    pub created_at: DateTime<Utc>,
+}
+
+// For DB insertion with Diesel
+#[derive(Debug, Clone, Serialize, Deserialize, Insertable)]
+#[diesel(table_name = job_artifacts)]
+pub struct NewJobArtifact {
+    pub id: Uuid,
+    pub job_id: Uuid,
+    pub content_type: String,
+    pub size_bytes: i64,
+    pub content: Vec<u8>,
+}
+
+impl NewJobArtifact {
+    pub fn new(job_id: Uuid, content_type: String, content: Vec<u8>) -> Self {
+        Self {
+            id: Uuid::new_v4(),
+            job_id,
+            content_type,
+            size_bytes: content.len() as i64,
+            content,
+        }
+    }
+}
@@ -0,0 +1,116 @@
+use chrono::{DateTime, Utc};
+use diesel::prelude::*;
+use serde::{Deserialize, Serialize};
+use uuid::Uuid;
+
+use crate::diesel_schema::job_attempts;
+use crate::models::job_error::JobError;
+
+/// State of a single execution of a job
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+pub enum AttemptStatus {
+    /// The runner is executing the job
+    Running,
+    Succeeded,
+    Failed,
+    /// The runner stopped reporting before the attempt finished and the job was handed
+    /// to another runner
+    Abandoned,
+}
+
+impl AttemptStatus {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            AttemptStatus::Running => "running",
+            AttemptStatus::Succeeded => "succeeded",
+            AttemptStatus::Failed => "failed",
+            AttemptStatus::Abandoned => "abandoned",
+        }
+    }
+
+    pub fn parse(s: &str) -> Option<Self> {
+        match s.to_lowercase().as_str() {
+            "running" => Some(AttemptStatus::Running),
+            "succeeded" => Some(AttemptStatus::Succeeded),
+            "failed" => Some(AttemptStatus::Failed),
+            "abandoned" => Some(AttemptStatus::Abandoned),
+            _ => None,
+        }
+    }
+}
+
+/// One execution of a job by a runner
+#[derive(Debug, Clone, Serialize, Deserialize, Queryable, Identifiable, Selectable)]
+#[diesel(table_name = job_attempts)]
+#[diesel(check_for_backend(diesel::pg::Pg))]
+pub struct JobAttempt {
+    pub id: Uuid,
+    pub job_id: Uuid,
+    /// Position of the attempt among the job's attempts, starting at 1
+    pub attempt_number: i32,
+    pub runner_id: Option<Uuid>,
+    pub status: String,
+    /// Structured error of a failed attempt, as a serialized `JobError`
+    pub error: Option<serde_json::Value>,
+    pub cost_cents: i32,
+    pub started_at: DateTime<Utc>,
+    pub finished_at: Option<DateTime<Utc>>,
+    /// Crash of the runner while it ran the attempt, as a serialized `CrashReport`
+    pub crash: Option<serde_json::Value>,
+}
+
+impl JobAttempt {
+    /// Typed status of the attempt
+    pub fn attempt_status(&self) -> Option<AttemptStatus> {
+        AttemptStatus::parse(&self.status)
+    }
+
+    /// Error the attempt failed with, if any
+    pub fn job_error(&self) -> Option<JobError> {
+        self.error.clone().and_then(|error| serde_json::from_value(error).ok())
+    }
+
+    /// Crash the runner reported for the attempt, if it went down while running it
+    pub fn crash_report(&self) -> Option<CrashReport> {
+        self.crash.clone().and_then(|crash| serde_json::from_value(crash).ok())
+    }
+
+    /// Time from the start to the end of a finished attempt, in milliseconds
+    pub fn duration_ms(&self) -> Option<i64> {
+        self.finished_at.map(|finished_at| (finished_at - self.started_at).num_milliseconds().max(0))
+    }
+}
+
+// For DB insertion with Diesel
+#[derive(Debug, Clone, Serialize, Deserialize, Insertable)]
+#[diesel(table_name = job_attempts)]
+pub struct NewJobAttempt {
+    pub id: Uuid,
+    pub job_id: Uuid,
+    pub attempt_number: i32,
+    pub runner_id: Option<Uuid>,
+    pub status: String,
+    pub started_at: DateTime<Utc>,
+}
+
+/// How an attempt ended, as reported by its runner
+#[derive(Debug, Clone)]
+pub struct AttemptOutcome {
+    pub success: bool,
+    pub error: Option<JobError>,
+    pub cost_cents: i32,
+    /// When the runner finished the job, which precedes the write if the result was buffered
+    pub finished_at: DateTime<Utc>,
+}
+
+/// Crash of a runner that went down while running an attempt, reported when it restarts
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct CrashReport {
+    pub runner_id: Option<Uuid>,
+    /// When the runner started the attempt it did not finish
+    pub started_at: DateTime<Utc>,
+    /// Message and location of the panic the runner went down with, if one was captured
+    pub panic_message: Option<String>,
+    pub reported_at: DateTime<Utc>,
+}
@@ -0,0 +1,113 @@
+use serde::{Deserialize, Serialize};
+
+use crate::models::job::PriorityLevel;
+
+/// Share of a failed job's estimated cost charged as failure fee, in basis points
+pub const FAILURE_FEE_RATE: i32 = 2500;
+
+/// Surcharge on the base cost of jobs of a priority, in basis points
+pub fn priority_surcharge_rate(priority: &PriorityLevel) -> i32 {
+    match priority {
+        PriorityLevel::Low | PriorityLevel::Medium => 0,
+        PriorityLevel::High => 5000,      // 50% premium
+        PriorityLevel::Critical => 10000, // 100% premium
+    }
+}
+
+/// `rate` basis points of an amount, rounded half up
+pub(crate) fn share(amount_cents: i64, rate: i32) -> i64 {
+    (amount_cents * rate as i64 + 5000).div_euclid(10000)
+}
+
+/// Itemized cost of a job: what it was charged and why
+///
+/// Amounts are in cents. The items add up to the total: base cost, priority surcharge,
+/// reseller markup and failure fee, less the promotional credit applied. The same shape
+/// totals the jobs of an invoice.
+#[derive(Debug, Clone, Copy, Default, Serialize, Deserialize, PartialEq, Eq)]
+pub struct CostBreakdown {
+    /// Price of the job type, or the job's estimate
+    pub base_cents: i64,
+    /// Premium for high and critical priority
+    pub priority_surcharge_cents: i64,
+    /// Added by the customer's reseller
+    pub reseller_markup_cents: i64,
+    /// Promotional credit spent on the charge
+    pub credits_applied_cents: i64,
+    /// Charged instead of the other items when the job failed
+    pub failure_fee_cents: i64,
+    /// Amount charged to the wallet
+    pub total_cents: i64,
+}
+
+impl CostBreakdown {
+    /// Cost of a job that succeeded: its base cost with the surcharge of its priority,
+    /// marked up by `markup_rate` basis points
+    pub fn completed(base_cents: i32, priority: &PriorityLevel, markup_rate: i32) -> Self {
+        let base_cents = base_cents.max(0) as i64;
+        let priority_surcharge_cents = share(base_cents, priority_surcharge_rate(priority));
+        let reseller_markup_cents = share(base_cents + priority_surcharge_cents, markup_rate.max(0));
+        Self {
+            base_cents,
+            priority_surcharge_cents,
+            reseller_markup_cents,
+            total_cents: base_cents + priority_surcharge_cents + reseller_markup_cents,
+            ..Self::default()
+        }
+    }
+
+    /// Cost of a job that failed: `fee_rate` basis points of its estimated cost
+    pub fn failed(estimated_cost_cents: i32, fee_rate: i32) -> Self {
+        let failure_fee_cents = share(estimated_cost_cents.max(0) as i64, fee_rate.max(0));
+        Self {
+            failure_fee_cents,
+            total_cents: failure_fee_cents,
+            ..Self::default()
+        }
+    }
+
+    /// Cost of a job charged without itemizing it, counted as base cost
+    pub fn unitemized(cost_cents: i32) -> Self {
+        let base_cents = cost_cents.max(0) as i64;
+        Self {
+            base_cents,
+            total_cents: base_cents,
+            ..Self::default()
+        }
+    }
+
+    /// Spend up to `available_cents` of promotional credit on the charge, returning the
+    /// amount spent
+    pub fn apply_credits(&mut self, available_cents: i64) -> i64 {
+        let applied = available_cents.clamp(0, self.total_cents);
+        self.credits_applied_cents += applied;
+        self.total_cents -= applied;
+        applied
+    }
+
+    /// Total charged, as stored in a job's `cost_cents`
+    pub fn total(&self) -> i32 {
+        self.total_cents.clamp(0, i32::MAX as i64) as i32
+    }
+}
+
+impl std::ops::Add for CostBreakdown {
+    type Output = Self;
+
+    fn add(self, other: Self) -> Self {
+        Self {
+            base_cents: self.base_cents + other.base_cents,
+            priority_surcharge_cents: self.priority_surcharge_cents + other.priority_surcharge_cents,
+            reseller_markup_cents: self.reseller_markup_cents + other.reseller_markup_cents,
+            credits_applied_cents: self.credits_applied_cents + other.credits_applied_cents,
+            failure_fee_cents: self.failure_fee_cents + other.failure_fee_cents,
+            total_cents: self.total_cents + other.total_cents,
+        }
+    }
+}
+
+impl std::iter::Sum for CostBreakdown {
+    fn sum<I: Iterator<Item = Self>>(iter: I) -> Self {
+        iter.fold(Self::default(), |total, breakdown| total + breakdown)
+    }
+}
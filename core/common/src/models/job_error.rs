@@ -0,0 +1,116 @@
+use std::fmt;
+
+use serde::{Deserialize, Serialize};
+
+/// Broad classification of why a job failed
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+pub enum ErrorCategory {
+    /// The job's input or the customer's account state is invalid; retrying unchanged will fail again
+    InputError,
+    /// An external service the processor depends on failed
+    ProviderError,
+    /// Something went wrong inside InnoSystem
+    SystemError,
+}
+
+impl ErrorCategory {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            ErrorCategory::InputError => "input_error",
+            ErrorCategory::ProviderError => "provider_error",
+            ErrorCategory::SystemError => "system_error",
+        }
+    }
+
+    pub fn parse(s: &str) -> Option<Self> {
+        match s.to_lowercase().as_str() {
+            "input_error" => Some(ErrorCategory::InputError),
+            "provider_error" => Some(ErrorCategory::ProviderError),
+            "system_error" => Some(ErrorCategory::SystemError),
+            _ => None,
+        }
+    }
+}
+
+/// Well-known error codes set by the runner and billing
+pub mod codes {
+    pub const INVALID_INPUT: &str = "invalid_input";
+    pub const MISSING_FIELD: &str = "missing_field";
+    pub const OUTPUT_TOO_LARGE: &str = "output_too_large";
+    pub const REDACTION_FAILED: &str = "redaction_failed";
+    pub const INSUFFICIENT_FUNDS: &str = "insufficient_funds";
+    pub const BILLING_FAILED: &str = "billing_failed";
+    pub const PROVIDER_UNREACHABLE: &str = "provider_unreachable";
+    pub const PROVIDER_TIMEOUT: &str = "provider_timeout";
+    pub const PROVIDER_REJECTED: &str = "provider_rejected";
+    pub const PROCESSOR_NOT_IMPLEMENTED: &str = "processor_not_implemented";
+    pub const INVALID_SUB_TASK_TYPE: &str = "invalid_sub_task_type";
+    pub const INVALID_WEBHOOK_TEMPLATE: &str = "invalid_webhook_template";
+    pub const INVALID_RESPONSE_POLICY: &str = "invalid_response_policy";
+    pub const JOB_CANCELLED: &str = "job_cancelled";
+    pub const INTERNAL_ERROR: &str = "internal_error";
+}
+
+/// Customer-visible description of a job failure
+///
+/// Processors return it wrapped in an `anyhow::Error`; any other error reaching
+/// the runner is reported as a retryable `internal_error`.
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq, Eq)]
+pub struct JobError {
+    /// Stable machine-readable code, see [`codes`]
+    pub code: String,
+    pub category: ErrorCategory,
+    /// Whether submitting the same job again may succeed
+    pub retryable: bool,
+    /// Human-readable explanation
+    pub detail: String,
+}
+
+impl JobError {
+    /// Create an error with the category's default retryability
+    pub fn new(code: &str, category: ErrorCategory, detail: impl Into<String>) -> Self {
+        Self {
+            code: code.to_string(),
+            category,
+            retryable: category != ErrorCategory::InputError,
+            detail: detail.into(),
+        }
+    }
+
+    /// Create a non-retryable input error
+    pub fn input(code: &str, detail: impl Into<String>) -> Self {
+        Self::new(code, ErrorCategory::InputError, detail)
+    }
+
+    /// Create a retryable provider error
+    pub fn provider(code: &str, detail: impl Into<String>) -> Self {
+        Self::new(code, ErrorCategory::ProviderError, detail)
+    }
+
+    /// Create a retryable system error
+    pub fn system(code: &str, detail: impl Into<String>) -> Self {
+        Self::new(code, ErrorCategory::SystemError, detail)
+    }
+
+    /// Override whether the error is retryable
+    pub fn with_retryable(mut self, retryable: bool) -> Self {
+        self.retryable = retryable;
+        self
+    }
+
+    /// Extract the JobError carried by an anyhow error, or classify it as an internal error
+    pub fn from_anyhow(err: &anyhow::Error) -> Self {
+        err.downcast_ref::<JobError>()
+            .cloned()
+            .unwrap_or_else(|| Self::system(codes::INTERNAL_ERROR, err.to_string()))
+    }
+}
+
+impl fmt::Display for JobError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{} ({}): {}", self.code, self.category.as_str(), self.detail)
+    }
+}
+
+impl std::error::Error for JobError {}
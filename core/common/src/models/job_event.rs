@@ -0,0 +1,93 @@
+use chrono::{DateTime, Utc};
+use diesel::prelude::*;
+use serde::{Deserialize, Serialize};
+use serde_json::{json, Value};
+use uuid::Uuid;
+
+use crate::diesel_schema::job_lifecycle_events;
+use crate::models::job::{Job, JobStatus};
+use crate::models::webhook::WebhookEventType;
+
+/// Events captured from the jobs themselves as runners start and finish them
+pub const LIFECYCLE_EVENTS: [WebhookEventType; 3] = [
+    WebhookEventType::JobStarted,
+    WebhookEventType::JobSucceeded,
+    WebhookEventType::JobFailed,
+];
+
+/// Lifecycle event of a job in the outbox of customer notifications
+#[derive(Debug, Clone, Serialize, Deserialize, Queryable, Identifiable, Selectable)]
+#[diesel(table_name = job_lifecycle_events)]
+#[diesel(check_for_backend(diesel::pg::Pg))]
+pub struct JobLifecycleEvent {
+    pub id: Uuid,
+    pub job_id: Uuid,
+    pub customer_id: Uuid,
+    /// Event type name, e.g. `job.succeeded`
+    pub event_type: String,
+    /// Event data as sent to the customer
+    pub data: Value,
+    /// When the job started or finished
+    pub occurred_at: DateTime<Utc>,
+    /// When the event was handed to the customer's endpoints; None while pending
+    pub notified_at: Option<DateTime<Utc>>,
+    pub created_at: DateTime<Utc>,
+}
+
+impl JobLifecycleEvent {
+    /// Typed event type of the event
+    pub fn webhook_event_type(&self) -> Option<WebhookEventType> {
+        WebhookEventType::parse(&self.event_type)
+    }
+}
+
+// For DB insertion with Diesel
+#[derive(Debug, Clone, Serialize, Deserialize, Insertable)]
+#[diesel(table_name = job_lifecycle_events)]
+pub struct NewJobLifecycleEvent {
+    pub id: Uuid,
+    pub job_id: Uuid,
+    pub customer_id: Uuid,
+    pub event_type: String,
+    pub data: Value,
+    pub occurred_at: DateTime<Utc>,
+}
+
+impl NewJobLifecycleEvent {
+    /// Event of the given type for a job, or None if the job has not reached it
+    ///
+    /// The event gives the status the job took then, even if it finished since. Finished
+    /// jobs carry what they cost and failed ones the error explaining why.
+    pub fn for_job(job: &Job, event_type: WebhookEventType) -> Option<Self> {
+        let (status, occurred_at) = match (event_type, &job.status) {
+            (WebhookEventType::JobStarted, _) => (JobStatus::Running, job.started_at?),
+            (WebhookEventType::JobSucceeded, JobStatus::Succeeded) | (WebhookEventType::JobFailed, JobStatus::Failed) => (job.status.clone(), job.completed_at?),
+            _ => return None,
+        };
+
+        let mut data = json!({
+            "job_id": job.id,
+            "public_id": job.public_id,
+            "job_type_id": job.job_type_id,
+            "status": status.as_str(),
+            "test_mode": job.test_mode,
+            "customer_reference": job.customer_reference,
+            "occurred_at": occurred_at,
+        });
+        if event_type != WebhookEventType::JobStarted {
+            data["cost_cents"] = json!(job.cost_cents);
+        }
+        if event_type == WebhookEventType::JobFailed {
+            data["error"] = json!(job.error);
+        }
+
+        Some(Self {
+            id: Uuid::new_v4(),
+            job_id: job.id,
+            customer_id: job.customer_id,
+            event_type: event_type.as_str().to_string(),
+            data,
+            occurred_at,
+        })
+    }
+}
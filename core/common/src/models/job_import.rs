@@ -0,0 +1,358 @@
+use std::collections::BTreeMap;
+
+use serde::{Deserialize, Serialize};
+use serde_json::{Map, Value};
+use uuid::Uuid;
+use chrono::{DateTime, Utc};
+use diesel::prelude::*;
+use thiserror::Error;
+
+use crate::diesel_schema::{job_imports, job_import_rows};
+use crate::models::billing_period::csv_field;
+
+/// Most rows a single import may contain
+pub const MAX_IMPORT_ROWS: usize = 10_000;
+
+/// File format of an import
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+pub enum ImportFormat {
+    /// Comma-separated values with a header row naming the columns
+    Csv,
+    /// One JSON object per line, its keys being the columns
+    Ndjson,
+}
+
+impl ImportFormat {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            ImportFormat::Csv => "csv",
+            ImportFormat::Ndjson => "ndjson",
+        }
+    }
+
+    pub fn parse(s: &str) -> Option<Self> {
+        match s.to_lowercase().as_str() {
+            "csv" => Some(ImportFormat::Csv),
+            "ndjson" | "jsonl" => Some(ImportFormat::Ndjson),
+            _ => None,
+        }
+    }
+}
+
+/// State of an import
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq, Eq)]
+pub enum ImportStatus {
+    /// Accepted, no row submitted yet
+    Pending,
+    /// Rows are being submitted
+    Processing,
+    /// Every row was either submitted or rejected
+    Completed,
+    /// The import stopped before reaching the last row
+    Failed,
+}
+
+impl ImportStatus {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            ImportStatus::Pending => "pending",
+            ImportStatus::Processing => "processing",
+            ImportStatus::Completed => "completed",
+            ImportStatus::Failed => "failed",
+        }
+    }
+
+    pub fn parse(s: &str) -> Option<Self> {
+        match s.to_lowercase().as_str() {
+            "pending" => Some(ImportStatus::Pending),
+            "processing" => Some(ImportStatus::Processing),
+            "completed" => Some(ImportStatus::Completed),
+            "failed" => Some(ImportStatus::Failed),
+            _ => None,
+        }
+    }
+}
+
+/// Reasons an import file is refused as a whole
+#[derive(Debug, Error, PartialEq)]
+pub enum ImportError {
+    #[error("Invalid CSV: {0}")]
+    InvalidCsv(String),
+
+    #[error("The file contains no rows")]
+    Empty,
+
+    #[error("The file contains {0} rows, at most {MAX_IMPORT_ROWS} can be imported at once")]
+    TooManyRows(usize),
+
+    #[error("Mapped column {0} is not in the file")]
+    UnknownColumn(String),
+
+    #[error("Invalid mapping: {0}")]
+    InvalidMapping(String),
+}
+
+/// Which column of a file goes to which field of the jobs' input_data
+///
+/// Fields are dotted paths, so `{"street": "address.street"}` nests the column under
+/// an `address` object. Columns left out of the mapping are not imported; without a
+/// mapping every column goes to the top-level field of the same name.
+pub type ColumnMapping = BTreeMap<String, String>;
+
+/// Parse an import file into the input_data of each row's job, in file order
+///
+/// The outer error refuses the whole file; a row that cannot become input_data
+/// carries its own error so the other rows are still imported. CSV values are kept as
+/// strings, NDJSON values keep their JSON type.
+pub fn parse_rows(
+    format: ImportFormat,
+    content: &str,
+    mapping: Option<&ColumnMapping>,
+) -> Result<Vec<Result<Value, String>>, ImportError> {
+    if let Some(mapping) = mapping {
+        validate_mapping(mapping)?;
+    }
+
+    let rows = match format {
+        ImportFormat::Csv => parse_csv_rows(content, mapping)?,
+        ImportFormat::Ndjson => parse_ndjson_rows(content, mapping)?,
+    };
+    if rows.is_empty() {
+        return Err(ImportError::Empty);
+    }
+    if rows.len() > MAX_IMPORT_ROWS {
+        return Err(ImportError::TooManyRows(rows.len()));
+    }
+    Ok(rows)
+}
+
+fn validate_mapping(mapping: &ColumnMapping) -> Result<(), ImportError> {
+    if mapping.is_empty() {
+        return Err(ImportError::InvalidMapping("at least one column must be mapped".to_string()));
+    }
+    for (column, field) in mapping {
+        if field.split('.').any(str::is_empty) {
+            return Err(ImportError::InvalidMapping(format!("column {} maps to invalid field {:?}", column, field)));
+        }
+    }
+    Ok(())
+}
+
+fn parse_csv_rows(content: &str, mapping: Option<&ColumnMapping>) -> Result<Vec<Result<Value, String>>, ImportError> {
+    let mut records = split_csv(content)?.into_iter();
+    let header = records.next().ok_or(ImportError::Empty)?;
+    if let Some(mapping) = mapping {
+        if let Some(column) = mapping.keys().find(|column| !header.contains(column)) {
+            return Err(ImportError::UnknownColumn(column.clone()));
+        }
+    }
+
+    Ok(records
+        .map(|record| {
+            if record.len() != header.len() {
+                return Err(format!("expected {} columns, found {}", header.len(), record.len()));
+            }
+            let columns = header.iter().cloned().zip(record.into_iter().map(Value::String));
+            input_data(columns, mapping)
+        })
+        .collect())
+}
+
+fn parse_ndjson_rows(content: &str, mapping: Option<&ColumnMapping>) -> Result<Vec<Result<Value, String>>, ImportError> {
+    Ok(content
+        .lines()
+        .filter(|line| !line.trim().is_empty())
+        .map(|line| match serde_json::from_str::<Value>(line) {
+            Ok(Value::Object(object)) => {
+                if let Some(column) = mapping.and_then(|mapping| mapping.keys().find(|column| !object.contains_key(*column))) {
+                    return Err(format!("column {} is missing", column));
+                }
+                input_data(object, mapping)
+            }
+            Ok(_) => Err("expected a JSON object".to_string()),
+            Err(e) => Err(format!("invalid JSON: {}", e)),
+        })
+        .collect())
+}
+
+/// Build a row's input_data from its columns
+fn input_data(columns: impl IntoIterator<Item = (String, Value)>, mapping: Option<&ColumnMapping>) -> Result<Value, String> {
+    let mut input = Map::new();
+    for (column, value) in columns {
+        match mapping {
+            Some(mapping) => match mapping.get(&column) {
+                Some(field) => insert_field(&mut input, field, value)?,
+                None => continue,
+            },
+            // Column names are taken as they are, dots included
+            None => {
+                if input.insert(column.clone(), value).is_some() {
+                    return Err(format!("column {} appears more than once", column));
+                }
+            }
+        }
+    }
+    Ok(Value::Object(input))
+}
+
+/// Set a dotted field, creating the objects along its path
+fn insert_field(input: &mut Map<String, Value>, field: &str, value: Value) -> Result<(), String> {
+    match field.split_once('.') {
+        None => {
+            if input.insert(field.to_string(), value).is_some() {
+                return Err(format!("field {} is set by more than one column", field));
+            }
+            Ok(())
+        }
+        Some((parent, rest)) => {
+            match input.entry(parent.to_string()).or_insert_with(|| Value::Object(Map::new())) {
+                Value::Object(nested) => insert_field(nested, rest, value),
+                _ => Err(format!("field {} is set by more than one column", parent)),
+            }
+        }
+    }
+}
+
+/// Split CSV content into records of fields (RFC 4180: quoted fields may contain
+/// separators, doubled quotes and line breaks); blank lines are skipped
+fn split_csv(content: &str) -> Result<Vec<Vec<String>>, ImportError> {
+    let content = content.strip_prefix('\u{feff}').unwrap_or(content);
+    let mut records = Vec::new();
+    let mut record = Vec::new();
+    let mut field = String::new();
+    let mut quoted = false;
+    let mut line = 1;
+    let mut chars = content.chars().peekable();
+
+    while let Some(c) = chars.next() {
+        if quoted {
+            match c {
+                '"' if chars.peek() == Some(&'"') => {
+                    chars.next();
+                    field.push('"');
+                }
+                '"' => quoted = false,
+                '\n' => {
+                    line += 1;
+                    field.push(c);
+                }
+                _ => field.push(c),
+            }
+            continue;
+        }
+        match c {
+            '"' if field.is_empty() => quoted = true,
+            '"' => return Err(ImportError::InvalidCsv(format!("unexpected quote on line {}", line))),
+            ',' => record.push(std::mem::take(&mut field)),
+            '\r' if chars.peek() == Some(&'\n') => {}
+            '\n' => {
+                line += 1;
+                record.push(std::mem::take(&mut field));
+                if !(record.len() == 1 && record[0].is_empty()) {
+                    records.push(std::mem::take(&mut record));
+                } else {
+                    record.clear();
+                }
+            }
+            _ => field.push(c),
+        }
+    }
+    if quoted {
+        return Err(ImportError::InvalidCsv("unterminated quoted field".to_string()));
+    }
+    record.push(field);
+    if !(record.len() == 1 && record[0].is_empty()) {
+        records.push(record);
+    }
+    Ok(records)
+}
+
+/// A file of job submissions, imported one row at a time in the background
+#[derive(Debug, Clone, Serialize, Deserialize, Queryable, Identifiable, Selectable)]
+#[diesel(table_name = job_imports)]
+#[diesel(check_for_backend(diesel::pg::Pg))]
+pub struct JobImport {
+    pub id: Uuid,
+    pub customer_id: Uuid,
+    pub job_type_id: Uuid,
+    pub format: String,
+    pub mapping: Option<Value>,
+    /// File as uploaded
+    pub content: String,
+    pub priority: i32,
+    pub test_mode: bool,
+    pub status: String,
+    pub total_rows: i32,
+    /// First row (counted from 1, without a CSV header) not submitted yet
+    pub next_row: i32,
+    pub submitted_rows: i32,
+    pub failed_rows: i32,
+    pub created_at: Option<DateTime<Utc>>,
+    pub updated_at: Option<DateTime<Utc>>,
+    pub completed_at: Option<DateTime<Utc>>,
+}
+
+impl JobImport {
+    pub fn format(&self) -> Option<ImportFormat> {
+        ImportFormat::parse(&self.format)
+    }
+
+    pub fn status(&self) -> Option<ImportStatus> {
+        ImportStatus::parse(&self.status)
+    }
+
+    /// Column mapping the import was submitted with
+    pub fn column_mapping(&self) -> Option<ColumnMapping> {
+        self.mapping.clone().and_then(|mapping| serde_json::from_value(mapping).ok())
+    }
+
+    pub fn is_finished(&self) -> bool {
+        matches!(self.status(), Some(ImportStatus::Completed | ImportStatus::Failed))
+    }
+}
+
+// For DB insertion with Diesel
+#[derive(Debug, Clone, Serialize, Deserialize, Insertable)]
+#[diesel(table_name = job_imports)]
+pub struct NewJobImport {
+    pub id: Uuid,
+    pub customer_id: Uuid,
+    pub job_type_id: Uuid,
+    pub format: String,
+    pub mapping: Option<Value>,
+    pub content: String,
+    pub priority: i32,
+    pub test_mode: bool,
+    pub total_rows: i32,
+}
+
+/// Outcome of one row of an import
+#[derive(Debug, Clone, Serialize, Deserialize, Queryable, Selectable)]
+#[diesel(table_name = job_import_rows)]
+#[diesel(check_for_backend(diesel::pg::Pg))]
+pub struct JobImportRow {
+    pub import_id: Uuid,
+    pub row_number: i32,
+    /// Job submitted for the row
+    pub job_id: Option<Uuid>,
+    /// Why no job was submitted for the row
+    pub error: Option<String>,
+    pub created_at: Option<DateTime<Utc>>,
+}
+
+/// Per-row result report of an import as CSV, with the current status of each
+/// row's job (`rows` paired with the status of their job, if it was found)
+pub fn import_report_csv(rows: &[(JobImportRow, Option<String>)]) -> String {
+    let mut out = String::from("row,job_id,job_status,error\n");
+    for (row, job_status) in rows {
+        out.push_str(&format!(
+            "{},{},{},{}\n",
+            row.row_number,
+            row.job_id.map(|id| id.to_string()).unwrap_or_default(),
+            job_status.as_deref().unwrap_or_default(),
+            csv_field(row.error.as_deref().unwrap_or_default()),
+        ));
+    }
+    out
+}
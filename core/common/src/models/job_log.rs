@@ -0,0 +1,65 @@
+use serde::{Deserialize, Serialize};
+use uuid::Uuid;
+use chrono::{DateTime, Utc};
+use diesel::prelude::*;
+
+use crate::diesel_schema::job_logs;
+
+/// Severity of a job log line
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq, Eq)]
+pub enum LogLevel {
+    Debug,
+    Info,
+    Warn,
+    Error,
+}
+
+impl LogLevel {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            LogLevel::Debug => "debug",
+            LogLevel::Info => "info",
+            LogLevel::Warn => "warn",
+            LogLevel::Error => "error",
+        }
+    }
+
+    pub fn parse(s: &str) -> Option<Self> {
+        match s.to_lowercase().as_str() {
+            "debug" => Some(LogLevel::Debug),
+            "info" => Some(LogLevel::Info),
+            "warn" => Some(LogLevel::Warn),
+            "error" => Some(LogLevel::Error),
+            _ => None,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, Queryable, Identifiable, Selectable)]
+#[diesel(table_name = job_logs)]
+#[diesel(check_for_backend(diesel::pg::Pg))]
+pub struct JobLog {
+    pub id: Uuid,
+    pub job_id: Uuid,
+    /// Position of the line within the job's log, starting at 1
+    pub line_number: i32,
+    pub level: String,
+    pub message: String,
+    /// Optional structured context attached to the line
+    pub fields: Option<serde_json::Value>,
+    pub created_at: Option<DateTime<Utc>>,
+}
+
+// For DB insertion with Diesel
+#[derive(Debug, Clone, Serialize, Deserialize, Insertable)]
+#[diesel(table_name = job_logs)]
+pub struct NewJobLog {
+    pub id: Uuid,
+    pub job_id: Uuid,
+    pub line_number: i32,
+    pub level: String,
+    pub message: String,
+    pub fields: Option<serde_json::Value>,
+    /// Time the line was emitted, rather than the time it was persisted
+    pub created_at: Option<DateTime<Utc>>,
+}
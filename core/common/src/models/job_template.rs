@@ -0,0 +1,259 @@
+use std::collections::BTreeMap;
+
+use serde::{Deserialize, Serialize};
+use serde_json::{Map, Value};
+use uuid::Uuid;
+use chrono::{DateTime, Utc};
+use diesel::prelude::*;
+use thiserror::Error;
+
+use crate::diesel_schema::job_templates;
+
+/// JSON type a template variable must have
+#[derive(Debug, Clone, Copy, Default, Serialize, Deserialize, PartialEq, Eq)]
+#[serde(rename_all = "lowercase")]
+pub enum VariableType {
+    String,
+    Number,
+    Integer,
+    Boolean,
+    Object,
+    Array,
+    /// Any JSON value
+    #[default]
+    Any,
+}
+
+impl VariableType {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            VariableType::String => "string",
+            VariableType::Number => "number",
+            VariableType::Integer => "integer",
+            VariableType::Boolean => "boolean",
+            VariableType::Object => "object",
+            VariableType::Array => "array",
+            VariableType::Any => "any",
+        }
+    }
+
+    /// Whether the value has this type
+    pub fn accepts(&self, value: &Value) -> bool {
+        match self {
+            VariableType::String => value.is_string(),
+            VariableType::Number => value.is_number(),
+            VariableType::Integer => value.is_i64() || value.is_u64(),
+            VariableType::Boolean => value.is_boolean(),
+            VariableType::Object => value.is_object(),
+            VariableType::Array => value.is_array(),
+            VariableType::Any => true,
+        }
+    }
+}
+
+/// Declaration of a single template variable
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+#[serde(deny_unknown_fields)]
+pub struct TemplateVariable {
+    #[serde(rename = "type", default)]
+    pub var_type: VariableType,
+    /// Whether a value must be supplied; variables with a default never are
+    #[serde(default = "default_required")]
+    pub required: bool,
+    /// Value used when none is supplied
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub default: Option<Value>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub description: Option<String>,
+}
+
+fn default_required() -> bool {
+    true
+}
+
+/// Reasons a template cannot be stored or rendered
+#[derive(Debug, Error, PartialEq)]
+pub enum TemplateError {
+    #[error("Invalid variable declarations: {0}")]
+    InvalidDefinition(String),
+
+    #[error("Template uses undeclared variable: {0}")]
+    UndeclaredVariable(String),
+
+    #[error("Unknown variable: {0}")]
+    UnknownVariable(String),
+
+    #[error("Missing value for required variable: {0}")]
+    MissingVariable(String),
+
+    #[error("Variable {name} must be of type {expected}")]
+    TypeMismatch { name: String, expected: &'static str },
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, Queryable, Identifiable, Selectable)]
+#[diesel(table_name = job_templates)]
+#[diesel(check_for_backend(diesel::pg::Pg))]
+pub struct JobTemplate {
+    pub id: Uuid,
+    pub customer_id: Uuid,
+    pub job_type_id: Uuid,
+    pub name: String,
+    pub description: Option<String>,
+    /// input_data skeleton; strings may contain `{{variable}}` placeholders
+    pub input_template: Value,
+    /// Declared variables, name -> [`TemplateVariable`]
+    pub variables: Value,
+    pub created_at: Option<DateTime<Utc>>,
+    pub updated_at: Option<DateTime<Utc>>,
+}
+
+impl JobTemplate {
+    /// Parse variable declarations and check that the template only uses declared variables
+    pub fn validate(input_template: &Value, variables: &Value) -> Result<BTreeMap<String, TemplateVariable>, TemplateError> {
+        let declarations: BTreeMap<String, TemplateVariable> = serde_json::from_value(variables.clone())
+            .map_err(|e| TemplateError::InvalidDefinition(e.to_string()))?;
+
+        for (name, declaration) in &declarations {
+            if !is_variable_name(name) {
+                return Err(TemplateError::InvalidDefinition(format!("invalid variable name: {}", name)));
+            }
+            if let Some(default) = &declaration.default {
+                if !declaration.var_type.accepts(default) {
+                    return Err(TemplateError::TypeMismatch { name: name.clone(), expected: declaration.var_type.as_str() });
+                }
+            }
+        }
+
+        let mut used = Vec::new();
+        collect_placeholders(input_template, &mut used);
+        if let Some(name) = used.into_iter().find(|name| !declarations.contains_key(name)) {
+            return Err(TemplateError::UndeclaredVariable(name));
+        }
+
+        Ok(declarations)
+    }
+
+    /// Parsed variable declarations of the template
+    pub fn variable_definitions(&self) -> Result<BTreeMap<String, TemplateVariable>, TemplateError> {
+        serde_json::from_value(self.variables.clone())
+            .map_err(|e| TemplateError::InvalidDefinition(e.to_string()))
+    }
+
+    /// Build the job's input_data from the supplied variable values
+    ///
+    /// A string consisting of a single placeholder is replaced by the value itself,
+    /// keeping its JSON type; placeholders inside longer strings are interpolated.
+    pub fn render(&self, values: &Map<String, Value>) -> Result<Value, TemplateError> {
+        let declarations = self.variable_definitions()?;
+
+        if let Some(name) = values.keys().find(|name| !declarations.contains_key(*name)) {
+            return Err(TemplateError::UnknownVariable(name.clone()));
+        }
+
+        let mut resolved = Map::new();
+        for (name, declaration) in declarations {
+            let value = match values.get(&name).cloned().or(declaration.default) {
+                Some(value) => value,
+                None if declaration.required => return Err(TemplateError::MissingVariable(name)),
+                None => Value::Null,
+            };
+            if !value.is_null() && !declaration.var_type.accepts(&value) {
+                return Err(TemplateError::TypeMismatch { name, expected: declaration.var_type.as_str() });
+            }
+            resolved.insert(name, value);
+        }
+
+        Ok(render_value(&self.input_template, &resolved))
+    }
+}
+
+// For DB insertion with Diesel
+#[derive(Debug, Clone, Serialize, Deserialize, Insertable)]
+#[diesel(table_name = job_templates)]
+pub struct NewJobTemplate {
+    pub id: Uuid,
+    pub customer_id: Uuid,
+    pub job_type_id: Uuid,
+    pub name: String,
+    pub description: Option<String>,
+    pub input_template: Value,
+    pub variables: Value,
+}
+
+/// Whether the name is a valid variable identifier (letters, digits and underscores)
+fn is_variable_name(name: &str) -> bool {
+    let mut chars = name.chars();
+    chars.next().is_some_and(|c| c.is_ascii_alphabetic() || c == '_')
+        && chars.all(|c| c.is_ascii_alphanumeric() || c == '_')
+}
+
+/// Split a string into literal text and `{{name}}` placeholders
+fn parse_placeholders(s: &str) -> Vec<Segment<'_>> {
+    let mut segments = Vec::new();
+    let mut rest = s;
+    while let Some(start) = rest.find("{{") {
+        let Some(len) = rest[start + 2..].find("}}") else { break };
+        let name = rest[start + 2..start + 2 + len].trim();
+        if is_variable_name(name) {
+            if start > 0 {
+                segments.push(Segment::Text(&rest[..start]));
+            }
+            segments.push(Segment::Variable(name));
+        } else {
+            segments.push(Segment::Text(&rest[..start + 4 + len]));
+        }
+        rest = &rest[start + 4 + len..];
+    }
+    if !rest.is_empty() {
+        segments.push(Segment::Text(rest));
+    }
+    segments
+}
+
+enum Segment<'a> {
+    Text(&'a str),
+    Variable(&'a str),
+}
+
+fn collect_placeholders(value: &Value, names: &mut Vec<String>) {
+    match value {
+        Value::String(s) => {
+            for segment in parse_placeholders(s) {
+                if let Segment::Variable(name) = segment {
+                    names.push(name.to_string());
+                }
+            }
+        }
+        Value::Array(items) => items.iter().for_each(|item| collect_placeholders(item, names)),
+        Value::Object(fields) => fields.values().for_each(|field| collect_placeholders(field, names)),
+        _ => {}
+    }
+}
+
+fn render_value(value: &Value, values: &Map<String, Value>) -> Value {
+    match value {
+        Value::String(s) => {
+            let segments = parse_placeholders(s);
+            if let [Segment::Variable(name)] = segments.as_slice() {
+                return values.get(*name).cloned().unwrap_or(Value::Null);
+            }
+            let mut rendered = String::with_capacity(s.len());
+            for segment in segments {
+                match segment {
+                    Segment::Text(text) => rendered.push_str(text),
+                    Segment::Variable(name) => match values.get(name) {
+                        Some(Value::String(text)) => rendered.push_str(text),
+                        Some(Value::Null) | None => {}
+                        Some(other) => rendered.push_str(&other.to_string()),
+                    },
+                }
+            }
+            Value::String(rendered)
+        }
+        Value::Array(items) => Value::Array(items.iter().map(|item| render_value(item, values)).collect()),
+        Value::Object(fields) => Value::Object(
+            fields.iter().map(|(key, field)| (key.clone(), render_value(field, values))).collect()
+        ),
+        other => other.clone(),
+    }
+}
@@ -1,6 +1,6 @@
 use serde::{Deserialize, Serialize};
 use uuid::Uuid;
-use chrono::NaiveDateTime;
+use chrono::{DateTime, Utc};
 use diesel::prelude::*;
 use diesel::pg::Pg;
 use diesel::serialize::{self, IsNull, Output, ToSql};
@@ -9,6 +9,8 @@ use diesel::sql_types::Text;
 use std::io::Write;
 
 use crate::diesel_schema::job_types;
+use crate::models::response_policy::ResponsePolicy;
+use crate::models::webhook_template::WebhookTemplate;
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub enum ProcessorType {
@@ -83,6 +85,72 @@ impl ProcessorType {
     }
 }
 
+/// Who a job type is listed for in the marketplace catalog
+#[derive(Debug, Clone, Copy, Default, Serialize, Deserialize, PartialEq, Eq)]
+#[serde(rename_all = "lowercase")]
+pub enum CatalogVisibility {
+    /// Listed for resellers and their customers
+    #[default]
+    Public,
+    /// Listed for resellers only, e.g. offerings they configure for their customers
+    Reseller,
+    /// Not listed; only administrators see the job type
+    Internal,
+}
+
+impl Queryable<Text, Pg> for CatalogVisibility {
+    type Row = String;
+    
+    fn build(row: Self::Row) -> diesel::deserialize::Result<Self> {
+        match CatalogVisibility::parse(&row) {
+            Some(visibility) => Ok(visibility),
+            None => {
+                let error_message = format!("Unrecognized catalog visibility: {}", row);
+                Err(Box::new(std::io::Error::new(std::io::ErrorKind::InvalidData, error_message)))
+            }
+        }
+    }
+}
+
+impl ToSql<Text, Pg> for CatalogVisibility {
+    fn to_sql<'b>(&'b self, out: &mut Output<'b, '_, Pg>) -> serialize::Result {
+        out.write_all(self.as_str().as_bytes())?;
+        Ok(IsNull::No)
+    }
+}
+
+impl FromSql<Text, Pg> for CatalogVisibility {
+    fn from_sql(bytes: diesel::pg::PgValue) -> deserialize::Result<Self> {
+        let string_value = <String as FromSql<Text, Pg>>::from_sql(bytes)?;
+        match CatalogVisibility::parse(&string_value) {
+            Some(visibility) => Ok(visibility),
+            None => {
+                let error_message = format!("Unrecognized catalog visibility: {}", string_value);
+                Err(Box::new(std::io::Error::new(std::io::ErrorKind::InvalidData, error_message)))
+            }
+        }
+    }
+}
+
+impl CatalogVisibility {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            CatalogVisibility::Public => "public",
+            CatalogVisibility::Reseller => "reseller",
+            CatalogVisibility::Internal => "internal",
+        }
+    }
+    
+    pub fn parse(s: &str) -> Option<Self> {
+        match s.to_lowercase().as_str() {
+            "public" => Some(CatalogVisibility::Public),
+            "reseller" => Some(CatalogVisibility::Reseller),
+            "internal" => Some(CatalogVisibility::Internal),
+            _ => None,
+        }
+    }
+}
+
 // Updated for Phase 2 with Diesel support
 #[derive(Debug, Clone, Serialize, Deserialize, Queryable, Identifiable, Selectable)]
 #[diesel(table_name = job_types)]
@@ -95,8 +163,37 @@ pub struct JobType {
     pub processor_type: ProcessorType,
     pub standard_cost_cents: i32,
     pub enabled: bool,
-    pub created_at: Option<NaiveDateTime>,
-    pub updated_at: Option<NaiveDateTime>,
+    pub created_at: Option<DateTime<Utc>>,
+    pub updated_at: Option<DateTime<Utc>>,
+    /// Marketplace category, e.g. "documents" or "images"
+    pub category: Option<String>,
+    /// Icon name or image URL shown in the catalog
+    pub icon: Option<String>,
+    pub documentation_url: Option<String>,
+    /// Example input_data shown in the catalog
+    pub sample_input: Option<serde_json::Value>,
+    /// Example output of a job run on `sample_input`
+    pub sample_output: Option<serde_json::Value>,
+    pub visibility: CatalogVisibility,
+    /// JSONPath patterns of output values replaced before the output is stored, see
+    /// [`RedactionRule`](crate::models::redaction::RedactionRule)
+    pub redaction_rules: Vec<String>,
+    /// Hours the original output of jobs with redacted values is kept for administrators;
+    /// None keeps no unredacted output
+    pub unredacted_retention_hours: Option<i32>,
+    /// External provider the job type depends on; its jobs are held back while it is down
+    pub provider_id: Option<Uuid>,
+    /// Priority level of jobs submitted without one, unless their customer has its own;
+    /// medium when unset
+    pub default_priority: Option<i32>,
+    /// Method, headers and body template of webhook jobs, see
+    /// [`WebhookTemplate`](crate::models::webhook_template::WebhookTemplate); jobs post
+    /// their input_data when unset
+    pub webhook_template: Option<serde_json::Value>,
+    /// Size limit, JSON parsing and handling of binary bodies of webhook responses, see
+    /// [`ResponsePolicy`](crate::models::response_policy::ResponsePolicy); the
+    /// defaults apply when unset
+    pub response_policy: Option<serde_json::Value>,
 }
 
 impl JobType {
@@ -116,8 +213,30 @@ impl JobType {
             enabled: true,
             created_at: None,
             updated_at: None,
+            category: None,
+            icon: None,
+            documentation_url: None,
+            sample_input: None,
+            sample_output: None,
+            visibility: CatalogVisibility::Public,
+            redaction_rules: Vec::new(),
+            unredacted_retention_hours: None,
+            provider_id: None,
+            default_priority: None,
+            webhook_template: None,
+            response_policy: None,
         }
     }
+
+    /// Parsed webhook template, if the job type has one
+    pub fn webhook_template(&self) -> Result<Option<WebhookTemplate>, serde_json::Error> {
+        self.webhook_template.clone().map(serde_json::from_value).transpose()
+    }
+
+    /// Parsed response policy, the defaults when unset
+    pub fn response_policy(&self) -> Result<ResponsePolicy, serde_json::Error> {
+        Ok(self.response_policy.clone().map(serde_json::from_value).transpose()?.unwrap_or_default())
+    }
 }
 
 // For DB insertion with Diesel
@@ -131,4 +250,10 @@ pub struct NewJobType {
     pub processor_type: String,
     pub standard_cost_cents: i32,
     pub enabled: bool,
+    pub category: Option<String>,
+    pub icon: Option<String>,
+    pub documentation_url: Option<String>,
+    pub sample_input: Option<serde_json::Value>,
+    pub sample_output: Option<serde_json::Value>,
+    pub visibility: String,
 }
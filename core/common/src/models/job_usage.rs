@@ -0,0 +1,147 @@
+use std::collections::BTreeMap;
+
+use chrono::{DateTime, Utc};
+use diesel::prelude::*;
+use serde::{Deserialize, Serialize};
+use uuid::Uuid;
+
+use crate::diesel_schema::job_usage;
+use crate::models::job::Job;
+
+/// Resources a runner measured while it ran a job
+#[derive(Debug, Clone, Copy, Default, Serialize, Deserialize, PartialEq, Eq)]
+pub struct ResourceUsage {
+    /// Time from the start of the job to its completion
+    pub wall_time_ms: i64,
+    /// CPU time the runner spent on the job, if the runner can measure it
+    pub cpu_time_ms: Option<i64>,
+    /// Peak resident memory of the runner while the job ran, if the runner can measure it
+    pub peak_memory_bytes: Option<i64>,
+    /// Request bodies sent to webhooks and external APIs
+    pub bytes_sent: i64,
+    /// Response bodies received from webhooks and external APIs
+    pub bytes_received: i64,
+}
+
+/// Resource usage reported with the completion of a job
+#[derive(Debug, Clone, Serialize, Deserialize, Queryable, Identifiable, Selectable)]
+#[diesel(table_name = job_usage)]
+#[diesel(primary_key(job_id))]
+#[diesel(check_for_backend(diesel::pg::Pg))]
+pub struct JobUsage {
+    pub job_id: Uuid,
+    /// Attempt of the job that completed it, if it could be recorded
+    pub attempt_id: Option<Uuid>,
+    pub runner_id: Option<Uuid>,
+    pub customer_id: Uuid,
+    pub job_type_id: Uuid,
+    pub wall_time_ms: i64,
+    pub cpu_time_ms: Option<i64>,
+    pub peak_memory_bytes: Option<i64>,
+    pub bytes_sent: i64,
+    pub bytes_received: i64,
+    pub recorded_at: DateTime<Utc>,
+}
+
+impl JobUsage {
+    /// Resources the job used
+    pub fn usage(&self) -> ResourceUsage {
+        ResourceUsage {
+            wall_time_ms: self.wall_time_ms,
+            cpu_time_ms: self.cpu_time_ms,
+            peak_memory_bytes: self.peak_memory_bytes,
+            bytes_sent: self.bytes_sent,
+            bytes_received: self.bytes_received,
+        }
+    }
+}
+
+// For DB insertion with Diesel
+#[derive(Debug, Clone, Serialize, Deserialize, Insertable)]
+#[diesel(table_name = job_usage)]
+pub struct NewJobUsage {
+    pub job_id: Uuid,
+    pub attempt_id: Option<Uuid>,
+    pub runner_id: Option<Uuid>,
+    pub customer_id: Uuid,
+    pub job_type_id: Uuid,
+    pub wall_time_ms: i64,
+    pub cpu_time_ms: Option<i64>,
+    pub peak_memory_bytes: Option<i64>,
+    pub bytes_sent: i64,
+    pub bytes_received: i64,
+}
+
+impl NewJobUsage {
+    /// Usage of a run of `job` by a runner
+    pub fn new(job: &Job, attempt_id: Option<Uuid>, runner_id: Option<Uuid>, usage: ResourceUsage) -> Self {
+        Self {
+            job_id: job.id,
+            attempt_id,
+            runner_id,
+            customer_id: job.customer_id,
+            job_type_id: job.job_type_id,
+            wall_time_ms: usage.wall_time_ms,
+            cpu_time_ms: usage.cpu_time_ms,
+            peak_memory_bytes: usage.peak_memory_bytes,
+            bytes_sent: usage.bytes_sent,
+            bytes_received: usage.bytes_received,
+        }
+    }
+}
+
+/// Resources used by the jobs of a customer and job type over a period
+#[derive(Debug, Clone, Default, Serialize, PartialEq, Eq)]
+pub struct UsageSummary {
+    pub customer_id: Uuid,
+    pub job_type_id: Uuid,
+    pub jobs: i64,
+    pub wall_time_ms: i64,
+    /// CPU time of the jobs it was measured for
+    pub cpu_time_ms: i64,
+    /// Highest peak memory of a single job
+    pub peak_memory_bytes: Option<i64>,
+    pub bytes_sent: i64,
+    pub bytes_received: i64,
+}
+
+impl UsageSummary {
+    /// Add another summary of the same customer and job type
+    pub fn merge(&mut self, other: &UsageSummary) {
+        self.jobs += other.jobs;
+        self.wall_time_ms += other.wall_time_ms;
+        self.cpu_time_ms += other.cpu_time_ms;
+        self.peak_memory_bytes = self.peak_memory_bytes.max(other.peak_memory_bytes);
+        self.bytes_sent += other.bytes_sent;
+        self.bytes_received += other.bytes_received;
+    }
+}
+
+/// Summarize job usage per customer and job type, ordered by customer and job type
+pub fn summarize<'a>(usages: impl IntoIterator<Item = &'a JobUsage>) -> Vec<UsageSummary> {
+    merge_summaries(usages.into_iter().map(|usage| UsageSummary {
+        customer_id: usage.customer_id,
+        job_type_id: usage.job_type_id,
+        jobs: 1,
+        wall_time_ms: usage.wall_time_ms,
+        cpu_time_ms: usage.cpu_time_ms.unwrap_or(0),
+        peak_memory_bytes: usage.peak_memory_bytes,
+        bytes_sent: usage.bytes_sent,
+        bytes_received: usage.bytes_received,
+    }))
+}
+
+/// Combine summaries, e.g. of several schemas, into one per customer and job type,
+/// ordered by customer and job type
+pub fn merge_summaries(summaries: impl IntoIterator<Item = UsageSummary>) -> Vec<UsageSummary> {
+    let mut merged: BTreeMap<(Uuid, Uuid), UsageSummary> = BTreeMap::new();
+    for summary in summaries {
+        match merged.get_mut(&(summary.customer_id, summary.job_type_id)) {
+            Some(existing) => existing.merge(&summary),
+            None => {
+                merged.insert((summary.customer_id, summary.job_type_id), summary);
+            }
+        }
+    }
+    merged.into_values().collect()
+}
@@ -0,0 +1,191 @@
+use chrono::{DateTime, Utc};
+use diesel::prelude::*;
+use serde::{Deserialize, Serialize};
+use uuid::Uuid;
+
+use crate::diesel_schema::{ledger_accounts, ledger_entries};
+use crate::models::job_cost::CostBreakdown;
+use crate::models::reseller::Reseller;
+use crate::models::reseller_invoice::InvoiceAmounts;
+use crate::models::wallet::job_charge_reference;
+
+/// Whose money an account of the ledger holds
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum LedgerAccountKind {
+    /// Funds of a customer's wallet, owned by the wallet
+    CustomerWallet,
+    /// What the platform earned, with no owner
+    PlatformRevenue,
+    /// Markup and commission a reseller earned, owned by the reseller
+    ResellerCommission,
+}
+
+impl LedgerAccountKind {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            LedgerAccountKind::CustomerWallet => "customer_wallet",
+            LedgerAccountKind::PlatformRevenue => "platform_revenue",
+            LedgerAccountKind::ResellerCommission => "reseller_commission",
+        }
+    }
+
+    pub fn parse(s: &str) -> Option<Self> {
+        match s.to_lowercase().as_str() {
+            "customer_wallet" => Some(LedgerAccountKind::CustomerWallet),
+            "platform_revenue" => Some(LedgerAccountKind::PlatformRevenue),
+            "reseller_commission" => Some(LedgerAccountKind::ResellerCommission),
+            _ => None,
+        }
+    }
+}
+
+/// Account of the internal ledger
+#[derive(Debug, Clone, Serialize, Deserialize, Queryable, Identifiable, Selectable)]
+#[diesel(table_name = ledger_accounts)]
+#[diesel(check_for_backend(diesel::pg::Pg))]
+pub struct LedgerAccount {
+    pub id: Uuid,
+    /// See [`LedgerAccountKind`]
+    pub kind: String,
+    /// Wallet or reseller the account belongs to; none for the platform's revenue
+    pub owner_id: Option<Uuid>,
+    pub created_at: DateTime<Utc>,
+}
+
+// For DB insertion with Diesel
+#[derive(Debug, Clone, Serialize, Deserialize, Insertable)]
+#[diesel(table_name = ledger_accounts)]
+pub struct NewLedgerAccount {
+    pub id: Uuid,
+    pub kind: String,
+    pub owner_id: Option<Uuid>,
+}
+
+impl NewLedgerAccount {
+    pub fn new(kind: LedgerAccountKind, owner_id: Option<Uuid>) -> Self {
+        Self {
+            id: Uuid::new_v4(),
+            kind: kind.as_str().to_string(),
+            owner_id,
+        }
+    }
+}
+
+/// Amount moved into or out of an account by a wallet transaction
+///
+/// What the account receives is positive and what it gives negative, so the entries of
+/// a transaction add up to zero.
+#[derive(Debug, Clone, Serialize, Deserialize, Queryable, Identifiable, Selectable)]
+#[diesel(table_name = ledger_entries)]
+#[diesel(check_for_backend(diesel::pg::Pg))]
+pub struct LedgerEntry {
+    pub id: Uuid,
+    /// Wallet transaction the entry posts
+    pub transaction_id: Uuid,
+    pub account_id: Uuid,
+    pub job_id: Option<Uuid>,
+    pub amount_cents: i64,
+    pub created_at: DateTime<Utc>,
+}
+
+// For DB insertion with Diesel
+#[derive(Debug, Clone, Serialize, Deserialize, Insertable)]
+#[diesel(table_name = ledger_entries)]
+pub struct NewLedgerEntry {
+    pub id: Uuid,
+    pub transaction_id: Uuid,
+    pub account_id: Uuid,
+    pub job_id: Option<Uuid>,
+    pub amount_cents: i64,
+}
+
+/// Amount to post to the account of a kind and owner, before the account is looked up
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct LedgerPosting {
+    pub kind: LedgerAccountKind,
+    pub owner_id: Option<Uuid>,
+    pub amount_cents: i64,
+}
+
+/// Whether postings add up to zero, as the entries of a transaction must
+pub fn is_balanced(postings: &[LedgerPosting]) -> bool {
+    postings.iter().map(|posting| posting.amount_cents).sum::<i64>() == 0
+}
+
+/// Balance of an account: what it received less what it gave
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq, Eq)]
+pub struct AccountBalance {
+    pub account_id: Uuid,
+    pub kind: String,
+    pub owner_id: Option<Uuid>,
+    pub balance_cents: i64,
+}
+
+/// Charge of a job to a wallet, split between the platform and the customer's reseller
+///
+/// The split follows the reseller's invoice: the reseller keeps the markup and earns its
+/// commission on the platform's price, and the platform keeps the rest.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct JobDebit {
+    pub wallet_id: Uuid,
+    pub job_id: Uuid,
+    pub description: Option<String>,
+    /// Reseller of the customer, if it has one
+    pub reseller_id: Option<Uuid>,
+    /// The charge and its split, for a single job
+    pub amounts: InvoiceAmounts,
+    /// Refuse the charge when the balance does not cover it, as withdrawals are
+    pub require_funds: bool,
+    /// Reference ID of the charge, so that a charge retried after it went through is not
+    /// posted twice; the job's charge reference unless given another
+    pub reference_id: Uuid,
+}
+
+impl JobDebit {
+    /// Debit of a job charged `cost`, for a customer of `reseller` if it has one
+    pub fn new(wallet_id: Uuid, job_id: Uuid, cost: &CostBreakdown, reseller: Option<&Reseller>) -> Self {
+        let commission_rate = reseller.map_or(0, |reseller| reseller.commission_rate);
+        Self {
+            wallet_id,
+            job_id,
+            description: None,
+            reseller_id: reseller.map(|reseller| reseller.id),
+            amounts: InvoiceAmounts::new(1, cost, commission_rate),
+            require_funds: false,
+            reference_id: job_charge_reference(job_id),
+        }
+    }
+
+    pub fn with_description(mut self, description: impl Into<String>) -> Self {
+        self.description = Some(description.into());
+        self
+    }
+
+    /// Refuse the charge when the balance does not cover it
+    pub fn requiring_funds(mut self) -> Self {
+        self.require_funds = true;
+        self
+    }
+
+    /// Amount taken from the wallet
+    pub fn amount_cents(&self) -> i32 {
+        self.amounts.charged_cents.clamp(0, i32::MAX as i64) as i32
+    }
+
+    /// Postings moving the charge out of the wallet's account into the reseller's
+    /// commission and the platform's revenue; accounts getting nothing are left out
+    pub fn postings(&self) -> Vec<LedgerPosting> {
+        let charged_cents = self.amount_cents() as i64;
+        let reseller_cents = match self.reseller_id {
+            Some(_) => self.amounts.markup_cents + self.amounts.commission_cents,
+            None => 0,
+        };
+        let postings = [
+            LedgerPosting { kind: LedgerAccountKind::CustomerWallet, owner_id: Some(self.wallet_id), amount_cents: -charged_cents },
+            LedgerPosting { kind: LedgerAccountKind::ResellerCommission, owner_id: self.reseller_id, amount_cents: reseller_cents },
+            LedgerPosting { kind: LedgerAccountKind::PlatformRevenue, owner_id: None, amount_cents: charged_cents - reseller_cents },
+        ];
+        postings.into_iter().filter(|posting| posting.amount_cents != 0).collect()
+    }
+}
@@ -0,0 +1,160 @@
+use chrono::{DateTime, Duration, Utc};
+use diesel::prelude::*;
+use serde::{Deserialize, Serialize};
+use uuid::Uuid;
+
+use crate::diesel_schema::load_windows;
+
+/// Where a load window is in its warm-up
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq, Eq)]
+#[serde(rename_all = "lowercase")]
+pub enum LoadWindowStatus {
+    /// The warm-up has not begun yet
+    Planned,
+    /// Autoscalers were told to scale the pool up; it has not reached its target yet
+    Warming,
+    /// The pool reached its target before the window started
+    Ready,
+    /// Scheduled jobs of the window are no longer held back
+    Released,
+    /// Cancelled by an admin before it was released
+    Cancelled,
+}
+
+impl LoadWindowStatus {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            LoadWindowStatus::Planned => "planned",
+            LoadWindowStatus::Warming => "warming",
+            LoadWindowStatus::Ready => "ready",
+            LoadWindowStatus::Released => "released",
+            LoadWindowStatus::Cancelled => "cancelled",
+        }
+    }
+
+    pub fn parse(s: &str) -> Option<Self> {
+        match s.to_lowercase().as_str() {
+            "planned" => Some(LoadWindowStatus::Planned),
+            "warming" => Some(LoadWindowStatus::Warming),
+            "ready" => Some(LoadWindowStatus::Ready),
+            "released" => Some(LoadWindowStatus::Released),
+            "cancelled" => Some(LoadWindowStatus::Cancelled),
+            _ => None,
+        }
+    }
+
+    /// Whether the window is still checked: neither released nor cancelled
+    pub fn is_open(&self) -> bool {
+        !matches!(self, LoadWindowStatus::Released | LoadWindowStatus::Cancelled)
+    }
+}
+
+/// Outcome of checking a load window against the runners its pool has
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct LoadWindowStep {
+    pub status: LoadWindowStatus,
+    /// The warm-up begins with this check, so autoscalers are to be told now
+    pub notify: bool,
+    /// Scheduled jobs of the window's job types coming due before this time are to be
+    /// pushed back to it, as the pool is not ready when they would run
+    pub hold_until: Option<DateTime<Utc>>,
+}
+
+/// Upcoming load spike declared by an admin, e.g. a nightly batch at 02:00
+///
+/// From `warm_up_minutes` before the start, autoscalers are asked for the target runner
+/// count of the pool. Scheduled jobs of the window's job types coming due are held back
+/// until the pool has the target count of active runners, or until `max_hold_minutes`
+/// after the start, after which they run with whatever capacity there is. A window
+/// without a pool counts every runner; one without job types holds back no jobs.
+#[derive(Debug, Clone, Serialize, Deserialize, Queryable, Identifiable, Selectable)]
+#[diesel(table_name = load_windows)]
+#[diesel(check_for_backend(diesel::pg::Pg))]
+pub struct LoadWindow {
+    pub id: Uuid,
+    pub name: String,
+    /// Name the runners of the pool register under; None for the whole fleet
+    pub pool: Option<String>,
+    pub job_type_ids: Vec<Uuid>,
+    pub target_runners: i32,
+    pub starts_at: DateTime<Utc>,
+    pub ends_at: DateTime<Utc>,
+    pub warm_up_minutes: i32,
+    pub max_hold_minutes: i32,
+    pub status: String,
+    /// Active runners of the pool at the last check
+    pub active_runners: Option<i32>,
+    pub notified_at: Option<DateTime<Utc>>,
+    pub ready_at: Option<DateTime<Utc>>,
+    pub released_at: Option<DateTime<Utc>>,
+    pub created_by: String,
+    pub created_at: DateTime<Utc>,
+    pub updated_at: DateTime<Utc>,
+}
+
+impl LoadWindow {
+    /// Typed status of the window
+    pub fn window_status(&self) -> LoadWindowStatus {
+        LoadWindowStatus::parse(&self.status).unwrap_or(LoadWindowStatus::Planned)
+    }
+
+    /// When autoscalers are told to scale the pool up
+    pub fn warm_up_at(&self) -> DateTime<Utc> {
+        self.starts_at - Duration::minutes(self.warm_up_minutes.max(0) as i64)
+    }
+
+    /// When held jobs are released even if the pool is not ready
+    pub fn give_up_at(&self) -> DateTime<Utc> {
+        self.starts_at + Duration::minutes(self.max_hold_minutes.max(0) as i64)
+    }
+
+    /// Whether a runner registered under `name` belongs to the window's pool
+    pub fn includes_runner(&self, name: &str) -> bool {
+        self.pool.as_deref().is_none_or(|pool| pool == name)
+    }
+
+    /// Where the window stands at `now` with the given active runners in its pool,
+    /// the next check being at `next_check`
+    pub fn step(&self, now: DateTime<Utc>, active_runners: u32, next_check: DateTime<Utc>) -> LoadWindowStep {
+        let current = self.window_status();
+        let unchanged = LoadWindowStep { status: current, notify: false, hold_until: None };
+        if !current.is_open() {
+            return unchanged;
+        }
+        if now >= self.ends_at {
+            return LoadWindowStep { status: LoadWindowStatus::Released, ..unchanged };
+        }
+        if now < self.warm_up_at() {
+            return unchanged;
+        }
+
+        let notify = current == LoadWindowStatus::Planned;
+        let status = if active_runners >= self.target_runners.max(0) as u32 {
+            if now >= self.starts_at { LoadWindowStatus::Released } else { LoadWindowStatus::Ready }
+        } else if now >= self.give_up_at() {
+            LoadWindowStatus::Released
+        } else {
+            LoadWindowStatus::Warming
+        };
+
+        // Jobs coming due before the next check would run on a pool that is not ready
+        let hold = status == LoadWindowStatus::Warming && next_check > self.starts_at && !self.job_type_ids.is_empty();
+        LoadWindowStep { status, notify, hold_until: hold.then_some(next_check) }
+    }
+}
+
+// For DB insertion with Diesel
+#[derive(Debug, Clone, Serialize, Deserialize, Insertable)]
+#[diesel(table_name = load_windows)]
+pub struct NewLoadWindow {
+    pub id: Uuid,
+    pub name: String,
+    pub pool: Option<String>,
+    pub job_type_ids: Vec<Uuid>,
+    pub target_runners: i32,
+    pub starts_at: DateTime<Utc>,
+    pub ends_at: DateTime<Utc>,
+    pub warm_up_minutes: i32,
+    pub max_hold_minutes: i32,
+    pub created_by: String,
+}
@@ -1,17 +1,85 @@
 pub mod customer;
 pub mod wallet;
 pub mod job;
+pub mod job_cost;
 pub mod job_type;
+pub mod job_error;
 pub mod reseller;
 pub mod project;
 pub mod runner;
+pub mod webhook;
+pub mod notification;
+pub mod job_log;
+pub mod job_attempt;
+pub mod job_template;
+pub mod submission_window;
+pub mod pipeline;
+pub mod wallet_adjustment;
+pub mod audit;
+pub mod billing_period;
+pub mod feature_flag;
+pub mod redaction;
+pub mod spending_alert;
+pub mod config_document;
+pub mod partition;
+pub mod request_signature;
+pub mod provider;
+pub mod job_import;
+pub mod security_event;
+pub mod fixture;
+pub mod dry_run;
+pub mod input_contract;
+pub mod billing_export;
+pub mod job_event;
+pub mod job_usage;
+pub mod load_window;
+pub mod submission;
+pub mod dependency;
+pub mod replay;
+pub mod reseller_invoice;
+pub mod purge;
+pub mod webhook_template;
+pub mod response_policy;
+pub mod job_artifact;
+pub mod ledger;
+pub mod pii;
 
 // Re-export common types
 pub use customer::Customer;
 pub use wallet::Wallet;
 pub use job::{Job, JobStatus};
+pub use job_cost::CostBreakdown;
 pub use job_type::JobType;
+pub use job_error::{JobError, ErrorCategory};
 pub use reseller::Reseller;
 pub use project::Project;
-pub use runner::{Runner, RunnerStatus};
+pub use runner::{Runner, RunnerStatus, RunnerHealthCheck};
 pub use wallet::WalletTransaction;
+pub use webhook::{CustomerWebhook, WebhookEventType};
+pub use notification::{NotificationDelivery, DeliveryChannel, DeliveryStatus, NotificationMode, NotificationPreference};
+pub use job_log::{JobLog, LogLevel};
+pub use job_attempt::{JobAttempt, AttemptStatus};
+pub use job_template::{JobTemplate, TemplateVariable, VariableType, TemplateError};
+pub use submission_window::SubmissionWindow;
+pub use pipeline::{Pipeline, PipelineDefinition, PipelineRun, PipelineRunStep, PipelineError};
+pub use wallet_adjustment::{WalletAdjustment, AdjustmentKind, AdjustmentStatus, AdjustmentError};
+pub use audit::AuditEntry;
+pub use billing_period::{BillingPeriod, Invoice, LedgerError};
+pub use feature_flag::{FeatureFlag, FeatureFlagError};
+pub use redaction::{RedactionRule, RedactionError, UnredactedOutput};
+pub use spending_alert::{SpendingAlert, AnomalyKind};
+pub use config_document::{ConfigDocument, ConfigDocumentError};
+pub use partition::PartitionedTable;
+pub use request_signature::{SignedRequest, SignatureError};
+pub use provider::{Provider, ProviderStatus};
+pub use job_import::{JobImport, JobImportRow, ImportFormat, ImportStatus, ImportError};
+pub use security_event::{SecurityEvent, SecurityEventKind, AuthSubject};
+pub use fixture::{FixtureBundle, FixtureSpec, FixtureError};
+pub use input_contract::{InputContract, InputField, InputFieldType};
+pub use billing_export::{BillingExport, ExportStatus, BillingReconciliation};
+pub use job_event::JobLifecycleEvent;
+pub use job_usage::{JobUsage, ResourceUsage, UsageSummary};
+pub use load_window::{LoadWindow, LoadWindowStatus};
+pub use submission::{Submission, SubmissionSource, SubmissionSummary};
+pub use dependency::{Blocker, BlockerKind};
+pub use replay::{ReplayCase, ReplayComparison, ReplayOutcome, ReplayReport};
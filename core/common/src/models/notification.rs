@@ -0,0 +1,207 @@
+use serde::{Deserialize, Serialize};
+use uuid::Uuid;
+use chrono::{DateTime, Utc};
+use diesel::prelude::*;
+
+use crate::diesel_schema::{notification_deliveries, notification_preferences};
+use crate::models::webhook::WebhookEventType;
+
+/// Channel an outgoing notification is delivered through
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq, Eq)]
+pub enum DeliveryChannel {
+    Webhook,
+    Email,
+}
+
+impl DeliveryChannel {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            DeliveryChannel::Webhook => "webhook",
+            DeliveryChannel::Email => "email",
+        }
+    }
+
+    pub fn parse(s: &str) -> Option<Self> {
+        match s.to_lowercase().as_str() {
+            "webhook" => Some(DeliveryChannel::Webhook),
+            "email" => Some(DeliveryChannel::Email),
+            _ => None,
+        }
+    }
+}
+
+/// State of a notification delivery
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq, Eq)]
+pub enum DeliveryStatus {
+    /// Created but not attempted yet
+    Pending,
+    /// Held back for the customer's next digest
+    Queued,
+    Succeeded,
+    /// Last attempt failed; retried automatically while `next_retry_at` is set
+    Failed,
+}
+
+impl DeliveryStatus {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            DeliveryStatus::Pending => "pending",
+            DeliveryStatus::Queued => "queued",
+            DeliveryStatus::Succeeded => "succeeded",
+            DeliveryStatus::Failed => "failed",
+        }
+    }
+
+    pub fn parse(s: &str) -> Option<Self> {
+        match s.to_lowercase().as_str() {
+            "pending" => Some(DeliveryStatus::Pending),
+            "queued" => Some(DeliveryStatus::Queued),
+            "succeeded" => Some(DeliveryStatus::Succeeded),
+            "failed" => Some(DeliveryStatus::Failed),
+            _ => None,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, Queryable, Identifiable, Selectable)]
+#[diesel(table_name = notification_deliveries)]
+#[diesel(check_for_backend(diesel::pg::Pg))]
+pub struct NotificationDelivery {
+    pub id: Uuid,
+    pub customer_id: Uuid,
+    /// Target webhook endpoint, None for email or if the endpoint was deleted
+    pub webhook_id: Option<Uuid>,
+    pub channel: String,
+    pub event_type: String,
+    /// Exact body that was sent, re-sent verbatim on retries
+    pub payload: String,
+    pub status: String,
+    pub status_code: Option<i32>,
+    pub latency_ms: Option<i32>,
+    pub last_error: Option<String>,
+    pub retry_count: i32,
+    pub next_retry_at: Option<DateTime<Utc>>,
+    pub created_at: Option<DateTime<Utc>>,
+    pub updated_at: Option<DateTime<Utc>>,
+}
+
+impl NotificationDelivery {
+    pub fn status(&self) -> Option<DeliveryStatus> {
+        DeliveryStatus::parse(&self.status)
+    }
+
+    pub fn channel(&self) -> Option<DeliveryChannel> {
+        DeliveryChannel::parse(&self.channel)
+    }
+}
+
+// For DB insertion with Diesel
+#[derive(Debug, Clone, Serialize, Deserialize, Insertable)]
+#[diesel(table_name = notification_deliveries)]
+pub struct NewNotificationDelivery {
+    pub id: Uuid,
+    pub customer_id: Uuid,
+    pub webhook_id: Option<Uuid>,
+    pub channel: String,
+    pub event_type: String,
+    pub payload: String,
+    pub status: String,
+}
+
+/// Outcome of a single delivery attempt, applied to an existing delivery record
+#[derive(Debug, Clone)]
+pub struct DeliveryAttempt {
+    pub status: DeliveryStatus,
+    pub status_code: Option<i32>,
+    pub latency_ms: Option<i32>,
+    pub last_error: Option<String>,
+    pub retry_count: i32,
+    pub next_retry_at: Option<DateTime<Utc>>,
+}
+
+/// How a customer wants to hear about an event on a channel
+#[derive(Debug, Clone, Copy, Default, Serialize, Deserialize, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+pub enum NotificationMode {
+    /// Sent as soon as the event happens
+    #[default]
+    Immediate,
+    /// Held back and sent with the other events of the period in one digest
+    Digest,
+    /// Not sent
+    Off,
+}
+
+impl NotificationMode {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            NotificationMode::Immediate => "immediate",
+            NotificationMode::Digest => "digest",
+            NotificationMode::Off => "off",
+        }
+    }
+
+    pub fn parse(s: &str) -> Option<Self> {
+        match s.to_lowercase().as_str() {
+            "immediate" => Some(NotificationMode::Immediate),
+            "digest" => Some(NotificationMode::Digest),
+            "off" => Some(NotificationMode::Off),
+            _ => None,
+        }
+    }
+}
+
+/// A customer's choice of how to hear about one event type on one channel
+#[derive(Debug, Clone, Serialize, Deserialize, Queryable, Identifiable, Selectable)]
+#[diesel(table_name = notification_preferences)]
+#[diesel(check_for_backend(diesel::pg::Pg))]
+pub struct NotificationPreference {
+    pub id: Uuid,
+    pub customer_id: Uuid,
+    /// Event type name (e.g. "job.succeeded")
+    pub event_type: String,
+    pub channel: String,
+    pub mode: String,
+    pub created_at: Option<DateTime<Utc>>,
+    pub updated_at: Option<DateTime<Utc>>,
+}
+
+impl NotificationPreference {
+    pub fn mode(&self) -> Option<NotificationMode> {
+        NotificationMode::parse(&self.mode)
+    }
+}
+
+// For DB insertion with Diesel
+#[derive(Debug, Clone, Serialize, Deserialize, Insertable)]
+#[diesel(table_name = notification_preferences)]
+pub struct NewNotificationPreference {
+    pub id: Uuid,
+    pub customer_id: Uuid,
+    pub event_type: String,
+    pub channel: String,
+    pub mode: String,
+}
+
+impl NewNotificationPreference {
+    pub fn new(customer_id: Uuid, event_type: WebhookEventType, channel: DeliveryChannel, mode: NotificationMode) -> Self {
+        Self {
+            id: Uuid::new_v4(),
+            customer_id,
+            event_type: event_type.as_str().to_string(),
+            channel: channel.as_str().to_string(),
+            mode: mode.as_str().to_string(),
+        }
+    }
+}
+
+/// Mode an event is sent in on a channel, given a customer's preferences
+///
+/// Events without a preference are sent right away, so customers keep receiving
+/// everything until they opt out.
+pub fn mode_for(preferences: &[NotificationPreference], event_type: WebhookEventType, channel: DeliveryChannel) -> NotificationMode {
+    preferences.iter()
+        .find(|preference| preference.event_type == event_type.as_str() && preference.channel == channel.as_str())
+        .and_then(NotificationPreference::mode)
+        .unwrap_or_default()
+}
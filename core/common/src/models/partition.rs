@@ -0,0 +1,85 @@
+use chrono::{DateTime, Datelike, Months, NaiveDate, Utc};
+use serde::{Deserialize, Serialize};
+
+/// Table partitioned by month of `created_at`
+///
+/// Each month is stored in its own partition, e.g. `jobs_y2025m04`; rows of months
+/// without a partition are kept in the table's default partition, e.g. `jobs_default`.
+/// Only the `public` schema is partitioned: reseller schemas keep plain tables.
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq, Eq, Hash)]
+#[serde(rename_all = "snake_case")]
+pub enum PartitionedTable {
+    Jobs,
+    WalletTransactions,
+}
+
+impl PartitionedTable {
+    /// All partitioned tables
+    pub const ALL: [PartitionedTable; 2] = [PartitionedTable::Jobs, PartitionedTable::WalletTransactions];
+
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            PartitionedTable::Jobs => "jobs",
+            PartitionedTable::WalletTransactions => "wallet_transactions",
+        }
+    }
+
+    /// Name of the partition holding the rows of months without a partition
+    pub fn default_partition(&self) -> String {
+        format!("{}_default", self.as_str())
+    }
+
+    /// Name of the partition of the month starting on `month`
+    pub fn partition_name(&self, month: NaiveDate) -> String {
+        format!("{}_y{:04}m{:02}", self.as_str(), month.year(), month.month())
+    }
+
+    /// Month of a partition named by `partition_name`, or None for other tables
+    pub fn parse_partition_name(&self, name: &str) -> Option<NaiveDate> {
+        let suffix = name.strip_prefix(self.as_str())?.strip_prefix("_y")?;
+        let (year, month) = suffix.split_once('m')?;
+        if year.len() != 4 || month.len() != 2 {
+            return None;
+        }
+        NaiveDate::from_ymd_opt(year.parse().ok()?, month.parse().ok()?, 1)
+    }
+}
+
+/// First day of the month `at` falls in
+pub fn month_of(at: DateTime<Utc>) -> NaiveDate {
+    at.date_naive().with_day(1).unwrap_or(at.date_naive())
+}
+
+/// First day of the month following `month`
+pub fn next_month(month: NaiveDate) -> NaiveDate {
+    month.checked_add_months(Months::new(1)).unwrap_or(NaiveDate::MAX)
+}
+
+/// Months that should have a partition at `now`: the current month and the
+/// `premake_months` following it, so rows never land in the default partition
+pub fn months_to_create(now: DateTime<Utc>, premake_months: u32) -> Vec<NaiveDate> {
+    let mut months = vec![month_of(now)];
+    for _ in 0..premake_months {
+        let last = months[months.len() - 1];
+        months.push(next_month(last));
+    }
+    months
+}
+
+/// Partitioned months whose rows are past a retention of `retention_months` at `now`
+///
+/// Rows are kept for at least `retention_months` full months: a month's partition is
+/// only dropped once that many months have passed since the month ended.
+pub fn months_past_retention(months: &[NaiveDate], now: DateTime<Utc>, retention_months: u32) -> Vec<NaiveDate> {
+    let current = month_of(now);
+    let mut expired: Vec<NaiveDate> = months.iter()
+        .copied()
+        .filter(|month| {
+            next_month(*month)
+                .checked_add_months(Months::new(retention_months))
+                .is_some_and(|kept_until| kept_until <= current)
+        })
+        .collect();
+    expired.sort();
+    expired
+}
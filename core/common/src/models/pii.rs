@@ -0,0 +1,246 @@
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+
+use crate::models::redaction::REDACTED;
+
+/// Kind of personal data found in a job's input
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, PartialOrd, Ord, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum PiiKind {
+    Email,
+    /// A number of 13 to 19 digits passing the Luhn check, optionally grouped by spaces or dashes
+    CreditCard,
+}
+
+impl PiiKind {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            PiiKind::Email => "email",
+            PiiKind::CreditCard => "credit_card",
+        }
+    }
+}
+
+/// What to do with a job whose input contains personal data
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum PiiPolicy {
+    /// Refuse the job
+    Reject,
+    /// Replace the personal data with [`REDACTED`] before the job is stored
+    Mask,
+    /// Accept the job as it is, with a warning in its log
+    Flag,
+}
+
+impl PiiPolicy {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            PiiPolicy::Reject => "reject",
+            PiiPolicy::Mask => "mask",
+            PiiPolicy::Flag => "flag",
+        }
+    }
+
+    pub fn parse(s: &str) -> Option<Self> {
+        match s.to_lowercase().as_str() {
+            "reject" => Some(PiiPolicy::Reject),
+            "mask" => Some(PiiPolicy::Mask),
+            "flag" => Some(PiiPolicy::Flag),
+            _ => None,
+        }
+    }
+}
+
+/// Personal data found in a string of a job's input
+///
+/// The value itself is not kept, so findings can be logged and returned safely.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct PiiFinding {
+    /// Where the string is in the input, e.g. `$.customer.contact` or `$.rows[2]`
+    pub path: String,
+    pub kind: PiiKind,
+}
+
+/// Find the personal data in every string of a job's input
+pub fn scan(input: &Value) -> Vec<PiiFinding> {
+    let mut findings = Vec::new();
+    visit(input, "$".to_string(), &mut |path, text| {
+        findings.extend(matches(text).into_iter().map(|(kind, _)| PiiFinding { path: path.to_string(), kind }));
+    });
+    findings
+}
+
+/// Replace the personal data in every string of a job's input with [`REDACTED`],
+/// returning what was replaced
+pub fn mask(input: &mut Value) -> Vec<PiiFinding> {
+    let mut findings = Vec::new();
+    visit_mut(input, "$".to_string(), &mut |path, text| {
+        let found = matches(text);
+        if found.is_empty() {
+            return;
+        }
+        let mut masked = String::with_capacity(text.len());
+        let mut end = 0;
+        for (kind, range) in found {
+            masked.push_str(&text[end..range.start]);
+            masked.push_str(REDACTED);
+            end = range.end;
+            findings.push(PiiFinding { path: path.to_string(), kind });
+        }
+        masked.push_str(&text[end..]);
+        *text = masked;
+    });
+    findings
+}
+
+/// Path of a member below `path`, quoted when it is not a plain name
+fn child_path(path: &str, name: &str) -> String {
+    if !name.is_empty() && name.chars().all(|c| c.is_ascii_alphanumeric() || c == '_') {
+        format!("{}.{}", path, name)
+    } else {
+        format!("{}['{}']", path, name)
+    }
+}
+
+fn visit(value: &Value, path: String, on_string: &mut dyn FnMut(&str, &str)) {
+    match value {
+        Value::String(text) => on_string(&path, text),
+        Value::Array(items) => {
+            for (index, item) in items.iter().enumerate() {
+                visit(item, format!("{}[{}]", path, index), on_string);
+            }
+        }
+        Value::Object(map) => {
+            for (name, child) in map {
+                visit(child, child_path(&path, name), on_string);
+            }
+        }
+        _ => {}
+    }
+}
+
+fn visit_mut(value: &mut Value, path: String, on_string: &mut dyn FnMut(&str, &mut String)) {
+    match value {
+        Value::String(text) => on_string(&path, text),
+        Value::Array(items) => {
+            for (index, item) in items.iter_mut().enumerate() {
+                visit_mut(item, format!("{}[{}]", path, index), on_string);
+            }
+        }
+        Value::Object(map) => {
+            for (name, child) in map.iter_mut() {
+                visit_mut(child, child_path(&path, name), on_string);
+            }
+        }
+        _ => {}
+    }
+}
+
+/// Personal data in a string, with the byte range it spans, in order of appearance
+fn matches(text: &str) -> Vec<(PiiKind, std::ops::Range<usize>)> {
+    let mut found: Vec<_> = emails(text).into_iter().map(|range| (PiiKind::Email, range))
+        .chain(card_numbers(text).into_iter().map(|range| (PiiKind::CreditCard, range)))
+        .collect();
+    found.sort_by_key(|(_, range)| range.start);
+    // A card number inside an email address is part of the address
+    let mut end = 0;
+    found.retain(|(_, range)| {
+        let keep = range.start >= end;
+        if keep {
+            end = range.end;
+        }
+        keep
+    });
+    found
+}
+
+fn is_local_char(c: u8) -> bool {
+    c.is_ascii_alphanumeric() || matches!(c, b'.' | b'_' | b'%' | b'+' | b'-')
+}
+
+fn is_domain_char(c: u8) -> bool {
+    c.is_ascii_alphanumeric() || matches!(c, b'.' | b'-')
+}
+
+/// Byte ranges of email addresses: a local part, `@` and a domain with a top-level
+/// domain of at least two letters
+fn emails(text: &str) -> Vec<std::ops::Range<usize>> {
+    let bytes = text.as_bytes();
+    let mut found = Vec::new();
+    let mut from = 0;
+    while let Some(offset) = text[from..].find('@') {
+        let at = from + offset;
+        from = at + 1;
+
+        let start = bytes[..at].iter().rposition(|c| !is_local_char(*c)).map_or(0, |i| i + 1);
+        let mut end = at + 1 + bytes[at + 1..].iter().position(|c| !is_domain_char(*c)).unwrap_or(bytes.len() - at - 1);
+        // A sentence may end right after the address
+        while end > at + 1 && matches!(bytes[end - 1], b'.' | b'-') {
+            end -= 1;
+        }
+        if start == at || found.last().is_some_and(|last: &std::ops::Range<usize>| last.end > start) {
+            continue;
+        }
+        let domain = &text[at + 1..end];
+        let valid_domain = domain.rsplit_once('.').is_some_and(|(name, tld)| {
+            !name.is_empty() && !name.starts_with('.') && !name.contains("..")
+                && tld.len() >= 2 && tld.bytes().all(|c| c.is_ascii_alphabetic())
+        });
+        if valid_domain {
+            found.push(start..end);
+            from = end;
+        }
+    }
+    found
+}
+
+/// Byte ranges of card numbers: runs of 13 to 19 digits, each group separated by at
+/// most one space or dash, that pass the Luhn check
+fn card_numbers(text: &str) -> Vec<std::ops::Range<usize>> {
+    let bytes = text.as_bytes();
+    let mut found = Vec::new();
+    let mut i = 0;
+    while i < bytes.len() {
+        // Digits glued to letters or other digits are part of something else, e.g. an ID
+        if !bytes[i].is_ascii_digit() || (i > 0 && bytes[i - 1].is_ascii_alphanumeric()) {
+            i += 1;
+            continue;
+        }
+        let start = i;
+        let mut digits = Vec::new();
+        let mut end = i;
+        while i < bytes.len() {
+            if bytes[i].is_ascii_digit() {
+                digits.push(bytes[i] - b'0');
+                i += 1;
+                end = i;
+            } else if matches!(bytes[i], b' ' | b'-') && bytes.get(i + 1).is_some_and(u8::is_ascii_digit) {
+                i += 1;
+            } else {
+                break;
+            }
+        }
+        let glued = bytes.get(end).is_some_and(u8::is_ascii_alphanumeric);
+        if (13..=19).contains(&digits.len()) && !glued && luhn_valid(&digits) {
+            found.push(start..end);
+        }
+        i = end.max(start + 1);
+    }
+    found
+}
+
+/// Whether digits pass the Luhn checksum used by payment card numbers
+pub fn luhn_valid(digits: &[u8]) -> bool {
+    let sum: u32 = digits.iter().rev().enumerate()
+        .map(|(position, digit)| {
+            let digit = u32::from(*digit);
+            match position % 2 {
+                0 => digit,
+                _ if digit * 2 > 9 => digit * 2 - 9,
+                _ => digit * 2,
+            }
+        })
+        .sum();
+    !digits.is_empty() && sum % 10 == 0
+}
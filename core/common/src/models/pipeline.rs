@@ -0,0 +1,594 @@
+use std::collections::{HashMap, HashSet};
+
+use serde::{Deserialize, Serialize};
+use serde_json::{Map, Value};
+use uuid::Uuid;
+use chrono::{DateTime, Utc};
+use diesel::prelude::*;
+use thiserror::Error;
+
+use crate::diesel_schema::{pipelines, pipeline_runs, pipeline_run_steps};
+use crate::models::job::PriorityLevel;
+
+/// What happens to the rest of a run when a step's job fails
+#[derive(Debug, Clone, Copy, Default, Serialize, Deserialize, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+pub enum FailurePolicy {
+    /// Stop the run: steps that have not started are skipped and the run fails
+    #[default]
+    Fail,
+    /// Skip the steps depending on this one; independent steps still run, the run fails
+    SkipDependents,
+    /// Ignore the failure: dependents run and see a null output of this step
+    Continue,
+}
+
+/// How the jobs of a run's steps get their priority from the run
+#[derive(Debug, Clone, Copy, Default, Serialize, Deserialize, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+pub enum PriorityPolicy {
+    /// Every step runs at the priority the run was started with
+    #[default]
+    Inherit,
+    /// Each step runs one level above the steps it depends on, up to critical, so
+    /// runs under way finish before other work at the run's priority
+    Escalate,
+}
+
+/// A single step of a pipeline definition
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+#[serde(deny_unknown_fields)]
+pub struct PipelineStep {
+    /// Name other steps refer to the step by, unique within the pipeline
+    pub name: String,
+    /// ID or name of the job type the step runs
+    pub job_type: String,
+    /// Steps that must finish before this one starts
+    #[serde(default)]
+    pub depends_on: Vec<String>,
+    /// input_data of the step's job; strings may reference `{{ input.<path> }}` and
+    /// `{{ steps.<step>.output.<path> }}`
+    #[serde(default = "empty_object")]
+    pub input: Value,
+    #[serde(default)]
+    pub on_failure: FailurePolicy,
+}
+
+fn empty_object() -> Value {
+    Value::Object(Map::new())
+}
+
+/// Pipeline definition as uploaded in YAML
+///
+/// ```yaml
+/// name: invoice-processing
+/// steps:
+///   - name: ocr
+///     job_type: ocr
+///     input:
+///       document: "{{ input.document_url }}"
+///   - name: totals
+///     job_type: extract-totals
+///     depends_on: [ocr]
+///     on_failure: continue
+///     input:
+///       text: "{{ steps.ocr.output.text }}"
+/// priority_policy: escalate
+/// ```
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+#[serde(deny_unknown_fields)]
+pub struct PipelineDefinition {
+    pub name: String,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub description: Option<String>,
+    #[serde(default)]
+    pub priority_policy: PriorityPolicy,
+    pub steps: Vec<PipelineStep>,
+}
+
+/// Reasons a pipeline definition cannot be stored
+#[derive(Debug, Error, PartialEq)]
+pub enum PipelineError {
+    #[error("Invalid pipeline YAML: {0}")]
+    InvalidYaml(String),
+
+    #[error("Invalid pipeline definition: {0}")]
+    InvalidDefinition(String),
+
+    #[error("Step {step} depends on unknown step {dependency}")]
+    UnknownDependency { step: String, dependency: String },
+
+    #[error("Pipeline steps form a cycle through step {0}")]
+    Cycle(String),
+
+    #[error("Step {step} uses invalid reference {reference}")]
+    InvalidReference { step: String, reference: String },
+
+    #[error("Step {step} uses the output of {dependency}, which it does not depend on")]
+    UnavailableOutput { step: String, dependency: String },
+}
+
+/// State of a step within a run
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq, Eq)]
+pub enum StepStatus {
+    /// Waiting for its dependencies
+    Pending,
+    /// Its job was submitted and has not finished
+    Running,
+    Succeeded,
+    Failed,
+    /// Never started because a dependency failed or the run stopped
+    Skipped,
+}
+
+impl StepStatus {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            StepStatus::Pending => "pending",
+            StepStatus::Running => "running",
+            StepStatus::Succeeded => "succeeded",
+            StepStatus::Failed => "failed",
+            StepStatus::Skipped => "skipped",
+        }
+    }
+
+    pub fn parse(s: &str) -> Option<Self> {
+        match s.to_lowercase().as_str() {
+            "pending" => Some(StepStatus::Pending),
+            "running" => Some(StepStatus::Running),
+            "succeeded" => Some(StepStatus::Succeeded),
+            "failed" => Some(StepStatus::Failed),
+            "skipped" => Some(StepStatus::Skipped),
+            _ => None,
+        }
+    }
+}
+
+/// State of a pipeline run
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq, Eq)]
+pub enum PipelineRunStatus {
+    Running,
+    Succeeded,
+    Failed,
+}
+
+impl PipelineRunStatus {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            PipelineRunStatus::Running => "running",
+            PipelineRunStatus::Succeeded => "succeeded",
+            PipelineRunStatus::Failed => "failed",
+        }
+    }
+
+    pub fn parse(s: &str) -> Option<Self> {
+        match s.to_lowercase().as_str() {
+            "running" => Some(PipelineRunStatus::Running),
+            "succeeded" => Some(PipelineRunStatus::Succeeded),
+            "failed" => Some(PipelineRunStatus::Failed),
+            _ => None,
+        }
+    }
+}
+
+/// Changes to apply to a run, decided from the current status of its steps
+#[derive(Debug, Default, PartialEq)]
+pub struct RunProgress {
+    /// Steps whose dependencies are satisfied, to be submitted now
+    pub start: Vec<String>,
+    /// Steps that will never run
+    pub skip: Vec<String>,
+    /// Final status once no step is pending or running
+    pub outcome: Option<PipelineRunStatus>,
+}
+
+impl PipelineDefinition {
+    /// Parse and validate a YAML definition
+    pub fn parse(yaml: &str) -> Result<Self, PipelineError> {
+        let definition: Self = serde_yaml::from_str(yaml)
+            .map_err(|e| PipelineError::InvalidYaml(e.to_string()))?;
+        definition.validate()?;
+        Ok(definition)
+    }
+
+    /// Check step names, dependencies and references, and that the steps form a DAG
+    pub fn validate(&self) -> Result<(), PipelineError> {
+        if self.name.trim().is_empty() {
+            return Err(PipelineError::InvalidDefinition("name must not be empty".to_string()));
+        }
+        if self.steps.is_empty() {
+            return Err(PipelineError::InvalidDefinition("a pipeline needs at least one step".to_string()));
+        }
+
+        let mut names = HashSet::new();
+        for step in &self.steps {
+            if !is_step_name(&step.name) {
+                return Err(PipelineError::InvalidDefinition(format!("invalid step name: {}", step.name)));
+            }
+            if !names.insert(step.name.as_str()) {
+                return Err(PipelineError::InvalidDefinition(format!("duplicate step name: {}", step.name)));
+            }
+        }
+        for step in &self.steps {
+            if let Some(dependency) = step.depends_on.iter().find(|d| !names.contains(d.as_str())) {
+                return Err(PipelineError::UnknownDependency { step: step.name.clone(), dependency: dependency.clone() });
+            }
+        }
+
+        self.topological_order()?;
+
+        for step in &self.steps {
+            let ancestors = self.ancestors(&step.name);
+            let mut references = Vec::new();
+            collect_references(&step.input, &mut references);
+            for reference in references {
+                match parse_reference(&reference) {
+                    Some(Reference::Input(_)) => {}
+                    Some(Reference::Output(dependency, _)) if ancestors.contains(dependency) => {}
+                    Some(Reference::Output(dependency, _)) => {
+                        return Err(PipelineError::UnavailableOutput { step: step.name.clone(), dependency: dependency.to_string() });
+                    }
+                    None => {
+                        return Err(PipelineError::InvalidReference { step: step.name.clone(), reference });
+                    }
+                }
+            }
+        }
+
+        Ok(())
+    }
+
+    /// Step with the given name
+    pub fn step(&self, name: &str) -> Option<&PipelineStep> {
+        self.steps.iter().find(|step| step.name == name)
+    }
+
+    /// Steps ordered so that each one comes after its dependencies
+    pub fn topological_order(&self) -> Result<Vec<&PipelineStep>, PipelineError> {
+        let mut ordered: Vec<&PipelineStep> = Vec::with_capacity(self.steps.len());
+        let mut placed: HashSet<&str> = HashSet::new();
+        while ordered.len() < self.steps.len() {
+            let ready: Vec<&PipelineStep> = self.steps.iter()
+                .filter(|step| !placed.contains(step.name.as_str()))
+                .filter(|step| step.depends_on.iter().all(|d| placed.contains(d.as_str())))
+                .collect();
+            if ready.is_empty() {
+                let stuck = self.steps.iter().find(|step| !placed.contains(step.name.as_str())).map(|step| step.name.clone());
+                return Err(PipelineError::Cycle(stuck.unwrap_or_default()));
+            }
+            for step in ready {
+                placed.insert(step.name.as_str());
+                ordered.push(step);
+            }
+        }
+        Ok(ordered)
+    }
+
+    /// Priority of the job of a step in a run started with `run_priority`
+    ///
+    /// Under the escalate policy a step is raised one level for each step in the
+    /// longest chain of dependencies leading to it.
+    pub fn step_priority(&self, name: &str, run_priority: PriorityLevel) -> PriorityLevel {
+        let levels = match self.priority_policy {
+            PriorityPolicy::Inherit => 0,
+            PriorityPolicy::Escalate => self.depth(name),
+        };
+        let critical = PriorityLevel::Critical.as_i32();
+        PriorityLevel::from_i32((run_priority.as_i32() + levels as i32).min(critical))
+    }
+
+    /// Length of the longest chain of dependencies leading to a step
+    fn depth(&self, name: &str) -> usize {
+        let Ok(order) = self.topological_order() else { return 0 };
+        let mut depths: HashMap<&str, usize> = HashMap::new();
+        for step in order {
+            let depth = step.depends_on.iter()
+                .map(|dependency| depths.get(dependency.as_str()).map_or(0, |depth| depth + 1))
+                .max()
+                .unwrap_or(0);
+            if step.name == name {
+                return depth;
+            }
+            depths.insert(step.name.as_str(), depth);
+        }
+        0
+    }
+
+    /// Names of all steps a step transitively depends on
+    fn ancestors(&self, name: &str) -> HashSet<&str> {
+        let mut ancestors = HashSet::new();
+        let mut queue: Vec<&str> = vec![name];
+        while let Some(current) = queue.pop() {
+            for dependency in self.step(current).map(|step| step.depends_on.as_slice()).unwrap_or_default() {
+                if ancestors.insert(dependency.as_str()) {
+                    queue.push(dependency.as_str());
+                }
+            }
+        }
+        ancestors
+    }
+
+    /// Decide which steps to start or skip and whether the run is finished
+    ///
+    /// Steps missing from `statuses` count as pending.
+    pub fn progress(&self, statuses: &HashMap<String, StepStatus>) -> RunProgress {
+        let Ok(order) = self.topological_order() else {
+            return RunProgress { outcome: Some(PipelineRunStatus::Failed), ..RunProgress::default() };
+        };
+        let mut statuses: HashMap<&str, StepStatus> = self.steps.iter()
+            .map(|step| (step.name.as_str(), statuses.get(&step.name).copied().unwrap_or(StepStatus::Pending)))
+            .collect();
+        let failed_with = |statuses: &HashMap<&str, StepStatus>, policy: FailurePolicy| {
+            self.steps.iter().any(|step| step.on_failure == policy && statuses[step.name.as_str()] == StepStatus::Failed)
+        };
+
+        let mut progress = RunProgress::default();
+        let stopped = failed_with(&statuses, FailurePolicy::Fail);
+        for step in order {
+            if statuses[step.name.as_str()] != StepStatus::Pending {
+                continue;
+            }
+            let blocked = stopped || step.depends_on.iter().any(|d| {
+                let dependency = self.step(d).map(|s| s.on_failure).unwrap_or_default();
+                match statuses[d.as_str()] {
+                    StepStatus::Skipped => true,
+                    StepStatus::Failed => dependency != FailurePolicy::Continue,
+                    _ => false,
+                }
+            });
+            let ready = step.depends_on.iter().all(|d| matches!(statuses[d.as_str()], StepStatus::Succeeded | StepStatus::Failed));
+            if blocked {
+                statuses.insert(step.name.as_str(), StepStatus::Skipped);
+                progress.skip.push(step.name.clone());
+            } else if ready {
+                statuses.insert(step.name.as_str(), StepStatus::Running);
+                progress.start.push(step.name.clone());
+            }
+        }
+
+        let unfinished = statuses.values().any(|status| matches!(status, StepStatus::Pending | StepStatus::Running));
+        if !unfinished {
+            let failed = failed_with(&statuses, FailurePolicy::Fail) || failed_with(&statuses, FailurePolicy::SkipDependents);
+            progress.outcome = Some(if failed { PipelineRunStatus::Failed } else { PipelineRunStatus::Succeeded });
+        }
+        progress
+    }
+}
+
+impl PipelineStep {
+    /// Build the input_data of the step's job from the run input and the outputs of
+    /// finished steps
+    ///
+    /// A string consisting of a single reference is replaced by the referenced value,
+    /// keeping its JSON type; references inside longer strings are interpolated.
+    /// Missing values resolve to null.
+    pub fn render_input(&self, run_input: &Value, outputs: &HashMap<String, Value>) -> Value {
+        render_value(&self.input, run_input, outputs)
+    }
+}
+
+/// Whether the name can be used as a step name (letters, digits, `_` and `-`)
+fn is_step_name(name: &str) -> bool {
+    let mut chars = name.chars();
+    chars.next().is_some_and(|c| c.is_ascii_alphabetic() || c == '_')
+        && chars.all(|c| c.is_ascii_alphanumeric() || c == '_' || c == '-')
+}
+
+/// A value a step input refers to
+enum Reference<'a> {
+    /// Path into the run input
+    Input(Vec<&'a str>),
+    /// Step name and path into its output
+    Output(&'a str, Vec<&'a str>),
+}
+
+fn parse_reference(reference: &str) -> Option<Reference<'_>> {
+    let mut segments = reference.split('.');
+    match segments.next()? {
+        "input" => Some(Reference::Input(segments.collect())),
+        "steps" => {
+            let step = segments.next().filter(|step| is_step_name(step))?;
+            (segments.next()? == "output").then(|| Reference::Output(step, segments.collect()))
+        }
+        _ => None,
+    }
+}
+
+/// Split a string into literal text and `{{ reference }}` placeholders
+fn parse_placeholders(s: &str) -> Vec<Segment<'_>> {
+    let mut segments = Vec::new();
+    let mut rest = s;
+    while let Some(start) = rest.find("{{") {
+        let Some(len) = rest[start + 2..].find("}}") else { break };
+        if start > 0 {
+            segments.push(Segment::Text(&rest[..start]));
+        }
+        segments.push(Segment::Reference(rest[start + 2..start + 2 + len].trim()));
+        rest = &rest[start + 4 + len..];
+    }
+    if !rest.is_empty() {
+        segments.push(Segment::Text(rest));
+    }
+    segments
+}
+
+enum Segment<'a> {
+    Text(&'a str),
+    Reference(&'a str),
+}
+
+fn collect_references(value: &Value, references: &mut Vec<String>) {
+    match value {
+        Value::String(s) => {
+            for segment in parse_placeholders(s) {
+                if let Segment::Reference(reference) = segment {
+                    references.push(reference.to_string());
+                }
+            }
+        }
+        Value::Array(items) => items.iter().for_each(|item| collect_references(item, references)),
+        Value::Object(fields) => fields.values().for_each(|field| collect_references(field, references)),
+        _ => {}
+    }
+}
+
+/// Follow a path of object keys and array indices
+fn lookup(value: &Value, path: &[&str]) -> Value {
+    let mut current = value;
+    for segment in path {
+        let next = match current {
+            Value::Object(fields) => fields.get(*segment),
+            Value::Array(items) => segment.parse::<usize>().ok().and_then(|index| items.get(index)),
+            _ => None,
+        };
+        match next {
+            Some(next) => current = next,
+            None => return Value::Null,
+        }
+    }
+    current.clone()
+}
+
+fn resolve(reference: &str, run_input: &Value, outputs: &HashMap<String, Value>) -> Value {
+    match parse_reference(reference) {
+        Some(Reference::Input(path)) => lookup(run_input, &path),
+        Some(Reference::Output(step, path)) => outputs.get(step).map(|output| lookup(output, &path)).unwrap_or(Value::Null),
+        None => Value::Null,
+    }
+}
+
+fn render_value(value: &Value, run_input: &Value, outputs: &HashMap<String, Value>) -> Value {
+    match value {
+        Value::String(s) => {
+            let segments = parse_placeholders(s);
+            if let [Segment::Reference(reference)] = segments.as_slice() {
+                return resolve(reference, run_input, outputs);
+            }
+            let mut rendered = String::with_capacity(s.len());
+            for segment in segments {
+                match segment {
+                    Segment::Text(text) => rendered.push_str(text),
+                    Segment::Reference(reference) => match resolve(reference, run_input, outputs) {
+                        Value::String(text) => rendered.push_str(&text),
+                        Value::Null => {}
+                        other => rendered.push_str(&other.to_string()),
+                    },
+                }
+            }
+            Value::String(rendered)
+        }
+        Value::Array(items) => Value::Array(items.iter().map(|item| render_value(item, run_input, outputs)).collect()),
+        Value::Object(fields) => Value::Object(
+            fields.iter().map(|(key, field)| (key.clone(), render_value(field, run_input, outputs))).collect()
+        ),
+        other => other.clone(),
+    }
+}
+
+/// A stored pipeline of a customer
+#[derive(Debug, Clone, Serialize, Deserialize, Queryable, Identifiable, Selectable)]
+#[diesel(table_name = pipelines)]
+#[diesel(check_for_backend(diesel::pg::Pg))]
+pub struct Pipeline {
+    pub id: Uuid,
+    pub customer_id: Uuid,
+    pub name: String,
+    pub description: Option<String>,
+    /// YAML definition as uploaded
+    pub definition: String,
+    pub created_at: Option<DateTime<Utc>>,
+    pub updated_at: Option<DateTime<Utc>>,
+}
+
+impl Pipeline {
+    /// Parsed definition of the pipeline
+    pub fn definition(&self) -> Result<PipelineDefinition, PipelineError> {
+        PipelineDefinition::parse(&self.definition)
+    }
+}
+
+// For DB insertion with Diesel
+#[derive(Debug, Clone, Serialize, Deserialize, Insertable)]
+#[diesel(table_name = pipelines)]
+pub struct NewPipeline {
+    pub id: Uuid,
+    pub customer_id: Uuid,
+    pub name: String,
+    pub description: Option<String>,
+    pub definition: String,
+}
+
+/// A single execution of a pipeline
+#[derive(Debug, Clone, Serialize, Deserialize, Queryable, Identifiable, Selectable)]
+#[diesel(table_name = pipeline_runs)]
+#[diesel(check_for_backend(diesel::pg::Pg))]
+pub struct PipelineRun {
+    pub id: Uuid,
+    pub pipeline_id: Uuid,
+    pub customer_id: Uuid,
+    pub status: String,
+    /// Snapshot of the definition at the start of the run; later edits do not affect it
+    pub definition: String,
+    /// Run input, referenced by steps as `{{ input.<path> }}`
+    pub input: Value,
+    /// Priority the run was started with; its steps' jobs get theirs from it
+    /// according to the definition's priority policy
+    pub priority: i32,
+    pub test_mode: bool,
+    pub created_at: Option<DateTime<Utc>>,
+    pub completed_at: Option<DateTime<Utc>>,
+}
+
+impl PipelineRun {
+    pub fn status(&self) -> Option<PipelineRunStatus> {
+        PipelineRunStatus::parse(&self.status)
+    }
+
+    /// Parsed definition the run follows
+    pub fn definition(&self) -> Result<PipelineDefinition, PipelineError> {
+        PipelineDefinition::parse(&self.definition)
+    }
+}
+
+// For DB insertion with Diesel
+#[derive(Debug, Clone, Serialize, Deserialize, Insertable)]
+#[diesel(table_name = pipeline_runs)]
+pub struct NewPipelineRun {
+    pub id: Uuid,
+    pub pipeline_id: Uuid,
+    pub customer_id: Uuid,
+    pub definition: String,
+    pub input: Value,
+    pub priority: i32,
+    pub test_mode: bool,
+}
+
+/// Progress of one step within a run
+#[derive(Debug, Clone, Serialize, Deserialize, Queryable, Identifiable, Selectable)]
+#[diesel(table_name = pipeline_run_steps)]
+#[diesel(check_for_backend(diesel::pg::Pg))]
+pub struct PipelineRunStep {
+    pub id: Uuid,
+    pub run_id: Uuid,
+    pub step_name: String,
+    /// Job submitted for the step, once it started
+    pub job_id: Option<Uuid>,
+    pub status: String,
+    pub created_at: Option<DateTime<Utc>>,
+    pub updated_at: Option<DateTime<Utc>>,
+}
+
+impl PipelineRunStep {
+    pub fn status(&self) -> Option<StepStatus> {
+        StepStatus::parse(&self.status)
+    }
+}
+
+// For DB insertion with Diesel
+#[derive(Debug, Clone, Serialize, Deserialize, Insertable)]
+#[diesel(table_name = pipeline_run_steps)]
+pub struct NewPipelineRunStep {
+    pub id: Uuid,
+    pub run_id: Uuid,
+    pub step_name: String,
+}
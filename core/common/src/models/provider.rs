@@ -0,0 +1,108 @@
+use chrono::{DateTime, Duration, Utc};
+use diesel::prelude::*;
+use serde::{Deserialize, Serialize};
+use uuid::Uuid;
+
+use crate::diesel_schema::providers;
+
+/// Health of an external provider as last assessed
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq, Eq)]
+#[serde(rename_all = "lowercase")]
+pub enum ProviderStatus {
+    /// Jobs of the provider's job types are queued as usual
+    Up,
+    /// New jobs of the provider's job types are held back until it recovers
+    Down,
+}
+
+impl ProviderStatus {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            ProviderStatus::Up => "up",
+            ProviderStatus::Down => "down",
+        }
+    }
+
+    pub fn parse(s: &str) -> Option<Self> {
+        match s.to_lowercase().as_str() {
+            "up" => Some(ProviderStatus::Up),
+            "down" => Some(ProviderStatus::Down),
+            _ => None,
+        }
+    }
+}
+
+/// External service job types depend on, e.g. a third-party API their processor calls
+///
+/// A provider is down when its ping URL does not answer with a success status or when
+/// too many of the recent jobs of its job types failed.
+#[derive(Debug, Clone, Serialize, Deserialize, Queryable, Identifiable, Selectable)]
+#[diesel(table_name = providers)]
+#[diesel(check_for_backend(diesel::pg::Pg))]
+pub struct Provider {
+    pub id: Uuid,
+    pub name: String,
+    /// URL probed with GET; without one the failure rate alone decides
+    pub ping_url: Option<String>,
+    /// Share of failed jobs, from 0 to 1, from which the provider is down
+    pub failure_rate_threshold: f64,
+    /// Finished jobs needed within the check window before the failure rate counts
+    pub min_jobs: i32,
+    /// Seconds new jobs are held back while the provider is down
+    pub retry_delay_secs: i32,
+    pub status: String,
+    /// Why the provider is down
+    pub status_reason: Option<String>,
+    pub last_checked_at: Option<DateTime<Utc>>,
+    pub status_changed_at: Option<DateTime<Utc>>,
+    pub created_at: Option<DateTime<Utc>>,
+    pub updated_at: Option<DateTime<Utc>>,
+}
+
+impl Provider {
+    /// Typed status of the provider
+    pub fn provider_status(&self) -> ProviderStatus {
+        ProviderStatus::parse(&self.status).unwrap_or(ProviderStatus::Up)
+    }
+
+    /// Status the provider has given its ping result (None without a ping URL) and the
+    /// number of finished and failed jobs of its job types, with the reason when down
+    pub fn assess(&self, ping: Option<&Result<(), String>>, finished: u64, failed: u64) -> (ProviderStatus, Option<String>) {
+        if let Some(Err(e)) = ping {
+            return (ProviderStatus::Down, Some(format!("Ping failed: {}", e)));
+        }
+
+        if finished > 0 && finished >= self.min_jobs.max(0) as u64 {
+            let failure_rate = failed as f64 / finished as f64;
+            if failure_rate >= self.failure_rate_threshold {
+                return (
+                    ProviderStatus::Down,
+                    Some(format!("{} of the last {} jobs failed", failed, finished)),
+                );
+            }
+        }
+
+        (ProviderStatus::Up, None)
+    }
+
+    /// When a job of the provider's job types submitted at `now` may be queued: None
+    /// while the provider is up
+    pub fn hold_until(&self, now: DateTime<Utc>) -> Option<DateTime<Utc>> {
+        match self.provider_status() {
+            ProviderStatus::Up => None,
+            ProviderStatus::Down => Some(now + Duration::seconds(self.retry_delay_secs.max(1) as i64)),
+        }
+    }
+}
+
+// For DB insertion with Diesel
+#[derive(Debug, Clone, Serialize, Deserialize, Insertable)]
+#[diesel(table_name = providers)]
+pub struct NewProvider {
+    pub id: Uuid,
+    pub name: String,
+    pub ping_url: Option<String>,
+    pub failure_rate_threshold: f64,
+    pub min_jobs: i32,
+    pub retry_delay_secs: i32,
+}
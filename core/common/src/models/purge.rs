@@ -0,0 +1,98 @@
+use std::collections::BTreeMap;
+
+use chrono::{DateTime, Months, Utc};
+use serde::Serialize;
+use uuid::Uuid;
+
+use crate::models::partition::month_of;
+
+/// Rows deleted by one batch of a purge, by table
+pub type PurgedRows = BTreeMap<String, usize>;
+
+/// Creation time before which finished jobs are past a retention of `retention_months`
+///
+/// Jobs are kept for the same full months as with partition retention, so a purge
+/// deletes the rows a dropped partition would have held.
+pub fn job_retention_cutoff(now: DateTime<Utc>, retention_months: u32) -> DateTime<Utc> {
+    month_of(now)
+        .checked_sub_months(Months::new(retention_months))
+        .unwrap_or(chrono::NaiveDate::MIN)
+        .and_hms_opt(0, 0, 0)
+        .unwrap_or_default()
+        .and_utc()
+}
+
+/// A batch deleted by a running purge
+#[derive(Debug, Clone, Serialize)]
+pub struct PurgeProgress {
+    /// Reseller whose schema the batch was deleted from; None for the shared schema
+    pub reseller_id: Option<Uuid>,
+    /// Table whose expired rows the batch deleted, with the rows referring to them
+    pub table: &'static str,
+    /// Number of the batch within the purge
+    pub batch: u64,
+    pub deleted: PurgedRows,
+}
+
+/// Verification report of a purge of the data past its retention
+///
+/// After deleting, the purge counts again what is past retention in every schema; the
+/// purge is verified when nothing is left.
+#[derive(Debug, Clone, Serialize)]
+pub struct PurgeReport {
+    pub id: Uuid,
+    /// Only counted what would be deleted
+    pub dry_run: bool,
+    /// Finished jobs created before this time were purged; None when jobs are kept forever
+    pub jobs_created_before: Option<DateTime<Utc>>,
+    pub batch_size: i64,
+    pub batches: u64,
+    /// Schemas purged: the shared one and each isolated reseller's
+    pub schemas: usize,
+    /// Rows deleted by table
+    pub deleted: BTreeMap<String, u64>,
+    /// Rows still past retention by table once the purge ended
+    pub remaining: BTreeMap<String, u64>,
+    pub verified: bool,
+    pub started_at: DateTime<Utc>,
+    pub finished_at: DateTime<Utc>,
+}
+
+impl PurgeReport {
+    /// Report of a purge starting now
+    pub fn new(dry_run: bool, jobs_created_before: Option<DateTime<Utc>>, batch_size: i64) -> Self {
+        let now = Utc::now();
+        Self {
+            id: Uuid::new_v4(),
+            dry_run,
+            jobs_created_before,
+            batch_size,
+            batches: 0,
+            schemas: 0,
+            deleted: BTreeMap::new(),
+            remaining: BTreeMap::new(),
+            verified: false,
+            started_at: now,
+            finished_at: now,
+        }
+    }
+
+    /// Count the rows a batch deleted
+    pub fn record_batch(&mut self, deleted: &PurgedRows) {
+        self.batches += 1;
+        for (table, rows) in deleted {
+            *self.deleted.entry(table.clone()).or_default() += *rows as u64;
+        }
+    }
+
+    /// Count rows of a table still past retention in a schema
+    pub fn record_remaining(&mut self, table: &str, rows: u64) {
+        *self.remaining.entry(table.to_string()).or_default() += rows;
+    }
+
+    /// Close the report, verifying nothing past retention is left
+    pub fn finish(&mut self) {
+        self.verified = self.remaining.values().all(|rows| *rows == 0);
+        self.finished_at = Utc::now();
+    }
+}
@@ -0,0 +1,201 @@
+use chrono::{DateTime, Duration, Utc};
+use diesel::prelude::*;
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use thiserror::Error;
+use uuid::Uuid;
+
+use crate::diesel_schema::job_unredacted_outputs;
+use crate::models::job_type::JobType;
+
+/// Value that replaces every redacted part of a job's output
+pub const REDACTED: &str = "[REDACTED]";
+
+/// Reasons a redaction rule is rejected
+#[derive(Debug, Clone, Error, PartialEq, Eq)]
+pub enum RedactionError {
+    #[error("Invalid redaction rule {rule:?}: {reason}")]
+    InvalidRule { rule: String, reason: String },
+}
+
+/// One step of a redaction rule's path
+#[derive(Debug, Clone, PartialEq, Eq)]
+enum Segment {
+    /// `.name` or `['name']`
+    Child(String),
+    /// `.*` or `[*]`: every member of an object or element of an array
+    Wildcard,
+    /// `[n]`
+    Index(usize),
+    /// `..name`: the member at any depth below
+    Descendant(String),
+}
+
+/// A JSONPath pattern selecting the parts of a job's output to redact
+///
+/// Supports the subset of JSONPath needed to address secrets: `$.token`,
+/// `$['x-api-key']`, `$.items[*].secret`, `$.pages[0].body` and `$..password`
+/// for a member at any depth. Filters and slices are not supported.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct RedactionRule {
+    segments: Vec<Segment>,
+}
+
+impl RedactionRule {
+    /// Parse a JSONPath pattern
+    pub fn parse(rule: &str) -> Result<Self, RedactionError> {
+        let invalid = |reason: &str| RedactionError::InvalidRule { rule: rule.to_string(), reason: reason.to_string() };
+
+        let mut rest = rule.trim().strip_prefix('$').ok_or_else(|| invalid("must start with '$'"))?;
+        let mut segments = Vec::new();
+        while !rest.is_empty() {
+            if let Some(after) = rest.strip_prefix("..") {
+                let (name, after) = split_name(after);
+                if name.is_empty() {
+                    return Err(invalid("'..' must be followed by a member name"));
+                }
+                segments.push(Segment::Descendant(name.to_string()));
+                rest = after;
+            } else if let Some(after) = rest.strip_prefix(".*") {
+                segments.push(Segment::Wildcard);
+                rest = after;
+            } else if let Some(after) = rest.strip_prefix('.') {
+                let (name, after) = split_name(after);
+                if name.is_empty() {
+                    return Err(invalid("'.' must be followed by a member name"));
+                }
+                segments.push(Segment::Child(name.to_string()));
+                rest = after;
+            } else if let Some(after) = rest.strip_prefix('[') {
+                let end = after.find(']').ok_or_else(|| invalid("unclosed '['"))?;
+                let selector = after[..end].trim();
+                segments.push(if selector == "*" {
+                    Segment::Wildcard
+                } else if let Some(name) = quoted(selector) {
+                    Segment::Child(name.to_string())
+                } else {
+                    Segment::Index(selector.parse().map_err(|_| invalid("brackets must hold '*', a quoted name or an index"))?)
+                });
+                rest = &after[end + 1..];
+            } else {
+                return Err(invalid("expected '.', '..' or '[' between path segments"));
+            }
+        }
+
+        if segments.is_empty() {
+            return Err(invalid("the whole output cannot be redacted"));
+        }
+        Ok(Self { segments })
+    }
+
+    /// Replace every value the rule selects with [`REDACTED`], returning how many were replaced
+    pub fn apply(&self, value: &mut Value) -> usize {
+        self.replace_with(value, &mut |selected| {
+            (selected.as_str() != Some(REDACTED)).then(|| Value::String(REDACTED.to_string()))
+        })
+    }
+
+    /// Replace every value the rule selects with what `replace` returns for it, leaving
+    /// values it returns `None` for untouched; returns how many were replaced
+    pub fn replace_with(&self, value: &mut Value, replace: &mut dyn FnMut(&Value) -> Option<Value>) -> usize {
+        replace_at(value, &self.segments, replace)
+    }
+}
+
+/// Split a dotted member name off the start of a path
+fn split_name(path: &str) -> (&str, &str) {
+    let end = path.find(['.', '[']).unwrap_or(path.len());
+    path.split_at(end)
+}
+
+/// Name inside single or double quotes
+fn quoted(selector: &str) -> Option<&str> {
+    ['\'', '"'].iter().find_map(|quote| selector.strip_prefix(*quote)?.strip_suffix(*quote))
+}
+
+fn replace_at(value: &mut Value, segments: &[Segment], replace: &mut dyn FnMut(&Value) -> Option<Value>) -> usize {
+    let Some((segment, rest)) = segments.split_first() else {
+        let Some(replacement) = replace(value) else {
+            return 0;
+        };
+        *value = replacement;
+        return 1;
+    };
+
+    match (segment, value) {
+        (Segment::Child(name), Value::Object(map)) => map.get_mut(name).map_or(0, |child| replace_at(child, rest, replace)),
+        (Segment::Index(index), Value::Array(items)) => items.get_mut(*index).map_or(0, |item| replace_at(item, rest, replace)),
+        (Segment::Wildcard, Value::Object(map)) => map.values_mut().map(|child| replace_at(child, rest, replace)).sum(),
+        (Segment::Wildcard, Value::Array(items)) => items.iter_mut().map(|item| replace_at(item, rest, replace)).sum(),
+        (Segment::Descendant(name), value) => {
+            let mut count = 0;
+            if let Value::Object(map) = value {
+                if let Some(child) = map.get_mut(name) {
+                    count += replace_at(child, rest, replace);
+                }
+            }
+            let children: Vec<&mut Value> = match value {
+                Value::Object(map) => map.values_mut().collect(),
+                Value::Array(items) => items.iter_mut().collect(),
+                _ => return 0,
+            };
+            count + children.into_iter().map(|child| replace_at(child, segments, replace)).sum::<usize>()
+        }
+        _ => 0,
+    }
+}
+
+/// Parse a job type's redaction rules, failing on the first invalid one
+pub fn parse_rules(rules: &[String]) -> Result<Vec<RedactionRule>, RedactionError> {
+    rules.iter().map(|rule| RedactionRule::parse(rule)).collect()
+}
+
+/// Output of a job after its job type's redaction rules were applied
+#[derive(Debug, Clone)]
+pub struct RedactedOutput {
+    /// Output to store and return to the customer
+    pub output: Value,
+    /// Number of values that were redacted
+    pub redacted: usize,
+    /// Original output to keep for administrators, if anything was redacted and the
+    /// job type retains unredacted output
+    pub unredacted: Option<NewUnredactedOutput>,
+}
+
+/// Apply a job type's redaction rules to the output of one of its jobs
+pub fn redact_output(job_type: &JobType, job_id: Uuid, output: Value, now: DateTime<Utc>) -> Result<RedactedOutput, RedactionError> {
+    let rules = parse_rules(&job_type.redaction_rules)?;
+    let mut redacted_output = output.clone();
+    let redacted = rules.iter().map(|rule| rule.apply(&mut redacted_output)).sum();
+
+    let unredacted = job_type.unredacted_retention_hours
+        .filter(|_| redacted > 0)
+        .map(|hours| NewUnredactedOutput {
+            job_id,
+            output_data: output,
+            expires_at: now + Duration::hours(hours as i64),
+        });
+    Ok(RedactedOutput { output: redacted_output, redacted, unredacted })
+}
+
+/// Original output of a job that had values redacted
+#[derive(Debug, Clone, Serialize, Deserialize, Queryable, Identifiable, Selectable)]
+#[diesel(table_name = job_unredacted_outputs)]
+#[diesel(primary_key(job_id))]
+#[diesel(check_for_backend(diesel::pg::Pg))]
+pub struct UnredactedOutput {
+    pub job_id: Uuid,
+    pub output_data: Value,
+    pub created_at: DateTime<Utc>,
+    /// After this time the output is no longer returned and gets purged
+    pub expires_at: DateTime<Utc>,
+}
+
+// For DB insertion with Diesel
+#[derive(Debug, Clone, Serialize, Deserialize, Insertable)]
+#[diesel(table_name = job_unredacted_outputs)]
+pub struct NewUnredactedOutput {
+    pub job_id: Uuid,
+    pub output_data: Value,
+    pub expires_at: DateTime<Utc>,
+}
@@ -0,0 +1,245 @@
+use std::collections::BTreeSet;
+
+use chrono::{DateTime, Utc};
+use diesel::prelude::*;
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use sha2::{Digest, Sha256};
+use uuid::Uuid;
+
+use crate::diesel_schema::replay_cases;
+use crate::models::job::Job;
+use crate::models::redaction::{RedactionRule, REDACTED};
+
+/// Prefix of the values an [`Anonymizer`] puts in place of the ones it selects
+pub const PSEUDONYM_PREFIX: &str = "anon-";
+
+/// A historical job of a job type kept to replay against new processor versions
+///
+/// Only the job's anonymized input is kept. The output and cost it is compared
+/// against are recorded by the first replay, so later processor versions are judged
+/// against what an earlier one made of the same anonymized input.
+#[derive(Debug, Clone, Serialize, Deserialize, Queryable, Identifiable, Selectable)]
+#[diesel(table_name = replay_cases)]
+#[diesel(check_for_backend(diesel::pg::Pg))]
+pub struct ReplayCase {
+    pub id: Uuid,
+    pub job_type_id: Uuid,
+    /// Job the case was sampled from
+    pub source_job_id: Uuid,
+    pub priority: i32,
+    /// Input of the job with the sampled paths anonymized
+    pub input_data: Value,
+    /// Reseller markup the job was priced with, in basis points
+    pub markup_rate: i32,
+    pub sampled_at: DateTime<Utc>,
+    /// Output of the run recorded as baseline, once replayed
+    pub baseline_output: Option<Value>,
+    /// Cost of the run recorded as baseline, once replayed
+    pub baseline_cost_cents: Option<i32>,
+    /// Processor version of the run recorded as baseline
+    pub baseline_version: Option<String>,
+    pub baseline_recorded_at: Option<DateTime<Utc>>,
+}
+
+impl ReplayCase {
+    /// Whether an output and cost were recorded to compare replays against
+    pub fn has_baseline(&self) -> bool {
+        self.baseline_output.is_some() && self.baseline_cost_cents.is_some()
+    }
+}
+
+// For DB insertion with Diesel
+#[derive(Debug, Clone, Serialize, Deserialize, Insertable)]
+#[diesel(table_name = replay_cases)]
+pub struct NewReplayCase {
+    pub id: Uuid,
+    pub job_type_id: Uuid,
+    pub source_job_id: Uuid,
+    pub priority: i32,
+    pub input_data: Value,
+    pub markup_rate: i32,
+}
+
+impl NewReplayCase {
+    /// Sample a job into the replay corpus of its job type, anonymizing its input
+    pub fn sample(job: &Job, markup_rate: i32, anonymizer: &Anonymizer) -> Self {
+        let mut input_data = job.input_data.clone();
+        anonymizer.anonymize(&mut input_data);
+        Self {
+            id: Uuid::new_v4(),
+            job_type_id: job.job_type_id,
+            source_job_id: job.id,
+            priority: job.priority.as_i32(),
+            input_data,
+            markup_rate,
+        }
+    }
+}
+
+/// Replaces the values a set of JSONPath rules select with pseudonyms
+///
+/// The same value gets the same pseudonym within one anonymizer, so values that were
+/// equal in a job's input stay equal. Pseudonyms are keyed by a random salt that is
+/// never stored, so they cannot be traced back to the original values. Values that
+/// are already pseudonyms or [`REDACTED`] are left alone.
+pub struct Anonymizer {
+    rules: Vec<RedactionRule>,
+    salt: [u8; 16],
+}
+
+impl Anonymizer {
+    /// Create an anonymizer for the values the given rules select
+    pub fn new(rules: Vec<RedactionRule>) -> Self {
+        Self { rules, salt: rand::random() }
+    }
+
+    /// Pseudonym of a value
+    pub fn pseudonym(&self, value: &Value) -> Value {
+        let mut hasher = Sha256::new();
+        hasher.update(self.salt);
+        hasher.update(value.to_string().as_bytes());
+        let digest = hex::encode(hasher.finalize());
+        Value::String(format!("{}{}", PSEUDONYM_PREFIX, &digest[..16]))
+    }
+
+    /// Replace every value the rules select with its pseudonym, returning how many were replaced
+    pub fn anonymize(&self, value: &mut Value) -> usize {
+        self.rules.iter()
+            .map(|rule| rule.replace_with(value, &mut |selected| {
+                match selected.as_str() {
+                    Some(text) if text == REDACTED || text.starts_with(PSEUDONYM_PREFIX) => None,
+                    _ => Some(self.pseudonym(selected)),
+                }
+            }))
+            .sum()
+    }
+}
+
+/// How the replay of a case compares to its baseline
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq, Eq)]
+pub struct ReplayComparison {
+    pub case_id: Uuid,
+    pub source_job_id: Uuid,
+    /// Paths of the output at which the replay differs from the baseline, in JSONPath
+    pub output_differences: Vec<String>,
+    pub baseline_cost_cents: i32,
+    pub cost_cents: i32,
+}
+
+impl ReplayComparison {
+    /// Compare the output and cost of a replay with the case's baseline, ignoring the
+    /// values the `ignored` rules select in both (such as timestamps)
+    ///
+    /// Returns None if the case has no baseline yet.
+    pub fn compare(case: &ReplayCase, output: &Value, cost_cents: i32, ignored: &[RedactionRule]) -> Option<Self> {
+        let (Some(baseline_output), Some(baseline_cost_cents)) = (&case.baseline_output, case.baseline_cost_cents) else {
+            return None;
+        };
+
+        let mut baseline_output = baseline_output.clone();
+        let mut output = output.clone();
+        for rule in ignored {
+            rule.apply(&mut baseline_output);
+            rule.apply(&mut output);
+        }
+
+        Some(Self {
+            case_id: case.id,
+            source_job_id: case.source_job_id,
+            output_differences: diff_paths(&baseline_output, &output),
+            baseline_cost_cents,
+            cost_cents,
+        })
+    }
+
+    /// Whether the replay reproduced the baseline's output and cost
+    pub fn matches(&self) -> bool {
+        self.output_differences.is_empty() && self.cost_cents == self.baseline_cost_cents
+    }
+}
+
+/// Paths at which two JSON documents differ, in JSONPath
+///
+/// Members missing on either side and array elements past the end of the shorter
+/// array count as differences; a path is not descended further once the values at it
+/// differ in kind.
+pub fn diff_paths(baseline: &Value, replay: &Value) -> Vec<String> {
+    let mut paths = Vec::new();
+    collect_differences(baseline, replay, "$".to_string(), &mut paths);
+    paths
+}
+
+fn collect_differences(baseline: &Value, replay: &Value, path: String, paths: &mut Vec<String>) {
+    match (baseline, replay) {
+        (Value::Object(left), Value::Object(right)) => {
+            let names: BTreeSet<&String> = left.keys().chain(right.keys()).collect();
+            for name in names {
+                let child = member_path(&path, name);
+                match (left.get(name), right.get(name)) {
+                    (Some(left), Some(right)) => collect_differences(left, right, child, paths),
+                    _ => paths.push(child),
+                }
+            }
+        }
+        (Value::Array(left), Value::Array(right)) => {
+            for index in 0..left.len().max(right.len()) {
+                let child = format!("{}[{}]", path, index);
+                match (left.get(index), right.get(index)) {
+                    (Some(left), Some(right)) => collect_differences(left, right, child, paths),
+                    _ => paths.push(child),
+                }
+            }
+        }
+        (left, right) if left != right => paths.push(path),
+        _ => {}
+    }
+}
+
+/// Path of an object member, quoted unless the name can follow a dot
+fn member_path(path: &str, name: &str) -> String {
+    if !name.is_empty() && name.chars().all(|c| c.is_ascii_alphanumeric() || c == '_' || c == '-') {
+        format!("{}.{}", path, name)
+    } else {
+        format!("{}['{}']", path, name)
+    }
+}
+
+/// Outcome of replaying one case
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq, Eq)]
+#[serde(tag = "outcome", rename_all = "snake_case")]
+pub enum ReplayOutcome {
+    /// The case had no baseline yet and the replay was recorded as its baseline
+    Recorded { case_id: Uuid, cost_cents: i32 },
+    /// The replay was compared with the case's baseline
+    Compared(ReplayComparison),
+    /// The processor failed on the case
+    Failed { case_id: Uuid, error: String },
+}
+
+impl ReplayOutcome {
+    /// Whether the outcome is a regression from the baseline
+    pub fn is_regression(&self) -> bool {
+        match self {
+            ReplayOutcome::Recorded { .. } => false,
+            ReplayOutcome::Compared(comparison) => !comparison.matches(),
+            ReplayOutcome::Failed { .. } => true,
+        }
+    }
+}
+
+/// Result of replaying the corpus of a job type against a processor version
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq, Eq)]
+pub struct ReplayReport {
+    pub job_type_id: Uuid,
+    /// Processor version that was replayed
+    pub version: String,
+    pub outcomes: Vec<ReplayOutcome>,
+}
+
+impl ReplayReport {
+    /// Number of cases whose replay regressed from their baseline
+    pub fn regressions(&self) -> usize {
+        self.outcomes.iter().filter(|outcome| outcome.is_regression()).count()
+    }
+}
@@ -0,0 +1,89 @@
+use hmac::{Hmac, Mac};
+use sha2::{Digest, Sha256};
+use thiserror::Error;
+
+/// Header naming the reseller whose signing secret signed the request
+pub const RESELLER_HEADER: &str = "X-Innosystem-Reseller";
+/// Header with the Unix time in seconds the request was signed at
+pub const TIMESTAMP_HEADER: &str = "X-Innosystem-Timestamp";
+/// Header with a value unique to the request, so it cannot be replayed
+pub const NONCE_HEADER: &str = "X-Innosystem-Nonce";
+/// Header with the `sha256=<hex>` signature of the request
+pub const SIGNATURE_HEADER: &str = "X-Innosystem-Signature";
+
+/// Bounds of the nonce length, long enough to be random and short enough to store
+pub const MIN_NONCE_LEN: usize = 16;
+pub const MAX_NONCE_LEN: usize = 128;
+
+/// Reasons a signed request is refused
+#[derive(Debug, Clone, Error, PartialEq, Eq)]
+pub enum SignatureError {
+    #[error("Nonce must be {MIN_NONCE_LEN} to {MAX_NONCE_LEN} letters, digits, '-' or '_'")]
+    MalformedNonce,
+    #[error("Request was signed {skew_secs} seconds away from the server time, more than the {tolerance_secs} accepted")]
+    StaleTimestamp { skew_secs: u64, tolerance_secs: i64 },
+    #[error("Signature does not match the request")]
+    Mismatch,
+}
+
+/// The parts of a request covered by its signature
+///
+/// The signature is the HMAC-SHA256, keyed with the reseller's signing secret, of
+/// the method, the path with its query string, the timestamp, the nonce and the
+/// SHA-256 of the body, each on its own line.
+#[derive(Debug, Clone, Copy)]
+pub struct SignedRequest<'a> {
+    pub method: &'a str,
+    /// Path and query string as sent, e.g. `/customers?limit=10`
+    pub path: &'a str,
+    pub timestamp: i64,
+    pub nonce: &'a str,
+    pub body: &'a [u8],
+}
+
+impl SignedRequest<'_> {
+    /// The text the signature is computed over
+    pub fn string_to_sign(&self) -> String {
+        format!(
+            "{}\n{}\n{}\n{}\n{}",
+            self.method.to_ascii_uppercase(),
+            self.path,
+            self.timestamp,
+            self.nonce,
+            hex::encode(Sha256::digest(self.body)),
+        )
+    }
+
+    fn mac(&self, secret: &str) -> Hmac<Sha256> {
+        let mut mac = Hmac::<Sha256>::new_from_slice(secret.as_bytes())
+            .expect("HMAC accepts keys of any length");
+        mac.update(self.string_to_sign().as_bytes());
+        mac
+    }
+
+    /// Compute the `sha256=<hex>` signature of the request
+    pub fn sign(&self, secret: &str) -> String {
+        format!("sha256={}", hex::encode(self.mac(secret).finalize().into_bytes()))
+    }
+
+    /// Check the nonce, that the request was signed within `tolerance_secs` of `now`
+    /// and the signature, compared in constant time
+    pub fn verify(&self, secret: &str, signature: &str, now: i64, tolerance_secs: i64) -> Result<(), SignatureError> {
+        let nonce_valid = (MIN_NONCE_LEN..=MAX_NONCE_LEN).contains(&self.nonce.len())
+            && self.nonce.chars().all(|c| c.is_ascii_alphanumeric() || c == '-' || c == '_');
+        if !nonce_valid {
+            return Err(SignatureError::MalformedNonce);
+        }
+
+        // The timestamp comes from a header, so it may be anywhere in the i64 range
+        let skew_secs = now.abs_diff(self.timestamp);
+        if skew_secs > u64::try_from(tolerance_secs).unwrap_or(0) {
+            return Err(SignatureError::StaleTimestamp { skew_secs, tolerance_secs });
+        }
+
+        let digest = signature.strip_prefix("sha256=")
+            .and_then(|digest| hex::decode(digest).ok())
+            .ok_or(SignatureError::Mismatch)?;
+        self.mac(secret).verify_slice(&digest).map_err(|_| SignatureError::Mismatch)
+    }
+}
@@ -1,9 +1,10 @@
 use serde::{Deserialize, Serialize};
 use uuid::Uuid;
-use chrono::NaiveDateTime;
+use chrono::{DateTime, Utc};
 use diesel::prelude::*;
 
 use crate::diesel_schema::resellers;
+use crate::i18n::Locale;
 
 #[derive(Debug, Clone, Serialize, Deserialize, Queryable, Identifiable, Selectable)]
 #[diesel(table_name = resellers)]
@@ -17,8 +18,31 @@ pub struct Reseller {
     /// Commission rate in basis points (1/100 of a percent)
     /// Example: 1000 = 10.00%, 2500 = 25.00%
     pub commission_rate: i32,
-    pub created_at: Option<NaiveDateTime>,
-    pub updated_at: Option<NaiveDateTime>,
+    pub created_at: Option<DateTime<Utc>>,
+    pub updated_at: Option<DateTime<Utc>>,
+    /// While inactive, the reseller's customers may read but not submit jobs
+    pub suspend_customers: bool,
+    /// While inactive, no new customers can be created under the reseller
+    pub block_customer_creation: bool,
+    /// Reason for the deactivation, shown to suspended customers
+    pub deactivation_reason: Option<String>,
+    /// Locale of messages to the reseller's customers when their request names no
+    /// supported language, e.g. `de`; English when unset
+    pub default_locale: Option<String>,
+    /// Key of the HMAC signatures of the reseller's signed requests; None when signing is off
+    pub signing_secret: Option<String>,
+    /// Refuse the reseller's API key, so only signed requests are accepted
+    pub require_signed_requests: bool,
+    /// Markup added to the price of the reseller's customers' jobs, in basis points
+    /// Example: 1500 = 15.00%
+    pub markup_rate: i32,
+    /// IANA time zone of the reseller's customers that set none of their own, e.g.
+    /// `Europe/Helsinki`; UTC when unset
+    pub timezone: Option<String>,
+    /// Reseller this sandbox tenant was spun up for; None for real resellers
+    pub sandbox_of: Option<Uuid>,
+    /// When the sandbox tenant is deleted with all of its data
+    pub sandbox_expires_at: Option<DateTime<Utc>>,
 }
 
 impl Reseller {
@@ -37,12 +61,33 @@ impl Reseller {
             commission_rate,
             created_at: None,
             updated_at: None,
+            suspend_customers: false,
+            block_customer_creation: false,
+            deactivation_reason: None,
+            default_locale: None,
+            signing_secret: None,
+            require_signed_requests: false,
+            markup_rate: 0,
+            timezone: None,
+            sandbox_of: None,
+            sandbox_expires_at: None,
         }
     }
 
+    /// Locale of messages to the reseller's customers, if one is set and supported
+    pub fn default_locale(&self) -> Option<Locale> {
+        self.default_locale.as_deref().and_then(Locale::parse)
+    }
+
     pub fn generate_api_key() -> String {
         format!("rs_{}", Uuid::new_v4().to_string().replace("-", ""))
     }
+
+    /// Generate a random key for signing requests
+    pub fn generate_signing_secret() -> String {
+        let bytes: [u8; 32] = rand::random();
+        format!("rss_{}", hex::encode(bytes))
+    }
 }
 
 // For DB insertion with Diesel
@@ -55,6 +100,27 @@ pub struct NewReseller {
     pub api_key: String,
     pub active: bool,
     pub commission_rate: i32,
+    pub markup_rate: i32,
+    pub sandbox_of: Option<Uuid>,
+    pub sandbox_expires_at: Option<DateTime<Utc>>,
+}
+
+impl NewReseller {
+    /// A sandbox tenant of a reseller, earning no commission and expiring at the given time
+    pub fn sandbox(parent: &Reseller, name: String, expires_at: DateTime<Utc>) -> Self {
+        let id = Uuid::new_v4();
+        Self {
+            id,
+            name,
+            email: format!("sandbox-{}@{}.sandbox.invalid", id.simple(), parent.id.simple()),
+            api_key: Reseller::generate_api_key(),
+            active: true,
+            commission_rate: 0,
+            markup_rate: 0,
+            sandbox_of: Some(parent.id),
+            sandbox_expires_at: Some(expires_at),
+        }
+    }
 }
 
 impl From<Reseller> for NewReseller {
@@ -66,6 +132,9 @@ impl From<Reseller> for NewReseller {
             api_key: reseller.api_key,
             active: reseller.active,
             commission_rate: reseller.commission_rate,
+            markup_rate: reseller.markup_rate,
+            sandbox_of: reseller.sandbox_of,
+            sandbox_expires_at: reseller.sandbox_expires_at,
         }
     }
 }
@@ -80,4 +149,34 @@ impl Reseller {
     pub fn set_commission_rate_from_percentage(&mut self, percentage: f64) {
         self.commission_rate = (percentage * 100.0).round() as i32;
     }
+
+    /// Get the markup rate as a percentage value
+    pub fn markup_rate_percentage(&self) -> f64 {
+        self.markup_rate as f64 / 100.0
+    }
+
+    /// Set the markup rate using a percentage value
+    pub fn set_markup_rate_from_percentage(&mut self, percentage: f64) {
+        self.markup_rate = (percentage * 100.0).round() as i32;
+    }
+
+    /// Whether the reseller's customers are currently suspended from submitting jobs
+    pub fn customers_suspended(&self) -> bool {
+        !self.active && self.suspend_customers
+    }
+
+    /// Whether new customers may currently be created under this reseller
+    pub fn allows_customer_creation(&self) -> bool {
+        self.active || !self.block_customer_creation
+    }
+
+    /// Whether this is a sandbox tenant, whose customers only run test jobs
+    pub fn is_sandbox(&self) -> bool {
+        self.sandbox_of.is_some()
+    }
+
+    /// Whether the reseller may authenticate with its API key instead of signing requests
+    pub fn accepts_api_key(&self) -> bool {
+        !(self.require_signed_requests && self.signing_secret.is_some())
+    }
 }
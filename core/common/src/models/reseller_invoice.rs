@@ -0,0 +1,297 @@
+use std::collections::{BTreeMap, HashMap};
+
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use thiserror::Error;
+use uuid::Uuid;
+
+use crate::models::billing_period::{csv_field, month_bounds};
+use crate::models::job_cost::{share, CostBreakdown};
+use crate::models::reseller::Reseller;
+use crate::pdf;
+
+/// Reasons a reseller invoice cannot be made
+#[derive(Debug, Error, PartialEq, Eq)]
+pub enum ResellerInvoiceError {
+    #[error("Invalid invoice period {0:?}, expected a month such as 2025-04")]
+    InvalidPeriod(String),
+}
+
+/// Start and (exclusive) end of the month an invoice period names, e.g. `2025-04`
+pub fn period_bounds(period: &str) -> Result<(DateTime<Utc>, DateTime<Utc>), ResellerInvoiceError> {
+    let invalid = || ResellerInvoiceError::InvalidPeriod(period.to_string());
+    let (year, month) = period.split_once('-').ok_or_else(invalid)?;
+    if year.len() != 4 || month.len() != 2 {
+        return Err(invalid());
+    }
+    let (year, month) = (year.parse().map_err(|_| invalid())?, month.parse().map_err(|_| invalid())?);
+    month_bounds(year, month).map_err(|_| invalid())
+}
+
+/// What one customer was charged for the jobs of one job type over a period
+#[derive(Debug, Clone, PartialEq)]
+pub struct JobTypeCharges {
+    pub customer_id: Uuid,
+    pub job_type_id: Uuid,
+    /// Jobs charged in the period
+    pub jobs: i64,
+    /// What those jobs were charged, item by item
+    pub cost: CostBreakdown,
+}
+
+/// Amounts of a reseller invoice line, in cents
+///
+/// The reseller keeps the markup its customers paid and earns its commission on the
+/// rest, the platform's price of the jobs; it owes the platform that price less the
+/// commission.
+#[derive(Debug, Clone, Copy, Default, Serialize, Deserialize, PartialEq, Eq)]
+pub struct InvoiceAmounts {
+    pub jobs: i64,
+    /// Charged to the customers' wallets
+    pub charged_cents: i64,
+    /// Reseller markup included in the charges
+    pub markup_cents: i64,
+    /// Charges less the markup
+    pub platform_cents: i64,
+    /// Reseller's commission on the platform's price
+    pub commission_cents: i64,
+    /// Owed to the platform
+    pub due_cents: i64,
+}
+
+impl InvoiceAmounts {
+    /// Amounts of jobs charged `cost` in total, at a commission of `commission_rate` basis points
+    pub fn new(jobs: i64, cost: &CostBreakdown, commission_rate: i32) -> Self {
+        let platform_cents = cost.total_cents - cost.reseller_markup_cents;
+        let commission_cents = share(platform_cents, commission_rate.max(0));
+        Self {
+            jobs,
+            charged_cents: cost.total_cents,
+            markup_cents: cost.reseller_markup_cents,
+            platform_cents,
+            commission_cents,
+            due_cents: platform_cents - commission_cents,
+        }
+    }
+}
+
+impl std::ops::Add for InvoiceAmounts {
+    type Output = Self;
+
+    fn add(self, other: Self) -> Self {
+        Self {
+            jobs: self.jobs + other.jobs,
+            charged_cents: self.charged_cents + other.charged_cents,
+            markup_cents: self.markup_cents + other.markup_cents,
+            platform_cents: self.platform_cents + other.platform_cents,
+            commission_cents: self.commission_cents + other.commission_cents,
+            due_cents: self.due_cents + other.due_cents,
+        }
+    }
+}
+
+impl std::iter::Sum for InvoiceAmounts {
+    fn sum<I: Iterator<Item = Self>>(iter: I) -> Self {
+        iter.fold(Self::default(), |total, amounts| total + amounts)
+    }
+}
+
+/// Jobs of one job type charged to one customer of the reseller
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq, Eq)]
+pub struct ResellerInvoiceLine {
+    pub customer_id: Uuid,
+    pub customer_name: String,
+    pub job_type_id: Uuid,
+    pub job_type_name: String,
+    #[serde(flatten)]
+    pub amounts: InvoiceAmounts,
+}
+
+/// Jobs charged to one customer of the reseller, over all job types
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq, Eq)]
+pub struct CustomerSubtotal {
+    pub customer_id: Uuid,
+    pub customer_name: String,
+    #[serde(flatten)]
+    pub amounts: InvoiceAmounts,
+}
+
+/// Jobs of one job type charged to the reseller's customers, over all customers
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq, Eq)]
+pub struct JobTypeSubtotal {
+    pub job_type_id: Uuid,
+    pub job_type_name: String,
+    #[serde(flatten)]
+    pub amounts: InvoiceAmounts,
+}
+
+/// Monthly invoice of a reseller consolidating what its customers were charged
+///
+/// Covers the jobs completed in the calendar month (UTC) that were charged anything,
+/// test mode jobs aside, with a line per customer and job type. Subtotals and totals
+/// are the sums of the lines, so the commission is rounded per line.
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq, Eq)]
+pub struct ResellerInvoice {
+    pub reseller_id: Uuid,
+    pub reseller_name: String,
+    /// Month of the invoice, e.g. `2025-04`
+    pub period: String,
+    pub period_start: DateTime<Utc>,
+    /// Exclusive end of the period
+    pub period_end: DateTime<Utc>,
+    /// Commission of the reseller on the platform's price, in basis points
+    pub commission_rate: i32,
+    /// Markup the reseller currently adds to its customers' jobs, in basis points; the
+    /// lines show the markup actually charged
+    pub markup_rate: i32,
+    /// Whether the month's billing period is closed, so its charges are final
+    pub closed: bool,
+    /// Ordered by customer name, then job type name
+    pub lines: Vec<ResellerInvoiceLine>,
+    pub customers: Vec<CustomerSubtotal>,
+    pub job_types: Vec<JobTypeSubtotal>,
+    pub totals: InvoiceAmounts,
+}
+
+impl ResellerInvoice {
+    /// Consolidate the charges of a reseller's customers over a period into an invoice
+    ///
+    /// Customers and job types missing from `customer_names` and `job_type_names` are
+    /// shown by their ID.
+    pub fn build(
+        reseller: &Reseller,
+        period: &str,
+        closed: bool,
+        charges: Vec<JobTypeCharges>,
+        customer_names: &HashMap<Uuid, String>,
+        job_type_names: &HashMap<Uuid, String>,
+    ) -> Result<Self, ResellerInvoiceError> {
+        let (period_start, period_end) = period_bounds(period)?;
+        let name = |names: &HashMap<Uuid, String>, id: Uuid| names.get(&id).cloned().unwrap_or_else(|| id.to_string());
+
+        // Charges of the same customer and job type are merged into one line
+        let mut merged: BTreeMap<(Uuid, Uuid), (i64, CostBreakdown)> = BTreeMap::new();
+        for charge in charges {
+            let (jobs, cost) = merged.entry((charge.customer_id, charge.job_type_id)).or_default();
+            *jobs += charge.jobs;
+            *cost = *cost + charge.cost;
+        }
+        let mut lines: Vec<ResellerInvoiceLine> = merged.into_iter()
+            .map(|((customer_id, job_type_id), (jobs, cost))| ResellerInvoiceLine {
+                customer_id,
+                customer_name: name(customer_names, customer_id),
+                job_type_id,
+                job_type_name: name(job_type_names, job_type_id),
+                amounts: InvoiceAmounts::new(jobs, &cost, reseller.commission_rate),
+            })
+            .collect();
+        lines.sort_by(|a, b| (&a.customer_name, a.customer_id, &a.job_type_name).cmp(&(&b.customer_name, b.customer_id, &b.job_type_name)));
+
+        let mut customers: Vec<CustomerSubtotal> = Vec::new();
+        for line in &lines {
+            match customers.iter_mut().find(|subtotal| subtotal.customer_id == line.customer_id) {
+                Some(subtotal) => subtotal.amounts = subtotal.amounts + line.amounts,
+                None => customers.push(CustomerSubtotal {
+                    customer_id: line.customer_id,
+                    customer_name: line.customer_name.clone(),
+                    amounts: line.amounts,
+                }),
+            }
+        }
+        let mut job_types: Vec<JobTypeSubtotal> = Vec::new();
+        for line in &lines {
+            match job_types.iter_mut().find(|subtotal| subtotal.job_type_id == line.job_type_id) {
+                Some(subtotal) => subtotal.amounts = subtotal.amounts + line.amounts,
+                None => job_types.push(JobTypeSubtotal {
+                    job_type_id: line.job_type_id,
+                    job_type_name: line.job_type_name.clone(),
+                    amounts: line.amounts,
+                }),
+            }
+        }
+        job_types.sort_by(|a, b| (&a.job_type_name, a.job_type_id).cmp(&(&b.job_type_name, b.job_type_id)));
+
+        Ok(Self {
+            reseller_id: reseller.id,
+            reseller_name: reseller.name.clone(),
+            period: period.to_string(),
+            period_start,
+            period_end,
+            commission_rate: reseller.commission_rate,
+            markup_rate: reseller.markup_rate,
+            closed,
+            totals: lines.iter().map(|line| line.amounts).sum(),
+            lines,
+            customers,
+            job_types,
+        })
+    }
+
+    /// Render the invoice as CSV: a row per line, followed by a row of totals
+    pub fn to_csv(&self) -> String {
+        let mut out = String::from("customer_id,customer_name,job_type_id,job_type_name,jobs,charged_cents,markup_cents,platform_cents,commission_cents,due_cents\n");
+        let row = |out: &mut String, ids: [String; 4], amounts: &InvoiceAmounts| {
+            out.push_str(&format!(
+                "{},{},{},{},{},{},{},{},{},{}\n",
+                ids[0], csv_field(&ids[1]), ids[2], csv_field(&ids[3]),
+                amounts.jobs, amounts.charged_cents, amounts.markup_cents,
+                amounts.platform_cents, amounts.commission_cents, amounts.due_cents,
+            ));
+        };
+        for line in &self.lines {
+            let ids = [line.customer_id.to_string(), line.customer_name.clone(), line.job_type_id.to_string(), line.job_type_name.clone()];
+            row(&mut out, ids, &line.amounts);
+        }
+        row(&mut out, [String::new(), "Total".to_string(), String::new(), String::new()], &self.totals);
+        out
+    }
+
+    /// Render the invoice as a PDF statement: its lines grouped by customer, the
+    /// subtotals per job type and the totals
+    pub fn to_pdf(&self) -> Vec<u8> {
+        let columns = |label: &str, amounts: &InvoiceAmounts| format!(
+            "  {:<37} {:>7} {:>11} {:>11} {:>11} {:>11}",
+            truncate(label, 37), amounts.jobs, cents(amounts.charged_cents), cents(amounts.markup_cents),
+            cents(amounts.commission_cents), cents(amounts.due_cents),
+        );
+        let mut lines = vec![
+            format!("Consolidated invoice {} - {}", self.period, self.reseller_name),
+            format!("Reseller {}", self.reseller_id),
+            format!(
+                "Period {} to {} (UTC), commission {}.{:02}%{}",
+                self.period_start.format("%Y-%m-%d"), self.period_end.format("%Y-%m-%d"),
+                self.commission_rate / 100, self.commission_rate % 100,
+                if self.closed { "" } else { ", period not closed: amounts may change" },
+            ),
+            String::new(),
+            format!("  {:<37} {:>7} {:>11} {:>11} {:>11} {:>11}", "", "Jobs", "Charged", "Markup", "Commission", "Due"),
+        ];
+        for customer in &self.customers {
+            lines.push(String::new());
+            lines.push(truncate(&format!("{} ({})", customer.customer_name, customer.customer_id), pdf::LINE_WIDTH));
+            for line in self.lines.iter().filter(|line| line.customer_id == customer.customer_id) {
+                lines.push(columns(&format!("  {}", line.job_type_name), &line.amounts));
+            }
+            lines.push(columns("Subtotal", &customer.amounts));
+        }
+        lines.push(String::new());
+        lines.push("By job type".to_string());
+        for job_type in &self.job_types {
+            lines.push(columns(&format!("  {}", job_type.job_type_name), &job_type.amounts));
+        }
+        lines.push(String::new());
+        lines.push(columns("Total", &self.totals));
+        pdf::text_document(&lines)
+    }
+}
+
+/// Amount in cents written as units with two decimals
+fn cents(amount: i64) -> String {
+    let sign = if amount < 0 { "-" } else { "" };
+    format!("{}{}.{:02}", sign, amount.abs() / 100, amount.abs() % 100)
+}
+
+/// At most `width` characters of a label
+fn truncate(label: &str, width: usize) -> String {
+    label.chars().take(width).collect()
+}
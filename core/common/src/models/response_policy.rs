@@ -0,0 +1,158 @@
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use thiserror::Error;
+
+/// Bytes of a text or JSON response kept in the job's output unless the job type says otherwise
+pub const DEFAULT_MAX_CAPTURED_BYTES: usize = 64 * 1024;
+
+/// Largest binary response kept as an artifact unless the job type says otherwise
+pub const DEFAULT_MAX_ARTIFACT_BYTES: usize = 10 * 1024 * 1024;
+
+/// Upper bound of both limits, so a job type cannot make runners buffer arbitrary responses
+pub const MAX_RESPONSE_BYTES: usize = 100 * 1024 * 1024;
+
+/// Reasons a response policy is rejected
+#[derive(Debug, Clone, Error, PartialEq, Eq)]
+pub enum ResponsePolicyError {
+    #[error("{field} must be between 1 and {max} bytes, got {value}")]
+    InvalidLimit { field: &'static str, value: usize, max: usize },
+}
+
+/// What happens to binary response bodies
+#[derive(Debug, Clone, Copy, Default, Serialize, Deserialize, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+pub enum BinaryResponses {
+    /// Stored as an artifact the job's output refers to
+    #[default]
+    Artifact,
+    /// Only their content type and size are kept
+    Discard,
+}
+
+/// How the responses of a job type's webhook calls end up in the job's output
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq, Eq)]
+#[serde(deny_unknown_fields)]
+pub struct ResponsePolicy {
+    /// Bytes of a text or JSON response kept; longer responses are truncated
+    #[serde(default = "default_max_captured_bytes")]
+    pub max_captured_bytes: usize,
+    /// Whether JSON responses become structured output rather than text
+    #[serde(default = "parse_json_by_default")]
+    pub parse_json: bool,
+    #[serde(default)]
+    pub binary: BinaryResponses,
+    /// Largest binary response stored as an artifact; larger ones fail the job
+    #[serde(default = "default_max_artifact_bytes")]
+    pub max_artifact_bytes: usize,
+}
+
+fn default_max_captured_bytes() -> usize {
+    DEFAULT_MAX_CAPTURED_BYTES
+}
+
+fn parse_json_by_default() -> bool {
+    true
+}
+
+fn default_max_artifact_bytes() -> usize {
+    DEFAULT_MAX_ARTIFACT_BYTES
+}
+
+impl Default for ResponsePolicy {
+    fn default() -> Self {
+        Self {
+            max_captured_bytes: DEFAULT_MAX_CAPTURED_BYTES,
+            parse_json: true,
+            binary: BinaryResponses::default(),
+            max_artifact_bytes: DEFAULT_MAX_ARTIFACT_BYTES,
+        }
+    }
+}
+
+/// Kind of a response body, from its content type
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ResponseKind {
+    Json,
+    Text,
+    Binary,
+}
+
+impl ResponseKind {
+    /// Kind of a body with the given `Content-Type` header
+    ///
+    /// `application/json` and `+json` types are JSON; `text/*`, XML, JavaScript and form
+    /// data are text; anything else is binary. Bodies without a content type are text
+    /// when they are valid UTF-8.
+    pub fn of(content_type: Option<&str>, body: &[u8]) -> Self {
+        let Some(content_type) = content_type else {
+            return if std::str::from_utf8(body).is_ok() { ResponseKind::Text } else { ResponseKind::Binary };
+        };
+        let media_type = content_type.split(';').next().unwrap_or_default().trim().to_ascii_lowercase();
+        if media_type == "application/json" || media_type.ends_with("+json") {
+            ResponseKind::Json
+        } else if media_type.starts_with("text/")
+            || media_type.ends_with("+xml")
+            || matches!(media_type.as_str(), "application/xml" | "application/javascript" | "application/x-www-form-urlencoded")
+        {
+            ResponseKind::Text
+        } else {
+            ResponseKind::Binary
+        }
+    }
+}
+
+/// A response body as kept in the job's output
+#[derive(Debug, Clone, PartialEq)]
+pub enum CapturedResponse {
+    /// Parsed JSON body
+    Json(Value),
+    /// Text body, cut at the policy's limit
+    Text { text: String, truncated: bool },
+    /// Binary body, stored or discarded as the policy says
+    Binary,
+}
+
+impl ResponsePolicy {
+    /// Check the limits of the policy
+    pub fn validate(&self) -> Result<(), ResponsePolicyError> {
+        for (field, value) in [("max_captured_bytes", self.max_captured_bytes), ("max_artifact_bytes", self.max_artifact_bytes)] {
+            if !(1..=MAX_RESPONSE_BYTES).contains(&value) {
+                return Err(ResponsePolicyError::InvalidLimit { field, value, max: MAX_RESPONSE_BYTES });
+            }
+        }
+        Ok(())
+    }
+
+    /// Bytes of a response worth reading: one more than the largest body kept, so
+    /// longer bodies are known to be cut
+    pub fn read_limit(&self) -> usize {
+        let kept = match self.binary {
+            BinaryResponses::Artifact => self.max_captured_bytes.max(self.max_artifact_bytes),
+            BinaryResponses::Discard => self.max_captured_bytes,
+        };
+        kept + 1
+    }
+
+    /// Capture a response body read up to [`read_limit`](Self::read_limit)
+    ///
+    /// JSON bodies that are cut or do not parse are kept as text.
+    pub fn capture(&self, content_type: Option<&str>, body: &[u8]) -> CapturedResponse {
+        let kind = ResponseKind::of(content_type, body);
+        if kind == ResponseKind::Binary {
+            return CapturedResponse::Binary;
+        }
+        let truncated = body.len() > self.max_captured_bytes;
+        if kind == ResponseKind::Json && self.parse_json && !truncated {
+            if let Ok(value) = serde_json::from_slice(body) {
+                return CapturedResponse::Json(value);
+            }
+        }
+        let kept = &body[..body.len().min(self.max_captured_bytes)];
+        let mut text = String::from_utf8_lossy(kept).into_owned();
+        // A cut through a multi-byte character leaves a replacement character at the end
+        if truncated && text.ends_with(char::REPLACEMENT_CHARACTER) {
+            text.pop();
+        }
+        CapturedResponse::Text { text, truncated }
+    }
+}
@@ -1,6 +1,8 @@
 use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
+use std::collections::{BTreeMap, BTreeSet};
 use uuid::Uuid;
-use chrono::NaiveDateTime;
+use chrono::{DateTime, Utc};
 use diesel::prelude::*;
 use diesel::pg::Pg;
 use diesel::serialize::{self, IsNull, Output, ToSql};
@@ -8,7 +10,7 @@ use diesel::deserialize::{self, FromSql};
 use diesel::sql_types::Text;
 use std::io::Write;
 
-use crate::diesel_schema::{runners, runner_job_type_compatibility};
+use crate::diesel_schema::{runners, runner_job_type_compatibility, runner_health_checks, runner_environments};
 use crate::models::job_type::JobType;
 
 #[derive(Debug, Clone, Serialize, Deserialize, PartialEq, Eq)]
@@ -76,18 +78,48 @@ impl FromSql<Text, Pg> for RunnerStatus {
     }
 }
 
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Runner {
+    pub id: Uuid,
+    pub name: String,
+    pub description: Option<String>,
+    pub status: RunnerStatus,
+    /// Names of the job types the runner is compatible with, derived from its
+    /// rows in `runner_job_type_compatibility`
+    pub compatible_job_types: Vec<String>,
+    pub last_heartbeat: Option<DateTime<Utc>>,
+    pub created_at: Option<DateTime<Utc>>,
+    pub updated_at: Option<DateTime<Utc>>,
+}
+
+// Database representation of a Runner, without its compatible job types
 #[derive(Debug, Clone, Serialize, Deserialize, Queryable, Identifiable, Selectable)]
 #[diesel(table_name = runners)]
 #[diesel(check_for_backend(diesel::pg::Pg))]
-pub struct Runner {
+pub struct RunnerDb {
     pub id: Uuid,
     pub name: String,
     pub description: Option<String>,
     pub status: RunnerStatus,
-    pub compatible_job_types: Vec<String>,
-    pub last_heartbeat: Option<NaiveDateTime>,
-    pub created_at: Option<NaiveDateTime>,
-    pub updated_at: Option<NaiveDateTime>,
+    pub last_heartbeat: Option<DateTime<Utc>>,
+    pub created_at: Option<DateTime<Utc>>,
+    pub updated_at: Option<DateTime<Utc>>,
+}
+
+impl RunnerDb {
+    /// Application model of the runner, given the names of its compatible job types
+    pub fn into_runner(self, compatible_job_types: Vec<String>) -> Runner {
+        Runner {
+            id: self.id,
+            name: self.name,
+            description: self.description,
+            status: self.status,
+            compatible_job_types,
+            last_heartbeat: self.last_heartbeat,
+            created_at: self.created_at,
+            updated_at: self.updated_at,
+        }
+    }
 }
 
 impl Runner {
@@ -108,23 +140,13 @@ impl Runner {
         }
     }
     
-    pub fn update_heartbeat(&mut self, time: NaiveDateTime) {
+    pub fn update_heartbeat(&mut self, time: DateTime<Utc>) {
         self.last_heartbeat = Some(time);
     }
     
     pub fn set_status(&mut self, status: RunnerStatus) {
         self.status = status;
     }
-    
-    pub fn add_compatible_job_type(&mut self, job_type: String) {
-        if !self.compatible_job_types.contains(&job_type) {
-            self.compatible_job_types.push(job_type);
-        }
-    }
-    
-    pub fn remove_compatible_job_type(&mut self, job_type: &str) {
-        self.compatible_job_types.retain(|t| t != job_type);
-    }
 }
 
 // For DB insertion with Diesel
@@ -135,6 +157,9 @@ pub struct NewRunner {
     pub name: String,
     pub description: Option<String>,
     pub status: String,
+    /// Names of the job types the runner is compatible with; stored as rows in
+    /// `runner_job_type_compatibility` when the runner is registered
+    #[diesel(skip_insertion)]
     pub compatible_job_types: Vec<String>,
 }
 
@@ -152,14 +177,14 @@ impl From<Runner> for NewRunner {
 
 // For joining runners and job types
 #[derive(Debug, Clone, Serialize, Deserialize, Queryable, Identifiable, Associations)]
-#[diesel(belongs_to(Runner))]
+#[diesel(belongs_to(RunnerDb, foreign_key = runner_id))]
 #[diesel(belongs_to(JobType))]
 #[diesel(table_name = runner_job_type_compatibility)]
 #[diesel(primary_key(runner_id, job_type_id))]
 pub struct JobTypeCompatibility {
     pub runner_id: Uuid,
     pub job_type_id: Uuid,
-    pub created_at: Option<NaiveDateTime>,
+    pub created_at: Option<DateTime<Utc>>,
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize, Insertable)]
@@ -168,3 +193,183 @@ pub struct NewJobTypeCompatibility {
     pub runner_id: Uuid,
     pub job_type_id: Uuid,
 }
+
+/// Recorded outcome of a runner health evaluation
+#[derive(Debug, Clone, Serialize, Deserialize, Queryable, Identifiable, Selectable)]
+#[diesel(table_name = runner_health_checks)]
+#[diesel(check_for_backend(diesel::pg::Pg))]
+pub struct RunnerHealthCheck {
+    pub id: Uuid,
+    pub runner_id: Uuid,
+    /// Health status name (healthy, warning, critical or unknown)
+    pub status: String,
+    /// Composite score from 0 (down) to 100 (fully healthy)
+    pub score: i32,
+    pub heartbeat_age_seconds: Option<i64>,
+    /// Share of failed jobs in the sample window, if enough jobs ran to tell
+    pub failure_rate: Option<f64>,
+    pub avg_processing_seconds: Option<f64>,
+    /// Number of jobs the failure rate and processing time are based on
+    pub jobs_sampled: i32,
+    pub created_at: Option<DateTime<Utc>>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, Insertable)]
+#[diesel(table_name = runner_health_checks)]
+pub struct NewRunnerHealthCheck {
+    pub id: Uuid,
+    pub runner_id: Uuid,
+    pub status: String,
+    pub score: i32,
+    pub heartbeat_age_seconds: Option<i64>,
+    pub failure_rate: Option<f64>,
+    pub avg_processing_seconds: Option<f64>,
+    pub jobs_sampled: i32,
+}
+
+/// Most libraries a runner environment may list
+pub const MAX_ENVIRONMENT_LIBRARIES: usize = 100;
+/// Longest name or version in a runner environment, in characters
+pub const MAX_ENVIRONMENT_VALUE_LEN: usize = 128;
+
+/// Execution environment a runner reports at registration and on its heartbeats
+///
+/// Runners of one pool (runners registered under the same name) are expected to run in
+/// the same environment; differences between them explain jobs that only fail on some.
+#[derive(Debug, Clone, Default, Serialize, Deserialize, PartialEq, Eq)]
+pub struct RunnerEnvironment {
+    /// Operating system, e.g. `linux`
+    pub os: String,
+    /// Processor architecture, e.g. `x86_64`
+    pub arch: String,
+    /// Processors available to the runner
+    pub processors: u32,
+    /// Versions of the runner and of the libraries its processors rely on, by name
+    #[serde(default)]
+    pub libraries: BTreeMap<String, String>,
+}
+
+impl RunnerEnvironment {
+    /// SHA-256 of the environment, hex encoded: equal for identical environments
+    pub fn fingerprint(&self) -> String {
+        // Fields and libraries serialize in a fixed order, so the JSON is canonical
+        let json = serde_json::to_vec(self).unwrap_or_default();
+        hex::encode(Sha256::digest(json))
+    }
+
+    /// Check a reported environment is within the accepted size
+    pub fn validate(&self) -> Result<(), String> {
+        if self.libraries.len() > MAX_ENVIRONMENT_LIBRARIES {
+            return Err(format!("A runner environment lists at most {} libraries", MAX_ENVIRONMENT_LIBRARIES));
+        }
+        let values = [&self.os, &self.arch].into_iter()
+            .chain(self.libraries.iter().flat_map(|(name, version)| [name, version]));
+        for value in values {
+            if value.chars().count() > MAX_ENVIRONMENT_VALUE_LEN {
+                return Err(format!("Runner environment values have at most {} characters", MAX_ENVIRONMENT_VALUE_LEN));
+            }
+        }
+        Ok(())
+    }
+
+    /// Settings compared between runners, by name; libraries are named `library:<name>`
+    fn settings(&self) -> BTreeMap<String, String> {
+        let mut settings = BTreeMap::from([
+            ("os".to_string(), self.os.clone()),
+            ("arch".to_string(), self.arch.clone()),
+            ("processors".to_string(), self.processors.to_string()),
+        ]);
+        for (name, version) in &self.libraries {
+            settings.insert(format!("library:{}", name), version.clone());
+        }
+        settings
+    }
+}
+
+/// Value of a setting shared by some runners of a pool
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq, Eq)]
+pub struct DriftValue {
+    /// None for runners that do not report the setting, e.g. lack the library
+    pub value: Option<String>,
+    pub runner_ids: Vec<Uuid>,
+}
+
+/// Setting on which the runners of a pool disagree
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq, Eq)]
+pub struct EnvironmentDrift {
+    /// `os`, `arch`, `processors` or `library:<name>`
+    pub setting: String,
+    /// Every value the setting has in the pool, in order
+    pub values: Vec<DriftValue>,
+}
+
+/// Settings on which the given runners of one pool disagree, from their latest
+/// environments, ordered by setting name
+pub fn environment_drift(environments: &[(Uuid, RunnerEnvironment)]) -> Vec<EnvironmentDrift> {
+    let settings: Vec<(Uuid, BTreeMap<String, String>)> = environments.iter()
+        .map(|(runner_id, environment)| (*runner_id, environment.settings()))
+        .collect();
+    let names: BTreeSet<&String> = settings.iter()
+        .flat_map(|(_, settings)| settings.keys())
+        .collect();
+
+    let mut drift = Vec::new();
+    for name in names {
+        let mut values: BTreeMap<Option<String>, Vec<Uuid>> = BTreeMap::new();
+        for (runner_id, settings) in &settings {
+            values.entry(settings.get(name).cloned()).or_default().push(*runner_id);
+        }
+        if values.len() > 1 {
+            drift.push(EnvironmentDrift {
+                setting: name.clone(),
+                values: values.into_iter()
+                    .map(|(value, mut runner_ids)| {
+                        runner_ids.sort();
+                        DriftValue { value, runner_ids }
+                    })
+                    .collect(),
+            });
+        }
+    }
+    drift
+}
+
+/// Environment a runner reported, kept each time it changes
+#[derive(Debug, Clone, Serialize, Deserialize, Queryable, Identifiable, Selectable)]
+#[diesel(table_name = runner_environments)]
+#[diesel(check_for_backend(diesel::pg::Pg))]
+pub struct RunnerEnvironmentReport {
+    pub id: Uuid,
+    pub runner_id: Uuid,
+    /// Fingerprint of the environment, see [`RunnerEnvironment::fingerprint`]
+    pub fingerprint: String,
+    pub environment: serde_json::Value,
+    pub reported_at: DateTime<Utc>,
+}
+
+impl RunnerEnvironmentReport {
+    /// Typed environment, None if the stored document does not match the current format
+    pub fn runner_environment(&self) -> Option<RunnerEnvironment> {
+        serde_json::from_value(self.environment.clone()).ok()
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, Insertable)]
+#[diesel(table_name = runner_environments)]
+pub struct NewRunnerEnvironmentReport {
+    pub id: Uuid,
+    pub runner_id: Uuid,
+    pub fingerprint: String,
+    pub environment: serde_json::Value,
+}
+
+impl NewRunnerEnvironmentReport {
+    pub fn new(runner_id: Uuid, environment: &RunnerEnvironment) -> Self {
+        Self {
+            id: Uuid::new_v4(),
+            runner_id,
+            fingerprint: environment.fingerprint(),
+            environment: serde_json::to_value(environment).unwrap_or_default(),
+        }
+    }
+}
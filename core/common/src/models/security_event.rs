@@ -0,0 +1,99 @@
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use uuid::Uuid;
+
+/// What a security event reports
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+pub enum SecurityEventKind {
+    /// A source IP or key prefix failed to authenticate too often and is locked out
+    Lockout,
+    /// One source IP presented many different API keys, as when guessing keys
+    KeyEnumeration,
+    /// Many source IPs failed with keys of the same prefix, as when a botnet
+    /// completes a leaked partial key
+    DistributedAttack,
+}
+
+impl SecurityEventKind {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            SecurityEventKind::Lockout => "lockout",
+            SecurityEventKind::KeyEnumeration => "key_enumeration",
+            SecurityEventKind::DistributedAttack => "distributed_attack",
+        }
+    }
+
+    pub fn parse(s: &str) -> Option<Self> {
+        match s.to_lowercase().as_str() {
+            "lockout" => Some(SecurityEventKind::Lockout),
+            "key_enumeration" => Some(SecurityEventKind::KeyEnumeration),
+            "distributed_attack" => Some(SecurityEventKind::DistributedAttack),
+            _ => None,
+        }
+    }
+}
+
+/// What failed authentications are counted against
+#[derive(Debug, Clone, PartialEq, Eq, Hash, Serialize, Deserialize)]
+#[serde(tag = "type", content = "value", rename_all = "snake_case")]
+pub enum AuthSubject {
+    SourceIp(String),
+    /// First characters of the presented API key, never the whole key
+    KeyPrefix(String),
+}
+
+impl AuthSubject {
+    /// Key of the subject within Redis keys, e.g. `ip:203.0.113.7`
+    pub fn key(&self) -> String {
+        match self {
+            AuthSubject::SourceIp(ip) => format!("ip:{}", ip),
+            AuthSubject::KeyPrefix(prefix) => format!("key:{}", prefix),
+        }
+    }
+}
+
+/// A brute-force pattern noticed in failed authentications
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+pub struct SecurityEvent {
+    pub id: Uuid,
+    pub kind: SecurityEventKind,
+    pub subject: AuthSubject,
+    /// Failed authentications of the subject that led to the event
+    pub failures: u64,
+    /// Distinct key prefixes (key enumeration) or source IPs (distributed attack) seen
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub distinct: Option<u64>,
+    /// Duration of the lockout, doubling with each lockout of the subject
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub lockout_secs: Option<u64>,
+    pub occurred_at: DateTime<Utc>,
+}
+
+impl SecurityEvent {
+    pub fn new(kind: SecurityEventKind, subject: AuthSubject, failures: u64) -> Self {
+        Self {
+            id: Uuid::new_v4(),
+            kind,
+            subject,
+            failures,
+            distinct: None,
+            lockout_secs: None,
+            occurred_at: Utc::now(),
+        }
+    }
+}
+
+/// Prefix of an API key safe to record: at most `length` characters, and never more
+/// than half of the key so short keys are not recorded whole
+pub fn key_prefix(api_key: &str, length: usize) -> String {
+    let length = length.min(api_key.chars().count() / 2);
+    api_key.chars().take(length).collect()
+}
+
+/// Seconds a subject is locked out for its `level`-th lockout in a row: the base
+/// duration doubles with each lockout, up to the maximum
+pub fn lockout_secs(base_secs: u64, max_secs: u64, level: u32) -> u64 {
+    let doublings = level.saturating_sub(1).min(63);
+    base_secs.saturating_mul(1u64 << doublings).min(max_secs)
+}
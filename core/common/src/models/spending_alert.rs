@@ -0,0 +1,73 @@
+use chrono::{DateTime, Utc};
+use diesel::prelude::*;
+use serde::{Deserialize, Serialize};
+use uuid::Uuid;
+
+use crate::diesel_schema::spending_alerts;
+
+/// Kind of deviation from a customer's usual activity
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+pub enum AnomalyKind {
+    /// Spend within the detection window is a multiple of what the customer usually spends
+    SpendSpike,
+    /// A much larger share of the customer's jobs fails than usual
+    FailureRateJump,
+}
+
+impl AnomalyKind {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            AnomalyKind::SpendSpike => "spend_spike",
+            AnomalyKind::FailureRateJump => "failure_rate_jump",
+        }
+    }
+
+    pub fn parse(s: &str) -> Option<Self> {
+        match s.to_lowercase().as_str() {
+            "spend_spike" => Some(AnomalyKind::SpendSpike),
+            "failure_rate_jump" => Some(AnomalyKind::FailureRateJump),
+            _ => None,
+        }
+    }
+}
+
+/// Anomaly detected in a customer's spending pattern
+#[derive(Debug, Clone, Serialize, Deserialize, Queryable, Identifiable, Selectable)]
+#[diesel(table_name = spending_alerts)]
+#[diesel(check_for_backend(diesel::pg::Pg))]
+pub struct SpendingAlert {
+    pub id: Uuid,
+    pub customer_id: Uuid,
+    /// Reseller of the customer when the alert was raised
+    pub reseller_id: Option<Uuid>,
+    pub kind: String,
+    /// Spend in cents or failure rate observed within the window
+    pub observed: f64,
+    /// The same measure expected from the customer's history
+    pub baseline: f64,
+    pub window_start: DateTime<Utc>,
+    pub window_end: DateTime<Utc>,
+    pub created_at: DateTime<Utc>,
+}
+
+impl SpendingAlert {
+    /// Typed kind of the alert
+    pub fn anomaly_kind(&self) -> Option<AnomalyKind> {
+        AnomalyKind::parse(&self.kind)
+    }
+}
+
+// For DB insertion with Diesel
+#[derive(Debug, Clone, Serialize, Deserialize, Insertable)]
+#[diesel(table_name = spending_alerts)]
+pub struct NewSpendingAlert {
+    pub id: Uuid,
+    pub customer_id: Uuid,
+    pub reseller_id: Option<Uuid>,
+    pub kind: String,
+    pub observed: f64,
+    pub baseline: f64,
+    pub window_start: DateTime<Utc>,
+    pub window_end: DateTime<Utc>,
+}
@@ -0,0 +1,104 @@
+use chrono::{DateTime, Utc};
+use diesel::prelude::*;
+use serde::{Deserialize, Serialize};
+use uuid::Uuid;
+
+use crate::diesel_schema::submissions;
+use crate::models::job::{Job, JobStatus};
+
+/// How the jobs of a submission were submitted
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq, Eq)]
+#[serde(rename_all = "lowercase")]
+pub enum SubmissionSource {
+    /// Submitted together through the batch endpoint
+    Batch,
+    /// Rows of a file import; the submission has the import's ID
+    Import,
+}
+
+impl SubmissionSource {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            SubmissionSource::Batch => "batch",
+            SubmissionSource::Import => "import",
+        }
+    }
+}
+
+/// Group of logically related jobs a customer submitted together, tracked and
+/// cancelled as one
+#[derive(Debug, Clone, Serialize, Deserialize, Queryable, Identifiable, Selectable)]
+#[diesel(table_name = submissions)]
+#[diesel(check_for_backend(diesel::pg::Pg))]
+pub struct Submission {
+    pub id: Uuid,
+    pub customer_id: Uuid,
+    /// batch or import
+    pub source: String,
+    pub created_at: DateTime<Utc>,
+    /// When the customer cancelled the jobs of the submission that had not started
+    pub cancelled_at: Option<DateTime<Utc>>,
+}
+
+// For DB insertion with Diesel
+#[derive(Debug, Clone, Serialize, Deserialize, Insertable)]
+#[diesel(table_name = submissions)]
+pub struct NewSubmission {
+    pub id: Uuid,
+    pub customer_id: Uuid,
+    pub source: String,
+}
+
+impl NewSubmission {
+    pub fn new(id: Uuid, customer_id: Uuid, source: SubmissionSource) -> Self {
+        Self { id, customer_id, source: source.as_str().to_string() }
+    }
+}
+
+/// Where the jobs of a submission stand, counted by status
+#[derive(Debug, Clone, Default, Serialize, PartialEq, Eq)]
+pub struct SubmissionSummary {
+    pub total: usize,
+    /// Pending or scheduled: not started yet, so they can still be cancelled
+    pub pending: usize,
+    pub running: usize,
+    pub succeeded: usize,
+    pub failed: usize,
+    pub cancelled: usize,
+    /// Not started before their deadline
+    pub expired: usize,
+    /// What the jobs that ran were charged; cancelled and expired ones never ran
+    pub cost_cents: i64,
+    /// What the jobs still pending or running are expected to cost
+    pub estimated_cost_cents: i64,
+}
+
+impl SubmissionSummary {
+    /// Summarize the jobs of a submission
+    pub fn of(jobs: &[Job]) -> Self {
+        let mut summary = Self { total: jobs.len(), ..Self::default() };
+        for job in jobs {
+            match job.status {
+                JobStatus::Pending | JobStatus::Scheduled => summary.pending += 1,
+                JobStatus::Running => summary.running += 1,
+                JobStatus::Succeeded => summary.succeeded += 1,
+                JobStatus::Failed => summary.failed += 1,
+                JobStatus::Cancelled => summary.cancelled += 1,
+                JobStatus::Expired => summary.expired += 1,
+            }
+            match job.status {
+                JobStatus::Succeeded | JobStatus::Failed => summary.cost_cents += job.cost_cents as i64,
+                JobStatus::Pending | JobStatus::Scheduled | JobStatus::Running => {
+                    summary.estimated_cost_cents += job.estimated_cost_cents as i64
+                }
+                JobStatus::Cancelled | JobStatus::Expired => {}
+            }
+        }
+        summary
+    }
+
+    /// Whether every job of the submission finished, one way or another
+    pub fn is_finished(&self) -> bool {
+        self.pending == 0 && self.running == 0
+    }
+}
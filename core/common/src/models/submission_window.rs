@@ -0,0 +1,67 @@
+use serde::{Deserialize, Serialize};
+use uuid::Uuid;
+use chrono::{DateTime, Duration, NaiveDateTime, NaiveTime, Utc};
+use diesel::prelude::*;
+
+use crate::diesel_schema::submission_windows;
+use crate::timezone::{self, Tz};
+
+/// Daily time window in which jobs of a type may enter the queue
+///
+/// A window belongs either to a customer or to a reseller; a reseller's windows apply
+/// to all of its customers that have no windows of their own for the job type. Its
+/// times are wall-clock times in the time zone of the customer submitting the job.
+#[derive(Debug, Clone, Serialize, Deserialize, Queryable, Identifiable, Selectable)]
+#[diesel(table_name = submission_windows)]
+#[diesel(check_for_backend(diesel::pg::Pg))]
+pub struct SubmissionWindow {
+    pub id: Uuid,
+    pub job_type_id: Uuid,
+    pub customer_id: Option<Uuid>,
+    pub reseller_id: Option<Uuid>,
+    /// Opening time
+    pub start_time: NaiveTime,
+    /// Closing time (exclusive); before `start_time` the window wraps past midnight
+    pub end_time: NaiveTime,
+    pub created_at: Option<DateTime<Utc>>,
+}
+
+impl SubmissionWindow {
+    /// Whether the window is open at the given time of day
+    pub fn contains(&self, time: NaiveTime) -> bool {
+        if self.start_time < self.end_time {
+            self.start_time <= time && time < self.end_time
+        } else {
+            time >= self.start_time || time < self.end_time
+        }
+    }
+
+    /// The next time the window opens after `now`
+    pub fn next_opening(&self, now: NaiveDateTime) -> NaiveDateTime {
+        let opening = now.date().and_time(self.start_time);
+        if opening > now { opening } else { opening + Duration::days(1) }
+    }
+
+    /// When a job submitted at `now` (UTC) by a customer in the time zone `tz` may be
+    /// queued: None if any window is open (or there are no windows), otherwise the
+    /// earliest next opening, in UTC
+    pub fn release_time(windows: &[SubmissionWindow], now: DateTime<Utc>, tz: Tz) -> Option<DateTime<Utc>> {
+        let local = timezone::to_local(now, tz);
+        if windows.iter().any(|window| window.contains(local.time())) {
+            return None;
+        }
+        windows.iter().map(|window| timezone::to_utc(window.next_opening(local), tz)).min()
+    }
+}
+
+// For DB insertion with Diesel
+#[derive(Debug, Clone, Serialize, Deserialize, Insertable)]
+#[diesel(table_name = submission_windows)]
+pub struct NewSubmissionWindow {
+    pub id: Uuid,
+    pub job_type_id: Uuid,
+    pub customer_id: Option<Uuid>,
+    pub reseller_id: Option<Uuid>,
+    pub start_time: NaiveTime,
+    pub end_time: NaiveTime,
+}
@@ -8,7 +8,7 @@ use serde::{Deserialize, Serialize};
 // Removed unused import
 use std::io::Write;
 use uuid::Uuid;
-use chrono::NaiveDateTime;
+use chrono::{DateTime, Utc};
 
 use crate::diesel_schema::{wallets, wallet_transactions};
 
@@ -49,6 +49,16 @@ impl TransactionType {
             TransactionType::RefundCredit => "REFUND_CREDIT",
         }
     }
+    
+    /// Postings of a job counted to number a reservation or release of its funds, see
+    /// [`job_hold_reference`]
+    pub fn cycle_counter(&self) -> Option<TransactionType> {
+        match self {
+            TransactionType::Reserved => Some(TransactionType::Released),
+            TransactionType::Released => Some(TransactionType::Reserved),
+            _ => None,
+        }
+    }
 }
 
 impl ToString for TransactionType {
@@ -82,14 +92,55 @@ impl FromSql<Text, Pg> for TransactionType {
     }
 }
 
+/// What happens when a job's reservation would take a wallet below zero
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum OverdraftPolicy {
+    /// Refuse the reservation, so reservations never take the balance below zero
+    Deny,
+    /// Allow the balance down to minus the wallet's overdraft limit
+    Grace,
+    /// Allow the balance down to minus the overdraft limit, and refuse new submissions
+    /// while it is below zero until the wallet is topped up
+    Suspend,
+}
+
+impl OverdraftPolicy {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            OverdraftPolicy::Deny => "deny",
+            OverdraftPolicy::Grace => "grace",
+            OverdraftPolicy::Suspend => "suspend",
+        }
+    }
+
+    pub fn parse(s: &str) -> Option<Self> {
+        match s.to_lowercase().as_str() {
+            "deny" => Some(OverdraftPolicy::Deny),
+            "grace" => Some(OverdraftPolicy::Grace),
+            "suspend" => Some(OverdraftPolicy::Suspend),
+            _ => None,
+        }
+    }
+}
+
 #[derive(Debug, Clone, Serialize, Deserialize, Queryable, Selectable)]
 #[diesel(check_for_backend(diesel::pg::Pg))]
 pub struct Wallet {
     pub id: Uuid,
     pub customer_id: Uuid,
     pub balance_cents: i32,
-    pub created_at: Option<NaiveDateTime>,
-    pub updated_at: Option<NaiveDateTime>,
+    pub created_at: Option<DateTime<Utc>>,
+    pub updated_at: Option<DateTime<Utc>>,
+    /// Play-money balance charged by test-mode jobs
+    pub test_balance_cents: i32,
+    /// Promotional credit, spent on job charges before the balance
+    pub credit_cents: i32,
+    /// `deny`, `grace` or `suspend`, see [`OverdraftPolicy`]
+    pub overdraft_policy: String,
+    /// How far below zero reservations may take the balance under the `grace` and
+    /// `suspend` policies
+    pub overdraft_limit_cents: i32,
 }
 
 impl Wallet {
@@ -100,11 +151,43 @@ impl Wallet {
             balance_cents: initial_balance_cents,
             created_at: None,
             updated_at: None,
+            test_balance_cents: 0,
+            credit_cents: 0,
+            overdraft_policy: OverdraftPolicy::Deny.as_str().to_string(),
+            overdraft_limit_cents: 0,
+        }
+    }
+
+    pub fn overdraft_policy(&self) -> OverdraftPolicy {
+        OverdraftPolicy::parse(&self.overdraft_policy).unwrap_or(OverdraftPolicy::Deny)
+    }
+
+    /// Lowest balance a reservation may take the wallet to
+    pub fn balance_floor(&self) -> i32 {
+        match self.overdraft_policy() {
+            OverdraftPolicy::Deny => 0,
+            OverdraftPolicy::Grace | OverdraftPolicy::Suspend => -self.overdraft_limit_cents.max(0),
         }
     }
 
+    /// Funds that can still be reserved, overdraft included
     pub fn available_balance(&self) -> i32 {
-        self.balance_cents
+        self.balance_cents.saturating_sub(self.balance_floor())
+    }
+
+    /// Whether reserving the amount keeps the balance at or above the floor
+    pub fn can_reserve(&self, amount: i32) -> bool {
+        amount as i64 <= self.available_balance() as i64
+    }
+
+    /// How far the balance is below zero, or 0
+    pub fn overdraft_cents(&self) -> i32 {
+        self.balance_cents.saturating_neg().max(0)
+    }
+
+    /// Whether new submissions are refused until the wallet is topped up
+    pub fn submissions_suspended(&self) -> bool {
+        self.overdraft_policy() == OverdraftPolicy::Suspend && self.balance_cents < 0
     }
 }
 
@@ -117,6 +200,64 @@ pub struct NewWallet {
     pub balance_cents: i32,
 }
 
+/// Longest customer reference accepted, in characters
+pub const MAX_CUSTOMER_REFERENCE_LEN: usize = 128;
+/// Largest customer metadata accepted, in bytes of JSON
+pub const MAX_CUSTOMER_METADATA_BYTES: usize = 4096;
+
+/// Check a customer reference and its metadata before they are stored: the reference must
+/// not be blank or too long, and the metadata must be a small JSON object
+pub fn validate_customer_reference(reference: Option<&str>, metadata: Option<&serde_json::Value>) -> Result<(), String> {
+    if let Some(reference) = reference {
+        if reference.trim().is_empty() {
+            return Err("Customer reference must not be blank".to_string());
+        }
+        if reference.chars().count() > MAX_CUSTOMER_REFERENCE_LEN {
+            return Err(format!("Customer reference must have at most {} characters", MAX_CUSTOMER_REFERENCE_LEN));
+        }
+    }
+    if let Some(metadata) = metadata {
+        if !metadata.is_object() {
+            return Err("Customer metadata must be a JSON object".to_string());
+        }
+        if metadata.to_string().len() > MAX_CUSTOMER_METADATA_BYTES {
+            return Err(format!("Customer metadata must be at most {} bytes", MAX_CUSTOMER_METADATA_BYTES));
+        }
+    }
+    Ok(())
+}
+
+/// Namespace of the reference IDs derived from the keys of wallet postings
+const REFERENCE_NAMESPACE: Uuid = Uuid::from_u128(0x5c3e91a4_7d2b_4f08_b6e1_0a9d3f72c845);
+
+/// Reference ID of a wallet posting, derived from a key naming the operation, e.g.
+/// `job:{id}:charge`
+///
+/// A wallet records each reference once, so an operation retried after it went through
+/// is not posted again.
+pub fn reference_id(key: &str) -> Uuid {
+    Uuid::new_v5(&REFERENCE_NAMESPACE, key.as_bytes())
+}
+
+/// Reference ID of the charge of a job; a job is charged once, whoever bills it
+pub fn job_charge_reference(job_id: Uuid) -> Uuid {
+    reference_id(&format!("job:{}:charge", job_id))
+}
+
+/// Reference ID of a reservation or release of funds for a job, or None for other postings
+///
+/// Funds are reserved and released again on every attempt of a job, so these postings
+/// are numbered by `cycle`: the releases recorded for the job before a reservation, and
+/// its reservations before a release.
+pub fn job_hold_reference(job_id: Uuid, transaction_type: &TransactionType, cycle: i64) -> Option<Uuid> {
+    let operation = match transaction_type {
+        TransactionType::Reserved => "reserve",
+        TransactionType::Released => "release",
+        _ => return None,
+    };
+    Some(reference_id(&format!("job:{}:{}:{}", job_id, operation, cycle)))
+}
+
 #[derive(Debug, Clone, Serialize, Deserialize, Queryable, Selectable, Identifiable)]
 #[diesel(table_name = wallet_transactions)]
 #[diesel(check_for_backend(diesel::pg::Pg))]
@@ -129,7 +270,11 @@ pub struct WalletTransaction {
     pub reference_id: Option<Uuid>,
     pub description: Option<String>,
     pub job_id: Option<Uuid>,
-    pub created_at: Option<NaiveDateTime>,
+    pub created_at: Option<DateTime<Utc>>,
+    /// Customer's own reference, e.g. a purchase order number, for reconciliation
+    pub customer_reference: Option<String>,
+    /// Customer's own metadata given with the reference
+    pub customer_metadata: Option<serde_json::Value>,
 }
 
 impl WalletTransaction {
@@ -152,6 +297,8 @@ impl WalletTransaction {
             description,
             job_id,
             created_at: None,
+            customer_reference: None,
+            customer_metadata: None,
         }
     }
     
@@ -188,5 +335,9 @@ pub struct NewWalletTransaction {
     pub reference_id: Option<Uuid>,
     pub description: Option<String>,
     pub job_id: Option<Uuid>,
-    pub created_at: Option<NaiveDateTime>,
+    pub created_at: Option<DateTime<Utc>>,
+    /// Customer's own reference, e.g. a purchase order number, for reconciliation
+    pub customer_reference: Option<String>,
+    /// Customer's own metadata given with the reference
+    pub customer_metadata: Option<serde_json::Value>,
 }
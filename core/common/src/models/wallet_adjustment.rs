@@ -0,0 +1,157 @@
+use chrono::{DateTime, Utc};
+use diesel::prelude::*;
+use serde::{Deserialize, Serialize};
+use thiserror::Error;
+use uuid::Uuid;
+
+use crate::diesel_schema::wallet_adjustments;
+use crate::models::wallet::TransactionType;
+
+/// What a manual wallet change is for
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+pub enum AdjustmentKind {
+    /// Correction of the balance in either direction
+    Adjustment,
+    /// Credit returning money the customer was charged
+    Refund,
+}
+
+impl AdjustmentKind {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            AdjustmentKind::Adjustment => "adjustment",
+            AdjustmentKind::Refund => "refund",
+        }
+    }
+
+    pub fn parse(s: &str) -> Option<Self> {
+        match s.to_lowercase().as_str() {
+            "adjustment" => Some(AdjustmentKind::Adjustment),
+            "refund" => Some(AdjustmentKind::Refund),
+            _ => None,
+        }
+    }
+}
+
+/// Review state of a manual wallet change
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+pub enum AdjustmentStatus {
+    /// Waiting for a second admin
+    Pending,
+    /// Posted to the wallet
+    Approved,
+    /// Turned down; the wallet was not changed
+    Rejected,
+}
+
+impl AdjustmentStatus {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            AdjustmentStatus::Pending => "pending",
+            AdjustmentStatus::Approved => "approved",
+            AdjustmentStatus::Rejected => "rejected",
+        }
+    }
+
+    pub fn parse(s: &str) -> Option<Self> {
+        match s.to_lowercase().as_str() {
+            "pending" => Some(AdjustmentStatus::Pending),
+            "approved" => Some(AdjustmentStatus::Approved),
+            "rejected" => Some(AdjustmentStatus::Rejected),
+            _ => None,
+        }
+    }
+}
+
+/// Reasons a manual wallet change cannot be requested, reviewed or posted
+#[derive(Debug, Error, PartialEq, Eq)]
+pub enum AdjustmentError {
+    #[error("Amount must not be zero")]
+    ZeroAmount,
+
+    #[error("Refunds must credit the wallet")]
+    NegativeRefund,
+
+    #[error("Adjustment is already {0}")]
+    NotPending(String),
+
+    #[error("Adjustments above the approval threshold must be reviewed by another admin")]
+    SameReviewer,
+
+    #[error("Wallet balance of {balance_cents} cents does not cover a debit of {amount_cents} cents")]
+    InsufficientFunds { balance_cents: i32, amount_cents: i32 },
+}
+
+/// Manual change of a wallet balance made by an admin
+#[derive(Debug, Clone, Serialize, Deserialize, Queryable, Identifiable, Selectable)]
+#[diesel(table_name = wallet_adjustments)]
+#[diesel(check_for_backend(diesel::pg::Pg))]
+pub struct WalletAdjustment {
+    pub id: Uuid,
+    pub wallet_id: Uuid,
+    pub customer_id: Uuid,
+    pub kind: String,
+    /// Positive amounts credit the wallet, negative ones debit it
+    pub amount_cents: i32,
+    pub description: Option<String>,
+    pub job_id: Option<Uuid>,
+    pub status: String,
+    /// Whether the amount is above the threshold that needs a second admin
+    pub requires_approval: bool,
+    pub requested_by: String,
+    pub reviewed_by: Option<String>,
+    pub review_note: Option<String>,
+    /// Wallet transaction posted when the adjustment was approved
+    pub transaction_id: Option<Uuid>,
+    pub created_at: Option<DateTime<Utc>>,
+    pub reviewed_at: Option<DateTime<Utc>>,
+}
+
+impl WalletAdjustment {
+    pub fn kind(&self) -> Option<AdjustmentKind> {
+        AdjustmentKind::parse(&self.kind)
+    }
+
+    pub fn status(&self) -> Option<AdjustmentStatus> {
+        AdjustmentStatus::parse(&self.status)
+    }
+
+    /// Type of the wallet transaction the adjustment posts
+    pub fn transaction_type(&self) -> TransactionType {
+        match self.kind() {
+            Some(AdjustmentKind::Refund) => TransactionType::RefundCredit,
+            _ if self.amount_cents < 0 => TransactionType::Withdrawal,
+            _ => TransactionType::Deposit,
+        }
+    }
+}
+
+// For DB insertion with Diesel
+#[derive(Debug, Clone, Serialize, Deserialize, Insertable)]
+#[diesel(table_name = wallet_adjustments)]
+pub struct NewWalletAdjustment {
+    pub id: Uuid,
+    pub wallet_id: Uuid,
+    pub customer_id: Uuid,
+    pub kind: String,
+    pub amount_cents: i32,
+    pub description: Option<String>,
+    pub job_id: Option<Uuid>,
+    pub requires_approval: bool,
+    pub requested_by: String,
+}
+
+impl NewWalletAdjustment {
+    /// Check the amount fits the kind of adjustment
+    pub fn validate(&self) -> Result<(), AdjustmentError> {
+        if self.amount_cents == 0 {
+            return Err(AdjustmentError::ZeroAmount);
+        }
+        if AdjustmentKind::parse(&self.kind) == Some(AdjustmentKind::Refund) && self.amount_cents < 0 {
+            return Err(AdjustmentError::NegativeRefund);
+        }
+        Ok(())
+    }
+}
@@ -0,0 +1,151 @@
+use std::net::{IpAddr, Ipv4Addr};
+
+use serde::{Deserialize, Serialize};
+use uuid::Uuid;
+use chrono::{DateTime, Utc};
+use diesel::prelude::*;
+
+use crate::diesel_schema::customer_webhooks;
+
+/// Job lifecycle events a customer webhook can subscribe to
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq, Eq)]
+pub enum WebhookEventType {
+    JobCreated,
+    JobStarted,
+    JobSucceeded,
+    JobFailed,
+    JobCancelled,
+    /// The job was not started before its deadline and will not run
+    JobExpired,
+    /// The customer's spend or failure rate deviates sharply from its usual level
+    SpendingAnomaly,
+    /// A brute-force pattern in failed authentications; sent to the operators' alert
+    /// endpoint only, so it cannot be subscribed to
+    SecurityAlert,
+    /// Events held back for a customer's digest, sent together; follows the
+    /// subscriptions of the events it bundles, so it cannot be subscribed to
+    Digest,
+    /// Sample event sent by the test-fire endpoint; cannot be subscribed to
+    Test,
+}
+
+impl WebhookEventType {
+    /// Event types customers may subscribe to and set notification preferences for
+    pub const SUBSCRIBABLE: [WebhookEventType; 7] = [
+        WebhookEventType::JobCreated,
+        WebhookEventType::JobStarted,
+        WebhookEventType::JobSucceeded,
+        WebhookEventType::JobFailed,
+        WebhookEventType::JobCancelled,
+        WebhookEventType::JobExpired,
+        WebhookEventType::SpendingAnomaly,
+    ];
+
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            WebhookEventType::JobCreated => "job.created",
+            WebhookEventType::JobStarted => "job.started",
+            WebhookEventType::JobSucceeded => "job.succeeded",
+            WebhookEventType::JobFailed => "job.failed",
+            WebhookEventType::JobCancelled => "job.cancelled",
+            WebhookEventType::JobExpired => "job.expired",
+            WebhookEventType::SpendingAnomaly => "spending.anomaly",
+            WebhookEventType::SecurityAlert => "security.alert",
+            WebhookEventType::Digest => "notification.digest",
+            WebhookEventType::Test => "webhook.test",
+        }
+    }
+
+    pub fn parse(s: &str) -> Option<Self> {
+        match s.to_lowercase().as_str() {
+            "job.created" => Some(WebhookEventType::JobCreated),
+            "job.started" => Some(WebhookEventType::JobStarted),
+            "job.succeeded" => Some(WebhookEventType::JobSucceeded),
+            "job.failed" => Some(WebhookEventType::JobFailed),
+            "job.cancelled" => Some(WebhookEventType::JobCancelled),
+            "job.expired" => Some(WebhookEventType::JobExpired),
+            "spending.anomaly" => Some(WebhookEventType::SpendingAnomaly),
+            "security.alert" => Some(WebhookEventType::SecurityAlert),
+            "notification.digest" => Some(WebhookEventType::Digest),
+            "webhook.test" => Some(WebhookEventType::Test),
+            _ => None,
+        }
+    }
+
+    /// Whether customers may subscribe an endpoint to this event type
+    pub fn is_subscribable(&self) -> bool {
+        !matches!(self, WebhookEventType::Test | WebhookEventType::SecurityAlert | WebhookEventType::Digest)
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, Queryable, Identifiable, Selectable)]
+#[diesel(table_name = customer_webhooks)]
+#[diesel(check_for_backend(diesel::pg::Pg))]
+pub struct CustomerWebhook {
+    pub id: Uuid,
+    pub customer_id: Uuid,
+    pub url: String,
+    /// Shared secret used to sign outgoing payloads
+    pub secret: String,
+    /// Subscribed event types, stored by their string names (e.g. "job.succeeded")
+    pub event_types: Vec<String>,
+    pub active: bool,
+    pub last_tested_at: Option<DateTime<Utc>>,
+    pub last_test_status_code: Option<i32>,
+    pub last_test_response: Option<String>,
+    /// Start of the current run of consecutive delivery failures, if any
+    pub failing_since: Option<DateTime<Utc>>,
+    pub created_at: Option<DateTime<Utc>>,
+    pub updated_at: Option<DateTime<Utc>>,
+}
+
+impl CustomerWebhook {
+    pub fn generate_secret() -> String {
+        format!("whsec_{}", Uuid::new_v4().to_string().replace("-", ""))
+    }
+
+    /// Check whether this endpoint should receive the given event
+    pub fn is_subscribed_to(&self, event_type: WebhookEventType) -> bool {
+        self.active && self.event_types.iter().any(|e| e == event_type.as_str())
+    }
+}
+
+// For DB insertion with Diesel
+#[derive(Debug, Clone, Serialize, Deserialize, Insertable)]
+#[diesel(table_name = customer_webhooks)]
+pub struct NewCustomerWebhook {
+    pub id: Uuid,
+    pub customer_id: Uuid,
+    pub url: String,
+    pub secret: String,
+    pub event_types: Vec<String>,
+    pub active: bool,
+}
+
+/// Whether an address is on the public internet, so webhooks may be delivered to it
+///
+/// Loopback, private, link-local, shared, documentation and other reserved ranges are
+/// not: an endpoint there would let customers reach the API's own network.
+pub fn is_public_address(ip: IpAddr) -> bool {
+    match ip {
+        IpAddr::V4(ip) => is_public_ipv4(ip),
+        IpAddr::V6(ip) => match ip.to_ipv4_mapped() {
+            Some(mapped) => is_public_ipv4(mapped),
+            None => {
+                let documentation = ip.segments()[0] == 0x2001 && ip.segments()[1] == 0x0db8;
+                !(ip.is_loopback() || ip.is_unspecified() || ip.is_multicast() || ip.is_unique_local()
+                    || ip.is_unicast_link_local() || documentation)
+            }
+        },
+    }
+}
+
+fn is_public_ipv4(ip: Ipv4Addr) -> bool {
+    let [a, b, c, _] = ip.octets();
+    let shared = a == 100 && (64..128).contains(&b);
+    let benchmarking = a == 198 && (18..20).contains(&b);
+    let protocol_assignments = a == 192 && b == 0 && c == 0;
+    !(ip.is_private() || ip.is_loopback() || ip.is_link_local() || ip.is_unspecified() || ip.is_broadcast()
+        || ip.is_multicast() || ip.is_documentation() || a == 0 || a >= 240
+        || shared || benchmarking || protocol_assignments)
+}
@@ -0,0 +1,277 @@
+use std::collections::BTreeMap;
+
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use thiserror::Error;
+use uuid::Uuid;
+
+use crate::models::job_error::{codes, JobError};
+
+/// Headers the webhook processor sets itself
+const RESERVED_HEADERS: [&str; 4] = ["content-length", "content-type", "host", "transfer-encoding"];
+
+/// Reasons a webhook template is rejected
+#[derive(Debug, Clone, Error, PartialEq, Eq)]
+pub enum WebhookTemplateError {
+    #[error("Invalid header name {0:?}")]
+    InvalidHeaderName(String),
+    #[error("Invalid value for header {0}")]
+    InvalidHeaderValue(String),
+    #[error("Header {0} is set by the webhook processor")]
+    ReservedHeader(String),
+    #[error("Unknown placeholder {{{{ {0} }}}}, expected input, input.<path>, job.id or datetime")]
+    UnknownPlaceholder(String),
+    #[error("{0} requests have no body")]
+    BodyNotAllowed(&'static str),
+}
+
+/// HTTP method of a webhook request
+#[derive(Debug, Clone, Copy, Default, Serialize, Deserialize, PartialEq, Eq)]
+#[serde(rename_all = "UPPERCASE")]
+pub enum WebhookMethod {
+    Get,
+    #[default]
+    Post,
+    Put,
+    Patch,
+    Delete,
+}
+
+impl WebhookMethod {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            WebhookMethod::Get => "GET",
+            WebhookMethod::Post => "POST",
+            WebhookMethod::Put => "PUT",
+            WebhookMethod::Patch => "PATCH",
+            WebhookMethod::Delete => "DELETE",
+        }
+    }
+
+    /// Whether requests with this method carry a JSON body
+    pub fn has_body(&self) -> bool {
+        !matches!(self, WebhookMethod::Get | WebhookMethod::Delete)
+    }
+}
+
+/// How the webhook jobs of a job type call their URL
+///
+/// Header values and strings anywhere in the body may reference `{{ input }}`,
+/// `{{ input.<path> }}` into the job's input_data, `{{ job.id }}` and `{{ datetime }}`,
+/// the time the request is sent. A string holding nothing but a placeholder takes the
+/// referenced value as is; otherwise the value is interpolated as text. Input paths
+/// that do not exist resolve to null.
+#[derive(Debug, Clone, Default, Serialize, Deserialize, PartialEq)]
+#[serde(deny_unknown_fields)]
+pub struct WebhookTemplate {
+    #[serde(default)]
+    pub method: WebhookMethod,
+    #[serde(default)]
+    pub headers: BTreeMap<String, String>,
+    /// Body sent as JSON; the job's input_data without `webhook_url` when unset
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub body: Option<Value>,
+}
+
+/// A webhook request rendered for one job
+#[derive(Debug, Clone, Serialize, PartialEq)]
+pub struct WebhookRequest {
+    pub method: WebhookMethod,
+    pub headers: BTreeMap<String, String>,
+    pub body: Option<Value>,
+}
+
+impl WebhookTemplate {
+    /// Check the headers and every placeholder of the template
+    pub fn validate(&self) -> Result<(), WebhookTemplateError> {
+        for (name, value) in &self.headers {
+            if name.is_empty() || !name.bytes().all(is_token_byte) {
+                return Err(WebhookTemplateError::InvalidHeaderName(name.clone()));
+            }
+            if RESERVED_HEADERS.contains(&name.to_ascii_lowercase().as_str()) {
+                return Err(WebhookTemplateError::ReservedHeader(name.clone()));
+            }
+            if !is_header_value(value) {
+                return Err(WebhookTemplateError::InvalidHeaderValue(name.clone()));
+            }
+        }
+        if self.body.is_some() && !self.method.has_body() {
+            return Err(WebhookTemplateError::BodyNotAllowed(self.method.as_str()));
+        }
+
+        let mut references = Vec::new();
+        for value in self.headers.values() {
+            collect_references(&Value::String(value.clone()), &mut references);
+        }
+        if let Some(body) = &self.body {
+            collect_references(body, &mut references);
+        }
+        match references.into_iter().find(|reference| parse_reference(reference).is_none()) {
+            Some(reference) => Err(WebhookTemplateError::UnknownPlaceholder(reference)),
+            None => Ok(()),
+        }
+    }
+
+    /// Request a webhook job with the given input sends at `now`
+    ///
+    /// Fails with an input error when a header value interpolates input that cannot be
+    /// sent in a header.
+    pub fn render(&self, input: &Value, job_id: Uuid, now: DateTime<Utc>) -> Result<WebhookRequest, JobError> {
+        let variables = Variables { input, job_id, now };
+        let mut headers = BTreeMap::new();
+        for (name, value) in &self.headers {
+            let rendered = match render_value(&Value::String(value.clone()), &variables) {
+                Value::String(text) => text,
+                other => other.to_string(),
+            };
+            if !is_header_value(&rendered) {
+                return Err(JobError::input(codes::INVALID_INPUT, format!("Input rendered into header {} is not a valid header value", name)));
+            }
+            headers.insert(name.clone(), rendered);
+        }
+        let body = match &self.body {
+            Some(body) => Some(render_value(body, &variables)),
+            None if self.method.has_body() => Some(forwarded_input(input)),
+            None => None,
+        };
+        Ok(WebhookRequest { method: self.method, headers, body })
+    }
+}
+
+/// Body of webhook jobs without a template: their input_data without `webhook_url`
+fn forwarded_input(input: &Value) -> Value {
+    match input {
+        Value::Object(fields) => Value::Object(
+            fields.iter().filter(|(key, _)| *key != "webhook_url").map(|(key, field)| (key.clone(), field.clone())).collect()
+        ),
+        other => other.clone(),
+    }
+}
+
+fn is_token_byte(byte: u8) -> bool {
+    byte.is_ascii_alphanumeric() || b"!#$%&'*+-.^_`|~".contains(&byte)
+}
+
+fn is_header_value(value: &str) -> bool {
+    value.bytes().all(|byte| byte == b'\t' || (b' '..=b'~').contains(&byte))
+}
+
+struct Variables<'a> {
+    input: &'a Value,
+    job_id: Uuid,
+    now: DateTime<Utc>,
+}
+
+/// A placeholder's reference
+enum Reference<'a> {
+    /// Path into the job's input_data
+    Input(Vec<&'a str>),
+    JobId,
+    Datetime,
+}
+
+fn parse_reference(reference: &str) -> Option<Reference<'_>> {
+    match reference {
+        "input" => Some(Reference::Input(Vec::new())),
+        "job.id" => Some(Reference::JobId),
+        "datetime" => Some(Reference::Datetime),
+        _ => {
+            let path = reference.strip_prefix("input.")?;
+            let segments: Vec<&str> = path.split('.').collect();
+            segments.iter().all(|segment| !segment.is_empty()).then_some(Reference::Input(segments))
+        }
+    }
+}
+
+/// Split a string into literal text and `{{ reference }}` placeholders
+fn parse_placeholders(s: &str) -> Vec<Segment<'_>> {
+    let mut segments = Vec::new();
+    let mut rest = s;
+    while let Some(start) = rest.find("{{") {
+        let Some(len) = rest[start + 2..].find("}}") else { break };
+        if start > 0 {
+            segments.push(Segment::Text(&rest[..start]));
+        }
+        segments.push(Segment::Reference(rest[start + 2..start + 2 + len].trim()));
+        rest = &rest[start + 4 + len..];
+    }
+    if !rest.is_empty() {
+        segments.push(Segment::Text(rest));
+    }
+    segments
+}
+
+enum Segment<'a> {
+    Text(&'a str),
+    Reference(&'a str),
+}
+
+fn collect_references(value: &Value, references: &mut Vec<String>) {
+    match value {
+        Value::String(s) => {
+            for segment in parse_placeholders(s) {
+                if let Segment::Reference(reference) = segment {
+                    references.push(reference.to_string());
+                }
+            }
+        }
+        Value::Array(items) => items.iter().for_each(|item| collect_references(item, references)),
+        Value::Object(fields) => fields.values().for_each(|field| collect_references(field, references)),
+        _ => {}
+    }
+}
+
+/// Follow a path of object keys and array indices
+fn lookup(value: &Value, path: &[&str]) -> Value {
+    let mut current = value;
+    for segment in path {
+        let next = match current {
+            Value::Object(fields) => fields.get(*segment),
+            Value::Array(items) => segment.parse::<usize>().ok().and_then(|index| items.get(index)),
+            _ => None,
+        };
+        match next {
+            Some(next) => current = next,
+            None => return Value::Null,
+        }
+    }
+    current.clone()
+}
+
+fn resolve(reference: &str, variables: &Variables) -> Value {
+    match parse_reference(reference) {
+        Some(Reference::Input(path)) => lookup(variables.input, &path),
+        Some(Reference::JobId) => Value::String(variables.job_id.to_string()),
+        Some(Reference::Datetime) => Value::String(variables.now.to_rfc3339()),
+        None => Value::Null,
+    }
+}
+
+fn render_value(value: &Value, variables: &Variables) -> Value {
+    match value {
+        Value::String(s) => {
+            let segments = parse_placeholders(s);
+            if let [Segment::Reference(reference)] = segments.as_slice() {
+                return resolve(reference, variables);
+            }
+            let mut rendered = String::with_capacity(s.len());
+            for segment in segments {
+                match segment {
+                    Segment::Text(text) => rendered.push_str(text),
+                    Segment::Reference(reference) => match resolve(reference, variables) {
+                        Value::String(text) => rendered.push_str(&text),
+                        Value::Null => {}
+                        other => rendered.push_str(&other.to_string()),
+                    },
+                }
+            }
+            Value::String(rendered)
+        }
+        Value::Array(items) => Value::Array(items.iter().map(|item| render_value(item, variables)).collect()),
+        Value::Object(fields) => Value::Object(
+            fields.iter().map(|(key, field)| (key.clone(), render_value(field, variables))).collect()
+        ),
+        other => other.clone(),
+    }
+}
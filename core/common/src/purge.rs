@@ -0,0 +1,90 @@
+//! Permanent deletion of data past its retention, as required by data protection
+//! agreements
+
+use std::sync::Arc;
+
+use anyhow::Result;
+use chrono::{DateTime, Utc};
+use uuid::Uuid;
+
+use crate::database::with_reseller;
+use crate::models::purge::{PurgeProgress, PurgeReport};
+use crate::repositories::PurgeRepository;
+
+/// Deletes the data past its retention from every schema in batches, one transaction
+/// per batch, then verifies nothing past retention is left
+///
+/// Purged are finished jobs created before the job retention cutoff, with the rows
+/// referring to them, and unredacted job outputs whose retention ended.
+pub struct Purger {
+    purge_repo: Arc<dyn PurgeRepository>,
+    batch_size: i64,
+}
+
+impl Purger {
+    /// Create a purger deleting up to `batch_size` rows per transaction
+    pub fn new(purge_repo: Arc<dyn PurgeRepository>, batch_size: i64) -> Self {
+        Self { purge_repo, batch_size: batch_size.max(1) }
+    }
+
+    /// Purge the given schemas, reporting each batch to `progress`
+    ///
+    /// Jobs are only purged when `jobs_created_before` is set. A dry run deletes nothing
+    /// and reports what is past retention as remaining.
+    pub async fn run(
+        &self,
+        scopes: &[Option<Uuid>],
+        jobs_created_before: Option<DateTime<Utc>>,
+        dry_run: bool,
+        mut progress: impl FnMut(&PurgeProgress),
+    ) -> Result<PurgeReport> {
+        let mut report = PurgeReport::new(dry_run, jobs_created_before, self.batch_size);
+
+        for scope in scopes {
+            with_reseller(*scope, async {
+                if !dry_run {
+                    self.purge_scope(*scope, jobs_created_before, &mut report, &mut progress).await?;
+                }
+                // Verify in a second pass, so rows the batches missed are counted too
+                if let Some(created_before) = jobs_created_before {
+                    report.record_remaining("jobs", self.purge_repo.count_expired_jobs(created_before).await?);
+                }
+                report.record_remaining("job_unredacted_outputs", self.purge_repo.count_expired_unredacted_outputs().await?);
+                Ok::<_, anyhow::Error>(())
+            }).await?;
+            report.schemas += 1;
+        }
+
+        report.finish();
+        Ok(report)
+    }
+
+    /// Delete the data past retention from the schema the caller acts for
+    async fn purge_scope(
+        &self,
+        reseller_id: Option<Uuid>,
+        jobs_created_before: Option<DateTime<Utc>>,
+        report: &mut PurgeReport,
+        progress: &mut impl FnMut(&PurgeProgress),
+    ) -> Result<()> {
+        if let Some(created_before) = jobs_created_before {
+            loop {
+                let deleted = self.purge_repo.purge_expired_jobs(created_before, self.batch_size).await?;
+                if deleted.is_empty() {
+                    break;
+                }
+                report.record_batch(&deleted);
+                progress(&PurgeProgress { reseller_id, table: "jobs", batch: report.batches, deleted });
+            }
+        }
+        loop {
+            let deleted = self.purge_repo.purge_expired_unredacted_outputs(self.batch_size).await?;
+            if deleted.is_empty() {
+                break;
+            }
+            report.record_batch(&deleted);
+            progress(&PurgeProgress { reseller_id, table: "job_unredacted_outputs", batch: report.batches, deleted });
+        }
+        Ok(())
+    }
+}
@@ -0,0 +1,276 @@
+use std::collections::VecDeque;
+use std::env;
+use std::sync::Mutex;
+use std::time::Duration;
+
+use async_trait::async_trait;
+use chrono::Utc;
+use tracing::warn;
+
+use crate::models::job::PriorityLevel;
+use crate::queue::JobQueue;
+use crate::repositories::JobRepository;
+
+/// Priority queues, highest priority first; per-priority settings are given in this order
+const PRIORITIES: [PriorityLevel; 4] = [
+    PriorityLevel::Critical,
+    PriorityLevel::High,
+    PriorityLevel::Medium,
+    PriorityLevel::Low,
+];
+
+/// Position of a priority in `PRIORITIES`
+fn index(priority: &PriorityLevel) -> usize {
+    match priority {
+        PriorityLevel::Critical => 0,
+        PriorityLevel::High => 1,
+        PriorityLevel::Medium => 2,
+        PriorityLevel::Low => 3,
+    }
+}
+
+/// What a strategy may look at to decide the order of the priority queues
+pub struct DequeueContext<'a> {
+    pub queue: &'a dyn JobQueue,
+    pub jobs: &'a dyn JobRepository,
+}
+
+/// Policy deciding which priority queue a runner takes its next job from
+///
+/// The runner takes a job from the first non-empty queue in the returned order, so a
+/// strategy listing every queue lets runners steal work from the other queues while
+/// the preferred ones are empty.
+#[async_trait]
+pub trait DequeueStrategy: Send + Sync {
+    /// Priority queues in the order they are tried for the next job
+    async fn queue_order(&self, context: &DequeueContext<'_>) -> Vec<PriorityLevel>;
+
+    /// Record that a job was taken from a priority queue
+    fn record_dequeue(&self, _priority: &PriorityLevel) {}
+}
+
+/// Always serve the highest non-empty priority; lower priorities may starve under load
+pub struct StrictPriority;
+
+#[async_trait]
+impl DequeueStrategy for StrictPriority {
+    async fn queue_order(&self, _context: &DequeueContext<'_>) -> Vec<PriorityLevel> {
+        PRIORITIES.to_vec()
+    }
+}
+
+/// Give each priority a share of the dequeues proportional to its weight, measured
+/// over the most recent dequeues
+///
+/// The queue furthest below its share is tried first. Shares left unused by empty
+/// queues go to the others, and a queue that was idle catches up for at most one
+/// window.
+pub struct WeightedFairShare {
+    weights: [u32; 4],
+    window: usize,
+    recent: Mutex<VecDeque<usize>>,
+}
+
+impl WeightedFairShare {
+    /// Create the strategy with weights in priority order (critical, high, medium, low)
+    pub fn new(weights: [u32; 4], window: usize) -> Self {
+        Self {
+            weights,
+            window: window.max(1),
+            recent: Mutex::new(VecDeque::new()),
+        }
+    }
+
+    /// Queue order given the recent dequeues: the largest shortfall against the
+    /// weighted share first, ties and zero-weight queues in priority order
+    pub fn next_order(&self) -> Vec<PriorityLevel> {
+        let recent = self.recent.lock().unwrap_or_else(|e| e.into_inner());
+        let mut counts = [0usize; 4];
+        for served in recent.iter() {
+            counts[*served] += 1;
+        }
+        let total_weight: u32 = self.weights.iter().sum();
+        let served = recent.len().max(1) as f64;
+
+        let shortfall = |i: usize| {
+            if self.weights[i] == 0 {
+                return f64::NEG_INFINITY;
+            }
+            self.weights[i] as f64 / total_weight as f64 - counts[i] as f64 / served
+        };
+        let mut order: Vec<usize> = (0..PRIORITIES.len()).collect();
+        order.sort_by(|a, b| shortfall(*b).total_cmp(&shortfall(*a)).then(a.cmp(b)));
+        order.into_iter().map(|i| PRIORITIES[i].clone()).collect()
+    }
+}
+
+#[async_trait]
+impl DequeueStrategy for WeightedFairShare {
+    async fn queue_order(&self, _context: &DequeueContext<'_>) -> Vec<PriorityLevel> {
+        self.next_order()
+    }
+
+    fn record_dequeue(&self, priority: &PriorityLevel) {
+        let mut recent = self.recent.lock().unwrap_or_else(|e| e.into_inner());
+        recent.push_back(index(priority));
+        while recent.len() > self.window {
+            recent.pop_front();
+        }
+    }
+}
+
+/// Serve strictly by priority unless the oldest job of a queue has waited longer than
+/// the queue's maximum wait; overdue queues go first, the most overdue first
+///
+/// Waits are read from the job records, so jobs stored in a reseller schema count as
+/// not overdue.
+pub struct DeadlineAware {
+    max_wait: [Duration; 4],
+}
+
+impl DeadlineAware {
+    /// Create the strategy with maximum waits in priority order (critical, high, medium, low)
+    pub fn new(max_wait: [Duration; 4]) -> Self {
+        Self { max_wait }
+    }
+
+    /// Queue order given how long the oldest job of each queue has waited, in
+    /// priority order (None for empty queues)
+    pub fn order_for_waits(&self, waits: &[Option<Duration>; 4]) -> Vec<PriorityLevel> {
+        let overdue = |i: usize| {
+            waits[i]
+                .filter(|wait| *wait > self.max_wait[i])
+                .map(|wait| wait.as_secs_f64() / self.max_wait[i].as_secs_f64().max(f64::EPSILON))
+        };
+        let mut order: Vec<usize> = (0..PRIORITIES.len()).collect();
+        order.sort_by(|a, b| match (overdue(*a), overdue(*b)) {
+            (Some(a_ratio), Some(b_ratio)) => b_ratio.total_cmp(&a_ratio),
+            (Some(_), None) => std::cmp::Ordering::Less,
+            (None, Some(_)) => std::cmp::Ordering::Greater,
+            (None, None) => a.cmp(b),
+        });
+        order.into_iter().map(|i| PRIORITIES[i].clone()).collect()
+    }
+
+    /// How long the oldest job of a queue has waited, if the queue has one
+    async fn oldest_wait(context: &DequeueContext<'_>, priority: &PriorityLevel) -> anyhow::Result<Option<Duration>> {
+        let Some(job_id) = context.queue.peek_job_by_priority(priority.clone()).await? else {
+            return Ok(None);
+        };
+        let job = context.jobs.find_by_id(job_id).await?;
+
+        // Jobs held back by a submission window wait from their release
+        let queued_at = job.scheduled_for.or(job.created_at).unwrap_or_else(Utc::now);
+        Ok((Utc::now() - queued_at).to_std().ok())
+    }
+}
+
+#[async_trait]
+impl DequeueStrategy for DeadlineAware {
+    async fn queue_order(&self, context: &DequeueContext<'_>) -> Vec<PriorityLevel> {
+        let mut waits = [None; 4];
+        for (i, priority) in PRIORITIES.iter().enumerate() {
+            waits[i] = Self::oldest_wait(context, priority).await
+                .unwrap_or_else(|e| {
+                    warn!("Failed to read the oldest job of the priority {} queue: {}", priority.as_i32(), e);
+                    None
+                });
+        }
+        self.order_for_waits(&waits)
+    }
+}
+
+/// Built-in dequeue policies
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum DequeuePolicy {
+    StrictPriority,
+    WeightedFairShare,
+    DeadlineAware,
+}
+
+impl DequeuePolicy {
+    /// Parse a policy from its configuration value
+    pub fn parse(value: &str) -> Option<Self> {
+        match value {
+            "strict_priority" => Some(Self::StrictPriority),
+            "weighted_fair_share" => Some(Self::WeightedFairShare),
+            "deadline_aware" => Some(Self::DeadlineAware),
+            _ => None,
+        }
+    }
+
+    /// Configuration value of the policy
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Self::StrictPriority => "strict_priority",
+            Self::WeightedFairShare => "weighted_fair_share",
+            Self::DeadlineAware => "deadline_aware",
+        }
+    }
+}
+
+/// Configuration of how runners choose between the priority queues
+#[derive(Debug, Clone, PartialEq)]
+pub struct DequeueConfig {
+    pub policy: DequeuePolicy,
+    /// Weighted fair share: relative share of dequeues per priority (critical, high, medium, low)
+    pub weights: [u32; 4],
+    /// Weighted fair share: number of recent dequeues the shares are measured over
+    pub window: usize,
+    /// Deadline aware: longest wait in seconds per priority (critical, high, medium, low)
+    /// before the queue is served ahead of higher priorities
+    pub max_wait_secs: [u64; 4],
+}
+
+impl Default for DequeueConfig {
+    fn default() -> Self {
+        Self {
+            policy: DequeuePolicy::StrictPriority,
+            weights: [8, 4, 2, 1],
+            window: 100,
+            max_wait_secs: [10, 60, 300, 900],
+        }
+    }
+}
+
+/// Parse four comma-separated values, one per priority
+fn parse_per_priority<T: std::str::FromStr + Copy + Default>(value: &str) -> Option<[T; 4]> {
+    let values: Vec<T> = value.split(',').map(|part| part.trim().parse().ok()).collect::<Option<_>>()?;
+    let mut parsed = [T::default(); 4];
+    (values.len() == parsed.len()).then(|| {
+        parsed.copy_from_slice(&values);
+        parsed
+    })
+}
+
+impl DequeueConfig {
+    /// Load the configuration from `DEQUEUE_*` environment variables, using defaults for unset values
+    pub fn from_env() -> Self {
+        let defaults = Self::default();
+        Self {
+            policy: env::var("DEQUEUE_POLICY").ok()
+                .and_then(|value| DequeuePolicy::parse(&value))
+                .unwrap_or(defaults.policy),
+            weights: env::var("DEQUEUE_WEIGHTS").ok()
+                .and_then(|value| parse_per_priority(&value))
+                .filter(|weights: &[u32; 4]| weights.iter().any(|weight| *weight > 0))
+                .unwrap_or(defaults.weights),
+            window: env::var("DEQUEUE_FAIR_SHARE_WINDOW").ok()
+                .and_then(|value| value.parse().ok())
+                .filter(|window| *window > 0)
+                .unwrap_or(defaults.window),
+            max_wait_secs: env::var("DEQUEUE_MAX_WAIT_SECS").ok()
+                .and_then(|value| parse_per_priority(&value))
+                .unwrap_or(defaults.max_wait_secs),
+        }
+    }
+
+    /// Build the strategy of the configured policy
+    pub fn strategy(&self) -> Box<dyn DequeueStrategy> {
+        match self.policy {
+            DequeuePolicy::StrictPriority => Box::new(StrictPriority),
+            DequeuePolicy::WeightedFairShare => Box::new(WeightedFairShare::new(self.weights, self.window)),
+            DequeuePolicy::DeadlineAware => Box::new(DeadlineAware::new(self.max_wait_secs.map(Duration::from_secs))),
+        }
+    }
+}
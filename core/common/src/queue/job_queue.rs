@@ -1,32 +1,158 @@
+use std::env;
+use std::sync::Arc;
+use std::time::Duration;
+
 use async_trait::async_trait;
+use chrono::{DateTime, Utc};
+use redis::{ConnectionAddr, ConnectionInfo, IntoConnectionInfo};
+use serde::{Deserialize, Serialize};
 use uuid::Uuid;
 
+use crate::database::PoolStatus;
 use crate::models::job::PriorityLevel;
 use crate::queue::error::QueueError;
+use crate::queue::{InMemoryJobQueue, RedisJobQueue};
+
+/// Where a job queue keeps its jobs
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum QueueBackend {
+    /// A Redis server or cluster, shared by every API replica and runner
+    #[default]
+    Redis,
+    /// The memory of the process, for tests and local development without Redis
+    Memory,
+}
+
+impl QueueBackend {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            QueueBackend::Redis => "redis",
+            QueueBackend::Memory => "memory",
+        }
+    }
+
+    pub fn parse(s: &str) -> Option<Self> {
+        match s.to_lowercase().as_str() {
+            "redis" => Some(QueueBackend::Redis),
+            "memory" => Some(QueueBackend::Memory),
+            _ => None,
+        }
+    }
+}
 
 /// Configuration for a job queue
 #[derive(Debug, Clone)]
 pub struct JobQueueConfig {
-    /// Redis URL (e.g., "redis://127.0.0.1:6379")
+    /// Where jobs are kept; the Redis settings are ignored for the memory backend
+    pub backend: QueueBackend,
+    /// Redis URL (e.g., "redis://127.0.0.1:6379", or "rediss://..." for TLS)
     pub redis_url: String,
     /// Base key prefix for all queue keys
     pub key_prefix: String,
     /// Connection pool size
     pub pool_size: u32,
+    /// Time an operation waits for a connection of the pool before it fails
+    pub pool_timeout: Duration,
     /// Queue timeout in seconds
     pub timeout_seconds: u64,
+    /// URLs of Redis Cluster nodes to discover the cluster from; when set, the queue
+    /// connects to the cluster instead of `redis_url`
+    pub cluster_nodes: Vec<String>,
+    /// AUTH username, replacing the one in the URLs
+    pub username: Option<String>,
+    /// AUTH password, replacing the one in the URLs
+    pub password: Option<String>,
+    /// Connect over TLS even where the URLs use `redis://`
+    pub tls: bool,
+    /// Time allowed to establish a connection
+    pub connect_timeout: Duration,
+    /// Attempts to get a connection before an operation fails
+    pub reconnect_attempts: u32,
+    /// Wait before the second attempt, doubled for every further one
+    pub reconnect_backoff: Duration,
+    /// Longest wait between two attempts
+    pub max_reconnect_backoff: Duration,
 }
 
 impl JobQueueConfig {
     pub fn new(redis_url: String) -> Self {
         Self {
+            backend: QueueBackend::Redis,
             redis_url,
             key_prefix: "innosystem:jobs".to_string(),
             pool_size: 10,
+            pool_timeout: Duration::from_secs(5),
             timeout_seconds: 60,
+            cluster_nodes: Vec::new(),
+            username: None,
+            password: None,
+            tls: false,
+            connect_timeout: Duration::from_secs(5),
+            reconnect_attempts: 5,
+            reconnect_backoff: Duration::from_millis(100),
+            max_reconnect_backoff: Duration::from_secs(5),
+        }
+    }
+    
+    /// Configuration for `redis_url` with the topology, credentials, pool and
+    /// reconnection settings of the `REDIS_*` environment variables
+    ///
+    /// `REDIS_CLUSTER_NODES` is a comma-separated list of node URLs, and
+    /// `QUEUE_BACKEND=memory` keeps jobs in memory instead.
+    pub fn from_env(redis_url: String) -> Self {
+        let defaults = Self::new(redis_url);
+        Self {
+            backend: env::var("QUEUE_BACKEND").ok()
+                .and_then(|value| QueueBackend::parse(&value))
+                .unwrap_or(defaults.backend),
+            pool_size: env::var("REDIS_POOL_SIZE").ok()
+                .and_then(|value| value.parse().ok())
+                .filter(|size| *size > 0)
+                .unwrap_or(defaults.pool_size),
+            pool_timeout: env::var("REDIS_POOL_TIMEOUT_MS").ok()
+                .and_then(|value| value.parse().ok())
+                .filter(|ms| *ms > 0)
+                .map(Duration::from_millis)
+                .unwrap_or(defaults.pool_timeout),
+            cluster_nodes: env::var("REDIS_CLUSTER_NODES").ok()
+                .map(|nodes| nodes.split(',')
+                    .map(|node| node.trim().to_string())
+                    .filter(|node| !node.is_empty())
+                    .collect())
+                .unwrap_or_default(),
+            username: env::var("REDIS_USERNAME").ok()
+                .filter(|username| !username.is_empty()),
+            password: env::var("REDIS_PASSWORD").ok()
+                .filter(|password| !password.is_empty()),
+            tls: env::var("REDIS_TLS").ok()
+                .and_then(|value| value.parse().ok())
+                .unwrap_or(defaults.tls),
+            connect_timeout: env::var("REDIS_CONNECT_TIMEOUT_MS").ok()
+                .and_then(|value| value.parse().ok())
+                .filter(|ms| *ms > 0)
+                .map(Duration::from_millis)
+                .unwrap_or(defaults.connect_timeout),
+            reconnect_attempts: env::var("REDIS_RECONNECT_ATTEMPTS").ok()
+                .and_then(|value| value.parse().ok())
+                .filter(|attempts| *attempts > 0)
+                .unwrap_or(defaults.reconnect_attempts),
+            reconnect_backoff: env::var("REDIS_RECONNECT_BACKOFF_MS").ok()
+                .and_then(|value| value.parse().ok())
+                .map(Duration::from_millis)
+                .unwrap_or(defaults.reconnect_backoff),
+            max_reconnect_backoff: env::var("REDIS_MAX_RECONNECT_BACKOFF_MS").ok()
+                .and_then(|value| value.parse().ok())
+                .map(Duration::from_millis)
+                .unwrap_or(defaults.max_reconnect_backoff),
+            ..defaults
         }
     }
     
+    pub fn with_backend(mut self, backend: QueueBackend) -> Self {
+        self.backend = backend;
+        self
+    }
+    
     pub fn with_prefix(mut self, prefix: &str) -> Self {
         self.key_prefix = prefix.to_string();
         self
@@ -41,6 +167,85 @@ impl JobQueueConfig {
         self.timeout_seconds = seconds;
         self
     }
+    
+    pub fn with_cluster_nodes(mut self, nodes: Vec<String>) -> Self {
+        self.cluster_nodes = nodes;
+        self
+    }
+    
+    pub fn with_credentials(mut self, username: Option<String>, password: Option<String>) -> Self {
+        self.username = username;
+        self.password = password;
+        self
+    }
+    
+    pub fn with_tls(mut self, tls: bool) -> Self {
+        self.tls = tls;
+        self
+    }
+    
+    /// Whether the queue connects to a Redis Cluster
+    pub fn is_cluster(&self) -> bool {
+        !self.cluster_nodes.is_empty()
+    }
+    
+    /// Prefix of the queue's keys as stored
+    ///
+    /// On a cluster the prefix is a hash tag, so every key of the queue lives in the same
+    /// slot and commands and transactions spanning several of them keep working.
+    pub fn stored_key_prefix(&self) -> String {
+        if self.is_cluster() && !self.key_prefix.contains('{') {
+            format!("{{{}}}", self.key_prefix)
+        } else {
+            self.key_prefix.clone()
+        }
+    }
+    
+    /// Connection details for one of the configured URLs, with the configured
+    /// credentials and TLS applied
+    pub fn connection_info(&self, url: &str) -> Result<ConnectionInfo, QueueError> {
+        let mut info = url.into_connection_info()
+            .map_err(|e| QueueError::Configuration(format!("Invalid Redis URL: {}", e)))?;
+        if let Some(username) = &self.username {
+            info.redis.username = Some(username.clone());
+        }
+        if let Some(password) = &self.password {
+            info.redis.password = Some(password.clone());
+        }
+        if self.tls {
+            if let ConnectionAddr::Tcp(host, port) = info.addr {
+                info.addr = ConnectionAddr::TcpTls { host, port, insecure: false, tls_params: None };
+            }
+        }
+        Ok(info)
+    }
+    
+    /// Wait before the given retry of a connection, counting from 1: the backoff,
+    /// doubled for every further retry up to the maximum
+    pub fn reconnect_delay(&self, retry: u32) -> Duration {
+        let factor = 2u32.saturating_pow(retry.saturating_sub(1));
+        self.reconnect_backoff.saturating_mul(factor).min(self.max_reconnect_backoff)
+    }
+}
+
+/// Open the job queue of the configured backend
+pub async fn connect(config: JobQueueConfig) -> Result<Arc<dyn JobQueue>, QueueError> {
+    match config.backend {
+        QueueBackend::Redis => Ok(Arc::new(RedisJobQueue::new(config).await?)),
+        QueueBackend::Memory => Ok(Arc::new(InMemoryJobQueue::new(config))),
+    }
+}
+
+/// An entry of a queue that is not a job ID, set aside so it does not block the queue
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+pub struct QuarantinedEntry {
+    /// Entry as it was found in the queue
+    pub value: String,
+    /// Key of the list or set the entry was taken from
+    pub source: String,
+    /// Why the entry could not be processed
+    pub reason: String,
+    pub quarantined_at: DateTime<Utc>,
 }
 
 /// Trait defining the job queue interface
@@ -55,6 +260,17 @@ pub trait JobQueue: Send + Sync {
     /// Pop a job from the queue with timeout
     async fn pop_job_with_timeout(&self, timeout_seconds: u64) -> Result<Option<Uuid>, QueueError>;
     
+    /// Pop a job from the queue (blocking), along with the priority queue it was taken from
+    async fn pop_job_with_priority(&self) -> Result<Option<(Uuid, PriorityLevel)>, QueueError>;
+    
+    /// Pop a job from the first non-empty of the given priority queues, waiting up to
+    /// the timeout for one to receive a job
+    async fn pop_job_from(&self, priorities: &[PriorityLevel], timeout_seconds: u64) -> Result<Option<(Uuid, PriorityLevel)>, QueueError>;
+    
+    /// Pop up to `max` jobs from the given priority queues in order, without waiting
+    /// if they are all empty; each queue costs a single round trip
+    async fn try_pop_jobs_from(&self, priorities: &[PriorityLevel], max: usize) -> Result<Vec<(Uuid, PriorityLevel)>, QueueError>;
+    
     /// Get the number of jobs in the queue
     async fn queue_length(&self) -> Result<usize, QueueError>;
     
@@ -64,9 +280,34 @@ pub trait JobQueue: Send + Sync {
     /// Peek at the next job in the queue without removing it
     async fn peek_next_job(&self) -> Result<Option<Uuid>, QueueError>;
     
+    /// Peek at the oldest job of a priority queue without removing it
+    async fn peek_job_by_priority(&self, priority: PriorityLevel) -> Result<Option<Uuid>, QueueError>;
+    
     /// Schedule a job for future execution
     async fn schedule_job(&self, job_id: Uuid, execute_at: chrono::DateTime<chrono::Utc>) -> Result<(), QueueError>;
     
-    /// Get jobs that are scheduled for execution now
-    async fn get_due_scheduled_jobs(&self) -> Result<Vec<Uuid>, QueueError>;
+    /// Claim up to `max` of the scheduled jobs that are due, earliest first
+    ///
+    /// Claiming takes the jobs off the schedule atomically, so when several runners
+    /// poll at once each due job is handed to exactly one of them.
+    async fn claim_due_scheduled_jobs(&self, max: usize) -> Result<Vec<Uuid>, QueueError>;
+    
+    /// Get the most recently quarantined entries, newest first
+    async fn quarantined_entries(&self, limit: usize) -> Result<Vec<QuarantinedEntry>, QueueError>;
+    
+    /// Get the number of entries held in quarantine
+    async fn quarantine_length(&self) -> Result<usize, QueueError>;
+    
+    /// Get the number of entries quarantined since the queue was created, including
+    /// those no longer held
+    async fn quarantined_total(&self) -> Result<u64, QueueError>;
+    
+    /// Drop every entry held in quarantine, returning how many were dropped
+    async fn clear_quarantine(&self) -> Result<usize, QueueError>;
+    
+    /// Check that the queue's Redis answers, without retrying
+    async fn health_check(&self) -> Result<(), QueueError>;
+    
+    /// Utilization of the queue's connection pool and the checkouts that waited on it
+    fn pool_status(&self) -> PoolStatus;
 }
@@ -0,0 +1,228 @@
+use std::collections::{BTreeMap, HashSet};
+use std::env;
+use std::sync::Mutex;
+use std::time::Duration;
+
+use bb8_redis::{
+    bb8::Pool,
+    redis::{AsyncCommands, Script},
+    RedisConnectionManager,
+};
+use serde::Serialize;
+use tracing::{info, warn};
+use uuid::Uuid;
+
+use crate::queue::QueueError;
+
+/// Take the lease if it is free, or extend it if this instance already holds it
+const ACQUIRE_SCRIPT: &str = r#"
+local holder = redis.call('GET', KEYS[1])
+if holder == ARGV[1] then
+    redis.call('PEXPIRE', KEYS[1], ARGV[2])
+    return 1
+end
+if not holder then
+    redis.call('SET', KEYS[1], ARGV[1], 'PX', ARGV[2])
+    return 1
+end
+return 0
+"#;
+
+/// Configuration of leader election between API replicas for periodic background tasks
+#[derive(Debug, Clone)]
+pub struct LeaderElectionConfig {
+    /// Whether replicas coordinate; when disabled every instance runs every task
+    pub enabled: bool,
+    /// Name of this instance as shown to the other replicas and in the admin API
+    pub instance_id: String,
+    /// Base key prefix of the leases
+    pub key_prefix: String,
+    /// Shortest lease; a task's lease is at least three of its intervals
+    pub min_lease: Duration,
+    /// How long to wait for Redis before running the task locally
+    pub redis_timeout: Duration,
+}
+
+impl Default for LeaderElectionConfig {
+    fn default() -> Self {
+        Self {
+            enabled: true,
+            instance_id: format!("api-{}", &Uuid::new_v4().simple().to_string()[..8]),
+            key_prefix: "innosystem:leader".to_string(),
+            min_lease: Duration::from_secs(30),
+            redis_timeout: Duration::from_secs(2),
+        }
+    }
+}
+
+impl LeaderElectionConfig {
+    /// Load the configuration from `CLUSTER_*` environment variables, using defaults for unset values
+    ///
+    /// The instance ID defaults to the host name, which is the pod or container name
+    /// when running in Kubernetes or Docker.
+    pub fn from_env() -> Self {
+        let defaults = Self::default();
+        Self {
+            enabled: env::var("CLUSTER_LEADER_ELECTION").ok()
+                .and_then(|value| value.parse().ok())
+                .unwrap_or(defaults.enabled),
+            instance_id: env::var("CLUSTER_INSTANCE_ID").ok()
+                .or_else(|| env::var("HOSTNAME").ok())
+                .filter(|id| !id.is_empty())
+                .unwrap_or(defaults.instance_id),
+            key_prefix: defaults.key_prefix,
+            min_lease: env::var("CLUSTER_MIN_LEASE_SECS").ok()
+                .and_then(|value| value.parse().ok())
+                .filter(|secs| *secs > 0)
+                .map(Duration::from_secs)
+                .unwrap_or(defaults.min_lease),
+            redis_timeout: env::var("CLUSTER_REDIS_TIMEOUT_MS").ok()
+                .and_then(|value| value.parse().ok())
+                .filter(|ms| *ms > 0)
+                .map(Duration::from_millis)
+                .unwrap_or(defaults.redis_timeout),
+        }
+    }
+}
+
+/// Current holder of a task's lease
+#[derive(Debug, Clone, Serialize)]
+pub struct TaskLeader {
+    pub task: String,
+    /// Instance holding the lease, if any
+    pub leader: Option<String>,
+    /// Whether this instance holds the lease
+    pub is_self: bool,
+    /// Milliseconds until the lease expires unless renewed
+    pub lease_remaining_ms: Option<i64>,
+}
+
+/// Leadership of the periodic tasks as seen from this instance
+#[derive(Debug, Clone, Serialize)]
+pub struct ClusterStatus {
+    pub instance_id: String,
+    pub leader_election: bool,
+    pub tasks: Vec<TaskLeader>,
+}
+
+/// Redis lease based leader election, so each periodic task runs on one replica at a time
+///
+/// Each tick the task's leader renews its lease and the other replicas skip the task;
+/// when the leader stops renewing, the first replica to tick after the lease expired
+/// takes over. If Redis cannot be reached the task runs locally, as it would without
+/// coordination, so background work never stops on a Redis outage alone.
+pub struct LeaderElection {
+    pool: Pool<RedisConnectionManager>,
+    config: LeaderElectionConfig,
+    /// Tasks that asked for leadership, with their lease
+    tasks: Mutex<BTreeMap<String, Duration>>,
+    /// Tasks this instance currently leads
+    held: Mutex<HashSet<String>>,
+}
+
+impl LeaderElection {
+    /// Create the leader election against the given Redis
+    pub async fn new(redis_url: &str, config: LeaderElectionConfig) -> Result<Self, QueueError> {
+        let manager = RedisConnectionManager::new(redis_url)
+            .map_err(|e| QueueError::Connection(format!("Failed to create Redis manager: {}", e)))?;
+
+        let pool = Pool::builder()
+            .max_size(2)
+            .connection_timeout(config.redis_timeout)
+            .build(manager)
+            .await
+            .map_err(|e| QueueError::Connection(format!("Failed to create Redis pool: {}", e)))?;
+
+        Ok(Self {
+            pool,
+            config,
+            tasks: Mutex::new(BTreeMap::new()),
+            held: Mutex::new(HashSet::new()),
+        })
+    }
+
+    /// Name of this instance
+    pub fn instance_id(&self) -> &str {
+        &self.config.instance_id
+    }
+
+    /// Get the Redis key of a task's lease
+    fn lease_key(&self, task: &str) -> String {
+        format!("{}:{}", self.config.key_prefix, task)
+    }
+
+    /// Whether this instance should run the task now; call once per tick of a task
+    /// run every `interval`
+    pub async fn acquire(&self, task: &str, interval: Duration) -> bool {
+        let lease = (interval * 3).max(self.config.min_lease);
+        self.tasks.lock().unwrap_or_else(|e| e.into_inner()).insert(task.to_string(), lease);
+        if !self.config.enabled {
+            return true;
+        }
+
+        let acquired = match self.try_acquire(task, lease).await {
+            Ok(acquired) => acquired,
+            Err(e) => {
+                warn!("Failed to coordinate task {} with the other replicas, running it locally: {}", task, e);
+                return true;
+            }
+        };
+
+        let mut held = self.held.lock().unwrap_or_else(|e| e.into_inner());
+        if acquired && held.insert(task.to_string()) {
+            info!("Instance {} is now the leader of task {}", self.config.instance_id, task);
+        } else if !acquired && held.remove(task) {
+            info!("Instance {} lost the leadership of task {}", self.config.instance_id, task);
+        }
+        acquired
+    }
+
+    async fn try_acquire(&self, task: &str, lease: Duration) -> Result<bool, QueueError> {
+        let mut conn = self.pool.get().await
+            .map_err(|e| QueueError::Connection(format!("Failed to get Redis connection: {}", e)))?;
+
+        let acquired: i32 = Script::new(ACQUIRE_SCRIPT)
+            .key(self.lease_key(task))
+            .arg(&self.config.instance_id)
+            .arg(lease.as_millis() as u64)
+            .invoke_async(&mut *conn)
+            .await
+            .map_err(QueueError::Redis)?;
+        Ok(acquired == 1)
+    }
+
+    /// Report the leader of every task this instance has run or skipped
+    pub async fn status(&self) -> Result<ClusterStatus, QueueError> {
+        let tasks: Vec<String> = self.tasks.lock().unwrap_or_else(|e| e.into_inner()).keys().cloned().collect();
+
+        let mut leaders = Vec::with_capacity(tasks.len());
+        if self.config.enabled {
+            let mut conn = self.pool.get().await
+                .map_err(|e| QueueError::Connection(format!("Failed to get Redis connection: {}", e)))?;
+            for task in tasks {
+                let key = self.lease_key(&task);
+                let leader: Option<String> = conn.get(&key).await.map_err(QueueError::Redis)?;
+                let remaining: i64 = conn.pttl(&key).await.map_err(QueueError::Redis)?;
+                leaders.push(TaskLeader {
+                    is_self: leader.as_deref() == Some(self.config.instance_id.as_str()),
+                    lease_remaining_ms: leader.as_ref().map(|_| remaining).filter(|ms| *ms >= 0),
+                    leader,
+                    task,
+                });
+            }
+        } else {
+            leaders.extend(tasks.into_iter().map(|task| TaskLeader {
+                task,
+                leader: Some(self.config.instance_id.clone()),
+                is_self: true,
+                lease_remaining_ms: None,
+            }));
+        }
+
+        Ok(ClusterStatus {
+            instance_id: self.config.instance_id.clone(),
+            leader_election: self.config.enabled,
+            tasks: leaders,
+        })
+    }
+}
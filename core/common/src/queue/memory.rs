@@ -0,0 +1,205 @@
+use std::collections::{BTreeMap, HashMap, VecDeque};
+use std::sync::{Mutex, MutexGuard};
+use std::time::Duration;
+
+use async_trait::async_trait;
+use chrono::{DateTime, Utc};
+use tokio::sync::Notify;
+use tokio::time::Instant;
+use uuid::Uuid;
+
+use crate::database::PoolStatus;
+use crate::models::job::PriorityLevel;
+use crate::queue::{JobQueue, JobQueueConfig, QuarantinedEntry, QueueError};
+
+/// All priority queues, highest priority first
+const PRIORITIES: [PriorityLevel; 4] = [
+    PriorityLevel::Critical,
+    PriorityLevel::High,
+    PriorityLevel::Medium,
+    PriorityLevel::Low,
+];
+
+/// Jobs held by an in-memory queue
+#[derive(Default)]
+struct QueueState {
+    /// Waiting jobs of each priority, oldest first
+    pending: BTreeMap<PriorityLevel, VecDeque<Uuid>>,
+    /// Scheduled jobs with their execution time; scheduling a job again moves it
+    scheduled: HashMap<Uuid, DateTime<Utc>>,
+}
+
+impl QueueState {
+    /// Take up to `max` jobs from the given queues in order
+    fn take(&mut self, priorities: &[PriorityLevel], max: usize) -> Vec<(Uuid, PriorityLevel)> {
+        let mut jobs = Vec::new();
+        for priority in priorities {
+            let Some(queue) = self.pending.get_mut(priority) else {
+                continue;
+            };
+            while jobs.len() < max {
+                let Some(job_id) = queue.pop_front() else {
+                    break;
+                };
+                jobs.push((job_id, priority.clone()));
+            }
+        }
+        jobs
+    }
+}
+
+/// In-memory implementation of the JobQueue trait, for tests and local development
+/// without Redis
+///
+/// Follows the semantics of the Redis queue: jobs are popped in priority order and
+/// first in, first out within a priority, blocking pops wait up to their timeout (or
+/// indefinitely for a timeout of 0) for a job to be pushed, and due scheduled jobs are
+/// removed as they are fetched. Jobs live in the process, so they are lost on restart
+/// and invisible to runners in other processes. Only job IDs can be pushed, so nothing
+/// is ever quarantined.
+pub struct InMemoryJobQueue {
+    state: Mutex<QueueState>,
+    /// Wakes blocking pops when a job is pushed
+    pushed: Notify,
+    config: JobQueueConfig,
+}
+
+impl Default for InMemoryJobQueue {
+    fn default() -> Self {
+        Self::new(JobQueueConfig::new(String::new()))
+    }
+}
+
+impl InMemoryJobQueue {
+    /// Create an empty in-memory job queue; only the timeout of the configuration is used
+    pub fn new(config: JobQueueConfig) -> Self {
+        Self {
+            state: Mutex::new(QueueState::default()),
+            pushed: Notify::new(),
+            config,
+        }
+    }
+
+    fn state(&self) -> MutexGuard<'_, QueueState> {
+        self.state.lock().unwrap_or_else(|e| e.into_inner())
+    }
+
+    /// Pop the next job from the first non-empty of the given queues, waiting for a
+    /// push until the timeout
+    async fn pop_from(&self, priorities: &[PriorityLevel], timeout_seconds: u64) -> Result<Option<(Uuid, PriorityLevel)>, QueueError> {
+        let deadline = (timeout_seconds > 0).then(|| Instant::now() + Duration::from_secs(timeout_seconds));
+        loop {
+            // Register for the next push before looking, so one landing in between is not missed
+            let notified = self.pushed.notified();
+            tokio::pin!(notified);
+            notified.as_mut().enable();
+
+            if let Some(job) = self.state().take(priorities, 1).pop() {
+                return Ok(Some(job));
+            }
+
+            match deadline {
+                Some(deadline) => {
+                    if tokio::time::timeout_at(deadline, notified).await.is_err() {
+                        return Ok(None);
+                    }
+                }
+                None => notified.await,
+            }
+        }
+    }
+}
+
+#[async_trait]
+impl JobQueue for InMemoryJobQueue {
+    async fn push_job(&self, job_id: Uuid, priority: PriorityLevel) -> Result<(), QueueError> {
+        self.state().pending.entry(priority).or_default().push_back(job_id);
+        self.pushed.notify_waiters();
+        Ok(())
+    }
+
+    async fn pop_job(&self) -> Result<Option<Uuid>, QueueError> {
+        self.pop_job_with_timeout(self.config.timeout_seconds).await
+    }
+
+    async fn pop_job_with_timeout(&self, timeout_seconds: u64) -> Result<Option<Uuid>, QueueError> {
+        Ok(self.pop_from(&PRIORITIES, timeout_seconds).await?.map(|(job_id, _)| job_id))
+    }
+
+    async fn pop_job_with_priority(&self) -> Result<Option<(Uuid, PriorityLevel)>, QueueError> {
+        self.pop_from(&PRIORITIES, self.config.timeout_seconds).await
+    }
+
+    async fn pop_job_from(&self, priorities: &[PriorityLevel], timeout_seconds: u64) -> Result<Option<(Uuid, PriorityLevel)>, QueueError> {
+        self.pop_from(priorities, timeout_seconds).await
+    }
+
+    async fn try_pop_jobs_from(&self, priorities: &[PriorityLevel], max: usize) -> Result<Vec<(Uuid, PriorityLevel)>, QueueError> {
+        Ok(self.state().take(priorities, max))
+    }
+
+    async fn queue_length(&self) -> Result<usize, QueueError> {
+        Ok(self.state().pending.values().map(VecDeque::len).sum())
+    }
+
+    async fn queue_length_by_priority(&self, priority: PriorityLevel) -> Result<usize, QueueError> {
+        Ok(self.state().pending.get(&priority).map_or(0, VecDeque::len))
+    }
+
+    async fn peek_next_job(&self) -> Result<Option<Uuid>, QueueError> {
+        let state = self.state();
+        Ok(PRIORITIES.iter()
+            .find_map(|priority| state.pending.get(priority).and_then(|queue| queue.front().copied())))
+    }
+
+    async fn peek_job_by_priority(&self, priority: PriorityLevel) -> Result<Option<Uuid>, QueueError> {
+        Ok(self.state().pending.get(&priority).and_then(|queue| queue.front().copied()))
+    }
+
+    async fn schedule_job(&self, job_id: Uuid, execute_at: DateTime<Utc>) -> Result<(), QueueError> {
+        self.state().scheduled.insert(job_id, execute_at);
+        Ok(())
+    }
+
+    async fn claim_due_scheduled_jobs(&self, max: usize) -> Result<Vec<Uuid>, QueueError> {
+        let now = Utc::now();
+        let mut state = self.state();
+
+        // Earliest first, as the Redis queue claims them
+        let mut due: Vec<(DateTime<Utc>, Uuid)> = state.scheduled.iter()
+            .filter(|(_, execute_at)| **execute_at <= now)
+            .map(|(job_id, execute_at)| (*execute_at, *job_id))
+            .collect();
+        due.sort();
+        due.truncate(max);
+
+        for (_, job_id) in &due {
+            state.scheduled.remove(job_id);
+        }
+        Ok(due.into_iter().map(|(_, job_id)| job_id).collect())
+    }
+
+    async fn quarantined_entries(&self, _limit: usize) -> Result<Vec<QuarantinedEntry>, QueueError> {
+        Ok(Vec::new())
+    }
+
+    async fn quarantine_length(&self) -> Result<usize, QueueError> {
+        Ok(0)
+    }
+
+    async fn quarantined_total(&self) -> Result<u64, QueueError> {
+        Ok(0)
+    }
+
+    async fn clear_quarantine(&self) -> Result<usize, QueueError> {
+        Ok(0)
+    }
+
+    async fn health_check(&self) -> Result<(), QueueError> {
+        Ok(())
+    }
+
+    fn pool_status(&self) -> PoolStatus {
+        PoolStatus::default()
+    }
+}
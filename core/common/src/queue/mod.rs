@@ -1,7 +1,13 @@
 pub mod redis;
+pub mod memory;
 pub mod error;
 pub mod job_queue;
+pub mod dequeue;
+pub mod leader;
 
 pub use error::QueueError;
-pub use job_queue::{JobQueue, JobQueueConfig};
+pub use job_queue::{connect, JobQueue, JobQueueConfig, QuarantinedEntry, QueueBackend};
 pub use redis::RedisJobQueue;
+pub use memory::InMemoryJobQueue;
+pub use dequeue::{DeadlineAware, DequeueConfig, DequeueContext, DequeuePolicy, DequeueStrategy, StrictPriority, WeightedFairShare};
+pub use leader::{ClusterStatus, LeaderElection, LeaderElectionConfig, TaskLeader};
@@ -1,52 +1,287 @@
+use std::num::NonZeroUsize;
+
 use async_trait::async_trait;
 use bb8_redis::{
-    bb8::Pool,
-    redis::{AsyncCommands, RedisResult},
+    bb8::{ManageConnection, Pool, PooledConnection, RunError},
+    redis::{
+        self,
+        aio::ConnectionLike,
+        cluster::{ClusterClient, ClusterClientBuilder},
+        cluster_async::ClusterConnection,
+        AsyncCommands, Cmd, ErrorKind, Pipeline, RedisError, RedisFuture, RedisResult, Script, Value,
+    },
     RedisConnectionManager,
 };
 
+use chrono::Utc;
+use tracing::warn;
 use uuid::Uuid;
 
+use crate::database::{self, PoolStatus};
 use crate::models::job::PriorityLevel;
-use crate::queue::{JobQueue, JobQueueConfig, QueueError};
+use crate::queue::{JobQueue, JobQueueConfig, QuarantinedEntry, QueueError};
+
+/// Most entries held in quarantine; older ones are dropped, though still counted
+const MAX_QUARANTINED_ENTRIES: isize = 1_000;
+
+/// Remove and return up to ARGV[2] entries of the scheduled set due by ARGV[1], earliest first
+const CLAIM_DUE_SCRIPT: &str = r#"
+local due = redis.call('ZRANGEBYSCORE', KEYS[1], '-inf', ARGV[1], 'LIMIT', 0, ARGV[2])
+if #due > 0 then
+    redis.call('ZREM', KEYS[1], unpack(due))
+end
+return due
+"#;
+
+/// Manages connections to a Redis Cluster, each routing commands to the node holding
+/// their keys and following the cluster through failovers and resharding
+#[derive(Clone)]
+struct ClusterConnectionManager {
+    client: ClusterClient,
+}
+
+impl ManageConnection for ClusterConnectionManager {
+    type Connection = ClusterConnection;
+    type Error = RedisError;
+
+    async fn connect(&self) -> Result<Self::Connection, Self::Error> {
+        self.client.get_async_connection().await
+    }
+
+    async fn is_valid(&self, conn: &mut Self::Connection) -> Result<(), Self::Error> {
+        let pong: String = redis::cmd("PING").query_async(conn).await?;
+        match pong.as_str() {
+            "PONG" => Ok(()),
+            _ => Err((ErrorKind::ResponseError, "ping request").into()),
+        }
+    }
+
+    fn has_broken(&self, _: &mut Self::Connection) -> bool {
+        false
+    }
+}
+
+/// Connections of a queue, to a single Redis server or to a cluster
+enum QueuePool {
+    Single(Pool<RedisConnectionManager>),
+    Cluster(Pool<ClusterConnectionManager>),
+}
+
+/// A connection taken from the queue's pool; blocking pops hold it on their own, so
+/// they do not hold up other commands
+enum QueueConnection<'a> {
+    Single(PooledConnection<'a, RedisConnectionManager>),
+    Cluster(PooledConnection<'a, ClusterConnectionManager>),
+}
+
+impl ConnectionLike for QueueConnection<'_> {
+    fn req_packed_command<'a>(&'a mut self, cmd: &'a Cmd) -> RedisFuture<'a, Value> {
+        match self {
+            Self::Single(conn) => conn.req_packed_command(cmd),
+            Self::Cluster(conn) => conn.req_packed_command(cmd),
+        }
+    }
+
+    fn req_packed_commands<'a>(&'a mut self, cmd: &'a Pipeline, offset: usize, count: usize) -> RedisFuture<'a, Vec<Value>> {
+        match self {
+            Self::Single(conn) => conn.req_packed_commands(cmd, offset, count),
+            Self::Cluster(conn) => conn.req_packed_commands(cmd, offset, count),
+        }
+    }
+
+    fn get_db(&self) -> i64 {
+        match self {
+            Self::Single(conn) => conn.get_db(),
+            Self::Cluster(conn) => conn.get_db(),
+        }
+    }
+}
+
+/// Error for a connection the pool could not hand out; checkouts that waited the whole
+/// pool timeout flag the current task as having hit an exhausted pool
+fn pool_error<E: std::error::Error + 'static>(server: &str, e: RunError<E>) -> QueueError {
+    if matches!(e, RunError::TimedOut) {
+        database::mark_pool_exhausted();
+    }
+    QueueError::Connection(format!("Failed to get {} connection: {}", server, e))
+}
 
 /// Redis implementation of the JobQueue trait
+///
+/// Connects to a single Redis server, or to a Redis Cluster when cluster nodes are
+/// configured, over TLS for `rediss://` URLs or when TLS is switched on. Connections
+/// are checked before use, and getting one is retried with backoff while Redis is
+/// unreachable.
 pub struct RedisJobQueue {
-    pool: Pool<RedisConnectionManager>,
+    pool: QueuePool,
     config: JobQueueConfig,
+    /// Prefix of the queue's keys as stored, hash tagged on a cluster
+    key_prefix: String,
 }
 
 impl RedisJobQueue {
     /// Create a new Redis job queue
     pub async fn new(config: JobQueueConfig) -> Result<Self, QueueError> {
-        let manager = RedisConnectionManager::new(config.redis_url.clone())
-            .map_err(|e| QueueError::Connection(format!("Failed to create Redis manager: {}", e)))?;
+        let pool = if config.is_cluster() {
+            let nodes = config.cluster_nodes.iter()
+                .map(|url| config.connection_info(url))
+                .collect::<Result<Vec<_>, _>>()?;
+            let client = ClusterClientBuilder::new(nodes)
+                .connection_timeout(config.connect_timeout)
+                .retries(config.reconnect_attempts)
+                .min_retry_wait(config.reconnect_backoff.as_millis() as u64)
+                .max_retry_wait(config.max_reconnect_backoff.as_millis() as u64)
+                .build()
+                .map_err(|e| QueueError::Connection(format!("Failed to create Redis Cluster client: {}", e)))?;
+
+            let pool = Pool::builder()
+                .max_size(config.pool_size)
+                .connection_timeout(config.pool_timeout)
+                .build(ClusterConnectionManager { client })
+                .await
+                .map_err(|e| QueueError::Connection(format!("Failed to create Redis Cluster pool: {}", e)))?;
+            QueuePool::Cluster(pool)
+        } else {
+            let manager = RedisConnectionManager::new(config.connection_info(&config.redis_url)?)
+                .map_err(|e| QueueError::Connection(format!("Failed to create Redis manager: {}", e)))?;
+
+            let pool = Pool::builder()
+                .max_size(config.pool_size)
+                .connection_timeout(config.pool_timeout)
+                .build(manager)
+                .await
+                .map_err(|e| QueueError::Connection(format!("Failed to create Redis pool: {}", e)))?;
+            QueuePool::Single(pool)
+        };
+
+        let key_prefix = config.stored_key_prefix();
+        Ok(Self { pool, config, key_prefix })
+    }
 
-        let pool = Pool::builder()
-            .max_size(config.pool_size)
-            .build(manager)
-            .await
-            .map_err(|e| QueueError::Connection(format!("Failed to create Redis pool: {}", e)))?;
+    /// Get a connection from the pool, checked before it is handed out
+    ///
+    /// Broken connections fail their check and are replaced by new ones, so the queue
+    /// reconnects on its own once Redis is back.
+    async fn try_connection(&self) -> Result<QueueConnection<'_>, QueueError> {
+        match &self.pool {
+            QueuePool::Single(pool) => pool.get().await.map(QueueConnection::Single)
+                .map_err(|e| pool_error("Redis", e)),
+            QueuePool::Cluster(pool) => pool.get().await.map(QueueConnection::Cluster)
+                .map_err(|e| pool_error("Redis Cluster", e)),
+        }
+    }
 
-        Ok(Self { pool, config })
+    /// Get a connection from the pool, retrying with backoff while Redis is unreachable
+    async fn connection(&self) -> Result<QueueConnection<'_>, QueueError> {
+        let mut retry = 0;
+        loop {
+            match self.try_connection().await {
+                Ok(conn) => return Ok(conn),
+                Err(e) if retry + 1 < self.config.reconnect_attempts => {
+                    retry += 1;
+                    let delay = self.config.reconnect_delay(retry);
+                    warn!("{}, retrying in {:?}", e, delay);
+                    tokio::time::sleep(delay).await;
+                }
+                Err(e) => return Err(e),
+            }
+        }
     }
 
     /// Get the Redis key for a priority queue
     fn priority_queue_key(&self, priority: PriorityLevel) -> String {
-        format!("{}:p{}:pending", self.config.key_prefix, priority.as_i32())
+        format!("{}:p{}:pending", self.key_prefix, priority.as_i32())
     }
 
     /// Get the Redis key for the scheduled queue
     fn scheduled_queue_key(&self) -> String {
-        format!("{}:scheduled", self.config.key_prefix)
+        format!("{}:scheduled", self.key_prefix)
+    }
+
+    /// Get the Redis key of the list of quarantined entries
+    fn quarantine_key(&self) -> String {
+        format!("{}:quarantine", self.key_prefix)
+    }
+
+    /// Get the Redis key counting every entry ever quarantined
+    fn quarantined_total_key(&self) -> String {
+        format!("{}:quarantine:total", self.key_prefix)
+    }
+
+    /// Set an entry that is not a job ID aside, so the queue it was taken from keeps
+    /// moving instead of failing on it again and again
+    async fn quarantine(&self, conn: &mut QueueConnection<'_>, source: &str, value: &str) -> Result<(), QueueError> {
+        warn!("Quarantining entry {:?} of {}: not a job ID", value, source);
+        let entry = QuarantinedEntry {
+            value: value.to_string(),
+            source: source.to_string(),
+            reason: format!("Invalid job ID format: {}", value),
+            quarantined_at: Utc::now(),
+        };
+
+        let quarantine_key = self.quarantine_key();
+        let _: () = redis::pipe()
+            .atomic()
+            .lpush(&quarantine_key, serde_json::to_string(&entry)?).ignore()
+            .ltrim(&quarantine_key, 0, MAX_QUARANTINED_ENTRIES - 1).ignore()
+            .incr(self.quarantined_total_key(), 1).ignore()
+            .query_async(conn)
+            .await
+            .map_err(QueueError::Redis)?;
+
+        Ok(())
+    }
+
+    /// Pop the next job in priority order, returning the priority of the queue it came from
+    async fn pop_prioritized(&self, timeout_seconds: u64) -> Result<Option<(Uuid, PriorityLevel)>, QueueError> {
+        // All priority queues, highest priority first
+        let priorities = [
+            PriorityLevel::Critical,
+            PriorityLevel::High,
+            PriorityLevel::Medium,
+            PriorityLevel::Low,
+        ];
+        self.pop_from(&priorities, timeout_seconds).await
+    }
+
+    /// Pop the next job from the first non-empty of the given queues
+    async fn pop_from(&self, priorities: &[PriorityLevel], timeout_seconds: u64) -> Result<Option<(Uuid, PriorityLevel)>, QueueError> {
+        let mut conn = self.connection().await?;
+
+        let queue_keys: Vec<String> = priorities.iter()
+            .map(|priority| self.priority_queue_key(priority.clone()))
+            .collect();
+
+        loop {
+            // Try to pop a job from any queue in the given order with timeout
+            let result: RedisResult<Option<(String, String)>> = conn
+                .brpop(&queue_keys, timeout_seconds as f64)
+                .await;
+
+            match result {
+                Ok(Some((queue_key, job_id_str))) => {
+                    // Parse the job ID, setting anything else aside and waiting for the next entry
+                    let Ok(job_id) = Uuid::parse_str(&job_id_str) else {
+                        self.quarantine(&mut conn, &queue_key, &job_id_str).await?;
+                        continue;
+                    };
+                    let priority = queue_keys.iter()
+                        .position(|key| *key == queue_key)
+                        .map(|index| priorities[index].clone())
+                        .unwrap_or(PriorityLevel::Medium);
+                    return Ok(Some((job_id, priority)));
+                }
+                Ok(None) => return Ok(None), // Timeout, no job available
+                Err(e) => return Err(QueueError::Redis(e)),
+            }
+        }
     }
 }
 
 #[async_trait]
 impl JobQueue for RedisJobQueue {
     async fn push_job(&self, job_id: Uuid, priority: PriorityLevel) -> Result<(), QueueError> {
-        let mut conn = self.pool.get().await
-            .map_err(|e| QueueError::Connection(format!("Failed to get Redis connection: {}", e)))?;
+        let mut conn = self.connection().await?;
 
         let queue_key = self.priority_queue_key(priority);
         let job_id_str = job_id.to_string();
@@ -63,33 +298,37 @@ impl JobQueue for RedisJobQueue {
     }
 
     async fn pop_job_with_timeout(&self, timeout_seconds: u64) -> Result<Option<Uuid>, QueueError> {
-        let mut conn = self.pool.get().await
-            .map_err(|e| QueueError::Connection(format!("Failed to get Redis connection: {}", e)))?;
-
-        // Create keys for all priority queues, highest priority first
-        let queue_keys: Vec<String> = vec![
-            self.priority_queue_key(PriorityLevel::Critical),
-            self.priority_queue_key(PriorityLevel::High),
-            self.priority_queue_key(PriorityLevel::Medium),
-            self.priority_queue_key(PriorityLevel::Low),
-        ];
+        Ok(self.pop_prioritized(timeout_seconds).await?.map(|(job_id, _)| job_id))
+    }
 
-        // Try to pop a job from any queue in priority order with timeout
-        let result: RedisResult<Option<(String, String)>> = conn
-            .brpop(&queue_keys, timeout_seconds as f64)
-            .await;
+    async fn pop_job_with_priority(&self) -> Result<Option<(Uuid, PriorityLevel)>, QueueError> {
+        self.pop_prioritized(self.config.timeout_seconds).await
+    }
+
+    async fn pop_job_from(&self, priorities: &[PriorityLevel], timeout_seconds: u64) -> Result<Option<(Uuid, PriorityLevel)>, QueueError> {
+        self.pop_from(priorities, timeout_seconds).await
+    }
 
-        match result {
-            Ok(Some((_, job_id_str))) => {
-                // Parse the job ID
+    async fn try_pop_jobs_from(&self, priorities: &[PriorityLevel], max: usize) -> Result<Vec<(Uuid, PriorityLevel)>, QueueError> {
+        let mut conn = self.connection().await?;
+
+        let mut jobs = Vec::new();
+        for priority in priorities {
+            let Some(count) = NonZeroUsize::new(max - jobs.len()) else {
+                break;
+            };
+            let queue_key = self.priority_queue_key(priority.clone());
+            let job_ids: Vec<String> = conn.rpop(&queue_key, Some(count)).await
+                .map_err(QueueError::Redis)?;
+            for job_id_str in job_ids {
                 match Uuid::parse_str(&job_id_str) {
-                    Ok(job_id) => Ok(Some(job_id)),
-                    Err(_) => Err(QueueError::JobAcquisition(format!("Invalid job ID format: {}", job_id_str))),
+                    Ok(job_id) => jobs.push((job_id, priority.clone())),
+                    Err(_) => self.quarantine(&mut conn, &queue_key, &job_id_str).await?,
                 }
             }
-            Ok(None) => Ok(None), // Timeout, no job available
-            Err(e) => Err(QueueError::Redis(e)),
         }
+
+        Ok(jobs)
     }
 
     async fn queue_length(&self) -> Result<usize, QueueError> {
@@ -109,8 +348,7 @@ impl JobQueue for RedisJobQueue {
     }
 
     async fn queue_length_by_priority(&self, priority: PriorityLevel) -> Result<usize, QueueError> {
-        let mut conn = self.pool.get().await
-            .map_err(|e| QueueError::Connection(format!("Failed to get Redis connection: {}", e)))?;
+        let mut conn = self.connection().await?;
 
         let queue_key = self.priority_queue_key(priority);
         
@@ -121,8 +359,7 @@ impl JobQueue for RedisJobQueue {
     }
 
     async fn peek_next_job(&self) -> Result<Option<Uuid>, QueueError> {
-        let mut conn = self.pool.get().await
-            .map_err(|e| QueueError::Connection(format!("Failed to get Redis connection: {}", e)))?;
+        let mut conn = self.connection().await?;
 
         // Try to get the next job from each priority queue (in order)
         for priority in [
@@ -147,9 +384,21 @@ impl JobQueue for RedisJobQueue {
         Ok(None) // No jobs in any queue
     }
 
+    async fn peek_job_by_priority(&self, priority: PriorityLevel) -> Result<Option<Uuid>, QueueError> {
+        let mut conn = self.connection().await?;
+
+        // Jobs are pushed to the head and popped from the tail
+        let result: Option<String> = conn.lindex(self.priority_queue_key(priority), -1).await
+            .map_err(QueueError::Redis)?;
+
+        result
+            .map(|job_id_str| Uuid::parse_str(&job_id_str)
+                .map_err(|_| QueueError::JobAcquisition(format!("Invalid job ID format: {}", job_id_str))))
+            .transpose()
+    }
+
     async fn schedule_job(&self, job_id: Uuid, execute_at: chrono::DateTime<chrono::Utc>) -> Result<(), QueueError> {
-        let mut conn = self.pool.get().await
-            .map_err(|e| QueueError::Connection(format!("Failed to get Redis connection: {}", e)))?;
+        let mut conn = self.connection().await?;
 
         let scheduled_key = self.scheduled_queue_key();
         let job_id_str = job_id.to_string();
@@ -162,33 +411,109 @@ impl JobQueue for RedisJobQueue {
         Ok(())
     }
 
-    async fn get_due_scheduled_jobs(&self) -> Result<Vec<Uuid>, QueueError> {
-        let mut conn = self.pool.get().await
-            .map_err(|e| QueueError::Connection(format!("Failed to get Redis connection: {}", e)))?;
+    async fn claim_due_scheduled_jobs(&self, max: usize) -> Result<Vec<Uuid>, QueueError> {
+        if max == 0 {
+            return Ok(Vec::new());
+        }
+        let mut conn = self.connection().await?;
 
         let scheduled_key = self.scheduled_queue_key();
-        let now = chrono::Utc::now().timestamp_millis() as f64;
-
-        // Get all jobs with score (execution time) less than or equal to now
-        let job_ids: Vec<String> = conn.zrangebyscore(&scheduled_key, 0.0, now).await
-            .map_err(|e| QueueError::Redis(e))?;
+        let now = chrono::Utc::now().timestamp_millis();
+
+        // Take the earliest due entries off the schedule in one step, so runners polling
+        // at the same time never get the same job
+        let job_ids: Vec<String> = Script::new(CLAIM_DUE_SCRIPT)
+            .key(&scheduled_key)
+            .arg(now)
+            .arg(max)
+            .invoke_async(&mut conn)
+            .await
+            .map_err(QueueError::Redis)?;
 
-        // Parse job IDs and return
+        // Parse job IDs and return, setting anything else aside
         let mut result = Vec::with_capacity(job_ids.len());
-        for job_id_str in job_ids {
-            match Uuid::parse_str(&job_id_str) {
+        for job_id_str in &job_ids {
+            match Uuid::parse_str(job_id_str) {
                 Ok(job_id) => result.push(job_id),
-                Err(_) => return Err(QueueError::JobAcquisition(format!("Invalid job ID format: {}", job_id_str))),
+                Err(_) => self.quarantine(&mut conn, &scheduled_key, job_id_str).await?,
             }
         }
 
-        // Remove the retrieved jobs from the scheduled queue
-        if !result.is_empty() {
-            let job_id_strs: Vec<String> = result.iter().map(|id| id.to_string()).collect();
-            let _: () = conn.zrem(&scheduled_key, &job_id_strs).await
-                .map_err(|e| QueueError::Redis(e))?;
+        Ok(result)
+    }
+
+    async fn quarantined_entries(&self, limit: usize) -> Result<Vec<QuarantinedEntry>, QueueError> {
+        if limit == 0 {
+            return Ok(Vec::new());
         }
+        let mut conn = self.connection().await?;
 
-        Ok(result)
+        // Entries are pushed to the head, so the newest come first
+        let entries: Vec<String> = conn.lrange(self.quarantine_key(), 0, limit as isize - 1).await
+            .map_err(QueueError::Redis)?;
+
+        entries.iter()
+            .map(|entry| serde_json::from_str(entry).map_err(QueueError::from))
+            .collect()
+    }
+
+    async fn quarantine_length(&self) -> Result<usize, QueueError> {
+        let mut conn = self.connection().await?;
+
+        let length: usize = conn.llen(self.quarantine_key()).await
+            .map_err(QueueError::Redis)?;
+
+        Ok(length)
+    }
+
+    async fn quarantined_total(&self) -> Result<u64, QueueError> {
+        let mut conn = self.connection().await?;
+
+        let total: Option<u64> = conn.get(self.quarantined_total_key()).await
+            .map_err(QueueError::Redis)?;
+
+        Ok(total.unwrap_or(0))
+    }
+
+    async fn clear_quarantine(&self) -> Result<usize, QueueError> {
+        let mut conn = self.connection().await?;
+
+        // Count and drop the entries in one transaction, so none slips in between
+        let (length,): (usize,) = redis::pipe()
+            .atomic()
+            .llen(self.quarantine_key())
+            .del(self.quarantine_key()).ignore()
+            .query_async(&mut conn)
+            .await
+            .map_err(QueueError::Redis)?;
+
+        Ok(length)
+    }
+
+    async fn health_check(&self) -> Result<(), QueueError> {
+        // A single attempt, so health probes are not held up by the backoff
+        let mut conn = self.try_connection().await?;
+
+        let _: String = redis::cmd("PING").query_async(&mut conn).await
+            .map_err(QueueError::Redis)?;
+
+        Ok(())
+    }
+
+    fn pool_status(&self) -> PoolStatus {
+        let state = match &self.pool {
+            QueuePool::Single(pool) => pool.state(),
+            QueuePool::Cluster(pool) => pool.state(),
+        };
+        let statistics = state.statistics;
+        PoolStatus {
+            max_size: self.config.pool_size,
+            connections: state.connections,
+            idle_connections: state.idle_connections,
+            checkouts: statistics.get_direct + statistics.get_waited,
+            timeouts: statistics.get_timed_out,
+            wait_seconds_total: statistics.get_wait_time.as_secs_f64(),
+            wait_seconds_max: None,
+        }
     }
 }
@@ -0,0 +1,15 @@
+use async_trait::async_trait;
+use uuid::Uuid;
+use anyhow::Result;
+
+use crate::models::audit::{AuditEntry, NewAuditEntry};
+
+/// Repository trait for the audit log
+#[async_trait]
+pub trait AuditLogRepository: Send + Sync {
+    /// Append an entry to the log
+    async fn record(&self, entry: NewAuditEntry) -> Result<AuditEntry>;
+
+    /// List the entries of an entity, oldest first
+    async fn find_by_entity(&self, entity_type: String, entity_id: Uuid) -> Result<Vec<AuditEntry>>;
+}
@@ -0,0 +1,29 @@
+use async_trait::async_trait;
+use anyhow::Result;
+use chrono::{DateTime, Utc};
+use uuid::Uuid;
+
+use crate::models::billing_export::{BillingExport, BillingReconciliation, ExportAttempt};
+
+/// Repository trait for the outbox of job charges exported to an external billing system
+#[async_trait]
+pub trait BillingExportRepository: Send + Sync {
+    /// Add an export for every charge finalized since `since` that has none yet, oldest
+    /// first and at most `limit` of them; returns how many were added
+    ///
+    /// A job gets at most one export, however often its charge is captured.
+    async fn capture(&self, since: DateTime<Utc>, limit: i64) -> Result<usize>;
+
+    /// Find an export by ID
+    async fn find_by_id(&self, id: Uuid) -> Result<BillingExport>;
+
+    /// Pending exports whose next attempt is due, oldest first
+    async fn find_due(&self, now: DateTime<Utc>, limit: i64) -> Result<Vec<BillingExport>>;
+
+    /// Store the outcome of an attempt
+    async fn record_attempt(&self, id: Uuid, attempt: ExportAttempt) -> Result<BillingExport>;
+
+    /// Compare the charges finalized between `from` and `to` with their exports,
+    /// listing at most `limit` of those not exported
+    async fn reconcile(&self, from: DateTime<Utc>, to: DateTime<Utc>, limit: i64) -> Result<BillingReconciliation>;
+}
@@ -0,0 +1,34 @@
+use async_trait::async_trait;
+use chrono::{DateTime, Utc};
+use uuid::Uuid;
+use anyhow::Result;
+
+use crate::models::billing_period::{BillingPeriod, Invoice};
+use crate::models::wallet::WalletTransaction;
+
+/// Repository trait for closing billing periods and reading their invoices and ledger
+#[async_trait]
+pub trait BillingPeriodRepository: Send + Sync {
+    /// Close a period that has ended: finalize an invoice for every wallet that existed
+    /// or was charged during the period and record the checksum of its ledger export
+    ///
+    /// Each invoice covers the period's wall-clock bounds in its customer's time zone.
+    /// Fails with a `LedgerError` if the period has not ended in every customer's time
+    /// zone or overlaps a closed one.
+    async fn close(&self, period_start: DateTime<Utc>, period_end: DateTime<Utc>, closed_by: String) -> Result<BillingPeriod>;
+
+    /// Find a closed period by ID
+    async fn find_by_id(&self, id: Uuid) -> Result<BillingPeriod>;
+
+    /// List the closed periods, most recent first
+    async fn list(&self) -> Result<Vec<BillingPeriod>>;
+
+    /// List the invoices of a closed period
+    async fn find_invoices(&self, billing_period_id: Uuid) -> Result<Vec<Invoice>>;
+
+    /// List the invoices of a customer, most recent period first
+    async fn find_invoices_by_customer(&self, customer_id: Uuid) -> Result<Vec<Invoice>>;
+
+    /// Wallet transactions of a closed period in ledger order (oldest first)
+    async fn ledger(&self, billing_period_id: Uuid) -> Result<Vec<WalletTransaction>>;
+}
@@ -3,21 +3,47 @@ use uuid::Uuid;
 use anyhow::Result;
 
 use crate::models::customer::{Customer, NewCustomer};
+use crate::models::pii::PiiPolicy;
 
 #[async_trait]
 pub trait CustomerRepository: Send + Sync {
     /// Create a new customer
     async fn create(&self, new_customer: NewCustomer) -> Result<Customer>;
     
+    /// Create many customers in one transaction, with multi-row inserts; either all of
+    /// them are created or none
+    async fn create_many(&self, new_customers: Vec<NewCustomer>) -> Result<Vec<Customer>>;
+    
     /// Find a customer by ID
     async fn find_by_id(&self, id: Uuid) -> Result<Customer>;
     
     /// Find a customer by API key
     async fn find_by_api_key(&self, api_key: &str) -> Result<Customer>;
     
+    /// Find a customer by sandbox (test mode) API key
+    async fn find_by_test_api_key(&self, test_api_key: &str) -> Result<Customer>;
+    
     /// Find customers by reseller ID
     async fn find_by_reseller_id(&self, reseller_id: Uuid) -> Result<Vec<Customer>>;
     
+    /// Find the sub-accounts of a customer
+    async fn find_by_parent_id(&self, parent_id: Uuid) -> Result<Vec<Customer>>;
+    
+    /// Set or clear the monthly budget of a sub-account
+    async fn set_budget(&self, customer_id: Uuid, budget_cents: Option<i32>) -> Result<Customer>;
+    
+    /// Set or clear the time zone of a customer
+    async fn set_timezone(&self, customer_id: Uuid, timezone: Option<String>) -> Result<Customer>;
+    
+    /// Set or clear the priority level of a customer's jobs submitted without one
+    async fn set_default_priority(&self, customer_id: Uuid, default_priority: Option<i32>) -> Result<Customer>;
+    
+    /// Set or clear what happens to a customer's jobs whose input contains personal data
+    async fn set_pii_policy(&self, customer_id: Uuid, pii_policy: Option<PiiPolicy>) -> Result<Customer>;
+    
+    /// Activate or deactivate a customer
+    async fn set_active(&self, customer_id: Uuid, active: bool) -> Result<Customer>;
+    
     /// Update a customer
     async fn update(&self, customer: &Customer) -> Result<Customer>;
     
@@ -27,6 +53,9 @@ pub trait CustomerRepository: Send + Sync {
     /// Generate and set API key for a customer
     async fn generate_api_key(&self, customer_id: Uuid) -> Result<String>;
     
+    /// Generate and set the sandbox (test mode) API key for a customer
+    async fn generate_test_api_key(&self, customer_id: Uuid) -> Result<String>;
+    
     /// List all customers
     async fn list_all(&self) -> Result<Vec<Customer>>;
 }
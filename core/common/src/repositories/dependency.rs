@@ -0,0 +1,27 @@
+use async_trait::async_trait;
+use anyhow::Result;
+use uuid::Uuid;
+
+use crate::models::dependency::Blocker;
+
+/// Repository trait for the dependents checked before destructive operations
+///
+/// Jobs and reservations are looked up in the schema of the current reseller scope;
+/// callers acting on entities shared by all resellers check every scope.
+#[async_trait]
+pub trait DependencyRepository: Send + Sync {
+    /// Jobs keeping a project from being deleted
+    async fn project_blockers(&self, project_id: Uuid) -> Result<Vec<Blocker>>;
+
+    /// Queued jobs keeping a job type from being disabled
+    async fn job_type_blockers(&self, job_type_id: Uuid) -> Result<Vec<Blocker>>;
+
+    /// Queued jobs and reserved funds keeping a customer from being deactivated
+    async fn customer_blockers(&self, customer_id: Uuid) -> Result<Vec<Blocker>>;
+
+    /// IDs of the pending or scheduled jobs of a job type
+    async fn find_queued_jobs_by_job_type(&self, job_type_id: Uuid) -> Result<Vec<Uuid>>;
+
+    /// IDs of the pending or scheduled jobs of a customer
+    async fn find_queued_jobs_by_customer(&self, customer_id: Uuid) -> Result<Vec<Uuid>>;
+}
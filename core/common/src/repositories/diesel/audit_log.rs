@@ -0,0 +1,50 @@
+use async_trait::async_trait;
+use diesel::prelude::*;
+use crate::database::TenantPool;
+use uuid::Uuid;
+use anyhow::Result;
+
+use crate::models::audit::{AuditEntry, NewAuditEntry};
+use crate::repositories::AuditLogRepository;
+use crate::diesel_schema::audit_log;
+
+/// Diesel implementation of the AuditLogRepository
+pub struct DieselAuditLogRepository {
+    pool: TenantPool,
+}
+
+impl DieselAuditLogRepository {
+    /// Create a new DieselAuditLogRepository with the given connection pool
+    pub fn new(pool: impl Into<TenantPool>) -> Self {
+        Self { pool: pool.into() }
+    }
+}
+
+#[async_trait]
+impl AuditLogRepository for DieselAuditLogRepository {
+    async fn record(&self, entry: NewAuditEntry) -> Result<AuditEntry> {
+        let mut conn = self.pool.get()?;
+
+        let entry: AuditEntry = tokio::task::spawn_blocking(move || {
+            diesel::insert_into(audit_log::table)
+                .values(&entry)
+                .get_result::<AuditEntry>(&mut conn)
+        }).await??;
+
+        Ok(entry)
+    }
+
+    async fn find_by_entity(&self, entity_type: String, entity_id: Uuid) -> Result<Vec<AuditEntry>> {
+        let mut conn = self.pool.get()?;
+
+        let entries = tokio::task::spawn_blocking(move || {
+            audit_log::table
+                .filter(audit_log::entity_type.eq(entity_type))
+                .filter(audit_log::entity_id.eq(entity_id))
+                .order((audit_log::created_at.asc(), audit_log::id.asc()))
+                .load::<AuditEntry>(&mut conn)
+        }).await??;
+
+        Ok(entries)
+    }
+}
@@ -0,0 +1,212 @@
+use async_trait::async_trait;
+use chrono::{DateTime, Utc};
+use diesel::dsl::{count_star, exists, not, sum};
+use diesel::pg::Pg;
+use diesel::prelude::*;
+use crate::database::TenantPool;
+use anyhow::{Result, anyhow};
+use uuid::Uuid;
+
+use crate::models::billing_export::{BillingExport, BillingReconciliation, ExportAttempt, ExportStatus, NewBillingExport, UnexportedRecord};
+use crate::models::job::JobStatus;
+use crate::repositories::BillingExportRepository;
+use crate::diesel_schema::{billing_exports, jobs};
+
+/// Jobs whose charge is final: succeeded, with their cost breakdown recorded, and billed
+/// on their own rather than through a parent or in test mode
+fn finalized_charges<'a>(from: DateTime<Utc>, to: Option<DateTime<Utc>>) -> jobs::BoxedQuery<'a, Pg> {
+    let mut query = jobs::table
+        .filter(jobs::status.eq(JobStatus::Succeeded.as_str()))
+        .filter(jobs::cost_breakdown.is_not_null())
+        .filter(jobs::test_mode.eq(false))
+        .filter(jobs::parent_id.is_null())
+        .filter(jobs::completed_at.ge(from))
+        .into_boxed();
+    if let Some(to) = to {
+        query = query.filter(jobs::completed_at.lt(to));
+    }
+    query
+}
+
+/// Diesel implementation of the BillingExportRepository
+pub struct DieselBillingExportRepository {
+    pool: TenantPool,
+}
+
+impl DieselBillingExportRepository {
+    /// Create a new DieselBillingExportRepository with the given connection pool
+    pub fn new(pool: impl Into<TenantPool>) -> Self {
+        Self { pool: pool.into() }
+    }
+}
+
+#[async_trait]
+impl BillingExportRepository for DieselBillingExportRepository {
+    async fn capture(&self, since: DateTime<Utc>, limit: i64) -> Result<usize> {
+        let mut conn = self.pool.get()?;
+
+        let captured = tokio::task::spawn_blocking(move || {
+            conn.transaction(|conn| {
+                let charges = finalized_charges(since, None)
+                    .filter(not(exists(billing_exports::table.filter(billing_exports::job_id.eq(jobs::id)))))
+                    .order(jobs::completed_at.asc())
+                    .limit(limit)
+                    .select((jobs::id, jobs::customer_id, jobs::cost_cents, jobs::cost_breakdown.assume_not_null(), jobs::completed_at.assume_not_null()))
+                    .load::<(Uuid, Uuid, i32, serde_json::Value, DateTime<Utc>)>(conn)?;
+                let exports: Vec<NewBillingExport> = charges.into_iter()
+                    .map(|(job_id, customer_id, amount_cents, breakdown, completed_at)| {
+                        NewBillingExport::new(job_id, customer_id, amount_cents, breakdown, completed_at)
+                    })
+                    .collect();
+
+                // A concurrent capture of the same job loses the race on the unique job ID
+                diesel::insert_into(billing_exports::table)
+                    .values(&exports)
+                    .on_conflict(billing_exports::job_id)
+                    .do_nothing()
+                    .execute(conn)
+            })
+        }).await??;
+
+        Ok(captured)
+    }
+
+    async fn find_by_id(&self, id: Uuid) -> Result<BillingExport> {
+        let mut conn = self.pool.get()?;
+
+        let export: BillingExport = tokio::task::spawn_blocking(move || {
+            billing_exports::table
+                .find(id)
+                .first(&mut conn)
+                .optional()
+        }).await??
+            .ok_or_else(|| anyhow!("Billing export not found with ID: {}", id))?;
+
+        Ok(export)
+    }
+
+    async fn find_due(&self, now: DateTime<Utc>, limit: i64) -> Result<Vec<BillingExport>> {
+        let mut conn = self.pool.get()?;
+
+        let exports = tokio::task::spawn_blocking(move || {
+            billing_exports::table
+                .filter(billing_exports::status.eq(ExportStatus::Pending.as_str()))
+                .filter(billing_exports::next_attempt_at.is_null().or(billing_exports::next_attempt_at.le(now)))
+                .order(billing_exports::created_at.asc())
+                .limit(limit)
+                .load::<BillingExport>(&mut conn)
+        }).await??;
+
+        Ok(exports)
+    }
+
+    async fn record_attempt(&self, id: Uuid, attempt: ExportAttempt) -> Result<BillingExport> {
+        let mut conn = self.pool.get()?;
+
+        let export = tokio::task::spawn_blocking(move || {
+            diesel::update(billing_exports::table.find(id))
+                .set((
+                    billing_exports::status.eq(attempt.status.as_str()),
+                    billing_exports::attempts.eq(attempt.attempts),
+                    billing_exports::status_code.eq(attempt.status_code),
+                    billing_exports::last_error.eq(attempt.last_error),
+                    billing_exports::next_attempt_at.eq(attempt.next_attempt_at),
+                    billing_exports::exported_at.eq(attempt.exported_at),
+                    billing_exports::updated_at.eq(Utc::now()),
+                ))
+                .get_result::<BillingExport>(&mut conn)
+        }).await??;
+
+        Ok(export)
+    }
+
+    async fn reconcile(&self, from: DateTime<Utc>, to: DateTime<Utc>, limit: i64) -> Result<BillingReconciliation> {
+        let mut conn = self.pool.get()?;
+
+        let reconciliation = tokio::task::spawn_blocking(move || {
+            conn.transaction::<_, diesel::result::Error, _>(|conn| {
+                let (finalized_jobs, finalized_cents) = finalized_charges(from, Some(to))
+                    .select((count_star(), sum(jobs::cost_cents)))
+                    .first::<(i64, Option<i64>)>(conn)?;
+
+                let uncaptured_jobs = || finalized_charges(from, Some(to))
+                    .filter(not(exists(billing_exports::table.filter(billing_exports::job_id.eq(jobs::id)))));
+                let (uncaptured, uncaptured_cents) = uncaptured_jobs()
+                    .select((count_star(), sum(jobs::cost_cents)))
+                    .first::<(i64, Option<i64>)>(conn)?;
+
+                let by_status = billing_exports::table
+                    .filter(billing_exports::completed_at.ge(from))
+                    .filter(billing_exports::completed_at.lt(to))
+                    .group_by(billing_exports::status)
+                    .select((billing_exports::status, count_star(), sum(billing_exports::amount_cents)))
+                    .load::<(String, i64, Option<i64>)>(conn)?;
+
+                let mut reconciliation = BillingReconciliation {
+                    finalized_jobs,
+                    finalized_cents: finalized_cents.unwrap_or(0),
+                    uncaptured,
+                    unexported_cents: uncaptured_cents.unwrap_or(0),
+                    ..BillingReconciliation::default()
+                };
+                for (status, count, cents) in by_status {
+                    let cents = cents.unwrap_or(0);
+                    match ExportStatus::parse(&status) {
+                        Some(ExportStatus::Exported) => {
+                            reconciliation.exported += count;
+                            reconciliation.exported_cents += cents;
+                        }
+                        Some(ExportStatus::Pending) => {
+                            reconciliation.pending += count;
+                            reconciliation.unexported_cents += cents;
+                        }
+                        Some(ExportStatus::Failed) | None => {
+                            reconciliation.failed += count;
+                            reconciliation.unexported_cents += cents;
+                        }
+                    }
+                }
+
+                let uncaptured_records = uncaptured_jobs()
+                    .order(jobs::completed_at.asc())
+                    .limit(limit)
+                    .select((jobs::id, jobs::customer_id, jobs::cost_cents, jobs::completed_at.assume_not_null()))
+                    .load::<(Uuid, Uuid, i32, DateTime<Utc>)>(conn)?
+                    .into_iter()
+                    .map(|(job_id, customer_id, amount_cents, completed_at)| UnexportedRecord {
+                        job_id,
+                        customer_id,
+                        amount_cents,
+                        completed_at,
+                        status: "uncaptured".to_string(),
+                        attempts: 0,
+                        last_error: None,
+                    });
+                let unexported_exports = billing_exports::table
+                    .filter(billing_exports::completed_at.ge(from))
+                    .filter(billing_exports::completed_at.lt(to))
+                    .filter(billing_exports::status.ne(ExportStatus::Exported.as_str()))
+                    .order(billing_exports::completed_at.asc())
+                    .limit(limit)
+                    .load::<BillingExport>(conn)?
+                    .into_iter()
+                    .map(|export| UnexportedRecord {
+                        job_id: export.job_id,
+                        customer_id: export.customer_id,
+                        amount_cents: export.amount_cents,
+                        completed_at: export.completed_at,
+                        status: export.status,
+                        attempts: export.attempts,
+                        last_error: export.last_error,
+                    });
+                reconciliation.unexported = uncaptured_records.chain(unexported_exports).collect();
+                reconciliation.unexported.sort_by_key(|record| record.completed_at);
+                reconciliation.unexported.truncate(limit.max(0) as usize);
+
+                Ok(reconciliation)
+            })
+        }).await??;
+
+        Ok(reconciliation)
+    }
+}
@@ -0,0 +1,254 @@
+use std::collections::{HashMap, HashSet};
+
+use async_trait::async_trait;
+use chrono::{DateTime, Duration, Utc};
+use diesel::dsl::sum;
+use diesel::prelude::*;
+use crate::database::TenantPool;
+use uuid::Uuid;
+use anyhow::{Result, anyhow};
+
+use crate::models::billing_period::{ledger_csv, ledger_sha256, BillingPeriod, Invoice, LedgerError, NewBillingPeriod, NewInvoice};
+use crate::models::job_cost::CostBreakdown;
+use crate::models::wallet::{TransactionType, Wallet, WalletTransaction};
+use crate::repositories::BillingPeriodRepository;
+use crate::diesel_schema::{billing_periods, customers, invoices, jobs, resellers, wallet_transactions, wallets};
+use crate::timezone;
+
+/// Diesel implementation of the BillingPeriodRepository
+pub struct DieselBillingPeriodRepository {
+    pool: TenantPool,
+}
+
+impl DieselBillingPeriodRepository {
+    /// Create a new DieselBillingPeriodRepository with the given connection pool
+    pub fn new(pool: impl Into<TenantPool>) -> Self {
+        Self { pool: pool.into() }
+    }
+}
+
+/// Fail if a transaction dated `at` would fall in a closed billing period
+pub(crate) fn ensure_period_open(conn: &mut PgConnection, at: DateTime<Utc>) -> Result<()> {
+    let closed = billing_periods::table
+        .filter(billing_periods::period_start.le(at))
+        .filter(billing_periods::period_end.gt(at))
+        .select(billing_periods::id)
+        .first::<Uuid>(conn)
+        .optional()?;
+    match closed {
+        Some(_) => Err(LedgerError::PeriodClosed(at).into()),
+        None => Ok(()),
+    }
+}
+
+/// Wallet transactions dated within a period, in ledger order
+fn period_transactions(conn: &mut PgConnection, start: DateTime<Utc>, end: DateTime<Utc>) -> QueryResult<Vec<WalletTransaction>> {
+    wallet_transactions::table
+        .filter(wallet_transactions::created_at.ge(start))
+        .filter(wallet_transactions::created_at.lt(end))
+        .order((wallet_transactions::created_at.asc(), wallet_transactions::id.asc()))
+        .load::<WalletTransaction>(conn)
+}
+
+/// Whether a transaction charges a job, rather than holding funds for it
+fn is_job_charge(tx: &WalletTransaction) -> bool {
+    tx.job_id.is_some() && tx.amount_cents < 0 && tx.transaction_type != TransactionType::Reserved.as_str()
+}
+
+/// Itemized costs of the jobs charged by the given transactions, by job
+fn charged_job_costs(conn: &mut PgConnection, transactions: &[WalletTransaction]) -> QueryResult<HashMap<Uuid, CostBreakdown>> {
+    let job_ids: HashSet<Uuid> = transactions.iter().filter(|tx| is_job_charge(tx)).filter_map(|tx| tx.job_id).collect();
+    let costs = jobs::table
+        .filter(jobs::id.eq_any(job_ids))
+        .filter(jobs::cost_breakdown.is_not_null())
+        .select((jobs::id, jobs::cost_breakdown))
+        .load::<(Uuid, Option<serde_json::Value>)>(conn)?;
+    Ok(costs.into_iter()
+        .filter_map(|(job_id, breakdown)| Some((job_id, serde_json::from_value(breakdown?).ok()?)))
+        .collect())
+}
+
+#[async_trait]
+impl BillingPeriodRepository for DieselBillingPeriodRepository {
+    async fn close(&self, period_start: DateTime<Utc>, period_end: DateTime<Utc>, closed_by: String) -> Result<BillingPeriod> {
+        if period_end > Utc::now() {
+            return Err(LedgerError::PeriodNotOver(period_start).into());
+        }
+        let mut conn = self.pool.get()?;
+
+        tokio::task::spawn_blocking(move || {
+            conn.transaction(|conn| {
+                // Closing is rare; serialize it so two admins cannot close overlapping periods
+                diesel::sql_query("LOCK TABLE billing_periods IN SHARE ROW EXCLUSIVE MODE").execute(conn)?;
+                let overlapping = billing_periods::table
+                    .filter(billing_periods::period_start.lt(period_end))
+                    .filter(billing_periods::period_end.gt(period_start))
+                    .count()
+                    .get_result::<i64>(conn)?;
+                if overlapping > 0 {
+                    return Err(LedgerError::AlreadyClosed(period_start).into());
+                }
+
+                let transactions = period_transactions(conn, period_start, period_end)?;
+                // Invoices are cut at the period's wall-clock bounds in each customer's time
+                // zone, which lie within a day of the period
+                let slack = Duration::days(1);
+                let nearby = period_transactions(conn, period_start - slack, period_end + slack)?;
+                // Balances at the end of an invoice are today's minus everything posted since
+                let posted_later: HashMap<Uuid, i64> = wallet_transactions::table
+                    .filter(wallet_transactions::created_at.ge(period_end + slack))
+                    .group_by(wallet_transactions::wallet_id)
+                    .select((wallet_transactions::wallet_id, sum(wallet_transactions::amount_cents)))
+                    .load::<(Uuid, Option<i64>)>(conn)?
+                    .into_iter()
+                    .map(|(wallet_id, total)| (wallet_id, total.unwrap_or(0)))
+                    .collect();
+
+                let period = NewBillingPeriod {
+                    id: Uuid::new_v4(),
+                    period_start,
+                    period_end,
+                    closed_by,
+                    transaction_count: transactions.len() as i32,
+                    credits_cents: transactions.iter().map(|tx| tx.amount_cents.max(0) as i64).sum(),
+                    debits_cents: transactions.iter().map(|tx| -(tx.amount_cents.min(0) as i64)).sum(),
+                    ledger_sha256: ledger_sha256(&ledger_csv(&transactions)),
+                };
+                let period = diesel::insert_into(billing_periods::table)
+                    .values(&period)
+                    .get_result::<BillingPeriod>(conn)?;
+
+                // Every wallet that existed during its invoice's period, or was charged in it, gets an invoice
+                let charged: Vec<Uuid> = nearby.iter().map(|tx| tx.wallet_id).collect();
+                let wallets = wallets::table
+                    .inner_join(customers::table.on(customers::id.eq(wallets::customer_id)))
+                    .left_join(resellers::table.on(customers::reseller_id.eq(resellers::id.nullable())))
+                    .filter(
+                        wallets::created_at.lt(period_end + slack)
+                            .or(wallets::created_at.is_null())
+                            .or(wallets::id.eq_any(charged))
+                    )
+                    .select((Wallet::as_select(), customers::timezone, resellers::timezone.nullable()))
+                    .load::<(Wallet, Option<String>, Option<String>)>(conn)?;
+                let job_costs = charged_job_costs(conn, &nearby)?;
+                let now = Utc::now();
+                let mut invoices = Vec::with_capacity(wallets.len());
+                for (wallet, customer_timezone, reseller_timezone) in wallets {
+                    let tz = timezone::effective(customer_timezone.as_deref(), reseller_timezone.as_deref());
+                    let (start, end) = (timezone::to_utc(period_start.naive_utc(), tz), timezone::to_utc(period_end.naive_utc(), tz));
+                    // Customers west of UTC see the period end later
+                    if end > now {
+                        return Err(LedgerError::PeriodNotOver(period_start).into());
+                    }
+
+                    let own: Vec<&WalletTransaction> = nearby.iter()
+                        .filter(|tx| tx.wallet_id == wallet.id)
+                        .filter(|tx| tx.created_at.is_some_and(|at| start <= at && at < end))
+                        .collect();
+                    if own.is_empty() && wallet.created_at.is_some_and(|created_at| created_at >= end) {
+                        continue;
+                    }
+                    let posted_since = posted_later.get(&wallet.id).copied().unwrap_or(0)
+                        + nearby.iter()
+                            .filter(|tx| tx.wallet_id == wallet.id && tx.created_at.is_some_and(|at| at >= end))
+                            .map(|tx| tx.amount_cents as i64)
+                            .sum::<i64>();
+                    let own_jobs: HashSet<Uuid> = own.iter()
+                        .filter(|tx| is_job_charge(tx))
+                        .filter_map(|tx| tx.job_id)
+                        .collect();
+                    let job_costs: Vec<CostBreakdown> = own_jobs.iter().filter_map(|job_id| job_costs.get(job_id).copied()).collect();
+                    let credits_cents: i64 = own.iter().map(|tx| tx.amount_cents.max(0) as i64).sum();
+                    let debits_cents: i64 = own.iter().map(|tx| -(tx.amount_cents.min(0) as i64)).sum();
+                    let closing_balance_cents = wallet.balance_cents as i64 - posted_since;
+                    invoices.push(NewInvoice {
+                        id: Uuid::new_v4(),
+                        billing_period_id: period.id,
+                        customer_id: wallet.customer_id,
+                        wallet_id: wallet.id,
+                        opening_balance_cents: closing_balance_cents - credits_cents + debits_cents,
+                        credits_cents,
+                        debits_cents,
+                        closing_balance_cents,
+                        transaction_count: own.len() as i32,
+                        cost_breakdown: (!job_costs.is_empty())
+                            .then(|| serde_json::json!(job_costs.into_iter().sum::<CostBreakdown>())),
+                        period_start: start,
+                        period_end: end,
+                        timezone: tz.name().to_string(),
+                    });
+                }
+                diesel::insert_into(invoices::table)
+                    .values(&invoices)
+                    .execute(conn)?;
+
+                Ok(period)
+            })
+        }).await?
+    }
+
+    async fn find_by_id(&self, id: Uuid) -> Result<BillingPeriod> {
+        let mut conn = self.pool.get()?;
+
+        let period: BillingPeriod = tokio::task::spawn_blocking(move || {
+            billing_periods::table
+                .find(id)
+                .first(&mut conn)
+                .optional()
+        }).await??
+            .ok_or_else(|| anyhow!("Billing period not found with ID: {}", id))?;
+
+        Ok(period)
+    }
+
+    async fn list(&self) -> Result<Vec<BillingPeriod>> {
+        let mut conn = self.pool.get()?;
+
+        let periods = tokio::task::spawn_blocking(move || {
+            billing_periods::table
+                .order(billing_periods::period_start.desc())
+                .load::<BillingPeriod>(&mut conn)
+        }).await??;
+
+        Ok(periods)
+    }
+
+    async fn find_invoices(&self, billing_period_id: Uuid) -> Result<Vec<Invoice>> {
+        let mut conn = self.pool.get()?;
+
+        let invoices = tokio::task::spawn_blocking(move || {
+            invoices::table
+                .filter(invoices::billing_period_id.eq(billing_period_id))
+                .order(invoices::customer_id.asc())
+                .load::<Invoice>(&mut conn)
+        }).await??;
+
+        Ok(invoices)
+    }
+
+    async fn find_invoices_by_customer(&self, customer_id: Uuid) -> Result<Vec<Invoice>> {
+        let mut conn = self.pool.get()?;
+
+        let invoices = tokio::task::spawn_blocking(move || {
+            invoices::table
+                .inner_join(billing_periods::table.on(billing_periods::id.eq(invoices::billing_period_id)))
+                .filter(invoices::customer_id.eq(customer_id))
+                .order(billing_periods::period_start.desc())
+                .select(Invoice::as_select())
+                .load::<Invoice>(&mut conn)
+        }).await??;
+
+        Ok(invoices)
+    }
+
+    async fn ledger(&self, billing_period_id: Uuid) -> Result<Vec<WalletTransaction>> {
+        let period = self.find_by_id(billing_period_id).await?;
+        let mut conn = self.pool.get()?;
+
+        let transactions = tokio::task::spawn_blocking(move || {
+            period_transactions(&mut conn, period.period_start, period.period_end)
+        }).await??;
+
+        Ok(transactions)
+    }
+}
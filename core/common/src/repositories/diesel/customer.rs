@@ -1,6 +1,6 @@
 use async_trait::async_trait;
 use diesel::prelude::*;
-use diesel::r2d2::{ConnectionManager, Pool};
+use crate::database::TenantPool;
 use uuid::Uuid;
 use anyhow::{Result, anyhow};
 use rand::Rng;
@@ -8,16 +8,18 @@ use chrono::Utc;
 
 use crate::diesel_schema::customers;
 use crate::models::customer::{Customer, NewCustomer};
+use crate::models::pii::PiiPolicy;
 use crate::repositories::CustomerRepository;
+use super::BULK_INSERT_ROWS;
 
 /// Diesel-backed implementation of CustomerRepository
 pub struct DieselCustomerRepository {
-    pool: Pool<ConnectionManager<PgConnection>>,
+    pool: TenantPool,
 }
 
 impl DieselCustomerRepository {
-    pub fn new(pool: Pool<ConnectionManager<PgConnection>>) -> Self {
-        Self { pool }
+    pub fn new(pool: impl Into<TenantPool>) -> Self {
+        Self { pool: pool.into() }
     }
     
     /// Generate a random API key
@@ -57,6 +59,24 @@ impl CustomerRepository for DieselCustomerRepository {
         Ok(customer)
     }
 
+    async fn create_many(&self, new_customers: Vec<NewCustomer>) -> Result<Vec<Customer>> {
+        let mut conn = self.pool.get()?;
+        
+        let customers: Vec<Customer> = tokio::task::spawn_blocking(move || {
+            conn.transaction(|conn| {
+                let mut created = Vec::with_capacity(new_customers.len());
+                for chunk in new_customers.chunks(BULK_INSERT_ROWS) {
+                    created.extend(diesel::insert_into(customers::table)
+                        .values(chunk)
+                        .get_results::<Customer>(conn)?);
+                }
+                Ok::<_, diesel::result::Error>(created)
+            }).map_err(|e| anyhow!("Failed to create customers: {}", e))
+        }).await??;
+        
+        Ok(customers)
+    }
+
     async fn find_by_id(&self, id: Uuid) -> Result<Customer> {
         let mut conn = self.pool.get()?;
         
@@ -86,6 +106,21 @@ impl CustomerRepository for DieselCustomerRepository {
         Ok(customer)
     }
     
+    async fn find_by_test_api_key(&self, test_api_key: &str) -> Result<Customer> {
+        let test_api_key = test_api_key.to_string();
+        let mut conn = self.pool.get()?;
+        
+        let customer: Customer = tokio::task::spawn_blocking(move || {
+            customers::table
+                .filter(customers::test_api_key.eq(test_api_key))
+                .first(&mut conn)
+                .optional()
+        }).await??
+            .ok_or_else(|| anyhow!("Customer not found with test API key"))?;
+        
+        Ok(customer)
+    }
+    
     async fn find_by_reseller_id(&self, reseller_id: Uuid) -> Result<Vec<Customer>> {
         let mut conn = self.pool.get()?;
         
@@ -103,13 +138,113 @@ impl CustomerRepository for DieselCustomerRepository {
         Ok(customers)
     }
 
+    async fn find_by_parent_id(&self, parent_id: Uuid) -> Result<Vec<Customer>> {
+        let mut conn = self.pool.get()?;
+        
+        let customers: Vec<Customer> = tokio::task::spawn_blocking(move || {
+            customers::table
+                .filter(customers::parent_id.eq(parent_id))
+                .order(customers::created_at.asc())
+                .load::<Customer>(&mut conn)
+                .map_err(|e| anyhow!("Failed to find sub-accounts of customer: {}", e))
+        }).await??;
+        
+        Ok(customers)
+    }
+    
+    async fn set_budget(&self, customer_id: Uuid, budget_cents: Option<i32>) -> Result<Customer> {
+        let mut conn = self.pool.get()?;
+        
+        let customer = tokio::task::spawn_blocking(move || {
+            diesel::update(customers::table.find(customer_id))
+                .set((
+                    customers::budget_cents.eq(budget_cents),
+                    customers::updated_at.eq(Utc::now()),
+                ))
+                .get_result::<Customer>(&mut conn)
+                .optional()
+        }).await??
+            .ok_or_else(|| anyhow!("Customer not found with ID: {}", customer_id))?;
+        
+        Ok(customer)
+    }
+
+    async fn set_timezone(&self, customer_id: Uuid, timezone: Option<String>) -> Result<Customer> {
+        let mut conn = self.pool.get()?;
+        
+        let customer = tokio::task::spawn_blocking(move || {
+            diesel::update(customers::table.find(customer_id))
+                .set((
+                    customers::timezone.eq(timezone),
+                    customers::updated_at.eq(Utc::now()),
+                ))
+                .get_result::<Customer>(&mut conn)
+                .optional()
+        }).await??
+            .ok_or_else(|| anyhow!("Customer not found with ID: {}", customer_id))?;
+        
+        Ok(customer)
+    }
+
+    async fn set_default_priority(&self, customer_id: Uuid, default_priority: Option<i32>) -> Result<Customer> {
+        let mut conn = self.pool.get()?;
+        
+        let customer = tokio::task::spawn_blocking(move || {
+            diesel::update(customers::table.find(customer_id))
+                .set((
+                    customers::default_priority.eq(default_priority),
+                    customers::updated_at.eq(Utc::now()),
+                ))
+                .get_result::<Customer>(&mut conn)
+                .optional()
+        }).await??
+            .ok_or_else(|| anyhow!("Customer not found with ID: {}", customer_id))?;
+        
+        Ok(customer)
+    }
+
+    async fn set_pii_policy(&self, customer_id: Uuid, pii_policy: Option<PiiPolicy>) -> Result<Customer> {
+        let mut conn = self.pool.get()?;
+        
+        let pii_policy = pii_policy.map(|policy| policy.as_str());
+        let customer = tokio::task::spawn_blocking(move || {
+            diesel::update(customers::table.find(customer_id))
+                .set((
+                    customers::pii_policy.eq(pii_policy),
+                    customers::updated_at.eq(Utc::now()),
+                ))
+                .get_result::<Customer>(&mut conn)
+                .optional()
+        }).await??
+            .ok_or_else(|| anyhow!("Customer not found with ID: {}", customer_id))?;
+        
+        Ok(customer)
+    }
+
+    async fn set_active(&self, customer_id: Uuid, active: bool) -> Result<Customer> {
+        let mut conn = self.pool.get()?;
+        
+        let customer = tokio::task::spawn_blocking(move || {
+            diesel::update(customers::table.find(customer_id))
+                .set((
+                    customers::active.eq(active),
+                    customers::updated_at.eq(Utc::now()),
+                ))
+                .get_result::<Customer>(&mut conn)
+                .optional()
+        }).await??
+            .ok_or_else(|| anyhow!("Customer not found with ID: {}", customer_id))?;
+        
+        Ok(customer)
+    }
+
     async fn update(&self, customer: &Customer) -> Result<Customer> {
         let customer_clone = customer.clone();
         let mut conn = self.pool.get()?;
         
         // Create an updated customer with the current timestamp
         let mut updated_customer = customer_clone.clone();
-        updated_customer.updated_at = Some(Utc::now().naive_utc());
+        updated_customer.updated_at = Some(Utc::now());
         
         let updated_customer = tokio::task::spawn_blocking(move || {
             let result = diesel::update(customers::table.find(customer_clone.id))
@@ -173,6 +308,25 @@ impl CustomerRepository for DieselCustomerRepository {
         Ok(customer.api_key.unwrap_or_default())
     }
 
+    async fn generate_test_api_key(&self, customer_id: Uuid) -> Result<String> {
+        let mut conn = self.pool.get()?;
+        
+        let test_api_key = Customer::generate_test_api_key();
+        
+        let customer = tokio::task::spawn_blocking(move || {
+            let result = diesel::update(customers::table.find(customer_id))
+                .set(customers::test_api_key.eq(&test_api_key))
+                .get_result::<Customer>(&mut conn);
+                
+            match result {
+                Ok(customer) => Ok(customer),
+                Err(e) => Err(anyhow!("Failed to update test API key for customer: {}", e))
+            }
+        }).await??;
+        
+        Ok(customer.test_api_key.unwrap_or_default())
+    }
+
     async fn list_all(&self) -> Result<Vec<Customer>> {
         let mut conn = self.pool.get()?;
         
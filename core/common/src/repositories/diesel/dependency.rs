@@ -0,0 +1,116 @@
+use async_trait::async_trait;
+use diesel::prelude::*;
+use diesel::dsl::sum;
+use crate::database::TenantPool;
+use anyhow::Result;
+use uuid::Uuid;
+
+use crate::models::dependency::{Blocker, BlockerKind};
+use crate::models::job::JobStatus;
+use crate::models::wallet::TransactionType;
+use crate::repositories::DependencyRepository;
+use crate::diesel_schema::{jobs, wallet_transactions};
+
+/// Diesel implementation of the DependencyRepository
+pub struct DieselDependencyRepository {
+    pool: TenantPool,
+}
+
+impl DieselDependencyRepository {
+    /// Create a new DieselDependencyRepository with the given connection pool
+    pub fn new(pool: impl Into<TenantPool>) -> Self {
+        Self { pool: pool.into() }
+    }
+}
+
+#[async_trait]
+impl DependencyRepository for DieselDependencyRepository {
+    async fn project_blockers(&self, project_id: Uuid) -> Result<Vec<Blocker>> {
+        let mut conn = self.pool.get()?;
+
+        let count: i64 = tokio::task::spawn_blocking(move || {
+            jobs::table
+                .filter(jobs::project_id.eq(project_id))
+                .count()
+                .get_result(&mut conn)
+        }).await??;
+
+        Ok(Blocker::from_counts([(BlockerKind::Jobs, count)]))
+    }
+
+    async fn job_type_blockers(&self, job_type_id: Uuid) -> Result<Vec<Blocker>> {
+        let mut conn = self.pool.get()?;
+
+        let queued: i64 = tokio::task::spawn_blocking(move || {
+            jobs::table
+                .filter(jobs::job_type_id.eq(job_type_id))
+                .filter(jobs::status.eq_any([JobStatus::Pending.as_str(), JobStatus::Scheduled.as_str()]))
+                .count()
+                .get_result(&mut conn)
+        }).await??;
+
+        Ok(Blocker::from_counts([(BlockerKind::QueuedJobs, queued)]))
+    }
+
+    async fn customer_blockers(&self, customer_id: Uuid) -> Result<Vec<Blocker>> {
+        let mut conn = self.pool.get()?;
+
+        let (queued, net): (i64, Option<i64>) = tokio::task::spawn_blocking(move || {
+            conn.transaction(|conn| {
+                let queued = jobs::table
+                    .filter(jobs::customer_id.eq(customer_id))
+                    .filter(jobs::status.eq_any([JobStatus::Pending.as_str(), JobStatus::Scheduled.as_str()]))
+                    .count()
+                    .get_result(conn)?;
+                // Reservations are recorded as negative amounts and releases as positive ones
+                let net = wallet_transactions::table
+                    .filter(wallet_transactions::job_id.eq_any(
+                        jobs::table.filter(jobs::customer_id.eq(customer_id)).select(jobs::id.nullable())
+                    ))
+                    .filter(wallet_transactions::transaction_type.eq_any([
+                        TransactionType::Reserved.as_str(),
+                        TransactionType::Released.as_str(),
+                    ]))
+                    .select(sum(wallet_transactions::amount_cents))
+                    .first(conn)?;
+                Ok::<_, diesel::result::Error>((queued, net))
+            })
+        }).await??;
+
+        let reserved = (-net.unwrap_or(0)).max(0);
+        Ok(Blocker::from_counts([
+            (BlockerKind::QueuedJobs, queued),
+            (BlockerKind::ReservedFunds, reserved),
+        ]))
+    }
+
+    async fn find_queued_jobs_by_job_type(&self, job_type_id: Uuid) -> Result<Vec<Uuid>> {
+        let mut conn = self.pool.get()?;
+
+        let ids = tokio::task::spawn_blocking(move || {
+            jobs::table
+                .filter(jobs::job_type_id.eq(job_type_id))
+                .filter(jobs::status.eq_any([JobStatus::Pending.as_str(), JobStatus::Scheduled.as_str()]))
+                .order(jobs::created_at.asc())
+                .select(jobs::id)
+                .load::<Uuid>(&mut conn)
+        }).await??;
+
+        Ok(ids)
+    }
+
+    async fn find_queued_jobs_by_customer(&self, customer_id: Uuid) -> Result<Vec<Uuid>> {
+        let mut conn = self.pool.get()?;
+
+        let ids = tokio::task::spawn_blocking(move || {
+            jobs::table
+                .filter(jobs::customer_id.eq(customer_id))
+                .filter(jobs::status.eq_any([JobStatus::Pending.as_str(), JobStatus::Scheduled.as_str()]))
+                .order(jobs::created_at.asc())
+                .select(jobs::id)
+                .load::<Uuid>(&mut conn)
+        }).await??;
+
+        Ok(ids)
+    }
+}
@@ -0,0 +1,84 @@
+use async_trait::async_trait;
+use diesel::prelude::*;
+use crate::database::TenantPool;
+use anyhow::{anyhow, Result};
+
+use crate::models::feature_flag::{validate_name, FeatureFlag, NewFeatureFlag};
+use crate::repositories::FeatureFlagRepository;
+use crate::diesel_schema::feature_flags;
+
+/// Diesel implementation of the FeatureFlagRepository
+pub struct DieselFeatureFlagRepository {
+    pool: TenantPool,
+}
+
+impl DieselFeatureFlagRepository {
+    /// Create a new DieselFeatureFlagRepository with the given connection pool
+    pub fn new(pool: impl Into<TenantPool>) -> Self {
+        Self { pool: pool.into() }
+    }
+}
+
+#[async_trait]
+impl FeatureFlagRepository for DieselFeatureFlagRepository {
+    async fn upsert(&self, flag: NewFeatureFlag) -> Result<FeatureFlag> {
+        validate_name(&flag.name)?;
+        let mut conn = self.pool.get()?;
+
+        let flag: FeatureFlag = tokio::task::spawn_blocking(move || {
+            diesel::insert_into(feature_flags::table)
+                .values(&flag)
+                .on_conflict(feature_flags::name)
+                .do_update()
+                .set((
+                    feature_flags::enabled.eq(flag.enabled),
+                    feature_flags::reseller_ids.eq(&flag.reseller_ids),
+                    feature_flags::description.eq(&flag.description),
+                    feature_flags::updated_by.eq(&flag.updated_by),
+                    feature_flags::updated_at.eq(diesel::dsl::now),
+                ))
+                .get_result::<FeatureFlag>(&mut conn)
+        }).await??;
+
+        Ok(flag)
+    }
+
+    async fn find_by_name(&self, name: String) -> Result<FeatureFlag> {
+        let mut conn = self.pool.get()?;
+
+        let lookup = name.clone();
+        let flag = tokio::task::spawn_blocking(move || {
+            feature_flags::table
+                .filter(feature_flags::name.eq(lookup))
+                .first::<FeatureFlag>(&mut conn)
+                .optional()
+        }).await??;
+
+        flag.ok_or_else(|| anyhow!("Feature flag not found with name: {}", name))
+    }
+
+    async fn list(&self) -> Result<Vec<FeatureFlag>> {
+        let mut conn = self.pool.get()?;
+
+        let flags = tokio::task::spawn_blocking(move || {
+            feature_flags::table
+                .order(feature_flags::name.asc())
+                .load::<FeatureFlag>(&mut conn)
+        }).await??;
+
+        Ok(flags)
+    }
+
+    async fn delete(&self, name: String) -> Result<FeatureFlag> {
+        let mut conn = self.pool.get()?;
+
+        let lookup = name.clone();
+        let flag = tokio::task::spawn_blocking(move || {
+            diesel::delete(feature_flags::table.filter(feature_flags::name.eq(lookup)))
+                .get_result::<FeatureFlag>(&mut conn)
+                .optional()
+        }).await??;
+
+        flag.ok_or_else(|| anyhow!("Feature flag not found with name: {}", name))
+    }
+}
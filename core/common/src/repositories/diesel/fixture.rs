@@ -0,0 +1,129 @@
+use async_trait::async_trait;
+use chrono::Utc;
+use diesel::prelude::*;
+use crate::database::TenantPool;
+use uuid::Uuid;
+use anyhow::{Result, anyhow};
+
+use crate::models::fixture::{FixtureBundle, FixtureError, FixtureRecords, NewFixtureBundle};
+use crate::models::job::JobStatus;
+use crate::models::runner::NewJobTypeCompatibility;
+use crate::repositories::FixtureRepository;
+use crate::diesel_schema::{
+    customers, fixture_bundles, invoices, job_types, jobs, resellers, runner_job_type_compatibility, runners, wallets,
+};
+
+/// Diesel implementation of the FixtureRepository
+pub struct DieselFixtureRepository {
+    pool: TenantPool,
+}
+
+impl DieselFixtureRepository {
+    /// Create a new DieselFixtureRepository with the given connection pool
+    pub fn new(pool: impl Into<TenantPool>) -> Self {
+        Self { pool: pool.into() }
+    }
+}
+
+#[async_trait]
+impl FixtureRepository for DieselFixtureRepository {
+    async fn create(&self, bundle: NewFixtureBundle, records: FixtureRecords) -> Result<FixtureBundle> {
+        let mut conn = self.pool.get()?;
+
+        let bundle: FixtureBundle = tokio::task::spawn_blocking(move || {
+            conn.transaction(|conn| {
+                diesel::insert_into(resellers::table).values(&records.reseller).execute(conn)?;
+                diesel::insert_into(job_types::table).values(&records.job_type).execute(conn)?;
+                diesel::insert_into(customers::table).values(&records.customers).execute(conn)?;
+                diesel::insert_into(wallets::table).values(&records.wallets).execute(conn)?;
+                diesel::insert_into(runners::table).values(&records.runners).execute(conn)?;
+                let compatibilities: Vec<NewJobTypeCompatibility> = records.runners.iter()
+                    .map(|runner| NewJobTypeCompatibility { runner_id: runner.id, job_type_id: records.job_type.id })
+                    .collect();
+                diesel::insert_into(runner_job_type_compatibility::table).values(&compatibilities).execute(conn)?;
+                diesel::insert_into(jobs::table).values(&records.jobs).execute(conn)?;
+
+                // Started jobs run on the bundle's first runner, finished ones completed now
+                let now = Utc::now();
+                let started: Vec<Uuid> = records.jobs.iter()
+                    .filter(|job| job.status == JobStatus::Running.as_str())
+                    .map(|job| job.id)
+                    .collect();
+                diesel::update(jobs::table.filter(jobs::id.eq_any(started)))
+                    .set((jobs::runner_id.eq(records.runners.first().map(|runner| runner.id)), jobs::claimed_at.eq(now)))
+                    .execute(conn)?;
+                let finished: Vec<Uuid> = records.jobs.iter()
+                    .filter(|job| JobStatus::from_str(&job.status).is_some_and(|status| status.is_terminal()))
+                    .map(|job| job.id)
+                    .collect();
+                diesel::update(jobs::table.filter(jobs::id.eq_any(finished)))
+                    .set(jobs::completed_at.eq(now))
+                    .execute(conn)?;
+
+                diesel::insert_into(fixture_bundles::table)
+                    .values(&bundle)
+                    .get_result::<FixtureBundle>(conn)
+            })
+        }).await??;
+
+        Ok(bundle)
+    }
+
+    async fn find_by_id(&self, id: Uuid) -> Result<FixtureBundle> {
+        let mut conn = self.pool.get()?;
+
+        let bundle: FixtureBundle = tokio::task::spawn_blocking(move || {
+            fixture_bundles::table
+                .find(id)
+                .first(&mut conn)
+                .optional()
+        }).await??
+            .ok_or_else(|| anyhow!("Fixture bundle not found with ID: {}", id))?;
+
+        Ok(bundle)
+    }
+
+    async fn list(&self) -> Result<Vec<FixtureBundle>> {
+        let mut conn = self.pool.get()?;
+
+        let bundles: Vec<FixtureBundle> = tokio::task::spawn_blocking(move || {
+            fixture_bundles::table
+                .order(fixture_bundles::created_at.desc())
+                .load::<FixtureBundle>(&mut conn)
+        }).await??;
+
+        Ok(bundles)
+    }
+
+    async fn delete(&self, id: Uuid) -> Result<()> {
+        let mut conn = self.pool.get()?;
+
+        tokio::task::spawn_blocking(move || -> Result<()> {
+            conn.transaction(|conn| {
+                let bundle = fixture_bundles::table
+                    .find(id)
+                    .for_update()
+                    .first::<FixtureBundle>(conn)
+                    .optional()?
+                    .ok_or_else(|| anyhow!("Fixture bundle not found with ID: {}", id))?;
+
+                let invoiced = invoices::table
+                    .filter(invoices::customer_id.eq_any(&bundle.customer_ids))
+                    .count()
+                    .get_result::<i64>(conn)?;
+                if invoiced > 0 {
+                    return Err(FixtureError::Invoiced(bundle.name).into());
+                }
+
+                // Deleting the customers takes their jobs, wallets and everything else
+                // they own along
+                diesel::delete(customers::table.filter(customers::id.eq_any(&bundle.customer_ids))).execute(conn)?;
+                diesel::delete(runners::table.filter(runners::id.eq_any(&bundle.runner_ids))).execute(conn)?;
+                diesel::delete(job_types::table.find(bundle.job_type_id)).execute(conn)?;
+                diesel::delete(resellers::table.find(bundle.reseller_id)).execute(conn)?;
+                diesel::delete(fixture_bundles::table.find(id)).execute(conn)?;
+                Ok(())
+            })
+        }).await?
+    }
+}
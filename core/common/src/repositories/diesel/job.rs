@@ -1,26 +1,38 @@
+use std::collections::HashMap;
+
 use async_trait::async_trait;
-use chrono::Utc;
+use chrono::{DateTime, Utc};
 use diesel::prelude::*;
 use diesel::dsl::{count_star, sum};
 // No need to import private BoxedSelectStatement type
 use uuid::Uuid;
 
-use crate::database::{get_connection, PgPool, Transaction};
-use crate::diesel_schema::jobs;
+use crate::database::{TenantPool, Transaction};
+use crate::diesel_schema::{job_attempts, jobs};
 use crate::errors::Error;
-use crate::models::job::{Job, JobDb, JobStatus, NewJob};
+use crate::models::job::{resolve_completion, CompletionResolution, HeldCompletion, Job, JobDb, JobHolder, JobStatus, NewJob, PriorityLevel};
+use crate::models::job_attempt::AttemptStatus;
+use crate::models::job_cost::CostBreakdown;
+use crate::models::job_error::JobError;
+use crate::models::reseller_invoice::JobTypeCharges;
 use crate::repositories::JobRepository;
-use crate::repositories::job::{JobFilter, JobSortOrder, Pagination};
+use super::BULK_INSERT_ROWS;
+use crate::repositories::job::{WINDOW_STATS_MAX_JOB_AGE_DAYS, JobCursor, JobFilter, JobSortOrder, CustomerActivity, CustomerSpend, JobTypeExecutionStats, JobTypeQueueStats, Pagination, PriorityWaitStats, RunnerJobStats};
 use crate::Result;
 
+define_sql_function! {
+    /// The first of two timestamps that is not null
+    fn coalesce(a: diesel::sql_types::Nullable<diesel::sql_types::Timestamptz>, b: diesel::sql_types::Nullable<diesel::sql_types::Timestamptz>) -> diesel::sql_types::Nullable<diesel::sql_types::Timestamptz>;
+}
+
 /// Diesel-backed implementation of JobRepository
 pub struct DieselJobRepository {
-    pool: PgPool,
+    pool: TenantPool,
 }
 
 impl DieselJobRepository {
-    pub fn new(pool: PgPool) -> Self {
-        Self { pool }
+    pub fn new(pool: impl Into<TenantPool>) -> Self {
+        Self { pool: pool.into() }
     }
     
     // Helper function to apply filters to a query
@@ -41,6 +53,11 @@ impl DieselJobRepository {
             query = query.filter(jobs::status.eq(status.as_str()));
         }
         
+        // Apply public_id filter if provided
+        if let Some(public_id) = &filter.public_id {
+            query = query.filter(jobs::public_id.eq(public_id.clone()));
+        }
+        
         // Filter by created_after if provided
         if let Some(created_after) = filter.created_after {
             query = query.filter(jobs::created_at.ge(created_after));
@@ -69,11 +86,9 @@ impl DieselJobRepository {
         match sort {
             Some(JobSortOrder::CreatedDesc) => query.order(jobs::created_at.desc()),
             Some(JobSortOrder::CreatedAsc) => query.order(jobs::created_at.asc()),
-            // Note: For PriorityDesc/Asc we'd ideally use the actual priority field,
-            // but since it's not stored in database, we're using other fields as proxy
-            // This is a limitation of our current model separation
-            Some(JobSortOrder::PriorityDesc) => query.order(jobs::id.desc()), // Using ID as a proxy for now
-            Some(JobSortOrder::PriorityAsc) => query.order(jobs::id.asc()),   // Using ID as a proxy for now
+            // Jobs of the same priority are listed newest first, as by default
+            Some(JobSortOrder::PriorityDesc) => query.order((jobs::priority.desc(), jobs::created_at.desc())),
+            Some(JobSortOrder::PriorityAsc) => query.order((jobs::priority.asc(), jobs::created_at.desc())),
             None => query.order(jobs::created_at.desc()), // Default sort
         }
     }
@@ -81,18 +96,107 @@ impl DieselJobRepository {
     // Helper function to apply pagination
     fn apply_pagination<'a>(&self, query: jobs::BoxedQuery<'a, diesel::pg::Pg>, pagination: &Option<Pagination>) -> jobs::BoxedQuery<'a, diesel::pg::Pg> {
         if let Some(pagination) = pagination {
-            let offset = pagination.page * pagination.per_page;
-            query.offset(offset.into()).limit(pagination.per_page.into())
+            // Widened before multiplying: page and per_page may come straight from a request
+            let offset = i64::from(pagination.page).saturating_mul(i64::from(pagination.per_page));
+            query.offset(offset).limit(pagination.per_page.into())
         } else {
             query
         }
     }
 }
 
+/// Statuses from which a job may move to `status`
+fn statuses_leading_to(status: &JobStatus) -> Vec<&'static str> {
+    [
+        JobStatus::Pending,
+        JobStatus::Scheduled,
+        JobStatus::Running,
+        JobStatus::Succeeded,
+        JobStatus::Failed,
+        JobStatus::Cancelled,
+        JobStatus::Expired,
+    ]
+    .iter()
+    .filter(|from| from.can_transition_to(status))
+    .map(|from| from.as_str())
+    .collect()
+}
+
+/// Explain why a guarded status update matched no row: the job is missing, its
+/// current status does not allow the transition or its deadline passed
+fn rejected_transition(conn: &mut PgConnection, id: Uuid, status: &JobStatus) -> Error {
+    match jobs::table.find(id).select((jobs::status, jobs::expires_at)).first::<(String, Option<DateTime<Utc>>)>(conn) {
+        Ok((current, Some(expires_at))) if expires_at <= Utc::now() && statuses_leading_to(status).contains(&current.as_str()) => Error::InvalidInput(format!(
+            "Job expired at {} before it could start",
+            expires_at
+        )),
+        Ok((current, _)) => Error::InvalidInput(format!(
+            "Invalid job status transition from {} to {}",
+            current,
+            status.as_str()
+        )),
+        Err(diesel::result::Error::NotFound) => Error::NotFound(format!("Job not found: {}", id)),
+        Err(e) => Error::Database(e),
+    }
+}
+
+/// Store a runner's result for a job within the caller's transaction, see `complete_held`
+///
+/// The job stays locked until the transaction ends, so it cannot be handed back or
+/// started again in between.
+fn complete_held_in(conn: &mut PgConnection, completion: &HeldCompletion) -> diesel::QueryResult<(CompletionResolution, Job)> {
+    let id = completion.job_id;
+    let error_value = completion.error.as_ref().map(serde_json::to_value).transpose()
+        .map_err(|e| diesel::result::Error::SerializationError(Box::new(e)))?;
+    
+    let job = Job::from(jobs::table
+        .find(id)
+        .select(JobDb::as_select())
+        .for_update()
+        .first(conn)?);
+    
+    let attempt_running = match completion.holder.attempt_id {
+        Some(attempt_id) => Some(job_attempts::table
+            .find(attempt_id)
+            .filter(job_attempts::job_id.eq(id))
+            .select(job_attempts::status)
+            .first::<String>(conn)
+            .optional()?
+            .is_some_and(|status| status == AttemptStatus::Running.as_str())),
+        None => None,
+    };
+    
+    let resolution = resolve_completion(&job.status, attempt_running, job.runner_id, &completion.holder);
+    if resolution == CompletionResolution::Discarded {
+        return Ok((resolution, job));
+    }
+    
+    let status = match (completion.success, job.cancel_requested_at) {
+        (true, _) => JobStatus::Succeeded,
+        (false, Some(_)) => JobStatus::Cancelled,
+        (false, None) => JobStatus::Failed,
+    };
+    let job_db = diesel::update(jobs::table)
+        .filter(jobs::id.eq(id))
+        .set((
+            jobs::status.eq(status.as_str()),
+            jobs::cost_cents.eq(completion.cost_cents),
+            jobs::output_data.eq(completion.output.clone()),
+            jobs::error.eq(error_value),
+            jobs::lease_expires_at.eq(None::<DateTime<Utc>>),
+            jobs::completed_at.eq(diesel::dsl::now),
+            jobs::updated_at.eq(diesel::dsl::now),
+        ))
+        .returning(JobDb::as_select())
+        .get_result(conn)?;
+    
+    Ok((resolution, Job::from(job_db)))
+}
+
 #[async_trait]
 impl JobRepository for DieselJobRepository {
     async fn create(&self, new_job: NewJob) -> Result<Job> {
-        let mut conn = get_connection(&self.pool)?;
+        let mut conn = self.pool.get()?;
         
         // Execute the insert and return the new record
         let job_db = diesel::insert_into(jobs::table)
@@ -105,8 +209,25 @@ impl JobRepository for DieselJobRepository {
         Ok(Job::from(job_db))
     }
     
+    async fn create_many(&self, new_jobs: Vec<NewJob>) -> Result<Vec<Job>> {
+        let mut conn = self.pool.get()?;
+        
+        let jobs_db = conn.transaction(|conn| {
+            let mut created = Vec::with_capacity(new_jobs.len());
+            for chunk in new_jobs.chunks(BULK_INSERT_ROWS) {
+                created.extend(diesel::insert_into(jobs::table)
+                    .values(chunk)
+                    .returning(JobDb::as_select())
+                    .get_results(conn)?);
+            }
+            Ok::<_, diesel::result::Error>(created)
+        }).map_err(Error::Database)?;
+        
+        Ok(jobs_db.into_iter().map(Job::from).collect())
+    }
+    
     async fn find_by_id(&self, id: Uuid) -> Result<Job> {
-        let mut conn = get_connection(&self.pool)?;
+        let mut conn = self.pool.get()?;
         
         let job_db = jobs::table
             .find(id)
@@ -121,53 +242,68 @@ impl JobRepository for DieselJobRepository {
         Ok(Job::from(job_db))
     }
     
-    async fn update_status(&self, id: Uuid, status: JobStatus) -> Result<Job> {
-        let mut conn = get_connection(&self.pool)?;
+    async fn find_by_public_id(&self, public_id: &str) -> Result<Job> {
+        let mut conn = self.pool.get()?;
         
-        // First check if the entity exists
-        let _ = jobs::table
-            .find(id)
+        let job_db = jobs::table
+            .filter(jobs::public_id.eq(public_id))
             .select(JobDb::as_select())
-            .first::<JobDb>(&mut conn)
+            .first(&mut conn)
             .map_err(|e| match e {
-                diesel::result::Error::NotFound => Error::NotFound(format!("Job not found: {}", id)),
+                diesel::result::Error::NotFound => Error::NotFound(format!("Job not found: {}", public_id)),
                 e => Error::Database(e),
             })?;
+            
+        Ok(Job::from(job_db))
+    }
+    
+    async fn update_status(&self, id: Uuid, status: JobStatus) -> Result<Job> {
+        let mut conn = self.pool.get()?;
         
-        // Update the status
+        // Only update jobs whose current status allows the transition, so a job
+        // cancelled or finished concurrently is never moved back
         let job_db = diesel::update(jobs::table)
             .filter(jobs::id.eq(id))
+            .filter(jobs::status.eq_any(statuses_leading_to(&status)))
             .set((
                 jobs::status.eq(status.as_str()),
                 jobs::updated_at.eq(diesel::dsl::now),
             ))
             .returning(JobDb::as_select())
             .get_result(&mut conn)
-            .map_err(|e| Error::Database(e))?;
+            .optional()
+            .map_err(Error::Database)?;
             
-        // Convert to application model
-        Ok(Job::from(job_db))
+        match job_db {
+            Some(job_db) => Ok(Job::from(job_db)),
+            None => Err(rejected_transition(&mut conn, id, &status)),
+        }
     }
     
     async fn set_started(&self, id: Uuid) -> Result<Job> {
-        let mut conn = get_connection(&self.pool)?;
+        let mut conn = self.pool.get()?;
         
-        // Update the status to running and set the updated_at timestamp
+        // Update the status to running and record when it started, unless the job's
+        // deadline passed while it waited
         let job_db = diesel::update(jobs::table)
             .filter(jobs::id.eq(id))
+            .filter(jobs::status.eq_any(statuses_leading_to(&JobStatus::Running)))
+            .filter(jobs::expires_at.is_null().or(jobs::expires_at.gt(Utc::now())))
             .set((
                 jobs::status.eq(JobStatus::Running.as_str()),
+                jobs::lease_expires_at.eq(None::<DateTime<Utc>>),
+                jobs::started_at.eq(diesel::dsl::now),
                 jobs::updated_at.eq(diesel::dsl::now),
             ))
             .returning(JobDb::as_select())
             .get_result(&mut conn)
-            .map_err(|e| match e {
-                diesel::result::Error::NotFound => Error::NotFound(format!("Job not found: {}", id)),
-                e => Error::Database(e),
-            })?;
+            .optional()
+            .map_err(Error::Database)?;
             
-        // Convert to application model
-        Ok(Job::from(job_db))
+        match job_db {
+            Some(job_db) => Ok(Job::from(job_db)),
+            None => Err(rejected_transition(&mut conn, id, &JobStatus::Running)),
+        }
     }
     
     async fn set_completed(
@@ -175,9 +311,12 @@ impl JobRepository for DieselJobRepository {
         id: Uuid, 
         success: bool, 
         output: Option<serde_json::Value>, 
-        error: Option<String>, 
+        error: Option<JobError>, 
         cost_cents: i32
     ) -> Result<Job> {
+        let error_value = error.as_ref().map(serde_json::to_value).transpose()
+            .map_err(|e| Error::Other(e.into()))?;
+        
         // Use transaction to ensure atomicity of job completion
         self.pool.run_in_transaction(|conn| {
             let status = if success { JobStatus::Succeeded } else { JobStatus::Failed };
@@ -190,25 +329,291 @@ impl JobRepository for DieselJobRepository {
                 .set((
                     jobs::status.eq(status.as_str()),
                     jobs::cost_cents.eq(cost_cents),
+                    jobs::output_data.eq(output),
+                    jobs::error.eq(error_value),
                     jobs::completed_at.eq(diesel::dsl::now),
                     jobs::updated_at.eq(diesel::dsl::now),
                 ))
                 .returning(JobDb::as_select())
                 .get_result(conn)?;
             
-            // Create a Job from JobDb and add the non-DB fields
-            let mut job = Job::from(job_db);
-            
-            // Set the fields that aren't in the database
-            job.output_data = output;
-            job.error = error;
-            
-            Ok(job)
+            Ok(Job::from(job_db))
+        })
+    }
+    
+    async fn complete_held(
+        &self,
+        id: Uuid,
+        holder: JobHolder,
+        success: bool,
+        output: Option<serde_json::Value>,
+        error: Option<JobError>,
+        cost_cents: i32,
+    ) -> Result<(CompletionResolution, Job)> {
+        let completion = HeldCompletion { job_id: id, holder, success, output, error, cost_cents };
+        self.pool.run_in_transaction(|conn| complete_held_in(conn, &completion))
+    }
+
+    async fn complete_held_many(&self, completions: Vec<HeldCompletion>) -> Result<Vec<(CompletionResolution, Job)>> {
+        self.pool.run_in_transaction(|conn| {
+            completions.iter()
+                .map(|completion| complete_held_in(conn, completion))
+                .collect()
+        })
+    }
+
+    async fn hand_back(&self, id: Uuid, runner_id: Uuid) -> Result<Option<Job>> {
+        self.pool.run_in_transaction(|conn| {
+            let held = jobs::table
+                .find(id)
+                .filter(jobs::status.eq(JobStatus::Running.as_str()))
+                .filter(jobs::runner_id.eq(runner_id))
+                .filter(jobs::sub_task_count.is_null())
+                .select(jobs::cancel_requested_at)
+                .for_update()
+                .first::<Option<DateTime<Utc>>>(conn)
+                .optional()?;
+
+            // A job whose cancellation was requested is not started again; others are
+            // back in the queue, waiting to be claimed
+            let job_db = match held {
+                Some(cancel_requested_at) => {
+                    let status = if cancel_requested_at.is_some() { JobStatus::Cancelled } else { JobStatus::Pending };
+                    let requeued = (status == JobStatus::Pending).then(|| (
+                        jobs::queued_at.eq(Utc::now()),
+                        jobs::claimed_at.eq(None::<DateTime<Utc>>),
+                        jobs::started_at.eq(None::<DateTime<Utc>>),
+                    ));
+                    Some(diesel::update(jobs::table)
+                        .filter(jobs::id.eq(id))
+                        .set((
+                            jobs::status.eq(status.as_str()),
+                            jobs::updated_at.eq(diesel::dsl::now),
+                            requeued,
+                        ))
+                        .returning(JobDb::as_select())
+                        .get_result(conn)?)
+                }
+                None => None,
+            };
+
+            // The interrupted run stays in the job's history as abandoned
+            if job_db.is_some() {
+                diesel::update(job_attempts::table)
+                    .filter(job_attempts::job_id.eq(id))
+                    .filter(job_attempts::status.eq(AttemptStatus::Running.as_str()))
+                    .set((
+                        job_attempts::status.eq(AttemptStatus::Abandoned.as_str()),
+                        job_attempts::finished_at.eq(diesel::dsl::now),
+                    ))
+                    .execute(conn)?;
+            }
+
+            Ok(job_db.map(Job::from))
         })
     }
+
+    async fn request_cancellation(&self, id: Uuid) -> Result<Job> {
+        let mut conn = self.pool.get()?;
+
+        // Only the first request is recorded
+        let job_db = diesel::update(jobs::table)
+            .filter(jobs::id.eq(id))
+            .filter(jobs::status.eq(JobStatus::Running.as_str()))
+            .filter(jobs::sub_task_count.is_null())
+            .filter(jobs::cancel_requested_at.is_null())
+            .set(jobs::cancel_requested_at.eq(Utc::now()))
+            .returning(JobDb::as_select())
+            .get_result(&mut conn)
+            .optional()
+            .map_err(Error::Database)?;
+        if let Some(job_db) = job_db {
+            return Ok(Job::from(job_db));
+        }
+
+        let job = Job::from(jobs::table
+            .find(id)
+            .select(JobDb::as_select())
+            .first(&mut conn)
+            .optional()
+            .map_err(Error::Database)?
+            .ok_or_else(|| Error::NotFound(format!("Job not found: {}", id)))?);
+        if !job.can_request_cancellation() {
+            return Err(Error::InvalidInput(format!(
+                "Job {} is {} and not processed by a runner that could cancel it",
+                id, job.status.as_str()
+            )));
+        }
+        Ok(job)
+    }
+
+    async fn cancellation_requested(&self, id: Uuid) -> Result<bool> {
+        let mut conn = self.pool.get()?;
+
+        let cancel_requested_at = jobs::table
+            .find(id)
+            .select(jobs::cancel_requested_at)
+            .first::<Option<DateTime<Utc>>>(&mut conn)
+            .optional()
+            .map_err(Error::Database)?
+            .ok_or_else(|| Error::NotFound(format!("Job not found: {}", id)))?;
+        Ok(cancel_requested_at.is_some())
+    }
+
+    async fn find_running_by_runner(&self, runner_id: Uuid) -> Result<Vec<Job>> {
+        let mut conn = self.pool.get()?;
+
+        let jobs_db = jobs::table
+            .filter(jobs::status.eq(JobStatus::Running.as_str()))
+            .filter(jobs::runner_id.eq(runner_id))
+            .filter(jobs::sub_task_count.is_null())
+            .order(jobs::updated_at.asc())
+            .select(JobDb::as_select())
+            .load(&mut conn)
+            .map_err(Error::Database)?;
+
+        Ok(jobs_db.into_iter().map(Job::from).collect())
+    }
+
+    async fn set_cost_breakdown(&self, id: Uuid, breakdown: CostBreakdown) -> Result<()> {
+        let mut conn = self.pool.get()?;
+        
+        let count = diesel::update(jobs::table)
+            .filter(jobs::id.eq(id))
+            .set(jobs::cost_breakdown.eq(serde_json::json!(breakdown)))
+            .execute(&mut conn)
+            .map_err(Error::Database)?;
+        if count == 0 {
+            return Err(Error::NotFound(format!("Job not found: {}", id)));
+        }
+        
+        Ok(())
+    }
+    
+    async fn assign_runner(&self, id: Uuid, runner_id: Uuid) -> Result<()> {
+        let mut conn = self.pool.get()?;
+        
+        let count = diesel::update(jobs::table)
+            .filter(jobs::id.eq(id))
+            .set(jobs::runner_id.eq(runner_id))
+            .execute(&mut conn)
+            .map_err(Error::Database)?;
+        
+        if count == 0 {
+            return Err(Error::NotFound(format!("Job not found: {}", id)));
+        }
+        
+        Ok(())
+    }
+    
+    async fn record_claim(&self, id: Uuid, priority: PriorityLevel) -> Result<()> {
+        let mut conn = self.pool.get()?;
+        
+        let count = diesel::update(jobs::table)
+            .filter(jobs::id.eq(id))
+            .set((
+                jobs::claimed_at.eq(Utc::now()),
+                jobs::queue_priority.eq(priority.as_i32()),
+            ))
+            .execute(&mut conn)
+            .map_err(Error::Database)?;
+        
+        if count == 0 {
+            return Err(Error::NotFound(format!("Job not found: {}", id)));
+        }
+        
+        Ok(())
+    }
+    
+    async fn lease(&self, id: Uuid, priority: PriorityLevel, runner_id: Option<Uuid>, until: DateTime<Utc>) -> Result<()> {
+        let mut conn = self.pool.get()?;
+        
+        let count = diesel::update(jobs::table)
+            .filter(jobs::id.eq(id))
+            .filter(jobs::status.eq_any(statuses_leading_to(&JobStatus::Running)))
+            .filter(jobs::expires_at.is_null().or(jobs::expires_at.gt(Utc::now())))
+            .set((
+                jobs::claimed_at.eq(Utc::now()),
+                jobs::queue_priority.eq(priority.as_i32()),
+                jobs::lease_expires_at.eq(until),
+                runner_id.map(|runner_id| jobs::runner_id.eq(runner_id)),
+            ))
+            .execute(&mut conn)
+            .map_err(Error::Database)?;
+        
+        if count == 0 {
+            return Err(rejected_transition(&mut conn, id, &JobStatus::Running));
+        }
+        
+        Ok(())
+    }
+    
+    async fn release_expired_leases(&self, now: DateTime<Utc>) -> Result<Vec<(Uuid, PriorityLevel)>> {
+        let mut conn = self.pool.get()?;
+        
+        let released = diesel::update(jobs::table)
+            .filter(jobs::lease_expires_at.lt(now))
+            .filter(jobs::status.eq(JobStatus::Pending.as_str()))
+            .set((
+                jobs::lease_expires_at.eq(None::<DateTime<Utc>>),
+                jobs::runner_id.eq(None::<Uuid>),
+            ))
+            .returning((jobs::id, jobs::queue_priority))
+            .get_results::<(Uuid, Option<i32>)>(&mut conn)
+            .map_err(Error::Database)?;
+        
+        Ok(released.into_iter()
+            .map(|(id, priority)| (id, priority.map(PriorityLevel::from_i32).unwrap_or(PriorityLevel::Medium)))
+            .collect())
+    }
+    
+    async fn schedule(&self, id: Uuid, scheduled_for: DateTime<Utc>) -> Result<Job> {
+        let mut conn = self.pool.get()?;
+        
+        let job_db = diesel::update(jobs::table)
+            .filter(jobs::id.eq(id))
+            .filter(jobs::status.eq_any(statuses_leading_to(&JobStatus::Scheduled)))
+            .set((
+                jobs::status.eq(JobStatus::Scheduled.as_str()),
+                jobs::scheduled_for.eq(scheduled_for),
+                // The job enters the queue when it is released
+                jobs::queued_at.eq(scheduled_for),
+                jobs::updated_at.eq(diesel::dsl::now),
+            ))
+            .returning(JobDb::as_select())
+            .get_result(&mut conn)
+            .optional()
+            .map_err(Error::Database)?;
+            
+        match job_db {
+            Some(job_db) => Ok(Job::from(job_db)),
+            None => Err(rejected_transition(&mut conn, id, &JobStatus::Scheduled)),
+        }
+    }
+    
+    async fn postpone_scheduled(&self, job_type_ids: Vec<Uuid>, due_before: DateTime<Utc>, until: DateTime<Utc>) -> Result<Vec<Uuid>> {
+        if job_type_ids.is_empty() {
+            return Ok(Vec::new());
+        }
+        
+        let mut conn = self.pool.get()?;
+        
+        diesel::update(jobs::table)
+            .filter(jobs::job_type_id.eq_any(job_type_ids))
+            .filter(jobs::status.eq(JobStatus::Scheduled.as_str()))
+            .filter(jobs::scheduled_for.lt(due_before))
+            .set((
+                jobs::scheduled_for.eq(until),
+                jobs::queued_at.eq(until),
+                jobs::updated_at.eq(diesel::dsl::now),
+            ))
+            .returning(jobs::id)
+            .get_results(&mut conn)
+            .map_err(Error::Database)
+    }
     
     async fn find_by_customer_id(&self, customer_id: Uuid) -> Result<Vec<Job>> {
-        let mut conn = get_connection(&self.pool)?;
+        let mut conn = self.pool.get()?;
         
         let jobs_db = jobs::table
             .filter(jobs::customer_id.eq(customer_id))
@@ -222,7 +627,7 @@ impl JobRepository for DieselJobRepository {
     }
     
     async fn find_by_status(&self, status: JobStatus) -> Result<Vec<Job>> {
-        let mut conn = get_connection(&self.pool)?;
+        let mut conn = self.pool.get()?;
         
         let jobs_db = jobs::table
             .filter(jobs::status.eq(status.as_str()))
@@ -236,7 +641,7 @@ impl JobRepository for DieselJobRepository {
     }
     
     async fn find_pending_jobs(&self, limit: i32) -> Result<Vec<Job>> {
-        let mut conn = get_connection(&self.pool)?;
+        let mut conn = self.pool.get()?;
         
         let jobs_db = jobs::table
             .filter(jobs::status.eq(JobStatus::Pending.as_str()))
@@ -252,7 +657,7 @@ impl JobRepository for DieselJobRepository {
     }
     
     async fn query_jobs(&self, filter: JobFilter, sort: Option<JobSortOrder>, pagination: Option<Pagination>) -> Result<(Vec<Job>, u64)> {
-        let mut conn = get_connection(&self.pool)?;
+        let mut conn = self.pool.get()?;
         
         // First, let's create a count query with the same filters
         let count_query = jobs::table.into_boxed();
@@ -288,8 +693,38 @@ impl JobRepository for DieselJobRepository {
         Ok((jobs, total as u64))
     }
     
+    async fn query_jobs_after(&self, filter: JobFilter, cursor: Option<JobCursor>, limit: u32) -> Result<(Vec<Job>, Option<JobCursor>)> {
+        let mut conn = self.pool.get()?;
+        
+        let mut query = self.apply_filters(jobs::table.into_boxed(), &filter);
+        
+        // Continue strictly after the last job of the previous page
+        if let Some(cursor) = cursor {
+            query = query.filter(
+                jobs::created_at.lt(cursor.created_at)
+                    .or(jobs::created_at.eq(cursor.created_at).and(jobs::id.lt(cursor.id)))
+            );
+        }
+        
+        // Fetch one extra row to find out whether another page follows
+        let mut jobs_db = query
+            .order((jobs::created_at.desc(), jobs::id.desc()))
+            .limit(i64::from(limit) + 1)
+            .select(JobDb::as_select())
+            .load(&mut conn)
+            .map_err(Error::Database)?;
+        
+        let has_more = jobs_db.len() > limit as usize;
+        jobs_db.truncate(limit as usize);
+        
+        let jobs: Vec<Job> = jobs_db.into_iter().map(Job::from).collect();
+        let next_cursor = if has_more { jobs.last().and_then(JobCursor::after) } else { None };
+        
+        Ok((jobs, next_cursor))
+    }
+    
     async fn get_job_stats_by_status(&self) -> Result<Vec<(String, i64)>> {
-        let mut conn = get_connection(&self.pool)?;
+        let mut conn = self.pool.get()?;
         
         // Group by status and count jobs
         let results = jobs::table
@@ -302,7 +737,7 @@ impl JobRepository for DieselJobRepository {
     }
     
     async fn get_job_stats_by_customer(&self) -> Result<Vec<(Uuid, i64)>> {
-        let mut conn = get_connection(&self.pool)?;
+        let mut conn = self.pool.get()?;
         
         // Group by customer_id and count jobs
         let results = jobs::table
@@ -315,7 +750,7 @@ impl JobRepository for DieselJobRepository {
     }
     
     async fn get_cost_statistics(&self) -> Result<(i64, i64)> {
-        let mut conn = get_connection(&self.pool)?;
+        let mut conn = self.pool.get()?;
         
         // Calculate sum of estimated cost and actual cost for completed jobs
         // Define the filter criteria for completed jobs
@@ -341,14 +776,239 @@ impl JobRepository for DieselJobRepository {
         Ok((total_cost, completed_count))
     }
     
+    async fn get_queue_stats_by_job_type(&self, window_minutes: i32) -> Result<Vec<JobTypeQueueStats>> {
+        let mut conn = self.pool.get()?;
+        
+        // Count waiting and running jobs per job type
+        let counts = jobs::table
+            .filter(jobs::status.eq_any([JobStatus::Pending.as_str(), JobStatus::Running.as_str()]))
+            .group_by((jobs::job_type_id, jobs::status))
+            .select((jobs::job_type_id, jobs::status, count_star()))
+            .load::<(Uuid, String, i64)>(&mut conn)
+            .map_err(Error::Database)?;
+        
+        // Sample the turnaround of recently completed jobs
+        let cutoff = Utc::now() - chrono::Duration::minutes(window_minutes.into());
+        let completed = jobs::table
+            .filter(jobs::completed_at.gt(cutoff))
+            .filter(jobs::created_at.gt(cutoff - chrono::Duration::days(WINDOW_STATS_MAX_JOB_AGE_DAYS)))
+            .select((jobs::job_type_id, jobs::created_at, jobs::completed_at))
+            .load::<(Uuid, Option<chrono::DateTime<chrono::Utc>>, Option<chrono::DateTime<chrono::Utc>>)>(&mut conn)
+            .map_err(Error::Database)?;
+        
+        // Per job type: the statistics and the summed turnaround in seconds
+        let mut stats: HashMap<Uuid, (JobTypeQueueStats, f64)> = HashMap::new();
+        
+        for (job_type_id, status, count) in counts {
+            let (job_type_stats, _) = stats.entry(job_type_id)
+                .or_insert_with(|| (JobTypeQueueStats::new(job_type_id), 0.0));
+            if status == JobStatus::Pending.as_str() {
+                job_type_stats.pending = count;
+            } else {
+                job_type_stats.running = count;
+            }
+        }
+        
+        for (job_type_id, created_at, completed_at) in completed {
+            if let (Some(created_at), Some(completed_at)) = (created_at, completed_at) {
+                let (job_type_stats, total_seconds) = stats.entry(job_type_id)
+                    .or_insert_with(|| (JobTypeQueueStats::new(job_type_id), 0.0));
+                job_type_stats.completed_in_window += 1;
+                *total_seconds += (completed_at - created_at).num_milliseconds().max(0) as f64 / 1000.0;
+            }
+        }
+        
+        Ok(stats.into_values()
+            .map(|(mut job_type_stats, total_seconds)| {
+                if job_type_stats.completed_in_window > 0 {
+                    job_type_stats.avg_processing_seconds = Some(total_seconds / job_type_stats.completed_in_window as f64);
+                }
+                job_type_stats
+            })
+            .collect())
+    }
+    
+    async fn get_runner_job_stats(&self, runner_id: Uuid, window_minutes: i32) -> Result<RunnerJobStats> {
+        let mut conn = self.pool.get()?;
+        
+        let cutoff = Utc::now() - chrono::Duration::minutes(window_minutes.into());
+        let completed = jobs::table
+            .filter(jobs::runner_id.eq(runner_id))
+            .filter(jobs::completed_at.gt(cutoff))
+            .filter(jobs::created_at.gt(cutoff - chrono::Duration::days(WINDOW_STATS_MAX_JOB_AGE_DAYS)))
+            .filter(jobs::status.eq_any([JobStatus::Succeeded.as_str(), JobStatus::Failed.as_str()]))
+            .select((jobs::status, jobs::created_at, jobs::completed_at))
+            .load::<(String, Option<chrono::DateTime<chrono::Utc>>, Option<chrono::DateTime<chrono::Utc>>)>(&mut conn)
+            .map_err(Error::Database)?;
+        
+        let mut stats = RunnerJobStats::new(runner_id);
+        let mut total_seconds = 0.0;
+        let mut timed = 0;
+        for (status, created_at, completed_at) in completed {
+            if status == JobStatus::Succeeded.as_str() {
+                stats.succeeded += 1;
+            } else {
+                stats.failed += 1;
+            }
+            if let (Some(created_at), Some(completed_at)) = (created_at, completed_at) {
+                total_seconds += (completed_at - created_at).num_milliseconds().max(0) as f64 / 1000.0;
+                timed += 1;
+            }
+        }
+        if timed > 0 {
+            stats.avg_processing_seconds = Some(total_seconds / timed as f64);
+        }
+        
+        Ok(stats)
+    }
+    
+    async fn get_spend_by_customer(&self, customer_ids: Vec<Uuid>, since: DateTime<Utc>) -> Result<Vec<CustomerSpend>> {
+        let mut conn = self.pool.get()?;
+        
+        let spend = jobs::table
+            .filter(jobs::customer_id.eq_any(customer_ids))
+            .filter(jobs::created_at.ge(since))
+            .filter(jobs::status.ne(JobStatus::Cancelled.as_str()))
+            .filter(jobs::test_mode.eq(false))
+            .group_by(jobs::customer_id)
+            .select((jobs::customer_id, count_star(), sum(jobs::cost_cents)))
+            .load::<(Uuid, i64, Option<i64>)>(&mut conn)
+            .map_err(Error::Database)?;
+        
+        Ok(spend.into_iter()
+            .map(|(customer_id, jobs, cost_cents)| CustomerSpend {
+                customer_id,
+                jobs,
+                cost_cents: cost_cents.unwrap_or(0),
+            })
+            .collect())
+    }
+    
+    async fn get_customer_activity(&self, since: DateTime<Utc>, until: DateTime<Utc>) -> Result<Vec<CustomerActivity>> {
+        let mut conn = self.pool.get()?;
+        
+        let rows = jobs::table
+            .filter(jobs::created_at.ge(since))
+            .filter(jobs::created_at.lt(until))
+            .filter(jobs::status.ne(JobStatus::Cancelled.as_str()))
+            .filter(jobs::test_mode.eq(false))
+            .group_by((jobs::customer_id, jobs::status))
+            .select((jobs::customer_id, jobs::status, count_star(), sum(jobs::cost_cents)))
+            .load::<(Uuid, String, i64, Option<i64>)>(&mut conn)
+            .map_err(Error::Database)?;
+        
+        let mut activity: HashMap<Uuid, CustomerActivity> = HashMap::new();
+        for (customer_id, status, jobs, cost_cents) in rows {
+            let customer = activity.entry(customer_id)
+                .or_insert_with(|| CustomerActivity::new(customer_id));
+            customer.jobs += jobs;
+            customer.cost_cents += cost_cents.unwrap_or(0);
+            if status == JobStatus::Succeeded.as_str() {
+                customer.succeeded += jobs;
+            } else if status == JobStatus::Failed.as_str() {
+                customer.failed += jobs;
+            }
+        }
+        
+        Ok(activity.into_values().collect())
+    }
+    
+    async fn get_charges_by_job_type(&self, customer_ids: Vec<Uuid>, since: DateTime<Utc>, until: DateTime<Utc>) -> Result<Vec<JobTypeCharges>> {
+        let mut conn = self.pool.get()?;
+        
+        let charged = jobs::table
+            .filter(jobs::customer_id.eq_any(customer_ids))
+            .filter(jobs::completed_at.ge(since))
+            .filter(jobs::completed_at.lt(until))
+            .filter(jobs::cost_cents.gt(0))
+            .filter(jobs::test_mode.eq(false))
+            .filter(jobs::parent_id.is_null())
+            .select((jobs::customer_id, jobs::job_type_id, jobs::cost_cents, jobs::cost_breakdown))
+            .load::<(Uuid, Uuid, i32, Option<serde_json::Value>)>(&mut conn)
+            .map_err(Error::Database)?;
+        
+        let mut charges: HashMap<(Uuid, Uuid), JobTypeCharges> = HashMap::new();
+        for (customer_id, job_type_id, cost_cents, breakdown) in charged {
+            let cost = breakdown.and_then(|breakdown| serde_json::from_value(breakdown).ok())
+                .unwrap_or_else(|| CostBreakdown::unitemized(cost_cents));
+            let entry = charges.entry((customer_id, job_type_id))
+                .or_insert_with(|| JobTypeCharges { customer_id, job_type_id, jobs: 0, cost: CostBreakdown::default() });
+            entry.jobs += 1;
+            entry.cost = entry.cost + cost;
+        }
+        
+        Ok(charges.into_values().collect())
+    }
+    
+    async fn get_queue_wait_stats(&self, window_minutes: i32) -> Result<Vec<PriorityWaitStats>> {
+        let mut conn = self.pool.get()?;
+        
+        let cutoff = Utc::now() - chrono::Duration::minutes(window_minutes.into());
+        let claimed = jobs::table
+            .filter(jobs::claimed_at.gt(cutoff))
+            .filter(jobs::created_at.gt(cutoff - chrono::Duration::days(WINDOW_STATS_MAX_JOB_AGE_DAYS)))
+            .select((jobs::queue_priority, coalesce(jobs::queued_at, jobs::created_at), jobs::claimed_at))
+            .load::<(Option<i32>, Option<DateTime<Utc>>, Option<DateTime<Utc>>)>(&mut conn)
+            .map_err(Error::Database)?;
+        
+        // Wait times in seconds per priority level, from when the job entered the queue
+        let mut waits: HashMap<i32, Vec<f64>> = HashMap::new();
+        for (queue_priority, queued_at, claimed_at) in claimed {
+            if let (Some(queued_at), Some(claimed_at)) = (queued_at, claimed_at) {
+                let priority = PriorityLevel::from_i32(queue_priority.unwrap_or(PriorityLevel::Medium.as_i32()));
+                waits.entry(priority.as_i32())
+                    .or_default()
+                    .push((claimed_at - queued_at).num_milliseconds().max(0) as f64 / 1000.0);
+            }
+        }
+        
+        let mut stats: Vec<PriorityWaitStats> = waits.into_iter()
+            .filter_map(|(priority, waits)| PriorityWaitStats::from_samples(PriorityLevel::from_i32(priority), waits))
+            .collect();
+        stats.sort_by_key(|stats| stats.priority.as_i32());
+        
+        Ok(stats)
+    }
+    
+    async fn get_job_type_execution_stats(&self, job_type_id: Uuid, window_minutes: i32) -> Result<JobTypeExecutionStats> {
+        let mut conn = self.pool.get()?;
+        
+        let cutoff = Utc::now() - chrono::Duration::minutes(window_minutes.into());
+        let finished = jobs::table
+            .filter(jobs::job_type_id.eq(job_type_id))
+            .filter(jobs::completed_at.gt(cutoff))
+            .filter(jobs::created_at.gt(cutoff - chrono::Duration::days(WINDOW_STATS_MAX_JOB_AGE_DAYS)))
+            .filter(jobs::status.eq_any([JobStatus::Succeeded.as_str(), JobStatus::Failed.as_str()]))
+            .select((jobs::status, jobs::started_at, jobs::completed_at))
+            .load::<(String, Option<DateTime<Utc>>, Option<DateTime<Utc>>)>(&mut conn)
+            .map_err(Error::Database)?;
+        
+        let (mut succeeded, mut failed) = (0, 0);
+        let mut processing = Vec::new();
+        for (status, started_at, completed_at) in finished {
+            if status == JobStatus::Succeeded.as_str() {
+                succeeded += 1;
+            } else {
+                failed += 1;
+            }
+            if let (Some(started_at), Some(completed_at)) = (started_at, completed_at) {
+                processing.push((completed_at - started_at).num_milliseconds().max(0) as f64 / 1000.0);
+            }
+        }
+        
+        Ok(JobTypeExecutionStats::from_samples(job_type_id, succeeded, failed, processing))
+    }
+    
     async fn find_stalled_jobs(&self, running_threshold_minutes: i32) -> Result<Vec<Job>> {
-        let mut conn = get_connection(&self.pool)?;
+        let mut conn = self.pool.get()?;
         
         // Define stalled jobs as those that have been in 'Running' state for longer than the threshold
         // For stalled jobs, we need to find jobs that have been running for too long
         // First we'll get all running jobs, then filter based on the running_threshold_minutes
+        // Jobs waiting for their sub-tasks are not running themselves
         let running_jobs = jobs::table
             .filter(jobs::status.eq(JobStatus::Running.as_str()))
+            .filter(jobs::sub_task_count.is_null())
             .into_boxed();
         
         // Since we can't directly use interval arithmetic in a safe way with Diesel,
@@ -359,12 +1019,13 @@ impl JobRepository for DieselJobRepository {
             .map_err(|e| Error::Database(e))?;
             
         // Filter jobs in Rust based on the threshold
-        let now = Utc::now().naive_utc();
+        let now = Utc::now();
         let jobs_db: Vec<JobDb> = jobs_db
             .into_iter()
             .filter(|job| {
-                if let Some(updated_at) = job.updated_at {
-                    let duration = now.signed_duration_since(updated_at);
+                // Jobs started before their start was recorded fall back to their last update
+                if let Some(started_at) = job.started_at.or(job.updated_at) {
+                    let duration = now.signed_duration_since(started_at);
                     duration.num_minutes() >= running_threshold_minutes.into()
                 } else {
                     false
@@ -378,6 +1039,58 @@ impl JobRepository for DieselJobRepository {
         Ok(jobs)
     }
     
+    async fn find_waiting_since(&self, before: DateTime<Utc>) -> Result<Vec<Job>> {
+        let mut conn = self.pool.get()?;
+        
+        // Jobs without an update timestamp are judged by their creation time
+        let jobs_db = jobs::table
+            .filter(jobs::status.eq_any([JobStatus::Pending.as_str(), JobStatus::Scheduled.as_str()]))
+            .filter(
+                jobs::updated_at.lt(before)
+                    .or(jobs::updated_at.is_null().and(jobs::created_at.lt(before)))
+            )
+            .filter(jobs::scheduled_for.is_null().or(jobs::scheduled_for.lt(before)))
+            .order(jobs::created_at.asc())
+            .select(JobDb::as_select())
+            .load(&mut conn)
+            .map_err(Error::Database)?;
+        
+        let jobs = jobs_db.into_iter().map(Job::from).collect();
+        Ok(jobs)
+    }
+    
+    async fn find_expired(&self, now: DateTime<Utc>) -> Result<Vec<Job>> {
+        let mut conn = self.pool.get()?;
+        
+        let jobs_db = jobs::table
+            .filter(jobs::status.eq_any([JobStatus::Pending.as_str(), JobStatus::Scheduled.as_str()]))
+            .filter(jobs::expires_at.le(now))
+            .order(jobs::expires_at.asc())
+            .select(JobDb::as_select())
+            .load(&mut conn)
+            .map_err(Error::Database)?;
+        
+        let jobs = jobs_db.into_iter().map(Job::from).collect();
+        Ok(jobs)
+    }
+    
+    async fn find_changed_since(&self, since: DateTime<Utc>, limit: i64) -> Result<Vec<Job>> {
+        let mut conn = self.pool.get()?;
+        
+        // Jobs without an update timestamp changed when they were created
+        let changed_at = coalesce(jobs::updated_at, jobs::created_at);
+        let jobs_db = jobs::table
+            .filter(changed_at.gt(since))
+            .order((changed_at.asc(), jobs::id.asc()))
+            .limit(limit)
+            .select(JobDb::as_select())
+            .load(&mut conn)
+            .map_err(Error::Database)?;
+        
+        let jobs = jobs_db.into_iter().map(Job::from).collect();
+        Ok(jobs)
+    }
+    
     async fn bulk_update_status(&self, ids: Vec<Uuid>, status: JobStatus) -> Result<usize> {
         if ids.is_empty() {
             return Ok(0);
@@ -397,4 +1110,131 @@ impl JobRepository for DieselJobRepository {
             Ok(updated_count)
         })
     }
+    
+    async fn count_pending_by_job_type(&self, job_type_id: Uuid) -> Result<i64> {
+        let mut conn = self.pool.get()?;
+        
+        jobs::table
+            .filter(jobs::job_type_id.eq(job_type_id))
+            .filter(jobs::status.eq(JobStatus::Pending.as_str()))
+            .count()
+            .get_result(&mut conn)
+            .map_err(Error::Database)
+    }
+    
+    async fn fan_out(&self, id: Uuid, sub_tasks: Vec<NewJob>) -> Result<Vec<Job>> {
+        let created = self.pool.run_in_transaction(|conn| {
+            // Only a running job that has not fanned out yet gets sub-tasks
+            let updated = diesel::update(jobs::table)
+                .filter(jobs::id.eq(id))
+                .filter(jobs::status.eq(JobStatus::Running.as_str()))
+                .filter(jobs::sub_task_count.is_null())
+                .set((
+                    jobs::sub_task_count.eq(sub_tasks.len() as i32),
+                    jobs::updated_at.eq(diesel::dsl::now),
+                ))
+                .execute(conn)?;
+            if updated == 0 {
+                return Ok(None);
+            }
+            
+            diesel::insert_into(jobs::table)
+                .values(&sub_tasks)
+                .returning(JobDb::as_select())
+                .get_results(conn)
+                .map(Some)
+        })?;
+        
+        match created {
+            Some(mut jobs_db) => {
+                jobs_db.sort_by_key(|job| job.sub_task_index);
+                Ok(jobs_db.into_iter().map(Job::from).collect())
+            }
+            None => Err(Error::InvalidInput(format!("Job {} is not running or already fanned out", id))),
+        }
+    }
+    
+    async fn find_sub_tasks(&self, id: Uuid) -> Result<Vec<Job>> {
+        let mut conn = self.pool.get()?;
+        
+        let jobs_db = jobs::table
+            .filter(jobs::parent_id.eq(id))
+            .order(jobs::sub_task_index.asc())
+            .select(JobDb::as_select())
+            .load(&mut conn)
+            .map_err(Error::Database)?;
+        
+        Ok(jobs_db.into_iter().map(Job::from).collect())
+    }
+    
+    async fn fan_in(&self, id: Uuid) -> Result<Option<Job>> {
+        self.pool.run_in_transaction(|conn| {
+            // Lock the job so sub-tasks finishing at the same time finish it only once
+            let parent = jobs::table
+                .filter(jobs::id.eq(id))
+                .filter(jobs::status.eq(JobStatus::Running.as_str()))
+                .filter(jobs::sub_task_count.is_not_null())
+                .select(JobDb::as_select())
+                .for_update()
+                .first(conn)
+                .optional()?;
+            let Some(parent) = parent else {
+                return Ok(None);
+            };
+            
+            let sub_tasks: Vec<Job> = jobs::table
+                .filter(jobs::parent_id.eq(id))
+                .select(JobDb::as_select())
+                .load(conn)?
+                .into_iter()
+                .map(Job::from)
+                .collect();
+            if (sub_tasks.len() as i32) < parent.sub_task_count.unwrap_or_default() {
+                return Ok(None);
+            }
+            let Some(outcome) = crate::models::job::fan_in(&sub_tasks) else {
+                return Ok(None);
+            };
+            
+            diesel::update(jobs::table)
+                .filter(jobs::id.eq(id))
+                .set((
+                    jobs::status.eq(outcome.status.as_str()),
+                    jobs::cost_cents.eq(outcome.cost_cents),
+                    jobs::cost_breakdown.eq(outcome.cost_breakdown.map(|breakdown| serde_json::json!(breakdown))),
+                    jobs::completed_at.eq(diesel::dsl::now),
+                    jobs::updated_at.eq(diesel::dsl::now),
+                ))
+                .returning(JobDb::as_select())
+                .get_result(conn)
+                .map(|job_db| Some(Job::from(job_db)))
+        })
+    }
+    
+    async fn find_fanned_out(&self) -> Result<Vec<Job>> {
+        let mut conn = self.pool.get()?;
+        
+        let jobs_db = jobs::table
+            .filter(jobs::status.eq(JobStatus::Running.as_str()))
+            .filter(jobs::sub_task_count.is_not_null())
+            .order(jobs::created_at.asc())
+            .select(JobDb::as_select())
+            .load(&mut conn)
+            .map_err(Error::Database)?;
+        
+        Ok(jobs_db.into_iter().map(Job::from).collect())
+    }
+    
+    async fn find_by_submission(&self, submission_id: Uuid) -> Result<Vec<Job>> {
+        let mut conn = self.pool.get()?;
+        
+        let jobs_db = jobs::table
+            .filter(jobs::submission_id.eq(submission_id))
+            .order((jobs::created_at.asc(), jobs::id.asc()))
+            .select(JobDb::as_select())
+            .load(&mut conn)
+            .map_err(Error::Database)?;
+        
+        Ok(jobs_db.into_iter().map(Job::from).collect())
+    }
 }
@@ -0,0 +1,51 @@
+use async_trait::async_trait;
+use diesel::prelude::*;
+use crate::database::TenantPool;
+use anyhow::{anyhow, Result};
+use uuid::Uuid;
+
+use crate::models::job_artifact::{JobArtifact, NewJobArtifact};
+use crate::repositories::JobArtifactRepository;
+use crate::diesel_schema::job_artifacts;
+
+/// Diesel implementation of the JobArtifactRepository
+pub struct DieselJobArtifactRepository {
+    pool: TenantPool,
+}
+
+impl DieselJobArtifactRepository {
+    /// Create a new DieselJobArtifactRepository with the given connection pool
+    pub fn new(pool: impl Into<TenantPool>) -> Self {
+        Self { pool: pool.into() }
+    }
+}
+
+#[async_trait]
+impl JobArtifactRepository for DieselJobArtifactRepository {
+    async fn create(&self, artifact: NewJobArtifact) -> Result<()> {
+        let mut conn = self.pool.get()?;
+
+        tokio::task::spawn_blocking(move || {
+            diesel::insert_into(job_artifacts::table)
+                .values(&artifact)
+                .execute(&mut conn)
+        }).await??;
+
+        Ok(())
+    }
+
+    async fn find(&self, job_id: Uuid, artifact_id: Uuid) -> Result<JobArtifact> {
+        let mut conn = self.pool.get()?;
+
+        let artifact = tokio::task::spawn_blocking(move || {
+            job_artifacts::table
+                .find(artifact_id)
+                .filter(job_artifacts::job_id.eq(job_id))
+                .select(JobArtifact::as_select())
+                .first(&mut conn)
+                .optional()
+        }).await??;
+
+        artifact.ok_or_else(|| anyhow!("Artifact {} not found for job ID: {}", artifact_id, job_id))
+    }
+}
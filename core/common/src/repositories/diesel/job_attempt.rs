@@ -0,0 +1,168 @@
+use async_trait::async_trait;
+use chrono::{DateTime, Utc};
+use diesel::dsl::max;
+use diesel::prelude::*;
+use crate::database::TenantPool;
+use uuid::Uuid;
+use anyhow::{anyhow, Result};
+
+use crate::models::job_attempt::{AttemptOutcome, AttemptStatus, CrashReport, JobAttempt, NewJobAttempt};
+use crate::repositories::job::RunnerJobStats;
+use crate::repositories::JobAttemptRepository;
+use crate::diesel_schema::job_attempts;
+
+/// Diesel implementation of the JobAttemptRepository
+pub struct DieselJobAttemptRepository {
+    pool: TenantPool,
+}
+
+impl DieselJobAttemptRepository {
+    /// Create a new DieselJobAttemptRepository with the given connection pool
+    pub fn new(pool: impl Into<TenantPool>) -> Self {
+        Self { pool: pool.into() }
+    }
+}
+
+#[async_trait]
+impl JobAttemptRepository for DieselJobAttemptRepository {
+    async fn start(&self, job_id: Uuid, runner_id: Option<Uuid>) -> Result<JobAttempt> {
+        let mut conn = self.pool.get()?;
+
+        let attempt: JobAttempt = tokio::task::spawn_blocking(move || {
+            conn.transaction(|conn| {
+                let previous: Option<i32> = job_attempts::table
+                    .filter(job_attempts::job_id.eq(job_id))
+                    .select(max(job_attempts::attempt_number))
+                    .first(conn)?;
+
+                diesel::insert_into(job_attempts::table)
+                    .values(&NewJobAttempt {
+                        id: Uuid::new_v4(),
+                        job_id,
+                        attempt_number: previous.unwrap_or(0) + 1,
+                        runner_id,
+                        status: AttemptStatus::Running.as_str().to_string(),
+                        started_at: Utc::now(),
+                    })
+                    .get_result::<JobAttempt>(conn)
+            })
+        }).await??;
+
+        Ok(attempt)
+    }
+
+    async fn finish(&self, id: Uuid, outcome: AttemptOutcome) -> Result<JobAttempt> {
+        let mut conn = self.pool.get()?;
+
+        let status = if outcome.success { AttemptStatus::Succeeded } else { AttemptStatus::Failed };
+        let error = outcome.error.as_ref().map(serde_json::to_value).transpose()?;
+        let attempt = tokio::task::spawn_blocking(move || {
+            diesel::update(job_attempts::table.find(id))
+                .filter(job_attempts::status.eq(AttemptStatus::Running.as_str()))
+                .set((
+                    job_attempts::status.eq(status.as_str()),
+                    job_attempts::error.eq(error),
+                    job_attempts::cost_cents.eq(outcome.cost_cents),
+                    job_attempts::finished_at.eq(outcome.finished_at),
+                ))
+                .get_result::<JobAttempt>(&mut conn)
+                .optional()
+        }).await??;
+
+        match attempt {
+            Some(attempt) => Ok(attempt),
+            None => Err(anyhow!("Running job attempt not found with ID: {}", id)),
+        }
+    }
+
+    async fn record_crash(&self, id: Uuid, crash: CrashReport) -> Result<JobAttempt> {
+        let mut conn = self.pool.get()?;
+
+        let crash = serde_json::to_value(&crash)?;
+        let attempt = tokio::task::spawn_blocking(move || {
+            diesel::update(job_attempts::table.find(id))
+                .set(job_attempts::crash.eq(Some(crash)))
+                .get_result::<JobAttempt>(&mut conn)
+                .optional()
+        }).await??;
+
+        match attempt {
+            Some(attempt) => Ok(attempt),
+            None => Err(anyhow!("Job attempt not found with ID: {}", id)),
+        }
+    }
+
+    async fn abandon_running(&self, job_id: Uuid) -> Result<usize> {
+        let mut conn = self.pool.get()?;
+
+        let count = tokio::task::spawn_blocking(move || {
+            diesel::update(job_attempts::table)
+                .filter(job_attempts::job_id.eq(job_id))
+                .filter(job_attempts::status.eq(AttemptStatus::Running.as_str()))
+                .set((
+                    job_attempts::status.eq(AttemptStatus::Abandoned.as_str()),
+                    job_attempts::finished_at.eq(diesel::dsl::now),
+                ))
+                .execute(&mut conn)
+        }).await??;
+
+        Ok(count)
+    }
+
+    async fn find_abandoned_by_runner(&self, runner_id: Uuid, since: DateTime<Utc>) -> Result<Vec<JobAttempt>> {
+        let mut conn = self.pool.get()?;
+
+        let attempts = tokio::task::spawn_blocking(move || {
+            job_attempts::table
+                .filter(job_attempts::runner_id.eq(runner_id))
+                .filter(job_attempts::status.eq(AttemptStatus::Abandoned.as_str()))
+                .filter(job_attempts::finished_at.ge(since))
+                .order(job_attempts::finished_at.asc())
+                .load::<JobAttempt>(&mut conn)
+        }).await??;
+
+        Ok(attempts)
+    }
+
+    async fn find_by_job_id(&self, job_id: Uuid) -> Result<Vec<JobAttempt>> {
+        let mut conn = self.pool.get()?;
+
+        let attempts = tokio::task::spawn_blocking(move || {
+            job_attempts::table
+                .filter(job_attempts::job_id.eq(job_id))
+                .order(job_attempts::attempt_number.asc())
+                .load::<JobAttempt>(&mut conn)
+        }).await??;
+
+        Ok(attempts)
+    }
+
+    async fn get_runner_stats(&self, runner_id: Uuid, window_minutes: i32) -> Result<RunnerJobStats> {
+        let mut conn = self.pool.get()?;
+
+        let cutoff = Utc::now() - chrono::Duration::minutes(window_minutes.into());
+        let finished = tokio::task::spawn_blocking(move || {
+            job_attempts::table
+                .filter(job_attempts::runner_id.eq(runner_id))
+                .filter(job_attempts::finished_at.gt(cutoff))
+                .filter(job_attempts::status.ne(AttemptStatus::Running.as_str()))
+                .load::<JobAttempt>(&mut conn)
+        }).await??;
+
+        let mut stats = RunnerJobStats::new(runner_id);
+        let mut total_ms = 0;
+        for attempt in &finished {
+            if attempt.attempt_status() == Some(AttemptStatus::Succeeded) {
+                stats.succeeded += 1;
+            } else {
+                stats.failed += 1;
+            }
+            total_ms += attempt.duration_ms().unwrap_or(0);
+        }
+        if !finished.is_empty() {
+            stats.avg_processing_seconds = Some(total_ms as f64 / 1000.0 / finished.len() as f64);
+        }
+
+        Ok(stats)
+    }
+}
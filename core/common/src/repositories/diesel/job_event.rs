@@ -0,0 +1,124 @@
+use async_trait::async_trait;
+use chrono::{DateTime, Utc};
+use diesel::dsl::{exists, not};
+use diesel::pg::Pg;
+use diesel::prelude::*;
+use crate::database::TenantPool;
+use anyhow::{Result, anyhow};
+use uuid::Uuid;
+
+use crate::models::job::{Job, JobDb, JobStatus};
+use crate::models::job_event::{JobLifecycleEvent, NewJobLifecycleEvent, LIFECYCLE_EVENTS};
+use crate::models::webhook::WebhookEventType;
+use crate::repositories::JobEventRepository;
+use crate::diesel_schema::{job_lifecycle_events, jobs};
+
+/// Jobs that reached an event since `since`, oldest first, skipping sub-tasks of batch
+/// jobs and jobs that have the event already
+fn jobs_reaching<'a>(event_type: WebhookEventType, since: DateTime<Utc>) -> jobs::BoxedQuery<'a, Pg> {
+    let query = jobs::table
+        .filter(jobs::parent_id.is_null())
+        .filter(not(exists(
+            job_lifecycle_events::table
+                .filter(job_lifecycle_events::job_id.eq(jobs::id))
+                .filter(job_lifecycle_events::event_type.eq(event_type.as_str())),
+        )))
+        .into_boxed();
+    match event_type {
+        WebhookEventType::JobStarted => query
+            .filter(jobs::started_at.ge(since))
+            .order(jobs::started_at.asc()),
+        WebhookEventType::JobSucceeded => query
+            .filter(jobs::status.eq(JobStatus::Succeeded.as_str()))
+            .filter(jobs::completed_at.ge(since))
+            .order(jobs::completed_at.asc()),
+        _ => query
+            .filter(jobs::status.eq(JobStatus::Failed.as_str()))
+            .filter(jobs::completed_at.ge(since))
+            .order(jobs::completed_at.asc()),
+    }
+}
+
+/// Diesel implementation of the JobEventRepository
+pub struct DieselJobEventRepository {
+    pool: TenantPool,
+}
+
+impl DieselJobEventRepository {
+    /// Create a new DieselJobEventRepository with the given connection pool
+    pub fn new(pool: impl Into<TenantPool>) -> Self {
+        Self { pool: pool.into() }
+    }
+}
+
+#[async_trait]
+impl JobEventRepository for DieselJobEventRepository {
+    async fn capture(&self, since: DateTime<Utc>, limit: i64) -> Result<usize> {
+        let mut conn = self.pool.get()?;
+
+        let captured = tokio::task::spawn_blocking(move || {
+            conn.transaction(|conn| {
+                let mut events = Vec::new();
+                for event_type in LIFECYCLE_EVENTS {
+                    let reached = jobs_reaching(event_type, since)
+                        .limit(limit)
+                        .select(JobDb::as_select())
+                        .load::<JobDb>(conn)?;
+                    events.extend(reached.into_iter()
+                        .map(Job::from)
+                        .filter_map(|job| NewJobLifecycleEvent::for_job(&job, event_type)));
+                }
+
+                // A concurrent capture of the same event loses the race on the job and event type
+                diesel::insert_into(job_lifecycle_events::table)
+                    .values(&events)
+                    .on_conflict((job_lifecycle_events::job_id, job_lifecycle_events::event_type))
+                    .do_nothing()
+                    .execute(conn)
+            })
+        }).await??;
+
+        Ok(captured)
+    }
+
+    async fn find_pending(&self, limit: i64) -> Result<Vec<JobLifecycleEvent>> {
+        let mut conn = self.pool.get()?;
+
+        let events = tokio::task::spawn_blocking(move || {
+            job_lifecycle_events::table
+                .filter(job_lifecycle_events::notified_at.is_null())
+                .order((job_lifecycle_events::occurred_at.asc(), job_lifecycle_events::created_at.asc()))
+                .limit(limit)
+                .load::<JobLifecycleEvent>(&mut conn)
+        }).await??;
+
+        Ok(events)
+    }
+
+    async fn find_by_job(&self, job_id: Uuid) -> Result<Vec<JobLifecycleEvent>> {
+        let mut conn = self.pool.get()?;
+
+        let events = tokio::task::spawn_blocking(move || {
+            job_lifecycle_events::table
+                .filter(job_lifecycle_events::job_id.eq(job_id))
+                .order((job_lifecycle_events::occurred_at.asc(), job_lifecycle_events::created_at.asc()))
+                .load::<JobLifecycleEvent>(&mut conn)
+        }).await??;
+
+        Ok(events)
+    }
+
+    async fn mark_notified(&self, id: Uuid, notified_at: DateTime<Utc>) -> Result<JobLifecycleEvent> {
+        let mut conn = self.pool.get()?;
+
+        let event = tokio::task::spawn_blocking(move || {
+            diesel::update(job_lifecycle_events::table.find(id))
+                .set(job_lifecycle_events::notified_at.eq(notified_at))
+                .get_result::<JobLifecycleEvent>(&mut conn)
+                .optional()
+        }).await??
+            .ok_or_else(|| anyhow!("Job lifecycle event not found with ID: {}", id))?;
+
+        Ok(event)
+    }
+}
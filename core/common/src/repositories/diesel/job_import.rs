@@ -0,0 +1,186 @@
+use async_trait::async_trait;
+use chrono::Utc;
+use diesel::prelude::*;
+use crate::database::TenantPool;
+use uuid::Uuid;
+use anyhow::{Result, anyhow};
+
+use crate::models::job_import::{ImportStatus, JobImport, JobImportRow, NewJobImport};
+use crate::repositories::JobImportRepository;
+use crate::diesel_schema::{job_imports, job_import_rows, jobs};
+
+/// Diesel implementation of the JobImportRepository
+pub struct DieselJobImportRepository {
+    pool: TenantPool,
+}
+
+impl DieselJobImportRepository {
+    /// Create a new DieselJobImportRepository with the given connection pool
+    pub fn new(pool: impl Into<TenantPool>) -> Self {
+        Self { pool: pool.into() }
+    }
+}
+
+#[async_trait]
+impl JobImportRepository for DieselJobImportRepository {
+    async fn create(&self, import: NewJobImport) -> Result<JobImport> {
+        let mut conn = self.pool.get()?;
+
+        let import: JobImport = tokio::task::spawn_blocking(move || {
+            diesel::insert_into(job_imports::table)
+                .values(&import)
+                .get_result::<JobImport>(&mut conn)
+        }).await??;
+
+        Ok(import)
+    }
+
+    async fn find_by_id(&self, id: Uuid) -> Result<JobImport> {
+        let mut conn = self.pool.get()?;
+
+        let import: JobImport = tokio::task::spawn_blocking(move || {
+            job_imports::table
+                .find(id)
+                .first(&mut conn)
+                .optional()
+        }).await??
+            .ok_or_else(|| anyhow!("Job import not found with ID: {}", id))?;
+
+        Ok(import)
+    }
+
+    async fn find_by_customer_id(&self, customer_id: Uuid) -> Result<Vec<JobImport>> {
+        let mut conn = self.pool.get()?;
+
+        let imports: Vec<JobImport> = tokio::task::spawn_blocking(move || {
+            job_imports::table
+                .filter(job_imports::customer_id.eq(customer_id))
+                .order(job_imports::created_at.desc())
+                .load::<JobImport>(&mut conn)
+        }).await??;
+
+        Ok(imports)
+    }
+
+    async fn find_unfinished(&self) -> Result<Vec<JobImport>> {
+        let mut conn = self.pool.get()?;
+
+        let imports: Vec<JobImport> = tokio::task::spawn_blocking(move || {
+            job_imports::table
+                .filter(job_imports::status.eq_any([ImportStatus::Pending.as_str(), ImportStatus::Processing.as_str()]))
+                .order(job_imports::created_at.asc())
+                .load::<JobImport>(&mut conn)
+        }).await??;
+
+        Ok(imports)
+    }
+
+    async fn claim_row(&self, import_id: Uuid, row_number: i32, job_id: Option<Uuid>, error: Option<String>) -> Result<bool> {
+        let mut conn = self.pool.get()?;
+
+        let claimed = tokio::task::spawn_blocking(move || {
+            conn.transaction(|conn| {
+                // Only the instance moving next_row past the row records it
+                let count = diesel::update(
+                    job_imports::table
+                        .filter(job_imports::id.eq(import_id))
+                        .filter(job_imports::next_row.eq(row_number))
+                )
+                    .set((
+                        job_imports::next_row.eq(row_number + 1),
+                        job_imports::submitted_rows.eq(job_imports::submitted_rows + if job_id.is_some() { 1 } else { 0 }),
+                        job_imports::failed_rows.eq(job_imports::failed_rows + if job_id.is_some() { 0 } else { 1 }),
+                        job_imports::status.eq(ImportStatus::Processing.as_str()),
+                        job_imports::updated_at.eq(Utc::now()),
+                    ))
+                    .execute(conn)?;
+                if count == 0 {
+                    return Ok(false);
+                }
+
+                diesel::insert_into(job_import_rows::table)
+                    .values((
+                        job_import_rows::import_id.eq(import_id),
+                        job_import_rows::row_number.eq(row_number),
+                        job_import_rows::job_id.eq(job_id),
+                        job_import_rows::error.eq(error),
+                    ))
+                    .execute(conn)?;
+
+                Ok::<_, diesel::result::Error>(true)
+            })
+        }).await??;
+
+        Ok(claimed)
+    }
+
+    async fn fail_row(&self, import_id: Uuid, row_number: i32, error: String) -> Result<JobImportRow> {
+        let mut conn = self.pool.get()?;
+
+        let row: JobImportRow = tokio::task::spawn_blocking(move || {
+            conn.transaction(|conn| {
+                let row = diesel::update(
+                    job_import_rows::table
+                        .filter(job_import_rows::import_id.eq(import_id))
+                        .filter(job_import_rows::row_number.eq(row_number))
+                        .filter(job_import_rows::job_id.is_not_null())
+                )
+                    .set((
+                        job_import_rows::job_id.eq(None::<Uuid>),
+                        job_import_rows::error.eq(Some(error)),
+                    ))
+                    .get_result::<JobImportRow>(conn)
+                    .optional()?;
+
+                if row.is_some() {
+                    diesel::update(job_imports::table.find(import_id))
+                        .set((
+                            job_imports::submitted_rows.eq(job_imports::submitted_rows - 1),
+                            job_imports::failed_rows.eq(job_imports::failed_rows + 1),
+                            job_imports::updated_at.eq(Utc::now()),
+                        ))
+                        .execute(conn)?;
+                }
+
+                Ok::<_, diesel::result::Error>(row)
+            })
+        }).await??
+            .ok_or_else(|| anyhow!("Claimed row {} not found in job import {}", row_number, import_id))?;
+
+        Ok(row)
+    }
+
+    async fn finish(&self, id: Uuid, status: ImportStatus) -> Result<JobImport> {
+        let mut conn = self.pool.get()?;
+
+        let import: JobImport = tokio::task::spawn_blocking(move || {
+            let now = Utc::now();
+            diesel::update(job_imports::table.find(id))
+                .set((
+                    job_imports::status.eq(status.as_str()),
+                    job_imports::updated_at.eq(now),
+                    job_imports::completed_at.eq(now),
+                ))
+                .get_result::<JobImport>(&mut conn)
+                .optional()
+        }).await??
+            .ok_or_else(|| anyhow!("Job import not found with ID: {}", id))?;
+
+        Ok(import)
+    }
+
+    async fn find_rows(&self, import_id: Uuid) -> Result<Vec<(JobImportRow, Option<String>)>> {
+        let mut conn = self.pool.get()?;
+
+        let rows: Vec<(JobImportRow, Option<String>)> = tokio::task::spawn_blocking(move || {
+            job_import_rows::table
+                .left_join(jobs::table.on(jobs::id.nullable().eq(job_import_rows::job_id)))
+                .filter(job_import_rows::import_id.eq(import_id))
+                .order(job_import_rows::row_number.asc())
+                .select((JobImportRow::as_select(), jobs::status.nullable()))
+                .load::<(JobImportRow, Option<String>)>(&mut conn)
+        }).await??;
+
+        Ok(rows)
+    }
+}
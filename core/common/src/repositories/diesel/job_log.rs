@@ -0,0 +1,97 @@
+use async_trait::async_trait;
+use chrono::Utc;
+use diesel::dsl::max;
+use diesel::prelude::*;
+use crate::database::TenantPool;
+use uuid::Uuid;
+use anyhow::Result;
+
+use crate::models::job_log::{JobLog, LogLevel, NewJobLog};
+use crate::repositories::JobLogRepository;
+use crate::diesel_schema::job_logs;
+
+/// Diesel implementation of the JobLogRepository
+pub struct DieselJobLogRepository {
+    pool: TenantPool,
+}
+
+impl DieselJobLogRepository {
+    /// Create a new DieselJobLogRepository with the given connection pool
+    pub fn new(pool: impl Into<TenantPool>) -> Self {
+        Self { pool: pool.into() }
+    }
+}
+
+#[async_trait]
+impl JobLogRepository for DieselJobLogRepository {
+    async fn append(&self, lines: Vec<NewJobLog>) -> Result<usize> {
+        if lines.is_empty() {
+            return Ok(0);
+        }
+
+        let mut conn = self.pool.get()?;
+
+        let count = tokio::task::spawn_blocking(move || {
+            diesel::insert_into(job_logs::table)
+                .values(&lines)
+                .execute(&mut conn)
+        }).await??;
+
+        Ok(count)
+    }
+
+    async fn append_event(&self, job_id: Uuid, level: LogLevel, message: String, fields: Option<serde_json::Value>) -> Result<JobLog> {
+        let mut conn = self.pool.get()?;
+
+        let line = tokio::task::spawn_blocking(move || {
+            conn.transaction(|conn| {
+                let last_line: Option<i32> = job_logs::table
+                    .filter(job_logs::job_id.eq(job_id))
+                    .select(max(job_logs::line_number))
+                    .first(conn)?;
+
+                diesel::insert_into(job_logs::table)
+                    .values(NewJobLog {
+                        id: Uuid::new_v4(),
+                        job_id,
+                        line_number: last_line.unwrap_or(0) + 1,
+                        level: level.as_str().to_string(),
+                        message,
+                        fields,
+                        created_at: Some(Utc::now()),
+                    })
+                    .get_result::<JobLog>(conn)
+            })
+        }).await??;
+
+        Ok(line)
+    }
+
+    async fn find_by_job_id(&self, job_id: Uuid, limit: i64, offset: i64) -> Result<Vec<JobLog>> {
+        let mut conn = self.pool.get()?;
+
+        let lines: Vec<JobLog> = tokio::task::spawn_blocking(move || {
+            job_logs::table
+                .filter(job_logs::job_id.eq(job_id))
+                .order(job_logs::line_number.asc())
+                .limit(limit)
+                .offset(offset)
+                .load::<JobLog>(&mut conn)
+        }).await??;
+
+        Ok(lines)
+    }
+
+    async fn count_by_job_id(&self, job_id: Uuid) -> Result<i64> {
+        let mut conn = self.pool.get()?;
+
+        let count: i64 = tokio::task::spawn_blocking(move || {
+            job_logs::table
+                .filter(job_logs::job_id.eq(job_id))
+                .count()
+                .get_result(&mut conn)
+        }).await??;
+
+        Ok(count)
+    }
+}
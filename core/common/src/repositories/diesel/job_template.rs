@@ -0,0 +1,78 @@
+use async_trait::async_trait;
+use diesel::prelude::*;
+use crate::database::TenantPool;
+use uuid::Uuid;
+use anyhow::{Result, anyhow};
+
+use crate::models::job_template::{JobTemplate, NewJobTemplate};
+use crate::repositories::JobTemplateRepository;
+use crate::diesel_schema::job_templates;
+
+/// Diesel implementation of the JobTemplateRepository
+pub struct DieselJobTemplateRepository {
+    pool: TenantPool,
+}
+
+impl DieselJobTemplateRepository {
+    /// Create a new DieselJobTemplateRepository with the given connection pool
+    pub fn new(pool: impl Into<TenantPool>) -> Self {
+        Self { pool: pool.into() }
+    }
+}
+
+#[async_trait]
+impl JobTemplateRepository for DieselJobTemplateRepository {
+    async fn create(&self, template: NewJobTemplate) -> Result<JobTemplate> {
+        let mut conn = self.pool.get()?;
+
+        let template: JobTemplate = tokio::task::spawn_blocking(move || {
+            diesel::insert_into(job_templates::table)
+                .values(&template)
+                .get_result::<JobTemplate>(&mut conn)
+        }).await??;
+
+        Ok(template)
+    }
+
+    async fn find_by_id(&self, id: Uuid) -> Result<JobTemplate> {
+        let mut conn = self.pool.get()?;
+
+        let template: JobTemplate = tokio::task::spawn_blocking(move || {
+            job_templates::table
+                .find(id)
+                .first(&mut conn)
+                .optional()
+        }).await??
+            .ok_or_else(|| anyhow!("Job template not found with ID: {}", id))?;
+
+        Ok(template)
+    }
+
+    async fn find_by_customer_id(&self, customer_id: Uuid) -> Result<Vec<JobTemplate>> {
+        let mut conn = self.pool.get()?;
+
+        let templates: Vec<JobTemplate> = tokio::task::spawn_blocking(move || {
+            job_templates::table
+                .filter(job_templates::customer_id.eq(customer_id))
+                .order(job_templates::name.asc())
+                .load::<JobTemplate>(&mut conn)
+        }).await??;
+
+        Ok(templates)
+    }
+
+    async fn delete(&self, id: Uuid) -> Result<()> {
+        let mut conn = self.pool.get()?;
+
+        let count = tokio::task::spawn_blocking(move || {
+            diesel::delete(job_templates::table.find(id))
+                .execute(&mut conn)
+        }).await??;
+
+        if count == 0 {
+            return Err(anyhow!("Job template not found with ID: {}", id));
+        }
+
+        Ok(())
+    }
+}
@@ -2,28 +2,29 @@ use async_trait::async_trait;
 use diesel::prelude::*;
 use uuid::Uuid;
 
-use crate::database::{PgPool, get_connection};
+use crate::database::TenantPool;
 use crate::diesel_schema::job_types;
 use crate::errors::Error;
-use crate::models::job_type::{JobType, NewJobType};
+use crate::models::job_type::{CatalogVisibility, JobType, NewJobType};
 use crate::repositories::JobTypeRepository;
+use crate::repositories::job_type::CatalogFilter;
 use crate::Result;
 
 /// Diesel-backed implementation of JobTypeRepository
 pub struct DieselJobTypeRepository {
-    pool: PgPool,
+    pool: TenantPool,
 }
 
 impl DieselJobTypeRepository {
-    pub fn new(pool: PgPool) -> Self {
-        Self { pool }
+    pub fn new(pool: impl Into<TenantPool>) -> Self {
+        Self { pool: pool.into() }
     }
 }
 
 #[async_trait]
 impl JobTypeRepository for DieselJobTypeRepository {
     async fn create(&self, new_job_type: NewJobType) -> Result<JobType> {
-        let mut conn = get_connection(&self.pool)?;
+        let mut conn = self.pool.get()?;
         
         // Execute the insert and return the new record
         diesel::insert_into(job_types::table)
@@ -34,7 +35,7 @@ impl JobTypeRepository for DieselJobTypeRepository {
     }
 
     async fn find_by_id(&self, id: Uuid) -> Result<JobType> {
-        let mut conn = get_connection(&self.pool)?;
+        let mut conn = self.pool.get()?;
         
         job_types::table
             .find(id)
@@ -47,7 +48,7 @@ impl JobTypeRepository for DieselJobTypeRepository {
     }
 
     async fn update(&self, job_type: JobType) -> Result<JobType> {
-        let mut conn = get_connection(&self.pool)?;
+        let mut conn = self.pool.get()?;
         
         // First check if the entity exists
         let _ = job_types::table
@@ -69,6 +70,18 @@ impl JobTypeRepository for DieselJobTypeRepository {
                 job_types::processor_type.eq(job_type.processor_type.as_str()),
                 job_types::standard_cost_cents.eq(job_type.standard_cost_cents),
                 job_types::enabled.eq(job_type.enabled),
+                job_types::category.eq(job_type.category),
+                job_types::icon.eq(job_type.icon),
+                job_types::documentation_url.eq(job_type.documentation_url),
+                job_types::sample_input.eq(job_type.sample_input),
+                job_types::sample_output.eq(job_type.sample_output),
+                job_types::visibility.eq(job_type.visibility.as_str()),
+                job_types::redaction_rules.eq(job_type.redaction_rules),
+                job_types::unredacted_retention_hours.eq(job_type.unredacted_retention_hours),
+                job_types::provider_id.eq(job_type.provider_id),
+                job_types::default_priority.eq(job_type.default_priority),
+                job_types::webhook_template.eq(job_type.webhook_template),
+                job_types::response_policy.eq(job_type.response_policy),
                 job_types::updated_at.eq(diesel::dsl::now),
             ))
             .returning(JobType::as_select())
@@ -77,7 +90,7 @@ impl JobTypeRepository for DieselJobTypeRepository {
     }
 
     async fn list_all(&self) -> Result<Vec<JobType>> {
-        let mut conn = get_connection(&self.pool)?;
+        let mut conn = self.pool.get()?;
         
         job_types::table
             .select(JobType::as_select())
@@ -86,7 +99,7 @@ impl JobTypeRepository for DieselJobTypeRepository {
     }
 
     async fn list_enabled(&self) -> Result<Vec<JobType>> {
-        let mut conn = get_connection(&self.pool)?;
+        let mut conn = self.pool.get()?;
         
         job_types::table
             .filter(job_types::enabled.eq(true))
@@ -94,4 +107,56 @@ impl JobTypeRepository for DieselJobTypeRepository {
             .load(&mut conn)
             .map_err(|e| Error::Database(e))
     }
+
+    async fn search_catalog(&self, filter: CatalogFilter) -> Result<Vec<JobType>> {
+        let mut conn = self.pool.get()?;
+        
+        let visibilities: Vec<&str> = filter.visibilities.iter().map(CatalogVisibility::as_str).collect();
+        let mut query = job_types::table
+            .filter(job_types::enabled.eq(true))
+            .filter(job_types::visibility.eq_any(visibilities))
+            .into_boxed();
+        
+        if let Some(category) = filter.category {
+            query = query.filter(job_types::category.ilike(escape_like(&category)));
+        }
+        if let Some(search) = filter.search {
+            let pattern = format!("%{}%", escape_like(&search));
+            query = query.filter(
+                job_types::name.ilike(pattern.clone())
+                    .or(job_types::description.ilike(pattern.clone()))
+                    .or(job_types::category.ilike(pattern))
+            );
+        }
+        
+        query
+            .order((job_types::category.asc(), job_types::name.asc()))
+            .select(JobType::as_select())
+            .load(&mut conn)
+            .map_err(Error::Database)
+    }
+
+    async fn list_catalog_categories(&self, visibilities: Vec<CatalogVisibility>) -> Result<Vec<(String, i64)>> {
+        let mut conn = self.pool.get()?;
+        
+        let visibilities: Vec<&str> = visibilities.iter().map(CatalogVisibility::as_str).collect();
+        let categories = job_types::table
+            .filter(job_types::enabled.eq(true))
+            .filter(job_types::visibility.eq_any(visibilities))
+            .group_by(job_types::category)
+            .select((job_types::category, diesel::dsl::count_star()))
+            .order(job_types::category.asc())
+            .load::<(Option<String>, i64)>(&mut conn)
+            .map_err(Error::Database)?;
+        
+        // Job types without a category are not browsable by category
+        Ok(categories.into_iter()
+            .filter_map(|(category, count)| category.map(|category| (category, count)))
+            .collect())
+    }
+}
+
+/// Escape the LIKE wildcards in user input so it matches literally
+pub(crate) fn escape_like(value: &str) -> String {
+    value.replace('\\', "\\\\").replace('%', "\\%").replace('_', "\\_")
 }
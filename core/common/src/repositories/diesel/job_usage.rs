@@ -0,0 +1,79 @@
+use async_trait::async_trait;
+use chrono::{DateTime, Utc};
+use diesel::prelude::*;
+use crate::database::TenantPool;
+use anyhow::Result;
+use uuid::Uuid;
+
+use crate::models::job_usage::{self, JobUsage, NewJobUsage, UsageSummary};
+use crate::repositories::JobUsageRepository;
+use crate::diesel_schema::job_usage as job_usage_table;
+
+/// Diesel implementation of the JobUsageRepository
+pub struct DieselJobUsageRepository {
+    pool: TenantPool,
+}
+
+impl DieselJobUsageRepository {
+    /// Create a new DieselJobUsageRepository with the given connection pool
+    pub fn new(pool: impl Into<TenantPool>) -> Self {
+        Self { pool: pool.into() }
+    }
+}
+
+#[async_trait]
+impl JobUsageRepository for DieselJobUsageRepository {
+    async fn record(&self, usage: NewJobUsage) -> Result<JobUsage> {
+        let mut conn = self.pool.get()?;
+
+        let usage = tokio::task::spawn_blocking(move || {
+            diesel::insert_into(job_usage_table::table)
+                .values(&usage)
+                .on_conflict(job_usage_table::job_id)
+                .do_update()
+                .set((
+                    job_usage_table::attempt_id.eq(usage.attempt_id),
+                    job_usage_table::runner_id.eq(usage.runner_id),
+                    job_usage_table::wall_time_ms.eq(usage.wall_time_ms),
+                    job_usage_table::cpu_time_ms.eq(usage.cpu_time_ms),
+                    job_usage_table::peak_memory_bytes.eq(usage.peak_memory_bytes),
+                    job_usage_table::bytes_sent.eq(usage.bytes_sent),
+                    job_usage_table::bytes_received.eq(usage.bytes_received),
+                    job_usage_table::recorded_at.eq(Utc::now()),
+                ))
+                .get_result::<JobUsage>(&mut conn)
+        }).await??;
+
+        Ok(usage)
+    }
+
+    async fn find_by_job(&self, job_id: Uuid) -> Result<Option<JobUsage>> {
+        let mut conn = self.pool.get()?;
+
+        let usage = tokio::task::spawn_blocking(move || {
+            job_usage_table::table
+                .find(job_id)
+                .first::<JobUsage>(&mut conn)
+                .optional()
+        }).await??;
+
+        Ok(usage)
+    }
+
+    async fn summarize(&self, from: DateTime<Utc>, to: DateTime<Utc>, customer_id: Option<Uuid>) -> Result<Vec<UsageSummary>> {
+        let mut conn = self.pool.get()?;
+
+        let usages = tokio::task::spawn_blocking(move || {
+            let mut query = job_usage_table::table
+                .filter(job_usage_table::recorded_at.ge(from))
+                .filter(job_usage_table::recorded_at.lt(to))
+                .into_boxed();
+            if let Some(customer_id) = customer_id {
+                query = query.filter(job_usage_table::customer_id.eq(customer_id));
+            }
+            query.load::<JobUsage>(&mut conn)
+        }).await??;
+
+        Ok(job_usage::summarize(&usages))
+    }
+}
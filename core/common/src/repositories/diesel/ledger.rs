@@ -0,0 +1,117 @@
+use async_trait::async_trait;
+use diesel::dsl::sql;
+use diesel::prelude::*;
+use diesel::sql_types::BigInt;
+use crate::database::TenantPool;
+use anyhow::{anyhow, Result};
+use uuid::Uuid;
+
+use crate::models::ledger::{is_balanced, AccountBalance, LedgerEntry, LedgerPosting, NewLedgerAccount, NewLedgerEntry};
+use crate::repositories::LedgerRepository;
+use crate::diesel_schema::{ledger_accounts, ledger_entries};
+
+/// Find the account of a kind and owner, opening it on its first posting
+fn account_id(conn: &mut PgConnection, posting: &LedgerPosting) -> QueryResult<Uuid> {
+    let kind = posting.kind.as_str();
+    let find = |conn: &mut PgConnection| {
+        let query = ledger_accounts::table
+            .filter(ledger_accounts::kind.eq(kind))
+            .select(ledger_accounts::id)
+            .into_boxed();
+        match posting.owner_id {
+            Some(owner_id) => query.filter(ledger_accounts::owner_id.eq(owner_id)),
+            None => query.filter(ledger_accounts::owner_id.is_null()),
+        }
+        .first::<Uuid>(conn)
+        .optional()
+    };
+
+    if let Some(id) = find(conn)? {
+        return Ok(id);
+    }
+    // A concurrent posting may open the account first; then its account is used
+    diesel::insert_into(ledger_accounts::table)
+        .values(NewLedgerAccount::new(posting.kind, posting.owner_id))
+        .on_conflict_do_nothing()
+        .execute(conn)?;
+    find(conn)?.ok_or(diesel::result::Error::NotFound)
+}
+
+/// Post the entries of a wallet transaction, within the connection's transaction
+///
+/// Unbalanced postings are refused here; the database also checks every transaction's
+/// entries add up to zero when it commits.
+pub(crate) fn post_entries(
+    conn: &mut PgConnection,
+    transaction_id: Uuid,
+    job_id: Option<Uuid>,
+    postings: &[LedgerPosting],
+) -> Result<()> {
+    if !is_balanced(postings) {
+        return Err(anyhow!("Ledger entries of transaction {} do not balance", transaction_id));
+    }
+    for posting in postings {
+        let entry = NewLedgerEntry {
+            id: Uuid::new_v4(),
+            transaction_id,
+            account_id: account_id(conn, posting)?,
+            job_id,
+            amount_cents: posting.amount_cents,
+        };
+        diesel::insert_into(ledger_entries::table)
+            .values(&entry)
+            .execute(conn)?;
+    }
+    Ok(())
+}
+
+/// Diesel implementation of the LedgerRepository
+pub struct DieselLedgerRepository {
+    pool: TenantPool,
+}
+
+impl DieselLedgerRepository {
+    /// Create a new DieselLedgerRepository with the given connection pool
+    pub fn new(pool: impl Into<TenantPool>) -> Self {
+        Self { pool: pool.into() }
+    }
+}
+
+#[async_trait]
+impl LedgerRepository for DieselLedgerRepository {
+    async fn find_by_job(&self, job_id: Uuid) -> Result<Vec<LedgerEntry>> {
+        let mut conn = self.pool.get()?;
+
+        let entries = tokio::task::spawn_blocking(move || {
+            ledger_entries::table
+                .filter(ledger_entries::job_id.eq(job_id))
+                .order((ledger_entries::created_at.asc(), ledger_entries::id.asc()))
+                .select(LedgerEntry::as_select())
+                .load(&mut conn)
+        }).await??;
+
+        Ok(entries)
+    }
+
+    async fn balances(&self) -> Result<Vec<AccountBalance>> {
+        let mut conn = self.pool.get()?;
+
+        let balances = tokio::task::spawn_blocking(move || {
+            ledger_accounts::table
+                .left_join(ledger_entries::table)
+                .group_by((ledger_accounts::id, ledger_accounts::kind, ledger_accounts::owner_id))
+                .order((ledger_accounts::kind.asc(), ledger_accounts::owner_id.asc()))
+                .select((
+                    ledger_accounts::id,
+                    ledger_accounts::kind,
+                    ledger_accounts::owner_id,
+                    sql::<BigInt>("COALESCE(SUM(ledger_entries.amount_cents), 0)::BIGINT"),
+                ))
+                .load::<(Uuid, String, Option<Uuid>, i64)>(&mut conn)
+        }).await??;
+
+        Ok(balances.into_iter()
+            .map(|(account_id, kind, owner_id, balance_cents)| AccountBalance { account_id, kind, owner_id, balance_cents })
+            .collect())
+    }
+}
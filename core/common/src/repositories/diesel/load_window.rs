@@ -0,0 +1,140 @@
+use async_trait::async_trait;
+use diesel::prelude::*;
+use crate::database::TenantPool;
+use uuid::Uuid;
+use anyhow::{Result, anyhow};
+
+use crate::models::load_window::{LoadWindow, LoadWindowStatus, NewLoadWindow};
+use crate::repositories::LoadWindowRepository;
+use crate::diesel_schema::load_windows;
+
+/// Statuses of the windows still checked
+const OPEN_STATUSES: [&str; 3] = ["planned", "warming", "ready"];
+
+/// Diesel implementation of the LoadWindowRepository
+pub struct DieselLoadWindowRepository {
+    pool: TenantPool,
+}
+
+impl DieselLoadWindowRepository {
+    /// Create a new DieselLoadWindowRepository with the given connection pool
+    pub fn new(pool: impl Into<TenantPool>) -> Self {
+        Self { pool: pool.into() }
+    }
+}
+
+#[async_trait]
+impl LoadWindowRepository for DieselLoadWindowRepository {
+    async fn create(&self, window: NewLoadWindow) -> Result<LoadWindow> {
+        let mut conn = self.pool.get()?;
+
+        let window: LoadWindow = tokio::task::spawn_blocking(move || {
+            diesel::insert_into(load_windows::table)
+                .values(&window)
+                .returning(LoadWindow::as_select())
+                .get_result(&mut conn)
+        }).await??;
+
+        Ok(window)
+    }
+
+    async fn find_by_id(&self, id: Uuid) -> Result<LoadWindow> {
+        let mut conn = self.pool.get()?;
+
+        let window: LoadWindow = tokio::task::spawn_blocking(move || {
+            load_windows::table
+                .find(id)
+                .select(LoadWindow::as_select())
+                .first(&mut conn)
+                .optional()
+        }).await??
+            .ok_or_else(|| anyhow!("Load window not found with ID: {}", id))?;
+
+        Ok(window)
+    }
+
+    async fn list_all(&self) -> Result<Vec<LoadWindow>> {
+        let mut conn = self.pool.get()?;
+
+        let windows: Vec<LoadWindow> = tokio::task::spawn_blocking(move || {
+            load_windows::table
+                .order((load_windows::starts_at.desc(), load_windows::id.asc()))
+                .select(LoadWindow::as_select())
+                .load(&mut conn)
+        }).await??;
+
+        Ok(windows)
+    }
+
+    async fn list_open(&self) -> Result<Vec<LoadWindow>> {
+        let mut conn = self.pool.get()?;
+
+        let windows: Vec<LoadWindow> = tokio::task::spawn_blocking(move || {
+            load_windows::table
+                .filter(load_windows::status.eq_any(OPEN_STATUSES))
+                .order((load_windows::starts_at.asc(), load_windows::id.asc()))
+                .select(LoadWindow::as_select())
+                .load(&mut conn)
+        }).await??;
+
+        Ok(windows)
+    }
+
+    async fn record_check(&self, id: Uuid, status: LoadWindowStatus, active_runners: i32, notified: bool) -> Result<LoadWindow> {
+        let mut conn = self.pool.get()?;
+
+        let window: LoadWindow = tokio::task::spawn_blocking(move || {
+            conn.transaction(|conn| {
+                let current: Option<LoadWindow> = load_windows::table
+                    .find(id)
+                    .filter(load_windows::status.eq_any(OPEN_STATUSES))
+                    .select(LoadWindow::as_select())
+                    .for_update()
+                    .first(conn)
+                    .optional()?;
+                let Some(current) = current else {
+                    return Ok(None);
+                };
+
+                let now = chrono::Utc::now();
+                // A window released with its target reached was ready, if only at its start
+                let ready = matches!(status, LoadWindowStatus::Ready | LoadWindowStatus::Released)
+                    && active_runners >= current.target_runners;
+                diesel::update(load_windows::table.find(id))
+                    .set((
+                        load_windows::status.eq(status.as_str()),
+                        load_windows::active_runners.eq(active_runners),
+                        (notified && current.notified_at.is_none()).then_some(load_windows::notified_at.eq(now)),
+                        (ready && current.ready_at.is_none()).then_some(load_windows::ready_at.eq(now)),
+                        (status == LoadWindowStatus::Released).then_some(load_windows::released_at.eq(now)),
+                        load_windows::updated_at.eq(diesel::dsl::now),
+                    ))
+                    .returning(LoadWindow::as_select())
+                    .get_result(conn)
+                    .map(Some)
+            })
+        }).await??
+            .ok_or_else(|| anyhow!("No open load window with ID: {}", id))?;
+
+        Ok(window)
+    }
+
+    async fn cancel(&self, id: Uuid) -> Result<LoadWindow> {
+        let mut conn = self.pool.get()?;
+
+        let window: LoadWindow = tokio::task::spawn_blocking(move || {
+            diesel::update(load_windows::table.find(id))
+                .filter(load_windows::status.eq_any(OPEN_STATUSES))
+                .set((
+                    load_windows::status.eq(LoadWindowStatus::Cancelled.as_str()),
+                    load_windows::updated_at.eq(diesel::dsl::now),
+                ))
+                .returning(LoadWindow::as_select())
+                .get_result(&mut conn)
+                .optional()
+        }).await??
+            .ok_or_else(|| anyhow!("No open load window with ID: {}", id))?;
+
+        Ok(window)
+    }
+}
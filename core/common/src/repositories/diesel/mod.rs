@@ -1,3 +1,7 @@
+/// Rows per statement of a bulk insert, well below Postgres' limit of 65535 bind
+/// parameters per statement for the widest tables
+pub(crate) const BULK_INSERT_ROWS: usize = 1000;
+
 // Export diesel-backed repository implementations
 pub mod job_type;
 pub mod job;
@@ -7,6 +11,36 @@ pub mod reseller;
 pub mod project;
 pub mod runner;
 pub mod wallet_transaction;
+pub mod webhook;
+pub mod notification;
+pub mod notification_preference;
+pub mod job_log;
+pub mod job_attempt;
+pub mod job_template;
+pub mod submission_window;
+pub mod pipeline;
+pub mod wallet_adjustment;
+pub mod audit_log;
+pub mod billing_period;
+pub mod feature_flag;
+pub mod unredacted_output;
+pub mod spending_alert;
+pub mod partition;
+pub mod request_nonce;
+pub mod provider;
+pub mod search;
+pub mod job_import;
+pub mod fixture;
+pub mod billing_export;
+pub mod job_event;
+pub mod job_usage;
+pub mod load_window;
+pub mod submission;
+pub mod dependency;
+pub mod replay_case;
+pub mod purge;
+pub mod job_artifact;
+pub mod ledger;
 
 // Export repository implementations for public use
 pub use job_type::DieselJobTypeRepository;
@@ -17,3 +51,33 @@ pub use reseller::DieselResellerRepository;
 pub use project::DieselProjectRepository;
 pub use runner::DieselRunnerRepository;
 pub use wallet_transaction::DieselWalletTransactionRepository;
+pub use webhook::DieselCustomerWebhookRepository;
+pub use notification::DieselNotificationDeliveryRepository;
+pub use notification_preference::DieselNotificationPreferenceRepository;
+pub use job_log::DieselJobLogRepository;
+pub use job_attempt::DieselJobAttemptRepository;
+pub use job_template::DieselJobTemplateRepository;
+pub use submission_window::DieselSubmissionWindowRepository;
+pub use pipeline::DieselPipelineRepository;
+pub use wallet_adjustment::DieselWalletAdjustmentRepository;
+pub use audit_log::DieselAuditLogRepository;
+pub use billing_period::DieselBillingPeriodRepository;
+pub use feature_flag::DieselFeatureFlagRepository;
+pub use unredacted_output::DieselUnredactedOutputRepository;
+pub use spending_alert::DieselSpendingAlertRepository;
+pub use partition::DieselPartitionRepository;
+pub use request_nonce::DieselRequestNonceRepository;
+pub use provider::DieselProviderRepository;
+pub use search::DieselSearchRepository;
+pub use job_import::DieselJobImportRepository;
+pub use fixture::DieselFixtureRepository;
+pub use billing_export::DieselBillingExportRepository;
+pub use job_event::DieselJobEventRepository;
+pub use job_usage::DieselJobUsageRepository;
+pub use load_window::DieselLoadWindowRepository;
+pub use submission::DieselSubmissionRepository;
+pub use dependency::DieselDependencyRepository;
+pub use replay_case::DieselReplayCaseRepository;
+pub use purge::DieselPurgeRepository;
+pub use job_artifact::DieselJobArtifactRepository;
+pub use ledger::DieselLedgerRepository;
@@ -0,0 +1,118 @@
+use async_trait::async_trait;
+use diesel::prelude::*;
+use crate::database::TenantPool;
+use uuid::Uuid;
+use anyhow::{Result, anyhow};
+use chrono::{DateTime, Utc};
+
+use crate::models::notification::{DeliveryAttempt, DeliveryChannel, DeliveryStatus, NewNotificationDelivery, NotificationDelivery};
+use crate::repositories::NotificationDeliveryRepository;
+use crate::diesel_schema::notification_deliveries;
+
+/// Diesel implementation of the NotificationDeliveryRepository
+pub struct DieselNotificationDeliveryRepository {
+    pool: TenantPool,
+}
+
+impl DieselNotificationDeliveryRepository {
+    /// Create a new DieselNotificationDeliveryRepository with the given connection pool
+    pub fn new(pool: impl Into<TenantPool>) -> Self {
+        Self { pool: pool.into() }
+    }
+}
+
+#[async_trait]
+impl NotificationDeliveryRepository for DieselNotificationDeliveryRepository {
+    async fn create(&self, delivery: NewNotificationDelivery) -> Result<NotificationDelivery> {
+        let mut conn = self.pool.get()?;
+
+        let delivery: NotificationDelivery = tokio::task::spawn_blocking(move || {
+            diesel::insert_into(notification_deliveries::table)
+                .values(&delivery)
+                .get_result::<NotificationDelivery>(&mut conn)
+        }).await??;
+
+        Ok(delivery)
+    }
+
+    async fn find_by_id(&self, id: Uuid) -> Result<NotificationDelivery> {
+        let mut conn = self.pool.get()?;
+
+        let delivery: NotificationDelivery = tokio::task::spawn_blocking(move || {
+            notification_deliveries::table
+                .find(id)
+                .first(&mut conn)
+                .optional()
+        }).await??
+            .ok_or_else(|| anyhow!("Notification delivery not found with ID: {}", id))?;
+
+        Ok(delivery)
+    }
+
+    async fn find_failed_by_customer(&self, customer_id: Uuid, limit: i64, offset: i64) -> Result<Vec<NotificationDelivery>> {
+        let mut conn = self.pool.get()?;
+
+        let deliveries: Vec<NotificationDelivery> = tokio::task::spawn_blocking(move || {
+            notification_deliveries::table
+                .filter(notification_deliveries::customer_id.eq(customer_id))
+                .filter(notification_deliveries::status.eq(DeliveryStatus::Failed.as_str()))
+                .order(notification_deliveries::created_at.desc())
+                .limit(limit)
+                .offset(offset)
+                .load::<NotificationDelivery>(&mut conn)
+        }).await??;
+
+        Ok(deliveries)
+    }
+
+    async fn find_due_retries(&self, channel: DeliveryChannel, now: DateTime<Utc>, limit: i64) -> Result<Vec<NotificationDelivery>> {
+        let mut conn = self.pool.get()?;
+
+        let deliveries: Vec<NotificationDelivery> = tokio::task::spawn_blocking(move || {
+            notification_deliveries::table
+                .filter(notification_deliveries::channel.eq(channel.as_str()))
+                .filter(notification_deliveries::status.eq(DeliveryStatus::Failed.as_str()))
+                .filter(notification_deliveries::next_retry_at.le(now))
+                .order(notification_deliveries::next_retry_at.asc())
+                .limit(limit)
+                .load::<NotificationDelivery>(&mut conn)
+        }).await??;
+
+        Ok(deliveries)
+    }
+
+    async fn find_queued(&self, channel: DeliveryChannel, limit: i64) -> Result<Vec<NotificationDelivery>> {
+        let mut conn = self.pool.get()?;
+
+        let deliveries: Vec<NotificationDelivery> = tokio::task::spawn_blocking(move || {
+            notification_deliveries::table
+                .filter(notification_deliveries::channel.eq(channel.as_str()))
+                .filter(notification_deliveries::status.eq(DeliveryStatus::Queued.as_str()))
+                .order(notification_deliveries::created_at.asc())
+                .limit(limit)
+                .load::<NotificationDelivery>(&mut conn)
+        }).await??;
+
+        Ok(deliveries)
+    }
+
+    async fn record_attempt(&self, id: Uuid, attempt: DeliveryAttempt) -> Result<NotificationDelivery> {
+        let mut conn = self.pool.get()?;
+
+        let delivery = tokio::task::spawn_blocking(move || {
+            diesel::update(notification_deliveries::table.find(id))
+                .set((
+                    notification_deliveries::status.eq(attempt.status.as_str()),
+                    notification_deliveries::status_code.eq(attempt.status_code),
+                    notification_deliveries::latency_ms.eq(attempt.latency_ms),
+                    notification_deliveries::last_error.eq(attempt.last_error),
+                    notification_deliveries::retry_count.eq(attempt.retry_count),
+                    notification_deliveries::next_retry_at.eq(attempt.next_retry_at),
+                    notification_deliveries::updated_at.eq(Utc::now()),
+                ))
+                .get_result::<NotificationDelivery>(&mut conn)
+        }).await??;
+
+        Ok(delivery)
+    }
+}
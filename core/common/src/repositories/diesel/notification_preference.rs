@@ -0,0 +1,77 @@
+use async_trait::async_trait;
+use diesel::prelude::*;
+use crate::database::TenantPool;
+use uuid::Uuid;
+use anyhow::Result;
+use chrono::Utc;
+
+use crate::models::notification::{DeliveryChannel, NewNotificationPreference, NotificationPreference};
+use crate::models::webhook::WebhookEventType;
+use crate::repositories::NotificationPreferenceRepository;
+use crate::diesel_schema::notification_preferences;
+
+/// Diesel implementation of the NotificationPreferenceRepository
+pub struct DieselNotificationPreferenceRepository {
+    pool: TenantPool,
+}
+
+impl DieselNotificationPreferenceRepository {
+    /// Create a new DieselNotificationPreferenceRepository with the given connection pool
+    pub fn new(pool: impl Into<TenantPool>) -> Self {
+        Self { pool: pool.into() }
+    }
+}
+
+#[async_trait]
+impl NotificationPreferenceRepository for DieselNotificationPreferenceRepository {
+    async fn find_by_customer(&self, customer_id: Uuid) -> Result<Vec<NotificationPreference>> {
+        let mut conn = self.pool.get()?;
+
+        let preferences = tokio::task::spawn_blocking(move || {
+            notification_preferences::table
+                .filter(notification_preferences::customer_id.eq(customer_id))
+                .order((notification_preferences::event_type.asc(), notification_preferences::channel.asc()))
+                .load::<NotificationPreference>(&mut conn)
+        }).await??;
+
+        Ok(preferences)
+    }
+
+    async fn upsert(&self, preference: NewNotificationPreference) -> Result<NotificationPreference> {
+        let mut conn = self.pool.get()?;
+
+        let preference = tokio::task::spawn_blocking(move || {
+            diesel::insert_into(notification_preferences::table)
+                .values(&preference)
+                .on_conflict((
+                    notification_preferences::customer_id,
+                    notification_preferences::event_type,
+                    notification_preferences::channel,
+                ))
+                .do_update()
+                .set((
+                    notification_preferences::mode.eq(&preference.mode),
+                    notification_preferences::updated_at.eq(Utc::now()),
+                ))
+                .get_result::<NotificationPreference>(&mut conn)
+        }).await??;
+
+        Ok(preference)
+    }
+
+    async fn delete(&self, customer_id: Uuid, event_type: WebhookEventType, channel: DeliveryChannel) -> Result<bool> {
+        let mut conn = self.pool.get()?;
+
+        let deleted = tokio::task::spawn_blocking(move || {
+            diesel::delete(
+                notification_preferences::table
+                    .filter(notification_preferences::customer_id.eq(customer_id))
+                    .filter(notification_preferences::event_type.eq(event_type.as_str()))
+                    .filter(notification_preferences::channel.eq(channel.as_str())),
+            )
+            .execute(&mut conn)
+        }).await??;
+
+        Ok(deleted > 0)
+    }
+}
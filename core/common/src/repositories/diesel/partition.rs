@@ -0,0 +1,162 @@
+use async_trait::async_trait;
+use chrono::NaiveDate;
+use diesel::prelude::*;
+use diesel::sql_types::{BigInt, Bool, Date, Text, Timestamptz};
+use crate::database::TenantPool;
+use anyhow::Result;
+
+use crate::models::partition::{next_month, PartitionedTable};
+use crate::repositories::PartitionRepository;
+
+/// Tables whose rows refer to a job, deleted with the job's partition
+const JOB_CHILD_TABLES: [&str; 3] = ["job_logs", "job_attempts", "job_unredacted_outputs"];
+
+/// Diesel implementation of the PartitionRepository
+pub struct DieselPartitionRepository {
+    pool: TenantPool,
+}
+
+impl DieselPartitionRepository {
+    /// Create a new DieselPartitionRepository with the given connection pool
+    pub fn new(pool: impl Into<TenantPool>) -> Self {
+        Self { pool: pool.into() }
+    }
+}
+
+#[derive(QueryableByName)]
+struct PartitionName {
+    #[diesel(sql_type = Text)]
+    name: String,
+}
+
+#[derive(QueryableByName)]
+struct PartitionMonth {
+    #[diesel(sql_type = Date)]
+    month: NaiveDate,
+}
+
+#[derive(QueryableByName)]
+struct RowCount {
+    #[diesel(sql_type = BigInt)]
+    count: i64,
+}
+
+#[derive(QueryableByName)]
+struct Exists {
+    #[diesel(sql_type = Bool)]
+    exists: bool,
+}
+
+/// Whether a table exists in the `public` schema
+fn table_exists(conn: &mut PgConnection, name: &str) -> QueryResult<bool> {
+    diesel::sql_query("SELECT to_regclass($1) IS NOT NULL AS exists")
+        .bind::<Text, _>(format!("public.{}", name))
+        .get_result::<Exists>(conn)
+        .map(|row| row.exists)
+}
+
+#[async_trait]
+impl PartitionRepository for DieselPartitionRepository {
+    async fn list_months(&self, table: PartitionedTable) -> Result<Vec<NaiveDate>> {
+        let mut conn = self.pool.get()?;
+
+        let names = tokio::task::spawn_blocking(move || {
+            diesel::sql_query(
+                "SELECT c.relname::text AS name FROM pg_inherits i \
+                 JOIN pg_class c ON c.oid = i.inhrelid \
+                 WHERE i.inhparent = to_regclass($1)",
+            )
+            .bind::<Text, _>(format!("public.{}", table.as_str()))
+            .load::<PartitionName>(&mut conn)
+        }).await??;
+
+        let mut months: Vec<NaiveDate> = names.iter()
+            .filter_map(|partition| table.parse_partition_name(&partition.name))
+            .collect();
+        months.sort();
+        Ok(months)
+    }
+
+    async fn unpartitioned_months(&self, table: PartitionedTable) -> Result<Vec<NaiveDate>> {
+        let mut conn = self.pool.get()?;
+
+        let months = tokio::task::spawn_blocking(move || {
+            diesel::sql_query(format!(
+                "SELECT DISTINCT date_trunc('month', created_at AT TIME ZONE 'UTC')::date AS month FROM public.{} ORDER BY month",
+                table.default_partition()
+            ))
+            .load::<PartitionMonth>(&mut conn)
+        }).await??;
+
+        Ok(months.into_iter().map(|row| row.month).collect())
+    }
+
+    async fn create_month(&self, table: PartitionedTable, month: NaiveDate) -> Result<usize> {
+        let mut conn = self.pool.get()?;
+
+        let moved = tokio::task::spawn_blocking(move || {
+            let partition = table.partition_name(month);
+            let default = table.default_partition();
+            let end = next_month(month);
+            conn.transaction(|conn| {
+                if table_exists(conn, &partition)? {
+                    return Ok(0);
+                }
+                // Keep rows of the month from being written to the default partition
+                // while they are moved, which would make attaching the partition fail
+                diesel::sql_query(format!("LOCK TABLE public.{} IN EXCLUSIVE MODE", default)).execute(conn)?;
+                diesel::sql_query(format!(
+                    "CREATE TABLE public.{} (LIKE public.{} INCLUDING DEFAULTS INCLUDING CONSTRAINTS)",
+                    partition, table.as_str()
+                )).execute(conn)?;
+                let moved = diesel::sql_query(format!(
+                    "WITH moved AS (DELETE FROM public.{} WHERE created_at >= $1 AND created_at < $2 RETURNING *) \
+                     INSERT INTO public.{} SELECT * FROM moved",
+                    default, partition
+                ))
+                .bind::<Timestamptz, _>(month.and_hms_opt(0, 0, 0).unwrap_or_default().and_utc())
+                .bind::<Timestamptz, _>(end.and_hms_opt(0, 0, 0).unwrap_or_default().and_utc())
+                .execute(conn)?;
+                // Indexes and foreign keys of the table are created on the partition here;
+                // months are bounded in UTC whatever the session's time zone
+                diesel::sql_query(format!(
+                    "ALTER TABLE public.{} ATTACH PARTITION public.{} FOR VALUES FROM ('{} 00:00:00+00') TO ('{} 00:00:00+00')",
+                    table.as_str(), partition, month.format("%Y-%m-%d"), end.format("%Y-%m-%d")
+                )).execute(conn)?;
+                Ok::<_, diesel::result::Error>(moved)
+            })
+        }).await??;
+
+        Ok(moved)
+    }
+
+    async fn drop_month(&self, table: PartitionedTable, month: NaiveDate) -> Result<usize> {
+        let mut conn = self.pool.get()?;
+
+        let dropped = tokio::task::spawn_blocking(move || {
+            let partition = table.partition_name(month);
+            conn.transaction(|conn| {
+                if !table_exists(conn, &partition)? {
+                    return Ok(0);
+                }
+                let rows = diesel::sql_query(format!("SELECT count(*) AS count FROM public.{}", partition))
+                    .get_result::<RowCount>(conn)?
+                    .count;
+                // Wallet transactions keep the IDs of dropped jobs, so the ledgers of
+                // closed billing periods do not change
+                if table == PartitionedTable::Jobs {
+                    for child in JOB_CHILD_TABLES {
+                        diesel::sql_query(format!(
+                            "DELETE FROM public.{} WHERE job_id IN (SELECT id FROM public.{})",
+                            child, partition
+                        )).execute(conn)?;
+                    }
+                }
+                diesel::sql_query(format!("DROP TABLE public.{}", partition)).execute(conn)?;
+                Ok::<_, diesel::result::Error>(rows as usize)
+            })
+        }).await??;
+
+        Ok(dropped)
+    }
+}
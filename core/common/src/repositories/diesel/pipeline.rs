@@ -0,0 +1,237 @@
+use async_trait::async_trait;
+use chrono::Utc;
+use diesel::prelude::*;
+use crate::database::TenantPool;
+use uuid::Uuid;
+use anyhow::{Result, anyhow};
+
+use crate::models::pipeline::{
+    NewPipeline, NewPipelineRun, NewPipelineRunStep, Pipeline, PipelineRun, PipelineRunStatus, PipelineRunStep, StepStatus,
+};
+use crate::repositories::PipelineRepository;
+use crate::diesel_schema::{pipelines, pipeline_runs, pipeline_run_steps};
+
+/// Diesel implementation of the PipelineRepository
+pub struct DieselPipelineRepository {
+    pool: TenantPool,
+}
+
+impl DieselPipelineRepository {
+    /// Create a new DieselPipelineRepository with the given connection pool
+    pub fn new(pool: impl Into<TenantPool>) -> Self {
+        Self { pool: pool.into() }
+    }
+}
+
+#[async_trait]
+impl PipelineRepository for DieselPipelineRepository {
+    async fn create(&self, pipeline: NewPipeline) -> Result<Pipeline> {
+        let mut conn = self.pool.get()?;
+
+        let pipeline: Pipeline = tokio::task::spawn_blocking(move || {
+            diesel::insert_into(pipelines::table)
+                .values(&pipeline)
+                .get_result::<Pipeline>(&mut conn)
+        }).await??;
+
+        Ok(pipeline)
+    }
+
+    async fn find_by_id(&self, id: Uuid) -> Result<Pipeline> {
+        let mut conn = self.pool.get()?;
+
+        let pipeline: Pipeline = tokio::task::spawn_blocking(move || {
+            pipelines::table
+                .find(id)
+                .first(&mut conn)
+                .optional()
+        }).await??
+            .ok_or_else(|| anyhow!("Pipeline not found with ID: {}", id))?;
+
+        Ok(pipeline)
+    }
+
+    async fn find_by_customer_id(&self, customer_id: Uuid) -> Result<Vec<Pipeline>> {
+        let mut conn = self.pool.get()?;
+
+        let pipelines: Vec<Pipeline> = tokio::task::spawn_blocking(move || {
+            pipelines::table
+                .filter(pipelines::customer_id.eq(customer_id))
+                .order(pipelines::name.asc())
+                .load::<Pipeline>(&mut conn)
+        }).await??;
+
+        Ok(pipelines)
+    }
+
+    async fn update_definition(&self, id: Uuid, name: String, description: Option<String>, definition: String) -> Result<Pipeline> {
+        let mut conn = self.pool.get()?;
+
+        let pipeline: Pipeline = tokio::task::spawn_blocking(move || {
+            diesel::update(pipelines::table.find(id))
+                .set((
+                    pipelines::name.eq(name),
+                    pipelines::description.eq(description),
+                    pipelines::definition.eq(definition),
+                    pipelines::updated_at.eq(Utc::now()),
+                ))
+                .get_result::<Pipeline>(&mut conn)
+                .optional()
+        }).await??
+            .ok_or_else(|| anyhow!("Pipeline not found with ID: {}", id))?;
+
+        Ok(pipeline)
+    }
+
+    async fn delete(&self, id: Uuid) -> Result<()> {
+        let mut conn = self.pool.get()?;
+
+        let count = tokio::task::spawn_blocking(move || {
+            diesel::delete(pipelines::table.find(id))
+                .execute(&mut conn)
+        }).await??;
+
+        if count == 0 {
+            return Err(anyhow!("Pipeline not found with ID: {}", id));
+        }
+
+        Ok(())
+    }
+
+    async fn create_run(&self, run: NewPipelineRun, step_names: Vec<String>) -> Result<PipelineRun> {
+        let mut conn = self.pool.get()?;
+
+        let run: PipelineRun = tokio::task::spawn_blocking(move || {
+            conn.transaction(|conn| {
+                let run = diesel::insert_into(pipeline_runs::table)
+                    .values(&run)
+                    .get_result::<PipelineRun>(conn)?;
+
+                let steps: Vec<NewPipelineRunStep> = step_names.into_iter()
+                    .map(|step_name| NewPipelineRunStep { id: Uuid::new_v4(), run_id: run.id, step_name })
+                    .collect();
+                diesel::insert_into(pipeline_run_steps::table)
+                    .values(&steps)
+                    .execute(conn)?;
+
+                Ok::<_, diesel::result::Error>(run)
+            })
+        }).await??;
+
+        Ok(run)
+    }
+
+    async fn find_run_by_id(&self, id: Uuid) -> Result<PipelineRun> {
+        let mut conn = self.pool.get()?;
+
+        let run: PipelineRun = tokio::task::spawn_blocking(move || {
+            pipeline_runs::table
+                .find(id)
+                .first(&mut conn)
+                .optional()
+        }).await??
+            .ok_or_else(|| anyhow!("Pipeline run not found with ID: {}", id))?;
+
+        Ok(run)
+    }
+
+    async fn find_runs_by_pipeline_id(&self, pipeline_id: Uuid) -> Result<Vec<PipelineRun>> {
+        let mut conn = self.pool.get()?;
+
+        let runs: Vec<PipelineRun> = tokio::task::spawn_blocking(move || {
+            pipeline_runs::table
+                .filter(pipeline_runs::pipeline_id.eq(pipeline_id))
+                .order(pipeline_runs::created_at.desc())
+                .load::<PipelineRun>(&mut conn)
+        }).await??;
+
+        Ok(runs)
+    }
+
+    async fn find_running(&self) -> Result<Vec<PipelineRun>> {
+        let mut conn = self.pool.get()?;
+
+        let runs: Vec<PipelineRun> = tokio::task::spawn_blocking(move || {
+            pipeline_runs::table
+                .filter(pipeline_runs::status.eq(PipelineRunStatus::Running.as_str()))
+                .order(pipeline_runs::created_at.asc())
+                .load::<PipelineRun>(&mut conn)
+        }).await??;
+
+        Ok(runs)
+    }
+
+    async fn find_run_steps(&self, run_id: Uuid) -> Result<Vec<PipelineRunStep>> {
+        let mut conn = self.pool.get()?;
+
+        let steps: Vec<PipelineRunStep> = tokio::task::spawn_blocking(move || {
+            pipeline_run_steps::table
+                .filter(pipeline_run_steps::run_id.eq(run_id))
+                .order(pipeline_run_steps::step_name.asc())
+                .load::<PipelineRunStep>(&mut conn)
+        }).await??;
+
+        Ok(steps)
+    }
+
+    async fn claim_step(&self, run_id: Uuid, step_name: String, status: StepStatus, job_id: Option<Uuid>) -> Result<bool> {
+        let mut conn = self.pool.get()?;
+
+        let count = tokio::task::spawn_blocking(move || {
+            diesel::update(
+                pipeline_run_steps::table
+                    .filter(pipeline_run_steps::run_id.eq(run_id))
+                    .filter(pipeline_run_steps::step_name.eq(step_name))
+                    .filter(pipeline_run_steps::status.eq(StepStatus::Pending.as_str()))
+            )
+                .set((
+                    pipeline_run_steps::status.eq(status.as_str()),
+                    pipeline_run_steps::job_id.eq(job_id),
+                    pipeline_run_steps::updated_at.eq(Utc::now()),
+                ))
+                .execute(&mut conn)
+        }).await??;
+
+        Ok(count > 0)
+    }
+
+    async fn update_step(&self, run_id: Uuid, step_name: String, status: StepStatus, job_id: Option<Uuid>) -> Result<PipelineRunStep> {
+        let mut conn = self.pool.get()?;
+        let name = step_name.clone();
+
+        let step: PipelineRunStep = tokio::task::spawn_blocking(move || {
+            diesel::update(
+                pipeline_run_steps::table
+                    .filter(pipeline_run_steps::run_id.eq(run_id))
+                    .filter(pipeline_run_steps::step_name.eq(name))
+            )
+                .set((
+                    pipeline_run_steps::status.eq(status.as_str()),
+                    pipeline_run_steps::job_id.eq(job_id),
+                    pipeline_run_steps::updated_at.eq(Utc::now()),
+                ))
+                .get_result::<PipelineRunStep>(&mut conn)
+                .optional()
+        }).await??
+            .ok_or_else(|| anyhow!("Step {} not found in pipeline run {}", step_name, run_id))?;
+
+        Ok(step)
+    }
+
+    async fn finish_run(&self, id: Uuid, status: PipelineRunStatus) -> Result<PipelineRun> {
+        let mut conn = self.pool.get()?;
+
+        let run: PipelineRun = tokio::task::spawn_blocking(move || {
+            diesel::update(pipeline_runs::table.find(id))
+                .set((
+                    pipeline_runs::status.eq(status.as_str()),
+                    pipeline_runs::completed_at.eq(Utc::now()),
+                ))
+                .get_result::<PipelineRun>(&mut conn)
+                .optional()
+        }).await??
+            .ok_or_else(|| anyhow!("Pipeline run not found with ID: {}", id))?;
+
+        Ok(run)
+    }
+}
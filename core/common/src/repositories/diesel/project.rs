@@ -1,6 +1,6 @@
 use async_trait::async_trait;
 use diesel::prelude::*;
-use diesel::r2d2::{ConnectionManager, Pool};
+use crate::database::TenantPool;
 use uuid::Uuid;
 use anyhow::{Result, anyhow};
 use chrono::Utc;
@@ -11,13 +11,13 @@ use crate::diesel_schema::projects;
 
 /// Diesel implementation of the ProjectRepository
 pub struct DieselProjectRepository {
-    pool: Pool<ConnectionManager<PgConnection>>,
+    pool: TenantPool,
 }
 
 impl DieselProjectRepository {
     /// Create a new DieselProjectRepository with the given connection pool
-    pub fn new(pool: Pool<ConnectionManager<PgConnection>>) -> Self {
-        Self { pool }
+    pub fn new(pool: impl Into<TenantPool>) -> Self {
+        Self { pool: pool.into() }
     }
 }
 
@@ -68,7 +68,7 @@ impl ProjectRepository for DieselProjectRepository {
         
         // Create an updated project with the current timestamp
         let mut updated_project = project_clone.clone();
-        updated_project.updated_at = Some(Utc::now().naive_utc());
+        updated_project.updated_at = Some(Utc::now());
         
         let updated_project = tokio::task::spawn_blocking(move || {
             diesel::update(projects::table.find(project_clone.id))
@@ -0,0 +1,137 @@
+use async_trait::async_trait;
+use diesel::prelude::*;
+use crate::database::TenantPool;
+use uuid::Uuid;
+use anyhow::{Result, anyhow};
+
+use crate::models::provider::{NewProvider, Provider, ProviderStatus};
+use crate::repositories::ProviderRepository;
+use crate::diesel_schema::providers;
+
+/// Diesel implementation of the ProviderRepository
+pub struct DieselProviderRepository {
+    pool: TenantPool,
+}
+
+impl DieselProviderRepository {
+    /// Create a new DieselProviderRepository with the given connection pool
+    pub fn new(pool: impl Into<TenantPool>) -> Self {
+        Self { pool: pool.into() }
+    }
+}
+
+#[async_trait]
+impl ProviderRepository for DieselProviderRepository {
+    async fn create(&self, provider: NewProvider) -> Result<Provider> {
+        let mut conn = self.pool.get()?;
+
+        let provider: Provider = tokio::task::spawn_blocking(move || {
+            diesel::insert_into(providers::table)
+                .values(&provider)
+                .returning(Provider::as_select())
+                .get_result(&mut conn)
+        }).await??;
+
+        Ok(provider)
+    }
+
+    async fn find_by_id(&self, id: Uuid) -> Result<Provider> {
+        let mut conn = self.pool.get()?;
+
+        let provider: Provider = tokio::task::spawn_blocking(move || {
+            providers::table
+                .find(id)
+                .select(Provider::as_select())
+                .first(&mut conn)
+                .optional()
+        }).await??
+            .ok_or_else(|| anyhow!("Provider not found with ID: {}", id))?;
+
+        Ok(provider)
+    }
+
+    async fn list_all(&self) -> Result<Vec<Provider>> {
+        let mut conn = self.pool.get()?;
+
+        let providers: Vec<Provider> = tokio::task::spawn_blocking(move || {
+            providers::table
+                .order(providers::name.asc())
+                .select(Provider::as_select())
+                .load(&mut conn)
+        }).await??;
+
+        Ok(providers)
+    }
+
+    async fn update(&self, provider: Provider) -> Result<Provider> {
+        let mut conn = self.pool.get()?;
+        let id = provider.id;
+
+        let provider: Provider = tokio::task::spawn_blocking(move || {
+            diesel::update(providers::table.find(provider.id))
+                .set((
+                    providers::name.eq(provider.name),
+                    providers::ping_url.eq(provider.ping_url),
+                    providers::failure_rate_threshold.eq(provider.failure_rate_threshold),
+                    providers::min_jobs.eq(provider.min_jobs),
+                    providers::retry_delay_secs.eq(provider.retry_delay_secs),
+                    providers::updated_at.eq(diesel::dsl::now),
+                ))
+                .returning(Provider::as_select())
+                .get_result(&mut conn)
+                .optional()
+        }).await??
+            .ok_or_else(|| anyhow!("Provider not found with ID: {}", id))?;
+
+        Ok(provider)
+    }
+
+    async fn record_check(&self, id: Uuid, status: ProviderStatus, reason: Option<String>) -> Result<Provider> {
+        let mut conn = self.pool.get()?;
+
+        let provider: Provider = tokio::task::spawn_blocking(move || {
+            conn.transaction(|conn| {
+                let current: Option<String> = providers::table
+                    .find(id)
+                    .select(providers::status)
+                    .for_update()
+                    .first(conn)
+                    .optional()?;
+                let Some(current) = current else {
+                    return Ok(None);
+                };
+
+                let now = chrono::Utc::now();
+                let changed = current != status.as_str();
+                diesel::update(providers::table.find(id))
+                    .set((
+                        providers::status.eq(status.as_str()),
+                        providers::status_reason.eq(reason),
+                        providers::last_checked_at.eq(now),
+                        changed.then_some(providers::status_changed_at.eq(now)),
+                    ))
+                    .returning(Provider::as_select())
+                    .get_result(conn)
+                    .map(Some)
+            })
+        }).await??
+            .ok_or_else(|| anyhow!("Provider not found with ID: {}", id))?;
+
+        Ok(provider)
+    }
+
+    async fn delete(&self, id: Uuid) -> Result<()> {
+        let mut conn = self.pool.get()?;
+
+        let count = tokio::task::spawn_blocking(move || {
+            diesel::delete(providers::table.find(id))
+                .execute(&mut conn)
+        }).await??;
+
+        if count == 0 {
+            return Err(anyhow!("Provider not found with ID: {}", id));
+        }
+
+        Ok(())
+    }
+}
@@ -0,0 +1,121 @@
+use async_trait::async_trait;
+use chrono::{DateTime, Utc};
+use diesel::dsl::count_star;
+use diesel::prelude::*;
+use crate::database::TenantPool;
+use anyhow::Result;
+use uuid::Uuid;
+
+use crate::models::job::JobStatus;
+use crate::models::purge::PurgedRows;
+use crate::repositories::PurgeRepository;
+use crate::diesel_schema::{job_artifacts, job_attempts, job_logs, job_unredacted_outputs, job_usage, jobs};
+
+/// Statuses of jobs that will not change anymore
+const FINISHED_STATUSES: [JobStatus; 4] = [JobStatus::Succeeded, JobStatus::Failed, JobStatus::Cancelled, JobStatus::Expired];
+
+fn finished_statuses() -> Vec<&'static str> {
+    FINISHED_STATUSES.iter().map(JobStatus::as_str).collect()
+}
+
+/// Diesel implementation of the PurgeRepository
+pub struct DieselPurgeRepository {
+    pool: TenantPool,
+}
+
+impl DieselPurgeRepository {
+    /// Create a new DieselPurgeRepository with the given connection pool
+    pub fn new(pool: impl Into<TenantPool>) -> Self {
+        Self { pool: pool.into() }
+    }
+}
+
+#[async_trait]
+impl PurgeRepository for DieselPurgeRepository {
+    async fn count_expired_jobs(&self, created_before: DateTime<Utc>) -> Result<u64> {
+        let mut conn = self.pool.get()?;
+
+        let count = tokio::task::spawn_blocking(move || {
+            jobs::table
+                .filter(jobs::created_at.lt(created_before))
+                .filter(jobs::status.eq_any(finished_statuses()))
+                .select(count_star())
+                .first::<i64>(&mut conn)
+        }).await??;
+
+        Ok(count as u64)
+    }
+
+    async fn purge_expired_jobs(&self, created_before: DateTime<Utc>, limit: i64) -> Result<PurgedRows> {
+        let mut conn = self.pool.get()?;
+
+        let purged = tokio::task::spawn_blocking(move || {
+            conn.transaction(|conn| {
+                // Jobs locked by a concurrent purge are left to it
+                let ids: Vec<Uuid> = jobs::table
+                    .filter(jobs::created_at.lt(created_before))
+                    .filter(jobs::status.eq_any(finished_statuses()))
+                    .order(jobs::created_at.asc())
+                    .limit(limit)
+                    .select(jobs::id)
+                    .for_update()
+                    .skip_locked()
+                    .load(conn)?;
+
+                let mut purged = PurgedRows::new();
+                if ids.is_empty() {
+                    return Ok::<_, diesel::result::Error>(purged);
+                }
+                purged.insert("job_logs".to_string(), diesel::delete(job_logs::table.filter(job_logs::job_id.eq_any(&ids))).execute(conn)?);
+                purged.insert("job_attempts".to_string(), diesel::delete(job_attempts::table.filter(job_attempts::job_id.eq_any(&ids))).execute(conn)?);
+                purged.insert(
+                    "job_unredacted_outputs".to_string(),
+                    diesel::delete(job_unredacted_outputs::table.filter(job_unredacted_outputs::job_id.eq_any(&ids))).execute(conn)?,
+                );
+                purged.insert("job_usage".to_string(), diesel::delete(job_usage::table.filter(job_usage::job_id.eq_any(&ids))).execute(conn)?);
+                purged.insert("job_artifacts".to_string(), diesel::delete(job_artifacts::table.filter(job_artifacts::job_id.eq_any(&ids))).execute(conn)?);
+                purged.insert("jobs".to_string(), diesel::delete(jobs::table.filter(jobs::id.eq_any(&ids))).execute(conn)?);
+                Ok(purged)
+            })
+        }).await??;
+
+        Ok(purged)
+    }
+
+    async fn count_expired_unredacted_outputs(&self) -> Result<u64> {
+        let mut conn = self.pool.get()?;
+
+        let count = tokio::task::spawn_blocking(move || {
+            job_unredacted_outputs::table
+                .filter(job_unredacted_outputs::expires_at.le(diesel::dsl::now))
+                .select(count_star())
+                .first::<i64>(&mut conn)
+        }).await??;
+
+        Ok(count as u64)
+    }
+
+    async fn purge_expired_unredacted_outputs(&self, limit: i64) -> Result<PurgedRows> {
+        let mut conn = self.pool.get()?;
+
+        let purged = tokio::task::spawn_blocking(move || {
+            conn.transaction(|conn| {
+                let expired: Vec<Uuid> = job_unredacted_outputs::table
+                    .filter(job_unredacted_outputs::expires_at.le(diesel::dsl::now))
+                    .limit(limit)
+                    .select(job_unredacted_outputs::job_id)
+                    .for_update()
+                    .skip_locked()
+                    .load(conn)?;
+                diesel::delete(job_unredacted_outputs::table.filter(job_unredacted_outputs::job_id.eq_any(&expired)))
+                    .execute(conn)
+            })
+        }).await??;
+
+        let mut rows = PurgedRows::new();
+        if purged > 0 {
+            rows.insert("job_unredacted_outputs".to_string(), purged);
+        }
+        Ok(rows)
+    }
+}
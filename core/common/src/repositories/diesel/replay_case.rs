@@ -0,0 +1,126 @@
+use async_trait::async_trait;
+use diesel::dsl::sql;
+use diesel::prelude::*;
+use diesel::sql_types::Double;
+use crate::database::TenantPool;
+use anyhow::Result;
+use serde_json::Value;
+use uuid::Uuid;
+
+use crate::models::job::{Job, JobDb, JobStatus};
+use crate::models::replay::{NewReplayCase, ReplayCase};
+use crate::repositories::ReplayCaseRepository;
+use crate::diesel_schema::{jobs, replay_cases};
+
+/// Diesel implementation of the ReplayCaseRepository
+pub struct DieselReplayCaseRepository {
+    pool: TenantPool,
+}
+
+impl DieselReplayCaseRepository {
+    /// Create a new DieselReplayCaseRepository with the given connection pool
+    pub fn new(pool: impl Into<TenantPool>) -> Self {
+        Self { pool: pool.into() }
+    }
+}
+
+#[async_trait]
+impl ReplayCaseRepository for DieselReplayCaseRepository {
+    async fn find_candidates(&self, job_type_id: Uuid, limit: i64) -> Result<Vec<Job>> {
+        let mut conn = self.pool.get()?;
+
+        let jobs = tokio::task::spawn_blocking(move || {
+            jobs::table
+                .filter(jobs::job_type_id.eq(job_type_id))
+                .filter(jobs::status.eq(JobStatus::Succeeded.as_str()))
+                .filter(jobs::test_mode.eq(false))
+                .filter(jobs::id.ne_all(
+                    replay_cases::table
+                        .filter(replay_cases::job_type_id.eq(job_type_id))
+                        .select(replay_cases::source_job_id)
+                ))
+                .order(sql::<Double>("random()"))
+                .limit(limit)
+                .select(JobDb::as_select())
+                .load::<JobDb>(&mut conn)
+        }).await??;
+
+        Ok(jobs.into_iter().map(Job::from).collect())
+    }
+
+    async fn add(&self, cases: Vec<NewReplayCase>) -> Result<Vec<ReplayCase>> {
+        let mut conn = self.pool.get()?;
+
+        let cases = tokio::task::spawn_blocking(move || {
+            diesel::insert_into(replay_cases::table)
+                .values(&cases)
+                .on_conflict((replay_cases::job_type_id, replay_cases::source_job_id))
+                .do_nothing()
+                .returning(ReplayCase::as_returning())
+                .get_results(&mut conn)
+        }).await??;
+
+        Ok(cases)
+    }
+
+    async fn list_by_job_type(&self, job_type_id: Uuid) -> Result<Vec<ReplayCase>> {
+        let mut conn = self.pool.get()?;
+
+        let cases = tokio::task::spawn_blocking(move || {
+            replay_cases::table
+                .filter(replay_cases::job_type_id.eq(job_type_id))
+                .order((replay_cases::sampled_at.asc(), replay_cases::id.asc()))
+                .select(ReplayCase::as_select())
+                .load(&mut conn)
+        }).await??;
+
+        Ok(cases)
+    }
+
+    async fn list_job_types(&self) -> Result<Vec<Uuid>> {
+        let mut conn = self.pool.get()?;
+
+        let job_type_ids = tokio::task::spawn_blocking(move || {
+            replay_cases::table
+                .select(replay_cases::job_type_id)
+                .distinct()
+                .load(&mut conn)
+        }).await??;
+
+        Ok(job_type_ids)
+    }
+
+    async fn record_baseline(&self, id: Uuid, output: Value, cost_cents: i32, version: &str) -> Result<ReplayCase> {
+        let mut conn = self.pool.get()?;
+        let version = version.to_string();
+
+        let case = tokio::task::spawn_blocking(move || {
+            diesel::update(replay_cases::table.find(id))
+                .set((
+                    replay_cases::baseline_output.eq(output),
+                    replay_cases::baseline_cost_cents.eq(cost_cents),
+                    replay_cases::baseline_version.eq(version),
+                    replay_cases::baseline_recorded_at.eq(diesel::dsl::now),
+                ))
+                .returning(ReplayCase::as_returning())
+                .get_result(&mut conn)
+        }).await??;
+
+        Ok(case)
+    }
+
+    async fn delete(&self, job_type_id: Uuid, id: Uuid) -> Result<bool> {
+        let mut conn = self.pool.get()?;
+
+        let deleted = tokio::task::spawn_blocking(move || {
+            diesel::delete(
+                replay_cases::table
+                    .filter(replay_cases::id.eq(id))
+                    .filter(replay_cases::job_type_id.eq(job_type_id)),
+            )
+            .execute(&mut conn)
+        }).await??;
+
+        Ok(deleted > 0)
+    }
+}
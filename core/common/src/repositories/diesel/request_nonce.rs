@@ -0,0 +1,54 @@
+use async_trait::async_trait;
+use chrono::{DateTime, Utc};
+use diesel::prelude::*;
+use crate::database::TenantPool;
+use anyhow::Result;
+use uuid::Uuid;
+
+use crate::repositories::RequestNonceRepository;
+use crate::diesel_schema::request_nonces;
+
+/// Diesel implementation of the RequestNonceRepository
+pub struct DieselRequestNonceRepository {
+    pool: TenantPool,
+}
+
+impl DieselRequestNonceRepository {
+    /// Create a new DieselRequestNonceRepository with the given connection pool
+    pub fn new(pool: impl Into<TenantPool>) -> Self {
+        Self { pool: pool.into() }
+    }
+}
+
+#[async_trait]
+impl RequestNonceRepository for DieselRequestNonceRepository {
+    async fn record(&self, reseller_id: Uuid, nonce: &str, expires_at: DateTime<Utc>) -> Result<bool> {
+        let nonce = nonce.to_string();
+        let mut conn = self.pool.get()?;
+
+        // The primary key makes concurrent replays of the same request race for one row
+        let inserted = tokio::task::spawn_blocking(move || {
+            diesel::insert_into(request_nonces::table)
+                .values((
+                    request_nonces::reseller_id.eq(reseller_id),
+                    request_nonces::nonce.eq(&nonce),
+                    request_nonces::expires_at.eq(expires_at),
+                ))
+                .on_conflict_do_nothing()
+                .execute(&mut conn)
+        }).await??;
+
+        Ok(inserted == 1)
+    }
+
+    async fn purge_expired(&self) -> Result<usize> {
+        let mut conn = self.pool.get()?;
+
+        let purged = tokio::task::spawn_blocking(move || {
+            diesel::delete(request_nonces::table.filter(request_nonces::expires_at.le(diesel::dsl::now)))
+                .execute(&mut conn)
+        }).await??;
+
+        Ok(purged)
+    }
+}
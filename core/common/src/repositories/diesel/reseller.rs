@@ -1,23 +1,23 @@
 use async_trait::async_trait;
 use diesel::prelude::*;
-use diesel::r2d2::{ConnectionManager, Pool};
+use crate::database::TenantPool;
 use uuid::Uuid;
 use anyhow::{Result, anyhow};
-use chrono::Utc;
+use chrono::{DateTime, Utc};
 
 use crate::models::reseller::{Reseller, NewReseller};
 use crate::repositories::ResellerRepository;
-use crate::diesel_schema::resellers;
+use crate::diesel_schema::{customers, invoices, resellers};
 
 /// Diesel implementation of the ResellerRepository
 pub struct DieselResellerRepository {
-    pool: Pool<ConnectionManager<PgConnection>>,
+    pool: TenantPool,
 }
 
 impl DieselResellerRepository {
     /// Create a new DieselResellerRepository with the given connection pool
-    pub fn new(pool: Pool<ConnectionManager<PgConnection>>) -> Self {
-        Self { pool }
+    pub fn new(pool: impl Into<TenantPool>) -> Self {
+        Self { pool: pool.into() }
     }
 }
 
@@ -71,7 +71,7 @@ impl ResellerRepository for DieselResellerRepository {
         
         // Create an updated reseller with the current timestamp
         let mut updated_reseller = reseller_clone.clone();
-        updated_reseller.updated_at = Some(Utc::now().naive_utc());
+        updated_reseller.updated_at = Some(Utc::now());
         
         let updated_reseller = tokio::task::spawn_blocking(move || {
             diesel::update(resellers::table.find(reseller_clone.id))
@@ -81,6 +81,14 @@ impl ResellerRepository for DieselResellerRepository {
                     resellers::api_key.eq(&updated_reseller.api_key),
                     resellers::active.eq(updated_reseller.active),
                     resellers::commission_rate.eq(updated_reseller.commission_rate),
+                    resellers::suspend_customers.eq(updated_reseller.suspend_customers),
+                    resellers::block_customer_creation.eq(updated_reseller.block_customer_creation),
+                    resellers::deactivation_reason.eq(&updated_reseller.deactivation_reason),
+                    resellers::default_locale.eq(&updated_reseller.default_locale),
+                    resellers::signing_secret.eq(&updated_reseller.signing_secret),
+                    resellers::require_signed_requests.eq(updated_reseller.require_signed_requests),
+                    resellers::markup_rate.eq(updated_reseller.markup_rate),
+                    resellers::timezone.eq(&updated_reseller.timezone),
                     resellers::updated_at.eq(updated_reseller.updated_at),
                 ))
                 .get_result::<Reseller>(&mut conn)
@@ -111,4 +119,60 @@ impl ResellerRepository for DieselResellerRepository {
         
         Ok(resellers)
     }
+    
+    async fn list_sandboxes(&self, parent_id: Uuid) -> Result<Vec<Reseller>> {
+        let mut conn = self.pool.get()?;
+        
+        let sandboxes: Vec<Reseller> = tokio::task::spawn_blocking(move || {
+            resellers::table
+                .filter(resellers::sandbox_of.eq(parent_id))
+                .order(resellers::created_at.desc())
+                .load::<Reseller>(&mut conn)
+        }).await??;
+        
+        Ok(sandboxes)
+    }
+    
+    async fn find_expired_sandboxes(&self, now: DateTime<Utc>) -> Result<Vec<Reseller>> {
+        let mut conn = self.pool.get()?;
+        
+        let sandboxes: Vec<Reseller> = tokio::task::spawn_blocking(move || {
+            resellers::table
+                .filter(resellers::sandbox_of.is_not_null())
+                .filter(resellers::sandbox_expires_at.le(now))
+                .order(resellers::sandbox_expires_at.asc())
+                .load::<Reseller>(&mut conn)
+        }).await??;
+        
+        Ok(sandboxes)
+    }
+    
+    async fn delete_sandbox(&self, id: Uuid) -> Result<()> {
+        let mut conn = self.pool.get()?;
+        
+        tokio::task::spawn_blocking(move || -> Result<()> {
+            conn.transaction(|conn| {
+                let sandbox = resellers::table
+                    .find(id)
+                    .for_update()
+                    .first::<Reseller>(conn)
+                    .optional()?
+                    .ok_or_else(|| anyhow!("Reseller not found with ID: {}", id))?;
+                if !sandbox.is_sandbox() {
+                    return Err(anyhow!("Reseller {} is not a sandbox tenant", id));
+                }
+                
+                // Invoices of sandbox customers only ever cover test jobs; deleting the
+                // customers takes their jobs, wallets and everything else along
+                let customer_ids: Vec<Uuid> = customers::table
+                    .filter(customers::reseller_id.eq(id))
+                    .select(customers::id)
+                    .load(conn)?;
+                diesel::delete(invoices::table.filter(invoices::customer_id.eq_any(&customer_ids))).execute(conn)?;
+                diesel::delete(customers::table.filter(customers::id.eq_any(&customer_ids))).execute(conn)?;
+                diesel::delete(resellers::table.find(id)).execute(conn)?;
+                Ok(())
+            })
+        }).await?
+    }
 }
@@ -1,69 +1,68 @@
 use async_trait::async_trait;
 use diesel::prelude::*;
-use diesel::r2d2::{ConnectionManager, Pool};
+use diesel::dsl::{max, min};
+use crate::database::TenantPool;
+use diesel::pg::PgConnection;
+use std::collections::{HashMap, HashSet};
 use uuid::Uuid;
 use anyhow::{Result, anyhow};
-use chrono::{NaiveDateTime, Utc};
+use chrono::{DateTime, Utc};
 
 
-use crate::models::runner::{Runner, NewRunner, NewJobTypeCompatibility, RunnerStatus};
+use crate::models::runner::{Runner, RunnerDb, NewRunner, NewJobTypeCompatibility, RunnerStatus, RunnerHealthCheck, NewRunnerHealthCheck, RunnerEnvironmentReport, NewRunnerEnvironmentReport};
 use crate::repositories::RunnerRepository;
-use crate::diesel_schema::{runners, runner_job_type_compatibility};
+use crate::diesel_schema::{job_types, runners, runner_job_type_compatibility, runner_health_checks, runner_environments};
 use crate::models::job_type::JobType;
 
 /// Diesel implementation of the RunnerRepository
 pub struct DieselRunnerRepository {
-    pool: Pool<ConnectionManager<PgConnection>>,
+    pool: TenantPool,
 }
 
 impl DieselRunnerRepository {
     /// Create a new DieselRunnerRepository with the given connection pool
-    pub fn new(pool: Pool<ConnectionManager<PgConnection>>) -> Self {
-        Self { pool }
+    pub fn new(pool: impl Into<TenantPool>) -> Self {
+        Self { pool: pool.into() }
     }
+}
+
+// Runners with the names of their compatible job types, from the compatibility table
+fn with_job_types(conn: &mut PgConnection, rows: Vec<RunnerDb>) -> QueryResult<Vec<Runner>> {
+    let runner_ids: Vec<Uuid> = rows.iter().map(|row| row.id).collect();
+    let compatible: Vec<(Uuid, String)> = runner_job_type_compatibility::table
+        .inner_join(job_types::table.on(job_types::id.eq(runner_job_type_compatibility::job_type_id)))
+        .filter(runner_job_type_compatibility::runner_id.eq_any(&runner_ids))
+        .select((runner_job_type_compatibility::runner_id, job_types::name))
+        .order(job_types::name.asc())
+        .load(conn)?;
     
-    // Helper to save job type compatibilities
-    async fn save_job_type_compatibilities(&self, runner_id: Uuid, job_type_ids: Vec<Uuid>) -> Result<()> {
-        let mut conn = self.pool.get()?;
-        
-        tokio::task::spawn_blocking(move || -> Result<()> {
-            // Start a transaction
-            conn.transaction(|conn| {
-                // First delete existing compatibilities
-                diesel::delete(runner_job_type_compatibility::table)
-                    .filter(runner_job_type_compatibility::runner_id.eq(runner_id))
-                    .execute(conn)?;
-                
-                // Then insert new ones
-                for job_type_id in job_type_ids {
-                    let compatibility = NewJobTypeCompatibility {
-                        runner_id,
-                        job_type_id,
-                    };
-                    
-                    diesel::insert_into(runner_job_type_compatibility::table)
-                        .values(&compatibility)
-                        .execute(conn)?;
-                }
-                
-                Ok(())
-            })
-        }).await?
+    let mut names: HashMap<Uuid, Vec<String>> = HashMap::new();
+    for (runner_id, name) in compatible {
+        names.entry(runner_id).or_default().push(name);
     }
+    Ok(rows.into_iter()
+        .map(|row| {
+            let job_type_names = names.remove(&row.id).unwrap_or_default();
+            row.into_runner(job_type_names)
+        })
+        .collect())
+}
+
+// Replace the job types a runner is compatible with
+fn replace_compatibilities(conn: &mut PgConnection, runner_id: Uuid, job_type_ids: &[Uuid]) -> QueryResult<()> {
+    diesel::delete(runner_job_type_compatibility::table)
+        .filter(runner_job_type_compatibility::runner_id.eq(runner_id))
+        .execute(conn)?;
     
-    // Helper to get job type compatibilities
-    async fn get_job_type_compatibilities(&self, runner_id: Uuid) -> Result<Vec<Uuid>> {
-        let mut conn = self.pool.get()?;
-        
-        let compatibilities = tokio::task::spawn_blocking(move || {
-            runner_job_type_compatibility::table
-                .filter(runner_job_type_compatibility::runner_id.eq(runner_id))
-                .select(runner_job_type_compatibility::job_type_id)
-                .load::<Uuid>(&mut conn)
-        }).await??;
-        
-        Ok(compatibilities)
-    }
+    let compatibilities: Vec<NewJobTypeCompatibility> = job_type_ids.iter()
+        .collect::<HashSet<_>>()
+        .into_iter()
+        .map(|job_type_id| NewJobTypeCompatibility { runner_id, job_type_id: *job_type_id })
+        .collect();
+    diesel::insert_into(runner_job_type_compatibility::table)
+        .values(&compatibilities)
+        .execute(conn)?;
+    Ok(())
 }
 
 #[async_trait]
@@ -71,31 +70,39 @@ impl RunnerRepository for DieselRunnerRepository {
     async fn register(&self, runner: NewRunner) -> Result<Runner> {
         let mut conn = self.pool.get()?;
         
-        // Insert the new runner
-        let runner: Runner = tokio::task::spawn_blocking(move || {
-            diesel::insert_into(runners::table)
-                .values(&runner)
-                .get_result(&mut conn)
+        let runner = tokio::task::spawn_blocking(move || {
+            conn.transaction(|conn| -> Result<Runner> {
+                // Compatible job types are given by name and stored by ID
+                let job_type_ids: Vec<(Uuid, String)> = job_types::table
+                    .filter(job_types::name.eq_any(&runner.compatible_job_types))
+                    .select((job_types::id, job_types::name))
+                    .load(conn)?;
+                if let Some(unknown) = runner.compatible_job_types.iter().find(|name| !job_type_ids.iter().any(|(_, known)| known == *name)) {
+                    return Err(anyhow!("Unknown job type: {}", unknown));
+                }
+                
+                let row: RunnerDb = diesel::insert_into(runners::table)
+                    .values(&runner)
+                    .get_result(conn)?;
+                let job_type_ids: Vec<Uuid> = job_type_ids.into_iter().map(|(id, _)| id).collect();
+                replace_compatibilities(conn, row.id, &job_type_ids)?;
+                
+                Ok(with_job_types(conn, vec![row])?.remove(0))
+            })
         }).await??;
         
-        // Convert string job types to UUIDs and save compatibility if any exist
-        if !runner.compatible_job_types.is_empty() {
-            // Just passing empty vec since we can't get UUIDs from strings here
-            // The caller should use update_capabilities after registration if needed
-            self.save_job_type_compatibilities(runner.id, Vec::new()).await?;
-        }
-        
         Ok(runner)
     }
     
-    async fn update_heartbeat(&self, id: Uuid, timestamp: NaiveDateTime) -> Result<Runner> {
+    async fn update_heartbeat(&self, id: Uuid, timestamp: DateTime<Utc>) -> Result<Runner> {
         let mut conn = self.pool.get()?;
         
         let runner = tokio::task::spawn_blocking(move || {
-            diesel::update(runners::table.find(id))
+            let row = diesel::update(runners::table.find(id))
                 .set(runners::last_heartbeat.eq(timestamp))
-                .get_result::<Runner>(&mut conn)
-        }).await??;
+                .get_result::<RunnerDb>(&mut conn)?;
+            with_job_types(&mut conn, vec![row])
+        }).await??.remove(0);
         
         Ok(runner)
     }
@@ -103,92 +110,95 @@ impl RunnerRepository for DieselRunnerRepository {
     async fn find_by_id(&self, id: Uuid) -> Result<Runner> {
         let mut conn = self.pool.get()?;
         
-        let runner: Runner = tokio::task::spawn_blocking(move || {
-            runners::table
+        let runner = tokio::task::spawn_blocking(move || {
+            let rows: Vec<RunnerDb> = runners::table
                 .find(id)
-                .first(&mut conn)
-                .optional()
+                .load(&mut conn)?;
+            with_job_types(&mut conn, rows)
         }).await??
+            .pop()
             .ok_or_else(|| anyhow!("Runner not found with ID: {}", id))?;
         
-        // Load job type compatibilities - runner already has compatible_job_types as strings
-        // We don't need to modify the runner object with job_type_ids since that field doesn't exist
-        
         Ok(runner)
     }
     
     async fn update_capabilities(&self, id: Uuid, job_type_ids: Vec<Uuid>) -> Result<Runner> {
-        // First ensure runner exists
-        let runner = self.find_by_id(id).await?;
-        
-        // Save the new capabilities
-        self.save_job_type_compatibilities(id, job_type_ids.clone()).await?;
+        let mut conn = self.pool.get()?;
         
-        // The Runner model doesn't have a job_type_ids field, it has compatible_job_types
-        // We would need to query job types to get their names
-        // For now, just return the runner as is
+        let runner = tokio::task::spawn_blocking(move || {
+            conn.transaction(|conn| -> Result<Runner> {
+                let row: RunnerDb = runners::table
+                    .find(id)
+                    .for_update()
+                    .first(conn)
+                    .optional()?
+                    .ok_or_else(|| anyhow!("Runner not found with ID: {}", id))?;
+                
+                replace_compatibilities(conn, id, &job_type_ids)?;
+                
+                Ok(with_job_types(conn, vec![row])?.remove(0))
+            })
+        }).await??;
         
         Ok(runner)
     }
     
-    async fn list_all(&self) -> Result<Vec<Runner>> {
+    async fn find_compatible_job_type_ids(&self, id: Uuid) -> Result<Vec<Uuid>> {
         let mut conn = self.pool.get()?;
         
-        let runners: Vec<Runner> = tokio::task::spawn_blocking(move || {
-            runners::table
-                .load::<Runner>(&mut conn)
+        let compatibilities = tokio::task::spawn_blocking(move || {
+            runner_job_type_compatibility::table
+                .filter(runner_job_type_compatibility::runner_id.eq(id))
+                .select(runner_job_type_compatibility::job_type_id)
+                .load::<Uuid>(&mut conn)
         }).await??;
         
-        // Runners already have their compatible_job_types as strings
-        // We don't need to modify each runner with job_type_ids
+        Ok(compatibilities)
+    }
+    
+    async fn list_all(&self) -> Result<Vec<Runner>> {
+        let mut conn = self.pool.get()?;
+        
+        let runners = tokio::task::spawn_blocking(move || {
+            let rows = runners::table
+                .load::<RunnerDb>(&mut conn)?;
+            with_job_types(&mut conn, rows)
+        }).await??;
         
         Ok(runners)
     }
     
-    async fn list_active(&self, since: NaiveDateTime) -> Result<Vec<Runner>> {
+    async fn list_active(&self, since: DateTime<Utc>) -> Result<Vec<Runner>> {
         let mut conn = self.pool.get()?;
         
-        let runners: Vec<Runner> = tokio::task::spawn_blocking(move || {
-            runners::table
+        let runners = tokio::task::spawn_blocking(move || {
+            let rows = runners::table
                 .filter(runners::status.eq(RunnerStatus::Active.as_str()))
                 .filter(runners::last_heartbeat.ge(since))
-                .load::<Runner>(&mut conn)
+                .load::<RunnerDb>(&mut conn)?;
+            with_job_types(&mut conn, rows)
         }).await??;
         
-        // Runners already have their compatible_job_types as strings
-        // We don't need to modify each runner with job_type_ids
-        
         Ok(runners)
     }
     
     async fn find_compatible_with_job_type(&self, job_type: &JobType) -> Result<Vec<Runner>> {
         let job_type_id = job_type.id;
         let mut conn = self.pool.get()?;
-        
-        // Find runners that are compatible with this job type
-        let runner_ids: Vec<Uuid> = tokio::task::spawn_blocking(move || {
-            runner_job_type_compatibility::table
-                .filter(runner_job_type_compatibility::job_type_id.eq(job_type_id))
-                .select(runner_job_type_compatibility::runner_id)
-                .load::<Uuid>(&mut conn)
-        }).await??;
-        
-        if runner_ids.is_empty() {
-            return Ok(Vec::new());
-        }
-        
-        // Get the active runners from those IDs
-        let mut conn = self.pool.get()?;
-        let runner_ids_clone = runner_ids.clone();
         // Use chrono::Utc::now() for the timestamp calculation
-        let since = Utc::now().naive_utc() - chrono::Duration::minutes(5);
+        let since = Utc::now() - chrono::Duration::minutes(5);
         
-        let runners: Vec<Runner> = tokio::task::spawn_blocking(move || {
-            runners::table
-                .filter(runners::id.eq_any(runner_ids_clone))
+        // Find the active runners that are compatible with this job type
+        let runners = tokio::task::spawn_blocking(move || {
+            let compatible = runner_job_type_compatibility::table
+                .filter(runner_job_type_compatibility::job_type_id.eq(job_type_id))
+                .select(runner_job_type_compatibility::runner_id);
+            let rows = runners::table
+                .filter(runners::id.eq_any(compatible))
                 .filter(runners::status.eq(RunnerStatus::Active.as_str()))
                 .filter(runners::last_heartbeat.ge(since))
-                .load::<Runner>(&mut conn)
+                .load::<RunnerDb>(&mut conn)?;
+            with_job_types(&mut conn, rows)
         }).await??;
         
         Ok(runners)
@@ -199,16 +209,178 @@ impl RunnerRepository for DieselRunnerRepository {
         let status = if active { RunnerStatus::Active } else { RunnerStatus::Inactive };
         
         let runner = tokio::task::spawn_blocking(move || {
-            diesel::update(runners::table.find(id))
+            let row = diesel::update(runners::table.find(id))
                 .set((
                     runners::status.eq(status.as_str()),
-                    runners::updated_at.eq(Utc::now().naive_utc()),
+                    runners::updated_at.eq(Utc::now()),
                 ))
-                .get_result::<Runner>(&mut conn)
+                .get_result::<RunnerDb>(&mut conn)?;
+            with_job_types(&mut conn, vec![row])
+        }).await??.remove(0);
+        
+        Ok(runner)
+    }
+    
+    async fn record_health_check(&self, check: NewRunnerHealthCheck) -> Result<RunnerHealthCheck> {
+        let mut conn = self.pool.get()?;
+        
+        let check = tokio::task::spawn_blocking(move || {
+            diesel::insert_into(runner_health_checks::table)
+                .values(&check)
+                .get_result::<RunnerHealthCheck>(&mut conn)
         }).await??;
         
-        // The runner already has compatible_job_types as strings
+        Ok(check)
+    }
+    
+    async fn latest_health_check(&self, runner_id: Uuid) -> Result<Option<RunnerHealthCheck>> {
+        let mut conn = self.pool.get()?;
+        
+        let check = tokio::task::spawn_blocking(move || {
+            runner_health_checks::table
+                .filter(runner_health_checks::runner_id.eq(runner_id))
+                .order(runner_health_checks::created_at.desc())
+                .first::<RunnerHealthCheck>(&mut conn)
+                .optional()
+        }).await??;
         
-        Ok(runner)
+        Ok(check)
+    }
+    
+    async fn get_health_history(&self, runner_id: Uuid, limit: i64) -> Result<Vec<RunnerHealthCheck>> {
+        let mut conn = self.pool.get()?;
+        
+        let checks = tokio::task::spawn_blocking(move || {
+            runner_health_checks::table
+                .filter(runner_health_checks::runner_id.eq(runner_id))
+                .order(runner_health_checks::created_at.desc())
+                .limit(limit)
+                .load::<RunnerHealthCheck>(&mut conn)
+        }).await??;
+        
+        Ok(checks)
+    }
+    
+    async fn health_status_since(&self, runner_id: Uuid, status: &str) -> Result<Option<DateTime<Utc>>> {
+        let mut conn = self.pool.get()?;
+        let status = status.to_string();
+        
+        let since = tokio::task::spawn_blocking(move || -> QueryResult<Option<DateTime<Utc>>> {
+            // The run starts after the latest check with another status
+            let other_status_at: Option<DateTime<Utc>> = runner_health_checks::table
+                .filter(runner_health_checks::runner_id.eq(runner_id))
+                .filter(runner_health_checks::status.ne(&status))
+                .select(max(runner_health_checks::created_at))
+                .first(&mut conn)?;
+            
+            let mut query = runner_health_checks::table
+                .filter(runner_health_checks::runner_id.eq(runner_id))
+                .filter(runner_health_checks::status.eq(&status))
+                .into_boxed();
+            if let Some(other_status_at) = other_status_at {
+                query = query.filter(runner_health_checks::created_at.gt(other_status_at));
+            }
+            
+            query
+                .select(min(runner_health_checks::created_at))
+                .first(&mut conn)
+        }).await??;
+        
+        Ok(since)
+    }
+    
+    async fn delete_inactive_before(&self, before: DateTime<Utc>) -> Result<Vec<Uuid>> {
+        let mut conn = self.pool.get()?;
+        
+        let deleted = tokio::task::spawn_blocking(move || {
+            conn.transaction(|conn| {
+                let runner_ids: Vec<Uuid> = runners::table
+                    .filter(runners::status.eq(RunnerStatus::Inactive.as_str()))
+                    .filter(runners::updated_at.lt(before).or(runners::updated_at.is_null()))
+                    .filter(runners::last_heartbeat.lt(before).or(runners::last_heartbeat.is_null()))
+                    .select(runners::id)
+                    .load(conn)?;
+                
+                if runner_ids.is_empty() {
+                    return Ok(runner_ids);
+                }
+                
+                diesel::delete(runner_job_type_compatibility::table)
+                    .filter(runner_job_type_compatibility::runner_id.eq_any(&runner_ids))
+                    .execute(conn)?;
+                diesel::delete(runner_health_checks::table)
+                    .filter(runner_health_checks::runner_id.eq_any(&runner_ids))
+                    .execute(conn)?;
+                diesel::delete(runner_environments::table)
+                    .filter(runner_environments::runner_id.eq_any(&runner_ids))
+                    .execute(conn)?;
+                diesel::delete(runners::table)
+                    .filter(runners::id.eq_any(&runner_ids))
+                    .execute(conn)?;
+                
+                Ok::<_, diesel::result::Error>(runner_ids)
+            })
+        }).await??;
+        
+        Ok(deleted)
+    }    
+    async fn record_environment(&self, report: NewRunnerEnvironmentReport) -> Result<Option<RunnerEnvironmentReport>> {
+        let mut conn = self.pool.get()?;
+        
+        let stored = tokio::task::spawn_blocking(move || {
+            conn.transaction(|conn| {
+                // Lock the runner so concurrent heartbeats do not both store the same change
+                runners::table
+                    .find(report.runner_id)
+                    .select(runners::id)
+                    .for_update()
+                    .first::<Uuid>(conn)?;
+                
+                let latest: Option<String> = runner_environments::table
+                    .filter(runner_environments::runner_id.eq(report.runner_id))
+                    .order(runner_environments::reported_at.desc())
+                    .select(runner_environments::fingerprint)
+                    .first(conn)
+                    .optional()?;
+                if latest.as_ref() == Some(&report.fingerprint) {
+                    return Ok(None);
+                }
+                
+                diesel::insert_into(runner_environments::table)
+                    .values(&report)
+                    .get_result::<RunnerEnvironmentReport>(conn)
+                    .map(Some)
+            })
+        }).await?
+            .map_err(|e: diesel::result::Error| anyhow!("Failed to record runner environment: {}", e))?;
+        
+        Ok(stored)
+    }
+    
+    async fn get_environment_history(&self, runner_id: Uuid, limit: i64) -> Result<Vec<RunnerEnvironmentReport>> {
+        let mut conn = self.pool.get()?;
+        
+        let reports = tokio::task::spawn_blocking(move || {
+            runner_environments::table
+                .filter(runner_environments::runner_id.eq(runner_id))
+                .order(runner_environments::reported_at.desc())
+                .limit(limit)
+                .load::<RunnerEnvironmentReport>(&mut conn)
+        }).await??;
+        
+        Ok(reports)
+    }
+    
+    async fn latest_environments(&self) -> Result<Vec<RunnerEnvironmentReport>> {
+        let mut conn = self.pool.get()?;
+        
+        let reports = tokio::task::spawn_blocking(move || {
+            runner_environments::table
+                .distinct_on(runner_environments::runner_id)
+                .order((runner_environments::runner_id, runner_environments::reported_at.desc()))
+                .load::<RunnerEnvironmentReport>(&mut conn)
+        }).await??;
+        
+        Ok(reports)
     }
 }
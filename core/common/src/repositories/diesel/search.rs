@@ -0,0 +1,125 @@
+use async_trait::async_trait;
+use diesel::prelude::*;
+use diesel::sql_types::Text;
+use crate::database::TenantPool;
+use anyhow::Result;
+use uuid::Uuid;
+
+use crate::models::customer::Customer;
+use crate::models::job::{Job, JobDb, PUBLIC_ID_PREFIX};
+use crate::models::project::Project;
+use crate::models::reseller::Reseller;
+use crate::repositories::SearchRepository;
+use crate::repositories::diesel::job_type::escape_like;
+use crate::diesel_schema::{customers, jobs, projects, resellers};
+
+define_sql_function! {
+    /// Trigram similarity of two strings from 0 to 1, from the `pg_trgm` extension
+    fn similarity(a: Text, b: Text) -> Float;
+}
+
+/// Diesel implementation of the SearchRepository, backed by trigram indexes
+pub struct DieselSearchRepository {
+    pool: TenantPool,
+}
+
+impl DieselSearchRepository {
+    /// Create a new DieselSearchRepository with the given connection pool
+    pub fn new(pool: impl Into<TenantPool>) -> Self {
+        Self { pool: pool.into() }
+    }
+}
+
+/// Pattern matching the query anywhere in a column
+fn contains(query: &str) -> String {
+    format!("%{}%", escape_like(query))
+}
+
+#[async_trait]
+impl SearchRepository for DieselSearchRepository {
+    async fn search_customers(&self, query: &str, limit: i64) -> Result<Vec<Customer>> {
+        let query = query.to_string();
+        let mut conn = self.pool.get()?;
+
+        let customers = tokio::task::spawn_blocking(move || {
+            if let Ok(id) = Uuid::parse_str(&query) {
+                return customers::table.find(id).limit(limit).load::<Customer>(&mut conn);
+            }
+            let pattern = contains(&query);
+            customers::table
+                .filter(customers::name.ilike(&pattern).or(customers::email.ilike(&pattern)))
+                .order((
+                    similarity(customers::name, &query).desc(),
+                    similarity(customers::email, &query).desc(),
+                ))
+                .limit(limit)
+                .load::<Customer>(&mut conn)
+        }).await??;
+
+        Ok(customers)
+    }
+
+    async fn search_resellers(&self, query: &str, limit: i64) -> Result<Vec<Reseller>> {
+        let query = query.to_string();
+        let mut conn = self.pool.get()?;
+
+        let resellers = tokio::task::spawn_blocking(move || {
+            if let Ok(id) = Uuid::parse_str(&query) {
+                return resellers::table.find(id).limit(limit).load::<Reseller>(&mut conn);
+            }
+            let pattern = contains(&query);
+            resellers::table
+                .filter(resellers::name.ilike(&pattern).or(resellers::email.ilike(&pattern)))
+                .order((
+                    similarity(resellers::name, &query).desc(),
+                    similarity(resellers::email, &query).desc(),
+                ))
+                .limit(limit)
+                .load::<Reseller>(&mut conn)
+        }).await??;
+
+        Ok(resellers)
+    }
+
+    async fn search_projects(&self, query: &str, limit: i64) -> Result<Vec<Project>> {
+        let query = query.to_string();
+        let mut conn = self.pool.get()?;
+
+        let projects = tokio::task::spawn_blocking(move || {
+            if let Ok(id) = Uuid::parse_str(&query) {
+                return projects::table.find(id).limit(limit).load::<Project>(&mut conn);
+            }
+            projects::table
+                .filter(projects::name.ilike(contains(&query)))
+                .order(similarity(projects::name, &query).desc())
+                .limit(limit)
+                .load::<Project>(&mut conn)
+        }).await??;
+
+        Ok(projects)
+    }
+
+    async fn search_jobs(&self, query: &str, limit: i64) -> Result<Vec<Job>> {
+        let query = query.to_lowercase();
+        let mut conn = self.pool.get()?;
+
+        let jobs_db = tokio::task::spawn_blocking(move || {
+            if let Ok(id) = Uuid::parse_str(&query) {
+                return jobs::table
+                    .filter(jobs::id.eq(id))
+                    .select(JobDb::as_select())
+                    .limit(limit)
+                    .load(&mut conn);
+            }
+            let short_id = query.strip_prefix(PUBLIC_ID_PREFIX).unwrap_or(&query);
+            jobs::table
+                .filter(jobs::public_id.ilike(contains(short_id)))
+                .order(jobs::created_at.desc())
+                .select(JobDb::as_select())
+                .limit(limit)
+                .load(&mut conn)
+        }).await??;
+
+        Ok(jobs_db.into_iter().map(Job::from).collect())
+    }
+}
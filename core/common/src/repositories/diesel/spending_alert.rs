@@ -0,0 +1,94 @@
+use async_trait::async_trait;
+use chrono::{DateTime, Utc};
+use diesel::prelude::*;
+use crate::database::TenantPool;
+use anyhow::Result;
+use uuid::Uuid;
+
+use crate::models::spending_alert::{AnomalyKind, NewSpendingAlert, SpendingAlert};
+use crate::repositories::SpendingAlertRepository;
+use crate::diesel_schema::spending_alerts;
+
+/// Diesel implementation of the SpendingAlertRepository
+pub struct DieselSpendingAlertRepository {
+    pool: TenantPool,
+}
+
+impl DieselSpendingAlertRepository {
+    /// Create a new DieselSpendingAlertRepository with the given connection pool
+    pub fn new(pool: impl Into<TenantPool>) -> Self {
+        Self { pool: pool.into() }
+    }
+}
+
+#[async_trait]
+impl SpendingAlertRepository for DieselSpendingAlertRepository {
+    async fn create(&self, alert: NewSpendingAlert) -> Result<SpendingAlert> {
+        let mut conn = self.pool.get()?;
+
+        let alert = tokio::task::spawn_blocking(move || {
+            diesel::insert_into(spending_alerts::table)
+                .values(&alert)
+                .get_result::<SpendingAlert>(&mut conn)
+        }).await??;
+
+        Ok(alert)
+    }
+
+    async fn exists_since(&self, customer_id: Uuid, kind: AnomalyKind, since: DateTime<Utc>) -> Result<bool> {
+        let mut conn = self.pool.get()?;
+
+        let exists = tokio::task::spawn_blocking(move || {
+            diesel::select(diesel::dsl::exists(
+                spending_alerts::table
+                    .filter(spending_alerts::customer_id.eq(customer_id))
+                    .filter(spending_alerts::kind.eq(kind.as_str()))
+                    .filter(spending_alerts::created_at.ge(since)),
+            ))
+            .get_result::<bool>(&mut conn)
+        }).await??;
+
+        Ok(exists)
+    }
+
+    async fn list_by_customer(&self, customer_id: Uuid, limit: i64) -> Result<Vec<SpendingAlert>> {
+        let mut conn = self.pool.get()?;
+
+        let alerts = tokio::task::spawn_blocking(move || {
+            spending_alerts::table
+                .filter(spending_alerts::customer_id.eq(customer_id))
+                .order(spending_alerts::created_at.desc())
+                .limit(limit)
+                .load::<SpendingAlert>(&mut conn)
+        }).await??;
+
+        Ok(alerts)
+    }
+
+    async fn list_by_reseller(&self, reseller_id: Uuid, limit: i64) -> Result<Vec<SpendingAlert>> {
+        let mut conn = self.pool.get()?;
+
+        let alerts = tokio::task::spawn_blocking(move || {
+            spending_alerts::table
+                .filter(spending_alerts::reseller_id.eq(reseller_id))
+                .order(spending_alerts::created_at.desc())
+                .limit(limit)
+                .load::<SpendingAlert>(&mut conn)
+        }).await??;
+
+        Ok(alerts)
+    }
+
+    async fn list_recent(&self, limit: i64) -> Result<Vec<SpendingAlert>> {
+        let mut conn = self.pool.get()?;
+
+        let alerts = tokio::task::spawn_blocking(move || {
+            spending_alerts::table
+                .order(spending_alerts::created_at.desc())
+                .limit(limit)
+                .load::<SpendingAlert>(&mut conn)
+        }).await??;
+
+        Ok(alerts)
+    }
+}
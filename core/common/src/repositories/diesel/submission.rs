@@ -0,0 +1,74 @@
+use async_trait::async_trait;
+use diesel::prelude::*;
+use crate::database::TenantPool;
+use uuid::Uuid;
+use anyhow::{Result, anyhow};
+
+use crate::models::submission::{NewSubmission, Submission};
+use crate::repositories::SubmissionRepository;
+use crate::diesel_schema::submissions;
+
+/// Diesel implementation of the SubmissionRepository
+pub struct DieselSubmissionRepository {
+    pool: TenantPool,
+}
+
+impl DieselSubmissionRepository {
+    /// Create a new DieselSubmissionRepository with the given connection pool
+    pub fn new(pool: impl Into<TenantPool>) -> Self {
+        Self { pool: pool.into() }
+    }
+}
+
+#[async_trait]
+impl SubmissionRepository for DieselSubmissionRepository {
+    async fn create(&self, submission: NewSubmission) -> Result<Submission> {
+        let mut conn = self.pool.get()?;
+
+        let submission: Submission = tokio::task::spawn_blocking(move || {
+            diesel::insert_into(submissions::table)
+                .values(&submission)
+                .returning(Submission::as_select())
+                .get_result(&mut conn)
+        }).await??;
+
+        Ok(submission)
+    }
+
+    async fn find_by_id(&self, id: Uuid) -> Result<Submission> {
+        let mut conn = self.pool.get()?;
+
+        let submission: Submission = tokio::task::spawn_blocking(move || {
+            submissions::table
+                .find(id)
+                .select(Submission::as_select())
+                .first(&mut conn)
+                .optional()
+        }).await??
+            .ok_or_else(|| anyhow!("Submission not found with ID: {}", id))?;
+
+        Ok(submission)
+    }
+
+    async fn cancel(&self, id: Uuid) -> Result<Submission> {
+        let mut conn = self.pool.get()?;
+
+        let submission: Submission = tokio::task::spawn_blocking(move || {
+            conn.transaction(|conn| {
+                // Cancelling again keeps the first cancellation time
+                diesel::update(submissions::table.find(id))
+                    .filter(submissions::cancelled_at.is_null())
+                    .set(submissions::cancelled_at.eq(diesel::dsl::now))
+                    .execute(conn)?;
+                submissions::table
+                    .find(id)
+                    .select(Submission::as_select())
+                    .first(conn)
+                    .optional()
+            })
+        }).await??
+            .ok_or_else(|| anyhow!("Submission not found with ID: {}", id))?;
+
+        Ok(submission)
+    }
+}
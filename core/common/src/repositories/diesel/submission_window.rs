@@ -0,0 +1,112 @@
+use async_trait::async_trait;
+use diesel::prelude::*;
+use crate::database::TenantPool;
+use uuid::Uuid;
+use anyhow::{Result, anyhow};
+
+use crate::models::submission_window::{NewSubmissionWindow, SubmissionWindow};
+use crate::repositories::SubmissionWindowRepository;
+use crate::diesel_schema::submission_windows;
+
+/// Diesel implementation of the SubmissionWindowRepository
+pub struct DieselSubmissionWindowRepository {
+    pool: TenantPool,
+}
+
+impl DieselSubmissionWindowRepository {
+    /// Create a new DieselSubmissionWindowRepository with the given connection pool
+    pub fn new(pool: impl Into<TenantPool>) -> Self {
+        Self { pool: pool.into() }
+    }
+}
+
+#[async_trait]
+impl SubmissionWindowRepository for DieselSubmissionWindowRepository {
+    async fn create(&self, window: NewSubmissionWindow) -> Result<SubmissionWindow> {
+        let mut conn = self.pool.get()?;
+
+        let window: SubmissionWindow = tokio::task::spawn_blocking(move || {
+            diesel::insert_into(submission_windows::table)
+                .values(&window)
+                .get_result::<SubmissionWindow>(&mut conn)
+        }).await??;
+
+        Ok(window)
+    }
+
+    async fn find_by_id(&self, id: Uuid) -> Result<SubmissionWindow> {
+        let mut conn = self.pool.get()?;
+
+        let window: SubmissionWindow = tokio::task::spawn_blocking(move || {
+            submission_windows::table
+                .find(id)
+                .first(&mut conn)
+                .optional()
+        }).await??
+            .ok_or_else(|| anyhow!("Submission window not found with ID: {}", id))?;
+
+        Ok(window)
+    }
+
+    async fn find_by_customer_id(&self, customer_id: Uuid) -> Result<Vec<SubmissionWindow>> {
+        let mut conn = self.pool.get()?;
+
+        let windows: Vec<SubmissionWindow> = tokio::task::spawn_blocking(move || {
+            submission_windows::table
+                .filter(submission_windows::customer_id.eq(customer_id))
+                .order((submission_windows::job_type_id.asc(), submission_windows::start_time.asc()))
+                .load::<SubmissionWindow>(&mut conn)
+        }).await??;
+
+        Ok(windows)
+    }
+
+    async fn find_by_reseller_id(&self, reseller_id: Uuid) -> Result<Vec<SubmissionWindow>> {
+        let mut conn = self.pool.get()?;
+
+        let windows: Vec<SubmissionWindow> = tokio::task::spawn_blocking(move || {
+            submission_windows::table
+                .filter(submission_windows::reseller_id.eq(reseller_id))
+                .order((submission_windows::job_type_id.asc(), submission_windows::start_time.asc()))
+                .load::<SubmissionWindow>(&mut conn)
+        }).await??;
+
+        Ok(windows)
+    }
+
+    async fn find_applicable(&self, job_type_id: Uuid, customer_id: Uuid, reseller_id: Option<Uuid>) -> Result<Vec<SubmissionWindow>> {
+        let mut conn = self.pool.get()?;
+
+        let windows: Vec<SubmissionWindow> = tokio::task::spawn_blocking(move || {
+            submission_windows::table
+                .filter(submission_windows::job_type_id.eq(job_type_id))
+                .filter(
+                    submission_windows::customer_id.eq(customer_id)
+                        .or(submission_windows::reseller_id.nullable().eq(reseller_id))
+                )
+                .order(submission_windows::start_time.asc())
+                .load::<SubmissionWindow>(&mut conn)
+        }).await??;
+
+        // Customer windows override the reseller's
+        let (own, inherited): (Vec<_>, Vec<_>) = windows.into_iter()
+            .partition(|window| window.customer_id == Some(customer_id));
+
+        Ok(if own.is_empty() { inherited } else { own })
+    }
+
+    async fn delete(&self, id: Uuid) -> Result<()> {
+        let mut conn = self.pool.get()?;
+
+        let count = tokio::task::spawn_blocking(move || {
+            diesel::delete(submission_windows::table.find(id))
+                .execute(&mut conn)
+        }).await??;
+
+        if count == 0 {
+            return Err(anyhow!("Submission window not found with ID: {}", id));
+        }
+
+        Ok(())
+    }
+}
@@ -0,0 +1,70 @@
+use async_trait::async_trait;
+use diesel::prelude::*;
+use crate::database::TenantPool;
+use anyhow::{anyhow, Result};
+use uuid::Uuid;
+
+use crate::models::redaction::{NewUnredactedOutput, UnredactedOutput};
+use crate::repositories::UnredactedOutputRepository;
+use crate::diesel_schema::job_unredacted_outputs;
+
+/// Diesel implementation of the UnredactedOutputRepository
+pub struct DieselUnredactedOutputRepository {
+    pool: TenantPool,
+}
+
+impl DieselUnredactedOutputRepository {
+    /// Create a new DieselUnredactedOutputRepository with the given connection pool
+    pub fn new(pool: impl Into<TenantPool>) -> Self {
+        Self { pool: pool.into() }
+    }
+}
+
+#[async_trait]
+impl UnredactedOutputRepository for DieselUnredactedOutputRepository {
+    async fn store(&self, output: NewUnredactedOutput) -> Result<UnredactedOutput> {
+        let mut conn = self.pool.get()?;
+
+        let output = tokio::task::spawn_blocking(move || {
+            diesel::insert_into(job_unredacted_outputs::table)
+                .values(&output)
+                .on_conflict(job_unredacted_outputs::job_id)
+                .do_update()
+                .set((
+                    job_unredacted_outputs::output_data.eq(&output.output_data),
+                    job_unredacted_outputs::expires_at.eq(output.expires_at),
+                    job_unredacted_outputs::created_at.eq(diesel::dsl::now),
+                ))
+                .get_result::<UnredactedOutput>(&mut conn)
+        }).await??;
+
+        Ok(output)
+    }
+
+    async fn find_by_job_id(&self, job_id: Uuid) -> Result<UnredactedOutput> {
+        let mut conn = self.pool.get()?;
+
+        let output = tokio::task::spawn_blocking(move || {
+            job_unredacted_outputs::table
+                .find(job_id)
+                .filter(job_unredacted_outputs::expires_at.gt(diesel::dsl::now))
+                .first::<UnredactedOutput>(&mut conn)
+                .optional()
+        }).await??;
+
+        output.ok_or_else(|| anyhow!("Unredacted output not found for job ID: {}", job_id))
+    }
+
+    async fn purge_expired(&self) -> Result<usize> {
+        let mut conn = self.pool.get()?;
+
+        let purged = tokio::task::spawn_blocking(move || {
+            diesel::delete(
+                job_unredacted_outputs::table.filter(job_unredacted_outputs::expires_at.le(diesel::dsl::now)),
+            )
+            .execute(&mut conn)
+        }).await??;
+
+        Ok(purged)
+    }
+}
@@ -1,22 +1,167 @@
 use async_trait::async_trait;
 use diesel::prelude::*;
-use diesel::r2d2::{ConnectionManager, Pool};
+use diesel::dsl::sum;
+use crate::database::TenantPool;
 use uuid::Uuid;
 use anyhow::{Result, anyhow};
 use chrono::Utc;
+use serde_json::Value;
 
-use crate::diesel_schema::{wallets, wallet_transactions};
-use crate::models::wallet::{Wallet, NewWallet, WalletTransaction, NewWalletTransaction, TransactionType};
+use crate::diesel_schema::{jobs, wallets, wallet_transaction_references, wallet_transactions};
+use crate::errors::Error;
+use crate::models::ledger::JobDebit;
+use crate::models::wallet::{job_charge_reference, job_hold_reference, Wallet, NewWallet, OverdraftPolicy, WalletTransaction, NewWalletTransaction, TransactionType};
 use crate::repositories::WalletRepository;
+use super::BULK_INSERT_ROWS;
+use crate::repositories::diesel::billing_period::ensure_period_open;
+use crate::repositories::diesel::ledger::post_entries;
+
+/// Reference and metadata the customer gave a job, copied onto the job's wallet transactions
+pub(crate) fn job_reference(conn: &mut PgConnection, job_id: Option<Uuid>) -> QueryResult<(Option<String>, Option<Value>)> {
+    let Some(job_id) = job_id else {
+        return Ok((None, None));
+    };
+    Ok(jobs::table
+        .filter(jobs::id.eq(job_id))
+        .select((jobs::customer_reference, jobs::customer_metadata))
+        .first(conn)
+        .optional()?
+        .unwrap_or((None, None)))
+}
+
+/// Reference ID of a posting for a job, derived from the job and the postings already
+/// recorded for it on the wallet, or None for postings not bound to a job
+///
+/// Called with the wallet locked, so the reservation cycle it counts cannot move on.
+fn job_posting_reference(
+    conn: &mut PgConnection,
+    wallet_id: Uuid,
+    job_id: Option<Uuid>,
+    transaction_type: &TransactionType,
+) -> QueryResult<Option<Uuid>> {
+    let Some(job_id) = job_id else {
+        return Ok(None);
+    };
+    if *transaction_type == TransactionType::JobDebit {
+        return Ok(Some(job_charge_reference(job_id)));
+    }
+    let Some(counter) = transaction_type.cycle_counter() else {
+        return Ok(None);
+    };
+    let cycle: i64 = wallet_transactions::table
+        .filter(wallet_transactions::wallet_id.eq(wallet_id))
+        .filter(wallet_transactions::job_id.eq(job_id))
+        .filter(wallet_transactions::transaction_type.eq(counter.as_str()))
+        .count()
+        .get_result(conn)?;
+    Ok(job_hold_reference(job_id, transaction_type, cycle))
+}
+
+/// Record the reference ID of a posting to a wallet, returning the transaction already
+/// recorded under it instead if there is one
+pub(crate) fn claim_reference(
+    conn: &mut PgConnection,
+    wallet_id: Uuid,
+    reference_id: Uuid,
+    transaction_id: Uuid,
+) -> QueryResult<Option<Uuid>> {
+    let claimed = diesel::insert_into(wallet_transaction_references::table)
+        .values((
+            wallet_transaction_references::wallet_id.eq(wallet_id),
+            wallet_transaction_references::reference_id.eq(reference_id),
+            wallet_transaction_references::transaction_id.eq(transaction_id),
+        ))
+        .on_conflict_do_nothing()
+        .execute(conn)?;
+    if claimed == 1 {
+        return Ok(None);
+    }
+    wallet_transaction_references::table
+        .find((wallet_id, reference_id))
+        .select(wallet_transaction_references::transaction_id)
+        .first(conn)
+        .map(Some)
+}
+
+/// Post an amount to a wallet with its transaction record, once `check` accepts the
+/// wallet as it is before the posting, returning the wallet and the record's ID
+///
+/// The wallet row stays locked until the posting commits, so concurrent postings see
+/// each other's balance instead of all passing the check against the same one. A posting
+/// whose reference ID the wallet recorded already is not posted again: the wallet is
+/// returned as it is, without a record ID. Postings for a job get a reference derived
+/// from the job unless given one.
+#[allow(clippy::too_many_arguments)]
+fn post_to_wallet(
+    conn: &mut PgConnection,
+    id: Uuid,
+    amount: i32,
+    transaction_type: TransactionType,
+    description: Option<String>,
+    job_id: Option<Uuid>,
+    reference_id: Option<Uuid>,
+    check: impl FnOnce(&Wallet) -> Result<()>,
+) -> Result<(Wallet, Option<Uuid>)> {
+    conn.transaction(|conn| {
+        let wallet = wallets::table
+            .find(id)
+            .for_update()
+            .first::<Wallet>(conn)
+            .optional()?
+            .ok_or_else(|| anyhow!("Wallet not found with ID: {}", id))?;
+        
+        // A retried operation that went through already is not checked or posted again
+        let transaction_id = Uuid::new_v4();
+        let reference_id = match reference_id {
+            Some(reference_id) => Some(reference_id),
+            None => job_posting_reference(conn, id, job_id, &transaction_type)?,
+        };
+        if let Some(reference_id) = reference_id {
+            if let Some(posted) = claim_reference(conn, id, reference_id, transaction_id)? {
+                tracing::info!("{} of {} cents to wallet {} was posted already as transaction {}", transaction_type.as_str(), amount, id, posted);
+                return Ok((wallet, None));
+            }
+        }
+        check(&wallet)?;
+        
+        // Create a transaction record, carrying the customer's reference for the job
+        let (customer_reference, customer_metadata) = job_reference(conn, job_id)?;
+        let transaction = NewWalletTransaction {
+            id: transaction_id,
+            wallet_id: id,
+            amount_cents: amount,
+            transaction_type: transaction_type.to_string(),
+            customer_id: wallet.customer_id,
+            reference_id,
+            description,
+            job_id,
+            created_at: None,
+            customer_reference,
+            customer_metadata,
+        };
+        diesel::insert_into(wallet_transactions::table)
+            .values(&transaction)
+            .execute(conn)?;
+        
+        let updated_wallet = diesel::update(wallets::table.find(id))
+            .set((
+                wallets::balance_cents.eq(wallet.balance_cents + amount),
+                wallets::updated_at.eq(Utc::now()),
+            ))
+            .get_result::<Wallet>(conn)?;
+        
+        Ok((updated_wallet, Some(transaction.id)))
+    })
+}
 
 /// Diesel-backed implementation of WalletRepository
 pub struct DieselWalletRepository {
-    pool: Pool<ConnectionManager<PgConnection>>,
+    pool: TenantPool,
 }
 
 impl DieselWalletRepository {
-    pub fn new(pool: Pool<ConnectionManager<PgConnection>>) -> Self {
-        Self { pool }
+    pub fn new(pool: impl Into<TenantPool>) -> Self {
+        Self { pool: pool.into() }
     }
 }
 
@@ -40,6 +185,24 @@ impl WalletRepository for DieselWalletRepository {
         Ok(wallet)
     }
 
+    async fn create_many(&self, new_wallets: Vec<NewWallet>) -> Result<Vec<Wallet>> {
+        let mut conn = self.pool.get()?;
+        
+        let wallets: Vec<Wallet> = tokio::task::spawn_blocking(move || {
+            conn.transaction(|conn| {
+                let mut created = Vec::with_capacity(new_wallets.len());
+                for chunk in new_wallets.chunks(BULK_INSERT_ROWS) {
+                    created.extend(diesel::insert_into(wallets::table)
+                        .values(chunk)
+                        .get_results::<Wallet>(conn)?);
+                }
+                Ok::<_, diesel::result::Error>(created)
+            }).map_err(|e| anyhow!("Failed to create wallets: {}", e))
+        }).await??;
+        
+        Ok(wallets)
+    }
+
     async fn find_by_id(&self, id: Uuid) -> Result<Wallet> {
         let mut conn = self.pool.get()?;
         
@@ -78,48 +241,10 @@ impl WalletRepository for DieselWalletRepository {
     ) -> Result<Wallet> {
         let mut conn = self.pool.get()?;
         
-        // Create a transaction to ensure atomicity
-        let (wallet, _) = tokio::task::spawn_blocking(move || -> Result<(Wallet, WalletTransaction)> {
-            conn.transaction(|conn| {
-                // First get the wallet to check available balance
-                let wallet = wallets::table
-                    .find(id)
-                    .first::<Wallet>(conn)?;
-                
-                // Calculate new balance
-                let new_balance = wallet.balance_cents + amount;
-                
-                // Create a transaction record
-                let transaction = NewWalletTransaction {
-                    id: Uuid::new_v4(),
-                    wallet_id: id,
-                    amount_cents: amount,
-                    transaction_type: transaction_type.to_string(),
-                    customer_id: wallet.customer_id,
-                    reference_id: None,
-                    description,
-                    job_id,
-                    created_at: None,
-                };
-                
-                // Insert the transaction record
-                let transaction_record = diesel::insert_into(wallet_transactions::table)
-                    .values(&transaction)
-                    .get_result::<WalletTransaction>(conn)?;
-                
-                // Update the wallet balance
-                let updated_wallet = diesel::update(wallets::table.find(id))
-                    .set((
-                        wallets::balance_cents.eq(new_balance),
-                        wallets::updated_at.eq(Utc::now().naive_utc()),
-                    ))
-                    .get_result::<Wallet>(conn)?;
-                
-                Ok((updated_wallet, transaction_record))
-            })
-        }).await??;
-        
-        Ok(wallet)
+        tokio::task::spawn_blocking(move || {
+            post_to_wallet(&mut conn, id, amount, transaction_type, description, job_id, None, |_| Ok(()))
+                .map(|(wallet, _)| wallet)
+        }).await?
     }
     
     async fn deposit(
@@ -153,19 +278,51 @@ impl WalletRepository for DieselWalletRepository {
             return Err(anyhow!("Withdrawal amount must be positive"));
         }
         
-        // Check if there are sufficient funds
-        let wallet = self.find_by_id(id).await?;
-        if wallet.balance_cents < amount {
-            return Err(anyhow!("Insufficient funds for withdrawal"));
+        let mut conn = self.pool.get()?;
+        let description = description.or_else(|| Some(format!("Withdrawal of {} cents", amount)));
+        
+        // Withdrawals never draw on the overdraft; the balance is checked under the wallet's lock
+        tokio::task::spawn_blocking(move || {
+            post_to_wallet(&mut conn, id, -amount, TransactionType::Withdrawal, description, job_id, None, |wallet| {
+                if wallet.balance_cents < amount {
+                    return Err(Error::InsufficientFunds(format!(
+                        "Insufficient funds for withdrawal. Available: {}, requested: {}", wallet.balance_cents, amount
+                    )).into());
+                }
+                Ok(())
+            }).map(|(wallet, _)| wallet)
+        }).await?
+    }
+
+    async fn charge_job(&self, debit: JobDebit) -> Result<Wallet> {
+        let amount = debit.amount_cents();
+        if amount <= 0 {
+            return Err(anyhow!("Job charge must be positive"));
         }
         
-        self.update_balance(
-            id, 
-            -amount, // Negative for withdrawal
-            TransactionType::Withdrawal,
-            description.or_else(|| Some(format!("Withdrawal of {} cents", amount))),
-            job_id
-        ).await
+        let mut conn = self.pool.get()?;
+        let postings = debit.postings();
+        let description = debit.description.clone()
+            .or_else(|| Some(format!("Charge of {} cents for job {}", amount, debit.job_id)));
+        
+        // The debit and its ledger entries commit together, under the wallet's lock
+        tokio::task::spawn_blocking(move || {
+            conn.transaction(|conn| {
+                let (wallet, transaction_id) = post_to_wallet(conn, debit.wallet_id, -amount, TransactionType::JobDebit, description, Some(debit.job_id), Some(debit.reference_id), |wallet| {
+                    if debit.require_funds && wallet.balance_cents < amount {
+                        return Err(Error::InsufficientFunds(format!(
+                            "Insufficient funds for job charge. Available: {}, requested: {}", wallet.balance_cents, amount
+                        )).into());
+                    }
+                    Ok(())
+                })?;
+                // A charge posted already has its ledger entries
+                if let Some(transaction_id) = transaction_id {
+                    post_entries(conn, transaction_id, Some(debit.job_id), &postings)?;
+                }
+                Ok(wallet)
+            })
+        }).await?
     }
 
     async fn reserve_funds(
@@ -179,19 +336,21 @@ impl WalletRepository for DieselWalletRepository {
             return Err(anyhow!("Reservation amount must be positive"));
         }
         
-        // Check if there are sufficient funds
-        let wallet = self.find_by_id(id).await?;
-        if wallet.balance_cents < amount {
-            return Err(anyhow!("Insufficient funds for reservation"));
-        }
+        let mut conn = self.pool.get()?;
+        let description = description.or_else(|| Some(format!("Reservation of {} cents", amount)));
         
-        self.update_balance(
-            id, 
-            -amount, // Negative for reservation
-            TransactionType::Reserved,
-            description.or_else(|| Some(format!("Reservation of {} cents", amount))),
-            job_id
-        ).await
+        // Concurrent reservations queue on the wallet's lock, so together they cannot
+        // take the balance below the floor of its overdraft policy
+        tokio::task::spawn_blocking(move || {
+            post_to_wallet(&mut conn, id, -amount, TransactionType::Reserved, description, job_id, None, |wallet| {
+                if !wallet.can_reserve(amount) {
+                    return Err(Error::InsufficientFunds(format!(
+                        "Insufficient funds for reservation. Available: {}, requested: {}", wallet.available_balance(), amount
+                    )).into());
+                }
+                Ok(())
+            }).map(|(wallet, _)| wallet)
+        }).await?
     }
 
     async fn release_reservation(
@@ -214,18 +373,44 @@ impl WalletRepository for DieselWalletRepository {
         ).await
     }
 
-    async fn add_transaction(&self, new_transaction: NewWalletTransaction) -> Result<WalletTransaction> {
+    async fn add_transaction(&self, mut new_transaction: NewWalletTransaction) -> Result<WalletTransaction> {
         let mut conn = self.pool.get()?;
         let wallet_id = new_transaction.wallet_id;
         
         // Use a transaction to ensure atomicity
         let (transaction, _) = tokio::task::spawn_blocking(move || -> Result<(WalletTransaction, Wallet)> {
             conn.transaction(|conn| {
-                // First check if the wallet exists
+                // First check if the wallet exists, locking it against concurrent postings
                 let wallet = wallets::table
                     .find(wallet_id)
+                    .for_update()
                     .first::<Wallet>(conn)?;
                 
+                // Backdated transactions must not reopen a closed billing period
+                if let Some(created_at) = new_transaction.created_at {
+                    ensure_period_open(conn, created_at)?;
+                }
+                
+                // A transaction whose reference the wallet recorded already is returned as it was
+                if new_transaction.reference_id.is_none() {
+                    if let Some(transaction_type) = TransactionType::from_str(&new_transaction.transaction_type) {
+                        new_transaction.reference_id = job_posting_reference(conn, wallet_id, new_transaction.job_id, &transaction_type)?;
+                    }
+                }
+                if let Some(reference_id) = new_transaction.reference_id {
+                    if let Some(posted) = claim_reference(conn, wallet_id, reference_id, new_transaction.id)? {
+                        let transaction_record = wallet_transactions::table
+                            .filter(wallet_transactions::id.eq(posted))
+                            .first::<WalletTransaction>(conn)?;
+                        return Ok((transaction_record, wallet));
+                    }
+                }
+                
+                // Job transactions carry the customer's reference for the job unless given one
+                if new_transaction.customer_reference.is_none() && new_transaction.customer_metadata.is_none() {
+                    (new_transaction.customer_reference, new_transaction.customer_metadata) = job_reference(conn, new_transaction.job_id)?;
+                }
+                
                 // Insert the transaction record
                 let transaction_record = diesel::insert_into(wallet_transactions::table)
                     .values(&new_transaction)
@@ -236,7 +421,7 @@ impl WalletRepository for DieselWalletRepository {
                 let updated_wallet = diesel::update(wallets::table.find(wallet_id))
                     .set((
                         wallets::balance_cents.eq(new_balance),
-                        wallets::updated_at.eq(Utc::now().naive_utc()),
+                        wallets::updated_at.eq(Utc::now()),
                     ))
                     .get_result::<Wallet>(conn)?;
                 
@@ -267,6 +452,114 @@ impl WalletRepository for DieselWalletRepository {
         Ok(transactions)
     }
     
+    async fn adjust_test_balance(&self, id: Uuid, amount: i32) -> Result<Wallet> {
+        let mut conn = self.pool.get()?;
+        
+        let wallet: Wallet = tokio::task::spawn_blocking(move || {
+            diesel::update(wallets::table.find(id))
+                .set((
+                    wallets::test_balance_cents.eq(wallets::test_balance_cents + amount),
+                    wallets::updated_at.eq(Utc::now()),
+                ))
+                .get_result::<Wallet>(&mut conn)
+                .optional()
+        }).await??
+            .ok_or_else(|| anyhow!("Wallet not found with ID: {}", id))?;
+        
+        Ok(wallet)
+    }
+    
+    async fn grant_credit(&self, id: Uuid, amount: i32) -> Result<Wallet> {
+        if amount <= 0 {
+            return Err(anyhow!("Credit must be positive"));
+        }
+        let mut conn = self.pool.get()?;
+        
+        let wallet: Wallet = tokio::task::spawn_blocking(move || {
+            diesel::update(wallets::table.find(id))
+                .set((
+                    wallets::credit_cents.eq(wallets::credit_cents + amount),
+                    wallets::updated_at.eq(Utc::now()),
+                ))
+                .get_result::<Wallet>(&mut conn)
+                .optional()
+        }).await??
+            .ok_or_else(|| anyhow!("Wallet not found with ID: {}", id))?;
+        
+        Ok(wallet)
+    }
+    
+    async fn spend_credit(&self, id: Uuid, amount: i32) -> Result<i32> {
+        if amount <= 0 {
+            return Ok(0);
+        }
+        let mut conn = self.pool.get()?;
+        
+        // Lock the wallet so concurrent charges cannot spend the same credit twice
+        let spent = tokio::task::spawn_blocking(move || {
+            conn.transaction::<_, anyhow::Error, _>(|conn| {
+                let credit_cents: i32 = wallets::table
+                    .find(id)
+                    .select(wallets::credit_cents)
+                    .for_update()
+                    .first(conn)
+                    .optional()?
+                    .ok_or_else(|| anyhow!("Wallet not found with ID: {}", id))?;
+                let spent = amount.min(credit_cents);
+                if spent > 0 {
+                    diesel::update(wallets::table.find(id))
+                        .set((
+                            wallets::credit_cents.eq(wallets::credit_cents - spent),
+                            wallets::updated_at.eq(Utc::now()),
+                        ))
+                        .execute(conn)?;
+                }
+                Ok(spent)
+            })
+        }).await??;
+        
+        Ok(spent)
+    }
+    
+    async fn get_reserved_for_job(&self, job_id: Uuid) -> Result<i32> {
+        let mut conn = self.pool.get()?;
+        
+        // Reservations are recorded as negative amounts and releases as positive ones
+        let net: Option<i64> = tokio::task::spawn_blocking(move || {
+            wallet_transactions::table
+                .filter(wallet_transactions::job_id.eq(job_id))
+                .filter(wallet_transactions::transaction_type.eq_any([
+                    TransactionType::Reserved.as_str(),
+                    TransactionType::Released.as_str(),
+                ]))
+                .select(sum(wallet_transactions::amount_cents))
+                .first(&mut conn)
+        }).await??;
+        
+        Ok((-net.unwrap_or(0)).max(0) as i32)
+    }
+
+    async fn set_overdraft_policy(&self, id: Uuid, policy: OverdraftPolicy, limit_cents: i32) -> Result<Wallet> {
+        if limit_cents < 0 {
+            return Err(anyhow!("Overdraft limit cannot be negative"));
+        }
+        let mut conn = self.pool.get()?;
+        
+        let wallet: Wallet = tokio::task::spawn_blocking(move || {
+            diesel::update(wallets::table.find(id))
+                .set((
+                    wallets::overdraft_policy.eq(policy.as_str()),
+                    wallets::overdraft_limit_cents.eq(limit_cents),
+                    wallets::updated_at.eq(Utc::now()),
+                ))
+                .get_result::<Wallet>(&mut conn)
+                .optional()
+        }).await??
+            .ok_or_else(|| anyhow!("Wallet not found with ID: {}", id))?;
+        
+        Ok(wallet)
+    }
+
     async fn get_balance(&self, id: Uuid) -> Result<i32> {
         let wallet = self.find_by_id(id).await?;
         Ok(wallet.balance_cents)
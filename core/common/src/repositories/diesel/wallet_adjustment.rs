@@ -0,0 +1,210 @@
+use async_trait::async_trait;
+use chrono::Utc;
+use serde_json::{json, Value};
+use diesel::prelude::*;
+use crate::database::TenantPool;
+use uuid::Uuid;
+use anyhow::{Result, anyhow};
+
+use crate::models::audit::NewAuditEntry;
+use crate::models::wallet::{NewWalletTransaction, Wallet};
+use crate::models::wallet_adjustment::{AdjustmentError, AdjustmentStatus, NewWalletAdjustment, WalletAdjustment};
+use crate::repositories::WalletAdjustmentRepository;
+use crate::repositories::diesel::wallet::{claim_reference, job_reference};
+use crate::diesel_schema::{audit_log, wallet_adjustments, wallet_transactions, wallets};
+
+/// Diesel implementation of the WalletAdjustmentRepository
+pub struct DieselWalletAdjustmentRepository {
+    pool: TenantPool,
+}
+
+impl DieselWalletAdjustmentRepository {
+    /// Create a new DieselWalletAdjustmentRepository with the given connection pool
+    pub fn new(pool: impl Into<TenantPool>) -> Self {
+        Self { pool: pool.into() }
+    }
+}
+
+/// Lock a pending adjustment for review by `reviewed_by`
+fn lock_for_review(conn: &mut PgConnection, id: Uuid, reviewed_by: &str) -> Result<WalletAdjustment> {
+    let adjustment: WalletAdjustment = wallet_adjustments::table
+        .find(id)
+        .for_update()
+        .first(conn)
+        .optional()?
+        .ok_or_else(|| anyhow!("Wallet adjustment not found with ID: {}", id))?;
+
+    if adjustment.status() != Some(AdjustmentStatus::Pending) {
+        return Err(AdjustmentError::NotPending(adjustment.status.clone()).into());
+    }
+    if adjustment.requires_approval && adjustment.requested_by == reviewed_by {
+        return Err(AdjustmentError::SameReviewer.into());
+    }
+    Ok(adjustment)
+}
+
+/// Append a step of an adjustment's review chain to the audit log
+fn record_audit(conn: &mut PgConnection, actor: &str, action: &str, adjustment: &WalletAdjustment, details: Value) -> QueryResult<usize> {
+    diesel::insert_into(audit_log::table)
+        .values(NewAuditEntry::new(actor, action, "wallet_adjustment", adjustment.id, details))
+        .execute(conn)
+}
+
+#[async_trait]
+impl WalletAdjustmentRepository for DieselWalletAdjustmentRepository {
+    async fn create(&self, adjustment: NewWalletAdjustment) -> Result<WalletAdjustment> {
+        adjustment.validate()?;
+        let mut conn = self.pool.get()?;
+
+        let adjustment: WalletAdjustment = tokio::task::spawn_blocking(move || {
+            conn.transaction(|conn| {
+                let created = diesel::insert_into(wallet_adjustments::table)
+                    .values(&adjustment)
+                    .get_result::<WalletAdjustment>(conn)?;
+                record_audit(conn, &created.requested_by, "wallet_adjustment.requested", &created, json!({
+                    "wallet_id": created.wallet_id,
+                    "customer_id": created.customer_id,
+                    "kind": created.kind,
+                    "amount_cents": created.amount_cents,
+                    "description": created.description,
+                    "requires_approval": created.requires_approval,
+                }))?;
+                Ok::<_, diesel::result::Error>(created)
+            })
+        }).await??;
+
+        Ok(adjustment)
+    }
+
+    async fn find_by_id(&self, id: Uuid) -> Result<WalletAdjustment> {
+        let mut conn = self.pool.get()?;
+
+        let adjustment: WalletAdjustment = tokio::task::spawn_blocking(move || {
+            wallet_adjustments::table
+                .find(id)
+                .first(&mut conn)
+                .optional()
+        }).await??
+            .ok_or_else(|| anyhow!("Wallet adjustment not found with ID: {}", id))?;
+
+        Ok(adjustment)
+    }
+
+    async fn find_by_status(&self, status: AdjustmentStatus) -> Result<Vec<WalletAdjustment>> {
+        let mut conn = self.pool.get()?;
+
+        let adjustments = tokio::task::spawn_blocking(move || {
+            wallet_adjustments::table
+                .filter(wallet_adjustments::status.eq(status.as_str()))
+                .order(wallet_adjustments::created_at.asc())
+                .load::<WalletAdjustment>(&mut conn)
+        }).await??;
+
+        Ok(adjustments)
+    }
+
+    async fn find_by_wallet_id(&self, wallet_id: Uuid) -> Result<Vec<WalletAdjustment>> {
+        let mut conn = self.pool.get()?;
+
+        let adjustments = tokio::task::spawn_blocking(move || {
+            wallet_adjustments::table
+                .filter(wallet_adjustments::wallet_id.eq(wallet_id))
+                .order(wallet_adjustments::created_at.desc())
+                .load::<WalletAdjustment>(&mut conn)
+        }).await??;
+
+        Ok(adjustments)
+    }
+
+    async fn approve(&self, id: Uuid, reviewed_by: String, note: Option<String>) -> Result<WalletAdjustment> {
+        let mut conn = self.pool.get()?;
+
+        tokio::task::spawn_blocking(move || {
+            conn.transaction(|conn| {
+                let adjustment = lock_for_review(conn, id, &reviewed_by)?;
+
+                let wallet: Wallet = wallets::table
+                    .find(adjustment.wallet_id)
+                    .for_update()
+                    .first(conn)?;
+                let new_balance = wallet.balance_cents + adjustment.amount_cents;
+                if new_balance < 0 {
+                    return Err(AdjustmentError::InsufficientFunds {
+                        balance_cents: wallet.balance_cents,
+                        amount_cents: -adjustment.amount_cents,
+                    }.into());
+                }
+
+                // The transaction refers back to the adjustment that posted it
+                let (customer_reference, customer_metadata) = job_reference(conn, adjustment.job_id)?;
+                let transaction = NewWalletTransaction {
+                    id: Uuid::new_v4(),
+                    wallet_id: wallet.id,
+                    amount_cents: adjustment.amount_cents,
+                    transaction_type: adjustment.transaction_type().to_string(),
+                    customer_id: wallet.customer_id,
+                    reference_id: Some(adjustment.id),
+                    description: adjustment.description.clone(),
+                    job_id: adjustment.job_id,
+                    created_at: None,
+                    customer_reference,
+                    customer_metadata,
+                };
+                if claim_reference(conn, wallet.id, adjustment.id, transaction.id)?.is_some() {
+                    return Err(AdjustmentError::NotPending(AdjustmentStatus::Approved.as_str().to_string()).into());
+                }
+                diesel::insert_into(wallet_transactions::table)
+                    .values(&transaction)
+                    .execute(conn)?;
+                diesel::update(wallets::table.find(wallet.id))
+                    .set((
+                        wallets::balance_cents.eq(new_balance),
+                        wallets::updated_at.eq(Utc::now()),
+                    ))
+                    .execute(conn)?;
+
+                let approved = diesel::update(wallet_adjustments::table.find(id))
+                    .set((
+                        wallet_adjustments::status.eq(AdjustmentStatus::Approved.as_str()),
+                        wallet_adjustments::reviewed_by.eq(&reviewed_by),
+                        wallet_adjustments::review_note.eq(note),
+                        wallet_adjustments::transaction_id.eq(transaction.id),
+                        wallet_adjustments::reviewed_at.eq(Utc::now()),
+                    ))
+                    .get_result::<WalletAdjustment>(conn)?;
+                record_audit(conn, &reviewed_by, "wallet_adjustment.approved", &approved, json!({
+                    "requested_by": approved.requested_by,
+                    "note": approved.review_note,
+                    "transaction_id": transaction.id,
+                    "amount_cents": approved.amount_cents,
+                    "balance_cents": new_balance,
+                }))?;
+                Ok(approved)
+            })
+        }).await?
+    }
+
+    async fn reject(&self, id: Uuid, reviewed_by: String, note: Option<String>) -> Result<WalletAdjustment> {
+        let mut conn = self.pool.get()?;
+
+        tokio::task::spawn_blocking(move || {
+            conn.transaction(|conn| {
+                lock_for_review(conn, id, &reviewed_by)?;
+
+                let rejected = diesel::update(wallet_adjustments::table.find(id))
+                    .set((
+                        wallet_adjustments::status.eq(AdjustmentStatus::Rejected.as_str()),
+                        wallet_adjustments::reviewed_by.eq(&reviewed_by),
+                        wallet_adjustments::review_note.eq(note),
+                        wallet_adjustments::reviewed_at.eq(Utc::now()),
+                    ))
+                    .get_result::<WalletAdjustment>(conn)?;
+                record_audit(conn, &reviewed_by, "wallet_adjustment.rejected", &rejected, json!({
+                    "requested_by": rejected.requested_by,
+                    "note": rejected.review_note,
+                }))?;
+                Ok(rejected)
+            })
+        }).await?
+    }
+}
@@ -1,23 +1,24 @@
 use async_trait::async_trait;
 use diesel::prelude::*;
-use diesel::r2d2::{ConnectionManager, Pool};
+use crate::database::TenantPool;
 use uuid::Uuid;
 use anyhow::{Result, anyhow};
-use chrono::NaiveDateTime;
+use chrono::{DateTime, Utc};
 
 use crate::models::wallet::{WalletTransaction, NewWalletTransaction, TransactionType};
 use crate::repositories::WalletTransactionRepository;
+use crate::repositories::diesel::billing_period::ensure_period_open;
 use crate::diesel_schema::wallet_transactions;
 
 /// Diesel implementation of the WalletTransactionRepository
 pub struct DieselWalletTransactionRepository {
-    pool: Pool<ConnectionManager<PgConnection>>,
+    pool: TenantPool,
 }
 
 impl DieselWalletTransactionRepository {
     /// Create a new DieselWalletTransactionRepository with the given connection pool
-    pub fn new(pool: Pool<ConnectionManager<PgConnection>>) -> Self {
-        Self { pool }
+    pub fn new(pool: impl Into<TenantPool>) -> Self {
+        Self { pool: pool.into() }
     }
 }
 
@@ -26,11 +27,14 @@ impl WalletTransactionRepository for DieselWalletTransactionRepository {
     async fn create(&self, transaction: NewWalletTransaction) -> Result<WalletTransaction> {
         let mut conn = self.pool.get()?;
         
-        // Insert the new transaction
-        let transaction: WalletTransaction = tokio::task::spawn_blocking(move || {
-            diesel::insert_into(wallet_transactions::table)
+        // Insert the new transaction, unless it is backdated into a closed billing period
+        let transaction: WalletTransaction = tokio::task::spawn_blocking(move || -> Result<WalletTransaction> {
+            if let Some(created_at) = transaction.created_at {
+                ensure_period_open(&mut conn, created_at)?;
+            }
+            Ok(diesel::insert_into(wallet_transactions::table)
                 .values(&transaction)
-                .get_result(&mut conn)
+                .get_result(&mut conn)?)
         }).await??;
         
         Ok(transaction)
@@ -76,7 +80,7 @@ impl WalletTransactionRepository for DieselWalletTransactionRepository {
         Ok(transactions)
     }
     
-    async fn find_in_time_range(&self, start_time: NaiveDateTime, end_time: NaiveDateTime) -> Result<Vec<WalletTransaction>> {
+    async fn find_in_time_range(&self, start_time: DateTime<Utc>, end_time: DateTime<Utc>) -> Result<Vec<WalletTransaction>> {
         let mut conn = self.pool.get()?;
         
         let transactions: Vec<WalletTransaction> = tokio::task::spawn_blocking(move || {
@@ -114,6 +118,20 @@ impl WalletTransactionRepository for DieselWalletTransactionRepository {
                 .load::<WalletTransaction>(&mut conn)
         }).await??;
         
+        Ok(transactions)
+    }    
+    async fn find_by_customer_reference(&self, customer_id: Uuid, reference: &str) -> Result<Vec<WalletTransaction>> {
+        let mut conn = self.pool.get()?;
+        let reference = reference.to_string();
+        
+        let transactions: Vec<WalletTransaction> = tokio::task::spawn_blocking(move || {
+            wallet_transactions::table
+                .filter(wallet_transactions::customer_id.eq(customer_id))
+                .filter(wallet_transactions::customer_reference.eq(reference))
+                .order(wallet_transactions::created_at.desc())
+                .load::<WalletTransaction>(&mut conn)
+        }).await??;
+        
         Ok(transactions)
     }
 }
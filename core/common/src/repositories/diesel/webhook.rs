@@ -0,0 +1,180 @@
+use async_trait::async_trait;
+use diesel::prelude::*;
+use crate::database::TenantPool;
+use uuid::Uuid;
+use anyhow::{Result, anyhow};
+use chrono::{DateTime, Utc};
+
+use crate::models::webhook::{CustomerWebhook, NewCustomerWebhook, WebhookEventType};
+use crate::repositories::CustomerWebhookRepository;
+use crate::diesel_schema::customer_webhooks;
+
+/// Diesel implementation of the CustomerWebhookRepository
+pub struct DieselCustomerWebhookRepository {
+    pool: TenantPool,
+}
+
+impl DieselCustomerWebhookRepository {
+    /// Create a new DieselCustomerWebhookRepository with the given connection pool
+    pub fn new(pool: impl Into<TenantPool>) -> Self {
+        Self { pool: pool.into() }
+    }
+}
+
+#[async_trait]
+impl CustomerWebhookRepository for DieselCustomerWebhookRepository {
+    async fn create(&self, webhook: NewCustomerWebhook) -> Result<CustomerWebhook> {
+        let mut conn = self.pool.get()?;
+
+        // Insert the new webhook endpoint
+        let webhook: CustomerWebhook = tokio::task::spawn_blocking(move || {
+            diesel::insert_into(customer_webhooks::table)
+                .values(&webhook)
+                .get_result::<CustomerWebhook>(&mut conn)
+        }).await??;
+
+        Ok(webhook)
+    }
+
+    async fn find_by_id(&self, id: Uuid) -> Result<CustomerWebhook> {
+        let mut conn = self.pool.get()?;
+
+        let webhook: CustomerWebhook = tokio::task::spawn_blocking(move || {
+            customer_webhooks::table
+                .find(id)
+                .first(&mut conn)
+                .optional()
+        }).await??
+            .ok_or_else(|| anyhow!("Webhook not found with ID: {}", id))?;
+
+        Ok(webhook)
+    }
+
+    async fn find_by_customer_id(&self, customer_id: Uuid) -> Result<Vec<CustomerWebhook>> {
+        let mut conn = self.pool.get()?;
+
+        let webhooks: Vec<CustomerWebhook> = tokio::task::spawn_blocking(move || {
+            customer_webhooks::table
+                .filter(customer_webhooks::customer_id.eq(customer_id))
+                .order(customer_webhooks::created_at.asc())
+                .load::<CustomerWebhook>(&mut conn)
+        }).await??;
+
+        Ok(webhooks)
+    }
+
+    async fn find_active_for_event(&self, customer_id: Uuid, event_type: WebhookEventType) -> Result<Vec<CustomerWebhook>> {
+        let mut conn = self.pool.get()?;
+        let event_name = event_type.as_str().to_string();
+
+        let webhooks: Vec<CustomerWebhook> = tokio::task::spawn_blocking(move || {
+            customer_webhooks::table
+                .filter(customer_webhooks::customer_id.eq(customer_id))
+                .filter(customer_webhooks::active.eq(true))
+                .filter(customer_webhooks::event_types.contains(vec![event_name]))
+                .load::<CustomerWebhook>(&mut conn)
+        }).await??;
+
+        Ok(webhooks)
+    }
+
+    async fn update(&self, webhook: &CustomerWebhook) -> Result<CustomerWebhook> {
+        let webhook_clone = webhook.clone();
+        let mut conn = self.pool.get()?;
+
+        let updated_webhook = tokio::task::spawn_blocking(move || {
+            diesel::update(customer_webhooks::table.find(webhook_clone.id))
+                .set((
+                    customer_webhooks::url.eq(&webhook_clone.url),
+                    customer_webhooks::event_types.eq(&webhook_clone.event_types),
+                    customer_webhooks::active.eq(webhook_clone.active),
+                    customer_webhooks::failing_since.eq(webhook_clone.failing_since),
+                    customer_webhooks::updated_at.eq(Utc::now()),
+                ))
+                .get_result::<CustomerWebhook>(&mut conn)
+        }).await??;
+
+        Ok(updated_webhook)
+    }
+
+    async fn record_test_result(&self, id: Uuid, status_code: Option<i32>, response: Option<String>) -> Result<CustomerWebhook> {
+        let mut conn = self.pool.get()?;
+
+        let webhook = tokio::task::spawn_blocking(move || {
+            diesel::update(customer_webhooks::table.find(id))
+                .set((
+                    customer_webhooks::last_tested_at.eq(Utc::now()),
+                    customer_webhooks::last_test_status_code.eq(status_code),
+                    customer_webhooks::last_test_response.eq(response),
+                ))
+                .get_result::<CustomerWebhook>(&mut conn)
+        }).await??;
+
+        Ok(webhook)
+    }
+
+    async fn set_failing_since(&self, id: Uuid, failing_since: Option<DateTime<Utc>>) -> Result<CustomerWebhook> {
+        let mut conn = self.pool.get()?;
+
+        let webhook = tokio::task::spawn_blocking(move || {
+            diesel::update(customer_webhooks::table.find(id))
+                .set(customer_webhooks::failing_since.eq(failing_since))
+                .get_result::<CustomerWebhook>(&mut conn)
+        }).await??;
+
+        Ok(webhook)
+    }
+
+    async fn record_delivery_failure(&self, id: Uuid, at: DateTime<Utc>, disable_failing_since: DateTime<Utc>) -> Result<Option<CustomerWebhook>> {
+        let mut conn = self.pool.get()?;
+
+        let disabled = tokio::task::spawn_blocking(move || {
+            conn.transaction(|conn| {
+                diesel::update(customer_webhooks::table.find(id))
+                    .filter(customer_webhooks::failing_since.is_null())
+                    .set(customer_webhooks::failing_since.eq(at))
+                    .execute(conn)?;
+
+                diesel::update(customer_webhooks::table.find(id))
+                    .filter(customer_webhooks::active.eq(true))
+                    .filter(customer_webhooks::failing_since.le(disable_failing_since))
+                    .set((
+                        customer_webhooks::active.eq(false),
+                        customer_webhooks::updated_at.eq(Utc::now()),
+                    ))
+                    .get_result::<CustomerWebhook>(conn)
+                    .optional()
+            })
+        }).await??;
+
+        Ok(disabled)
+    }
+
+    async fn record_delivery_success(&self, id: Uuid) -> Result<()> {
+        let mut conn = self.pool.get()?;
+
+        tokio::task::spawn_blocking(move || {
+            diesel::update(customer_webhooks::table.find(id))
+                .filter(customer_webhooks::failing_since.is_not_null())
+                .set(customer_webhooks::failing_since.eq(None::<DateTime<Utc>>))
+                .execute(&mut conn)
+        }).await??;
+
+        Ok(())
+    }
+
+    async fn delete(&self, id: Uuid) -> Result<()> {
+        let mut conn = self.pool.get()?;
+
+        let count = tokio::task::spawn_blocking(move || {
+            diesel::delete(customer_webhooks::table.find(id))
+                .execute(&mut conn)
+        }).await??;
+
+        if count == 0 {
+            return Err(anyhow!("Webhook not found with ID: {}", id));
+        }
+
+        Ok(())
+    }
+}
@@ -0,0 +1,20 @@
+use async_trait::async_trait;
+use anyhow::Result;
+
+use crate::models::feature_flag::{FeatureFlag, NewFeatureFlag};
+
+/// Repository trait for runtime feature flags
+#[async_trait]
+pub trait FeatureFlagRepository: Send + Sync {
+    /// Create the flag, or replace the settings of the flag with the same name
+    async fn upsert(&self, flag: NewFeatureFlag) -> Result<FeatureFlag>;
+
+    /// Find a flag by name
+    async fn find_by_name(&self, name: String) -> Result<FeatureFlag>;
+
+    /// List all flags, by name
+    async fn list(&self) -> Result<Vec<FeatureFlag>>;
+
+    /// Delete a flag by name, turning it off for everyone
+    async fn delete(&self, name: String) -> Result<FeatureFlag>;
+}
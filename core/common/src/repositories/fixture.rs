@@ -0,0 +1,22 @@
+use async_trait::async_trait;
+use uuid::Uuid;
+use anyhow::Result;
+
+use crate::models::fixture::{FixtureBundle, FixtureRecords, NewFixtureBundle};
+
+/// Repository trait for bundles of test data
+#[async_trait]
+pub trait FixtureRepository: Send + Sync {
+    /// Create a bundle with all of its records, or nothing if any of them cannot be created
+    async fn create(&self, bundle: NewFixtureBundle, records: FixtureRecords) -> Result<FixtureBundle>;
+
+    /// Find a bundle by ID
+    async fn find_by_id(&self, id: Uuid) -> Result<FixtureBundle>;
+
+    /// List the bundles, newest first
+    async fn list(&self) -> Result<Vec<FixtureBundle>>;
+
+    /// Delete a bundle with every record it created, including what its customers did
+    /// since (jobs, transactions, webhooks...)
+    async fn delete(&self, id: Uuid) -> Result<()>;
+}
@@ -1,25 +1,70 @@
+use std::cmp::Reverse;
 use std::collections::HashMap;
 use std::sync::{Arc, Mutex};
 use async_trait::async_trait;
 use uuid::Uuid;
-use chrono::Utc;
+use chrono::{DateTime, Utc};
 
 use crate::errors::Error;
-use crate::models::job::{Job, JobStatus, NewJob, PriorityLevel};
+use crate::models::job::{resolve_completion, CompletionResolution, HeldCompletion, Job, JobHolder, JobStatus, NewJob, PriorityLevel};
+use crate::models::job_cost::CostBreakdown;
+use crate::models::job_error::JobError;
+use crate::models::reseller_invoice::JobTypeCharges;
 use crate::repositories::JobRepository;
-use crate::repositories::job::{JobFilter, JobSortOrder, Pagination};
+use crate::repositories::job::{WINDOW_STATS_MAX_JOB_AGE_DAYS, JobCursor, JobFilter, JobSortOrder, CustomerActivity, CustomerSpend, JobTypeExecutionStats, JobTypeQueueStats, Pagination, PriorityWaitStats, RunnerJobStats};
 use crate::Result;
 
-/// In-memory implementation of JobRepository for Phase 1
+/// In-memory implementation of JobRepository
+///
+/// Unlike the Diesel implementation, status changes are checked against
+/// `JobStatus::can_transition_to` and rejected with `Error::InvalidInput`.
+#[derive(Default)]
 pub struct InMemoryJobRepository {
     jobs: Arc<Mutex<HashMap<Uuid, Job>>>,
 }
 
 impl InMemoryJobRepository {
     pub fn new() -> Self {
-        Self {
-            jobs: Arc::new(Mutex::new(HashMap::new())),
-        }
+        Self::default()
+    }
+}
+
+/// Whether the job matches all criteria of the filter
+fn matches_filter(job: &Job, filter: &JobFilter) -> bool {
+    filter.customer_id.is_none_or(|customer_id| job.customer_id == customer_id)
+        && filter.job_type_id.is_none_or(|job_type_id| job.job_type_id == job_type_id)
+        && filter.status.as_ref().is_none_or(|status| job.status == *status)
+        && filter.public_id.as_ref().is_none_or(|public_id| job.public_id == *public_id)
+        && filter.priority.as_ref().is_none_or(|priority| job.priority == *priority)
+        && filter.created_after.is_none_or(|after| job.created_at.is_some_and(|created_at| created_at >= after))
+        && filter.created_before.is_none_or(|before| job.created_at.is_some_and(|created_at| created_at <= before))
+        && (!filter.completed_only || job.completed_at.is_some())
+        && (!filter.failed_only || job.status == JobStatus::Failed)
+}
+
+/// Move a job to a new status, rejecting transitions the state machine does not allow
+fn transition(job: &mut Job, status: JobStatus) -> Result<()> {
+    if !job.status.can_transition_to(&status) {
+        return Err(Error::InvalidInput(format!(
+            "Invalid job status transition from {} to {}",
+            job.status.as_str(),
+            status.as_str()
+        )));
+    }
+
+    job.status = status;
+    job.updated_at = Some(Utc::now());
+    Ok(())
+}
+
+/// Refuse to start a job whose deadline passed while it waited
+fn check_deadline(job: &Job) -> Result<()> {
+    match job.expires_at {
+        Some(expires_at) if expires_at <= Utc::now() => Err(Error::InvalidInput(format!(
+            "Job expired at {} before it could start",
+            expires_at
+        ))),
+        _ => Ok(()),
     }
 }
 
@@ -31,15 +76,33 @@ impl JobRepository for InMemoryJobRepository {
             customer_id: new_job.customer_id,
             job_type_id: new_job.job_type_id,
             status: JobStatus::from_str(&new_job.status).ok_or_else(|| Error::InvalidInput(format!("Invalid job status: {}", new_job.status)))?,
-            priority: PriorityLevel::Medium, // Default value since not stored in DB
-            input_data: serde_json::Value::Null, // Default value since not stored in DB
+            priority: PriorityLevel::from_i32(new_job.priority),
+            input_data: new_job.input_data,
             output_data: None,
             error: None,
             estimated_cost_cents: new_job.cost_cents, // Use cost as estimate
             cost_cents: new_job.cost_cents,
-            created_at: Some(chrono::Utc::now().naive_utc()),
+            created_at: Some(chrono::Utc::now()),
             updated_at: None,
             completed_at: None,
+            test_mode: new_job.test_mode,
+            runner_id: None,
+            claimed_at: None,
+            scheduled_for: None,
+            lease_expires_at: None,
+            public_id: new_job.public_id,
+            expires_at: new_job.expires_at,
+            customer_reference: new_job.customer_reference,
+            customer_metadata: new_job.customer_metadata,
+            parent_id: new_job.parent_id,
+            sub_task_index: new_job.sub_task_index,
+            sub_task_count: None,
+            cost_breakdown: None,
+            submission_id: new_job.submission_id,
+            cancel_requested_at: None,
+            requeued_from: new_job.requeued_from,
+            queued_at: Some(chrono::Utc::now()),
+            started_at: None,
         };
         
         let mut jobs = self.jobs.lock().map_err(|_| Error::Other(anyhow::anyhow!("Lock error")))?;
@@ -48,6 +111,19 @@ impl JobRepository for InMemoryJobRepository {
         Ok(job)
     }
     
+    async fn create_many(&self, new_jobs: Vec<NewJob>) -> Result<Vec<Job>> {
+        // Either all jobs are created or none, as in one transaction
+        if let Some(invalid) = new_jobs.iter().find(|new_job| JobStatus::from_str(&new_job.status).is_none()) {
+            return Err(Error::InvalidInput(format!("Invalid job status: {}", invalid.status)));
+        }
+        
+        let mut created = Vec::with_capacity(new_jobs.len());
+        for new_job in new_jobs {
+            created.push(self.create(new_job).await?);
+        }
+        Ok(created)
+    }
+    
     async fn find_by_id(&self, id: Uuid) -> Result<Job> {
         let jobs = self.jobs.lock().map_err(|_| Error::Other(anyhow::anyhow!("Lock error")))?;
         jobs.get(&id)
@@ -55,13 +131,21 @@ impl JobRepository for InMemoryJobRepository {
             .ok_or_else(|| Error::NotFound(format!("Job not found: {}", id)))
     }
     
+    async fn find_by_public_id(&self, public_id: &str) -> Result<Job> {
+        let jobs = self.jobs.lock().map_err(|_| Error::Other(anyhow::anyhow!("Lock error")))?;
+        jobs.values()
+            .find(|job| job.public_id == public_id)
+            .cloned()
+            .ok_or_else(|| Error::NotFound(format!("Job not found: {}", public_id)))
+    }
+
     async fn update_status(&self, id: Uuid, status: JobStatus) -> Result<Job> {
         let mut jobs = self.jobs.lock().map_err(|_| Error::Other(anyhow::anyhow!("Lock error")))?;
         
         let job = jobs.get_mut(&id)
             .ok_or_else(|| Error::NotFound(format!("Job not found: {}", id)))?;
             
-        job.status = status;
+        transition(job, status)?;
         
         Ok(job.clone())
     }
@@ -71,9 +155,11 @@ impl JobRepository for InMemoryJobRepository {
         
         let job = jobs.get_mut(&id)
             .ok_or_else(|| Error::NotFound(format!("Job not found: {}", id)))?;
+        check_deadline(job)?;
             
-        job.status = JobStatus::Running;
-        job.updated_at = Some(chrono::Utc::now().naive_utc()); // Use updated_at instead of started_at
+        transition(job, JobStatus::Running)?;
+        job.lease_expires_at = None;
+        job.started_at = Some(Utc::now());
         
         Ok(job.clone())
     }
@@ -83,7 +169,7 @@ impl JobRepository for InMemoryJobRepository {
         id: Uuid, 
         success: bool, 
         output: Option<serde_json::Value>, 
-        error: Option<String>, 
+        error: Option<JobError>, 
         cost_cents: i32
     ) -> Result<Job> {
         let mut jobs = self.jobs.lock().map_err(|_| Error::Other(anyhow::anyhow!("Lock error")))?;
@@ -91,16 +177,214 @@ impl JobRepository for InMemoryJobRepository {
         let job = jobs.get_mut(&id)
             .ok_or_else(|| Error::NotFound(format!("Job not found: {}", id)))?;
             
-        job.status = if success { JobStatus::Succeeded } else { JobStatus::Failed };
+        transition(job, if success { JobStatus::Succeeded } else { JobStatus::Failed })?;
+        job.output_data = output;
+        job.error = error;
+        job.cost_cents = cost_cents;
+        job.completed_at = job.updated_at;
+        
+        Ok(job.clone())
+    }
+    
+    async fn complete_held(
+        &self,
+        id: Uuid,
+        holder: JobHolder,
+        success: bool,
+        output: Option<serde_json::Value>,
+        error: Option<JobError>,
+        cost_cents: i32,
+    ) -> Result<(CompletionResolution, Job)> {
+        let mut jobs = self.jobs.lock().map_err(|_| Error::Other(anyhow::anyhow!("Lock error")))?;
+        
+        let job = jobs.get_mut(&id)
+            .ok_or_else(|| Error::NotFound(format!("Job not found: {}", id)))?;
+        
+        // Attempts are not kept here, so the job's runner decides whether it is still held
+        let resolution = resolve_completion(&job.status, None, job.runner_id, &holder);
+        if resolution == CompletionResolution::Discarded {
+            return Ok((resolution, job.clone()));
+        }
+        
+        let status = match (success, job.cancel_requested_at) {
+            (true, _) => JobStatus::Succeeded,
+            (false, Some(_)) => JobStatus::Cancelled,
+            (false, None) => JobStatus::Failed,
+        };
+        transition(job, status)?;
         job.output_data = output;
         job.error = error;
         job.cost_cents = cost_cents;
-        job.updated_at = Some(chrono::Utc::now().naive_utc());
-        job.completed_at = Some(chrono::Utc::now().naive_utc());
+        job.lease_expires_at = None;
+        job.completed_at = job.updated_at;
+        
+        Ok((resolution, job.clone()))
+    }
+    
+    async fn complete_held_many(&self, completions: Vec<HeldCompletion>) -> Result<Vec<(CompletionResolution, Job)>> {
+        let mut completed = Vec::with_capacity(completions.len());
+        for completion in completions {
+            completed.push(self.complete_held(
+                completion.job_id,
+                completion.holder,
+                completion.success,
+                completion.output,
+                completion.error,
+                completion.cost_cents,
+            ).await?);
+        }
+        Ok(completed)
+    }
+    
+    async fn hand_back(&self, id: Uuid, runner_id: Uuid) -> Result<Option<Job>> {
+        let mut jobs = self.jobs.lock().map_err(|_| Error::Other(anyhow::anyhow!("Lock error")))?;
+        
+        let job = jobs.get_mut(&id)
+            .ok_or_else(|| Error::NotFound(format!("Job not found: {}", id)))?;
+        if job.status != JobStatus::Running || job.runner_id != Some(runner_id) || job.sub_task_count.is_some() {
+            return Ok(None);
+        }
+        
+        // A job whose cancellation was requested is not started again
+        transition(job, if job.cancel_requested_at.is_some() { JobStatus::Cancelled } else { JobStatus::Pending })?;
+        if job.status == JobStatus::Pending {
+            job.queued_at = Some(Utc::now());
+            job.claimed_at = None;
+            job.started_at = None;
+        }
+        Ok(Some(job.clone()))
+    }
+    
+    async fn request_cancellation(&self, id: Uuid) -> Result<Job> {
+        let mut jobs = self.jobs.lock().map_err(|_| Error::Other(anyhow::anyhow!("Lock error")))?;
+        
+        let job = jobs.get_mut(&id)
+            .ok_or_else(|| Error::NotFound(format!("Job not found: {}", id)))?;
+        if !job.can_request_cancellation() {
+            return Err(Error::InvalidInput(format!(
+                "Job {} is {} and not processed by a runner that could cancel it",
+                id, job.status.as_str()
+            )));
+        }
+        job.cancel_requested_at.get_or_insert_with(Utc::now);
+        
+        Ok(job.clone())
+    }
+    
+    async fn cancellation_requested(&self, id: Uuid) -> Result<bool> {
+        let jobs = self.jobs.lock().map_err(|_| Error::Other(anyhow::anyhow!("Lock error")))?;
+        jobs.get(&id)
+            .map(|job| job.cancel_requested_at.is_some())
+            .ok_or_else(|| Error::NotFound(format!("Job not found: {}", id)))
+    }
+    
+    async fn find_running_by_runner(&self, runner_id: Uuid) -> Result<Vec<Job>> {
+        let jobs = self.jobs.lock().map_err(|_| Error::Other(anyhow::anyhow!("Lock error")))?;
+        
+        let mut running: Vec<Job> = jobs.values()
+            .filter(|job| job.status == JobStatus::Running && job.runner_id == Some(runner_id) && job.sub_task_count.is_none())
+            .cloned()
+            .collect();
+        running.sort_by_key(|job| (job.updated_at, job.id));
+        Ok(running)
+    }
+    
+    async fn set_cost_breakdown(&self, id: Uuid, breakdown: CostBreakdown) -> Result<()> {
+        let mut jobs = self.jobs.lock().map_err(|_| Error::Other(anyhow::anyhow!("Lock error")))?;
+        
+        let job = jobs.get_mut(&id)
+            .ok_or_else(|| Error::NotFound(format!("Job not found: {}", id)))?;
+        job.cost_breakdown = Some(breakdown);
+        
+        Ok(())
+    }
+    
+    async fn assign_runner(&self, id: Uuid, runner_id: Uuid) -> Result<()> {
+        let mut jobs = self.jobs.lock().map_err(|_| Error::Other(anyhow::anyhow!("Lock error")))?;
+        
+        let job = jobs.get_mut(&id)
+            .ok_or_else(|| Error::NotFound(format!("Job not found: {}", id)))?;
+        job.runner_id = Some(runner_id);
+        
+        Ok(())
+    }
+    
+    async fn record_claim(&self, id: Uuid, priority: PriorityLevel) -> Result<()> {
+        let mut jobs = self.jobs.lock().map_err(|_| Error::Other(anyhow::anyhow!("Lock error")))?;
+        
+        let job = jobs.get_mut(&id)
+            .ok_or_else(|| Error::NotFound(format!("Job not found: {}", id)))?;
+        job.priority = priority;
+        job.claimed_at = Some(Utc::now());
+        
+        Ok(())
+    }
+    
+    async fn lease(&self, id: Uuid, priority: PriorityLevel, runner_id: Option<Uuid>, until: DateTime<Utc>) -> Result<()> {
+        let mut jobs = self.jobs.lock().map_err(|_| Error::Other(anyhow::anyhow!("Lock error")))?;
+        
+        let job = jobs.get_mut(&id)
+            .ok_or_else(|| Error::NotFound(format!("Job not found: {}", id)))?;
+        if !job.status.can_transition_to(&JobStatus::Running) {
+            return Err(Error::InvalidInput(format!(
+                "Invalid job status transition from {} to {}",
+                job.status.as_str(),
+                JobStatus::Running.as_str()
+            )));
+        }
+        check_deadline(job)?;
+        job.priority = priority;
+        job.claimed_at = Some(Utc::now());
+        job.lease_expires_at = Some(until);
+        if runner_id.is_some() {
+            job.runner_id = runner_id;
+        }
+        
+        Ok(())
+    }
+    
+    async fn release_expired_leases(&self, now: DateTime<Utc>) -> Result<Vec<(Uuid, PriorityLevel)>> {
+        let mut jobs = self.jobs.lock().map_err(|_| Error::Other(anyhow::anyhow!("Lock error")))?;
+        
+        let mut released = Vec::new();
+        for job in jobs.values_mut() {
+            if job.status == JobStatus::Pending && job.lease_expires_at.is_some_and(|expires_at| expires_at < now) {
+                job.lease_expires_at = None;
+                job.runner_id = None;
+                released.push((job.id, job.priority.clone()));
+            }
+        }
+        
+        Ok(released)
+    }
+    
+    async fn schedule(&self, id: Uuid, scheduled_for: DateTime<Utc>) -> Result<Job> {
+        let mut jobs = self.jobs.lock().map_err(|_| Error::Other(anyhow::anyhow!("Lock error")))?;
+        
+        let job = jobs.get_mut(&id)
+            .ok_or_else(|| Error::NotFound(format!("Job not found: {}", id)))?;
+            
+        transition(job, JobStatus::Scheduled)?;
+        job.scheduled_for = Some(scheduled_for);
+        job.queued_at = Some(scheduled_for);
         
         Ok(job.clone())
     }
     
+    async fn postpone_scheduled(&self, job_type_ids: Vec<Uuid>, due_before: DateTime<Utc>, until: DateTime<Utc>) -> Result<Vec<Uuid>> {
+        let mut jobs = self.jobs.lock().map_err(|_| Error::Other(anyhow::anyhow!("Lock error")))?;
+        
+        Ok(jobs.values_mut()
+            .filter(|job| job.status == JobStatus::Scheduled && job_type_ids.contains(&job.job_type_id))
+            .filter(|job| job.scheduled_for.is_some_and(|scheduled_for| scheduled_for < due_before))
+            .map(|job| {
+                job.scheduled_for = Some(until);
+                job.queued_at = Some(until);
+                job.id
+            })
+            .collect())
+    }
+    
     async fn find_by_customer_id(&self, customer_id: Uuid) -> Result<Vec<Job>> {
         let jobs = self.jobs.lock().map_err(|_| Error::Other(anyhow::anyhow!("Lock error")))?;
         
@@ -124,49 +408,19 @@ impl JobRepository for InMemoryJobRepository {
         
         Ok(jobs.values()
             .filter(|job| job.status == JobStatus::Pending)
-            .cloned()
             .take(limit as usize)
+            .cloned()
             .collect())
     }
     
     async fn query_jobs(&self, filter: JobFilter, sort: Option<JobSortOrder>, pagination: Option<Pagination>) -> Result<(Vec<Job>, u64)> {
         let jobs = self.jobs.lock().map_err(|_| Error::Other(anyhow::anyhow!("Lock error")))?;
         
-        // Start with all jobs and apply filters
-        let mut filtered_jobs: Vec<Job> = jobs.values().cloned().collect();
-        
-        // Apply filters
-        if let Some(customer_id) = filter.customer_id {
-            filtered_jobs.retain(|job| job.customer_id == customer_id);
-        }
-        
-        if let Some(job_type_id) = filter.job_type_id {
-            filtered_jobs.retain(|job| job.job_type_id == job_type_id);
-        }
-        
-        if let Some(status) = filter.status {
-            filtered_jobs.retain(|job| job.status == status);
-        }
-        
-        if let Some(priority) = filter.priority {
-            filtered_jobs.retain(|job| job.priority == priority);
-        }
-        
-        if let Some(created_after) = filter.created_after {
-            filtered_jobs.retain(|job| job.created_at.map_or(false, |created_at| created_at >= created_after));
-        }
-        
-        if let Some(created_before) = filter.created_before {
-            filtered_jobs.retain(|job| job.created_at.map_or(false, |created_at| created_at <= created_before));
-        }
-        
-        if filter.completed_only {
-            filtered_jobs.retain(|job| job.completed_at.is_some());
-        }
-        
-        if filter.failed_only {
-            filtered_jobs.retain(|job| job.status == JobStatus::Failed);
-        }
+        // Start with all jobs matching the filter
+        let mut filtered_jobs: Vec<Job> = jobs.values()
+            .filter(|job| matches_filter(job, &filter))
+            .cloned()
+            .collect();
         
         // Get total count before pagination
         let total_count = filtered_jobs.len() as u64;
@@ -174,33 +428,53 @@ impl JobRepository for InMemoryJobRepository {
         // Apply sorting
         match sort {
             Some(JobSortOrder::CreatedDesc) => {
-                filtered_jobs.sort_by(|a, b| b.created_at.cmp(&a.created_at));
+                filtered_jobs.sort_by_key(|job| Reverse(job.created_at));
             },
             Some(JobSortOrder::CreatedAsc) => {
-                filtered_jobs.sort_by(|a, b| a.created_at.cmp(&b.created_at));
+                filtered_jobs.sort_by_key(|job| job.created_at);
             },
             Some(JobSortOrder::PriorityDesc) => {
-                filtered_jobs.sort_by(|a, b| b.priority.cmp(&a.priority));
+                filtered_jobs.sort_by_key(|job| Reverse(job.priority.clone()));
             },
             Some(JobSortOrder::PriorityAsc) => {
-                filtered_jobs.sort_by(|a, b| a.priority.cmp(&b.priority));
+                filtered_jobs.sort_by_key(|job| job.priority.clone());
             },
             None => {
                 // Default sort by created_at descending
-                filtered_jobs.sort_by(|a, b| b.created_at.cmp(&a.created_at));
+                filtered_jobs.sort_by_key(|job| Reverse(job.created_at));
             }
         }
         
         // Apply pagination
         if let Some(pagination) = pagination {
-            let start = (pagination.page * pagination.per_page) as usize;
-            let end = start + pagination.per_page as usize;
-            filtered_jobs = filtered_jobs.into_iter().skip(start).take(end - start).collect();
+            let start = u64::from(pagination.page) * u64::from(pagination.per_page);
+            let start = usize::try_from(start).unwrap_or(usize::MAX);
+            filtered_jobs = filtered_jobs.into_iter().skip(start).take(pagination.per_page as usize).collect();
         }
         
         Ok((filtered_jobs, total_count))
     }
     
+    async fn query_jobs_after(&self, filter: JobFilter, cursor: Option<JobCursor>, limit: u32) -> Result<(Vec<Job>, Option<JobCursor>)> {
+        let jobs = self.jobs.lock().map_err(|_| Error::Other(anyhow::anyhow!("Lock error")))?;
+        
+        // Newest first by (created_at, id), continuing strictly after the cursor
+        let mut filtered_jobs: Vec<Job> = jobs.values()
+            .filter(|job| matches_filter(job, &filter))
+            .filter(|job| cursor.as_ref().is_none_or(|cursor| {
+                job.created_at.is_some_and(|created_at| (created_at, job.id) < (cursor.created_at, cursor.id))
+            }))
+            .cloned()
+            .collect();
+        filtered_jobs.sort_by_key(|job| Reverse((job.created_at, job.id)));
+        
+        let has_more = filtered_jobs.len() > limit as usize;
+        filtered_jobs.truncate(limit as usize);
+        let next_cursor = if has_more { filtered_jobs.last().and_then(JobCursor::after) } else { None };
+        
+        Ok((filtered_jobs, next_cursor))
+    }
+    
     async fn get_job_stats_by_status(&self) -> Result<Vec<(String, i64)>> {
         let jobs = self.jobs.lock().map_err(|_| Error::Other(anyhow::anyhow!("Lock error")))?;
         
@@ -248,18 +522,200 @@ impl JobRepository for InMemoryJobRepository {
         Ok((total_cost, completed_count))
     }
     
+    async fn get_queue_stats_by_job_type(&self, window_minutes: i32) -> Result<Vec<JobTypeQueueStats>> {
+        let jobs = self.jobs.lock().map_err(|_| Error::Other(anyhow::anyhow!("Lock error")))?;
+        
+        let cutoff = Utc::now() - chrono::Duration::minutes(window_minutes.into());
+        let oldest = cutoff - chrono::Duration::days(WINDOW_STATS_MAX_JOB_AGE_DAYS);
+        let mut stats: HashMap<Uuid, (JobTypeQueueStats, f64)> = HashMap::new();
+        
+        for job in jobs.values() {
+            let (job_type_stats, total_seconds) = stats.entry(job.job_type_id)
+                .or_insert_with(|| (JobTypeQueueStats::new(job.job_type_id), 0.0));
+            match job.status {
+                JobStatus::Pending => job_type_stats.pending += 1,
+                JobStatus::Running => job_type_stats.running += 1,
+                _ => {}
+            }
+            if let (Some(created_at), Some(completed_at)) = (job.created_at, job.completed_at) {
+                if completed_at > cutoff && created_at > oldest {
+                    job_type_stats.completed_in_window += 1;
+                    *total_seconds += (completed_at - created_at).num_milliseconds().max(0) as f64 / 1000.0;
+                }
+            }
+        }
+        
+        Ok(stats.into_values()
+            .map(|(mut job_type_stats, total_seconds)| {
+                if job_type_stats.completed_in_window > 0 {
+                    job_type_stats.avg_processing_seconds = Some(total_seconds / job_type_stats.completed_in_window as f64);
+                }
+                job_type_stats
+            })
+            .filter(|job_type_stats| job_type_stats.pending + job_type_stats.running + job_type_stats.completed_in_window > 0)
+            .collect())
+    }
+    
+    async fn get_runner_job_stats(&self, runner_id: Uuid, window_minutes: i32) -> Result<RunnerJobStats> {
+        let jobs = self.jobs.lock().map_err(|_| Error::Other(anyhow::anyhow!("Lock error")))?;
+        
+        let cutoff = Utc::now() - chrono::Duration::minutes(window_minutes.into());
+        let oldest = cutoff - chrono::Duration::days(WINDOW_STATS_MAX_JOB_AGE_DAYS);
+        let mut stats = RunnerJobStats::new(runner_id);
+        let mut total_seconds = 0.0;
+        
+        for job in jobs.values().filter(|job| job.runner_id == Some(runner_id)) {
+            let (Some(created_at), Some(completed_at)) = (job.created_at, job.completed_at) else { continue };
+            if completed_at <= cutoff || created_at <= oldest {
+                continue;
+            }
+            match job.status {
+                JobStatus::Succeeded => stats.succeeded += 1,
+                JobStatus::Failed => stats.failed += 1,
+                _ => continue,
+            }
+            total_seconds += (completed_at - created_at).num_milliseconds().max(0) as f64 / 1000.0;
+        }
+        if stats.completed() > 0 {
+            stats.avg_processing_seconds = Some(total_seconds / stats.completed() as f64);
+        }
+        
+        Ok(stats)
+    }
+    
+    async fn get_spend_by_customer(&self, customer_ids: Vec<Uuid>, since: DateTime<Utc>) -> Result<Vec<CustomerSpend>> {
+        let jobs = self.jobs.lock().map_err(|_| Error::Other(anyhow::anyhow!("Lock error")))?;
+        
+        let mut spend: HashMap<Uuid, CustomerSpend> = HashMap::new();
+        for job in jobs.values() {
+            if !customer_ids.contains(&job.customer_id)
+                || job.status == JobStatus::Cancelled
+                || job.test_mode
+                || job.created_at.is_none_or(|created_at| created_at < since)
+            {
+                continue;
+            }
+            let customer_spend = spend.entry(job.customer_id)
+                .or_insert_with(|| CustomerSpend::new(job.customer_id));
+            customer_spend.jobs += 1;
+            customer_spend.cost_cents += job.cost_cents as i64;
+        }
+        
+        Ok(spend.into_values().collect())
+    }
+    
+    async fn get_customer_activity(&self, since: DateTime<Utc>, until: DateTime<Utc>) -> Result<Vec<CustomerActivity>> {
+        let jobs = self.jobs.lock().map_err(|_| Error::Other(anyhow::anyhow!("Lock error")))?;
+        
+        let mut activity: HashMap<Uuid, CustomerActivity> = HashMap::new();
+        for job in jobs.values() {
+            if job.status == JobStatus::Cancelled
+                || job.test_mode
+                || job.created_at.is_none_or(|created_at| created_at < since || created_at >= until)
+            {
+                continue;
+            }
+            let customer = activity.entry(job.customer_id)
+                .or_insert_with(|| CustomerActivity::new(job.customer_id));
+            customer.jobs += 1;
+            customer.cost_cents += job.cost_cents as i64;
+            match job.status {
+                JobStatus::Succeeded => customer.succeeded += 1,
+                JobStatus::Failed => customer.failed += 1,
+                _ => {}
+            }
+        }
+        
+        Ok(activity.into_values().collect())
+    }
+    
+    async fn get_charges_by_job_type(&self, customer_ids: Vec<Uuid>, since: DateTime<Utc>, until: DateTime<Utc>) -> Result<Vec<JobTypeCharges>> {
+        let jobs = self.jobs.lock().map_err(|_| Error::Other(anyhow::anyhow!("Lock error")))?;
+        
+        let mut charges: HashMap<(Uuid, Uuid), JobTypeCharges> = HashMap::new();
+        for job in jobs.values() {
+            if !customer_ids.contains(&job.customer_id)
+                || job.cost_cents <= 0
+                || job.test_mode
+                || job.parent_id.is_some()
+                || job.completed_at.is_none_or(|completed_at| completed_at < since || completed_at >= until)
+            {
+                continue;
+            }
+            let entry = charges.entry((job.customer_id, job.job_type_id))
+                .or_insert_with(|| JobTypeCharges { customer_id: job.customer_id, job_type_id: job.job_type_id, jobs: 0, cost: CostBreakdown::default() });
+            entry.jobs += 1;
+            entry.cost = entry.cost + job.cost_breakdown.unwrap_or_else(|| CostBreakdown::unitemized(job.cost_cents));
+        }
+        
+        Ok(charges.into_values().collect())
+    }
+    
+    async fn get_queue_wait_stats(&self, window_minutes: i32) -> Result<Vec<PriorityWaitStats>> {
+        let jobs = self.jobs.lock().map_err(|_| Error::Other(anyhow::anyhow!("Lock error")))?;
+        
+        let cutoff = Utc::now() - chrono::Duration::minutes(window_minutes.into());
+        let oldest = cutoff - chrono::Duration::days(WINDOW_STATS_MAX_JOB_AGE_DAYS);
+        let mut waits: HashMap<i32, Vec<f64>> = HashMap::new();
+        
+        for job in jobs.values() {
+            let (Some(created_at), Some(claimed_at)) = (job.created_at, job.claimed_at) else { continue };
+            if claimed_at <= cutoff || created_at <= oldest {
+                continue;
+            }
+            let queued_at = job.queued_at.unwrap_or(created_at);
+            waits.entry(job.priority.as_i32())
+                .or_default()
+                .push((claimed_at - queued_at).num_milliseconds().max(0) as f64 / 1000.0);
+        }
+        
+        let mut stats: Vec<PriorityWaitStats> = waits.into_iter()
+            .filter_map(|(priority, waits)| PriorityWaitStats::from_samples(PriorityLevel::from_i32(priority), waits))
+            .collect();
+        stats.sort_by_key(|stats| stats.priority.as_i32());
+        
+        Ok(stats)
+    }
+    
+    async fn get_job_type_execution_stats(&self, job_type_id: Uuid, window_minutes: i32) -> Result<JobTypeExecutionStats> {
+        let jobs = self.jobs.lock().map_err(|_| Error::Other(anyhow::anyhow!("Lock error")))?;
+        
+        let cutoff = Utc::now() - chrono::Duration::minutes(window_minutes.into());
+        let oldest = cutoff - chrono::Duration::days(WINDOW_STATS_MAX_JOB_AGE_DAYS);
+        let (mut succeeded, mut failed) = (0, 0);
+        let mut processing = Vec::new();
+        
+        for job in jobs.values().filter(|job| job.job_type_id == job_type_id) {
+            let (Some(created_at), Some(completed_at)) = (job.created_at, job.completed_at) else { continue };
+            if completed_at <= cutoff || created_at <= oldest {
+                continue;
+            }
+            match job.status {
+                JobStatus::Succeeded => succeeded += 1,
+                JobStatus::Failed => failed += 1,
+                _ => continue,
+            }
+            if let Some(started_at) = job.started_at {
+                processing.push((completed_at - started_at).num_milliseconds().max(0) as f64 / 1000.0);
+            }
+        }
+        
+        Ok(JobTypeExecutionStats::from_samples(job_type_id, succeeded, failed, processing))
+    }
+    
     async fn find_stalled_jobs(&self, running_threshold_minutes: i32) -> Result<Vec<Job>> {
         let jobs = self.jobs.lock().map_err(|_| Error::Other(anyhow::anyhow!("Lock error")))?;
         
         // Get current time
-        let now = Utc::now().naive_utc();
+        let now = Utc::now();
         
         // Filter jobs that are in running state for too long
         let stalled_jobs = jobs.values()
             .filter(|job| {
                 job.status == JobStatus::Running && 
-                job.updated_at.map_or(false, |updated_at| {
-                    let duration = now.signed_duration_since(updated_at);
+                job.sub_task_count.is_none() &&
+                job.started_at.or(job.updated_at).is_some_and(|started_at| {
+                    let duration = now.signed_duration_since(started_at);
                     duration.num_minutes() >= running_threshold_minutes.into()
                 })
             })
@@ -269,6 +725,46 @@ impl JobRepository for InMemoryJobRepository {
         Ok(stalled_jobs)
     }
     
+    async fn find_waiting_since(&self, before: DateTime<Utc>) -> Result<Vec<Job>> {
+        let jobs = self.jobs.lock().map_err(|_| Error::Other(anyhow::anyhow!("Lock error")))?;
+        
+        let mut waiting: Vec<Job> = jobs.values()
+            .filter(|job| matches!(job.status, JobStatus::Pending | JobStatus::Scheduled))
+            .filter(|job| job.updated_at.or(job.created_at).is_some_and(|changed_at| changed_at < before))
+            .filter(|job| job.scheduled_for.is_none_or(|scheduled_for| scheduled_for < before))
+            .cloned()
+            .collect();
+        waiting.sort_by_key(|job| job.created_at);
+        
+        Ok(waiting)
+    }
+    
+    async fn find_expired(&self, now: DateTime<Utc>) -> Result<Vec<Job>> {
+        let jobs = self.jobs.lock().map_err(|_| Error::Other(anyhow::anyhow!("Lock error")))?;
+        
+        let mut expired: Vec<Job> = jobs.values()
+            .filter(|job| matches!(job.status, JobStatus::Pending | JobStatus::Scheduled))
+            .filter(|job| job.expires_at.is_some_and(|expires_at| expires_at <= now))
+            .cloned()
+            .collect();
+        expired.sort_by_key(|job| job.expires_at);
+        
+        Ok(expired)
+    }
+    
+    async fn find_changed_since(&self, since: DateTime<Utc>, limit: i64) -> Result<Vec<Job>> {
+        let jobs = self.jobs.lock().map_err(|_| Error::Other(anyhow::anyhow!("Lock error")))?;
+        
+        let mut changed: Vec<Job> = jobs.values()
+            .filter(|job| job.updated_at.or(job.created_at).is_some_and(|changed_at| changed_at > since))
+            .cloned()
+            .collect();
+        changed.sort_by_key(|job| (job.updated_at.or(job.created_at), job.id));
+        changed.truncate(limit.max(0) as usize);
+        
+        Ok(changed)
+    }
+    
     async fn bulk_update_status(&self, ids: Vec<Uuid>, status: JobStatus) -> Result<usize> {
         if ids.is_empty() {
             return Ok(0);
@@ -276,18 +772,106 @@ impl JobRepository for InMemoryJobRepository {
         
         let mut jobs = self.jobs.lock().map_err(|_| Error::Other(anyhow::anyhow!("Lock error")))?;
         
-        let now = Utc::now().naive_utc();
         let mut updated_count = 0;
         
-        // Update each job that matches an ID in the list
+        // Update each job that matches an ID in the list and may take the new status
         for id in ids {
             if let Some(job) = jobs.get_mut(&id) {
-                job.status = status.clone();
-                job.updated_at = Some(now);
-                updated_count += 1;
+                if transition(job, status.clone()).is_ok() {
+                    updated_count += 1;
+                }
             }
         }
         
         Ok(updated_count)
     }
+    
+    async fn count_pending_by_job_type(&self, job_type_id: Uuid) -> Result<i64> {
+        let jobs = self.jobs.lock().map_err(|_| Error::Other(anyhow::anyhow!("Lock error")))?;
+        
+        Ok(jobs.values()
+            .filter(|job| job.job_type_id == job_type_id && job.status == JobStatus::Pending)
+            .count() as i64)
+    }
+    
+    async fn fan_out(&self, id: Uuid, sub_tasks: Vec<NewJob>) -> Result<Vec<Job>> {
+        {
+            let mut jobs = self.jobs.lock().map_err(|_| Error::Other(anyhow::anyhow!("Lock error")))?;
+            let parent = jobs.get_mut(&id)
+                .filter(|job| job.status == JobStatus::Running && job.sub_task_count.is_none())
+                .ok_or_else(|| Error::InvalidInput(format!("Job {} is not running or already fanned out", id)))?;
+            parent.sub_task_count = Some(sub_tasks.len() as i32);
+            parent.updated_at = Some(Utc::now());
+        }
+        
+        let mut created = Vec::with_capacity(sub_tasks.len());
+        for sub_task in sub_tasks {
+            created.push(self.create(sub_task).await?);
+        }
+        created.sort_by_key(|job| job.sub_task_index);
+        Ok(created)
+    }
+    
+    async fn find_sub_tasks(&self, id: Uuid) -> Result<Vec<Job>> {
+        let jobs = self.jobs.lock().map_err(|_| Error::Other(anyhow::anyhow!("Lock error")))?;
+        
+        let mut sub_tasks: Vec<Job> = jobs.values()
+            .filter(|job| job.parent_id == Some(id))
+            .cloned()
+            .collect();
+        sub_tasks.sort_by_key(|job| job.sub_task_index);
+        Ok(sub_tasks)
+    }
+    
+    async fn fan_in(&self, id: Uuid) -> Result<Option<Job>> {
+        let mut jobs = self.jobs.lock().map_err(|_| Error::Other(anyhow::anyhow!("Lock error")))?;
+        
+        let Some(sub_task_count) = jobs.get(&id)
+            .filter(|job| job.status == JobStatus::Running)
+            .and_then(|job| job.sub_task_count)
+        else {
+            return Ok(None);
+        };
+        let sub_tasks: Vec<Job> = jobs.values()
+            .filter(|job| job.parent_id == Some(id))
+            .cloned()
+            .collect();
+        if (sub_tasks.len() as i32) < sub_task_count {
+            return Ok(None);
+        }
+        let Some(outcome) = crate::models::job::fan_in(&sub_tasks) else {
+            return Ok(None);
+        };
+        
+        let parent = jobs.get_mut(&id).ok_or_else(|| Error::NotFound(format!("Job not found: {}", id)))?;
+        let now = Utc::now();
+        parent.status = outcome.status;
+        parent.cost_cents = outcome.cost_cents;
+        parent.cost_breakdown = outcome.cost_breakdown;
+        parent.completed_at = Some(now);
+        parent.updated_at = Some(now);
+        Ok(Some(parent.clone()))
+    }
+    
+    async fn find_fanned_out(&self) -> Result<Vec<Job>> {
+        let jobs = self.jobs.lock().map_err(|_| Error::Other(anyhow::anyhow!("Lock error")))?;
+        
+        let mut fanned_out: Vec<Job> = jobs.values()
+            .filter(|job| job.status == JobStatus::Running && job.sub_task_count.is_some())
+            .cloned()
+            .collect();
+        fanned_out.sort_by_key(|job| job.created_at);
+        Ok(fanned_out)
+    }
+    
+    async fn find_by_submission(&self, submission_id: Uuid) -> Result<Vec<Job>> {
+        let jobs = self.jobs.lock().map_err(|_| Error::Other(anyhow::anyhow!("Lock error")))?;
+        
+        let mut submitted: Vec<Job> = jobs.values()
+            .filter(|job| job.submission_id == Some(submission_id))
+            .cloned()
+            .collect();
+        submitted.sort_by_key(|job| (job.created_at, job.id));
+        Ok(submitted)
+    }
 }
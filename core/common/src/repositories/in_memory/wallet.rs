@@ -1,178 +1,400 @@
 use std::collections::HashMap;
 use std::sync::{Arc, Mutex};
+use anyhow::{anyhow, Result};
 use async_trait::async_trait;
+use chrono::Utc;
 use uuid::Uuid;
 
 use crate::errors::Error;
-use crate::models::wallet::{Wallet, NewWallet, WalletTransaction, NewWalletTransaction};
+use crate::models::ledger::{is_balanced, JobDebit};
+use crate::models::wallet::{job_charge_reference, job_hold_reference, Wallet, NewWallet, OverdraftPolicy, WalletTransaction, NewWalletTransaction, TransactionType};
 use crate::repositories::WalletRepository;
-use crate::Result;
 
-/// In-memory implementation of WalletRepository for Phase 1
+/// In-memory implementation of WalletRepository, mirroring the Diesel implementation
+///
+/// Balances and the transaction ledger live in process memory, which makes it cheap
+/// to run long randomized operation sequences against the billing logic.
+#[derive(Default)]
 pub struct InMemoryWalletRepository {
     wallets: Arc<Mutex<HashMap<Uuid, Wallet>>>,
-    customer_wallets: Arc<Mutex<HashMap<Uuid, Uuid>>>,
-    transactions: Arc<Mutex<HashMap<Uuid, WalletTransaction>>>,
-    wallet_transactions: Arc<Mutex<HashMap<Uuid, Vec<Uuid>>>>,
+    transactions: Arc<Mutex<Vec<WalletTransaction>>>,
+    /// Transaction recorded under each reference ID of a wallet
+    references: Arc<Mutex<HashMap<(Uuid, Uuid), Uuid>>>,
 }
 
 impl InMemoryWalletRepository {
     pub fn new() -> Self {
-        Self {
-            wallets: Arc::new(Mutex::new(HashMap::new())),
-            customer_wallets: Arc::new(Mutex::new(HashMap::new())),
-            transactions: Arc::new(Mutex::new(HashMap::new())),
-            wallet_transactions: Arc::new(Mutex::new(HashMap::new())),
+        Self::default()
+    }
+
+    /// Post an amount to a wallet with its transaction record once `check` accepts the
+    /// wallet; like the Diesel implementation, a posting whose reference ID the wallet
+    /// recorded already is not posted again
+    #[allow(clippy::too_many_arguments)]
+    fn post(
+        &self,
+        id: Uuid,
+        amount: i32,
+        transaction_type: TransactionType,
+        description: Option<String>,
+        job_id: Option<Uuid>,
+        reference_id: Option<Uuid>,
+        check: impl FnOnce(&Wallet) -> Result<()>,
+    ) -> Result<Wallet> {
+        let mut wallets = self.wallets.lock().map_err(|_| anyhow!("Lock error"))?;
+        let mut transactions = self.transactions.lock().map_err(|_| anyhow!("Lock error"))?;
+        let mut references = self.references.lock().map_err(|_| anyhow!("Lock error"))?;
+
+        let wallet = wallets.get_mut(&id)
+            .ok_or_else(|| anyhow!("Wallet not found with ID: {}", id))?;
+
+        let reference_id = reference_id.or_else(|| job_posting_reference(&transactions, id, job_id, &transaction_type));
+        if reference_id.is_some_and(|reference_id| references.contains_key(&(id, reference_id))) {
+            return Ok(wallet.clone());
+        }
+        check(wallet)?;
+
+        let now = Utc::now();
+        let transaction_id = Uuid::new_v4();
+        if let Some(reference_id) = reference_id {
+            references.insert((id, reference_id), transaction_id);
         }
+        transactions.push(WalletTransaction {
+            id: transaction_id,
+            wallet_id: id,
+            amount_cents: amount,
+            transaction_type: transaction_type.to_string(),
+            customer_id: wallet.customer_id,
+            reference_id,
+            description,
+            job_id,
+            created_at: Some(now),
+            customer_reference: None,
+            customer_metadata: None,
+        });
+
+        wallet.balance_cents += amount;
+        wallet.updated_at = Some(now);
+
+        Ok(wallet.clone())
     }
 }
 
+/// Reference ID of a posting for a job, derived from the job and the postings already
+/// recorded for it on the wallet as the Diesel implementation does
+fn job_posting_reference(
+    transactions: &[WalletTransaction],
+    wallet_id: Uuid,
+    job_id: Option<Uuid>,
+    transaction_type: &TransactionType,
+) -> Option<Uuid> {
+    let job_id = job_id?;
+    if *transaction_type == TransactionType::JobDebit {
+        return Some(job_charge_reference(job_id));
+    }
+    let counter = transaction_type.cycle_counter()?;
+    let cycle = transactions.iter()
+        .filter(|transaction| transaction.wallet_id == wallet_id && transaction.job_id == Some(job_id))
+        .filter(|transaction| transaction.transaction_type == counter.as_str())
+        .count();
+    job_hold_reference(job_id, transaction_type, cycle as i64)
+}
+
 #[async_trait]
 impl WalletRepository for InMemoryWalletRepository {
     async fn create(&self, new_wallet: NewWallet) -> Result<Wallet> {
+        let now = Utc::now();
         let wallet = Wallet {
             id: new_wallet.id,
             customer_id: new_wallet.customer_id,
             balance_cents: new_wallet.balance_cents,
-            created_at: Some(chrono::Utc::now().naive_utc()),
-            updated_at: Some(chrono::Utc::now().naive_utc()),
+            created_at: Some(now),
+            updated_at: Some(now),
+            test_balance_cents: 100000,
+            credit_cents: 0,
+            overdraft_policy: OverdraftPolicy::Deny.as_str().to_string(),
+            overdraft_limit_cents: 0,
         };
-        
-        let mut wallets = self.wallets.lock().map_err(|_| Error::Other(anyhow::anyhow!("Lock error")))?;
+
+        let mut wallets = self.wallets.lock().map_err(|_| anyhow!("Lock error"))?;
         wallets.insert(wallet.id, wallet.clone());
-        
-        let mut customer_wallets = self.customer_wallets.lock().map_err(|_| Error::Other(anyhow::anyhow!("Lock error")))?;
-        customer_wallets.insert(wallet.customer_id, wallet.id);
-        
-        let mut wallet_transactions = self.wallet_transactions.lock().map_err(|_| Error::Other(anyhow::anyhow!("Lock error")))?;
-        wallet_transactions.insert(wallet.id, Vec::new());
-        
+
         Ok(wallet)
     }
-    
+
+    async fn create_many(&self, new_wallets: Vec<NewWallet>) -> Result<Vec<Wallet>> {
+        let mut created = Vec::with_capacity(new_wallets.len());
+        for new_wallet in new_wallets {
+            created.push(self.create(new_wallet).await?);
+        }
+        Ok(created)
+    }
+
     async fn find_by_id(&self, id: Uuid) -> Result<Wallet> {
-        let wallets = self.wallets.lock().map_err(|_| Error::Other(anyhow::anyhow!("Lock error")))?;
-        
+        let wallets = self.wallets.lock().map_err(|_| anyhow!("Lock error"))?;
+
         wallets.get(&id)
             .cloned()
-            .ok_or_else(|| Error::NotFound(format!("Wallet not found: {}", id)))
+            .ok_or_else(|| anyhow!("Wallet not found with ID: {}", id))
     }
-    
+
     async fn find_by_customer_id(&self, customer_id: Uuid) -> Result<Wallet> {
-        // Get wallet ID from customer wallets map, but drop the lock before the await
-        let wallet_id = {
-            let customer_wallets = self.customer_wallets.lock().map_err(|_| Error::Other(anyhow::anyhow!("Lock error")))?;
-            
-            customer_wallets.get(&customer_id)
-                .cloned()
-                .ok_or_else(|| Error::NotFound(format!("Wallet not found for customer: {}", customer_id)))?
-        };
-            
-        // Now find the wallet with the ID (no lock held across await)
-        self.find_by_id(wallet_id).await
-    }
-    
-    async fn update_balance(&self, id: Uuid, new_balance: i32) -> Result<Wallet> {
-        let mut wallets = self.wallets.lock().map_err(|_| Error::Other(anyhow::anyhow!("Lock error")))?;
-        
-        let wallet = wallets.get_mut(&id)
-            .ok_or_else(|| Error::NotFound(format!("Wallet not found: {}", id)))?;
-            
-        wallet.balance_cents = new_balance;
-        wallet.updated_at = Some(chrono::Utc::now().naive_utc());
-        
-        Ok(wallet.clone())
+        let wallets = self.wallets.lock().map_err(|_| anyhow!("Lock error"))?;
+
+        wallets.values()
+            .find(|wallet| wallet.customer_id == customer_id)
+            .cloned()
+            .ok_or_else(|| anyhow!("Wallet not found for customer: {}", customer_id))
     }
-    
-    async fn reserve_funds(&self, id: Uuid, amount: i32) -> Result<Wallet> {
-        let mut wallets = self.wallets.lock().map_err(|_| Error::Other(anyhow::anyhow!("Lock error")))?;
-        
-        let wallet = wallets.get_mut(&id)
-            .ok_or_else(|| Error::NotFound(format!("Wallet not found: {}", id)))?;
-            
-        if wallet.available_balance() < amount {
-            return Err(Error::InsufficientFunds(format!("Insufficient funds. Available: {}, Requested: {}", wallet.available_balance(), amount)));
+
+    async fn update_balance(
+        &self,
+        id: Uuid,
+        amount: i32,
+        transaction_type: TransactionType,
+        description: Option<String>,
+        job_id: Option<Uuid>
+    ) -> Result<Wallet> {
+        self.post(id, amount, transaction_type, description, job_id, None, |_| Ok(()))
+    }
+
+    async fn deposit(
+        &self,
+        id: Uuid,
+        amount: i32,
+        description: Option<String>,
+        job_id: Option<Uuid>
+    ) -> Result<Wallet> {
+        if amount <= 0 {
+            return Err(anyhow!("Deposit amount must be positive"));
         }
-        
-        // Since pending_charges_cents was removed, we'll just update the balance directly
-        // Reserving funds means reducing the available balance
-        wallet.balance_cents -= amount;
-        wallet.updated_at = Some(chrono::Utc::now().naive_utc());
-        
-        Ok(wallet.clone())
+
+        self.update_balance(
+            id,
+            amount,
+            TransactionType::Deposit,
+            description.or_else(|| Some(format!("Deposit of {} cents", amount))),
+            job_id
+        ).await
     }
-    
-    async fn release_reservation(&self, id: Uuid, amount: i32) -> Result<Wallet> {
-        let mut wallets = self.wallets.lock().map_err(|_| Error::Other(anyhow::anyhow!("Lock error")))?;
-        
-        let wallet = wallets.get_mut(&id)
-            .ok_or_else(|| Error::NotFound(format!("Wallet not found: {}", id)))?;
-            
-        // Since pending_charges_cents was removed, we'll just update the balance directly
-        // Releasing a reservation means adding back to the available balance
-        wallet.balance_cents += amount;
-        wallet.updated_at = Some(chrono::Utc::now().naive_utc());
-        
-        Ok(wallet.clone())
+
+    async fn withdraw(
+        &self,
+        id: Uuid,
+        amount: i32,
+        description: Option<String>,
+        job_id: Option<Uuid>
+    ) -> Result<Wallet> {
+        if amount <= 0 {
+            return Err(anyhow!("Withdrawal amount must be positive"));
+        }
+
+        let description = description.or_else(|| Some(format!("Withdrawal of {} cents", amount)));
+        self.post(id, -amount, TransactionType::Withdrawal, description, job_id, None, |wallet| {
+            if wallet.balance_cents < amount {
+                return Err(Error::InsufficientFunds(
+                    format!("Insufficient funds for withdrawal. Available: {}, requested: {}", wallet.balance_cents, amount)
+                ).into());
+            }
+            Ok(())
+        })
+    }
+
+    async fn charge_job(&self, debit: JobDebit) -> Result<Wallet> {
+        let amount = debit.amount_cents();
+        if amount <= 0 {
+            return Err(anyhow!("Job charge must be positive"));
+        }
+
+        // No ledger is kept in memory; the split is only checked to balance
+        if !is_balanced(&debit.postings()) {
+            return Err(anyhow!("Ledger entries of the charge of job {} do not balance", debit.job_id));
+        }
+        let description = debit.description.or_else(|| Some(format!("Charge of {} cents for job {}", amount, debit.job_id)));
+        self.post(debit.wallet_id, -amount, TransactionType::JobDebit, description, Some(debit.job_id), Some(debit.reference_id), |wallet| {
+            if debit.require_funds && wallet.balance_cents < amount {
+                return Err(Error::InsufficientFunds(
+                    format!("Insufficient funds for job charge. Available: {}, requested: {}", wallet.balance_cents, amount)
+                ).into());
+            }
+            Ok(())
+        })
+    }
+
+    async fn reserve_funds(
+        &self,
+        id: Uuid,
+        amount: i32,
+        description: Option<String>,
+        job_id: Option<Uuid>
+    ) -> Result<Wallet> {
+        if amount <= 0 {
+            return Err(anyhow!("Reservation amount must be positive"));
+        }
+
+        let description = description.or_else(|| Some(format!("Reservation of {} cents", amount)));
+        self.post(id, -amount, TransactionType::Reserved, description, job_id, None, |wallet| {
+            if !wallet.can_reserve(amount) {
+                return Err(Error::InsufficientFunds(
+                    format!("Insufficient funds for reservation. Available: {}, requested: {}", wallet.available_balance(), amount)
+                ).into());
+            }
+            Ok(())
+        })
     }
-    
+
+    async fn release_reservation(
+        &self,
+        id: Uuid,
+        amount: i32,
+        description: Option<String>,
+        job_id: Option<Uuid>
+    ) -> Result<Wallet> {
+        if amount <= 0 {
+            return Err(anyhow!("Release amount must be positive"));
+        }
+
+        self.update_balance(
+            id,
+            amount,
+            TransactionType::Released,
+            description.or_else(|| Some(format!("Release of reservation of {} cents", amount))),
+            job_id
+        ).await
+    }
+
     async fn add_transaction(&self, new_transaction: NewWalletTransaction) -> Result<WalletTransaction> {
+        let mut wallets = self.wallets.lock().map_err(|_| anyhow!("Lock error"))?;
+        let mut transactions = self.transactions.lock().map_err(|_| anyhow!("Lock error"))?;
+        let mut references = self.references.lock().map_err(|_| anyhow!("Lock error"))?;
+
+        let wallet = wallets.get_mut(&new_transaction.wallet_id)
+            .ok_or_else(|| anyhow!("Wallet not found with ID: {}", new_transaction.wallet_id))?;
+
+        // A transaction whose reference the wallet recorded already is returned as it was
+        let reference_id = new_transaction.reference_id.or_else(|| {
+            let transaction_type = TransactionType::from_str(&new_transaction.transaction_type)?;
+            job_posting_reference(&transactions, wallet.id, new_transaction.job_id, &transaction_type)
+        });
+        if let Some(reference_id) = reference_id {
+            if let Some(posted) = references.get(&(wallet.id, reference_id)) {
+                return transactions.iter()
+                    .find(|transaction| transaction.id == *posted)
+                    .cloned()
+                    .ok_or_else(|| anyhow!("Transaction not found with ID: {}", posted));
+            }
+            references.insert((wallet.id, reference_id), new_transaction.id);
+        }
+
+        let now = Utc::now();
         let transaction = WalletTransaction {
             id: new_transaction.id,
             wallet_id: new_transaction.wallet_id,
             amount_cents: new_transaction.amount_cents,
             transaction_type: new_transaction.transaction_type,
-            reference_id: new_transaction.reference_id,
+            customer_id: new_transaction.customer_id,
+            reference_id,
             description: new_transaction.description,
             job_id: new_transaction.job_id,
-            created_at: Some(chrono::Utc::now().naive_utc()),
+            created_at: Some(new_transaction.created_at.unwrap_or(now)),
+            customer_reference: new_transaction.customer_reference,
+            customer_metadata: new_transaction.customer_metadata,
         };
-        
-        // Validate wallet exists
-        self.find_by_id(transaction.wallet_id).await?;
-        
-        // Update wallet balance based on transaction
-        let mut wallets = self.wallets.lock().map_err(|_| Error::Other(anyhow::anyhow!("Lock error")))?;
-        
-        let wallet = wallets.get_mut(&transaction.wallet_id)
-            .ok_or_else(|| Error::NotFound(format!("Wallet not found: {}", transaction.wallet_id)))?;
-            
+        transactions.push(transaction.clone());
+
         wallet.balance_cents += transaction.amount_cents;
-        wallet.updated_at = Some(chrono::Utc::now().naive_utc());
-        
-        // Store transaction
-        let mut transactions = self.transactions.lock().map_err(|_| Error::Other(anyhow::anyhow!("Lock error")))?;
-        transactions.insert(transaction.id, transaction.clone());
-        
-        // Associate transaction with wallet
-        let mut wallet_transactions = self.wallet_transactions.lock().map_err(|_| Error::Other(anyhow::anyhow!("Lock error")))?;
-        
-        if let Some(txs) = wallet_transactions.get_mut(&transaction.wallet_id) {
-            txs.push(transaction.id);
-        } else {
-            wallet_transactions.insert(transaction.wallet_id, vec![transaction.id]);
-        }
-        
+        wallet.updated_at = Some(now);
+
         Ok(transaction)
     }
-    
+
     async fn get_transactions(&self, wallet_id: Uuid, limit: i32, offset: i32) -> Result<Vec<WalletTransaction>> {
-        // Check if wallet exists
-        self.find_by_id(wallet_id).await?;
-        
-        let wallet_transactions = self.wallet_transactions.lock().map_err(|_| Error::Other(anyhow::anyhow!("Lock error")))?;
-        let transactions = self.transactions.lock().map_err(|_| Error::Other(anyhow::anyhow!("Lock error")))?;
-        
-        let transaction_ids = wallet_transactions.get(&wallet_id)
+        let transactions = self.transactions.lock().map_err(|_| anyhow!("Lock error"))?;
+
+        // Newest first, like the Diesel implementation
+        Ok(transactions.iter()
+            .rev()
+            .filter(|transaction| transaction.wallet_id == wallet_id)
+            .skip(offset.max(0) as usize)
+            .take(limit.max(0) as usize)
             .cloned()
-            .unwrap_or_default();
-            
-        let result: Vec<WalletTransaction> = transaction_ids.iter()
-            .skip(offset as usize)
-            .take(limit as usize)
-            .filter_map(|id| transactions.get(id).cloned())
-            .collect();
-            
-        Ok(result)
+            .collect())
+    }
+
+    async fn adjust_test_balance(&self, id: Uuid, amount: i32) -> Result<Wallet> {
+        let mut wallets = self.wallets.lock().map_err(|_| anyhow!("Lock error"))?;
+
+        let wallet = wallets.get_mut(&id)
+            .ok_or_else(|| anyhow!("Wallet not found with ID: {}", id))?;
+
+        wallet.test_balance_cents += amount;
+        wallet.updated_at = Some(Utc::now());
+
+        Ok(wallet.clone())
+    }
+
+    async fn grant_credit(&self, id: Uuid, amount: i32) -> Result<Wallet> {
+        if amount <= 0 {
+            return Err(anyhow!("Credit must be positive"));
+        }
+        let mut wallets = self.wallets.lock().map_err(|_| anyhow!("Lock error"))?;
+
+        let wallet = wallets.get_mut(&id)
+            .ok_or_else(|| anyhow!("Wallet not found with ID: {}", id))?;
+
+        wallet.credit_cents += amount;
+        wallet.updated_at = Some(Utc::now());
+
+        Ok(wallet.clone())
+    }
+
+    async fn spend_credit(&self, id: Uuid, amount: i32) -> Result<i32> {
+        let mut wallets = self.wallets.lock().map_err(|_| anyhow!("Lock error"))?;
+
+        let wallet = wallets.get_mut(&id)
+            .ok_or_else(|| anyhow!("Wallet not found with ID: {}", id))?;
+
+        let spent = amount.clamp(0, wallet.credit_cents);
+        if spent > 0 {
+            wallet.credit_cents -= spent;
+            wallet.updated_at = Some(Utc::now());
+        }
+
+        Ok(spent)
+    }
+
+    async fn get_reserved_for_job(&self, job_id: Uuid) -> Result<i32> {
+        let transactions = self.transactions.lock().map_err(|_| anyhow!("Lock error"))?;
+
+        let net: i64 = transactions.iter()
+            .filter(|transaction| transaction.job_id == Some(job_id))
+            .filter(|transaction| matches!(
+                TransactionType::from_str(&transaction.transaction_type),
+                Some(TransactionType::Reserved | TransactionType::Released)
+            ))
+            .map(|transaction| transaction.amount_cents as i64)
+            .sum();
+
+        Ok((-net).max(0) as i32)
+    }
+
+    async fn set_overdraft_policy(&self, id: Uuid, policy: OverdraftPolicy, limit_cents: i32) -> Result<Wallet> {
+        if limit_cents < 0 {
+            return Err(anyhow!("Overdraft limit cannot be negative"));
+        }
+        let mut wallets = self.wallets.lock().map_err(|_| anyhow!("Lock error"))?;
+
+        let wallet = wallets.get_mut(&id)
+            .ok_or_else(|| anyhow!("Wallet not found with ID: {}", id))?;
+        wallet.overdraft_policy = policy.as_str().to_string();
+        wallet.overdraft_limit_cents = limit_cents;
+        wallet.updated_at = Some(Utc::now());
+
+        Ok(wallet.clone())
+    }
+
+    async fn get_balance(&self, id: Uuid) -> Result<i32> {
+        let wallet = self.find_by_id(id).await?;
+        Ok(wallet.balance_cents)
     }
 }
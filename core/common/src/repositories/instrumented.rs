@@ -0,0 +1,1441 @@
+use std::collections::BTreeMap;
+use std::env;
+use std::fmt::Display;
+use std::future::Future;
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant};
+
+use async_trait::async_trait;
+use chrono::{DateTime, NaiveDate, Utc};
+use serde::Serialize;
+use tracing::{warn, Instrument};
+use uuid::Uuid;
+
+use crate::models::audit::{AuditEntry, NewAuditEntry};
+use crate::models::billing_export::{BillingExport, BillingReconciliation, ExportAttempt};
+use crate::models::job_event::JobLifecycleEvent;
+use crate::models::billing_period::{BillingPeriod, Invoice};
+use crate::models::customer::{Customer, NewCustomer};
+use crate::models::pii::PiiPolicy;
+use crate::models::dependency::Blocker;
+use crate::models::feature_flag::{FeatureFlag, NewFeatureFlag};
+use crate::models::job::{CompletionResolution, HeldCompletion, Job, JobHolder, JobStatus, NewJob, PriorityLevel};
+use crate::models::job_cost::CostBreakdown;
+use crate::models::job_error::JobError;
+use crate::models::purge::PurgedRows;
+use crate::models::reseller_invoice::JobTypeCharges;
+use crate::models::job_attempt::{AttemptOutcome, CrashReport, JobAttempt};
+use crate::models::job_log::{JobLog, LogLevel, NewJobLog};
+use crate::models::job_template::{JobTemplate, NewJobTemplate};
+use crate::models::job_usage::{JobUsage, NewJobUsage, UsageSummary};
+use crate::models::load_window::{LoadWindow, LoadWindowStatus, NewLoadWindow};
+use crate::models::submission::{NewSubmission, Submission};
+use crate::models::job_type::{CatalogVisibility, JobType, NewJobType};
+use crate::models::notification::{DeliveryAttempt, DeliveryChannel, NewNotificationDelivery, NewNotificationPreference, NotificationDelivery, NotificationPreference};
+use crate::models::partition::PartitionedTable;
+use crate::models::job_import::{ImportStatus, JobImport, JobImportRow, NewJobImport};
+use crate::models::fixture::{FixtureBundle, FixtureRecords, NewFixtureBundle};
+use crate::models::pipeline::{NewPipeline, NewPipelineRun, Pipeline, PipelineRun, PipelineRunStatus, PipelineRunStep, StepStatus};
+use crate::models::project::{NewProject, Project};
+use crate::models::provider::{NewProvider, Provider, ProviderStatus};
+use crate::models::job_artifact::{JobArtifact, NewJobArtifact};
+use crate::models::ledger::{AccountBalance, JobDebit, LedgerEntry};
+use crate::models::redaction::{NewUnredactedOutput, UnredactedOutput};
+use crate::models::replay::{NewReplayCase, ReplayCase};
+use crate::models::reseller::{NewReseller, Reseller};
+use crate::models::runner::{NewRunner, NewRunnerEnvironmentReport, NewRunnerHealthCheck, Runner, RunnerEnvironmentReport, RunnerHealthCheck};
+use crate::models::spending_alert::{AnomalyKind, NewSpendingAlert, SpendingAlert};
+use crate::models::submission_window::{NewSubmissionWindow, SubmissionWindow};
+use crate::models::wallet::{NewWallet, NewWalletTransaction, OverdraftPolicy, TransactionType, Wallet, WalletTransaction};
+use crate::models::wallet_adjustment::{AdjustmentStatus, NewWalletAdjustment, WalletAdjustment};
+use crate::models::webhook::{CustomerWebhook, NewCustomerWebhook, WebhookEventType};
+use crate::repositories::job::{CustomerActivity, CustomerSpend, JobCursor, JobFilter, JobSortOrder, JobTypeExecutionStats, JobTypeQueueStats, Pagination, PriorityWaitStats, RunnerJobStats};
+use crate::repositories::job_type::CatalogFilter;
+use crate::repositories::{
+    AuditLogRepository, BillingExportRepository, BillingPeriodRepository, CustomerRepository, CustomerWebhookRepository, DependencyRepository, FeatureFlagRepository, JobArtifactRepository, JobAttemptRepository, JobEventRepository, JobLogRepository, JobRepository, JobTemplateRepository, JobUsageRepository,
+    JobTypeRepository, LedgerRepository, LoadWindowRepository, NotificationDeliveryRepository, NotificationPreferenceRepository, PartitionRepository, PipelineRepository, JobImportRepository, PurgeRepository, FixtureRepository, ProjectRepository, ProviderRepository, ReplayCaseRepository, RequestNonceRepository, ResellerRepository, RunnerRepository, SearchRepository,
+    SpendingAlertRepository, SubmissionRepository, SubmissionWindowRepository, UnredactedOutputRepository, WalletAdjustmentRepository, WalletRepository,
+    WalletTransactionRepository,
+};
+
+/// Configuration for repository call metrics and slow query logging
+#[derive(Debug, Clone)]
+pub struct RepositoryMetricsConfig {
+    /// Calls taking longer than this (in milliseconds) are logged with their filter parameters (0 disables the log)
+    pub slow_query_threshold_ms: u64,
+}
+
+impl Default for RepositoryMetricsConfig {
+    fn default() -> Self {
+        Self {
+            slow_query_threshold_ms: 500,
+        }
+    }
+}
+
+impl RepositoryMetricsConfig {
+    /// Load the configuration from `REPOSITORY_*` environment variables, using defaults for unset values
+    pub fn from_env() -> Self {
+        let defaults = Self::default();
+        Self {
+            slow_query_threshold_ms: env::var("REPOSITORY_SLOW_QUERY_THRESHOLD_MS").ok()
+                .and_then(|value| value.parse().ok())
+                .unwrap_or(defaults.slow_query_threshold_ms),
+        }
+    }
+}
+
+/// Calls, failures and timings of a single repository method
+#[derive(Debug, Clone, Serialize)]
+pub struct MethodStats {
+    /// Entity the repository stores, e.g. `job`
+    pub entity: &'static str,
+    pub method: &'static str,
+    pub calls: u64,
+    /// Calls that returned an error
+    pub errors: u64,
+    /// Calls exceeding the slow query threshold
+    pub slow_calls: u64,
+    pub total_seconds: f64,
+    pub max_seconds: f64,
+}
+
+impl MethodStats {
+    fn new(entity: &'static str, method: &'static str) -> Self {
+        Self { entity, method, calls: 0, errors: 0, slow_calls: 0, total_seconds: 0.0, max_seconds: 0.0 }
+    }
+
+    /// Average duration of a call, 0 without calls
+    pub fn avg_seconds(&self) -> f64 {
+        if self.calls == 0 { 0.0 } else { self.total_seconds / self.calls as f64 }
+    }
+}
+
+/// Aggregated call metrics of all instrumented repositories, shared by their decorators
+pub struct RepositoryMetrics {
+    config: RepositoryMetricsConfig,
+    methods: Mutex<BTreeMap<(&'static str, &'static str), MethodStats>>,
+}
+
+impl RepositoryMetrics {
+    /// Create an empty metrics registry
+    pub fn new(config: RepositoryMetricsConfig) -> Self {
+        Self {
+            config,
+            methods: Mutex::new(BTreeMap::new()),
+        }
+    }
+
+    /// Configured slow query threshold in milliseconds
+    pub fn slow_query_threshold_ms(&self) -> u64 {
+        self.config.slow_query_threshold_ms
+    }
+
+    /// Whether slow calls are logged, so their parameters need to be captured
+    fn logs_slow_calls(&self) -> bool {
+        self.config.slow_query_threshold_ms > 0
+    }
+
+    /// Record a finished call; returns whether it was slow
+    pub fn record(&self, entity: &'static str, method: &'static str, elapsed: Duration, failed: bool) -> bool {
+        let slow = self.logs_slow_calls() && elapsed >= Duration::from_millis(self.config.slow_query_threshold_ms);
+        let seconds = elapsed.as_secs_f64();
+
+        // A poisoned lock only means another call panicked while recording; keep counting
+        let mut methods = self.methods.lock().unwrap_or_else(|poisoned| poisoned.into_inner());
+        let stats = methods.entry((entity, method)).or_insert_with(|| MethodStats::new(entity, method));
+        stats.calls += 1;
+        stats.errors += u64::from(failed);
+        stats.slow_calls += u64::from(slow);
+        stats.total_seconds += seconds;
+        stats.max_seconds = stats.max_seconds.max(seconds);
+
+        slow
+    }
+
+    /// Statistics of every method called so far, ordered by entity and method
+    pub fn snapshot(&self) -> Vec<MethodStats> {
+        let methods = self.methods.lock().unwrap_or_else(|poisoned| poisoned.into_inner());
+        methods.values().cloned().collect()
+    }
+
+    /// Time a repository call inside a tracing span, record it and log it if it was slow
+    async fn observe<T, E: Display>(
+        &self,
+        entity: &'static str,
+        method: &'static str,
+        params: Vec<String>,
+        call: impl Future<Output = Result<T, E>>,
+    ) -> Result<T, E> {
+        let span = tracing::debug_span!("repository", entity, method);
+        let started = Instant::now();
+        let result = call.instrument(span).await;
+        let elapsed = started.elapsed();
+
+        if self.record(entity, method, elapsed, result.is_err()) {
+            warn!(
+                "Slow repository call {}.{} took {} ms ({}){}",
+                entity,
+                method,
+                elapsed.as_millis(),
+                params.join(", "),
+                result.as_ref().err().map(|e| format!(": {}", e)).unwrap_or_default()
+            );
+        }
+
+        result
+    }
+}
+
+/// Repository decorator recording the duration and outcome of every call
+///
+/// Wraps any repository implementation; the filter parameters of each method are
+/// captured so slow calls can be logged with them. Payloads and API keys are not.
+pub struct Instrumented<R> {
+    entity: &'static str,
+    inner: R,
+    metrics: Arc<RepositoryMetrics>,
+}
+
+impl<R> Instrumented<R> {
+    /// Wrap a repository, recording its calls under `entity` in `metrics`
+    pub fn new(entity: &'static str, inner: R, metrics: Arc<RepositoryMetrics>) -> Self {
+        Self { entity, inner, metrics }
+    }
+}
+
+/// Delegate a call to the wrapped repository through `RepositoryMetrics::observe`;
+/// the arguments listed after `;` are logged if the call is slow
+macro_rules! observe {
+    ($self:ident.$method:ident($($arg:ident),*) $(; $($param:ident),+)?) => {{
+        let params: Vec<String> = if $self.metrics.logs_slow_calls() {
+            vec![$($(format!("{}={:?}", stringify!($param), $param)),+)?]
+        } else {
+            Vec::new()
+        };
+        $self.metrics.observe($self.entity, stringify!($method), params, $self.inner.$method($($arg),*)).await
+    }};
+}
+
+#[async_trait]
+impl<R: CustomerRepository> CustomerRepository for Instrumented<R> {
+    async fn create(&self, new_customer: NewCustomer) -> anyhow::Result<Customer> {
+        observe!(self.create(new_customer))
+    }
+
+    async fn create_many(&self, new_customers: Vec<NewCustomer>) -> anyhow::Result<Vec<Customer>> {
+        observe!(self.create_many(new_customers))
+    }
+
+    async fn find_by_id(&self, id: Uuid) -> anyhow::Result<Customer> {
+        observe!(self.find_by_id(id); id)
+    }
+
+    async fn find_by_api_key(&self, api_key: &str) -> anyhow::Result<Customer> {
+        observe!(self.find_by_api_key(api_key))
+    }
+
+    async fn find_by_test_api_key(&self, test_api_key: &str) -> anyhow::Result<Customer> {
+        observe!(self.find_by_test_api_key(test_api_key))
+    }
+
+    async fn find_by_reseller_id(&self, reseller_id: Uuid) -> anyhow::Result<Vec<Customer>> {
+        observe!(self.find_by_reseller_id(reseller_id); reseller_id)
+    }
+
+    async fn find_by_parent_id(&self, parent_id: Uuid) -> anyhow::Result<Vec<Customer>> {
+        observe!(self.find_by_parent_id(parent_id); parent_id)
+    }
+
+    async fn set_budget(&self, customer_id: Uuid, budget_cents: Option<i32>) -> anyhow::Result<Customer> {
+        observe!(self.set_budget(customer_id, budget_cents); customer_id, budget_cents)
+    }
+
+    async fn set_timezone(&self, customer_id: Uuid, timezone: Option<String>) -> anyhow::Result<Customer> {
+        observe!(self.set_timezone(customer_id, timezone); customer_id, timezone)
+    }
+
+    async fn set_default_priority(&self, customer_id: Uuid, default_priority: Option<i32>) -> anyhow::Result<Customer> {
+        observe!(self.set_default_priority(customer_id, default_priority); customer_id, default_priority)
+    }
+
+    async fn set_pii_policy(&self, customer_id: Uuid, pii_policy: Option<PiiPolicy>) -> anyhow::Result<Customer> {
+        observe!(self.set_pii_policy(customer_id, pii_policy); customer_id, pii_policy)
+    }
+
+    async fn set_active(&self, customer_id: Uuid, active: bool) -> anyhow::Result<Customer> {
+        observe!(self.set_active(customer_id, active); customer_id, active)
+    }
+
+    async fn update(&self, customer: &Customer) -> anyhow::Result<Customer> {
+        observe!(self.update(customer))
+    }
+
+    async fn set_reseller(&self, customer_id: Uuid, reseller_id: Option<Uuid>) -> anyhow::Result<Customer> {
+        observe!(self.set_reseller(customer_id, reseller_id); customer_id, reseller_id)
+    }
+
+    async fn generate_api_key(&self, customer_id: Uuid) -> anyhow::Result<String> {
+        observe!(self.generate_api_key(customer_id); customer_id)
+    }
+
+    async fn generate_test_api_key(&self, customer_id: Uuid) -> anyhow::Result<String> {
+        observe!(self.generate_test_api_key(customer_id); customer_id)
+    }
+
+    async fn list_all(&self) -> anyhow::Result<Vec<Customer>> {
+        observe!(self.list_all())
+    }
+}
+
+#[async_trait]
+impl<R: WalletRepository> WalletRepository for Instrumented<R> {
+    async fn create(&self, new_wallet: NewWallet) -> anyhow::Result<Wallet> {
+        observe!(self.create(new_wallet))
+    }
+
+    async fn create_many(&self, new_wallets: Vec<NewWallet>) -> anyhow::Result<Vec<Wallet>> {
+        observe!(self.create_many(new_wallets))
+    }
+
+    async fn find_by_id(&self, id: Uuid) -> anyhow::Result<Wallet> {
+        observe!(self.find_by_id(id); id)
+    }
+
+    async fn find_by_customer_id(&self, customer_id: Uuid) -> anyhow::Result<Wallet> {
+        observe!(self.find_by_customer_id(customer_id); customer_id)
+    }
+
+    async fn update_balance(&self, id: Uuid, amount: i32, transaction_type: TransactionType, description: Option<String>, job_id: Option<Uuid>) -> anyhow::Result<Wallet> {
+        observe!(self.update_balance(id, amount, transaction_type, description, job_id); id, amount, transaction_type, job_id)
+    }
+
+    async fn deposit(&self, id: Uuid, amount: i32, description: Option<String>, job_id: Option<Uuid>) -> anyhow::Result<Wallet> {
+        observe!(self.deposit(id, amount, description, job_id); id, amount, job_id)
+    }
+
+    async fn withdraw(&self, id: Uuid, amount: i32, description: Option<String>, job_id: Option<Uuid>) -> anyhow::Result<Wallet> {
+        observe!(self.withdraw(id, amount, description, job_id); id, amount, job_id)
+    }
+
+    async fn charge_job(&self, debit: JobDebit) -> anyhow::Result<Wallet> {
+        observe!(self.charge_job(debit); debit)
+    }
+
+    async fn reserve_funds(&self, id: Uuid, amount: i32, description: Option<String>, job_id: Option<Uuid>) -> anyhow::Result<Wallet> {
+        observe!(self.reserve_funds(id, amount, description, job_id); id, amount, job_id)
+    }
+
+    async fn release_reservation(&self, id: Uuid, amount: i32, description: Option<String>, job_id: Option<Uuid>) -> anyhow::Result<Wallet> {
+        observe!(self.release_reservation(id, amount, description, job_id); id, amount, job_id)
+    }
+
+    async fn add_transaction(&self, new_transaction: NewWalletTransaction) -> anyhow::Result<WalletTransaction> {
+        observe!(self.add_transaction(new_transaction))
+    }
+
+    async fn get_transactions(&self, wallet_id: Uuid, limit: i32, offset: i32) -> anyhow::Result<Vec<WalletTransaction>> {
+        observe!(self.get_transactions(wallet_id, limit, offset); wallet_id, limit, offset)
+    }
+
+    async fn get_reserved_for_job(&self, job_id: Uuid) -> anyhow::Result<i32> {
+        observe!(self.get_reserved_for_job(job_id); job_id)
+    }
+
+    async fn adjust_test_balance(&self, id: Uuid, amount: i32) -> anyhow::Result<Wallet> {
+        observe!(self.adjust_test_balance(id, amount); id, amount)
+    }
+
+    async fn grant_credit(&self, id: Uuid, amount: i32) -> anyhow::Result<Wallet> {
+        observe!(self.grant_credit(id, amount); id, amount)
+    }
+
+    async fn spend_credit(&self, id: Uuid, amount: i32) -> anyhow::Result<i32> {
+        observe!(self.spend_credit(id, amount); id, amount)
+    }
+
+    async fn set_overdraft_policy(&self, id: Uuid, policy: OverdraftPolicy, limit_cents: i32) -> anyhow::Result<Wallet> {
+        observe!(self.set_overdraft_policy(id, policy, limit_cents); id, policy, limit_cents)
+    }
+
+    async fn get_balance(&self, id: Uuid) -> anyhow::Result<i32> {
+        observe!(self.get_balance(id); id)
+    }
+}
+
+#[async_trait]
+impl<R: JobRepository> JobRepository for Instrumented<R> {
+    async fn create(&self, new_job: NewJob) -> crate::Result<Job> {
+        observe!(self.create(new_job))
+    }
+
+    async fn create_many(&self, new_jobs: Vec<NewJob>) -> crate::Result<Vec<Job>> {
+        observe!(self.create_many(new_jobs))
+    }
+
+    async fn find_by_id(&self, id: Uuid) -> crate::Result<Job> {
+        observe!(self.find_by_id(id); id)
+    }
+
+    async fn find_by_public_id(&self, public_id: &str) -> crate::Result<Job> {
+        observe!(self.find_by_public_id(public_id); public_id)
+    }
+
+    async fn update_status(&self, id: Uuid, status: JobStatus) -> crate::Result<Job> {
+        observe!(self.update_status(id, status); id, status)
+    }
+
+    async fn set_started(&self, id: Uuid) -> crate::Result<Job> {
+        observe!(self.set_started(id); id)
+    }
+
+    async fn set_completed(&self, id: Uuid, success: bool, output: Option<serde_json::Value>, error: Option<JobError>, cost_cents: i32) -> crate::Result<Job> {
+        observe!(self.set_completed(id, success, output, error, cost_cents); id, success, cost_cents)
+    }
+
+    async fn complete_held(
+        &self,
+        id: Uuid,
+        holder: JobHolder,
+        success: bool,
+        output: Option<serde_json::Value>,
+        error: Option<JobError>,
+        cost_cents: i32,
+    ) -> crate::Result<(CompletionResolution, Job)> {
+        observe!(self.complete_held(id, holder, success, output, error, cost_cents); id, holder, success, cost_cents)
+    }
+
+    async fn complete_held_many(&self, completions: Vec<HeldCompletion>) -> crate::Result<Vec<(CompletionResolution, Job)>> {
+        let jobs = completions.len();
+        observe!(self.complete_held_many(completions); jobs)
+    }
+
+    async fn hand_back(&self, id: Uuid, runner_id: Uuid) -> crate::Result<Option<Job>> {
+        observe!(self.hand_back(id, runner_id); id, runner_id)
+    }
+
+    async fn request_cancellation(&self, id: Uuid) -> crate::Result<Job> {
+        observe!(self.request_cancellation(id); id)
+    }
+
+    async fn cancellation_requested(&self, id: Uuid) -> crate::Result<bool> {
+        observe!(self.cancellation_requested(id); id)
+    }
+
+    async fn find_running_by_runner(&self, runner_id: Uuid) -> crate::Result<Vec<Job>> {
+        observe!(self.find_running_by_runner(runner_id); runner_id)
+    }
+
+    async fn set_cost_breakdown(&self, id: Uuid, breakdown: CostBreakdown) -> crate::Result<()> {
+        observe!(self.set_cost_breakdown(id, breakdown); id)
+    }
+
+    async fn assign_runner(&self, id: Uuid, runner_id: Uuid) -> crate::Result<()> {
+        observe!(self.assign_runner(id, runner_id); id, runner_id)
+    }
+
+    async fn record_claim(&self, id: Uuid, priority: PriorityLevel) -> crate::Result<()> {
+        observe!(self.record_claim(id, priority); id, priority)
+    }
+
+    async fn lease(&self, id: Uuid, priority: PriorityLevel, runner_id: Option<Uuid>, until: DateTime<Utc>) -> crate::Result<()> {
+        observe!(self.lease(id, priority, runner_id, until); id, priority, runner_id, until)
+    }
+
+    async fn release_expired_leases(&self, now: DateTime<Utc>) -> crate::Result<Vec<(Uuid, PriorityLevel)>> {
+        observe!(self.release_expired_leases(now); now)
+    }
+
+    async fn schedule(&self, id: Uuid, scheduled_for: DateTime<Utc>) -> crate::Result<Job> {
+        observe!(self.schedule(id, scheduled_for); id, scheduled_for)
+    }
+
+    async fn postpone_scheduled(&self, job_type_ids: Vec<Uuid>, due_before: DateTime<Utc>, until: DateTime<Utc>) -> crate::Result<Vec<Uuid>> {
+        observe!(self.postpone_scheduled(job_type_ids, due_before, until); job_type_ids, due_before, until)
+    }
+
+    async fn find_by_customer_id(&self, customer_id: Uuid) -> crate::Result<Vec<Job>> {
+        observe!(self.find_by_customer_id(customer_id); customer_id)
+    }
+
+    async fn find_by_status(&self, status: JobStatus) -> crate::Result<Vec<Job>> {
+        observe!(self.find_by_status(status); status)
+    }
+
+    async fn find_pending_jobs(&self, limit: i32) -> crate::Result<Vec<Job>> {
+        observe!(self.find_pending_jobs(limit); limit)
+    }
+
+    async fn query_jobs(&self, filter: JobFilter, sort: Option<JobSortOrder>, pagination: Option<Pagination>) -> crate::Result<(Vec<Job>, u64)> {
+        observe!(self.query_jobs(filter, sort, pagination); filter, sort, pagination)
+    }
+
+    async fn query_jobs_after(&self, filter: JobFilter, cursor: Option<JobCursor>, limit: u32) -> crate::Result<(Vec<Job>, Option<JobCursor>)> {
+        observe!(self.query_jobs_after(filter, cursor, limit); filter, cursor, limit)
+    }
+
+    async fn get_job_stats_by_status(&self) -> crate::Result<Vec<(String, i64)>> {
+        observe!(self.get_job_stats_by_status())
+    }
+
+    async fn get_job_stats_by_customer(&self) -> crate::Result<Vec<(Uuid, i64)>> {
+        observe!(self.get_job_stats_by_customer())
+    }
+
+    async fn get_cost_statistics(&self) -> crate::Result<(i64, i64)> {
+        observe!(self.get_cost_statistics())
+    }
+
+    async fn get_queue_stats_by_job_type(&self, window_minutes: i32) -> crate::Result<Vec<JobTypeQueueStats>> {
+        observe!(self.get_queue_stats_by_job_type(window_minutes); window_minutes)
+    }
+
+    async fn get_runner_job_stats(&self, runner_id: Uuid, window_minutes: i32) -> crate::Result<RunnerJobStats> {
+        observe!(self.get_runner_job_stats(runner_id, window_minutes); runner_id, window_minutes)
+    }
+
+    async fn get_spend_by_customer(&self, customer_ids: Vec<Uuid>, since: DateTime<Utc>) -> crate::Result<Vec<CustomerSpend>> {
+        observe!(self.get_spend_by_customer(customer_ids, since); customer_ids, since)
+    }
+
+    async fn get_customer_activity(&self, since: DateTime<Utc>, until: DateTime<Utc>) -> crate::Result<Vec<CustomerActivity>> {
+        observe!(self.get_customer_activity(since, until); since, until)
+    }
+
+    async fn get_charges_by_job_type(&self, customer_ids: Vec<Uuid>, since: DateTime<Utc>, until: DateTime<Utc>) -> crate::Result<Vec<JobTypeCharges>> {
+        observe!(self.get_charges_by_job_type(customer_ids, since, until); customer_ids, since, until)
+    }
+
+    async fn get_queue_wait_stats(&self, window_minutes: i32) -> crate::Result<Vec<PriorityWaitStats>> {
+        observe!(self.get_queue_wait_stats(window_minutes); window_minutes)
+    }
+
+    async fn get_job_type_execution_stats(&self, job_type_id: Uuid, window_minutes: i32) -> crate::Result<JobTypeExecutionStats> {
+        observe!(self.get_job_type_execution_stats(job_type_id, window_minutes); job_type_id, window_minutes)
+    }
+
+    async fn find_stalled_jobs(&self, running_threshold_minutes: i32) -> crate::Result<Vec<Job>> {
+        observe!(self.find_stalled_jobs(running_threshold_minutes); running_threshold_minutes)
+    }
+
+    async fn find_waiting_since(&self, before: DateTime<Utc>) -> crate::Result<Vec<Job>> {
+        observe!(self.find_waiting_since(before); before)
+    }
+
+    async fn find_expired(&self, now: DateTime<Utc>) -> crate::Result<Vec<Job>> {
+        observe!(self.find_expired(now); now)
+    }
+
+    async fn find_changed_since(&self, since: DateTime<Utc>, limit: i64) -> crate::Result<Vec<Job>> {
+        observe!(self.find_changed_since(since, limit); since, limit)
+    }
+
+    async fn bulk_update_status(&self, ids: Vec<Uuid>, status: JobStatus) -> crate::Result<usize> {
+        observe!(self.bulk_update_status(ids, status); ids, status)
+    }
+
+    async fn count_pending_by_job_type(&self, job_type_id: Uuid) -> crate::Result<i64> {
+        observe!(self.count_pending_by_job_type(job_type_id); job_type_id)
+    }
+
+    async fn fan_out(&self, id: Uuid, sub_tasks: Vec<NewJob>) -> crate::Result<Vec<Job>> {
+        observe!(self.fan_out(id, sub_tasks); id)
+    }
+
+    async fn find_sub_tasks(&self, id: Uuid) -> crate::Result<Vec<Job>> {
+        observe!(self.find_sub_tasks(id); id)
+    }
+
+    async fn fan_in(&self, id: Uuid) -> crate::Result<Option<Job>> {
+        observe!(self.fan_in(id); id)
+    }
+
+    async fn find_fanned_out(&self) -> crate::Result<Vec<Job>> {
+        observe!(self.find_fanned_out())
+    }
+
+    async fn find_by_submission(&self, submission_id: Uuid) -> crate::Result<Vec<Job>> {
+        observe!(self.find_by_submission(submission_id); submission_id)
+    }
+}
+
+#[async_trait]
+impl<R: JobTypeRepository> JobTypeRepository for Instrumented<R> {
+    async fn create(&self, new_job_type: NewJobType) -> crate::Result<JobType> {
+        observe!(self.create(new_job_type))
+    }
+
+    async fn find_by_id(&self, id: Uuid) -> crate::Result<JobType> {
+        observe!(self.find_by_id(id); id)
+    }
+
+    async fn update(&self, job_type: JobType) -> crate::Result<JobType> {
+        observe!(self.update(job_type))
+    }
+
+    async fn list_all(&self) -> crate::Result<Vec<JobType>> {
+        observe!(self.list_all())
+    }
+
+    async fn list_enabled(&self) -> crate::Result<Vec<JobType>> {
+        observe!(self.list_enabled())
+    }
+
+    async fn search_catalog(&self, filter: CatalogFilter) -> crate::Result<Vec<JobType>> {
+        observe!(self.search_catalog(filter); filter)
+    }
+
+    async fn list_catalog_categories(&self, visibilities: Vec<CatalogVisibility>) -> crate::Result<Vec<(String, i64)>> {
+        observe!(self.list_catalog_categories(visibilities); visibilities)
+    }
+}
+
+#[async_trait]
+impl<R: ResellerRepository> ResellerRepository for Instrumented<R> {
+    async fn create(&self, reseller: NewReseller) -> anyhow::Result<Reseller> {
+        observe!(self.create(reseller))
+    }
+
+    async fn find_by_id(&self, id: Uuid) -> anyhow::Result<Reseller> {
+        observe!(self.find_by_id(id); id)
+    }
+
+    async fn find_by_api_key(&self, api_key: &str) -> anyhow::Result<Reseller> {
+        observe!(self.find_by_api_key(api_key))
+    }
+
+    async fn update(&self, reseller: &Reseller) -> anyhow::Result<Reseller> {
+        observe!(self.update(reseller))
+    }
+
+    async fn list_all(&self) -> anyhow::Result<Vec<Reseller>> {
+        observe!(self.list_all())
+    }
+
+    async fn list_active(&self) -> anyhow::Result<Vec<Reseller>> {
+        observe!(self.list_active())
+    }
+
+    async fn list_sandboxes(&self, parent_id: Uuid) -> anyhow::Result<Vec<Reseller>> {
+        observe!(self.list_sandboxes(parent_id); parent_id)
+    }
+
+    async fn find_expired_sandboxes(&self, now: DateTime<Utc>) -> anyhow::Result<Vec<Reseller>> {
+        observe!(self.find_expired_sandboxes(now); now)
+    }
+
+    async fn delete_sandbox(&self, id: Uuid) -> anyhow::Result<()> {
+        observe!(self.delete_sandbox(id); id)
+    }
+}
+
+#[async_trait]
+impl<R: ProjectRepository> ProjectRepository for Instrumented<R> {
+    async fn create(&self, project: NewProject) -> anyhow::Result<Project> {
+        observe!(self.create(project))
+    }
+
+    async fn find_by_id(&self, id: Uuid) -> anyhow::Result<Project> {
+        observe!(self.find_by_id(id); id)
+    }
+
+    async fn find_by_customer_id(&self, customer_id: Uuid) -> anyhow::Result<Vec<Project>> {
+        observe!(self.find_by_customer_id(customer_id); customer_id)
+    }
+
+    async fn update(&self, project: &Project) -> anyhow::Result<Project> {
+        observe!(self.update(project))
+    }
+
+    async fn list_all(&self) -> anyhow::Result<Vec<Project>> {
+        observe!(self.list_all())
+    }
+
+    async fn delete(&self, id: Uuid) -> anyhow::Result<()> {
+        observe!(self.delete(id); id)
+    }
+}
+
+#[async_trait]
+impl<R: RunnerRepository> RunnerRepository for Instrumented<R> {
+    async fn register(&self, runner: NewRunner) -> anyhow::Result<Runner> {
+        observe!(self.register(runner))
+    }
+
+    async fn update_heartbeat(&self, id: Uuid, timestamp: DateTime<Utc>) -> anyhow::Result<Runner> {
+        observe!(self.update_heartbeat(id, timestamp); id, timestamp)
+    }
+
+    async fn find_by_id(&self, id: Uuid) -> anyhow::Result<Runner> {
+        observe!(self.find_by_id(id); id)
+    }
+
+    async fn update_capabilities(&self, id: Uuid, job_types: Vec<Uuid>) -> anyhow::Result<Runner> {
+        observe!(self.update_capabilities(id, job_types); id, job_types)
+    }
+
+    async fn find_compatible_job_type_ids(&self, id: Uuid) -> anyhow::Result<Vec<Uuid>> {
+        observe!(self.find_compatible_job_type_ids(id); id)
+    }
+
+    async fn list_all(&self) -> anyhow::Result<Vec<Runner>> {
+        observe!(self.list_all())
+    }
+
+    async fn list_active(&self, since: DateTime<Utc>) -> anyhow::Result<Vec<Runner>> {
+        observe!(self.list_active(since); since)
+    }
+
+    async fn find_compatible_with_job_type(&self, job_type: &JobType) -> anyhow::Result<Vec<Runner>> {
+        observe!(self.find_compatible_with_job_type(job_type))
+    }
+
+    async fn set_status(&self, id: Uuid, active: bool) -> anyhow::Result<Runner> {
+        observe!(self.set_status(id, active); id, active)
+    }
+
+    async fn record_health_check(&self, check: NewRunnerHealthCheck) -> anyhow::Result<RunnerHealthCheck> {
+        observe!(self.record_health_check(check))
+    }
+
+    async fn latest_health_check(&self, runner_id: Uuid) -> anyhow::Result<Option<RunnerHealthCheck>> {
+        observe!(self.latest_health_check(runner_id); runner_id)
+    }
+
+    async fn get_health_history(&self, runner_id: Uuid, limit: i64) -> anyhow::Result<Vec<RunnerHealthCheck>> {
+        observe!(self.get_health_history(runner_id, limit); runner_id, limit)
+    }
+
+    async fn health_status_since(&self, runner_id: Uuid, status: &str) -> anyhow::Result<Option<DateTime<Utc>>> {
+        observe!(self.health_status_since(runner_id, status); runner_id, status)
+    }
+
+    async fn delete_inactive_before(&self, before: DateTime<Utc>) -> anyhow::Result<Vec<Uuid>> {
+        observe!(self.delete_inactive_before(before); before)
+    }
+
+    async fn record_environment(&self, report: NewRunnerEnvironmentReport) -> anyhow::Result<Option<RunnerEnvironmentReport>> {
+        observe!(self.record_environment(report))
+    }
+
+    async fn get_environment_history(&self, runner_id: Uuid, limit: i64) -> anyhow::Result<Vec<RunnerEnvironmentReport>> {
+        observe!(self.get_environment_history(runner_id, limit); runner_id, limit)
+    }
+
+    async fn latest_environments(&self) -> anyhow::Result<Vec<RunnerEnvironmentReport>> {
+        observe!(self.latest_environments())
+    }
+}
+
+#[async_trait]
+impl<R: WalletTransactionRepository> WalletTransactionRepository for Instrumented<R> {
+    async fn create(&self, transaction: NewWalletTransaction) -> anyhow::Result<WalletTransaction> {
+        observe!(self.create(transaction))
+    }
+
+    async fn find_by_id(&self, id: Uuid) -> anyhow::Result<WalletTransaction> {
+        observe!(self.find_by_id(id); id)
+    }
+
+    async fn find_by_wallet_id(&self, wallet_id: Uuid) -> anyhow::Result<Vec<WalletTransaction>> {
+        observe!(self.find_by_wallet_id(wallet_id); wallet_id)
+    }
+
+    async fn find_by_customer_id(&self, customer_id: Uuid) -> anyhow::Result<Vec<WalletTransaction>> {
+        observe!(self.find_by_customer_id(customer_id); customer_id)
+    }
+
+    async fn find_in_time_range(&self, start_time: DateTime<Utc>, end_time: DateTime<Utc>) -> anyhow::Result<Vec<WalletTransaction>> {
+        observe!(self.find_in_time_range(start_time, end_time); start_time, end_time)
+    }
+
+    async fn find_by_transaction_type(&self, transaction_type: TransactionType) -> anyhow::Result<Vec<WalletTransaction>> {
+        observe!(self.find_by_transaction_type(transaction_type); transaction_type)
+    }
+
+    async fn find_by_job_id(&self, job_id: Option<Uuid>) -> anyhow::Result<Vec<WalletTransaction>> {
+        observe!(self.find_by_job_id(job_id); job_id)
+    }
+
+    async fn find_by_customer_reference(&self, customer_id: Uuid, reference: &str) -> anyhow::Result<Vec<WalletTransaction>> {
+        observe!(self.find_by_customer_reference(customer_id, reference); customer_id, reference)
+    }
+}
+
+#[async_trait]
+impl<R: CustomerWebhookRepository> CustomerWebhookRepository for Instrumented<R> {
+    async fn create(&self, webhook: NewCustomerWebhook) -> anyhow::Result<CustomerWebhook> {
+        observe!(self.create(webhook))
+    }
+
+    async fn find_by_id(&self, id: Uuid) -> anyhow::Result<CustomerWebhook> {
+        observe!(self.find_by_id(id); id)
+    }
+
+    async fn find_by_customer_id(&self, customer_id: Uuid) -> anyhow::Result<Vec<CustomerWebhook>> {
+        observe!(self.find_by_customer_id(customer_id); customer_id)
+    }
+
+    async fn find_active_for_event(&self, customer_id: Uuid, event_type: WebhookEventType) -> anyhow::Result<Vec<CustomerWebhook>> {
+        observe!(self.find_active_for_event(customer_id, event_type); customer_id, event_type)
+    }
+
+    async fn update(&self, webhook: &CustomerWebhook) -> anyhow::Result<CustomerWebhook> {
+        observe!(self.update(webhook))
+    }
+
+    async fn record_test_result(&self, id: Uuid, status_code: Option<i32>, response: Option<String>) -> anyhow::Result<CustomerWebhook> {
+        observe!(self.record_test_result(id, status_code, response); id, status_code)
+    }
+
+    async fn set_failing_since(&self, id: Uuid, failing_since: Option<DateTime<Utc>>) -> anyhow::Result<CustomerWebhook> {
+        observe!(self.set_failing_since(id, failing_since); id, failing_since)
+    }
+
+    async fn record_delivery_failure(&self, id: Uuid, at: DateTime<Utc>, disable_failing_since: DateTime<Utc>) -> anyhow::Result<Option<CustomerWebhook>> {
+        observe!(self.record_delivery_failure(id, at, disable_failing_since); id)
+    }
+
+    async fn record_delivery_success(&self, id: Uuid) -> anyhow::Result<()> {
+        observe!(self.record_delivery_success(id); id)
+    }
+
+    async fn delete(&self, id: Uuid) -> anyhow::Result<()> {
+        observe!(self.delete(id); id)
+    }
+}
+
+#[async_trait]
+impl<R: NotificationDeliveryRepository> NotificationDeliveryRepository for Instrumented<R> {
+    async fn create(&self, delivery: NewNotificationDelivery) -> anyhow::Result<NotificationDelivery> {
+        observe!(self.create(delivery))
+    }
+
+    async fn find_by_id(&self, id: Uuid) -> anyhow::Result<NotificationDelivery> {
+        observe!(self.find_by_id(id); id)
+    }
+
+    async fn find_failed_by_customer(&self, customer_id: Uuid, limit: i64, offset: i64) -> anyhow::Result<Vec<NotificationDelivery>> {
+        observe!(self.find_failed_by_customer(customer_id, limit, offset); customer_id, limit, offset)
+    }
+
+    async fn find_due_retries(&self, channel: DeliveryChannel, now: DateTime<Utc>, limit: i64) -> anyhow::Result<Vec<NotificationDelivery>> {
+        observe!(self.find_due_retries(channel, now, limit); channel, now, limit)
+    }
+
+    async fn find_queued(&self, channel: DeliveryChannel, limit: i64) -> anyhow::Result<Vec<NotificationDelivery>> {
+        observe!(self.find_queued(channel, limit); channel, limit)
+    }
+
+    async fn record_attempt(&self, id: Uuid, attempt: DeliveryAttempt) -> anyhow::Result<NotificationDelivery> {
+        observe!(self.record_attempt(id, attempt); id)
+    }
+}
+
+#[async_trait]
+impl<R: NotificationPreferenceRepository> NotificationPreferenceRepository for Instrumented<R> {
+    async fn find_by_customer(&self, customer_id: Uuid) -> anyhow::Result<Vec<NotificationPreference>> {
+        observe!(self.find_by_customer(customer_id); customer_id)
+    }
+
+    async fn upsert(&self, preference: NewNotificationPreference) -> anyhow::Result<NotificationPreference> {
+        observe!(self.upsert(preference))
+    }
+
+    async fn delete(&self, customer_id: Uuid, event_type: WebhookEventType, channel: DeliveryChannel) -> anyhow::Result<bool> {
+        observe!(self.delete(customer_id, event_type, channel); customer_id, event_type, channel)
+    }
+}
+
+#[async_trait]
+impl<R: JobLogRepository> JobLogRepository for Instrumented<R> {
+    async fn append(&self, lines: Vec<NewJobLog>) -> anyhow::Result<usize> {
+        observe!(self.append(lines))
+    }
+
+    async fn append_event(&self, job_id: Uuid, level: LogLevel, message: String, fields: Option<serde_json::Value>) -> anyhow::Result<JobLog> {
+        observe!(self.append_event(job_id, level, message, fields); job_id, level)
+    }
+
+    async fn find_by_job_id(&self, job_id: Uuid, limit: i64, offset: i64) -> anyhow::Result<Vec<JobLog>> {
+        observe!(self.find_by_job_id(job_id, limit, offset); job_id, limit, offset)
+    }
+
+    async fn count_by_job_id(&self, job_id: Uuid) -> anyhow::Result<i64> {
+        observe!(self.count_by_job_id(job_id); job_id)
+    }
+}
+
+#[async_trait]
+impl<R: JobTemplateRepository> JobTemplateRepository for Instrumented<R> {
+    async fn create(&self, template: NewJobTemplate) -> anyhow::Result<JobTemplate> {
+        observe!(self.create(template))
+    }
+
+    async fn find_by_id(&self, id: Uuid) -> anyhow::Result<JobTemplate> {
+        observe!(self.find_by_id(id); id)
+    }
+
+    async fn find_by_customer_id(&self, customer_id: Uuid) -> anyhow::Result<Vec<JobTemplate>> {
+        observe!(self.find_by_customer_id(customer_id); customer_id)
+    }
+
+    async fn delete(&self, id: Uuid) -> anyhow::Result<()> {
+        observe!(self.delete(id); id)
+    }
+}
+
+#[async_trait]
+impl<R: SubmissionWindowRepository> SubmissionWindowRepository for Instrumented<R> {
+    async fn create(&self, window: NewSubmissionWindow) -> anyhow::Result<SubmissionWindow> {
+        observe!(self.create(window))
+    }
+
+    async fn find_by_id(&self, id: Uuid) -> anyhow::Result<SubmissionWindow> {
+        observe!(self.find_by_id(id); id)
+    }
+
+    async fn find_by_customer_id(&self, customer_id: Uuid) -> anyhow::Result<Vec<SubmissionWindow>> {
+        observe!(self.find_by_customer_id(customer_id); customer_id)
+    }
+
+    async fn find_by_reseller_id(&self, reseller_id: Uuid) -> anyhow::Result<Vec<SubmissionWindow>> {
+        observe!(self.find_by_reseller_id(reseller_id); reseller_id)
+    }
+
+    async fn find_applicable(&self, job_type_id: Uuid, customer_id: Uuid, reseller_id: Option<Uuid>) -> anyhow::Result<Vec<SubmissionWindow>> {
+        observe!(self.find_applicable(job_type_id, customer_id, reseller_id); job_type_id, customer_id, reseller_id)
+    }
+
+    async fn delete(&self, id: Uuid) -> anyhow::Result<()> {
+        observe!(self.delete(id); id)
+    }
+}
+
+#[async_trait]
+impl<R: PipelineRepository> PipelineRepository for Instrumented<R> {
+    async fn create(&self, pipeline: NewPipeline) -> anyhow::Result<Pipeline> {
+        observe!(self.create(pipeline))
+    }
+
+    async fn find_by_id(&self, id: Uuid) -> anyhow::Result<Pipeline> {
+        observe!(self.find_by_id(id); id)
+    }
+
+    async fn find_by_customer_id(&self, customer_id: Uuid) -> anyhow::Result<Vec<Pipeline>> {
+        observe!(self.find_by_customer_id(customer_id); customer_id)
+    }
+
+    async fn update_definition(&self, id: Uuid, name: String, description: Option<String>, definition: String) -> anyhow::Result<Pipeline> {
+        observe!(self.update_definition(id, name, description, definition); id)
+    }
+
+    async fn delete(&self, id: Uuid) -> anyhow::Result<()> {
+        observe!(self.delete(id); id)
+    }
+
+    async fn create_run(&self, run: NewPipelineRun, step_names: Vec<String>) -> anyhow::Result<PipelineRun> {
+        observe!(self.create_run(run, step_names))
+    }
+
+    async fn find_run_by_id(&self, id: Uuid) -> anyhow::Result<PipelineRun> {
+        observe!(self.find_run_by_id(id); id)
+    }
+
+    async fn find_runs_by_pipeline_id(&self, pipeline_id: Uuid) -> anyhow::Result<Vec<PipelineRun>> {
+        observe!(self.find_runs_by_pipeline_id(pipeline_id); pipeline_id)
+    }
+
+    async fn find_running(&self) -> anyhow::Result<Vec<PipelineRun>> {
+        observe!(self.find_running())
+    }
+
+    async fn find_run_steps(&self, run_id: Uuid) -> anyhow::Result<Vec<PipelineRunStep>> {
+        observe!(self.find_run_steps(run_id); run_id)
+    }
+
+    async fn claim_step(&self, run_id: Uuid, step_name: String, status: StepStatus, job_id: Option<Uuid>) -> anyhow::Result<bool> {
+        observe!(self.claim_step(run_id, step_name, status, job_id); run_id)
+    }
+
+    async fn update_step(&self, run_id: Uuid, step_name: String, status: StepStatus, job_id: Option<Uuid>) -> anyhow::Result<PipelineRunStep> {
+        observe!(self.update_step(run_id, step_name, status, job_id); run_id)
+    }
+
+    async fn finish_run(&self, id: Uuid, status: PipelineRunStatus) -> anyhow::Result<PipelineRun> {
+        observe!(self.finish_run(id, status); id)
+    }
+}
+
+#[async_trait]
+impl<R: JobImportRepository> JobImportRepository for Instrumented<R> {
+    async fn create(&self, import: NewJobImport) -> anyhow::Result<JobImport> {
+        observe!(self.create(import))
+    }
+
+    async fn find_by_id(&self, id: Uuid) -> anyhow::Result<JobImport> {
+        observe!(self.find_by_id(id); id)
+    }
+
+    async fn find_by_customer_id(&self, customer_id: Uuid) -> anyhow::Result<Vec<JobImport>> {
+        observe!(self.find_by_customer_id(customer_id); customer_id)
+    }
+
+    async fn find_unfinished(&self) -> anyhow::Result<Vec<JobImport>> {
+        observe!(self.find_unfinished())
+    }
+
+    async fn claim_row(&self, import_id: Uuid, row_number: i32, job_id: Option<Uuid>, error: Option<String>) -> anyhow::Result<bool> {
+        observe!(self.claim_row(import_id, row_number, job_id, error); import_id, row_number)
+    }
+
+    async fn fail_row(&self, import_id: Uuid, row_number: i32, error: String) -> anyhow::Result<JobImportRow> {
+        observe!(self.fail_row(import_id, row_number, error); import_id, row_number)
+    }
+
+    async fn finish(&self, id: Uuid, status: ImportStatus) -> anyhow::Result<JobImport> {
+        observe!(self.finish(id, status); id, status)
+    }
+
+    async fn find_rows(&self, import_id: Uuid) -> anyhow::Result<Vec<(JobImportRow, Option<String>)>> {
+        observe!(self.find_rows(import_id); import_id)
+    }
+}
+
+#[async_trait]
+impl<R: FixtureRepository> FixtureRepository for Instrumented<R> {
+    async fn create(&self, bundle: NewFixtureBundle, records: FixtureRecords) -> anyhow::Result<FixtureBundle> {
+        observe!(self.create(bundle, records))
+    }
+
+    async fn find_by_id(&self, id: Uuid) -> anyhow::Result<FixtureBundle> {
+        observe!(self.find_by_id(id); id)
+    }
+
+    async fn list(&self) -> anyhow::Result<Vec<FixtureBundle>> {
+        observe!(self.list())
+    }
+
+    async fn delete(&self, id: Uuid) -> anyhow::Result<()> {
+        observe!(self.delete(id); id)
+    }
+}
+
+#[async_trait]
+impl<R: BillingExportRepository> BillingExportRepository for Instrumented<R> {
+    async fn capture(&self, since: DateTime<Utc>, limit: i64) -> anyhow::Result<usize> {
+        observe!(self.capture(since, limit); since, limit)
+    }
+
+    async fn find_by_id(&self, id: Uuid) -> anyhow::Result<BillingExport> {
+        observe!(self.find_by_id(id); id)
+    }
+
+    async fn find_due(&self, now: DateTime<Utc>, limit: i64) -> anyhow::Result<Vec<BillingExport>> {
+        observe!(self.find_due(now, limit); now, limit)
+    }
+
+    async fn record_attempt(&self, id: Uuid, attempt: ExportAttempt) -> anyhow::Result<BillingExport> {
+        observe!(self.record_attempt(id, attempt); id)
+    }
+
+    async fn reconcile(&self, from: DateTime<Utc>, to: DateTime<Utc>, limit: i64) -> anyhow::Result<BillingReconciliation> {
+        observe!(self.reconcile(from, to, limit); from, to, limit)
+    }
+}
+
+#[async_trait]
+impl<R: JobEventRepository> JobEventRepository for Instrumented<R> {
+    async fn capture(&self, since: DateTime<Utc>, limit: i64) -> anyhow::Result<usize> {
+        observe!(self.capture(since, limit); since, limit)
+    }
+
+    async fn find_pending(&self, limit: i64) -> anyhow::Result<Vec<JobLifecycleEvent>> {
+        observe!(self.find_pending(limit); limit)
+    }
+
+    async fn find_by_job(&self, job_id: Uuid) -> anyhow::Result<Vec<JobLifecycleEvent>> {
+        observe!(self.find_by_job(job_id); job_id)
+    }
+
+    async fn mark_notified(&self, id: Uuid, notified_at: DateTime<Utc>) -> anyhow::Result<JobLifecycleEvent> {
+        observe!(self.mark_notified(id, notified_at); id)
+    }
+}
+
+#[async_trait]
+impl<R: JobUsageRepository> JobUsageRepository for Instrumented<R> {
+    async fn record(&self, usage: NewJobUsage) -> anyhow::Result<JobUsage> {
+        observe!(self.record(usage))
+    }
+
+    async fn find_by_job(&self, job_id: Uuid) -> anyhow::Result<Option<JobUsage>> {
+        observe!(self.find_by_job(job_id); job_id)
+    }
+
+    async fn summarize(&self, from: DateTime<Utc>, to: DateTime<Utc>, customer_id: Option<Uuid>) -> anyhow::Result<Vec<UsageSummary>> {
+        observe!(self.summarize(from, to, customer_id); from, to, customer_id)
+    }
+}
+
+#[async_trait]
+impl<R: LoadWindowRepository> LoadWindowRepository for Instrumented<R> {
+    async fn create(&self, window: NewLoadWindow) -> anyhow::Result<LoadWindow> {
+        observe!(self.create(window))
+    }
+
+    async fn find_by_id(&self, id: Uuid) -> anyhow::Result<LoadWindow> {
+        observe!(self.find_by_id(id); id)
+    }
+
+    async fn list_all(&self) -> anyhow::Result<Vec<LoadWindow>> {
+        observe!(self.list_all())
+    }
+
+    async fn list_open(&self) -> anyhow::Result<Vec<LoadWindow>> {
+        observe!(self.list_open())
+    }
+
+    async fn record_check(&self, id: Uuid, status: LoadWindowStatus, active_runners: i32, notified: bool) -> anyhow::Result<LoadWindow> {
+        observe!(self.record_check(id, status, active_runners, notified); id, status, active_runners, notified)
+    }
+
+    async fn cancel(&self, id: Uuid) -> anyhow::Result<LoadWindow> {
+        observe!(self.cancel(id); id)
+    }
+}
+
+#[async_trait]
+impl<R: SubmissionRepository> SubmissionRepository for Instrumented<R> {
+    async fn create(&self, submission: NewSubmission) -> anyhow::Result<Submission> {
+        observe!(self.create(submission))
+    }
+
+    async fn find_by_id(&self, id: Uuid) -> anyhow::Result<Submission> {
+        observe!(self.find_by_id(id); id)
+    }
+
+    async fn cancel(&self, id: Uuid) -> anyhow::Result<Submission> {
+        observe!(self.cancel(id); id)
+    }
+}
+
+#[async_trait]
+impl<R: WalletAdjustmentRepository> WalletAdjustmentRepository for Instrumented<R> {
+    async fn create(&self, adjustment: NewWalletAdjustment) -> anyhow::Result<WalletAdjustment> {
+        observe!(self.create(adjustment))
+    }
+
+    async fn find_by_id(&self, id: Uuid) -> anyhow::Result<WalletAdjustment> {
+        observe!(self.find_by_id(id); id)
+    }
+
+    async fn find_by_status(&self, status: AdjustmentStatus) -> anyhow::Result<Vec<WalletAdjustment>> {
+        observe!(self.find_by_status(status); status)
+    }
+
+    async fn find_by_wallet_id(&self, wallet_id: Uuid) -> anyhow::Result<Vec<WalletAdjustment>> {
+        observe!(self.find_by_wallet_id(wallet_id); wallet_id)
+    }
+
+    async fn approve(&self, id: Uuid, reviewed_by: String, note: Option<String>) -> anyhow::Result<WalletAdjustment> {
+        observe!(self.approve(id, reviewed_by, note); id)
+    }
+
+    async fn reject(&self, id: Uuid, reviewed_by: String, note: Option<String>) -> anyhow::Result<WalletAdjustment> {
+        observe!(self.reject(id, reviewed_by, note); id)
+    }
+}
+
+#[async_trait]
+impl<R: AuditLogRepository> AuditLogRepository for Instrumented<R> {
+    async fn record(&self, entry: NewAuditEntry) -> anyhow::Result<AuditEntry> {
+        observe!(self.record(entry))
+    }
+
+    async fn find_by_entity(&self, entity_type: String, entity_id: Uuid) -> anyhow::Result<Vec<AuditEntry>> {
+        observe!(self.find_by_entity(entity_type, entity_id); entity_type, entity_id)
+    }
+}
+
+#[async_trait]
+impl<R: BillingPeriodRepository> BillingPeriodRepository for Instrumented<R> {
+    async fn close(&self, period_start: DateTime<Utc>, period_end: DateTime<Utc>, closed_by: String) -> anyhow::Result<BillingPeriod> {
+        observe!(self.close(period_start, period_end, closed_by); period_start, period_end)
+    }
+
+    async fn find_by_id(&self, id: Uuid) -> anyhow::Result<BillingPeriod> {
+        observe!(self.find_by_id(id); id)
+    }
+
+    async fn list(&self) -> anyhow::Result<Vec<BillingPeriod>> {
+        observe!(self.list())
+    }
+
+    async fn find_invoices(&self, billing_period_id: Uuid) -> anyhow::Result<Vec<Invoice>> {
+        observe!(self.find_invoices(billing_period_id); billing_period_id)
+    }
+
+    async fn find_invoices_by_customer(&self, customer_id: Uuid) -> anyhow::Result<Vec<Invoice>> {
+        observe!(self.find_invoices_by_customer(customer_id); customer_id)
+    }
+
+    async fn ledger(&self, billing_period_id: Uuid) -> anyhow::Result<Vec<WalletTransaction>> {
+        observe!(self.ledger(billing_period_id); billing_period_id)
+    }
+}
+
+#[async_trait]
+impl<R: FeatureFlagRepository> FeatureFlagRepository for Instrumented<R> {
+    async fn upsert(&self, flag: NewFeatureFlag) -> anyhow::Result<FeatureFlag> {
+        observe!(self.upsert(flag))
+    }
+
+    async fn find_by_name(&self, name: String) -> anyhow::Result<FeatureFlag> {
+        observe!(self.find_by_name(name); name)
+    }
+
+    async fn list(&self) -> anyhow::Result<Vec<FeatureFlag>> {
+        observe!(self.list())
+    }
+
+    async fn delete(&self, name: String) -> anyhow::Result<FeatureFlag> {
+        observe!(self.delete(name); name)
+    }
+}
+
+#[async_trait]
+impl<R: JobAttemptRepository> JobAttemptRepository for Instrumented<R> {
+    async fn start(&self, job_id: Uuid, runner_id: Option<Uuid>) -> anyhow::Result<JobAttempt> {
+        observe!(self.start(job_id, runner_id); job_id)
+    }
+
+    async fn finish(&self, id: Uuid, outcome: AttemptOutcome) -> anyhow::Result<JobAttempt> {
+        observe!(self.finish(id, outcome); id)
+    }
+
+    async fn record_crash(&self, id: Uuid, crash: CrashReport) -> anyhow::Result<JobAttempt> {
+        observe!(self.record_crash(id, crash); id)
+    }
+
+    async fn abandon_running(&self, job_id: Uuid) -> anyhow::Result<usize> {
+        observe!(self.abandon_running(job_id); job_id)
+    }
+
+    async fn find_abandoned_by_runner(&self, runner_id: Uuid, since: DateTime<Utc>) -> anyhow::Result<Vec<JobAttempt>> {
+        observe!(self.find_abandoned_by_runner(runner_id, since); runner_id, since)
+    }
+
+    async fn find_by_job_id(&self, job_id: Uuid) -> anyhow::Result<Vec<JobAttempt>> {
+        observe!(self.find_by_job_id(job_id); job_id)
+    }
+
+    async fn get_runner_stats(&self, runner_id: Uuid, window_minutes: i32) -> anyhow::Result<RunnerJobStats> {
+        observe!(self.get_runner_stats(runner_id, window_minutes); runner_id, window_minutes)
+    }
+}
+
+#[async_trait]
+impl<R: UnredactedOutputRepository> UnredactedOutputRepository for Instrumented<R> {
+    async fn store(&self, output: NewUnredactedOutput) -> anyhow::Result<UnredactedOutput> {
+        observe!(self.store(output))
+    }
+
+    async fn find_by_job_id(&self, job_id: Uuid) -> anyhow::Result<UnredactedOutput> {
+        observe!(self.find_by_job_id(job_id); job_id)
+    }
+
+    async fn purge_expired(&self) -> anyhow::Result<usize> {
+        observe!(self.purge_expired())
+    }
+}
+
+#[async_trait]
+impl<R: SpendingAlertRepository> SpendingAlertRepository for Instrumented<R> {
+    async fn create(&self, alert: NewSpendingAlert) -> anyhow::Result<SpendingAlert> {
+        observe!(self.create(alert))
+    }
+
+    async fn exists_since(&self, customer_id: Uuid, kind: AnomalyKind, since: DateTime<Utc>) -> anyhow::Result<bool> {
+        observe!(self.exists_since(customer_id, kind, since); customer_id, kind, since)
+    }
+
+    async fn list_by_customer(&self, customer_id: Uuid, limit: i64) -> anyhow::Result<Vec<SpendingAlert>> {
+        observe!(self.list_by_customer(customer_id, limit); customer_id, limit)
+    }
+
+    async fn list_by_reseller(&self, reseller_id: Uuid, limit: i64) -> anyhow::Result<Vec<SpendingAlert>> {
+        observe!(self.list_by_reseller(reseller_id, limit); reseller_id, limit)
+    }
+
+    async fn list_recent(&self, limit: i64) -> anyhow::Result<Vec<SpendingAlert>> {
+        observe!(self.list_recent(limit); limit)
+    }
+}
+
+#[async_trait]
+impl<R: PartitionRepository> PartitionRepository for Instrumented<R> {
+    async fn list_months(&self, table: PartitionedTable) -> anyhow::Result<Vec<NaiveDate>> {
+        observe!(self.list_months(table); table)
+    }
+
+    async fn unpartitioned_months(&self, table: PartitionedTable) -> anyhow::Result<Vec<NaiveDate>> {
+        observe!(self.unpartitioned_months(table); table)
+    }
+
+    async fn create_month(&self, table: PartitionedTable, month: NaiveDate) -> anyhow::Result<usize> {
+        observe!(self.create_month(table, month); table, month)
+    }
+
+    async fn drop_month(&self, table: PartitionedTable, month: NaiveDate) -> anyhow::Result<usize> {
+        observe!(self.drop_month(table, month); table, month)
+    }
+}
+
+#[async_trait]
+impl<R: PurgeRepository> PurgeRepository for Instrumented<R> {
+    async fn count_expired_jobs(&self, created_before: DateTime<Utc>) -> anyhow::Result<u64> {
+        observe!(self.count_expired_jobs(created_before); created_before)
+    }
+
+    async fn purge_expired_jobs(&self, created_before: DateTime<Utc>, limit: i64) -> anyhow::Result<PurgedRows> {
+        observe!(self.purge_expired_jobs(created_before, limit); created_before, limit)
+    }
+
+    async fn count_expired_unredacted_outputs(&self) -> anyhow::Result<u64> {
+        observe!(self.count_expired_unredacted_outputs())
+    }
+
+    async fn purge_expired_unredacted_outputs(&self, limit: i64) -> anyhow::Result<PurgedRows> {
+        observe!(self.purge_expired_unredacted_outputs(limit); limit)
+    }
+}
+
+#[async_trait]
+impl<R: RequestNonceRepository> RequestNonceRepository for Instrumented<R> {
+    async fn record(&self, reseller_id: Uuid, nonce: &str, expires_at: DateTime<Utc>) -> anyhow::Result<bool> {
+        observe!(self.record(reseller_id, nonce, expires_at); reseller_id, expires_at)
+    }
+
+    async fn purge_expired(&self) -> anyhow::Result<usize> {
+        observe!(self.purge_expired())
+    }
+}
+
+#[async_trait]
+impl<R: ProviderRepository> ProviderRepository for Instrumented<R> {
+    async fn create(&self, provider: NewProvider) -> anyhow::Result<Provider> {
+        observe!(self.create(provider))
+    }
+
+    async fn find_by_id(&self, id: Uuid) -> anyhow::Result<Provider> {
+        observe!(self.find_by_id(id); id)
+    }
+
+    async fn list_all(&self) -> anyhow::Result<Vec<Provider>> {
+        observe!(self.list_all())
+    }
+
+    async fn update(&self, provider: Provider) -> anyhow::Result<Provider> {
+        observe!(self.update(provider))
+    }
+
+    async fn record_check(&self, id: Uuid, status: ProviderStatus, reason: Option<String>) -> anyhow::Result<Provider> {
+        observe!(self.record_check(id, status, reason); id, status)
+    }
+
+    async fn delete(&self, id: Uuid) -> anyhow::Result<()> {
+        observe!(self.delete(id); id)
+    }
+}
+
+#[async_trait]
+impl<R: SearchRepository> SearchRepository for Instrumented<R> {
+    async fn search_customers(&self, query: &str, limit: i64) -> anyhow::Result<Vec<Customer>> {
+        observe!(self.search_customers(query, limit); query, limit)
+    }
+
+    async fn search_resellers(&self, query: &str, limit: i64) -> anyhow::Result<Vec<Reseller>> {
+        observe!(self.search_resellers(query, limit); query, limit)
+    }
+
+    async fn search_projects(&self, query: &str, limit: i64) -> anyhow::Result<Vec<Project>> {
+        observe!(self.search_projects(query, limit); query, limit)
+    }
+
+    async fn search_jobs(&self, query: &str, limit: i64) -> anyhow::Result<Vec<Job>> {
+        observe!(self.search_jobs(query, limit); query, limit)
+    }
+}
+
+#[async_trait]
+impl<R: DependencyRepository> DependencyRepository for Instrumented<R> {
+    async fn project_blockers(&self, project_id: Uuid) -> anyhow::Result<Vec<Blocker>> {
+        observe!(self.project_blockers(project_id); project_id)
+    }
+
+    async fn job_type_blockers(&self, job_type_id: Uuid) -> anyhow::Result<Vec<Blocker>> {
+        observe!(self.job_type_blockers(job_type_id); job_type_id)
+    }
+
+    async fn customer_blockers(&self, customer_id: Uuid) -> anyhow::Result<Vec<Blocker>> {
+        observe!(self.customer_blockers(customer_id); customer_id)
+    }
+
+    async fn find_queued_jobs_by_job_type(&self, job_type_id: Uuid) -> anyhow::Result<Vec<Uuid>> {
+        observe!(self.find_queued_jobs_by_job_type(job_type_id); job_type_id)
+    }
+
+    async fn find_queued_jobs_by_customer(&self, customer_id: Uuid) -> anyhow::Result<Vec<Uuid>> {
+        observe!(self.find_queued_jobs_by_customer(customer_id); customer_id)
+    }
+}
+
+#[async_trait]
+impl<R: ReplayCaseRepository> ReplayCaseRepository for Instrumented<R> {
+    async fn find_candidates(&self, job_type_id: Uuid, limit: i64) -> anyhow::Result<Vec<Job>> {
+        observe!(self.find_candidates(job_type_id, limit); job_type_id, limit)
+    }
+
+    async fn add(&self, cases: Vec<NewReplayCase>) -> anyhow::Result<Vec<ReplayCase>> {
+        observe!(self.add(cases))
+    }
+
+    async fn list_by_job_type(&self, job_type_id: Uuid) -> anyhow::Result<Vec<ReplayCase>> {
+        observe!(self.list_by_job_type(job_type_id); job_type_id)
+    }
+
+    async fn list_job_types(&self) -> anyhow::Result<Vec<Uuid>> {
+        observe!(self.list_job_types())
+    }
+
+    async fn record_baseline(&self, id: Uuid, output: serde_json::Value, cost_cents: i32, version: &str) -> anyhow::Result<ReplayCase> {
+        observe!(self.record_baseline(id, output, cost_cents, version); id)
+    }
+
+    async fn delete(&self, job_type_id: Uuid, id: Uuid) -> anyhow::Result<bool> {
+        observe!(self.delete(job_type_id, id); job_type_id, id)
+    }
+}
+
+#[async_trait]
+impl<R: JobArtifactRepository> JobArtifactRepository for Instrumented<R> {
+    async fn create(&self, artifact: NewJobArtifact) -> anyhow::Result<()> {
+        observe!(self.create(artifact))
+    }
+
+    async fn find(&self, job_id: Uuid, artifact_id: Uuid) -> anyhow::Result<JobArtifact> {
+        observe!(self.find(job_id, artifact_id); job_id, artifact_id)
+    }
+}
+
+#[async_trait]
+impl<R: LedgerRepository> LedgerRepository for Instrumented<R> {
+    async fn find_by_job(&self, job_id: Uuid) -> anyhow::Result<Vec<LedgerEntry>> {
+        observe!(self.find_by_job(job_id); job_id)
+    }
+
+    async fn balances(&self) -> anyhow::Result<Vec<AccountBalance>> {
+        observe!(self.balances())
+    }
+}
@@ -1,11 +1,19 @@
 use async_trait::async_trait;
-use chrono::NaiveDateTime;
+use chrono::{DateTime, Utc};
 use uuid::Uuid;
 
-use crate::models::job::{Job, JobStatus, NewJob, PriorityLevel};
+use crate::models::job::{CompletionResolution, HeldCompletion, Job, JobHolder, JobStatus, NewJob, PriorityLevel};
+use crate::models::job_cost::CostBreakdown;
+use crate::models::job_error::JobError;
+use crate::models::reseller_invoice::JobTypeCharges;
 use crate::Result;
 
+/// Window statistics leave out jobs created more than this many days before the
+/// window, so they only read the latest monthly partitions of `jobs`
+pub const WINDOW_STATS_MAX_JOB_AGE_DAYS: i64 = 31;
+
 /// Sorting options for job queries
+#[derive(Debug)]
 pub enum JobSortOrder {
     /// Most recently created first
     CreatedDesc,
@@ -17,7 +25,21 @@ pub enum JobSortOrder {
     PriorityAsc,
 }
 
+impl JobSortOrder {
+    /// Parse a sort order from its query value, e.g. `created_desc`
+    pub fn parse(value: &str) -> Option<Self> {
+        match value {
+            "created_desc" => Some(Self::CreatedDesc),
+            "created_asc" => Some(Self::CreatedAsc),
+            "priority_desc" => Some(Self::PriorityDesc),
+            "priority_asc" => Some(Self::PriorityAsc),
+            _ => None,
+        }
+    }
+}
+
 /// Filter criteria for job queries
+#[derive(Debug)]
 pub struct JobFilter {
     /// Filter by customer ID
     pub customer_id: Option<Uuid>,
@@ -25,12 +47,14 @@ pub struct JobFilter {
     pub job_type_id: Option<Uuid>,
     /// Filter by job status
     pub status: Option<JobStatus>,
+    /// Filter by public ID
+    pub public_id: Option<String>,
     /// Filter by priority level
     pub priority: Option<PriorityLevel>,
     /// Filter by jobs created after this timestamp
-    pub created_after: Option<NaiveDateTime>,
+    pub created_after: Option<DateTime<Utc>>,
     /// Filter by jobs created before this timestamp
-    pub created_before: Option<NaiveDateTime>,
+    pub created_before: Option<DateTime<Utc>>,
     /// Filter by completed jobs only
     pub completed_only: bool,
     /// Filter by failed jobs only
@@ -43,6 +67,7 @@ impl Default for JobFilter {
             customer_id: None,
             job_type_id: None,
             status: None,
+            public_id: None,
             priority: None,
             created_after: None,
             created_before: None,
@@ -53,6 +78,7 @@ impl Default for JobFilter {
 }
 
 /// Pagination options for job queries
+#[derive(Debug)]
 pub struct Pagination {
     /// Page number (0-based)
     pub page: u32,
@@ -69,14 +95,332 @@ impl Default for Pagination {
     }
 }
 
+/// Queue statistics of a single job type, used for capacity planning
+#[derive(Debug, Clone, PartialEq)]
+pub struct JobTypeQueueStats {
+    pub job_type_id: Uuid,
+    /// Jobs waiting to be picked up
+    pub pending: i64,
+    /// Jobs currently being processed
+    pub running: i64,
+    /// Jobs completed within the sampling window
+    pub completed_in_window: i64,
+    /// Average time from creation to completion of jobs completed within the window
+    pub avg_processing_seconds: Option<f64>,
+}
+
+impl JobTypeQueueStats {
+    /// Empty statistics for a job type
+    pub fn new(job_type_id: Uuid) -> Self {
+        Self {
+            job_type_id,
+            pending: 0,
+            running: 0,
+            completed_in_window: 0,
+            avg_processing_seconds: None,
+        }
+    }
+}
+
+/// Outcome statistics of the jobs a single runner completed recently, used for health scoring
+#[derive(Debug, Clone, PartialEq)]
+pub struct RunnerJobStats {
+    pub runner_id: Uuid,
+    /// Jobs the runner completed successfully within the sampling window
+    pub succeeded: i64,
+    /// Jobs that failed on the runner within the sampling window
+    pub failed: i64,
+    /// Average time from creation to completion of those jobs, or from start to finish
+    /// when computed from their attempts
+    pub avg_processing_seconds: Option<f64>,
+}
+
+impl RunnerJobStats {
+    /// Empty statistics for a runner
+    pub fn new(runner_id: Uuid) -> Self {
+        Self {
+            runner_id,
+            succeeded: 0,
+            failed: 0,
+            avg_processing_seconds: None,
+        }
+    }
+
+    /// Number of jobs the statistics are based on
+    pub fn completed(&self) -> i64 {
+        self.succeeded + self.failed
+    }
+
+    /// Share of failed jobs, if any job completed
+    pub fn failure_rate(&self) -> Option<f64> {
+        match self.completed() {
+            0 => None,
+            completed => Some(self.failed as f64 / completed as f64),
+        }
+    }
+}
+
+/// Jobs and spend of a single customer over a period
+#[derive(Debug, Clone, PartialEq)]
+pub struct CustomerSpend {
+    pub customer_id: Uuid,
+    /// Jobs submitted in the period, excluding cancelled and test mode ones
+    pub jobs: i64,
+    /// Cost of those jobs in cents: the final cost of finished jobs, the estimate of others
+    pub cost_cents: i64,
+}
+
+impl CustomerSpend {
+    /// No jobs or spend for a customer
+    pub fn new(customer_id: Uuid) -> Self {
+        Self {
+            customer_id,
+            jobs: 0,
+            cost_cents: 0,
+        }
+    }
+}
+
+/// Jobs, spend and failures of a single customer over a period
+#[derive(Debug, Clone, PartialEq)]
+pub struct CustomerActivity {
+    pub customer_id: Uuid,
+    /// Jobs submitted in the period, excluding cancelled and test mode ones
+    pub jobs: i64,
+    /// Cost of those jobs in cents: the final cost of finished jobs, the estimate of others
+    pub cost_cents: i64,
+    /// Those jobs that succeeded
+    pub succeeded: i64,
+    /// Those jobs that failed
+    pub failed: i64,
+}
+
+impl CustomerActivity {
+    /// No activity of a customer
+    pub fn new(customer_id: Uuid) -> Self {
+        Self {
+            customer_id,
+            jobs: 0,
+            cost_cents: 0,
+            succeeded: 0,
+            failed: 0,
+        }
+    }
+
+    /// Number of jobs that finished
+    pub fn finished(&self) -> i64 {
+        self.succeeded + self.failed
+    }
+
+    /// Share of finished jobs that failed, if any job finished
+    pub fn failure_rate(&self) -> Option<f64> {
+        match self.finished() {
+            0 => None,
+            finished => Some(self.failed as f64 / finished as f64),
+        }
+    }
+}
+
+/// Time jobs of one priority level waited in the queue before a runner claimed them
+#[derive(Debug, Clone, PartialEq)]
+pub struct PriorityWaitStats {
+    pub priority: PriorityLevel,
+    /// Jobs claimed within the sampling window
+    pub samples: i64,
+    /// Wait time percentiles (in seconds) from entering the queue until claim
+    pub p50_seconds: f64,
+    pub p90_seconds: f64,
+    pub p99_seconds: f64,
+    pub max_seconds: f64,
+}
+
+impl PriorityWaitStats {
+    /// Compute the statistics from individual wait times, interpolating percentiles
+    /// the way Postgres' `percentile_cont` does; None without samples
+    pub fn from_samples(priority: PriorityLevel, mut waits: Vec<f64>) -> Option<Self> {
+        if waits.is_empty() {
+            return None;
+        }
+        waits.sort_by(f64::total_cmp);
+
+        Some(Self {
+            priority,
+            samples: waits.len() as i64,
+            p50_seconds: percentile_cont(&waits, 0.5),
+            p90_seconds: percentile_cont(&waits, 0.9),
+            p99_seconds: percentile_cont(&waits, 0.99),
+            max_seconds: waits[waits.len() - 1],
+        })
+    }
+}
+
+/// Processing times and outcomes of the jobs of one job type that finished recently
+#[derive(Debug, Clone, PartialEq)]
+pub struct JobTypeExecutionStats {
+    pub job_type_id: Uuid,
+    /// Jobs that succeeded within the sampling window
+    pub succeeded: i64,
+    /// Jobs that failed within the sampling window
+    pub failed: i64,
+    /// Processing time percentiles (in seconds) from start to completion; None without
+    /// finished jobs that recorded their start
+    pub p50_processing_seconds: Option<f64>,
+    pub p95_processing_seconds: Option<f64>,
+}
+
+impl JobTypeExecutionStats {
+    /// Compute the statistics from the outcomes and the individual processing times of
+    /// finished jobs, interpolating percentiles like `PriorityWaitStats::from_samples`
+    pub fn from_samples(job_type_id: Uuid, succeeded: i64, failed: i64, mut processing: Vec<f64>) -> Self {
+        processing.sort_by(f64::total_cmp);
+        let percentile = |fraction| (!processing.is_empty()).then(|| percentile_cont(&processing, fraction));
+
+        Self {
+            job_type_id,
+            succeeded,
+            failed,
+            p50_processing_seconds: percentile(0.5),
+            p95_processing_seconds: percentile(0.95),
+        }
+    }
+
+    /// Number of jobs that finished
+    pub fn finished(&self) -> i64 {
+        self.succeeded + self.failed
+    }
+
+    /// Share of finished jobs that succeeded, if any job finished
+    pub fn success_rate(&self) -> Option<f64> {
+        match self.finished() {
+            0 => None,
+            finished => Some(self.succeeded as f64 / finished as f64),
+        }
+    }
+}
+
+/// Percentile of sorted, non-empty samples, interpolated the way Postgres' `percentile_cont` does
+fn percentile_cont(sorted: &[f64], fraction: f64) -> f64 {
+    let rank = fraction * (sorted.len() - 1) as f64;
+    let (lower, upper) = (rank.floor() as usize, rank.ceil() as usize);
+    sorted[lower] + (sorted[upper] - sorted[lower]) * (rank - lower as f64)
+}
+
+/// Position in a keyset-paginated job list: the last job of the previous page
+///
+/// Jobs are ordered by (created_at, id) newest first, so pages stay stable while new
+/// jobs are inserted. The cursor travels through the API as an opaque token.
+#[derive(Debug, Clone, PartialEq)]
+pub struct JobCursor {
+    pub created_at: DateTime<Utc>,
+    pub id: Uuid,
+}
+
+impl JobCursor {
+    /// Cursor pointing just past the given job, if it has a creation timestamp
+    pub fn after(job: &Job) -> Option<Self> {
+        job.created_at.map(|created_at| Self { created_at, id: job.id })
+    }
+
+    /// Encode the cursor as an opaque token
+    pub fn encode(&self) -> String {
+        hex::encode(format!("{}|{}", self.created_at.timestamp_micros(), self.id))
+    }
+
+    /// Decode a token produced by `encode`; returns None for malformed tokens
+    pub fn decode(token: &str) -> Option<Self> {
+        let raw = String::from_utf8(hex::decode(token).ok()?).ok()?;
+        let (micros, id) = raw.split_once('|')?;
+        Some(Self {
+            created_at: DateTime::from_timestamp_micros(micros.parse().ok()?)?,
+            id: Uuid::parse_str(id).ok()?,
+        })
+    }
+}
+
 #[async_trait]
 pub trait JobRepository: Send + Sync {
     // Basic CRUD operations
     async fn create(&self, new_job: NewJob) -> Result<Job>;
+    /// Create many jobs in one transaction, with multi-row inserts; either all of them
+    /// are created or none
+    async fn create_many(&self, new_jobs: Vec<NewJob>) -> Result<Vec<Job>>;
     async fn find_by_id(&self, id: Uuid) -> Result<Job>;
+    /// Find a job by its short public ID, e.g. `job_8f3kq2wx1m`
+    async fn find_by_public_id(&self, public_id: &str) -> Result<Job>;
     async fn update_status(&self, id: Uuid, status: JobStatus) -> Result<Job>;
     async fn set_started(&self, id: Uuid) -> Result<Job>;
-    async fn set_completed(&self, id: Uuid, success: bool, output: Option<serde_json::Value>, error: Option<String>, cost_cents: i32) -> Result<Job>;
+    async fn set_completed(&self, id: Uuid, success: bool, output: Option<serde_json::Value>, error: Option<JobError>, cost_cents: i32) -> Result<Job>;
+    
+    /// Complete a job on behalf of the runner that started it, unless the job was given
+    /// to another runner in the meantime (see [`resolve_completion`](crate::models::job::resolve_completion))
+    ///
+    /// A failed result of a job whose cancellation was requested is stored as cancelled.
+    ///
+    /// Returns how the result was resolved with the job as it stands afterwards; a
+    /// discarded result leaves the job unchanged.
+    async fn complete_held(
+        &self,
+        id: Uuid,
+        holder: JobHolder,
+        success: bool,
+        output: Option<serde_json::Value>,
+        error: Option<JobError>,
+        cost_cents: i32,
+    ) -> Result<(CompletionResolution, Job)>;
+    
+    /// Complete many jobs as `complete_held` does, in one transaction; either all
+    /// results are stored or none
+    ///
+    /// Returns the resolution and job of each result, in the order given.
+    async fn complete_held_many(&self, completions: Vec<HeldCompletion>) -> Result<Vec<(CompletionResolution, Job)>>;
+    
+    /// Put a job running on `runner_id` back to pending and abandon its running attempt,
+    /// so that another runner can start it; a job whose cancellation was requested is
+    /// cancelled instead
+    ///
+    /// Returns None if the runner no longer holds the job. Jobs waiting for their
+    /// sub-tasks are not handed back.
+    async fn hand_back(&self, id: Uuid, runner_id: Uuid) -> Result<Option<Job>>;
+    
+    /// Ask the runner processing a running job to cancel it
+    ///
+    /// The runner checks for the request while the job runs and stops it; the failed
+    /// result it reports is then stored as cancelled (see `complete_held`). Requesting
+    /// again keeps the time of the first request. Fails with `Error::InvalidInput` unless
+    /// the job is running and not waiting for its sub-tasks.
+    async fn request_cancellation(&self, id: Uuid) -> Result<Job>;
+    
+    /// Whether cancelling the job was requested while it was running
+    async fn cancellation_requested(&self, id: Uuid) -> Result<bool>;
+    
+    /// Find the jobs running on a runner, leaving out those waiting for their sub-tasks
+    async fn find_running_by_runner(&self, runner_id: Uuid) -> Result<Vec<Job>>;
+    
+    /// Record the itemized cost the job was charged; its `cost_cents` is set on completion
+    async fn set_cost_breakdown(&self, id: Uuid, breakdown: CostBreakdown) -> Result<()>;
+    
+    /// Record which runner is processing the job
+    async fn assign_runner(&self, id: Uuid, runner_id: Uuid) -> Result<()>;
+    
+    /// Record that a runner took the job off the queue of the given priority level
+    async fn record_claim(&self, id: Uuid, priority: PriorityLevel) -> Result<()>;
+    
+    /// Record that a runner took the job off the queue of the given priority level into
+    /// its prefetch buffer, holding it until `until`; starting the job ends the lease
+    ///
+    /// Fails with `Error::InvalidInput` if the job can no longer be started.
+    async fn lease(&self, id: Uuid, priority: PriorityLevel, runner_id: Option<Uuid>, until: DateTime<Utc>) -> Result<()>;
+    
+    /// End the leases of pending jobs that expired before `now`, returning each job with
+    /// the priority level of the queue it was taken from so it can be queued again
+    async fn release_expired_leases(&self, now: DateTime<Utc>) -> Result<Vec<(Uuid, PriorityLevel)>>;
+    
+    /// Move a pending job to scheduled state until it may be queued at `scheduled_for`
+    async fn schedule(&self, id: Uuid, scheduled_for: DateTime<Utc>) -> Result<Job>;
+    
+    /// Move the scheduled jobs of the given job types that are due before `due_before` to
+    /// `until`, returning their IDs so they can be scheduled again on the queue
+    async fn postpone_scheduled(&self, job_type_ids: Vec<Uuid>, due_before: DateTime<Utc>, until: DateTime<Utc>) -> Result<Vec<Uuid>>;
     
     // Basic query operations (from original trait)
     async fn find_by_customer_id(&self, customer_id: Uuid) -> Result<Vec<Job>>;
@@ -87,6 +431,11 @@ pub trait JobRepository: Send + Sync {
     /// Query jobs with advanced filtering, sorting and pagination
     async fn query_jobs(&self, filter: JobFilter, sort: Option<JobSortOrder>, pagination: Option<Pagination>) -> Result<(Vec<Job>, u64)>;
     
+    /// Query jobs newest first with keyset pagination, starting after `cursor`
+    ///
+    /// Returns at most `limit` jobs and the cursor of the next page, if there is one.
+    async fn query_jobs_after(&self, filter: JobFilter, cursor: Option<JobCursor>, limit: u32) -> Result<(Vec<Job>, Option<JobCursor>)>;
+    
     /// Get job statistics grouped by status
     async fn get_job_stats_by_status(&self) -> Result<Vec<(String, i64)>>;
     
@@ -96,9 +445,83 @@ pub trait JobRepository: Send + Sync {
     /// Get estimated vs actual cost statistics for completed jobs
     async fn get_cost_statistics(&self) -> Result<(i64, i64)>;
     
+    /// Get pending/running counts and recent processing times per job type
+    ///
+    /// Processing times are sampled from jobs completed in the last `window_minutes`,
+    /// up to `WINDOW_STATS_MAX_JOB_AGE_DAYS` after they were created.
+    async fn get_queue_stats_by_job_type(&self, window_minutes: i32) -> Result<Vec<JobTypeQueueStats>>;
+    
+    /// Get the outcomes and processing times of jobs a runner completed in the last `window_minutes`
+    ///
+    /// Jobs created more than `WINDOW_STATS_MAX_JOB_AGE_DAYS` before the window are left out.
+    async fn get_runner_job_stats(&self, runner_id: Uuid, window_minutes: i32) -> Result<RunnerJobStats>;
+    
+    /// Get the jobs and spend of each given customer for jobs submitted since `since`
+    ///
+    /// Customers without jobs in the period are omitted.
+    async fn get_spend_by_customer(&self, customer_ids: Vec<Uuid>, since: DateTime<Utc>) -> Result<Vec<CustomerSpend>>;
+    
+    /// Get the activity of every customer with jobs submitted in `[since, until)`
+    ///
+    /// Customers without jobs in the period are omitted.
+    async fn get_customer_activity(&self, since: DateTime<Utc>, until: DateTime<Utc>) -> Result<Vec<CustomerActivity>>;
+    
+    /// Get what each given customer was charged per job type for jobs completed in `[since, until)`
+    ///
+    /// Jobs charged nothing, test mode jobs and sub-tasks, charged through their parent,
+    /// are left out. Jobs charged without a cost breakdown count their whole cost as base cost.
+    async fn get_charges_by_job_type(&self, customer_ids: Vec<Uuid>, since: DateTime<Utc>, until: DateTime<Utc>) -> Result<Vec<JobTypeCharges>>;
+    
+    /// Get queue wait time percentiles per priority level of jobs claimed in the last `window_minutes`
+    ///
+    /// Priority levels without claimed jobs in the window are omitted. Jobs created more
+    /// than `WINDOW_STATS_MAX_JOB_AGE_DAYS` before the window are left out.
+    async fn get_queue_wait_stats(&self, window_minutes: i32) -> Result<Vec<PriorityWaitStats>>;
+    
+    /// Get the outcomes and processing time percentiles of jobs of a type completed in the last `window_minutes`
+    ///
+    /// Jobs created more than `WINDOW_STATS_MAX_JOB_AGE_DAYS` before the window are left out.
+    async fn get_job_type_execution_stats(&self, job_type_id: Uuid, window_minutes: i32) -> Result<JobTypeExecutionStats>;
+    
     /// Find jobs that have been in running state for too long (possibly stalled)
     async fn find_stalled_jobs(&self, running_threshold_minutes: i32) -> Result<Vec<Job>>;
     
+    /// Find pending or scheduled jobs that have not changed since `before` (possibly abandoned)
+    ///
+    /// Scheduled jobs count as waiting from their release time, not from their submission.
+    async fn find_waiting_since(&self, before: DateTime<Utc>) -> Result<Vec<Job>>;
+    
+    /// Find pending or scheduled jobs whose `expires_at` deadline is at or before `now`
+    async fn find_expired(&self, now: DateTime<Utc>) -> Result<Vec<Job>>;
+    
+    /// Find up to `limit` jobs created or updated after `since`, least recently changed first
+    async fn find_changed_since(&self, since: DateTime<Utc>, limit: i64) -> Result<Vec<Job>>;
+    
     /// Update multiple jobs with the same status in a single operation
     async fn bulk_update_status(&self, ids: Vec<Uuid>, status: JobStatus) -> Result<usize>;
+    
+    /// Count the pending jobs of a job type, i.e. its jobs waiting in the queue
+    async fn count_pending_by_job_type(&self, job_type_id: Uuid) -> Result<i64>;
+    
+    /// Fan a running job out into sub-tasks: record their number on the job and create
+    /// them, returning the sub-tasks in order
+    ///
+    /// Fails with `Error::InvalidInput` if the job is not running or already fanned out.
+    async fn fan_out(&self, id: Uuid, sub_tasks: Vec<NewJob>) -> Result<Vec<Job>>;
+    
+    /// Find the sub-tasks of a job in order
+    async fn find_sub_tasks(&self, id: Uuid) -> Result<Vec<Job>>;
+    
+    /// Finish a fanned-out job once all its sub-tasks finished, with the status and cost
+    /// derived from them (see [`fan_in`](crate::models::job::fan_in))
+    ///
+    /// Returns the finished job, or None while sub-tasks are outstanding or when the job
+    /// already finished.
+    async fn fan_in(&self, id: Uuid) -> Result<Option<Job>>;
+    
+    /// Find the running jobs waiting for their sub-tasks to finish
+    async fn find_fanned_out(&self) -> Result<Vec<Job>>;
+    
+    /// Find the jobs submitted with a batch or file import, oldest first
+    async fn find_by_submission(&self, submission_id: Uuid) -> Result<Vec<Job>>;
 }
@@ -0,0 +1,15 @@
+use async_trait::async_trait;
+use anyhow::Result;
+use uuid::Uuid;
+
+use crate::models::job_artifact::{JobArtifact, NewJobArtifact};
+
+/// Repository trait for the binary response bodies of jobs
+#[async_trait]
+pub trait JobArtifactRepository: Send + Sync {
+    /// Store an artifact of a job
+    async fn create(&self, artifact: NewJobArtifact) -> Result<()>;
+
+    /// Find an artifact of a job with its content
+    async fn find(&self, job_id: Uuid, artifact_id: Uuid) -> Result<JobArtifact>;
+}
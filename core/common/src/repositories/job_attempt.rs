@@ -0,0 +1,34 @@
+use async_trait::async_trait;
+use chrono::{DateTime, Utc};
+use uuid::Uuid;
+use anyhow::Result;
+
+use crate::models::job_attempt::{AttemptOutcome, CrashReport, JobAttempt};
+use crate::repositories::job::RunnerJobStats;
+
+/// Repository trait for the attempt history of jobs
+#[async_trait]
+pub trait JobAttemptRepository: Send + Sync {
+    /// Record that a runner started executing a job, numbered after the job's previous attempts
+    async fn start(&self, job_id: Uuid, runner_id: Option<Uuid>) -> Result<JobAttempt>;
+
+    /// Record how a running attempt ended
+    async fn finish(&self, id: Uuid, outcome: AttemptOutcome) -> Result<JobAttempt>;
+
+    /// Attach the crash a runner reported to an attempt, leaving its status as it is
+    async fn record_crash(&self, id: Uuid, crash: CrashReport) -> Result<JobAttempt>;
+
+    /// Mark the attempts of a job that are still running as abandoned, returning how many there were
+    async fn abandon_running(&self, job_id: Uuid) -> Result<usize>;
+
+    /// List the attempts of a runner abandoned since `since`, i.e. the jobs handed to
+    /// other runners, oldest first
+    async fn find_abandoned_by_runner(&self, runner_id: Uuid, since: DateTime<Utc>) -> Result<Vec<JobAttempt>>;
+
+    /// List the attempts of a job, oldest first
+    async fn find_by_job_id(&self, job_id: Uuid) -> Result<Vec<JobAttempt>>;
+
+    /// Statistics of the attempts a runner finished within the last `window_minutes`;
+    /// abandoned attempts count as failed
+    async fn get_runner_stats(&self, runner_id: Uuid, window_minutes: i32) -> Result<RunnerJobStats>;
+}
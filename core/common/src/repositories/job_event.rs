@@ -0,0 +1,27 @@
+use async_trait::async_trait;
+use anyhow::Result;
+use chrono::{DateTime, Utc};
+use uuid::Uuid;
+
+use crate::models::job_event::JobLifecycleEvent;
+
+/// Repository trait for the outbox of job lifecycle events notified to customers
+#[async_trait]
+pub trait JobEventRepository: Send + Sync {
+    /// Add an event for every job that started, succeeded or failed since `since` and
+    /// has none for it yet, oldest first and at most `limit` of each; returns how many
+    /// were added
+    ///
+    /// A job gets each event at most once, however often it is captured. Sub-tasks of
+    /// batch jobs are internal and get none.
+    async fn capture(&self, since: DateTime<Utc>, limit: i64) -> Result<usize>;
+
+    /// Events not notified yet, in the order they occurred
+    async fn find_pending(&self, limit: i64) -> Result<Vec<JobLifecycleEvent>>;
+
+    /// Events of a job, in the order they occurred
+    async fn find_by_job(&self, job_id: Uuid) -> Result<Vec<JobLifecycleEvent>>;
+
+    /// Mark an event as handed to the customer's endpoints
+    async fn mark_notified(&self, id: Uuid, notified_at: DateTime<Utc>) -> Result<JobLifecycleEvent>;
+}
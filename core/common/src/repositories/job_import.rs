@@ -0,0 +1,35 @@
+use async_trait::async_trait;
+use uuid::Uuid;
+use anyhow::Result;
+
+use crate::models::job_import::{ImportStatus, JobImport, JobImportRow, NewJobImport};
+
+/// Repository trait for job imports and the outcome of their rows
+#[async_trait]
+pub trait JobImportRepository: Send + Sync {
+    /// Create a new import
+    async fn create(&self, import: NewJobImport) -> Result<JobImport>;
+
+    /// Find an import by ID
+    async fn find_by_id(&self, id: Uuid) -> Result<JobImport>;
+
+    /// List the imports of a customer, newest first
+    async fn find_by_customer_id(&self, customer_id: Uuid) -> Result<Vec<JobImport>>;
+
+    /// List the imports that have rows left to submit, oldest first
+    async fn find_unfinished(&self) -> Result<Vec<JobImport>>;
+
+    /// Record the outcome of the import's next row, either the job about to be submitted
+    /// for it or why it has none, and move on to the following row; false if the row was
+    /// already recorded, e.g. because another API instance claimed it first
+    async fn claim_row(&self, import_id: Uuid, row_number: i32, job_id: Option<Uuid>, error: Option<String>) -> Result<bool>;
+
+    /// Turn a row claimed for a job into a failed one when the job was not accepted
+    async fn fail_row(&self, import_id: Uuid, row_number: i32, error: String) -> Result<JobImportRow>;
+
+    /// Mark an import as finished
+    async fn finish(&self, id: Uuid, status: ImportStatus) -> Result<JobImport>;
+
+    /// List the rows recorded so far, in file order, with the current status of their job
+    async fn find_rows(&self, import_id: Uuid) -> Result<Vec<(JobImportRow, Option<String>)>>;
+}
@@ -0,0 +1,22 @@
+use async_trait::async_trait;
+use uuid::Uuid;
+use anyhow::Result;
+
+use crate::models::job_log::{JobLog, LogLevel, NewJobLog};
+
+/// Repository trait for job execution logs
+#[async_trait]
+pub trait JobLogRepository: Send + Sync {
+    /// Append a batch of log lines
+    async fn append(&self, lines: Vec<NewJobLog>) -> Result<usize>;
+
+    /// Append a line after the job's last one, for events recorded outside of its
+    /// processor, e.g. the job being handed to another runner
+    async fn append_event(&self, job_id: Uuid, level: LogLevel, message: String, fields: Option<serde_json::Value>) -> Result<JobLog>;
+
+    /// List the log lines of a job in line order
+    async fn find_by_job_id(&self, job_id: Uuid, limit: i64, offset: i64) -> Result<Vec<JobLog>>;
+
+    /// Count the log lines of a job
+    async fn count_by_job_id(&self, job_id: Uuid) -> Result<i64>;
+}
@@ -0,0 +1,21 @@
+use async_trait::async_trait;
+use uuid::Uuid;
+use anyhow::Result;
+
+use crate::models::job_template::{JobTemplate, NewJobTemplate};
+
+/// Repository trait for job input templates
+#[async_trait]
+pub trait JobTemplateRepository: Send + Sync {
+    /// Create a new job template
+    async fn create(&self, template: NewJobTemplate) -> Result<JobTemplate>;
+
+    /// Find a job template by ID
+    async fn find_by_id(&self, id: Uuid) -> Result<JobTemplate>;
+
+    /// List all job templates of a customer
+    async fn find_by_customer_id(&self, customer_id: Uuid) -> Result<Vec<JobTemplate>>;
+
+    /// Delete a job template
+    async fn delete(&self, id: Uuid) -> Result<()>;
+}
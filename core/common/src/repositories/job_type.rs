@@ -1,9 +1,22 @@
 use async_trait::async_trait;
 use uuid::Uuid;
 
-use crate::models::job_type::{JobType, NewJobType};
+use crate::models::job_type::{CatalogVisibility, JobType, NewJobType};
 use crate::Result;
 
+/// Filter for browsing the marketplace catalog
+///
+/// Only enabled job types with one of the given visibilities are listed.
+#[derive(Debug, Clone, Default)]
+pub struct CatalogFilter {
+    /// Visibility scopes the caller may see
+    pub visibilities: Vec<CatalogVisibility>,
+    /// Exact (case-insensitive) category
+    pub category: Option<String>,
+    /// Case-insensitive text matched against name, description and category
+    pub search: Option<String>,
+}
+
 #[async_trait]
 pub trait JobTypeRepository: Send + Sync {
     async fn create(&self, new_job_type: NewJobType) -> Result<JobType>;
@@ -11,4 +24,10 @@ pub trait JobTypeRepository: Send + Sync {
     async fn update(&self, job_type: JobType) -> Result<JobType>;
     async fn list_all(&self) -> Result<Vec<JobType>>;
     async fn list_enabled(&self) -> Result<Vec<JobType>>;
+    
+    /// List the catalog entries matching the filter, ordered by category and name
+    async fn search_catalog(&self, filter: CatalogFilter) -> Result<Vec<JobType>>;
+    
+    /// Count the catalog entries per category among job types with the given visibilities
+    async fn list_catalog_categories(&self, visibilities: Vec<CatalogVisibility>) -> Result<Vec<(String, i64)>>;
 }
@@ -0,0 +1,20 @@
+use async_trait::async_trait;
+use anyhow::Result;
+use chrono::{DateTime, Utc};
+use uuid::Uuid;
+
+use crate::models::job_usage::{JobUsage, NewJobUsage, UsageSummary};
+
+/// Repository trait for the resource usage runners report with job completions
+#[async_trait]
+pub trait JobUsageRepository: Send + Sync {
+    /// Store the usage of a job, replacing the usage of an earlier completion
+    async fn record(&self, usage: NewJobUsage) -> Result<JobUsage>;
+
+    /// Find the usage of a job, if its runner reported one
+    async fn find_by_job(&self, job_id: Uuid) -> Result<Option<JobUsage>>;
+
+    /// Summarize the usage recorded between `from` and `to` per customer and job type,
+    /// for a single customer if one is given
+    async fn summarize(&self, from: DateTime<Utc>, to: DateTime<Utc>, customer_id: Option<Uuid>) -> Result<Vec<UsageSummary>>;
+}
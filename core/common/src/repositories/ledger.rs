@@ -0,0 +1,18 @@
+use async_trait::async_trait;
+use anyhow::Result;
+use uuid::Uuid;
+
+use crate::models::ledger::{AccountBalance, LedgerEntry};
+
+/// Repository trait for the double-entry ledger behind the wallets
+///
+/// Entries are posted along with the wallet transactions they split, see
+/// [`WalletRepository::charge_job`](crate::repositories::WalletRepository::charge_job).
+#[async_trait]
+pub trait LedgerRepository: Send + Sync {
+    /// Entries posted for a job, oldest first
+    async fn find_by_job(&self, job_id: Uuid) -> Result<Vec<LedgerEntry>>;
+
+    /// Balances of all accounts, which add up to zero
+    async fn balances(&self) -> Result<Vec<AccountBalance>>;
+}
@@ -0,0 +1,28 @@
+use async_trait::async_trait;
+use anyhow::Result;
+use uuid::Uuid;
+
+use crate::models::load_window::{LoadWindow, LoadWindowStatus, NewLoadWindow};
+
+/// Repository trait for the load windows the runner fleet is scaled up ahead of
+#[async_trait]
+pub trait LoadWindowRepository: Send + Sync {
+    /// Declare a new load window, initially planned
+    async fn create(&self, window: NewLoadWindow) -> Result<LoadWindow>;
+
+    /// Find a load window by ID
+    async fn find_by_id(&self, id: Uuid) -> Result<LoadWindow>;
+
+    /// List all load windows, latest start first
+    async fn list_all(&self) -> Result<Vec<LoadWindow>>;
+
+    /// List the load windows neither released nor cancelled, earliest start first
+    async fn list_open(&self) -> Result<Vec<LoadWindow>>;
+
+    /// Record the outcome of a check of an open window, noting when autoscalers were
+    /// notified and when the pool first became ready or the window was released
+    async fn record_check(&self, id: Uuid, status: LoadWindowStatus, active_runners: i32, notified: bool) -> Result<LoadWindow>;
+
+    /// Cancel an open load window; its held jobs run at their next due time
+    async fn cancel(&self, id: Uuid) -> Result<LoadWindow>;
+}
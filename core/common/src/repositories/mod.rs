@@ -6,6 +6,37 @@ pub mod reseller;
 pub mod project;
 pub mod runner;
 pub mod wallet_transaction;
+pub mod webhook;
+pub mod notification;
+pub mod notification_preference;
+pub mod job_log;
+pub mod job_attempt;
+pub mod job_template;
+pub mod submission_window;
+pub mod pipeline;
+pub mod wallet_adjustment;
+pub mod audit_log;
+pub mod billing_period;
+pub mod feature_flag;
+pub mod unredacted_output;
+pub mod spending_alert;
+pub mod partition;
+pub mod request_nonce;
+pub mod provider;
+pub mod search;
+pub mod job_import;
+pub mod fixture;
+pub mod billing_export;
+pub mod job_event;
+pub mod job_usage;
+pub mod load_window;
+pub mod submission;
+pub mod dependency;
+pub mod replay_case;
+pub mod purge;
+pub mod job_artifact;
+pub mod ledger;
+pub mod instrumented;
 pub mod diesel;
 
 // Re-export repository traits
@@ -17,8 +48,42 @@ pub use reseller::ResellerRepository;
 pub use project::ProjectRepository;
 pub use runner::RunnerRepository;
 pub use wallet_transaction::WalletTransactionRepository;
+pub use webhook::CustomerWebhookRepository;
+pub use notification::NotificationDeliveryRepository;
+pub use notification_preference::NotificationPreferenceRepository;
+pub use job_log::JobLogRepository;
+pub use job_attempt::JobAttemptRepository;
+pub use job_template::JobTemplateRepository;
+pub use submission_window::SubmissionWindowRepository;
+pub use pipeline::PipelineRepository;
+pub use wallet_adjustment::WalletAdjustmentRepository;
+pub use audit_log::AuditLogRepository;
+pub use billing_period::BillingPeriodRepository;
+pub use feature_flag::FeatureFlagRepository;
+pub use unredacted_output::UnredactedOutputRepository;
+pub use spending_alert::SpendingAlertRepository;
+pub use partition::PartitionRepository;
+pub use request_nonce::RequestNonceRepository;
+pub use provider::ProviderRepository;
+pub use search::SearchRepository;
+pub use job_import::JobImportRepository;
+pub use fixture::FixtureRepository;
+pub use billing_export::BillingExportRepository;
+pub use job_event::JobEventRepository;
+pub use job_usage::JobUsageRepository;
+pub use load_window::LoadWindowRepository;
+pub use submission::SubmissionRepository;
+pub use dependency::DependencyRepository;
+pub use replay_case::ReplayCaseRepository;
+pub use purge::PurgeRepository;
+pub use job_artifact::JobArtifactRepository;
+pub use ledger::LedgerRepository;
+pub use instrumented::{Instrumented, RepositoryMetrics, RepositoryMetricsConfig};
 
-// Phase 1 in-memory implementations are removed in Phase 3
+// Phase 1 in-memory implementations are removed in Phase 3; the wallet and job
+// repositories are kept as test doubles for property-based tests
+#[cfg(feature = "testing")]
+pub mod in_memory;
 
 // Re-export diesel implementations
 pub use diesel::{
@@ -29,5 +94,35 @@ pub use diesel::{
     DieselResellerRepository,
     DieselProjectRepository,
     DieselRunnerRepository,
-    DieselWalletTransactionRepository
+    DieselWalletTransactionRepository,
+    DieselCustomerWebhookRepository,
+    DieselNotificationDeliveryRepository,
+    DieselNotificationPreferenceRepository,
+    DieselJobLogRepository,
+    DieselJobAttemptRepository,
+    DieselJobTemplateRepository,
+    DieselSubmissionWindowRepository,
+    DieselPipelineRepository,
+    DieselWalletAdjustmentRepository,
+    DieselAuditLogRepository,
+    DieselBillingPeriodRepository,
+    DieselFeatureFlagRepository,
+    DieselUnredactedOutputRepository,
+    DieselSpendingAlertRepository,
+    DieselPartitionRepository,
+    DieselRequestNonceRepository,
+    DieselProviderRepository,
+    DieselSearchRepository,
+    DieselJobImportRepository,
+    DieselFixtureRepository,
+    DieselBillingExportRepository,
+    DieselJobEventRepository,
+    DieselJobUsageRepository,
+    DieselLoadWindowRepository,
+    DieselSubmissionRepository,
+    DieselDependencyRepository,
+    DieselReplayCaseRepository,
+    DieselPurgeRepository,
+    DieselJobArtifactRepository,
+    DieselLedgerRepository
 };
@@ -0,0 +1,28 @@
+use async_trait::async_trait;
+use uuid::Uuid;
+use anyhow::Result;
+use chrono::{DateTime, Utc};
+
+use crate::models::notification::{DeliveryAttempt, DeliveryChannel, NewNotificationDelivery, NotificationDelivery};
+
+/// Repository trait for the outgoing notification delivery log
+#[async_trait]
+pub trait NotificationDeliveryRepository: Send + Sync {
+    /// Create a new delivery record
+    async fn create(&self, delivery: NewNotificationDelivery) -> Result<NotificationDelivery>;
+
+    /// Find a delivery by ID
+    async fn find_by_id(&self, id: Uuid) -> Result<NotificationDelivery>;
+
+    /// List the failed deliveries of a customer, most recent first
+    async fn find_failed_by_customer(&self, customer_id: Uuid, limit: i64, offset: i64) -> Result<Vec<NotificationDelivery>>;
+
+    /// Find failed deliveries on a channel whose next retry is due at or before `now`
+    async fn find_due_retries(&self, channel: DeliveryChannel, now: DateTime<Utc>, limit: i64) -> Result<Vec<NotificationDelivery>>;
+
+    /// Find the deliveries on a channel held back for a digest, oldest first
+    async fn find_queued(&self, channel: DeliveryChannel, limit: i64) -> Result<Vec<NotificationDelivery>>;
+
+    /// Record the outcome of a delivery attempt
+    async fn record_attempt(&self, id: Uuid, attempt: DeliveryAttempt) -> Result<NotificationDelivery>;
+}
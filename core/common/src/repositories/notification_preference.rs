@@ -0,0 +1,20 @@
+use async_trait::async_trait;
+use uuid::Uuid;
+use anyhow::Result;
+
+use crate::models::notification::{DeliveryChannel, NewNotificationPreference, NotificationPreference};
+use crate::models::webhook::WebhookEventType;
+
+/// Repository trait for customers' notification preferences
+#[async_trait]
+pub trait NotificationPreferenceRepository: Send + Sync {
+    /// List the preferences of a customer
+    async fn find_by_customer(&self, customer_id: Uuid) -> Result<Vec<NotificationPreference>>;
+
+    /// Create the preference for its customer, event type and channel, or replace its mode
+    async fn upsert(&self, preference: NewNotificationPreference) -> Result<NotificationPreference>;
+
+    /// Delete a customer's preference for an event type and channel, returning whether
+    /// there was one
+    async fn delete(&self, customer_id: Uuid, event_type: WebhookEventType, channel: DeliveryChannel) -> Result<bool>;
+}
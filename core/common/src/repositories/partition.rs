@@ -0,0 +1,30 @@
+use async_trait::async_trait;
+use anyhow::Result;
+use chrono::NaiveDate;
+
+use crate::models::partition::PartitionedTable;
+
+/// Repository trait for the monthly partitions of the partitioned tables
+///
+/// Partitions only exist in the `public` schema, whichever reseller the caller acts for.
+#[async_trait]
+pub trait PartitionRepository: Send + Sync {
+    /// Months that have a partition of their own, oldest first
+    async fn list_months(&self, table: PartitionedTable) -> Result<Vec<NaiveDate>>;
+
+    /// Months that still have rows in the default partition, oldest first
+    async fn unpartitioned_months(&self, table: PartitionedTable) -> Result<Vec<NaiveDate>>;
+
+    /// Create the partition of a month, moving the month's rows out of the default
+    /// partition, and return how many rows were moved
+    ///
+    /// Does nothing if the partition exists. Writes to the default partition wait
+    /// until the rows are moved, so large months are best moved off-peak.
+    async fn create_month(&self, table: PartitionedTable, month: NaiveDate) -> Result<usize>;
+
+    /// Drop the partition of a month with its rows, returning how many rows were dropped
+    ///
+    /// The logs, attempts and unredacted outputs of dropped jobs are deleted with them;
+    /// wallet transactions keep their job IDs. Does nothing if the partition does not exist.
+    async fn drop_month(&self, table: PartitionedTable, month: NaiveDate) -> Result<usize>;
+}
@@ -0,0 +1,52 @@
+use async_trait::async_trait;
+use uuid::Uuid;
+use anyhow::Result;
+
+use crate::models::pipeline::{
+    NewPipeline, NewPipelineRun, Pipeline, PipelineRun, PipelineRunStatus, PipelineRunStep, StepStatus,
+};
+
+/// Repository trait for pipelines and their runs
+#[async_trait]
+pub trait PipelineRepository: Send + Sync {
+    /// Create a new pipeline
+    async fn create(&self, pipeline: NewPipeline) -> Result<Pipeline>;
+
+    /// Find a pipeline by ID
+    async fn find_by_id(&self, id: Uuid) -> Result<Pipeline>;
+
+    /// List the pipelines of a customer
+    async fn find_by_customer_id(&self, customer_id: Uuid) -> Result<Vec<Pipeline>>;
+
+    /// Replace the definition of a pipeline; runs already started keep theirs
+    async fn update_definition(&self, id: Uuid, name: String, description: Option<String>, definition: String) -> Result<Pipeline>;
+
+    /// Delete a pipeline together with its runs
+    async fn delete(&self, id: Uuid) -> Result<()>;
+
+    /// Create a run with a pending entry for each of the named steps
+    async fn create_run(&self, run: NewPipelineRun, step_names: Vec<String>) -> Result<PipelineRun>;
+
+    /// Find a run by ID
+    async fn find_run_by_id(&self, id: Uuid) -> Result<PipelineRun>;
+
+    /// List the runs of a pipeline, newest first
+    async fn find_runs_by_pipeline_id(&self, pipeline_id: Uuid) -> Result<Vec<PipelineRun>>;
+
+    /// List the runs that have not finished yet
+    async fn find_running(&self) -> Result<Vec<PipelineRun>>;
+
+    /// List the steps of a run
+    async fn find_run_steps(&self, run_id: Uuid) -> Result<Vec<PipelineRunStep>>;
+
+    /// Move a pending step to `status`, with the job submitted for it if it starts;
+    /// false if the step is no longer pending, e.g. because another API instance
+    /// claimed it first
+    async fn claim_step(&self, run_id: Uuid, step_name: String, status: StepStatus, job_id: Option<Uuid>) -> Result<bool>;
+
+    /// Set the status of a run's step, and its job once submitted
+    async fn update_step(&self, run_id: Uuid, step_name: String, status: StepStatus, job_id: Option<Uuid>) -> Result<PipelineRunStep>;
+
+    /// Mark a run as finished
+    async fn finish_run(&self, id: Uuid, status: PipelineRunStatus) -> Result<PipelineRun>;
+}
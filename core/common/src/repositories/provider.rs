@@ -0,0 +1,27 @@
+use async_trait::async_trait;
+use anyhow::Result;
+use uuid::Uuid;
+
+use crate::models::provider::{NewProvider, Provider, ProviderStatus};
+
+/// Repository trait for the external providers job types depend on
+#[async_trait]
+pub trait ProviderRepository: Send + Sync {
+    /// Create a new provider, initially up
+    async fn create(&self, provider: NewProvider) -> Result<Provider>;
+
+    /// Find a provider by ID
+    async fn find_by_id(&self, id: Uuid) -> Result<Provider>;
+
+    /// List all providers by name
+    async fn list_all(&self) -> Result<Vec<Provider>>;
+
+    /// Update the name, ping URL and thresholds of a provider
+    async fn update(&self, provider: Provider) -> Result<Provider>;
+
+    /// Record the outcome of a health check, noting when the status changed
+    async fn record_check(&self, id: Uuid, status: ProviderStatus, reason: Option<String>) -> Result<Provider>;
+
+    /// Delete a provider; its job types no longer depend on a provider
+    async fn delete(&self, id: Uuid) -> Result<()>;
+}
@@ -0,0 +1,28 @@
+use async_trait::async_trait;
+use anyhow::Result;
+use chrono::{DateTime, Utc};
+
+use crate::models::purge::PurgedRows;
+
+/// Repository trait for permanently deleting data past its retention
+///
+/// Each call acts on the schema of the reseller the caller acts for.
+#[async_trait]
+pub trait PurgeRepository: Send + Sync {
+    /// Count finished jobs created before `created_before`
+    async fn count_expired_jobs(&self, created_before: DateTime<Utc>) -> Result<u64>;
+
+    /// Delete up to `limit` finished jobs created before `created_before` in one
+    /// transaction, with their logs, attempts, unredacted outputs, usage and artifacts
+    ///
+    /// Wallet transactions and billing exports keep the IDs of deleted jobs, so closed
+    /// ledgers do not change. Returns the rows deleted by table; none once nothing is left.
+    async fn purge_expired_jobs(&self, created_before: DateTime<Utc>, limit: i64) -> Result<PurgedRows>;
+
+    /// Count unredacted job outputs whose retention ended
+    async fn count_expired_unredacted_outputs(&self) -> Result<u64>;
+
+    /// Delete up to `limit` unredacted job outputs whose retention ended, returning the
+    /// rows deleted by table
+    async fn purge_expired_unredacted_outputs(&self, limit: i64) -> Result<PurgedRows>;
+}
@@ -0,0 +1,33 @@
+use async_trait::async_trait;
+use anyhow::Result;
+use serde_json::Value;
+use uuid::Uuid;
+
+use crate::models::job::Job;
+use crate::models::replay::{NewReplayCase, ReplayCase};
+
+/// Repository trait for the replay corpus of job types
+///
+/// The corpus is shared by all resellers; jobs are sampled from the schema of the
+/// current reseller scope.
+#[async_trait]
+pub trait ReplayCaseRepository: Send + Sync {
+    /// Up to `limit` randomly chosen live jobs of a job type that succeeded and are not
+    /// in its corpus yet
+    async fn find_candidates(&self, job_type_id: Uuid, limit: i64) -> Result<Vec<Job>>;
+
+    /// Add cases to the corpus, skipping jobs already in it; returns the cases added
+    async fn add(&self, cases: Vec<NewReplayCase>) -> Result<Vec<ReplayCase>>;
+
+    /// The corpus of a job type, oldest cases first
+    async fn list_by_job_type(&self, job_type_id: Uuid) -> Result<Vec<ReplayCase>>;
+
+    /// Job types with a corpus
+    async fn list_job_types(&self) -> Result<Vec<Uuid>>;
+
+    /// Record the output and cost of a replay as the baseline of a case, replacing any earlier one
+    async fn record_baseline(&self, id: Uuid, output: Value, cost_cents: i32, version: &str) -> Result<ReplayCase>;
+
+    /// Remove a case from the corpus of a job type; returns whether it was there
+    async fn delete(&self, job_type_id: Uuid, id: Uuid) -> Result<bool>;
+}
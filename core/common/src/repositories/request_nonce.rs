@@ -0,0 +1,14 @@
+use async_trait::async_trait;
+use anyhow::Result;
+use chrono::{DateTime, Utc};
+use uuid::Uuid;
+
+/// Repository trait for the nonces of accepted signed requests
+#[async_trait]
+pub trait RequestNonceRepository: Send + Sync {
+    /// Record a reseller's nonce until it expires, returning false if it was already used
+    async fn record(&self, reseller_id: Uuid, nonce: &str, expires_at: DateTime<Utc>) -> Result<bool>;
+
+    /// Delete all expired nonces, returning how many were deleted
+    async fn purge_expired(&self) -> Result<usize>;
+}
@@ -1,6 +1,7 @@
 use async_trait::async_trait;
 use uuid::Uuid;
 use anyhow::Result;
+use chrono::{DateTime, Utc};
 
 use crate::models::reseller::Reseller;
 use crate::models::reseller::NewReseller;
@@ -25,4 +26,14 @@ pub trait ResellerRepository: Send + Sync {
     
     /// List only active resellers
     async fn list_active(&self) -> Result<Vec<Reseller>>;
+    
+    /// List the sandbox tenants of a reseller, newest first
+    async fn list_sandboxes(&self, parent_id: Uuid) -> Result<Vec<Reseller>>;
+    
+    /// Find the sandbox tenants that expired at or before the given time
+    async fn find_expired_sandboxes(&self, now: DateTime<Utc>) -> Result<Vec<Reseller>>;
+    
+    /// Delete a sandbox tenant with its customers and everything they own, including
+    /// their invoices; refused for resellers that are not sandboxes
+    async fn delete_sandbox(&self, id: Uuid) -> Result<()>;
 }
@@ -1,10 +1,12 @@
 use async_trait::async_trait;
 use uuid::Uuid;
 use anyhow::Result;
-use chrono::NaiveDateTime;
+use chrono::{DateTime, Utc};
 
 use crate::models::runner::Runner;
 use crate::models::runner::NewRunner;
+use crate::models::runner::{RunnerHealthCheck, NewRunnerHealthCheck};
+use crate::models::runner::{RunnerEnvironmentReport, NewRunnerEnvironmentReport};
 use crate::models::job_type::JobType;
 
 /// Repository trait for Runner operations
@@ -14,7 +16,7 @@ pub trait RunnerRepository: Send + Sync {
     async fn register(&self, runner: NewRunner) -> Result<Runner>;
     
     /// Update a runner's heartbeat timestamp
-    async fn update_heartbeat(&self, id: Uuid, timestamp: NaiveDateTime) -> Result<Runner>;
+    async fn update_heartbeat(&self, id: Uuid, timestamp: DateTime<Utc>) -> Result<Runner>;
     
     /// Find a runner by ID
     async fn find_by_id(&self, id: Uuid) -> Result<Runner>;
@@ -22,15 +24,49 @@ pub trait RunnerRepository: Send + Sync {
     /// Update a runner's capabilities
     async fn update_capabilities(&self, id: Uuid, job_types: Vec<Uuid>) -> Result<Runner>;
     
+    /// Get the IDs of the job types a runner is registered as compatible with
+    async fn find_compatible_job_type_ids(&self, id: Uuid) -> Result<Vec<Uuid>>;
+    
     /// List all runners
     async fn list_all(&self) -> Result<Vec<Runner>>;
     
     /// List active runners (with recent heartbeat)
-    async fn list_active(&self, since: NaiveDateTime) -> Result<Vec<Runner>>;
+    async fn list_active(&self, since: DateTime<Utc>) -> Result<Vec<Runner>>;
     
     /// Find runners compatible with a specific job type
     async fn find_compatible_with_job_type(&self, job_type: &JobType) -> Result<Vec<Runner>>;
     
     /// Set runner status (active/inactive)
     async fn set_status(&self, id: Uuid, active: bool) -> Result<Runner>;
+    
+    /// Store the result of a runner health evaluation
+    async fn record_health_check(&self, check: NewRunnerHealthCheck) -> Result<RunnerHealthCheck>;
+    
+    /// Get the most recent health check of a runner, if any
+    async fn latest_health_check(&self, runner_id: Uuid) -> Result<Option<RunnerHealthCheck>>;
+    
+    /// List a runner's health checks, newest first
+    async fn get_health_history(&self, runner_id: Uuid, limit: i64) -> Result<Vec<RunnerHealthCheck>>;
+    
+    /// Get the time of the first check in the runner's current run of checks with the given status
+    ///
+    /// Returns `None` if the latest check has a different status or there are no checks.
+    async fn health_status_since(&self, runner_id: Uuid, status: &str) -> Result<Option<DateTime<Utc>>>;
+    
+    /// Delete inactive runners that neither changed status nor sent a heartbeat since `before`
+    ///
+    /// Their compatibility rows and health history are deleted with them. Returns the deleted runners' IDs.
+    async fn delete_inactive_before(&self, before: DateTime<Utc>) -> Result<Vec<Uuid>>;
+    
+    /// Store an environment reported by a runner, unless it has the fingerprint of the
+    /// runner's latest one
+    ///
+    /// Returns the stored report, or `None` if the environment did not change.
+    async fn record_environment(&self, report: NewRunnerEnvironmentReport) -> Result<Option<RunnerEnvironmentReport>>;
+    
+    /// List the environments a runner reported, newest first
+    async fn get_environment_history(&self, runner_id: Uuid, limit: i64) -> Result<Vec<RunnerEnvironmentReport>>;
+    
+    /// Get the latest environment of every runner that reported one
+    async fn latest_environments(&self) -> Result<Vec<RunnerEnvironmentReport>>;
 }
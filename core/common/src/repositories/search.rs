@@ -0,0 +1,26 @@
+use async_trait::async_trait;
+use anyhow::Result;
+
+use crate::models::customer::Customer;
+use crate::models::job::Job;
+use crate::models::project::Project;
+use crate::models::reseller::Reseller;
+
+/// Repository trait for the admin search across entities
+///
+/// Text matches any part of the searched columns, case-insensitively, best matches
+/// first; a UUID matches the entity with that ID.
+#[async_trait]
+pub trait SearchRepository: Send + Sync {
+    /// Customers by ID, name or email
+    async fn search_customers(&self, query: &str, limit: i64) -> Result<Vec<Customer>>;
+
+    /// Resellers by ID, name or email
+    async fn search_resellers(&self, query: &str, limit: i64) -> Result<Vec<Reseller>>;
+
+    /// Projects by ID or name
+    async fn search_projects(&self, query: &str, limit: i64) -> Result<Vec<Project>>;
+
+    /// Jobs by ID or public ID, with or without its `job_` prefix; most recent first
+    async fn search_jobs(&self, query: &str, limit: i64) -> Result<Vec<Job>>;
+}
@@ -0,0 +1,25 @@
+use async_trait::async_trait;
+use anyhow::Result;
+use chrono::{DateTime, Utc};
+use uuid::Uuid;
+
+use crate::models::spending_alert::{AnomalyKind, NewSpendingAlert, SpendingAlert};
+
+/// Repository trait for anomalies detected in customers' spending patterns
+#[async_trait]
+pub trait SpendingAlertRepository: Send + Sync {
+    /// Record a new alert
+    async fn create(&self, alert: NewSpendingAlert) -> Result<SpendingAlert>;
+
+    /// Whether an alert of this kind was raised for the customer since `since`
+    async fn exists_since(&self, customer_id: Uuid, kind: AnomalyKind, since: DateTime<Utc>) -> Result<bool>;
+
+    /// List the alerts of a customer, newest first
+    async fn list_by_customer(&self, customer_id: Uuid, limit: i64) -> Result<Vec<SpendingAlert>>;
+
+    /// List the alerts of a reseller's customers, newest first
+    async fn list_by_reseller(&self, reseller_id: Uuid, limit: i64) -> Result<Vec<SpendingAlert>>;
+
+    /// List the most recent alerts of all customers, newest first
+    async fn list_recent(&self, limit: i64) -> Result<Vec<SpendingAlert>>;
+}
@@ -0,0 +1,18 @@
+use async_trait::async_trait;
+use anyhow::Result;
+use uuid::Uuid;
+
+use crate::models::submission::{NewSubmission, Submission};
+
+/// Repository trait for the groups of jobs customers submit together
+#[async_trait]
+pub trait SubmissionRepository: Send + Sync {
+    /// Record a new submission; its jobs refer to it by ID
+    async fn create(&self, submission: NewSubmission) -> Result<Submission>;
+
+    /// Find a submission by ID
+    async fn find_by_id(&self, id: Uuid) -> Result<Submission>;
+
+    /// Mark a submission cancelled, keeping the time it was first cancelled at
+    async fn cancel(&self, id: Uuid) -> Result<Submission>;
+}
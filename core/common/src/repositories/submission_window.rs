@@ -0,0 +1,30 @@
+use async_trait::async_trait;
+use uuid::Uuid;
+use anyhow::Result;
+
+use crate::models::submission_window::{NewSubmissionWindow, SubmissionWindow};
+
+/// Repository trait for job submission windows (quiet hours)
+#[async_trait]
+pub trait SubmissionWindowRepository: Send + Sync {
+    /// Create a new submission window
+    async fn create(&self, window: NewSubmissionWindow) -> Result<SubmissionWindow>;
+
+    /// Find a submission window by ID
+    async fn find_by_id(&self, id: Uuid) -> Result<SubmissionWindow>;
+
+    /// List the submission windows a customer set for itself
+    async fn find_by_customer_id(&self, customer_id: Uuid) -> Result<Vec<SubmissionWindow>>;
+
+    /// List the submission windows a reseller set for its customers
+    async fn find_by_reseller_id(&self, reseller_id: Uuid) -> Result<Vec<SubmissionWindow>>;
+
+    /// Find the windows restricting a customer's jobs of a type
+    ///
+    /// The customer's own windows take precedence; without them the windows of its
+    /// reseller apply. An empty list means jobs may be queued at any time.
+    async fn find_applicable(&self, job_type_id: Uuid, customer_id: Uuid, reseller_id: Option<Uuid>) -> Result<Vec<SubmissionWindow>>;
+
+    /// Delete a submission window
+    async fn delete(&self, id: Uuid) -> Result<()>;
+}
@@ -0,0 +1,18 @@
+use async_trait::async_trait;
+use anyhow::Result;
+use uuid::Uuid;
+
+use crate::models::redaction::{NewUnredactedOutput, UnredactedOutput};
+
+/// Repository trait for the original output of jobs that had values redacted
+#[async_trait]
+pub trait UnredactedOutputRepository: Send + Sync {
+    /// Keep the original output of a job, replacing the one of an earlier run
+    async fn store(&self, output: NewUnredactedOutput) -> Result<UnredactedOutput>;
+
+    /// Find the original output of a job, unless it expired
+    async fn find_by_job_id(&self, job_id: Uuid) -> Result<UnredactedOutput>;
+
+    /// Delete all expired outputs, returning how many were deleted
+    async fn purge_expired(&self) -> Result<usize>;
+}
@@ -2,13 +2,18 @@ use async_trait::async_trait;
 use uuid::Uuid;
 use anyhow::Result;
 
-use crate::models::wallet::{Wallet, NewWallet, WalletTransaction, NewWalletTransaction, TransactionType};
+use crate::models::ledger::JobDebit;
+use crate::models::wallet::{Wallet, NewWallet, OverdraftPolicy, WalletTransaction, NewWalletTransaction, TransactionType};
 
 #[async_trait]
 pub trait WalletRepository: Send + Sync {
     /// Create a new wallet for a customer
     async fn create(&self, new_wallet: NewWallet) -> Result<Wallet>;
     
+    /// Create many wallets in one transaction, with multi-row inserts; either all of
+    /// them are created or none
+    async fn create_many(&self, new_wallets: Vec<NewWallet>) -> Result<Vec<Wallet>>;
+    
     /// Find a wallet by its ID
     async fn find_by_id(&self, id: Uuid) -> Result<Wallet>;
     
@@ -34,7 +39,8 @@ pub trait WalletRepository: Send + Sync {
         job_id: Option<Uuid>
     ) -> Result<Wallet>;
     
-    /// Remove funds from wallet and create a withdrawal transaction record
+    /// Remove funds from wallet and create a withdrawal transaction record; withdrawals
+    /// never take the balance below zero, whatever the overdraft policy
     async fn withdraw(
         &self,
         id: Uuid,
@@ -43,7 +49,17 @@ pub trait WalletRepository: Send + Sync {
         job_id: Option<Uuid>
     ) -> Result<Wallet>;
     
+    /// Charge a job to the wallet with a job debit transaction record, posting the ledger
+    /// entries that split the charge between the platform and the reseller along with it
+    ///
+    /// The charge may take the balance below zero unless the debit requires funds.
+    async fn charge_job(&self, debit: JobDebit) -> Result<Wallet>;
+    
     /// Reserve funds for a pending transaction
+    ///
+    /// Refused with insufficient funds when the reservation would take the balance below
+    /// the floor of the wallet's overdraft policy; the check and the reservation are
+    /// atomic, so concurrent reservations cannot overdraw the wallet together.
     async fn reserve_funds(
         &self, 
         id: Uuid, 
@@ -67,6 +83,21 @@ pub trait WalletRepository: Send + Sync {
     /// Get transaction history for a wallet with pagination
     async fn get_transactions(&self, wallet_id: Uuid, limit: i32, offset: i32) -> Result<Vec<WalletTransaction>>;
     
+    /// Amount still held for a job: its reservations minus the releases recorded against it
+    async fn get_reserved_for_job(&self, job_id: Uuid) -> Result<i32>;
+    
+    /// Adjust the sandbox balance used by test-mode jobs; no transaction record is kept
+    async fn adjust_test_balance(&self, id: Uuid, amount: i32) -> Result<Wallet>;
+    
+    /// Add promotional credit to the wallet
+    async fn grant_credit(&self, id: Uuid, amount: i32) -> Result<Wallet>;
+    
+    /// Spend up to `amount` of the wallet's promotional credit, returning the amount spent
+    async fn spend_credit(&self, id: Uuid, amount: i32) -> Result<i32>;
+    
+    /// Set what happens when a reservation would take the wallet below zero
+    async fn set_overdraft_policy(&self, id: Uuid, policy: OverdraftPolicy, limit_cents: i32) -> Result<Wallet>;
+    
     /// Get the current balance of a wallet
     async fn get_balance(&self, id: Uuid) -> Result<i32>;
 }
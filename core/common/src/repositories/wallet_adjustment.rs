@@ -0,0 +1,35 @@
+use async_trait::async_trait;
+use uuid::Uuid;
+use anyhow::Result;
+
+use crate::models::wallet_adjustment::{AdjustmentStatus, NewWalletAdjustment, WalletAdjustment};
+
+/// Repository trait for manual wallet adjustments and their review
+///
+/// Every step of an adjustment's review is written to the audit log in the same
+/// database transaction as the step itself.
+#[async_trait]
+pub trait WalletAdjustmentRepository: Send + Sync {
+    /// Record a pending adjustment
+    ///
+    /// Fails with an `AdjustmentError` if the amount is invalid for its kind.
+    async fn create(&self, adjustment: NewWalletAdjustment) -> Result<WalletAdjustment>;
+
+    /// Find an adjustment by ID
+    async fn find_by_id(&self, id: Uuid) -> Result<WalletAdjustment>;
+
+    /// List the adjustments in a status, oldest first
+    async fn find_by_status(&self, status: AdjustmentStatus) -> Result<Vec<WalletAdjustment>>;
+
+    /// List the adjustments of a wallet, newest first
+    async fn find_by_wallet_id(&self, wallet_id: Uuid) -> Result<Vec<WalletAdjustment>>;
+
+    /// Approve a pending adjustment and post its transaction to the wallet atomically
+    ///
+    /// Fails with an `AdjustmentError` if the adjustment is no longer pending, needs
+    /// a reviewer other than its requester, or would debit more than the balance.
+    async fn approve(&self, id: Uuid, reviewed_by: String, note: Option<String>) -> Result<WalletAdjustment>;
+
+    /// Reject a pending adjustment, leaving the wallet unchanged
+    async fn reject(&self, id: Uuid, reviewed_by: String, note: Option<String>) -> Result<WalletAdjustment>;
+}
@@ -1,7 +1,7 @@
 use async_trait::async_trait;
 use uuid::Uuid;
 use anyhow::Result;
-use chrono::NaiveDateTime;
+use chrono::{DateTime, Utc};
 
 use crate::models::wallet::{WalletTransaction, NewWalletTransaction, TransactionType};
 
@@ -21,11 +21,14 @@ pub trait WalletTransactionRepository: Send + Sync {
     async fn find_by_customer_id(&self, customer_id: Uuid) -> Result<Vec<WalletTransaction>>;
     
     /// Get transactions within a time range
-    async fn find_in_time_range(&self, start_time: NaiveDateTime, end_time: NaiveDateTime) -> Result<Vec<WalletTransaction>>;
+    async fn find_in_time_range(&self, start_time: DateTime<Utc>, end_time: DateTime<Utc>) -> Result<Vec<WalletTransaction>>;
     
     /// Get transactions by transaction type
     async fn find_by_transaction_type(&self, transaction_type: TransactionType) -> Result<Vec<WalletTransaction>>;
     
     /// Get transactions for a specific job
     async fn find_by_job_id(&self, job_id: Option<Uuid>) -> Result<Vec<WalletTransaction>>;
+    
+    /// Get a customer's transactions carrying their own reference, newest first
+    async fn find_by_customer_reference(&self, customer_id: Uuid, reference: &str) -> Result<Vec<WalletTransaction>>;
 }
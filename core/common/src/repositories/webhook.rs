@@ -0,0 +1,44 @@
+use async_trait::async_trait;
+use uuid::Uuid;
+use anyhow::Result;
+use chrono::{DateTime, Utc};
+
+use crate::models::webhook::{CustomerWebhook, NewCustomerWebhook, WebhookEventType};
+
+/// Repository trait for customer webhook endpoints
+#[async_trait]
+pub trait CustomerWebhookRepository: Send + Sync {
+    /// Create a new webhook endpoint
+    async fn create(&self, webhook: NewCustomerWebhook) -> Result<CustomerWebhook>;
+
+    /// Find a webhook endpoint by ID
+    async fn find_by_id(&self, id: Uuid) -> Result<CustomerWebhook>;
+
+    /// List all webhook endpoints registered by a customer
+    async fn find_by_customer_id(&self, customer_id: Uuid) -> Result<Vec<CustomerWebhook>>;
+
+    /// Find the active endpoints of a customer that subscribe to an event type
+    async fn find_active_for_event(&self, customer_id: Uuid, event_type: WebhookEventType) -> Result<Vec<CustomerWebhook>>;
+
+    /// Update a webhook endpoint's URL, subscriptions, active flag and failure tracking
+    async fn update(&self, webhook: &CustomerWebhook) -> Result<CustomerWebhook>;
+
+    /// Record the outcome of a test-fire against the endpoint
+    async fn record_test_result(&self, id: Uuid, status_code: Option<i32>, response: Option<String>) -> Result<CustomerWebhook>;
+
+    /// Set or clear the start of the endpoint's current run of delivery failures
+    async fn set_failing_since(&self, id: Uuid, failing_since: Option<DateTime<Utc>>) -> Result<CustomerWebhook>;
+
+    /// Record a failed delivery: start the endpoint's failure run unless one is ongoing, and
+    /// disable the endpoint if its run started at or before `disable_failing_since`
+    ///
+    /// Both happen in the database, so concurrent deliveries cannot undo each other. Returns
+    /// the endpoint if this failure disabled it.
+    async fn record_delivery_failure(&self, id: Uuid, at: DateTime<Utc>, disable_failing_since: DateTime<Utc>) -> Result<Option<CustomerWebhook>>;
+
+    /// Record a successful delivery, ending the endpoint's failure run if one is ongoing
+    async fn record_delivery_success(&self, id: Uuid) -> Result<()>;
+
+    /// Delete a webhook endpoint
+    async fn delete(&self, id: Uuid) -> Result<()>;
+}
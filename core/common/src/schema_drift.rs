@@ -0,0 +1,258 @@
+//! Detection of drift between the Diesel table definitions and the database schema
+//!
+//! The tables in `diesel_schema` are maintained by hand next to the migrations, so the
+//! two can disagree without anything failing until a query touches the difference. The
+//! check here reads the table definitions compiled into the binary, introspects the
+//! database through `information_schema` and lists every column the code expects but
+//! the database does not provide as declared.
+
+use std::collections::HashMap;
+use std::env;
+use std::fmt;
+
+use diesel::prelude::*;
+use diesel::pg::PgConnection;
+use diesel::sql_types::Text;
+
+use crate::database::PgPool;
+use crate::errors::Error;
+
+/// Source of the Diesel table definitions, parsed instead of enumerating the tables by hand
+const DIESEL_SCHEMA: &str = include_str!("diesel_schema/mod.rs");
+
+/// A column as declared in a `table!` definition
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ExpectedColumn {
+    pub name: String,
+    /// Diesel SQL type without `Nullable`, e.g. `Timestamptz` or `Array<Text>`
+    pub sql_type: String,
+    pub nullable: bool,
+}
+
+/// A table as declared in a `table!` definition
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ExpectedTable {
+    pub name: String,
+    pub columns: Vec<ExpectedColumn>,
+}
+
+/// A difference between a declared table and the database
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum SchemaDrift {
+    /// The table does not exist
+    MissingTable { table: String },
+    /// The table exists without the column
+    MissingColumn { table: String, column: String },
+    /// The column has a type the declared one cannot be read from or written to
+    TypeMismatch { table: String, column: String, expected: String, actual: String },
+    /// The column is declared NOT NULL but the database allows NULL, so loading a row holding one fails
+    NullableColumn { table: String, column: String },
+}
+
+impl fmt::Display for SchemaDrift {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::MissingTable { table } => write!(f, "{}: table missing", table),
+            Self::MissingColumn { table, column } => write!(f, "{}.{}: column missing", table, column),
+            Self::TypeMismatch { table, column, expected, actual } => {
+                write!(f, "{}.{}: declared {} but the database has {}", table, column, expected, actual)
+            }
+            Self::NullableColumn { table, column } => {
+                write!(f, "{}.{}: declared NOT NULL but the database allows NULL", table, column)
+            }
+        }
+    }
+}
+
+/// Render drift as a diff, one difference per line
+pub fn format_drift(drift: &[SchemaDrift]) -> String {
+    drift.iter().map(|drift| format!("  - {}", drift)).collect::<Vec<_>>().join("\n")
+}
+
+/// Tables declared in `diesel_schema`, in declaration order
+pub fn expected_tables() -> Vec<ExpectedTable> {
+    parse_tables(DIESEL_SCHEMA)
+}
+
+/// Parse the `table!` definitions of a Diesel schema source file
+pub fn parse_tables(source: &str) -> Vec<ExpectedTable> {
+    let mut tables = Vec::new();
+    let mut current: Option<ExpectedTable> = None;
+
+    for line in source.lines().map(str::trim) {
+        match current.as_mut() {
+            None => {
+                // Table header, e.g. `jobs (id) {`
+                if let Some((name, rest)) = line.split_once(" (") {
+                    if rest.ends_with('{') && is_identifier(name) {
+                        current = Some(ExpectedTable { name: name.to_string(), columns: Vec::new() });
+                    }
+                }
+            }
+            Some(table) => {
+                if line == "}" {
+                    tables.extend(current.take());
+                } else if let Some((name, sql_type)) = line.trim_end_matches(',').split_once(" -> ") {
+                    let (sql_type, nullable) = match sql_type.strip_prefix("Nullable<").and_then(|inner| inner.strip_suffix('>')) {
+                        Some(inner) => (inner, true),
+                        None => (sql_type, false),
+                    };
+                    table.columns.push(ExpectedColumn {
+                        name: name.to_string(),
+                        sql_type: sql_type.to_string(),
+                        nullable,
+                    });
+                }
+            }
+        }
+    }
+
+    tables
+}
+
+fn is_identifier(name: &str) -> bool {
+    !name.is_empty() && name.chars().all(|c| c.is_ascii_lowercase() || c.is_ascii_digit() || c == '_')
+}
+
+/// Postgres type names (`udt_name`) a Diesel SQL type maps onto; None for types the check does not know
+pub fn compatible_types(sql_type: &str) -> Option<Vec<String>> {
+    if let Some(element) = sql_type.strip_prefix("Array<").and_then(|inner| inner.strip_suffix('>')) {
+        return compatible_types(element)
+            .map(|types| types.into_iter().map(|udt| format!("_{}", udt)).collect());
+    }
+    let types: &[&str] = match sql_type {
+        "Uuid" => &["uuid"],
+        "Text" | "VarChar" | "Varchar" => &["text", "varchar", "bpchar"],
+        "SmallInt" | "Int2" => &["int2"],
+        "Integer" | "Int4" => &["int4"],
+        "BigInt" | "Int8" => &["int8"],
+        "Bool" => &["bool"],
+        "Float" | "Float4" => &["float4"],
+        "Double" | "Float8" => &["float8"],
+        "Numeric" => &["numeric"],
+        "Jsonb" => &["jsonb"],
+        "Json" => &["json"],
+        "Bytea" | "Binary" => &["bytea"],
+        "Date" => &["date"],
+        "Time" => &["time"],
+        "Interval" => &["interval"],
+        "Timestamp" => &["timestamp"],
+        "Timestamptz" => &["timestamptz"],
+        _ => return None,
+    };
+    Some(types.iter().map(|udt| udt.to_string()).collect())
+}
+
+/// A column as found in `information_schema.columns`
+#[derive(Debug, Clone, QueryableByName)]
+pub struct ActualColumn {
+    #[diesel(sql_type = Text)]
+    pub table_name: String,
+    #[diesel(sql_type = Text)]
+    pub column_name: String,
+    #[diesel(sql_type = Text)]
+    pub udt_name: String,
+    #[diesel(sql_type = Text)]
+    pub is_nullable: String,
+}
+
+/// Compare declared tables with the columns found in the database
+///
+/// Columns the database has beyond the declared ones are not drift: migrations may add
+/// columns before the code reads them.
+pub fn compare(expected: &[ExpectedTable], actual: &[ActualColumn]) -> Vec<SchemaDrift> {
+    let mut found: HashMap<&str, HashMap<&str, &ActualColumn>> = HashMap::new();
+    for column in actual {
+        found.entry(column.table_name.as_str()).or_default().insert(column.column_name.as_str(), column);
+    }
+
+    let mut drift = Vec::new();
+    for table in expected {
+        let Some(columns) = found.get(table.name.as_str()) else {
+            drift.push(SchemaDrift::MissingTable { table: table.name.clone() });
+            continue;
+        };
+        for column in &table.columns {
+            let Some(actual) = columns.get(column.name.as_str()) else {
+                drift.push(SchemaDrift::MissingColumn { table: table.name.clone(), column: column.name.clone() });
+                continue;
+            };
+            if let Some(types) = compatible_types(&column.sql_type) {
+                if !types.contains(&actual.udt_name) {
+                    drift.push(SchemaDrift::TypeMismatch {
+                        table: table.name.clone(),
+                        column: column.name.clone(),
+                        expected: column.sql_type.clone(),
+                        actual: actual.udt_name.clone(),
+                    });
+                }
+            }
+            if !column.nullable && actual.is_nullable == "YES" {
+                drift.push(SchemaDrift::NullableColumn { table: table.name.clone(), column: column.name.clone() });
+            }
+        }
+    }
+
+    drift
+}
+
+/// Compare the declared tables with the current schema of the connection
+pub fn check(conn: &mut PgConnection) -> Result<Vec<SchemaDrift>, Error> {
+    let actual = diesel::sql_query(
+        "SELECT table_name::text, column_name::text, udt_name::text, is_nullable::text \
+         FROM information_schema.columns WHERE table_schema = current_schema()",
+    )
+    .load::<ActualColumn>(conn)?;
+
+    Ok(compare(&expected_tables(), &actual))
+}
+
+/// What services do about drift found on startup
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum SchemaCheckMode {
+    /// Refuse to start, listing the drift
+    Fail,
+    /// Log the drift and start anyway
+    Warn,
+    /// Skip the check
+    Off,
+}
+
+impl SchemaCheckMode {
+    /// Parse a mode from its configuration value
+    pub fn parse(value: &str) -> Option<Self> {
+        match value {
+            "fail" => Some(Self::Fail),
+            "warn" => Some(Self::Warn),
+            "off" => Some(Self::Off),
+            _ => None,
+        }
+    }
+
+    /// Load the mode from the `SCHEMA_DRIFT_CHECK` environment variable, failing on drift when unset
+    pub fn from_env() -> Self {
+        env::var("SCHEMA_DRIFT_CHECK").ok()
+            .and_then(|value| Self::parse(&value))
+            .unwrap_or(Self::Fail)
+    }
+}
+
+/// Check the shared schema on startup as `mode` says, returning the drift that was tolerated
+///
+/// Fails with a `Configuration` error listing the drift in `Fail` mode.
+pub fn verify(pool: &PgPool, mode: SchemaCheckMode) -> Result<Vec<SchemaDrift>, Error> {
+    if mode == SchemaCheckMode::Off {
+        return Ok(Vec::new());
+    }
+    let mut conn = pool.get()
+        .map_err(|e| Error::Configuration(format!("Failed to connect to check the database schema: {}", e)))?;
+    let drift = check(&mut conn)?;
+    if mode == SchemaCheckMode::Fail && !drift.is_empty() {
+        return Err(Error::Configuration(format!(
+            "The database schema drifted from diesel_schema ({} differences; set SCHEMA_DRIFT_CHECK=warn to start anyway):\n{}",
+            drift.len(),
+            format_drift(&drift),
+        )));
+    }
+    Ok(drift)
+}
@@ -1,8 +1,8 @@
 use crate::errors::Error;
 use crate::models::{
     customer::NewCustomer,
-    job::{JobStatus, NewJob},
-    job_type::{NewJobType, ProcessorType},
+    job::{generate_public_id, JobStatus, NewJob, PriorityLevel},
+    job_type::{CatalogVisibility, NewJobType, ProcessorType},
     wallet::NewWallet,
 };
 use crate::repositories::{
@@ -67,6 +67,12 @@ impl Seeder {
                 processor_type: ProcessorType::Async.as_str().to_string(),
                 standard_cost_cents: 100,
                 enabled: true,
+                category: Some("text".to_string()),
+                icon: None,
+                documentation_url: None,
+                sample_input: None,
+                sample_output: None,
+                visibility: CatalogVisibility::Public.as_str().to_string(),
             },
             NewJobType {
                 id: Uuid::new_v4(),
@@ -76,6 +82,12 @@ impl Seeder {
                 processor_type: ProcessorType::Async.as_str().to_string(),
                 standard_cost_cents: 200,
                 enabled: true,
+                category: Some("images".to_string()),
+                icon: None,
+                documentation_url: None,
+                sample_input: None,
+                sample_output: None,
+                visibility: CatalogVisibility::Public.as_str().to_string(),
             },
             NewJobType {
                 id: Uuid::new_v4(),
@@ -85,6 +97,12 @@ impl Seeder {
                 processor_type: ProcessorType::Batch.as_str().to_string(),
                 standard_cost_cents: 50,
                 enabled: true,
+                category: Some("data".to_string()),
+                icon: None,
+                documentation_url: None,
+                sample_input: None,
+                sample_output: None,
+                visibility: CatalogVisibility::Public.as_str().to_string(),
             },
             NewJobType {
                 id: Uuid::new_v4(),
@@ -94,6 +112,12 @@ impl Seeder {
                 processor_type: ProcessorType::Sync.as_str().to_string(),
                 standard_cost_cents: 75,
                 enabled: true,
+                category: Some("documents".to_string()),
+                icon: None,
+                documentation_url: None,
+                sample_input: None,
+                sample_output: None,
+                visibility: CatalogVisibility::Public.as_str().to_string(),
             },
             NewJobType {
                 id: Uuid::new_v4(),
@@ -103,6 +127,12 @@ impl Seeder {
                 processor_type: ProcessorType::Batch.as_str().to_string(),
                 standard_cost_cents: 25,
                 enabled: false, // This one is disabled for testing
+                category: Some("email".to_string()),
+                icon: None,
+                documentation_url: None,
+                sample_input: None,
+                sample_output: None,
+                visibility: CatalogVisibility::Public.as_str().to_string(),
             },
         ];
 
@@ -130,6 +160,8 @@ impl Seeder {
                 email: "contact@acme.example.com".to_string(),
                 reseller_id: None,
                 api_key: None,
+                parent_id: None,
+                budget_cents: None,
             },
             NewCustomer {
                 id: Uuid::new_v4(),
@@ -137,6 +169,8 @@ impl Seeder {
                 email: "info@techstart.example.com".to_string(),
                 reseller_id: None,
                 api_key: None,
+                parent_id: None,
+                budget_cents: None,
             },
             NewCustomer {
                 id: Uuid::new_v4(),
@@ -144,13 +178,12 @@ impl Seeder {
                 email: "support@globalservices.example.com".to_string(),
                 reseller_id: None,
                 api_key: None,
+                parent_id: None,
+                budget_cents: None,
             },
         ];
 
-        // Insert each customer
-        for customer in customers {
-            self.customer_repo.create(customer).await?;
-        }
+        self.customer_repo.create_many(customers).await?;
 
         Ok(())
     }
@@ -162,6 +195,7 @@ impl Seeder {
         let customers = self.customer_repo.list_all().await?;
 
         // Create a wallet for each customer if they don't already have one
+        let mut wallets = Vec::new();
         for customer in customers {
             // Try to find existing wallet for customer
             let wallet_result = self.wallet_repo.find_by_customer_id(customer.id).await;
@@ -170,14 +204,13 @@ impl Seeder {
                 continue;
             }
             
-            let new_wallet = NewWallet {
+            wallets.push(NewWallet {
                 id: Uuid::new_v4(),
                 customer_id: customer.id,
                 balance_cents: 10000, // Start with $100 balance
-            };
-
-            self.wallet_repo.create(new_wallet).await?;
+            });
         }
+        self.wallet_repo.create_many(wallets).await?;
 
         Ok(())
     }
@@ -215,6 +248,17 @@ impl Seeder {
                         customer_id: customer.id,
                         status: status.as_str().to_string(),
                         cost_cents: job_type.standard_cost_cents,
+                        test_mode: false,
+                        public_id: generate_public_id(),
+                        expires_at: None,
+                        customer_reference: None,
+                        customer_metadata: None,
+                        parent_id: None,
+                        sub_task_index: None,
+                        submission_id: None,
+                        requeued_from: None,
+                        input_data: serde_json::json!({}),
+                        priority: PriorityLevel::Medium.as_i32(),
                     };
 
                     jobs.push(job);
@@ -222,10 +266,7 @@ impl Seeder {
             }
         }
 
-        // Insert each job
-        for job in jobs {
-            self.job_repo.create(job).await?;
-        }
+        self.job_repo.create_many(jobs).await?;
 
         Ok(())
     }
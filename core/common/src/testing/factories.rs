@@ -0,0 +1,352 @@
+//! Builders that insert valid fixture records with sensible defaults
+//!
+//! Every factory generates unique names, emails and API keys, so tests sharing one
+//! database do not collide. Override only the fields a test cares about.
+
+use chrono::{DateTime, Utc};
+use serde_json::json;
+use uuid::Uuid;
+
+use crate::models::{
+    customer::{Customer, NewCustomer},
+    job::{Job, NewJob, PriorityLevel},
+    job_type::{CatalogVisibility, JobType, NewJobType, ProcessorType},
+    reseller::{NewReseller, Reseller},
+    wallet::{NewWallet, Wallet},
+};
+use crate::repositories::{
+    CustomerRepository, JobRepository, JobTypeRepository, ResellerRepository, WalletRepository,
+};
+
+/// Random suffix used to make fixture data unique, also across test runs
+fn unique_suffix() -> String {
+    Uuid::new_v4().simple().to_string()[..12].to_string()
+}
+
+/// Builds and inserts resellers
+pub struct ResellerFactory {
+    name: String,
+    email: String,
+    commission_rate: i32,
+    active: bool,
+}
+
+impl Default for ResellerFactory {
+    fn default() -> Self {
+        let n = unique_suffix();
+        Self {
+            name: format!("Reseller {}", n),
+            email: format!("reseller{}@example.test", n),
+            commission_rate: 1000,
+            active: true,
+        }
+    }
+}
+
+impl ResellerFactory {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn name(mut self, name: impl Into<String>) -> Self {
+        self.name = name.into();
+        self
+    }
+
+    pub fn commission_rate(mut self, commission_rate: i32) -> Self {
+        self.commission_rate = commission_rate;
+        self
+    }
+
+    pub fn inactive(mut self) -> Self {
+        self.active = false;
+        self
+    }
+
+    /// The reseller as an insertable record, without touching the database
+    pub fn build(self) -> NewReseller {
+        NewReseller {
+            id: Uuid::new_v4(),
+            name: self.name,
+            email: self.email,
+            api_key: Reseller::generate_api_key(),
+            active: self.active,
+            commission_rate: self.commission_rate,
+            markup_rate: 0,
+            sandbox_of: None,
+            sandbox_expires_at: None,
+        }
+    }
+
+    pub async fn create(self, repo: &dyn ResellerRepository) -> anyhow::Result<Reseller> {
+        repo.create(self.build()).await
+    }
+}
+
+/// Builds and inserts customers
+pub struct CustomerFactory {
+    name: String,
+    email: String,
+    reseller_id: Option<Uuid>,
+    parent_id: Option<Uuid>,
+    budget_cents: Option<i32>,
+}
+
+impl Default for CustomerFactory {
+    fn default() -> Self {
+        let n = unique_suffix();
+        Self {
+            name: format!("Customer {}", n),
+            email: format!("customer{}@example.test", n),
+            reseller_id: None,
+            parent_id: None,
+            budget_cents: None,
+        }
+    }
+}
+
+impl CustomerFactory {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn name(mut self, name: impl Into<String>) -> Self {
+        self.name = name.into();
+        self
+    }
+
+    pub fn reseller(mut self, reseller_id: Uuid) -> Self {
+        self.reseller_id = Some(reseller_id);
+        self
+    }
+
+    /// Sub-account of the given parent customer
+    pub fn parent(mut self, parent_id: Uuid) -> Self {
+        self.parent_id = Some(parent_id);
+        self
+    }
+
+    pub fn budget_cents(mut self, budget_cents: i32) -> Self {
+        self.budget_cents = Some(budget_cents);
+        self
+    }
+
+    /// The customer as an insertable record, without touching the database
+    pub fn build(self) -> NewCustomer {
+        NewCustomer {
+            id: Uuid::new_v4(),
+            name: self.name,
+            email: self.email,
+            reseller_id: self.reseller_id,
+            api_key: Some(Customer::generate_api_key()),
+            parent_id: self.parent_id,
+            budget_cents: self.budget_cents,
+        }
+    }
+
+    pub async fn create(self, repo: &dyn CustomerRepository) -> anyhow::Result<Customer> {
+        repo.create(self.build()).await
+    }
+}
+
+/// Builds and inserts wallets
+pub struct WalletFactory {
+    customer_id: Uuid,
+    balance_cents: i32,
+}
+
+impl WalletFactory {
+    /// Wallet for the given customer, with a $100.00 balance by default
+    pub fn new(customer_id: Uuid) -> Self {
+        Self {
+            customer_id,
+            balance_cents: 10000,
+        }
+    }
+
+    pub fn balance_cents(mut self, balance_cents: i32) -> Self {
+        self.balance_cents = balance_cents;
+        self
+    }
+
+    /// The wallet as an insertable record, without touching the database
+    pub fn build(self) -> NewWallet {
+        NewWallet {
+            id: Uuid::new_v4(),
+            customer_id: self.customer_id,
+            balance_cents: self.balance_cents,
+        }
+    }
+
+    pub async fn create(self, repo: &dyn WalletRepository) -> anyhow::Result<Wallet> {
+        repo.create(self.build()).await
+    }
+}
+
+/// Builds and inserts job types
+pub struct JobTypeFactory {
+    name: String,
+    processor_type: ProcessorType,
+    standard_cost_cents: i32,
+    enabled: bool,
+    category: Option<String>,
+    visibility: CatalogVisibility,
+}
+
+impl Default for JobTypeFactory {
+    fn default() -> Self {
+        Self {
+            name: format!("Job Type {}", unique_suffix()),
+            processor_type: ProcessorType::Sync,
+            standard_cost_cents: 100,
+            enabled: true,
+            category: None,
+            visibility: CatalogVisibility::Public,
+        }
+    }
+}
+
+impl JobTypeFactory {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn name(mut self, name: impl Into<String>) -> Self {
+        self.name = name.into();
+        self
+    }
+
+    pub fn processor_type(mut self, processor_type: ProcessorType) -> Self {
+        self.processor_type = processor_type;
+        self
+    }
+
+    pub fn standard_cost_cents(mut self, standard_cost_cents: i32) -> Self {
+        self.standard_cost_cents = standard_cost_cents;
+        self
+    }
+
+    pub fn disabled(mut self) -> Self {
+        self.enabled = false;
+        self
+    }
+
+    pub fn category(mut self, category: impl Into<String>) -> Self {
+        self.category = Some(category.into());
+        self
+    }
+
+    pub fn visibility(mut self, visibility: CatalogVisibility) -> Self {
+        self.visibility = visibility;
+        self
+    }
+
+    /// The job type as an insertable record, without touching the database
+    pub fn build(self) -> NewJobType {
+        NewJobType {
+            id: Uuid::new_v4(),
+            processing_logic_id: self.name.to_lowercase().replace(' ', "-"),
+            name: self.name,
+            description: None,
+            processor_type: self.processor_type.as_str().to_string(),
+            standard_cost_cents: self.standard_cost_cents,
+            enabled: self.enabled,
+            category: self.category,
+            icon: None,
+            documentation_url: None,
+            sample_input: None,
+            sample_output: None,
+            visibility: self.visibility.as_str().to_string(),
+        }
+    }
+
+    pub async fn create(self, repo: &dyn JobTypeRepository) -> anyhow::Result<JobType> {
+        Ok(repo.create(self.build()).await?)
+    }
+}
+
+/// Builds and inserts jobs
+pub struct JobFactory {
+    customer_id: Uuid,
+    job_type_id: Uuid,
+    input_data: serde_json::Value,
+    priority: PriorityLevel,
+    estimated_cost_cents: i32,
+    test_mode: bool,
+    expires_at: Option<DateTime<Utc>>,
+    customer_reference: Option<String>,
+    submission_id: Option<Uuid>,
+}
+
+impl JobFactory {
+    /// Pending job of the given customer and job type
+    pub fn new(customer_id: Uuid, job_type_id: Uuid) -> Self {
+        Self {
+            customer_id,
+            job_type_id,
+            input_data: json!({ "text": "hello world" }),
+            priority: PriorityLevel::Medium,
+            estimated_cost_cents: 100,
+            test_mode: false,
+            expires_at: None,
+            customer_reference: None,
+            submission_id: None,
+        }
+    }
+
+    pub fn input_data(mut self, input_data: serde_json::Value) -> Self {
+        self.input_data = input_data;
+        self
+    }
+
+    pub fn priority(mut self, priority: PriorityLevel) -> Self {
+        self.priority = priority;
+        self
+    }
+
+    pub fn estimated_cost_cents(mut self, estimated_cost_cents: i32) -> Self {
+        self.estimated_cost_cents = estimated_cost_cents;
+        self
+    }
+
+    pub fn test_mode(mut self) -> Self {
+        self.test_mode = true;
+        self
+    }
+
+    pub fn expires_at(mut self, expires_at: DateTime<Utc>) -> Self {
+        self.expires_at = Some(expires_at);
+        self
+    }
+
+    pub fn customer_reference(mut self, reference: impl Into<String>) -> Self {
+        self.customer_reference = Some(reference.into());
+        self
+    }
+
+    /// Submit the job as part of a batch or file import
+    pub fn submission(mut self, submission_id: Uuid) -> Self {
+        self.submission_id = Some(submission_id);
+        self
+    }
+
+    /// The job as an insertable record, without touching the database
+    pub fn build(self) -> NewJob {
+        let mut job = Job::new(
+            self.customer_id,
+            self.job_type_id,
+            self.input_data,
+            self.priority,
+            self.estimated_cost_cents,
+        );
+        job.test_mode = self.test_mode;
+        job.expires_at = self.expires_at;
+        job.customer_reference = self.customer_reference;
+        job.submission_id = self.submission_id;
+        NewJob::from(job)
+    }
+
+    pub async fn create(self, repo: &dyn JobRepository) -> anyhow::Result<Job> {
+        Ok(repo.create(self.build()).await?)
+    }
+}
@@ -0,0 +1,94 @@
+//! Integration test harness (enabled with the `testing` feature)
+//!
+//! Starts disposable Postgres and Redis instances with testcontainers and runs all
+//! migrations against them. Set `TEST_DATABASE_URL` / `TEST_REDIS_URL` to run against
+//! existing instances instead, e.g. when no Docker daemon is available.
+
+pub mod factories;
+
+use std::env;
+use std::sync::Mutex;
+
+use anyhow::anyhow;
+use diesel::pg::PgConnection;
+use diesel::r2d2::{ConnectionManager, Pool};
+use testcontainers::{ContainerAsync, ImageExt, runners::AsyncRunner};
+use testcontainers_modules::{postgres::Postgres, redis::{Redis, REDIS_PORT}};
+
+use crate::database::PgPool;
+use crate::migrations::run_migrations;
+
+/// Postgres image tag used for test containers, matching docker-compose
+const POSTGRES_TAG: &str = "16-alpine";
+
+/// Serializes migration runs when several tests share one external database
+static MIGRATION_LOCK: Mutex<()> = Mutex::new(());
+
+/// Database (and optionally Redis) instance for a single integration test
+///
+/// Containers are stopped and removed when the environment is dropped.
+pub struct TestEnvironment {
+    pub pool: PgPool,
+    pub database_url: String,
+    pub redis_url: Option<String>,
+    _postgres: Option<ContainerAsync<Postgres>>,
+    _redis: Option<ContainerAsync<Redis>>,
+}
+
+impl TestEnvironment {
+    /// Start Postgres and run all migrations
+    pub async fn start() -> anyhow::Result<Self> {
+        let (database_url, postgres) = match env::var("TEST_DATABASE_URL") {
+            Ok(url) => (url, None),
+            Err(_) => {
+                let container = Postgres::default().with_tag(POSTGRES_TAG).start().await?;
+                let host = container.get_host().await?;
+                let port = container.get_host_port_ipv4(5432).await?;
+                (format!("postgres://postgres:postgres@{}:{}/postgres", host, port), Some(container))
+            }
+        };
+
+        let url = database_url.clone();
+        tokio::task::spawn_blocking(move || {
+            let _guard = MIGRATION_LOCK.lock().unwrap_or_else(|e| e.into_inner());
+            run_migrations(&url)
+        }).await??;
+
+        let manager = ConnectionManager::<PgConnection>::new(database_url.clone());
+        let pool = Pool::builder()
+            .max_size(5)
+            .build(manager)
+            .map_err(|e| anyhow!("Failed to create test database pool: {}", e))?;
+
+        Ok(Self {
+            pool,
+            database_url,
+            redis_url: None,
+            _postgres: postgres,
+            _redis: None,
+        })
+    }
+
+    /// Start Postgres and Redis and run all migrations
+    pub async fn start_with_redis() -> anyhow::Result<Self> {
+        let mut environment = Self::start().await?;
+
+        match env::var("TEST_REDIS_URL") {
+            Ok(url) => environment.redis_url = Some(url),
+            Err(_) => {
+                let container = Redis::default().start().await?;
+                let host = container.get_host().await?;
+                let port = container.get_host_port_ipv4(REDIS_PORT).await?;
+                environment.redis_url = Some(format!("redis://{}:{}", host, port));
+                environment._redis = Some(container);
+            }
+        }
+
+        Ok(environment)
+    }
+
+    /// Redis URL of the environment; panics if it was started without Redis
+    pub fn redis_url(&self) -> &str {
+        self.redis_url.as_deref().expect("test environment was started without Redis")
+    }
+}
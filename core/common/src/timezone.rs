@@ -0,0 +1,50 @@
+//! Time zones of customers and resellers
+//!
+//! Timestamps are stored as `timestamptz` and serialized as RFC 3339 in UTC. A customer's time zone only decides where its
+//! local boundaries fall: the hours of its submission windows and the months of its
+//! invoices and sub-account budgets. Customers without one follow their reseller's,
+//! and UTC applies when neither is set.
+
+use chrono::{DateTime, Duration, LocalResult, NaiveDateTime, TimeZone, Timelike, Utc};
+pub use chrono_tz::Tz;
+
+/// Parse an IANA time zone name such as `Europe/Helsinki` or `UTC`
+pub fn parse(name: &str) -> Option<Tz> {
+    name.trim().parse().ok()
+}
+
+/// Time zone of a customer given its own setting and its reseller's; names that no
+/// longer parse are skipped
+pub fn effective(customer: Option<&str>, reseller: Option<&str>) -> Tz {
+    customer.and_then(parse)
+        .or_else(|| reseller.and_then(parse))
+        .unwrap_or(Tz::UTC)
+}
+
+/// Wall-clock time in the zone at a UTC instant
+pub fn to_local(utc: DateTime<Utc>, tz: Tz) -> NaiveDateTime {
+    utc.with_timezone(&tz).naive_local()
+}
+
+/// UTC instant of a wall-clock time in the zone
+///
+/// A time repeated when clocks go back is taken at its first occurrence; a time
+/// skipped when clocks go forward is taken as the moment they jump, so later local
+/// times never map to earlier instants.
+pub fn to_utc(local: NaiveDateTime, tz: Tz) -> DateTime<Utc> {
+    match tz.from_local_datetime(&local) {
+        LocalResult::Single(at) => at.with_timezone(&Utc),
+        LocalResult::Ambiguous(earliest, _) => earliest.with_timezone(&Utc),
+        LocalResult::None => {
+            // Transitions fall on whole minutes: the first one that exists ends the gap
+            let mut next = local.with_second(0).and_then(|at| at.with_nanosecond(0)).unwrap_or(local);
+            for _ in 0..24 * 60 {
+                next += Duration::minutes(1);
+                if let Some(at) = tz.from_local_datetime(&next).earliest() {
+                    return at.with_timezone(&Utc);
+                }
+            }
+            local.and_utc()
+        }
+    }
+}
@@ -0,0 +1,61 @@
+use innosystem_common::api::{ApiErrorBody, ApiListMeta, Paginated};
+use proptest::prelude::*;
+use serde_json::{json, Value};
+
+proptest! {
+    /// Error bodies keep their code and details at the top level and read back unchanged
+    #[test]
+    fn error_bodies_round_trip_with_their_details(
+        code in "[a-z_]{1,20}",
+        retry_after_secs in any::<u64>(),
+        reason in proptest::option::of("[a-zA-Z ]{0,30}"),
+    ) {
+        let mut body = ApiErrorBody::new(code.clone()).with("retry_after_secs", retry_after_secs);
+        if let Some(reason) = &reason {
+            body = body.with("reason", reason);
+        }
+
+        let value = serde_json::to_value(&body).unwrap();
+        prop_assert_eq!(&value["error"], &json!(code));
+        prop_assert_eq!(&value["retry_after_secs"], &json!(retry_after_secs));
+        prop_assert_eq!(value.get("reason").and_then(Value::as_str), reason.as_deref());
+        prop_assert!(value.get("details").is_none());
+
+        let parsed: ApiErrorBody = serde_json::from_value(value).unwrap();
+        prop_assert_eq!(parsed, body);
+    }
+
+    /// Pages read back unchanged, with only the meta fields of their kind of paging
+    #[test]
+    fn pages_round_trip_with_the_meta_of_their_paging(
+        items in prop::collection::vec(any::<i32>(), 0..20),
+        total in 0..1_000i64,
+        limit in 1..100i64,
+        offset in 0..1_000i64,
+        cursor in proptest::option::of("[0-9a-f]{8,32}"),
+        keyset in any::<bool>(),
+    ) {
+        let meta = if keyset {
+            ApiListMeta::keyset(limit, cursor.clone())
+        } else {
+            ApiListMeta::offset(total, limit, offset)
+        };
+        let page = Paginated::new(items.clone(), meta);
+
+        let value = serde_json::to_value(&page).unwrap();
+        prop_assert_eq!(value["data"].as_array().map(Vec::len), Some(items.len()));
+        let meta = value["meta"].as_object().unwrap();
+        if keyset {
+            prop_assert!(!meta.contains_key("total") && !meta.contains_key("offset"));
+            prop_assert_eq!(meta.contains_key("next_cursor"), cursor.is_some());
+            prop_assert_eq!(page.meta.has_more(), cursor.is_some());
+        } else {
+            prop_assert!(!meta.contains_key("next_cursor"));
+            prop_assert_eq!(page.meta.has_more(), offset + limit < total);
+        }
+
+        let parsed: Paginated<i32> = serde_json::from_value(value).unwrap();
+        prop_assert_eq!(&parsed, &page);
+        prop_assert_eq!(parsed.map(i64::from).data, items.into_iter().map(i64::from).collect::<Vec<_>>());
+    }
+}
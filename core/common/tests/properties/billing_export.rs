@@ -0,0 +1,87 @@
+use chrono::{DateTime, Duration, NaiveDate, Utc};
+use innosystem_common::models::billing_export::{self, BillingReconciliation, UnexportedRecord};
+use proptest::prelude::*;
+use uuid::Uuid;
+
+/// A reconciliation of a schema with up to five unexported records
+fn reconciliation() -> impl Strategy<Value = BillingReconciliation> {
+    (
+        0..1000i64,
+        0..1000i64,
+        0..1000i64,
+        prop::collection::vec((0..10_000i64, 1..5000i32), 0..5),
+    )
+        .prop_map(|(exported, pending, failed, unexported)| {
+            let start = NaiveDate::from_ymd_opt(2025, 1, 1).unwrap().and_hms_opt(0, 0, 0).unwrap().and_utc();
+            let mut unexported: Vec<UnexportedRecord> = unexported.into_iter()
+                .map(|(minutes, amount_cents)| UnexportedRecord {
+                    job_id: Uuid::new_v4(),
+                    customer_id: Uuid::new_v4(),
+                    amount_cents,
+                    completed_at: start + Duration::minutes(minutes),
+                    status: "pending".to_string(),
+                    attempts: 1,
+                    last_error: None,
+                })
+                .collect();
+            unexported.sort_by_key(|record| record.completed_at);
+            BillingReconciliation {
+                finalized_jobs: exported + pending + failed,
+                exported,
+                pending,
+                failed,
+                unexported_cents: unexported.iter().map(|record| record.amount_cents as i64).sum(),
+                unexported,
+                ..BillingReconciliation::default()
+            }
+        })
+}
+
+fn completed_at(record: &UnexportedRecord) -> DateTime<Utc> {
+    record.completed_at
+}
+
+proptest! {
+    /// Retries back off without ever waiting longer than the cap, and stop once the
+    /// attempts are used up
+    #[test]
+    fn retry_delays_grow_up_to_the_cap(attempts in 1..40i32, max_attempts in 1..40i32) {
+        match billing_export::retry_delay(attempts, max_attempts) {
+            None => prop_assert!(attempts >= max_attempts),
+            Some(delay) => {
+                prop_assert!(attempts < max_attempts);
+                prop_assert!(delay >= Duration::minutes(1) && delay <= Duration::hours(6));
+                if let Some(next) = billing_export::retry_delay(attempts + 1, max_attempts) {
+                    prop_assert!(next >= delay);
+                }
+            }
+        }
+    }
+
+    /// Every attempt to export a job's charge carries the same key, and no two jobs
+    /// share one
+    #[test]
+    fn idempotency_keys_identify_jobs(a in any::<u128>(), b in any::<u128>()) {
+        let (a, b) = (Uuid::from_u128(a), Uuid::from_u128(b));
+        prop_assert_eq!(billing_export::idempotency_key(a), billing_export::idempotency_key(a));
+        prop_assert_eq!(billing_export::idempotency_key(a) == billing_export::idempotency_key(b), a == b);
+    }
+
+    /// Merging the reconciliations of several schemas adds their counts and keeps the
+    /// oldest unexported records
+    #[test]
+    fn merged_reconciliations_add_up(parts in prop::collection::vec(reconciliation(), 1..5), limit in 0..12usize) {
+        let mut merged = BillingReconciliation::default();
+        for part in parts.clone() {
+            merged.merge(part, limit);
+        }
+        prop_assert_eq!(merged.finalized_jobs, parts.iter().map(|part| part.finalized_jobs).sum::<i64>());
+        prop_assert_eq!(merged.pending, parts.iter().map(|part| part.pending).sum::<i64>());
+        prop_assert_eq!(merged.unexported_cents, parts.iter().map(|part| part.unexported_cents).sum::<i64>());
+
+        let mut all: Vec<DateTime<Utc>> = parts.iter().flat_map(|part| part.unexported.iter().map(completed_at)).collect();
+        all.sort();
+        all.truncate(limit);
+        prop_assert_eq!(merged.unexported.iter().map(completed_at).collect::<Vec<_>>(), all);
+    }
+}
@@ -0,0 +1,47 @@
+use chrono::Datelike;
+use innosystem_common::models::billing_period::{ledger_csv, ledger_sha256, month_bounds};
+use innosystem_common::models::wallet::WalletTransaction;
+use proptest::prelude::*;
+use uuid::Uuid;
+
+proptest! {
+    /// Consecutive billing months leave no gap and do not overlap
+    #[test]
+    fn months_tile_the_calendar(year in 1900..2200i32, month in 1..=12u32) {
+        let (start, end) = month_bounds(year, month).unwrap();
+        prop_assert_eq!((start.year(), start.month(), start.day()), (year, month, 1));
+        prop_assert!(end > start);
+
+        let (next_year, next_month) = if month == 12 { (year + 1, 1) } else { (year, month + 1) };
+        let (next_start, _) = month_bounds(next_year, next_month).unwrap();
+        prop_assert_eq!(end, next_start);
+    }
+
+    #[test]
+    fn invalid_months_are_rejected(year in 1900..2200i32, month in prop_oneof![Just(0u32), 13..100u32]) {
+        prop_assert!(month_bounds(year, month).is_err());
+    }
+
+    /// Changing any amount or description changes the ledger checksum
+    #[test]
+    fn ledger_checksums_detect_changed_entries(
+        amounts in prop::collection::vec(-10_000..10_000i32, 1..20),
+        description in "[a-z ,\"\n]{0,20}",
+        changed in any::<prop::sample::Index>(),
+    ) {
+        let wallet_id = Uuid::new_v4();
+        let transactions: Vec<WalletTransaction> = amounts.iter()
+            .map(|amount| WalletTransaction::new(wallet_id, *amount, "DEPOSIT".to_string(), Uuid::new_v4(), None, Some(description.clone()), None))
+            .collect();
+        let checksum = ledger_sha256(&ledger_csv(&transactions));
+        let changed = changed.index(transactions.len());
+
+        let mut amended = transactions.clone();
+        amended[changed].amount_cents += 1;
+        prop_assert_ne!(ledger_sha256(&ledger_csv(&amended)), checksum.clone());
+
+        let mut redescribed = transactions.clone();
+        redescribed[changed].description = Some(format!("{}!", description));
+        prop_assert_ne!(ledger_sha256(&ledger_csv(&redescribed)), checksum);
+    }
+}
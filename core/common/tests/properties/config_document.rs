@@ -0,0 +1,172 @@
+use std::collections::{BTreeMap, BTreeSet};
+
+use innosystem_common::models::config_document::{
+    ChangeAction, ConfigDocument, DeployedRunner, FeatureFlagSpec, JobTypeSpec, ResourceKind, RunnerPoolSpec,
+};
+use innosystem_common::models::feature_flag::FeatureFlag;
+use innosystem_common::models::job_type::{JobType, ProcessorType};
+use innosystem_common::models::runner::{Runner, RunnerStatus};
+use proptest::prelude::*;
+use uuid::Uuid;
+
+fn job_type_spec() -> impl Strategy<Value = JobTypeSpec> {
+    (
+        prop::sample::select(vec!["sync", "async", "external_api", "batch", "webhook"]),
+        0i32..1000,
+        any::<bool>(),
+        prop::option::of("[a-z]{1,8}"),
+    )
+        .prop_map(|(processor_type, standard_cost_cents, enabled, category)| JobTypeSpec {
+            description: None,
+            processing_logic_id: format!("{}-logic", processor_type),
+            processor_type: processor_type.to_string(),
+            standard_cost_cents,
+            enabled,
+            category,
+            icon: None,
+            documentation_url: None,
+            sample_input: None,
+            sample_output: None,
+            visibility: Default::default(),
+            redaction_rules: Vec::new(),
+            unredacted_retention_hours: None,
+            default_priority: None,
+            webhook_template: None,
+            response_policy: None,
+        })
+}
+
+/// A document whose runner pools only use the enabled job types it declares
+fn document() -> impl Strategy<Value = ConfigDocument> {
+    (
+        prop::collection::btree_map("jt[a-z]{1,6}", job_type_spec(), 0..5),
+        prop::collection::btree_map("pool[a-z]{1,6}", (any::<bool>(), any::<u8>()), 0..4),
+        prop::collection::btree_map("flag[a-z]{1,6}", (any::<bool>(), 0usize..3), 0..4),
+    )
+        .prop_map(|(job_types, pools, flags)| {
+            let enabled: Vec<&String> = job_types.iter().filter(|(_, spec)| spec.enabled).map(|(name, _)| name).collect();
+            let runner_pools = pools.into_iter()
+                .map(|(name, (active, mask))| {
+                    let job_types = enabled.iter().enumerate()
+                        .filter(|(i, _)| mask & (1 << (i % 8)) != 0)
+                        .map(|(_, name)| name.to_string())
+                        .collect();
+                    (name, RunnerPoolSpec { job_types, active })
+                })
+                .collect();
+            let feature_flags = flags.into_iter()
+                .map(|(name, (enabled, resellers))| {
+                    let reseller_ids = (0..resellers).map(|_| Uuid::new_v4()).collect();
+                    (name, FeatureFlagSpec { enabled, reseller_ids, description: None })
+                })
+                .collect();
+            ConfigDocument {
+                job_types: Some(job_types),
+                runner_pools: Some(runner_pools),
+                feature_flags: Some(feature_flags),
+            }
+        })
+}
+
+/// The resources the platform has once the document is applied
+fn applied(document: &ConfigDocument) -> (Vec<JobType>, Vec<DeployedRunner>, Vec<FeatureFlag>) {
+    let job_types: Vec<JobType> = document.job_types.iter().flatten()
+        .map(|(name, spec)| {
+            let mut job_type = JobType::new(name.clone(), String::new(), ProcessorType::Sync, 0);
+            spec.apply_to(&mut job_type);
+            job_type
+        })
+        .collect();
+    let runners = document.runner_pools.iter().flatten()
+        .map(|(name, spec)| {
+            let mut runner = Runner::new(name.clone(), None, Vec::new());
+            runner.set_status(if spec.active { RunnerStatus::Active } else { RunnerStatus::Inactive });
+            let job_type_ids = job_types.iter()
+                .filter(|job_type| spec.job_types.contains(&job_type.name))
+                .map(|job_type| job_type.id)
+                .collect();
+            DeployedRunner { runner, job_type_ids }
+        })
+        .collect();
+    let feature_flags = document.feature_flags.iter().flatten()
+        .map(|(name, spec)| FeatureFlag {
+            id: Uuid::new_v4(),
+            name: name.clone(),
+            enabled: spec.enabled,
+            reseller_ids: spec.reseller_ids.iter().copied().collect(),
+            description: spec.description.clone(),
+            updated_by: "admin".to_string(),
+            created_at: None,
+            updated_at: None,
+        })
+        .collect();
+    (job_types, runners, feature_flags)
+}
+
+proptest! {
+    #[test]
+    fn every_declared_resource_is_created_on_an_empty_platform(document in document()) {
+        let plan = document.plan(&[], &[], &[], true).unwrap();
+
+        let declared = document.job_types.as_ref().unwrap().len()
+            + document.runner_pools.as_ref().unwrap().len()
+            + document.feature_flags.as_ref().unwrap().len();
+        prop_assert_eq!(plan.len(), declared);
+        prop_assert!(plan.iter().all(|change| change.action == ChangeAction::Create));
+        // Job types come first, as runner pools refer to them
+        let kinds: Vec<ResourceKind> = plan.iter().map(|change| change.kind).collect();
+        let mut sorted = kinds.clone();
+        sorted.sort();
+        prop_assert_eq!(kinds, sorted);
+    }
+
+    #[test]
+    fn applying_a_document_converges(document in document()) {
+        let (job_types, runners, feature_flags) = applied(&document);
+        prop_assert_eq!(document.plan(&job_types, &runners, &feature_flags, true).unwrap(), vec![]);
+    }
+
+    #[test]
+    fn resources_missing_from_a_section_are_only_removed_when_pruning(document in document(), name in "zz[a-z]{1,6}") {
+        let (mut job_types, mut runners, mut feature_flags) = applied(&document);
+        job_types.push(JobType::new(name.clone(), String::new(), ProcessorType::Sync, 0));
+        let mut runner = Runner::new(name.clone(), None, Vec::new());
+        runner.set_status(RunnerStatus::Active);
+        runners.push(DeployedRunner { runner, job_type_ids: Vec::new() });
+        feature_flags.push(FeatureFlag {
+            id: Uuid::new_v4(),
+            name: name.clone(),
+            enabled: true,
+            reseller_ids: Vec::new(),
+            description: None,
+            updated_by: "admin".to_string(),
+            created_at: None,
+            updated_at: None,
+        });
+
+        prop_assert_eq!(document.plan(&job_types, &runners, &feature_flags, false).unwrap(), vec![]);
+
+        let pruned: BTreeMap<ResourceKind, ChangeAction> = document.plan(&job_types, &runners, &feature_flags, true).unwrap()
+            .into_iter()
+            .inspect(|change| assert_eq!(change.name, name))
+            .map(|change| (change.kind, change.action))
+            .collect();
+        prop_assert_eq!(pruned, BTreeMap::from([
+            (ResourceKind::JobType, ChangeAction::Disable),
+            (ResourceKind::RunnerPool, ChangeAction::Disable),
+            (ResourceKind::FeatureFlag, ChangeAction::Delete),
+        ]));
+    }
+
+    #[test]
+    fn pools_cannot_use_job_types_the_document_disables(mut document in document(), spec in job_type_spec()) {
+        let name = "disabled".to_string();
+        document.job_types.as_mut().unwrap().insert(name.clone(), JobTypeSpec { enabled: false, ..spec });
+        document.runner_pools.as_mut().unwrap().insert("pool".to_string(), RunnerPoolSpec {
+            job_types: BTreeSet::from([name]),
+            active: true,
+        });
+
+        prop_assert!(document.plan(&[], &[], &[], true).is_err());
+    }
+}
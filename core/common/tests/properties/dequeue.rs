@@ -0,0 +1,104 @@
+use std::time::Duration;
+
+use innosystem_common::models::job::PriorityLevel;
+use innosystem_common::queue::{DeadlineAware, DequeueConfig, DequeuePolicy, DequeueStrategy, WeightedFairShare};
+use proptest::prelude::*;
+
+const PRIORITIES: [PriorityLevel; 4] = [
+    PriorityLevel::Critical,
+    PriorityLevel::High,
+    PriorityLevel::Medium,
+    PriorityLevel::Low,
+];
+
+/// Position of a priority in `PRIORITIES`
+fn index(priority: &PriorityLevel) -> usize {
+    PRIORITIES.iter().position(|p| p == priority).unwrap()
+}
+
+/// Dequeue `total` jobs with every queue backlogged and count the jobs taken per priority
+fn serve_backlogged(strategy: &WeightedFairShare, total: usize) -> [usize; 4] {
+    let mut counts = [0usize; 4];
+    for _ in 0..total {
+        let priority = strategy.next_order().remove(0);
+        counts[index(&priority)] += 1;
+        strategy.record_dequeue(&priority);
+    }
+    counts
+}
+
+proptest! {
+    #[test]
+    fn backlogged_queues_are_served_in_proportion_to_their_weights(weights in prop::array::uniform4(0u32..10)) {
+        prop_assume!(weights.iter().any(|weight| *weight > 0));
+        let window = 200;
+        let strategy = WeightedFairShare::new(weights, window);
+        let counts = serve_backlogged(&strategy, window);
+
+        let total_weight: u32 = weights.iter().sum();
+        for i in 0..PRIORITIES.len() {
+            let expected = window as f64 * weights[i] as f64 / total_weight as f64;
+            prop_assert!((counts[i] as f64 - expected).abs() <= 1.0, "priority {} served {} times, expected {}", i, counts[i], expected);
+        }
+    }
+
+    #[test]
+    fn every_policy_lists_each_queue_once(
+        weights in prop::array::uniform4(0u32..10),
+        waits in prop::array::uniform4(prop::option::of(0u64..2000)),
+    ) {
+        let orders = [
+            WeightedFairShare::new(weights, 10).next_order(),
+            DeadlineAware::new([10, 60, 300, 900].map(Duration::from_secs)).order_for_waits(&waits.map(|wait| wait.map(Duration::from_secs))),
+        ];
+        for order in orders {
+            let mut indices: Vec<usize> = order.iter().map(index).collect();
+            indices.sort();
+            prop_assert_eq!(indices, vec![0, 1, 2, 3]);
+        }
+    }
+
+    #[test]
+    fn overdue_queues_are_served_first_and_the_rest_by_priority(waits in prop::array::uniform4(prop::option::of(0u64..2000))) {
+        let max_wait = [10, 60, 300, 900];
+        let strategy = DeadlineAware::new(max_wait.map(Duration::from_secs));
+        let order: Vec<usize> = strategy
+            .order_for_waits(&waits.map(|wait| wait.map(Duration::from_secs)))
+            .iter()
+            .map(index)
+            .collect();
+
+        let overdue = |i: usize| waits[i].is_some_and(|wait| wait > max_wait[i]);
+        let ratio = |i: usize| waits[i].unwrap() as f64 / max_wait[i] as f64;
+        let first_on_time = order.iter().position(|i| !overdue(*i)).unwrap_or(order.len());
+        prop_assert!(order[first_on_time..].iter().all(|i| !overdue(*i)));
+        for pair in order[..first_on_time].windows(2) {
+            prop_assert!(ratio(pair[0]) >= ratio(pair[1]));
+        }
+        for pair in order[first_on_time..].windows(2) {
+            prop_assert!(pair[0] < pair[1]);
+        }
+    }
+}
+
+#[test]
+fn an_idle_queue_takes_over_unused_shares_until_work_arrives() {
+    let strategy = WeightedFairShare::new([8, 4, 2, 1], 100);
+
+    // Only low priority work for a while: it is served even though its weight is smallest
+    for _ in 0..100 {
+        strategy.record_dequeue(&PriorityLevel::Low);
+    }
+    // Once other queues have work, low priority goes to the back
+    assert_eq!(strategy.next_order().last(), Some(&PriorityLevel::Low));
+    assert_eq!(strategy.next_order().first(), Some(&PriorityLevel::Critical));
+}
+
+#[test]
+fn dequeue_policies_parse_from_their_configuration_values() {
+    for policy in [DequeuePolicy::StrictPriority, DequeuePolicy::WeightedFairShare, DequeuePolicy::DeadlineAware] {
+        assert_eq!(DequeuePolicy::parse(policy.as_str()), Some(policy));
+    }
+    assert_eq!(DequeuePolicy::parse("round_robin"), None);
+    assert_eq!(DequeueConfig::default().policy, DequeuePolicy::StrictPriority);
+}
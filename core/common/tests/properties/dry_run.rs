@@ -0,0 +1,129 @@
+use chrono::Utc;
+use innosystem_common::models::dry_run;
+use innosystem_common::models::job::MAX_SUB_TASKS;
+use innosystem_common::models::job_error::codes;
+use innosystem_common::models::job_type::{JobType, ProcessorType};
+use innosystem_common::models::webhook_template::{WebhookMethod, WebhookTemplate, WebhookTemplateError};
+use proptest::prelude::*;
+use serde_json::{json, Value};
+use uuid::Uuid;
+
+/// JSON values a few levels deep
+fn value() -> impl Strategy<Value = Value> {
+    let leaf = prop_oneof![
+        Just(Value::Null),
+        any::<bool>().prop_map(Value::from),
+        any::<i64>().prop_map(Value::from),
+        ".{0,20}".prop_map(Value::from),
+    ];
+    leaf.prop_recursive(3, 24, 4, |inner| prop_oneof![
+        prop::collection::vec(inner.clone(), 0..4).prop_map(Value::from),
+        prop::collection::btree_map("[a-z_]{1,10}", inner, 0..4)
+            .prop_map(|fields| Value::Object(fields.into_iter().collect())),
+    ])
+}
+
+/// Job type of the given processor type, without a webhook template
+fn job_type(processor_type: ProcessorType) -> JobType {
+    JobType::new("dry-run".to_string(), "dry-run-logic".to_string(), processor_type, 100)
+}
+
+proptest! {
+    /// Sync jobs echo their input, whatever it is
+    #[test]
+    fn sync_jobs_echo_their_input(input in value()) {
+        prop_assert_eq!(dry_run::simulate_output(&job_type(ProcessorType::Sync), &input, Uuid::new_v4(), Utc::now()), Ok(input));
+    }
+
+    /// Async jobs count the words of their text, and describe input without text
+    /// instead of failing
+    #[test]
+    fn async_jobs_transform_their_text(words in prop::collection::vec("[a-z]{1,8}", 0..10), other in value()) {
+        let text = words.join(" ");
+        let output = dry_run::simulate_output(&job_type(ProcessorType::Async), &json!({ "text": text }), Uuid::new_v4(), Utc::now()).unwrap();
+        prop_assert_eq!(&output["transformed_text"], &json!(text.to_uppercase()));
+        prop_assert_eq!(&output["word_count"], &json!(words.len()));
+
+        let output = dry_run::simulate_output(&job_type(ProcessorType::Async), &json!({ "other": other }), Uuid::new_v4(), Utc::now()).unwrap();
+        prop_assert!(output["error"].is_string());
+    }
+
+    /// Webhook jobs need a URL to post to, and simulating them only reports the payload:
+    /// their input without the URL unless the job type has a template
+    #[test]
+    fn webhook_jobs_are_simulated_without_a_response(url in "https://[a-z]{1,10}\\.example/[a-z]{0,10}", other in value()) {
+        let output = dry_run::simulate_output(
+            &job_type(ProcessorType::Webhook), &json!({ "webhook_url": url, "other": other }), Uuid::new_v4(), Utc::now(),
+        ).unwrap();
+        prop_assert_eq!(&output["webhook_url"], &json!(url));
+        prop_assert_eq!(&output["status"], "simulated");
+        prop_assert_eq!(&output["method"], "POST");
+        prop_assert_eq!(&output["payload"], &json!({ "other": other }));
+
+        let error = dry_run::simulate_output(&job_type(ProcessorType::Webhook), &json!({ "other": other }), Uuid::new_v4(), Utc::now()).unwrap_err();
+        prop_assert_eq!(error.code, codes::MISSING_FIELD);
+        prop_assert!(!error.retryable);
+    }
+
+    /// Batch jobs take between one and `MAX_SUB_TASKS` tasks
+    #[test]
+    fn batch_jobs_take_a_bounded_number_of_tasks(count in 0..MAX_SUB_TASKS + 5) {
+        let input = json!({ "tasks": vec![json!({}); count] });
+        match dry_run::simulate_output(&job_type(ProcessorType::Batch), &input, Uuid::new_v4(), Utc::now()) {
+            Ok(output) => {
+                prop_assert!((1..=MAX_SUB_TASKS).contains(&count));
+                prop_assert_eq!(&output["sub_task_count"], &json!(count));
+            }
+            Err(error) => {
+                prop_assert!(count == 0 || count > MAX_SUB_TASKS);
+                prop_assert_eq!(error.code, codes::INVALID_INPUT);
+            }
+        }
+    }
+
+    /// A placeholder alone in a string keeps the type of the value it references, and
+    /// placeholders within text are interpolated
+    #[test]
+    fn webhook_templates_substitute_the_job_input(value in value(), name in "[a-z]{1,10}") {
+        let template: WebhookTemplate = serde_json::from_value(json!({
+            "method": "PUT",
+            "headers": { "X-Job": "job-{{ job.id }}" },
+            "body": { "value": "{{ input.data.value }}", "greeting": "hello {{input.name}}", "missing": "{{ input.absent }}" },
+        })).unwrap();
+        prop_assert_eq!(template.validate(), Ok(()));
+
+        let job_id = Uuid::new_v4();
+        let input = json!({ "webhook_url": "https://hooks.example/", "data": { "value": value }, "name": name });
+        let request = template.render(&input, job_id, Utc::now()).unwrap();
+        prop_assert_eq!(request.method, WebhookMethod::Put);
+        let expected_header = format!("job-{}", job_id);
+        prop_assert_eq!(&request.headers["X-Job"], &expected_header);
+        let body = request.body.unwrap();
+        prop_assert_eq!(&body["value"], &value);
+        prop_assert_eq!(&body["greeting"], &json!(format!("hello {}", name)));
+        prop_assert_eq!(&body["missing"], &Value::Null);
+    }
+
+    /// Templates referencing anything but the input, the job ID and the time are refused,
+    /// as are headers the processor sets and bodies of GET requests
+    #[test]
+    fn webhook_templates_with_unknown_placeholders_are_refused(reference in "[a-z]{1,10}") {
+        prop_assume!(reference != "input" && reference != "datetime");
+        let template: WebhookTemplate = serde_json::from_value(json!({ "body": { "x": format!("{{{{ {} }}}}", reference) } })).unwrap();
+        prop_assert_eq!(template.validate(), Err(WebhookTemplateError::UnknownPlaceholder(reference)));
+
+        let template: WebhookTemplate = serde_json::from_value(json!({ "headers": { "Content-Type": "text/plain" } })).unwrap();
+        prop_assert!(matches!(template.validate(), Err(WebhookTemplateError::ReservedHeader(_))));
+        let template: WebhookTemplate = serde_json::from_value(json!({ "method": "GET", "body": {} })).unwrap();
+        prop_assert_eq!(template.validate(), Err(WebhookTemplateError::BodyNotAllowed("GET")));
+    }
+
+    /// Input interpolated into a header must not be able to inject headers
+    #[test]
+    fn webhook_headers_refuse_input_with_line_breaks(prefix in "[a-z]{0,5}", suffix in "[a-z]{0,5}") {
+        let template: WebhookTemplate = serde_json::from_value(json!({ "headers": { "X-Name": "{{ input.name }}" } })).unwrap();
+        let input = json!({ "name": format!("{}\r\nX-Injected: yes{}", prefix, suffix) });
+        let error = template.render(&input, Uuid::new_v4(), Utc::now()).unwrap_err();
+        prop_assert_eq!(error.code, codes::INVALID_INPUT);
+    }
+}
@@ -0,0 +1,94 @@
+use std::collections::{BTreeMap, HashSet};
+
+use innosystem_common::models::fixture::{CustomerFixture, FixtureError, FixtureSpec, RunnerFixture, MAX_FIXTURE_JOBS};
+use proptest::prelude::*;
+use uuid::Uuid;
+
+const STATUSES: [&str; 7] = ["pending", "scheduled", "running", "succeeded", "failed", "cancelled", "expired"];
+
+fn customer() -> impl Strategy<Value = CustomerFixture> {
+    ("[A-Z][a-z]{2,10}", 0i32..100_000, prop::collection::btree_map(prop::sample::select(&STATUSES[..]), 0u32..6, 0..4))
+        .prop_map(|(name, balance_cents, jobs)| CustomerFixture {
+            name,
+            balance_cents,
+            jobs: jobs.into_iter().map(|(status, count)| (status.to_string(), count)).collect(),
+        })
+}
+
+fn spec() -> impl Strategy<Value = FixtureSpec> {
+    (
+        "[a-z][a-z0-9-]{0,20}",
+        prop::collection::vec(customer(), 1..8),
+        prop::collection::vec("[a-z]{3,8}", 0..4),
+    )
+        .prop_map(|(name, customers, runners)| FixtureSpec {
+            name,
+            reseller: Default::default(),
+            customers,
+            runners: runners.into_iter().map(|name| RunnerFixture { name, status: "active".to_string() }).collect(),
+            job_cost_cents: 100,
+        })
+}
+
+proptest! {
+    /// A valid spec becomes exactly the records it describes, all tied to the bundle's
+    /// reseller and job type
+    #[test]
+    fn records_follow_the_spec(spec in spec()) {
+        prop_assert_eq!(spec.validate(), Ok(()));
+        let records = spec.records(Uuid::new_v4());
+
+        prop_assert_eq!(records.customers.len(), spec.customers.len());
+        prop_assert_eq!(records.wallets.len(), spec.customers.len());
+        prop_assert_eq!(records.runners.len(), spec.runners.len());
+        prop_assert!(records.customers.iter().all(|customer| customer.reseller_id == Some(records.reseller.id)));
+        prop_assert!(records.jobs.iter().all(|job| job.job_type_id == records.job_type.id));
+        prop_assert!(records.runners.iter().all(|runner| runner.compatible_job_types == vec![records.job_type.name.clone()]));
+
+        for (spec, customer) in spec.customers.iter().zip(&records.customers) {
+            let wallet = records.wallets.iter().find(|wallet| wallet.customer_id == customer.id).unwrap();
+            prop_assert_eq!(wallet.balance_cents, spec.balance_cents);
+
+            let mut statuses: BTreeMap<String, u32> = BTreeMap::new();
+            for job in records.jobs.iter().filter(|job| job.customer_id == customer.id) {
+                *statuses.entry(job.status.clone()).or_default() += 1;
+            }
+            let expected: BTreeMap<String, u32> = spec.jobs.iter()
+                .filter(|(_, count)| **count > 0)
+                .map(|(status, count)| (status.clone(), *count))
+                .collect();
+            prop_assert_eq!(statuses, expected);
+        }
+
+        let emails: HashSet<&String> = records.customers.iter().map(|customer| &customer.email).collect();
+        prop_assert_eq!(emails.len(), records.customers.len());
+    }
+
+    /// The same spec can be provisioned twice without the records colliding
+    #[test]
+    fn bundles_of_the_same_spec_do_not_collide(spec in spec()) {
+        let first = spec.records(Uuid::new_v4());
+        let second = spec.records(Uuid::new_v4());
+
+        prop_assert_ne!(&first.reseller.email, &second.reseller.email);
+        prop_assert_ne!(&first.job_type.name, &second.job_type.name);
+        prop_assert_ne!(&first.reseller.api_key, &second.reseller.api_key);
+        for (first, second) in first.customers.iter().zip(&second.customers) {
+            prop_assert_ne!(&first.email, &second.email);
+            prop_assert_ne!(&first.api_key, &second.api_key);
+        }
+    }
+
+    /// Specs with an unknown status or too many jobs are refused before anything is created
+    #[test]
+    fn invalid_specs_are_refused(mut spec in spec(), status in "[a-z]{3,10}", extra in 1u32..100) {
+        let mut unknown = spec.clone();
+        unknown.customers[0].jobs.insert(status.clone(), 1);
+        if !STATUSES.contains(&status.as_str()) {
+            prop_assert_eq!(unknown.validate(), Err(FixtureError::UnknownJobStatus(status)));
+        }
+
+        spec.customers[0].jobs.insert("pending".to_string(), MAX_FIXTURE_JOBS + extra);
+        prop_assert!(matches!(spec.validate(), Err(FixtureError::TooManyJobs(_))));
+    }
+}
@@ -0,0 +1,78 @@
+use innosystem_common::i18n::Locale;
+use proptest::prelude::*;
+
+const LOCALES: [Locale; 3] = [Locale::En, Locale::De, Locale::Fr];
+
+/// Codes customer-facing messages are written for
+const CODES: [&str; 14] = [
+    "account_suspended",
+    "auth_locked_out",
+    "wallet_overdrawn",
+    "read_only",
+    "feature_disabled",
+    "queue_saturated",
+    "database_busy",
+    "invalid_request_body",
+    "malformed_request_body",
+    "unsupported_media_type",
+    "job.cancelled.reservation_expired",
+    "job.expired.deadline_passed",
+    "spending.anomaly.spend_spike",
+    "spending.anomaly.failure_rate_jump",
+];
+
+fn locale() -> impl Strategy<Value = Locale> {
+    prop::sample::select(LOCALES.to_vec())
+}
+
+/// An `Accept-Language` entry: a supported or unsupported tag with an optional weight
+fn entry() -> impl Strategy<Value = (String, Option<u8>)> {
+    (
+        prop::sample::select(vec!["en", "en-GB", "de", "de-CH", "fr", "fr-BE", "it", "nl-NL", "*"]),
+        prop::option::of(0u8..=10),
+    )
+        .prop_map(|(tag, weight)| (tag.to_string(), weight))
+}
+
+proptest! {
+    #[test]
+    fn negotiation_picks_the_heaviest_supported_language(entries in prop::collection::vec(entry(), 0..6)) {
+        let header = entries.iter()
+            .map(|(tag, weight)| match weight {
+                Some(weight) => format!("{};q={}", tag, *weight as f32 / 10.0),
+                None => tag.clone(),
+            })
+            .collect::<Vec<_>>()
+            .join(", ");
+
+        let weight = |weight: &Option<u8>| weight.map_or(10, |weight| weight);
+        let expected = entries.iter()
+            .filter_map(|(tag, w)| Locale::parse(tag).map(|locale| (locale, weight(w))))
+            .filter(|(_, weight)| *weight > 0)
+            .fold(None, |best: Option<(Locale, u8)>, (locale, weight)| match best {
+                Some((_, best_weight)) if best_weight >= weight => best,
+                _ => Some((locale, weight)),
+            })
+            .map(|(locale, _)| locale);
+
+        prop_assert_eq!(Locale::negotiate(&header), expected);
+    }
+
+    #[test]
+    fn locales_round_trip_through_their_tags(locale in locale(), region in "[A-Za-z]{2}") {
+        prop_assert_eq!(Locale::parse(locale.as_str()), Some(locale));
+        prop_assert_eq!(Locale::parse(&format!("{}-{}", locale.as_str().to_uppercase(), region)), Some(locale));
+    }
+}
+
+#[test]
+fn every_message_is_translated_into_every_locale() {
+    for code in CODES {
+        let english = Locale::En.message(code).unwrap_or_else(|| panic!("no message for {}", code));
+        for locale in [Locale::De, Locale::Fr] {
+            let translated = locale.message(code).unwrap();
+            assert_ne!(translated, english, "{} is not translated into {}", code, locale.as_str());
+        }
+    }
+    assert_eq!(Locale::Fr.message("no_such_code"), None);
+}
@@ -0,0 +1,74 @@
+use chrono::Utc;
+use innosystem_common::models::dry_run;
+use innosystem_common::models::input_contract::{InputContract, InputFieldType};
+use innosystem_common::models::job_type::{JobType, ProcessorType};
+use proptest::prelude::*;
+use serde_json::{json, Map, Value};
+use uuid::Uuid;
+
+/// Processor types that run their input; external API jobs fail whatever the input
+fn processor_type() -> impl Strategy<Value = ProcessorType> {
+    prop::sample::select(vec![ProcessorType::Sync, ProcessorType::Async, ProcessorType::Webhook, ProcessorType::Batch])
+}
+
+/// Job type of the given processor type, without a webhook template
+fn job_type(processor_type: ProcessorType) -> JobType {
+    JobType::new("contract".to_string(), "contract-logic".to_string(), processor_type, 100)
+}
+
+/// Value of any of the types a field may have, or none of them
+fn field_value() -> impl Strategy<Value = Option<Value>> {
+    prop::option::of(prop_oneof![
+        Just(json!("https://example.com/hook")),
+        Just(json!([{ "n": 1 }])),
+        Just(json!({ "n": 1 })),
+        any::<i64>().prop_map(Value::from),
+        Just(Value::Null),
+    ])
+}
+
+/// Value of the given type a processor accepts
+fn valid_value(field_type: InputFieldType) -> Value {
+    match field_type {
+        InputFieldType::String => json!("hello world"),
+        InputFieldType::Url => json!("https://example.com/hook"),
+        InputFieldType::Array => json!([{ "n": 1 }]),
+        InputFieldType::Object => json!({ "n": 1 }),
+    }
+}
+
+proptest! {
+    /// Input refused by a processor's contract fails a dry run with the same error
+    #[test]
+    fn inputs_refused_by_the_contract_fail_with_the_same_error(
+        processor_type in processor_type(),
+        values in prop::collection::vec(field_value(), 3),
+    ) {
+        let contract = InputContract::of(&processor_type);
+        let mut input = Map::new();
+        for (name, value) in ["text", "webhook_url", "tasks"].into_iter().zip(values) {
+            if let Some(value) = value {
+                input.insert(name.to_string(), value);
+            }
+        }
+        let input = Value::Object(input);
+
+        if let Err(error) = contract.check(&input) {
+            prop_assert_eq!(dry_run::simulate_output(&job_type(processor_type), &input, Uuid::new_v4(), Utc::now()), Err(error));
+        }
+    }
+
+    /// Input with every required field of the contract runs
+    #[test]
+    fn inputs_with_the_required_fields_run(processor_type in processor_type(), with_optional in any::<bool>()) {
+        let contract = InputContract::of(&processor_type);
+        let input: Map<String, Value> = contract.fields.iter()
+            .filter(|field| field.required || with_optional)
+            .map(|field| (field.name.to_string(), valid_value(field.field_type)))
+            .collect();
+        let input = Value::Object(input);
+
+        prop_assert_eq!(contract.check(&input), Ok(()));
+        prop_assert!(dry_run::simulate_output(&job_type(processor_type), &input, Uuid::new_v4(), Utc::now()).is_ok());
+    }
+}
@@ -0,0 +1,346 @@
+use innosystem_common::Error;
+use chrono::{Duration, Utc};
+use innosystem_common::models::job::{CompletionResolution, Job, JobHolder, JobStatus, NewJob, PriorityLevel, PrioritySource};
+use innosystem_common::repositories::JobRepository;
+use innosystem_common::repositories::in_memory::InMemoryJobRepository;
+use innosystem_common::testing::factories::JobFactory;
+use proptest::prelude::*;
+use uuid::Uuid;
+
+use crate::block_on;
+
+const ALL_STATUSES: [JobStatus; 7] = [
+    JobStatus::Pending,
+    JobStatus::Running,
+    JobStatus::Succeeded,
+    JobStatus::Failed,
+    JobStatus::Cancelled,
+    JobStatus::Scheduled,
+    JobStatus::Expired,
+];
+
+fn status() -> impl Strategy<Value = JobStatus> {
+    prop::sample::select(ALL_STATUSES.to_vec())
+}
+
+/// A single status change requested through the repository
+#[derive(Debug, Clone)]
+enum Op {
+    UpdateStatus(JobStatus),
+    Start,
+    Complete(bool),
+    BulkUpdate(JobStatus),
+}
+
+impl Op {
+    fn target(&self) -> JobStatus {
+        match self {
+            Op::UpdateStatus(status) | Op::BulkUpdate(status) => status.clone(),
+            Op::Start => JobStatus::Running,
+            Op::Complete(true) => JobStatus::Succeeded,
+            Op::Complete(false) => JobStatus::Failed,
+        }
+    }
+}
+
+fn op() -> impl Strategy<Value = Op> {
+    prop_oneof![
+        status().prop_map(Op::UpdateStatus),
+        Just(Op::Start),
+        any::<bool>().prop_map(Op::Complete),
+        status().prop_map(Op::BulkUpdate),
+    ]
+}
+
+#[test]
+fn terminal_statuses_have_no_way_out() {
+    for from in ALL_STATUSES.iter().filter(|status| status.is_terminal()) {
+        for to in &ALL_STATUSES {
+            assert!(!from.can_transition_to(to), "{} -> {} should be rejected", from.as_str(), to.as_str());
+        }
+    }
+}
+
+#[test]
+fn every_status_can_reach_a_terminal_one() {
+    for from in ALL_STATUSES.iter().filter(|status| !status.is_terminal()) {
+        assert!(from.can_transition_to(&JobStatus::Cancelled), "{} cannot be cancelled", from.as_str());
+    }
+}
+
+/// Whether the change was applied; a rejection must be reported as invalid input
+fn was_applied<T>(result: innosystem_common::Result<T>) -> Result<bool, TestCaseError> {
+    match result {
+        Ok(_) => Ok(true),
+        Err(Error::InvalidInput(_)) => Ok(false),
+        Err(e) => Err(TestCaseError::fail(format!("unexpected error: {}", e))),
+    }
+}
+
+proptest! {
+    /// Statuses never transition to themselves
+    #[test]
+    fn no_self_transitions(status in status()) {
+        prop_assert!(!status.can_transition_to(&status));
+    }
+
+    /// Any sequence of status changes follows the state machine: allowed transitions
+    /// are applied, others are rejected without touching the job
+    #[test]
+    fn repository_follows_the_state_machine(ops in prop::collection::vec(op(), 1..30)) {
+        block_on(async {
+            let repo = InMemoryJobRepository::new();
+            let job = repo.create(JobFactory::new(Uuid::new_v4(), Uuid::new_v4()).build()).await.unwrap();
+            let mut expected = job.status.clone();
+
+            for op in ops {
+                let target = op.target();
+                let allowed = expected.can_transition_to(&target);
+
+                let applied = match &op {
+                    Op::UpdateStatus(status) => was_applied(repo.update_status(job.id, status.clone()).await)?,
+                    Op::Start => was_applied(repo.set_started(job.id).await)?,
+                    Op::Complete(success) => was_applied(repo.set_completed(job.id, *success, None, None, 100).await)?,
+                    // Bulk updates skip jobs that cannot take the new status
+                    Op::BulkUpdate(status) => repo.bulk_update_status(vec![job.id], status.clone()).await.unwrap() == 1,
+                };
+
+                prop_assert_eq!(applied, allowed, "{:?} from {}", op, expected.as_str());
+                if applied {
+                    expected = target;
+                }
+
+                let current = repo.find_by_id(job.id).await.unwrap();
+                prop_assert_eq!(&current.status, &expected);
+                if current.completed_at.is_some() {
+                    prop_assert!(current.status.is_terminal());
+                }
+            }
+            Ok(())
+        })?;
+    }
+
+    /// Jobs whose deadline passed can neither be leased nor started, and the sweep
+    /// finds exactly the waiting ones among them
+    #[test]
+    fn jobs_past_their_deadline_are_never_started(deadlines in prop::collection::vec((any::<bool>(), 60i64..86_400), 1..10)) {
+        block_on(async {
+            let repo = InMemoryJobRepository::new();
+            let now = Utc::now();
+            let mut overdue = Vec::new();
+
+            for (passed, seconds) in deadlines {
+                let expires_at = if passed { now - Duration::seconds(seconds) } else { now + Duration::seconds(seconds) };
+                let job = repo.create(JobFactory::new(Uuid::new_v4(), Uuid::new_v4()).expires_at(expires_at).build()).await.unwrap();
+
+                let leased = was_applied(repo.lease(job.id, PriorityLevel::Medium, None, now + Duration::seconds(30)).await)?;
+                let started = was_applied(repo.set_started(job.id).await)?;
+                prop_assert_eq!(leased, !passed);
+                prop_assert_eq!(started, !passed);
+                if passed {
+                    overdue.push(job.id);
+                }
+            }
+
+            let mut found: Vec<Uuid> = repo.find_expired(now).await.unwrap().into_iter().map(|job| job.id).collect();
+            found.sort();
+            overdue.sort();
+            prop_assert_eq!(&found, &overdue);
+            for id in overdue {
+                prop_assert!(was_applied(repo.update_status(id, JobStatus::Expired).await)?);
+            }
+            prop_assert!(repo.find_expired(now).await.unwrap().is_empty());
+            Ok(())
+        })?;
+    }
+
+    /// A batch job finishes once its last sub-task does, succeeding only if all of them
+    /// succeeded and costing what they cost together
+    #[test]
+    fn batch_jobs_finish_with_their_last_sub_task(outcomes in prop::collection::vec((0u8..3, 0i32..1000), 1..10)) {
+        block_on(async {
+            let repo = InMemoryJobRepository::new();
+            let parent = repo.create(JobFactory::new(Uuid::new_v4(), Uuid::new_v4()).build()).await.unwrap();
+            let parent = repo.set_started(parent.id).await.unwrap();
+
+            let sub_task_type = Uuid::new_v4();
+            let new_sub_tasks: Vec<NewJob> = outcomes.iter()
+                .enumerate()
+                .map(|(index, (_, cost))| parent.sub_task(index as i32, sub_task_type, serde_json::Value::Null, *cost).into())
+                .collect();
+            let sub_tasks = repo.fan_out(parent.id, new_sub_tasks.clone()).await.unwrap();
+            prop_assert_eq!(sub_tasks.len(), outcomes.len());
+            prop_assert!(sub_tasks.iter().enumerate().all(|(index, sub_task)| sub_task.parent_id == Some(parent.id) && sub_task.sub_task_index == Some(index as i32)));
+            prop_assert!(!was_applied(repo.fan_out(parent.id, new_sub_tasks).await)?);
+            prop_assert!(repo.find_stalled_jobs(0).await.unwrap().iter().all(|job| job.id != parent.id));
+
+            let (mut all_succeeded, mut cost_cents) = (true, 0);
+            for (index, (sub_task, (outcome, cost))) in sub_tasks.iter().zip(&outcomes).enumerate() {
+                prop_assert!(repo.find_fanned_out().await.unwrap().iter().any(|job| job.id == parent.id));
+                prop_assert!(repo.fan_in(parent.id).await.unwrap().is_none());
+                match outcome {
+                    0 => {
+                        repo.set_started(sub_task.id).await.unwrap();
+                        repo.set_completed(sub_task.id, true, None, None, *cost).await.unwrap();
+                        cost_cents += cost;
+                    }
+                    1 => {
+                        repo.set_started(sub_task.id).await.unwrap();
+                        repo.set_completed(sub_task.id, false, None, None, 0).await.unwrap();
+                        all_succeeded = false;
+                    }
+                    _ => {
+                        repo.update_status(sub_task.id, JobStatus::Cancelled).await.unwrap();
+                        all_succeeded = false;
+                    }
+                }
+                if index + 1 < sub_tasks.len() {
+                    prop_assert!(repo.fan_in(parent.id).await.unwrap().is_none());
+                }
+            }
+
+            let finished = repo.fan_in(parent.id).await.unwrap();
+            prop_assert!(finished.is_some());
+            let finished = finished.unwrap();
+            let expected = if all_succeeded { JobStatus::Succeeded } else { JobStatus::Failed };
+            prop_assert_eq!(&finished.status, &expected);
+            prop_assert_eq!(finished.cost_cents, cost_cents);
+            prop_assert!(repo.fan_in(parent.id).await.unwrap().is_none());
+            prop_assert!(repo.find_fanned_out().await.unwrap().is_empty());
+            Ok(())
+        })?;
+    }
+
+    /// A runner's late result is stored only while nobody else runs the job: as-is if it
+    /// still holds it, reclaimed if the job was handed back but not restarted, and
+    /// discarded once another runner took it over
+    #[test]
+    fn late_results_never_overwrite_another_runners_run(handed_back in any::<bool>(), taken_over in any::<bool>(), success in any::<bool>()) {
+        block_on(async {
+            let repo = InMemoryJobRepository::new();
+            let (late_runner, other_runner) = (Uuid::new_v4(), Uuid::new_v4());
+            let job = repo.create(JobFactory::new(Uuid::new_v4(), Uuid::new_v4()).build()).await.unwrap();
+            repo.assign_runner(job.id, late_runner).await.unwrap();
+            repo.set_started(job.id).await.unwrap();
+            prop_assert_eq!(repo.find_running_by_runner(late_runner).await.unwrap().len(), 1);
+
+            let taken_over = handed_back && taken_over;
+            if handed_back {
+                let returned = repo.hand_back(job.id, late_runner).await.unwrap();
+                prop_assert_eq!(returned.map(|job| job.status), Some(JobStatus::Pending));
+                prop_assert!(repo.hand_back(job.id, late_runner).await.unwrap().is_none());
+            }
+            if taken_over {
+                repo.assign_runner(job.id, other_runner).await.unwrap();
+                repo.set_started(job.id).await.unwrap();
+                prop_assert!(repo.hand_back(job.id, late_runner).await.unwrap().is_none());
+            }
+
+            let holder = JobHolder { runner_id: Some(late_runner), attempt_id: None };
+            let (resolution, current) = repo.complete_held(job.id, holder, success, None, None, 100).await.unwrap();
+            let expected = match (handed_back, taken_over) {
+                (_, true) => CompletionResolution::Discarded,
+                (true, false) => CompletionResolution::Reclaimed,
+                (false, false) => CompletionResolution::Held,
+            };
+            prop_assert_eq!(resolution, expected);
+
+            if taken_over {
+                prop_assert_eq!(&current.status, &JobStatus::Running);
+                prop_assert_eq!(current.runner_id, Some(other_runner));
+                prop_assert_eq!(current.cost_cents, job.cost_cents);
+                let holder = JobHolder { runner_id: Some(other_runner), attempt_id: None };
+                let (resolution, _) = repo.complete_held(job.id, holder, true, None, None, 100).await.unwrap();
+                prop_assert_eq!(resolution, CompletionResolution::Held);
+            } else {
+                let finished = if success { JobStatus::Succeeded } else { JobStatus::Failed };
+                prop_assert_eq!(&current.status, &finished);
+                prop_assert_eq!(current.cost_cents, 100);
+            }
+            prop_assert!(repo.find_running_by_runner(late_runner).await.unwrap().is_empty());
+            Ok(())
+        })?;
+    }
+
+    /// The requested priority wins, then the customer's default, then the job type's, then medium
+    #[test]
+    fn priorities_resolve_in_the_documented_order(
+        requested in prop::option::of(0i32..4),
+        customer_default in prop::option::of(0i32..4),
+        job_type_default in prop::option::of(0i32..4),
+    ) {
+        let (priority, source) = PriorityLevel::resolve(requested.map(PriorityLevel::from_i32), customer_default, job_type_default);
+        let expected = match (requested, customer_default, job_type_default) {
+            (Some(level), _, _) => (level, PrioritySource::Request),
+            (None, Some(level), _) => (level, PrioritySource::Customer),
+            (None, None, Some(level)) => (level, PrioritySource::JobType),
+            (None, None, None) => (PriorityLevel::Medium.as_i32(), PrioritySource::Default),
+        };
+        prop_assert_eq!((priority.as_i32(), source), expected);
+    }
+
+    /// Every level has a name that reads back in any case, and no other name is a priority
+    #[test]
+    fn priority_names_round_trip(level in 0i32..4, upper in any::<bool>(), other in "[a-z]{1,10}") {
+        let priority = PriorityLevel::from_i32(level);
+        let name = if upper { priority.as_str().to_uppercase() } else { priority.as_str().to_string() };
+        prop_assert_eq!(PriorityLevel::from_name(&name), Some(priority));
+        prop_assume!(!["low", "medium", "high", "critical"].contains(&other.as_str()));
+        prop_assert_eq!(PriorityLevel::from_name(&other), None);
+    }
+
+    /// Requeued jobs take every override, lose the fields overridden with null, keep the
+    /// rest of the input and refer back to the job they run again
+    #[test]
+    fn requeued_jobs_patch_their_input_and_keep_lineage(
+        status in status(),
+        input in prop::collection::btree_map("[a-e]", 0i64..100, 0..5),
+        overrides in prop::collection::btree_map("[a-h]", prop::option::of(0i64..100), 0..5),
+    ) {
+        let input_data = serde_json::to_value(&input).unwrap();
+        let mut job = Job::new(Uuid::new_v4(), Uuid::new_v4(), input_data, PriorityLevel::High, 500);
+        job.status = status.clone();
+        prop_assert_eq!(job.can_requeue(), matches!(status, JobStatus::Failed | JobStatus::Expired));
+
+        let patch: serde_json::Map<String, serde_json::Value> = overrides.iter()
+            .map(|(key, value)| (key.clone(), serde_json::to_value(value).unwrap()))
+            .collect();
+        let requeued = job.requeue(&patch, 1000);
+        let mut expected = input.clone();
+        for (key, value) in &overrides {
+            match value {
+                Some(value) => { expected.insert(key.clone(), *value); }
+                None => { expected.remove(key); }
+            }
+        }
+        prop_assert_eq!(requeued.input_data, serde_json::to_value(&expected).unwrap());
+        prop_assert_eq!(requeued.requeued_from, Some(job.id));
+        prop_assert_ne!(requeued.id, job.id);
+        prop_assert_eq!(requeued.status, JobStatus::Pending);
+        prop_assert_eq!((requeued.customer_id, requeued.job_type_id), (job.customer_id, job.job_type_id));
+    }
+
+    /// Queue wait runs from entering the queue until the claim, or the start of jobs
+    /// released from the schedule, and processing time from the start until completion
+    #[test]
+    fn queue_wait_and_processing_time_split_the_job_timeline(
+        wait_ms in 0i64..3_600_000,
+        claim_to_start_ms in 0i64..60_000,
+        processing_ms in 0i64..3_600_000,
+        claimed in any::<bool>(),
+    ) {
+        let mut job = Job::new(Uuid::new_v4(), Uuid::new_v4(), serde_json::Value::Null, PriorityLevel::Medium, 100);
+        let queued_at = Utc::now();
+        let started_at = queued_at + Duration::milliseconds(wait_ms + claim_to_start_ms);
+        job.queued_at = Some(queued_at);
+        job.claimed_at = claimed.then(|| queued_at + Duration::milliseconds(wait_ms));
+        prop_assert_eq!(job.queue_wait_ms(), claimed.then_some(wait_ms));
+        prop_assert_eq!(job.processing_ms(), None);
+
+        job.started_at = Some(started_at);
+        job.completed_at = Some(started_at + Duration::milliseconds(processing_ms));
+        let expected_wait = if claimed { wait_ms } else { wait_ms + claim_to_start_ms };
+        prop_assert_eq!(job.queue_wait_ms(), Some(expected_wait));
+        prop_assert_eq!(job.processing_ms(), Some(processing_ms));
+    }
+}
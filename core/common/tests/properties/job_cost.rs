@@ -0,0 +1,93 @@
+use innosystem_common::models::job::PriorityLevel;
+use innosystem_common::models::job_cost::{CostBreakdown, FAILURE_FEE_RATE};
+use proptest::prelude::*;
+
+fn priority() -> impl Strategy<Value = PriorityLevel> {
+    prop_oneof![
+        Just(PriorityLevel::Low),
+        Just(PriorityLevel::Medium),
+        Just(PriorityLevel::High),
+        Just(PriorityLevel::Critical),
+    ]
+}
+
+/// Sum of a breakdown's items, credits counting against the charge
+fn items(cost: &CostBreakdown) -> i64 {
+    cost.base_cents + cost.priority_surcharge_cents + cost.reseller_markup_cents + cost.failure_fee_cents
+        - cost.credits_applied_cents
+}
+
+proptest! {
+    /// Whatever credit is applied, the items of a charge add up to its total and the
+    /// charge never goes negative
+    #[test]
+    fn items_add_up_to_the_total(
+        base in 0i32..1_000_000,
+        priority in priority(),
+        markup_rate in 0i32..20_000,
+        credit in -1_000i64..5_000_000,
+    ) {
+        let mut cost = CostBreakdown::completed(base, &priority, markup_rate);
+        prop_assert_eq!(items(&cost), cost.total_cents);
+
+        let total = cost.total_cents;
+        let applied = cost.apply_credits(credit);
+
+        prop_assert!(applied >= 0 && applied <= total);
+        prop_assert!(applied <= credit.max(0));
+        prop_assert!(cost.total_cents >= 0);
+        prop_assert_eq!(items(&cost), cost.total_cents);
+    }
+
+    /// Only high and critical jobs carry a surcharge, and a job never costs less than
+    /// the same job at a lower priority
+    #[test]
+    fn surcharge_follows_priority(base in 0i32..1_000_000, markup_rate in 0i32..20_000) {
+        let costs: Vec<CostBreakdown> = [PriorityLevel::Low, PriorityLevel::Medium, PriorityLevel::High, PriorityLevel::Critical]
+            .iter()
+            .map(|priority| CostBreakdown::completed(base, priority, markup_rate))
+            .collect();
+
+        prop_assert_eq!(costs[0].priority_surcharge_cents, 0);
+        prop_assert_eq!(costs[1].priority_surcharge_cents, 0);
+        prop_assert_eq!(costs[3].priority_surcharge_cents, base as i64);
+        for pair in costs.windows(2) {
+            prop_assert!(pair[0].total_cents <= pair[1].total_cents);
+        }
+    }
+
+    /// A failed job is charged its failure fee alone, a quarter of its estimate
+    #[test]
+    fn failed_jobs_pay_only_the_failure_fee(estimate in 0i32..1_000_000) {
+        let cost = CostBreakdown::failed(estimate, FAILURE_FEE_RATE);
+
+        prop_assert_eq!(cost.base_cents + cost.priority_surcharge_cents + cost.reseller_markup_cents, 0);
+        prop_assert_eq!(cost.failure_fee_cents, (estimate as i64 + 2) / 4);
+        prop_assert_eq!(cost.total_cents, cost.failure_fee_cents);
+    }
+
+    /// Totalling breakdowns, as invoices do, totals each item
+    #[test]
+    fn summed_breakdowns_keep_their_items(
+        jobs in prop::collection::vec((0i32..100_000, priority(), 0i32..10_000, 0i64..50_000, any::<bool>()), 0..20),
+    ) {
+        let costs: Vec<CostBreakdown> = jobs
+            .into_iter()
+            .map(|(base, priority, markup_rate, credit, succeeded)| {
+                let mut cost = if succeeded {
+                    CostBreakdown::completed(base, &priority, markup_rate)
+                } else {
+                    CostBreakdown::failed(base, FAILURE_FEE_RATE)
+                };
+                cost.apply_credits(credit);
+                cost
+            })
+            .collect();
+
+        let total: CostBreakdown = costs.iter().copied().sum();
+
+        prop_assert_eq!(total.total_cents, costs.iter().map(|cost| cost.total_cents).sum::<i64>());
+        prop_assert_eq!(total.credits_applied_cents, costs.iter().map(|cost| cost.credits_applied_cents).sum::<i64>());
+        prop_assert_eq!(items(&total), total.total_cents);
+    }
+}
@@ -0,0 +1,85 @@
+use innosystem_common::models::job_import::{parse_rows, ColumnMapping, ImportError, ImportFormat};
+use proptest::prelude::*;
+use serde_json::{json, Map, Value};
+
+/// Quote a CSV field the way spreadsheet exports do
+fn quoted(value: &str) -> String {
+    format!("\"{}\"", value.replace('"', "\"\""))
+}
+
+proptest! {
+    /// Every CSV row becomes an object of its columns, whatever the fields contain
+    #[test]
+    fn csv_rows_keep_their_values(
+        rows in prop::collection::vec(prop::collection::vec("[a-z0-9 ,\"\n]{0,12}", 3), 1..20),
+    ) {
+        let mut content = String::from("name,city,note\n");
+        for row in &rows {
+            content.push_str(&row.iter().map(|field| quoted(field)).collect::<Vec<_>>().join(","));
+            content.push_str("\r\n");
+        }
+
+        let parsed = parse_rows(ImportFormat::Csv, &content, None).unwrap();
+        prop_assert_eq!(parsed.len(), rows.len());
+        for (row, parsed) in rows.iter().zip(parsed) {
+            prop_assert_eq!(parsed.unwrap(), json!({ "name": row[0], "city": row[1], "note": row[2] }));
+        }
+    }
+
+    /// A mapping picks the mapped columns only and nests dotted fields
+    #[test]
+    fn mappings_rename_and_nest_columns(values in prop::collection::vec(any::<i64>(), 1..20)) {
+        let content: String = values.iter()
+            .map(|value| format!("{}\n", json!({ "amount": value, "currency": "EUR", "ignored": true })))
+            .collect();
+        let mapping: ColumnMapping = [
+            ("amount".to_string(), "payment.amount".to_string()),
+            ("currency".to_string(), "payment.currency".to_string()),
+        ].into_iter().collect();
+
+        let parsed = parse_rows(ImportFormat::Ndjson, &content, Some(&mapping)).unwrap();
+        for (value, parsed) in values.iter().zip(parsed) {
+            prop_assert_eq!(parsed.unwrap(), json!({ "payment": { "amount": value, "currency": "EUR" } }));
+        }
+    }
+
+    /// Rows that cannot become input_data are reported on their own, without
+    /// refusing the rest of the file
+    #[test]
+    fn bad_rows_do_not_refuse_the_file(good in 1..10usize, bad in prop::sample::Index::arbitrary()) {
+        let mut lines: Vec<String> = (0..good).map(|i| json!({ "n": i }).to_string()).collect();
+        let bad = bad.index(good + 1);
+        lines.insert(bad, "[1, 2]".to_string());
+
+        let parsed = parse_rows(ImportFormat::Ndjson, &lines.join("\n"), None).unwrap();
+        prop_assert_eq!(parsed.len(), good + 1);
+        for (index, row) in parsed.iter().enumerate() {
+            prop_assert_eq!(row.is_err(), index == bad);
+        }
+    }
+}
+
+#[test]
+fn files_are_refused_as_a_whole() {
+    let mapping: ColumnMapping = [("email".to_string(), "to".to_string())].into_iter().collect();
+    assert_eq!(parse_rows(ImportFormat::Csv, "name\nAda\n", Some(&mapping)), Err(ImportError::UnknownColumn("email".to_string())));
+    assert_eq!(parse_rows(ImportFormat::Csv, "name\n", None), Err(ImportError::Empty));
+    assert_eq!(parse_rows(ImportFormat::Ndjson, "\n\n", None), Err(ImportError::Empty));
+    assert!(matches!(parse_rows(ImportFormat::Csv, "name\n\"Ada\n", None), Err(ImportError::InvalidCsv(_))));
+
+    let invalid: ColumnMapping = [("name".to_string(), "user..name".to_string())].into_iter().collect();
+    assert!(matches!(parse_rows(ImportFormat::Csv, "name\nAda\n", Some(&invalid)), Err(ImportError::InvalidMapping(_))));
+
+    let too_many = format!("n\n{}", "1\n".repeat(10_001));
+    assert_eq!(parse_rows(ImportFormat::Csv, &too_many, None), Err(ImportError::TooManyRows(10_001)));
+}
+
+#[test]
+fn rows_with_the_wrong_number_of_columns_are_rejected() {
+    let parsed = parse_rows(ImportFormat::Csv, "a,b\n1,2\n3\n", None).unwrap();
+    let mut expected = Map::new();
+    expected.insert("a".to_string(), Value::String("1".to_string()));
+    expected.insert("b".to_string(), Value::String("2".to_string()));
+    assert_eq!(parsed[0], Ok(Value::Object(expected)));
+    assert_eq!(parsed[1], Err("expected 2 columns, found 1".to_string()));
+}
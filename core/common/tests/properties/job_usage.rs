@@ -0,0 +1,78 @@
+use chrono::Utc;
+use innosystem_common::models::job_usage::{merge_summaries, summarize, JobUsage};
+use proptest::prelude::*;
+use uuid::Uuid;
+
+/// Usage of jobs spread over a few customers and job types, each tagged with the
+/// schema it was recorded in
+fn usages() -> impl Strategy<Value = Vec<(usize, JobUsage)>> {
+    let customers: Vec<Uuid> = (0..3).map(|_| Uuid::new_v4()).collect();
+    let job_types: Vec<Uuid> = (0..2).map(|_| Uuid::new_v4()).collect();
+    prop::collection::vec(
+        (
+            0..3usize,
+            prop::sample::select(customers),
+            prop::sample::select(job_types),
+            0..100_000i64,
+            prop::option::of(0..100_000i64),
+            prop::option::of(0..1i64 << 32),
+            0..1_000_000i64,
+            0..1_000_000i64,
+        ),
+        0..40,
+    )
+    .prop_map(|rows| {
+        rows.into_iter()
+            .map(|(schema, customer_id, job_type_id, wall_time_ms, cpu_time_ms, peak_memory_bytes, bytes_sent, bytes_received)| {
+                (schema, JobUsage {
+                    job_id: Uuid::new_v4(),
+                    attempt_id: None,
+                    runner_id: None,
+                    customer_id,
+                    job_type_id,
+                    wall_time_ms,
+                    cpu_time_ms,
+                    peak_memory_bytes,
+                    bytes_sent,
+                    bytes_received,
+                    recorded_at: Utc::now(),
+                })
+            })
+            .collect()
+    })
+}
+
+proptest! {
+    /// Summaries of each schema merged together equal the summary of all usage at once
+    #[test]
+    fn merged_schema_summaries_equal_the_summary_of_everything(usages in usages()) {
+        let per_schema = (0..3).flat_map(|schema| {
+            summarize(usages.iter().filter(|(s, _)| *s == schema).map(|(_, usage)| usage))
+        });
+        let merged = merge_summaries(per_schema);
+        prop_assert_eq!(merged, summarize(usages.iter().map(|(_, usage)| usage)));
+    }
+
+    /// Summaries keep every job and every total, and the peak is the highest of any job
+    #[test]
+    fn summaries_preserve_totals_and_the_highest_peak(usages in usages()) {
+        let summaries = summarize(usages.iter().map(|(_, usage)| usage));
+
+        prop_assert_eq!(summaries.iter().map(|s| s.jobs).sum::<i64>(), usages.len() as i64);
+        prop_assert_eq!(summaries.iter().map(|s| s.wall_time_ms).sum::<i64>(), usages.iter().map(|(_, u)| u.wall_time_ms).sum::<i64>());
+        prop_assert_eq!(summaries.iter().map(|s| s.cpu_time_ms).sum::<i64>(), usages.iter().filter_map(|(_, u)| u.cpu_time_ms).sum::<i64>());
+        prop_assert_eq!(summaries.iter().map(|s| s.bytes_sent).sum::<i64>(), usages.iter().map(|(_, u)| u.bytes_sent).sum::<i64>());
+        prop_assert_eq!(summaries.iter().map(|s| s.bytes_received).sum::<i64>(), usages.iter().map(|(_, u)| u.bytes_received).sum::<i64>());
+
+        for summary in &summaries {
+            let peak = usages.iter()
+                .filter(|(_, u)| u.customer_id == summary.customer_id && u.job_type_id == summary.job_type_id)
+                .filter_map(|(_, u)| u.peak_memory_bytes)
+                .max();
+            prop_assert_eq!(summary.peak_memory_bytes, peak);
+        }
+
+        // One summary per customer and job type, in order
+        prop_assert!(summaries.windows(2).all(|pair| (pair[0].customer_id, pair[0].job_type_id) < (pair[1].customer_id, pair[1].job_type_id)));
+    }
+}
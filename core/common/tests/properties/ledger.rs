@@ -0,0 +1,74 @@
+use innosystem_common::models::job::PriorityLevel;
+use innosystem_common::models::job_cost::{CostBreakdown, FAILURE_FEE_RATE};
+use innosystem_common::models::ledger::{is_balanced, JobDebit, LedgerAccountKind};
+use innosystem_common::models::reseller::Reseller;
+use proptest::prelude::*;
+use uuid::Uuid;
+
+fn priority() -> impl Strategy<Value = PriorityLevel> {
+    prop_oneof![
+        Just(PriorityLevel::Low),
+        Just(PriorityLevel::Medium),
+        Just(PriorityLevel::High),
+        Just(PriorityLevel::Critical),
+    ]
+}
+
+/// Amount a debit posts to the accounts of a kind
+fn posted(debit: &JobDebit, kind: LedgerAccountKind) -> i64 {
+    debit.postings().iter().filter(|posting| posting.kind == kind).map(|posting| posting.amount_cents).sum()
+}
+
+proptest! {
+    /// A job debit takes the charge out of the wallet and splits it between the reseller
+    /// and the platform as the reseller's invoice does, balancing to zero
+    #[test]
+    fn job_debits_balance_and_follow_the_invoice(
+        base_cents in 0..1_000_000i32,
+        priority in priority(),
+        markup_rate in 0..5_000i32,
+        commission_rate in 0..5_000i32,
+        credits in 0..10_000i64,
+        failed in any::<bool>(),
+        resold in any::<bool>(),
+    ) {
+        let reseller = Reseller::new("Acme".to_string(), "acme@example.com".to_string(), "key".to_string(), commission_rate);
+        let mut cost = if failed {
+            CostBreakdown::failed(base_cents, FAILURE_FEE_RATE)
+        } else {
+            CostBreakdown::completed(base_cents, &priority, if resold { markup_rate } else { 0 })
+        };
+        cost.apply_credits(credits);
+
+        let wallet_id = Uuid::new_v4();
+        let debit = JobDebit::new(wallet_id, Uuid::new_v4(), &cost, resold.then_some(&reseller));
+        let postings = debit.postings();
+
+        prop_assert!(is_balanced(&postings));
+        prop_assert!(postings.iter().all(|posting| posting.amount_cents != 0));
+        prop_assert_eq!(debit.amount_cents(), cost.total());
+        prop_assert_eq!(posted(&debit, LedgerAccountKind::CustomerWallet), -(cost.total() as i64));
+        prop_assert!(postings.iter()
+            .filter(|posting| posting.kind == LedgerAccountKind::CustomerWallet)
+            .all(|posting| posting.owner_id == Some(wallet_id)));
+
+        if resold {
+            let amounts = debit.amounts;
+            prop_assert_eq!(posted(&debit, LedgerAccountKind::ResellerCommission), amounts.markup_cents + amounts.commission_cents);
+            prop_assert_eq!(posted(&debit, LedgerAccountKind::PlatformRevenue), amounts.due_cents);
+        } else {
+            prop_assert_eq!(posted(&debit, LedgerAccountKind::ResellerCommission), 0);
+            prop_assert_eq!(posted(&debit, LedgerAccountKind::PlatformRevenue), cost.total() as i64);
+        }
+    }
+
+    /// Account kinds read back from their names
+    #[test]
+    fn account_kinds_round_trip(kind in prop_oneof![
+        Just(LedgerAccountKind::CustomerWallet),
+        Just(LedgerAccountKind::PlatformRevenue),
+        Just(LedgerAccountKind::ResellerCommission),
+    ]) {
+        prop_assert_eq!(LedgerAccountKind::parse(kind.as_str()), Some(kind));
+    }
+}
@@ -0,0 +1,140 @@
+use chrono::{DateTime, Duration, NaiveDate, Utc};
+use innosystem_common::models::load_window::{LoadWindow, LoadWindowStatus};
+use proptest::prelude::*;
+use uuid::Uuid;
+
+const STATUSES: [LoadWindowStatus; 5] = [
+    LoadWindowStatus::Planned,
+    LoadWindowStatus::Warming,
+    LoadWindowStatus::Ready,
+    LoadWindowStatus::Released,
+    LoadWindowStatus::Cancelled,
+];
+
+fn starts_at() -> DateTime<Utc> {
+    NaiveDate::from_ymd_opt(2025, 6, 1).unwrap().and_hms_opt(2, 0, 0).unwrap().and_utc()
+}
+
+fn window(status: LoadWindowStatus, target_runners: i32, warm_up_minutes: i32, max_hold_minutes: i32, job_types: usize) -> LoadWindow {
+    LoadWindow {
+        id: Uuid::new_v4(),
+        name: "nightly batch".to_string(),
+        pool: Some("batch".to_string()),
+        job_type_ids: (0..job_types).map(|_| Uuid::new_v4()).collect(),
+        target_runners,
+        starts_at: starts_at(),
+        ends_at: starts_at() + Duration::hours(2),
+        warm_up_minutes,
+        max_hold_minutes,
+        status: status.as_str().to_string(),
+        active_runners: None,
+        notified_at: None,
+        ready_at: None,
+        released_at: None,
+        created_by: "admin".to_string(),
+        created_at: starts_at() - Duration::days(1),
+        updated_at: starts_at() - Duration::days(1),
+    }
+}
+
+proptest! {
+    /// Jobs are only held while the pool is short of its target, from shortly before
+    /// the start until the window gives up on it, and never past the next check
+    #[test]
+    fn jobs_are_held_only_while_the_pool_is_short_around_the_start(
+        status in prop::sample::select(STATUSES.to_vec()),
+        offset_minutes in -180i64..180,
+        target_runners in 1i32..20,
+        active_runners in 0u32..25,
+        warm_up_minutes in 0i32..60,
+        max_hold_minutes in 0i32..60,
+        check_interval_secs in 1i64..600,
+        job_types in 0usize..3,
+    ) {
+        let window = window(status, target_runners, warm_up_minutes, max_hold_minutes, job_types);
+        let now = starts_at() + Duration::minutes(offset_minutes);
+        let next_check = now + Duration::seconds(check_interval_secs);
+
+        let step = window.step(now, active_runners, next_check);
+        if let Some(until) = step.hold_until {
+            prop_assert_eq!(until, next_check);
+            prop_assert_eq!(step.status, LoadWindowStatus::Warming);
+            prop_assert!(active_runners < target_runners as u32);
+            prop_assert!(job_types > 0);
+            prop_assert!(next_check > window.starts_at);
+            prop_assert!(now < window.give_up_at());
+        }
+        if status.is_open() && job_types > 0 && active_runners < target_runners as u32
+            && now >= window.warm_up_at() && next_check > window.starts_at && now < window.give_up_at() {
+            prop_assert!(step.hold_until.is_some());
+        }
+    }
+
+    /// Released and cancelled windows stay as they are, and autoscalers are told once,
+    /// when the warm-up of a planned window begins
+    #[test]
+    fn closed_windows_stay_closed_and_the_warm_up_is_announced_once(
+        status in prop::sample::select(STATUSES.to_vec()),
+        offset_minutes in -180i64..180,
+        active_runners in 0u32..25,
+    ) {
+        let window = window(status, 5, 15, 30, 1);
+        let now = starts_at() + Duration::minutes(offset_minutes);
+        let step = window.step(now, active_runners, now + Duration::seconds(30));
+
+        if !status.is_open() {
+            prop_assert_eq!(step.status, status);
+            prop_assert!(!step.notify && step.hold_until.is_none());
+        }
+        prop_assert_eq!(step.notify, status == LoadWindowStatus::Planned && now >= window.warm_up_at() && now < window.ends_at);
+        if now < window.warm_up_at() {
+            prop_assert_eq!(step.status, status);
+        }
+    }
+
+    /// Once open windows are due, they are released when the pool is ready or the hold
+    /// has run out, whichever comes first
+    #[test]
+    fn started_windows_are_released_once_ready_or_out_of_patience(
+        status in prop::sample::select(vec![LoadWindowStatus::Planned, LoadWindowStatus::Warming, LoadWindowStatus::Ready]),
+        offset_minutes in 0i64..180,
+        target_runners in 1i32..20,
+        active_runners in 0u32..25,
+        max_hold_minutes in 0i32..60,
+    ) {
+        let window = window(status, target_runners, 15, max_hold_minutes, 1);
+        let now = starts_at() + Duration::minutes(offset_minutes);
+        let step = window.step(now, active_runners, now + Duration::seconds(30));
+
+        let released = active_runners >= target_runners as u32 || now >= window.give_up_at() || now >= window.ends_at;
+        prop_assert_eq!(step.status == LoadWindowStatus::Released, released);
+        if !released {
+            prop_assert_eq!(step.status, LoadWindowStatus::Warming);
+        }
+    }
+}
+
+#[test]
+fn pools_reaching_their_target_before_the_start_are_ready() {
+    let window = window(LoadWindowStatus::Warming, 3, 15, 30, 1);
+    let now = starts_at() - Duration::minutes(5);
+
+    let step = window.step(now, 3, now + Duration::seconds(30));
+    assert_eq!(step.status, LoadWindowStatus::Ready);
+    assert_eq!(step.hold_until, None);
+
+    // Losing runners before the start goes back to warming up
+    let step = window.step(now, 2, now + Duration::minutes(10));
+    assert_eq!(step.status, LoadWindowStatus::Warming);
+    assert_eq!(step.hold_until, Some(now + Duration::minutes(10)));
+}
+
+#[test]
+fn windows_without_a_pool_count_every_runner() {
+    let mut window = window(LoadWindowStatus::Planned, 3, 15, 30, 0);
+    assert!(window.includes_runner("batch"));
+    assert!(!window.includes_runner("interactive"));
+
+    window.pool = None;
+    assert!(window.includes_runner("interactive"));
+}
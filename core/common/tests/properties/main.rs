@@ -0,0 +1,62 @@
+//! Property-based tests for billing arithmetic, job cost breakdowns, billing
+//! exports, the job state machine and job requeues, job imports, fixture bundles,
+//! dry runs, processor input contracts, webhook templates, the capture of webhook
+//! responses, pipeline scheduling, output redaction, locale negotiation,
+//! notification preferences, configuration plans, partition retention, request
+//! signatures, provider health, runner environment drift, auth lockouts, time zone
+//! conversions, queue connection settings, the in-memory job queue, connection pool
+//! utilization, job resource usage, load window warm-ups, submission summaries, the
+//! runner's dequeue policies, the anonymization and comparison of replayed jobs,
+//! consolidated reseller invoices, the ledger split of job debits, the API's
+//! response envelopes, the detection of personal data in job inputs, the
+//! addresses webhooks may be delivered to and the parsing of the Diesel schema
+//!
+//! These run against the in-memory repositories, so they need no database and are
+//! part of the default `cargo test` run. Set `PROPTEST_CASES` to explore more cases.
+
+mod api;
+mod billing_export;
+mod billing_period;
+mod config_document;
+mod dequeue;
+mod dry_run;
+mod fixture;
+mod i18n;
+mod input_contract;
+mod job;
+mod job_cost;
+mod job_import;
+mod job_usage;
+mod ledger;
+mod load_window;
+mod memory_queue;
+mod notification;
+mod partition;
+mod pii;
+mod pipeline;
+mod pool;
+mod provider;
+mod queue_config;
+mod redaction;
+mod replay;
+mod reseller_invoice;
+mod response_policy;
+mod request_signature;
+mod runner_environment;
+mod schema_drift;
+mod security_event;
+mod submission;
+mod timezone;
+mod wallet;
+mod webhook;
+
+use std::future::Future;
+
+/// Run a future to completion on a fresh single-threaded runtime
+pub fn block_on<F: Future>(future: F) -> F::Output {
+    tokio::runtime::Builder::new_current_thread()
+        .enable_all()
+        .build()
+        .expect("failed to build runtime")
+        .block_on(future)
+}
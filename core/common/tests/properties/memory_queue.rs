@@ -0,0 +1,182 @@
+use std::time::Duration;
+
+use chrono::{Duration as ChronoDuration, Utc};
+use innosystem_common::models::job::PriorityLevel;
+use innosystem_common::queue::{InMemoryJobQueue, JobQueue, QueueBackend};
+use proptest::prelude::*;
+use uuid::Uuid;
+
+use crate::block_on;
+
+const PRIORITIES: [PriorityLevel; 4] = [
+    PriorityLevel::Critical,
+    PriorityLevel::High,
+    PriorityLevel::Medium,
+    PriorityLevel::Low,
+];
+
+fn priority() -> impl Strategy<Value = PriorityLevel> {
+    prop::sample::select(PRIORITIES.to_vec())
+}
+
+proptest! {
+    /// Jobs come out highest priority first and in the order they were pushed within
+    /// a priority, whichever way they are popped
+    #[test]
+    fn jobs_are_popped_by_priority_then_in_push_order(priorities in prop::collection::vec(priority(), 1..40), batch in 1..10usize) {
+        block_on(async {
+            let queue = InMemoryJobQueue::default();
+            let mut pushed = Vec::new();
+            for priority in priorities {
+                let job_id = Uuid::new_v4();
+                queue.push_job(job_id, priority.clone()).await.unwrap();
+                pushed.push((job_id, priority));
+            }
+            prop_assert_eq!(queue.queue_length().await.unwrap(), pushed.len());
+
+            // A stable sort keeps the push order within a priority
+            let mut expected = pushed.clone();
+            expected.sort_by(|a, b| b.1.cmp(&a.1));
+
+            let mut popped = Vec::new();
+            while popped.len() < expected.len() {
+                let next = queue.peek_next_job().await.unwrap();
+                if popped.len() % 2 == 0 {
+                    let job = queue.pop_job_with_timeout(1).await.unwrap();
+                    prop_assert_eq!(job, next);
+                    let job_id = job.unwrap();
+                    popped.push(pushed.iter().find(|(id, _)| *id == job_id).cloned().unwrap());
+                } else {
+                    let jobs = queue.try_pop_jobs_from(&PRIORITIES, batch).await.unwrap();
+                    prop_assert_eq!(jobs.first().map(|(job_id, _)| *job_id), next);
+                    popped.extend(jobs);
+                }
+            }
+
+            prop_assert_eq!(popped, expected);
+            prop_assert_eq!(queue.queue_length().await.unwrap(), 0);
+            prop_assert!(queue.try_pop_jobs_from(&PRIORITIES, 10).await.unwrap().is_empty());
+            Ok(())
+        })?;
+    }
+
+    /// Popping from a subset of the queues leaves the others untouched
+    #[test]
+    fn pops_only_take_from_the_given_queues(priorities in prop::collection::vec(priority(), 1..40), allowed in priority()) {
+        block_on(async {
+            let queue = InMemoryJobQueue::default();
+            for priority in &priorities {
+                queue.push_job(Uuid::new_v4(), priority.clone()).await.unwrap();
+            }
+
+            let jobs = queue.try_pop_jobs_from(std::slice::from_ref(&allowed), priorities.len()).await.unwrap();
+            let expected = priorities.iter().filter(|priority| **priority == allowed).count();
+            prop_assert_eq!(jobs.len(), expected);
+            prop_assert!(jobs.iter().all(|(_, priority)| *priority == allowed));
+            prop_assert_eq!(queue.queue_length_by_priority(allowed.clone()).await.unwrap(), 0);
+            prop_assert_eq!(queue.queue_length().await.unwrap(), priorities.len() - expected);
+            Ok(())
+        })?;
+    }
+
+    /// Exactly the scheduled jobs that are due are claimed, earliest first and only
+    /// once, and scheduling a job again moves it
+    #[test]
+    fn due_scheduled_jobs_are_claimed_once(offsets in prop::collection::vec(-3_600i64..3_600, 1..30)) {
+        block_on(async {
+            let queue = InMemoryJobQueue::default();
+            let now = Utc::now();
+            let mut scheduled = Vec::new();
+            for offset in offsets {
+                // Keep clear of now, so the test does not race the clock
+                let offset = if offset >= 0 { offset + 60 } else { offset };
+                let job_id = Uuid::new_v4();
+                queue.schedule_job(job_id, now + ChronoDuration::seconds(offset)).await.unwrap();
+                scheduled.push((offset, job_id));
+            }
+
+            // Jobs due at the same time come out in the order of their IDs
+            let mut due: Vec<(i64, Uuid)> = scheduled.iter().filter(|(offset, _)| *offset < 0).cloned().collect();
+            due.sort();
+            let returned = queue.claim_due_scheduled_jobs(usize::MAX).await.unwrap();
+            prop_assert_eq!(&returned, &due.iter().map(|(_, job_id)| *job_id).collect::<Vec<_>>());
+            prop_assert_eq!(queue.claim_due_scheduled_jobs(usize::MAX).await.unwrap().len(), 0);
+
+            // Pull every job still waiting forward, so they are all due now
+            let waiting: Vec<Uuid> = scheduled.iter().filter(|(offset, _)| *offset >= 0).map(|(_, job_id)| *job_id).collect();
+            for job_id in &waiting {
+                queue.schedule_job(*job_id, now - ChronoDuration::seconds(1)).await.unwrap();
+            }
+            let mut returned = queue.claim_due_scheduled_jobs(usize::MAX).await.unwrap();
+            returned.sort();
+            let mut waiting = waiting;
+            waiting.sort();
+            prop_assert_eq!(returned, waiting);
+            Ok(())
+        })?;
+    }
+
+    /// Runners claiming in batches split the due jobs between them, each job going to
+    /// exactly one runner, earliest first
+    #[test]
+    fn concurrent_claims_split_due_jobs(due in 0usize..40, runners in 1usize..6, batch in 1usize..8) {
+        block_on(async {
+            let queue = InMemoryJobQueue::default();
+            let now = Utc::now();
+            let mut scheduled = Vec::new();
+            for offset in 0..due {
+                let job_id = Uuid::new_v4();
+                queue.schedule_job(job_id, now - ChronoDuration::seconds(due as i64 - offset as i64)).await.unwrap();
+                scheduled.push(job_id);
+            }
+
+            let mut claimed = Vec::new();
+            loop {
+                let mut round = Vec::new();
+                for _ in 0..runners {
+                    let claim = queue.claim_due_scheduled_jobs(batch).await.unwrap();
+                    prop_assert!(claim.len() <= batch);
+                    round.extend(claim);
+                }
+                prop_assert!(round.len() <= runners * batch);
+                if round.is_empty() {
+                    break;
+                }
+                claimed.extend(round);
+            }
+            prop_assert_eq!(claimed, scheduled);
+            Ok(())
+        })?;
+    }
+}
+
+#[test]
+fn blocking_pops_wait_for_a_push_until_their_timeout() {
+    block_on(async {
+        let queue = InMemoryJobQueue::default();
+        let job_id = Uuid::new_v4();
+
+        let (popped, _) = tokio::join!(
+            queue.pop_job_from(&[PriorityLevel::High], 5),
+            async {
+                tokio::time::sleep(Duration::from_millis(50)).await;
+                queue.push_job(job_id, PriorityLevel::Low).await.unwrap();
+                queue.push_job(job_id, PriorityLevel::High).await.unwrap();
+            },
+        );
+        assert_eq!(popped.unwrap(), Some((job_id, PriorityLevel::High)));
+
+        // The low priority job is not among the queues waited on
+        assert_eq!(queue.pop_job_from(&[PriorityLevel::Critical], 1).await.unwrap(), None);
+        assert_eq!(queue.queue_length_by_priority(PriorityLevel::Low).await.unwrap(), 1);
+    });
+}
+
+#[test]
+fn queue_backends_parse_from_their_configuration_values() {
+    for backend in [QueueBackend::Redis, QueueBackend::Memory] {
+        assert_eq!(QueueBackend::parse(backend.as_str()), Some(backend));
+    }
+    assert_eq!(QueueBackend::parse("kafka"), None);
+    assert_eq!(QueueBackend::default(), QueueBackend::Redis);
+}
@@ -0,0 +1,91 @@
+use innosystem_common::models::notification::{self, DeliveryChannel, NotificationMode, NotificationPreference};
+use innosystem_common::models::webhook::WebhookEventType;
+use proptest::prelude::*;
+use uuid::Uuid;
+
+const CHANNELS: [DeliveryChannel; 2] = [DeliveryChannel::Webhook, DeliveryChannel::Email];
+const MODES: [NotificationMode; 3] = [NotificationMode::Immediate, NotificationMode::Digest, NotificationMode::Off];
+
+fn event_type() -> impl Strategy<Value = WebhookEventType> {
+    prop::sample::select(WebhookEventType::SUBSCRIBABLE.to_vec())
+}
+
+/// Preferences of one customer, at most one per event type and channel
+fn preferences() -> impl Strategy<Value = Vec<NotificationPreference>> {
+    prop::collection::btree_map(
+        (0..WebhookEventType::SUBSCRIBABLE.len(), 0..CHANNELS.len()),
+        prop::sample::select(MODES.to_vec()),
+        0..10,
+    )
+    .prop_map(|modes| {
+        let customer_id = Uuid::new_v4();
+        modes.into_iter()
+            .map(|((event, channel), mode)| NotificationPreference {
+                id: Uuid::new_v4(),
+                customer_id,
+                event_type: WebhookEventType::SUBSCRIBABLE[event].as_str().to_string(),
+                channel: CHANNELS[channel].as_str().to_string(),
+                mode: mode.as_str().to_string(),
+                created_at: None,
+                updated_at: None,
+            })
+            .collect()
+    })
+}
+
+proptest! {
+    /// An event is sent in the mode the customer chose for it on the channel, and
+    /// right away when they chose nothing
+    #[test]
+    fn preferences_decide_the_mode(preferences in preferences(), event_type in event_type(), channel in prop::sample::select(CHANNELS.to_vec())) {
+        let chosen = preferences.iter()
+            .find(|preference| preference.event_type == event_type.as_str() && preference.channel == channel.as_str())
+            .map(|preference| preference.mode().unwrap());
+        prop_assert_eq!(
+            notification::mode_for(&preferences, event_type, channel),
+            chosen.unwrap_or(NotificationMode::Immediate)
+        );
+    }
+
+    /// Turning one event off on one channel leaves every other event and channel alone,
+    /// e.g. per-job emails can stop while failure alerts keep coming
+    #[test]
+    fn preferences_apply_to_their_event_and_channel_only(
+        preferences in preferences(),
+        event_type in event_type(),
+        channel in prop::sample::select(CHANNELS.to_vec()),
+    ) {
+        let mut with_off: Vec<NotificationPreference> = preferences.iter()
+            .filter(|preference| preference.event_type != event_type.as_str() || preference.channel != channel.as_str())
+            .cloned()
+            .collect();
+        with_off.push(NotificationPreference {
+            id: Uuid::new_v4(),
+            customer_id: Uuid::new_v4(),
+            event_type: event_type.as_str().to_string(),
+            channel: channel.as_str().to_string(),
+            mode: NotificationMode::Off.as_str().to_string(),
+            created_at: None,
+            updated_at: None,
+        });
+
+        prop_assert_eq!(notification::mode_for(&with_off, event_type, channel), NotificationMode::Off);
+        for other_event in WebhookEventType::SUBSCRIBABLE {
+            for other_channel in CHANNELS {
+                if other_event != event_type || other_channel != channel {
+                    prop_assert_eq!(
+                        notification::mode_for(&with_off, other_event, other_channel),
+                        notification::mode_for(&preferences, other_event, other_channel)
+                    );
+                }
+            }
+        }
+    }
+
+    /// Modes and channels round-trip through their stored names
+    #[test]
+    fn modes_round_trip(mode in prop::sample::select(MODES.to_vec()), channel in prop::sample::select(CHANNELS.to_vec())) {
+        prop_assert_eq!(NotificationMode::parse(mode.as_str()), Some(mode));
+        prop_assert_eq!(DeliveryChannel::parse(channel.as_str()), Some(channel));
+    }
+}
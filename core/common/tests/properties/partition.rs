@@ -0,0 +1,74 @@
+use chrono::{DateTime, Datelike, Months, NaiveDate, Utc};
+use innosystem_common::models::partition::{month_of, months_past_retention, months_to_create, next_month, PartitionedTable};
+use innosystem_common::models::purge::job_retention_cutoff;
+use proptest::prelude::*;
+
+fn instant() -> impl Strategy<Value = DateTime<Utc>> {
+    // 2000-01-01 to 2099-12-31
+    (946_684_800i64..4_102_444_800).prop_map(|secs| chrono::DateTime::from_timestamp(secs, 0).unwrap())
+}
+
+fn month() -> impl Strategy<Value = NaiveDate> {
+    (2000i32..2100, 1u32..=12).prop_map(|(year, month)| NaiveDate::from_ymd_opt(year, month, 1).unwrap())
+}
+
+fn table() -> impl Strategy<Value = PartitionedTable> {
+    prop::sample::select(PartitionedTable::ALL.to_vec())
+}
+
+proptest! {
+    #[test]
+    fn partition_names_identify_their_table_and_month(table in table(), month in month()) {
+        let name = table.partition_name(month);
+        prop_assert_eq!(table.parse_partition_name(&name), Some(month));
+        for other in PartitionedTable::ALL.into_iter().filter(|other| *other != table) {
+            prop_assert_eq!(other.parse_partition_name(&name), None);
+        }
+        prop_assert_eq!(table.parse_partition_name(&table.default_partition()), None);
+    }
+
+    #[test]
+    fn partitions_are_premade_for_consecutive_months_from_the_current_one(now in instant(), premake_months in 0u32..24) {
+        let months = months_to_create(now, premake_months);
+
+        prop_assert_eq!(months.len(), premake_months as usize + 1);
+        prop_assert_eq!(months[0], month_of(now));
+        prop_assert!(months[0] <= now.date_naive() && now.date_naive() < next_month(months[0]));
+        for pair in months.windows(2) {
+            prop_assert_eq!(pair[1], next_month(pair[0]));
+            prop_assert_eq!(pair[1].day(), 1);
+        }
+    }
+
+    #[test]
+    fn only_months_whose_rows_are_all_past_retention_are_dropped(
+        months in prop::collection::btree_set(month(), 0..24),
+        now in instant(),
+        retention_months in 0u32..36,
+    ) {
+        let months: Vec<NaiveDate> = months.into_iter().collect();
+        let expired = months_past_retention(&months, now, retention_months);
+
+        for month in &months {
+            // The newest row a month can hold was created just before the month ended
+            let kept_until = next_month(*month).checked_add_months(Months::new(retention_months)).unwrap();
+            prop_assert_eq!(expired.contains(month), kept_until <= now.date_naive());
+        }
+        // The current month and the retained months before it are never dropped
+        let oldest_kept = month_of(now).checked_sub_months(Months::new(retention_months)).unwrap();
+        prop_assert!(expired.iter().all(|month| *month < oldest_kept));
+        prop_assert!(expired.windows(2).all(|pair| pair[0] < pair[1]));
+    }
+
+    /// Purges delete the jobs of exactly the months partition retention drops
+    #[test]
+    fn purged_jobs_are_those_of_months_past_retention(month in month(), now in instant(), retention_months in 0u32..36) {
+        let cutoff = job_retention_cutoff(now, retention_months);
+        let expired = !months_past_retention(&[month], now, retention_months).is_empty();
+
+        // The newest job of the month was created just before the next one started
+        let newest = next_month(month).and_hms_opt(0, 0, 0).unwrap().and_utc() - chrono::Duration::seconds(1);
+        prop_assert_eq!(newest < cutoff, expired);
+        prop_assert!(cutoff <= now);
+    }
+}
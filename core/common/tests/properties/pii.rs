@@ -0,0 +1,114 @@
+use innosystem_common::models::pii::{luhn_valid, mask, scan, PiiFinding, PiiKind, PiiPolicy};
+use innosystem_common::models::redaction::REDACTED;
+use proptest::prelude::*;
+use serde_json::{json, Value};
+
+/// Check digit completing `digits` to a Luhn-valid number
+fn check_digit(digits: &[u8]) -> u8 {
+    (0..10).find(|digit| luhn_valid(&[digits, &[*digit]].concat())).unwrap()
+}
+
+/// Card numbers of 13 to 19 digits passing the Luhn check
+fn card_number() -> impl Strategy<Value = String> {
+    prop::collection::vec(0u8..10, 12..19).prop_map(|mut digits| {
+        digits.push(check_digit(&digits));
+        digits.iter().map(|digit| char::from(b'0' + digit)).collect()
+    })
+}
+
+/// Card numbers written in groups of four, as printed on cards
+fn grouped(number: &str, separator: char) -> String {
+    number.as_bytes().chunks(4)
+        .map(|group| std::str::from_utf8(group).unwrap())
+        .collect::<Vec<_>>()
+        .join(&separator.to_string())
+}
+
+fn email() -> impl Strategy<Value = String> {
+    ("[a-z][a-z0-9._+-]{0,10}", "[a-z][a-z0-9-]{0,10}", "[a-z]{2,6}")
+        .prop_map(|(local, domain, tld)| format!("{}@{}.{}", local, domain, tld))
+}
+
+fn finding(path: &str, kind: PiiKind) -> PiiFinding {
+    PiiFinding { path: path.to_string(), kind }
+}
+
+#[test]
+fn finds_emails_and_card_numbers_by_path() {
+    let input = json!({
+        "customer": { "contact": "Mail jane.doe@example.com." },
+        "rows": ["nothing here", "card 4111 1111 1111 1111 on file"],
+        "x-note": "4111-1111-1111-1111",
+        "order_id": "ORD4111111111111111",
+        "amount": 4111111111111111u64,
+    });
+    let mut findings = scan(&input);
+    findings.sort_by(|a, b| a.path.cmp(&b.path));
+    assert_eq!(findings, vec![
+        finding("$.customer.contact", PiiKind::Email),
+        finding("$.rows[1]", PiiKind::CreditCard),
+        finding("$['x-note']", PiiKind::CreditCard),
+    ]);
+}
+
+#[test]
+fn ignores_numbers_failing_the_luhn_check_and_incomplete_addresses() {
+    let input = json!(["4111 1111 1111 1112", "1234567890", "user@localhost", "@example.com", "a@b.c"]);
+    assert!(scan(&input).is_empty());
+}
+
+#[test]
+fn masks_the_data_and_keeps_the_rest_of_the_string() {
+    let mut input = json!({ "note": "Pay with 4111111111111111, receipt to a@example.org" });
+    let findings = mask(&mut input);
+    assert_eq!(findings, vec![finding("$.note", PiiKind::CreditCard), finding("$.note", PiiKind::Email)]);
+    assert_eq!(input, json!({ "note": format!("Pay with {}, receipt to {}", REDACTED, REDACTED) }));
+}
+
+#[test]
+fn policies_round_trip_through_their_names() {
+    for policy in [PiiPolicy::Reject, PiiPolicy::Mask, PiiPolicy::Flag] {
+        assert_eq!(PiiPolicy::parse(policy.as_str()), Some(policy));
+        assert_eq!(serde_json::to_value(policy).unwrap(), json!(policy.as_str()));
+    }
+    assert_eq!(PiiPolicy::parse("block"), None);
+}
+
+proptest! {
+    #[test]
+    fn card_numbers_are_found_however_they_are_grouped(number in card_number(), separator in prop::sample::select(vec![' ', '-']), grouping in any::<bool>()) {
+        let text = if grouping { grouped(&number, separator) } else { number };
+        let input = json!({ "payment": format!("card: {}", text) });
+        prop_assert_eq!(scan(&input), vec![finding("$.payment", PiiKind::CreditCard)]);
+    }
+
+    #[test]
+    fn emails_are_found_anywhere_in_a_string(address in email(), before in "[a-z ]{0,10}", after in "[a-z ]{0,10}") {
+        let input = json!([format!("{} {} {}", before, address, after)]);
+        prop_assert_eq!(scan(&input), vec![finding("$[0]", PiiKind::Email)]);
+    }
+
+    #[test]
+    fn strings_without_at_signs_or_digits_have_no_findings(text in "[a-zA-Z .,_-]{0,40}") {
+        let input = json!({ "text": text });
+        prop_assert!(scan(&input).is_empty());
+    }
+
+    #[test]
+    fn masking_leaves_nothing_to_find(number in card_number(), address in email(), filler in "[a-z ]{0,10}") {
+        let mut input = json!({ "a": [format!("{}{}", filler, address)], "b": { "c": format!("{} {}", number, filler) } });
+        let found = scan(&input);
+        let masked = mask(&mut input);
+        prop_assert_eq!(found, masked);
+        prop_assert!(scan(&input).is_empty());
+        let rendered = input.to_string();
+        prop_assert!(!rendered.contains(&address) && !rendered.contains(&number));
+    }
+
+    #[test]
+    fn scanning_does_not_change_values_without_strings(value in any::<i64>()) {
+        let mut input = Value::from(value);
+        prop_assert!(mask(&mut input).is_empty());
+        prop_assert_eq!(input, Value::from(value));
+    }
+}
@@ -0,0 +1,178 @@
+use std::collections::HashMap;
+
+use innosystem_common::models::job::PriorityLevel;
+use innosystem_common::models::pipeline::{
+    FailurePolicy, PipelineDefinition, PipelineError, PipelineRunStatus, PipelineStep, PriorityPolicy, StepStatus,
+};
+use proptest::prelude::*;
+use serde_json::json;
+
+fn policy() -> impl Strategy<Value = FailurePolicy> {
+    prop::sample::select(vec![FailurePolicy::Fail, FailurePolicy::SkipDependents, FailurePolicy::Continue])
+}
+
+/// A random DAG: each step may depend on any of the steps before it. Also returns
+/// whether each step's job succeeds when it runs.
+fn pipeline() -> impl Strategy<Value = (PipelineDefinition, Vec<bool>)> {
+    (1usize..8)
+        .prop_flat_map(|len| {
+            (
+                prop::collection::vec((prop::collection::vec(any::<bool>(), len), policy()), len),
+                prop::collection::vec(any::<bool>(), len),
+            )
+        })
+        .prop_map(|(steps, outcomes)| {
+            let steps = steps.into_iter().enumerate()
+                .map(|(i, (edges, on_failure))| PipelineStep {
+                    name: format!("step{}", i),
+                    job_type: "convert".to_string(),
+                    depends_on: (0..i).filter(|j| edges[*j]).map(|j| format!("step{}", j)).collect(),
+                    input: json!({}),
+                    on_failure,
+                })
+                .collect();
+            let definition = PipelineDefinition {
+                name: "generated".to_string(),
+                description: None,
+                priority_policy: PriorityPolicy::Inherit,
+                steps,
+            };
+            (definition, outcomes)
+        })
+}
+
+proptest! {
+    #[test]
+    fn generated_dags_validate_and_order_dependencies_first((definition, _) in pipeline()) {
+        prop_assert!(definition.validate().is_ok());
+
+        let order: Vec<&str> = definition.topological_order().unwrap().iter().map(|step| step.name.as_str()).collect();
+        prop_assert_eq!(order.len(), definition.steps.len());
+        for step in &definition.steps {
+            let position = order.iter().position(|name| *name == step.name).unwrap();
+            for dependency in &step.depends_on {
+                prop_assert!(order.iter().position(|name| name == dependency).unwrap() < position);
+            }
+        }
+    }
+
+    #[test]
+    fn back_edges_are_rejected_as_cycles((mut definition, _) in pipeline(), from in any::<prop::sample::Index>()) {
+        // Make a step depend on one of its dependents (or itself)
+        let last = definition.steps.len() - 1;
+        let from = from.index(definition.steps.len());
+        let (last_name, from_name) = (definition.steps[last].name.clone(), definition.steps[from].name.clone());
+        definition.steps[last].depends_on.push(from_name);
+        definition.steps[from].depends_on.push(last_name);
+
+        prop_assert!(matches!(definition.validate(), Err(PipelineError::Cycle(_))));
+    }
+
+    #[test]
+    fn escalated_steps_run_above_their_dependencies_until_critical((mut definition, _) in pipeline(), run_priority in 0i32..4) {
+        let run_priority = PriorityLevel::from_i32(run_priority);
+        for step in &definition.steps {
+            prop_assert_eq!(definition.step_priority(&step.name, run_priority.clone()), run_priority.clone());
+        }
+
+        definition.priority_policy = PriorityPolicy::Escalate;
+        for step in &definition.steps {
+            let priority = definition.step_priority(&step.name, run_priority.clone());
+            if step.depends_on.is_empty() {
+                prop_assert_eq!(&priority, &run_priority);
+            }
+            for dependency in &step.depends_on {
+                let dependency_priority = definition.step_priority(dependency, run_priority.clone());
+                prop_assert!(priority > dependency_priority || priority == PriorityLevel::Critical);
+            }
+        }
+    }
+
+    #[test]
+    fn runs_start_steps_only_after_their_dependencies_and_always_finish((definition, outcomes) in pipeline()) {
+        let mut statuses: HashMap<String, StepStatus> = HashMap::new();
+        let mut outcome = None;
+
+        // Each pass, running steps finish and the run makes progress
+        for _ in 0..=definition.steps.len() * 2 {
+            for (step, succeeds) in definition.steps.iter().zip(&outcomes) {
+                if statuses.get(&step.name) == Some(&StepStatus::Running) {
+                    statuses.insert(step.name.clone(), if *succeeds { StepStatus::Succeeded } else { StepStatus::Failed });
+                }
+            }
+
+            let progress = definition.progress(&statuses);
+            for name in &progress.start {
+                let step = definition.step(name).unwrap();
+                for dependency in &step.depends_on {
+                    let status = statuses[dependency];
+                    prop_assert!(status == StepStatus::Succeeded
+                        || (status == StepStatus::Failed && definition.step(dependency).unwrap().on_failure == FailurePolicy::Continue));
+                }
+                statuses.insert(name.clone(), StepStatus::Running);
+            }
+            for name in &progress.skip {
+                statuses.insert(name.clone(), StepStatus::Skipped);
+            }
+            if progress.outcome.is_some() {
+                outcome = progress.outcome;
+                break;
+            }
+        }
+
+        let Some(outcome) = outcome else {
+            return Err(TestCaseError::fail("run never finished"));
+        };
+        let fatal = definition.steps.iter()
+            .any(|step| statuses.get(&step.name) == Some(&StepStatus::Failed) && step.on_failure != FailurePolicy::Continue);
+        prop_assert_eq!(outcome == PipelineRunStatus::Failed, fatal);
+        if outcome == PipelineRunStatus::Succeeded {
+            prop_assert!(statuses.values().all(|status| matches!(status, StepStatus::Succeeded | StepStatus::Failed)));
+        }
+    }
+}
+
+#[test]
+fn step_inputs_resolve_references_to_run_input_and_outputs() {
+    let definition = PipelineDefinition::parse(r#"
+name: invoices
+steps:
+  - name: ocr
+    job_type: ocr
+    input:
+      document: "{{ input.documents.0 }}"
+  - name: totals
+    job_type: extract-totals
+    depends_on: [ocr]
+    on_failure: continue
+    input:
+      text: "{{ steps.ocr.output.text }}"
+      pages: "{{ steps.ocr.output.pages }}"
+      label: "{{ input.customer }}: {{ steps.ocr.output.pages }} pages"
+      missing: "{{ steps.ocr.output.language }}"
+"#).unwrap();
+
+    let run_input = json!({ "documents": ["s3://bucket/invoice.pdf"], "customer": "Acme" });
+    let outputs = HashMap::from([("ocr".to_string(), json!({ "text": "Total 12", "pages": 2 }))]);
+
+    assert_eq!(definition.steps[0].render_input(&run_input, &HashMap::new()), json!({ "document": "s3://bucket/invoice.pdf" }));
+    assert_eq!(
+        definition.steps[1].render_input(&run_input, &outputs),
+        json!({ "text": "Total 12", "pages": 2, "label": "Acme: 2 pages", "missing": null })
+    );
+}
+
+#[test]
+fn definitions_referring_to_unavailable_values_are_rejected() {
+    let unknown_dependency = "name: p\nsteps:\n  - name: a\n    job_type: t\n    depends_on: [b]\n";
+    assert!(matches!(PipelineDefinition::parse(unknown_dependency), Err(PipelineError::UnknownDependency { .. })));
+
+    let sibling_output = "name: p\nsteps:\n  - name: a\n    job_type: t\n  - name: b\n    job_type: t\n    input:\n      x: \"{{ steps.a.output }}\"\n";
+    assert!(matches!(PipelineDefinition::parse(sibling_output), Err(PipelineError::UnavailableOutput { .. })));
+
+    let bad_reference = "name: p\nsteps:\n  - name: a\n    job_type: t\n    input:\n      x: \"{{ env.HOME }}\"\n";
+    assert!(matches!(PipelineDefinition::parse(bad_reference), Err(PipelineError::InvalidReference { .. })));
+
+    let unknown_field = "name: p\nsteps:\n  - name: a\n    job_type: t\n    retries: 3\n";
+    assert!(matches!(PipelineDefinition::parse(unknown_field), Err(PipelineError::InvalidYaml(_))));
+}
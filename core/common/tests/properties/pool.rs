@@ -0,0 +1,34 @@
+use innosystem_common::database::PoolStatus;
+use proptest::prelude::*;
+
+/// A pool with no more connections than its maximum, some of them idle
+fn status() -> impl Strategy<Value = PoolStatus> {
+    (0..200u32)
+        .prop_flat_map(|max_size| (Just(max_size), 0..=max_size))
+        .prop_flat_map(|(max_size, connections)| (Just(max_size), Just(connections), 0..=connections))
+        .prop_map(|(max_size, connections, idle_connections)| PoolStatus {
+            max_size,
+            connections,
+            idle_connections,
+            ..PoolStatus::default()
+        })
+}
+
+proptest! {
+    /// Connections in use are those open and not idle, and never exceed the pool
+    #[test]
+    fn connections_in_use_fit_the_pool(status in status()) {
+        prop_assert_eq!(status.in_use() + status.idle_connections, status.connections);
+        prop_assert!(status.in_use() <= status.max_size);
+    }
+
+    /// Utilization runs from 0 for an idle pool to 1 for a pool with every connection
+    /// checked out
+    #[test]
+    fn utilization_is_a_share_of_the_pool(status in status()) {
+        let utilization = status.utilization();
+        prop_assert!((0.0..=1.0).contains(&utilization));
+        prop_assert_eq!(utilization == 0.0, status.in_use() == 0);
+        prop_assert_eq!(utilization == 1.0, status.max_size > 0 && status.in_use() == status.max_size);
+    }
+}
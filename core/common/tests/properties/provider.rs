@@ -0,0 +1,74 @@
+use chrono::{Duration, NaiveDate};
+use innosystem_common::models::provider::{Provider, ProviderStatus};
+use proptest::prelude::*;
+use uuid::Uuid;
+
+fn provider(failure_rate_threshold: f64, min_jobs: i32, retry_delay_secs: i32, status: ProviderStatus) -> Provider {
+    Provider {
+        id: Uuid::new_v4(),
+        name: "geocoder".to_string(),
+        ping_url: None,
+        failure_rate_threshold,
+        min_jobs,
+        retry_delay_secs,
+        status: status.as_str().to_string(),
+        status_reason: None,
+        last_checked_at: None,
+        status_changed_at: None,
+        created_at: None,
+        updated_at: None,
+    }
+}
+
+proptest! {
+    #[test]
+    fn a_failed_ping_takes_the_provider_down_whatever_its_jobs(
+        finished in 0u64..1000,
+        failed_share in 0.0f64..=1.0,
+        threshold in 0.01f64..=1.0,
+        min_jobs in 1i32..100,
+    ) {
+        let failed = (finished as f64 * failed_share) as u64;
+        let provider = provider(threshold, min_jobs, 300, ProviderStatus::Up);
+
+        let (status, reason) = provider.assess(Some(&Err("timed out".to_string())), finished, failed);
+        prop_assert_eq!(status, ProviderStatus::Down);
+        prop_assert!(reason.unwrap().contains("timed out"));
+    }
+
+    #[test]
+    fn the_failure_rate_counts_once_enough_jobs_finished(
+        finished in 0u64..1000,
+        failed_share in 0.0f64..=1.0,
+        threshold in 0.01f64..=1.0,
+        min_jobs in 1i32..100,
+        pinged in any::<bool>(),
+    ) {
+        let failed = (finished as f64 * failed_share) as u64;
+        let provider = provider(threshold, min_jobs, 300, ProviderStatus::Up);
+        let ping = pinged.then_some(Ok(()));
+
+        let (status, reason) = provider.assess(ping.as_ref(), finished, failed);
+        let expected = if finished >= min_jobs as u64 && failed as f64 / finished as f64 >= threshold {
+            ProviderStatus::Down
+        } else {
+            ProviderStatus::Up
+        };
+        prop_assert_eq!(status, expected);
+        prop_assert_eq!(reason.is_some(), status == ProviderStatus::Down);
+    }
+
+    #[test]
+    fn jobs_are_held_back_by_the_retry_delay_only_while_the_provider_is_down(
+        retry_delay_secs in 1i32..86_400,
+        offset_secs in 0i64..31_536_000,
+        down in any::<bool>(),
+    ) {
+        let now = NaiveDate::from_ymd_opt(2025, 1, 1).unwrap().and_hms_opt(0, 0, 0).unwrap().and_utc() + Duration::seconds(offset_secs);
+        let status = if down { ProviderStatus::Down } else { ProviderStatus::Up };
+        let provider = provider(0.5, 10, retry_delay_secs, status);
+
+        let expected = down.then(|| now + Duration::seconds(retry_delay_secs as i64));
+        prop_assert_eq!(provider.hold_until(now), expected);
+    }
+}
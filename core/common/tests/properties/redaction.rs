@@ -0,0 +1,125 @@
+use chrono::Utc;
+use innosystem_common::models::job_type::{JobType, ProcessorType};
+use innosystem_common::models::redaction::{redact_output, RedactionRule, REDACTED};
+use proptest::prelude::*;
+use serde_json::{json, Value};
+use uuid::Uuid;
+
+const KEYS: [&str; 5] = ["token", "id", "items", "secret", "name"];
+
+/// Arbitrary JSON documents built from a few member names, so rules find matches
+fn document() -> impl Strategy<Value = Value> {
+    let leaf = prop_oneof![
+        Just(Value::Null),
+        any::<bool>().prop_map(Value::from),
+        any::<i64>().prop_map(Value::from),
+        "[a-z]{0,8}".prop_map(Value::from),
+    ];
+    leaf.prop_recursive(4, 40, 5, |inner| {
+        prop_oneof![
+            prop::collection::vec(inner.clone(), 0..5).prop_map(Value::Array),
+            prop::collection::vec((prop::sample::select(KEYS.to_vec()), inner), 0..5)
+                .prop_map(|members| Value::Object(members.into_iter().map(|(k, v)| (k.to_string(), v)).collect())),
+        ]
+    })
+}
+
+/// Replace the value of every member with the given name, at any depth
+fn replace_members(value: &mut Value, name: &str) {
+    match value {
+        Value::Object(map) => {
+            for (key, child) in map.iter_mut() {
+                if key == name {
+                    *child = Value::String(REDACTED.to_string());
+                } else {
+                    replace_members(child, name);
+                }
+            }
+        }
+        Value::Array(items) => items.iter_mut().for_each(|item| replace_members(item, name)),
+        _ => {}
+    }
+}
+
+fn job_type(rules: &[&str], retention_hours: Option<i32>) -> JobType {
+    let mut job_type = JobType::new("echo".to_string(), String::new(), ProcessorType::Sync, 10);
+    job_type.redaction_rules = rules.iter().map(|rule| rule.to_string()).collect();
+    job_type.unredacted_retention_hours = retention_hours;
+    job_type
+}
+
+#[test]
+fn rules_use_a_subset_of_jsonpath() {
+    for rule in ["$.token", "$['x-api-key']", "$[\"auth\"].bearer", "$.items[*].secret", "$.pages[0].body", "$..password", "$.*.token"] {
+        assert!(RedactionRule::parse(rule).is_ok(), "{} should parse", rule);
+    }
+    for rule in ["", "$", "token", "$.", "$..", "$[", "$[-1]", "$.items[?(@.secret)]", "$token"] {
+        assert!(RedactionRule::parse(rule).is_err(), "{} should be rejected", rule);
+    }
+}
+
+#[test]
+fn rules_select_members_elements_and_descendants() {
+    let mut output = json!({
+        "token": "abc",
+        "items": [{ "secret": "s1", "name": "a" }, { "secret": "s2" }],
+        "pages": [{ "body": "first" }, { "body": "second" }],
+        "nested": { "deeper": { "password": "pw" } },
+        "x-api-key": "key",
+    });
+    let count: usize = ["$.token", "$.items[*].secret", "$.pages[1].body", "$..password", "$['x-api-key']"].iter()
+        .map(|rule| RedactionRule::parse(rule).unwrap().apply(&mut output))
+        .sum();
+
+    assert_eq!(count, 6);
+    assert_eq!(output, json!({
+        "token": REDACTED,
+        "items": [{ "secret": REDACTED, "name": "a" }, { "secret": REDACTED }],
+        "pages": [{ "body": "first" }, { "body": REDACTED }],
+        "nested": { "deeper": { "password": REDACTED } },
+        "x-api-key": REDACTED,
+    }));
+}
+
+#[test]
+fn unredacted_output_is_kept_only_when_something_was_redacted() {
+    let now = Utc::now();
+    let job_id = Uuid::new_v4();
+    let retaining = job_type(&["$.token"], Some(24));
+
+    let redacted = redact_output(&retaining, job_id, json!({ "token": "abc" }), now).unwrap();
+    assert_eq!(redacted.output, json!({ "token": REDACTED }));
+    let unredacted = redacted.unredacted.unwrap();
+    assert_eq!(unredacted.output_data, json!({ "token": "abc" }));
+    assert_eq!(unredacted.expires_at, now + chrono::Duration::hours(24));
+
+    assert!(redact_output(&retaining, job_id, json!({ "id": 1 }), now).unwrap().unredacted.is_none());
+    assert!(redact_output(&job_type(&["$.token"], None), job_id, json!({ "token": "abc" }), now).unwrap().unredacted.is_none());
+    assert!(redact_output(&job_type(&["token"], None), job_id, json!({}), now).is_err());
+}
+
+proptest! {
+    /// A descendant rule replaces the member at every depth and nothing else
+    #[test]
+    fn descendant_rules_redact_every_match(document in document()) {
+        let rule = RedactionRule::parse("$..token").unwrap();
+        let mut expected = document.clone();
+        replace_members(&mut expected, "token");
+
+        let mut redacted = document.clone();
+        rule.apply(&mut redacted);
+        prop_assert_eq!(&redacted, &expected);
+    }
+
+    /// Redacting an already redacted output changes nothing
+    #[test]
+    fn redaction_is_idempotent(document in document(), rule in prop::sample::select(vec!["$..token", "$.items[*].secret", "$.*.id", "$.items[0]"])) {
+        let rule = RedactionRule::parse(rule).unwrap();
+        let mut redacted = document;
+        rule.apply(&mut redacted);
+        let once = redacted.clone();
+
+        prop_assert_eq!(rule.apply(&mut redacted), 0);
+        prop_assert_eq!(redacted, once);
+    }
+}
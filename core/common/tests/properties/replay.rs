@@ -0,0 +1,140 @@
+use chrono::Utc;
+use innosystem_common::models::job::{Job, PriorityLevel};
+use innosystem_common::models::redaction::{RedactionRule, REDACTED};
+use innosystem_common::models::replay::{diff_paths, Anonymizer, NewReplayCase, ReplayCase, ReplayComparison, ReplayOutcome, PSEUDONYM_PREFIX};
+use proptest::prelude::*;
+use serde_json::{json, Value};
+use uuid::Uuid;
+
+const KEYS: [&str; 4] = ["email", "name", "items", "id"];
+
+/// Arbitrary JSON documents built from a few member names, so rules find matches
+fn document() -> impl Strategy<Value = Value> {
+    let leaf = prop_oneof![
+        Just(Value::Null),
+        any::<bool>().prop_map(Value::from),
+        (0i64..5).prop_map(Value::from),
+        "[a-c]{0,2}".prop_map(Value::from),
+    ];
+    leaf.prop_recursive(4, 40, 5, |inner| {
+        prop_oneof![
+            prop::collection::vec(inner.clone(), 0..5).prop_map(Value::Array),
+            prop::collection::vec((prop::sample::select(KEYS.to_vec()), inner), 0..5)
+                .prop_map(|members| Value::Object(members.into_iter().map(|(k, v)| (k.to_string(), v)).collect())),
+        ]
+    })
+}
+
+fn anonymizer(rules: &[&str]) -> Anonymizer {
+    Anonymizer::new(rules.iter().map(|rule| RedactionRule::parse(rule).unwrap()).collect())
+}
+
+fn case_with_baseline(output: Value, cost_cents: i32) -> ReplayCase {
+    ReplayCase {
+        id: Uuid::new_v4(),
+        job_type_id: Uuid::new_v4(),
+        source_job_id: Uuid::new_v4(),
+        priority: 1,
+        input_data: json!({}),
+        markup_rate: 0,
+        sampled_at: Utc::now(),
+        baseline_output: Some(output),
+        baseline_cost_cents: Some(cost_cents),
+        baseline_version: Some("1.0.0".to_string()),
+        baseline_recorded_at: Some(Utc::now()),
+    }
+}
+
+#[test]
+fn sampled_inputs_keep_equal_values_equal() {
+    let anonymizer = anonymizer(&["$..email", "$.owner"]);
+    let job = Job::new(Uuid::new_v4(), Uuid::new_v4(), json!({
+        "owner": "ada@example.com",
+        "users": [{ "email": "ada@example.com", "age": 36 }, { "email": "grace@example.com", "age": 85 }],
+        "token": REDACTED,
+    }), PriorityLevel::High, 100);
+
+    let case = NewReplayCase::sample(&job, 1000, &anonymizer);
+    assert_eq!(case.source_job_id, job.id);
+    assert_eq!(case.priority, PriorityLevel::High.as_i32());
+    assert_eq!(case.markup_rate, 1000);
+
+    let input = &case.input_data;
+    let owner = input["owner"].as_str().unwrap();
+    assert!(owner.starts_with(PSEUDONYM_PREFIX));
+    assert_eq!(input["users"][0]["email"], input["owner"]);
+    assert_ne!(input["users"][1]["email"], input["owner"]);
+    assert_eq!(input["users"][1]["age"], 85);
+    assert_eq!(input["token"], REDACTED);
+    assert!(!input.to_string().contains("example.com"));
+}
+
+#[test]
+fn differences_are_reported_by_path() {
+    let baseline = json!({ "text": "HELLO", "items": [1, 2], "meta": { "at": "2025-01-01", "x-id": 1 } });
+    let replay = json!({ "text": "Hello", "items": [1, 2, 3], "meta": { "at": "2025-02-01", "x-id": 1 }, "extra": null });
+
+    assert_eq!(diff_paths(&baseline, &replay), vec!["$.extra", "$.items[2]", "$.meta.at", "$.text"]);
+    assert_eq!(diff_paths(&json!({ "a b": 1 }), &json!({ "a b": 2 })), vec!["$['a b']"]);
+    assert!(diff_paths(&baseline, &baseline).is_empty());
+}
+
+#[test]
+fn ignored_paths_and_costs_decide_whether_a_replay_matches() {
+    let case = case_with_baseline(json!({ "status": "success", "sent_at": "2025-01-01T00:00:00Z" }), 150);
+    let output = json!({ "status": "success", "sent_at": "2025-06-01T00:00:00Z" });
+    let ignored = [RedactionRule::parse("$.sent_at").unwrap()];
+
+    let strict = ReplayComparison::compare(&case, &output, 150, &[]).unwrap();
+    assert_eq!(strict.output_differences, vec!["$.sent_at"]);
+    assert!(!strict.matches());
+
+    let lenient = ReplayComparison::compare(&case, &output, 150, &ignored).unwrap();
+    assert!(lenient.matches());
+    assert!(!ReplayOutcome::Compared(lenient).is_regression());
+
+    let repriced = ReplayComparison::compare(&case, &output, 200, &ignored).unwrap();
+    assert!(repriced.output_differences.is_empty());
+    assert!(ReplayOutcome::Compared(repriced).is_regression());
+
+    let mut unrecorded = case.clone();
+    unrecorded.baseline_output = None;
+    assert!(ReplayComparison::compare(&unrecorded, &output, 150, &ignored).is_none());
+    assert!(!ReplayOutcome::Recorded { case_id: case.id, cost_cents: 150 }.is_regression());
+    assert!(ReplayOutcome::Failed { case_id: case.id, error: "boom".to_string() }.is_regression());
+}
+
+proptest! {
+    /// Anonymizing replaces exactly the values the redaction rule would redact
+    #[test]
+    fn anonymization_selects_what_redaction_does(document in document(), rule in prop::sample::select(vec!["$..email", "$.items[*].id", "$.*.name", "$.items[0]"])) {
+        let mut redacted = document.clone();
+        let redacted_count = RedactionRule::parse(rule).unwrap().apply(&mut redacted);
+
+        let mut anonymized = document.clone();
+        let anonymized_count = anonymizer(&[rule]).anonymize(&mut anonymized);
+        prop_assert_eq!(anonymized_count, redacted_count);
+
+        // The same values are replaced: redacting the pseudonyms yields the redacted document
+        RedactionRule::parse(rule).unwrap().apply(&mut anonymized);
+        prop_assert_eq!(anonymized, redacted);
+    }
+
+    /// Anonymizing twice changes nothing the first pass did not
+    #[test]
+    fn anonymization_is_idempotent(document in document()) {
+        let anonymizer = anonymizer(&["$..email", "$..id"]);
+        let mut anonymized = document;
+        anonymizer.anonymize(&mut anonymized);
+        let once = anonymized.clone();
+        prop_assert_eq!(anonymizer.anonymize(&mut anonymized), 0);
+        prop_assert_eq!(anonymized, once);
+    }
+
+    /// Documents differ exactly when some path is reported
+    #[test]
+    fn diffs_are_empty_only_for_equal_documents(left in document(), right in document()) {
+        prop_assert_eq!(diff_paths(&left, &right).is_empty(), left == right);
+        prop_assert!(diff_paths(&left, &left).is_empty());
+    }
+}
@@ -0,0 +1,104 @@
+use innosystem_common::models::request_signature::{SignatureError, SignedRequest};
+use proptest::prelude::*;
+
+const TOLERANCE_SECS: i64 = 300;
+
+fn nonce() -> impl Strategy<Value = String> {
+    "[A-Za-z0-9_-]{16,64}"
+}
+
+fn method() -> impl Strategy<Value = String> {
+    prop::sample::select(vec!["GET", "POST", "PUT", "DELETE"]).prop_map(str::to_string)
+}
+
+prop_compose! {
+    fn request()(
+        method in method(),
+        path in "/[a-z0-9/]{0,40}(\\?[a-z]=[0-9]{1,3})?",
+        timestamp in 1_600_000_000i64..2_000_000_000,
+        nonce in nonce(),
+        body in prop::collection::vec(any::<u8>(), 0..256),
+    ) -> (String, String, i64, String, Vec<u8>) {
+        (method, path, timestamp, nonce, body)
+    }
+}
+
+fn signed((method, path, timestamp, nonce, body): &(String, String, i64, String, Vec<u8>)) -> SignedRequest<'_> {
+    SignedRequest { method, path, timestamp: *timestamp, nonce, body }
+}
+
+proptest! {
+    #[test]
+    fn requests_verify_with_the_secret_they_were_signed_with(
+        parts in request(),
+        secret in "[a-z0-9]{8,64}",
+        skew_secs in -TOLERANCE_SECS..=TOLERANCE_SECS,
+    ) {
+        let request = signed(&parts);
+        let signature = request.sign(&secret);
+        prop_assert_eq!(request.verify(&secret, &signature, request.timestamp + skew_secs, TOLERANCE_SECS), Ok(()));
+        prop_assert_eq!(
+            request.verify(&format!("{}x", secret), &signature, request.timestamp, TOLERANCE_SECS),
+            Err(SignatureError::Mismatch)
+        );
+    }
+
+    #[test]
+    fn any_change_to_a_signed_part_of_the_request_breaks_the_signature(
+        parts in request(),
+        other in request(),
+        secret in "[a-z0-9]{8,64}",
+    ) {
+        let signature = signed(&parts).sign(&secret);
+        let (method, path, timestamp, nonce, body) = parts.clone();
+        let changed = [
+            (other.0.clone(), path.clone(), timestamp, nonce.clone(), body.clone()),
+            (method.clone(), other.1.clone(), timestamp, nonce.clone(), body.clone()),
+            (method.clone(), path.clone(), timestamp + 1, nonce.clone(), body.clone()),
+            (method.clone(), path.clone(), timestamp, other.3.clone(), body.clone()),
+            (method.clone(), path.clone(), timestamp, nonce.clone(), other.4.clone()),
+        ];
+        for tampered in changed.iter().filter(|tampered| **tampered != parts) {
+            let request = signed(tampered);
+            prop_assert_eq!(request.verify(&secret, &signature, request.timestamp, TOLERANCE_SECS), Err(SignatureError::Mismatch));
+        }
+    }
+
+    #[test]
+    fn requests_signed_outside_the_tolerance_are_refused(
+        parts in request(),
+        skew_secs in (TOLERANCE_SECS + 1)..100_000,
+        late in any::<bool>(),
+    ) {
+        let request = signed(&parts);
+        let signature = request.sign("secret");
+        let now = if late { request.timestamp + skew_secs } else { request.timestamp - skew_secs };
+        prop_assert_eq!(
+            request.verify("secret", &signature, now, TOLERANCE_SECS),
+            Err(SignatureError::StaleTimestamp { skew_secs: skew_secs.unsigned_abs(), tolerance_secs: TOLERANCE_SECS })
+        );
+    }
+
+    #[test]
+    fn timestamps_at_the_ends_of_the_range_are_refused(
+        timestamp in prop_oneof![Just(i64::MIN), Just(i64::MAX), any::<i64>()],
+        now in 1_600_000_000i64..2_000_000_000,
+    ) {
+        let request = SignedRequest { method: "GET", path: "/jobs", timestamp, nonce: "0123456789abcdef", body: b"" };
+        let signature = request.sign("secret");
+        let skew_secs = now.abs_diff(timestamp);
+        prop_assume!(skew_secs > TOLERANCE_SECS as u64);
+        prop_assert_eq!(
+            request.verify("secret", &signature, now, TOLERANCE_SECS),
+            Err(SignatureError::StaleTimestamp { skew_secs, tolerance_secs: TOLERANCE_SECS })
+        );
+    }
+
+    #[test]
+    fn nonces_too_short_or_with_other_characters_are_refused(parts in request(), nonce in "[A-Za-z0-9]{0,15}|[A-Za-z0-9]{8}[ .:/+=][A-Za-z0-9]{8}") {
+        let (method, path, timestamp, _, body) = parts;
+        let request = SignedRequest { method: &method, path: &path, timestamp, nonce: &nonce, body: &body };
+        let signature = request.sign("secret");
+        prop_assert_eq!(request.verify("secret", &signature, timestamp, TOLERANCE_SECS), Err(SignatureError::MalformedNonce));
+    }
+}
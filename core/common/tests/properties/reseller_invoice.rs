@@ -0,0 +1,118 @@
+use std::collections::HashMap;
+
+use innosystem_common::models::job::PriorityLevel;
+use innosystem_common::models::job_cost::CostBreakdown;
+use innosystem_common::models::reseller::Reseller;
+use innosystem_common::models::reseller_invoice::{period_bounds, InvoiceAmounts, JobTypeCharges, ResellerInvoice};
+use innosystem_common::pdf;
+use proptest::prelude::*;
+use uuid::Uuid;
+
+/// Charges of jobs costing `base_cents` each, marked up by `markup_rate` basis points
+fn charges(customer_id: Uuid, job_type_id: Uuid, base_cents: &[i32], markup_rate: i32) -> JobTypeCharges {
+    JobTypeCharges {
+        customer_id,
+        job_type_id,
+        jobs: base_cents.len() as i64,
+        cost: base_cents.iter().map(|base| CostBreakdown::completed(*base, &PriorityLevel::Medium, markup_rate)).sum(),
+    }
+}
+
+proptest! {
+    /// Every line owes the platform what was charged less the markup and the commission,
+    /// and subtotals and totals add up the lines
+    #[test]
+    fn invoice_amounts_add_up(
+        costs in prop::collection::vec((0..3usize, 0..3usize, prop::collection::vec(1..100_000i32, 1..5)), 0..20),
+        commission_rate in 0..5_000i32,
+        markup_rate in 0..5_000i32,
+    ) {
+        let customer_ids: Vec<Uuid> = (0..3).map(|_| Uuid::new_v4()).collect();
+        let job_type_ids: Vec<Uuid> = (0..3).map(|_| Uuid::new_v4()).collect();
+        let reseller = Reseller::new("Acme".to_string(), "acme@example.com".to_string(), "key".to_string(), commission_rate);
+        let charged: Vec<JobTypeCharges> = costs.iter()
+            .map(|(customer, job_type, base_cents)| charges(customer_ids[*customer], job_type_ids[*job_type], base_cents, markup_rate))
+            .collect();
+        let charged_cents: i64 = charged.iter().map(|charge| charge.cost.total_cents).sum();
+        let jobs: i64 = charged.iter().map(|charge| charge.jobs).sum();
+
+        let invoice = ResellerInvoice::build(&reseller, "2025-04", false, charged, &HashMap::new(), &HashMap::new()).unwrap();
+
+        for line in &invoice.lines {
+            let amounts = line.amounts;
+            prop_assert_eq!(amounts.platform_cents, amounts.charged_cents - amounts.markup_cents);
+            prop_assert_eq!(amounts.due_cents, amounts.platform_cents - amounts.commission_cents);
+            prop_assert!(amounts.commission_cents >= 0 && amounts.commission_cents <= amounts.platform_cents);
+        }
+        let lines: InvoiceAmounts = invoice.lines.iter().map(|line| line.amounts).sum();
+        let customers: InvoiceAmounts = invoice.customers.iter().map(|subtotal| subtotal.amounts).sum();
+        let job_types: InvoiceAmounts = invoice.job_types.iter().map(|subtotal| subtotal.amounts).sum();
+        prop_assert_eq!(lines, invoice.totals);
+        prop_assert_eq!(customers, invoice.totals);
+        prop_assert_eq!(job_types, invoice.totals);
+        prop_assert_eq!(invoice.totals.charged_cents, charged_cents);
+        prop_assert_eq!(invoice.totals.jobs, jobs);
+
+        // One line per customer and job type charged
+        let mut pairs: Vec<(Uuid, Uuid)> = invoice.lines.iter().map(|line| (line.customer_id, line.job_type_id)).collect();
+        pairs.sort();
+        pairs.dedup();
+        prop_assert_eq!(pairs.len(), invoice.lines.len());
+    }
+
+    /// The CSV export has a row per line and a row of totals
+    #[test]
+    fn invoice_csv_has_a_row_per_line(
+        costs in prop::collection::vec((0..3usize, prop::collection::vec(1..100_000i32, 1..5)), 0..10),
+        name in "[a-z ,\"]{1,12}",
+    ) {
+        let customer_id = Uuid::new_v4();
+        let job_type_ids: Vec<Uuid> = (0..3).map(|_| Uuid::new_v4()).collect();
+        let reseller = Reseller::new("Acme".to_string(), "acme@example.com".to_string(), "key".to_string(), 1000);
+        let charged = costs.iter().map(|(job_type, base_cents)| charges(customer_id, job_type_ids[*job_type], base_cents, 1500)).collect();
+        let customer_names = HashMap::from([(customer_id, name)]);
+
+        let invoice = ResellerInvoice::build(&reseller, "2025-04", true, charged, &customer_names, &HashMap::new()).unwrap();
+        let csv = invoice.to_csv();
+
+        // Names are quoted when they contain separators, so count rows by their leading ID
+        let rows: Vec<&str> = csv.lines().filter(|row| row.starts_with(&customer_id.to_string())).collect();
+        prop_assert_eq!(rows.len(), invoice.lines.len());
+        let totals = format!(",{},{},{},{},{},{}", invoice.totals.jobs, invoice.totals.charged_cents, invoice.totals.markup_cents,
+            invoice.totals.platform_cents, invoice.totals.commission_cents, invoice.totals.due_cents);
+        prop_assert!(csv.trim_end().ends_with(&totals));
+    }
+
+    /// PDF statements start a page every `LINES_PER_PAGE` lines and point at each object
+    #[test]
+    fn pdf_documents_are_paginated(lines in prop::collection::vec("[ -~é]{0,100}", 0..200)) {
+        let document = String::from_utf8(pdf::text_document(&lines)).unwrap();
+        let pages = lines.len().div_ceil(pdf::LINES_PER_PAGE).max(1);
+
+        prop_assert!(document.starts_with("%PDF-1.4\n"));
+        prop_assert!(document.ends_with("%%EOF\n"));
+        prop_assert!(document.is_ascii());
+        let count = format!("/Count {} ", pages);
+        prop_assert!(document.contains(&count));
+        prop_assert_eq!(document.matches("/Type /Page ").count(), pages);
+
+        // Each entry of the cross-reference table points at its object
+        let xref = document.rfind("xref\n").unwrap();
+        for (index, entry) in document[xref..].lines().skip(3).take(3 + 2 * pages).enumerate() {
+            let offset: usize = entry[..10].parse().unwrap();
+            let object = format!("{} 0 obj", index + 1);
+            prop_assert!(document[offset..].starts_with(&object));
+        }
+    }
+}
+
+#[test]
+fn invoice_periods_are_calendar_months() {
+    let (start, end) = period_bounds("2024-12").unwrap();
+    assert_eq!(start.to_rfc3339(), "2024-12-01T00:00:00+00:00");
+    assert_eq!(end.to_rfc3339(), "2025-01-01T00:00:00+00:00");
+
+    for invalid in ["2024-13", "2024-1", "24-01", "2024", "2024-01-01", "abcd-01", ""] {
+        assert!(period_bounds(invalid).is_err(), "{} accepted", invalid);
+    }
+}
@@ -0,0 +1,75 @@
+use innosystem_common::models::response_policy::{
+    BinaryResponses, CapturedResponse, ResponseKind, ResponsePolicy, ResponsePolicyError, MAX_RESPONSE_BYTES,
+};
+use proptest::prelude::*;
+use serde_json::json;
+
+proptest! {
+    /// JSON bodies within the limit become structured output; cut or unparsable ones
+    /// and any body when parsing is off are kept as text
+    #[test]
+    fn json_responses_become_structured_output(n in any::<i64>(), name in "[a-z ]{0,20}", parse_json in any::<bool>()) {
+        let body = serde_json::to_vec(&json!({ "n": n, "name": name })).unwrap();
+        let policy = ResponsePolicy { parse_json, ..ResponsePolicy::default() };
+        match policy.capture(Some("application/json; charset=utf-8"), &body) {
+            CapturedResponse::Json(value) => {
+                prop_assert!(parse_json);
+                prop_assert_eq!(value, json!({ "n": n, "name": name }));
+            }
+            CapturedResponse::Text { text, truncated } => {
+                prop_assert!(!parse_json);
+                prop_assert_eq!(text.as_bytes(), &body[..]);
+                prop_assert!(!truncated);
+            }
+            CapturedResponse::Binary => prop_assert!(false, "JSON captured as binary"),
+        }
+
+        let cut = ResponsePolicy { max_captured_bytes: body.len() - 1, ..ResponsePolicy::default() };
+        let captured = cut.capture(Some("application/problem+json"), &body[..cut.read_limit().min(body.len())]);
+        let truncated = matches!(captured, CapturedResponse::Text { truncated: true, .. });
+        prop_assert!(truncated);
+    }
+
+    /// Text is cut at the limit without splitting a character
+    #[test]
+    fn text_responses_are_cut_at_the_limit(text in "[a-zé€ ]{0,200}", limit in 1usize..100) {
+        let policy = ResponsePolicy { max_captured_bytes: limit, binary: BinaryResponses::Discard, ..ResponsePolicy::default() };
+        let body = text.as_bytes();
+        let read = &body[..body.len().min(policy.read_limit())];
+        match policy.capture(Some("text/plain"), read) {
+            CapturedResponse::Text { text: kept, truncated } => {
+                prop_assert!(kept.len() <= limit);
+                prop_assert!(text.starts_with(&kept));
+                prop_assert_eq!(truncated, body.len() > limit);
+            }
+            other => prop_assert!(false, "text captured as {:?}", other),
+        }
+    }
+
+    /// Bodies are binary unless their content type says text or JSON, or they have
+    /// none and are valid UTF-8
+    #[test]
+    fn binary_responses_are_told_apart_by_content_type(body in prop::collection::vec(any::<u8>(), 0..64)) {
+        prop_assert_eq!(ResponseKind::of(Some("image/png"), &body), ResponseKind::Binary);
+        prop_assert_eq!(ResponseKind::of(Some("application/pdf"), &body), ResponseKind::Binary);
+        prop_assert_eq!(ResponseKind::of(Some("TEXT/CSV"), &body), ResponseKind::Text);
+        prop_assert_eq!(ResponseKind::of(Some("application/vnd.api+json"), &body), ResponseKind::Json);
+        let sniffed = if std::str::from_utf8(&body).is_ok() { ResponseKind::Text } else { ResponseKind::Binary };
+        prop_assert_eq!(ResponseKind::of(None, &body), sniffed);
+    }
+
+    /// Limits must be positive and bounded, so runners never buffer arbitrary responses
+    #[test]
+    fn response_policy_limits_are_bounded(max_captured_bytes in 0usize..=MAX_RESPONSE_BYTES + 1) {
+        let policy = ResponsePolicy { max_captured_bytes, ..ResponsePolicy::default() };
+        if (1..=MAX_RESPONSE_BYTES).contains(&max_captured_bytes) {
+            prop_assert_eq!(policy.validate(), Ok(()));
+            prop_assert!(policy.read_limit() > max_captured_bytes);
+        } else {
+            prop_assert_eq!(
+                policy.validate(),
+                Err(ResponsePolicyError::InvalidLimit { field: "max_captured_bytes", value: max_captured_bytes, max: MAX_RESPONSE_BYTES })
+            );
+        }
+    }
+}
@@ -0,0 +1,86 @@
+use std::collections::BTreeMap;
+
+use innosystem_common::models::runner::{environment_drift, RunnerEnvironment};
+use proptest::prelude::*;
+use uuid::Uuid;
+
+fn environment_strategy() -> impl Strategy<Value = RunnerEnvironment> {
+    (
+        prop::sample::select(vec!["linux", "macos", "windows"]),
+        prop::sample::select(vec!["x86_64", "aarch64"]),
+        1u32..128,
+        prop::collection::btree_map("[a-z]{1,12}", "[0-9]{1,2}\\.[0-9]{1,2}\\.[0-9]{1,2}", 0..6),
+    )
+        .prop_map(|(os, arch, processors, libraries)| RunnerEnvironment {
+            os: os.to_string(),
+            arch: arch.to_string(),
+            processors,
+            libraries,
+        })
+}
+
+proptest! {
+    #[test]
+    fn runners_in_the_same_environment_share_a_fingerprint_and_do_not_drift(
+        environment in environment_strategy(),
+        runners in 1usize..8,
+    ) {
+        let environments: Vec<(Uuid, RunnerEnvironment)> = (0..runners)
+            .map(|_| (Uuid::new_v4(), environment.clone()))
+            .collect();
+
+        prop_assert_eq!(environment.clone().fingerprint(), environment.fingerprint());
+        prop_assert!(environment_drift(&environments).is_empty());
+    }
+
+    #[test]
+    fn a_different_library_version_is_reported_with_the_runners_holding_each_version(
+        environment in environment_strategy(),
+        library in "[a-z]{1,12}",
+        (version, other_version) in ("[0-9]{1,2}\\.[0-9]{1,2}", "[0-9]{1,2}\\.[0-9]{1,2}")
+            .prop_filter("versions differ", |(a, b)| a != b),
+        agreeing in 1usize..5,
+    ) {
+        let mut current = environment.clone();
+        current.libraries.insert(library.clone(), version.clone());
+        let mut drifted = environment;
+        drifted.libraries.insert(library.clone(), other_version.clone());
+
+        let mut environments: Vec<(Uuid, RunnerEnvironment)> = (0..agreeing)
+            .map(|_| (Uuid::new_v4(), current.clone()))
+            .collect();
+        let drifted_id = Uuid::new_v4();
+        environments.push((drifted_id, drifted.clone()));
+
+        prop_assert_ne!(current.fingerprint(), drifted.fingerprint());
+        let drift = environment_drift(&environments);
+        prop_assert_eq!(drift.len(), 1);
+        prop_assert_eq!(&drift[0].setting, &format!("library:{}", library));
+
+        let by_value: BTreeMap<Option<String>, Vec<Uuid>> = drift[0].values.iter()
+            .map(|value| (value.value.clone(), value.runner_ids.clone()))
+            .collect();
+        let mut agreeing_ids: Vec<Uuid> = environments[..agreeing].iter().map(|(id, _)| *id).collect();
+        agreeing_ids.sort();
+        prop_assert_eq!(by_value.get(&Some(version)), Some(&agreeing_ids));
+        prop_assert_eq!(by_value.get(&Some(other_version)), Some(&vec![drifted_id]));
+    }
+
+    #[test]
+    fn a_missing_library_is_reported_without_a_value(
+        environment in environment_strategy(),
+        library in "[A-Z]{1,12}",
+        version in "[0-9]{1,2}\\.[0-9]{1,2}",
+    ) {
+        let mut with_library = environment.clone();
+        with_library.libraries.insert(library.clone(), version.clone());
+        let (with_id, without_id) = (Uuid::new_v4(), Uuid::new_v4());
+
+        let drift = environment_drift(&[(with_id, with_library), (without_id, environment)]);
+        prop_assert_eq!(drift.len(), 1);
+        prop_assert_eq!(&drift[0].setting, &format!("library:{}", library));
+        prop_assert_eq!(drift[0].values.len(), 2);
+        prop_assert!(drift[0].values.iter().any(|value| value.value.is_none() && value.runner_ids == vec![without_id]));
+        prop_assert!(drift[0].values.iter().any(|value| value.value == Some(version.clone()) && value.runner_ids == vec![with_id]));
+    }
+}
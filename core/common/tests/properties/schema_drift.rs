@@ -0,0 +1,62 @@
+use innosystem_common::schema_drift::{compare, compatible_types, expected_tables, parse_tables, ActualColumn, ExpectedColumn, SchemaDrift};
+
+const DIESEL_SCHEMA: &str = include_str!("../../src/diesel_schema/mod.rs");
+
+#[test]
+fn every_declared_table_is_parsed() {
+    let tables = expected_tables();
+    let jobs = tables.iter().find(|table| table.name == "jobs").unwrap();
+    assert!(jobs.columns.contains(&ExpectedColumn { name: "id".into(), sql_type: "Uuid".into(), nullable: false }));
+    assert!(jobs.columns.contains(&ExpectedColumn { name: "completed_at".into(), sql_type: "Timestamptz".into(), nullable: true }));
+
+    // Every table listed for joint queries is declared, and every declared type is known
+    let listed = DIESEL_SCHEMA.split("allow_tables_to_appear_in_same_query!(").nth(1).unwrap();
+    let listed = &listed[..listed.find(')').unwrap()];
+    for name in listed.split(|c: char| !(c.is_ascii_alphanumeric() || c == '_')).filter(|name| !name.is_empty()) {
+        assert!(tables.iter().any(|table| table.name == name), "{} is not parsed", name);
+    }
+    for column in tables.iter().flat_map(|table| &table.columns) {
+        assert!(compatible_types(&column.sql_type).is_some(), "{} has unknown type {}", column.name, column.sql_type);
+    }
+}
+
+#[test]
+fn reports_missing_mistyped_and_nullable_columns() {
+    let expected = parse_tables("table! {\n    things (id) {\n        id -> Uuid,\n        tags -> Array<Text>,\n        seen_at -> Timestamptz,\n        note -> Nullable<Text>,\n        size -> Integer,\n    }\n}\n\ntable! {\n    gone (id) {\n        id -> Uuid,\n    }\n}\n");
+    let column = |name: &str, udt: &str, nullable: &str| ActualColumn {
+        table_name: "things".into(),
+        column_name: name.into(),
+        udt_name: udt.into(),
+        is_nullable: nullable.into(),
+    };
+    let actual = vec![
+        column("id", "uuid", "NO"),
+        column("tags", "_text", "NO"),
+        column("seen_at", "timestamptz", "YES"),
+        column("size", "int8", "NO"),
+        column("extra", "text", "YES"),
+    ];
+
+    assert_eq!(compare(&expected, &actual), vec![
+        SchemaDrift::NullableColumn { table: "things".into(), column: "seen_at".into() },
+        SchemaDrift::MissingColumn { table: "things".into(), column: "note".into() },
+        SchemaDrift::TypeMismatch { table: "things".into(), column: "size".into(), expected: "Integer".into(), actual: "int8".into() },
+        SchemaDrift::MissingTable { table: "gone".into() },
+    ]);
+}
+
+#[test]
+fn reports_timestamps_without_time_zone() {
+    let expected = parse_tables("table! {\n    things (id) {\n        id -> Uuid,\n        seen_at -> Timestamptz,\n        logged_at -> Timestamp,\n    }\n}\n");
+    let column = |name: &str, udt: &str| ActualColumn {
+        table_name: "things".into(),
+        column_name: name.into(),
+        udt_name: udt.into(),
+        is_nullable: "NO".into(),
+    };
+    let actual = vec![column("id", "uuid"), column("seen_at", "timestamp"), column("logged_at", "timestamp")];
+
+    assert_eq!(compare(&expected, &actual), vec![
+        SchemaDrift::TypeMismatch { table: "things".into(), column: "seen_at".into(), expected: "Timestamptz".into(), actual: "timestamp".into() },
+    ]);
+}
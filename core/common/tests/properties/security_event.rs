@@ -0,0 +1,26 @@
+use innosystem_common::models::security_event::{key_prefix, lockout_secs};
+use proptest::prelude::*;
+
+proptest! {
+    /// Each lockout in a row lasts twice the previous one until the maximum is reached
+    #[test]
+    fn lockouts_double_up_to_the_maximum(base in 1u64..3_600, max in 1u64..1_000_000, level in 1u32..80) {
+        let current = lockout_secs(base, max, level);
+        let next = lockout_secs(base, max, level + 1);
+
+        prop_assert!(current <= max);
+        prop_assert_eq!(lockout_secs(base, max, 1), base.min(max));
+        prop_assert_eq!(next, current.saturating_mul(2).min(max));
+    }
+
+    /// The stored prefix never reveals more than half of a key, so it cannot be used to
+    /// authenticate
+    #[test]
+    fn key_prefixes_keep_most_of_the_key_secret(api_key in "[a-zA-Z0-9_]{0,64}", length in 0usize..80) {
+        let prefix = key_prefix(&api_key, length);
+
+        prop_assert!(api_key.starts_with(&prefix));
+        prop_assert!(prefix.len() <= length);
+        prop_assert!(prefix.len() * 2 <= api_key.len());
+    }
+}
@@ -0,0 +1,61 @@
+use innosystem_common::models::job::{Job, JobStatus, PriorityLevel};
+use innosystem_common::models::submission::SubmissionSummary;
+use proptest::prelude::*;
+use serde_json::json;
+use uuid::Uuid;
+
+fn status() -> impl Strategy<Value = JobStatus> {
+    prop::sample::select(vec![
+        JobStatus::Pending,
+        JobStatus::Scheduled,
+        JobStatus::Running,
+        JobStatus::Succeeded,
+        JobStatus::Failed,
+        JobStatus::Cancelled,
+        JobStatus::Expired,
+    ])
+}
+
+/// Jobs of a submission in any status, with what they were estimated and charged
+fn jobs() -> impl Strategy<Value = Vec<Job>> {
+    prop::collection::vec((status(), 0..10_000i32, 0..10_000i32), 0..50).prop_map(|jobs| {
+        jobs.into_iter()
+            .map(|(status, estimated_cost_cents, cost_cents)| {
+                let mut job = Job::new(Uuid::new_v4(), Uuid::new_v4(), json!({}), PriorityLevel::Medium, estimated_cost_cents);
+                job.status = status;
+                job.cost_cents = cost_cents;
+                job
+            })
+            .collect()
+    })
+}
+
+proptest! {
+    /// Every job is counted under exactly one status
+    #[test]
+    fn status_counts_add_up_to_the_total(jobs in jobs()) {
+        let summary = SubmissionSummary::of(&jobs);
+        prop_assert_eq!(summary.total, jobs.len());
+        prop_assert_eq!(
+            summary.pending + summary.running + summary.succeeded + summary.failed + summary.cancelled + summary.expired,
+            summary.total
+        );
+        prop_assert_eq!(summary.is_finished(), jobs.iter().all(|job| job.status.is_terminal()));
+    }
+
+    /// Only jobs that ran are charged, and only jobs that may still run are estimated
+    #[test]
+    fn costs_come_from_the_jobs_that_ran_or_may_still_run(jobs in jobs()) {
+        let summary = SubmissionSummary::of(&jobs);
+        let charged: i64 = jobs.iter()
+            .filter(|job| matches!(job.status, JobStatus::Succeeded | JobStatus::Failed))
+            .map(|job| i64::from(job.cost_cents))
+            .sum();
+        let estimated: i64 = jobs.iter()
+            .filter(|job| !job.status.is_terminal())
+            .map(|job| i64::from(job.estimated_cost_cents))
+            .sum();
+        prop_assert_eq!(summary.cost_cents, charged);
+        prop_assert_eq!(summary.estimated_cost_cents, estimated);
+    }
+}
@@ -0,0 +1,67 @@
+use chrono::{DateTime, Duration, NaiveDate, NaiveDateTime, Utc};
+use innosystem_common::timezone::{self, Tz};
+use proptest::prelude::*;
+
+/// Zones with and without daylight saving, on both sides of UTC and off the hour
+const ZONES: [&str; 7] = [
+    "UTC",
+    "Europe/Helsinki",
+    "America/New_York",
+    "Australia/Lord_Howe",
+    "Asia/Kolkata",
+    "Pacific/Chatham",
+    "America/St_Johns",
+];
+
+fn zone() -> impl Strategy<Value = Tz> {
+    prop::sample::select(ZONES.to_vec()).prop_map(|name| timezone::parse(name).unwrap())
+}
+
+/// A minute between 2000 and 2040
+fn instant() -> impl Strategy<Value = DateTime<Utc>> {
+    (0..40 * 366 * 24 * 60i64).prop_map(|minutes| {
+        NaiveDate::from_ymd_opt(2000, 1, 1).unwrap().and_hms_opt(0, 0, 0).unwrap().and_utc() + Duration::minutes(minutes)
+    })
+}
+
+/// A wall-clock minute between 2000 and 2040
+fn wall_clock() -> impl Strategy<Value = NaiveDateTime> {
+    instant().prop_map(|at| at.naive_utc())
+}
+
+proptest! {
+    /// Converting a UTC instant to wall-clock time and back lands on the same instant,
+    /// except for the second pass of an hour repeated when clocks go back
+    #[test]
+    fn utc_instants_round_trip(utc in instant(), tz in zone()) {
+        let back = timezone::to_utc(timezone::to_local(utc, tz), tz);
+        prop_assert!(back <= utc);
+        prop_assert!(utc - back <= Duration::hours(1));
+    }
+
+    /// Later wall-clock times never map to earlier instants, so local periods tile
+    #[test]
+    fn local_to_utc_is_monotone(local in wall_clock(), minutes in 0..3 * 24 * 60i64, tz in zone()) {
+        let later = local + Duration::minutes(minutes);
+        prop_assert!(timezone::to_utc(local, tz) <= timezone::to_utc(later, tz));
+    }
+
+    /// Wall-clock times skipped when clocks go forward map to the moment they jump
+    #[test]
+    fn skipped_times_move_forward(local in wall_clock(), tz in zone()) {
+        prop_assert!(timezone::to_local(timezone::to_utc(local, tz), tz) >= local);
+    }
+
+    /// A customer's own time zone wins over its reseller's, and UTC applies when
+    /// neither is set or parses
+    #[test]
+    fn customer_time_zones_win_over_resellers(
+        customer in prop::option::of(prop::sample::select(ZONES.to_vec())),
+        reseller in prop::option::of(prop::sample::select(ZONES.to_vec())),
+        garbage in "[a-z]{1,8}/[a-z]{1,8}",
+    ) {
+        let expected = customer.or(reseller).unwrap_or("UTC");
+        prop_assert_eq!(timezone::effective(customer, reseller).name(), expected);
+        prop_assert_eq!(timezone::effective(Some(&garbage), reseller).name(), reseller.unwrap_or("UTC"));
+    }
+}
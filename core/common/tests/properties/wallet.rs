@@ -0,0 +1,286 @@
+use innosystem_common::Error;
+use innosystem_common::models::job::PriorityLevel;
+use innosystem_common::models::job_cost::CostBreakdown;
+use innosystem_common::models::ledger::JobDebit;
+use innosystem_common::models::wallet::{validate_customer_reference, NewWalletTransaction, OverdraftPolicy, MAX_CUSTOMER_REFERENCE_LEN};
+use innosystem_common::repositories::WalletRepository;
+use innosystem_common::repositories::in_memory::InMemoryWalletRepository;
+use innosystem_common::testing::factories::WalletFactory;
+use proptest::prelude::*;
+use serde_json::json;
+use uuid::Uuid;
+
+use crate::block_on;
+
+/// A single billing operation against one wallet
+#[derive(Debug, Clone)]
+enum Op {
+    Deposit(i32),
+    Withdraw(i32),
+    /// Reserve funds for a new job
+    Reserve(i32),
+    /// Release an outstanding reservation without charging (the job failed)
+    Release(usize),
+    /// Release an outstanding reservation and charge a percentage of it (the job succeeded)
+    Charge(usize, u8),
+}
+
+fn op() -> impl Strategy<Value = Op> {
+    prop_oneof![
+        (1..5_000i32).prop_map(Op::Deposit),
+        (1..5_000i32).prop_map(Op::Withdraw),
+        (1..5_000i32).prop_map(Op::Reserve),
+        any::<usize>().prop_map(Op::Release),
+        (any::<usize>(), 0..=100u8).prop_map(|(index, percent)| Op::Charge(index, percent)),
+    ]
+}
+
+/// Expected state of the wallet, tracked alongside the repository
+#[derive(Debug, Default)]
+struct Model {
+    initial: i64,
+    deposited: i64,
+    withdrawn: i64,
+    charged: i64,
+    /// Outstanding reservations as (job ID, amount)
+    reservations: Vec<(Uuid, i32)>,
+}
+
+impl Model {
+    fn held(&self) -> i64 {
+        self.reservations.iter().map(|(_, amount)| *amount as i64).sum()
+    }
+
+    /// Balance implied by the money that entered and left the wallet
+    fn expected_balance(&self) -> i64 {
+        self.initial + self.deposited - self.withdrawn - self.charged - self.held()
+    }
+}
+
+/// Whether the error is the typed insufficient funds error
+fn is_insufficient_funds(error: &anyhow::Error) -> bool {
+    matches!(error.downcast_ref::<Error>(), Some(Error::InsufficientFunds(_)))
+}
+
+/// Sum of all ledger entries of the wallet
+async fn ledger_total(repo: &InMemoryWalletRepository, wallet_id: Uuid) -> i64 {
+    repo.get_transactions(wallet_id, i32::MAX, 0).await.unwrap()
+        .iter()
+        .map(|transaction| transaction.amount_cents as i64)
+        .sum()
+}
+
+/// Release a reservation and, if the job succeeded, record the charge the way the runner does
+async fn settle(repo: &InMemoryWalletRepository, wallet_id: Uuid, customer_id: Uuid, job_id: Uuid, reserved: i32, cost: i32) {
+    repo.release_reservation(wallet_id, reserved, None, Some(job_id)).await.unwrap();
+    if cost > 0 {
+        repo.add_transaction(NewWalletTransaction {
+            id: Uuid::new_v4(),
+            wallet_id,
+            amount_cents: -cost,
+            transaction_type: "job_charge".to_string(),
+            customer_id,
+            reference_id: Some(job_id),
+            description: None,
+            job_id: Some(job_id),
+            created_at: None,
+            customer_reference: None,
+            customer_metadata: None,
+        }).await.unwrap();
+    }
+}
+
+proptest! {
+    /// Any sequence of deposits, withdrawals, reservations, releases and charges keeps
+    /// the books balanced and never takes the wallet below zero
+    #[test]
+    fn billing_sequences_conserve_money(initial in 0..10_000i32, ops in prop::collection::vec(op(), 1..60)) {
+        block_on(async {
+            let repo = InMemoryWalletRepository::new();
+            let customer_id = Uuid::new_v4();
+            let wallet = WalletFactory::new(customer_id).balance_cents(initial).create(&repo).await.unwrap();
+            let mut model = Model { initial: initial as i64, ..Model::default() };
+
+            for op in ops {
+                let before = repo.get_balance(wallet.id).await.unwrap();
+
+                match op {
+                    Op::Deposit(amount) => {
+                        repo.deposit(wallet.id, amount, None, None).await.unwrap();
+                        model.deposited += amount as i64;
+                    }
+                    Op::Withdraw(amount) => match repo.withdraw(wallet.id, amount, None, None).await {
+                        Ok(_) => {
+                            prop_assert!(amount <= before);
+                            model.withdrawn += amount as i64;
+                        }
+                        Err(e) => {
+                            prop_assert!(is_insufficient_funds(&e), "unexpected error: {}", e);
+                            prop_assert!(amount > before);
+                        }
+                    },
+                    Op::Reserve(amount) => {
+                        let job_id = Uuid::new_v4();
+                        match repo.reserve_funds(wallet.id, amount, None, Some(job_id)).await {
+                            Ok(_) => {
+                                prop_assert!(amount <= before);
+                                model.reservations.push((job_id, amount));
+                            }
+                            Err(e) => {
+                                prop_assert!(is_insufficient_funds(&e), "unexpected error: {}", e);
+                                prop_assert!(amount > before);
+                            }
+                        }
+                    }
+                    Op::Release(index) => {
+                        if model.reservations.is_empty() {
+                            continue;
+                        }
+                        let (job_id, reserved) = model.reservations.remove(index % model.reservations.len());
+                        settle(&repo, wallet.id, customer_id, job_id, reserved, 0).await;
+                    }
+                    Op::Charge(index, percent) => {
+                        if model.reservations.is_empty() {
+                            continue;
+                        }
+                        let (job_id, reserved) = model.reservations.remove(index % model.reservations.len());
+                        let cost = (reserved as i64 * percent as i64 / 100) as i32;
+                        settle(&repo, wallet.id, customer_id, job_id, reserved, cost).await;
+                        model.charged += cost as i64;
+                    }
+                }
+
+                let balance = repo.get_balance(wallet.id).await.unwrap() as i64;
+                prop_assert!(balance >= 0, "balance went negative: {}", balance);
+                prop_assert_eq!(balance, model.expected_balance());
+                prop_assert_eq!(balance, model.initial + ledger_total(&repo, wallet.id).await);
+            }
+
+            // Once every reservation is released, only deposits, withdrawals and charges remain
+            for (job_id, reserved) in std::mem::take(&mut model.reservations) {
+                settle(&repo, wallet.id, customer_id, job_id, reserved, 0).await;
+            }
+            let balance = repo.get_balance(wallet.id).await.unwrap() as i64;
+            prop_assert_eq!(balance, model.initial + model.deposited - model.withdrawn - model.charged);
+            Ok(())
+        })?;
+    }
+
+    /// A failed reservation leaves the balance and the ledger untouched
+    #[test]
+    fn rejected_reservations_change_nothing(balance in 0..10_000i32, excess in 1..10_000i32) {
+        block_on(async {
+            let repo = InMemoryWalletRepository::new();
+            let wallet = WalletFactory::new(Uuid::new_v4()).balance_cents(balance).create(&repo).await.unwrap();
+
+            let error = repo.reserve_funds(wallet.id, balance + excess, None, None).await.unwrap_err();
+            prop_assert!(is_insufficient_funds(&error));
+            prop_assert_eq!(repo.get_balance(wallet.id).await.unwrap(), balance);
+            prop_assert!(repo.get_transactions(wallet.id, i32::MAX, 0).await.unwrap().is_empty());
+            Ok(())
+        })?;
+    }
+
+    /// Reservations succeed exactly while they keep the balance at or above the floor of
+    /// the overdraft policy, and suspended wallets are the overdrawn ones under `suspend`
+    #[test]
+    fn reservations_stop_at_the_overdraft_floor(
+        initial in 0..5_000i32,
+        limit in 0..5_000i32,
+        policy in prop::sample::select(vec![OverdraftPolicy::Deny, OverdraftPolicy::Grace, OverdraftPolicy::Suspend]),
+        amounts in prop::collection::vec(1..2_000i32, 1..30),
+    ) {
+        block_on(async {
+            let repo = InMemoryWalletRepository::new();
+            let wallet = WalletFactory::new(Uuid::new_v4()).balance_cents(initial).create(&repo).await.unwrap();
+            let wallet = repo.set_overdraft_policy(wallet.id, policy, limit).await.unwrap();
+            let floor = if policy == OverdraftPolicy::Deny { 0 } else { -limit };
+            prop_assert_eq!(wallet.balance_floor(), floor);
+
+            for amount in amounts {
+                let before = repo.get_balance(wallet.id).await.unwrap();
+                match repo.reserve_funds(wallet.id, amount, None, None).await {
+                    Ok(reserved) => {
+                        prop_assert!(before - amount >= floor);
+                        prop_assert_eq!(reserved.submissions_suspended(), policy == OverdraftPolicy::Suspend && reserved.balance_cents < 0);
+                        prop_assert_eq!(reserved.overdraft_cents(), (-reserved.balance_cents).max(0));
+                    }
+                    Err(e) => {
+                        prop_assert!(is_insufficient_funds(&e), "unexpected error: {}", e);
+                        prop_assert!(before - amount < floor);
+                        prop_assert_eq!(repo.get_balance(wallet.id).await.unwrap(), before);
+                    }
+                }
+                prop_assert!(repo.get_balance(wallet.id).await.unwrap() >= floor);
+            }
+            Ok(())
+        })?;
+    }
+
+    /// Reserving and releasing the same amount is a no-op on the balance
+    #[test]
+    fn reserve_then_release_is_neutral(balance in 1..10_000i32, fraction in 1..=100i32) {
+        block_on(async {
+            let repo = InMemoryWalletRepository::new();
+            let wallet = WalletFactory::new(Uuid::new_v4()).balance_cents(balance).create(&repo).await.unwrap();
+            let amount = (balance * fraction / 100).max(1);
+
+            let reserved = repo.reserve_funds(wallet.id, amount, None, None).await.unwrap();
+            prop_assert_eq!(reserved.balance_cents, balance - amount);
+            let released = repo.release_reservation(wallet.id, amount, None, None).await.unwrap();
+            prop_assert_eq!(released.balance_cents, balance);
+            Ok(())
+        })?;
+    }
+
+    /// Retrying any step of a job's billing, after it went through, leaves the balance and
+    /// the ledger as they were after the first try
+    #[test]
+    fn retried_job_billing_is_posted_once(
+        balance in 1_000..10_000i32,
+        amount in 1..1_000i32,
+        attempts in 1..4usize,
+        retries in 1..4usize,
+    ) {
+        block_on(async {
+            let repo = InMemoryWalletRepository::new();
+            let wallet = WalletFactory::new(Uuid::new_v4()).balance_cents(balance).create(&repo).await.unwrap();
+            let job_id = Uuid::new_v4();
+
+            for _ in 0..attempts {
+                for _ in 0..retries {
+                    let reserved = repo.reserve_funds(wallet.id, amount, None, Some(job_id)).await.unwrap();
+                    prop_assert_eq!(reserved.balance_cents, balance - amount);
+                }
+                for _ in 0..retries {
+                    let released = repo.release_reservation(wallet.id, amount, None, Some(job_id)).await.unwrap();
+                    prop_assert_eq!(released.balance_cents, balance);
+                }
+            }
+            let cost = CostBreakdown::completed(amount, &PriorityLevel::Medium, 0);
+            for _ in 0..retries {
+                let charged = repo.charge_job(JobDebit::new(wallet.id, job_id, &cost, None)).await.unwrap();
+                prop_assert_eq!(charged.balance_cents, balance - cost.total());
+            }
+
+            let transactions = repo.get_transactions(wallet.id, i32::MAX, 0).await.unwrap();
+            prop_assert_eq!(transactions.len(), 2 * attempts + 1);
+            prop_assert_eq!(ledger_total(&repo, wallet.id).await, -cost.total() as i64);
+            Ok(())
+        })?;
+    }
+
+    /// References are accepted up to their length limit, blank ones never, and metadata
+    /// only as a JSON object
+    #[test]
+    fn customer_references_are_validated(reference in "[ -~]{0,200}", key in "[a-z]{1,10}", value in any::<i64>()) {
+        let accepted = validate_customer_reference(Some(&reference), None).is_ok();
+        prop_assert_eq!(accepted, !reference.trim().is_empty() && reference.chars().count() <= MAX_CUSTOMER_REFERENCE_LEN);
+
+        let object = json!({ key.clone(): value });
+        prop_assert!(validate_customer_reference(None, Some(&object)).is_ok());
+        let array = json!([key, value]);
+        prop_assert!(validate_customer_reference(None, Some(&array)).is_err());
+        prop_assert!(validate_customer_reference(None, Some(&json!(value))).is_err());
+    }
+}
@@ -0,0 +1,35 @@
+use std::net::{IpAddr, Ipv4Addr, Ipv6Addr};
+
+use innosystem_common::models::webhook::is_public_address;
+use proptest::prelude::*;
+
+#[test]
+fn internal_addresses_are_not_public() {
+    for ip in ["127.0.0.1", "10.1.2.3", "172.16.0.1", "192.168.1.1", "169.254.169.254", "100.64.0.1", "0.0.0.0", "255.255.255.255", "::1", "::", "fe80::1", "fd00::1", "::ffff:127.0.0.1", "::ffff:10.0.0.1"] {
+        assert!(!is_public_address(ip.parse().unwrap()), "{} is public", ip);
+    }
+    for ip in ["93.184.216.34", "8.8.8.8", "2606:4700:4700::1111", "::ffff:8.8.8.8"] {
+        assert!(is_public_address(ip.parse().unwrap()), "{} is not public", ip);
+    }
+}
+
+proptest! {
+    /// IPv4 addresses mapped into IPv6 are judged like the IPv4 address itself
+    #[test]
+    fn mapped_addresses_follow_their_ipv4_address(octets in any::<[u8; 4]>()) {
+        let ip = Ipv4Addr::from(octets);
+        let mapped: Ipv6Addr = ip.to_ipv6_mapped();
+        prop_assert_eq!(is_public_address(IpAddr::V4(ip)), is_public_address(IpAddr::V6(mapped)));
+    }
+
+    /// Nothing in the loopback and private IPv4 ranges is public
+    #[test]
+    fn private_ranges_are_never_public(host in any::<[u8; 3]>(), range in 0usize..3) {
+        let ip = match range {
+            0 => Ipv4Addr::new(127, host[0], host[1], host[2]),
+            1 => Ipv4Addr::new(10, host[0], host[1], host[2]),
+            _ => Ipv4Addr::new(192, 168, host[1], host[2]),
+        };
+        prop_assert!(!is_public_address(IpAddr::V4(ip)));
+    }
+}
@@ -0,0 +1,145 @@
+use chrono::{Duration, Utc};
+use innosystem_common::models::billing_export::{self, ExportAttempt, ExportStatus};
+use innosystem_common::models::job::PriorityLevel;
+use innosystem_common::models::job_cost::CostBreakdown;
+use innosystem_common::repositories::{
+    BillingExportRepository, DieselBillingExportRepository, DieselCustomerRepository, DieselJobRepository,
+    DieselJobTypeRepository, JobRepository,
+};
+use innosystem_common::testing::factories::{CustomerFactory, JobFactory, JobTypeFactory};
+use uuid::Uuid;
+
+use crate::environment;
+
+/// Complete a job successfully with a charge of `cost_cents` and its breakdown
+async fn complete(repo: &DieselJobRepository, job_id: Uuid, cost_cents: i32) {
+    repo.set_completed(job_id, true, None, None, cost_cents).await.unwrap();
+    let breakdown = CostBreakdown::completed(cost_cents, &PriorityLevel::Medium, 0);
+    repo.set_cost_breakdown(job_id, breakdown).await.unwrap();
+}
+
+#[tokio::test]
+#[ignore = "requires Docker or TEST_DATABASE_URL"]
+async fn captures_each_finalized_charge_once() {
+    let env = environment().await;
+    let repo = DieselBillingExportRepository::new(env.pool.clone());
+    let job_repo = DieselJobRepository::new(env.pool.clone());
+    let customer = CustomerFactory::new()
+        .create(&DieselCustomerRepository::new(env.pool.clone()))
+        .await
+        .unwrap();
+    let job_type = JobTypeFactory::new()
+        .create(&DieselJobTypeRepository::new(env.pool.clone()))
+        .await
+        .unwrap();
+    let since = Utc::now() - Duration::hours(1);
+
+    let charged = JobFactory::new(customer.id, job_type.id).create(&job_repo).await.unwrap();
+    complete(&job_repo, charged.id, 120).await;
+    let test_mode = JobFactory::new(customer.id, job_type.id).test_mode().create(&job_repo).await.unwrap();
+    complete(&job_repo, test_mode.id, 0).await;
+    let mut sub_task = JobFactory::new(customer.id, job_type.id).build();
+    sub_task.parent_id = Some(charged.id);
+    let sub_task = job_repo.create(sub_task).await.unwrap();
+    complete(&job_repo, sub_task.id, 40).await;
+    // Running jobs have no final charge yet
+    JobFactory::new(customer.id, job_type.id).create(&job_repo).await.unwrap();
+
+    // Other tests share the database, so only the exports of this test's jobs are checked
+    let ours = |job_id: Uuid| [charged.id, test_mode.id, sub_task.id].contains(&job_id);
+    assert!(repo.capture(since, 1000).await.unwrap() >= 1);
+    let due: Vec<_> = repo.find_due(Utc::now(), 1000).await.unwrap()
+        .into_iter()
+        .filter(|export| ours(export.job_id))
+        .collect();
+    assert_eq!(due.len(), 1);
+    repo.capture(since, 1000).await.unwrap();
+    let again = repo.find_due(Utc::now(), 1000).await.unwrap();
+    assert_eq!(again.iter().filter(|export| ours(export.job_id)).count(), 1);
+
+    let export = &due[0];
+    assert_eq!(export.job_id, charged.id);
+    assert_eq!(export.customer_id, customer.id);
+    assert_eq!(export.amount_cents, 120);
+    assert_eq!(export.idempotency_key, billing_export::idempotency_key(charged.id));
+    assert_eq!(export.export_status(), Some(ExportStatus::Pending));
+    assert_eq!(repo.find_by_id(export.id).await.unwrap().job_id, charged.id);
+    assert!(repo.find_by_id(Uuid::new_v4()).await.is_err());
+}
+
+#[tokio::test]
+#[ignore = "requires Docker or TEST_DATABASE_URL"]
+async fn records_attempts_and_reconciles_exports() {
+    let env = environment().await;
+    let repo = DieselBillingExportRepository::new(env.pool.clone());
+    let job_repo = DieselJobRepository::new(env.pool.clone());
+    let customer = CustomerFactory::new()
+        .create(&DieselCustomerRepository::new(env.pool.clone()))
+        .await
+        .unwrap();
+    let job_type = JobTypeFactory::new()
+        .create(&DieselJobTypeRepository::new(env.pool.clone()))
+        .await
+        .unwrap();
+    let from = Utc::now() - Duration::hours(1);
+
+    let exported = JobFactory::new(customer.id, job_type.id).create(&job_repo).await.unwrap();
+    complete(&job_repo, exported.id, 100).await;
+    let retried = JobFactory::new(customer.id, job_type.id).create(&job_repo).await.unwrap();
+    complete(&job_repo, retried.id, 30).await;
+    assert!(repo.capture(from, 1000).await.unwrap() >= 2);
+    // Completed after the capture, so it has no export yet
+    let uncaptured = JobFactory::new(customer.id, job_type.id).create(&job_repo).await.unwrap();
+    complete(&job_repo, uncaptured.id, 7).await;
+
+    // Other tests share the database, so only the exports of this test's jobs are touched
+    let now = Utc::now();
+    let ours = |job_id: Uuid| [exported.id, retried.id, uncaptured.id].contains(&job_id);
+    let due: Vec<_> = repo.find_due(now, 1000).await.unwrap()
+        .into_iter()
+        .filter(|export| ours(export.job_id))
+        .collect();
+    assert_eq!(due.len(), 2);
+    for export in due {
+        let attempt = if export.job_id == exported.id {
+            ExportAttempt {
+                status: ExportStatus::Exported,
+                attempts: 1,
+                status_code: Some(201),
+                last_error: None,
+                next_attempt_at: None,
+                exported_at: Some(now),
+            }
+        } else {
+            ExportAttempt {
+                status: ExportStatus::Pending,
+                attempts: 1,
+                status_code: Some(503),
+                last_error: Some("503 Service Unavailable".to_string()),
+                next_attempt_at: Some(now + Duration::minutes(1)),
+                exported_at: None,
+            }
+        };
+        let recorded = repo.record_attempt(export.id, attempt).await.unwrap();
+        assert_eq!(recorded.attempts, 1);
+    }
+    // Neither is due: one is exported, the other waits for its next attempt
+    let due_now = repo.find_due(now, 1000).await.unwrap();
+    assert!(!due_now.iter().any(|export| ours(export.job_id)));
+    let due_later = repo.find_due(now + Duration::minutes(2), 1000).await.unwrap();
+    assert_eq!(due_later.iter().filter(|export| ours(export.job_id)).map(|export| export.job_id).collect::<Vec<_>>(), vec![retried.id]);
+
+    let to = Utc::now() + Duration::minutes(1);
+    let reconciliation = repo.reconcile(from, to, 1000).await.unwrap();
+    assert!(reconciliation.finalized_jobs >= 3);
+    assert!(reconciliation.exported >= 1 && reconciliation.pending >= 1 && reconciliation.uncaptured >= 1);
+    let unexported: Vec<_> = reconciliation.unexported.iter()
+        .filter(|record| ours(record.job_id))
+        .map(|record| (record.job_id, record.status.as_str()))
+        .collect();
+    assert_eq!(unexported, vec![(retried.id, "pending"), (uncaptured.id, "uncaptured")]);
+
+    let limited = repo.reconcile(from, to, 1).await.unwrap();
+    assert_eq!(limited.unexported.len(), 1);
+    assert!(limited.uncaptured >= 1);
+}
@@ -0,0 +1,177 @@
+use chrono::{DateTime, Duration, NaiveDate, Utc};
+use innosystem_common::models::billing_period::{ledger_csv, ledger_sha256, LedgerError};
+use innosystem_common::models::job::PriorityLevel;
+use innosystem_common::models::job_cost::{CostBreakdown, FAILURE_FEE_RATE};
+use innosystem_common::models::wallet::{NewWalletTransaction, TransactionType};
+use innosystem_common::repositories::{
+    BillingPeriodRepository, CustomerRepository, DieselBillingPeriodRepository, DieselCustomerRepository,
+    DieselJobRepository, DieselJobTypeRepository, DieselWalletRepository, JobRepository, WalletRepository,
+};
+use innosystem_common::testing::TestEnvironment;
+use innosystem_common::testing::factories::{CustomerFactory, JobFactory, JobTypeFactory, WalletFactory};
+use uuid::Uuid;
+
+use crate::environment;
+
+/// An hour-long period in the distant past that no other test run has closed
+fn unique_period() -> (DateTime<Utc>, DateTime<Utc>) {
+    let hours = (Uuid::new_v4().as_u128() % (100 * 365 * 24)) as i64;
+    let start = NaiveDate::from_ymd_opt(1900, 1, 1).unwrap().and_hms_opt(0, 0, 0).unwrap().and_utc() + Duration::hours(hours);
+    (start, start + Duration::hours(1))
+}
+
+/// Insert a customer with a wallet holding the given balance, returning both IDs
+async fn wallet(env: &TestEnvironment, balance_cents: i32) -> (Uuid, Uuid) {
+    let customer = CustomerFactory::new()
+        .create(&DieselCustomerRepository::new(env.pool.clone()))
+        .await
+        .unwrap();
+    let wallet = WalletFactory::new(customer.id)
+        .balance_cents(balance_cents)
+        .create(&DieselWalletRepository::new(env.pool.clone()))
+        .await
+        .unwrap();
+    (customer.id, wallet.id)
+}
+
+fn transaction(customer_id: Uuid, wallet_id: Uuid, amount_cents: i32, created_at: Option<DateTime<Utc>>) -> NewWalletTransaction {
+    NewWalletTransaction {
+        id: Uuid::new_v4(),
+        wallet_id,
+        amount_cents,
+        transaction_type: TransactionType::Deposit.to_string(),
+        customer_id,
+        reference_id: None,
+        description: Some("Top-up, by card".to_string()),
+        job_id: None,
+        created_at,
+        customer_reference: None,
+        customer_metadata: None,
+    }
+}
+
+#[tokio::test]
+#[ignore = "requires Docker or TEST_DATABASE_URL"]
+async fn closing_a_period_finalizes_invoices_and_locks_its_transactions() {
+    let env = environment().await;
+    let repo = DieselBillingPeriodRepository::new(env.pool.clone());
+    let wallets = DieselWalletRepository::new(env.pool.clone());
+    let (customer_id, wallet_id) = wallet(&env, 1000).await;
+    let (start, end) = unique_period();
+
+    wallets.add_transaction(transaction(customer_id, wallet_id, 500, Some(start + Duration::minutes(10)))).await.unwrap();
+    wallets.add_transaction(transaction(customer_id, wallet_id, -200, Some(start + Duration::minutes(20)))).await.unwrap();
+    // Posted after the period, so not part of its invoice
+    wallets.add_transaction(transaction(customer_id, wallet_id, 50, None)).await.unwrap();
+
+    let period = repo.close(start, end, "alice".to_string()).await.unwrap();
+    assert_eq!(period.closed_by, "alice");
+    assert_eq!(period.transaction_count, 2);
+    assert_eq!((period.credits_cents, period.debits_cents), (500, 200));
+
+    let invoices = repo.find_invoices(period.id).await.unwrap();
+    let invoice = invoices.iter().find(|invoice| invoice.wallet_id == wallet_id).unwrap();
+    assert_eq!(invoice.customer_id, customer_id);
+    assert_eq!(invoice.opening_balance_cents, 1000);
+    assert_eq!((invoice.credits_cents, invoice.debits_cents), (500, 200));
+    assert_eq!(invoice.closing_balance_cents, 1300);
+    assert_eq!(invoice.transaction_count, 2);
+    assert_eq!(repo.find_invoices_by_customer(customer_id).await.unwrap().len(), 1);
+
+    let ledger = repo.ledger(period.id).await.unwrap();
+    assert_eq!(ledger.iter().map(|tx| tx.amount_cents).collect::<Vec<_>>(), vec![500, -200]);
+    let csv = ledger_csv(&ledger);
+    assert!(csv.contains("\"Top-up, by card\""));
+    assert_eq!(ledger_sha256(&csv), period.ledger_sha256);
+
+    // Corrections go into the current period; backdating into the closed one is refused
+    let err = wallets.add_transaction(transaction(customer_id, wallet_id, 10, Some(start + Duration::minutes(30)))).await.unwrap_err();
+    assert!(matches!(err.downcast_ref::<LedgerError>(), Some(LedgerError::PeriodClosed(_))));
+    assert_eq!(wallets.get_balance(wallet_id).await.unwrap(), 1350);
+
+    let err = repo.close(start + Duration::minutes(30), end + Duration::hours(1), "bob".to_string()).await.unwrap_err();
+    assert!(matches!(err.downcast_ref::<LedgerError>(), Some(LedgerError::AlreadyClosed(_))));
+    assert!(repo.list().await.unwrap().iter().any(|closed| closed.id == period.id));
+}
+
+#[tokio::test]
+#[ignore = "requires Docker or TEST_DATABASE_URL"]
+async fn invoices_itemize_the_jobs_charged_in_the_period() {
+    let env = environment().await;
+    let repo = DieselBillingPeriodRepository::new(env.pool.clone());
+    let wallets = DieselWalletRepository::new(env.pool.clone());
+    let jobs = DieselJobRepository::new(env.pool.clone());
+    let (customer_id, wallet_id) = wallet(&env, 1000).await;
+    let job_type = JobTypeFactory::new()
+        .create(&DieselJobTypeRepository::new(env.pool.clone()))
+        .await
+        .unwrap();
+    let (start, end) = unique_period();
+
+    let mut succeeded = CostBreakdown::completed(200, &PriorityLevel::High, 1000);
+    succeeded.apply_credits(30);
+    let failed = CostBreakdown::failed(400, FAILURE_FEE_RATE);
+    for (minutes, cost) in [(10, succeeded), (20, failed)] {
+        let job = JobFactory::new(customer_id, job_type.id).create(&jobs).await.unwrap();
+        jobs.set_cost_breakdown(job.id, cost).await.unwrap();
+        let charge = NewWalletTransaction {
+            transaction_type: TransactionType::JobDebit.to_string(),
+            job_id: Some(job.id),
+            ..transaction(customer_id, wallet_id, -(cost.total()), Some(start + Duration::minutes(minutes)))
+        };
+        wallets.add_transaction(charge).await.unwrap();
+    }
+    // Deposits are not job costs
+    wallets.add_transaction(transaction(customer_id, wallet_id, 500, Some(start + Duration::minutes(30)))).await.unwrap();
+
+    let period = repo.close(start, end, "alice".to_string()).await.unwrap();
+    let invoices = repo.find_invoices(period.id).await.unwrap();
+    let invoice = invoices.iter().find(|invoice| invoice.wallet_id == wallet_id).unwrap();
+
+    let cost = invoice.cost_breakdown().unwrap();
+    assert_eq!(cost, succeeded + failed);
+    assert_eq!((cost.base_cents, cost.priority_surcharge_cents, cost.reseller_markup_cents), (200, 100, 30));
+    assert_eq!((cost.credits_applied_cents, cost.failure_fee_cents), (30, 100));
+    assert_eq!(cost.total_cents, invoice.debits_cents);
+}
+
+#[tokio::test]
+#[ignore = "requires Docker or TEST_DATABASE_URL"]
+async fn invoices_follow_the_customers_time_zone() {
+    let env = environment().await;
+    let repo = DieselBillingPeriodRepository::new(env.pool.clone());
+    let wallets = DieselWalletRepository::new(env.pool.clone());
+    let (customer_id, wallet_id) = wallet(&env, 1000).await;
+    // Three hours ahead of UTC all year round
+    DieselCustomerRepository::new(env.pool.clone())
+        .set_timezone(customer_id, Some("Etc/GMT-3".to_string()))
+        .await
+        .unwrap();
+    let (start, end) = unique_period();
+
+    // Before the period in UTC but inside it on the customer's clock, and the other way round
+    wallets.add_transaction(transaction(customer_id, wallet_id, 500, Some(start - Duration::minutes(170)))).await.unwrap();
+    wallets.add_transaction(transaction(customer_id, wallet_id, -200, Some(start + Duration::minutes(10)))).await.unwrap();
+
+    let period = repo.close(start, end, "alice".to_string()).await.unwrap();
+    let invoices = repo.find_invoices(period.id).await.unwrap();
+    let invoice = invoices.iter().find(|invoice| invoice.wallet_id == wallet_id).unwrap();
+
+    assert_eq!(invoice.timezone, "Etc/GMT-3");
+    assert_eq!((invoice.period_start, invoice.period_end), (start - Duration::hours(3), end - Duration::hours(3)));
+    assert_eq!((invoice.credits_cents, invoice.debits_cents), (500, 0));
+    assert_eq!((invoice.opening_balance_cents, invoice.closing_balance_cents), (1000, 1500));
+    assert_eq!(invoice.transaction_count, 1);
+}
+
+#[tokio::test]
+#[ignore = "requires Docker or TEST_DATABASE_URL"]
+async fn periods_cannot_be_closed_before_they_end() {
+    let env = environment().await;
+    let repo = DieselBillingPeriodRepository::new(env.pool.clone());
+    let now = chrono::Utc::now();
+
+    let err = repo.close(now - Duration::hours(1), now + Duration::hours(1), "alice".to_string()).await.unwrap_err();
+    assert!(matches!(err.downcast_ref::<LedgerError>(), Some(LedgerError::PeriodNotOver(_))));
+    assert!(repo.find_by_id(Uuid::new_v4()).await.unwrap_err().to_string().contains("not found"));
+}
@@ -0,0 +1,96 @@
+use std::time::{Duration, Instant};
+
+use diesel::RunQueryDsl;
+
+use innosystem_common::repositories::{
+    CustomerRepository, DieselCustomerRepository, DieselJobRepository, DieselJobTypeRepository, DieselWalletRepository,
+    JobRepository, WalletRepository,
+};
+use innosystem_common::testing::factories::{CustomerFactory, JobFactory, JobTypeFactory, WalletFactory};
+
+use crate::environment;
+
+/// Rows inserted per table, as many as a large seed or import
+const ROWS: usize = 10_000;
+
+/// How many times faster create_many must be; it measures 6 to 7 times on a local database
+const MIN_SPEEDUP: f64 = 3.0;
+
+/// Time an insert, logging it next to the name of the table
+async fn timed<T>(label: &str, insert: impl std::future::Future<Output = T>) -> Duration {
+    let started = Instant::now();
+    insert.await;
+    let elapsed = started.elapsed();
+    tracing::info!("{:<28} {:>8.2?}", label, elapsed);
+    elapsed
+}
+
+/// Benchmark of create_many against inserting the same rows one by one
+#[tokio::test]
+#[ignore = "benchmark; requires Docker or TEST_DATABASE_URL"]
+async fn bulk_inserts_of_10k_rows_outpace_row_by_row_inserts() {
+    let env = environment().await;
+    let customers = DieselCustomerRepository::new(env.pool.clone());
+    let wallets = DieselWalletRepository::new(env.pool.clone());
+    let jobs = DieselJobRepository::new(env.pool.clone());
+    let job_type = JobTypeFactory::new()
+        .create(&DieselJobTypeRepository::new(env.pool.clone()))
+        .await
+        .unwrap();
+
+    // Row by row, the way the seeder inserted before create_many
+    let new_customers: Vec<_> = (0..ROWS).map(|_| CustomerFactory::new().build()).collect();
+    let customer_ids: Vec<_> = new_customers.iter().map(|customer| customer.id).collect();
+    let mut one_by_one = timed("customers one by one", async {
+        for customer in new_customers {
+            customers.create(customer).await.unwrap();
+        }
+    }).await;
+    one_by_one += timed("wallets one by one", async {
+        for customer_id in &customer_ids {
+            wallets.create(WalletFactory::new(*customer_id).build()).await.unwrap();
+        }
+    }).await;
+    one_by_one += timed("jobs one by one", async {
+        for customer_id in &customer_ids {
+            jobs.create(JobFactory::new(*customer_id, job_type.id).build()).await.unwrap();
+        }
+    }).await;
+
+    // The same number of rows through create_many
+    let mut inserted_customers = customer_ids;
+    let new_customers: Vec<_> = (0..ROWS).map(|_| CustomerFactory::new().build()).collect();
+    let customer_ids: Vec<_> = new_customers.iter().map(|customer| customer.id).collect();
+    inserted_customers.extend(&customer_ids);
+    let mut bulk = timed("customers with create_many", async {
+        assert_eq!(customers.create_many(new_customers).await.unwrap().len(), ROWS);
+    }).await;
+    let new_wallets = customer_ids.iter().map(|customer_id| WalletFactory::new(*customer_id).build()).collect();
+    bulk += timed("wallets with create_many", async {
+        assert_eq!(wallets.create_many(new_wallets).await.unwrap().len(), ROWS);
+    }).await;
+    let new_jobs = customer_ids.iter().map(|customer_id| JobFactory::new(*customer_id, job_type.id).build()).collect();
+    bulk += timed("jobs with create_many", async {
+        assert_eq!(jobs.create_many(new_jobs).await.unwrap().len(), ROWS);
+    }).await;
+
+    let speedup = one_by_one.as_secs_f64() / bulk.as_secs_f64();
+
+    // Remove the rows again, so tests sharing the database do not page through them
+    let mut conn = env.pool.get().unwrap();
+    diesel::sql_query("DELETE FROM jobs WHERE job_type_id = $1")
+        .bind::<diesel::sql_types::Uuid, _>(job_type.id)
+        .execute(&mut conn)
+        .unwrap();
+    for statement in ["DELETE FROM wallets WHERE customer_id = ANY($1)", "DELETE FROM customers WHERE id = ANY($1)"] {
+        diesel::sql_query(statement)
+            .bind::<diesel::sql_types::Array<diesel::sql_types::Uuid>, _>(&inserted_customers)
+            .execute(&mut conn)
+            .unwrap();
+    }
+    assert!(
+        speedup >= MIN_SPEEDUP,
+        "create_many took {:.2?} against {:.2?} one by one, only {:.1}x faster",
+        bulk, one_by_one, speedup
+    );
+}
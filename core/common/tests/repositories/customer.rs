@@ -0,0 +1,133 @@
+use innosystem_common::models::pii::PiiPolicy;
+use innosystem_common::repositories::{CustomerRepository, DieselCustomerRepository, DieselResellerRepository};
+use innosystem_common::testing::factories::{CustomerFactory, ResellerFactory};
+
+use crate::environment;
+
+#[tokio::test]
+#[ignore = "requires Docker or TEST_DATABASE_URL"]
+async fn creates_and_finds_customers() {
+    let env = environment().await;
+    let repo = DieselCustomerRepository::new(env.pool.clone());
+
+    let customer = CustomerFactory::new().name("Acme").create(&repo).await.unwrap();
+    assert_eq!(customer.name, "Acme");
+
+    let found = repo.find_by_id(customer.id).await.unwrap();
+    assert_eq!(found.email, customer.email);
+
+    let api_key = customer.api_key.clone().unwrap();
+    assert_eq!(repo.find_by_api_key(&api_key).await.unwrap().id, customer.id);
+
+    let all = repo.list_all().await.unwrap();
+    assert!(all.iter().any(|c| c.id == customer.id));
+
+    let missing = repo.find_by_id(uuid::Uuid::new_v4()).await.unwrap_err();
+    assert!(missing.to_string().contains("not found"));
+}
+
+#[tokio::test]
+#[ignore = "requires Docker or TEST_DATABASE_URL"]
+async fn creates_many_customers_all_or_nothing() {
+    let env = environment().await;
+    let repo = DieselCustomerRepository::new(env.pool.clone());
+
+    let batch: Vec<_> = (0..3).map(|i| CustomerFactory::new().name(format!("Bulk {}", i)).build()).collect();
+    let created = repo.create_many(batch).await.unwrap();
+    assert_eq!(created.len(), 3);
+    assert_eq!(repo.find_by_id(created[2].id).await.unwrap().name, "Bulk 2");
+
+    // A duplicate ID fails the whole batch, so the row before it is rolled back
+    let fresh = CustomerFactory::new().build();
+    let fresh_id = fresh.id;
+    let mut duplicate = CustomerFactory::new().build();
+    duplicate.id = created[0].id;
+    assert!(repo.create_many(vec![fresh, duplicate]).await.is_err());
+    assert!(repo.find_by_id(fresh_id).await.is_err());
+}
+
+#[tokio::test]
+#[ignore = "requires Docker or TEST_DATABASE_URL"]
+async fn updates_customers_and_rotates_keys() {
+    let env = environment().await;
+    let repo = DieselCustomerRepository::new(env.pool.clone());
+    let mut customer = CustomerFactory::new().create(&repo).await.unwrap();
+
+    customer.name = "Renamed".to_string();
+    let updated = repo.update(&customer).await.unwrap();
+    assert_eq!(updated.name, "Renamed");
+
+    let old_key = customer.api_key.clone().unwrap();
+    let new_key = repo.generate_api_key(customer.id).await.unwrap();
+    assert_ne!(new_key, old_key);
+    assert!(repo.find_by_api_key(&old_key).await.is_err());
+    assert_eq!(repo.find_by_api_key(&new_key).await.unwrap().id, customer.id);
+
+    let test_key = repo.generate_test_api_key(customer.id).await.unwrap();
+    assert!(test_key.starts_with("cus_test_"));
+    assert_eq!(repo.find_by_test_api_key(&test_key).await.unwrap().id, customer.id);
+    assert!(repo.find_by_api_key(&test_key).await.is_err());
+}
+
+#[tokio::test]
+#[ignore = "requires Docker or TEST_DATABASE_URL"]
+async fn assigns_customers_to_resellers() {
+    let env = environment().await;
+    let repo = DieselCustomerRepository::new(env.pool.clone());
+    let reseller_repo = DieselResellerRepository::new(env.pool.clone());
+
+    let reseller = ResellerFactory::new().create(&reseller_repo).await.unwrap();
+    let owned = CustomerFactory::new().reseller(reseller.id).create(&repo).await.unwrap();
+    let direct = CustomerFactory::new().create(&repo).await.unwrap();
+
+    let customers = repo.find_by_reseller_id(reseller.id).await.unwrap();
+    assert_eq!(customers.len(), 1);
+    assert_eq!(customers[0].id, owned.id);
+
+    let moved = repo.set_reseller(direct.id, Some(reseller.id)).await.unwrap();
+    assert_eq!(moved.reseller_id, Some(reseller.id));
+    assert_eq!(repo.find_by_reseller_id(reseller.id).await.unwrap().len(), 2);
+
+    let detached = repo.set_reseller(owned.id, None).await.unwrap();
+    assert_eq!(detached.reseller_id, None);
+    assert_eq!(repo.find_by_reseller_id(reseller.id).await.unwrap().len(), 1);
+}
+
+#[tokio::test]
+#[ignore = "requires Docker or TEST_DATABASE_URL"]
+async fn finds_sub_accounts_and_sets_budgets() {
+    let env = environment().await;
+    let repo = DieselCustomerRepository::new(env.pool.clone());
+    let parent = CustomerFactory::new().create(&repo).await.unwrap();
+    let sub_account = CustomerFactory::new().parent(parent.id).budget_cents(500).create(&repo).await.unwrap();
+
+    assert!(!parent.is_sub_account());
+    assert!(sub_account.is_sub_account());
+    assert_eq!(sub_account.billing_customer_id(), parent.id);
+    assert_eq!(sub_account.budget_cents, Some(500));
+
+    let sub_accounts = repo.find_by_parent_id(parent.id).await.unwrap();
+    assert_eq!(sub_accounts.iter().map(|c| c.id).collect::<Vec<_>>(), vec![sub_account.id]);
+    assert!(repo.find_by_parent_id(sub_account.id).await.unwrap().is_empty());
+
+    let unlimited = repo.set_budget(sub_account.id, None).await.unwrap();
+    assert_eq!(unlimited.budget_cents, None);
+    assert!(repo.set_budget(uuid::Uuid::new_v4(), Some(1)).await.is_err());
+}
+
+#[tokio::test]
+#[ignore = "requires Docker or TEST_DATABASE_URL"]
+async fn sets_and_clears_pii_policies() {
+    let env = environment().await;
+    let repo = DieselCustomerRepository::new(env.pool.clone());
+    let customer = CustomerFactory::new().create(&repo).await.unwrap();
+    assert_eq!(customer.pii_policy(), None);
+
+    let updated = repo.set_pii_policy(customer.id, Some(PiiPolicy::Mask)).await.unwrap();
+    assert_eq!(updated.pii_policy(), Some(PiiPolicy::Mask));
+    assert_eq!(repo.find_by_id(customer.id).await.unwrap().pii_policy.as_deref(), Some("mask"));
+
+    let cleared = repo.set_pii_policy(customer.id, None).await.unwrap();
+    assert_eq!(cleared.pii_policy(), None);
+    assert!(repo.set_pii_policy(uuid::Uuid::new_v4(), Some(PiiPolicy::Reject)).await.is_err());
+}
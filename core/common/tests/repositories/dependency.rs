@@ -0,0 +1,87 @@
+use diesel::RunQueryDsl;
+use innosystem_common::models::dependency::{Blocker, BlockerKind};
+use innosystem_common::models::job::JobStatus;
+use innosystem_common::models::project::NewProject;
+use innosystem_common::repositories::{
+    DependencyRepository, DieselCustomerRepository, DieselDependencyRepository, DieselJobRepository,
+    DieselJobTypeRepository, DieselProjectRepository, DieselWalletRepository, JobRepository, ProjectRepository,
+    WalletRepository,
+};
+use innosystem_common::testing::factories::{CustomerFactory, JobFactory, JobTypeFactory, WalletFactory};
+use uuid::Uuid;
+
+use crate::environment;
+
+#[tokio::test]
+#[ignore = "requires Docker or TEST_DATABASE_URL"]
+async fn counts_the_queued_jobs_and_reserved_funds_blocking_a_customer() {
+    let env = environment().await;
+    let repo = DieselDependencyRepository::new(env.pool.clone());
+    let job_repo = DieselJobRepository::new(env.pool.clone());
+    let wallet_repo = DieselWalletRepository::new(env.pool.clone());
+    let customer = CustomerFactory::new()
+        .create(&DieselCustomerRepository::new(env.pool.clone()))
+        .await
+        .unwrap();
+    let wallet = WalletFactory::new(customer.id).balance_cents(1000).create(&wallet_repo).await.unwrap();
+    let job_type = JobTypeFactory::new()
+        .create(&DieselJobTypeRepository::new(env.pool.clone()))
+        .await
+        .unwrap();
+
+    assert!(repo.customer_blockers(customer.id).await.unwrap().is_empty());
+    assert!(repo.job_type_blockers(job_type.id).await.unwrap().is_empty());
+
+    let queued = JobFactory::new(customer.id, job_type.id).create(&job_repo).await.unwrap();
+    let running = JobFactory::new(customer.id, job_type.id).create(&job_repo).await.unwrap();
+    job_repo.update_status(running.id, JobStatus::Running).await.unwrap();
+    wallet_repo.reserve_funds(wallet.id, 300, None, Some(queued.id)).await.unwrap();
+    wallet_repo.reserve_funds(wallet.id, 200, None, Some(running.id)).await.unwrap();
+    wallet_repo.release_reservation(wallet.id, 50, None, Some(running.id)).await.unwrap();
+
+    assert_eq!(repo.customer_blockers(customer.id).await.unwrap(), vec![
+        Blocker::new(BlockerKind::QueuedJobs, 1),
+        Blocker::new(BlockerKind::ReservedFunds, 450),
+    ]);
+    assert_eq!(repo.job_type_blockers(job_type.id).await.unwrap(), vec![Blocker::new(BlockerKind::QueuedJobs, 1)]);
+    assert_eq!(repo.find_queued_jobs_by_customer(customer.id).await.unwrap(), vec![queued.id]);
+    assert_eq!(repo.find_queued_jobs_by_job_type(job_type.id).await.unwrap(), vec![queued.id]);
+}
+
+#[tokio::test]
+#[ignore = "requires Docker or TEST_DATABASE_URL"]
+async fn projects_are_blocked_by_their_jobs_and_detach_them_when_deleted() {
+    let env = environment().await;
+    let repo = DieselDependencyRepository::new(env.pool.clone());
+    let project_repo = DieselProjectRepository::new(env.pool.clone());
+    let job_repo = DieselJobRepository::new(env.pool.clone());
+    let customer = CustomerFactory::new()
+        .create(&DieselCustomerRepository::new(env.pool.clone()))
+        .await
+        .unwrap();
+    let job_type = JobTypeFactory::new()
+        .create(&DieselJobTypeRepository::new(env.pool.clone()))
+        .await
+        .unwrap();
+    let project = project_repo.create(NewProject {
+        id: Uuid::new_v4(),
+        customer_id: customer.id,
+        name: "Website".to_string(),
+        description: None,
+    }).await.unwrap();
+
+    assert!(repo.project_blockers(project.id).await.unwrap().is_empty());
+    let job = JobFactory::new(customer.id, job_type.id).create(&job_repo).await.unwrap();
+    let mut conn = env.pool.get().unwrap();
+    diesel::sql_query("UPDATE jobs SET project_id = $1 WHERE id = $2")
+        .bind::<diesel::sql_types::Uuid, _>(project.id)
+        .bind::<diesel::sql_types::Uuid, _>(job.id)
+        .execute(&mut conn)
+        .unwrap();
+    assert_eq!(repo.project_blockers(project.id).await.unwrap(), vec![Blocker::new(BlockerKind::Jobs, 1)]);
+
+    // The job outlives its project
+    project_repo.delete(project.id).await.unwrap();
+    assert!(repo.project_blockers(project.id).await.unwrap().is_empty());
+    assert_eq!(job_repo.find_by_id(job.id).await.unwrap().id, job.id);
+}
@@ -0,0 +1,61 @@
+use innosystem_common::models::feature_flag::{FeatureFlagError, NewFeatureFlag};
+use innosystem_common::repositories::{DieselFeatureFlagRepository, FeatureFlagRepository};
+use uuid::Uuid;
+
+use crate::environment;
+
+/// A flag with a name no other test uses
+fn flag(enabled: bool, reseller_ids: Vec<Uuid>) -> NewFeatureFlag {
+    NewFeatureFlag {
+        id: Uuid::new_v4(),
+        name: format!("canary.{}", Uuid::new_v4().simple()),
+        enabled,
+        reseller_ids,
+        description: Some("New dashboard".to_string()),
+        updated_by: "alice".to_string(),
+    }
+}
+
+#[tokio::test]
+#[ignore = "requires Docker or TEST_DATABASE_URL"]
+async fn flags_are_replaced_by_name_and_deleted() {
+    let env = environment().await;
+    let repo = DieselFeatureFlagRepository::new(env.pool.clone());
+    let canary = Uuid::new_v4();
+
+    let created = repo.upsert(flag(false, vec![canary])).await.unwrap();
+    assert!(!created.is_enabled_for(None));
+    assert!(created.is_enabled_for(Some(canary)));
+    assert!(!created.is_enabled_for(Some(Uuid::new_v4())));
+
+    // Writing the same name again replaces the settings but keeps the flag
+    let updated = repo.upsert(NewFeatureFlag {
+        name: created.name.clone(),
+        enabled: true,
+        reseller_ids: vec![],
+        updated_by: "bob".to_string(),
+        ..flag(false, vec![])
+    }).await.unwrap();
+    assert_eq!(updated.id, created.id);
+    assert!(updated.is_enabled_for(None));
+    assert_eq!(updated.updated_by, "bob");
+    assert!(repo.find_by_name(created.name.clone()).await.unwrap().enabled);
+    assert!(repo.list().await.unwrap().iter().any(|f| f.name == created.name));
+
+    let deleted = repo.delete(created.name.clone()).await.unwrap();
+    assert_eq!(deleted.id, created.id);
+    assert!(repo.find_by_name(created.name.clone()).await.unwrap_err().to_string().contains("not found"));
+    assert!(repo.delete(created.name).await.unwrap_err().to_string().contains("not found"));
+}
+
+#[tokio::test]
+#[ignore = "requires Docker or TEST_DATABASE_URL"]
+async fn flag_names_must_be_url_safe() {
+    let env = environment().await;
+    let repo = DieselFeatureFlagRepository::new(env.pool.clone());
+
+    for name in ["", "Read Only", "jobs/disabled"] {
+        let err = repo.upsert(NewFeatureFlag { name: name.to_string(), ..flag(true, vec![]) }).await.unwrap_err();
+        assert!(matches!(err.downcast_ref::<FeatureFlagError>(), Some(FeatureFlagError::InvalidName(_))));
+    }
+}
@@ -0,0 +1,72 @@
+use std::collections::BTreeMap;
+
+use innosystem_common::models::fixture::{CustomerFixture, FixtureSpec, NewFixtureBundle, RunnerFixture};
+use innosystem_common::models::job::JobStatus;
+use innosystem_common::repositories::{
+    CustomerRepository, DieselCustomerRepository, DieselFixtureRepository, DieselJobRepository, DieselJobTypeRepository,
+    DieselResellerRepository, DieselRunnerRepository, DieselWalletRepository, FixtureRepository, JobRepository,
+    JobTypeRepository, ResellerRepository, RunnerRepository, WalletRepository,
+};
+use uuid::Uuid;
+
+use crate::environment;
+
+fn spec(name: &str) -> FixtureSpec {
+    FixtureSpec {
+        name: name.to_string(),
+        reseller: Default::default(),
+        customers: vec![
+            CustomerFixture {
+                name: "Ada".to_string(),
+                balance_cents: 2500,
+                jobs: BTreeMap::from([("pending".to_string(), 2), ("running".to_string(), 1), ("succeeded".to_string(), 1)]),
+            },
+            CustomerFixture {
+                name: "Grace".to_string(),
+                balance_cents: 0,
+                jobs: BTreeMap::from([("failed".to_string(), 1)]),
+            },
+        ],
+        runners: vec![RunnerFixture { name: "fixture-runner".to_string(), status: "active".to_string() }],
+        job_cost_cents: 150,
+    }
+}
+
+#[tokio::test]
+#[ignore = "requires Docker or TEST_DATABASE_URL"]
+async fn provisions_bundles_and_tears_them_down_with_their_records() {
+    let env = environment().await;
+    let repo = DieselFixtureRepository::new(env.pool.clone());
+    let customers = DieselCustomerRepository::new(env.pool.clone());
+    let jobs = DieselJobRepository::new(env.pool.clone());
+    let runners = DieselRunnerRepository::new(env.pool.clone());
+
+    let spec = spec("checkout-smoke");
+    let id = Uuid::new_v4();
+    let records = spec.records(id);
+    let bundle = repo.create(NewFixtureBundle::new(id, &spec, &records, "alice".to_string()), records).await.unwrap();
+    assert_eq!(bundle.name, "checkout-smoke");
+    assert_eq!((bundle.customer_ids.len(), bundle.runner_ids.len(), bundle.job_count), (2, 1, 5));
+    assert!(repo.list().await.unwrap().iter().any(|listed| listed.id == bundle.id));
+
+    let ada = customers.find_by_id(bundle.customer_ids[0]).await.unwrap();
+    assert_eq!(ada.reseller_id, Some(bundle.reseller_id));
+    let wallet = DieselWalletRepository::new(env.pool.clone()).find_by_customer_id(ada.id).await.unwrap();
+    assert_eq!(wallet.balance_cents, 2500);
+    let ada_jobs = jobs.find_by_customer_id(ada.id).await.unwrap();
+    assert_eq!(ada_jobs.len(), 4);
+    let running = ada_jobs.iter().find(|job| job.status == JobStatus::Running).unwrap();
+    assert_eq!(running.runner_id, Some(bundle.runner_ids[0]));
+    assert!(ada_jobs.iter().find(|job| job.status == JobStatus::Succeeded).unwrap().completed_at.is_some());
+    assert!(ada_jobs.iter().filter(|job| job.status == JobStatus::Pending).all(|job| job.completed_at.is_none()));
+    assert_eq!(runners.find_compatible_job_type_ids(bundle.runner_ids[0]).await.unwrap(), vec![bundle.job_type_id]);
+
+    repo.delete(bundle.id).await.unwrap();
+    assert!(repo.find_by_id(bundle.id).await.is_err());
+    assert!(customers.find_by_id(ada.id).await.is_err());
+    assert!(jobs.find_by_id(running.id).await.is_err());
+    assert!(runners.find_by_id(bundle.runner_ids[0]).await.is_err());
+    assert!(DieselJobTypeRepository::new(env.pool.clone()).find_by_id(bundle.job_type_id).await.is_err());
+    assert!(DieselResellerRepository::new(env.pool.clone()).find_by_id(bundle.reseller_id).await.is_err());
+    assert!(repo.delete(bundle.id).await.unwrap_err().to_string().contains("not found"));
+}
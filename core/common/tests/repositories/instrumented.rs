@@ -0,0 +1,38 @@
+use std::sync::Arc;
+use std::time::Duration;
+
+use innosystem_common::repositories::{DieselJobTypeRepository, Instrumented, JobTypeRepository, RepositoryMetrics, RepositoryMetricsConfig};
+use innosystem_common::testing::factories::JobTypeFactory;
+use uuid::Uuid;
+
+use crate::environment;
+
+#[tokio::test]
+#[ignore = "requires Docker or TEST_DATABASE_URL"]
+async fn counts_and_times_repository_calls() {
+    let env = environment().await;
+    let metrics = Arc::new(RepositoryMetrics::new(RepositoryMetricsConfig { slow_query_threshold_ms: 60_000 }));
+    let repo = Instrumented::new("job_type", DieselJobTypeRepository::new(env.pool.clone()), metrics.clone());
+
+    let job_type = JobTypeFactory::new().create(&repo).await.unwrap();
+    repo.find_by_id(job_type.id).await.unwrap();
+    assert!(repo.find_by_id(Uuid::new_v4()).await.is_err());
+
+    let snapshot = metrics.snapshot();
+    let find = snapshot.iter().find(|m| m.entity == "job_type" && m.method == "find_by_id").unwrap();
+    assert_eq!((find.calls, find.errors, find.slow_calls), (2, 1, 0));
+    assert!(find.max_seconds > 0.0 && find.total_seconds >= find.max_seconds);
+    let create = snapshot.iter().find(|m| m.method == "create").unwrap();
+    assert_eq!((create.calls, create.errors), (1, 0));
+
+    // Calls at or above the threshold count as slow
+    assert!(metrics.record("job_type", "list_all", Duration::from_secs(60), false));
+    assert!(!metrics.record("job_type", "list_all", Duration::from_millis(5), false));
+    let list_all = metrics.snapshot().into_iter().find(|m| m.method == "list_all").unwrap();
+    assert_eq!((list_all.calls, list_all.slow_calls), (2, 1));
+    assert!((list_all.avg_seconds() - 30.0025).abs() < 1e-9);
+
+    // A zero threshold disables the slow query log
+    let disabled = RepositoryMetrics::new(RepositoryMetricsConfig { slow_query_threshold_ms: 0 });
+    assert!(!disabled.record("job", "find_by_id", Duration::from_secs(60), false));
+}
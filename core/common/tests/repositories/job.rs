@@ -0,0 +1,753 @@
+use chrono::{Duration, Utc};
+use diesel::RunQueryDsl;
+use innosystem_common::Error;
+use innosystem_common::models::job::{CompletionResolution, HeldCompletion, JobHolder, JobStatus, NewJob, PriorityLevel};
+use innosystem_common::models::job_attempt::AttemptStatus;
+use innosystem_common::models::job_cost::CostBreakdown;
+use innosystem_common::models::job_error::{codes, JobError};
+use innosystem_common::models::runner::{NewRunner, RunnerStatus};
+use innosystem_common::repositories::job::{JobCursor, JobFilter, JobSortOrder, Pagination};
+use innosystem_common::repositories::{
+    DieselCustomerRepository, DieselJobAttemptRepository, DieselJobRepository, DieselJobTypeRepository,
+    DieselRunnerRepository, JobAttemptRepository, JobRepository, RunnerRepository,
+};
+use innosystem_common::testing::TestEnvironment;
+use innosystem_common::testing::factories::{CustomerFactory, JobFactory, JobTypeFactory};
+use serde_json::json;
+use uuid::Uuid;
+
+use crate::environment;
+
+/// Insert a customer and a job type that jobs can reference
+async fn customer_and_job_type(env: &TestEnvironment) -> (Uuid, Uuid) {
+    let customer = CustomerFactory::new()
+        .create(&DieselCustomerRepository::new(env.pool.clone()))
+        .await
+        .unwrap();
+    let job_type = JobTypeFactory::new()
+        .create(&DieselJobTypeRepository::new(env.pool.clone()))
+        .await
+        .unwrap();
+    (customer.id, job_type.id)
+}
+
+#[tokio::test]
+#[ignore = "requires Docker or TEST_DATABASE_URL"]
+async fn creates_and_finds_jobs() {
+    let env = environment().await;
+    let repo = DieselJobRepository::new(env.pool.clone());
+    let (customer_id, job_type_id) = customer_and_job_type(&env).await;
+
+    let job = JobFactory::new(customer_id, job_type_id)
+        .estimated_cost_cents(500)
+        .test_mode()
+        .create(&repo)
+        .await
+        .unwrap();
+    assert!(matches!(job.status, JobStatus::Pending));
+    assert!(job.test_mode);
+
+    let found = repo.find_by_id(job.id).await.unwrap();
+    assert_eq!(found.customer_id, customer_id);
+    assert_eq!(found.cost_cents, 500);
+    assert!(found.test_mode);
+
+    let missing = repo.find_by_id(Uuid::new_v4()).await.unwrap_err();
+    assert!(matches!(missing, Error::NotFound(_)));
+
+    assert_eq!(repo.find_by_customer_id(customer_id).await.unwrap().len(), 1);
+    assert!(repo.find_by_status(JobStatus::Pending).await.unwrap().iter().any(|j| j.id == job.id));
+    assert!(repo.find_pending_jobs(1000).await.unwrap().iter().any(|j| j.id == job.id));
+    assert_eq!(repo.count_pending_by_job_type(job_type_id).await.unwrap(), 1);
+    assert_eq!(repo.count_pending_by_job_type(Uuid::new_v4()).await.unwrap(), 0);
+}
+
+#[tokio::test]
+#[ignore = "requires Docker or TEST_DATABASE_URL"]
+async fn finds_jobs_by_public_id() {
+    let env = environment().await;
+    let repo = DieselJobRepository::new(env.pool.clone());
+    let (customer_id, job_type_id) = customer_and_job_type(&env).await;
+
+    let job = JobFactory::new(customer_id, job_type_id).create(&repo).await.unwrap();
+    let other = JobFactory::new(customer_id, job_type_id).create(&repo).await.unwrap();
+    assert!(job.public_id.starts_with("job_"));
+    assert_ne!(job.public_id, other.public_id);
+
+    assert_eq!(repo.find_by_public_id(&job.public_id).await.unwrap().id, job.id);
+    assert!(matches!(repo.find_by_public_id("job_missing").await.unwrap_err(), Error::NotFound(_)));
+
+    let filter = JobFilter { public_id: Some(other.public_id.clone()), ..Default::default() };
+    let (jobs, total) = repo.query_jobs(filter, None, None).await.unwrap();
+    assert_eq!(total, 1);
+    assert_eq!(jobs[0].id, other.id);
+}
+
+#[tokio::test]
+#[ignore = "requires Docker or TEST_DATABASE_URL"]
+async fn jobs_past_their_deadline_are_refused_and_found_for_expiry() {
+    let env = environment().await;
+    let repo = DieselJobRepository::new(env.pool.clone());
+    let (customer_id, job_type_id) = customer_and_job_type(&env).await;
+    let now = Utc::now();
+
+    let overdue = JobFactory::new(customer_id, job_type_id).expires_at(now - Duration::minutes(1)).create(&repo).await.unwrap();
+    let timely = JobFactory::new(customer_id, job_type_id).expires_at(now + Duration::hours(1)).create(&repo).await.unwrap();
+    assert_eq!(overdue.expires_at.map(|at| at.timestamp()), Some((now - Duration::minutes(1)).timestamp()));
+
+    let refused = repo.set_started(overdue.id).await.unwrap_err();
+    assert!(matches!(&refused, Error::InvalidInput(reason) if reason.contains("expired")), "{}", refused);
+    assert!(matches!(repo.lease(overdue.id, PriorityLevel::High, None, now + Duration::minutes(1)).await, Err(Error::InvalidInput(_))));
+    assert!(matches!(repo.set_started(timely.id).await.unwrap().status, JobStatus::Running));
+
+    let found: Vec<Uuid> = repo.find_expired(Utc::now()).await.unwrap().into_iter().map(|job| job.id).collect();
+    assert!(found.contains(&overdue.id));
+    assert!(!found.contains(&timely.id));
+
+    assert!(matches!(repo.update_status(overdue.id, JobStatus::Expired).await.unwrap().status, JobStatus::Expired));
+    assert!(!repo.find_expired(Utc::now()).await.unwrap().iter().any(|job| job.id == overdue.id));
+    assert!(repo.update_status(overdue.id, JobStatus::Pending).await.is_err());
+}
+
+#[tokio::test]
+#[ignore = "requires Docker or TEST_DATABASE_URL"]
+async fn moves_jobs_through_their_lifecycle() {
+    let env = environment().await;
+    let repo = DieselJobRepository::new(env.pool.clone());
+    let (customer_id, job_type_id) = customer_and_job_type(&env).await;
+    let job = JobFactory::new(customer_id, job_type_id).create(&repo).await.unwrap();
+
+    let scheduled = repo.update_status(job.id, JobStatus::Scheduled).await.unwrap();
+    assert!(matches!(scheduled.status, JobStatus::Scheduled));
+    assert!(repo.update_status(Uuid::new_v4(), JobStatus::Running).await.is_err());
+
+    let started = repo.set_started(job.id).await.unwrap();
+    assert!(matches!(started.status, JobStatus::Running));
+    assert!(started.updated_at.is_some());
+    assert!(repo.find_stalled_jobs(0).await.unwrap().iter().any(|j| j.id == job.id));
+    assert!(!repo.find_stalled_jobs(60).await.unwrap().iter().any(|j| j.id == job.id));
+
+    let output = json!({ "result": "ok" });
+    let succeeded = repo.set_completed(job.id, true, Some(output.clone()), None, 75).await.unwrap();
+    assert!(matches!(succeeded.status, JobStatus::Succeeded));
+    assert_eq!(succeeded.cost_cents, 75);
+    assert_eq!(succeeded.output_data, Some(output));
+    assert!(succeeded.completed_at.is_some());
+    assert_eq!(succeeded.cost_breakdown, None);
+
+    let cost = CostBreakdown::completed(50, &PriorityLevel::High, 0);
+    repo.set_cost_breakdown(job.id, cost).await.unwrap();
+    assert_eq!(repo.find_by_id(job.id).await.unwrap().cost_breakdown, Some(cost));
+    assert!(repo.set_cost_breakdown(Uuid::new_v4(), cost).await.is_err());
+
+    // Finished jobs cannot be started or moved back
+    assert!(matches!(repo.set_started(job.id).await.unwrap_err(), Error::InvalidInput(_)));
+    assert!(matches!(repo.update_status(job.id, JobStatus::Pending).await.unwrap_err(), Error::InvalidInput(_)));
+    assert!(matches!(repo.set_started(Uuid::new_v4()).await.unwrap_err(), Error::NotFound(_)));
+
+    let other = JobFactory::new(customer_id, job_type_id).create(&repo).await.unwrap();
+    let error = JobError::provider(codes::PROVIDER_TIMEOUT, "timed out");
+    let failed = repo.set_completed(other.id, false, None, Some(error), 0).await.unwrap();
+    assert!(matches!(failed.status, JobStatus::Failed));
+    assert_eq!(failed.error.map(|e| e.code), Some(codes::PROVIDER_TIMEOUT.to_string()));
+}
+
+#[tokio::test]
+#[ignore = "requires Docker or TEST_DATABASE_URL"]
+async fn leases_prefetched_jobs_until_they_are_started_or_expire() {
+    let env = environment().await;
+    let repo = DieselJobRepository::new(env.pool.clone());
+    let (customer_id, job_type_id) = customer_and_job_type(&env).await;
+    let now = Utc::now();
+
+    let started = JobFactory::new(customer_id, job_type_id).create(&repo).await.unwrap();
+    let expired = JobFactory::new(customer_id, job_type_id).create(&repo).await.unwrap();
+    let held = JobFactory::new(customer_id, job_type_id).create(&repo).await.unwrap();
+    repo.lease(started.id, PriorityLevel::High, None, now + Duration::minutes(1)).await.unwrap();
+    repo.lease(expired.id, PriorityLevel::High, None, now - Duration::minutes(1)).await.unwrap();
+    repo.lease(held.id, PriorityLevel::Low, None, now + Duration::minutes(1)).await.unwrap();
+
+    // A leased job shows as claimed until it is started
+    let leased = repo.find_by_id(started.id).await.unwrap();
+    assert!(leased.claimed_at.is_some());
+    assert_eq!(leased.lease_expires_at.map(|expires_at| expires_at.timestamp()), Some((now + Duration::minutes(1)).timestamp()));
+    assert_eq!(leased.priority, PriorityLevel::High);
+    assert!(repo.set_started(started.id).await.unwrap().lease_expires_at.is_none());
+
+    let released = repo.release_expired_leases(now).await.unwrap();
+    assert!(released.contains(&(expired.id, PriorityLevel::High)));
+    assert!(released.iter().all(|(id, _)| *id != held.id && *id != started.id));
+    assert!(repo.find_by_id(expired.id).await.unwrap().lease_expires_at.is_none());
+    assert!(!repo.release_expired_leases(now).await.unwrap().iter().any(|(id, _)| *id == expired.id));
+
+    // Jobs that can no longer be started cannot be leased
+    let cancelled = JobFactory::new(customer_id, job_type_id).create(&repo).await.unwrap();
+    repo.update_status(cancelled.id, JobStatus::Cancelled).await.unwrap();
+    let err = repo.lease(cancelled.id, PriorityLevel::Medium, None, now).await.unwrap_err();
+    assert!(matches!(err, Error::InvalidInput(_)));
+}
+
+#[tokio::test]
+#[ignore = "requires Docker or TEST_DATABASE_URL"]
+async fn finds_jobs_waiting_since_a_cutoff() {
+    let env = environment().await;
+    let repo = DieselJobRepository::new(env.pool.clone());
+    let (customer_id, job_type_id) = customer_and_job_type(&env).await;
+
+    let waiting = JobFactory::new(customer_id, job_type_id).create(&repo).await.unwrap();
+    let scheduled = JobFactory::new(customer_id, job_type_id).create(&repo).await.unwrap();
+    repo.update_status(scheduled.id, JobStatus::Scheduled).await.unwrap();
+    let running = JobFactory::new(customer_id, job_type_id).create(&repo).await.unwrap();
+    repo.set_started(running.id).await.unwrap();
+    // Held back until its submission window opens tomorrow, so not abandoned
+    let deferred = JobFactory::new(customer_id, job_type_id).create(&repo).await.unwrap();
+    let deferred = repo.schedule(deferred.id, Utc::now() + Duration::days(1)).await.unwrap();
+    assert!(matches!(deferred.status, JobStatus::Scheduled));
+    assert!(deferred.scheduled_for.is_some());
+
+    let cutoff = Utc::now() - Duration::hours(1);
+    let found = repo.find_waiting_since(cutoff).await.unwrap();
+    assert!(!found.iter().any(|j| j.customer_id == customer_id));
+
+    let mut conn = env.pool.get().unwrap();
+    diesel::sql_query("UPDATE jobs SET created_at = NOW() - INTERVAL '2 hours', updated_at = NOW() - INTERVAL '2 hours' WHERE customer_id = $1")
+        .bind::<diesel::sql_types::Uuid, _>(customer_id)
+        .execute(&mut conn)
+        .unwrap();
+
+    let mut found: Vec<Uuid> = repo.find_waiting_since(cutoff).await.unwrap()
+        .into_iter()
+        .filter(|j| j.customer_id == customer_id)
+        .map(|j| j.id)
+        .collect();
+    let mut expected = vec![waiting.id, scheduled.id];
+    found.sort();
+    expected.sort();
+    assert_eq!(found, expected);
+}
+
+#[tokio::test]
+#[ignore = "requires Docker or TEST_DATABASE_URL"]
+async fn reports_queue_wait_percentiles_per_priority() {
+    let env = environment().await;
+    let repo = DieselJobRepository::new(env.pool.clone());
+    let (customer_id, job_type_id) = customer_and_job_type(&env).await;
+
+    // Jobs submitted 10 and 20 days ago, claimed from the Critical queue just now
+    let mut ids = Vec::new();
+    for days in [10, 20] {
+        let job = JobFactory::new(customer_id, job_type_id).create(&repo).await.unwrap();
+        let mut conn = env.pool.get().unwrap();
+        diesel::sql_query(format!("UPDATE jobs SET created_at = NOW() - INTERVAL '{0} days', queued_at = NOW() - INTERVAL '{0} days' WHERE id = $1", days))
+            .bind::<diesel::sql_types::Uuid, _>(job.id)
+            .execute(&mut conn)
+            .unwrap();
+        repo.record_claim(job.id, PriorityLevel::Critical).await.unwrap();
+        ids.push(job.id);
+    }
+
+    let claimed = repo.find_by_id(ids[0]).await.unwrap();
+    assert!(claimed.claimed_at.is_some());
+    assert_eq!(claimed.priority, PriorityLevel::Critical);
+
+    let stats = repo.get_queue_wait_stats(60).await.unwrap();
+    let critical = stats.iter().find(|s| s.priority == PriorityLevel::Critical).unwrap();
+    assert!(critical.samples >= 2);
+    assert!(critical.max_seconds >= Duration::days(20).num_seconds() as f64);
+    assert!(critical.p50_seconds <= critical.p90_seconds);
+    assert!(critical.p90_seconds <= critical.p99_seconds);
+    assert!(critical.p99_seconds <= critical.max_seconds);
+
+    assert!(matches!(repo.record_claim(Uuid::new_v4(), PriorityLevel::Low).await, Err(Error::NotFound(_))));
+}
+
+#[tokio::test]
+#[ignore = "requires Docker or TEST_DATABASE_URL"]
+async fn reports_execution_stats_of_a_job_type() {
+    let env = environment().await;
+    let repo = DieselJobRepository::new(env.pool.clone());
+    let (customer_id, job_type_id) = customer_and_job_type(&env).await;
+
+    // Jobs that ran 10, 20 and 30 seconds; the last one failed
+    for (seconds, success) in [(10, true), (20, true), (30, false)] {
+        let job = JobFactory::new(customer_id, job_type_id).create(&repo).await.unwrap();
+        repo.set_completed(job.id, success, None, None, 0).await.unwrap();
+        let mut conn = env.pool.get().unwrap();
+        diesel::sql_query(format!("UPDATE jobs SET started_at = completed_at - INTERVAL '{} seconds' WHERE id = $1", seconds))
+            .bind::<diesel::sql_types::Uuid, _>(job.id)
+            .execute(&mut conn)
+            .unwrap();
+    }
+    // Still pending, so not part of the statistics
+    JobFactory::new(customer_id, job_type_id).create(&repo).await.unwrap();
+
+    let stats = repo.get_job_type_execution_stats(job_type_id, 60).await.unwrap();
+    assert_eq!((stats.succeeded, stats.failed), (2, 1));
+    assert_eq!(stats.success_rate(), Some(2.0 / 3.0));
+    assert_eq!(stats.p50_processing_seconds, Some(20.0));
+    assert!((stats.p95_processing_seconds.unwrap() - 29.0).abs() < 1e-9);
+
+    let idle = repo.get_job_type_execution_stats(Uuid::new_v4(), 60).await.unwrap();
+    assert_eq!(idle.finished(), 0);
+    assert_eq!(idle.success_rate(), None);
+    assert_eq!(idle.p50_processing_seconds, None);
+}
+
+#[tokio::test]
+#[ignore = "requires Docker or TEST_DATABASE_URL"]
+async fn queries_jobs_with_filters_and_pagination() {
+    let env = environment().await;
+    let repo = DieselJobRepository::new(env.pool.clone());
+    let (customer_id, job_type_id) = customer_and_job_type(&env).await;
+
+    let mut ids = Vec::new();
+    for _ in 0..5 {
+        let job = JobFactory::new(customer_id, job_type_id)
+            .priority(PriorityLevel::High)
+            .create(&repo)
+            .await
+            .unwrap();
+        ids.push(job.id);
+    }
+    repo.set_completed(ids[0], false, None, None, 0).await.unwrap();
+
+    let filter = || JobFilter { customer_id: Some(customer_id), ..JobFilter::default() };
+
+    let (page, total) = repo
+        .query_jobs(filter(), Some(JobSortOrder::CreatedAsc), Some(Pagination { page: 0, per_page: 2 }))
+        .await
+        .unwrap();
+    assert_eq!(total, 5);
+    assert_eq!(page.len(), 2);
+
+    let (last_page, _) = repo
+        .query_jobs(filter(), Some(JobSortOrder::CreatedDesc), Some(Pagination { page: 2, per_page: 2 }))
+        .await
+        .unwrap();
+    assert_eq!(last_page.len(), 1);
+
+    // Pages far past the end are empty instead of overflowing the offset
+    let (beyond, total) = repo
+        .query_jobs(filter(), None, Some(Pagination { page: u32::MAX, per_page: u32::MAX }))
+        .await
+        .unwrap();
+    assert!(beyond.is_empty());
+    assert_eq!(total, 5);
+
+    let (failed, total) = repo
+        .query_jobs(JobFilter { failed_only: true, ..filter() }, None, None)
+        .await
+        .unwrap();
+    assert_eq!(total, 1);
+    assert_eq!(failed[0].id, ids[0]);
+
+    let (completed, _) = repo
+        .query_jobs(JobFilter { completed_only: true, ..filter() }, None, None)
+        .await
+        .unwrap();
+    assert_eq!(completed.len(), 1);
+
+    let (pending, total) = repo
+        .query_jobs(JobFilter { status: Some(JobStatus::Pending), job_type_id: Some(job_type_id), ..filter() }, None, None)
+        .await
+        .unwrap();
+    assert_eq!(total, 4);
+    assert_eq!(pending.len(), 4);
+}
+
+#[tokio::test]
+#[ignore = "requires Docker or TEST_DATABASE_URL"]
+async fn sorts_jobs_by_the_priority_they_were_submitted_with() {
+    let env = environment().await;
+    let repo = DieselJobRepository::new(env.pool.clone());
+    let (customer_id, job_type_id) = customer_and_job_type(&env).await;
+
+    for priority in [PriorityLevel::Medium, PriorityLevel::Critical, PriorityLevel::Low, PriorityLevel::High] {
+        let job = JobFactory::new(customer_id, job_type_id).priority(priority.clone()).create(&repo).await.unwrap();
+        assert_eq!(job.priority, priority);
+        assert_eq!(repo.find_by_id(job.id).await.unwrap().priority, priority);
+    }
+
+    let filter = || JobFilter { customer_id: Some(customer_id), ..JobFilter::default() };
+    let (jobs, _) = repo.query_jobs(filter(), Some(JobSortOrder::PriorityDesc), None).await.unwrap();
+    let levels: Vec<_> = jobs.iter().map(|job| job.priority.as_i32()).collect();
+    assert_eq!(levels, vec![3, 2, 1, 0]);
+
+    let (jobs, _) = repo.query_jobs(filter(), Some(JobSortOrder::PriorityAsc), None).await.unwrap();
+    let levels: Vec<_> = jobs.iter().map(|job| job.priority.as_i32()).collect();
+    assert_eq!(levels, vec![0, 1, 2, 3]);
+}
+
+#[tokio::test]
+#[ignore = "requires Docker or TEST_DATABASE_URL"]
+async fn pages_jobs_by_cursor() {
+    let env = environment().await;
+    let repo = DieselJobRepository::new(env.pool.clone());
+    let (customer_id, job_type_id) = customer_and_job_type(&env).await;
+
+    let mut ids = Vec::new();
+    for _ in 0..5 {
+        ids.push(JobFactory::new(customer_id, job_type_id).create(&repo).await.unwrap().id);
+    }
+    let filter = || JobFilter { customer_id: Some(customer_id), ..JobFilter::default() };
+
+    let (first, cursor) = repo.query_jobs_after(filter(), None, 2).await.unwrap();
+    assert_eq!(first.iter().map(|j| j.id).collect::<Vec<_>>(), vec![ids[4], ids[3]]);
+    let cursor = cursor.unwrap();
+    assert_eq!(JobCursor::decode(&cursor.encode()), Some(cursor.clone()));
+
+    // Jobs inserted between pages do not shift the following pages
+    JobFactory::new(customer_id, job_type_id).create(&repo).await.unwrap();
+
+    let (second, cursor) = repo.query_jobs_after(filter(), Some(cursor), 2).await.unwrap();
+    assert_eq!(second.iter().map(|j| j.id).collect::<Vec<_>>(), vec![ids[2], ids[1]]);
+
+    let (last, cursor) = repo.query_jobs_after(filter(), cursor, 2).await.unwrap();
+    assert_eq!(last.iter().map(|j| j.id).collect::<Vec<_>>(), vec![ids[0]]);
+    assert!(cursor.is_none());
+}
+
+#[tokio::test]
+#[ignore = "requires Docker or TEST_DATABASE_URL"]
+async fn reports_job_statistics() {
+    let env = environment().await;
+    let repo = DieselJobRepository::new(env.pool.clone());
+    let (customer_id, job_type_id) = customer_and_job_type(&env).await;
+
+    let (cost_before, completed_before) = repo.get_cost_statistics().await.unwrap();
+
+    let first = JobFactory::new(customer_id, job_type_id).create(&repo).await.unwrap();
+    let second = JobFactory::new(customer_id, job_type_id).create(&repo).await.unwrap();
+    repo.set_completed(first.id, true, None, None, 120).await.unwrap();
+    repo.set_completed(second.id, true, None, None, 80).await.unwrap();
+
+    let (cost_after, completed_after) = repo.get_cost_statistics().await.unwrap();
+    assert_eq!(cost_after - cost_before, 200);
+    assert_eq!(completed_after - completed_before, 2);
+
+    let by_customer = repo.get_job_stats_by_customer().await.unwrap();
+    assert!(by_customer.contains(&(customer_id, 2)));
+
+    let by_status = repo.get_job_stats_by_status().await.unwrap();
+    let succeeded = by_status.iter().find(|(status, _)| status == JobStatus::Succeeded.as_str());
+    assert!(succeeded.is_some_and(|(_, count)| *count >= 2));
+}
+
+#[tokio::test]
+#[ignore = "requires Docker or TEST_DATABASE_URL"]
+async fn reports_customer_activity_within_a_period() {
+    let env = environment().await;
+    let repo = DieselJobRepository::new(env.pool.clone());
+    let (customer_id, job_type_id) = customer_and_job_type(&env).await;
+
+    let succeeded = JobFactory::new(customer_id, job_type_id).create(&repo).await.unwrap();
+    let failed = JobFactory::new(customer_id, job_type_id).create(&repo).await.unwrap();
+    let cancelled = JobFactory::new(customer_id, job_type_id).create(&repo).await.unwrap();
+    JobFactory::new(customer_id, job_type_id).estimated_cost_cents(40).create(&repo).await.unwrap();
+    JobFactory::new(customer_id, job_type_id).test_mode().create(&repo).await.unwrap();
+    repo.set_completed(succeeded.id, true, None, None, 120).await.unwrap();
+    repo.set_completed(failed.id, false, None, None, 30).await.unwrap();
+    repo.update_status(cancelled.id, JobStatus::Cancelled).await.unwrap();
+
+    // Cancelled and test mode jobs are left out; pending jobs count with their estimate
+    let now = Utc::now();
+    let activity = repo.get_customer_activity(now - Duration::hours(1), now + Duration::minutes(1)).await.unwrap();
+    let customer = activity.iter().find(|activity| activity.customer_id == customer_id).unwrap();
+    assert_eq!(customer.jobs, 3);
+    assert_eq!(customer.cost_cents, 190);
+    assert_eq!((customer.succeeded, customer.failed), (1, 1));
+    assert_eq!(customer.failure_rate(), Some(0.5));
+
+    let earlier = repo.get_customer_activity(now - Duration::days(2), now - Duration::days(1)).await.unwrap();
+    assert!(earlier.iter().all(|activity| activity.customer_id != customer_id));
+}
+
+#[tokio::test]
+#[ignore = "requires Docker or TEST_DATABASE_URL"]
+async fn bulk_updates_job_status() {
+    let env = environment().await;
+    let repo = DieselJobRepository::new(env.pool.clone());
+    let (customer_id, job_type_id) = customer_and_job_type(&env).await;
+
+    let first = JobFactory::new(customer_id, job_type_id).create(&repo).await.unwrap();
+    let second = JobFactory::new(customer_id, job_type_id).create(&repo).await.unwrap();
+
+    assert_eq!(repo.bulk_update_status(Vec::new(), JobStatus::Cancelled).await.unwrap(), 0);
+    let updated = repo.bulk_update_status(vec![first.id, second.id], JobStatus::Cancelled).await.unwrap();
+    assert_eq!(updated, 2);
+
+    for job in repo.find_by_customer_id(customer_id).await.unwrap() {
+        assert!(matches!(job.status, JobStatus::Cancelled));
+    }
+}
+
+#[tokio::test]
+#[ignore = "requires Docker or TEST_DATABASE_URL"]
+async fn fans_jobs_out_into_sub_tasks_and_back_in() {
+    let env = environment().await;
+    let repo = std::sync::Arc::new(DieselJobRepository::new(env.pool.clone()));
+    let (customer_id, job_type_id) = customer_and_job_type(&env).await;
+
+    let parent = JobFactory::new(customer_id, job_type_id)
+        .customer_reference("PO-7")
+        .create(repo.as_ref())
+        .await
+        .unwrap();
+    let new_sub_tasks: Vec<NewJob> = (0..4)
+        .map(|index| parent.sub_task(index, job_type_id, json!({ "page": index }), 100).into())
+        .collect();
+
+    // Only a running job fans out, and only once
+    assert!(matches!(repo.fan_out(parent.id, new_sub_tasks.clone()).await, Err(Error::InvalidInput(_))));
+    let parent = repo.set_started(parent.id).await.unwrap();
+    let sub_tasks = repo.fan_out(parent.id, new_sub_tasks.clone()).await.unwrap();
+    assert!(matches!(repo.fan_out(parent.id, new_sub_tasks).await, Err(Error::InvalidInput(_))));
+    assert_eq!(sub_tasks.iter().map(|job| job.sub_task_index).collect::<Vec<_>>(), vec![Some(0), Some(1), Some(2), Some(3)]);
+    assert!(sub_tasks.iter().all(|job| job.parent_id == Some(parent.id) && job.customer_reference.as_deref() == Some("PO-7")));
+    assert_eq!(repo.find_by_id(parent.id).await.unwrap().sub_task_count, Some(4));
+    assert_eq!(repo.find_sub_tasks(parent.id).await.unwrap().iter().map(|job| job.id).collect::<Vec<_>>(), sub_tasks.iter().map(|job| job.id).collect::<Vec<_>>());
+    assert!(repo.find_fanned_out().await.unwrap().iter().any(|job| job.id == parent.id));
+    assert!(repo.fan_in(parent.id).await.unwrap().is_none());
+
+    // Waiting for sub-tasks is not stalling
+    diesel::sql_query("UPDATE jobs SET updated_at = NOW() - INTERVAL '2 hours' WHERE id = $1")
+        .bind::<diesel::sql_types::Uuid, _>(parent.id)
+        .execute(&mut env.pool.get().unwrap())
+        .unwrap();
+    assert!(!repo.find_stalled_jobs(60).await.unwrap().iter().any(|job| job.id == parent.id));
+
+    repo.update_status(sub_tasks[3].id, JobStatus::Cancelled).await.unwrap();
+    for sub_task in &sub_tasks[..2] {
+        repo.set_started(sub_task.id).await.unwrap();
+        repo.set_completed(sub_task.id, true, None, None, 100).await.unwrap();
+        repo.set_cost_breakdown(sub_task.id, CostBreakdown::completed(100, &PriorityLevel::Medium, 0)).await.unwrap();
+    }
+    repo.set_started(sub_tasks[2].id).await.unwrap();
+    repo.set_completed(sub_tasks[2].id, false, None, None, 0).await.unwrap();
+
+    // Sub-tasks finishing at the same time finish their parent once
+    let fan_ins: Vec<_> = (0..4)
+        .map(|_| {
+            let repo = repo.clone();
+            tokio::spawn(async move { repo.fan_in(parent.id).await.unwrap() })
+        })
+        .collect();
+    let mut finished = Vec::new();
+    for fan_in in fan_ins {
+        finished.extend(fan_in.await.unwrap());
+    }
+    assert_eq!(finished.len(), 1);
+    assert_eq!(finished[0].status, JobStatus::Failed);
+    assert_eq!(finished[0].cost_cents, 200);
+    assert_eq!(finished[0].cost_breakdown.map(|cost| (cost.base_cents, cost.total_cents)), Some((200, 200)));
+    assert!(finished[0].completed_at.is_some());
+    assert!(!repo.find_fanned_out().await.unwrap().iter().any(|job| job.id == parent.id));
+}
+
+#[tokio::test]
+#[ignore = "requires Docker or TEST_DATABASE_URL"]
+async fn late_results_are_checked_against_the_current_holder() {
+    let env = environment().await;
+    let repo = DieselJobRepository::new(env.pool.clone());
+    let attempts = DieselJobAttemptRepository::new(env.pool.clone());
+    let runners = DieselRunnerRepository::new(env.pool.clone());
+    let (customer_id, job_type_id) = customer_and_job_type(&env).await;
+    let mut runner_ids = Vec::new();
+    for name in ["lost-runner", "other-runner"] {
+        let runner = runners.register(NewRunner {
+            id: Uuid::new_v4(),
+            name: name.to_string(),
+            description: None,
+            status: RunnerStatus::Active.as_str().to_string(),
+            compatible_job_types: Vec::new(),
+        }).await.unwrap();
+        runner_ids.push(runner.id);
+    }
+    let (lost, other) = (runner_ids[0], runner_ids[1]);
+    let since = Utc::now() - Duration::seconds(1);
+
+    // Handed back and restarted elsewhere: the late result is discarded
+    let taken_over = JobFactory::new(customer_id, job_type_id).create(&repo).await.unwrap();
+    repo.assign_runner(taken_over.id, lost).await.unwrap();
+    repo.set_started(taken_over.id).await.unwrap();
+    let lost_attempt = attempts.start(taken_over.id, Some(lost)).await.unwrap();
+    assert_eq!(repo.find_running_by_runner(lost).await.unwrap().iter().map(|job| job.id).collect::<Vec<_>>(), vec![taken_over.id]);
+
+    let handed_back = repo.hand_back(taken_over.id, lost).await.unwrap().unwrap();
+    assert!(matches!(handed_back.status, JobStatus::Pending));
+    assert!(repo.hand_back(taken_over.id, lost).await.unwrap().is_none());
+    let abandoned = attempts.find_abandoned_by_runner(lost, since).await.unwrap();
+    assert_eq!(abandoned.iter().map(|attempt| attempt.id).collect::<Vec<_>>(), vec![lost_attempt.id]);
+    assert_eq!(abandoned[0].attempt_status(), Some(AttemptStatus::Abandoned));
+
+    repo.assign_runner(taken_over.id, other).await.unwrap();
+    repo.set_started(taken_over.id).await.unwrap();
+    let other_attempt = attempts.start(taken_over.id, Some(other)).await.unwrap();
+    let late = JobHolder { runner_id: Some(lost), attempt_id: Some(lost_attempt.id) };
+    let (resolution, job) = repo.complete_held(taken_over.id, late, true, Some(json!({ "late": true })), None, 40).await.unwrap();
+    assert_eq!(resolution, CompletionResolution::Discarded);
+    assert!(matches!(job.status, JobStatus::Running));
+    assert_eq!(job.output_data, None);
+
+    let current = JobHolder { runner_id: Some(other), attempt_id: Some(other_attempt.id) };
+    let (resolution, job) = repo.complete_held(taken_over.id, current, true, None, None, 60).await.unwrap();
+    assert_eq!(resolution, CompletionResolution::Held);
+    assert!(matches!(job.status, JobStatus::Succeeded));
+    assert_eq!(job.cost_cents, 60);
+
+    // Handed back but not restarted: the late result is kept instead of running it again
+    let reclaimed = JobFactory::new(customer_id, job_type_id).create(&repo).await.unwrap();
+    repo.assign_runner(reclaimed.id, lost).await.unwrap();
+    repo.set_started(reclaimed.id).await.unwrap();
+    let attempt = attempts.start(reclaimed.id, Some(lost)).await.unwrap();
+    repo.hand_back(reclaimed.id, lost).await.unwrap();
+    let late = JobHolder { runner_id: Some(lost), attempt_id: Some(attempt.id) };
+    let (resolution, job) = repo.complete_held(reclaimed.id, late, false, None, None, 0).await.unwrap();
+    assert_eq!(resolution, CompletionResolution::Reclaimed);
+    assert!(matches!(job.status, JobStatus::Failed));
+    assert!(job.lease_expires_at.is_none());
+    assert!(repo.find_running_by_runner(lost).await.unwrap().is_empty());
+}
+
+#[tokio::test]
+#[ignore = "requires Docker or TEST_DATABASE_URL"]
+async fn batched_results_are_stored_together_or_not_at_all() {
+    let env = environment().await;
+    let repo = DieselJobRepository::new(env.pool.clone());
+    let (customer_id, job_type_id) = customer_and_job_type(&env).await;
+
+    let mut completions = Vec::new();
+    for (success, cost_cents) in [(true, 10), (false, 0)] {
+        let job = JobFactory::new(customer_id, job_type_id).create(&repo).await.unwrap();
+        repo.set_started(job.id).await.unwrap();
+        completions.push(HeldCompletion {
+            job_id: job.id,
+            holder: JobHolder::default(),
+            success,
+            output: success.then(|| json!({ "ok": true })),
+            error: None,
+            cost_cents,
+        });
+    }
+
+    // A result for a job that does not exist fails the whole batch
+    let mut failing = completions.clone();
+    failing.push(HeldCompletion { job_id: Uuid::new_v4(), ..completions[0].clone() });
+    assert!(repo.complete_held_many(failing).await.is_err());
+    assert!(matches!(repo.find_by_id(completions[0].job_id).await.unwrap().status, JobStatus::Running));
+
+    let stored = repo.complete_held_many(completions.clone()).await.unwrap();
+    assert_eq!(stored.iter().map(|(_, job)| job.id).collect::<Vec<_>>(), completions.iter().map(|c| c.job_id).collect::<Vec<_>>());
+    assert!(stored.iter().all(|(resolution, _)| *resolution == CompletionResolution::Held));
+    assert!(matches!(stored[0].1.status, JobStatus::Succeeded));
+    assert_eq!(stored[0].1.cost_cents, 10);
+    assert!(matches!(stored[1].1.status, JobStatus::Failed));
+}
+
+#[tokio::test]
+#[ignore = "requires Docker or TEST_DATABASE_URL"]
+async fn cancelled_running_jobs_end_up_cancelled() {
+    let env = environment().await;
+    let repo = DieselJobRepository::new(env.pool.clone());
+    let attempts = DieselJobAttemptRepository::new(env.pool.clone());
+    let runners = DieselRunnerRepository::new(env.pool.clone());
+    let (customer_id, job_type_id) = customer_and_job_type(&env).await;
+    let runner = runners.register(NewRunner {
+        id: Uuid::new_v4(),
+        name: "cancelling-runner".to_string(),
+        description: None,
+        status: RunnerStatus::Active.as_str().to_string(),
+        compatible_job_types: Vec::new(),
+    }).await.unwrap();
+
+    // Only running jobs are cancelled by their runner
+    let job = JobFactory::new(customer_id, job_type_id).create(&repo).await.unwrap();
+    assert!(matches!(repo.request_cancellation(job.id).await, Err(Error::InvalidInput(_))));
+    assert!(matches!(repo.request_cancellation(Uuid::new_v4()).await, Err(Error::NotFound(_))));
+
+    repo.assign_runner(job.id, runner.id).await.unwrap();
+    repo.set_started(job.id).await.unwrap();
+    let attempt = attempts.start(job.id, Some(runner.id)).await.unwrap();
+    assert!(!repo.cancellation_requested(job.id).await.unwrap());
+    let requested = repo.request_cancellation(job.id).await.unwrap();
+    assert!(matches!(requested.status, JobStatus::Running));
+    let requested_at = requested.cancel_requested_at.unwrap();
+    assert_eq!(repo.request_cancellation(job.id).await.unwrap().cancel_requested_at, Some(requested_at));
+    assert!(repo.cancellation_requested(job.id).await.unwrap());
+
+    // The failed result of the stopped job is stored as cancelled, with the failure fee
+    let holder = JobHolder { runner_id: Some(runner.id), attempt_id: Some(attempt.id) };
+    let error = JobError::input(codes::JOB_CANCELLED, "The job was cancelled while it was running");
+    let (resolution, cancelled) = repo.complete_held(job.id, holder, false, None, Some(error), 25).await.unwrap();
+    assert_eq!(resolution, CompletionResolution::Held);
+    assert!(matches!(cancelled.status, JobStatus::Cancelled));
+    assert_eq!(cancelled.cost_cents, 25);
+    assert!(matches!(repo.request_cancellation(job.id).await, Err(Error::InvalidInput(_))));
+
+    // A job whose runner was lost after the request is cancelled rather than handed back
+    let orphaned = JobFactory::new(customer_id, job_type_id).create(&repo).await.unwrap();
+    repo.assign_runner(orphaned.id, runner.id).await.unwrap();
+    repo.set_started(orphaned.id).await.unwrap();
+    attempts.start(orphaned.id, Some(runner.id)).await.unwrap();
+    repo.request_cancellation(orphaned.id).await.unwrap();
+    let handed_back = repo.hand_back(orphaned.id, runner.id).await.unwrap().unwrap();
+    assert!(matches!(handed_back.status, JobStatus::Cancelled));
+    assert!(repo.find_running_by_runner(runner.id).await.unwrap().is_empty());
+}
+
+#[tokio::test]
+#[ignore = "requires Docker or TEST_DATABASE_URL"]
+async fn records_when_jobs_are_queued_claimed_and_started() {
+    let env = environment().await;
+    let repo = DieselJobRepository::new(env.pool.clone());
+    let (customer_id, job_type_id) = customer_and_job_type(&env).await;
+    let job = JobFactory::new(customer_id, job_type_id).create(&repo).await.unwrap();
+    assert!(job.queued_at.is_some());
+    assert!(job.started_at.is_none());
+
+    // A scheduled job enters the queue when it is released
+    let release = Utc::now() + Duration::hours(1);
+    let scheduled = repo.schedule(job.id, release).await.unwrap();
+    assert_eq!(scheduled.queued_at.map(|queued_at| queued_at.timestamp()), Some(release.timestamp()));
+    repo.update_status(job.id, JobStatus::Pending).await.unwrap();
+    diesel::sql_query("UPDATE jobs SET queued_at = NOW() - INTERVAL '5 seconds' WHERE id = $1")
+        .bind::<diesel::sql_types::Uuid, _>(job.id)
+        .execute(&mut env.pool.get().unwrap())
+        .unwrap();
+
+    repo.record_claim(job.id, PriorityLevel::High).await.unwrap();
+    let started = repo.set_started(job.id).await.unwrap();
+    assert!(started.started_at.is_some());
+    assert!(started.queue_wait_ms().unwrap() >= 5_000);
+
+    let completed = repo.set_completed(job.id, true, None, None, 10).await.unwrap();
+    assert!(completed.processing_ms().is_some());
+    assert_eq!(completed.started_at, started.started_at);
+}
+
+#[tokio::test]
+#[ignore = "requires Docker or TEST_DATABASE_URL"]
+async fn keeps_the_input_output_and_error_of_jobs() {
+    let env = environment().await;
+    let repo = DieselJobRepository::new(env.pool.clone());
+    let (customer_id, job_type_id) = customer_and_job_type(&env).await;
+    let input = json!({ "text": "summarize me", "options": { "length": 3 } });
+    let job = JobFactory::new(customer_id, job_type_id).input_data(input.clone()).create(&repo).await.unwrap();
+    let failing = JobFactory::new(customer_id, job_type_id).create(&repo).await.unwrap();
+
+    // The runner starts the job with the input the API accepted
+    assert_eq!(repo.set_started(job.id).await.unwrap().input_data, input);
+    let output = json!({ "summary": "short" });
+    repo.set_completed(job.id, true, Some(output.clone()), None, 10).await.unwrap();
+    let found = repo.find_by_id(job.id).await.unwrap();
+    assert_eq!(found.input_data, input);
+    assert_eq!(found.output_data, Some(output));
+    assert!(found.error.is_none());
+
+    let error = JobError::provider(codes::PROVIDER_TIMEOUT, "Provider timed out");
+    repo.set_started(failing.id).await.unwrap();
+    repo.set_completed(failing.id, false, None, Some(error.clone()), 0).await.unwrap();
+    let found = repo.find_by_id(failing.id).await.unwrap();
+    assert!(found.output_data.is_none());
+    assert_eq!(found.error, Some(error));
+}
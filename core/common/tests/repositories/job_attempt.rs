@@ -0,0 +1,119 @@
+use chrono::Utc;
+use innosystem_common::models::job_attempt::{AttemptOutcome, AttemptStatus, CrashReport};
+use innosystem_common::models::job_error::{codes, JobError};
+use innosystem_common::repositories::{
+    DieselCustomerRepository, DieselJobAttemptRepository, DieselJobRepository, DieselJobTypeRepository,
+    JobAttemptRepository,
+};
+use innosystem_common::testing::factories::{CustomerFactory, JobFactory, JobTypeFactory};
+use innosystem_common::testing::TestEnvironment;
+use uuid::Uuid;
+
+use crate::environment;
+
+/// Insert a job to record attempts for
+async fn job(env: &TestEnvironment) -> Uuid {
+    let customer = CustomerFactory::new()
+        .create(&DieselCustomerRepository::new(env.pool.clone()))
+        .await
+        .unwrap();
+    let job_type = JobTypeFactory::new()
+        .create(&DieselJobTypeRepository::new(env.pool.clone()))
+        .await
+        .unwrap();
+    JobFactory::new(customer.id, job_type.id)
+        .create(&DieselJobRepository::new(env.pool.clone()))
+        .await
+        .unwrap()
+        .id
+}
+
+fn outcome(success: bool, cost_cents: i32) -> AttemptOutcome {
+    AttemptOutcome {
+        success,
+        error: (!success).then(|| JobError::provider(codes::PROVIDER_TIMEOUT, "Provider timed out")),
+        cost_cents,
+        finished_at: Utc::now(),
+    }
+}
+
+#[tokio::test]
+#[ignore = "requires Docker or TEST_DATABASE_URL"]
+async fn attempts_are_numbered_and_keep_their_own_outcome() {
+    let env = environment().await;
+    let repo = DieselJobAttemptRepository::new(env.pool.clone());
+    let job_id = job(&env).await;
+    let (first_runner, second_runner) = (Uuid::new_v4(), Uuid::new_v4());
+
+    let first = repo.start(job_id, Some(first_runner)).await.unwrap();
+    assert_eq!(first.attempt_number, 1);
+    assert_eq!(first.attempt_status(), Some(AttemptStatus::Running));
+    repo.finish(first.id, outcome(false, 0)).await.unwrap();
+
+    let second = repo.start(job_id, Some(second_runner)).await.unwrap();
+    assert_eq!(second.attempt_number, 2);
+    let second = repo.finish(second.id, outcome(true, 250)).await.unwrap();
+    assert_eq!(second.attempt_status(), Some(AttemptStatus::Succeeded));
+    assert!(second.duration_ms().is_some());
+
+    // A finished attempt cannot be finished again
+    let err = repo.finish(second.id, outcome(false, 0)).await.unwrap_err();
+    assert!(err.to_string().contains("not found"));
+
+    let attempts = repo.find_by_job_id(job_id).await.unwrap();
+    assert_eq!(attempts.iter().map(|a| a.attempt_number).collect::<Vec<_>>(), vec![1, 2]);
+    assert_eq!(attempts[0].status, "failed");
+    assert_eq!(attempts[0].runner_id, Some(first_runner));
+    assert_eq!(attempts[0].job_error().unwrap().code, codes::PROVIDER_TIMEOUT);
+    assert!(attempts[1].job_error().is_none());
+    assert_eq!(attempts[1].cost_cents, 250);
+}
+
+#[tokio::test]
+#[ignore = "requires Docker or TEST_DATABASE_URL"]
+async fn runner_stats_count_every_finished_attempt() {
+    let env = environment().await;
+    let repo = DieselJobAttemptRepository::new(env.pool.clone());
+    let runner_id = Uuid::new_v4();
+
+    for success in [true, false] {
+        let attempt = repo.start(job(&env).await, Some(runner_id)).await.unwrap();
+        repo.finish(attempt.id, outcome(success, 0)).await.unwrap();
+    }
+    // An interrupted attempt counts as a failure once abandoned
+    let interrupted = job(&env).await;
+    repo.start(interrupted, Some(runner_id)).await.unwrap();
+    assert_eq!(repo.get_runner_stats(runner_id, 60).await.unwrap().completed(), 2);
+    assert_eq!(repo.abandon_running(interrupted).await.unwrap(), 1);
+    assert_eq!(repo.abandon_running(interrupted).await.unwrap(), 0);
+
+    let stats = repo.get_runner_stats(runner_id, 60).await.unwrap();
+    assert_eq!((stats.succeeded, stats.failed), (1, 2));
+    assert!(stats.avg_processing_seconds.is_some());
+    assert_eq!(repo.get_runner_stats(Uuid::new_v4(), 60).await.unwrap().completed(), 0);
+}
+
+#[tokio::test]
+#[ignore = "requires Docker or TEST_DATABASE_URL"]
+async fn crashes_are_attached_to_attempts_without_changing_their_status() {
+    let env = environment().await;
+    let repo = DieselJobAttemptRepository::new(env.pool.clone());
+    let job_id = job(&env).await;
+    let runner_id = Uuid::new_v4();
+
+    let attempt = repo.start(job_id, Some(runner_id)).await.unwrap();
+    assert!(attempt.crash_report().is_none());
+
+    let report = CrashReport {
+        runner_id: Some(runner_id),
+        started_at: attempt.started_at,
+        panic_message: Some("index out of bounds at src/processor.rs:42".to_string()),
+        reported_at: Utc::now(),
+    };
+    let crashed = repo.record_crash(attempt.id, report.clone()).await.unwrap();
+    assert_eq!(crashed.attempt_status(), Some(AttemptStatus::Running));
+    assert_eq!(crashed.crash_report(), Some(report));
+
+    let err = repo.record_crash(Uuid::new_v4(), crashed.crash_report().unwrap()).await.unwrap_err();
+    assert!(err.to_string().contains("not found"));
+}
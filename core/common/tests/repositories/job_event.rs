@@ -0,0 +1,79 @@
+use chrono::{Duration, Utc};
+use innosystem_common::models::job_error::{codes, JobError};
+use innosystem_common::models::job_event::JobLifecycleEvent;
+use innosystem_common::models::webhook::WebhookEventType;
+use innosystem_common::repositories::{
+    DieselCustomerRepository, DieselJobEventRepository, DieselJobRepository, DieselJobTypeRepository, JobEventRepository,
+    JobRepository,
+};
+use innosystem_common::testing::factories::{CustomerFactory, JobFactory, JobTypeFactory};
+use uuid::Uuid;
+
+use crate::environment;
+
+#[tokio::test]
+#[ignore = "requires Docker or TEST_DATABASE_URL"]
+async fn captures_each_transition_of_a_job_once() {
+    let env = environment().await;
+    let repo = DieselJobEventRepository::new(env.pool.clone());
+    let job_repo = DieselJobRepository::new(env.pool.clone());
+    let customer = CustomerFactory::new()
+        .create(&DieselCustomerRepository::new(env.pool.clone()))
+        .await
+        .unwrap();
+    let job_type = JobTypeFactory::new()
+        .create(&DieselJobTypeRepository::new(env.pool.clone()))
+        .await
+        .unwrap();
+    let since = Utc::now() - Duration::hours(1);
+
+    let succeeded = JobFactory::new(customer.id, job_type.id).create(&job_repo).await.unwrap();
+    job_repo.set_started(succeeded.id).await.unwrap();
+    job_repo.set_completed(succeeded.id, true, None, None, 120).await.unwrap();
+    let failed = JobFactory::new(customer.id, job_type.id).create(&job_repo).await.unwrap();
+    job_repo.set_started(failed.id).await.unwrap();
+    let error = JobError::input(codes::INVALID_INPUT, "Missing text");
+    job_repo.set_completed(failed.id, false, None, Some(error), 0).await.unwrap();
+    let running = JobFactory::new(customer.id, job_type.id).create(&job_repo).await.unwrap();
+    job_repo.set_started(running.id).await.unwrap();
+    // Pending jobs and sub-tasks of batch jobs get no events
+    let pending = JobFactory::new(customer.id, job_type.id).create(&job_repo).await.unwrap();
+    let mut sub_task = JobFactory::new(customer.id, job_type.id).build();
+    sub_task.parent_id = Some(succeeded.id);
+    let sub_task = job_repo.create(sub_task).await.unwrap();
+    job_repo.set_started(sub_task.id).await.unwrap();
+
+    assert!(repo.capture(since, 1000).await.unwrap() >= 5);
+    repo.capture(since, 1000).await.unwrap();
+    let types = |events: Vec<JobLifecycleEvent>| events.into_iter()
+        .map(|event| event.webhook_event_type().unwrap())
+        .collect::<Vec<_>>();
+    assert_eq!(types(repo.find_by_job(succeeded.id).await.unwrap()), vec![WebhookEventType::JobStarted, WebhookEventType::JobSucceeded]);
+    assert_eq!(types(repo.find_by_job(failed.id).await.unwrap()), vec![WebhookEventType::JobStarted, WebhookEventType::JobFailed]);
+    assert_eq!(types(repo.find_by_job(running.id).await.unwrap()), vec![WebhookEventType::JobStarted]);
+    assert!(repo.find_by_job(pending.id).await.unwrap().is_empty());
+    assert!(repo.find_by_job(sub_task.id).await.unwrap().is_empty());
+
+    // Events carry what the customer needs without fetching the job
+    let events = repo.find_by_job(succeeded.id).await.unwrap();
+    assert_eq!(events[0].data["status"], "running");
+    assert_eq!(events[1].data["status"], "succeeded");
+    assert_eq!(events[1].data["cost_cents"], 120);
+    assert_eq!(events[1].data["public_id"], succeeded.public_id);
+    let failure = repo.find_by_job(failed.id).await.unwrap().pop().unwrap();
+    assert_eq!(failure.data["error"]["code"], codes::INVALID_INPUT);
+    assert_eq!(failure.customer_id, customer.id);
+
+    // Notified events are no longer pending
+    let ours = |job_id: Uuid| [succeeded.id, failed.id, running.id].contains(&job_id);
+    let pending_events: Vec<_> = repo.find_pending(1000).await.unwrap()
+        .into_iter()
+        .filter(|event| ours(event.job_id))
+        .collect();
+    assert_eq!(pending_events.len(), 5);
+    let notified = repo.mark_notified(pending_events[0].id, Utc::now()).await.unwrap();
+    assert!(notified.notified_at.is_some());
+    let still_pending = repo.find_pending(1000).await.unwrap();
+    assert_eq!(still_pending.iter().filter(|event| ours(event.job_id)).count(), 4);
+    assert!(repo.mark_notified(Uuid::new_v4(), Utc::now()).await.is_err());
+}
@@ -0,0 +1,77 @@
+use innosystem_common::models::job_import::{import_report_csv, ImportStatus, NewJobImport};
+use innosystem_common::repositories::{
+    DieselCustomerRepository, DieselJobImportRepository, DieselJobRepository, DieselJobTypeRepository, JobImportRepository,
+};
+use innosystem_common::testing::factories::{CustomerFactory, JobFactory, JobTypeFactory};
+use serde_json::json;
+use uuid::Uuid;
+
+use crate::environment;
+
+#[tokio::test]
+#[ignore = "requires Docker or TEST_DATABASE_URL"]
+async fn records_row_outcomes_in_file_order() {
+    let env = environment().await;
+    let repo = DieselJobImportRepository::new(env.pool.clone());
+    let job_repo = DieselJobRepository::new(env.pool.clone());
+    let customer = CustomerFactory::new()
+        .create(&DieselCustomerRepository::new(env.pool.clone()))
+        .await
+        .unwrap();
+    let job_type = JobTypeFactory::new()
+        .create(&DieselJobTypeRepository::new(env.pool.clone()))
+        .await
+        .unwrap();
+
+    let import = repo.create(NewJobImport {
+        id: Uuid::new_v4(),
+        customer_id: customer.id,
+        job_type_id: job_type.id,
+        format: "csv".to_string(),
+        mapping: None,
+        content: "name\nAda\nGrace\n".to_string(),
+        priority: 1,
+        test_mode: false,
+        total_rows: 2,
+    }).await.unwrap();
+    assert_eq!(import.status(), Some(ImportStatus::Pending));
+    assert_eq!(import.next_row, 1);
+    assert!(repo.find_unfinished().await.unwrap().iter().any(|unfinished| unfinished.id == import.id));
+    assert_eq!(repo.find_by_customer_id(customer.id).await.unwrap().len(), 1);
+    assert!(repo.find_by_id(Uuid::new_v4()).await.is_err());
+
+    // Rows are claimed once and in order
+    let job = JobFactory::new(customer.id, job_type.id)
+        .input_data(json!({ "name": "Ada" }))
+        .create(&job_repo)
+        .await
+        .unwrap();
+    assert!(!repo.claim_row(import.id, 2, None, Some("out of order".to_string())).await.unwrap());
+    assert!(repo.claim_row(import.id, 1, Some(job.id), None).await.unwrap());
+    assert!(!repo.claim_row(import.id, 1, Some(Uuid::new_v4()), None).await.unwrap());
+
+    let second_job = Uuid::new_v4();
+    assert!(repo.claim_row(import.id, 2, Some(second_job), None).await.unwrap());
+    let failed = repo.fail_row(import.id, 2, "queue saturated".to_string()).await.unwrap();
+    assert_eq!((failed.job_id, failed.error.as_deref()), (None, Some("queue saturated")));
+    assert!(repo.fail_row(import.id, 2, "again".to_string()).await.is_err());
+
+    let processed = repo.find_by_id(import.id).await.unwrap();
+    assert_eq!(processed.status(), Some(ImportStatus::Processing));
+    assert_eq!((processed.next_row, processed.submitted_rows, processed.failed_rows), (3, 1, 1));
+
+    let rows = repo.find_rows(import.id).await.unwrap();
+    assert_eq!(rows.len(), 2);
+    assert_eq!(rows[0].0.job_id, Some(job.id));
+    assert_eq!(rows[0].1.as_deref(), Some("pending"));
+    assert_eq!(rows[1].1, None);
+    assert_eq!(
+        import_report_csv(&rows),
+        format!("row,job_id,job_status,error\n1,{},pending,\n2,,,queue saturated\n", job.id),
+    );
+
+    let finished = repo.finish(import.id, ImportStatus::Completed).await.unwrap();
+    assert!(finished.is_finished());
+    assert!(finished.completed_at.is_some());
+    assert!(!repo.find_unfinished().await.unwrap().iter().any(|unfinished| unfinished.id == import.id));
+}
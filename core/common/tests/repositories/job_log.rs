@@ -0,0 +1,50 @@
+use innosystem_common::models::job_log::{LogLevel, NewJobLog};
+use innosystem_common::repositories::{
+    DieselCustomerRepository, DieselJobLogRepository, DieselJobRepository, DieselJobTypeRepository,
+    JobLogRepository,
+};
+use innosystem_common::testing::factories::{CustomerFactory, JobFactory, JobTypeFactory};
+use serde_json::json;
+use uuid::Uuid;
+
+use crate::environment;
+
+#[tokio::test]
+#[ignore = "requires Docker or TEST_DATABASE_URL"]
+async fn appends_and_pages_job_logs() {
+    let env = environment().await;
+    let repo = DieselJobLogRepository::new(env.pool.clone());
+    let customer = CustomerFactory::new()
+        .create(&DieselCustomerRepository::new(env.pool.clone()))
+        .await
+        .unwrap();
+    let job_type = JobTypeFactory::new()
+        .create(&DieselJobTypeRepository::new(env.pool.clone()))
+        .await
+        .unwrap();
+    let job = JobFactory::new(customer.id, job_type.id)
+        .create(&DieselJobRepository::new(env.pool.clone()))
+        .await
+        .unwrap();
+
+    let lines: Vec<NewJobLog> = (1..=5)
+        .map(|line_number| NewJobLog {
+            id: Uuid::new_v4(),
+            job_id: job.id,
+            line_number,
+            level: LogLevel::Info.as_str().to_string(),
+            message: format!("line {}", line_number),
+            fields: Some(json!({ "line": line_number })),
+            created_at: None,
+        })
+        .collect();
+
+    assert_eq!(repo.append(Vec::new()).await.unwrap(), 0);
+    assert_eq!(repo.append(lines).await.unwrap(), 5);
+    assert_eq!(repo.count_by_job_id(job.id).await.unwrap(), 5);
+    assert_eq!(repo.count_by_job_id(Uuid::new_v4()).await.unwrap(), 0);
+
+    let page = repo.find_by_job_id(job.id, 2, 1).await.unwrap();
+    let numbers: Vec<i32> = page.iter().map(|l| l.line_number).collect();
+    assert_eq!(numbers, vec![2, 3]);
+}
@@ -0,0 +1,64 @@
+use innosystem_common::models::job_template::{JobTemplate, NewJobTemplate, TemplateError};
+use innosystem_common::repositories::{DieselCustomerRepository, DieselJobTemplateRepository, DieselJobTypeRepository, JobTemplateRepository};
+use innosystem_common::testing::factories::{CustomerFactory, JobTypeFactory};
+use serde_json::{json, Value};
+use uuid::Uuid;
+
+use crate::environment;
+
+#[tokio::test]
+#[ignore = "requires Docker or TEST_DATABASE_URL"]
+async fn stores_and_renders_job_templates() {
+    let env = environment().await;
+    let repo = DieselJobTemplateRepository::new(env.pool.clone());
+    let customer = CustomerFactory::new()
+        .create(&DieselCustomerRepository::new(env.pool.clone()))
+        .await
+        .unwrap();
+    let job_type = JobTypeFactory::new()
+        .create(&DieselJobTypeRepository::new(env.pool.clone()))
+        .await
+        .unwrap();
+
+    let input_template = json!({ "url": "https://{{host}}/reports", "pages": "{{pages}}", "format": "{{format}}" });
+    let variables = json!({
+        "host": { "type": "string" },
+        "pages": { "type": "integer" },
+        "format": { "type": "string", "default": "pdf" }
+    });
+    JobTemplate::validate(&input_template, &variables).unwrap();
+
+    let template = repo.create(NewJobTemplate {
+        id: Uuid::new_v4(),
+        customer_id: customer.id,
+        job_type_id: job_type.id,
+        name: "report".to_string(),
+        description: None,
+        input_template,
+        variables,
+    }).await.unwrap();
+
+    let found = repo.find_by_id(template.id).await.unwrap();
+    assert_eq!(found.input_template, template.input_template);
+    assert!(repo.find_by_id(Uuid::new_v4()).await.is_err());
+    assert_eq!(repo.find_by_customer_id(customer.id).await.unwrap().len(), 1);
+
+    let values = json!({ "host": "example.test", "pages": 3 });
+    let rendered = found.render(values.as_object().unwrap()).unwrap();
+    assert_eq!(rendered, json!({ "url": "https://example.test/reports", "pages": 3, "format": "pdf" }));
+
+    let wrong_type = json!({ "host": "example.test", "pages": "three" });
+    assert!(matches!(found.render(wrong_type.as_object().unwrap()), Err(TemplateError::TypeMismatch { .. })));
+    let missing = json!({ "pages": 3 });
+    assert_eq!(found.render(missing.as_object().unwrap()), Err(TemplateError::MissingVariable("host".to_string())));
+    let unknown = json!({ "host": "example.test", "pages": 3, "colour": "red" });
+    assert_eq!(found.render(unknown.as_object().unwrap()), Err(TemplateError::UnknownVariable("colour".to_string())));
+
+    // Placeholders must be declared
+    let undeclared = JobTemplate::validate(&json!({ "to": "{{recipient}}" }), &Value::Object(Default::default()));
+    assert_eq!(undeclared.unwrap_err(), TemplateError::UndeclaredVariable("recipient".to_string()));
+
+    repo.delete(template.id).await.unwrap();
+    assert!(repo.find_by_customer_id(customer.id).await.unwrap().is_empty());
+    assert!(repo.delete(template.id).await.is_err());
+}
@@ -0,0 +1,99 @@
+use innosystem_common::models::job_type::{CatalogVisibility, ProcessorType};
+use innosystem_common::repositories::job_type::CatalogFilter;
+use innosystem_common::repositories::{DieselJobTypeRepository, JobTypeRepository};
+use innosystem_common::testing::factories::JobTypeFactory;
+
+use crate::environment;
+
+#[tokio::test]
+#[ignore = "requires Docker or TEST_DATABASE_URL"]
+async fn creates_updates_and_lists_job_types() {
+    let env = environment().await;
+    let repo = DieselJobTypeRepository::new(env.pool.clone());
+
+    let enabled = JobTypeFactory::new()
+        .processor_type(ProcessorType::Webhook)
+        .standard_cost_cents(250)
+        .create(&repo)
+        .await
+        .unwrap();
+    let disabled = JobTypeFactory::new().disabled().create(&repo).await.unwrap();
+
+    let found = repo.find_by_id(enabled.id).await.unwrap();
+    assert!(matches!(found.processor_type, ProcessorType::Webhook));
+    assert_eq!(found.standard_cost_cents, 250);
+    assert!(repo.find_by_id(uuid::Uuid::new_v4()).await.is_err());
+
+    let all = repo.list_all().await.unwrap();
+    assert!(all.iter().any(|t| t.id == enabled.id));
+    assert!(all.iter().any(|t| t.id == disabled.id));
+
+    let listed = repo.list_enabled().await.unwrap();
+    assert!(listed.iter().any(|t| t.id == enabled.id));
+    assert!(!listed.iter().any(|t| t.id == disabled.id));
+
+    let mut changed = found.clone();
+    changed.enabled = false;
+    changed.standard_cost_cents = 300;
+    let updated = repo.update(changed).await.unwrap();
+    assert!(!updated.enabled);
+    assert_eq!(updated.standard_cost_cents, 300);
+    assert!(!repo.list_enabled().await.unwrap().iter().any(|t| t.id == enabled.id));
+}
+
+#[tokio::test]
+#[ignore = "requires Docker or TEST_DATABASE_URL"]
+async fn browses_the_catalog_by_visibility_category_and_text() {
+    let env = environment().await;
+    let repo = DieselJobTypeRepository::new(env.pool.clone());
+    let category = format!("catalog-{}", uuid::Uuid::new_v4());
+
+    let public = JobTypeFactory::new().name(format!("Invoice OCR {}", category)).category(&category).create(&repo).await.unwrap();
+    let reseller = JobTypeFactory::new().category(&category).visibility(CatalogVisibility::Reseller).create(&repo).await.unwrap();
+    let internal = JobTypeFactory::new().category(&category).visibility(CatalogVisibility::Internal).create(&repo).await.unwrap();
+    let disabled = JobTypeFactory::new().category(&category).disabled().create(&repo).await.unwrap();
+
+    let ids = |job_types: Vec<innosystem_common::models::job_type::JobType>| {
+        job_types.into_iter().map(|t| t.id).collect::<Vec<_>>()
+    };
+
+    let customer_view = repo.search_catalog(CatalogFilter {
+        visibilities: vec![CatalogVisibility::Public],
+        category: Some(category.to_uppercase()),
+        search: None,
+    }).await.unwrap();
+    assert_eq!(ids(customer_view), vec![public.id]);
+
+    let reseller_view = ids(repo.search_catalog(CatalogFilter {
+        visibilities: vec![CatalogVisibility::Public, CatalogVisibility::Reseller],
+        category: Some(category.clone()),
+        search: None,
+    }).await.unwrap());
+    assert!(reseller_view.contains(&public.id) && reseller_view.contains(&reseller.id));
+    assert!(!reseller_view.contains(&internal.id) && !reseller_view.contains(&disabled.id));
+
+    let searched = repo.search_catalog(CatalogFilter {
+        visibilities: vec![CatalogVisibility::Public],
+        category: None,
+        search: Some(format!("invoice ocr {}", category)),
+    }).await.unwrap();
+    assert_eq!(ids(searched), vec![public.id]);
+
+    // LIKE wildcards in the search text match literally
+    let wildcard = repo.search_catalog(CatalogFilter {
+        visibilities: vec![CatalogVisibility::Public],
+        category: Some(category.replace('-', "_")),
+        search: None,
+    }).await.unwrap();
+    assert!(wildcard.is_empty());
+
+    let categories = repo.list_catalog_categories(vec![CatalogVisibility::Public, CatalogVisibility::Reseller]).await.unwrap();
+    assert!(categories.contains(&(category.clone(), 2)));
+
+    let mut listing = repo.find_by_id(internal.id).await.unwrap();
+    listing.visibility = CatalogVisibility::Public;
+    listing.sample_input = Some(serde_json::json!({ "file": "invoice.pdf" }));
+    let updated = repo.update(listing).await.unwrap();
+    assert_eq!(updated.visibility, CatalogVisibility::Public);
+    assert_eq!(updated.sample_input.unwrap()["file"], "invoice.pdf");
+}
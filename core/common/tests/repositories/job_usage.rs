@@ -0,0 +1,89 @@
+use chrono::{Duration, Utc};
+use innosystem_common::models::job::Job;
+use innosystem_common::models::job_usage::{NewJobUsage, ResourceUsage};
+use innosystem_common::repositories::{
+    DieselCustomerRepository, DieselJobRepository, DieselJobTypeRepository, DieselJobUsageRepository,
+    JobUsageRepository,
+};
+use innosystem_common::testing::factories::{CustomerFactory, JobFactory, JobTypeFactory};
+use innosystem_common::testing::TestEnvironment;
+
+use crate::environment;
+
+/// Insert jobs of one customer and job type to record usage for
+async fn jobs(env: &TestEnvironment, count: usize) -> Vec<Job> {
+    let customer = CustomerFactory::new()
+        .create(&DieselCustomerRepository::new(env.pool.clone()))
+        .await
+        .unwrap();
+    let job_type = JobTypeFactory::new()
+        .create(&DieselJobTypeRepository::new(env.pool.clone()))
+        .await
+        .unwrap();
+    let mut jobs = Vec::new();
+    for _ in 0..count {
+        jobs.push(
+            JobFactory::new(customer.id, job_type.id)
+                .create(&DieselJobRepository::new(env.pool.clone()))
+                .await
+                .unwrap(),
+        );
+    }
+    jobs
+}
+
+fn usage(wall_time_ms: i64, peak_memory_bytes: Option<i64>) -> ResourceUsage {
+    ResourceUsage {
+        wall_time_ms,
+        cpu_time_ms: Some(wall_time_ms / 2),
+        peak_memory_bytes,
+        bytes_sent: 100,
+        bytes_received: 1_000,
+    }
+}
+
+#[tokio::test]
+#[ignore = "requires Docker or TEST_DATABASE_URL"]
+async fn usage_reported_again_replaces_the_earlier_report() {
+    let env = environment().await;
+    let repo = DieselJobUsageRepository::new(env.pool.clone());
+    let job = jobs(&env, 1).await.remove(0);
+
+    assert!(repo.find_by_job(job.id).await.unwrap().is_none());
+    repo.record(NewJobUsage::new(&job, None, None, usage(500, Some(1_024)))).await.unwrap();
+    let recorded = repo.record(NewJobUsage::new(&job, None, None, usage(800, None))).await.unwrap();
+    assert_eq!(recorded.usage(), usage(800, None));
+
+    let found = repo.find_by_job(job.id).await.unwrap().unwrap();
+    assert_eq!(found.usage(), usage(800, None));
+    assert_eq!(found.customer_id, job.customer_id);
+    assert_eq!(found.job_type_id, job.job_type_id);
+}
+
+#[tokio::test]
+#[ignore = "requires Docker or TEST_DATABASE_URL"]
+async fn usage_is_summarized_per_customer_and_job_type() {
+    let env = environment().await;
+    let repo = DieselJobUsageRepository::new(env.pool.clone());
+    let jobs = jobs(&env, 3).await;
+    let customer_id = jobs[0].customer_id;
+
+    repo.record(NewJobUsage::new(&jobs[0], None, None, usage(100, Some(2_048)))).await.unwrap();
+    repo.record(NewJobUsage::new(&jobs[1], None, None, usage(300, None))).await.unwrap();
+    repo.record(NewJobUsage::new(&jobs[2], None, None, usage(600, Some(4_096)))).await.unwrap();
+
+    let now = Utc::now();
+    let summaries = repo.summarize(now - Duration::hours(1), now + Duration::hours(1), Some(customer_id)).await.unwrap();
+    assert_eq!(summaries.len(), 1);
+    let summary = &summaries[0];
+    assert_eq!((summary.customer_id, summary.job_type_id), (customer_id, jobs[0].job_type_id));
+    assert_eq!(summary.jobs, 3);
+    assert_eq!(summary.wall_time_ms, 1_000);
+    assert_eq!(summary.cpu_time_ms, 500);
+    assert_eq!(summary.peak_memory_bytes, Some(4_096));
+    assert_eq!((summary.bytes_sent, summary.bytes_received), (300, 3_000));
+
+    // Usage recorded before the period is left out
+    let later = repo.summarize(now + Duration::hours(1), now + Duration::hours(2), Some(customer_id)).await.unwrap();
+    assert!(later.is_empty());
+}
@@ -0,0 +1,71 @@
+use chrono::{Duration, Utc};
+use innosystem_common::models::load_window::{LoadWindowStatus, NewLoadWindow};
+use innosystem_common::repositories::{DieselLoadWindowRepository, LoadWindowRepository};
+use uuid::Uuid;
+
+use crate::environment;
+
+fn new_window(target_runners: i32) -> NewLoadWindow {
+    let starts_at = Utc::now() + Duration::hours(1);
+    NewLoadWindow {
+        id: Uuid::new_v4(),
+        name: format!("batch-{}", Uuid::new_v4().simple()),
+        pool: Some("batch".to_string()),
+        job_type_ids: vec![Uuid::new_v4()],
+        target_runners,
+        starts_at,
+        ends_at: starts_at + Duration::hours(2),
+        warm_up_minutes: 15,
+        max_hold_minutes: 30,
+        created_by: "admin".to_string(),
+    }
+}
+
+#[tokio::test]
+#[ignore = "requires Docker or TEST_DATABASE_URL"]
+async fn checks_record_the_warm_up_of_a_window_until_its_release() {
+    let env = environment().await;
+    let repo = DieselLoadWindowRepository::new(env.pool.clone());
+
+    let window = repo.create(new_window(3)).await.unwrap();
+    assert_eq!(window.window_status(), LoadWindowStatus::Planned);
+    assert_eq!(window.job_type_ids.len(), 1);
+    assert!(repo.list_open().await.unwrap().iter().any(|open| open.id == window.id));
+
+    let warming = repo.record_check(window.id, LoadWindowStatus::Warming, 1, true).await.unwrap();
+    assert_eq!(warming.window_status(), LoadWindowStatus::Warming);
+    assert_eq!(warming.active_runners, Some(1));
+    let notified_at = warming.notified_at.unwrap();
+    assert!(warming.ready_at.is_none());
+
+    let ready = repo.record_check(window.id, LoadWindowStatus::Ready, 3, false).await.unwrap();
+    assert_eq!(ready.notified_at, Some(notified_at));
+    assert!(ready.ready_at.is_some());
+
+    let released = repo.record_check(window.id, LoadWindowStatus::Released, 3, false).await.unwrap();
+    assert_eq!(released.ready_at, ready.ready_at);
+    assert!(released.released_at.is_some());
+    assert!(!repo.list_open().await.unwrap().iter().any(|open| open.id == window.id));
+
+    // Released windows are no longer checked or cancelled
+    assert!(repo.record_check(window.id, LoadWindowStatus::Warming, 0, false).await.is_err());
+    assert!(repo.cancel(window.id).await.is_err());
+}
+
+#[tokio::test]
+#[ignore = "requires Docker or TEST_DATABASE_URL"]
+async fn windows_released_short_of_their_target_were_never_ready() {
+    let env = environment().await;
+    let repo = DieselLoadWindowRepository::new(env.pool.clone());
+
+    let window = repo.create(new_window(5)).await.unwrap();
+    let released = repo.record_check(window.id, LoadWindowStatus::Released, 2, true).await.unwrap();
+    assert!(released.ready_at.is_none());
+    assert!(released.released_at.is_some());
+
+    let cancelled = repo.create(new_window(5)).await.unwrap();
+    let cancelled = repo.cancel(cancelled.id).await.unwrap();
+    assert_eq!(cancelled.window_status(), LoadWindowStatus::Cancelled);
+    assert_eq!(repo.find_by_id(cancelled.id).await.unwrap().status, "cancelled");
+    assert!(repo.list_all().await.unwrap().iter().any(|listed| listed.id == cancelled.id));
+}
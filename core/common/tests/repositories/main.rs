@@ -0,0 +1,52 @@
+//! Integration tests for the Diesel repositories
+//!
+//! These need a database, so they are ignored by default. Run them with
+//! `cargo test -p innosystem-common --test repositories -- --ignored`; each test starts
+//! its own Postgres container unless `TEST_DATABASE_URL` points at an existing database.
+
+mod billing_export;
+mod billing_period;
+mod bulk_insert;
+mod customer;
+mod dependency;
+mod feature_flag;
+mod fixture;
+mod instrumented;
+mod job;
+mod job_attempt;
+mod job_event;
+mod job_import;
+mod job_log;
+mod job_template;
+mod job_type;
+mod job_usage;
+mod load_window;
+mod notification;
+mod partition;
+mod pipeline;
+mod pool;
+mod project;
+mod provider;
+mod purge;
+mod replay_case;
+mod request_nonce;
+mod reseller;
+mod runner;
+mod schema_drift;
+mod search;
+mod spending_alert;
+mod submission;
+mod submission_window;
+mod tenancy;
+mod unredacted_output;
+mod wallet;
+mod wallet_adjustment;
+mod wallet_transaction;
+mod webhook;
+
+use innosystem_common::testing::TestEnvironment;
+
+/// Start a fresh test environment, failing the test if it cannot be started
+pub async fn environment() -> TestEnvironment {
+    TestEnvironment::start().await.expect("failed to start test environment")
+}
@@ -0,0 +1,150 @@
+use chrono::{Duration, Utc};
+use innosystem_common::models::notification::{
+    self, DeliveryAttempt, DeliveryChannel, DeliveryStatus, NewNotificationDelivery, NewNotificationPreference, NotificationMode,
+};
+use innosystem_common::models::webhook::WebhookEventType;
+use innosystem_common::repositories::{
+    DieselCustomerRepository, DieselNotificationDeliveryRepository, DieselNotificationPreferenceRepository,
+    NotificationDeliveryRepository, NotificationPreferenceRepository,
+};
+use innosystem_common::testing::factories::CustomerFactory;
+use uuid::Uuid;
+
+use crate::environment;
+
+#[tokio::test]
+#[ignore = "requires Docker or TEST_DATABASE_URL"]
+async fn records_delivery_attempts_and_finds_retries() {
+    let env = environment().await;
+    let repo = DieselNotificationDeliveryRepository::new(env.pool.clone());
+    let customer = CustomerFactory::new()
+        .create(&DieselCustomerRepository::new(env.pool.clone()))
+        .await
+        .unwrap();
+
+    let delivery = repo.create(NewNotificationDelivery {
+        id: Uuid::new_v4(),
+        customer_id: customer.id,
+        webhook_id: None,
+        channel: DeliveryChannel::Webhook.as_str().to_string(),
+        event_type: "job.failed".to_string(),
+        payload: "{}".to_string(),
+        status: DeliveryStatus::Pending.as_str().to_string(),
+    }).await.unwrap();
+    assert_eq!(repo.find_by_id(delivery.id).await.unwrap().status(), Some(DeliveryStatus::Pending));
+    assert!(repo.find_by_id(Uuid::new_v4()).await.is_err());
+    assert!(repo.find_failed_by_customer(customer.id, 10, 0).await.unwrap().is_empty());
+
+    let now = Utc::now();
+    let failed = repo.record_attempt(delivery.id, DeliveryAttempt {
+        status: DeliveryStatus::Failed,
+        status_code: Some(503),
+        latency_ms: Some(42),
+        last_error: Some("Service Unavailable".to_string()),
+        retry_count: 1,
+        next_retry_at: Some(now - Duration::seconds(1)),
+    }).await.unwrap();
+    assert_eq!(failed.status_code, Some(503));
+    assert_eq!(failed.retry_count, 1);
+
+    let failures = repo.find_failed_by_customer(customer.id, 10, 0).await.unwrap();
+    assert_eq!(failures.len(), 1);
+    assert!(repo.find_failed_by_customer(customer.id, 10, 1).await.unwrap().is_empty());
+
+    let due = repo.find_due_retries(DeliveryChannel::Webhook, now, 100).await.unwrap();
+    assert!(due.iter().any(|d| d.id == delivery.id));
+    let due_email = repo.find_due_retries(DeliveryChannel::Email, now, 100).await.unwrap();
+    assert!(!due_email.iter().any(|d| d.id == delivery.id));
+
+    repo.record_attempt(delivery.id, DeliveryAttempt {
+        status: DeliveryStatus::Succeeded,
+        status_code: Some(200),
+        latency_ms: Some(12),
+        last_error: None,
+        retry_count: 2,
+        next_retry_at: None,
+    }).await.unwrap();
+    assert!(repo.find_failed_by_customer(customer.id, 10, 0).await.unwrap().is_empty());
+    assert!(!repo.find_due_retries(DeliveryChannel::Webhook, now, 100).await.unwrap().iter().any(|d| d.id == delivery.id));
+}
+
+#[tokio::test]
+#[ignore = "requires Docker or TEST_DATABASE_URL"]
+async fn finds_deliveries_queued_for_digests() {
+    let env = environment().await;
+    let repo = DieselNotificationDeliveryRepository::new(env.pool.clone());
+    let customer = CustomerFactory::new()
+        .create(&DieselCustomerRepository::new(env.pool.clone()))
+        .await
+        .unwrap();
+
+    let mut queued = Vec::new();
+    for channel in [DeliveryChannel::Email, DeliveryChannel::Email, DeliveryChannel::Webhook] {
+        queued.push(repo.create(NewNotificationDelivery {
+            id: Uuid::new_v4(),
+            customer_id: customer.id,
+            webhook_id: None,
+            channel: channel.as_str().to_string(),
+            event_type: "job.succeeded".to_string(),
+            payload: "{}".to_string(),
+            status: DeliveryStatus::Queued.as_str().to_string(),
+        }).await.unwrap());
+    }
+
+    let emails: Vec<Uuid> = repo.find_queued(DeliveryChannel::Email, 1000).await.unwrap()
+        .into_iter()
+        .filter(|delivery| delivery.customer_id == customer.id)
+        .map(|delivery| delivery.id)
+        .collect();
+    assert_eq!(emails, vec![queued[0].id, queued[1].id]);
+
+    // Once the digest is sent they are no longer queued
+    repo.record_attempt(queued[0].id, DeliveryAttempt {
+        status: DeliveryStatus::Succeeded,
+        status_code: Some(202),
+        latency_ms: Some(8),
+        last_error: None,
+        retry_count: 0,
+        next_retry_at: None,
+    }).await.unwrap();
+    let emails = repo.find_queued(DeliveryChannel::Email, 1000).await.unwrap();
+    assert!(!emails.iter().any(|delivery| delivery.id == queued[0].id));
+    assert!(emails.iter().any(|delivery| delivery.id == queued[1].id));
+}
+
+#[tokio::test]
+#[ignore = "requires Docker or TEST_DATABASE_URL"]
+async fn sets_and_resets_notification_preferences() {
+    let env = environment().await;
+    let repo = DieselNotificationPreferenceRepository::new(env.pool.clone());
+    let customer_repo = DieselCustomerRepository::new(env.pool.clone());
+    let customer = CustomerFactory::new().create(&customer_repo).await.unwrap();
+    let other = CustomerFactory::new().create(&customer_repo).await.unwrap();
+    assert!(repo.find_by_customer(customer.id).await.unwrap().is_empty());
+
+    repo.upsert(NewNotificationPreference::new(customer.id, WebhookEventType::JobSucceeded, DeliveryChannel::Email, NotificationMode::Digest))
+        .await
+        .unwrap();
+    repo.upsert(NewNotificationPreference::new(customer.id, WebhookEventType::JobFailed, DeliveryChannel::Email, NotificationMode::Immediate))
+        .await
+        .unwrap();
+    // Setting a preference again replaces its mode
+    let off = repo.upsert(NewNotificationPreference::new(customer.id, WebhookEventType::JobSucceeded, DeliveryChannel::Email, NotificationMode::Off))
+        .await
+        .unwrap();
+    assert_eq!(off.mode(), Some(NotificationMode::Off));
+    repo.upsert(NewNotificationPreference::new(other.id, WebhookEventType::JobFailed, DeliveryChannel::Email, NotificationMode::Off))
+        .await
+        .unwrap();
+
+    let preferences = repo.find_by_customer(customer.id).await.unwrap();
+    assert_eq!(preferences.len(), 2);
+    assert_eq!(notification::mode_for(&preferences, WebhookEventType::JobSucceeded, DeliveryChannel::Email), NotificationMode::Off);
+    assert_eq!(notification::mode_for(&preferences, WebhookEventType::JobFailed, DeliveryChannel::Email), NotificationMode::Immediate);
+    assert_eq!(notification::mode_for(&preferences, WebhookEventType::JobSucceeded, DeliveryChannel::Webhook), NotificationMode::Immediate);
+
+    assert!(repo.delete(customer.id, WebhookEventType::JobSucceeded, DeliveryChannel::Email).await.unwrap());
+    assert!(!repo.delete(customer.id, WebhookEventType::JobSucceeded, DeliveryChannel::Email).await.unwrap());
+    assert_eq!(repo.find_by_customer(customer.id).await.unwrap().len(), 1);
+    assert_eq!(repo.find_by_customer(other.id).await.unwrap().len(), 1);
+}
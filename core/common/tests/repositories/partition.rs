@@ -0,0 +1,67 @@
+use chrono::NaiveDate;
+use diesel::RunQueryDsl;
+use innosystem_common::Error;
+use innosystem_common::models::job_log::{LogLevel, NewJobLog};
+use innosystem_common::models::partition::PartitionedTable;
+use innosystem_common::repositories::{
+    DieselCustomerRepository, DieselJobLogRepository, DieselJobRepository, DieselJobTypeRepository,
+    DieselPartitionRepository, JobLogRepository, JobRepository, PartitionRepository,
+};
+use innosystem_common::testing::factories::{CustomerFactory, JobFactory, JobTypeFactory};
+use uuid::Uuid;
+
+use crate::environment;
+
+#[tokio::test]
+#[ignore = "requires Docker or TEST_DATABASE_URL"]
+async fn months_are_moved_into_their_own_partition_and_dropped_with_their_jobs() {
+    let env = environment().await;
+    let repo = DieselPartitionRepository::new(env.pool.clone());
+    let job_repo = DieselJobRepository::new(env.pool.clone());
+    let job_log_repo = DieselJobLogRepository::new(env.pool.clone());
+    let customer = CustomerFactory::new()
+        .create(&DieselCustomerRepository::new(env.pool.clone()))
+        .await
+        .unwrap();
+    let job_type = JobTypeFactory::new()
+        .create(&DieselJobTypeRepository::new(env.pool.clone()))
+        .await
+        .unwrap();
+
+    // A month of the last century no other test writes to
+    let offset = (Uuid::new_v4().as_u128() % 1200) as i32;
+    let month = NaiveDate::from_ymd_opt(1900 + offset / 12, (offset % 12) as u32 + 1, 1).unwrap();
+    let job = JobFactory::new(customer.id, job_type.id).create(&job_repo).await.unwrap();
+    job_log_repo.append(vec![NewJobLog {
+        id: Uuid::new_v4(),
+        job_id: job.id,
+        line_number: 1,
+        level: LogLevel::Info.as_str().to_string(),
+        message: "started".to_string(),
+        fields: None,
+        created_at: None,
+    }]).await.unwrap();
+    let mut conn = env.pool.get().unwrap();
+    diesel::sql_query("UPDATE jobs SET created_at = $1 WHERE id = $2")
+        .bind::<diesel::sql_types::Timestamptz, _>(month.and_hms_opt(12, 0, 0).unwrap())
+        .bind::<diesel::sql_types::Uuid, _>(job.id)
+        .execute(&mut conn)
+        .unwrap();
+
+    // Without a partition of its own, the month is kept in the default partition
+    assert!(repo.unpartitioned_months(PartitionedTable::Jobs).await.unwrap().contains(&month));
+    assert!(!repo.list_months(PartitionedTable::Jobs).await.unwrap().contains(&month));
+
+    assert!(repo.create_month(PartitionedTable::Jobs, month).await.unwrap() >= 1);
+    assert!(!repo.unpartitioned_months(PartitionedTable::Jobs).await.unwrap().contains(&month));
+    assert!(repo.list_months(PartitionedTable::Jobs).await.unwrap().contains(&month));
+    assert_eq!(job_repo.find_by_id(job.id).await.unwrap().created_at, month.and_hms_opt(12, 0, 0).map(|at| at.and_utc()));
+    // Creating a partition twice changes nothing
+    assert_eq!(repo.create_month(PartitionedTable::Jobs, month).await.unwrap(), 0);
+
+    assert!(repo.drop_month(PartitionedTable::Jobs, month).await.unwrap() >= 1);
+    assert!(!repo.list_months(PartitionedTable::Jobs).await.unwrap().contains(&month));
+    assert!(matches!(job_repo.find_by_id(job.id).await, Err(Error::NotFound(_))));
+    assert_eq!(job_log_repo.count_by_job_id(job.id).await.unwrap(), 0);
+    assert_eq!(repo.drop_month(PartitionedTable::Jobs, month).await.unwrap(), 0);
+}
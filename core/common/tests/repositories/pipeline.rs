@@ -0,0 +1,82 @@
+use innosystem_common::models::pipeline::{NewPipeline, NewPipelineRun, PipelineDefinition, PipelineRunStatus, StepStatus};
+use innosystem_common::repositories::{DieselCustomerRepository, DieselPipelineRepository, PipelineRepository};
+use innosystem_common::testing::factories::CustomerFactory;
+use serde_json::json;
+use uuid::Uuid;
+
+use crate::environment;
+
+const DEFINITION: &str = "
+name: thumbnails
+steps:
+  - name: resize
+    job_type: resize
+  - name: upload
+    job_type: upload
+    depends_on: [resize]
+";
+
+#[tokio::test]
+#[ignore = "requires Docker or TEST_DATABASE_URL"]
+async fn stores_pipelines_and_tracks_run_steps() {
+    let env = environment().await;
+    let repo = DieselPipelineRepository::new(env.pool.clone());
+    let customer = CustomerFactory::new()
+        .create(&DieselCustomerRepository::new(env.pool.clone()))
+        .await
+        .unwrap();
+
+    let pipeline = repo.create(NewPipeline {
+        id: Uuid::new_v4(),
+        customer_id: customer.id,
+        name: "thumbnails".to_string(),
+        description: None,
+        definition: DEFINITION.to_string(),
+    }).await.unwrap();
+    assert_eq!(pipeline.definition().unwrap(), PipelineDefinition::parse(DEFINITION).unwrap());
+    assert_eq!(repo.find_by_customer_id(customer.id).await.unwrap().len(), 1);
+    assert!(repo.find_by_id(Uuid::new_v4()).await.is_err());
+
+    let run = repo.create_run(NewPipelineRun {
+        id: Uuid::new_v4(),
+        pipeline_id: pipeline.id,
+        customer_id: customer.id,
+        definition: pipeline.definition.clone(),
+        input: json!({ "image": "cat.png" }),
+        priority: 2,
+        test_mode: false,
+    }, vec!["resize".to_string(), "upload".to_string()]).await.unwrap();
+    assert_eq!(run.status(), Some(PipelineRunStatus::Running));
+    assert!(repo.find_running().await.unwrap().iter().any(|running| running.id == run.id));
+
+    let steps = repo.find_run_steps(run.id).await.unwrap();
+    assert_eq!(steps.len(), 2);
+    assert!(steps.iter().all(|step| step.status() == Some(StepStatus::Pending) && step.job_id.is_none()));
+
+    // Only one caller can claim a pending step
+    let job_id = Uuid::new_v4();
+    assert!(repo.claim_step(run.id, "resize".to_string(), StepStatus::Running, Some(job_id)).await.unwrap());
+    assert!(!repo.claim_step(run.id, "resize".to_string(), StepStatus::Running, None).await.unwrap());
+    let steps = repo.find_run_steps(run.id).await.unwrap();
+    assert_eq!(steps.iter().find(|step| step.step_name == "resize").unwrap().job_id, Some(job_id));
+    let failed = repo.update_step(run.id, "resize".to_string(), StepStatus::Failed, None).await.unwrap();
+    assert_eq!(failed.status(), Some(StepStatus::Failed));
+    assert!(repo.update_step(run.id, "missing".to_string(), StepStatus::Failed, None).await.is_err());
+    assert!(repo.claim_step(run.id, "upload".to_string(), StepStatus::Skipped, None).await.unwrap());
+
+    let finished = repo.finish_run(run.id, PipelineRunStatus::Failed).await.unwrap();
+    assert_eq!(finished.status(), Some(PipelineRunStatus::Failed));
+    assert!(finished.completed_at.is_some());
+    assert!(!repo.find_running().await.unwrap().iter().any(|running| running.id == run.id));
+
+    // Editing the pipeline leaves the run's snapshot alone
+    let edited = DEFINITION.replace("thumbnails", "previews");
+    repo.update_definition(pipeline.id, "previews".to_string(), None, edited.clone()).await.unwrap();
+    assert_eq!(repo.find_by_id(pipeline.id).await.unwrap().definition, edited);
+    assert_eq!(repo.find_run_by_id(run.id).await.unwrap().definition, DEFINITION);
+    assert_eq!(repo.find_runs_by_pipeline_id(pipeline.id).await.unwrap().len(), 1);
+
+    repo.delete(pipeline.id).await.unwrap();
+    assert!(repo.find_run_by_id(run.id).await.is_err());
+    assert!(repo.delete(pipeline.id).await.is_err());
+}
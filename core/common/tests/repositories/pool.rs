@@ -0,0 +1,36 @@
+use std::sync::Arc;
+use std::time::Duration;
+
+use innosystem_common::Error;
+use innosystem_common::database::{build_pool, detect_pool_exhaustion, get_connection, PoolConfig, PoolMetrics};
+
+use crate::environment;
+
+#[tokio::test]
+#[ignore = "requires Docker or TEST_DATABASE_URL"]
+async fn checkouts_of_an_exhausted_pool_fail_fast_and_are_counted() {
+    let env = environment().await;
+    let config = PoolConfig {
+        max_size: 1,
+        min_idle: Some(1),
+        connection_timeout: Duration::from_millis(100),
+    };
+    let metrics = Arc::new(PoolMetrics::default());
+    let pool = build_pool(&env.database_url, &config, metrics.clone()).unwrap();
+
+    let held = get_connection(&pool).unwrap();
+    let (result, exhausted) = detect_pool_exhaustion(async { get_connection(&pool).map(|_| ()) }).await;
+    assert!(matches!(result, Err(Error::PoolExhausted(_))), "got {:?}", result);
+    assert!(exhausted);
+
+    drop(held);
+    let (result, exhausted) = detect_pool_exhaustion(async { get_connection(&pool).map(|_| ()) }).await;
+    assert!(result.is_ok());
+    assert!(!exhausted);
+
+    let status = metrics.status(&pool);
+    assert_eq!(status.max_size, 1);
+    assert_eq!(status.checkouts, 2);
+    assert_eq!(status.timeouts, 1);
+    assert!(status.wait_seconds_total >= 0.1);
+}
@@ -0,0 +1,39 @@
+use innosystem_common::models::project::NewProject;
+use innosystem_common::repositories::{DieselCustomerRepository, DieselProjectRepository, ProjectRepository};
+use innosystem_common::testing::factories::CustomerFactory;
+use uuid::Uuid;
+
+use crate::environment;
+
+#[tokio::test]
+#[ignore = "requires Docker or TEST_DATABASE_URL"]
+async fn manages_projects() {
+    let env = environment().await;
+    let repo = DieselProjectRepository::new(env.pool.clone());
+    let customer = CustomerFactory::new()
+        .create(&DieselCustomerRepository::new(env.pool.clone()))
+        .await
+        .unwrap();
+
+    let project = repo.create(NewProject {
+        id: Uuid::new_v4(),
+        customer_id: customer.id,
+        name: "Website".to_string(),
+        description: None,
+    }).await.unwrap();
+
+    assert_eq!(repo.find_by_id(project.id).await.unwrap().name, "Website");
+    assert_eq!(repo.find_by_customer_id(customer.id).await.unwrap().len(), 1);
+    assert!(repo.list_all().await.unwrap().iter().any(|p| p.id == project.id));
+
+    let mut changed = project.clone();
+    changed.name = "Web shop".to_string();
+    changed.description = Some("Storefront jobs".to_string());
+    let updated = repo.update(&changed).await.unwrap();
+    assert_eq!(updated.name, "Web shop");
+    assert_eq!(updated.description.as_deref(), Some("Storefront jobs"));
+
+    repo.delete(project.id).await.unwrap();
+    assert!(repo.find_by_id(project.id).await.is_err());
+    assert!(repo.delete(project.id).await.is_err());
+}
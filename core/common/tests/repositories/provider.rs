@@ -0,0 +1,96 @@
+use chrono::{Duration, Timelike, Utc};
+use innosystem_common::models::provider::{NewProvider, ProviderStatus};
+use innosystem_common::repositories::{
+    DieselCustomerRepository, DieselJobRepository, DieselJobTypeRepository, DieselProviderRepository,
+    JobRepository, JobTypeRepository, ProviderRepository,
+};
+use innosystem_common::testing::factories::{CustomerFactory, JobFactory, JobTypeFactory};
+use uuid::Uuid;
+
+use crate::environment;
+
+fn new_provider() -> NewProvider {
+    NewProvider {
+        id: Uuid::new_v4(),
+        name: format!("provider-{}", Uuid::new_v4().simple()),
+        ping_url: Some("https://status.example.com/ping".to_string()),
+        failure_rate_threshold: 0.5,
+        min_jobs: 10,
+        retry_delay_secs: 300,
+    }
+}
+
+#[tokio::test]
+#[ignore = "requires Docker or TEST_DATABASE_URL"]
+async fn checks_record_when_a_provider_changes_status() {
+    let env = environment().await;
+    let repo = DieselProviderRepository::new(env.pool.clone());
+    let job_type_repo = DieselJobTypeRepository::new(env.pool.clone());
+
+    let provider = repo.create(new_provider()).await.unwrap();
+    assert_eq!(provider.provider_status(), ProviderStatus::Up);
+    assert!(provider.last_checked_at.is_none());
+    assert!(repo.list_all().await.unwrap().iter().any(|listed| listed.id == provider.id));
+
+    let checked = repo.record_check(provider.id, ProviderStatus::Up, None).await.unwrap();
+    assert!(checked.last_checked_at.is_some());
+    assert!(checked.status_changed_at.is_none());
+
+    let down = repo.record_check(provider.id, ProviderStatus::Down, Some("Ping failed: HTTP 503".to_string())).await.unwrap();
+    assert_eq!(down.provider_status(), ProviderStatus::Down);
+    assert_eq!(down.status_reason.as_deref(), Some("Ping failed: HTTP 503"));
+    let changed_at = down.status_changed_at.unwrap();
+    let still_down = repo.record_check(provider.id, ProviderStatus::Down, Some("Ping failed: HTTP 503".to_string())).await.unwrap();
+    assert_eq!(still_down.status_changed_at, Some(changed_at));
+
+    let mut updated = still_down.clone();
+    updated.retry_delay_secs = 60;
+    updated.ping_url = None;
+    let updated = repo.update(updated).await.unwrap();
+    assert_eq!(updated.retry_delay_secs, 60);
+    assert!(updated.ping_url.is_none());
+    assert_eq!(updated.provider_status(), ProviderStatus::Down);
+
+    // Deleting a provider leaves its job types without one
+    let mut job_type = JobTypeFactory::new().create(&job_type_repo).await.unwrap();
+    job_type.provider_id = Some(provider.id);
+    assert_eq!(job_type_repo.update(job_type.clone()).await.unwrap().provider_id, Some(provider.id));
+    repo.delete(provider.id).await.unwrap();
+    assert!(repo.find_by_id(provider.id).await.is_err());
+    assert!(repo.delete(provider.id).await.is_err());
+    assert_eq!(job_type_repo.find_by_id(job_type.id).await.unwrap().provider_id, None);
+}
+
+#[tokio::test]
+#[ignore = "requires Docker or TEST_DATABASE_URL"]
+async fn scheduled_jobs_of_the_given_job_types_coming_due_are_postponed() {
+    let env = environment().await;
+    let job_repo = DieselJobRepository::new(env.pool.clone());
+    let job_type_repo = DieselJobTypeRepository::new(env.pool.clone());
+    let customer = CustomerFactory::new()
+        .create(&DieselCustomerRepository::new(env.pool.clone()))
+        .await
+        .unwrap();
+    let job_type = JobTypeFactory::new().create(&job_type_repo).await.unwrap();
+    let other_type = JobTypeFactory::new().create(&job_type_repo).await.unwrap();
+
+    // Whole seconds, as timestamps are stored with microseconds
+    let now = Utc::now().with_nanosecond(0).unwrap();
+    let until = now + Duration::minutes(10);
+    let due = JobFactory::new(customer.id, job_type.id).create(&job_repo).await.unwrap();
+    job_repo.schedule(due.id, now + Duration::minutes(1)).await.unwrap();
+    let later = JobFactory::new(customer.id, job_type.id).create(&job_repo).await.unwrap();
+    job_repo.schedule(later.id, now + Duration::hours(1)).await.unwrap();
+    let pending = JobFactory::new(customer.id, job_type.id).create(&job_repo).await.unwrap();
+    let other = JobFactory::new(customer.id, other_type.id).create(&job_repo).await.unwrap();
+    job_repo.schedule(other.id, now + Duration::minutes(1)).await.unwrap();
+
+    let postponed = job_repo.postpone_scheduled(vec![job_type.id], until, until).await.unwrap();
+    assert_eq!(postponed, vec![due.id]);
+    assert_eq!(job_repo.find_by_id(due.id).await.unwrap().scheduled_for, Some(until));
+    assert_eq!(job_repo.find_by_id(later.id).await.unwrap().scheduled_for, Some(now + Duration::hours(1)));
+    assert_eq!(job_repo.find_by_id(pending.id).await.unwrap().scheduled_for, None);
+    assert_eq!(job_repo.find_by_id(other.id).await.unwrap().scheduled_for, Some(now + Duration::minutes(1)));
+
+    assert!(job_repo.postpone_scheduled(Vec::new(), until, until).await.unwrap().is_empty());
+}
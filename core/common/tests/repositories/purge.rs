@@ -0,0 +1,86 @@
+use std::sync::Arc;
+
+use chrono::{NaiveDate, TimeZone, Utc};
+use diesel::RunQueryDsl;
+use innosystem_common::models::job_artifact::NewJobArtifact;
+use innosystem_common::models::job_log::LogLevel;
+use innosystem_common::purge::Purger;
+use innosystem_common::repositories::{
+    DieselCustomerRepository, DieselJobArtifactRepository, DieselJobLogRepository, DieselJobRepository,
+    DieselJobTypeRepository, DieselPurgeRepository, JobArtifactRepository, JobLogRepository, JobRepository, PurgeRepository,
+};
+use innosystem_common::testing::factories::{CustomerFactory, JobFactory, JobTypeFactory};
+use uuid::Uuid;
+
+use crate::environment;
+
+#[tokio::test]
+#[ignore = "requires Docker or TEST_DATABASE_URL"]
+async fn finished_jobs_past_retention_are_purged_in_batches_with_their_logs() {
+    let env = environment().await;
+    let repo = DieselPurgeRepository::new(env.pool.clone());
+    let job_repo = DieselJobRepository::new(env.pool.clone());
+    let job_log_repo = DieselJobLogRepository::new(env.pool.clone());
+    let artifact_repo = DieselJobArtifactRepository::new(env.pool.clone());
+    let customer = CustomerFactory::new()
+        .create(&DieselCustomerRepository::new(env.pool.clone()))
+        .await
+        .unwrap();
+    let job_type = JobTypeFactory::new()
+        .create(&DieselJobTypeRepository::new(env.pool.clone()))
+        .await
+        .unwrap();
+
+    // Jobs of a day before any other test's, so the purge leaves theirs alone
+    let offset = (Uuid::new_v4().as_u128() % 365) as u64;
+    let day = NaiveDate::from_ymd_opt(1800, 1, 1).unwrap() + chrono::Days::new(offset);
+    let created_at = Utc.from_utc_datetime(&day.and_hms_opt(12, 0, 0).unwrap());
+    let mut finished = Vec::new();
+    for _ in 0..3 {
+        let job = JobFactory::new(customer.id, job_type.id).create(&job_repo).await.unwrap();
+        job_repo.set_completed(job.id, true, None, None, 0).await.unwrap();
+        job_log_repo.append_event(job.id, LogLevel::Info, "done".to_string(), None).await.unwrap();
+        let artifact = NewJobArtifact::new(job.id, "image/png".to_string(), vec![0x89, b'P', b'N', b'G']);
+        let artifact_id = artifact.id;
+        artifact_repo.create(artifact).await.unwrap();
+        assert_eq!(artifact_repo.find(job.id, artifact_id).await.unwrap().content.len(), 4);
+        finished.push((job.id, artifact_id));
+    }
+    let running = JobFactory::new(customer.id, job_type.id).create(&job_repo).await.unwrap();
+    let mut conn = env.pool.get().unwrap();
+    for id in finished.iter().map(|(id, _)| id).chain([&running.id]) {
+        diesel::sql_query("UPDATE jobs SET created_at = $1 WHERE id = $2")
+            .bind::<diesel::sql_types::Timestamptz, _>(created_at)
+            .bind::<diesel::sql_types::Uuid, _>(*id)
+            .execute(&mut conn)
+            .unwrap();
+    }
+    let cutoff = created_at + chrono::Duration::hours(1);
+
+    // Unfinished jobs are kept whatever their age
+    assert_eq!(repo.count_expired_jobs(cutoff).await.unwrap(), 3);
+    assert_eq!(repo.count_expired_jobs(created_at).await.unwrap(), 0);
+
+    let batch = repo.purge_expired_jobs(cutoff, 2).await.unwrap();
+    assert_eq!(batch["jobs"], 2);
+    assert_eq!(batch["job_logs"], 2);
+    assert_eq!(batch["job_artifacts"], 2);
+    assert_eq!(repo.count_expired_jobs(cutoff).await.unwrap(), 1);
+
+    // A purge finishes the job and verifies nothing past retention is left
+    let purger = Purger::new(Arc::new(DieselPurgeRepository::new(env.pool.clone())), 2);
+    let mut batches = Vec::new();
+    let report = purger.run(&[None], Some(cutoff), false, |progress| batches.push(progress.deleted.clone())).await.unwrap();
+    assert_eq!(report.deleted.get("jobs"), Some(&1));
+    assert_eq!(report.remaining["jobs"], 0);
+    assert!(report.verified);
+    assert!(!batches.is_empty());
+
+    for (id, artifact_id) in &finished {
+        assert!(job_repo.find_by_id(*id).await.is_err());
+        assert!(job_log_repo.find_by_job_id(*id, 10, 0).await.unwrap().is_empty());
+        assert!(artifact_repo.find(*id, *artifact_id).await.is_err());
+    }
+    assert!(job_repo.find_by_id(running.id).await.is_ok());
+    assert!(purger.run(&[None], Some(cutoff), true, |_| {}).await.unwrap().verified);
+}
@@ -0,0 +1,73 @@
+use innosystem_common::models::job::PriorityLevel;
+use innosystem_common::models::redaction::RedactionRule;
+use innosystem_common::models::replay::{Anonymizer, NewReplayCase};
+use innosystem_common::repositories::{
+    DieselCustomerRepository, DieselJobRepository, DieselJobTypeRepository, DieselReplayCaseRepository,
+    JobRepository, ReplayCaseRepository,
+};
+use innosystem_common::testing::factories::{CustomerFactory, JobFactory, JobTypeFactory};
+use serde_json::json;
+use uuid::Uuid;
+
+use crate::environment;
+
+#[tokio::test]
+#[ignore = "requires Docker or TEST_DATABASE_URL"]
+async fn succeeded_live_jobs_are_sampled_once_and_keep_their_baseline() {
+    let env = environment().await;
+    let repo = DieselReplayCaseRepository::new(env.pool.clone());
+    let job_repo = DieselJobRepository::new(env.pool.clone());
+    let customer = CustomerFactory::new()
+        .create(&DieselCustomerRepository::new(env.pool.clone()))
+        .await
+        .unwrap();
+    let job_type = JobTypeFactory::new().create(&DieselJobTypeRepository::new(env.pool.clone())).await.unwrap();
+
+    let mut succeeded = Vec::new();
+    for name in ["ada", "grace"] {
+        let job = JobFactory::new(customer.id, job_type.id)
+            .create(&job_repo)
+            .await
+            .unwrap();
+        // A job's priority is known once it was claimed from its queue
+        job_repo.record_claim(job.id, PriorityLevel::High).await.unwrap();
+        job_repo.set_completed(job.id, true, Some(json!({ "greeting": name })), None, 100).await.unwrap();
+        succeeded.push(job.id);
+    }
+    // Failed, unfinished and test-mode jobs are never sampled
+    let failed = JobFactory::new(customer.id, job_type.id).create(&job_repo).await.unwrap();
+    job_repo.set_completed(failed.id, false, None, None, 0).await.unwrap();
+    JobFactory::new(customer.id, job_type.id).create(&job_repo).await.unwrap();
+    let test_job = JobFactory::new(customer.id, job_type.id).test_mode().create(&job_repo).await.unwrap();
+    job_repo.set_completed(test_job.id, true, Some(json!({})), None, 0).await.unwrap();
+
+    let candidates = repo.find_candidates(job_type.id, 10).await.unwrap();
+    let mut candidate_ids: Vec<Uuid> = candidates.iter().map(|job| job.id).collect();
+    candidate_ids.sort();
+    succeeded.sort();
+    assert_eq!(candidate_ids, succeeded);
+    assert_eq!(repo.find_candidates(job_type.id, 1).await.unwrap().len(), 1);
+
+    let anonymizer = Anonymizer::new(vec![RedactionRule::parse("$.name").unwrap()]);
+    let cases: Vec<NewReplayCase> = candidates.iter().map(|job| NewReplayCase::sample(job, 500, &anonymizer)).collect();
+    let added = repo.add(cases.clone()).await.unwrap();
+    assert_eq!(added.len(), 2);
+    assert!(added.iter().all(|case| !case.has_baseline() && case.priority == PriorityLevel::High.as_i32()));
+
+    // Jobs already in the corpus are skipped
+    let resampled: Vec<NewReplayCase> = candidates.iter().map(|job| NewReplayCase::sample(job, 500, &anonymizer)).collect();
+    assert!(repo.add(resampled).await.unwrap().is_empty());
+    assert!(repo.find_candidates(job_type.id, 10).await.unwrap().is_empty());
+    assert!(repo.list_job_types().await.unwrap().contains(&job_type.id));
+
+    let case = &repo.list_by_job_type(job_type.id).await.unwrap()[0];
+    let recorded = repo.record_baseline(case.id, json!({ "greeting": "anon" }), 150, "1.2.0").await.unwrap();
+    assert!(recorded.has_baseline());
+    assert_eq!(recorded.baseline_cost_cents, Some(150));
+    assert_eq!(recorded.baseline_version.as_deref(), Some("1.2.0"));
+    assert!(recorded.baseline_recorded_at.is_some());
+
+    assert!(!repo.delete(Uuid::new_v4(), case.id).await.unwrap());
+    assert!(repo.delete(job_type.id, case.id).await.unwrap());
+    assert_eq!(repo.list_by_job_type(job_type.id).await.unwrap().len(), 1);
+}
@@ -0,0 +1,36 @@
+use chrono::{Duration, Utc};
+use innosystem_common::models::reseller::Reseller;
+use innosystem_common::repositories::{DieselRequestNonceRepository, DieselResellerRepository, RequestNonceRepository, ResellerRepository};
+use innosystem_common::testing::factories::ResellerFactory;
+use uuid::Uuid;
+
+use crate::environment;
+
+#[tokio::test]
+#[ignore = "requires Docker or TEST_DATABASE_URL"]
+async fn nonces_are_accepted_once_per_reseller_until_they_expire() {
+    let env = environment().await;
+    let reseller_repo = DieselResellerRepository::new(env.pool.clone());
+    let repo = DieselRequestNonceRepository::new(env.pool.clone());
+    let mut reseller = ResellerFactory::new().create(&reseller_repo).await.unwrap();
+    let other = ResellerFactory::new().create(&reseller_repo).await.unwrap();
+
+    reseller.signing_secret = Some(Reseller::generate_signing_secret());
+    reseller.require_signed_requests = true;
+    let updated = reseller_repo.update(&reseller).await.unwrap();
+    assert_eq!(updated.signing_secret, reseller.signing_secret);
+    assert!(!updated.accepts_api_key());
+    assert!(other.accepts_api_key());
+
+    let nonce = Uuid::new_v4().simple().to_string();
+    let expires_at = Utc::now() + Duration::minutes(5);
+    assert!(repo.record(reseller.id, &nonce, expires_at).await.unwrap());
+    assert!(!repo.record(reseller.id, &nonce, expires_at).await.unwrap());
+    assert!(repo.record(other.id, &nonce, expires_at).await.unwrap());
+
+    let expired = Uuid::new_v4().simple().to_string();
+    repo.record(reseller.id, &expired, Utc::now() - Duration::minutes(1)).await.unwrap();
+    assert!(repo.purge_expired().await.unwrap() >= 1);
+    assert!(repo.record(reseller.id, &expired, expires_at).await.unwrap());
+    assert!(!repo.record(reseller.id, &nonce, expires_at).await.unwrap());
+}
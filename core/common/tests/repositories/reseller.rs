@@ -0,0 +1,79 @@
+use chrono::{Duration, Utc};
+use innosystem_common::models::reseller::NewReseller;
+use innosystem_common::repositories::{CustomerRepository, DieselCustomerRepository, DieselResellerRepository, ResellerRepository};
+use innosystem_common::testing::factories::{CustomerFactory, ResellerFactory};
+
+use crate::environment;
+
+#[tokio::test]
+#[ignore = "requires Docker or TEST_DATABASE_URL"]
+async fn creates_finds_and_lists_resellers() {
+    let env = environment().await;
+    let repo = DieselResellerRepository::new(env.pool.clone());
+
+    let active = ResellerFactory::new().commission_rate(2500).create(&repo).await.unwrap();
+    let inactive = ResellerFactory::new().inactive().create(&repo).await.unwrap();
+    assert_eq!(active.commission_rate, 2500);
+
+    assert_eq!(repo.find_by_id(active.id).await.unwrap().name, active.name);
+    assert_eq!(repo.find_by_api_key(&active.api_key).await.unwrap().id, active.id);
+    assert!(repo.find_by_id(uuid::Uuid::new_v4()).await.is_err());
+
+    let all = repo.list_all().await.unwrap();
+    assert!(all.iter().any(|r| r.id == active.id));
+    assert!(all.iter().any(|r| r.id == inactive.id));
+
+    let listed = repo.list_active().await.unwrap();
+    assert!(listed.iter().any(|r| r.id == active.id));
+    assert!(!listed.iter().any(|r| r.id == inactive.id));
+}
+
+#[tokio::test]
+#[ignore = "requires Docker or TEST_DATABASE_URL"]
+async fn updates_resellers() {
+    let env = environment().await;
+    let repo = DieselResellerRepository::new(env.pool.clone());
+    let mut reseller = ResellerFactory::new().create(&repo).await.unwrap();
+
+    reseller.active = false;
+    reseller.suspend_customers = true;
+    reseller.block_customer_creation = true;
+    reseller.deactivation_reason = Some("Contract ended".to_string());
+    let updated = repo.update(&reseller).await.unwrap();
+
+    assert!(!updated.active);
+    assert!(updated.customers_suspended());
+    assert!(!updated.allows_customer_creation());
+    assert_eq!(updated.deactivation_reason.as_deref(), Some("Contract ended"));
+}
+
+#[tokio::test]
+#[ignore = "requires Docker or TEST_DATABASE_URL"]
+async fn deletes_expired_sandboxes_with_their_customers() {
+    let env = environment().await;
+    let repo = DieselResellerRepository::new(env.pool.clone());
+    let customers = DieselCustomerRepository::new(env.pool.clone());
+    let parent = ResellerFactory::new().create(&repo).await.unwrap();
+    let now = Utc::now();
+
+    let expired = repo.create(NewReseller::sandbox(&parent, "Expired".to_string(), now - Duration::hours(1))).await.unwrap();
+    let current = repo.create(NewReseller::sandbox(&parent, "Current".to_string(), now + Duration::days(7))).await.unwrap();
+    assert!(expired.is_sandbox() && !parent.is_sandbox());
+    assert_eq!(expired.sandbox_of, Some(parent.id));
+    let customer = CustomerFactory::new().reseller(expired.id).create(&customers).await.unwrap();
+
+    let listed: Vec<_> = repo.list_sandboxes(parent.id).await.unwrap().into_iter().map(|sandbox| sandbox.id).collect();
+    assert_eq!(listed.len(), 2);
+    assert!(listed.contains(&expired.id) && listed.contains(&current.id));
+    let due: Vec<_> = repo.find_expired_sandboxes(now).await.unwrap().into_iter().map(|sandbox| sandbox.id).collect();
+    assert!(due.contains(&expired.id));
+    assert!(!due.contains(&current.id) && !due.contains(&parent.id));
+
+    // Only sandboxes are deleted this way
+    assert!(repo.delete_sandbox(parent.id).await.is_err());
+    repo.delete_sandbox(expired.id).await.unwrap();
+    assert!(repo.find_by_id(expired.id).await.is_err());
+    assert!(customers.find_by_id(customer.id).await.is_err());
+    assert!(repo.find_by_id(current.id).await.is_ok());
+    assert!(repo.find_by_id(parent.id).await.is_ok());
+}
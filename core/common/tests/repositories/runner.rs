@@ -0,0 +1,267 @@
+use std::collections::BTreeMap;
+
+use chrono::{Duration, Utc};
+use diesel::RunQueryDsl;
+use innosystem_common::models::runner::{NewRunner, NewRunnerEnvironmentReport, NewRunnerHealthCheck, RunnerEnvironment, RunnerStatus};
+use innosystem_common::repositories::{
+    DieselCustomerRepository, DieselJobRepository, DieselJobTypeRepository, DieselRunnerRepository, JobRepository,
+    RunnerRepository,
+};
+use innosystem_common::testing::factories::{CustomerFactory, JobFactory, JobTypeFactory};
+use uuid::Uuid;
+
+use crate::environment;
+
+#[tokio::test]
+#[ignore = "requires Docker or TEST_DATABASE_URL"]
+async fn registers_runners_and_tracks_heartbeats() {
+    let env = environment().await;
+    let repo = DieselRunnerRepository::new(env.pool.clone());
+
+    let runner = repo.register(NewRunner {
+        id: Uuid::new_v4(),
+        name: "runner-1".to_string(),
+        description: None,
+        status: RunnerStatus::Inactive.as_str().to_string(),
+        compatible_job_types: Vec::new(),
+    }).await.unwrap();
+    assert!(matches!(runner.status, RunnerStatus::Inactive));
+    assert_eq!(repo.find_by_id(runner.id).await.unwrap().name, "runner-1");
+    assert!(repo.find_by_id(Uuid::new_v4()).await.is_err());
+    assert!(repo.list_all().await.unwrap().iter().any(|r| r.id == runner.id));
+
+    let now = Utc::now();
+    let since = now - Duration::minutes(1);
+    let beating = repo.update_heartbeat(runner.id, now).await.unwrap();
+    assert!(beating.last_heartbeat.is_some());
+    assert!(!repo.list_active(since).await.unwrap().iter().any(|r| r.id == runner.id));
+
+    let active = repo.set_status(runner.id, true).await.unwrap();
+    assert!(matches!(active.status, RunnerStatus::Active));
+    assert!(repo.list_active(since).await.unwrap().iter().any(|r| r.id == runner.id));
+
+    let inactive = repo.set_status(runner.id, false).await.unwrap();
+    assert!(matches!(inactive.status, RunnerStatus::Inactive));
+}
+
+#[tokio::test]
+#[ignore = "requires Docker or TEST_DATABASE_URL"]
+async fn matches_runners_to_job_types() {
+    let env = environment().await;
+    let repo = DieselRunnerRepository::new(env.pool.clone());
+    let job_type_repo = DieselJobTypeRepository::new(env.pool.clone());
+    let supported = JobTypeFactory::new().create(&job_type_repo).await.unwrap();
+    let unsupported = JobTypeFactory::new().create(&job_type_repo).await.unwrap();
+
+    let runner = repo.register(NewRunner {
+        id: Uuid::new_v4(),
+        name: "runner-2".to_string(),
+        description: Some("Handles one job type".to_string()),
+        status: RunnerStatus::Active.as_str().to_string(),
+        compatible_job_types: Vec::new(),
+    }).await.unwrap();
+    repo.update_heartbeat(runner.id, Utc::now()).await.unwrap();
+    repo.update_capabilities(runner.id, vec![supported.id]).await.unwrap();
+
+    let compatible = repo.find_compatible_with_job_type(&supported).await.unwrap();
+    assert!(compatible.iter().any(|r| r.id == runner.id));
+    assert!(repo.find_compatible_with_job_type(&unsupported).await.unwrap().is_empty());
+
+    assert!(repo.update_capabilities(Uuid::new_v4(), vec![supported.id]).await.is_err());
+}
+
+#[tokio::test]
+#[ignore = "requires Docker or TEST_DATABASE_URL"]
+async fn compatible_job_type_names_follow_the_compatibility_table() {
+    let env = environment().await;
+    let repo = DieselRunnerRepository::new(env.pool.clone());
+    let job_type_repo = DieselJobTypeRepository::new(env.pool.clone());
+    let first = JobTypeFactory::new().create(&job_type_repo).await.unwrap();
+    let second = JobTypeFactory::new().create(&job_type_repo).await.unwrap();
+
+    let runner = repo.register(NewRunner {
+        id: Uuid::new_v4(),
+        name: "runner-3".to_string(),
+        description: None,
+        status: RunnerStatus::Active.as_str().to_string(),
+        compatible_job_types: vec![first.name.clone()],
+    }).await.unwrap();
+    assert_eq!(runner.compatible_job_types, vec![first.name.clone()]);
+    assert_eq!(repo.find_compatible_job_type_ids(runner.id).await.unwrap(), vec![first.id]);
+
+    let updated = repo.update_capabilities(runner.id, vec![second.id]).await.unwrap();
+    assert_eq!(updated.compatible_job_types, vec![second.name.clone()]);
+    assert_eq!(repo.find_by_id(runner.id).await.unwrap().compatible_job_types, vec![second.name.clone()]);
+
+    // Names without a job type are refused and nothing is registered
+    let unknown = NewRunner {
+        id: Uuid::new_v4(),
+        name: "runner-4".to_string(),
+        description: None,
+        status: RunnerStatus::Active.as_str().to_string(),
+        compatible_job_types: vec!["no-such-job-type".to_string()],
+    };
+    let unknown_id = unknown.id;
+    assert!(repo.register(unknown).await.is_err());
+    assert!(repo.find_by_id(unknown_id).await.is_err());
+}
+
+#[tokio::test]
+#[ignore = "requires Docker or TEST_DATABASE_URL"]
+async fn records_runner_job_outcomes_and_health_history() {
+    let env = environment().await;
+    let repo = DieselRunnerRepository::new(env.pool.clone());
+    let job_repo = DieselJobRepository::new(env.pool.clone());
+    let customer = CustomerFactory::new()
+        .create(&DieselCustomerRepository::new(env.pool.clone()))
+        .await
+        .unwrap();
+    let job_type = JobTypeFactory::new()
+        .create(&DieselJobTypeRepository::new(env.pool.clone()))
+        .await
+        .unwrap();
+
+    let runner = repo.register(NewRunner {
+        id: Uuid::new_v4(),
+        name: "runner-3".to_string(),
+        description: None,
+        status: RunnerStatus::Active.as_str().to_string(),
+        compatible_job_types: Vec::new(),
+    }).await.unwrap();
+
+    for success in [true, false, false] {
+        let job = JobFactory::new(customer.id, job_type.id).create(&job_repo).await.unwrap();
+        job_repo.set_started(job.id).await.unwrap();
+        job_repo.assign_runner(job.id, runner.id).await.unwrap();
+        job_repo.set_completed(job.id, success, None, None, 0).await.unwrap();
+    }
+    // Jobs of other runners do not count
+    let unattributed = JobFactory::new(customer.id, job_type.id).create(&job_repo).await.unwrap();
+    job_repo.set_completed(unattributed.id, false, None, None, 0).await.unwrap();
+
+    let stats = job_repo.get_runner_job_stats(runner.id, 60).await.unwrap();
+    assert_eq!((stats.succeeded, stats.failed), (1, 2));
+    assert!(stats.avg_processing_seconds.is_some());
+    assert!((stats.failure_rate().unwrap() - 2.0 / 3.0).abs() < 1e-9);
+    assert!(job_repo.assign_runner(Uuid::new_v4(), runner.id).await.is_err());
+
+    assert!(repo.latest_health_check(runner.id).await.unwrap().is_none());
+    for (status, score) in [("healthy", 100), ("critical", 20)] {
+        repo.record_health_check(NewRunnerHealthCheck {
+            id: Uuid::new_v4(),
+            runner_id: runner.id,
+            status: status.to_string(),
+            score,
+            heartbeat_age_seconds: Some(5),
+            failure_rate: stats.failure_rate(),
+            avg_processing_seconds: stats.avg_processing_seconds,
+            jobs_sampled: stats.completed() as i32,
+        }).await.unwrap();
+    }
+
+    let history = repo.get_health_history(runner.id, 10).await.unwrap();
+    assert_eq!(history.len(), 2);
+    assert_eq!(repo.latest_health_check(runner.id).await.unwrap().unwrap().id, history[0].id);
+    assert_eq!(repo.get_health_history(runner.id, 1).await.unwrap().len(), 1);
+}
+
+#[tokio::test]
+#[ignore = "requires Docker or TEST_DATABASE_URL"]
+async fn tracks_health_streaks_and_deletes_stale_runners() {
+    let env = environment().await;
+    let repo = DieselRunnerRepository::new(env.pool.clone());
+    let job_type = JobTypeFactory::new()
+        .create(&DieselJobTypeRepository::new(env.pool.clone()))
+        .await
+        .unwrap();
+
+    let mut runners = Vec::new();
+    for (name, status) in [("stale", RunnerStatus::Inactive), ("busy", RunnerStatus::Active)] {
+        let runner = repo.register(NewRunner {
+            id: Uuid::new_v4(),
+            name: name.to_string(),
+            description: None,
+            status: status.as_str().to_string(),
+            compatible_job_types: Vec::new(),
+        }).await.unwrap();
+        repo.update_capabilities(runner.id, vec![job_type.id]).await.unwrap();
+        runners.push(runner);
+    }
+    let (stale, busy) = (&runners[0], &runners[1]);
+
+    assert!(repo.health_status_since(busy.id, "critical").await.unwrap().is_none());
+    let mut recorded = Vec::new();
+    for status in ["healthy", "critical", "critical"] {
+        recorded.push(repo.record_health_check(NewRunnerHealthCheck {
+            id: Uuid::new_v4(),
+            runner_id: busy.id,
+            status: status.to_string(),
+            score: 50,
+            heartbeat_age_seconds: None,
+            failure_rate: None,
+            avg_processing_seconds: None,
+            jobs_sampled: 0,
+        }).await.unwrap());
+    }
+    // The run starts with the first critical check after the healthy one
+    assert_eq!(repo.health_status_since(busy.id, "critical").await.unwrap(), recorded[1].created_at);
+    assert!(repo.health_status_since(busy.id, "healthy").await.unwrap().is_none());
+
+    // Only inactive runners untouched since the cutoff are deleted; backdate the stale
+    // runner so runners of concurrently running tests stay out of range
+    let cutoff = Utc::now() - Duration::days(30);
+    assert!(!repo.delete_inactive_before(cutoff).await.unwrap().contains(&stale.id));
+    let mut conn = env.pool.get().unwrap();
+    diesel::sql_query("UPDATE runners SET updated_at = NOW() - INTERVAL '60 days' WHERE id = $1")
+        .bind::<diesel::sql_types::Uuid, _>(stale.id)
+        .execute(&mut conn)
+        .unwrap();
+    let deleted = repo.delete_inactive_before(cutoff).await.unwrap();
+    assert!(deleted.contains(&stale.id));
+    assert!(!deleted.contains(&busy.id));
+    assert!(repo.find_by_id(stale.id).await.is_err());
+    assert!(repo.find_by_id(busy.id).await.is_ok());
+    repo.update_heartbeat(busy.id, Utc::now()).await.unwrap();
+    let compatible = repo.find_compatible_with_job_type(&job_type).await.unwrap();
+    assert_eq!(compatible.iter().map(|r| r.id).collect::<Vec<_>>(), vec![busy.id]);
+}
+
+#[tokio::test]
+#[ignore = "requires Docker or TEST_DATABASE_URL"]
+async fn keeps_a_history_of_changed_runner_environments() {
+    let env = environment().await;
+    let repo = DieselRunnerRepository::new(env.pool.clone());
+    let runner = repo.register(NewRunner {
+        id: Uuid::new_v4(),
+        name: format!("pool-{}", Uuid::new_v4()),
+        description: None,
+        status: RunnerStatus::Active.as_str().to_string(),
+        compatible_job_types: Vec::new(),
+    }).await.unwrap();
+
+    let mut environment = RunnerEnvironment {
+        os: "linux".to_string(),
+        arch: "x86_64".to_string(),
+        processors: 8,
+        libraries: BTreeMap::from([("ffmpeg".to_string(), "6.1".to_string())]),
+    };
+    let first = repo.record_environment(NewRunnerEnvironmentReport::new(runner.id, &environment)).await.unwrap().unwrap();
+    assert_eq!(first.fingerprint, environment.fingerprint());
+    assert_eq!(first.runner_environment(), Some(environment.clone()));
+
+    // Reporting the same environment again is not a change
+    assert!(repo.record_environment(NewRunnerEnvironmentReport::new(runner.id, &environment)).await.unwrap().is_none());
+
+    environment.libraries.insert("ffmpeg".to_string(), "7.0".to_string());
+    let second = repo.record_environment(NewRunnerEnvironmentReport::new(runner.id, &environment)).await.unwrap().unwrap();
+    assert_ne!(second.fingerprint, first.fingerprint);
+
+    let history = repo.get_environment_history(runner.id, 10).await.unwrap();
+    assert_eq!(history.iter().map(|report| report.id).collect::<Vec<_>>(), vec![second.id, first.id]);
+    assert_eq!(repo.get_environment_history(runner.id, 1).await.unwrap().len(), 1);
+
+    let latest = repo.latest_environments().await.unwrap();
+    let ours: Vec<_> = latest.iter().filter(|report| report.runner_id == runner.id).collect();
+    assert_eq!(ours.len(), 1);
+    assert_eq!(ours[0].id, second.id);
+}
@@ -0,0 +1,13 @@
+use innosystem_common::schema_drift::{check, format_drift};
+
+use crate::environment;
+
+#[tokio::test]
+#[ignore = "requires Docker or TEST_DATABASE_URL"]
+async fn migrations_match_the_diesel_schema() {
+    let env = environment().await;
+    let mut conn = env.pool.get().unwrap();
+
+    let drift = check(&mut conn).unwrap();
+    assert!(drift.is_empty(), "the migrated database drifted from diesel_schema:\n{}", format_drift(&drift));
+}
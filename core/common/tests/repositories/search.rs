@@ -0,0 +1,64 @@
+use innosystem_common::models::project::NewProject;
+use innosystem_common::repositories::{
+    DieselCustomerRepository, DieselJobRepository, DieselJobTypeRepository, DieselProjectRepository,
+    DieselResellerRepository, DieselSearchRepository, ProjectRepository, SearchRepository,
+};
+use innosystem_common::testing::factories::{CustomerFactory, JobFactory, JobTypeFactory, ResellerFactory};
+use uuid::Uuid;
+
+use crate::environment;
+
+#[tokio::test]
+#[ignore = "requires Docker or TEST_DATABASE_URL"]
+async fn entities_are_found_by_part_of_their_name_email_or_public_id() {
+    let env = environment().await;
+    let repo = DieselSearchRepository::new(env.pool.clone());
+    let token = Uuid::new_v4().simple().to_string()[..12].to_string();
+    let reseller = ResellerFactory::new()
+        .name(format!("Reseller {}", token))
+        .create(&DieselResellerRepository::new(env.pool.clone()))
+        .await
+        .unwrap();
+    let customer = CustomerFactory::new()
+        .name(format!("Acme {} GmbH", token))
+        .reseller(reseller.id)
+        .create(&DieselCustomerRepository::new(env.pool.clone()))
+        .await
+        .unwrap();
+    let project = DieselProjectRepository::new(env.pool.clone()).create(NewProject {
+        id: Uuid::new_v4(),
+        customer_id: customer.id,
+        name: format!("Invoices {}", token),
+        description: None,
+    }).await.unwrap();
+    let job_type = JobTypeFactory::new()
+        .create(&DieselJobTypeRepository::new(env.pool.clone()))
+        .await
+        .unwrap();
+    let job = JobFactory::new(customer.id, job_type.id)
+        .create(&DieselJobRepository::new(env.pool.clone()))
+        .await
+        .unwrap();
+
+    // Any part of a name, in any case
+    let fragment = token[2..10].to_uppercase();
+    let customers = repo.search_customers(&fragment, 10).await.unwrap();
+    assert_eq!(customers.iter().map(|found| found.id).collect::<Vec<_>>(), vec![customer.id]);
+    let resellers = repo.search_resellers(&fragment, 10).await.unwrap();
+    assert_eq!(resellers.iter().map(|found| found.id).collect::<Vec<_>>(), vec![reseller.id]);
+    let projects = repo.search_projects(&fragment, 10).await.unwrap();
+    assert_eq!(projects.iter().map(|found| found.id).collect::<Vec<_>>(), vec![project.id]);
+    assert_eq!(repo.search_customers(&customer.email, 10).await.unwrap()[0].id, customer.id);
+
+    // Jobs by public ID, with or without its prefix, or by ID
+    let short_id = job.public_id.strip_prefix("job_").unwrap();
+    assert_eq!(repo.search_jobs(short_id, 10).await.unwrap()[0].id, job.id);
+    assert_eq!(repo.search_jobs(&job.public_id.to_uppercase(), 10).await.unwrap()[0].id, job.id);
+    assert_eq!(repo.search_jobs(&job.id.to_string(), 10).await.unwrap()[0].id, job.id);
+    assert_eq!(repo.search_customers(&customer.id.to_string(), 10).await.unwrap()[0].id, customer.id);
+    assert!(repo.search_projects(&Uuid::new_v4().to_string(), 10).await.unwrap().is_empty());
+
+    // Wildcards in the query match literally
+    assert!(repo.search_customers(&format!("{}%", token), 10).await.unwrap().is_empty());
+    assert!(repo.search_customers("Acme", 1).await.unwrap().len() <= 1);
+}
@@ -0,0 +1,60 @@
+use chrono::{Duration, Utc};
+use innosystem_common::models::spending_alert::{AnomalyKind, NewSpendingAlert};
+use innosystem_common::repositories::{
+    DieselCustomerRepository, DieselResellerRepository, DieselSpendingAlertRepository, SpendingAlertRepository,
+};
+use innosystem_common::testing::factories::{CustomerFactory, ResellerFactory};
+use uuid::Uuid;
+
+use crate::environment;
+
+fn new_alert(customer_id: Uuid, reseller_id: Option<Uuid>, kind: AnomalyKind) -> NewSpendingAlert {
+    let now = Utc::now();
+    NewSpendingAlert {
+        id: Uuid::new_v4(),
+        customer_id,
+        reseller_id,
+        kind: kind.as_str().to_string(),
+        observed: 5000.0,
+        baseline: 200.0,
+        window_start: now - Duration::hours(1),
+        window_end: now,
+    }
+}
+
+#[tokio::test]
+#[ignore = "requires Docker or TEST_DATABASE_URL"]
+async fn records_and_lists_spending_alerts() {
+    let env = environment().await;
+    let repo = DieselSpendingAlertRepository::new(env.pool.clone());
+    let reseller = ResellerFactory::new()
+        .create(&DieselResellerRepository::new(env.pool.clone()))
+        .await
+        .unwrap();
+    let customer_repo = DieselCustomerRepository::new(env.pool.clone());
+    let customer = CustomerFactory::new().reseller(reseller.id).create(&customer_repo).await.unwrap();
+    let other = CustomerFactory::new().create(&customer_repo).await.unwrap();
+
+    let before = Utc::now() - Duration::seconds(5);
+    assert!(!repo.exists_since(customer.id, AnomalyKind::SpendSpike, before).await.unwrap());
+
+    let spike = repo.create(new_alert(customer.id, Some(reseller.id), AnomalyKind::SpendSpike)).await.unwrap();
+    assert_eq!(spike.anomaly_kind(), Some(AnomalyKind::SpendSpike));
+    let jump = repo.create(new_alert(customer.id, Some(reseller.id), AnomalyKind::FailureRateJump)).await.unwrap();
+    let unrelated = repo.create(new_alert(other.id, None, AnomalyKind::SpendSpike)).await.unwrap();
+
+    // The cooldown is per customer and kind
+    assert!(repo.exists_since(customer.id, AnomalyKind::SpendSpike, before).await.unwrap());
+    assert!(!repo.exists_since(customer.id, AnomalyKind::SpendSpike, Utc::now() + Duration::minutes(1)).await.unwrap());
+
+    let of_customer = repo.list_by_customer(customer.id, 10).await.unwrap();
+    assert_eq!(of_customer.iter().map(|alert| alert.id).collect::<Vec<_>>(), vec![jump.id, spike.id]);
+    assert_eq!(repo.list_by_customer(customer.id, 1).await.unwrap().len(), 1);
+
+    let of_reseller = repo.list_by_reseller(reseller.id, 10).await.unwrap();
+    assert_eq!(of_reseller.len(), 2);
+    assert!(of_reseller.iter().all(|alert| alert.customer_id == customer.id));
+
+    let recent = repo.list_recent(10).await.unwrap();
+    assert!(recent.iter().any(|alert| alert.id == unrelated.id));
+}
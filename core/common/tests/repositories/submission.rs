@@ -0,0 +1,73 @@
+use innosystem_common::models::job::JobStatus;
+use innosystem_common::models::submission::{NewSubmission, SubmissionSource, SubmissionSummary};
+use innosystem_common::repositories::{
+    DieselCustomerRepository, DieselJobRepository, DieselJobTypeRepository, DieselSubmissionRepository, JobRepository,
+    SubmissionRepository,
+};
+use innosystem_common::testing::factories::{CustomerFactory, JobFactory, JobTypeFactory};
+use uuid::Uuid;
+
+use crate::environment;
+
+#[tokio::test]
+#[ignore = "requires Docker or TEST_DATABASE_URL"]
+async fn cancelling_a_submission_keeps_the_first_cancellation() {
+    let env = environment().await;
+    let repo = DieselSubmissionRepository::new(env.pool.clone());
+    let customer = CustomerFactory::new()
+        .create(&DieselCustomerRepository::new(env.pool.clone()))
+        .await
+        .unwrap();
+
+    let submission = repo.create(NewSubmission::new(Uuid::new_v4(), customer.id, SubmissionSource::Batch)).await.unwrap();
+    assert_eq!(submission.source, "batch");
+    assert!(submission.cancelled_at.is_none());
+    assert_eq!(repo.find_by_id(submission.id).await.unwrap().customer_id, customer.id);
+    assert!(repo.find_by_id(Uuid::new_v4()).await.is_err());
+
+    let cancelled = repo.cancel(submission.id).await.unwrap();
+    let cancelled_at = cancelled.cancelled_at.unwrap();
+    assert_eq!(repo.cancel(submission.id).await.unwrap().cancelled_at, Some(cancelled_at));
+    assert!(repo.cancel(Uuid::new_v4()).await.is_err());
+}
+
+#[tokio::test]
+#[ignore = "requires Docker or TEST_DATABASE_URL"]
+async fn finds_the_jobs_of_a_submission_oldest_first() {
+    let env = environment().await;
+    let repo = DieselSubmissionRepository::new(env.pool.clone());
+    let job_repo = DieselJobRepository::new(env.pool.clone());
+    let customer = CustomerFactory::new()
+        .create(&DieselCustomerRepository::new(env.pool.clone()))
+        .await
+        .unwrap();
+    let job_type = JobTypeFactory::new()
+        .create(&DieselJobTypeRepository::new(env.pool.clone()))
+        .await
+        .unwrap();
+
+    let submission = repo.create(NewSubmission::new(Uuid::new_v4(), customer.id, SubmissionSource::Import)).await.unwrap();
+    let mut ids = Vec::new();
+    for _ in 0..3 {
+        let job = JobFactory::new(customer.id, job_type.id)
+            .estimated_cost_cents(250)
+            .submission(submission.id)
+            .create(&job_repo)
+            .await
+            .unwrap();
+        ids.push(job.id);
+    }
+    // Jobs outside the submission are left out
+    JobFactory::new(customer.id, job_type.id).create(&job_repo).await.unwrap();
+
+    job_repo.update_status(ids[0], JobStatus::Cancelled).await.unwrap();
+
+    let jobs = job_repo.find_by_submission(submission.id).await.unwrap();
+    assert_eq!(jobs.iter().map(|job| job.id).collect::<Vec<_>>(), ids);
+    assert!(jobs.iter().all(|job| job.submission_id == Some(submission.id)));
+
+    let summary = SubmissionSummary::of(&jobs);
+    assert_eq!((summary.total, summary.pending, summary.cancelled), (3, 2, 1));
+    assert_eq!(summary.estimated_cost_cents, 500);
+    assert!(job_repo.find_by_submission(Uuid::new_v4()).await.unwrap().is_empty());
+}
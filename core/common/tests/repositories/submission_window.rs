@@ -0,0 +1,66 @@
+use chrono::{NaiveDate, NaiveTime};
+use innosystem_common::models::submission_window::{NewSubmissionWindow, SubmissionWindow};
+use innosystem_common::repositories::{DieselCustomerRepository, DieselJobTypeRepository, DieselResellerRepository, DieselSubmissionWindowRepository, SubmissionWindowRepository};
+use innosystem_common::testing::factories::{CustomerFactory, JobTypeFactory, ResellerFactory};
+use innosystem_common::timezone::Tz;
+use uuid::Uuid;
+
+use crate::environment;
+
+fn time(hour: u32, minute: u32) -> NaiveTime {
+    NaiveTime::from_hms_opt(hour, minute, 0).unwrap()
+}
+
+fn window(job_type_id: Uuid, customer_id: Option<Uuid>, reseller_id: Option<Uuid>, start: NaiveTime, end: NaiveTime) -> NewSubmissionWindow {
+    NewSubmissionWindow { id: Uuid::new_v4(), job_type_id, customer_id, reseller_id, start_time: start, end_time: end }
+}
+
+#[tokio::test]
+#[ignore = "requires Docker or TEST_DATABASE_URL"]
+async fn customer_windows_override_reseller_windows() {
+    let env = environment().await;
+    let repo = DieselSubmissionWindowRepository::new(env.pool.clone());
+    let customer_repo = DieselCustomerRepository::new(env.pool.clone());
+    let reseller = ResellerFactory::new().create(&DieselResellerRepository::new(env.pool.clone())).await.unwrap();
+    let customer = CustomerFactory::new().reseller(reseller.id).create(&customer_repo).await.unwrap();
+    let other = CustomerFactory::new().reseller(reseller.id).create(&customer_repo).await.unwrap();
+    let job_type = JobTypeFactory::new().create(&DieselJobTypeRepository::new(env.pool.clone())).await.unwrap();
+
+    // Batch jobs of the reseller's customers run at night, wrapping past midnight
+    let nightly = repo.create(window(job_type.id, None, Some(reseller.id), time(22, 0), time(6, 0))).await.unwrap();
+    let own = repo.create(window(job_type.id, Some(customer.id), None, time(12, 0), time(13, 0))).await.unwrap();
+
+    let applicable = repo.find_applicable(job_type.id, customer.id, Some(reseller.id)).await.unwrap();
+    assert_eq!(applicable.iter().map(|w| w.id).collect::<Vec<_>>(), vec![own.id]);
+    let inherited = repo.find_applicable(job_type.id, other.id, Some(reseller.id)).await.unwrap();
+    assert_eq!(inherited.iter().map(|w| w.id).collect::<Vec<_>>(), vec![nightly.id]);
+    assert!(repo.find_applicable(job_type.id, other.id, None).await.unwrap().is_empty());
+
+    assert_eq!(repo.find_by_reseller_id(reseller.id).await.unwrap().len(), 1);
+    assert_eq!(repo.find_by_customer_id(customer.id).await.unwrap().len(), 1);
+
+    // Inside the wrapped window jobs are queued immediately, outside they wait for its opening
+    let day = NaiveDate::from_ymd_opt(2025, 4, 16).unwrap();
+    assert_eq!(SubmissionWindow::release_time(&inherited, day.and_time(time(23, 30)).and_utc(), Tz::UTC), None);
+    assert_eq!(SubmissionWindow::release_time(&inherited, day.and_time(time(5, 59)).and_utc(), Tz::UTC), None);
+    assert_eq!(SubmissionWindow::release_time(&inherited, day.and_time(time(6, 0)).and_utc(), Tz::UTC), Some(day.and_time(time(22, 0)).and_utc()));
+    assert_eq!(
+        SubmissionWindow::release_time(&applicable, day.and_time(time(13, 0)).and_utc(), Tz::UTC),
+        Some(day.succ_opt().unwrap().and_time(time(12, 0)).and_utc())
+    );
+    assert_eq!(SubmissionWindow::release_time(&[], day.and_time(time(13, 0)).and_utc(), Tz::UTC), None);
+
+    // Window times are the customer's wall-clock times: 22:00 in Helsinki (UTC+3 in summer) is 19:00 UTC
+    let helsinki = Tz::Europe__Helsinki;
+    assert_eq!(SubmissionWindow::release_time(&inherited, day.and_time(time(20, 0)).and_utc(), helsinki), None);
+    assert_eq!(
+        SubmissionWindow::release_time(&inherited, day.and_time(time(12, 0)).and_utc(), helsinki),
+        Some(day.and_time(time(19, 0)).and_utc())
+    );
+
+    repo.delete(own.id).await.unwrap();
+    assert!(repo.find_by_id(own.id).await.is_err());
+    assert!(repo.delete(own.id).await.is_err());
+    let applicable = repo.find_applicable(job_type.id, customer.id, Some(reseller.id)).await.unwrap();
+    assert_eq!(applicable.iter().map(|w| w.id).collect::<Vec<_>>(), vec![nightly.id]);
+}
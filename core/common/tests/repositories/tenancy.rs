@@ -0,0 +1,148 @@
+use std::sync::Arc;
+
+use chrono::{Duration, Utc};
+use diesel::RunQueryDsl;
+use innosystem_common::database::{with_reseller, SchemaResolver, TenancyConfig, TenancyMode, TenantPool};
+use innosystem_common::models::job::PriorityLevel;
+use innosystem_common::models::job_artifact::NewJobArtifact;
+use innosystem_common::models::job_cost::CostBreakdown;
+use innosystem_common::models::ledger::JobDebit;
+use innosystem_common::repositories::{
+    DieselCustomerRepository, DieselJobArtifactRepository, DieselJobEventRepository, DieselJobRepository, DieselJobTypeRepository,
+    DieselLedgerRepository, DieselResellerRepository, DieselWalletRepository, JobArtifactRepository, JobEventRepository, JobRepository,
+    LedgerRepository, WalletRepository,
+};
+use innosystem_common::testing::factories::{CustomerFactory, JobFactory, JobTypeFactory, ResellerFactory, WalletFactory};
+use innosystem_common::Error;
+use uuid::Uuid;
+
+use crate::environment;
+
+#[tokio::test]
+#[ignore = "requires Docker or TEST_DATABASE_URL"]
+async fn reseller_schemas_isolate_customer_data() {
+    let env = environment().await;
+    let config = TenancyConfig { mode: TenancyMode::SchemaPerReseller, ..TenancyConfig::default() };
+    let resolver = Arc::new(SchemaResolver::new(env.pool.clone(), env.database_url.clone(), config));
+    let pool = TenantPool::new(resolver.clone());
+    let wallet_repo = DieselWalletRepository::new(pool.clone());
+    let job_repo = DieselJobRepository::new(pool.clone());
+    let customer_repo = DieselCustomerRepository::new(pool.clone());
+    let reseller_repo = DieselResellerRepository::new(pool.clone());
+
+    let isolated = ResellerFactory::new().create(&reseller_repo).await.unwrap();
+    let shared = ResellerFactory::new().create(&reseller_repo).await.unwrap();
+    let customer = CustomerFactory::new().reseller(isolated.id).create(&customer_repo).await.unwrap();
+    let neighbour = CustomerFactory::new().reseller(shared.id).create(&customer_repo).await.unwrap();
+    let job_type = JobTypeFactory::new().create(&DieselJobTypeRepository::new(pool.clone())).await.unwrap();
+    WalletFactory::new(neighbour.id).create(&wallet_repo).await.unwrap();
+
+    // Resellers whose customers already have data in the shared schema are refused
+    assert!(matches!(resolver.provision(shared.id), Err(Error::InvalidInput(_))));
+    let schema = resolver.provision(isolated.id).unwrap();
+    assert_eq!(schema, format!("reseller_{}", isolated.id.simple()));
+    assert!(resolver.isolated_resellers().unwrap().contains(&isolated.id));
+    // Provisioning again only syncs the schema
+    assert_eq!(resolver.provision(isolated.id).unwrap(), schema);
+
+    // Data written on behalf of the reseller lands in its schema; the customer
+    // directory and job types stay shared
+    let artifact_repo = DieselJobArtifactRepository::new(pool.clone());
+    let (job, artifact) = with_reseller(Some(isolated.id), async {
+        WalletFactory::new(customer.id).create(&wallet_repo).await.unwrap();
+        let job = JobFactory::new(customer.id, job_type.id).create(&job_repo).await.unwrap();
+        let artifact = NewJobArtifact::new(job.id, "image/png".to_string(), vec![0x89, b'P', b'N', b'G']);
+        artifact_repo.create(artifact.clone()).await.unwrap();
+        (job, artifact)
+    }).await;
+
+    assert!(with_reseller(Some(isolated.id), wallet_repo.find_by_customer_id(customer.id)).await.is_ok());
+    assert!(with_reseller(Some(isolated.id), wallet_repo.find_by_customer_id(neighbour.id)).await.is_err());
+    assert!(wallet_repo.find_by_customer_id(customer.id).await.is_err());
+    assert!(wallet_repo.find_by_customer_id(neighbour.id).await.is_ok());
+    assert!(job_repo.find_by_id(job.id).await.is_err());
+    assert!(with_reseller(Some(isolated.id), artifact_repo.find(job.id, artifact.id)).await.is_ok());
+    assert!(artifact_repo.find(job.id, artifact.id).await.is_err());
+
+    // Workers that only know a job ID find the schema holding it
+    assert_eq!(resolver.locate_job(job.id).unwrap(), Some(isolated.id));
+    let shared_job = JobFactory::new(neighbour.id, job_type.id).create(&job_repo).await.unwrap();
+    assert_eq!(resolver.locate_job(shared_job.id).unwrap(), None);
+
+    // Resellers without a schema keep using the shared one
+    with_reseller(Some(shared.id), job_repo.find_by_id(shared_job.id)).await.unwrap();
+}
+
+#[tokio::test]
+#[ignore = "requires Docker or TEST_DATABASE_URL"]
+async fn postings_to_wallets_in_reseller_schemas_are_charged_once() {
+    let env = environment().await;
+    let config = TenancyConfig { mode: TenancyMode::SchemaPerReseller, ..TenancyConfig::default() };
+    let resolver = Arc::new(SchemaResolver::new(env.pool.clone(), env.database_url.clone(), config));
+    let pool = TenantPool::new(resolver.clone());
+    let wallet_repo = DieselWalletRepository::new(pool.clone());
+
+    let reseller = ResellerFactory::new().create(&DieselResellerRepository::new(pool.clone())).await.unwrap();
+    let customer = CustomerFactory::new().reseller(reseller.id).create(&DieselCustomerRepository::new(pool.clone())).await.unwrap();
+    resolver.provision(reseller.id).unwrap();
+
+    // The posting's reference is claimed next to the wallet, in the reseller's schema
+    let job_id = Uuid::new_v4();
+    let cost = CostBreakdown::completed(500, &PriorityLevel::Medium, 2000);
+    let wallet = with_reseller(Some(reseller.id), async {
+        let wallet = WalletFactory::new(customer.id).balance_cents(1000).create(&wallet_repo).await.unwrap();
+        wallet_repo.charge_job(JobDebit::new(wallet.id, job_id, &cost, Some(&reseller))).await.unwrap();
+        // A retried charge is not posted again
+        wallet_repo.charge_job(JobDebit::new(wallet.id, job_id, &cost, Some(&reseller))).await.unwrap()
+    }).await;
+    assert_eq!(i64::from(wallet.balance_cents), 1000 - cost.total_cents);
+    let transactions = with_reseller(Some(reseller.id), wallet_repo.get_transactions(wallet.id, 10, 0)).await.unwrap();
+    assert_eq!(transactions.len(), 1);
+
+    // Its ledger entries are kept in the reseller's schema too, and must balance there
+    let ledger = DieselLedgerRepository::new(pool.clone());
+    assert_eq!(with_reseller(Some(reseller.id), ledger.find_by_job(job_id)).await.unwrap().len(), 3);
+    assert!(ledger.find_by_job(job_id).await.unwrap().is_empty());
+    let unbalanced = with_reseller(Some(reseller.id), async {
+        let mut conn = pool.get().unwrap();
+        diesel::sql_query(
+            "INSERT INTO ledger_entries (id, transaction_id, account_id, job_id, amount_cents) \
+             SELECT $1, $2, account_id, job_id, 1 FROM ledger_entries WHERE job_id = $3 LIMIT 1",
+        )
+        .bind::<diesel::sql_types::Uuid, _>(Uuid::new_v4())
+        .bind::<diesel::sql_types::Uuid, _>(Uuid::new_v4())
+        .bind::<diesel::sql_types::Uuid, _>(job_id)
+        .execute(&mut conn)
+    }).await;
+    assert!(unbalanced.unwrap_err().to_string().contains("do not balance"));
+}
+
+#[tokio::test]
+#[ignore = "requires Docker or TEST_DATABASE_URL"]
+async fn job_lifecycle_events_are_captured_in_reseller_schemas() {
+    let env = environment().await;
+    let config = TenancyConfig { mode: TenancyMode::SchemaPerReseller, ..TenancyConfig::default() };
+    let resolver = Arc::new(SchemaResolver::new(env.pool.clone(), env.database_url.clone(), config));
+    let pool = TenantPool::new(resolver.clone());
+    let job_repo = DieselJobRepository::new(pool.clone());
+    let event_repo = DieselJobEventRepository::new(pool.clone());
+
+    let reseller = ResellerFactory::new().create(&DieselResellerRepository::new(pool.clone())).await.unwrap();
+    let customer = CustomerFactory::new().reseller(reseller.id).create(&DieselCustomerRepository::new(pool.clone())).await.unwrap();
+    let job_type = JobTypeFactory::new().create(&DieselJobTypeRepository::new(pool.clone())).await.unwrap();
+    resolver.provision(reseller.id).unwrap();
+
+    let since = Utc::now() - Duration::hours(1);
+    let job = with_reseller(Some(reseller.id), async {
+        let job = JobFactory::new(customer.id, job_type.id).create(&job_repo).await.unwrap();
+        job_repo.set_started(job.id).await.unwrap();
+        job_repo.set_completed(job.id, true, None, None, 120).await.unwrap();
+        assert_eq!(event_repo.capture(since, 1000).await.unwrap(), 2);
+        job
+    }).await;
+
+    // The events are pending in the reseller's schema only, next to its customers' webhooks
+    let pending = with_reseller(Some(reseller.id), event_repo.find_pending(1000)).await.unwrap();
+    assert_eq!(pending.iter().filter(|event| event.job_id == job.id).count(), 2);
+    assert!(event_repo.find_by_job(job.id).await.unwrap().is_empty());
+}
@@ -0,0 +1,54 @@
+use chrono::{Duration, Utc};
+use innosystem_common::models::redaction::NewUnredactedOutput;
+use innosystem_common::repositories::{
+    DieselCustomerRepository, DieselJobRepository, DieselJobTypeRepository, DieselUnredactedOutputRepository,
+    JobTypeRepository, UnredactedOutputRepository,
+};
+use innosystem_common::testing::factories::{CustomerFactory, JobFactory, JobTypeFactory};
+use serde_json::json;
+
+use crate::environment;
+
+#[tokio::test]
+#[ignore = "requires Docker or TEST_DATABASE_URL"]
+async fn unredacted_outputs_are_returned_until_they_expire() {
+    let env = environment().await;
+    let repo = DieselUnredactedOutputRepository::new(env.pool.clone());
+    let job_type_repo = DieselJobTypeRepository::new(env.pool.clone());
+    let customer = CustomerFactory::new()
+        .create(&DieselCustomerRepository::new(env.pool.clone()))
+        .await
+        .unwrap();
+    let mut job_type = JobTypeFactory::new().create(&job_type_repo).await.unwrap();
+    let job_repo = DieselJobRepository::new(env.pool.clone());
+    let kept = JobFactory::new(customer.id, job_type.id).create(&job_repo).await.unwrap();
+    let expired = JobFactory::new(customer.id, job_type.id).create(&job_repo).await.unwrap();
+
+    // The redaction settings are stored with the job type
+    job_type.redaction_rules = vec!["$.token".to_string()];
+    job_type.unredacted_retention_hours = Some(24);
+    let job_type = job_type_repo.update(job_type).await.unwrap();
+    assert_eq!(job_type.redaction_rules, vec!["$.token"]);
+    assert_eq!(job_type.unredacted_retention_hours, Some(24));
+
+    let now = Utc::now();
+    repo.store(NewUnredactedOutput { job_id: kept.id, output_data: json!({ "token": "first" }), expires_at: now + Duration::hours(1) })
+        .await
+        .unwrap();
+    // A rerun replaces the output of the earlier run
+    repo.store(NewUnredactedOutput { job_id: kept.id, output_data: json!({ "token": "second" }), expires_at: now + Duration::hours(2) })
+        .await
+        .unwrap();
+    repo.store(NewUnredactedOutput { job_id: expired.id, output_data: json!({ "token": "old" }), expires_at: now - Duration::minutes(1) })
+        .await
+        .unwrap();
+
+    let output = repo.find_by_job_id(kept.id).await.unwrap();
+    assert_eq!(output.output_data, json!({ "token": "second" }));
+    assert!(output.expires_at > now + Duration::minutes(90));
+    let err = repo.find_by_job_id(expired.id).await.unwrap_err();
+    assert!(err.to_string().contains("not found"));
+
+    assert!(repo.purge_expired().await.unwrap() >= 1);
+    assert!(repo.find_by_job_id(kept.id).await.is_ok());
+}
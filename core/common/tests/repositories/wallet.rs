@@ -0,0 +1,330 @@
+use innosystem_common::models::job::PriorityLevel;
+use innosystem_common::models::job_cost::CostBreakdown;
+use innosystem_common::models::ledger::{JobDebit, LedgerAccountKind};
+use innosystem_common::models::wallet::{NewWalletTransaction, OverdraftPolicy, TransactionType};
+use innosystem_common::repositories::{
+    DieselCustomerRepository, DieselJobRepository, DieselJobTypeRepository, DieselLedgerRepository, DieselResellerRepository,
+    DieselWalletRepository, DieselWalletTransactionRepository, LedgerRepository, WalletRepository, WalletTransactionRepository,
+};
+use innosystem_common::testing::TestEnvironment;
+use innosystem_common::testing::factories::{CustomerFactory, JobFactory, JobTypeFactory, ResellerFactory, WalletFactory};
+use serde_json::json;
+use uuid::Uuid;
+
+use crate::environment;
+
+/// Insert a customer and return its ID
+async fn customer_id(env: &TestEnvironment) -> Uuid {
+    CustomerFactory::new()
+        .create(&DieselCustomerRepository::new(env.pool.clone()))
+        .await
+        .unwrap()
+        .id
+}
+
+#[tokio::test]
+#[ignore = "requires Docker or TEST_DATABASE_URL"]
+async fn creates_and_finds_wallets() {
+    let env = environment().await;
+    let repo = DieselWalletRepository::new(env.pool.clone());
+    let customer_id = customer_id(&env).await;
+
+    let wallet = WalletFactory::new(customer_id).balance_cents(2500).create(&repo).await.unwrap();
+    assert_eq!(wallet.balance_cents, 2500);
+
+    assert_eq!(repo.find_by_id(wallet.id).await.unwrap().customer_id, customer_id);
+    assert_eq!(repo.find_by_customer_id(customer_id).await.unwrap().id, wallet.id);
+    assert_eq!(repo.get_balance(wallet.id).await.unwrap(), 2500);
+
+    let missing = repo.find_by_customer_id(Uuid::new_v4()).await.unwrap_err();
+    assert!(missing.to_string().contains("not found"));
+}
+
+#[tokio::test]
+#[ignore = "requires Docker or TEST_DATABASE_URL"]
+async fn creates_many_wallets() {
+    let env = environment().await;
+    let repo = DieselWalletRepository::new(env.pool.clone());
+    let first = customer_id(&env).await;
+    let second = customer_id(&env).await;
+
+    let created = repo
+        .create_many(vec![
+            WalletFactory::new(first).balance_cents(100).build(),
+            WalletFactory::new(second).balance_cents(200).build(),
+        ])
+        .await
+        .unwrap();
+    assert_eq!(created.len(), 2);
+    assert_eq!(repo.find_by_customer_id(second).await.unwrap().balance_cents, 200);
+    assert!(repo.create_many(Vec::new()).await.unwrap().is_empty());
+}
+
+#[tokio::test]
+#[ignore = "requires Docker or TEST_DATABASE_URL"]
+async fn deposits_and_withdraws_funds() {
+    let env = environment().await;
+    let repo = DieselWalletRepository::new(env.pool.clone());
+    let wallet = WalletFactory::new(customer_id(&env).await).balance_cents(1000).create(&repo).await.unwrap();
+
+    let wallet = repo.deposit(wallet.id, 500, None, None).await.unwrap();
+    assert_eq!(wallet.balance_cents, 1500);
+    assert!(repo.deposit(wallet.id, 0, None, None).await.is_err());
+
+    let wallet = repo.withdraw(wallet.id, 300, Some("Payout".to_string()), None).await.unwrap();
+    assert_eq!(wallet.balance_cents, 1200);
+    assert!(repo.withdraw(wallet.id, 5000, None, None).await.is_err());
+    assert!(repo.withdraw(wallet.id, -1, None, None).await.is_err());
+
+    let wallet = repo.update_balance(wallet.id, -200, TransactionType::JobDebit, None, None).await.unwrap();
+    assert_eq!(wallet.balance_cents, 1000);
+
+    let transactions = repo.get_transactions(wallet.id, 10, 0).await.unwrap();
+    assert_eq!(transactions.len(), 3);
+    let total: i32 = transactions.iter().map(|t| t.amount_cents).sum();
+    assert_eq!(total, 0);
+    assert_eq!(repo.get_transactions(wallet.id, 2, 2).await.unwrap().len(), 1);
+}
+
+#[tokio::test]
+#[ignore = "requires Docker or TEST_DATABASE_URL"]
+async fn reserves_and_releases_funds() {
+    let env = environment().await;
+    let repo = DieselWalletRepository::new(env.pool.clone());
+    let customer_id = customer_id(&env).await;
+    let wallet = WalletFactory::new(customer_id).balance_cents(1000).create(&repo).await.unwrap();
+    let job_type = JobTypeFactory::new()
+        .create(&DieselJobTypeRepository::new(env.pool.clone()))
+        .await
+        .unwrap();
+    let job = JobFactory::new(customer_id, job_type.id)
+        .create(&DieselJobRepository::new(env.pool.clone()))
+        .await
+        .unwrap();
+    let job_id = Some(job.id);
+
+    assert_eq!(repo.get_reserved_for_job(job.id).await.unwrap(), 0);
+    let reserved = repo.reserve_funds(wallet.id, 400, None, job_id).await.unwrap();
+    assert_eq!(reserved.balance_cents, 600);
+    assert_eq!(repo.get_reserved_for_job(job.id).await.unwrap(), 400);
+
+    let err = repo.reserve_funds(wallet.id, 700, None, None).await.unwrap_err();
+    assert!(err.to_string().contains("Insufficient funds"));
+
+    // A reservation retried before the job's funds were released is not posted again
+    let retried = repo.reserve_funds(wallet.id, 400, None, job_id).await.unwrap();
+    assert_eq!(retried.balance_cents, 600);
+    assert_eq!(repo.get_reserved_for_job(job.id).await.unwrap(), 400);
+
+    let released = repo.release_reservation(wallet.id, 400, None, job_id).await.unwrap();
+    assert_eq!(released.balance_cents, 1000);
+    assert_eq!(repo.release_reservation(wallet.id, 400, None, job_id).await.unwrap().balance_cents, 1000);
+    assert_eq!(repo.get_reserved_for_job(job.id).await.unwrap(), 0);
+    assert!(repo.release_reservation(wallet.id, 0, None, job_id).await.is_err());
+
+    // The next attempt of the job reserves its funds again
+    assert_eq!(repo.reserve_funds(wallet.id, 400, None, job_id).await.unwrap().balance_cents, 600);
+    assert_eq!(repo.get_reserved_for_job(job.id).await.unwrap(), 400);
+}
+
+#[tokio::test]
+#[ignore = "requires Docker or TEST_DATABASE_URL"]
+async fn charges_jobs_with_balanced_ledger_entries() {
+    let env = environment().await;
+    let repo = DieselWalletRepository::new(env.pool.clone());
+    let ledger = DieselLedgerRepository::new(env.pool.clone());
+    let reseller = ResellerFactory::new()
+        .commission_rate(1000)
+        .create(&DieselResellerRepository::new(env.pool.clone()))
+        .await
+        .unwrap();
+    let customer_id = CustomerFactory::new()
+        .reseller(reseller.id)
+        .create(&DieselCustomerRepository::new(env.pool.clone()))
+        .await
+        .unwrap()
+        .id;
+    let wallet = WalletFactory::new(customer_id).balance_cents(1000).create(&repo).await.unwrap();
+
+    // 600 charged: 100 of markup and 50 of commission on 500 go to the reseller
+    let job_id = Uuid::new_v4();
+    let cost = CostBreakdown::completed(500, &PriorityLevel::Medium, 2000);
+    let wallet = repo.charge_job(JobDebit::new(wallet.id, job_id, &cost, Some(&reseller))).await.unwrap();
+    assert_eq!(wallet.balance_cents, 400);
+    let transactions = repo.get_transactions(wallet.id, 10, 0).await.unwrap();
+    assert_eq!(transactions[0].transaction_type, TransactionType::JobDebit.as_str());
+
+    let entries = ledger.find_by_job(job_id).await.unwrap();
+    assert_eq!(entries.len(), 3);
+    assert!(entries.iter().all(|entry| entry.transaction_id == transactions[0].id));
+    assert_eq!(entries.iter().map(|entry| entry.amount_cents).sum::<i64>(), 0);
+
+    let balances = ledger.balances().await.unwrap();
+    let balance = |kind: LedgerAccountKind| balances.iter()
+        .filter(|balance| balance.kind == kind.as_str())
+        .map(|balance| balance.balance_cents)
+        .sum::<i64>();
+    assert_eq!(balance(LedgerAccountKind::CustomerWallet), -600);
+    assert_eq!(balance(LedgerAccountKind::ResellerCommission), 150);
+    assert_eq!(balance(LedgerAccountKind::PlatformRevenue), 450);
+
+    // A charge retried after it went through is neither debited nor entered twice
+    let retried = repo.charge_job(JobDebit::new(wallet.id, job_id, &cost, Some(&reseller))).await.unwrap();
+    assert_eq!(retried.balance_cents, 400);
+    assert_eq!(ledger.find_by_job(job_id).await.unwrap().len(), 3);
+
+    // Debits requiring funds are refused, with no entries, when the balance falls short
+    let other_job = Uuid::new_v4();
+    let err = repo.charge_job(JobDebit::new(wallet.id, other_job, &cost, Some(&reseller)).requiring_funds()).await.unwrap_err();
+    assert!(err.to_string().contains("Insufficient funds"));
+    assert!(ledger.find_by_job(other_job).await.unwrap().is_empty());
+    // Others may take the balance below zero
+    let wallet = repo.charge_job(JobDebit::new(wallet.id, other_job, &cost, None)).await.unwrap();
+    assert_eq!(wallet.balance_cents, -200);
+    assert_eq!(ledger.balances().await.unwrap().iter().map(|balance| balance.balance_cents).sum::<i64>(), 0);
+}
+
+#[tokio::test]
+#[ignore = "requires Docker or TEST_DATABASE_URL"]
+async fn concurrent_reservations_respect_the_overdraft_policy() {
+    let env = environment().await;
+    let repo = std::sync::Arc::new(DieselWalletRepository::new(env.pool.clone()));
+    let wallet = WalletFactory::new(customer_id(&env).await).balance_cents(1000).create(repo.as_ref()).await.unwrap();
+    assert_eq!(wallet.overdraft_policy(), OverdraftPolicy::Deny);
+
+    // Eight reservations race for funds covering three of them
+    let tasks: Vec<_> = (0..8)
+        .map(|_| {
+            let repo = repo.clone();
+            tokio::spawn(async move { repo.reserve_funds(wallet.id, 300, None, None).await })
+        })
+        .collect();
+    let mut reserved = 0;
+    for task in tasks {
+        if task.await.unwrap().is_ok() {
+            reserved += 1;
+        }
+    }
+    assert_eq!(reserved, 3);
+    assert_eq!(repo.get_balance(wallet.id).await.unwrap(), 100);
+
+    // A grace limit lets reservations go below zero down to the limit, not further
+    let wallet = repo.set_overdraft_policy(wallet.id, OverdraftPolicy::Grace, 500).await.unwrap();
+    assert_eq!((wallet.balance_floor(), wallet.available_balance()), (-500, 600));
+    let wallet = repo.reserve_funds(wallet.id, 600, None, None).await.unwrap();
+    assert_eq!((wallet.balance_cents, wallet.overdraft_cents()), (-500, 500));
+    assert!(!wallet.submissions_suspended());
+    let err = repo.reserve_funds(wallet.id, 1, None, None).await.unwrap_err();
+    assert!(err.to_string().contains("Insufficient funds"));
+    // Withdrawals never draw on the overdraft
+    assert!(repo.withdraw(wallet.id, 1, None, None).await.is_err());
+
+    // Suspended wallets take no new submissions until topped up
+    let wallet = repo.set_overdraft_policy(wallet.id, OverdraftPolicy::Suspend, 500).await.unwrap();
+    assert!(wallet.submissions_suspended());
+    let wallet = repo.deposit(wallet.id, 500, None, None).await.unwrap();
+    assert!(!wallet.submissions_suspended());
+    assert!(repo.set_overdraft_policy(wallet.id, OverdraftPolicy::Grace, -1).await.is_err());
+}
+
+#[tokio::test]
+#[ignore = "requires Docker or TEST_DATABASE_URL"]
+async fn grants_and_spends_promotional_credit() {
+    let env = environment().await;
+    let repo = DieselWalletRepository::new(env.pool.clone());
+    let wallet = WalletFactory::new(customer_id(&env).await).balance_cents(1000).create(&repo).await.unwrap();
+    assert_eq!(wallet.credit_cents, 0);
+
+    let wallet = repo.grant_credit(wallet.id, 300).await.unwrap();
+    assert_eq!(wallet.credit_cents, 300);
+    assert!(repo.grant_credit(wallet.id, 0).await.is_err());
+
+    assert_eq!(repo.spend_credit(wallet.id, 200).await.unwrap(), 200);
+    // Only what is left is spent
+    assert_eq!(repo.spend_credit(wallet.id, 500).await.unwrap(), 100);
+    assert_eq!(repo.spend_credit(wallet.id, 500).await.unwrap(), 0);
+
+    let wallet = repo.find_by_id(wallet.id).await.unwrap();
+    assert_eq!(wallet.credit_cents, 0);
+    assert_eq!(wallet.balance_cents, 1000);
+}
+
+#[tokio::test]
+#[ignore = "requires Docker or TEST_DATABASE_URL"]
+async fn adds_transactions_and_adjusts_test_balance() {
+    let env = environment().await;
+    let repo = DieselWalletRepository::new(env.pool.clone());
+    let customer_id = customer_id(&env).await;
+    let wallet = WalletFactory::new(customer_id).balance_cents(1000).create(&repo).await.unwrap();
+
+    let transaction = repo.add_transaction(NewWalletTransaction {
+        id: Uuid::new_v4(),
+        wallet_id: wallet.id,
+        amount_cents: -250,
+        transaction_type: "job_charge".to_string(),
+        customer_id,
+        reference_id: None,
+        description: Some("Job charge".to_string()),
+        job_id: None,
+        created_at: None,
+        customer_reference: None,
+        customer_metadata: None,
+    }).await.unwrap();
+    assert_eq!(transaction.amount_cents, -250);
+    assert_eq!(repo.get_balance(wallet.id).await.unwrap(), 750);
+
+    let before = repo.find_by_id(wallet.id).await.unwrap().test_balance_cents;
+    let adjusted = repo.adjust_test_balance(wallet.id, -100).await.unwrap();
+    assert_eq!(adjusted.test_balance_cents, before - 100);
+    assert_eq!(adjusted.balance_cents, 750);
+    assert!(repo.adjust_test_balance(Uuid::new_v4(), 100).await.is_err());
+}
+
+#[tokio::test]
+#[ignore = "requires Docker or TEST_DATABASE_URL"]
+async fn job_transactions_carry_the_customers_reference() {
+    let env = environment().await;
+    let repo = DieselWalletRepository::new(env.pool.clone());
+    let transaction_repo = DieselWalletTransactionRepository::new(env.pool.clone());
+    let customer_id = customer_id(&env).await;
+    let wallet = WalletFactory::new(customer_id).balance_cents(1000).create(&repo).await.unwrap();
+    let job_type = JobTypeFactory::new()
+        .create(&DieselJobTypeRepository::new(env.pool.clone()))
+        .await
+        .unwrap();
+    let reference = format!("PO-{}", Uuid::new_v4());
+    let job = JobFactory::new(customer_id, job_type.id)
+        .customer_reference(reference.clone())
+        .create(&DieselJobRepository::new(env.pool.clone()))
+        .await
+        .unwrap();
+
+    repo.reserve_funds(wallet.id, 300, None, Some(job.id)).await.unwrap();
+    repo.release_reservation(wallet.id, 300, None, Some(job.id)).await.unwrap();
+    repo.withdraw(wallet.id, 250, None, Some(job.id)).await.unwrap();
+    // A reference given with the transaction wins over the job's
+    repo.add_transaction(NewWalletTransaction {
+        id: Uuid::new_v4(),
+        wallet_id: wallet.id,
+        amount_cents: 100,
+        transaction_type: TransactionType::Deposit.to_string(),
+        customer_id,
+        reference_id: None,
+        description: None,
+        job_id: None,
+        created_at: None,
+        customer_reference: Some(format!("{}-topup", reference)),
+        customer_metadata: Some(json!({ "cost_center": "42" })),
+    }).await.unwrap();
+    repo.deposit(wallet.id, 50, None, None).await.unwrap();
+
+    let transactions = transaction_repo.find_by_customer_reference(customer_id, &reference).await.unwrap();
+    assert_eq!(transactions.len(), 3);
+    assert!(transactions.iter().all(|t| t.job_id == Some(job.id)));
+
+    let topups = transaction_repo.find_by_customer_reference(customer_id, &format!("{}-topup", reference)).await.unwrap();
+    assert_eq!(topups.len(), 1);
+    assert_eq!(topups[0].customer_metadata, Some(json!({ "cost_center": "42" })));
+
+    assert!(transaction_repo.find_by_customer_reference(Uuid::new_v4(), &reference).await.unwrap().is_empty());
+}
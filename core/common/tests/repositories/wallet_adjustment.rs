@@ -0,0 +1,117 @@
+use innosystem_common::models::audit::AuditEntry;
+use innosystem_common::models::wallet::TransactionType;
+use innosystem_common::models::wallet_adjustment::{AdjustmentError, AdjustmentKind, AdjustmentStatus, NewWalletAdjustment};
+use innosystem_common::repositories::{
+    AuditLogRepository, DieselAuditLogRepository, DieselCustomerRepository, DieselWalletAdjustmentRepository,
+    DieselWalletRepository, WalletAdjustmentRepository, WalletRepository,
+};
+use innosystem_common::testing::TestEnvironment;
+use innosystem_common::testing::factories::{CustomerFactory, WalletFactory};
+use uuid::Uuid;
+
+use crate::environment;
+
+/// Insert a customer with a wallet holding the given balance
+async fn wallet(env: &TestEnvironment, balance_cents: i32) -> (Uuid, Uuid) {
+    let customer = CustomerFactory::new()
+        .create(&DieselCustomerRepository::new(env.pool.clone()))
+        .await
+        .unwrap();
+    let wallet = WalletFactory::new(customer.id)
+        .balance_cents(balance_cents)
+        .create(&DieselWalletRepository::new(env.pool.clone()))
+        .await
+        .unwrap();
+    (customer.id, wallet.id)
+}
+
+/// A pending adjustment needing a second admin, requested by `alice`
+fn adjustment(customer_id: Uuid, wallet_id: Uuid, kind: AdjustmentKind, amount_cents: i32) -> NewWalletAdjustment {
+    NewWalletAdjustment {
+        id: Uuid::new_v4(),
+        wallet_id,
+        customer_id,
+        kind: kind.as_str().to_string(),
+        amount_cents,
+        description: Some("Goodwill credit".to_string()),
+        job_id: None,
+        requires_approval: true,
+        requested_by: "alice".to_string(),
+    }
+}
+
+fn actions(entries: &[AuditEntry]) -> Vec<(&str, &str)> {
+    entries.iter().map(|entry| (entry.actor.as_str(), entry.action.as_str())).collect()
+}
+
+#[tokio::test]
+#[ignore = "requires Docker or TEST_DATABASE_URL"]
+async fn approved_adjustments_post_once_reviewed_by_another_admin() {
+    let env = environment().await;
+    let repo = DieselWalletAdjustmentRepository::new(env.pool.clone());
+    let wallets = DieselWalletRepository::new(env.pool.clone());
+    let audit = DieselAuditLogRepository::new(env.pool.clone());
+    let (customer_id, wallet_id) = wallet(&env, 1000).await;
+
+    let pending = repo.create(adjustment(customer_id, wallet_id, AdjustmentKind::Refund, 5000)).await.unwrap();
+    assert_eq!(pending.status(), Some(AdjustmentStatus::Pending));
+    assert_eq!(wallets.get_balance(wallet_id).await.unwrap(), 1000);
+    assert_eq!(repo.find_by_status(AdjustmentStatus::Pending).await.unwrap().iter().filter(|a| a.id == pending.id).count(), 1);
+
+    // The requester cannot approve their own adjustment
+    let err = repo.approve(pending.id, "alice".to_string(), None).await.unwrap_err();
+    assert_eq!(err.downcast_ref::<AdjustmentError>(), Some(&AdjustmentError::SameReviewer));
+    assert_eq!(wallets.get_balance(wallet_id).await.unwrap(), 1000);
+
+    let approved = repo.approve(pending.id, "bob".to_string(), Some("Checked the invoice".to_string())).await.unwrap();
+    assert_eq!(approved.status(), Some(AdjustmentStatus::Approved));
+    assert_eq!(approved.reviewed_by.as_deref(), Some("bob"));
+    assert_eq!(wallets.get_balance(wallet_id).await.unwrap(), 6000);
+
+    let transactions = wallets.get_transactions(wallet_id, 10, 0).await.unwrap();
+    let posted = transactions.iter().find(|t| Some(t.id) == approved.transaction_id).unwrap();
+    assert_eq!(posted.amount_cents, 5000);
+    assert_eq!(posted.transaction_type, TransactionType::RefundCredit.as_str());
+    assert_eq!(posted.reference_id, Some(approved.id));
+
+    // Reviewed adjustments cannot be reviewed again
+    let err = repo.reject(pending.id, "carol".to_string(), None).await.unwrap_err();
+    assert!(matches!(err.downcast_ref::<AdjustmentError>(), Some(AdjustmentError::NotPending(_))));
+
+    let trail = audit.find_by_entity("wallet_adjustment".to_string(), approved.id).await.unwrap();
+    assert_eq!(actions(&trail), vec![("alice", "wallet_adjustment.requested"), ("bob", "wallet_adjustment.approved")]);
+    assert_eq!(trail[1].details["transaction_id"], approved.transaction_id.unwrap().to_string());
+    assert_eq!(trail[1].details["note"], "Checked the invoice");
+}
+
+#[tokio::test]
+#[ignore = "requires Docker or TEST_DATABASE_URL"]
+async fn rejected_and_uncovered_adjustments_leave_the_wallet_unchanged() {
+    let env = environment().await;
+    let repo = DieselWalletAdjustmentRepository::new(env.pool.clone());
+    let wallets = DieselWalletRepository::new(env.pool.clone());
+    let audit = DieselAuditLogRepository::new(env.pool.clone());
+    let (customer_id, wallet_id) = wallet(&env, 1000).await;
+
+    let zero = repo.create(adjustment(customer_id, wallet_id, AdjustmentKind::Adjustment, 0)).await.unwrap_err();
+    assert_eq!(zero.downcast_ref::<AdjustmentError>(), Some(&AdjustmentError::ZeroAmount));
+    let negative_refund = repo.create(adjustment(customer_id, wallet_id, AdjustmentKind::Refund, -100)).await.unwrap_err();
+    assert_eq!(negative_refund.downcast_ref::<AdjustmentError>(), Some(&AdjustmentError::NegativeRefund));
+
+    let rejected = repo.create(adjustment(customer_id, wallet_id, AdjustmentKind::Adjustment, -500)).await.unwrap();
+    let rejected = repo.reject(rejected.id, "bob".to_string(), Some("Not justified".to_string())).await.unwrap();
+    assert_eq!(rejected.status(), Some(AdjustmentStatus::Rejected));
+    assert_eq!(rejected.transaction_id, None);
+    let trail = audit.find_by_entity("wallet_adjustment".to_string(), rejected.id).await.unwrap();
+    assert_eq!(actions(&trail), vec![("alice", "wallet_adjustment.requested"), ("bob", "wallet_adjustment.rejected")]);
+
+    // Debits beyond the balance stay pending
+    let uncovered = repo.create(adjustment(customer_id, wallet_id, AdjustmentKind::Adjustment, -1500)).await.unwrap();
+    let err = repo.approve(uncovered.id, "bob".to_string(), None).await.unwrap_err();
+    assert!(matches!(err.downcast_ref::<AdjustmentError>(), Some(AdjustmentError::InsufficientFunds { .. })));
+    assert_eq!(repo.find_by_id(uncovered.id).await.unwrap().status(), Some(AdjustmentStatus::Pending));
+
+    assert_eq!(wallets.get_balance(wallet_id).await.unwrap(), 1000);
+    assert!(wallets.get_transactions(wallet_id, 10, 0).await.unwrap().is_empty());
+    assert_eq!(repo.find_by_wallet_id(wallet_id).await.unwrap().len(), 2);
+}
@@ -0,0 +1,81 @@
+use chrono::{Duration, Utc};
+use innosystem_common::models::wallet::{NewWalletTransaction, TransactionType};
+use innosystem_common::repositories::{
+    DieselCustomerRepository, DieselJobRepository, DieselJobTypeRepository, DieselWalletRepository,
+    DieselWalletTransactionRepository, WalletTransactionRepository,
+};
+use innosystem_common::testing::factories::{CustomerFactory, JobFactory, JobTypeFactory, WalletFactory};
+use uuid::Uuid;
+
+use crate::environment;
+
+#[tokio::test]
+#[ignore = "requires Docker or TEST_DATABASE_URL"]
+async fn creates_and_finds_wallet_transactions() {
+    let env = environment().await;
+    let repo = DieselWalletTransactionRepository::new(env.pool.clone());
+    let customer = CustomerFactory::new()
+        .create(&DieselCustomerRepository::new(env.pool.clone()))
+        .await
+        .unwrap();
+    let wallet = WalletFactory::new(customer.id)
+        .create(&DieselWalletRepository::new(env.pool.clone()))
+        .await
+        .unwrap();
+    let job_type = JobTypeFactory::new()
+        .create(&DieselJobTypeRepository::new(env.pool.clone()))
+        .await
+        .unwrap();
+    let job_id = JobFactory::new(customer.id, job_type.id)
+        .create(&DieselJobRepository::new(env.pool.clone()))
+        .await
+        .unwrap()
+        .id;
+
+    let deposit = repo.create(NewWalletTransaction {
+        id: Uuid::new_v4(),
+        wallet_id: wallet.id,
+        amount_cents: 1000,
+        transaction_type: TransactionType::Deposit.to_string(),
+        customer_id: customer.id,
+        reference_id: None,
+        description: None,
+        job_id: None,
+        created_at: None,
+        customer_reference: None,
+        customer_metadata: None,
+    }).await.unwrap();
+    let debit = repo.create(NewWalletTransaction {
+        id: Uuid::new_v4(),
+        wallet_id: wallet.id,
+        amount_cents: -150,
+        transaction_type: TransactionType::JobDebit.to_string(),
+        customer_id: customer.id,
+        reference_id: Some(job_id),
+        description: Some("Job charge".to_string()),
+        job_id: Some(job_id),
+        created_at: None,
+        customer_reference: None,
+        customer_metadata: None,
+    }).await.unwrap();
+
+    assert_eq!(repo.find_by_id(deposit.id).await.unwrap().amount_cents, 1000);
+    assert!(repo.find_by_id(Uuid::new_v4()).await.is_err());
+
+    assert_eq!(repo.find_by_wallet_id(wallet.id).await.unwrap().len(), 2);
+    assert_eq!(repo.find_by_customer_id(customer.id).await.unwrap().len(), 2);
+
+    let by_job = repo.find_by_job_id(Some(job_id)).await.unwrap();
+    assert_eq!(by_job.len(), 1);
+    assert_eq!(by_job[0].id, debit.id);
+
+    let debits = repo.find_by_transaction_type(TransactionType::JobDebit).await.unwrap();
+    assert!(debits.iter().any(|t| t.id == debit.id));
+    assert!(!debits.iter().any(|t| t.id == deposit.id));
+
+    let now = Utc::now();
+    let recent = repo.find_in_time_range(now - Duration::hours(1), now + Duration::hours(1)).await.unwrap();
+    assert!(recent.iter().any(|t| t.id == deposit.id));
+    let old = repo.find_in_time_range(now - Duration::days(2), now - Duration::days(1)).await.unwrap();
+    assert!(!old.iter().any(|t| t.id == deposit.id));
+}
@@ -0,0 +1,89 @@
+use chrono::{Duration, Utc};
+use innosystem_common::models::webhook::{CustomerWebhook, NewCustomerWebhook, WebhookEventType};
+use innosystem_common::repositories::{CustomerWebhookRepository, DieselCustomerRepository, DieselCustomerWebhookRepository};
+use innosystem_common::testing::factories::CustomerFactory;
+use uuid::Uuid;
+
+use crate::environment;
+
+/// Insertable webhook subscribed to the given events
+fn new_webhook(customer_id: Uuid, event_types: &[WebhookEventType]) -> NewCustomerWebhook {
+    NewCustomerWebhook {
+        id: Uuid::new_v4(),
+        customer_id,
+        url: "https://example.test/hooks".to_string(),
+        secret: CustomerWebhook::generate_secret(),
+        event_types: event_types.iter().map(|e| e.as_str().to_string()).collect(),
+        active: true,
+    }
+}
+
+#[tokio::test]
+#[ignore = "requires Docker or TEST_DATABASE_URL"]
+async fn manages_customer_webhooks() {
+    let env = environment().await;
+    let repo = DieselCustomerWebhookRepository::new(env.pool.clone());
+    let customer = CustomerFactory::new()
+        .create(&DieselCustomerRepository::new(env.pool.clone()))
+        .await
+        .unwrap();
+
+    let succeeded = repo.create(new_webhook(customer.id, &[WebhookEventType::JobSucceeded])).await.unwrap();
+    let failed = repo.create(new_webhook(customer.id, &[WebhookEventType::JobFailed])).await.unwrap();
+
+    assert_eq!(repo.find_by_id(succeeded.id).await.unwrap().url, succeeded.url);
+    assert!(repo.find_by_id(Uuid::new_v4()).await.is_err());
+    assert_eq!(repo.find_by_customer_id(customer.id).await.unwrap().len(), 2);
+
+    let active = repo.find_active_for_event(customer.id, WebhookEventType::JobFailed).await.unwrap();
+    assert_eq!(active.len(), 1);
+    assert_eq!(active[0].id, failed.id);
+
+    let mut disabled = failed.clone();
+    disabled.active = false;
+    repo.update(&disabled).await.unwrap();
+    assert!(repo.find_active_for_event(customer.id, WebhookEventType::JobFailed).await.unwrap().is_empty());
+
+    let tested = repo.record_test_result(succeeded.id, Some(200), Some("ok".to_string())).await.unwrap();
+    assert_eq!(tested.last_test_status_code, Some(200));
+    assert!(tested.last_tested_at.is_some());
+
+    let failing = repo.set_failing_since(succeeded.id, Some(Utc::now())).await.unwrap();
+    assert!(failing.failing_since.is_some());
+    let recovered = repo.set_failing_since(succeeded.id, None).await.unwrap();
+    assert!(recovered.failing_since.is_none());
+
+    repo.delete(failed.id).await.unwrap();
+    assert_eq!(repo.find_by_customer_id(customer.id).await.unwrap().len(), 1);
+}
+
+#[tokio::test]
+#[ignore = "requires Docker or TEST_DATABASE_URL"]
+async fn tracks_failure_runs_of_endpoints_in_the_database() {
+    let env = environment().await;
+    let repo = DieselCustomerWebhookRepository::new(env.pool.clone());
+    let customer = CustomerFactory::new()
+        .create(&DieselCustomerRepository::new(env.pool.clone()))
+        .await
+        .unwrap();
+    let webhook = repo.create(new_webhook(customer.id, &[WebhookEventType::JobFailed])).await.unwrap();
+    let started = Utc::now() - Duration::hours(30);
+
+    // Later failures keep the start of the run, and the endpoint stays active within the limit
+    let cutoff = started - Duration::hours(1);
+    assert!(repo.record_delivery_failure(webhook.id, started, cutoff).await.unwrap().is_none());
+    assert!(repo.record_delivery_failure(webhook.id, Utc::now(), cutoff).await.unwrap().is_none());
+    let failing = repo.find_by_id(webhook.id).await.unwrap();
+    assert_eq!(failing.failing_since.map(|since| since.timestamp()), Some(started.timestamp()));
+    assert!(failing.active);
+
+    // A success ends the run, so the next failure starts a new one
+    repo.record_delivery_success(webhook.id).await.unwrap();
+    assert!(repo.find_by_id(webhook.id).await.unwrap().failing_since.is_none());
+    repo.record_delivery_failure(webhook.id, started, cutoff).await.unwrap();
+
+    // Only the failure that crosses the limit reports disabling the endpoint
+    let disabled = repo.record_delivery_failure(webhook.id, Utc::now(), Utc::now() - Duration::hours(24)).await.unwrap();
+    assert!(!disabled.unwrap().active);
+    assert!(repo.record_delivery_failure(webhook.id, Utc::now(), Utc::now()).await.unwrap().is_none());
+}
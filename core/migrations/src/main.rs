@@ -1,8 +1,12 @@
 use clap::{Parser, Subcommand};
 use dotenvy::dotenv;
-use innosystem_common::{migrations, seed::{Seeder}, database};
-use innosystem_common::repositories::diesel::{DieselJobTypeRepository, DieselJobRepository, DieselCustomerRepository, DieselWalletRepository};
-use innosystem_common::repositories::{job_type::JobTypeRepository, customer::CustomerRepository, job::JobRepository, wallet::WalletRepository};
+use innosystem_common::{migrations, schema_drift, seed::{Seeder}, database};
+use innosystem_common::database::{SchemaResolver, TenancyConfig, TenantPool};
+use innosystem_common::models::partition::{months_to_create, PartitionedTable};
+use innosystem_common::models::purge::job_retention_cutoff;
+use innosystem_common::purge::Purger;
+use innosystem_common::repositories::diesel::{DieselJobTypeRepository, DieselJobRepository, DieselCustomerRepository, DieselWalletRepository, DieselPartitionRepository, DieselPurgeRepository};
+use innosystem_common::repositories::{job_type::JobTypeRepository, customer::CustomerRepository, job::JobRepository, wallet::WalletRepository, partition::PartitionRepository};
 use std::env;
 use std::error::Error;
 use std::sync::Arc;
@@ -29,9 +33,41 @@ enum Commands {
     #[clap(name = "rerun-latest")]
     RerunLatest,
 
+    /// Compare the database schema with the Diesel schema the services are built with,
+    /// listing every difference and failing when there is any
+    #[clap(name = "check-schema")]
+    CheckSchema,
+
     /// Seed the database with development data
     #[clap(name = "seed")]
     Seed,
+
+    /// Move the rows of the default partitions into monthly partitions, a month at a
+    /// time, and create the partitions of the coming months
+    #[clap(name = "partitions")]
+    Partitions {
+        /// Months after the current one to create partitions for
+        #[clap(long, default_value = "3")]
+        premake_months: u32,
+    },
+
+    /// Permanently delete the data past its retention from every schema, in batches,
+    /// and verify nothing past retention is left
+    #[clap(name = "purge")]
+    Purge {
+        /// Full months finished jobs are kept after the month they were created in;
+        /// defaults to `PARTITION_JOB_RETENTION_MONTHS`, jobs are kept when neither is set
+        #[clap(long)]
+        job_retention_months: Option<u32>,
+
+        /// Rows deleted per transaction
+        #[clap(long, default_value = "1000")]
+        batch_size: i64,
+
+        /// Only count what would be deleted
+        #[clap(long)]
+        dry_run: bool,
+    },
 }
 
 #[tokio::main]
@@ -61,6 +97,17 @@ async fn main() -> Result<(), Box<dyn Error>> {
             println!("Rerun latest migration feature not yet implemented.");
             println!("This will be added in a future update.");
         },
+        Commands::CheckSchema => {
+            println!("Checking the database schema against diesel_schema...");
+            let pool = database::init_pool()?;
+            let mut conn = pool.get()?;
+            let drift = schema_drift::check(&mut conn)?;
+            if !drift.is_empty() {
+                println!("{}", schema_drift::format_drift(&drift));
+                return Err(format!("The database schema drifted from diesel_schema ({} differences)", drift.len()).into());
+            }
+            println!("The database schema matches diesel_schema.");
+        },
         Commands::Seed => {
             println!("Seeding database with development data...");
             
@@ -97,6 +144,55 @@ async fn main() -> Result<(), Box<dyn Error>> {
             
             println!("Seed data successfully inserted into database.");
         },
+        Commands::Partitions { premake_months } => {
+            let pool = database::init_pool()?;
+            let partition_repo = DieselPartitionRepository::new(pool);
+            let now = chrono::Utc::now();
+            
+            for table in PartitionedTable::ALL {
+                let mut months = partition_repo.unpartitioned_months(table).await?;
+                months.extend(months_to_create(now, premake_months));
+                months.sort();
+                months.dedup();
+                
+                println!("Partitioning {} ({} months)...", table.as_str(), months.len());
+                for month in months {
+                    let moved = partition_repo.create_month(table, month).await?;
+                    println!("  {}: {} rows moved", table.partition_name(month), moved);
+                }
+            }
+            println!("Partitioning completed successfully.");
+        },
+        Commands::Purge { job_retention_months, batch_size, dry_run } => {
+            let resolver = Arc::new(SchemaResolver::new(database::init_pool()?, database_url, TenancyConfig::from_env()));
+            let scopes = resolver.scopes()?;
+            let purger = Purger::new(Arc::new(DieselPurgeRepository::new(TenantPool::new(resolver))), batch_size);
+            let job_retention_months = job_retention_months
+                .or_else(|| env::var("PARTITION_JOB_RETENTION_MONTHS").ok().and_then(|months| months.parse().ok()));
+            let jobs_created_before = job_retention_months.map(|months| job_retention_cutoff(chrono::Utc::now(), months));
+            
+            match jobs_created_before {
+                Some(cutoff) => println!("Purging finished jobs created before {} and expired unredacted outputs from {} schemas...", cutoff, scopes.len()),
+                None => println!("Purging expired unredacted outputs from {} schemas (no job retention set)...", scopes.len()),
+            }
+            let report = purger.run(&scopes, jobs_created_before, dry_run, |progress| {
+                let schema = progress.reseller_id.map(|id| id.to_string()).unwrap_or_else(|| "shared".to_string());
+                let rows: Vec<String> = progress.deleted.iter().map(|(table, rows)| format!("{} {}", rows, table)).collect();
+                println!("  batch {} ({} schema): {}", progress.batch, schema, rows.join(", "));
+            }).await?;
+            
+            println!("Purge {} {}:", report.id, if dry_run { "dry run" } else { "report" });
+            for (table, rows) in &report.deleted {
+                println!("  {}: {} rows deleted", table, rows);
+            }
+            for (table, rows) in &report.remaining {
+                println!("  {}: {} rows past retention remaining", table, rows);
+            }
+            if !report.verified {
+                return Err(if dry_run { "Data past retention found".into() } else { "Data past retention remains after the purge".into() });
+            }
+            println!("Purge verified: no data past retention remains.");
+        },
     }
     
     Ok(())
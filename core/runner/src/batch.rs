@@ -0,0 +1,220 @@
+use std::time::{Duration, Instant};
+
+use innosystem_common::config::parse_env;
+use uuid::Uuid;
+
+use crate::cache::PendingCompletion;
+
+/// Configuration of the batched storage of job results
+#[derive(Debug, Clone, PartialEq)]
+pub struct CompletionBatchConfig {
+    /// Most job results stored in one transaction (1 stores each result as its job finishes)
+    pub max_batch_size: usize,
+    /// Milliseconds the oldest result may wait for the batch to fill before it is stored
+    pub flush_interval_ms: u64,
+    /// Interval between two reports of the batch sizes
+    pub report_interval_secs: u64,
+}
+
+impl Default for CompletionBatchConfig {
+    fn default() -> Self {
+        Self {
+            max_batch_size: 1,
+            flush_interval_ms: 200,
+            report_interval_secs: 300,
+        }
+    }
+}
+
+impl CompletionBatchConfig {
+    /// Load the configuration from `COMPLETION_BATCH_*` environment variables, using defaults for unset values
+    pub fn from_env() -> Self {
+        let defaults = Self::default();
+        Self {
+            max_batch_size: parse_env("COMPLETION_BATCH_SIZE").filter(|size| *size > 0).unwrap_or(defaults.max_batch_size),
+            flush_interval_ms: parse_env("COMPLETION_BATCH_FLUSH_INTERVAL_MS").unwrap_or(defaults.flush_interval_ms),
+            report_interval_secs: parse_env("COMPLETION_BATCH_REPORT_INTERVAL_SECS").unwrap_or(defaults.report_interval_secs),
+        }
+    }
+
+    /// Whether results are batched at all
+    pub fn enabled(&self) -> bool {
+        self.max_batch_size > 1
+    }
+}
+
+/// Result of a finished job waiting in the batch
+#[derive(Debug, Clone)]
+pub struct BatchedCompletion {
+    pub completion: PendingCompletion,
+    /// Parent of the job if it is a sub-task, fanned in once the result is stored
+    pub parent_id: Option<Uuid>,
+}
+
+/// Batches stored since the last report
+#[derive(Debug, Default)]
+struct Measurements {
+    batches: u32,
+    completions: u32,
+    largest: usize,
+}
+
+/// Results of finished jobs held back to be stored together, saving a transaction per
+/// job on high-throughput short jobs
+///
+/// A batch is due once it is full or its oldest result waited for the flush interval.
+/// Jobs stay running until their batch is stored, so the interval bounds how late
+/// customers see them finish. Meanwhile each result is staged in the completion buffer
+/// on disk, so results of a runner that goes down before storing its batch are not lost,
+/// short of those staged within the buffer's flush interval (see `CompletionBuffer::stage`).
+pub struct CompletionBatch {
+    config: CompletionBatchConfig,
+    completions: Vec<BatchedCompletion>,
+    /// When the oldest result in the batch was added
+    oldest: Option<Instant>,
+    measurements: Measurements,
+    last_report: Instant,
+}
+
+impl CompletionBatch {
+    /// Create an empty batch
+    pub fn new(config: CompletionBatchConfig) -> Self {
+        Self {
+            config,
+            completions: Vec::new(),
+            oldest: None,
+            measurements: Measurements::default(),
+            last_report: Instant::now(),
+        }
+    }
+
+    /// Apply a reloaded configuration; results already in the batch stay in it
+    pub fn reconfigure(&mut self, config: CompletionBatchConfig) {
+        self.config = config;
+    }
+
+    /// Whether results are batched at all
+    pub fn enabled(&self) -> bool {
+        self.config.enabled()
+    }
+
+    /// Add the result of a finished job
+    pub fn push(&mut self, completion: BatchedCompletion) {
+        self.oldest.get_or_insert_with(Instant::now);
+        self.completions.push(completion);
+    }
+
+    /// Whether the batch is full or its oldest result waited long enough
+    pub fn is_due(&self) -> bool {
+        self.completions.len() >= self.config.max_batch_size
+            || self.oldest.is_some_and(|oldest| oldest.elapsed() >= Duration::from_millis(self.config.flush_interval_ms))
+    }
+
+    /// Take the results to store, recording the size of the batch
+    pub fn take(&mut self) -> Vec<BatchedCompletion> {
+        let completions = std::mem::take(&mut self.completions);
+        self.oldest = None;
+        if !completions.is_empty() {
+            self.measurements.batches += 1;
+            self.measurements.completions += completions.len() as u32;
+            self.measurements.largest = self.measurements.largest.max(completions.len());
+        }
+        completions
+    }
+
+    /// Log the sizes of the batches stored once per report interval
+    pub fn report_if_due(&mut self) {
+        if self.config.report_interval_secs == 0
+            || self.last_report.elapsed() < Duration::from_secs(self.config.report_interval_secs)
+        {
+            return;
+        }
+        let measurements = std::mem::take(&mut self.measurements);
+        self.last_report = Instant::now();
+        if measurements.batches == 0 {
+            return;
+        }
+
+        tracing::info!(
+            "Stored {} job results in {} batches: {:.1} results per batch on average, {} at most (limit {})",
+            measurements.completions,
+            measurements.batches,
+            measurements.completions as f64 / measurements.batches as f64,
+            measurements.largest,
+            self.config.max_batch_size,
+        );
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use chrono::Utc;
+
+    use super::*;
+
+    fn batched() -> BatchedCompletion {
+        let completion = PendingCompletion {
+            job_id: Uuid::new_v4(),
+            success: true,
+            output: None,
+            error: None,
+            cost_cents: 0,
+            completed_at: Utc::now(),
+            reseller_id: None,
+            attempt_id: None,
+            runner_id: None,
+            usage: None,
+        };
+        BatchedCompletion { completion, parent_id: None }
+    }
+
+    fn config(max_batch_size: usize, flush_interval_ms: u64) -> CompletionBatchConfig {
+        CompletionBatchConfig { max_batch_size, flush_interval_ms, ..CompletionBatchConfig::default() }
+    }
+
+    #[test]
+    fn batching_is_off_for_batches_of_one() {
+        assert!(!CompletionBatchConfig::default().enabled());
+        assert!(config(2, 200).enabled());
+    }
+
+    #[test]
+    fn full_batches_are_due() {
+        let mut batch = CompletionBatch::new(config(2, 60_000));
+        assert!(!batch.is_due());
+        batch.push(batched());
+        assert!(!batch.is_due());
+        batch.push(batched());
+        assert!(batch.is_due());
+
+        assert_eq!(batch.take().len(), 2);
+        assert!(!batch.is_due());
+        assert!(batch.take().is_empty());
+        assert_eq!(batch.measurements.batches, 1);
+        assert_eq!(batch.measurements.completions, 2);
+    }
+
+    #[test]
+    fn batches_are_due_once_their_oldest_result_waited_the_flush_interval() {
+        let mut batch = CompletionBatch::new(config(100, 200));
+        batch.push(batched());
+        assert!(!batch.is_due());
+        std::thread::sleep(Duration::from_millis(250));
+        assert!(batch.is_due());
+
+        // The interval starts again with the next batch's oldest result
+        batch.take();
+        batch.push(batched());
+        assert!(!batch.is_due());
+    }
+
+    #[test]
+    fn reconfiguring_keeps_the_waiting_results() {
+        let mut batch = CompletionBatch::new(config(10, 60_000));
+        batch.push(batched());
+        batch.push(batched());
+        batch.reconfigure(config(2, 60_000));
+        assert!(batch.is_due());
+        assert_eq!(batch.take().len(), 2);
+    }
+}
@@ -0,0 +1,368 @@
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use uuid::Uuid;
+
+use innosystem_common::{
+    database::with_reseller,
+    errors::Error,
+    models::{
+        job::{CompletionResolution, HeldCompletion, Job, JobHolder},
+        job_attempt::AttemptOutcome,
+        job_error::JobError,
+        job_log::LogLevel,
+        job_usage::NewJobUsage,
+    },
+    repositories::{JobAttemptRepository, JobLogRepository, JobRepository, JobUsageRepository},
+};
+
+/// Completion result of a job that could not be written to the database yet
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct PendingCompletion {
+    pub job_id: Uuid,
+    pub success: bool,
+    pub output: Option<serde_json::Value>,
+    pub error: Option<JobError>,
+    pub cost_cents: i32,
+    /// When the runner finished the job, recorded as the end of its attempt
+    pub completed_at: DateTime<Utc>,
+    /// Reseller whose schema holds the job, if it has one
+    #[serde(default)]
+    pub reseller_id: Option<Uuid>,
+    /// Attempt of the job this completion ends, if it could be recorded
+    #[serde(default)]
+    pub attempt_id: Option<Uuid>,
+    /// Runner that ran the job, which must still hold it for the result to be stored
+    #[serde(default)]
+    pub runner_id: Option<Uuid>,
+    /// Resources the job used on this runner
+    #[serde(default)]
+    pub usage: Option<NewJobUsage>,
+}
+
+impl PendingCompletion {
+    /// Runner and attempt the result is reported for
+    pub fn holder(&self) -> JobHolder {
+        JobHolder { runner_id: self.runner_id, attempt_id: self.attempt_id }
+    }
+
+    /// The result as stored along with others by `complete_held_many`
+    pub fn held(&self) -> HeldCompletion {
+        HeldCompletion {
+            job_id: self.job_id,
+            holder: self.holder(),
+            success: self.success,
+            output: self.output.clone(),
+            error: self.error.clone(),
+            cost_cents: self.cost_cents,
+        }
+    }
+
+    /// Store the result, unless the job was handed to another runner in the meantime
+    pub async fn store<R: JobRepository + ?Sized>(&self, job_repo: &R) -> innosystem_common::Result<(CompletionResolution, Job)> {
+        let stored = job_repo.complete_held(
+            self.job_id,
+            self.holder(),
+            self.success,
+            self.output.clone(),
+            self.error.clone(),
+            self.cost_cents,
+        );
+        with_reseller(self.reseller_id, stored).await
+    }
+
+    /// Note in the job's log how a late result was resolved against the job being handed
+    /// back; results of jobs the runner still held need no note
+    pub async fn record_resolution<L: JobLogRepository + ?Sized>(&self, log_repo: &L, resolution: CompletionResolution, job: &Job) {
+        let runner = self.runner_id.map_or_else(|| "unknown".to_string(), |runner_id| runner_id.to_string());
+        let message = match resolution {
+            CompletionResolution::Held => return,
+            CompletionResolution::Reclaimed => {
+                tracing::info!("Stored the late result of job {} handed back while its runner was unresponsive", self.job_id);
+                format!("Stored the late result of runner {}: the job was handed back but had not been started again", runner)
+            }
+            CompletionResolution::Discarded => {
+                tracing::warn!("Discarded the late result of job {}, which is {} on another runner or finished", self.job_id, job.status.as_str());
+                format!("Discarded the late result of runner {}: the job was handed back and is {} since", runner, job.status.as_str())
+            }
+        };
+        // The cost of a discarded result was charged by the run that produced it
+        let fields = serde_json::json!({
+            "event": format!("late_result_{}", resolution.as_str()),
+            "runner_id": self.runner_id,
+            "attempt_id": self.attempt_id,
+            "success": self.success,
+            "cost_cents": self.cost_cents,
+            "job_status": job.status.as_str(),
+            "job_runner_id": job.runner_id,
+        });
+        let recorded = log_repo.append_event(self.job_id, LogLevel::Warn, message, Some(fields));
+        if let Err(e) = with_reseller(self.reseller_id, recorded).await {
+            tracing::warn!("Failed to record the resolution of the late result of job {}: {}", self.job_id, e);
+        }
+    }
+
+    /// Close the job's attempt with this result; the attempt history is best effort, so
+    /// failures are only logged
+    pub async fn finish_attempt<A: JobAttemptRepository + ?Sized>(&self, attempt_repo: &A) {
+        let Some(attempt_id) = self.attempt_id else {
+            return;
+        };
+        let outcome = AttemptOutcome {
+            success: self.success,
+            error: self.error.clone(),
+            cost_cents: self.cost_cents,
+            finished_at: self.completed_at,
+        };
+        if let Err(e) = with_reseller(self.reseller_id, attempt_repo.finish(attempt_id, outcome)).await {
+            tracing::warn!("Failed to record the outcome of attempt {} of job {}: {}", attempt_id, self.job_id, e);
+        }
+    }
+
+    /// Store the resources the job used; like the attempt history this is best effort
+    pub async fn record_usage<U: JobUsageRepository + ?Sized>(&self, usage_repo: &U) {
+        let Some(usage) = self.usage.clone() else {
+            return;
+        };
+        if let Err(e) = with_reseller(self.reseller_id, usage_repo.record(usage)).await {
+            tracing::warn!("Failed to record the resource usage of job {}: {}", self.job_id, e);
+        }
+    }
+}
+
+/// Local on-disk buffer for job completions made while the database is unreachable
+///
+/// Entries are keyed by job ID, so buffering the same job twice keeps only the
+/// latest result. The buffer survives runner restarts.
+pub struct CompletionBuffer {
+    tree: sled::Db,
+    /// Results waiting in the runner's batch, kept until the batch is stored
+    staged: sled::Tree,
+}
+
+impl CompletionBuffer {
+    /// Open (or create) the buffer at the given path
+    ///
+    /// Results a previous run staged but never stored are moved into the buffer, to be
+    /// flushed like any other buffered completion.
+    pub fn open(path: &str) -> anyhow::Result<Self> {
+        let tree = sled::open(path)?;
+        let staged = tree.open_tree("staged_completions")?;
+        let buffer = Self { tree, staged };
+        for entry in buffer.staged.iter() {
+            let (key, value) = entry?;
+            buffer.tree.insert(key.clone(), value)?;
+            buffer.staged.remove(key)?;
+        }
+        buffer.tree.flush()?;
+        Ok(buffer)
+    }
+
+    /// Open (or create) another tree of records kept next to the buffer
+    pub fn open_tree(&self, name: &str) -> anyhow::Result<sled::Tree> {
+        Ok(self.tree.open_tree(name)?)
+    }
+
+    /// Store a completion locally, flushing it to disk before returning
+    pub fn push(&self, completion: &PendingCompletion) -> anyhow::Result<()> {
+        let value = serde_json::to_vec(completion)?;
+        self.tree.insert(completion.job_id.as_bytes(), value)?;
+        self.tree.flush()?;
+        Ok(())
+    }
+
+    /// Keep the result of a job on disk while it waits in the runner's batch
+    ///
+    /// Staged results are not flushed one by one, which would cost a sync per job: sled
+    /// writes them to disk within its flush interval (500 ms by default), and `unstage`
+    /// flushes once per batch. Results staged within that interval before the runner
+    /// crashes are lost; their jobs are handed back to the queue once the runner is found
+    /// critical.
+    pub fn stage(&self, completion: &PendingCompletion) -> anyhow::Result<()> {
+        let value = serde_json::to_vec(completion)?;
+        self.staged.insert(completion.job_id.as_bytes(), value)?;
+        Ok(())
+    }
+
+    /// Drop staged results once their batch was stored or moved into the buffer
+    pub fn unstage(&self, job_ids: impl IntoIterator<Item = Uuid>) {
+        for job_id in job_ids {
+            if let Err(e) = self.staged.remove(job_id.as_bytes()) {
+                tracing::error!("Failed to unstage the result of job {}: {}", job_id, e);
+            }
+        }
+        if let Err(e) = self.staged.flush() {
+            tracing::error!("Failed to flush the staged job results: {}", e);
+        }
+    }
+
+    /// Number of buffered completions
+    pub fn len(&self) -> usize {
+        self.tree.len()
+    }
+
+    /// Whether there are no buffered completions
+    pub fn is_empty(&self) -> bool {
+        self.tree.is_empty()
+    }
+
+    /// All buffered completions; unreadable entries are dropped with an error log
+    fn pending(&self) -> Vec<PendingCompletion> {
+        let mut completions = Vec::new();
+        for entry in self.tree.iter() {
+            match entry {
+                Ok((key, value)) => match serde_json::from_slice::<PendingCompletion>(&value) {
+                    Ok(completion) => completions.push(completion),
+                    Err(e) => {
+                        tracing::error!("Dropping unreadable buffered completion: {}", e);
+                        let _ = self.tree.remove(key);
+                    }
+                },
+                Err(e) => tracing::error!("Failed to read completion buffer: {}", e),
+            }
+        }
+        completions
+    }
+
+    /// Remove a completion once it has been written to the database
+    fn remove(&self, job_id: Uuid) -> anyhow::Result<()> {
+        self.tree.remove(job_id.as_bytes())?;
+        self.tree.flush()?;
+        Ok(())
+    }
+
+    /// Write buffered completions to the database, stopping at the first connectivity error
+    ///
+    /// Results of jobs handed to another runner while the database was unreachable are
+    /// discarded, with a note in the job's log. Returns the number of completions that
+    /// were flushed.
+    pub async fn flush<R, A, U, L>(&self, job_repo: &R, attempt_repo: &A, usage_repo: &U, log_repo: &L) -> usize
+    where
+        R: JobRepository + ?Sized,
+        A: JobAttemptRepository + ?Sized,
+        U: JobUsageRepository + ?Sized,
+        L: JobLogRepository + ?Sized,
+    {
+        let mut flushed = 0;
+        for completion in self.pending() {
+            match completion.store(job_repo).await {
+                Ok((resolution, job)) => {
+                    tracing::info!("Flushed buffered completion for job {} ({})", completion.job_id, resolution.as_str());
+                    completion.record_resolution(log_repo, resolution, &job).await;
+                    // A discarded result leaves the attempt as abandoned
+                    if resolution != CompletionResolution::Discarded {
+                        completion.finish_attempt(attempt_repo).await;
+                    }
+                    completion.record_usage(usage_repo).await;
+                }
+                Err(e @ (Error::NotFound(_) | Error::Database(diesel::result::Error::NotFound))) => {
+                    // The job no longer exists, so the result can never be stored
+                    tracing::warn!("Discarding buffered completion for job {}: {}", completion.job_id, e);
+                }
+                Err(e) => {
+                    tracing::warn!("Database still unavailable, keeping {} buffered completions: {}", self.len(), e);
+                    return flushed;
+                }
+            }
+
+            if let Err(e) = self.remove(completion.job_id) {
+                tracing::error!("Failed to remove flushed completion for job {}: {}", completion.job_id, e);
+            }
+            flushed += 1;
+        }
+        flushed
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// A buffer in a directory of its own, removed when the test is done with it
+    struct TempBuffer(std::path::PathBuf);
+
+    impl TempBuffer {
+        fn new() -> Self {
+            Self(std::env::temp_dir().join(format!("innosystem-buffer-{}", Uuid::new_v4())))
+        }
+
+        fn open(&self) -> CompletionBuffer {
+            CompletionBuffer::open(self.0.to_str().unwrap()).unwrap()
+        }
+    }
+
+    impl Drop for TempBuffer {
+        fn drop(&mut self) {
+            let _ = std::fs::remove_dir_all(&self.0);
+        }
+    }
+
+    fn completion(job_id: Uuid) -> PendingCompletion {
+        PendingCompletion {
+            job_id,
+            success: true,
+            output: Some(serde_json::json!({ "text": "done" })),
+            error: None,
+            cost_cents: 120,
+            completed_at: Utc::now(),
+            reseller_id: None,
+            attempt_id: Some(Uuid::new_v4()),
+            runner_id: Some(Uuid::new_v4()),
+            usage: None,
+        }
+    }
+
+    #[test]
+    fn staged_results_are_buffered_when_the_runner_starts_again() {
+        let dir = TempBuffer::new();
+        let staged = completion(Uuid::new_v4());
+        {
+            let buffer = dir.open();
+            buffer.stage(&staged).unwrap();
+            // Staged results wait for their batch, not for the buffer's flush
+            assert!(buffer.is_empty());
+        }
+
+        let buffer = dir.open();
+        assert_eq!(buffer.len(), 1);
+        assert!(buffer.staged.is_empty());
+        let pending = buffer.pending();
+        assert_eq!(pending[0].job_id, staged.job_id);
+        assert_eq!(pending[0].cost_cents, staged.cost_cents);
+        assert_eq!(pending[0].attempt_id, staged.attempt_id);
+    }
+
+    #[test]
+    fn unstaged_results_are_not_recovered() {
+        let dir = TempBuffer::new();
+        let stored = completion(Uuid::new_v4());
+        let waiting = completion(Uuid::new_v4());
+        {
+            let buffer = dir.open();
+            buffer.stage(&stored).unwrap();
+            buffer.stage(&waiting).unwrap();
+            buffer.unstage([stored.job_id]);
+            assert_eq!(buffer.staged.len(), 1);
+        }
+
+        let buffer = dir.open();
+        let pending = buffer.pending();
+        assert_eq!(pending.len(), 1);
+        assert_eq!(pending[0].job_id, waiting.job_id);
+    }
+
+    #[test]
+    fn buffering_a_job_again_keeps_its_latest_result() {
+        let dir = TempBuffer::new();
+        let buffer = dir.open();
+        let job_id = Uuid::new_v4();
+        buffer.push(&completion(job_id)).unwrap();
+        let mut retried = completion(job_id);
+        retried.success = false;
+        buffer.push(&retried).unwrap();
+
+        assert_eq!(buffer.len(), 1);
+        assert!(!buffer.pending()[0].success);
+        buffer.remove(job_id).unwrap();
+        assert!(buffer.is_empty());
+    }
+}
@@ -1,5 +1,16 @@
 use std::env;
-use dotenvy::dotenv;
+use std::time::Duration;
+use innosystem_common::config::{load_env_file, reload_env_file};
+use innosystem_common::database::{PoolConfig, TenancyConfig};
+use innosystem_common::queue::DequeueConfig;
+use innosystem_common::schema_drift::SchemaCheckMode;
+use rand::Rng;
+use uuid::Uuid;
+
+use crate::batch::CompletionBatchConfig;
+use crate::environment::EnvironmentConfig;
+use crate::prefetch::PrefetchConfig;
+use crate::replay::ReplayConfig;
 
 /// Runner configuration loaded from environment variables
 #[derive(Debug, Clone)]
@@ -12,21 +23,68 @@ pub struct RunnerConfig {
     /// Database URL (for Phase 2)
     #[allow(dead_code)]
     pub database_url: Option<String>,
+    /// Size of the database connection pool and how long jobs wait for a connection (`DATABASE_POOL_*` variables)
+    pub database_pool: PoolConfig,
     /// Queue polling interval in milliseconds
     pub poll_interval_ms: u64,
+    /// Most milliseconds added at random to each polling interval, so runners started
+    /// together do not all poll at the same moment
+    pub poll_jitter_ms: u64,
+    /// Most due scheduled jobs claimed at once, leaving the rest to other runners
+    pub scheduled_batch_size: usize,
     /// Queue timeout in seconds
     pub queue_timeout_seconds: u64,
+    /// How often a running job is checked for a request to cancel it, in milliseconds
+    pub cancellation_check_interval_ms: u64,
+    /// Seconds the job in progress may keep running after SIGTERM or SIGINT before it is
+    /// handed back to the queue; jobs of runners without `RUNNER_ID` are always finished
+    pub shutdown_grace_secs: u64,
     /// Maximum number of concurrent jobs
     #[allow(dead_code)]
     pub max_concurrent_jobs: usize,
+    /// Directory of the local buffer for job results while the database is unreachable
+    pub local_cache_path: String,
+    /// Maximum serialized size of a job's input in bytes
+    pub max_input_bytes: usize,
+    /// Maximum serialized size of a job's output in bytes
+    pub max_output_bytes: usize,
+    /// ID under which this runner is registered; jobs it processes are attributed to it
+    pub runner_id: Option<Uuid>,
+    /// Optional schema-per-reseller storage of customer data (`TENANCY_*` variables)
+    pub tenancy: TenancyConfig,
+    /// Policy for choosing between the priority queues (`DEQUEUE_*` variables)
+    pub dequeue: DequeueConfig,
+    /// Local buffer of jobs taken off the queue ahead of time (`PREFETCH_*` variables)
+    pub prefetch: PrefetchConfig,
+    /// Results of finished jobs stored together in one transaction (`COMPLETION_BATCH_*` variables)
+    pub completion_batch: CompletionBatchConfig,
+    /// Heartbeats reporting the runner's environment, sent while `RUNNER_ID` is set
+    /// (`RUNNER_HEARTBEAT_INTERVAL_SECS` and `RUNNER_LIBRARY_VERSIONS` variables)
+    pub heartbeat: EnvironmentConfig,
+    /// Replay mode, set when `RUNNER_MODE` is `replay`: the runner replays the corpus of
+    /// job types against its processor version and exits (`REPLAY_*` variables)
+    pub replay: Option<ReplayConfig>,
+    /// What to do when the database schema drifted from the Diesel schema on startup (`SCHEMA_DRIFT_CHECK` variable)
+    pub schema_check: SchemaCheckMode,
 }
 
 impl RunnerConfig {
     /// Load configuration from environment variables
     pub fn load() -> anyhow::Result<Self> {
-        // Load .env file if present
-        let _ = dotenv();
-        
+        // Load the settings file if present
+        load_env_file();
+        Self::from_env()
+    }
+
+    /// Load the configuration again, with the settings file's current values replacing
+    /// the ones read on startup
+    pub fn reload() -> anyhow::Result<Self> {
+        reload_env_file()?;
+        Self::from_env()
+    }
+
+    /// Read and validate the configuration from environment variables
+    fn from_env() -> anyhow::Result<Self> {
         // Read configuration from environment variables
         let redis_url = env::var("REDIS_URL")
             .unwrap_or_else(|_| "redis://127.0.0.1:6379".into());
@@ -40,21 +98,165 @@ impl RunnerConfig {
             .unwrap_or_else(|_| "1000".into())
             .parse::<u64>()?;
             
+        let poll_jitter_ms = env::var("POLL_JITTER_MS")
+            .unwrap_or_else(|_| "250".into())
+            .parse::<u64>()?;
+            
+        let scheduled_batch_size = env::var("SCHEDULED_BATCH_SIZE")
+            .unwrap_or_else(|_| "10".into())
+            .parse::<usize>()?;
+            
         let queue_timeout_seconds = env::var("QUEUE_TIMEOUT_SECONDS")
             .unwrap_or_else(|_| "30".into())
             .parse::<u64>()?;
             
+        let cancellation_check_interval_ms = env::var("CANCELLATION_CHECK_INTERVAL_MS")
+            .unwrap_or_else(|_| "2000".into())
+            .parse::<u64>()?;
+            
+        let shutdown_grace_secs = env::var("SHUTDOWN_GRACE_SECS")
+            .unwrap_or_else(|_| "30".into())
+            .parse::<u64>()?;
+            
         let max_concurrent_jobs = env::var("MAX_CONCURRENT_JOBS")
             .unwrap_or_else(|_| "4".into())
             .parse::<usize>()?;
             
-        Ok(Self {
+        let local_cache_path = env::var("LOCAL_CACHE_PATH")
+            .unwrap_or_else(|_| "./runner-cache".into());
+            
+        let max_input_bytes = env::var("MAX_INPUT_BYTES")
+            .unwrap_or_else(|_| "1048576".into())
+            .parse::<usize>()?;
+            
+        let max_output_bytes = env::var("MAX_OUTPUT_BYTES")
+            .unwrap_or_else(|_| "1048576".into())
+            .parse::<usize>()?;
+            
+        let runner_id = env::var("RUNNER_ID")
+            .ok()
+            .map(|id| Uuid::parse_str(&id))
+            .transpose()?;
+            
+        let replay = match env::var("RUNNER_MODE").unwrap_or_else(|_| "process".into()).as_str() {
+            "process" => None,
+            "replay" => Some(ReplayConfig::from_env()?),
+            mode => anyhow::bail!("RUNNER_MODE must be process or replay, got {}", mode),
+        };
+            
+        let config = Self {
             redis_url,
             environment,
             database_url,
+            database_pool: PoolConfig::from_env(),
             poll_interval_ms,
+            poll_jitter_ms,
+            scheduled_batch_size,
             queue_timeout_seconds,
+            cancellation_check_interval_ms,
+            shutdown_grace_secs,
             max_concurrent_jobs,
-        })
+            local_cache_path,
+            max_input_bytes,
+            max_output_bytes,
+            runner_id,
+            tenancy: TenancyConfig::from_env(),
+            dequeue: DequeueConfig::from_env(),
+            prefetch: PrefetchConfig::from_env(),
+            completion_batch: CompletionBatchConfig::from_env(),
+            heartbeat: EnvironmentConfig::from_env(),
+            replay,
+            schema_check: SchemaCheckMode::from_env(),
+        };
+        config.validate()?;
+        Ok(config)
+    }
+
+    /// Reject settings the processing loop cannot run with
+    fn validate(&self) -> anyhow::Result<()> {
+        if self.poll_interval_ms == 0 {
+            anyhow::bail!("POLL_INTERVAL_MS must be positive");
+        }
+        if self.scheduled_batch_size == 0 {
+            anyhow::bail!("SCHEDULED_BATCH_SIZE must be positive");
+        }
+        // A zero timeout would block on an empty queue forever, starving scheduled jobs
+        if self.queue_timeout_seconds == 0 {
+            anyhow::bail!("QUEUE_TIMEOUT_SECONDS must be positive");
+        }
+        if self.cancellation_check_interval_ms == 0 {
+            anyhow::bail!("CANCELLATION_CHECK_INTERVAL_MS must be positive");
+        }
+        if self.max_concurrent_jobs == 0 {
+            anyhow::bail!("MAX_CONCURRENT_JOBS must be positive");
+        }
+        Ok(())
+    }
+
+    /// Settings of this configuration applied without restarting the runner
+    pub fn tunables(&self) -> TunableConfig {
+        TunableConfig {
+            poll_interval_ms: self.poll_interval_ms,
+            poll_jitter_ms: self.poll_jitter_ms,
+            scheduled_batch_size: self.scheduled_batch_size,
+            queue_timeout_seconds: self.queue_timeout_seconds,
+            dequeue: self.dequeue.clone(),
+            prefetch: self.prefetch.clone(),
+            completion_batch: self.completion_batch.clone(),
+        }
+    }
+
+    /// Variables of settings that differ from another configuration but only take
+    /// effect when the runner restarts
+    pub fn restart_required(&self, other: &Self) -> Vec<&'static str> {
+        [
+            ("REDIS_URL", self.redis_url != other.redis_url),
+            ("DATABASE_URL", self.database_url != other.database_url),
+            ("DATABASE_POOL_SIZE", self.database_pool.max_size != other.database_pool.max_size),
+            ("DATABASE_POOL_MIN_IDLE", self.database_pool.min_idle != other.database_pool.min_idle),
+            ("DATABASE_POOL_TIMEOUT_MS", self.database_pool.connection_timeout != other.database_pool.connection_timeout),
+            ("CANCELLATION_CHECK_INTERVAL_MS", self.cancellation_check_interval_ms != other.cancellation_check_interval_ms),
+            ("SHUTDOWN_GRACE_SECS", self.shutdown_grace_secs != other.shutdown_grace_secs),
+            ("LOCAL_CACHE_PATH", self.local_cache_path != other.local_cache_path),
+            ("MAX_INPUT_BYTES", self.max_input_bytes != other.max_input_bytes),
+            ("MAX_OUTPUT_BYTES", self.max_output_bytes != other.max_output_bytes),
+            ("RUNNER_ID", self.runner_id != other.runner_id),
+        ]
+        .into_iter()
+        .filter(|(_, changed)| *changed)
+        .map(|(name, _)| name)
+        .collect()
+    }
+}
+
+/// Settings the runner picks up on SIGHUP, swapped as a whole between two jobs
+#[derive(Debug, Clone, PartialEq)]
+pub struct TunableConfig {
+    /// Queue polling interval in milliseconds
+    pub poll_interval_ms: u64,
+    /// Most milliseconds added at random to each polling interval
+    pub poll_jitter_ms: u64,
+    /// Most due scheduled jobs claimed at once
+    pub scheduled_batch_size: usize,
+    /// Seconds to block on an empty queue before checking for scheduled jobs
+    pub queue_timeout_seconds: u64,
+    /// Policy for choosing between the priority queues
+    pub dequeue: DequeueConfig,
+    /// Local buffer of jobs taken off the queue ahead of time
+    pub prefetch: PrefetchConfig,
+    /// Results of finished jobs stored together in one transaction
+    pub completion_batch: CompletionBatchConfig,
+}
+
+impl TunableConfig {
+    /// How long to wait before polling an empty queue again: the polling interval plus
+    /// a random share of the jitter
+    pub fn poll_delay(&self) -> Duration {
+        let jitter = if self.poll_jitter_ms > 0 {
+            rand::rng().random_range(0..=self.poll_jitter_ms)
+        } else {
+            0
+        };
+        Duration::from_millis(self.poll_interval_ms + jitter)
     }
 }
@@ -0,0 +1,160 @@
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use uuid::Uuid;
+
+use innosystem_common::{
+    database::with_reseller,
+    models::{job_attempt::CrashReport, job_log::LogLevel},
+    repositories::{JobAttemptRepository, JobLogRepository},
+};
+
+use crate::cache::CompletionBuffer;
+
+/// Job the runner was processing, recorded until processing ends
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct InFlightJob {
+    pub job_id: Uuid,
+    /// Attempt of the job being run, if it could be recorded
+    pub attempt_id: Option<Uuid>,
+    /// Reseller whose schema holds the job, if it has one
+    pub reseller_id: Option<Uuid>,
+    pub runner_id: Option<Uuid>,
+    pub started_at: DateTime<Utc>,
+    /// Message and location of the panic the runner went down with
+    #[serde(default)]
+    pub panic: Option<String>,
+}
+
+impl InFlightJob {
+    /// Crash report of a job the runner did not finish processing before it went down
+    fn crash_report(&self) -> CrashReport {
+        CrashReport {
+            runner_id: self.runner_id,
+            started_at: self.started_at,
+            panic_message: self.panic.clone(),
+            reported_at: Utc::now(),
+        }
+    }
+}
+
+/// Local record of the jobs being processed, kept next to the completion buffer
+///
+/// A record left over when the runner starts belongs to a job the previous run went
+/// down with. It is reported on the job's attempt and in its log, along with the panic
+/// message if the runner panicked.
+pub struct CrashJournal {
+    tree: sled::Tree,
+}
+
+impl CrashJournal {
+    /// Open the journal in the completion buffer's database
+    pub fn open(buffer: &CompletionBuffer) -> anyhow::Result<Self> {
+        Ok(Self { tree: buffer.open_tree("in_flight_jobs")? })
+    }
+
+    /// Record the panic message in every in-flight job, then run the previous panic hook
+    pub fn capture_panics(&self) {
+        let tree = self.tree.clone();
+        let previous = std::panic::take_hook();
+        std::panic::set_hook(Box::new(move |info| {
+            let payload = info.payload();
+            let message = payload.downcast_ref::<&str>().map(|message| message.to_string())
+                .or_else(|| payload.downcast_ref::<String>().cloned())
+                .unwrap_or_else(|| "unknown panic payload".to_string());
+            let panic = match info.location() {
+                Some(location) => format!("{} at {}:{}", message, location.file(), location.line()),
+                None => message,
+            };
+            for (key, value) in tree.iter().flatten() {
+                if let Ok(mut job) = serde_json::from_slice::<InFlightJob>(&value) {
+                    job.panic = Some(panic.clone());
+                    if let Ok(value) = serde_json::to_vec(&job) {
+                        let _ = tree.insert(key, value);
+                    }
+                }
+            }
+            let _ = tree.flush();
+            previous(info);
+        }));
+    }
+
+    /// Record that processing of a job started, flushing it to disk before returning
+    pub fn begin(&self, job: &InFlightJob) -> anyhow::Result<()> {
+        let value = serde_json::to_vec(job)?;
+        self.tree.insert(job.job_id.as_bytes(), value)?;
+        self.tree.flush()?;
+        Ok(())
+    }
+
+    /// Record that processing of a job ended, whatever its outcome
+    pub fn end(&self, job_id: Uuid) {
+        if let Err(e) = self.tree.remove(job_id.as_bytes()).and_then(|_| self.tree.flush()) {
+            tracing::error!("Failed to clear the in-flight record of job {}: {}", job_id, e);
+        }
+    }
+
+    /// Jobs left in flight by a previous run; unreadable records are dropped with an error log
+    fn leftover(&self) -> Vec<InFlightJob> {
+        let mut jobs = Vec::new();
+        for entry in self.tree.iter() {
+            match entry {
+                Ok((key, value)) => match serde_json::from_slice::<InFlightJob>(&value) {
+                    Ok(job) => jobs.push(job),
+                    Err(e) => {
+                        tracing::error!("Dropping unreadable in-flight job record: {}", e);
+                        let _ = self.tree.remove(key);
+                    }
+                },
+                Err(e) => tracing::error!("Failed to read the in-flight job records: {}", e),
+            }
+        }
+        jobs
+    }
+
+    /// Report the jobs a previous run went down with on their attempts and in their logs
+    ///
+    /// Records whose crash could not be stored are kept and reported again at the next
+    /// start. Returns the number of crashes reported.
+    pub async fn report_crashes<A, L>(&self, attempt_repo: &A, log_repo: &L) -> usize
+    where
+        A: JobAttemptRepository + ?Sized,
+        L: JobLogRepository + ?Sized,
+    {
+        let mut reported = 0;
+        for job in self.leftover() {
+            tracing::warn!(
+                "Job {} was in progress when the runner went down{}",
+                job.job_id,
+                job.panic.as_ref().map_or_else(String::new, |panic| format!(": {}", panic)),
+            );
+
+            if let Some(attempt_id) = job.attempt_id {
+                let recorded = attempt_repo.record_crash(attempt_id, job.crash_report());
+                if let Err(e) = with_reseller(job.reseller_id, recorded).await {
+                    tracing::warn!("Failed to attach the crash to attempt {} of job {}, keeping it for the next start: {}", attempt_id, job.job_id, e);
+                    continue;
+                }
+            }
+
+            let message = match &job.panic {
+                Some(panic) => format!("The runner crashed while running the job: {}", panic),
+                None => "The runner went down while running the job".to_string(),
+            };
+            let fields = serde_json::json!({
+                "event": "runner_crash",
+                "runner_id": job.runner_id,
+                "attempt_id": job.attempt_id,
+                "started_at": job.started_at,
+                "panic_message": job.panic,
+            });
+            let recorded = log_repo.append_event(job.job_id, LogLevel::Error, message, Some(fields));
+            if let Err(e) = with_reseller(job.reseller_id, recorded).await {
+                tracing::warn!("Failed to record the crash in the log of job {}: {}", job.job_id, e);
+            }
+
+            self.end(job.job_id);
+            reported += 1;
+        }
+        reported
+    }
+}
@@ -0,0 +1,95 @@
+use std::collections::BTreeMap;
+use std::env;
+use std::time::Duration;
+
+use chrono::Utc;
+use innosystem_common::models::runner::{NewRunnerEnvironmentReport, RunnerEnvironment};
+use innosystem_common::repositories::RunnerRepository;
+use uuid::Uuid;
+
+/// Configuration of the heartbeats through which a registered runner reports its environment
+#[derive(Debug, Clone)]
+pub struct EnvironmentConfig {
+    /// Interval between two heartbeats
+    pub heartbeat_interval_secs: u64,
+    /// Versions of the libraries jobs are run with, reported next to the runner's own
+    /// (`RUNNER_LIBRARY_VERSIONS`, as `name=version` pairs separated by commas)
+    pub library_versions: BTreeMap<String, String>,
+}
+
+impl Default for EnvironmentConfig {
+    fn default() -> Self {
+        Self {
+            heartbeat_interval_secs: 30,
+            library_versions: BTreeMap::new(),
+        }
+    }
+}
+
+impl EnvironmentConfig {
+    /// Load the configuration from environment variables, using defaults for unset values
+    pub fn from_env() -> Self {
+        let defaults = Self::default();
+        Self {
+            heartbeat_interval_secs: env::var("RUNNER_HEARTBEAT_INTERVAL_SECS").ok()
+                .and_then(|value| value.parse().ok())
+                .filter(|secs| *secs > 0)
+                .unwrap_or(defaults.heartbeat_interval_secs),
+            library_versions: env::var("RUNNER_LIBRARY_VERSIONS").ok()
+                .map(|value| parse_library_versions(&value))
+                .unwrap_or(defaults.library_versions),
+        }
+    }
+
+    /// Environment this runner executes jobs in
+    pub fn detect(&self) -> RunnerEnvironment {
+        let mut libraries = self.library_versions.clone();
+        libraries.insert(env!("CARGO_PKG_NAME").to_string(), env!("CARGO_PKG_VERSION").to_string());
+
+        RunnerEnvironment {
+            os: env::consts::OS.to_string(),
+            arch: env::consts::ARCH.to_string(),
+            processors: std::thread::available_parallelism().map_or(1, |n| n.get() as u32),
+            libraries,
+        }
+    }
+}
+
+/// Parse `name=version` pairs separated by commas, skipping malformed pairs
+fn parse_library_versions(value: &str) -> BTreeMap<String, String> {
+    value.split(',')
+        .filter_map(|pair| pair.split_once('='))
+        .map(|(name, version)| (name.trim().to_string(), version.trim().to_string()))
+        .filter(|(name, version)| !name.is_empty() && !version.is_empty())
+        .collect()
+}
+
+/// Send a heartbeat with the runner's environment on every interval, for as long as the
+/// runner runs
+///
+/// The environment is only stored again when it changed since the last report, so drift
+/// between the runners of a pool shows up without a history entry per heartbeat.
+pub async fn report_heartbeats(runner_repo: impl RunnerRepository + 'static, runner_id: Uuid, config: EnvironmentConfig) {
+    let environment = config.detect();
+    let report_environment = environment.validate()
+        .map_err(|reason| tracing::warn!("Not reporting the environment of runner {}: {}", runner_id, reason))
+        .is_ok();
+    let mut interval = tokio::time::interval(Duration::from_secs(config.heartbeat_interval_secs));
+
+    loop {
+        interval.tick().await;
+
+        if let Err(err) = runner_repo.update_heartbeat(runner_id, Utc::now()).await {
+            tracing::warn!("Failed to send the heartbeat of runner {}: {}", runner_id, err);
+            continue;
+        }
+        if !report_environment {
+            continue;
+        }
+        match runner_repo.record_environment(NewRunnerEnvironmentReport::new(runner_id, &environment)).await {
+            Ok(Some(report)) => tracing::info!("Reported environment {} of runner {}", report.fingerprint, runner_id),
+            Ok(None) => {}
+            Err(err) => tracing::warn!("Failed to report the environment of runner {}: {}", runner_id, err),
+        }
+    }
+}
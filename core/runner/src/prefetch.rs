@@ -0,0 +1,206 @@
+use std::collections::VecDeque;
+use std::time::{Duration, Instant};
+
+use chrono::{DateTime, Utc};
+use innosystem_common::config::parse_env;
+use innosystem_common::models::job::PriorityLevel;
+use uuid::Uuid;
+
+/// Share of a runner's busy time queue latency may take before a larger buffer is advised
+const TARGET_LATENCY_SHARE: f64 = 0.1;
+
+/// Weight of the latest job in the recent average processing time
+const PROCESSING_SMOOTHING: f64 = 0.2;
+
+/// Configuration of the local buffer of jobs taken off the queue ahead of time
+#[derive(Debug, Clone, PartialEq)]
+pub struct PrefetchConfig {
+    /// Jobs taken off the queue along with the next one and held locally (0 disables prefetching)
+    pub buffer_size: usize,
+    /// Seconds a prefetched job is leased to this runner; a job not started by then is
+    /// handed back to its queue by the API
+    pub lease_secs: i64,
+    /// Recent average processing time (in milliseconds) above which no jobs are prefetched,
+    /// so long jobs do not hold back jobs an idle runner could take (0 disables the limit)
+    pub max_processing_ms: u64,
+    /// Interval between two reports of queue latency against processing time
+    pub report_interval_secs: u64,
+}
+
+impl Default for PrefetchConfig {
+    fn default() -> Self {
+        Self {
+            buffer_size: 0,
+            lease_secs: 60,
+            max_processing_ms: 1000,
+            report_interval_secs: 300,
+        }
+    }
+}
+
+impl PrefetchConfig {
+    /// Load the configuration from `PREFETCH_*` environment variables, using defaults for unset values
+    pub fn from_env() -> Self {
+        let defaults = Self::default();
+        Self {
+            buffer_size: parse_env("PREFETCH_JOBS").unwrap_or(defaults.buffer_size),
+            lease_secs: parse_env("PREFETCH_LEASE_SECS").filter(|secs| *secs > 0).unwrap_or(defaults.lease_secs),
+            max_processing_ms: parse_env("PREFETCH_MAX_PROCESSING_MS").unwrap_or(defaults.max_processing_ms),
+            report_interval_secs: parse_env("PREFETCH_REPORT_INTERVAL_SECS").unwrap_or(defaults.report_interval_secs),
+        }
+    }
+}
+
+/// A job taken off the queue ahead of time and leased to this runner
+#[derive(Debug, Clone)]
+pub struct PrefetchedJob {
+    pub job_id: Uuid,
+    /// Priority level of the queue the job was taken from
+    pub priority: PriorityLevel,
+    /// Reseller whose schema holds the job, if it has one
+    pub reseller_id: Option<Uuid>,
+}
+
+/// Queue latency and processing time since the last report
+#[derive(Debug, Default)]
+struct Measurements {
+    /// Queue round trips that returned jobs, and their total duration
+    fetches: u32,
+    fetch_time: Duration,
+    /// Jobs processed, and their total processing time
+    processed: u32,
+    processing_time: Duration,
+    /// Jobs of those that were served from the buffer
+    from_buffer: u32,
+}
+
+/// Bounded local buffer of prefetched jobs, hiding the queue round trip of short jobs
+///
+/// When the buffer is empty the runner takes up to `buffer_size + 1` jobs off the
+/// queue in one round trip, runs the first and leases the others to itself, so they
+/// show up as claimed. It also measures queue latency against processing time and
+/// periodically reports whether the buffer is large enough to hide the latency.
+pub struct PrefetchBuffer {
+    config: PrefetchConfig,
+    jobs: VecDeque<PrefetchedJob>,
+    measurements: Measurements,
+    /// Recent average processing time in milliseconds
+    recent_processing_ms: Option<f64>,
+    last_report: Instant,
+}
+
+impl PrefetchBuffer {
+    /// Create an empty buffer
+    pub fn new(config: PrefetchConfig) -> Self {
+        Self {
+            config,
+            jobs: VecDeque::new(),
+            measurements: Measurements::default(),
+            recent_processing_ms: None,
+            last_report: Instant::now(),
+        }
+    }
+
+    /// Apply a reloaded configuration; jobs already leased stay in the buffer even if
+    /// it shrank, and the measurements carry on
+    pub fn reconfigure(&mut self, config: PrefetchConfig) {
+        self.config = config;
+    }
+
+    /// Take the next prefetched job, oldest first
+    pub fn pop(&mut self) -> Option<PrefetchedJob> {
+        self.jobs.pop_front()
+    }
+
+    /// Number of leased jobs waiting in the buffer
+    pub fn len(&self) -> usize {
+        self.jobs.len()
+    }
+
+    /// Whether no leased job is waiting
+    pub fn is_empty(&self) -> bool {
+        self.jobs.is_empty()
+    }
+
+    /// Hold a leased job until the runner is free
+    pub fn push(&mut self, job: PrefetchedJob) {
+        self.jobs.push_back(job);
+    }
+
+    /// Number of jobs to take off the queue in the next round trip: the next job plus
+    /// a full buffer, or just the next job while jobs take too long to prefetch
+    pub fn batch_size(&self) -> usize {
+        let too_slow = self.config.max_processing_ms > 0
+            && self.recent_processing_ms.is_some_and(|ms| ms > self.config.max_processing_ms as f64);
+        if too_slow {
+            1
+        } else {
+            1 + self.config.buffer_size.saturating_sub(self.jobs.len())
+        }
+    }
+
+    /// End of the lease of jobs prefetched now
+    pub fn lease_until(&self) -> DateTime<Utc> {
+        Utc::now() + chrono::Duration::seconds(self.config.lease_secs)
+    }
+
+    /// Record the duration of a queue round trip that returned jobs
+    pub fn record_fetch(&mut self, elapsed: Duration) {
+        self.measurements.fetches += 1;
+        self.measurements.fetch_time += elapsed;
+    }
+
+    /// Record the processing time of a job, and whether it was served from the buffer
+    pub fn record_processing(&mut self, elapsed: Duration, from_buffer: bool) {
+        self.measurements.processed += 1;
+        self.measurements.processing_time += elapsed;
+        if from_buffer {
+            self.measurements.from_buffer += 1;
+        }
+
+        let ms = elapsed.as_secs_f64() * 1000.0;
+        self.recent_processing_ms = Some(match self.recent_processing_ms {
+            Some(recent) => recent + PROCESSING_SMOOTHING * (ms - recent),
+            None => ms,
+        });
+    }
+
+    /// Log queue latency against processing time once per report interval
+    ///
+    /// With a buffer of `n` every round trip serves `n + 1` jobs, so the share of busy
+    /// time lost to the queue is `latency / (latency + (n + 1) * processing)`. The report
+    /// advises the smallest buffer keeping that share below 10%.
+    pub fn report_if_due(&mut self) {
+        if self.config.report_interval_secs == 0
+            || self.last_report.elapsed() < Duration::from_secs(self.config.report_interval_secs)
+        {
+            return;
+        }
+        let measurements = std::mem::take(&mut self.measurements);
+        self.last_report = Instant::now();
+        if measurements.fetches == 0 || measurements.processed == 0 {
+            return;
+        }
+
+        let latency_ms = measurements.fetch_time.as_secs_f64() * 1000.0 / measurements.fetches as f64;
+        let processing_ms = measurements.processing_time.as_secs_f64() * 1000.0 / measurements.processed as f64;
+        let per_round_trip = measurements.processed as f64 / measurements.fetches as f64;
+        let latency_share = latency_ms / (latency_ms + per_round_trip * processing_ms);
+        let advised = (latency_ms * (1.0 - TARGET_LATENCY_SHARE) / (TARGET_LATENCY_SHARE * processing_ms.max(0.001)))
+            .ceil()
+            .max(1.0) as usize
+            - 1;
+
+        tracing::info!(
+            "Queue latency {:.1} ms per round trip against {:.1} ms processing per job: {} of {} jobs served from the prefetch buffer, {:.0}% of busy time spent on the queue; a buffer of {} (currently {}) keeps it under {:.0}%",
+            latency_ms,
+            processing_ms,
+            measurements.from_buffer,
+            measurements.processed,
+            latency_share * 100.0,
+            advised,
+            self.config.buffer_size,
+            TARGET_LATENCY_SHARE * 100.0,
+        );
+    }
+}
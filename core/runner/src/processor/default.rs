@@ -1,25 +1,44 @@
 use std::sync::Arc;
+use std::time::Instant;
 
 use innosystem_common::{
     models::{
-        job::Job,
-        job_type::ProcessorType,
-        wallet::{NewWalletTransaction, Wallet},
+        customer::Customer,
+        dry_run,
+        job::{Job, NewJob, PriorityLevel},
+        job_artifact::NewJobArtifact,
+        job_cost::{CostBreakdown, FAILURE_FEE_RATE},
+        job_error::{codes, JobError},
+        job_log::LogLevel,
+        job_type::{JobType, ProcessorType},
+        ledger::JobDebit,
+        redaction::redact_output,
+        replay::ReplayCase,
+        reseller::Reseller,
+        response_policy::{BinaryResponses, CapturedResponse, ResponsePolicy},
+        wallet::Wallet,
+    },
+    repositories::{
+        CustomerRepository, JobArtifactRepository, JobLogRepository, JobRepository, JobTypeRepository, ResellerRepository, WalletRepository,
     },
-    repositories::{CustomerRepository, JobRepository, JobTypeRepository, WalletRepository},
 };
 use serde_json::json;
+use tokio_util::sync::CancellationToken;
 use uuid::Uuid;
 
-use super::JobProcessor;
+use super::{JobHook, JobLogger, JobOutcome, JobProcessor};
+use crate::usage::UsageMeter;
 
 /// Default implementation of the JobProcessor
 pub struct DefaultJobProcessor {
-    #[allow(dead_code)]
     job_repo: Arc<dyn JobRepository>,
     job_type_repo: Arc<dyn JobTypeRepository>,
     wallet_repo: Arc<dyn WalletRepository>,
     customer_repo: Arc<dyn CustomerRepository>,
+    reseller_repo: Arc<dyn ResellerRepository>,
+    job_log_repo: Arc<dyn JobLogRepository>,
+    artifact_repo: Arc<dyn JobArtifactRepository>,
+    hooks: Vec<Arc<dyn JobHook>>,
 }
 
 impl DefaultJobProcessor {
@@ -29,15 +48,28 @@ impl DefaultJobProcessor {
         job_type_repo: Arc<dyn JobTypeRepository>,
         wallet_repo: Arc<dyn WalletRepository>,
         customer_repo: Arc<dyn CustomerRepository>,
+        reseller_repo: Arc<dyn ResellerRepository>,
+        job_log_repo: Arc<dyn JobLogRepository>,
+        artifact_repo: Arc<dyn JobArtifactRepository>,
     ) -> Self {
         Self {
             job_repo,
             job_type_repo,
             wallet_repo,
             customer_repo,
+            reseller_repo,
+            job_log_repo,
+            artifact_repo,
+            hooks: Vec::new(),
         }
     }
 
+    /// Register a hook to run around every job, in registration order
+    pub fn with_hook(mut self, hook: Arc<dyn JobHook>) -> Self {
+        self.hooks.push(hook);
+        self
+    }
+
     /// Reserve funds from customer wallet for job processing
     async fn reserve_funds(&self, job: &Job) -> anyhow::Result<Wallet> {
         let wallet = self.wallet_repo.find_by_customer_id(job.customer_id).await?;
@@ -47,16 +79,45 @@ impl DefaultJobProcessor {
             Some(format!("Reserve funds for job {}", job.id)),
             Some(job.id)
         ).await
-            .map_err(|e| anyhow::anyhow!("Failed to reserve funds: {}", e))
+            .map_err(|e| {
+                if e.to_string().contains("Insufficient funds") {
+                    JobError::input(
+                        codes::INSUFFICIENT_FUNDS,
+                        format!("Insufficient funds to reserve {} cents for the job", job.estimated_cost_cents),
+                    ).into()
+                } else {
+                    JobError::system(codes::BILLING_FAILED, format!("Failed to reserve funds: {}", e)).into()
+                }
+            })
+    }
+
+    /// Price a job that ran: its estimated cost with the surcharge of its priority and the
+    /// markup of the customer's reseller
+    async fn price(&self, job: &Job, customer: &Customer) -> anyhow::Result<CostBreakdown> {
+        let markup_rate = self.reseller_of(customer).await?.map_or(0, |reseller| reseller.markup_rate);
+        Ok(CostBreakdown::completed(job.estimated_cost_cents, &job.priority, markup_rate))
+    }
+
+    /// The reseller of a customer, if it has one
+    async fn reseller_of(&self, customer: &Customer) -> anyhow::Result<Option<Reseller>> {
+        match customer.reseller_id {
+            Some(reseller_id) => Ok(Some(self.reseller_repo.find_by_id(reseller_id).await?)),
+            None => Ok(None),
+        }
     }
 
     /// Charge customer wallet for completed job
+    ///
+    /// Promotional credit is spent before the balance; the itemized cost charged is
+    /// recorded on the job and returned. Failed jobs are only charged a failure fee, if
+    /// any is given. The charge is split in the ledger between the platform and the
+    /// customer's reseller.
     async fn charge_wallet(
         &self,
         job: &Job,
-        cost_cents: i32,
+        mut cost: CostBreakdown,
         success: bool,
-    ) -> anyhow::Result<()> {
+    ) -> anyhow::Result<CostBreakdown> {
         let wallet = self.wallet_repo.find_by_customer_id(job.customer_id).await?;
         
         // Release the reserved funds
@@ -69,24 +130,58 @@ impl DefaultJobProcessor {
             )
             .await?;
         
-        // If job was successful, create a transaction for the actual cost
-        if success {
-            let transaction = NewWalletTransaction {
-                id: Uuid::new_v4(),
-                wallet_id: wallet.id,
-                amount_cents: -(cost_cents as i32),
-                transaction_type: "job_charge".to_string(),
-                reference_id: Some(job.id),
-                description: Some(format!("Job charge for job {}", job.id)),
-                job_id: Some(job.id),
-                customer_id: job.customer_id,
-                created_at: None,
-            };
-            
-            self.wallet_repo.add_transaction(transaction).await?;
+        // If job was successful or owes a failure fee, create a transaction for the actual cost
+        if success || cost.total() > 0 {
+            let credits = self.wallet_repo.spend_credit(wallet.id, cost.total()).await?;
+            cost.apply_credits(credits as i64);
+
+            if cost.total() > 0 {
+                let customer = self.customer_repo.find_by_id(job.customer_id).await?;
+                let reseller = self.reseller_of(&customer).await?;
+                let debit = JobDebit::new(wallet.id, job.id, &cost, reseller.as_ref())
+                    .with_description(if success {
+                        format!("Job charge for job {}", job.id)
+                    } else {
+                        format!("Failure fee for job {}", job.id)
+                    });
+                
+                self.wallet_repo.charge_job(debit).await?;
+            }
+
+            if let Err(e) = self.job_repo.set_cost_breakdown(job.id, cost).await {
+                tracing::warn!("Failed to record the cost breakdown of job {}: {}", job.id, e);
+            }
         }
         
-        Ok(())
+        Ok(cost)
+    }
+    
+    /// Keep a binary response body as an artifact of the job, or only describe it when
+    /// the response policy discards binary bodies
+    async fn store_artifact(
+        &self,
+        job: &Job,
+        policy: &ResponsePolicy,
+        content_type: Option<String>,
+        body: Vec<u8>,
+    ) -> anyhow::Result<serde_json::Value> {
+        let content_type = content_type.unwrap_or_else(|| "application/octet-stream".to_string());
+        match policy.binary {
+            BinaryResponses::Discard => Ok(json!({ "content_type": content_type, "size_bytes": body.len(), "discarded": true })),
+            BinaryResponses::Artifact => {
+                if body.len() > policy.max_artifact_bytes {
+                    return Err(JobError::input(
+                        codes::OUTPUT_TOO_LARGE,
+                        format!("Binary webhook response exceeds the artifact limit of {} bytes", policy.max_artifact_bytes),
+                    ).into());
+                }
+                let artifact = NewJobArtifact::new(job.id, content_type, body);
+                let reference = json!({ "id": artifact.id, "content_type": artifact.content_type, "size_bytes": artifact.size_bytes });
+                self.artifact_repo.create(artifact).await
+                    .map_err(|e| JobError::system(codes::INTERNAL_ERROR, format!("Failed to store the webhook response: {}", e)))?;
+                Ok(reference)
+            }
+        }
     }
     
     /// Process a specific job type based on its processor type
@@ -94,9 +189,15 @@ impl DefaultJobProcessor {
         &self,
         job: &Job,
         job_type_id: Uuid,
+        logger: &JobLogger,
+        meter: &UsageMeter,
     ) -> anyhow::Result<serde_json::Value> {
         // Get the job type details
         let job_type = self.job_type_repo.find_by_id(job_type_id).await?;
+        logger.info(format!("Running {} processor for job type {}", job_type.processor_type.as_str(), job_type.name));
+        
+        // Input missing a field the processor declares as required fails up front
+        self.describe(&job_type.processor_type).check(&job.input_data)?;
         
         // Process based on processor type
         match job_type.processor_type {
@@ -106,110 +207,310 @@ impl DefaultJobProcessor {
             }
             ProcessorType::Async => {
                 // Async processor performs a simple transformation (like the old Transform processor)
-                let result = if let Some(text) = job.input_data.get("text") {
-                    if let Some(text_str) = text.as_str() {
-                        json!({
-                            "original_text": text_str,
-                            "transformed_text": text_str.to_uppercase(),
-                            "character_count": text_str.len(),
-                            "word_count": text_str.split_whitespace().count()
-                        })
-                    } else {
-                        json!({ "error": "Invalid text format, expected string" })
-                    }
-                } else {
-                    json!({ "error": "Missing text field in input" })
-                };
+                let result = dry_run::async_output(&job.input_data);
+                if let Some(error) = result.get("error").and_then(|error| error.as_str()) {
+                    logger.warn(error);
+                }
                 
                 Ok(result)
             }
             ProcessorType::Webhook => {
-                // Webhook processor sends data to a specified URL
-                let webhook_url = match job.input_data.get("webhook_url") {
-                    Some(url_value) => match url_value.as_str() {
-                        Some(url) => url,
-                        None => return Err(anyhow::anyhow!("webhook_url must be a string"))
-                    },
-                    None => return Err(anyhow::anyhow!("webhook_url is required for webhook jobs"))
-                };
-                
-                // Create payload with datetime and "hello world" value
-                let payload = json!({
-                    "datetime": chrono::Utc::now().to_rfc3339(),
-                    "value": "hello world"
-                });
+                // Webhook processor calls the URL of the input as the job type's template says
+                let webhook_url = dry_run::webhook_url(&job.input_data)?;
+                let request = dry_run::webhook_request(&job_type, &job.input_data, job.id, chrono::Utc::now())?;
+                let payload = request.body.clone().unwrap_or(serde_json::Value::Null);
                 
                 // Send the webhook request
-                tracing::info!("Sending webhook to URL: {}", webhook_url);
+                tracing::info!("Sending webhook to URL: {} {}", request.method.as_str(), webhook_url);
                 tracing::info!("Webhook payload: {}", payload);
+                logger.info(format!("Sending webhook to {} {}", request.method.as_str(), webhook_url));
                 
-                // Use reqwest to make the HTTP POST request
                 let client = reqwest::Client::new();
+                let method = reqwest::Method::from_bytes(request.method.as_str().as_bytes())?;
+                let mut builder = client.request(method, webhook_url);
+                for (name, value) in &request.headers {
+                    builder = builder.header(name.as_str(), value.as_str());
+                }
+                if let Some(body) = &request.body {
+                    builder = builder.json(body);
+                }
                 let response = match tokio::time::timeout(
                     std::time::Duration::from_secs(10),
-                    client.post(webhook_url)
-                        .json(&payload)
-                        .send()
+                    builder.send()
                 ).await {
                     Ok(result) => match result {
                         Ok(resp) => resp,
-                        Err(e) => return Err(anyhow::anyhow!("Failed to send webhook: {}", e))
+                        Err(e) => return Err(JobError::provider(codes::PROVIDER_UNREACHABLE, format!("Failed to send webhook: {}", e)).into())
                     },
-                    Err(_) => return Err(anyhow::anyhow!("Webhook request timed out after 10 seconds"))
+                    Err(_) => return Err(JobError::provider(codes::PROVIDER_TIMEOUT, "Webhook request timed out after 10 seconds").into())
                 };
                 
                 // Check if the request was successful
                 let status = response.status();
                 let status_code = status.as_u16();
+                logger.log(LogLevel::Info, "Webhook responded", Some(json!({ "status_code": status_code })));
+                let sent = request.body.as_ref().map_or(0, |body| body.to_string().len());
                 
                 if status.is_success() {
-                    // Return the result of the webhook call
-                    let response_text = response.text().await
-                        .unwrap_or_else(|_| "No response body".to_string());
+                    // Keep the response as the job type's response policy says
+                    let policy = job_type.response_policy()
+                        .map_err(|e| JobError::system(
+                            codes::INVALID_RESPONSE_POLICY,
+                            format!("Job type {} has an invalid response policy: {}", job_type.name, e),
+                        ).with_retryable(false))?;
+                    let content_type = response.headers()
+                        .get(reqwest::header::CONTENT_TYPE)
+                        .and_then(|value| value.to_str().ok())
+                        .map(str::to_string);
+                    let body = read_body(response, policy.read_limit()).await?;
+                    meter.record_transfer(sent, body.len());
                     
-                    Ok(json!({
+                    let mut output = json!({
                         "webhook_url": webhook_url,
+                        "method": request.method,
                         "payload": payload,
                         "status": "success",
                         "status_code": status_code,
-                        "response": response_text
-                    }))
+                        "content_type": content_type,
+                    });
+                    match policy.capture(content_type.as_deref(), &body) {
+                        CapturedResponse::Json(value) => output["response"] = value,
+                        CapturedResponse::Text { text, truncated } => {
+                            if truncated {
+                                logger.warn(format!("Webhook response truncated to {} bytes", policy.max_captured_bytes));
+                            }
+                            output["response"] = json!(text);
+                            output["response_truncated"] = json!(truncated);
+                        }
+                        CapturedResponse::Binary => {
+                            output["response"] = serde_json::Value::Null;
+                            output["artifact"] = self.store_artifact(job, &policy, content_type, body).await?;
+                        }
+                    }
+                    Ok(output)
                 } else {
-                    // Return error information
-                    Err(anyhow::anyhow!("Webhook request failed with status: {}", status))
+                    meter.record_transfer(sent, response.content_length().unwrap_or(0) as usize);
+                    // Return error information; only server errors and throttling are worth retrying
+                    let retryable = status.is_server_error() || status_code == 429;
+                    Err(JobError::provider(codes::PROVIDER_REJECTED, format!("Webhook request failed with status: {}", status))
+                        .with_retryable(retryable)
+                        .into())
                 }
             }
             ProcessorType::ExternalApi => {
                 // External API processor not implemented in Phase 1
-                Err(anyhow::anyhow!("External API processor not implemented in Phase 1"))
+                Err(JobError::system(codes::PROCESSOR_NOT_IMPLEMENTED, "External API processor not implemented in Phase 1")
+                    .with_retryable(false)
+                    .into())
             }
             ProcessorType::Batch => {
-                // Batch processor not implemented in Phase 1
-                Err(anyhow::anyhow!("Batch processor not implemented in Phase 1"))
+                // Batch jobs are fanned out before they get here
+                Err(JobError::system(codes::PROCESSOR_NOT_IMPLEMENTED, "Batch jobs are fanned out into sub-tasks")
+                    .with_retryable(false)
+                    .into())
             }
         }
     }
-}
 
-#[async_trait::async_trait]
-impl JobProcessor for DefaultJobProcessor {
-    async fn process_job(&self, job: Job) -> anyhow::Result<(serde_json::Value, i32)> {
+    /// Fan a batch job out into one sub-task per element of its `tasks` input
+    ///
+    /// Sub-tasks run the job type named by the batch job type's `processing_logic_id`
+    /// (its ID or name) on whichever runner takes them off the queue. Nothing is reserved
+    /// or charged for the batch job itself: each sub-task is billed like a job of its own.
+    async fn fan_out(&self, job: &Job, job_type: &JobType, logger: &JobLogger) -> anyhow::Result<JobOutcome> {
+        let tasks = dry_run::batch_tasks(&job.input_data)?;
+
+        let sub_task_type = self.sub_task_job_type(job_type).await?;
+        let sub_tasks: Vec<NewJob> = tasks.iter()
+            .enumerate()
+            .map(|(index, input)| job.sub_task(index as i32, sub_task_type.id, input.clone(), sub_task_type.standard_cost_cents).into())
+            .collect();
+        let sub_tasks = self.job_repo.fan_out(job.id, sub_tasks).await?;
+        logger.info(format!("Fanned out into {} sub-tasks of job type {}", sub_tasks.len(), sub_task_type.name));
+
+        Ok(JobOutcome::FannedOut(sub_tasks))
+    }
+
+    /// Enabled, non-batch job type the sub-tasks of a batch job type run
+    async fn sub_task_job_type(&self, job_type: &JobType) -> anyhow::Result<JobType> {
+        let reference = job_type.processing_logic_id.as_str();
+        let sub_task_type = match Uuid::parse_str(reference) {
+            Ok(id) => self.job_type_repo.find_by_id(id).await.ok(),
+            Err(_) => self.job_type_repo.list_all().await?
+                .into_iter()
+                .find(|candidate| candidate.name == reference),
+        };
+
+        match sub_task_type {
+            Some(sub_task_type) if sub_task_type.enabled && !matches!(sub_task_type.processor_type, ProcessorType::Batch) => Ok(sub_task_type),
+            _ => Err(JobError::system(
+                codes::INVALID_SUB_TASK_TYPE,
+                format!("Job type {} does not name an enabled, non-batch job type for its sub-tasks: {}", job_type.name, reference),
+            ).with_retryable(false).into()),
+        }
+    }
+
+    /// Execute a test-mode job with the stub processor, billing the sandbox balance
+    ///
+    /// No external webhooks or APIs are called and no wallet transactions are recorded.
+    async fn execute_test_mode(&self, job: &Job, logger: &JobLogger) -> anyhow::Result<(serde_json::Value, i32)> {
+        let wallet = self.wallet_repo.find_by_customer_id(job.customer_id).await?;
+        let customer = self.customer_repo.find_by_id(job.customer_id).await?;
+        // Priced like a live job, without spending promotional credit
+        let cost = self.price(job, &customer).await?;
+        let cost_cents = cost.total();
+        if wallet.test_balance_cents < cost_cents {
+            return Err(JobError::input(
+                codes::INSUFFICIENT_FUNDS,
+                format!("Insufficient test balance to run the job for {} cents", cost_cents),
+            ).into());
+        }
+
+        let job_type = self.job_type_repo.find_by_id(job.job_type_id).await?;
+        logger.info(format!("Running stub processor for job type {} (test mode)", job_type.name));
+
+        let started = Instant::now();
+        let mut result = Ok(json!({
+            "test_mode": true,
+            "processor_type": job_type.processor_type.as_str(),
+            "input": job.input_data.clone(),
+        }));
+        let elapsed = started.elapsed();
+
+        for hook in self.hooks.iter().rev() {
+            result = hook.after_job(job, result, elapsed).await;
+        }
+        let output = result?;
+
+        self.wallet_repo.adjust_test_balance(wallet.id, -cost_cents).await
+            .map_err(|e| JobError::system(codes::BILLING_FAILED, format!("Failed to charge test balance: {}", e)))?;
+        if let Err(e) = self.job_repo.set_cost_breakdown(job.id, cost).await {
+            tracing::warn!("Failed to record the cost breakdown of job {}: {}", job.id, e);
+        }
+
+        Ok((output, cost_cents))
+    }
+
+    /// Run a replay case through the processor of its job type, returning the output,
+    /// redacted like a live job's, and what the job would be charged
+    ///
+    /// Nothing is reserved or charged and the hooks do not run; webhook job types do
+    /// call their (anonymized) URL. Batch job types cannot be replayed.
+    pub async fn replay(&self, case: &ReplayCase, job_type: &JobType) -> anyhow::Result<(serde_json::Value, i32)> {
+        let priority = PriorityLevel::from_i32(case.priority);
+        let job = Job::new(Uuid::nil(), job_type.id, case.input_data.clone(), priority.clone(), job_type.standard_cost_cents);
+        let logger = JobLogger::new(job.id);
+        let output = self.process_job_type(&job, job_type.id, &logger, &UsageMeter::start()).await?;
+
+        let output = redact_output(job_type, job.id, output, chrono::Utc::now())?.output;
+        let cost = CostBreakdown::completed(job_type.standard_cost_cents, &priority, case.markup_rate);
+        Ok((output, cost.total()))
+    }
+
+    /// Stop a job cancelled while it ran, charging the failure fee failed jobs pay from
+    /// what was reserved for it
+    async fn cancel(&self, job: &Job, logger: &JobLogger) -> anyhow::Result<JobOutcome> {
+        logger.warn("Job cancelled while it was running");
+        let cost = self.charge_wallet(job, CostBreakdown::failed(job.estimated_cost_cents, FAILURE_FEE_RATE), false).await
+            .map_err(|e| JobError::system(codes::BILLING_FAILED, format!("Failed to charge the failure fee of the cancelled job: {}", e)))?;
+        Ok(JobOutcome::Cancelled { cost_cents: cost.total() })
+    }
+
+    /// Run the hooks, reserve funds, execute and charge a single job, or fan it out
+    async fn execute(&self, job: &Job, logger: &JobLogger, meter: &UsageMeter, cancellation: &CancellationToken) -> anyhow::Result<JobOutcome> {
+        // Run the before hooks; any of them may reject the job
+        for hook in &self.hooks {
+            hook.before_job(job).await
+                .map_err(|e| match e.downcast::<JobError>() {
+                    Ok(job_error) => job_error.into(),
+                    Err(e) => anyhow::anyhow!("Job rejected by {} hook: {}", hook.name(), e),
+                })?;
+        }
+
+        // Batch jobs are split across runners instead, in test mode as well
+        let job_type = self.job_type_repo.find_by_id(job.job_type_id).await?;
+        if matches!(job_type.processor_type, ProcessorType::Batch) {
+            return self.fan_out(job, &job_type, logger).await;
+        }
+
+        // Test-mode jobs use the stub processor and the sandbox balance instead
+        if job.test_mode {
+            let (output, cost_cents) = self.execute_test_mode(job, logger).await?;
+            return Ok(JobOutcome::Completed { output, cost_cents });
+        }
+
         // Reserve funds for the job
-        self.reserve_funds(&job).await?;
+        self.reserve_funds(job).await?;
         
-        // Get the customer details (for future use in Phase 2)
-        let _customer = self.customer_repo.find_by_id(job.customer_id).await?;
+        // Get the customer details, whose reseller may mark the price up
+        let customer = self.customer_repo.find_by_id(job.customer_id).await?;
         
-        // Process the job based on its type
-        let output = self.process_job_type(&job, job.job_type_id).await?;
+        // Process the job based on its type, dropping calls still in flight once it is cancelled
+        let started = Instant::now();
+        let mut result = tokio::select! {
+            result = self.process_job_type(job, job.job_type_id, logger, meter) => result,
+            _ = cancellation.cancelled() => return self.cancel(job, logger).await,
+        };
+        let elapsed = started.elapsed();
+
+        // Run the after hooks in reverse order so the first hook wraps all others
+        for hook in self.hooks.iter().rev() {
+            result = hook.after_job(job, result, elapsed).await;
+        }
+
+        let output = match result {
+            Ok(output) => output,
+            Err(e) => {
+                // Give the reserved funds back before reporting the failure
+                if let Err(release_err) = self.charge_wallet(job, CostBreakdown::default(), false).await {
+                    tracing::error!("Failed to release reservation for job {}: {}", job.id, release_err);
+                }
+                return Err(e);
+            }
+        };
         
-        // Calculate the actual cost (in Phase 1, use the estimated cost)
-        let cost_cents = job.estimated_cost_cents;
+        // Calculate the actual cost from the estimate, the job's priority and the reseller's markup
+        let cost = self.price(job, &customer).await?;
         
         // Charge the customer's wallet
-        self.charge_wallet(&job, cost_cents, true).await?;
+        let cost = self.charge_wallet(job, cost, true).await?;
         
         // Return the output and cost
-        Ok((output, cost_cents))
+        Ok(JobOutcome::Completed { output, cost_cents: cost.total() })
+    }
+}
+
+#[async_trait::async_trait]
+impl JobProcessor for DefaultJobProcessor {
+    async fn process_job(&self, job: Job, meter: &UsageMeter, cancellation: &CancellationToken) -> anyhow::Result<JobOutcome> {
+        let logger = JobLogger::new(job.id);
+
+        let result = self.execute(&job, &logger, meter, cancellation).await;
+        if let Err(e) = &result {
+            logger.error(format!("Job failed: {}", e));
+        }
+
+        // Persist the collected log lines; losing them must not fail the job
+        if let Err(e) = self.job_log_repo.append(logger.take_lines()).await {
+            tracing::warn!("Failed to persist logs for job {}: {}", job.id, e);
+        }
+
+        result
+    }
+}
+
+/// Read a response body up to `limit` bytes
+async fn read_body(mut response: reqwest::Response, limit: usize) -> anyhow::Result<Vec<u8>> {
+    let mut body = Vec::new();
+    while let Some(chunk) = response.chunk().await
+        .map_err(|e| JobError::provider(codes::PROVIDER_UNREACHABLE, format!("Failed to read the webhook response: {}", e)))?
+    {
+        let room = limit - body.len();
+        if chunk.len() >= room {
+            body.extend_from_slice(&chunk[..room]);
+            break;
+        }
+        body.extend_from_slice(&chunk);
     }
+    Ok(body)
 }
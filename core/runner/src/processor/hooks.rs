@@ -0,0 +1,236 @@
+use std::sync::Arc;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::time::Duration;
+
+use chrono::Utc;
+use innosystem_common::models::{
+    job::Job,
+    job_error::{codes, JobError},
+    redaction::redact_output,
+};
+use innosystem_common::repositories::{JobTypeRepository, UnredactedOutputRepository};
+
+/// Hook run around the execution of every job by the `DefaultJobProcessor`
+///
+/// `before_job` hooks run in registration order before any funds are reserved;
+/// an error aborts the job. `after_job` hooks run in reverse registration order
+/// and receive the result of the previous hook, which they may pass through,
+/// replace or turn into an error.
+#[async_trait::async_trait]
+pub trait JobHook: Send + Sync {
+    /// Name used when logging hook failures
+    fn name(&self) -> &'static str;
+
+    /// Called before the job is executed
+    async fn before_job(&self, _job: &Job) -> anyhow::Result<()> {
+        Ok(())
+    }
+
+    /// Called after the job was executed, with the time spent executing it
+    async fn after_job(
+        &self,
+        _job: &Job,
+        result: anyhow::Result<serde_json::Value>,
+        _elapsed: Duration,
+    ) -> anyhow::Result<serde_json::Value> {
+        result
+    }
+}
+
+/// Logs the start, outcome and duration of every job
+pub struct LoggingHook;
+
+#[async_trait::async_trait]
+impl JobHook for LoggingHook {
+    fn name(&self) -> &'static str {
+        "logging"
+    }
+
+    async fn before_job(&self, job: &Job) -> anyhow::Result<()> {
+        tracing::info!("Starting job {} (type: {}, customer: {})", job.id, job.job_type_id, job.customer_id);
+        Ok(())
+    }
+
+    async fn after_job(
+        &self,
+        job: &Job,
+        result: anyhow::Result<serde_json::Value>,
+        elapsed: Duration,
+    ) -> anyhow::Result<serde_json::Value> {
+        match &result {
+            Ok(_) => tracing::info!("Job {} executed in {} ms", job.id, elapsed.as_millis()),
+            Err(e) => tracing::warn!("Job {} failed after {} ms: {}", job.id, elapsed.as_millis(), e),
+        }
+        result
+    }
+}
+
+/// Counts executed jobs and their total execution time
+#[derive(Default)]
+pub struct MetricsHook {
+    succeeded: AtomicU64,
+    failed: AtomicU64,
+    total_duration_ms: AtomicU64,
+}
+
+impl MetricsHook {
+    /// Create a new MetricsHook with all counters at zero
+    pub fn new() -> Self {
+        Self::default()
+    }
+}
+
+#[async_trait::async_trait]
+impl JobHook for MetricsHook {
+    fn name(&self) -> &'static str {
+        "metrics"
+    }
+
+    async fn after_job(
+        &self,
+        _job: &Job,
+        result: anyhow::Result<serde_json::Value>,
+        elapsed: Duration,
+    ) -> anyhow::Result<serde_json::Value> {
+        if result.is_ok() {
+            self.succeeded.fetch_add(1, Ordering::Relaxed);
+        } else {
+            self.failed.fetch_add(1, Ordering::Relaxed);
+        }
+        let total_ms = self.total_duration_ms.fetch_add(elapsed.as_millis() as u64, Ordering::Relaxed)
+            + elapsed.as_millis() as u64;
+
+        let succeeded = self.succeeded.load(Ordering::Relaxed);
+        let failed = self.failed.load(Ordering::Relaxed);
+        tracing::debug!(
+            "Runner totals: {} succeeded, {} failed, {} ms average execution time",
+            succeeded,
+            failed,
+            total_ms / (succeeded + failed).max(1)
+        );
+
+        result
+    }
+}
+
+/// Rejects jobs whose input is not a JSON object or exceeds a size limit
+pub struct InputValidationHook {
+    max_input_bytes: usize,
+}
+
+impl InputValidationHook {
+    /// Create a new InputValidationHook with the given size limit
+    pub fn new(max_input_bytes: usize) -> Self {
+        Self { max_input_bytes }
+    }
+}
+
+#[async_trait::async_trait]
+impl JobHook for InputValidationHook {
+    fn name(&self) -> &'static str {
+        "input_validation"
+    }
+
+    async fn before_job(&self, job: &Job) -> anyhow::Result<()> {
+        if !job.input_data.is_object() {
+            return Err(JobError::input(codes::INVALID_INPUT, "Job input must be a JSON object").into());
+        }
+
+        let size = serde_json::to_vec(&job.input_data)?.len();
+        if size > self.max_input_bytes {
+            return Err(JobError::input(
+                codes::INVALID_INPUT,
+                format!("Job input is {} bytes, exceeding the limit of {} bytes", size, self.max_input_bytes),
+            ).into());
+        }
+
+        Ok(())
+    }
+}
+
+/// Fails jobs whose output exceeds a size limit
+pub struct OutputSizeLimitHook {
+    max_output_bytes: usize,
+}
+
+impl OutputSizeLimitHook {
+    /// Create a new OutputSizeLimitHook with the given size limit
+    pub fn new(max_output_bytes: usize) -> Self {
+        Self { max_output_bytes }
+    }
+}
+
+#[async_trait::async_trait]
+impl JobHook for OutputSizeLimitHook {
+    fn name(&self) -> &'static str {
+        "output_size_limit"
+    }
+
+    async fn after_job(
+        &self,
+        _job: &Job,
+        result: anyhow::Result<serde_json::Value>,
+        _elapsed: Duration,
+    ) -> anyhow::Result<serde_json::Value> {
+        let output = result?;
+        let size = serde_json::to_vec(&output)?.len();
+        if size > self.max_output_bytes {
+            return Err(JobError::input(
+                codes::OUTPUT_TOO_LARGE,
+                format!("Job output is {} bytes, exceeding the limit of {} bytes", size, self.max_output_bytes),
+            ).into());
+        }
+        Ok(output)
+    }
+}
+
+/// Redacts the output values selected by the redaction rules of the job's type
+///
+/// Only the redacted output is stored and returned by the API. When the job type
+/// retains unredacted output, the original is kept for administrators until the
+/// retention ends. A job whose redaction rules cannot be read fails rather than
+/// risk storing a secret.
+pub struct RedactionHook {
+    job_type_repo: Arc<dyn JobTypeRepository>,
+    unredacted_output_repo: Arc<dyn UnredactedOutputRepository>,
+}
+
+impl RedactionHook {
+    /// Create a new RedactionHook
+    pub fn new(job_type_repo: Arc<dyn JobTypeRepository>, unredacted_output_repo: Arc<dyn UnredactedOutputRepository>) -> Self {
+        Self { job_type_repo, unredacted_output_repo }
+    }
+}
+
+#[async_trait::async_trait]
+impl JobHook for RedactionHook {
+    fn name(&self) -> &'static str {
+        "redaction"
+    }
+
+    async fn after_job(
+        &self,
+        job: &Job,
+        result: anyhow::Result<serde_json::Value>,
+        _elapsed: Duration,
+    ) -> anyhow::Result<serde_json::Value> {
+        let output = result?;
+        let job_type = self.job_type_repo.find_by_id(job.job_type_id).await
+            .map_err(|e| JobError::system(codes::REDACTION_FAILED, format!("Failed to load the redaction rules: {}", e)))?;
+        if job_type.redaction_rules.is_empty() {
+            return Ok(output);
+        }
+
+        let redacted = redact_output(&job_type, job.id, output, Utc::now())
+            .map_err(|e| JobError::system(codes::REDACTION_FAILED, e.to_string()).with_retryable(false))?;
+        if redacted.redacted > 0 {
+            tracing::debug!("Redacted {} values from the output of job {}", redacted.redacted, job.id);
+        }
+        if let Some(unredacted) = redacted.unredacted {
+            if let Err(e) = self.unredacted_output_repo.store(unredacted).await {
+                tracing::warn!("Failed to keep the unredacted output of job {}: {}", job.id, e);
+            }
+        }
+        Ok(redacted.output)
+    }
+}
@@ -0,0 +1,70 @@
+use std::sync::Mutex;
+use std::sync::atomic::{AtomicI32, Ordering};
+
+use chrono::Utc;
+use innosystem_common::models::job_log::{LogLevel, NewJobLog};
+use uuid::Uuid;
+
+/// Handle through which processors emit log lines for the job they execute
+///
+/// Lines are collected in memory and persisted by the `DefaultJobProcessor`
+/// once the job has finished, so logging never slows down or fails a job.
+pub struct JobLogger {
+    job_id: Uuid,
+    next_line: AtomicI32,
+    lines: Mutex<Vec<NewJobLog>>,
+}
+
+impl JobLogger {
+    /// Create an empty logger for the given job
+    pub fn new(job_id: Uuid) -> Self {
+        Self {
+            job_id,
+            next_line: AtomicI32::new(1),
+            lines: Mutex::new(Vec::new()),
+        }
+    }
+
+    /// Record a line with optional structured context
+    pub fn log(&self, level: LogLevel, message: impl Into<String>, fields: Option<serde_json::Value>) {
+        let line = NewJobLog {
+            id: Uuid::new_v4(),
+            job_id: self.job_id,
+            line_number: self.next_line.fetch_add(1, Ordering::Relaxed),
+            level: level.as_str().to_string(),
+            message: message.into(),
+            fields,
+            created_at: Some(Utc::now()),
+        };
+
+        if let Ok(mut lines) = self.lines.lock() {
+            lines.push(line);
+        }
+    }
+
+    /// Record a debug line
+    #[allow(dead_code)]
+    pub fn debug(&self, message: impl Into<String>) {
+        self.log(LogLevel::Debug, message, None);
+    }
+
+    /// Record an info line
+    pub fn info(&self, message: impl Into<String>) {
+        self.log(LogLevel::Info, message, None);
+    }
+
+    /// Record a warning line
+    pub fn warn(&self, message: impl Into<String>) {
+        self.log(LogLevel::Warn, message, None);
+    }
+
+    /// Record an error line
+    pub fn error(&self, message: impl Into<String>) {
+        self.log(LogLevel::Error, message, None);
+    }
+
+    /// Take all recorded lines, leaving the logger empty
+    pub fn take_lines(&self) -> Vec<NewJobLog> {
+        self.lines.lock().map(|mut lines| std::mem::take(&mut *lines)).unwrap_or_default()
+    }
+}
@@ -1,11 +1,42 @@
 mod default;
+mod hooks;
+mod logger;
 
 pub use default::DefaultJobProcessor;
+pub use logger::JobLogger;
+pub use hooks::{InputValidationHook, JobHook, LoggingHook, MetricsHook, OutputSizeLimitHook, RedactionHook};
+use innosystem_common::models::input_contract::InputContract;
 use innosystem_common::models::job::Job;
+use innosystem_common::models::job_type::ProcessorType;
+use tokio_util::sync::CancellationToken;
+
+use crate::usage::UsageMeter;
+
+/// What became of a job a processor ran successfully
+#[derive(Debug)]
+pub enum JobOutcome {
+    /// The job ran to completion with the given output and cost
+    Completed { output: serde_json::Value, cost_cents: i32 },
+    /// The job fanned out into the given sub-tasks, which still have to be queued; it
+    /// finishes once all of them did
+    FannedOut(Vec<Job>),
+    /// The job was cancelled while it ran and was charged the given failure fee
+    Cancelled { cost_cents: i32 },
+}
 
 /// Trait for job processors
 #[async_trait::async_trait]
 pub trait JobProcessor: Send + Sync {
-    /// Process a job and return the output data and cost if successful
-    async fn process_job(&self, job: Job) -> anyhow::Result<(serde_json::Value, i32)>;
+    /// Process a job and return what became of it if successful, counting the data it
+    /// transfers on the meter
+    ///
+    /// Once `cancellation` is cancelled the processor stops the job as soon as it can,
+    /// cutting calls to external services short.
+    async fn process_job(&self, job: Job, meter: &UsageMeter, cancellation: &CancellationToken) -> anyhow::Result<JobOutcome>;
+
+    /// Input fields the processor expects for jobs of the given processor type, as
+    /// listed in the job type catalog
+    fn describe(&self, processor_type: &ProcessorType) -> InputContract {
+        InputContract::of(processor_type)
+    }
 }
@@ -0,0 +1,48 @@
+use tokio::signal::unix::{signal, SignalKind};
+use tokio::sync::watch;
+
+use crate::config::{RunnerConfig, TunableConfig};
+
+/// Reload the configuration whenever the runner receives SIGHUP and publish its
+/// tunable settings to the processing loop
+///
+/// A configuration that fails to load or validate is logged and ignored, so the runner
+/// keeps the settings it has. Changed settings that are not tunable are reported as
+/// taking effect on the next restart.
+pub async fn reload_on_hangup(started: RunnerConfig, tunables: watch::Sender<TunableConfig>) {
+    let mut hangups = match signal(SignalKind::hangup()) {
+        Ok(hangups) => hangups,
+        Err(err) => {
+            tracing::error!("Failed to listen for SIGHUP, the configuration cannot be reloaded: {}", err);
+            return;
+        }
+    };
+
+    while hangups.recv().await.is_some() {
+        let config = match RunnerConfig::reload() {
+            Ok(config) => config,
+            Err(err) => {
+                tracing::error!("Keeping the current configuration, the reloaded one is invalid: {}", err);
+                continue;
+            }
+        };
+
+        for name in started.restart_required(&config) {
+            tracing::warn!("{} changed but only takes effect after a restart", name);
+        }
+
+        let reloaded = config.tunables();
+        let changed = tunables.send_if_modified(|current| {
+            if *current == reloaded {
+                return false;
+            }
+            *current = reloaded.clone();
+            true
+        });
+        if changed {
+            tracing::info!("Reloaded configuration: {:?}", reloaded);
+        } else {
+            tracing::info!("Reloaded configuration, no tunable setting changed");
+        }
+    }
+}
@@ -0,0 +1,122 @@
+use std::env;
+
+use innosystem_common::models::redaction::parse_rules;
+use innosystem_common::models::replay::{ReplayComparison, ReplayOutcome, ReplayReport};
+use innosystem_common::repositories::{JobTypeRepository, ReplayCaseRepository};
+use uuid::Uuid;
+
+use crate::processor::DefaultJobProcessor;
+
+/// Configuration of replay mode, in which the runner replays the corpus of job types
+/// against its processor version instead of taking jobs off the queue (`RUNNER_MODE=replay`)
+#[derive(Debug, Clone)]
+pub struct ReplayConfig {
+    /// Job types whose corpus is replayed; every job type with a corpus when empty
+    /// (`REPLAY_JOB_TYPES`, IDs separated by commas)
+    pub job_types: Vec<Uuid>,
+    /// JSONPath patterns of output values that differ between runs, such as timestamps,
+    /// ignored when comparing with the baseline (`REPLAY_IGNORE_PATHS`, separated by commas)
+    pub ignore_paths: Vec<String>,
+    /// Record every replay as the new baseline, accepting this version's outputs and
+    /// costs (`REPLAY_ACCEPT`)
+    pub accept: bool,
+    /// Processor version recorded with baselines (`REPLAY_PROCESSOR_VERSION`, defaults
+    /// to the runner's version)
+    pub version: String,
+    /// File the reports are written to as JSON (`REPLAY_REPORT_PATH`)
+    pub report_path: Option<String>,
+}
+
+impl ReplayConfig {
+    /// Load the configuration from `REPLAY_*` environment variables
+    pub fn from_env() -> anyhow::Result<Self> {
+        let job_types = list_env("REPLAY_JOB_TYPES")
+            .iter()
+            .map(|id| Uuid::parse_str(id))
+            .collect::<Result<_, _>>()?;
+        let ignore_paths = list_env("REPLAY_IGNORE_PATHS");
+        parse_rules(&ignore_paths)?;
+
+        Ok(Self {
+            job_types,
+            ignore_paths,
+            accept: env::var("REPLAY_ACCEPT").is_ok_and(|value| value == "true" || value == "1"),
+            version: env::var("REPLAY_PROCESSOR_VERSION").unwrap_or_else(|_| env!("CARGO_PKG_VERSION").to_string()),
+            report_path: env::var("REPLAY_REPORT_PATH").ok().filter(|path| !path.is_empty()),
+        })
+    }
+}
+
+/// Values of an environment variable separated by commas, skipping empty ones
+fn list_env(name: &str) -> Vec<String> {
+    env::var(name).unwrap_or_default()
+        .split(',')
+        .map(str::trim)
+        .filter(|value| !value.is_empty())
+        .map(String::from)
+        .collect()
+}
+
+/// Replay the corpus of the configured job types, returning a report per job type
+///
+/// Cases without a baseline, and every case when accepting, get this run recorded as
+/// their baseline; the others are compared with theirs. Failures to record a
+/// baseline are logged and reported as failed cases.
+pub async fn replay_corpus(
+    processor: &DefaultJobProcessor,
+    replay_case_repo: &dyn ReplayCaseRepository,
+    job_type_repo: &dyn JobTypeRepository,
+    config: &ReplayConfig,
+) -> anyhow::Result<Vec<ReplayReport>> {
+    let ignored = parse_rules(&config.ignore_paths)?;
+    let job_type_ids = if config.job_types.is_empty() {
+        replay_case_repo.list_job_types().await?
+    } else {
+        config.job_types.clone()
+    };
+
+    let mut reports = Vec::with_capacity(job_type_ids.len());
+    for job_type_id in job_type_ids {
+        let job_type = job_type_repo.find_by_id(job_type_id).await?;
+        let cases = replay_case_repo.list_by_job_type(job_type_id).await?;
+        tracing::info!("Replaying {} cases of job type {} against version {}", cases.len(), job_type.name, config.version);
+
+        let mut outcomes = Vec::with_capacity(cases.len());
+        for case in cases {
+            let outcome = match processor.replay(&case, &job_type).await {
+                Err(err) => ReplayOutcome::Failed { case_id: case.id, error: err.to_string() },
+                Ok((output, cost_cents)) if config.accept || !case.has_baseline() => {
+                    match replay_case_repo.record_baseline(case.id, output, cost_cents, &config.version).await {
+                        Ok(_) => ReplayOutcome::Recorded { case_id: case.id, cost_cents },
+                        Err(err) => {
+                            tracing::error!("Failed to record the baseline of replay case {}: {}", case.id, err);
+                            ReplayOutcome::Failed { case_id: case.id, error: format!("Failed to record the baseline: {}", err) }
+                        }
+                    }
+                }
+                Ok((output, cost_cents)) => ReplayComparison::compare(&case, &output, cost_cents, &ignored)
+                    .map_or_else(
+                        || ReplayOutcome::Failed { case_id: case.id, error: "The case has no baseline".to_string() },
+                        ReplayOutcome::Compared,
+                    ),
+            };
+            if outcome.is_regression() {
+                tracing::warn!("Replay of case {} of job type {} regressed: {:?}", case.id, job_type.name, outcome);
+            }
+            outcomes.push(outcome);
+        }
+
+        let report = ReplayReport { job_type_id, version: config.version.clone(), outcomes };
+        tracing::info!(
+            "Replayed {} cases of job type {}: {} regressed",
+            report.outcomes.len(), job_type.name, report.regressions()
+        );
+        reports.push(report);
+    }
+
+    if let Some(path) = &config.report_path {
+        std::fs::write(path, serde_json::to_vec_pretty(&reports)?)?;
+        tracing::info!("Wrote the replay reports to {}", path);
+    }
+    Ok(reports)
+}
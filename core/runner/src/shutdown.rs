@@ -0,0 +1,42 @@
+use tokio_util::sync::CancellationToken;
+
+/// Cancel `shutdown` once the runner receives SIGTERM or SIGINT
+///
+/// The processing loop then stops taking jobs off the queue; the job in progress is
+/// finished, or handed back to the queue if it outlives the shutdown grace period.
+pub async fn cancel_on_signal(shutdown: CancellationToken) {
+    let signal = wait_for_signal().await;
+    tracing::info!("Received {}, stopping after the current job", signal);
+    shutdown.cancel();
+}
+
+#[cfg(unix)]
+async fn wait_for_signal() -> &'static str {
+    use tokio::signal::unix::{signal, SignalKind};
+
+    let mut terminations = match signal(SignalKind::terminate()) {
+        Ok(terminations) => terminations,
+        Err(err) => {
+            tracing::error!("Failed to listen for SIGTERM, only SIGINT stops the runner gracefully: {}", err);
+            return wait_for_interrupt().await;
+        }
+    };
+    tokio::select! {
+        _ = terminations.recv() => "SIGTERM",
+        signal = wait_for_interrupt() => signal,
+    }
+}
+
+#[cfg(not(unix))]
+async fn wait_for_signal() -> &'static str {
+    wait_for_interrupt().await
+}
+
+/// Wait for SIGINT (Ctrl-C); never returns if it cannot be listened for
+async fn wait_for_interrupt() -> &'static str {
+    if let Err(err) = tokio::signal::ctrl_c().await {
+        tracing::error!("Failed to listen for SIGINT: {}", err);
+        std::future::pending::<()>().await;
+    }
+    "SIGINT"
+}
@@ -0,0 +1,73 @@
+use std::fs;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::time::Instant;
+
+use innosystem_common::models::job_usage::ResourceUsage;
+
+/// Clock ticks per second of the CPU times in `/proc`, fixed by the Linux ABI
+const USER_HZ: i64 = 100;
+
+/// Measures the resources used by the job this runner is running
+///
+/// The runner runs one job at a time, so the CPU time and peak memory of the process
+/// while the job ran are attributed to it. Both are read from `/proc` and are unknown
+/// on other systems; where the peak cannot be reset at the start of a job, it is the
+/// peak of the process so far.
+pub struct UsageMeter {
+    started: Instant,
+    cpu_started_ms: Option<i64>,
+    bytes_sent: AtomicU64,
+    bytes_received: AtomicU64,
+}
+
+impl UsageMeter {
+    /// Start measuring a job
+    pub fn start() -> Self {
+        // Writing 5 to clear_refs resets the peak resident memory of the process
+        let _ = fs::write("/proc/self/clear_refs", "5");
+        Self {
+            started: Instant::now(),
+            cpu_started_ms: process_cpu_time_ms(),
+            bytes_sent: AtomicU64::new(0),
+            bytes_received: AtomicU64::new(0),
+        }
+    }
+
+    /// Count the bodies of a request to a webhook or external API and of its response
+    pub fn record_transfer(&self, sent: usize, received: usize) {
+        self.bytes_sent.fetch_add(sent as u64, Ordering::Relaxed);
+        self.bytes_received.fetch_add(received as u64, Ordering::Relaxed);
+    }
+
+    /// Resources used since the job started
+    pub fn finish(&self) -> ResourceUsage {
+        let cpu_time_ms = self.cpu_started_ms
+            .zip(process_cpu_time_ms())
+            .map(|(started, now)| (now - started).max(0));
+        ResourceUsage {
+            wall_time_ms: self.started.elapsed().as_millis() as i64,
+            cpu_time_ms,
+            peak_memory_bytes: peak_memory_bytes(),
+            bytes_sent: self.bytes_sent.load(Ordering::Relaxed) as i64,
+            bytes_received: self.bytes_received.load(Ordering::Relaxed) as i64,
+        }
+    }
+}
+
+/// User and system CPU time of the process so far, from `/proc/self/stat`
+fn process_cpu_time_ms() -> Option<i64> {
+    let stat = fs::read_to_string("/proc/self/stat").ok()?;
+    // The command name may contain spaces, so fields are counted from its closing parenthesis
+    let mut fields = stat.rsplit_once(')')?.1.split_whitespace().skip(11);
+    let user: i64 = fields.next()?.parse().ok()?;
+    let system: i64 = fields.next()?.parse().ok()?;
+    Some((user + system) * 1000 / USER_HZ)
+}
+
+/// Peak resident memory of the process, from the `VmHWM` line of `/proc/self/status`
+fn peak_memory_bytes() -> Option<i64> {
+    let status = fs::read_to_string("/proc/self/status").ok()?;
+    let line = status.lines().find(|line| line.starts_with("VmHWM:"))?;
+    let kib: i64 = line.trim_start_matches("VmHWM:").trim().trim_end_matches("kB").trim().parse().ok()?;
+    Some(kib * 1024)
+}